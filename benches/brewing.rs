@@ -0,0 +1,30 @@
+//! Measures the time to generate Moka IR for the methods in the corpus.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mokapot::ir::MokaIRMethodExt;
+
+mod corpus;
+
+fn brewing(c: &mut Criterion) {
+    let classes = corpus::classes();
+    let methods: Vec<_> = classes
+        .iter()
+        .flat_map(|it| &it.methods)
+        .filter(|it| it.body.is_some())
+        .collect();
+    let mut group = c.benchmark_group("brewing");
+    group.throughput(Throughput::Elements(methods.len() as u64));
+    group.bench_function("methods", |b| {
+        b.iter(|| {
+            for method in &methods {
+                let _ = black_box(method.brew());
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, brewing);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+//! Measures the analyses of the control flow graphs of the methods in the corpus.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mokapot::ir::MokaIRMethodExt;
+
+mod corpus;
+
+/// The methods with more branches are skipped as the size of the path conditions may grow
+/// exponentially with the number of branches.
+const MAX_EDGES: usize = 64;
+
+fn control_flow(c: &mut Criterion) {
+    let classes = corpus::classes();
+    let methods: Vec<_> = classes
+        .iter()
+        .flat_map(|it| &it.methods)
+        .filter_map(|it| it.brew().ok())
+        .filter(|it| it.control_flow_graph.edges().count() <= MAX_EDGES)
+        .collect();
+    let mut group = c.benchmark_group("control_flow");
+    group.throughput(Throughput::Elements(methods.len() as u64));
+    group.bench_function("reverse_postorder", |b| {
+        b.iter(|| {
+            for method in &methods {
+                black_box(method.control_flow_graph.reverse_postorder());
+            }
+        });
+    });
+    group.bench_function("path_conditions", |b| {
+        b.iter(|| {
+            for method in &methods {
+                black_box(method.control_flow_graph.path_conditions());
+            }
+        });
+    });
+    group.bench_function("bdd_path_conditions", |b| {
+        b.iter(|| {
+            for method in &methods {
+                black_box(method.control_flow_graph.bdd_path_conditions());
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, control_flow);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+//! The class files measured by the benchmarks.
+//! The corpus consists of the classes compiled from `test_data` by the build script, or the
+//! classes in the directory in the `JDK_CLASSES` environment variable (e.g., extracted with
+//! `jimage extract`) to measure a larger and more representative workload.
+
+use std::{env, fs, path::PathBuf};
+
+use mokapot::jvm::Class;
+
+/// A class file in the corpus.
+pub struct ClassFile {
+    /// The path of the class file relative to the corpus directory.
+    pub name: String,
+    /// The content of the class file.
+    pub bytes: Vec<u8>,
+}
+
+/// Reads the class files in the corpus sorted by their names.
+///
+/// # Panics
+/// Panics if the corpus is empty, e.g., when `javac` is not available to compile the test data.
+pub fn class_files() -> Vec<ClassFile> {
+    let dir = env::var_os("JDK_CLASSES").map_or_else(
+        || PathBuf::from(concat!(env!("OUT_DIR"), "/mokapot/java_classes")),
+        PathBuf::from,
+    );
+    let mut class_files: Vec<_> = walkdir::WalkDir::new(&dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|it| it.path().extension().is_some_and(|it| it == "class"))
+        .filter_map(|it| {
+            let bytes = fs::read(it.path()).ok()?;
+            let name = it.path().strip_prefix(&dir).ok()?.display().to_string();
+            Some(ClassFile { name, bytes })
+        })
+        .collect();
+    assert!(
+        !class_files.is_empty(),
+        "No class files found in {}",
+        dir.display()
+    );
+    class_files.sort_by(|lhs, rhs| lhs.name.cmp(&rhs.name));
+    class_files
+}
+
+/// Parses the class files in the corpus, skipping the ones that cannot be parsed.
+#[allow(dead_code, reason = "Not used by all benchmarks")]
+pub fn classes() -> Vec<Class> {
+    class_files()
+        .iter()
+        .filter_map(|it| Class::from_reader(it.bytes.as_slice()).ok())
+        .collect()
+}
+
+/// Returns the total size of the class files in bytes.
+#[allow(dead_code, reason = "Not used by all benchmarks")]
+pub fn total_size(class_files: &[ClassFile]) -> u64 {
+    class_files.iter().map(|it| it.bytes.len() as u64).sum()
+}
@@ -0,0 +1,151 @@
+//! Measures the heap memory retained by the classes parsed from the corpus.
+//! Compare the results with and without the `intern` feature (e.g.,
+//! `cargo bench --bench memory --features intern`) to see the memory saved by sharing the names
+//! and the descriptors repeated across the classes.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Measurement, ValueFormatter},
+    Criterion, SamplingMode, Throughput,
+};
+use mokapot::jvm::Class;
+
+mod corpus;
+
+/// The number of bytes allocated on the heap and not freed yet.
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+/// An allocator keeping track of [`LIVE_BYTES`].
+struct CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// SAFETY: The allocations are delegated to the system allocator.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+            LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        }
+        new_ptr
+    }
+}
+
+/// Measures the growth of [`LIVE_BYTES`], i.e., the memory retained by the benchmarked routine.
+struct RetainedBytes;
+
+impl Measurement for RetainedBytes {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {
+        LIVE_BYTES.load(Ordering::SeqCst)
+    }
+
+    fn end(&self, start: Self::Intermediate) -> Self::Value {
+        LIVE_BYTES.load(Ordering::SeqCst).saturating_sub(start)
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &BytesFormatter
+    }
+}
+
+struct BytesFormatter;
+
+impl ValueFormatter for BytesFormatter {
+    fn scale_values(&self, typical_value: f64, values: &mut [f64]) -> &'static str {
+        let (divisor, unit) = if typical_value >= 1024.0 * 1024.0 * 1024.0 {
+            (1024.0 * 1024.0 * 1024.0, "GiB")
+        } else if typical_value >= 1024.0 * 1024.0 {
+            (1024.0 * 1024.0, "MiB")
+        } else if typical_value >= 1024.0 {
+            (1024.0, "KiB")
+        } else {
+            (1.0, "B")
+        };
+        for value in values {
+            *value /= divisor;
+        }
+        unit
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        let (count, unit) = match *throughput {
+            Throughput::Elements(count) => (count, "B/class"),
+            Throughput::Bytes(count) | Throughput::BytesDecimal(count) => (count, "B/B"),
+        };
+        for value in values {
+            *value /= count as f64;
+        }
+        unit
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "B"
+    }
+}
+
+fn memory(c: &mut Criterion<RetainedBytes>) {
+    let class_files = corpus::class_files();
+    let mut group = c.benchmark_group("memory");
+    // The parsed classes are kept until the end of each batch of iterations, so the number of
+    // iterations in a sample is kept small.
+    group.sampling_mode(SamplingMode::Flat);
+    group.throughput(Throughput::Elements(class_files.len() as u64));
+    group.bench_function("parsed_classes", |b| {
+        b.iter_with_large_drop(|| {
+            class_files
+                .iter()
+                .filter_map(|it| Class::from_reader(it.bytes.as_slice()).ok())
+                .collect::<Vec<_>>()
+        });
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_measurement(RetainedBytes).sample_size(10);
+    targets = memory
+}
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+//! Measures the throughput of parsing class files, fully and lazily.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mokapot::jvm::{bytecode::lazy::LazyClass, Class};
+
+mod corpus;
+
+fn parsing(c: &mut Criterion) {
+    let class_files = corpus::class_files();
+    let mut group = c.benchmark_group("parsing");
+    group.throughput(Throughput::Bytes(corpus::total_size(&class_files)));
+    group.bench_function("full", |b| {
+        b.iter(|| {
+            for class_file in &class_files {
+                let _ = black_box(Class::from_reader(class_file.bytes.as_slice()));
+            }
+        });
+    });
+    group.bench_function("lazy", |b| {
+        b.iter(|| {
+            for class_file in &class_files {
+                let _ = black_box(LazyClass::parse(&class_file.bytes));
+            }
+        });
+    });
+    // Reading only the annotations of the methods is the typical metadata-only workload.
+    group.bench_function("lazy_method_annotations", |b| {
+        b.iter(|| {
+            for class_file in &class_files {
+                let Ok(class) = LazyClass::parse(&class_file.bytes) else {
+                    continue;
+                };
+                for index in 0..class.methods.len() {
+                    let _ = black_box(class.method_annotations(index));
+                }
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parsing);
+criterion_main!(benches);
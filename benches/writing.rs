@@ -0,0 +1,27 @@
+//! Measures the time to write the classes in the corpus with a [`ClassWriterVisitor`].
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use mokapot::jvm::visitor::ClassWriterVisitor;
+
+mod corpus;
+
+fn writing(c: &mut Criterion) {
+    let classes = corpus::classes();
+    let mut group = c.benchmark_group("writing");
+    group.throughput(Throughput::Elements(classes.len() as u64));
+    group.bench_function("classes", |b| {
+        b.iter(|| {
+            for class in &classes {
+                let mut writer = ClassWriterVisitor::new();
+                class.accept(&mut writer);
+                black_box(writer.into_class());
+            }
+        });
+    });
+    group.finish();
+}
+
+criterion_group!(benches, writing);
+criterion_main!(benches);
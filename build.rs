@@ -40,6 +40,7 @@ fn compile_java_files(path: &str) {
     let status = Command::new("javac")
         .current_dir(test_data_path)
         .arg("-g")
+        .args(["-encoding", "UTF-8"])
         .arg("-d")
         .arg(build_path.join(path).join("java_classes"))
         .args(java_source_files.into_iter().map(|it| {
@@ -0,0 +1,29 @@
+use afl::fuzz;
+
+use mokapot::fuzzing::{brew, rebuild, strategies::arb_class, write_back};
+use proptest::{
+    strategy::{Strategy, ValueTree},
+    test_runner::{Config, RngAlgorithm, TestRng, TestRunner},
+};
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        // The input drives the choices of the generator, so that the fuzzer explores the space
+        // of the valid classes instead of the space of the bytes.
+        let rng = TestRng::from_seed(RngAlgorithm::PassThrough, data);
+        let mut runner = TestRunner::new_with_rng(Config::default(), rng);
+        let Ok(tree) = arb_class().new_tree(&mut runner) else {
+            return;
+        };
+        let class = tree.current();
+        for result in brew(&class) {
+            if let Err(error) = result {
+                panic!("Failed to brew a generated method: {error}");
+            }
+        }
+        let _ = rebuild(&class);
+        if let Err(error) = write_back(&class) {
+            panic!("Failed to write a generated class: {error}");
+        }
+    });
+}
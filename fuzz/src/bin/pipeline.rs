@@ -0,0 +1,7 @@
+use afl::fuzz;
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        mokapot::fuzzing::pipeline(data);
+    });
+}
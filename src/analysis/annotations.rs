@@ -0,0 +1,488 @@
+//! An index of the annotations on a set of classes.
+//!
+//! An [`AnnotationIndex`] answers queries such as "all methods annotated with
+//! `org/junit/jupiter/api/Test`" or "all classes meta-annotated with
+//! `org/springframework/stereotype/Component`" without rescanning the annotations of every
+//! element.
+//! The index follows the semantics of the Java reflection APIs:
+//! - The annotations on a class include the annotations of its super classes whose type is
+//!   marked as `@Inherited`, unless the class is directly annotated with the same type.
+//! - The annotations contained in a container of a `@Repeatable` annotation are indexed
+//!   together with the container.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    jvm::{
+        annotation::ElementValue,
+        references::{ClassRef, FieldRef, MethodRef},
+        Annotation, Class,
+    },
+    types::{field_type::FieldType, method_descriptor::ReturnType},
+};
+
+const INHERITED: &str = "java/lang/annotation/Inherited";
+const REPEATABLE: &str = "java/lang/annotation/Repeatable";
+
+/// An element that can be annotated.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum AnnotatedElement {
+    /// A class.
+    #[display("{_0}")]
+    Class(ClassRef),
+    /// A method.
+    #[display("{_0}")]
+    Method(MethodRef),
+    /// A field.
+    #[display("{_0}")]
+    Field(FieldRef),
+    /// A parameter of a method.
+    #[display("{method}#{index}")]
+    Parameter {
+        /// The method declaring the parameter.
+        method: MethodRef,
+        /// The index of the parameter.
+        index: usize,
+    },
+}
+
+/// An index of the annotations on a set of classes and their members.
+/// Both runtime visible and runtime invisible annotations are indexed.
+#[derive(Debug, Clone)]
+pub struct AnnotationIndex<'a> {
+    classes: HashMap<ClassRef, &'a Class>,
+    annotations: HashMap<AnnotatedElement, Vec<&'a Annotation>>,
+    elements: HashMap<ClassRef, BTreeSet<AnnotatedElement>>,
+}
+
+impl<'a> AnnotationIndex<'a> {
+    /// Creates an index of the annotations on `classes`.
+    /// The classes of the annotation types should be included to recognize `@Inherited` and
+    /// `@Repeatable` annotations and to answer meta-annotation queries.
+    pub fn new(classes: impl IntoIterator<Item = &'a Class>) -> Self {
+        let classes: HashMap<_, _> = classes.into_iter().map(|it| (it.as_ref(), it)).collect();
+        let mut index = Self {
+            classes,
+            annotations: HashMap::new(),
+            elements: HashMap::new(),
+        };
+        let mut annotations = HashMap::new();
+        for class in index.classes.values() {
+            annotations.insert(
+                AnnotatedElement::Class(class.as_ref()),
+                index.expand(
+                    class
+                        .runtime_visible_annotations
+                        .iter()
+                        .chain(&class.runtime_invisible_annotations),
+                ),
+            );
+            for method in &class.methods {
+                annotations.insert(
+                    AnnotatedElement::Method(method.as_ref()),
+                    index.expand(
+                        method
+                            .runtime_visible_annotations
+                            .iter()
+                            .chain(&method.runtime_invisible_annotations),
+                    ),
+                );
+                let parameters = method
+                    .runtime_visible_parameter_annotations
+                    .iter()
+                    .enumerate()
+                    .chain(
+                        method
+                            .runtime_invisible_parameter_annotations
+                            .iter()
+                            .enumerate(),
+                    );
+                for (index_of_parameter, parameter_annotations) in parameters {
+                    let element = AnnotatedElement::Parameter {
+                        method: method.as_ref(),
+                        index: index_of_parameter,
+                    };
+                    let expanded = index.expand(parameter_annotations);
+                    annotations.entry(element).or_default().extend(expanded);
+                }
+            }
+            for field in &class.fields {
+                annotations.insert(
+                    AnnotatedElement::Field(field.as_ref()),
+                    index.expand(
+                        field
+                            .runtime_visible_annotations
+                            .iter()
+                            .chain(&field.runtime_invisible_annotations),
+                    ),
+                );
+            }
+        }
+        for class in index.classes.values() {
+            let inherited = index.inherited_annotations(class, &annotations);
+            if let Some(it) = annotations.get_mut(&AnnotatedElement::Class(class.as_ref())) {
+                it.extend(inherited);
+            }
+        }
+        annotations.retain(|_, it| !it.is_empty());
+        for (element, element_annotations) in &annotations {
+            for annotation_type in element_annotations.iter().filter_map(|it| type_of(it)) {
+                index
+                    .elements
+                    .entry(annotation_type)
+                    .or_default()
+                    .insert(element.clone());
+            }
+        }
+        index.annotations = annotations;
+        index
+    }
+
+    /// Gets the annotations on `element`, including the inherited ones and the ones contained
+    /// in repeatable containers.
+    #[must_use]
+    pub fn annotations_of(&self, element: &AnnotatedElement) -> &[&'a Annotation] {
+        self.annotations.get(element).map_or(&[], Vec::as_slice)
+    }
+
+    /// Gets the annotations of type `annotation_type` on `element`.
+    pub fn annotations_by_type(
+        &self,
+        element: &AnnotatedElement,
+        annotation_type: &ClassRef,
+    ) -> impl Iterator<Item = &'a Annotation> + '_ {
+        let annotation_type = annotation_type.clone();
+        self.annotations_of(element)
+            .iter()
+            .copied()
+            .filter(move |it| type_of(it).as_ref() == Some(&annotation_type))
+    }
+
+    /// Gets the elements annotated with `annotation_type`.
+    pub fn annotated_with(
+        &self,
+        annotation_type: &ClassRef,
+    ) -> impl Iterator<Item = &AnnotatedElement> {
+        self.elements.get(annotation_type).into_iter().flatten()
+    }
+
+    /// Gets the classes annotated with `annotation_type`.
+    pub fn classes_annotated_with(
+        &self,
+        annotation_type: &ClassRef,
+    ) -> impl Iterator<Item = &ClassRef> {
+        self.annotated_with(annotation_type)
+            .filter_map(|it| match it {
+                AnnotatedElement::Class(class) => Some(class),
+                _ => None,
+            })
+    }
+
+    /// Gets the methods annotated with `annotation_type`.
+    pub fn methods_annotated_with(
+        &self,
+        annotation_type: &ClassRef,
+    ) -> impl Iterator<Item = &MethodRef> {
+        self.annotated_with(annotation_type)
+            .filter_map(|it| match it {
+                AnnotatedElement::Method(method) => Some(method),
+                _ => None,
+            })
+    }
+
+    /// Gets the fields annotated with `annotation_type`.
+    pub fn fields_annotated_with(
+        &self,
+        annotation_type: &ClassRef,
+    ) -> impl Iterator<Item = &FieldRef> {
+        self.annotated_with(annotation_type)
+            .filter_map(|it| match it {
+                AnnotatedElement::Field(field) => Some(field),
+                _ => None,
+            })
+    }
+
+    /// Gets the annotation types that are meta-annotations of `annotation_type`, i.e., the
+    /// types of the annotations on the class of `annotation_type`, directly or transitively.
+    #[must_use]
+    pub fn meta_annotations(&self, annotation_type: &ClassRef) -> BTreeSet<ClassRef> {
+        let mut meta_annotations = BTreeSet::new();
+        let mut stack = vec![annotation_type.clone()];
+        while let Some(current) = stack.pop() {
+            let element = AnnotatedElement::Class(current);
+            for meta in self
+                .annotations_of(&element)
+                .iter()
+                .filter_map(|it| type_of(it))
+            {
+                if meta_annotations.insert(meta.clone()) {
+                    stack.push(meta);
+                }
+            }
+        }
+        meta_annotations
+    }
+
+    /// Gets the elements annotated with `annotation_type` either directly or through a
+    /// meta-annotation, e.g., a class annotated with `@Service`, which is in turn annotated
+    /// with `@Component`, is meta-annotated with `@Component`.
+    #[must_use]
+    pub fn meta_annotated_with(&self, annotation_type: &ClassRef) -> BTreeSet<&AnnotatedElement> {
+        self.elements
+            .iter()
+            .filter(|(it, _)| {
+                *it == annotation_type || self.meta_annotations(it).contains(annotation_type)
+            })
+            .flat_map(|(_, elements)| elements)
+            .collect()
+    }
+
+    /// Gets the classes annotated with `annotation_type` either directly or through a
+    /// meta-annotation.
+    #[must_use]
+    pub fn classes_meta_annotated_with(&self, annotation_type: &ClassRef) -> BTreeSet<&ClassRef> {
+        self.meta_annotated_with(annotation_type)
+            .into_iter()
+            .filter_map(|it| match it {
+                AnnotatedElement::Class(class) => Some(class),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Checks whether the annotation type `annotation_type` is marked as `@Inherited`.
+    #[must_use]
+    pub fn is_inherited(&self, annotation_type: &ClassRef) -> bool {
+        self.declaration_annotations(annotation_type)
+            .any(|it| type_of(it).is_some_and(|it| &*it.binary_name == INHERITED))
+    }
+
+    /// Gets the container annotation type of `annotation_type` if it is marked as
+    /// `@Repeatable`.
+    #[must_use]
+    pub fn repeatable_container(&self, annotation_type: &ClassRef) -> Option<ClassRef> {
+        self.declaration_annotations(annotation_type)
+            .filter(|it| type_of(it).is_some_and(|it| &*it.binary_name == REPEATABLE))
+            .find_map(|it| match it.element_value_pairs.as_slice() {
+                [(
+                    name,
+                    ElementValue::Class {
+                        return_descriptor: ReturnType::Some(FieldType::Object(container)),
+                    },
+                )] if name == "value" => Some(container.clone()),
+                _ => None,
+            })
+    }
+
+    /// Gets the annotations declared on the class of `annotation_type`, without the inherited
+    /// ones.
+    fn declaration_annotations(
+        &self,
+        annotation_type: &ClassRef,
+    ) -> impl Iterator<Item = &'a Annotation> + '_ {
+        self.classes
+            .get(annotation_type)
+            .into_iter()
+            .flat_map(|it| {
+                it.runtime_visible_annotations
+                    .iter()
+                    .chain(&it.runtime_invisible_annotations)
+            })
+    }
+
+    /// Lists `annotations` followed by the annotations contained in the repeatable containers
+    /// among them.
+    fn expand(&self, annotations: impl IntoIterator<Item = &'a Annotation>) -> Vec<&'a Annotation> {
+        let mut expanded = Vec::new();
+        for annotation in annotations {
+            expanded.push(annotation);
+            expanded.extend(self.contained_annotations(annotation));
+        }
+        expanded
+    }
+
+    /// Gets the annotations in `container` if it is the container of a repeatable annotation.
+    fn contained_annotations(&self, container: &'a Annotation) -> Vec<&'a Annotation> {
+        let [(name, ElementValue::Array(values))] = container.element_value_pairs.as_slice() else {
+            return Vec::new();
+        };
+        let members: Vec<_> = values
+            .iter()
+            .map_while(|it| match it {
+                ElementValue::AnnotationInterface(annotation) => Some(annotation),
+                _ => None,
+            })
+            .collect();
+        let container_type = type_of(container);
+        let is_container = name == "value"
+            && members.len() == values.len()
+            && members.iter().all(|it| {
+                type_of(it).is_some_and(|it| self.repeatable_container(&it) == container_type)
+            });
+        if is_container {
+            members
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Gets the `@Inherited` annotations that `class` inherits from its super classes.
+    fn inherited_annotations(
+        &self,
+        class: &Class,
+        annotations: &HashMap<AnnotatedElement, Vec<&'a Annotation>>,
+    ) -> Vec<&'a Annotation> {
+        let annotations_of = |it: &ClassRef| {
+            annotations
+                .get(&AnnotatedElement::Class(it.clone()))
+                .map_or(&[][..], Vec::as_slice)
+        };
+        let mut present: HashSet<_> = annotations_of(&class.as_ref())
+            .iter()
+            .filter_map(|it| type_of(it))
+            .collect();
+        let mut inherited = Vec::new();
+        let mut visited = HashSet::from([class.as_ref()]);
+        let mut super_class = class.super_class.clone();
+        while let Some(current) = super_class.filter(|it| visited.insert(it.clone())) {
+            let mut found = HashSet::new();
+            for annotation in annotations_of(&current) {
+                let Some(annotation_type) = type_of(annotation) else {
+                    continue;
+                };
+                let is_contained = self
+                    .repeatable_container(&annotation_type)
+                    .is_some_and(|it| self.is_inherited(&it));
+                let is_inherited = self.is_inherited(&annotation_type) || is_contained;
+                if is_inherited && !present.contains(&annotation_type) {
+                    inherited.push(*annotation);
+                    found.insert(annotation_type);
+                }
+            }
+            present.extend(found);
+            super_class = self
+                .classes
+                .get(&current)
+                .and_then(|it| it.super_class.clone());
+        }
+        inherited
+    }
+}
+
+fn type_of(annotation: &Annotation) -> Option<ClassRef> {
+    match &annotation.annotation_type {
+        FieldType::Object(class) => Some(class.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jvm::Method, tests::annotation};
+
+    fn class(name: &str, super_class: &str, annotations: Vec<Annotation>) -> Class {
+        Class {
+            binary_name: name.to_owned(),
+            super_class: Some(ClassRef::new(super_class)),
+            runtime_visible_annotations: annotations,
+            ..Default::default()
+        }
+    }
+
+    fn annotation_class(name: &str, annotations: Vec<Annotation>) -> Class {
+        let mut class = class(name, "java/lang/Object", annotations);
+        class.interfaces = vec![ClassRef::new("java/lang/annotation/Annotation")];
+        class
+    }
+
+    #[test]
+    fn inherited_and_meta_annotations() {
+        let component = annotation_class("Component", vec![annotation(INHERITED)]);
+        let service = annotation_class("Service", vec![annotation("Component")]);
+        let base = class("Base", "java/lang/Object", vec![annotation("Component")]);
+        let derived = class("Derived", "Base", Vec::new());
+        let controller = class(
+            "Controller",
+            "java/lang/Object",
+            vec![annotation("Service")],
+        );
+        let mut tests = class("Tests", "java/lang/Object", Vec::new());
+        tests.methods = vec![Method {
+            name: "test".to_owned(),
+            descriptor: "()V".parse().unwrap(),
+            owner: ClassRef::new("Tests"),
+            runtime_visible_annotations: vec![annotation("Test")],
+            ..Default::default()
+        }];
+        let classes = [component, service, base, derived, controller, tests];
+        let index = AnnotationIndex::new(&classes);
+
+        let annotated: BTreeSet<_> = index
+            .classes_annotated_with(&ClassRef::new("Component"))
+            .map(|it| it.binary_name.as_ref())
+            .collect();
+        assert_eq!(annotated, BTreeSet::from(["Base", "Derived", "Service"]));
+        let meta_annotated: BTreeSet<_> = index
+            .classes_meta_annotated_with(&ClassRef::new("Component"))
+            .into_iter()
+            .map(|it| it.binary_name.as_ref())
+            .collect();
+        assert_eq!(
+            meta_annotated,
+            BTreeSet::from(["Base", "Controller", "Derived", "Service"])
+        );
+        let tests: Vec<_> = index
+            .methods_annotated_with(&ClassRef::new("Test"))
+            .map(|it| it.name.as_ref())
+            .collect();
+        assert_eq!(tests, vec!["test"]);
+        assert!(index.is_inherited(&ClassRef::new("Component")));
+        assert!(!index.is_inherited(&ClassRef::new("Service")));
+    }
+
+    #[test]
+    fn repeatable_annotations() {
+        let repeatable = Annotation {
+            annotation_type: FieldType::Object(ClassRef::new(REPEATABLE)),
+            element_value_pairs: vec![(
+                "value".to_owned(),
+                ElementValue::Class {
+                    return_descriptor: ReturnType::Some(FieldType::Object(ClassRef::new("Tags"))),
+                },
+            )],
+        };
+        let tag = annotation_class("Tag", vec![repeatable]);
+        let tags = annotation_class("Tags", Vec::new());
+        let container = Annotation {
+            annotation_type: FieldType::Object(ClassRef::new("Tags")),
+            element_value_pairs: vec![(
+                "value".to_owned(),
+                ElementValue::Array(vec![
+                    ElementValue::AnnotationInterface(annotation("Tag")),
+                    ElementValue::AnnotationInterface(annotation("Tag")),
+                ]),
+            )],
+        };
+        let tagged = class("Tagged", "java/lang/Object", vec![container]);
+        let classes = [tag, tags, tagged];
+        let index = AnnotationIndex::new(&classes);
+
+        assert_eq!(
+            index.repeatable_container(&ClassRef::new("Tag")),
+            Some(ClassRef::new("Tags"))
+        );
+        let element = AnnotatedElement::Class(ClassRef::new("Tagged"));
+        assert_eq!(index.annotations_of(&element).len(), 3);
+        assert_eq!(
+            index
+                .annotations_by_type(&element, &ClassRef::new("Tag"))
+                .count(),
+            2
+        );
+        let tagged: Vec<_> = index
+            .classes_annotated_with(&ClassRef::new("Tag"))
+            .collect();
+        assert_eq!(tagged, vec![&ClassRef::new("Tagged")]);
+    }
+}
@@ -0,0 +1,653 @@
+//! Anonymization of classes for sharing bug reports.
+//!
+//! An [`Anonymizer`] replaces the names of the application classes and their members, as well
+//! as string constants, with salted hashes, and strips the debug information, signatures,
+//! annotations, and unrecognized attributes.
+//! The structure of the classes (i.e., the members, flags, types, instructions, and exception
+//! handlers) is preserved, so the anonymized classes exercise the same code paths in the parser
+//! and the IR generator as the original ones.
+//!
+//! Library classes (by default the ones in `java/`, `javax/`, `jdk/`, and `sun/`) are not
+//! renamed, and neither are the methods of the application classes that may override library
+//! methods (i.e., the ones sharing a name with a method referenced on a library class).
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    jvm::{
+        class::{
+            BootstrapMethod, DynamicConstant, EnclosingMethod, InnerClassInfo, MethodHandle,
+            RecordComponent,
+        },
+        code::{ExceptionTableEntry, Instruction, MethodBody, VerificationType},
+        method::ParameterInfo,
+        references::{ClassRef, FieldRef, MethodRef, PackageRef},
+        Class, ConstantValue, Field, JavaString, Method,
+    },
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::artifacts::Hasher;
+
+/// Replaces the identifiers in classes with salted hashes.
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    salt: String,
+    kept_packages: Vec<String>,
+    kept_methods: BTreeSet<String>,
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl Anonymizer {
+    /// Creates an anonymizer hashing the identifiers with `salt`.
+    /// The same salt always produces the same names, so classes anonymized separately remain
+    /// consistent with each other.
+    pub fn new(salt: impl Into<String>) -> Self {
+        let kept_methods = [
+            "main", "equals", "hashCode", "toString", "clone", "finalize",
+        ]
+        .into_iter()
+        .map(str::to_owned)
+        .collect();
+        Self {
+            salt: salt.into(),
+            kept_packages: ["java/", "javax/", "jdk/", "sun/"]
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            kept_methods,
+        }
+    }
+
+    /// Keeps the names of the classes in `package` (e.g., `org/slf4j`) and its subpackages.
+    #[must_use]
+    pub fn with_kept_package(mut self, package: &str) -> Self {
+        self.kept_packages
+            .push(format!("{}/", package.trim_end_matches('/')));
+        self
+    }
+
+    /// Keeps the name of the methods named `name` (e.g., methods overriding library methods
+    /// that are not referenced in the anonymized classes).
+    #[must_use]
+    pub fn with_kept_method(mut self, name: impl Into<String>) -> Self {
+        self.kept_methods.insert(name.into());
+        self
+    }
+
+    /// Returns whether the class named `binary_name` keeps its name.
+    #[must_use]
+    pub fn is_kept(&self, binary_name: &str) -> bool {
+        let element = binary_name.trim_start_matches('[');
+        if element.len() == binary_name.len() {
+            self.kept_packages.iter().any(|it| element.starts_with(it))
+        } else {
+            // An array of primitives or classes.
+            element.len() == 1
+                || self
+                    .kept_packages
+                    .iter()
+                    .any(|it| element.trim_start_matches('L').starts_with(it))
+        }
+    }
+
+    /// Returns the anonymized name of the class named `binary_name`.
+    /// Each package segment and each part of a nested class name is hashed separately, so the
+    /// package and nesting structure is preserved.
+    #[must_use]
+    pub fn class_name(&self, binary_name: &str) -> String {
+        if binary_name.starts_with('[') {
+            return binary_name.parse().map_or_else(
+                |_| self.hash('C', binary_name),
+                |it| self.field_type(&it).descriptor(),
+            );
+        }
+        if self.is_kept(binary_name) {
+            return binary_name.to_owned();
+        }
+        let (package, simple_name) = binary_name
+            .rsplit_once('/')
+            .map_or(("", binary_name), |(p, n)| (p, n));
+        let mut name = self.package_name(package);
+        if !name.is_empty() {
+            name.push('/');
+        }
+        let parts: Vec<_> = simple_name
+            .split('$')
+            .map(|it| {
+                // Keep the numbers of anonymous and local classes.
+                if !it.is_empty() && it.bytes().all(|b| b.is_ascii_digit()) {
+                    it.to_owned()
+                } else {
+                    self.hash('C', it)
+                }
+            })
+            .collect();
+        name.push_str(&parts.join("$"));
+        name
+    }
+
+    /// Returns the anonymized name of the package named `binary_name` (e.g., `com/example`).
+    #[must_use]
+    pub fn package_name(&self, binary_name: &str) -> String {
+        if self.is_kept(&format!("{binary_name}/")) {
+            return binary_name.to_owned();
+        }
+        binary_name
+            .split('/')
+            .filter(|it| !it.is_empty())
+            .map(|it| self.hash('p', it))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Anonymizes `class`.
+    #[must_use]
+    pub fn anonymize(&self, class: &Class) -> Class {
+        self.context(std::slice::from_ref(class)).class(class)
+    }
+
+    /// Anonymizes `classes` consistently.
+    /// The methods sharing a name with a method referenced on a library class in any of the
+    /// classes keep their names.
+    #[must_use]
+    pub fn anonymize_all(&self, classes: &[Class]) -> Vec<Class> {
+        let context = self.context(classes);
+        classes.iter().map(|it| context.class(it)).collect()
+    }
+
+    fn context(&self, classes: &[Class]) -> Context<'_> {
+        let mut kept_methods = self.kept_methods.clone();
+        for instruction in classes
+            .iter()
+            .flat_map(|it| &it.methods)
+            .filter_map(|it| it.body.as_ref())
+            .flat_map(|it| it.instructions.iter().map(|(_, insn)| insn))
+        {
+            if let Instruction::InvokeVirtual(method)
+            | Instruction::InvokeSpecial(method)
+            | Instruction::InvokeStatic(method)
+            | Instruction::InvokeInterface(method, _) = instruction
+            {
+                if self.is_kept(&method.owner.binary_name) {
+                    kept_methods.insert(method.name.to_string());
+                }
+            }
+        }
+        Context {
+            anonymizer: self,
+            kept_methods,
+        }
+    }
+
+    fn hash(&self, prefix: char, name: &str) -> String {
+        let mut hasher = Hasher::default();
+        hasher.update(self.salt.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(name.as_bytes());
+        let digest = hasher.finish().to_string();
+        format!("{prefix}{}", &digest[..12])
+    }
+
+    fn class_ref(&self, class: &ClassRef) -> ClassRef {
+        ClassRef::new(self.class_name(&class.binary_name))
+    }
+
+    fn field_type(&self, field_type: &FieldType) -> FieldType {
+        match field_type {
+            FieldType::Base(_) => field_type.clone(),
+            FieldType::Object(class) => FieldType::Object(self.class_ref(class)),
+            FieldType::Array(element) => FieldType::Array(Box::new(self.field_type(element))),
+        }
+    }
+
+    fn descriptor(&self, descriptor: &MethodDescriptor) -> MethodDescriptor {
+        MethodDescriptor::new(
+            descriptor
+                .parameters_types
+                .iter()
+                .map(|it| self.field_type(it))
+                .collect::<Vec<_>>(),
+            match &descriptor.return_type {
+                ReturnType::Some(it) => ReturnType::Some(self.field_type(it)),
+                ReturnType::Void => ReturnType::Void,
+            },
+        )
+    }
+}
+
+struct Context<'a> {
+    anonymizer: &'a Anonymizer,
+    kept_methods: BTreeSet<String>,
+}
+
+impl Context<'_> {
+    fn class(&self, class: &Class) -> Class {
+        let a = self.anonymizer;
+        let class_refs = |refs: &[ClassRef]| refs.iter().map(|it| a.class_ref(it)).collect();
+        Class {
+            version: class.version,
+            access_flags: class.access_flags,
+            binary_name: a.class_name(&class.binary_name),
+            super_class: class.super_class.as_ref().map(|it| a.class_ref(it)),
+            interfaces: class_refs(&class.interfaces),
+            fields: class.fields.iter().map(|it| self.field(it)).collect(),
+            methods: class.methods.iter().map(|it| self.method(it)).collect(),
+            source_file: None,
+            inner_classes: class
+                .inner_classes
+                .iter()
+                .map(|it| InnerClassInfo {
+                    inner_class: a.class_ref(&it.inner_class),
+                    outer_class: it.outer_class.as_ref().map(|it| a.class_ref(it)),
+                    inner_name: it.inner_name.as_ref().map(|name| {
+                        if a.is_kept(&it.inner_class.binary_name) {
+                            name.clone()
+                        } else {
+                            a.hash('C', name)
+                        }
+                    }),
+                    access_flags: it.access_flags,
+                })
+                .collect(),
+            enclosing_method: class.enclosing_method.as_ref().map(|it| EnclosingMethod {
+                class: a.class_ref(&it.class),
+                method_name_and_desc: it
+                    .method_name_and_desc
+                    .as_ref()
+                    .map(|(name, desc)| (self.method_name(&it.class, name), a.descriptor(desc))),
+            }),
+            source_debug_extension: None,
+            compilation_id: None,
+            source_id: None,
+            runtime_visible_annotations: Vec::new(),
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            bootstrap_methods: class
+                .bootstrap_methods
+                .iter()
+                .map(|it| self.bootstrap_method(it))
+                .collect(),
+            module: class.module.clone(),
+            module_packages: class
+                .module_packages
+                .iter()
+                .map(|it| {
+                    let name = a.class_name(&format!("{}/_", it.binary_name));
+                    PackageRef {
+                        binary_name: name
+                            .rsplit_once('/')
+                            .map_or(name.clone(), |(p, _)| p.into()),
+                    }
+                })
+                .collect(),
+            module_main_class: class.module_main_class.as_ref().map(|it| a.class_ref(it)),
+            nest_host: class.nest_host.as_ref().map(|it| a.class_ref(it)),
+            nest_members: class_refs(&class.nest_members),
+            permitted_subclasses: class_refs(&class.permitted_subclasses),
+            is_synthetic: class.is_synthetic,
+            is_deprecated: class.is_deprecated,
+            signature: None,
+            record: class.record.as_ref().map(|components| {
+                components
+                    .iter()
+                    .map(|it| RecordComponent {
+                        name: self.field_name(&class.as_ref(), &it.name),
+                        component_type: a.field_type(&it.component_type),
+                        signature: None,
+                        runtime_visible_annotations: Vec::new(),
+                        runtime_invisible_annotations: Vec::new(),
+                        runtime_visible_type_annotations: Vec::new(),
+                        runtime_invisible_type_annotations: Vec::new(),
+                        free_attributes: Vec::new(),
+                        custom_attributes: Vec::new(),
+                    })
+                    .collect()
+            }),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        }
+    }
+
+    fn field(&self, field: &Field) -> Field {
+        let a = self.anonymizer;
+        Field {
+            access_flags: field.access_flags,
+            name: self.field_name(&field.owner, &field.name),
+            owner: a.class_ref(&field.owner),
+            field_type: a.field_type(&field.field_type),
+            constant_value: field.constant_value.as_ref().map(|it| self.constant(it)),
+            is_synthetic: field.is_synthetic,
+            is_deprecated: field.is_deprecated,
+            signature: None,
+            runtime_visible_annotations: Vec::new(),
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        }
+    }
+
+    fn method(&self, method: &Method) -> Method {
+        let a = self.anonymizer;
+        Method {
+            access_flags: method.access_flags,
+            name: self.method_name(&method.owner, &method.name),
+            descriptor: a.descriptor(&method.descriptor),
+            owner: a.class_ref(&method.owner),
+            body: method.body.as_ref().map(|it| self.body(it)),
+            exceptions: method.exceptions.iter().map(|it| a.class_ref(it)).collect(),
+            runtime_visible_annotations: Vec::new(),
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            runtime_visible_parameter_annotations: Vec::new(),
+            runtime_invisible_parameter_annotations: Vec::new(),
+            annotation_default: None,
+            parameters: method.parameters.as_ref().map(|parameters| {
+                parameters
+                    .iter()
+                    .map(|it| ParameterInfo {
+                        name: None,
+                        access_flags: it.access_flags,
+                    })
+                    .collect()
+            }),
+            is_synthetic: method.is_synthetic,
+            is_deprecated: method.is_deprecated,
+            signature: None,
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        }
+    }
+
+    fn body(&self, body: &MethodBody) -> MethodBody {
+        let a = self.anonymizer;
+        MethodBody {
+            max_stack: body.max_stack,
+            max_locals: body.max_locals,
+            instructions: body
+                .instructions
+                .iter()
+                .map(|(pc, insn)| (*pc, self.instruction(insn)))
+                .collect::<BTreeMap<_, _>>()
+                .into(),
+            exception_table: body
+                .exception_table
+                .iter()
+                .map(|it| ExceptionTableEntry {
+                    catch_type: it.catch_type.as_ref().map(|it| a.class_ref(it)),
+                    ..it.clone()
+                })
+                .collect(),
+            line_number_table: None,
+            character_range_table: None,
+            local_variable_table: None,
+            stack_map_table: body.stack_map_table.clone().map(|mut frames| {
+                for frame in &mut frames {
+                    for it in frame.verification_types_mut() {
+                        if let VerificationType::ObjectVariable(class) = it {
+                            *class = a.class_ref(class);
+                        }
+                    }
+                }
+                frames
+            }),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        }
+    }
+
+    fn instruction(&self, instruction: &Instruction) -> Instruction {
+        #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+        use Instruction::*;
+        let a = self.anonymizer;
+        match instruction {
+            Ldc(it) => Ldc(self.constant(it)),
+            LdcW(it) => LdcW(self.constant(it)),
+            Ldc2W(it) => Ldc2W(self.constant(it)),
+            GetStatic(it) => GetStatic(self.field_ref(it)),
+            PutStatic(it) => PutStatic(self.field_ref(it)),
+            GetField(it) => GetField(self.field_ref(it)),
+            PutField(it) => PutField(self.field_ref(it)),
+            InvokeVirtual(it) => InvokeVirtual(self.method_ref(it)),
+            InvokeSpecial(it) => InvokeSpecial(self.method_ref(it)),
+            InvokeStatic(it) => InvokeStatic(self.method_ref(it)),
+            InvokeInterface(it, count) => InvokeInterface(self.method_ref(it), *count),
+            InvokeDynamic {
+                bootstrap_method_index,
+                name,
+                descriptor,
+            } => InvokeDynamic {
+                bootstrap_method_index: *bootstrap_method_index,
+                // The name is usually the one of the implemented functional interface method.
+                name: name.clone(),
+                descriptor: a.descriptor(descriptor),
+            },
+            New(it) => New(a.class_ref(it)),
+            ANewArray(it) => ANewArray(a.class_ref(it)),
+            CheckCast(it) => CheckCast(a.field_type(it)),
+            InstanceOf(it) => InstanceOf(a.field_type(it)),
+            MultiANewArray(it, dimensions) => MultiANewArray(a.field_type(it), *dimensions),
+            other => other.clone(),
+        }
+    }
+
+    fn constant(&self, constant: &ConstantValue) -> ConstantValue {
+        let a = self.anonymizer;
+        match constant {
+            ConstantValue::String(JavaString::Utf8(it)) => {
+                ConstantValue::String(JavaString::Utf8(a.hash('s', it)))
+            }
+            // Keep the string invalid so that the parser takes the same path.
+            ConstantValue::String(JavaString::InvalidUtf8(it)) => {
+                ConstantValue::String(JavaString::InvalidUtf8(vec![0xC0; it.len()]))
+            }
+            ConstantValue::Class(it) => ConstantValue::Class(a.class_ref(it)),
+            ConstantValue::Handle(it) => ConstantValue::Handle(self.handle(it)),
+            ConstantValue::MethodType(it) => ConstantValue::MethodType(a.descriptor(it)),
+            ConstantValue::Dynamic(it) => ConstantValue::Dynamic(Box::new(DynamicConstant {
+                bootstrap: self.bootstrap_method(&it.bootstrap),
+                name: it.name.clone(),
+                descriptor: a.field_type(&it.descriptor),
+            })),
+            other => other.clone(),
+        }
+    }
+
+    fn bootstrap_method(&self, bootstrap_method: &BootstrapMethod) -> BootstrapMethod {
+        BootstrapMethod {
+            method: self.handle(&bootstrap_method.method),
+            arguments: bootstrap_method
+                .arguments
+                .iter()
+                .map(|it| self.constant(it))
+                .collect(),
+        }
+    }
+
+    fn handle(&self, handle: &MethodHandle) -> MethodHandle {
+        match handle {
+            MethodHandle::RefGetField(it) => MethodHandle::RefGetField(self.field_ref(it)),
+            MethodHandle::RefGetStatic(it) => MethodHandle::RefGetStatic(self.field_ref(it)),
+            MethodHandle::RefPutField(it) => MethodHandle::RefPutField(self.field_ref(it)),
+            MethodHandle::RefPutStatic(it) => MethodHandle::RefPutStatic(self.field_ref(it)),
+            MethodHandle::RefInvokeVirtual(it) => {
+                MethodHandle::RefInvokeVirtual(self.method_ref(it))
+            }
+            MethodHandle::RefInvokeStatic(it) => MethodHandle::RefInvokeStatic(self.method_ref(it)),
+            MethodHandle::RefInvokeSpecial(it) => {
+                MethodHandle::RefInvokeSpecial(self.method_ref(it))
+            }
+            MethodHandle::RefNewInvokeSpecial(it) => {
+                MethodHandle::RefNewInvokeSpecial(self.method_ref(it))
+            }
+            MethodHandle::RefInvokeInterface(it) => {
+                MethodHandle::RefInvokeInterface(self.method_ref(it))
+            }
+        }
+    }
+
+    fn field_ref(&self, field: &FieldRef) -> FieldRef {
+        let a = self.anonymizer;
+        FieldRef::new(
+            a.class_ref(&field.owner),
+            self.field_name(&field.owner, &field.name),
+            a.field_type(&field.field_type),
+        )
+    }
+
+    fn method_ref(&self, method: &MethodRef) -> MethodRef {
+        let a = self.anonymizer;
+        MethodRef::new(
+            a.class_ref(&method.owner),
+            self.method_name(&method.owner, &method.name),
+            a.descriptor(&method.descriptor),
+        )
+    }
+
+    fn field_name(&self, owner: &ClassRef, name: &str) -> String {
+        if self.anonymizer.is_kept(&owner.binary_name) {
+            name.to_owned()
+        } else {
+            self.anonymizer.hash('f', name)
+        }
+    }
+
+    fn method_name(&self, owner: &ClassRef, name: &str) -> String {
+        if name.starts_with('<')
+            || self.kept_methods.contains(name)
+            || self.anonymizer.is_kept(&owner.binary_name)
+        {
+            name.to_owned()
+        } else {
+            self.anonymizer.hash('m', name)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{code::InstructionList, references::ClassRef},
+    };
+    use Instruction::{ALoad0, AReturn, InvokeInterface, InvokeVirtual, Ldc, Return};
+
+    fn class() -> Class {
+        let owner = ClassRef::new("com/acme/Secret$Inner");
+        let runnable = ClassRef::new("java/lang/Runnable");
+        let method = |name: &str, instructions| Method {
+            name: name.to_owned(),
+            owner: owner.clone(),
+            descriptor: "()Ljava/lang/Object;".parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 2,
+                max_locals: 1,
+                instructions,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        Class {
+            binary_name: owner.binary_name.to_string(),
+            interfaces: vec![runnable.clone()],
+            source_file: Some("Secret.java".to_owned()),
+            methods: vec![
+                method(
+                    "run",
+                    InstructionList::from([
+                        (0.into(), ALoad0),
+                        (
+                            1.into(),
+                            InvokeInterface(
+                                MethodRef::new(runnable, "run", "()V".parse().unwrap()),
+                                1,
+                            ),
+                        ),
+                        (6.into(), Return),
+                    ]),
+                ),
+                method(
+                    "leak",
+                    InstructionList::from([
+                        (0.into(), ALoad0),
+                        (
+                            1.into(),
+                            InvokeVirtual(MethodRef::new(
+                                owner.clone(),
+                                "secretHelper",
+                                "()Lcom/acme/Secret;".parse().unwrap(),
+                            )),
+                        ),
+                        (
+                            4.into(),
+                            Ldc(ConstantValue::String(JavaString::Utf8(
+                                "password".to_owned(),
+                            ))),
+                        ),
+                        (6.into(), AReturn),
+                    ]),
+                ),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn anonymize_class() {
+        let original = class();
+        let salted = Anonymizer::new("salt");
+        let anonymized = salted.anonymize(&original);
+
+        let name = salted.class_name("com/acme/Secret$Inner");
+        assert_eq!(anonymized.binary_name, name);
+        assert!(!name.contains("acme") && !name.contains("Secret"));
+        assert_eq!(name.matches('/').count(), 2);
+        assert_eq!(name.matches('$').count(), 1);
+        assert_eq!(
+            salted.class_name("java/lang/Runnable"),
+            "java/lang/Runnable"
+        );
+        assert_eq!(
+            salted.class_name("com/acme/Anon$1").rsplit('$').next(),
+            Some("1")
+        );
+        assert_eq!(anonymized.interfaces, original.interfaces);
+        assert_eq!(anonymized.source_file, None);
+
+        let debug = format!("{anonymized:?}");
+        for secret in ["acme", "Secret", "leak", "secretHelper", "password"] {
+            assert!(!debug.contains(secret), "{secret} is not anonymized");
+        }
+        // `run` overrides a library method, so it keeps its name.
+        assert_eq!(anonymized.methods[0].name, "run");
+        assert_eq!(
+            Anonymizer::new("salt").anonymize(&original).methods[1].name,
+            anonymized.methods[1].name
+        );
+        assert_ne!(
+            Anonymizer::new("pepper").anonymize(&original).methods[1].name,
+            anonymized.methods[1].name
+        );
+
+        for (original, anonymized) in original.methods.iter().zip(&anonymized.methods) {
+            let expected = original.brew().unwrap().instructions.iter().count();
+            let actual = anonymized.brew().unwrap().instructions.iter().count();
+            assert_eq!(expected, actual);
+        }
+    }
+}
@@ -0,0 +1,501 @@
+//! The public API surface of a set of classes.
+//!
+//! [`extract`] collects the `public` classes and their `public` and `protected` members, together
+//! with their generic signatures, annotations, and `throws` clauses, into an [`ApiModel`].
+//! The model is ordered by names so that two models can be compared directly, e.g., to check the
+//! compatibility of two versions of a library.
+//! A model can be written in a line-based text format with [`Display`] and read back with
+//! [`ApiModel::parse`].
+//!
+//! The text format has one line per declaration, nested by indentation:
+//! ```text
+//! class 0x0001 org/mokapot/Example
+//!   extends java/lang/Object
+//!   annotation Ljava/lang/Deprecated;
+//!   field 0x0019 NAME Ljava/lang/String;
+//!   method 0x0001 run (I)V
+//!     throws java/io/IOException
+//!     parameter 0 Lorg/mokapot/NonNull;
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use crate::{
+    jvm::{class, field, method, references::ClassRef, Annotation, Class, Field, Method},
+    types::{field_type::FieldType, method_descriptor::MethodDescriptor},
+};
+
+/// An error when parsing an API model.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Malformed API model at line {line}: {content}")]
+pub struct ParseError {
+    /// The line number (starting from 1).
+    pub line: usize,
+    /// The content of the line.
+    pub content: String,
+}
+
+/// The API surface of a set of classes. See the [module-level documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiModel {
+    /// The `public` classes.
+    pub classes: BTreeMap<ClassRef, ApiClass>,
+}
+
+/// The API of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiClass {
+    /// The access flags of the class, without `ACC_SUPER`.
+    pub access_flags: class::AccessFlags,
+    /// The superclass.
+    pub super_class: Option<ClassRef>,
+    /// The interfaces implemented by the class.
+    pub interfaces: BTreeSet<ClassRef>,
+    /// The generic signature.
+    pub signature: Option<class::Signature>,
+    /// The types of the annotations on the class.
+    pub annotations: BTreeSet<FieldType>,
+    /// The `public` and `protected` fields, keyed by name.
+    pub fields: BTreeMap<String, ApiField>,
+    /// The `public` and `protected` methods, keyed by name and descriptor.
+    pub methods: BTreeMap<(String, MethodDescriptor), ApiMethod>,
+}
+
+/// The API of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiField {
+    /// The access flags of the field.
+    pub access_flags: field::AccessFlags,
+    /// The type of the field.
+    pub field_type: FieldType,
+    /// The generic signature.
+    pub signature: Option<field::Signature>,
+    /// The types of the annotations on the field.
+    pub annotations: BTreeSet<FieldType>,
+}
+
+/// The API of a method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiMethod {
+    /// The access flags of the method.
+    pub access_flags: method::AccessFlags,
+    /// The checked exceptions in the `throws` clause.
+    pub exceptions: BTreeSet<ClassRef>,
+    /// The generic signature.
+    pub signature: Option<method::Signature>,
+    /// The types of the annotations on the method.
+    pub annotations: BTreeSet<FieldType>,
+    /// The types of the annotations on the parameters, keyed by the index of the parameter.
+    /// Parameters without annotations are omitted.
+    pub parameter_annotations: BTreeMap<usize, BTreeSet<FieldType>>,
+}
+
+/// Extracts the API surface of `classes`.
+/// Non-`public` classes, synthetic classes and members, and members that are neither `public` nor
+/// `protected` are not part of the API.
+#[must_use]
+pub fn extract(classes: &[Class]) -> ApiModel {
+    let classes = classes
+        .iter()
+        .filter(|it| {
+            it.access_flags.contains(class::AccessFlags::PUBLIC)
+                && !it.access_flags.contains(class::AccessFlags::SYNTHETIC)
+                && !it.is_synthetic
+        })
+        .map(|it| (it.as_ref(), ApiClass::of(it)))
+        .collect();
+    ApiModel { classes }
+}
+
+impl ApiClass {
+    fn of(class: &Class) -> Self {
+        let fields = class
+            .fields
+            .iter()
+            .filter(|it| is_api_field(it))
+            .map(|it| (it.name.clone(), ApiField::of(it)))
+            .collect();
+        let methods = class
+            .methods
+            .iter()
+            .filter(|it| is_api_method(it))
+            .map(|it| ((it.name.clone(), it.descriptor.clone()), ApiMethod::of(it)))
+            .collect();
+        Self {
+            access_flags: class.access_flags - class::AccessFlags::SUPER,
+            super_class: class.super_class.clone(),
+            interfaces: class.interfaces.iter().cloned().collect(),
+            signature: class.signature.clone(),
+            annotations: annotation_types(
+                &class.runtime_visible_annotations,
+                &class.runtime_invisible_annotations,
+            ),
+            fields,
+            methods,
+        }
+    }
+}
+
+impl ApiField {
+    fn of(field: &Field) -> Self {
+        Self {
+            access_flags: field.access_flags,
+            field_type: field.field_type.clone(),
+            signature: field.signature.clone(),
+            annotations: annotation_types(
+                &field.runtime_visible_annotations,
+                &field.runtime_invisible_annotations,
+            ),
+        }
+    }
+}
+
+impl ApiMethod {
+    fn of(method: &Method) -> Self {
+        let visible = &method.runtime_visible_parameter_annotations;
+        let invisible = &method.runtime_invisible_parameter_annotations;
+        let parameter_annotations = (0..visible.len().max(invisible.len()))
+            .map(|idx| {
+                let annotations = annotation_types(
+                    visible.get(idx).map_or(&[], Vec::as_slice),
+                    invisible.get(idx).map_or(&[], Vec::as_slice),
+                );
+                (idx, annotations)
+            })
+            .filter(|(_, annotations)| !annotations.is_empty())
+            .collect();
+        Self {
+            access_flags: method.access_flags,
+            exceptions: method.exceptions.iter().cloned().collect(),
+            signature: method.signature.clone(),
+            annotations: annotation_types(
+                &method.runtime_visible_annotations,
+                &method.runtime_invisible_annotations,
+            ),
+            parameter_annotations,
+        }
+    }
+}
+
+fn is_api_field(field: &Field) -> bool {
+    field
+        .access_flags
+        .intersects(field::AccessFlags::PUBLIC | field::AccessFlags::PROTECTED)
+        && !field.access_flags.contains(field::AccessFlags::SYNTHETIC)
+        && !field.is_synthetic
+}
+
+fn is_api_method(method: &Method) -> bool {
+    method
+        .access_flags
+        .intersects(method::AccessFlags::PUBLIC | method::AccessFlags::PROTECTED)
+        && !method.access_flags.contains(method::AccessFlags::SYNTHETIC)
+        && !method.is_synthetic
+}
+
+fn annotation_types(visible: &[Annotation], invisible: &[Annotation]) -> BTreeSet<FieldType> {
+    visible
+        .iter()
+        .chain(invisible)
+        .map(|it| it.annotation_type.clone())
+        .collect()
+}
+
+impl Display for ApiModel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (class_ref, class) in &self.classes {
+            writeln!(f, "class {:#06x} {class_ref}", class.access_flags.bits())?;
+            if let Some(super_class) = &class.super_class {
+                writeln!(f, "  extends {super_class}")?;
+            }
+            for interface in &class.interfaces {
+                writeln!(f, "  implements {interface}")?;
+            }
+            write_details(f, "  ", class.signature.as_ref(), &class.annotations)?;
+            for (name, field) in &class.fields {
+                writeln!(
+                    f,
+                    "  field {:#06x} {name} {}",
+                    field.access_flags.bits(),
+                    field.field_type.descriptor()
+                )?;
+                write_details(f, "    ", field.signature.as_ref(), &field.annotations)?;
+            }
+            for ((name, descriptor), method) in &class.methods {
+                writeln!(
+                    f,
+                    "  method {:#06x} {name} {}",
+                    method.access_flags.bits(),
+                    descriptor.descriptor()
+                )?;
+                for exception in &method.exceptions {
+                    writeln!(f, "    throws {exception}")?;
+                }
+                write_details(f, "    ", method.signature.as_ref(), &method.annotations)?;
+                for (idx, annotations) in &method.parameter_annotations {
+                    for annotation in annotations {
+                        writeln!(f, "    parameter {idx} {}", annotation.descriptor())?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_details(
+    f: &mut Formatter<'_>,
+    indent: &str,
+    signature: Option<&String>,
+    annotations: &BTreeSet<FieldType>,
+) -> fmt::Result {
+    if let Some(signature) = signature {
+        writeln!(f, "{indent}signature {signature}")?;
+    }
+    for annotation in annotations {
+        writeln!(f, "{indent}annotation {}", annotation.descriptor())?;
+    }
+    Ok(())
+}
+
+/// The declaration that the indented lines following it belong to.
+enum Member {
+    Class,
+    Field(String),
+    Method(String, MethodDescriptor),
+}
+
+impl ApiModel {
+    /// Parses an API model in the text format written by its [`Display`] implementation.
+    ///
+    /// # Errors
+    /// See [`ParseError`].
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut classes = BTreeMap::new();
+        let mut current: Option<(ClassRef, ApiClass)> = None;
+        let mut member = Member::Class;
+        for (idx, line) in text.lines().enumerate() {
+            let error = || ParseError {
+                line: idx + 1,
+                content: line.to_owned(),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let indent = line.len() - line.trim_start().len();
+            let tokens: Vec<_> = line.split_whitespace().collect();
+            match (indent, tokens.as_slice()) {
+                (0, ["class", flags, name]) => {
+                    classes.extend(current.take());
+                    let access_flags = parse_flags(flags).ok_or_else(error)?;
+                    let class = ApiClass {
+                        access_flags: class::AccessFlags::from_bits_retain(access_flags),
+                        super_class: None,
+                        interfaces: BTreeSet::new(),
+                        signature: None,
+                        annotations: BTreeSet::new(),
+                        fields: BTreeMap::new(),
+                        methods: BTreeMap::new(),
+                    };
+                    current = Some((ClassRef::new(*name), class));
+                    member = Member::Class;
+                }
+                (2, tokens) => {
+                    let (_, class) = current.as_mut().ok_or_else(error)?;
+                    member = parse_class_line(class, tokens).ok_or_else(error)?;
+                }
+                (4, tokens) => {
+                    let (_, class) = current.as_mut().ok_or_else(error)?;
+                    parse_member_line(class, &member, tokens).ok_or_else(error)?;
+                }
+                _ => return Err(error()),
+            }
+        }
+        classes.extend(current);
+        Ok(Self { classes })
+    }
+}
+
+fn parse_flags(flags: &str) -> Option<u16> {
+    u16::from_str_radix(flags.strip_prefix("0x")?, 16).ok()
+}
+
+fn parse_class_line(class: &mut ApiClass, tokens: &[&str]) -> Option<Member> {
+    match tokens {
+        ["extends", super_class] => class.super_class = Some(ClassRef::new(*super_class)),
+        ["implements", interface] => {
+            class.interfaces.insert(ClassRef::new(*interface));
+        }
+        ["signature", signature] => class.signature = Some((*signature).to_owned()),
+        ["annotation", annotation] => {
+            class
+                .annotations
+                .insert(FieldType::from_str(annotation).ok()?);
+        }
+        ["field", flags, name, field_type] => {
+            let field = ApiField {
+                access_flags: field::AccessFlags::from_bits_retain(parse_flags(flags)?),
+                field_type: FieldType::from_str(field_type).ok()?,
+                signature: None,
+                annotations: BTreeSet::new(),
+            };
+            class.fields.insert((*name).to_owned(), field);
+            return Some(Member::Field((*name).to_owned()));
+        }
+        ["method", flags, name, descriptor] => {
+            let descriptor = MethodDescriptor::from_str(descriptor).ok()?;
+            let method = ApiMethod {
+                access_flags: method::AccessFlags::from_bits_retain(parse_flags(flags)?),
+                exceptions: BTreeSet::new(),
+                signature: None,
+                annotations: BTreeSet::new(),
+                parameter_annotations: BTreeMap::new(),
+            };
+            let key = ((*name).to_owned(), descriptor);
+            class.methods.insert(key.clone(), method);
+            return Some(Member::Method(key.0, key.1));
+        }
+        _ => return None,
+    }
+    Some(Member::Class)
+}
+
+fn parse_member_line(class: &mut ApiClass, member: &Member, tokens: &[&str]) -> Option<()> {
+    match (member, tokens) {
+        (Member::Field(name), ["signature", signature]) => {
+            class.fields.get_mut(name)?.signature = Some((*signature).to_owned());
+        }
+        (Member::Field(name), ["annotation", annotation]) => {
+            let annotation = FieldType::from_str(annotation).ok()?;
+            class.fields.get_mut(name)?.annotations.insert(annotation);
+        }
+        (Member::Method(name, descriptor), tokens) => {
+            let method = class.methods.get_mut(&(name.clone(), descriptor.clone()))?;
+            match tokens {
+                ["throws", exception] => {
+                    method.exceptions.insert(ClassRef::new(*exception));
+                }
+                ["signature", signature] => method.signature = Some((*signature).to_owned()),
+                ["annotation", annotation] => {
+                    method
+                        .annotations
+                        .insert(FieldType::from_str(annotation).ok()?);
+                }
+                ["parameter", idx, annotation] => {
+                    method
+                        .parameter_annotations
+                        .entry(idx.parse().ok()?)
+                        .or_default()
+                        .insert(FieldType::from_str(annotation).ok()?);
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    fn library() -> Vec<Class> {
+        let public = jasmin::read(
+            r"
+            .class public org/mokapot/Api
+            .super java/lang/Object
+            .implements java/lang/Runnable
+            .field public static final NAME Ljava/lang/String;
+            .field protected count I
+            .field private secret I
+            .method public run()V
+                .limit stack 0
+                .limit locals 1
+                return
+            .end method
+            .method protected native load(Ljava/lang/String;)I
+                .throws java/io/IOException
+            .end method
+            .method helper()V
+                .limit stack 0
+                .limit locals 1
+                return
+            .end method
+            ",
+        )
+        .unwrap();
+        let internal = jasmin::read(
+            r"
+            .class org/mokapot/Internal
+            .super java/lang/Object
+            ",
+        )
+        .unwrap();
+        vec![public, internal]
+    }
+
+    #[test]
+    fn extract_public_members() {
+        let model = extract(&library());
+        assert_eq!(model.classes.len(), 1);
+        let api = &model.classes[&ClassRef::new("org/mokapot/Api")];
+        assert!(!api.access_flags.contains(class::AccessFlags::SUPER));
+        assert_eq!(
+            api.interfaces,
+            BTreeSet::from([ClassRef::new("java/lang/Runnable")])
+        );
+        assert_eq!(
+            api.fields.keys().map(String::as_str).collect::<Vec<_>>(),
+            ["NAME", "count"]
+        );
+        assert_eq!(
+            api.methods
+                .keys()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>(),
+            ["load", "run"]
+        );
+        let load = api
+            .methods
+            .iter()
+            .find_map(|((name, _), it)| (name == "load").then_some(it))
+            .unwrap();
+        assert_eq!(
+            load.exceptions,
+            BTreeSet::from([ClassRef::new("java/io/IOException")])
+        );
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut classes = library();
+        let method = &mut classes[0].methods[0];
+        method.signature = Some("<T:Ljava/lang/Object;>()V".to_owned());
+        method.runtime_visible_parameter_annotations = vec![vec![Annotation {
+            annotation_type: FieldType::from_str("Lorg/mokapot/NonNull;").unwrap(),
+            element_value_pairs: Vec::new(),
+        }]];
+        classes[0].runtime_invisible_annotations = vec![Annotation {
+            annotation_type: FieldType::from_str("Ljava/lang/Deprecated;").unwrap(),
+            element_value_pairs: Vec::new(),
+        }];
+        let model = extract(&classes);
+        let text = model.to_string();
+        assert!(text.contains("  annotation Ljava/lang/Deprecated;\n"));
+        assert!(text.contains("    throws java/io/IOException\n"));
+        assert_eq!(ApiModel::parse(&text).unwrap(), model);
+        assert_eq!(
+            ApiModel::parse("  field 0x0001 x I"),
+            Err(ParseError {
+                line: 1,
+                content: "  field 0x0001 x I".to_owned()
+            })
+        );
+    }
+}
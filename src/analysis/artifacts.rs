@@ -0,0 +1,522 @@
+//! A content-addressable store for analysis artifacts shared across tools.
+//!
+//! Artifacts (e.g., serialized IR, call graphs, or method summaries) are keyed by an
+//! [`ArtifactKey`], which consists of the kind of the artifact, the [`Digest`] of the analyzed
+//! input, and the [`Digest`] of the analysis configuration.
+//! The content of each artifact is stored once under its own digest, so identical artifacts
+//! computed by different tools share the same storage.
+//!
+//! The store lives in a directory and can be used by multiple processes at the same time.
+//! Files are written to a temporary location and then renamed, so readers never observe partial
+//! writes, and the content is verified against its digest when read.
+//! Unused artifacts are removed with [`ArtifactStore::gc`] unless they are held by a [`Lease`].
+//! Leases left behind by crashed processes expire like unused artifacts.
+
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use sha2::{Digest as _, Sha256};
+
+/// A SHA-256 digest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Digest([u8; 32]);
+
+impl Digest {
+    /// Computes the digest of `bytes`.
+    #[must_use]
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut hasher = Hasher::default();
+        hasher.update(bytes);
+        hasher.finish()
+    }
+
+    /// Returns the bytes of the digest.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Display for Digest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+/// An error indicating that a string is not a hexadecimal SHA-256 digest.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid digest")]
+pub struct InvalidDigest;
+
+impl FromStr for Digest {
+    type Err = InvalidDigest;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        if hex.len() != 64 || !hex.is_ascii() {
+            return Err(InvalidDigest);
+        }
+        let mut digest = [0; 32];
+        for (byte, idx) in digest.iter_mut().zip((0..hex.len()).step_by(2)) {
+            *byte = u8::from_str_radix(&hex[idx..idx + 2], 16).map_err(|_| InvalidDigest)?;
+        }
+        Ok(Self(digest))
+    }
+}
+
+/// An incremental SHA-256 hasher producing a [`Digest`].
+#[derive(Debug, Clone, Default)]
+pub struct Hasher(Sha256);
+
+impl Hasher {
+    /// Feeds `bytes` into the hasher.
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    /// Returns the digest of the bytes fed so far.
+    #[must_use]
+    pub fn finish(self) -> Digest {
+        Digest(self.0.finalize().into())
+    }
+}
+
+/// The key of an artifact.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArtifactKey {
+    /// The kind of the artifact (e.g., `"call-graph"`).
+    pub kind: String,
+    /// The digest of the analyzed input (e.g., the bytes of the classes).
+    pub input: Digest,
+    /// The digest of the analysis configuration.
+    pub config: Digest,
+}
+
+impl ArtifactKey {
+    /// Creates a key.
+    pub fn new(kind: impl Into<String>, input: Digest, config: Digest) -> Self {
+        Self {
+            kind: kind.into(),
+            input,
+            config,
+        }
+    }
+
+    /// Returns the digest identifying the key in the store.
+    #[must_use]
+    pub fn digest(&self) -> Digest {
+        let mut hasher = Hasher::default();
+        hasher.update(&(self.kind.len() as u64).to_be_bytes());
+        hasher.update(self.kind.as_bytes());
+        hasher.update(self.input.as_bytes());
+        hasher.update(self.config.as_bytes());
+        hasher.finish()
+    }
+}
+
+/// The result of a garbage collection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct GcReport {
+    /// The number of removed keys.
+    pub removed_entries: usize,
+    /// The number of removed artifact contents.
+    pub removed_objects: usize,
+    /// The number of bytes freed.
+    pub freed_bytes: u64,
+    /// The number of removed leases that were not renewed in time.
+    pub expired_leases: usize,
+}
+
+/// A content-addressable store of analysis artifacts in a directory.
+#[derive(Debug, Clone)]
+pub struct ArtifactStore {
+    root: PathBuf,
+}
+
+const OBJECTS: &str = "objects";
+const ENTRIES: &str = "entries";
+const LEASES: &str = "leases";
+const TMP: &str = "tmp";
+
+impl ArtifactStore {
+    /// Opens the store in `root`, creating the directory if it does not exist.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if the directory cannot be created.
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        for dir in [OBJECTS, ENTRIES, LEASES, TMP] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        Ok(Self { root })
+    }
+
+    /// Returns the directory of the store.
+    #[must_use]
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Stores `content` as the artifact of `key`, replacing the previous one.
+    /// Returns the digest of `content`.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if writing fails.
+    pub fn put(&self, key: &ArtifactKey, content: &[u8]) -> io::Result<Digest> {
+        let digest = Digest::of(content);
+        // The object is rewritten even if it exists so that a concurrent GC sees a fresh file.
+        self.write_atomically(&self.object_path(&digest), content)?;
+        self.write_atomically(&self.entry_path(key), digest.to_string().as_bytes())?;
+        Ok(digest)
+    }
+
+    /// Returns the artifact of `key`, or `None` if it is not in the store.
+    /// Artifacts whose content does not match its digest are treated as absent.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if reading fails.
+    pub fn get(&self, key: &ArtifactKey) -> io::Result<Option<Vec<u8>>> {
+        let entry = self.entry_path(key);
+        let Some(digest) = read_if_exists(&entry)? else {
+            return Ok(None);
+        };
+        let digest = String::from_utf8_lossy(&digest).into_owned();
+        let Some(content) = read_if_exists(&self.root.join(OBJECTS).join(&digest))? else {
+            return Ok(None);
+        };
+        if Digest::of(&content).to_string() != digest {
+            return Ok(None);
+        }
+        // Mark the entry as recently used. Failing to do so only makes it a candidate for GC.
+        let _ = fs::File::options()
+            .append(true)
+            .open(&entry)
+            .and_then(|it| it.set_modified(SystemTime::now()));
+        Ok(Some(content))
+    }
+
+    /// Returns the artifact of `key`, computing and storing it with `compute` if it is absent.
+    ///
+    /// # Errors
+    /// Returns the error of `compute`, or an [`io::Error`] if accessing the store fails.
+    pub fn get_or_insert_with<E>(
+        &self,
+        key: &ArtifactKey,
+        compute: impl FnOnce() -> Result<Vec<u8>, E>,
+    ) -> Result<Vec<u8>, E>
+    where
+        E: From<io::Error>,
+    {
+        if let Some(content) = self.get(key)? {
+            return Ok(content);
+        }
+        let content = compute()?;
+        self.put(key, &content)?;
+        Ok(content)
+    }
+
+    /// Removes the key from the store. Its content is removed by the next GC if no other key
+    /// refers to it.
+    /// Returns `true` if the key was in the store.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if removing fails.
+    pub fn remove(&self, key: &ArtifactKey) -> io::Result<bool> {
+        remove_if_exists(&self.entry_path(key))
+    }
+
+    /// Holds the artifact of `key` so that it is not removed by [`ArtifactStore::gc`] until
+    /// the returned [`Lease`] is dropped. Multiple leases of the same key may coexist.
+    /// A lease that is not [renewed](Lease::renew) for longer than the `max_idle` of a GC is
+    /// considered abandoned and removed.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if creating the lease fails.
+    pub fn lease(&self, key: &ArtifactKey) -> io::Result<Lease> {
+        let path = self
+            .root
+            .join(LEASES)
+            .join(format!("{}.{}", key.digest(), unique_suffix()));
+        fs::write(&path, [])?;
+        Ok(Lease { path })
+    }
+
+    /// Removes the leases that have not been renewed for `max_idle`, the keys that are not
+    /// leased and have not been used for `max_idle`, and then the contents that are no longer
+    /// referred to by any key and are older than `max_idle`.
+    /// Files removed concurrently by other processes are treated as already collected.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if accessing the store fails.
+    pub fn gc(&self, max_idle: Duration) -> io::Result<GcReport> {
+        let now = SystemTime::now();
+        let is_idle = |metadata: &fs::Metadata| {
+            metadata
+                .modified()
+                .is_ok_and(|it| now.duration_since(it).unwrap_or_default() >= max_idle)
+        };
+        let mut report = GcReport::default();
+
+        let mut leased = HashSet::new();
+        for (name, path) in list_dir(&self.root.join(LEASES))? {
+            if self.remove_if_idle(&path, is_idle)?.is_some() {
+                report.expired_leases += 1;
+            } else if let Some(key) = name.split('.').next() {
+                leased.insert(key.to_owned());
+            }
+        }
+
+        let mut referenced = HashSet::new();
+        for (name, path) in list_dir(&self.root.join(ENTRIES))? {
+            if !leased.contains(&name) && self.remove_if_idle(&path, is_idle)?.is_some() {
+                report.removed_entries += 1;
+            } else if let Some(digest) = read_if_exists(&path)? {
+                referenced.insert(String::from_utf8_lossy(&digest).into_owned());
+            }
+        }
+
+        for (name, path) in list_dir(&self.root.join(OBJECTS))? {
+            if referenced.contains(&name) {
+                continue;
+            }
+            if let Some(size) = self.remove_if_idle(&path, is_idle)? {
+                report.removed_objects += 1;
+                report.freed_bytes += size;
+            }
+        }
+        Ok(report)
+    }
+
+    /// Removes `path` if it is idle and returns its size.
+    /// The file is moved aside before it is checked, so a file that a concurrent writer puts in
+    /// place after the check is not removed. If the moved file turns out to be fresh, it is put
+    /// back unless it has been replaced in the meantime.
+    fn remove_if_idle(
+        &self,
+        path: &Path,
+        is_idle: impl Fn(&fs::Metadata) -> bool,
+    ) -> io::Result<Option<u64>> {
+        match fs::metadata(path) {
+            Ok(metadata) if is_idle(&metadata) => {}
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let aside = self.root.join(TMP).join(unique_suffix());
+        match fs::rename(path, &aside) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        let metadata = fs::metadata(&aside)?;
+        if is_idle(&metadata) {
+            fs::remove_file(&aside)?;
+            return Ok(Some(metadata.len()));
+        }
+        match fs::hard_link(&aside, path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e),
+        }
+        fs::remove_file(&aside)?;
+        Ok(None)
+    }
+
+    fn object_path(&self, digest: &Digest) -> PathBuf {
+        self.root.join(OBJECTS).join(digest.to_string())
+    }
+
+    fn entry_path(&self, key: &ArtifactKey) -> PathBuf {
+        self.root.join(ENTRIES).join(key.digest().to_string())
+    }
+
+    fn write_atomically(&self, path: &Path, content: &[u8]) -> io::Result<()> {
+        let tmp = self.root.join(TMP).join(unique_suffix());
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp);
+        })
+    }
+}
+
+/// A hold on an artifact preventing it from being garbage collected.
+/// The hold is released when the lease is dropped.
+#[derive(Debug)]
+pub struct Lease {
+    path: PathBuf,
+}
+
+impl Lease {
+    /// Renews the lease so that it does not expire in the next GC.
+    /// Holders of long-lived leases should renew them more often than the `max_idle` of the GC.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if the lease has expired or cannot be renewed.
+    pub fn renew(&self) -> io::Result<()> {
+        fs::File::options()
+            .append(true)
+            .open(&self.path)?
+            .set_modified(SystemTime::now())
+    }
+}
+
+impl Drop for Lease {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn unique_suffix() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!(
+        "{}-{nanos}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(path) {
+        Ok(it) => Ok(Some(it)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn list_dir(dir: &Path) -> io::Result<Vec<(String, PathBuf)>> {
+    fs::read_dir(dir)?
+        .map(|entry| {
+            let entry = entry?;
+            Ok((
+                entry.file_name().to_string_lossy().into_owned(),
+                entry.path(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256() {
+        assert_eq!(
+            Digest::of(b"").to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        let mut hasher = Hasher::default();
+        (0..1000).for_each(|_| hasher.update(b"a"));
+        assert_eq!(
+            hasher.finish().to_string(),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+        let digest = Digest::of(b"mokapot");
+        assert_eq!(digest.to_string().parse(), Ok(digest));
+        assert_eq!("e3b0".parse::<Digest>(), Err(InvalidDigest));
+    }
+
+    const MAX_IDLE: Duration = Duration::from_hours(1);
+
+    fn temp_store() -> ArtifactStore {
+        let root = std::env::temp_dir().join(format!("mokapot-artifacts-{}", unique_suffix()));
+        ArtifactStore::open(root).unwrap()
+    }
+
+    /// Makes every file in the store look unused for longer than [`MAX_IDLE`].
+    fn age(store: &ArtifactStore) {
+        let past = SystemTime::now() - 2 * MAX_IDLE;
+        for dir in [OBJECTS, ENTRIES, LEASES] {
+            for (_, path) in list_dir(&store.root().join(dir)).unwrap() {
+                fs::File::options()
+                    .append(true)
+                    .open(path)
+                    .and_then(|it| it.set_modified(past))
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn store_and_collect() {
+        let store = temp_store();
+        let input = Digest::of(b"classes");
+        let ir = ArtifactKey::new("ir", input, Digest::of(b"default"));
+        let call_graph = ArtifactKey::new("call-graph", input, Digest::of(b"default"));
+        let summary = ArtifactKey::new("summary", input, Digest::of(b"default"));
+
+        assert_eq!(store.get(&ir).unwrap(), None);
+        let computed = store
+            .get_or_insert_with(&ir, || Ok::<_, io::Error>(b"ir".to_vec()))
+            .unwrap();
+        assert_eq!(computed, b"ir");
+        let cached = store
+            .get_or_insert_with(&ir, || -> io::Result<_> { unreachable!() })
+            .unwrap();
+        assert_eq!(cached, b"ir");
+        store.put(&call_graph, b"shared").unwrap();
+        store.put(&summary, b"shared").unwrap();
+
+        assert_eq!(store.gc(MAX_IDLE).unwrap(), GcReport::default());
+        let lease = store.lease(&ir).unwrap();
+        assert!(store.remove(&summary).unwrap());
+        age(&store);
+        lease.renew().unwrap();
+        assert_eq!(
+            store.gc(MAX_IDLE).unwrap(),
+            GcReport {
+                removed_entries: 1,
+                removed_objects: 1,
+                freed_bytes: 6,
+                expired_leases: 0,
+            }
+        );
+        assert_eq!(store.get(&ir).unwrap().as_deref(), Some(&b"ir"[..]));
+        assert_eq!(store.get(&summary).unwrap(), None);
+        drop(lease);
+        assert_eq!(store.gc(Duration::ZERO).unwrap().removed_entries, 1);
+        assert_eq!(store.get(&ir).unwrap(), None);
+        fs::remove_dir_all(store.root()).unwrap();
+    }
+
+    #[test]
+    fn expire_abandoned_leases() {
+        let store = temp_store();
+        let key = ArtifactKey::new("ir", Digest::of(b"classes"), Digest::of(b"default"));
+        store.put(&key, b"ir").unwrap();
+        // A process crashes while holding the lease.
+        std::mem::forget(store.lease(&key).unwrap());
+        age(&store);
+        assert_eq!(
+            store.gc(MAX_IDLE).unwrap(),
+            GcReport {
+                removed_entries: 1,
+                removed_objects: 1,
+                freed_bytes: 2,
+                expired_leases: 1,
+            }
+        );
+        assert_eq!(store.get(&key).unwrap(), None);
+        // Files removed by another process are already collected.
+        assert_eq!(store.gc(Duration::ZERO).unwrap(), GcReport::default());
+        fs::remove_dir_all(store.root()).unwrap();
+    }
+}
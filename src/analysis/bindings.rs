@@ -0,0 +1,539 @@
+//! Generation of Rust struct definitions from Java data transfer object (DTO) classes.
+//!
+//! A record is mapped from its components, and any other concrete class (e.g., a Java bean) is
+//! mapped from its instance fields that are neither `static` nor `transient`.
+//! Reference typed fields become [`Option`]s unless they are annotated as non-null (see
+//! [`Nullness::from_annotations`]).
+
+use std::fmt::Display;
+
+use crate::{
+    jvm::{class, field, references::ClassRef, Annotation, Class, Field, TypeAnnotation},
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+use super::nullness::Nullness;
+
+/// An error that can occur when generating a binding.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BindingError {
+    /// The class is an interface, an annotation interface or a module.
+    #[error("{0} is not a class")]
+    NotAClass(ClassRef),
+    /// The class is abstract.
+    #[error("{0} is abstract")]
+    Abstract(ClassRef),
+    /// The class is an enum.
+    #[error("{0} is an enum")]
+    Enum(ClassRef),
+}
+
+/// A generated Rust struct definition.
+/// Its [`Display`] implementation renders the Rust source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustStruct {
+    /// The Java class from which the struct is generated.
+    pub java_class: ClassRef,
+    /// The name of the struct.
+    pub name: String,
+    /// The fields of the struct.
+    pub fields: Vec<RustField>,
+    /// Whether to derive `serde::Serialize` and `serde::Deserialize`.
+    pub serde: bool,
+}
+
+/// A field of a [`RustStruct`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RustField {
+    /// The name of the field in Rust.
+    pub name: String,
+    /// The name of the field in Java.
+    pub java_name: String,
+    /// The type of the field, excluding the [`Option`] wrapper.
+    pub rust_type: String,
+    /// Whether the field is wrapped in an [`Option`].
+    pub optional: bool,
+}
+
+/// A generator of [`RustStruct`]s.
+#[derive(Debug, Clone)]
+pub struct BindingGenerator {
+    serde: bool,
+    unannotated_nullable: bool,
+}
+
+impl Default for BindingGenerator {
+    fn default() -> Self {
+        Self {
+            serde: true,
+            unannotated_nullable: true,
+        }
+    }
+}
+
+impl BindingGenerator {
+    /// Creates a generator that derives the serde traits and treats unannotated reference types
+    /// as nullable.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to derive the serde traits and emit the serde attributes.
+    #[must_use]
+    pub const fn with_serde(self, serde: bool) -> Self {
+        Self { serde, ..self }
+    }
+
+    /// Sets whether reference typed fields without nullness annotations are wrapped in an
+    /// [`Option`].
+    #[must_use]
+    pub const fn with_unannotated_nullable(self, unannotated_nullable: bool) -> Self {
+        Self {
+            unannotated_nullable,
+            ..self
+        }
+    }
+
+    /// Generates the Rust struct for `class`.
+    ///
+    /// # Errors
+    /// See [`BindingError`].
+    pub fn generate(&self, class: &Class) -> Result<RustStruct, BindingError> {
+        let flags = class.access_flags;
+        if flags.intersects(
+            class::AccessFlags::INTERFACE
+                | class::AccessFlags::ANNOTATION
+                | class::AccessFlags::MODULE,
+        ) {
+            return Err(BindingError::NotAClass(class.as_ref()));
+        }
+        if flags.contains(class::AccessFlags::ENUM) {
+            return Err(BindingError::Enum(class.as_ref()));
+        }
+        if flags.contains(class::AccessFlags::ABSTRACT) {
+            return Err(BindingError::Abstract(class.as_ref()));
+        }
+
+        let fields = if let Some(components) = &class.record {
+            components
+                .iter()
+                .map(|it| {
+                    self.field(
+                        &it.name,
+                        &it.component_type,
+                        it.signature.as_deref(),
+                        &it.runtime_visible_annotations,
+                        &it.runtime_visible_type_annotations,
+                    )
+                })
+                .collect()
+        } else {
+            let excluded = field::AccessFlags::STATIC
+                | field::AccessFlags::TRANSIENT
+                | field::AccessFlags::SYNTHETIC;
+            class
+                .fields
+                .iter()
+                .filter(|it| !it.access_flags.intersects(excluded) && !it.is_synthetic)
+                .map(|it: &Field| {
+                    self.field(
+                        &it.name,
+                        &it.field_type,
+                        it.signature.as_deref(),
+                        &it.runtime_visible_annotations,
+                        &it.runtime_visible_type_annotations,
+                    )
+                })
+                .collect()
+        };
+        Ok(RustStruct {
+            java_class: class.as_ref(),
+            name: struct_name(&class.binary_name),
+            fields,
+            serde: self.serde,
+        })
+    }
+
+    fn field(
+        &self,
+        java_name: &str,
+        field_type: &FieldType,
+        signature: Option<&str>,
+        annotations: &[Annotation],
+        type_annotations: &[TypeAnnotation],
+    ) -> RustField {
+        let type_signature = signature
+            .and_then(TypeSignature::parse)
+            .unwrap_or_else(|| TypeSignature::from(field_type));
+        let (rust_type, optional) = match &type_signature {
+            TypeSignature::Base(_) => (type_signature.rust_type(), false),
+            TypeSignature::Class { name, args } if name == "java/util/Optional" => {
+                let inner = args.first().and_then(Option::as_ref);
+                (
+                    inner.map_or_else(json_value, TypeSignature::rust_type),
+                    true,
+                )
+            }
+            _ => {
+                // Type annotations on the outermost type apply to the field itself.
+                let type_use_annotations: Vec<_> = type_annotations
+                    .iter()
+                    .filter(|it| it.target_path.is_empty())
+                    .map(|it| Annotation {
+                        annotation_type: it.annotation_type.clone(),
+                        element_value_pairs: it.element_value_pairs.clone(),
+                    })
+                    .collect();
+                let optional = match Nullness::from_annotations(
+                    annotations.iter().chain(&type_use_annotations),
+                ) {
+                    Nullness::NonNull => false,
+                    Nullness::Null | Nullness::Nullable => true,
+                    Nullness::Unknown => self.unannotated_nullable,
+                };
+                (type_signature.rust_type(), optional)
+            }
+        };
+        RustField {
+            name: field_name(java_name),
+            java_name: java_name.to_owned(),
+            rust_type,
+            optional,
+        }
+    }
+}
+
+impl Display for RustStruct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "/// Generated from `{}`.", self.java_class)?;
+        if self.serde {
+            writeln!(
+                f,
+                "#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]"
+            )?;
+        } else {
+            writeln!(f, "#[derive(Debug, Clone, PartialEq)]")?;
+        }
+        writeln!(f, "pub struct {} {{", self.name)?;
+        for field in &self.fields {
+            if self.serde {
+                let mut attributes = Vec::new();
+                if field.name.trim_start_matches("r#") != field.java_name {
+                    attributes.push(format!("rename = \"{}\"", field.java_name));
+                }
+                if field.optional {
+                    attributes.push("default".to_owned());
+                    attributes.push("skip_serializing_if = \"Option::is_none\"".to_owned());
+                }
+                if !attributes.is_empty() {
+                    writeln!(f, "    #[serde({})]", attributes.join(", "))?;
+                }
+            }
+            if field.optional {
+                writeln!(f, "    pub {}: Option<{}>,", field.name, field.rust_type)?;
+            } else {
+                writeln!(f, "    pub {}: {},", field.name, field.rust_type)?;
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+fn struct_name(binary_name: &str) -> String {
+    binary_name
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .replace('$', "")
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false",
+    "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref",
+    "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while",
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+fn field_name(java_name: &str) -> String {
+    let mut name = String::with_capacity(java_name.len());
+    let mut previous_lowercase = false;
+    for ch in java_name.chars() {
+        if ch.is_uppercase() {
+            if previous_lowercase {
+                name.push('_');
+            }
+            name.extend(ch.to_lowercase());
+            previous_lowercase = false;
+        } else if ch == '$' {
+            name.push('_');
+            previous_lowercase = false;
+        } else {
+            name.push(ch);
+            previous_lowercase = ch.is_lowercase() || ch.is_ascii_digit();
+        }
+    }
+    match name.as_str() {
+        "self" | "super" | "crate" => format!("{name}_"),
+        it if RUST_KEYWORDS.contains(&it) => format!("r#{name}"),
+        _ => name,
+    }
+}
+
+fn json_value() -> String {
+    "serde_json::Value".to_owned()
+}
+
+/// The type of a field, including the type arguments from its generic signature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TypeSignature {
+    Base(PrimitiveType),
+    Class {
+        name: String,
+        /// The type arguments, where [`None`] stands for a wildcard or a lower bounded type.
+        args: Vec<Option<TypeSignature>>,
+    },
+    Array(Box<TypeSignature>),
+    TypeVariable,
+}
+
+impl From<&FieldType> for TypeSignature {
+    fn from(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Base(it) => Self::Base(*it),
+            FieldType::Object(class_ref) => Self::Class {
+                name: class_ref.binary_name.to_string(),
+                args: Vec::new(),
+            },
+            FieldType::Array(element_type) => Self::Array(Box::new(element_type.as_ref().into())),
+        }
+    }
+}
+
+impl TypeSignature {
+    fn parse(signature: &str) -> Option<Self> {
+        let (parsed, remaining) = Self::parse_prefix(signature)?;
+        remaining.is_empty().then_some(parsed)
+    }
+
+    fn parse_prefix(signature: &str) -> Option<(Self, &str)> {
+        let mut chars = signature.chars();
+        let first = chars.next()?;
+        let rest = chars.as_str();
+        match first {
+            'L' => {
+                let mut name = String::new();
+                let mut args = Vec::new();
+                let mut remaining = rest;
+                loop {
+                    let end = remaining.find(['<', '.', ';'])?;
+                    name.push_str(&remaining[..end]);
+                    remaining = &remaining[end..];
+                    args.clear();
+                    if let Some(mut in_args) = remaining.strip_prefix('<') {
+                        while let Some(first) = in_args.chars().next() {
+                            match first {
+                                '>' => break,
+                                '*' => {
+                                    args.push(None);
+                                    in_args = &in_args[1..];
+                                }
+                                '-' => {
+                                    let (_, rest) = Self::parse_prefix(&in_args[1..])?;
+                                    args.push(None);
+                                    in_args = rest;
+                                }
+                                '+' => {
+                                    let (arg, rest) = Self::parse_prefix(&in_args[1..])?;
+                                    args.push(Some(arg));
+                                    in_args = rest;
+                                }
+                                _ => {
+                                    let (arg, rest) = Self::parse_prefix(in_args)?;
+                                    args.push(Some(arg));
+                                    in_args = rest;
+                                }
+                            }
+                        }
+                        remaining = in_args.strip_prefix('>')?;
+                    }
+                    if let Some(rest) = remaining.strip_prefix('.') {
+                        name.push('$');
+                        remaining = rest;
+                    } else {
+                        remaining = remaining.strip_prefix(';')?;
+                        return Some((Self::Class { name, args }, remaining));
+                    }
+                }
+            }
+            'T' => {
+                let end = rest.find(';')?;
+                Some((Self::TypeVariable, &rest[end + 1..]))
+            }
+            '[' => {
+                let (element, remaining) = Self::parse_prefix(rest)?;
+                Some((Self::Array(Box::new(element)), remaining))
+            }
+            it => Some((Self::Base(PrimitiveType::try_from(it).ok()?), rest)),
+        }
+    }
+
+    fn rust_type(&self) -> String {
+        match self {
+            Self::Base(it) => primitive_rust_type(*it).to_owned(),
+            Self::Array(element) => format!("Vec<{}>", element.rust_type()),
+            Self::TypeVariable => json_value(),
+            Self::Class { name, args } => {
+                let arg = |index: usize| {
+                    args.get(index)
+                        .and_then(Option::as_ref)
+                        .map_or_else(json_value, Self::rust_type)
+                };
+                match name.as_str() {
+                    "java/lang/String" | "java/lang/CharSequence" => "String".to_owned(),
+                    "java/lang/Boolean" => "bool".to_owned(),
+                    "java/lang/Byte" => "i8".to_owned(),
+                    "java/lang/Short" => "i16".to_owned(),
+                    "java/lang/Character" => "u16".to_owned(),
+                    "java/lang/Integer" => "i32".to_owned(),
+                    "java/lang/Long" => "i64".to_owned(),
+                    "java/lang/Float" => "f32".to_owned(),
+                    "java/lang/Double" => "f64".to_owned(),
+                    "java/util/Optional" => format!("Option<{}>", arg(0)),
+                    "java/lang/Iterable"
+                    | "java/util/Collection"
+                    | "java/util/List"
+                    | "java/util/ArrayList"
+                    | "java/util/LinkedList"
+                    | "java/util/Set"
+                    | "java/util/HashSet"
+                    | "java/util/LinkedHashSet"
+                    | "java/util/SortedSet"
+                    | "java/util/TreeSet" => format!("Vec<{}>", arg(0)),
+                    "java/util/Map"
+                    | "java/util/HashMap"
+                    | "java/util/LinkedHashMap"
+                    | "java/util/SortedMap"
+                    | "java/util/TreeMap" => {
+                        format!("std::collections::HashMap<{}, {}>", arg(0), arg(1))
+                    }
+                    it if it.starts_with("java/") => json_value(),
+                    it => struct_name(it),
+                }
+            }
+        }
+    }
+}
+
+const fn primitive_rust_type(primitive_type: PrimitiveType) -> &'static str {
+    match primitive_type {
+        PrimitiveType::Boolean => "bool",
+        PrimitiveType::Char => "u16",
+        PrimitiveType::Float => "f32",
+        PrimitiveType::Double => "f64",
+        PrimitiveType::Byte => "i8",
+        PrimitiveType::Short => "i16",
+        PrimitiveType::Int => "i32",
+        PrimitiveType::Long => "i64",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, field_type: &str, signature: Option<&str>) -> Field {
+        Field {
+            access_flags: field::AccessFlags::PRIVATE,
+            name: name.to_owned(),
+            owner: ClassRef::new("org/mokapot/Person"),
+            field_type: field_type.parse().unwrap(),
+            signature: signature.map(str::to_owned),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn bean_binding() {
+        let mut age = field("age", "I", None);
+        age.access_flags |= field::AccessFlags::FINAL;
+        let mut name = field("firstName", "Ljava/lang/String;", None);
+        name.runtime_visible_annotations.push(Annotation {
+            annotation_type: "Ljakarta/annotation/Nonnull;".parse().unwrap(),
+            element_value_pairs: Vec::new(),
+        });
+        let mut counter = field("counter", "I", None);
+        counter.access_flags |= field::AccessFlags::STATIC;
+        let class = Class {
+            binary_name: "org/mokapot/Person".to_owned(),
+            fields: vec![
+                name,
+                age,
+                field(
+                    "tags",
+                    "Ljava/util/List;",
+                    Some("Ljava/util/List<Ljava/lang/String;>;"),
+                ),
+                field(
+                    "friends",
+                    "Ljava/util/Map;",
+                    Some("Ljava/util/Map<Ljava/lang/String;+Lorg/mokapot/Person$Friend;>;"),
+                ),
+                field("type", "[B", None),
+                counter,
+            ],
+            ..Default::default()
+        };
+        let binding = BindingGenerator::new().generate(&class).unwrap();
+        assert_eq!(
+            binding
+                .fields
+                .iter()
+                .map(|it| (it.name.as_str(), it.rust_type.as_str(), it.optional))
+                .collect::<Vec<_>>(),
+            vec![
+                ("first_name", "String", false),
+                ("age", "i32", false),
+                ("tags", "Vec<String>", true),
+                (
+                    "friends",
+                    "std::collections::HashMap<String, PersonFriend>",
+                    true
+                ),
+                ("r#type", "Vec<i8>", true),
+            ]
+        );
+        let source = binding.to_string();
+        assert!(source.contains("pub struct Person {"));
+        assert!(
+            source.contains("    #[serde(rename = \"firstName\")]\n    pub first_name: String,")
+        );
+        assert!(source.contains("    pub age: i32,"));
+        assert!(source.contains(
+            "    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n    pub r#type: Option<Vec<i8>>,"
+        ));
+    }
+
+    #[test]
+    fn not_a_dto() {
+        let class = Class {
+            binary_name: "org/mokapot/Shape".to_owned(),
+            access_flags: class::AccessFlags::ABSTRACT,
+            ..Default::default()
+        };
+        assert_eq!(
+            BindingGenerator::new().generate(&class),
+            Err(BindingError::Abstract(ClassRef::new("org/mokapot/Shape")))
+        );
+    }
+
+    #[test]
+    fn field_names() {
+        assert_eq!(field_name("firstName"), "first_name");
+        assert_eq!(field_name("URL"), "url");
+        assert_eq!(field_name("htmlURL2"), "html_url2");
+        assert_eq!(field_name("self"), "self_");
+        assert_eq!(field_name("match"), "r#match");
+    }
+}
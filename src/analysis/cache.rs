@@ -0,0 +1,318 @@
+//! A persistent cache of analysis results keyed by the digests of the analyzed class files.
+//!
+//! An [`AnalysisCache`] stores [`CacheableArtifact`]s in an [`ArtifactStore`] under the SHA-256
+//! digest of the bytes of the classes they are computed from, so repeated runs over unchanged
+//! classes reuse the results of the previous runs instead of analyzing the classes again.
+//! Artifacts computed with a different configuration or by a different version of the encoding
+//! are kept apart.
+
+use std::{
+    io,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::artifacts::{ArtifactKey, ArtifactStore, Digest, Hasher};
+
+/// An analysis result that can be stored in an [`AnalysisCache`].
+pub trait CacheableArtifact: Sized {
+    /// The kind of the artifact (e.g., `"call-graph"`).
+    const KIND: &'static str;
+    /// The version of the encoding. Artifacts encoded with another version are ignored.
+    const VERSION: u32;
+
+    /// Encodes the artifact.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decodes an artifact encoded by [`CacheableArtifact::encode`], or returns `None` if
+    /// `bytes` is malformed.
+    fn decode(bytes: &[u8]) -> Option<Self>;
+}
+
+impl CacheableArtifact for Vec<u8> {
+    const KIND: &'static str = "bytes";
+    const VERSION: u32 = 0;
+
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        Some(bytes.to_vec())
+    }
+}
+
+impl CacheableArtifact for String {
+    const KIND: &'static str = "string";
+    const VERSION: u32 = 0;
+
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        String::from_utf8(bytes.to_vec()).ok()
+    }
+}
+
+/// The numbers of cache hits and misses of an [`AnalysisCache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// The number of artifacts read from the cache.
+    pub hits: u64,
+    /// The number of artifacts computed because they were not in the cache.
+    pub misses: u64,
+}
+
+/// A persistent cache of analysis results. See the [module-level documentation](self).
+#[derive(Debug)]
+pub struct AnalysisCache {
+    store: ArtifactStore,
+    config: Digest,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl AnalysisCache {
+    /// Opens the cache in the directory `root` for analyses configured by `config`.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if the directory cannot be created.
+    pub fn open(root: impl Into<PathBuf>, config: &[u8]) -> io::Result<Self> {
+        ArtifactStore::open(root).map(|store| Self::with_store(store, config))
+    }
+
+    /// Creates a cache in `store` for analyses configured by `config`.
+    #[must_use]
+    pub fn with_store(store: ArtifactStore, config: &[u8]) -> Self {
+        Self {
+            store,
+            config: Digest::of(config),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the underlying store.
+    #[must_use]
+    pub const fn store(&self) -> &ArtifactStore {
+        &self.store
+    }
+
+    /// Returns the numbers of cache hits and misses so far.
+    #[must_use]
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Computes the digest identifying the input made of the class files `classes`.
+    /// The digest does not depend on the order of the classes.
+    pub fn input_digest<'a>(classes: impl IntoIterator<Item = &'a [u8]>) -> Digest {
+        let mut digests: Vec<_> = classes.into_iter().map(Digest::of).collect();
+        if let [digest] = digests.as_slice() {
+            return *digest;
+        }
+        digests.sort_unstable();
+        let mut hasher = Hasher::default();
+        hasher.update(&(digests.len() as u64).to_be_bytes());
+        for digest in &digests {
+            hasher.update(digest.as_bytes());
+        }
+        hasher.finish()
+    }
+
+    /// Returns the artifact computed from the class file `class_bytes`, or `None` if it is not
+    /// in the cache or cannot be decoded.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if reading the cache fails.
+    pub fn get<A: CacheableArtifact>(&self, class_bytes: &[u8]) -> io::Result<Option<A>> {
+        self.get_by_digest(Self::input_digest([class_bytes]))
+    }
+
+    /// Stores `artifact` as the one computed from the class file `class_bytes`.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if writing the cache fails.
+    pub fn put<A: CacheableArtifact>(&self, class_bytes: &[u8], artifact: &A) -> io::Result<()> {
+        self.put_by_digest(Self::input_digest([class_bytes]), artifact)
+    }
+
+    /// Returns the artifact computed from the class file `class_bytes`, computing and storing it
+    /// with `compute` if it is not in the cache.
+    ///
+    /// # Errors
+    /// Returns the error of `compute`, or an [`io::Error`] if accessing the cache fails.
+    pub fn get_or_compute<A, E>(
+        &self,
+        class_bytes: &[u8],
+        compute: impl FnOnce(&[u8]) -> Result<A, E>,
+    ) -> Result<A, E>
+    where
+        A: CacheableArtifact,
+        E: From<io::Error>,
+    {
+        self.get_or_compute_by_digest(Self::input_digest([class_bytes]), || compute(class_bytes))
+    }
+
+    /// Returns the artifact computed from the input identified by `input` (see
+    /// [`AnalysisCache::input_digest`]), computing and storing it with `compute` if it is not in
+    /// the cache.
+    ///
+    /// # Errors
+    /// Returns the error of `compute`, or an [`io::Error`] if accessing the cache fails.
+    pub fn get_or_compute_by_digest<A, E>(
+        &self,
+        input: Digest,
+        compute: impl FnOnce() -> Result<A, E>,
+    ) -> Result<A, E>
+    where
+        A: CacheableArtifact,
+        E: From<io::Error>,
+    {
+        if let Some(artifact) = self.get_by_digest(input)? {
+            return Ok(artifact);
+        }
+        let artifact = compute()?;
+        self.put_by_digest(input, &artifact)?;
+        Ok(artifact)
+    }
+
+    /// Returns the artifact computed from the input identified by `input`, or `None` if it is
+    /// not in the cache or cannot be decoded.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if reading the cache fails.
+    pub fn get_by_digest<A: CacheableArtifact>(&self, input: Digest) -> io::Result<Option<A>> {
+        let artifact = self
+            .store
+            .get(&self.key::<A>(input))?
+            .and_then(|it| A::decode(&it));
+        let counter = if artifact.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(artifact)
+    }
+
+    /// Stores `artifact` as the one computed from the input identified by `input`.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if writing the cache fails.
+    pub fn put_by_digest<A: CacheableArtifact>(
+        &self,
+        input: Digest,
+        artifact: &A,
+    ) -> io::Result<()> {
+        self.store
+            .put(&self.key::<A>(input), &artifact.encode())
+            .map(|_| ())
+    }
+
+    fn key<A: CacheableArtifact>(&self, input: Digest) -> ArtifactKey {
+        let mut config = Hasher::default();
+        config.update(self.config.as_bytes());
+        config.update(&A::VERSION.to_be_bytes());
+        ArtifactKey::new(A::KIND, input, config.finish())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{jvm::Class, tests::empty_class_with_version};
+
+    struct MethodCount(usize);
+
+    impl CacheableArtifact for MethodCount {
+        const KIND: &'static str = "method-count";
+        const VERSION: u32 = 1;
+
+        fn encode(&self) -> Vec<u8> {
+            (self.0 as u64).to_be_bytes().to_vec()
+        }
+
+        fn decode(bytes: &[u8]) -> Option<Self> {
+            let bytes = bytes.try_into().ok()?;
+            usize::try_from(u64::from_be_bytes(bytes)).ok().map(Self)
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "mokapot-cache-{name}-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        root
+    }
+
+    fn count_methods(bytes: &[u8]) -> io::Result<MethodCount> {
+        let class = Class::from_bytes(bytes).map_err(io::Error::other)?;
+        Ok(MethodCount(class.methods.len()))
+    }
+
+    #[test]
+    fn reuse_across_runs() {
+        let root = temp_dir("reuse");
+        let class = empty_class_with_version(52, 0);
+
+        let first = AnalysisCache::open(&root, b"config").unwrap();
+        let count = first.get_or_compute(&class, count_methods).unwrap();
+        assert_eq!(count.0, 0);
+        assert_eq!(first.stats(), CacheStats { hits: 0, misses: 1 });
+
+        let second = AnalysisCache::open(&root, b"config").unwrap();
+        let count: MethodCount = second
+            .get_or_compute(&class, |_| -> io::Result<_> { unreachable!() })
+            .unwrap();
+        assert_eq!(count.0, 0);
+        assert_eq!(second.stats(), CacheStats { hits: 1, misses: 0 });
+
+        let other_config = AnalysisCache::open(&root, b"other").unwrap();
+        assert!(other_config.get::<MethodCount>(&class).unwrap().is_none());
+        let other_class = empty_class_with_version(61, 0);
+        assert!(second.get::<MethodCount>(&other_class).unwrap().is_none());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn input_digest_ignores_order() {
+        let (a, b) = (&b"a"[..], &b"b"[..]);
+        assert_eq!(
+            AnalysisCache::input_digest([a, b]),
+            AnalysisCache::input_digest([b, a])
+        );
+        assert_eq!(AnalysisCache::input_digest([a]), Digest::of(a));
+        assert_ne!(
+            AnalysisCache::input_digest([a, b]),
+            AnalysisCache::input_digest([a])
+        );
+    }
+
+    #[test]
+    fn undecodable_artifacts_are_misses() {
+        let root = temp_dir("undecodable");
+        let cache = AnalysisCache::open(&root, b"").unwrap();
+        let input = Digest::of(b"input");
+        cache
+            .store()
+            .put(&cache.key::<MethodCount>(input), b"bad")
+            .unwrap();
+        assert!(cache.get_by_digest::<MethodCount>(input).unwrap().is_none());
+        cache.put_by_digest(input, &"text".to_owned()).unwrap();
+        assert_eq!(
+            cache.get_by_digest::<String>(input).unwrap().as_deref(),
+            Some("text")
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
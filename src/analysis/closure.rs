@@ -0,0 +1,177 @@
+//! Computing the transitive closure of the classes referenced by a set of classes.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::jvm::{
+    class_loader::{self, ClassPath},
+    references::ClassRef,
+    Class, ClassLoader,
+};
+
+/// The classes transitively referenced by a set of seed classes.
+#[derive(Debug, Default)]
+pub struct ClassPathClosure {
+    /// The classes in the closure, including the seed classes.
+    pub classes: BTreeMap<ClassRef, Class>,
+    /// The referenced classes that cannot be found, mapped to the classes referring to them.
+    pub missing: BTreeMap<ClassRef, BTreeSet<ClassRef>>,
+    /// The referenced classes that are found but cannot be loaded.
+    pub failed: BTreeMap<ClassRef, class_loader::Error>,
+}
+
+impl ClassPathClosure {
+    /// Computes the closure of `seeds` by loading the referenced classes (see
+    /// [`Class::referenced_classes`]) from `class_loader` until no new class is referenced.
+    /// A seed that cannot be found is recorded as missing with no referrers.
+    pub fn compute<P, I>(class_loader: &ClassLoader<P>, seeds: I) -> Self
+    where
+        P: ClassPath,
+        I: IntoIterator<Item = ClassRef>,
+    {
+        let mut closure = Self::default();
+        let mut visited = BTreeSet::new();
+        let mut worklist: VecDeque<_> = seeds.into_iter().map(|it| (it, None)).collect();
+        while let Some((class_ref, referrer)) = worklist.pop_front() {
+            if !visited.insert(class_ref.clone()) {
+                if let Some(referrers) = closure.missing.get_mut(&class_ref) {
+                    referrers.extend(referrer);
+                }
+                continue;
+            }
+            match class_loader.load_class(&class_ref.binary_name) {
+                Ok(class) => {
+                    worklist.extend(
+                        class
+                            .referenced_classes()
+                            .into_iter()
+                            .map(|it| (it, Some(class_ref.clone()))),
+                    );
+                    closure.classes.insert(class_ref, class);
+                }
+                Err(class_loader::Error::NotFound) => {
+                    closure
+                        .missing
+                        .entry(class_ref)
+                        .or_default()
+                        .extend(referrer);
+                }
+                Err(err) => {
+                    closure.failed.insert(class_ref, err);
+                }
+            }
+        }
+        closure
+    }
+
+    /// Checks whether all the referenced classes are loaded.
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty() && self.failed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::{class_loader::Error, Field};
+
+    use super::*;
+
+    struct InMemoryClassPath(BTreeMap<String, Class>);
+
+    impl ClassPath for InMemoryClassPath {
+        fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+            self.0.get(binary_name).cloned().ok_or(Error::NotFound)
+        }
+    }
+
+    #[test]
+    fn closure_with_missing_classes() {
+        let classes = [
+            Class {
+                binary_name: "java/lang/Object".to_owned(),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "A".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Object")),
+                fields: vec![
+                    Field {
+                        name: "f".to_owned(),
+                        owner: ClassRef::new("A"),
+                        field_type: "LB;".parse().unwrap(),
+                        ..Default::default()
+                    },
+                    Field {
+                        name: "f".to_owned(),
+                        owner: ClassRef::new("A"),
+                        field_type: "[LC;".parse().unwrap(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "B".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Object")),
+                fields: vec![
+                    Field {
+                        name: "f".to_owned(),
+                        owner: ClassRef::new("B"),
+                        field_type: "LA;".parse().unwrap(),
+                        ..Default::default()
+                    },
+                    Field {
+                        name: "f".to_owned(),
+                        owner: ClassRef::new("B"),
+                        field_type: "LMissing;".parse().unwrap(),
+                        ..Default::default()
+                    },
+                ],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "C".to_owned(),
+                super_class: Some(ClassRef::new("Missing")),
+                fields: vec![Field {
+                    name: "f".to_owned(),
+                    owner: ClassRef::new("C"),
+                    field_type: "I".parse().unwrap(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Unreachable".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Object")),
+                ..Default::default()
+            },
+        ];
+        let class_path = InMemoryClassPath(
+            classes
+                .into_iter()
+                .map(|it| (it.binary_name.clone(), it))
+                .collect(),
+        );
+        let class_loader = ClassLoader::new([class_path]);
+
+        let closure = ClassPathClosure::compute(&class_loader, [ClassRef::new("A")]);
+        let loaded: BTreeSet<_> = closure
+            .classes
+            .keys()
+            .map(|it| it.binary_name.as_ref())
+            .collect();
+        assert_eq!(loaded, BTreeSet::from(["A", "B", "C", "java/lang/Object"]));
+        assert_eq!(
+            closure.missing,
+            BTreeMap::from([(
+                ClassRef::new("Missing"),
+                BTreeSet::from([ClassRef::new("B"), ClassRef::new("C")])
+            )])
+        );
+        assert!(!closure.is_complete());
+
+        let closure = ClassPathClosure::compute(&class_loader, [ClassRef::new("Unreachable")]);
+        assert_eq!(closure.classes.len(), 2);
+        assert!(closure.is_complete());
+    }
+}
@@ -0,0 +1,598 @@
+//! Resolution of the concrete targets of virtual calls.
+//!
+//! For each `invokevirtual` and `invokeinterface` call, the [`Devirtualizer`] collects the
+//! classes the receiver may be an instance of, and resolves the method each of them dispatches
+//! to. The receiver classes are, in the order of preference,
+//! 1. the types given by a [`PointsTo`] analysis, if provided,
+//! 2. the class instantiated in the same method, if the receiver is created by `new`, or
+//! 3. the concrete subclasses and implementors of the declared owner (i.e., CHA).
+
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Debug},
+};
+
+use crate::{
+    ir::{
+        expression::Expression, DefUseChain, Identifier, MokaIRBrewingError, MokaIRMethod,
+        MokaIRMethodExt, MokaInstruction, Operand,
+    },
+    jvm::{
+        code::{Instruction, ProgramCounter},
+        method,
+        references::{ClassRef, MethodRef},
+        Class, Method,
+    },
+};
+
+use super::ResolutionContext;
+
+/// The results of a points-to analysis.
+pub trait PointsTo {
+    /// Returns the classes of the objects that `value` in `method` may point to, or [`None`]
+    /// if they are unknown.
+    fn types_of(&self, method: &MethodRef, value: &Operand) -> Option<BTreeSet<ClassRef>>;
+}
+
+/// The kind of a virtual call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum InvokeKind {
+    /// A call made with `invokevirtual`.
+    Virtual,
+    /// A call made with `invokeinterface`.
+    Interface,
+}
+
+/// The dispatch behavior of a call site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dispatch {
+    /// The call always dispatches to the same method, so it can be replaced with a direct
+    /// call.
+    Monomorphic(MethodRef),
+    /// The call may dispatch to any of several methods.
+    Polymorphic(BTreeSet<MethodRef>),
+    /// The targets are unknown, e.g., the receiver may be a class outside the context.
+    Unknown,
+}
+
+/// A virtual call site and its dispatch targets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallSite {
+    /// The method containing the call.
+    pub caller: MethodRef,
+    /// The location of the call.
+    pub pc: ProgramCounter,
+    /// The method referred to by the call instruction.
+    pub declared: MethodRef,
+    /// The kind of the call.
+    pub kind: InvokeKind,
+    /// The methods the call may dispatch to.
+    pub dispatch: Dispatch,
+}
+
+/// Resolves the dispatch targets of virtual calls among the classes in a
+/// [`ResolutionContext`].
+pub struct Devirtualizer<'a> {
+    context: &'a ResolutionContext,
+    points_to: Option<&'a dyn PointsTo>,
+}
+
+impl Debug for Devirtualizer<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Devirtualizer")
+            .field("context", &self.context)
+            .field("points_to", &self.points_to.is_some())
+            .finish()
+    }
+}
+
+impl<'a> Devirtualizer<'a> {
+    /// Creates a devirtualizer using the class hierarchy in `context`.
+    #[must_use]
+    pub const fn new(context: &'a ResolutionContext) -> Self {
+        Self {
+            context,
+            points_to: None,
+        }
+    }
+
+    /// Refines the receiver classes with the results of a points-to analysis.
+    #[must_use]
+    pub const fn with_points_to(mut self, points_to: &'a dyn PointsTo) -> Self {
+        self.points_to = Some(points_to);
+        self
+    }
+
+    /// Analyzes the virtual calls in all application classes.
+    /// The call sites are ordered by their classes, then by the order of the methods in the
+    /// classes and their locations in the methods.
+    /// Methods that cannot be brewed into Moka IR are skipped.
+    #[must_use]
+    pub fn analyze(&self) -> Vec<CallSite> {
+        self.context
+            .application_classes
+            .values()
+            .flat_map(|class| &class.methods)
+            .filter_map(|method| self.call_sites(method).ok())
+            .flatten()
+            .collect()
+    }
+
+    /// Analyzes the virtual calls in `method`.
+    ///
+    /// # Errors
+    /// See [`MokaIRBrewingError`].
+    pub fn call_sites(&self, method: &Method) -> Result<Vec<CallSite>, MokaIRBrewingError> {
+        let Some(body) = &method.body else {
+            return Ok(Vec::new());
+        };
+        let ir_method = method.brew()?;
+        let du_chain = DefUseChain::new(&ir_method);
+        let caller = method.as_ref();
+        let mut call_sites = Vec::new();
+        for (pc, insn) in &ir_method.instructions {
+            let (kind, declared) = match body.instruction_at(*pc) {
+                Some(Instruction::InvokeVirtual(declared)) => (InvokeKind::Virtual, declared),
+                Some(Instruction::InvokeInterface(declared, _)) => {
+                    (InvokeKind::Interface, declared)
+                }
+                _ => continue,
+            };
+            let MokaInstruction::Definition {
+                expr:
+                    Expression::Call {
+                        this: Some(receiver),
+                        ..
+                    },
+                ..
+            } = insn
+            else {
+                continue;
+            };
+            let receiver_types = self
+                .points_to
+                .and_then(|it| it.types_of(&caller, receiver))
+                .or_else(|| allocated_type(&ir_method, &du_chain, receiver));
+            let dispatch = self.dispatch(declared, receiver_types.as_ref());
+            call_sites.push(CallSite {
+                caller: caller.clone(),
+                pc: *pc,
+                declared: declared.clone(),
+                kind,
+                dispatch,
+            });
+        }
+        Ok(call_sites)
+    }
+
+    /// Computes the methods a call to `declared` dispatches to when the receiver is an instance
+    /// of one of `receiver_types`, or of any subtype of the owner if `receiver_types` is
+    /// [`None`].
+    #[must_use]
+    pub fn dispatch(
+        &self,
+        declared: &MethodRef,
+        receiver_types: Option<&BTreeSet<ClassRef>>,
+    ) -> Dispatch {
+        let receiver_types = match receiver_types {
+            Some(it) => it.clone(),
+            None => self.subtypes(&declared.owner),
+        };
+        let mut targets = BTreeSet::new();
+        for receiver_type in &receiver_types {
+            let Some(class) = self.context.get_class(receiver_type) else {
+                return Dispatch::Unknown;
+            };
+            if class.is_interface() || class.is_abstract() {
+                continue;
+            }
+            match self.select(class, declared) {
+                Some(target) => targets.insert(target),
+                None => return Dispatch::Unknown,
+            };
+        }
+        let mut targets = targets.into_iter();
+        match (targets.next(), targets.next()) {
+            (None, _) => Dispatch::Unknown,
+            (Some(target), None) => Dispatch::Monomorphic(target),
+            (Some(first), Some(second)) => {
+                Dispatch::Polymorphic([first, second].into_iter().chain(targets).collect())
+            }
+        }
+    }
+
+    /// Returns `class_ref` and all of its subclasses and implementors.
+    fn subtypes(&self, class_ref: &ClassRef) -> BTreeSet<ClassRef> {
+        let hierarchy = &self.context.class_hierarchy;
+        let mut subtypes = BTreeSet::from([class_ref.clone()]);
+        subtypes.extend(hierarchy.subclasses(class_ref));
+        for implementor in self
+            .context
+            .interface_implementations
+            .implementors(class_ref)
+        {
+            subtypes.extend(hierarchy.subclasses(&implementor));
+            subtypes.insert(implementor);
+        }
+        subtypes
+    }
+
+    /// Selects the method invoked on an instance of `class`, looking up the super classes and
+    /// then the default methods of the super interfaces.
+    fn select(&self, class: &Class, declared: &MethodRef) -> Option<MethodRef> {
+        let mut interfaces = Vec::new();
+        let mut current = Some(class);
+        while let Some(class) = current {
+            if let Some(method) = class.get_method(&declared.name, &declared.descriptor) {
+                if !method.access_flags.contains(method::AccessFlags::ABSTRACT) {
+                    return Some(method.as_ref());
+                }
+            }
+            interfaces.extend(class.interfaces.iter().cloned());
+            current = match &class.super_class {
+                Some(super_class) => Some(self.context.get_class(super_class)?),
+                None => None,
+            };
+        }
+        let mut visited = BTreeSet::new();
+        while let Some(interface) = interfaces.pop() {
+            if !visited.insert(interface.clone()) {
+                continue;
+            }
+            let interface = self.context.get_class(&interface)?;
+            if let Some(method) = interface.get_method(&declared.name, &declared.descriptor) {
+                if !method.access_flags.contains(method::AccessFlags::ABSTRACT) {
+                    return Some(method.as_ref());
+                }
+            }
+            interfaces.extend(interface.interfaces.iter().cloned());
+        }
+        None
+    }
+}
+
+/// Returns the class of `operand` if it is created by `new` in the same method.
+fn allocated_type(
+    ir_method: &MokaIRMethod,
+    du_chain: &DefUseChain<'_>,
+    operand: &Operand,
+) -> Option<BTreeSet<ClassRef>> {
+    operand
+        .iter()
+        .map(|it| {
+            let Identifier::Local(value) = it else {
+                return None;
+            };
+            let defined_at = du_chain.defined_at(value)?;
+            match ir_method.instructions.get(&defined_at)? {
+                MokaInstruction::Definition {
+                    expr: Expression::New(class_ref),
+                    ..
+                } => Some(class_ref.clone()),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        ir::{ClassHierarchy, InterfaceImplHierarchy},
+        jvm::interop::jasmin,
+        tests::method_ref,
+    };
+
+    const SHAPE: &str = "
+        .bytecode 52.0
+        .class public abstract Shape
+        .super java/lang/Object
+        .method public abstract area()I
+        .end method
+    ";
+
+    const SQUARE: &str = "
+        .bytecode 52.0
+        .class public Square
+        .super Shape
+        .method public area()I
+            .limit stack 1
+            iconst_1
+            ireturn
+        .end method
+    ";
+
+    const CIRCLE: &str = "
+        .bytecode 52.0
+        .class public Circle
+        .super Shape
+        .method public area()I
+            .limit stack 1
+            iconst_3
+            ireturn
+        .end method
+    ";
+
+    const CLIENT: &str = "
+        .bytecode 52.0
+        .class public Client
+        .super java/lang/Object
+        .method public static any(LShape;)I
+            .limit stack 1
+            aload_0
+            invokevirtual Shape/area()I
+            ireturn
+        .end method
+        .method public static square()I
+            .limit stack 2
+            new Square
+            dup
+            invokespecial Square/<init>()V
+            invokevirtual Shape/area()I
+            ireturn
+        .end method
+    ";
+
+    fn context<'a>(sources: impl IntoIterator<Item = &'a str>) -> ResolutionContext {
+        let classes: BTreeMap<_, _> = sources
+            .into_iter()
+            .map(|it| jasmin::read(it).unwrap())
+            .map(|it| (it.as_ref(), it))
+            .collect();
+        ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(classes.values()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(classes.values()),
+            application_classes: classes,
+            library_classes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn resolve_targets() {
+        let context = context([SHAPE, SQUARE, CIRCLE, CLIENT]);
+        let devirtualizer = Devirtualizer::new(&context);
+        let client = &context.application_classes[&ClassRef::new("Client")];
+        let area =
+            |owner: &str| MethodRef::new(ClassRef::new(owner), "area", "()I".parse().unwrap());
+
+        let any = devirtualizer.call_sites(&client.methods[0]).unwrap();
+        assert_eq!(
+            any[0].dispatch,
+            Dispatch::Polymorphic(BTreeSet::from([area("Circle"), area("Square")]))
+        );
+        let square = devirtualizer.call_sites(&client.methods[1]).unwrap();
+        assert_eq!(square[0].kind, InvokeKind::Virtual);
+        assert_eq!(square[0].dispatch, Dispatch::Monomorphic(area("Square")));
+        assert_eq!(devirtualizer.analyze().len(), 2);
+    }
+
+    fn area(owner: &str) -> MethodRef {
+        method_ref(owner, "area", "()I")
+    }
+
+    /// Returns the dispatch of the first call site in the method `name` of `Client`.
+    fn dispatch_in(
+        devirtualizer: &Devirtualizer<'_>,
+        context: &ResolutionContext,
+        name: &str,
+    ) -> Dispatch {
+        let client = &context.application_classes[&ClassRef::new("Client")];
+        let method = client.methods.iter().find(|it| it.name == name).unwrap();
+        let call_sites = devirtualizer.call_sites(method).unwrap();
+        call_sites[0].dispatch.clone()
+    }
+
+    #[test]
+    fn single_target() {
+        const SINGLE_CLIENT: &str = "
+            .bytecode 52.0
+            .class public Client
+            .super java/lang/Object
+            .method public static square(LSquare;)I
+                .limit stack 1
+                aload_0
+                invokevirtual Square/area()I
+                ireturn
+            .end method
+        ";
+        let context = context([SHAPE, SQUARE, CIRCLE, SINGLE_CLIENT]);
+        let devirtualizer = Devirtualizer::new(&context);
+        assert_eq!(
+            dispatch_in(&devirtualizer, &context, "square"),
+            Dispatch::Monomorphic(area("Square"))
+        );
+    }
+
+    #[test]
+    fn final_members() {
+        const BASE: &str = "
+            .bytecode 52.0
+            .class public Base
+            .super java/lang/Object
+            .method public final id()I
+                .limit stack 1
+                iconst_0
+                ireturn
+            .end method
+        ";
+        const DERIVED: &str = "
+            .bytecode 52.0
+            .class public Derived
+            .super Base
+        ";
+        const LEAF: &str = "
+            .bytecode 52.0
+            .class public final Leaf
+            .super Base
+            .method public size()I
+                .limit stack 1
+                iconst_1
+                ireturn
+            .end method
+        ";
+        const FINAL_CLIENT: &str = "
+            .bytecode 52.0
+            .class public Client
+            .super java/lang/Object
+            .method public static id(LBase;)I
+                .limit stack 1
+                aload_0
+                invokevirtual Base/id()I
+                ireturn
+            .end method
+            .method public static size(LLeaf;)I
+                .limit stack 1
+                aload_0
+                invokevirtual Leaf/size()I
+                ireturn
+            .end method
+        ";
+        let context = context([BASE, DERIVED, LEAF, FINAL_CLIENT]);
+        let devirtualizer = Devirtualizer::new(&context);
+        assert_eq!(
+            dispatch_in(&devirtualizer, &context, "id"),
+            Dispatch::Monomorphic(method_ref("Base", "id", "()I"))
+        );
+        assert_eq!(
+            dispatch_in(&devirtualizer, &context, "size"),
+            Dispatch::Monomorphic(method_ref("Leaf", "size", "()I"))
+        );
+    }
+
+    #[test]
+    fn interface_implementations() {
+        const NAMED: &str = "
+            .bytecode 52.0
+            .interface public abstract Named
+            .super java/lang/Object
+            .method public name()I
+                .limit stack 1
+                iconst_0
+                ireturn
+            .end method
+        ";
+        const FIRST: &str = "
+            .bytecode 52.0
+            .class public First
+            .super java/lang/Object
+            .implements Named
+            .method public name()I
+                .limit stack 1
+                iconst_1
+                ireturn
+            .end method
+        ";
+        const SECOND: &str = "
+            .bytecode 52.0
+            .class public Second
+            .super java/lang/Object
+            .implements Named
+        ";
+        const THIRD: &str = "
+            .bytecode 52.0
+            .class public Third
+            .super First
+        ";
+        const NAMED_CLIENT: &str = "
+            .bytecode 52.0
+            .class public Client
+            .super java/lang/Object
+            .method public static name(LNamed;)I
+                .limit stack 1
+                aload_0
+                invokeinterface Named/name()I 1
+                ireturn
+            .end method
+        ";
+        let mut context = context([NAMED, FIRST, SECOND, THIRD, NAMED_CLIENT]);
+        let object = Class {
+            binary_name: "java/lang/Object".to_owned(),
+            ..Default::default()
+        };
+        context.library_classes.insert(object.as_ref(), object);
+        let devirtualizer = Devirtualizer::new(&context);
+        let client = &context.application_classes[&ClassRef::new("Client")];
+        let call_sites = devirtualizer.call_sites(&client.methods[0]).unwrap();
+        assert_eq!(call_sites[0].kind, InvokeKind::Interface);
+        // `Second` inherits the default method, and `Third` inherits the method of `First`.
+        assert_eq!(
+            call_sites[0].dispatch,
+            Dispatch::Polymorphic(BTreeSet::from([
+                method_ref("First", "name", "()I"),
+                method_ref("Named", "name", "()I"),
+            ]))
+        );
+    }
+
+    #[test]
+    fn points_to_refinement() {
+        struct ReceiverTypes(Option<BTreeSet<ClassRef>>);
+
+        impl PointsTo for ReceiverTypes {
+            fn types_of(&self, _: &MethodRef, _: &Operand) -> Option<BTreeSet<ClassRef>> {
+                self.0.clone()
+            }
+        }
+
+        let context = context([SHAPE, SQUARE, CIRCLE, CLIENT]);
+        let circle = ReceiverTypes(Some(BTreeSet::from([ClassRef::new("Circle")])));
+        let devirtualizer = Devirtualizer::new(&context).with_points_to(&circle);
+        assert_eq!(
+            dispatch_in(&devirtualizer, &context, "any"),
+            Dispatch::Monomorphic(area("Circle"))
+        );
+        // The points-to results take precedence over the allocation site.
+        assert_eq!(
+            dispatch_in(&devirtualizer, &context, "square"),
+            Dispatch::Monomorphic(area("Circle"))
+        );
+
+        let unknown = ReceiverTypes(None);
+        let devirtualizer = Devirtualizer::new(&context).with_points_to(&unknown);
+        assert_eq!(
+            dispatch_in(&devirtualizer, &context, "any"),
+            Dispatch::Polymorphic(BTreeSet::from([area("Circle"), area("Square")]))
+        );
+
+        let outside = ReceiverTypes(Some(BTreeSet::from([ClassRef::new("Triangle")])));
+        let devirtualizer = Devirtualizer::new(&context).with_points_to(&outside);
+        assert_eq!(
+            dispatch_in(&devirtualizer, &context, "any"),
+            Dispatch::Unknown
+        );
+    }
+
+    #[test]
+    fn stable_order() {
+        const OTHER_CLIENT: &str = "
+            .bytecode 52.0
+            .class public AnotherClient
+            .super java/lang/Object
+            .method public static any(LShape;)I
+                .limit stack 1
+                aload_0
+                invokevirtual Shape/area()I
+                ireturn
+            .end method
+        ";
+        let sources = [SHAPE, SQUARE, CIRCLE, CLIENT, OTHER_CLIENT];
+        let call_sites = Devirtualizer::new(&context(sources)).analyze();
+        let reversed = Devirtualizer::new(&context(sources.into_iter().rev())).analyze();
+        assert_eq!(call_sites, reversed);
+        let callers: Vec<_> = call_sites
+            .iter()
+            .map(|it| (&*it.caller.owner.binary_name, &*it.caller.name))
+            .collect();
+        assert_eq!(
+            callers,
+            [
+                ("AnotherClient", "any"),
+                ("Client", "any"),
+                ("Client", "square")
+            ]
+        );
+    }
+}
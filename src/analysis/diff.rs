@@ -0,0 +1,470 @@
+//! Semantic differences between two versions of a class.
+//!
+//! The members of the two versions are matched by name (and descriptor for methods), so that the
+//! report lists the added, removed, and changed members rather than raw byte differences.
+//! This is useful for checking binary compatibility and auditing updates of dependencies.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    jvm::{
+        class,
+        code::{Instruction, ProgramCounter},
+        field, method,
+        references::ClassRef,
+        Class, ConstantValue, Field, Method,
+    },
+    types::{field_type::FieldType, method_descriptor::MethodDescriptor},
+};
+
+/// A change of a value from one version to another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change<T> {
+    /// The value in the old version.
+    pub old: T,
+    /// The value in the new version.
+    pub new: T,
+}
+
+impl<T: PartialEq + Clone> Change<T> {
+    fn between(old: &T, new: &T) -> Option<Self> {
+        (old != new).then(|| Self {
+            old: old.clone(),
+            new: new.clone(),
+        })
+    }
+}
+
+/// Options for [`diff_classes_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    bytecode: bool,
+}
+
+impl DiffOptions {
+    /// Sets whether to compute instruction-level differences for the changed method bodies.
+    #[must_use]
+    pub const fn with_bytecode(mut self, bytecode: bool) -> Self {
+        self.bytecode = bytecode;
+        self
+    }
+}
+
+/// The differences between two versions of a class.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClassDiff {
+    /// The change of the access flags of the class.
+    pub access_flags: Option<Change<class::AccessFlags>>,
+    /// The change of the superclass.
+    pub super_class: Option<Change<Option<ClassRef>>>,
+    /// The interfaces implemented only by the new version.
+    pub added_interfaces: Vec<ClassRef>,
+    /// The interfaces implemented only by the old version.
+    pub removed_interfaces: Vec<ClassRef>,
+    /// The fields declared only in the new version.
+    pub added_fields: Vec<(String, FieldType)>,
+    /// The fields declared only in the old version.
+    pub removed_fields: Vec<(String, FieldType)>,
+    /// The fields declared in both versions but changed.
+    pub changed_fields: Vec<FieldDiff>,
+    /// The methods declared only in the new version.
+    pub added_methods: Vec<(String, MethodDescriptor)>,
+    /// The methods declared only in the old version.
+    pub removed_methods: Vec<(String, MethodDescriptor)>,
+    /// The methods declared in both versions but changed.
+    pub changed_methods: Vec<MethodDiff>,
+}
+
+impl ClassDiff {
+    /// Checks whether the two versions have no differences.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// The differences of a field declared in both versions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldDiff {
+    /// The name of the field.
+    pub name: String,
+    /// The change of the access flags.
+    pub access_flags: Option<Change<field::AccessFlags>>,
+    /// The change of the type.
+    pub field_type: Option<Change<FieldType>>,
+    /// The change of the constant value.
+    pub constant_value: Option<Change<Option<ConstantValue>>>,
+}
+
+/// The differences of a method declared in both versions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MethodDiff {
+    /// The name of the method.
+    pub name: String,
+    /// The descriptor of the method in the old version.
+    pub descriptor: MethodDescriptor,
+    /// The change of the descriptor.
+    /// Methods with different descriptors are matched only if the name is not overloaded.
+    pub descriptor_change: Option<Change<MethodDescriptor>>,
+    /// The change of the access flags.
+    pub access_flags: Option<Change<method::AccessFlags>>,
+    /// The change of the declared exceptions.
+    pub exceptions: Option<Change<Vec<ClassRef>>>,
+    /// Whether the instructions of the method are changed.
+    pub body_changed: bool,
+    /// The instruction-level differences if enabled with [`DiffOptions::with_bytecode`] and the
+    /// body is changed.
+    pub bytecode: Vec<InstructionEdit>,
+}
+
+/// An edit that turns the instructions of the old version into the ones of the new version.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstructionEdit {
+    /// An instruction is removed from the old version.
+    Removed(ProgramCounter, Instruction),
+    /// An instruction is inserted to the new version.
+    Inserted(ProgramCounter, Instruction),
+}
+
+/// Computes the differences between `old` and `new` without instruction-level differences.
+#[must_use]
+pub fn diff_classes(old: &Class, new: &Class) -> ClassDiff {
+    diff_classes_with_options(old, new, &DiffOptions::default())
+}
+
+/// Computes the differences between `old` and `new`.
+#[must_use]
+pub fn diff_classes_with_options(old: &Class, new: &Class, options: &DiffOptions) -> ClassDiff {
+    let old_interfaces: BTreeSet<_> = old.interfaces.iter().collect();
+    let new_interfaces: BTreeSet<_> = new.interfaces.iter().collect();
+    let (added_fields, removed_fields, changed_fields) = diff_fields(&old.fields, &new.fields);
+    let (added_methods, removed_methods, changed_methods) =
+        diff_methods(&old.methods, &new.methods, options);
+    ClassDiff {
+        access_flags: Change::between(&old.access_flags, &new.access_flags),
+        super_class: Change::between(&old.super_class, &new.super_class),
+        added_interfaces: new_interfaces
+            .difference(&old_interfaces)
+            .map(|&it| it.clone())
+            .collect(),
+        removed_interfaces: old_interfaces
+            .difference(&new_interfaces)
+            .map(|&it| it.clone())
+            .collect(),
+        added_fields,
+        removed_fields,
+        changed_fields,
+        added_methods,
+        removed_methods,
+        changed_methods,
+    }
+}
+
+type MemberChanges<K, D> = (Vec<K>, Vec<K>, Vec<D>);
+
+fn diff_fields(old: &[Field], new: &[Field]) -> MemberChanges<(String, FieldType), FieldDiff> {
+    let new_by_name: BTreeMap<_, _> = new.iter().map(|it| (&it.name, it)).collect();
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = Vec::new();
+    for old_field in old {
+        let Some(new_field) = new_by_name.get(&old_field.name) else {
+            removed.push((old_field.name.clone(), old_field.field_type.clone()));
+            continue;
+        };
+        let diff = FieldDiff {
+            name: old_field.name.clone(),
+            access_flags: Change::between(&old_field.access_flags, &new_field.access_flags),
+            field_type: Change::between(&old_field.field_type, &new_field.field_type),
+            constant_value: Change::between(&old_field.constant_value, &new_field.constant_value),
+        };
+        if diff.access_flags.is_some() || diff.field_type.is_some() || diff.constant_value.is_some()
+        {
+            changed.push(diff);
+        }
+    }
+    let old_names: BTreeSet<_> = old.iter().map(|it| &it.name).collect();
+    for new_field in new {
+        if !old_names.contains(&new_field.name) {
+            added.push((new_field.name.clone(), new_field.field_type.clone()));
+        }
+    }
+    (added, removed, changed)
+}
+
+fn diff_methods(
+    old: &[Method],
+    new: &[Method],
+    options: &DiffOptions,
+) -> MemberChanges<(String, MethodDescriptor), MethodDiff> {
+    let key = |it: &Method| (it.name.clone(), it.descriptor.clone());
+    let old_by_key: BTreeMap<_, _> = old.iter().map(|it| (key(it), it)).collect();
+    let new_by_key: BTreeMap<_, _> = new.iter().map(|it| (key(it), it)).collect();
+    let mut pairs: Vec<(&Method, &Method)> = old_by_key
+        .iter()
+        .filter_map(|(key, &old)| new_by_key.get(key).map(|&new| (old, new)))
+        .collect();
+    let unmatched_old: Vec<_> = old_by_key
+        .iter()
+        .filter(|(key, _)| !new_by_key.contains_key(*key))
+        .map(|(_, &it)| it)
+        .collect();
+    let unmatched_new: Vec<_> = new_by_key
+        .iter()
+        .filter(|(key, _)| !old_by_key.contains_key(*key))
+        .map(|(_, &it)| it)
+        .collect();
+
+    // A method whose descriptor is changed is matched by name if the name is not overloaded.
+    let old_by_name = group_by_name(&unmatched_old);
+    let new_by_name = group_by_name(&unmatched_new);
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    for (name, olds) in &old_by_name {
+        match (olds.as_slice(), new_by_name.get(name).map(Vec::as_slice)) {
+            ([old], Some([new])) => pairs.push((old, new)),
+            _ => removed.extend(olds.iter().map(|&it| key(it))),
+        }
+    }
+    for (name, news) in &new_by_name {
+        let matched = matches!(
+            (old_by_name.get(name).map(Vec::as_slice), news.as_slice()),
+            (Some([_]), [_])
+        );
+        if !matched {
+            added.extend(news.iter().map(|&it| key(it)));
+        }
+    }
+
+    let changed = pairs
+        .into_iter()
+        .filter_map(|(old, new)| diff_method(old, new, options))
+        .collect();
+    (added, removed, changed)
+}
+
+fn group_by_name<'m>(methods: &[&'m Method]) -> BTreeMap<&'m str, Vec<&'m Method>> {
+    let mut by_name: BTreeMap<&str, Vec<&Method>> = BTreeMap::new();
+    for &method in methods {
+        by_name.entry(&method.name).or_default().push(method);
+    }
+    by_name
+}
+
+fn diff_method(old: &Method, new: &Method, options: &DiffOptions) -> Option<MethodDiff> {
+    let old_instructions = instructions(old);
+    let new_instructions = instructions(new);
+    let body_changed = old_instructions.len() != new_instructions.len()
+        || old_instructions
+            .iter()
+            .zip(&new_instructions)
+            .any(|((_, old), (_, new))| old != new);
+    let bytecode = if options.bytecode && body_changed {
+        diff_instructions(&old_instructions, &new_instructions)
+    } else {
+        Vec::new()
+    };
+    let diff = MethodDiff {
+        name: old.name.clone(),
+        descriptor: old.descriptor.clone(),
+        descriptor_change: Change::between(&old.descriptor, &new.descriptor),
+        access_flags: Change::between(&old.access_flags, &new.access_flags),
+        exceptions: Change::between(&old.exceptions, &new.exceptions),
+        body_changed,
+        bytecode,
+    };
+    let changed = diff.descriptor_change.is_some()
+        || diff.access_flags.is_some()
+        || diff.exceptions.is_some()
+        || diff.body_changed;
+    changed.then_some(diff)
+}
+
+fn instructions(method: &Method) -> Vec<(ProgramCounter, &Instruction)> {
+    method
+        .body
+        .iter()
+        .flat_map(|it| it.instructions.iter().map(|(pc, insn)| (*pc, insn)))
+        .collect()
+}
+
+/// Computes a shortest edit script with the longest common subsequence of the instructions.
+/// Instructions are compared by value, so jumps whose targets are shifted are reported as edits.
+fn diff_instructions(
+    old: &[(ProgramCounter, &Instruction)],
+    new: &[(ProgramCounter, &Instruction)],
+) -> Vec<InstructionEdit> {
+    // The common prefix and suffix are skipped to keep the table small for local changes.
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|((_, old), (_, new))| old == new)
+        .count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|((_, old), (_, new))| old == new)
+        .count();
+    let old = &old[prefix..old.len() - suffix];
+    let new = &new[prefix..new.len() - suffix];
+
+    // `lcs[i][j]` is the length of the longest common subsequence of `old[i..]` and `new[j..]`.
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i].1 == new[j].1 {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i].1 == new[j].1 {
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            edits.push(InstructionEdit::Inserted(new[j].0, new[j].1.clone()));
+            j += 1;
+        } else {
+            edits.push(InstructionEdit::Removed(old[i].0, old[i].1.clone()));
+            i += 1;
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{jvm::code::MethodBody, types::field_type::PrimitiveType};
+
+    use super::*;
+
+    fn method(name: &str, descriptor: &str, instructions: Vec<Instruction>) -> Method {
+        let instructions: BTreeMap<ProgramCounter, Instruction> = (0u16..)
+            .map(ProgramCounter::from)
+            .zip(instructions)
+            .collect();
+        Method {
+            name: name.to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                instructions: instructions.into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn field(name: &str, field_type: &str) -> Field {
+        Field {
+            name: name.to_owned(),
+            field_type: field_type.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identical_classes() {
+        let class = Class {
+            fields: vec![field("x", "I")],
+            methods: vec![method("run", "()V", vec![Instruction::Return])],
+            ..Default::default()
+        };
+        assert!(diff_classes(&class, &class).is_empty());
+    }
+
+    #[test]
+    fn member_changes() {
+        let old = Class {
+            fields: vec![field("x", "I"), field("y", "I")],
+            methods: vec![
+                method("run", "()V", vec![Instruction::Return]),
+                method(
+                    "size",
+                    "()I",
+                    vec![Instruction::IConst0, Instruction::IReturn],
+                ),
+                method("gone", "()V", vec![Instruction::Return]),
+            ],
+            ..Default::default()
+        };
+        let new = Class {
+            interfaces: vec![ClassRef::new("java/lang/Runnable")],
+            fields: vec![field("x", "J"), field("z", "I")],
+            methods: vec![
+                method("run", "()V", vec![Instruction::Return]),
+                method(
+                    "size",
+                    "()J",
+                    vec![Instruction::LConst0, Instruction::LReturn],
+                ),
+                method("fresh", "()V", vec![Instruction::Return]),
+            ],
+            ..Default::default()
+        };
+        let diff = diff_classes(&old, &new);
+        assert_eq!(
+            diff.added_interfaces,
+            vec![ClassRef::new("java/lang/Runnable")]
+        );
+        assert_eq!(
+            diff.added_fields,
+            vec![("z".to_owned(), FieldType::Base(PrimitiveType::Int))]
+        );
+        assert_eq!(diff.removed_fields.len(), 1);
+        assert_eq!(diff.changed_fields[0].name, "x");
+        assert!(diff.changed_fields[0].field_type.is_some());
+        assert_eq!(diff.added_methods[0].0, "fresh");
+        assert_eq!(diff.removed_methods[0].0, "gone");
+        let [size] = diff.changed_methods.as_slice() else {
+            panic!("Expected exactly one changed method");
+        };
+        assert_eq!(size.name, "size");
+        assert!(size.descriptor_change.is_some());
+        assert!(size.body_changed);
+        assert!(size.bytecode.is_empty());
+    }
+
+    #[test]
+    fn bytecode_edits() {
+        let old = Class {
+            methods: vec![method(
+                "run",
+                "()V",
+                vec![
+                    Instruction::Nop,
+                    Instruction::IConst0,
+                    Instruction::Pop,
+                    Instruction::Return,
+                ],
+            )],
+            ..Default::default()
+        };
+        let new = Class {
+            methods: vec![method(
+                "run",
+                "()V",
+                vec![
+                    Instruction::Nop,
+                    Instruction::IConst1,
+                    Instruction::Pop,
+                    Instruction::Return,
+                ],
+            )],
+            ..Default::default()
+        };
+        let diff =
+            diff_classes_with_options(&old, &new, &DiffOptions::default().with_bytecode(true));
+        assert_eq!(
+            diff.changed_methods[0].bytecode,
+            vec![
+                InstructionEdit::Inserted(1.into(), Instruction::IConst1),
+                InstructionEdit::Removed(1.into(), Instruction::IConst0),
+            ]
+        );
+    }
+}
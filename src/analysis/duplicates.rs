@@ -0,0 +1,203 @@
+//! Detection of duplicate classes and split packages across the entries of a class path.
+//!
+//! When several entries of a class path contain a class with the same name, only the copy in the
+//! first entry is ever loaded and the others are shadowed.
+//! Likewise, a package whose classes are spread over several entries (a split package) is not
+//! allowed among modules and is a common source of surprises on the class path.
+//! [`analyze`] reports both, identifying the entries by their indices in the class path.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::jvm::{
+    class_loader::{ClassPath, Error},
+    references::ClassRef,
+};
+
+use super::{artifacts::Digest, ClassRefs};
+
+/// A copy of a class in an entry of a class path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassCopy {
+    /// The index of the entry in the class path.
+    pub entry: usize,
+    /// The SHA-256 digest of the class file.
+    pub digest: Digest,
+    /// The size of the class file in bytes.
+    pub size: usize,
+}
+
+/// A class contained in more than one entry of a class path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateClass {
+    /// The class.
+    pub class: ClassRef,
+    /// The copies of the class in the order the entries are searched.
+    pub copies: Vec<ClassCopy>,
+}
+
+impl DuplicateClass {
+    /// Returns the copy that is loaded.
+    ///
+    /// # Panics
+    /// Panics if there are no copies, which never happens for the duplicates reported by
+    /// [`analyze`].
+    #[must_use]
+    pub fn picked(&self) -> &ClassCopy {
+        self.copies.first().expect("A duplicate class has copies")
+    }
+
+    /// Returns the copies that are shadowed by the one that is loaded.
+    #[must_use]
+    pub fn shadowed(&self) -> &[ClassCopy] {
+        self.copies.get(1..).unwrap_or_default()
+    }
+
+    /// Checks whether all the copies have the same content, in which case the duplication is
+    /// harmless.
+    #[must_use]
+    pub fn is_identical(&self) -> bool {
+        self.copies
+            .windows(2)
+            .all(|pair| pair[0].digest == pair[1].digest)
+    }
+}
+
+/// A package whose classes are contained in more than one entry of a class path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPackage {
+    /// The binary name of the package (e.g., `org/mokapot`).
+    pub package: String,
+    /// The indices of the entries containing classes of the package.
+    pub entries: BTreeSet<usize>,
+}
+
+/// The duplicate classes and the split packages in a class path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DuplicationReport {
+    /// The classes contained in more than one entry, ordered by name.
+    pub duplicate_classes: Vec<DuplicateClass>,
+    /// The packages split across entries, ordered by name.
+    /// Classes in the unnamed package are not considered.
+    pub split_packages: Vec<SplitPackage>,
+}
+
+/// Scans the entries of `class_path` for duplicate classes and split packages.
+/// The entries are expected to be in the order they are searched by the class loader.
+///
+/// # Errors
+/// See [`Error`]. In particular, [`Error::NotFound`] is returned if an entry does not provide the
+/// class files of its classes (see [`ClassPath::find_class_bytes`]).
+pub fn analyze<P>(class_path: &[P]) -> Result<DuplicationReport, Error>
+where
+    P: ClassPath + ClassRefs,
+{
+    let mut classes: BTreeMap<ClassRef, Vec<usize>> = BTreeMap::new();
+    let mut packages: BTreeMap<String, BTreeSet<usize>> = BTreeMap::new();
+    for (entry, class_path) in class_path.iter().enumerate() {
+        for class_ref in class_path.class_refs() {
+            if let Some((package, _)) = class_ref.binary_name.rsplit_once('/') {
+                packages
+                    .entry(package.to_owned())
+                    .or_default()
+                    .insert(entry);
+            }
+            classes.entry(class_ref).or_default().push(entry);
+        }
+    }
+    let duplicate_classes = classes
+        .into_iter()
+        .filter(|(_, entries)| entries.len() > 1)
+        .map(|(class, entries)| {
+            let copies = entries
+                .into_iter()
+                .map(|entry| {
+                    let bytes = class_path[entry].find_class_bytes(&class.binary_name)?;
+                    Ok(ClassCopy {
+                        entry,
+                        digest: Digest::of(&bytes),
+                        size: bytes.len(),
+                    })
+                })
+                .collect::<Result<_, Error>>()?;
+            Ok(DuplicateClass { class, copies })
+        })
+        .collect::<Result<_, Error>>()?;
+    let split_packages = packages
+        .into_iter()
+        .filter(|(_, entries)| entries.len() > 1)
+        .map(|(package, entries)| SplitPackage { package, entries })
+        .collect();
+    Ok(DuplicationReport {
+        duplicate_classes,
+        split_packages,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::*;
+    use crate::{jvm::Class, tests::empty_class_with_version};
+
+    struct InMemoryClassPath(HashMap<&'static str, Vec<u8>>);
+
+    impl ClassPath for InMemoryClassPath {
+        fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+            let bytes = self.find_class_bytes(binary_name)?;
+            Class::from_bytes(&bytes).map_err(Into::into)
+        }
+
+        fn find_class_bytes(&self, binary_name: &str) -> Result<Vec<u8>, Error> {
+            self.0.get(binary_name).cloned().ok_or(Error::NotFound)
+        }
+    }
+
+    impl ClassRefs for InMemoryClassPath {
+        fn class_refs(&self) -> BTreeSet<ClassRef> {
+            self.0.keys().copied().map(ClassRef::new).collect()
+        }
+    }
+
+    #[test]
+    fn duplicates_and_split_packages() {
+        let (old, new) = (
+            empty_class_with_version(52, 0).to_vec(),
+            empty_class_with_version(61, 0).to_vec(),
+        );
+        let class_path = [
+            InMemoryClassPath(HashMap::from([
+                ("org/mokapot/A", old.clone()),
+                ("org/mokapot/B", old.clone()),
+                ("Main", old.clone()),
+            ])),
+            InMemoryClassPath(HashMap::from([
+                ("org/mokapot/A", new.clone()),
+                ("org/mokapot/C", new.clone()),
+                ("Main", old.clone()),
+            ])),
+            InMemoryClassPath(HashMap::from([("org/other/D", new)])),
+        ];
+        let report = analyze(&class_path).unwrap();
+
+        let [main, a] = report.duplicate_classes.as_slice() else {
+            panic!("Unexpected duplicates: {:?}", report.duplicate_classes);
+        };
+        assert_eq!(main.class, ClassRef::new("Main"));
+        assert!(main.is_identical());
+        assert_eq!(a.class, ClassRef::new("org/mokapot/A"));
+        assert!(!a.is_identical());
+        assert_eq!(a.picked().entry, 0);
+        assert_eq!(a.picked().digest, Digest::of(&old));
+        assert_eq!(a.shadowed().len(), 1);
+        assert_eq!(a.shadowed()[0].entry, 1);
+
+        assert_eq!(
+            report.split_packages,
+            [SplitPackage {
+                package: "org/mokapot".to_owned(),
+                entries: BTreeSet::from([0, 1]),
+            }]
+        );
+    }
+}
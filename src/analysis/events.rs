@@ -0,0 +1,577 @@
+//! Extraction of event flows for architecture reconstruction.
+//!
+//! The observer pattern and event buses introduce implicit invocations that do not show up as
+//! calls between the components.
+//! This module recognizes the listener registrations and the event publications with
+//! configurable [`EventPattern`]s, and connects the components in an [`EventFlowGraph`].
+
+use std::collections::BTreeSet;
+
+use crate::{
+    ir::{
+        expression::{Expression, FieldAccess},
+        DefUseChain, Identifier, MokaIRMethod, MokaIRMethodExt, MokaInstruction, Operand,
+    },
+    jvm::{
+        class::MethodHandle,
+        code::ProgramCounter,
+        references::{ClassRef, MethodRef},
+        Class, ConstantValue, Method,
+    },
+    types::{field_type::FieldType, method_descriptor::ReturnType},
+};
+
+/// A pattern matching the methods being called.
+/// The owner, name and descriptor are matched with globs in which `*` matches any sequence of
+/// characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodPattern {
+    owner: Option<String>,
+    name: String,
+    descriptor: Option<String>,
+}
+
+impl MethodPattern {
+    /// Creates a pattern matching the methods with the given name in any class.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            owner: None,
+            name: name.into(),
+            descriptor: None,
+        }
+    }
+
+    /// Restricts the pattern to the methods declared in the classes matching `owner`, which is
+    /// a glob over binary names (e.g., `com/google/common/eventbus/*`).
+    #[must_use]
+    pub fn with_owner(self, owner: impl Into<String>) -> Self {
+        Self {
+            owner: Some(owner.into()),
+            ..self
+        }
+    }
+
+    /// Restricts the pattern to the methods with descriptors matching `descriptor`.
+    #[must_use]
+    pub fn with_descriptor(self, descriptor: impl Into<String>) -> Self {
+        Self {
+            descriptor: Some(descriptor.into()),
+            ..self
+        }
+    }
+
+    /// Checks whether `method` matches the pattern.
+    #[must_use]
+    pub fn matches(&self, method: &MethodRef) -> bool {
+        self.owner
+            .as_ref()
+            .is_none_or(|it| glob_matches(it, &method.owner.binary_name))
+            && glob_matches(&self.name, &method.name)
+            && self
+                .descriptor
+                .as_ref()
+                .is_none_or(|it| glob_matches(it, &method.descriptor.descriptor()))
+    }
+}
+
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == text,
+        Some((prefix, rest)) => {
+            let Some(text) = text.strip_prefix(prefix) else {
+                return false;
+            };
+            text.char_indices()
+                .map(|(idx, _)| idx)
+                .chain([text.len()])
+                .any(|idx| glob_matches(rest, &text[idx..]))
+        }
+    }
+}
+
+/// A pattern of the calls that take part in the event flows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventPattern {
+    /// A call registering a listener to the receiver (e.g., `addActionListener`).
+    Registration {
+        /// The methods registering the listeners.
+        method: MethodPattern,
+        /// The index of the argument holding the listener.
+        listener_argument: usize,
+    },
+    /// A call publishing an event (e.g., `EventBus.post`).
+    Publication {
+        /// The methods publishing the events.
+        method: MethodPattern,
+        /// The index of the argument holding the event.
+        event_argument: usize,
+    },
+}
+
+/// A registration of listeners.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenerRegistration {
+    /// The method containing the registration.
+    pub method: MethodRef,
+    /// The location of the call.
+    pub pc: ProgramCounter,
+    /// The method being called.
+    pub api: MethodRef,
+    /// The possible classes of the object on which the listener is registered.
+    pub subjects: BTreeSet<ClassRef>,
+    /// The possible classes of the listener, or an empty set if they are unknown.
+    pub listeners: BTreeSet<ClassRef>,
+}
+
+/// A publication of events.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventPublication {
+    /// The method containing the publication.
+    pub method: MethodRef,
+    /// The location of the call.
+    pub pc: ProgramCounter,
+    /// The method being called.
+    pub api: MethodRef,
+    /// The possible types of the event, or an empty set if they are unknown.
+    pub event_types: BTreeSet<ClassRef>,
+}
+
+/// The kind of an [`EventFlowEdge`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EventFlowKind {
+    /// The source notifies the target registered as its listener.
+    Listener {
+        /// The method registering the listener.
+        api: MethodRef,
+    },
+    /// The target subscribes to the events published by the source.
+    Event {
+        /// The type of the event.
+        event_type: ClassRef,
+    },
+}
+
+/// An implicit invocation from a component to another.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct EventFlowEdge {
+    /// The component triggering the invocation.
+    pub source: ClassRef,
+    /// The component being invoked.
+    pub target: ClassRef,
+    /// The kind of the invocation.
+    pub kind: EventFlowKind,
+}
+
+/// The event flows between components.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventFlowGraph {
+    /// The listener registrations found.
+    pub registrations: Vec<ListenerRegistration>,
+    /// The event publications found.
+    pub publications: Vec<EventPublication>,
+    /// The edges between the components.
+    pub edges: BTreeSet<EventFlowEdge>,
+}
+
+/// An analyzer building [`EventFlowGraph`]s.
+#[derive(Debug, Clone)]
+pub struct EventFlowAnalyzer {
+    patterns: Vec<EventPattern>,
+    subscriber_annotations: BTreeSet<ClassRef>,
+}
+
+impl Default for EventFlowAnalyzer {
+    /// Creates an analyzer recognizing the `add*Listener` methods, the Guava `EventBus`, and
+    /// the Spring application events.
+    fn default() -> Self {
+        const GUAVA_EVENT_BUS: &str = "com/google/common/eventbus/*EventBus";
+        let patterns = vec![
+            EventPattern::Registration {
+                method: MethodPattern::new("add*Listener"),
+                listener_argument: 0,
+            },
+            EventPattern::Registration {
+                method: MethodPattern::new("register").with_owner(GUAVA_EVENT_BUS),
+                listener_argument: 0,
+            },
+            EventPattern::Publication {
+                method: MethodPattern::new("post").with_owner(GUAVA_EVENT_BUS),
+                event_argument: 0,
+            },
+            EventPattern::Publication {
+                method: MethodPattern::new("publishEvent")
+                    .with_owner("org/springframework/context/*"),
+                event_argument: 0,
+            },
+        ];
+        let subscriber_annotations = BTreeSet::from([
+            ClassRef::new("com/google/common/eventbus/Subscribe"),
+            ClassRef::new("org/springframework/context/event/EventListener"),
+        ]);
+        Self {
+            patterns,
+            subscriber_annotations,
+        }
+    }
+}
+
+impl EventFlowAnalyzer {
+    /// Creates an analyzer recognizing the given patterns only.
+    #[must_use]
+    pub fn new(patterns: Vec<EventPattern>) -> Self {
+        Self {
+            patterns,
+            subscriber_annotations: BTreeSet::new(),
+        }
+    }
+
+    /// Treats the methods annotated with `annotation` as event subscribers.
+    /// A subscriber method takes a single parameter whose type is the type of the events.
+    #[must_use]
+    pub fn with_subscriber_annotation(mut self, annotation: ClassRef) -> Self {
+        self.subscriber_annotations.insert(annotation);
+        self
+    }
+
+    /// Builds the event flow graph among `classes`.
+    /// Methods that cannot be brewed into Moka IR are skipped.
+    pub fn analyze<'a>(&self, classes: impl IntoIterator<Item = &'a Class>) -> EventFlowGraph {
+        let mut graph = EventFlowGraph::default();
+        let mut subscriptions = BTreeSet::new();
+        for class in classes {
+            for method in &class.methods {
+                if let Some(event_type) = self.subscribed_event_type(method) {
+                    subscriptions.insert((event_type, class.as_ref()));
+                }
+                if method.body.is_none() {
+                    continue;
+                }
+                let Ok(ir_method) = method.brew() else {
+                    continue;
+                };
+                self.analyze_method(class, &ir_method, &mut graph);
+            }
+        }
+
+        for registration in &graph.registrations {
+            for subject in &registration.subjects {
+                for listener in &registration.listeners {
+                    graph.edges.insert(EventFlowEdge {
+                        source: subject.clone(),
+                        target: listener.clone(),
+                        kind: EventFlowKind::Listener {
+                            api: registration.api.clone(),
+                        },
+                    });
+                }
+            }
+        }
+        for publication in &graph.publications {
+            for event_type in &publication.event_types {
+                let subscribers = subscriptions.iter().filter(|(subscribed, _)| {
+                    subscribed == event_type || &*subscribed.binary_name == "java/lang/Object"
+                });
+                for (_, subscriber) in subscribers {
+                    graph.edges.insert(EventFlowEdge {
+                        source: publication.method.owner.clone(),
+                        target: subscriber.clone(),
+                        kind: EventFlowKind::Event {
+                            event_type: event_type.clone(),
+                        },
+                    });
+                }
+            }
+        }
+        graph
+    }
+
+    fn subscribed_event_type(&self, method: &Method) -> Option<ClassRef> {
+        let is_subscriber =
+            method
+                .runtime_visible_annotations
+                .iter()
+                .any(|it| match &it.annotation_type {
+                    FieldType::Object(annotation_type) => {
+                        self.subscriber_annotations.contains(annotation_type)
+                    }
+                    _ => false,
+                });
+        match &*method.descriptor.parameters_types {
+            [FieldType::Object(event_type)] if is_subscriber => Some(event_type.clone()),
+            _ => None,
+        }
+    }
+
+    fn analyze_method(&self, class: &Class, ir_method: &MokaIRMethod, graph: &mut EventFlowGraph) {
+        let resolver = TypeResolver {
+            class,
+            ir_method,
+            du_chain: DefUseChain::new(ir_method),
+        };
+        let method_ref = MethodRef::new(
+            ir_method.owner.clone(),
+            &ir_method.name,
+            ir_method.descriptor.clone(),
+        );
+        for (pc, insn) in &ir_method.instructions {
+            let MokaInstruction::Definition {
+                expr:
+                    Expression::Call {
+                        method: api,
+                        this,
+                        args,
+                    },
+                ..
+            } = insn
+            else {
+                continue;
+            };
+            for pattern in &self.patterns {
+                match pattern {
+                    EventPattern::Registration {
+                        method,
+                        listener_argument,
+                    } if method.matches(api) => {
+                        let mut subjects = this
+                            .as_ref()
+                            .map(|it| resolver.classes_of(it))
+                            .unwrap_or_default();
+                        if subjects.is_empty() {
+                            subjects.insert(api.owner.clone());
+                        }
+                        let listeners = args
+                            .get(*listener_argument)
+                            .map(|it| resolver.classes_of(it))
+                            .unwrap_or_default();
+                        graph.registrations.push(ListenerRegistration {
+                            method: method_ref.clone(),
+                            pc: *pc,
+                            api: api.clone(),
+                            subjects,
+                            listeners,
+                        });
+                    }
+                    EventPattern::Publication {
+                        method,
+                        event_argument,
+                    } if method.matches(api) => {
+                        let event_types = args
+                            .get(*event_argument)
+                            .map(|it| resolver.classes_of(it))
+                            .unwrap_or_default();
+                        graph.publications.push(EventPublication {
+                            method: method_ref.clone(),
+                            pc: *pc,
+                            api: api.clone(),
+                            event_types,
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Resolves the classes of the values in a method.
+struct TypeResolver<'a> {
+    class: &'a Class,
+    ir_method: &'a MokaIRMethod,
+    du_chain: DefUseChain<'a>,
+}
+
+impl TypeResolver<'_> {
+    fn classes_of(&self, operand: &Operand) -> BTreeSet<ClassRef> {
+        operand
+            .into_iter()
+            .filter_map(|it| self.class_of(*it))
+            .collect()
+    }
+
+    fn class_of(&self, identifier: Identifier) -> Option<ClassRef> {
+        let object_class = |field_type: &FieldType| match field_type {
+            FieldType::Object(class_ref) => Some(class_ref.clone()),
+            _ => None,
+        };
+        match identifier {
+            Identifier::This => Some(self.ir_method.owner.clone()),
+            Identifier::Arg(idx) => self
+                .ir_method
+                .descriptor
+                .parameters_types
+                .get(usize::from(idx))
+                .and_then(object_class),
+            Identifier::Local(value) => {
+                let defined_at = self.du_chain.defined_at(&value)?;
+                let MokaInstruction::Definition { expr, .. } =
+                    self.ir_method.instructions.get(&defined_at)?
+                else {
+                    return None;
+                };
+                match expr {
+                    Expression::New(class_ref) => Some(class_ref.clone()),
+                    Expression::Field(
+                        FieldAccess::ReadStatic { field } | FieldAccess::ReadInstance { field, .. },
+                    ) => object_class(&field.field_type),
+                    Expression::Call { method, .. } => match &method.descriptor.return_type {
+                        ReturnType::Some(return_type) => object_class(return_type),
+                        ReturnType::Void => None,
+                    },
+                    Expression::Closure {
+                        bootstrap_method_index,
+                        closure_descriptor,
+                        ..
+                    } => self
+                        .closure_implementation(*bootstrap_method_index)
+                        .or_else(|| match &closure_descriptor.return_type {
+                            ReturnType::Some(return_type) => object_class(return_type),
+                            ReturnType::Void => None,
+                        }),
+                    _ => None,
+                }
+            }
+            Identifier::CaughtException => None,
+        }
+    }
+
+    /// Gets the class implementing a lambda expression created by `LambdaMetafactory`.
+    fn closure_implementation(&self, bootstrap_method_index: u16) -> Option<ClassRef> {
+        let bootstrap_method = self
+            .class
+            .bootstrap_methods
+            .get(usize::from(bootstrap_method_index))?;
+        bootstrap_method.arguments.iter().find_map(|it| match it {
+            ConstantValue::Handle(
+                MethodHandle::RefInvokeStatic(method)
+                | MethodHandle::RefInvokeVirtual(method)
+                | MethodHandle::RefInvokeSpecial(method)
+                | MethodHandle::RefNewInvokeSpecial(method)
+                | MethodHandle::RefInvokeInterface(method),
+            ) => Some(method.owner.clone()),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::jvm::{
+        code::{Instruction, MethodBody},
+        method,
+        references::FieldRef,
+        Annotation,
+    };
+
+    use super::*;
+    use crate::tests::method_ref;
+
+    fn static_method(owner: &str, descriptor: &str, instructions: Vec<Instruction>) -> Method {
+        let mut pc = 0;
+        let instructions = instructions
+            .into_iter()
+            .map(|insn| {
+                let current = pc;
+                pc += 3;
+                (current.into(), insn)
+            })
+            .collect::<BTreeMap<_, _>>();
+        Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: "run".to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+            owner: ClassRef::new(owner),
+            body: Some(MethodBody {
+                max_stack: 3,
+                max_locals: 1,
+                instructions: instructions.into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn glob() {
+        assert!(glob_matches("add*Listener", "addActionListener"));
+        assert!(glob_matches("add*Listener", "addListener"));
+        assert!(!glob_matches("add*Listener", "addListeners"));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "aXbYbc"));
+    }
+
+    #[test]
+    fn listener_and_event_edges() {
+        let app = Class {
+            binary_name: "App".to_owned(),
+            methods: vec![static_method(
+                "App",
+                "(LButton;)V",
+                vec![
+                    Instruction::ALoad0,
+                    Instruction::New(ClassRef::new("AppListener")),
+                    Instruction::Dup,
+                    Instruction::InvokeSpecial(method_ref("AppListener", "<init>", "()V")),
+                    Instruction::InvokeVirtual(method_ref(
+                        "Button",
+                        "addActionListener",
+                        "(LActionListener;)V",
+                    )),
+                    Instruction::GetStatic(FieldRef::new(
+                        ClassRef::new("App"),
+                        "bus",
+                        "Lcom/google/common/eventbus/EventBus;".parse().unwrap(),
+                    )),
+                    Instruction::New(ClassRef::new("OrderPlaced")),
+                    Instruction::Dup,
+                    Instruction::InvokeSpecial(method_ref("OrderPlaced", "<init>", "()V")),
+                    Instruction::InvokeVirtual(method_ref(
+                        "com/google/common/eventbus/EventBus",
+                        "post",
+                        "(Ljava/lang/Object;)V",
+                    )),
+                    Instruction::Return,
+                ],
+            )],
+            ..Default::default()
+        };
+        let handler = Class {
+            binary_name: "Handler".to_owned(),
+            methods: vec![Method {
+                name: "on".to_owned(),
+                descriptor: "(LOrderPlaced;)V".parse().unwrap(),
+                owner: ClassRef::new("Handler"),
+                runtime_visible_annotations: vec![Annotation {
+                    annotation_type: "Lcom/google/common/eventbus/Subscribe;".parse().unwrap(),
+                    element_value_pairs: Vec::new(),
+                }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let graph = EventFlowAnalyzer::default().analyze([&app, &handler]);
+        assert_eq!(graph.registrations.len(), 1);
+        assert_eq!(graph.publications.len(), 1);
+        assert_eq!(
+            graph.edges,
+            BTreeSet::from([
+                EventFlowEdge {
+                    source: ClassRef::new("Button"),
+                    target: ClassRef::new("AppListener"),
+                    kind: EventFlowKind::Listener {
+                        api: method_ref("Button", "addActionListener", "(LActionListener;)V"),
+                    },
+                },
+                EventFlowEdge {
+                    source: ClassRef::new("App"),
+                    target: ClassRef::new("Handler"),
+                    kind: EventFlowKind::Event {
+                        event_type: ClassRef::new("OrderPlaced"),
+                    },
+                },
+            ])
+        );
+    }
+}
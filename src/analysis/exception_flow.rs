@@ -0,0 +1,721 @@
+//! Inference of the exceptions that may escape from methods.
+//!
+//! The [`ExceptionFlowAnalyzer`] collects the exceptions raised in a method, i.e.,
+//! - the runtime exceptions raised by instructions (e.g., `ArrayIndexOutOfBoundsException` by
+//!   array accesses, or `NullPointerException` by accesses to a possibly `null` receiver),
+//! - the exceptions thrown with `athrow`, whose types are inferred from the thrown values, and
+//! - the exceptions escaping from the callees,
+//!
+//! and routes them through the exception table. The exceptions that are not caught escape from
+//! the method, and are propagated to the callers until a fixed point is reached.
+//! The results can be compared against the `throws` clauses with
+//! [`ExceptionFlowAnalyzer::undocumented`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    ir::{
+        expression::{Conversion, Expression, FieldAccess},
+        DefUseChain, Identifier, MokaIRMethod, MokaIRMethodExt, MokaInstruction,
+    },
+    jvm::{
+        code::{ExceptionTableEntry, Instruction, ProgramCounter},
+        references::{ClassRef, MethodRef},
+        Method,
+    },
+    types::{field_type::FieldType, method_descriptor::ReturnType},
+};
+
+use super::ResolutionContext;
+
+const THROWABLE: &str = "java/lang/Throwable";
+const RUNTIME_EXCEPTION: &str = "java/lang/RuntimeException";
+const ERROR: &str = "java/lang/Error";
+const NULL_POINTER: &str = "java/lang/NullPointerException";
+
+/// The super classes of the exceptions in `java.lang` that are raised by instructions, which
+/// are used when the JDK classes are not in the [`ResolutionContext`].
+const JDK_EXCEPTIONS: [(&str, &str); 14] = [
+    ("java/lang/Exception", THROWABLE),
+    (ERROR, THROWABLE),
+    (RUNTIME_EXCEPTION, "java/lang/Exception"),
+    ("java/io/IOException", "java/lang/Exception"),
+    (NULL_POINTER, RUNTIME_EXCEPTION),
+    ("java/lang/IndexOutOfBoundsException", RUNTIME_EXCEPTION),
+    (
+        "java/lang/ArrayIndexOutOfBoundsException",
+        "java/lang/IndexOutOfBoundsException",
+    ),
+    ("java/lang/ArrayStoreException", RUNTIME_EXCEPTION),
+    ("java/lang/ArithmeticException", RUNTIME_EXCEPTION),
+    ("java/lang/ClassCastException", RUNTIME_EXCEPTION),
+    ("java/lang/NegativeArraySizeException", RUNTIME_EXCEPTION),
+    ("java/lang/IllegalMonitorStateException", RUNTIME_EXCEPTION),
+    ("java/lang/IllegalArgumentException", RUNTIME_EXCEPTION),
+    ("java/lang/IllegalStateException", RUNTIME_EXCEPTION),
+];
+
+/// The exceptions that may escape from each analyzed method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ThrownExceptions {
+    summaries: BTreeMap<MethodRef, BTreeSet<ClassRef>>,
+}
+
+impl ThrownExceptions {
+    /// Returns the exceptions that may escape from `method`, or [`None`] if `method` is not
+    /// analyzed.
+    #[must_use]
+    pub fn get(&self, method: &MethodRef) -> Option<&BTreeSet<ClassRef>> {
+        self.summaries.get(method)
+    }
+
+    /// Creates an iterator over the analyzed methods and their exceptions.
+    pub fn iter(&self) -> impl Iterator<Item = (&MethodRef, &BTreeSet<ClassRef>)> {
+        self.summaries.iter()
+    }
+}
+
+/// An exception that may escape from a method but is not declared in its `throws` clause.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UndocumentedException {
+    /// The method.
+    pub method: MethodRef,
+    /// The type of the exception.
+    pub exception: ClassRef,
+    /// Whether the exception is checked, i.e., it is neither a `RuntimeException` nor an
+    /// `Error`.
+    pub checked: bool,
+}
+
+/// A location where an exception is raised.
+#[derive(Debug)]
+enum Raise {
+    /// The exceptions of the given types are raised.
+    Types(BTreeSet<ClassRef>),
+    /// The exceptions escaping from the callee are raised.
+    Call(MethodRef),
+    /// The exception caught by the handler at the given location is rethrown.
+    Rethrow(ProgramCounter),
+}
+
+/// The exception sources of a method.
+#[derive(Debug)]
+struct MethodFacts {
+    raises: Vec<(ProgramCounter, Raise)>,
+    exception_table: Vec<ExceptionTableEntry>,
+}
+
+/// An analyzer of the exceptions escaping from the methods in the application classes of a
+/// [`ResolutionContext`].
+#[derive(Debug)]
+pub struct ExceptionFlowAnalyzer<'a> {
+    context: &'a ResolutionContext,
+}
+
+impl<'a> ExceptionFlowAnalyzer<'a> {
+    /// Creates an analyzer resolving classes and callees in `context`.
+    #[must_use]
+    pub const fn new(context: &'a ResolutionContext) -> Self {
+        Self { context }
+    }
+
+    /// Infers the exceptions that may escape from the methods in the application classes.
+    #[must_use]
+    pub fn analyze(&self) -> ThrownExceptions {
+        let facts: BTreeMap<_, _> = self
+            .context
+            .application_classes
+            .values()
+            .flat_map(|it| &it.methods)
+            .filter(|it| it.body.is_some())
+            .map(|it| (it.as_ref(), Self::method_facts(it)))
+            .collect();
+        let mut thrown = ThrownExceptions {
+            summaries: facts
+                .keys()
+                .map(|it| (it.clone(), BTreeSet::new()))
+                .collect(),
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (method, facts) in &facts {
+                let escaping = self.escaping(facts, &thrown);
+                let summary = thrown.summaries.entry(method.clone()).or_default();
+                if *summary != escaping {
+                    *summary = escaping;
+                    changed = true;
+                }
+            }
+        }
+        thrown
+    }
+
+    /// Reports the exceptions in `thrown` that are not covered by the `throws` clauses of the
+    /// methods.
+    #[must_use]
+    pub fn undocumented(&self, thrown: &ThrownExceptions) -> Vec<UndocumentedException> {
+        let mut reports = Vec::new();
+        for (method_ref, exceptions) in thrown.iter() {
+            let Some(method) = self.method(method_ref) else {
+                continue;
+            };
+            for exception in exceptions {
+                let documented = method
+                    .exceptions
+                    .iter()
+                    .any(|declared| self.is_subclass(exception, declared));
+                if !documented {
+                    let checked = !self.is_subclass(exception, &ClassRef::new(RUNTIME_EXCEPTION))
+                        && !self.is_subclass(exception, &ClassRef::new(ERROR));
+                    reports.push(UndocumentedException {
+                        method: method_ref.clone(),
+                        exception: exception.clone(),
+                        checked,
+                    });
+                }
+            }
+        }
+        reports
+    }
+
+    /// Checks whether `class` is `super_class` or one of its subclasses.
+    #[must_use]
+    pub fn is_subclass(&self, class: &ClassRef, super_class: &ClassRef) -> bool {
+        let mut current = class.clone();
+        loop {
+            if &current == super_class {
+                return true;
+            }
+            let next = match self.context.get_class(&current) {
+                Some(class) => class.super_class.clone(),
+                None => JDK_EXCEPTIONS
+                    .iter()
+                    .find(|(it, _)| *it == &*current.binary_name)
+                    .map(|(_, super_class)| ClassRef::new(*super_class)),
+            };
+            match next {
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+    }
+
+    /// Computes the exceptions escaping from a method given the summaries of the callees.
+    fn escaping(&self, facts: &MethodFacts, thrown: &ThrownExceptions) -> BTreeSet<ClassRef> {
+        let mut caught: BTreeMap<ProgramCounter, BTreeSet<ClassRef>> = BTreeMap::new();
+        loop {
+            let mut escaping = BTreeSet::new();
+            let mut next_caught = BTreeMap::new();
+            for (pc, raise) in &facts.raises {
+                let types = match raise {
+                    Raise::Types(types) => types.clone(),
+                    Raise::Call(callee) => self.callee_exceptions(callee, thrown),
+                    Raise::Rethrow(handler_pc) => {
+                        caught.get(handler_pc).cloned().unwrap_or_default()
+                    }
+                };
+                for exception in types {
+                    self.route(facts, *pc, exception, &mut next_caught, &mut escaping);
+                }
+            }
+            if next_caught == caught {
+                return escaping;
+            }
+            caught = next_caught;
+        }
+    }
+
+    /// Routes an exception raised at `pc` to the handlers covering `pc`.
+    fn route(
+        &self,
+        facts: &MethodFacts,
+        pc: ProgramCounter,
+        exception: ClassRef,
+        caught: &mut BTreeMap<ProgramCounter, BTreeSet<ClassRef>>,
+        escaping: &mut BTreeSet<ClassRef>,
+    ) {
+        for entry in facts.exception_table.iter().filter(|it| it.covers(pc)) {
+            match &entry.catch_type {
+                Some(catch_type) if self.is_subclass(&exception, catch_type) => {}
+                Some(catch_type) if self.is_subclass(catch_type, &exception) => {
+                    // Only the exceptions of the subclass are caught.
+                    caught
+                        .entry(entry.handler_pc)
+                        .or_default()
+                        .insert(catch_type.clone());
+                    continue;
+                }
+                Some(_) => continue,
+                None => {}
+            }
+            caught
+                .entry(entry.handler_pc)
+                .or_default()
+                .insert(exception);
+            return;
+        }
+        escaping.insert(exception);
+    }
+
+    fn callee_exceptions(
+        &self,
+        callee: &MethodRef,
+        thrown: &ThrownExceptions,
+    ) -> BTreeSet<ClassRef> {
+        if let Some(exceptions) = thrown.get(callee) {
+            return exceptions.clone();
+        }
+        self.method(callee)
+            .map(|it| it.exceptions.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Finds the declaration of `method_ref` in its owner or the super classes of the owner.
+    fn method(&self, method_ref: &MethodRef) -> Option<&'a Method> {
+        let mut owner = self.context.get_class(&method_ref.owner)?;
+        loop {
+            if let Some(method) = owner.get_method(&method_ref.name, &method_ref.descriptor) {
+                return Some(method);
+            }
+            owner = self.context.get_class(owner.super_class.as_ref()?)?;
+        }
+    }
+
+    /// Collects the locations where exceptions are raised in `method`.
+    fn method_facts(method: &Method) -> MethodFacts {
+        let Some(body) = &method.body else {
+            return MethodFacts {
+                raises: Vec::new(),
+                exception_table: Vec::new(),
+            };
+        };
+        let ir_method = method.brew().ok();
+        let types = IrTypes {
+            ir_method: ir_method.as_ref(),
+            du_chain: ir_method.as_ref().map(DefUseChain::new),
+        };
+        let raises = body
+            .instructions
+            .iter()
+            .flat_map(|(pc, insn)| {
+                let raises: Vec<_> = match insn {
+                    Instruction::InvokeVirtual(callee)
+                    | Instruction::InvokeSpecial(callee)
+                    | Instruction::InvokeStatic(callee)
+                    | Instruction::InvokeInterface(callee, _) => {
+                        vec![Raise::Call(callee.clone())]
+                    }
+                    Instruction::AThrow => types.thrown_at(*pc, &body.exception_table),
+                    _ => Vec::new(),
+                };
+                let implicit = implicit_exceptions(insn, types.may_be_null_receiver(*pc));
+                raises
+                    .into_iter()
+                    .chain((!implicit.is_empty()).then_some(Raise::Types(implicit)))
+                    .map(|it| (*pc, it))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        MethodFacts {
+            raises,
+            exception_table: body.exception_table.clone(),
+        }
+    }
+}
+
+/// Returns the runtime exceptions raised by `insn` itself.
+fn implicit_exceptions(insn: &Instruction, may_be_null: bool) -> BTreeSet<ClassRef> {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let mut exceptions = match insn {
+        IALoad | LALoad | FALoad | DALoad | AALoad | BALoad | CALoad | SALoad | IAStore
+        | LAStore | FAStore | DAStore | BAStore | CAStore | SAStore => {
+            vec!["java/lang/ArrayIndexOutOfBoundsException"]
+        }
+        AAStore => vec![
+            "java/lang/ArrayIndexOutOfBoundsException",
+            "java/lang/ArrayStoreException",
+        ],
+        IDiv | LDiv | IRem | LRem => vec!["java/lang/ArithmeticException"],
+        CheckCast(_) => vec!["java/lang/ClassCastException"],
+        NewArray(_) | ANewArray(_) | MultiANewArray(_, _) => {
+            vec!["java/lang/NegativeArraySizeException"]
+        }
+        MonitorExit => vec!["java/lang/IllegalMonitorStateException"],
+        _ => Vec::new(),
+    };
+    let dereferences = matches!(
+        insn,
+        IALoad
+            | LALoad
+            | FALoad
+            | DALoad
+            | AALoad
+            | BALoad
+            | CALoad
+            | SALoad
+            | IAStore
+            | LAStore
+            | FAStore
+            | DAStore
+            | AAStore
+            | BAStore
+            | CAStore
+            | SAStore
+            | ArrayLength
+            | GetField(_)
+            | PutField(_)
+            | InvokeVirtual(_)
+            | InvokeSpecial(_)
+            | InvokeInterface(_, _)
+            | MonitorEnter
+            | MonitorExit
+    );
+    if dereferences && may_be_null {
+        exceptions.push(NULL_POINTER);
+    }
+    exceptions.into_iter().map(ClassRef::new).collect()
+}
+
+/// Infers the types of values from the Moka IR of a method.
+struct IrTypes<'a> {
+    ir_method: Option<&'a MokaIRMethod>,
+    du_chain: Option<DefUseChain<'a>>,
+}
+
+impl IrTypes<'_> {
+    /// Returns the exceptions raised by the `athrow` at `pc`.
+    fn thrown_at(&self, pc: ProgramCounter, exception_table: &[ExceptionTableEntry]) -> Vec<Raise> {
+        let throwable = || vec![Raise::Types(BTreeSet::from([ClassRef::new(THROWABLE)]))];
+        let Some(MokaInstruction::Definition {
+            expr: Expression::Throw(operand),
+            ..
+        }) = self.ir_method.and_then(|it| it.instructions.get(&pc))
+        else {
+            return throwable();
+        };
+        let mut types = BTreeSet::new();
+        let mut raises = Vec::new();
+        for identifier in operand {
+            if *identifier == Identifier::CaughtException {
+                // The handler whose code contains the `athrow`.
+                let handler_pc = exception_table
+                    .iter()
+                    .map(|it| it.handler_pc)
+                    .filter(|it| *it <= pc)
+                    .max();
+                match handler_pc {
+                    Some(handler_pc) => raises.push(Raise::Rethrow(handler_pc)),
+                    None => {
+                        types.insert(ClassRef::new(THROWABLE));
+                    }
+                }
+            } else {
+                types.insert(
+                    self.class_of(*identifier)
+                        .unwrap_or_else(|| ClassRef::new(THROWABLE)),
+                );
+            }
+        }
+        if !types.is_empty() {
+            raises.push(Raise::Types(types));
+        }
+        raises
+    }
+
+    /// Checks whether the receiver of the instruction at `pc` may be `null`.
+    fn may_be_null_receiver(&self, pc: ProgramCounter) -> bool {
+        let Some(MokaInstruction::Definition { expr, .. }) =
+            self.ir_method.and_then(|it| it.instructions.get(&pc))
+        else {
+            return true;
+        };
+        let (Expression::Call {
+            this: Some(receiver),
+            ..
+        }
+        | Expression::Field(
+            FieldAccess::ReadInstance {
+                object_ref: receiver,
+                ..
+            }
+            | FieldAccess::WriteInstance {
+                object_ref: receiver,
+                ..
+            },
+        )) = expr
+        else {
+            return true;
+        };
+        !receiver.iter().all(|it| self.is_non_null(*it))
+    }
+
+    fn is_non_null(&self, identifier: Identifier) -> bool {
+        match identifier {
+            Identifier::This | Identifier::CaughtException => true,
+            Identifier::Local(_) => matches!(self.definition(identifier), Some(Expression::New(_))),
+            Identifier::Arg(_) => false,
+        }
+    }
+
+    fn definition(&self, identifier: Identifier) -> Option<&Expression> {
+        let Identifier::Local(value) = identifier else {
+            return None;
+        };
+        let defined_at = self.du_chain.as_ref()?.defined_at(&value)?;
+        match self.ir_method?.instructions.get(&defined_at)? {
+            MokaInstruction::Definition { expr, .. } => Some(expr),
+            _ => None,
+        }
+    }
+
+    fn class_of(&self, identifier: Identifier) -> Option<ClassRef> {
+        let object_class = |field_type: &FieldType| match field_type {
+            FieldType::Object(class_ref) => Some(class_ref.clone()),
+            _ => None,
+        };
+        if let Identifier::Arg(idx) = identifier {
+            return self
+                .ir_method?
+                .descriptor
+                .parameters_types
+                .get(usize::from(idx))
+                .and_then(object_class);
+        }
+        match self.definition(identifier)? {
+            Expression::New(class_ref) => Some(class_ref.clone()),
+            Expression::Field(
+                FieldAccess::ReadStatic { field } | FieldAccess::ReadInstance { field, .. },
+            ) => object_class(&field.field_type),
+            Expression::Call { method, .. } => match &method.descriptor.return_type {
+                ReturnType::Some(return_type) => object_class(return_type),
+                ReturnType::Void => None,
+            },
+            Expression::Conversion(Conversion::CheckCast(_, target_type)) => {
+                object_class(target_type)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        ir::{ClassHierarchy, InterfaceImplHierarchy},
+        jvm::interop::jasmin,
+        tests::method_ref,
+    };
+
+    const JASMIN: &str = "
+        .bytecode 52.0
+        .class public Foo
+        .super java/lang/Object
+        .method public static fail()V
+            .limit stack 2
+            new java/io/IOException
+            dup
+            invokespecial java/io/IOException/<init>()V
+            athrow
+        .end method
+        .method public static declared()V
+            .throws java/io/IOException
+            invokestatic Foo/fail()V
+            return
+        .end method
+        .method public static guarded()V
+            .limit stack 1
+            .catch java/lang/Exception from L0 to L1 using L1
+        L0:
+            invokestatic Foo/fail()V
+            return
+        L1:
+            pop
+            return
+        .end method
+        .method public static element([I)I
+            .limit stack 2
+            aload_0
+            iconst_0
+            iaload
+            ireturn
+        .end method
+    ";
+
+    const HANDLERS: &str = "
+        .bytecode 52.0
+        .class public Handlers
+        .super java/lang/Object
+        .method public static io()V
+            .limit stack 2
+            new java/io/IOException
+            dup
+            invokespecial java/io/IOException/<init>()V
+            athrow
+        .end method
+        .method public static state()V
+            .limit stack 2
+            new java/lang/IllegalStateException
+            dup
+            invokespecial java/lang/IllegalStateException/<init>()V
+            athrow
+        .end method
+        .method public static nested()V
+            .limit stack 1
+            .catch java/lang/IllegalStateException from L0 to L1 using L1
+            .catch java/io/IOException from L0 to L2 using L2
+        L0:
+            invokestatic Handlers/state()V
+            invokestatic Handlers/io()V
+            return
+        L1:
+            pop
+            return
+        L2:
+            pop
+            return
+        .end method
+        .method public static overlapping()V
+            .limit stack 1
+            .catch java/lang/IllegalStateException from L0 to L2 using L3
+            .catch java/io/IOException from L1 to L3 using L3
+        L0:
+            invokestatic Handlers/io()V
+        L1:
+            invokestatic Handlers/state()V
+        L2:
+            invokestatic Handlers/state()V
+            return
+        L3:
+            pop
+            return
+        .end method
+        .method public static catchAll()V
+            .limit stack 1
+            .catch all from L0 to L1 using L1
+        L0:
+            invokestatic Handlers/io()V
+            invokestatic Handlers/state()V
+            return
+        L1:
+            pop
+            return
+        .end method
+        .method public static rethrow()V
+            .limit stack 1
+            .catch java/lang/Exception from L0 to L1 using L1
+        L0:
+            invokestatic Handlers/io()V
+            return
+        L1:
+            athrow
+        .end method
+        .method public static rethrowAll()V
+            .limit stack 1
+            .catch all from L0 to L1 using L1
+        L0:
+            invokestatic Handlers/io()V
+            invokestatic Handlers/state()V
+            return
+        L1:
+            athrow
+        .end method
+        .method public static narrower()V
+            .limit stack 1
+            .catch java/lang/IllegalArgumentException from L0 to L1 using L1
+        L0:
+            invokestatic Handlers/io()V
+            invokestatic Handlers/state()V
+            return
+        L1:
+            pop
+            return
+        .end method
+        .method public static ping()V
+            invokestatic Handlers/pong()V
+            return
+        .end method
+        .method public static pong()V
+            invokestatic Handlers/ping()V
+            invokestatic Handlers/state()V
+            return
+        .end method
+    ";
+
+    fn context(source: &str) -> ResolutionContext {
+        let class = jasmin::read(source).unwrap();
+        let classes = BTreeMap::from([(class.as_ref(), class)]);
+        ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(classes.values()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(classes.values()),
+            application_classes: classes,
+            library_classes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn infer_thrown_exceptions() {
+        let context = context(JASMIN);
+        let class = &context.application_classes[&ClassRef::new("Foo")];
+        let analyzer = ExceptionFlowAnalyzer::new(&context);
+        let thrown = analyzer.analyze();
+        let exceptions = |idx: usize| {
+            thrown
+                .get(&class.methods[idx].as_ref())
+                .unwrap()
+                .iter()
+                .map(|it| it.binary_name.as_ref())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(exceptions(0), vec!["java/io/IOException"]);
+        assert_eq!(exceptions(1), vec!["java/io/IOException"]);
+        assert_eq!(exceptions(2), Vec::<&str>::new());
+        assert_eq!(
+            exceptions(3),
+            vec![
+                "java/lang/ArrayIndexOutOfBoundsException",
+                "java/lang/NullPointerException"
+            ]
+        );
+
+        let undocumented = analyzer.undocumented(&thrown);
+        assert_eq!(undocumented.len(), 3);
+        let (checked, unchecked): (Vec<_>, Vec<_>) =
+            undocumented.into_iter().partition(|it| it.checked);
+        assert_eq!(checked.len(), 1);
+        assert_eq!(&*checked[0].method.name, "fail");
+        assert!(unchecked.iter().all(|it| &*it.method.name == "element"));
+    }
+
+    #[test]
+    fn route_through_handlers() {
+        const IO: &str = "java/io/IOException";
+        const STATE: &str = "java/lang/IllegalStateException";
+        let context = context(HANDLERS);
+        let thrown = ExceptionFlowAnalyzer::new(&context).analyze();
+        let exceptions = |name: &str| {
+            thrown
+                .get(&method_ref("Handlers", name, "()V"))
+                .unwrap()
+                .iter()
+                .map(|it| it.binary_name.as_ref())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(exceptions("nested"), Vec::<&str>::new());
+        // Only the second call is covered by both entries.
+        assert_eq!(exceptions("overlapping"), vec![IO, STATE]);
+        assert_eq!(exceptions("catchAll"), Vec::<&str>::new());
+        // The rethrown exceptions keep the types that are caught.
+        assert_eq!(exceptions("rethrow"), vec![IO]);
+        assert_eq!(exceptions("rethrowAll"), vec![IO, STATE]);
+        assert_eq!(exceptions("narrower"), vec![IO, STATE]);
+        // The exceptions escape through recursive calls.
+        assert_eq!(exceptions("ping"), vec![STATE]);
+        assert_eq!(exceptions("pong"), vec![STATE]);
+    }
+}
@@ -0,0 +1,394 @@
+//! Detection of exception handling smells.
+//!
+//! The detector inspects each `catch` block, i.e., the instructions dominated by the handler of
+//! an entry in the exception table, and reports the handlers that swallow the exceptions, catch
+//! exceptions too broadly, or discard the cause when wrapping the exceptions.
+//! Handlers without a catch type, which implement `finally` blocks, are not inspected.
+
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::{
+    ir::{
+        expression::Expression, DefUseChain, Identifier, MokaIRBrewingError, MokaIRMethod,
+        MokaIRMethodExt, MokaInstruction, Operand,
+    },
+    jvm::{code::ProgramCounter, references::ClassRef, references::MethodRef, Class, Method},
+};
+
+/// An exception handling smell.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ExceptionSmell {
+    /// The method containing the handler.
+    pub method: MethodRef,
+    /// The location of the handler.
+    pub handler_pc: ProgramCounter,
+    /// The type of the exceptions caught by the handler.
+    pub catch_type: ClassRef,
+    /// The kind of the smell.
+    pub kind: ExceptionSmellKind,
+}
+
+/// The kind of an [`ExceptionSmell`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExceptionSmellKind {
+    /// The `catch` block does nothing.
+    EmptyCatch,
+    /// The `catch` block only prints the stack trace of the exception.
+    PrintStackTraceOnly,
+    /// A broad exception type (e.g., `java/lang/Exception`) is caught outside the boundary
+    /// packages.
+    BroadCatch,
+    /// A new exception is thrown in the `catch` block without the caught one as its cause.
+    CauseDiscarded {
+        /// The location where the new exception is thrown.
+        throw_pc: ProgramCounter,
+        /// The type of the new exception.
+        thrown: ClassRef,
+    },
+}
+
+/// A detector of [`ExceptionSmell`]s.
+#[derive(Debug, Clone)]
+pub struct ExceptionSmellDetector {
+    broad_types: BTreeSet<ClassRef>,
+    boundary_packages: Vec<String>,
+}
+
+impl Default for ExceptionSmellDetector {
+    /// Creates a detector treating `java/lang/Throwable` and `java/lang/Exception` as broad
+    /// types, and no package as boundary.
+    fn default() -> Self {
+        Self {
+            broad_types: BTreeSet::from([
+                ClassRef::new("java/lang/Throwable"),
+                ClassRef::new("java/lang/Exception"),
+            ]),
+            boundary_packages: Vec::new(),
+        }
+    }
+}
+
+impl ExceptionSmellDetector {
+    /// Treats `broad_type` as a broad exception type.
+    #[must_use]
+    pub fn with_broad_type(mut self, broad_type: ClassRef) -> Self {
+        self.broad_types.insert(broad_type);
+        self
+    }
+
+    /// Allows catching broad exception types in the classes in `package` (e.g., `com/example/web`)
+    /// and its subpackages, which is expected at the boundaries of an application.
+    #[must_use]
+    pub fn with_boundary_package(mut self, package: impl Into<String>) -> Self {
+        self.boundary_packages.push(package.into());
+        self
+    }
+
+    /// Detects the smells in the methods of `class`.
+    /// Methods that cannot be brewed into Moka IR are skipped.
+    #[must_use]
+    pub fn detect_class(&self, class: &Class) -> Vec<ExceptionSmell> {
+        class
+            .methods
+            .iter()
+            .filter(|it| it.body.is_some())
+            .filter_map(|it| self.detect_method(it).ok())
+            .flatten()
+            .collect()
+    }
+
+    /// Detects the smells in `method`.
+    ///
+    /// # Errors
+    /// See [`MokaIRBrewingError`].
+    pub fn detect_method(
+        &self,
+        method: &Method,
+    ) -> Result<Vec<ExceptionSmell>, MokaIRBrewingError> {
+        let ir_method = method.brew()?;
+        let du_chain = DefUseChain::new(&ir_method);
+        let handlers: BTreeMap<_, _> = ir_method
+            .exception_table
+            .iter()
+            .filter_map(|it| Some((it.handler_pc, it.catch_type.clone()?)))
+            .collect();
+        let is_boundary = self.boundary_packages.iter().any(|package| {
+            method
+                .owner
+                .binary_name
+                .strip_prefix(package.trim_end_matches('/'))
+                .is_some_and(|rest| rest.starts_with('/'))
+        });
+
+        let mut smells = Vec::new();
+        for (handler_pc, catch_type) in handlers {
+            let mut report = |kind| {
+                smells.push(ExceptionSmell {
+                    method: method.as_ref(),
+                    handler_pc,
+                    catch_type: catch_type.clone(),
+                    kind,
+                });
+            };
+            if !is_boundary && self.broad_types.contains(&catch_type) {
+                report(ExceptionSmellKind::BroadCatch);
+            }
+            let block: Vec<_> = catch_block(&ir_method, handler_pc)
+                .into_iter()
+                .filter_map(|pc| Some((pc, ir_method.instructions.get(&pc)?)))
+                .collect();
+            let mut effects = block.iter().filter(|(_, insn)| {
+                !matches!(
+                    insn,
+                    MokaInstruction::Nop
+                        | MokaInstruction::Jump {
+                            condition: None,
+                            ..
+                        }
+                )
+            });
+            let is_print_stack_trace = |insn: &MokaInstruction| {
+                matches!(
+                    insn,
+                    MokaInstruction::Definition {
+                        expr: Expression::Call { method, args, .. },
+                        ..
+                    } if &*method.name == "printStackTrace" && args.is_empty()
+                )
+            };
+            if effects.clone().next().is_none() {
+                report(ExceptionSmellKind::EmptyCatch);
+            } else if effects.all(|(_, insn)| is_print_stack_trace(insn)) {
+                report(ExceptionSmellKind::PrintStackTraceOnly);
+            }
+            for (throw_pc, insn) in &block {
+                let MokaInstruction::Definition {
+                    expr: Expression::Throw(thrown),
+                    ..
+                } = insn
+                else {
+                    continue;
+                };
+                for (value, thrown) in created_exceptions(&ir_method, &du_chain, thrown) {
+                    if !is_cause_passed(&ir_method, &du_chain, value) {
+                        report(ExceptionSmellKind::CauseDiscarded {
+                            throw_pc: *throw_pc,
+                            thrown,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(smells)
+    }
+}
+
+/// Gets the instructions dominated by `handler_pc`, i.e., those reachable from `handler_pc` but
+/// not from the entry point without passing through `handler_pc`.
+fn catch_block(ir_method: &MokaIRMethod, handler_pc: ProgramCounter) -> BTreeSet<ProgramCounter> {
+    let cfg = &ir_method.control_flow_graph;
+    let reachable = |start: ProgramCounter, blocked: Option<ProgramCounter>| {
+        let mut visited = BTreeSet::new();
+        let mut worklist = VecDeque::from([start]);
+        while let Some(pc) = worklist.pop_front() {
+            if Some(pc) == blocked || !visited.insert(pc) {
+                continue;
+            }
+            worklist.extend(
+                cfg.edges_from(pc)
+                    .into_iter()
+                    .flatten()
+                    .map(|(_, dst, _)| dst),
+            );
+        }
+        visited
+    };
+    let bypassing = reachable(cfg.entry_point(), Some(handler_pc));
+    reachable(handler_pc, None)
+        .difference(&bypassing)
+        .copied()
+        .collect()
+}
+
+/// Gets the values created by `new` that may flow to `operand`.
+fn created_exceptions(
+    ir_method: &MokaIRMethod,
+    du_chain: &DefUseChain<'_>,
+    operand: &Operand,
+) -> Vec<(Identifier, ClassRef)> {
+    operand
+        .into_iter()
+        .filter_map(|id| {
+            let Identifier::Local(value) = id else {
+                return None;
+            };
+            let defined_at = du_chain.defined_at(value)?;
+            match ir_method.instructions.get(&defined_at)? {
+                MokaInstruction::Definition {
+                    expr: Expression::New(class_ref),
+                    ..
+                } => Some((*id, class_ref.clone())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Checks whether the caught exception is passed to a method (e.g., the constructor or
+/// `initCause`) called on `exception`.
+fn is_cause_passed(
+    ir_method: &MokaIRMethod,
+    du_chain: &DefUseChain<'_>,
+    exception: Identifier,
+) -> bool {
+    du_chain.used_at(&exception).into_iter().any(|pc| {
+        matches!(
+            ir_method.instructions.get(&pc),
+            Some(MokaInstruction::Definition {
+                expr: Expression::Call { this: Some(this), args, .. },
+                ..
+            }) if this.into_iter().any(|it| *it == exception)
+                && args
+                    .iter()
+                    .flatten()
+                    .any(|it| *it == Identifier::CaughtException)
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::{
+        code::{ExceptionTableEntry, Instruction, InstructionList, MethodBody},
+        method, ConstantValue, JavaString,
+    };
+
+    use super::*;
+    use crate::tests::method_ref;
+    use Instruction::{
+        ALoad0, AStore0, AThrow, Dup, Goto, InvokeSpecial, InvokeStatic, InvokeVirtual, Ldc, New,
+        Return,
+    };
+
+    fn method_with(
+        catch_type: &str,
+        handler: impl IntoIterator<Item = (u16, Instruction)>,
+    ) -> Method {
+        let mut instructions: BTreeMap<_, _> = [
+            (
+                0,
+                InvokeStatic(method_ref("com/example/Util", "work", "()V")),
+            ),
+            (3, Goto(100.into())),
+        ]
+        .into_iter()
+        .chain(handler)
+        .chain([(100, Return)])
+        .map(|(pc, insn)| (pc.into(), insn))
+        .collect();
+        instructions.entry(6.into()).or_insert(AStore0);
+        Method {
+            access_flags: method::AccessFlags::STATIC,
+            owner: ClassRef::new("com/example/service/Service"),
+            descriptor: "()V".parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 3,
+                max_locals: 1,
+                instructions: InstructionList::from(instructions),
+                exception_table: vec![ExceptionTableEntry {
+                    covered_pc: 0.into()..=0.into(),
+                    handler_pc: 6.into(),
+                    catch_type: Some(ClassRef::new(catch_type)),
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn smell_kinds(detector: &ExceptionSmellDetector, method: &Method) -> Vec<ExceptionSmellKind> {
+        detector
+            .detect_method(method)
+            .unwrap()
+            .into_iter()
+            .map(|it| it.kind)
+            .collect()
+    }
+
+    #[test]
+    fn empty_and_broad_catch() {
+        let method = method_with("java/lang/Exception", [(7, Goto(100.into()))]);
+        let detector = ExceptionSmellDetector::default();
+        assert_eq!(
+            smell_kinds(&detector, &method),
+            vec![
+                ExceptionSmellKind::BroadCatch,
+                ExceptionSmellKind::EmptyCatch
+            ]
+        );
+        let detector = detector.with_boundary_package("com/example");
+        assert_eq!(
+            smell_kinds(&detector, &method),
+            vec![ExceptionSmellKind::EmptyCatch]
+        );
+    }
+
+    #[test]
+    fn print_stack_trace_only() {
+        let method = method_with(
+            "java/io/IOException",
+            [
+                (7, ALoad0),
+                (
+                    8,
+                    InvokeVirtual(method_ref("java/lang/Throwable", "printStackTrace", "()V")),
+                ),
+                (11, Goto(100.into())),
+            ],
+        );
+        assert_eq!(
+            smell_kinds(&ExceptionSmellDetector::default(), &method),
+            vec![ExceptionSmellKind::PrintStackTraceOnly]
+        );
+    }
+
+    #[test]
+    fn cause_discarded() {
+        let wrapping = |pass_cause: bool| {
+            let (argument, descriptor) = if pass_cause {
+                (ALoad0, "(Ljava/lang/Throwable;)V")
+            } else {
+                (
+                    Ldc(ConstantValue::String(JavaString::Utf8("failed".to_owned()))),
+                    "(Ljava/lang/String;)V",
+                )
+            };
+            method_with(
+                "java/io/IOException",
+                [
+                    (7, New(ClassRef::new("java/lang/RuntimeException"))),
+                    (10, Dup),
+                    (11, argument),
+                    (
+                        13,
+                        InvokeSpecial(method_ref(
+                            "java/lang/RuntimeException",
+                            "<init>",
+                            descriptor,
+                        )),
+                    ),
+                    (16, AThrow),
+                ],
+            )
+        };
+        let detector = ExceptionSmellDetector::default();
+        assert_eq!(
+            smell_kinds(&detector, &wrapping(false)),
+            vec![ExceptionSmellKind::CauseDiscarded {
+                throw_pc: 16.into(),
+                thrown: ClassRef::new("java/lang/RuntimeException"),
+            }]
+        );
+        assert!(smell_kinds(&detector, &wrapping(true)).is_empty());
+    }
+}
@@ -37,6 +37,30 @@ pub trait Analyzer {
         incoming_fact: Self::Fact,
     ) -> Result<Self::Fact, Self::Err>;
 
+    /// Widens `previous` with `next`, the fact updated at the same location, to ensure the
+    /// termination of the analysis on lattices of infinite height (e.g., intervals).
+    /// Called by [`Analyzer::analyze_with`] after the fact at a location has been updated
+    /// [`Solver::widening_delay`] times.
+    /// The default implementation returns `next`, i.e., no widening.
+    /// # Errors
+    /// - [`Err`] If an error occurred during widening.
+    fn widen(&self, previous: &Self::Fact, next: Self::Fact) -> Result<Self::Fact, Self::Err> {
+        let _ = previous;
+        Ok(next)
+    }
+
+    /// Narrows `current`, the fact at a location after widening, with `recomputed`, the fact
+    /// obtained by propagating the current facts once more, to recover the precision lost by
+    /// widening.
+    /// Called by [`Analyzer::analyze_with`] in each of the [`Solver::narrowing_passes`].
+    /// The default implementation returns `current`, i.e., no narrowing.
+    /// # Errors
+    /// - [`Err`] If an error occurred during narrowing.
+    fn narrow(&self, current: Self::Fact, recomputed: Self::Fact) -> Result<Self::Fact, Self::Err> {
+        let _ = recomputed;
+        Ok(current)
+    }
+
     /// Runs fixed-point analysis on a given analyzer, and returns a map of the facts (at fixed points)
     /// for each location in the control flow graph.
     /// # Errors
@@ -46,45 +70,291 @@ pub trait Analyzer {
         Self::Location: Ord + Eq,
         Self::Fact: Ord + Eq,
     {
-        let mut facts: BTreeMap<Self::Location, Self::Fact> = BTreeMap::new();
-        let mut dirty_nodes: BTreeMap<_, _> = self
-            .entry_fact()?
-            .into_iter()
-            .map(|(loc, fact)| (loc, BTreeSet::from([fact])))
-            .collect();
-        //let mut dirty_nodes = BTreeMap::from([(entry_point, BTreeSet::from([entry_fact]))]);
-
-        while let Some((location, incoming_facts)) = dirty_nodes.pop_first() {
-            let incoming_fact = {
-                // TODO: Replace it with `try_reduce` when it's stable.
-                //       See https://github.com/rust-lang/rust/issues/87053.
-                let mut merged_fact = None;
-                for incoming_fact in incoming_facts {
-                    if let Some(ref merged) = merged_fact {
-                        let new = self.merge_facts(merged, incoming_fact)?;
-                        merged_fact.replace(new);
-                    } else {
-                        merged_fact.replace(incoming_fact);
-                    }
-                }
-                merged_fact.unwrap()
-            };
+        self.analyze_with(&Solver::default()).map(|it| it.facts)
+    }
+
+    /// Runs fixed-point analysis on a given analyzer with the iteration strategy of `solver`.
+    /// # Errors
+    /// - [`Analyzer::Err`] If the analysis fails.
+    fn analyze_with(
+        &mut self,
+        solver: &Solver<Self::Location>,
+    ) -> Result<FixedPoint<Self::Location, Self::Fact>, Self::Err>
+    where
+        Self::Location: Ord + Eq,
+        Self::Fact: Ord + Eq,
+    {
+        let ranks: BTreeMap<_, _> = match &solver.order {
+            IterationOrder::Ascending => BTreeMap::new(),
+            IterationOrder::Given(order) => order
+                .iter()
+                .enumerate()
+                .map(|(rank, loc)| (loc, rank))
+                .collect(),
+        };
+        let rank_of = |loc: &Self::Location| ranks.get(loc).copied().unwrap_or(usize::MAX);
+        // The facts with the numbers of times they are updated.
+        let mut facts: BTreeMap<Self::Location, (Self::Fact, usize)> = BTreeMap::new();
+        let mut dirty_nodes: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (loc, fact) in self.entry_fact()? {
+            dirty_nodes
+                .entry((rank_of(&loc), loc))
+                .or_default()
+                .insert(fact);
+        }
+        let mut iterations = 0;
+
+        while let Some(((_, location), incoming_facts)) = dirty_nodes.pop_first() {
+            if solver
+                .max_iterations
+                .is_some_and(|max_iterations| iterations >= max_iterations)
+            {
+                return Ok(FixedPoint {
+                    facts: facts
+                        .into_iter()
+                        .map(|(loc, (fact, _))| (loc, fact))
+                        .collect(),
+                    iterations,
+                    converged: false,
+                });
+            }
+            iterations += 1;
+            let incoming_fact = merge_all(self, incoming_facts)?;
             let maybe_updated_fact = match facts.get(&location) {
-                Some(current_fact) => {
-                    let merged_fact = self.merge_facts(current_fact, incoming_fact)?;
-                    Some(merged_fact).filter(|it| it != current_fact)
+                Some((current_fact, updates)) => {
+                    let mut merged_fact = self.merge_facts(current_fact, incoming_fact)?;
+                    if solver.widening_delay.is_some_and(|delay| *updates >= delay)
+                        && solver
+                            .widening_points
+                            .as_ref()
+                            .is_none_or(|it| it.contains(&location))
+                        && merged_fact != *current_fact
+                    {
+                        merged_fact = self.widen(current_fact, merged_fact)?;
+                    }
+                    Some((merged_fact, updates + 1)).filter(|(it, _)| it != current_fact)
                 }
-                None => Some(incoming_fact),
+                None => Some((incoming_fact, 0)),
             };
 
-            if let Some(fact) = maybe_updated_fact {
+            if let Some((fact, updates)) = maybe_updated_fact {
                 for (loc, new_fact) in self.analyze_location(&location, &fact)? {
-                    dirty_nodes.entry(loc).or_default().insert(new_fact);
+                    dirty_nodes
+                        .entry((rank_of(&loc), loc))
+                        .or_default()
+                        .insert(new_fact);
                 }
-                facts.insert(location, fact);
+                facts.insert(location, (fact, updates));
+            }
+        }
+
+        let mut facts: BTreeMap<_, _> = facts
+            .into_iter()
+            .map(|(loc, (fact, _))| (loc, fact))
+            .collect();
+        for _ in 0..solver.narrowing_passes {
+            let mut recomputed: BTreeMap<Self::Location, BTreeSet<Self::Fact>> = BTreeMap::new();
+            let mut affected: Vec<_> = self.entry_fact()?.into_iter().collect();
+            for (loc, fact) in &facts {
+                affected.extend(self.analyze_location(loc, fact)?);
+            }
+            for (loc, fact) in affected {
+                recomputed.entry(loc).or_default().insert(fact);
+            }
+            let mut narrowed = BTreeMap::new();
+            for (loc, current) in facts {
+                let fact = match recomputed.remove(&loc) {
+                    Some(incoming_facts) => {
+                        let recomputed = merge_all(self, incoming_facts)?;
+                        self.narrow(current, recomputed)?
+                    }
+                    None => current,
+                };
+                narrowed.insert(loc, fact);
             }
+            facts = narrowed;
         }
 
-        Ok(facts)
+        Ok(FixedPoint {
+            facts,
+            iterations,
+            converged: true,
+        })
+    }
+}
+
+/// The order in which an [`Analyzer`] visits the locations to update.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum IterationOrder<L> {
+    /// Visits the locations in ascending order.
+    #[default]
+    Ascending,
+    /// Visits the locations in the given order, e.g., the reverse postorder of a control flow
+    /// graph (see [`ControlFlowGraph::reverse_postorder`](crate::ir::ControlFlowGraph::reverse_postorder)).
+    /// The locations not in the order are visited last in ascending order.
+    Given(Vec<L>),
+}
+
+/// The iteration strategy of [`Analyzer::analyze_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solver<L> {
+    /// The order in which the locations are visited.
+    pub order: IterationOrder<L>,
+    /// The number of times the fact at a location is updated before [`Analyzer::widen`] is
+    /// applied to it, or [`None`] if widening is disabled.
+    pub widening_delay: Option<usize>,
+    /// The locations where widening is applied, or [`None`] to apply it everywhere.
+    /// Widening only at the heads of loops retains more precision.
+    pub widening_points: Option<BTreeSet<L>>,
+    /// The number of passes applying [`Analyzer::narrow`] after reaching the fixed point.
+    pub narrowing_passes: usize,
+    /// The maximum number of locations visited before giving up, or [`None`] if unbounded.
+    pub max_iterations: Option<usize>,
+}
+
+impl<L> Default for Solver<L> {
+    /// Creates a solver visiting the locations in ascending order without widening, narrowing,
+    /// or bounds, which is the strategy of [`Analyzer::analyze`].
+    fn default() -> Self {
+        Self {
+            order: IterationOrder::Ascending,
+            widening_delay: None,
+            widening_points: None,
+            narrowing_passes: 0,
+            max_iterations: None,
+        }
+    }
+}
+
+/// The result of [`Analyzer::analyze_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedPoint<L, F> {
+    /// The facts at each location.
+    pub facts: BTreeMap<L, F>,
+    /// The number of locations visited.
+    pub iterations: usize,
+    /// Whether the fixed point is reached within [`Solver::max_iterations`].
+    /// If not, the facts are the ones computed so far and are not sound.
+    pub converged: bool,
+}
+
+/// Merges the non-empty set of facts flowing into a location.
+fn merge_all<A>(analyzer: &A, facts: BTreeSet<A::Fact>) -> Result<A::Fact, A::Err>
+where
+    A: Analyzer + ?Sized,
+{
+    // TODO: Replace it with `try_reduce` when it's stable.
+    //       See https://github.com/rust-lang/rust/issues/87053.
+    let mut merged_fact = None;
+    for incoming_fact in facts {
+        if let Some(ref merged) = merged_fact {
+            let new = analyzer.merge_facts(merged, incoming_fact)?;
+            merged_fact.replace(new);
+        } else {
+            merged_fact.replace(incoming_fact);
+        }
+    }
+    Ok(merged_fact.unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An interval analysis of `x = 0; while (x < bound) { x += 1; }` with the locations
+    /// 0 (entry), 1 (loop head), 2 (loop body), and 3 (exit).
+    struct Counter {
+        bound: i64,
+    }
+
+    type Interval = (i64, i64);
+
+    impl Analyzer for Counter {
+        type Location = u8;
+        type Fact = Interval;
+        type Err = ();
+        type AffectedLocations = Vec<(u8, Interval)>;
+
+        fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+            Ok(vec![(0, (0, 0))])
+        }
+
+        fn analyze_location(
+            &mut self,
+            location: &u8,
+            &(lo, hi): &Interval,
+        ) -> Result<Self::AffectedLocations, Self::Err> {
+            let affected = match location {
+                0 => vec![(1, (lo, hi))],
+                1 => vec![
+                    (2, (lo, hi.min(self.bound - 1))),
+                    (3, (lo.max(self.bound), hi)),
+                ],
+                2 => vec![(1, (lo.saturating_add(1), hi.saturating_add(1)))],
+                _ => Vec::new(),
+            };
+            Ok(affected
+                .into_iter()
+                .filter(|(_, (lo, hi))| lo <= hi)
+                .collect())
+        }
+
+        fn merge_facts(&self, current: &Interval, incoming: Interval) -> Result<Interval, ()> {
+            Ok((current.0.min(incoming.0), current.1.max(incoming.1)))
+        }
+
+        fn widen(&self, previous: &Interval, next: Interval) -> Result<Interval, ()> {
+            let lo = if next.0 < previous.0 {
+                i64::MIN
+            } else {
+                next.0
+            };
+            let hi = if next.1 > previous.1 {
+                i64::MAX
+            } else {
+                next.1
+            };
+            Ok((lo, hi))
+        }
+
+        fn narrow(&self, current: Interval, recomputed: Interval) -> Result<Interval, ()> {
+            let lo = if current.0 == i64::MIN {
+                recomputed.0
+            } else {
+                current.0
+            };
+            let hi = if current.1 == i64::MAX {
+                recomputed.1
+            } else {
+                current.1
+            };
+            Ok((lo, hi))
+        }
+    }
+
+    #[test]
+    fn widening_and_narrowing() {
+        let solver = Solver {
+            order: IterationOrder::Given(vec![0, 1, 2, 3]),
+            widening_delay: Some(0),
+            widening_points: Some(BTreeSet::from([1])),
+            narrowing_passes: 2,
+            max_iterations: None,
+        };
+        let result = Counter { bound: 1_000_000 }.analyze_with(&solver).unwrap();
+        assert!(result.converged);
+        assert!(result.iterations < 10);
+        assert_eq!(result.facts[&1], (0, 1_000_000));
+        assert_eq!(result.facts[&3], (1_000_000, 1_000_000));
+
+        // Without widening, the analysis takes a step per iteration of the loop.
+        let bounded = Solver {
+            max_iterations: Some(100),
+            ..Solver::default()
+        };
+        let result = Counter { bound: 1_000_000 }.analyze_with(&bounded).unwrap();
+        assert!(!result.converged);
+        assert_eq!(result.iterations, 100);
+        assert_eq!(Counter { bound: 10 }.analyze().unwrap()[&3], (10, 10));
     }
 }
@@ -0,0 +1,277 @@
+//! Heuristics for recognizing classes generated at runtime.
+//!
+//! Dynamic proxies, `CGLIB` and `ByteBuddy` subclasses, and the classes spun by
+//! `LambdaMetafactory` show up in heap dumps, agent captures, and class paths assembled from
+//! running applications.
+//! They carry little information of their own and are better presented as the types they were
+//! generated from.
+//! [`classify`] recognizes such classes from their names and hierarchies, and
+//! [`GeneratedClasses`] maps them back to their origin types so that whole-program views can
+//! collapse them.
+
+use std::collections::BTreeMap;
+
+use crate::jvm::{references::ClassRef, Class};
+
+const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+const JDK_PROXY: &str = "java/lang/reflect/Proxy";
+
+/// The marker interfaces implemented by the classes of each generator, which are not origins.
+const MARKER_INTERFACES: &[(Generator, &str)] = &[
+    (Generator::Cglib, "net/sf/cglib/proxy/Factory"),
+    (Generator::Cglib, "org/springframework/cglib/proxy/Factory"),
+    (Generator::Javassist, "javassist/util/proxy/ProxyObject"),
+    (Generator::Javassist, "javassist/util/proxy/Proxy"),
+];
+
+/// The substrings of the names of the classes created by each generator.
+const NAME_PATTERNS: &[(Generator, &str)] = &[
+    (Generator::Lambda, "$$Lambda"),
+    (Generator::Cglib, "$$EnhancerByCGLIB$$"),
+    (Generator::Cglib, "$$EnhancerBySpringCGLIB$$"),
+    (Generator::Cglib, "$$FastClassByCGLIB$$"),
+    (Generator::Cglib, "$$FastClassBySpringCGLIB$$"),
+    (Generator::Cglib, "$$SpringCGLIB$$"),
+    (Generator::ByteBuddy, "$ByteBuddy$"),
+    (Generator::ByteBuddy, "$MockitoMock$"),
+    (Generator::ByteBuddy, "$HibernateProxy$"),
+    (Generator::Javassist, "_$$_jvst"),
+];
+
+/// The mechanism that generated a class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Generator {
+    /// `java.lang.reflect.Proxy`.
+    #[display("jdk-proxy")]
+    JdkProxy,
+    /// `LambdaMetafactory`.
+    #[display("lambda")]
+    Lambda,
+    /// `CGLIB`, including the copy repackaged by Spring.
+    #[display("cglib")]
+    Cglib,
+    /// `ByteBuddy`, which also backs Mockito and Hibernate proxies.
+    #[display("bytebuddy")]
+    ByteBuddy,
+    /// Javassist.
+    #[display("javassist")]
+    Javassist,
+}
+
+/// A class that is likely generated at runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedClass {
+    /// The generated class.
+    pub class: ClassRef,
+    /// The mechanism that generated the class.
+    pub generator: Generator,
+    /// The types the class is generated from, with the primary one first.
+    /// For lambdas, these are the class containing the lambda followed by the functional
+    /// interface.
+    /// For proxies, these are the proxied class, if any, followed by the proxied interfaces.
+    pub origins: Vec<ClassRef>,
+}
+
+impl GeneratedClass {
+    /// Returns the primary type the class is generated from.
+    #[must_use]
+    pub fn primary_origin(&self) -> Option<&ClassRef> {
+        self.origins.first()
+    }
+}
+
+/// Classifies `class` as generated at runtime, or returns [`None`] if it does not look generated.
+#[must_use]
+pub fn classify(class: &Class) -> Option<GeneratedClass> {
+    let name = class.binary_name.as_str();
+    let super_class = class
+        .super_class
+        .as_ref()
+        .filter(|it| &*it.binary_name != JAVA_LANG_OBJECT);
+    let generator = NAME_PATTERNS
+        .iter()
+        .find(|(_, pattern)| name.contains(pattern))
+        .map(|(generator, _)| *generator)
+        .or_else(|| {
+            if super_class.is_some_and(|it| &*it.binary_name == JDK_PROXY) {
+                return Some(Generator::JdkProxy);
+            }
+            class.interfaces.iter().find_map(|interface| {
+                MARKER_INTERFACES
+                    .iter()
+                    .find(|(_, marker)| &*interface.binary_name == *marker)
+                    .map(|(generator, _)| *generator)
+            })
+        })?;
+    let interfaces = class.interfaces.iter().filter(|interface| {
+        !MARKER_INTERFACES
+            .iter()
+            .any(|(_, marker)| &*interface.binary_name == *marker)
+    });
+    let origins = match generator {
+        Generator::Lambda => {
+            let host = name.split_once("$$Lambda").map(|(host, _)| host);
+            host.map(ClassRef::new)
+                .into_iter()
+                .chain(interfaces.cloned())
+                .collect()
+        }
+        Generator::JdkProxy => interfaces.cloned().collect(),
+        Generator::Cglib | Generator::ByteBuddy | Generator::Javassist => {
+            super_class.into_iter().chain(interfaces).cloned().collect()
+        }
+    };
+    Some(GeneratedClass {
+        class: ClassRef::new(name),
+        generator,
+        origins,
+    })
+}
+
+/// The generated classes in a set of classes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GeneratedClasses {
+    classes: BTreeMap<ClassRef, GeneratedClass>,
+}
+
+impl GeneratedClasses {
+    /// Classifies each of `classes`.
+    pub fn detect<'a>(classes: impl IntoIterator<Item = &'a Class>) -> Self {
+        let classes = classes
+            .into_iter()
+            .filter_map(classify)
+            .map(|it| (it.class.clone(), it))
+            .collect();
+        Self { classes }
+    }
+
+    /// Gets the classification of `class` if it is generated.
+    #[must_use]
+    pub fn get(&self, class: &ClassRef) -> Option<&GeneratedClass> {
+        self.classes.get(class)
+    }
+
+    /// Checks whether `class` is generated.
+    #[must_use]
+    pub fn is_generated(&self, class: &ClassRef) -> bool {
+        self.classes.contains_key(class)
+    }
+
+    /// Returns an iterator over the generated classes, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = &GeneratedClass> {
+        self.classes.values()
+    }
+
+    /// Returns the generated classes whose primary origin is `origin`.
+    pub fn generated_from<'a>(
+        &'a self,
+        origin: &'a ClassRef,
+    ) -> impl Iterator<Item = &'a GeneratedClass> {
+        self.iter()
+            .filter(move |it| it.primary_origin() == Some(origin))
+    }
+
+    /// Maps `class` to the type it stands for in a collapsed view, following the primary
+    /// origins of generated classes (e.g., a proxy of a lambda's host class).
+    /// Classes that are not generated, and generated classes without origins, map to themselves.
+    #[must_use]
+    pub fn collapse<'a>(&'a self, mut class: &'a ClassRef) -> &'a ClassRef {
+        // Bounded by the number of generated classes to guard against cycles.
+        for _ in 0..=self.classes.len() {
+            match self.get(class).and_then(GeneratedClass::primary_origin) {
+                Some(origin) => class = origin,
+                None => break,
+            }
+        }
+        class
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_generators() {
+        let proxy = classify(&Class {
+            binary_name: "com/sun/proxy/$Proxy12".to_owned(),
+            super_class: Some(ClassRef::new(JDK_PROXY)),
+            interfaces: vec![ClassRef::new("org/mokapot/Service")],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(proxy.generator, Generator::JdkProxy);
+        assert_eq!(proxy.origins, [ClassRef::new("org/mokapot/Service")]);
+
+        let lambda = classify(&Class {
+            binary_name: "org/mokapot/Main$$Lambda/0x0000000801001234".to_owned(),
+            super_class: Some(ClassRef::new(JAVA_LANG_OBJECT)),
+            interfaces: vec![ClassRef::new("java/lang/Runnable")],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(lambda.generator, Generator::Lambda);
+        assert_eq!(
+            lambda.origins,
+            [
+                ClassRef::new("org/mokapot/Main"),
+                ClassRef::new("java/lang/Runnable")
+            ]
+        );
+
+        let cglib = classify(&Class {
+            binary_name: "org/mokapot/Service$$SpringCGLIB$$0".to_owned(),
+            super_class: Some(ClassRef::new("org/mokapot/Service")),
+            interfaces: vec![ClassRef::new("org/springframework/cglib/proxy/Factory")],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(cglib.generator, Generator::Cglib);
+        assert_eq!(cglib.origins, [ClassRef::new("org/mokapot/Service")]);
+
+        let javassist = classify(&Class {
+            binary_name: "org/mokapot/Entity_Proxy".to_owned(),
+            super_class: Some(ClassRef::new("org/mokapot/Entity")),
+            interfaces: vec![ClassRef::new("javassist/util/proxy/ProxyObject")],
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(javassist.generator, Generator::Javassist);
+
+        assert!(classify(&Class {
+            binary_name: "org/mokapot/Service".to_owned(),
+            super_class: Some(ClassRef::new(JAVA_LANG_OBJECT)),
+            interfaces: vec![ClassRef::new("java/io/Serializable")],
+            ..Default::default()
+        })
+        .is_none());
+    }
+
+    #[test]
+    fn collapse_to_origins() {
+        let classes = [
+            Class {
+                binary_name: "org/mokapot/Service".to_owned(),
+                super_class: Some(ClassRef::new(JAVA_LANG_OBJECT)),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "org/mokapot/Service$ByteBuddy$abc".to_owned(),
+                super_class: Some(ClassRef::new("org/mokapot/Service")),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "org/mokapot/Service$ByteBuddy$abc$$EnhancerByCGLIB$$1".to_owned(),
+                super_class: Some(ClassRef::new("org/mokapot/Service$ByteBuddy$abc")),
+                ..Default::default()
+            },
+        ];
+        let generated = GeneratedClasses::detect(&classes);
+        assert!(!generated.is_generated(&ClassRef::new("org/mokapot/Service")));
+        let service = ClassRef::new("org/mokapot/Service");
+        let nested = ClassRef::new("org/mokapot/Service$ByteBuddy$abc$$EnhancerByCGLIB$$1");
+        assert_eq!(generated.collapse(&nested), &service);
+        assert_eq!(generated.collapse(&service), &service);
+        assert_eq!(generated.generated_from(&service).count(), 1);
+    }
+}
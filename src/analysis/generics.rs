@@ -0,0 +1,547 @@
+//! Generics-aware type inference on Moka IR.
+//!
+//! The static types recorded in [`MokaIRMethod::types`] are erased. This module recovers the type
+//! arguments (e.g., `List<String>`) from the `Signature` attributes of the analyzed classes and
+//! propagates them through the values of a method.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::{
+    ir::{
+        expression::{ArrayOperation, Conversion, Expression, FieldAccess},
+        Identifier, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::{
+        method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, Method,
+    },
+    types::{
+        field_type::FieldType,
+        signature::{
+            ClassSignature, ClassTypeSignature, MethodSignature, TypeArgument, TypeSignature,
+        },
+    },
+};
+
+/// The generic signatures of a set of classes and their members.
+#[derive(Debug, Clone, Default)]
+pub struct GenericContext {
+    classes: HashMap<ClassRef, DeclaredClass>,
+    fields: HashMap<FieldRef, TypeSignature>,
+    methods: HashMap<MethodRef, MethodSignature>,
+}
+
+/// The type parameters and the direct supertypes of a class.
+#[derive(Debug, Clone)]
+struct DeclaredClass {
+    type_parameters: Vec<String>,
+    supertypes: Vec<ClassTypeSignature>,
+}
+
+/// The generic types of the values in a method, as inferred by [`GenericContext::infer`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GenericTypes {
+    types: BTreeMap<Identifier, TypeSignature>,
+}
+
+/// The type arguments bound to the type variables in scope.
+type Bindings = HashMap<String, TypeArgument>;
+
+impl GenericContext {
+    /// Creates a context from the signatures of `classes`.
+    /// The classes without a signature or with a malformed one are treated as non-generic.
+    #[must_use]
+    pub fn new<'a>(classes: impl IntoIterator<Item = &'a Class>) -> Self {
+        let mut context = Self::default();
+        for class in classes {
+            let declared = match class
+                .signature
+                .as_deref()
+                .and_then(|it| it.parse::<ClassSignature>().ok())
+            {
+                Some(signature) => DeclaredClass {
+                    type_parameters: signature
+                        .type_parameters
+                        .into_iter()
+                        .map(|it| it.name)
+                        .collect(),
+                    supertypes: std::iter::once(signature.super_class)
+                        .chain(signature.interfaces)
+                        .collect(),
+                },
+                None => DeclaredClass {
+                    type_parameters: Vec::new(),
+                    supertypes: class
+                        .super_class
+                        .iter()
+                        .chain(&class.interfaces)
+                        .map(|it| ClassTypeSignature {
+                            class: it.clone(),
+                            type_arguments: Vec::new(),
+                        })
+                        .collect(),
+                },
+            };
+            context.classes.insert(class.as_ref(), declared);
+            for field in &class.fields {
+                if let Some(signature) = field.signature.as_deref().and_then(|it| it.parse().ok()) {
+                    context.fields.insert(field.as_ref(), signature);
+                }
+            }
+            for method in &class.methods {
+                if let Some(signature) = method.signature.as_deref().and_then(|it| it.parse().ok())
+                {
+                    context.methods.insert(method.as_ref(), signature);
+                }
+            }
+        }
+        context
+    }
+
+    /// Returns the supertype `target` of `ty` with the type arguments derived from those of `ty`,
+    /// or [`None`] if `target` is not a known supertype of `ty`.
+    /// The supertypes of a raw type are raw.
+    #[must_use]
+    pub fn as_super(
+        &self,
+        ty: &ClassTypeSignature,
+        target: &ClassRef,
+    ) -> Option<ClassTypeSignature> {
+        let mut visited = HashSet::new();
+        self.as_super_impl(ty, target, &mut visited)
+    }
+
+    fn as_super_impl(
+        &self,
+        ty: &ClassTypeSignature,
+        target: &ClassRef,
+        visited: &mut HashSet<ClassRef>,
+    ) -> Option<ClassTypeSignature> {
+        if &ty.class == target {
+            return Some(ty.clone());
+        }
+        if !visited.insert(ty.class.clone()) {
+            return None;
+        }
+        let declared = self.classes.get(&ty.class)?;
+        let bindings = bind(&declared.type_parameters, &ty.type_arguments);
+        declared.supertypes.iter().find_map(|supertype| {
+            let supertype = if bindings.is_empty() {
+                ClassTypeSignature {
+                    class: supertype.class.clone(),
+                    type_arguments: Vec::new(),
+                }
+            } else {
+                substitute_class(supertype, &bindings).unwrap_or_else(|| ClassTypeSignature {
+                    class: supertype.class.clone(),
+                    type_arguments: Vec::new(),
+                })
+            };
+            self.as_super_impl(&supertype, target, visited)
+        })
+    }
+
+    /// Infers the generic types of the values in `ir` generated from `method`.
+    /// The values whose type arguments cannot be determined get their erased types.
+    #[must_use]
+    pub fn infer(&self, method: &Method, ir: &MokaIRMethod) -> GenericTypes {
+        let mut types = BTreeMap::new();
+        if !method.access_flags.contains(method::AccessFlags::STATIC) {
+            let type_arguments = self
+                .classes
+                .get(&method.owner)
+                .map(|it| {
+                    it.type_parameters
+                        .iter()
+                        .map(|name| TypeArgument::Exact(TypeSignature::TypeVariable(name.clone())))
+                        .collect()
+                })
+                .unwrap_or_default();
+            types.insert(
+                Identifier::This,
+                TypeSignature::Class(ClassTypeSignature {
+                    class: method.owner.clone(),
+                    type_arguments,
+                }),
+            );
+        }
+        let parameters = method
+            .signature
+            .as_deref()
+            .and_then(|it| it.parse::<MethodSignature>().ok())
+            .map(|it| it.parameters)
+            // The signature omits synthetic parameters (e.g., the outer instance of an inner class).
+            .filter(|it| it.len() == method.descriptor.parameters_types.len())
+            .unwrap_or_else(|| {
+                method
+                    .descriptor
+                    .parameters_types
+                    .iter()
+                    .cloned()
+                    .map(TypeSignature::from)
+                    .collect()
+            });
+        for (index, param) in (0..).zip(parameters) {
+            types.insert(Identifier::Arg(index), param);
+        }
+
+        // Bounded in case the types of the values in a loop keep alternating.
+        for _ in 0..=ir.instructions.iter().count() {
+            let mut changed = false;
+            for (_, insn) in ir.instructions.iter() {
+                let Some(value) = insn.def() else {
+                    continue;
+                };
+                let inferred = match insn {
+                    MokaInstruction::Definition { expr, .. } => self.type_of_expr(expr, ir, &types),
+                    MokaInstruction::Phi { incoming, .. } => join(incoming.values(), ir, &types),
+                    _ => None,
+                };
+                if let Some(ty) = inferred {
+                    if types.get(&value.into()) != Some(&ty) {
+                        types.insert(value.into(), ty);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        for (id, ty) in &ir.types {
+            types.entry(*id).or_insert_with(|| ty.clone().into());
+        }
+        GenericTypes { types }
+    }
+
+    fn type_of_expr(
+        &self,
+        expr: &Expression,
+        ir: &MokaIRMethod,
+        types: &BTreeMap<Identifier, TypeSignature>,
+    ) -> Option<TypeSignature> {
+        let of = |operand: &Operand| join(operand.iter(), ir, types);
+        match expr {
+            Expression::Call { method, this, args } => {
+                let (declaring, signature) = self.lookup(&method.owner, |owner| {
+                    self.methods.get(&MethodRef {
+                        owner: owner.clone(),
+                        ..method.clone()
+                    })
+                })?;
+                let mut bindings = match this.as_ref().and_then(of) {
+                    Some(TypeSignature::Class(receiver)) => self.bindings_of(&receiver, &declaring),
+                    _ => Bindings::new(),
+                };
+                let method_variables: Vec<_> = signature
+                    .type_parameters
+                    .iter()
+                    .map(|it| it.name.as_str())
+                    .collect();
+                bindings.retain(|name, _| !method_variables.contains(&name.as_str()));
+                let mut conflicts = HashSet::new();
+                for (param, arg) in signature.parameters.iter().zip(args) {
+                    if let Some(arg) = of(arg) {
+                        unify(
+                            param,
+                            &arg,
+                            &method_variables,
+                            &mut bindings,
+                            &mut conflicts,
+                        );
+                    }
+                }
+                bindings.retain(|name, _| !conflicts.contains(name));
+                substitute(signature.return_type.as_ref()?, &bindings)
+            }
+            Expression::Field(FieldAccess::ReadInstance { object_ref, field }) => {
+                let (declaring, signature) = self.lookup(&field.owner, |owner| {
+                    self.fields.get(&FieldRef {
+                        owner: owner.clone(),
+                        ..field.clone()
+                    })
+                })?;
+                let bindings = match of(object_ref) {
+                    Some(TypeSignature::Class(object)) => self.bindings_of(&object, &declaring),
+                    _ => Bindings::new(),
+                };
+                substitute(signature, &bindings)
+            }
+            Expression::Field(FieldAccess::ReadStatic { field }) => {
+                // The type variables of a class are not in scope of its static fields.
+                substitute(self.fields.get(field)?, &Bindings::new())
+            }
+            Expression::Array(ArrayOperation::Read { array_ref, .. }) => match of(array_ref)? {
+                TypeSignature::Array(element_type) => Some(*element_type),
+                _ => None,
+            },
+            Expression::Conversion(Conversion::CheckCast(operand, target)) => {
+                let source = of(operand)?;
+                match (source, target) {
+                    (source, target) if source.erasure() == *target => Some(source),
+                    (TypeSignature::Class(source), FieldType::Object(target)) => {
+                        self.as_super(&source, target).map(TypeSignature::Class)
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Finds the class declaring a member, starting from `owner` and visiting its supertypes.
+    fn lookup<'s, T>(
+        &'s self,
+        owner: &ClassRef,
+        member: impl Fn(&ClassRef) -> Option<&'s T>,
+    ) -> Option<(ClassRef, &'s T)> {
+        let mut visited = HashSet::new();
+        let mut pending = vec![owner.clone()];
+        while let Some(class) = pending.pop() {
+            if let Some(found) = member(&class) {
+                return Some((class, found));
+            }
+            if !visited.insert(class.clone()) {
+                continue;
+            }
+            if let Some(declared) = self.classes.get(&class) {
+                pending.extend(declared.supertypes.iter().rev().map(|it| it.class.clone()));
+            }
+        }
+        None
+    }
+
+    /// Returns the type arguments bound to the type variables of `declaring` when accessed via
+    /// a value of type `ty`.
+    fn bindings_of(&self, ty: &ClassTypeSignature, declaring: &ClassRef) -> Bindings {
+        let Some(declared) = self.classes.get(declaring) else {
+            return Bindings::new();
+        };
+        self.as_super(ty, declaring)
+            .map(|it| bind(&declared.type_parameters, &it.type_arguments))
+            .unwrap_or_default()
+    }
+}
+
+impl GenericTypes {
+    /// Returns the generic type of `id`.
+    #[must_use]
+    pub fn get(&self, id: &Identifier) -> Option<&TypeSignature> {
+        self.types.get(id)
+    }
+
+    /// Returns an iterator over the values and their generic types.
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &TypeSignature)> {
+        self.types.iter()
+    }
+}
+
+/// Binds `type_parameters` to `type_arguments`, or nothing if the type is raw.
+fn bind(type_parameters: &[String], type_arguments: &[TypeArgument]) -> Bindings {
+    if type_parameters.len() != type_arguments.len() {
+        return Bindings::new();
+    }
+    type_parameters
+        .iter()
+        .cloned()
+        .zip(type_arguments.iter().cloned())
+        .collect()
+}
+
+/// Joins the types of `ids` if they agree, ignoring the `null` constants.
+fn join<'a>(
+    ids: impl IntoIterator<Item = &'a Identifier>,
+    ir: &MokaIRMethod,
+    types: &BTreeMap<Identifier, TypeSignature>,
+) -> Option<TypeSignature> {
+    let mut joined = None;
+    for id in ids.into_iter().filter(|it| !ir.is_null_constant(it)) {
+        let ty = types.get(id)?;
+        match joined {
+            None => joined = Some(ty),
+            Some(it) if it == ty => {}
+            Some(_) => return None,
+        }
+    }
+    joined.cloned()
+}
+
+/// Replaces the type variables in `ty` with their bindings, or returns [`None`] if a type
+/// variable is unbound or bound to a wildcard outside a type argument.
+fn substitute(ty: &TypeSignature, bindings: &Bindings) -> Option<TypeSignature> {
+    match ty {
+        TypeSignature::Base(_) => Some(ty.clone()),
+        TypeSignature::Class(class) => substitute_class(class, bindings).map(TypeSignature::Class),
+        TypeSignature::Array(element) => {
+            substitute(element, bindings).map(|it| TypeSignature::Array(Box::new(it)))
+        }
+        TypeSignature::TypeVariable(name) => match bindings.get(name)? {
+            // Reading a `? extends T` yields a `T`.
+            TypeArgument::Exact(it) | TypeArgument::Extends(it) => Some(it.clone()),
+            TypeArgument::Super(_) | TypeArgument::Any => None,
+        },
+    }
+}
+
+fn substitute_class(class: &ClassTypeSignature, bindings: &Bindings) -> Option<ClassTypeSignature> {
+    let type_arguments = class
+        .type_arguments
+        .iter()
+        .map(|arg| match arg {
+            TypeArgument::Exact(TypeSignature::TypeVariable(name)) => bindings.get(name).cloned(),
+            TypeArgument::Exact(it) => substitute(it, bindings).map(TypeArgument::Exact),
+            TypeArgument::Extends(it) => substitute(it, bindings).map(TypeArgument::Extends),
+            TypeArgument::Super(it) => substitute(it, bindings).map(TypeArgument::Super),
+            TypeArgument::Any => Some(TypeArgument::Any),
+        })
+        .collect::<Option<_>>()?;
+    Some(ClassTypeSignature {
+        class: class.class.clone(),
+        type_arguments,
+    })
+}
+
+/// Binds the type `variables` in `param` by matching it against the type of the argument.
+/// The variables bound to different types are recorded in `conflicts`.
+fn unify(
+    param: &TypeSignature,
+    arg: &TypeSignature,
+    variables: &[&str],
+    bindings: &mut Bindings,
+    conflicts: &mut HashSet<String>,
+) {
+    match (param, arg) {
+        (TypeSignature::TypeVariable(name), _) if variables.contains(&name.as_str()) => {
+            let arg = TypeArgument::Exact(arg.clone());
+            match bindings.get(name) {
+                Some(bound) if *bound != arg => {
+                    conflicts.insert(name.clone());
+                }
+                Some(_) => {}
+                None => {
+                    bindings.insert(name.clone(), arg);
+                }
+            }
+        }
+        (TypeSignature::Array(param), TypeSignature::Array(arg)) => {
+            unify(param, arg, variables, bindings, conflicts);
+        }
+        (TypeSignature::Class(param), TypeSignature::Class(arg))
+            if param.class == arg.class
+                && param.type_arguments.len() == arg.type_arguments.len() =>
+        {
+            for pair in param.type_arguments.iter().zip(&arg.type_arguments) {
+                if let (
+                    TypeArgument::Exact(param) | TypeArgument::Extends(param),
+                    TypeArgument::Exact(arg),
+                ) = pair
+                {
+                    unify(param, arg, variables, bindings, conflicts);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{LocalValue, MokaIRMethodExt},
+        jvm::interop::jasmin,
+    };
+
+    fn class(src: &str, signature: &str, method_signatures: &[&str]) -> Class {
+        let mut class = jasmin::read(src).unwrap();
+        class.signature = Some(signature.to_owned());
+        for (method, signature) in class.methods.iter_mut().zip(method_signatures) {
+            method.signature = Some((*signature).to_owned());
+        }
+        class
+    }
+
+    #[test]
+    fn infer_through_supertypes_and_method_type_parameters() {
+        let list = class(
+            "
+            .interface public abstract java/util/List
+            .super java/lang/Object
+            .method public abstract get(I)Ljava/lang/Object;
+            .end method
+            ",
+            "<E:Ljava/lang/Object;>Ljava/lang/Object;",
+            &["(I)TE;"],
+        );
+        let array_list = class(
+            "
+            .class public java/util/ArrayList
+            .super java/util/AbstractList
+            .implements java/util/List
+            ",
+            "<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;",
+            &[],
+        );
+        let collections = class(
+            "
+            .class public java/util/Collections
+            .super java/lang/Object
+            .method public static singletonList(Ljava/lang/Object;)Ljava/util/List;
+                .limit stack 1
+                .limit locals 1
+                aconst_null
+                areturn
+            .end method
+            ",
+            "Ljava/lang/Object;",
+            &["<T:Ljava/lang/Object;>(TT;)Ljava/util/List<TT;>;"],
+        );
+        let mut test = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static run(Ljava/util/ArrayList;)Ljava/util/List;
+                .limit stack 2
+                .limit locals 1
+                aload_0
+                iconst_0
+                invokeinterface java/util/List/get(I)Ljava/lang/Object; 2
+                invokestatic java/util/Collections/singletonList(Ljava/lang/Object;)Ljava/util/List;
+                areturn
+            .end method
+            ",
+        )
+        .unwrap();
+        test.methods[0].signature = Some(
+            "(Ljava/util/ArrayList<Ljava/lang/String;>;)Ljava/util/List<Ljava/lang/String;>;"
+                .to_owned(),
+        );
+        let context = GenericContext::new([&list, &array_list, &collections, &test]);
+        let method = &test.methods[0];
+        let ir = method.brew().unwrap();
+        let types = context.infer(method, &ir);
+
+        let string: TypeSignature = "Ljava/lang/String;".parse().unwrap();
+        assert_eq!(
+            types.get(&Identifier::Arg(0)),
+            Some(&"Ljava/util/ArrayList<Ljava/lang/String;>;".parse().unwrap())
+        );
+        assert_eq!(
+            types.get(&Identifier::Local(LocalValue::new(2))),
+            Some(&string)
+        );
+        assert_eq!(
+            types
+                .get(&Identifier::Local(LocalValue::new(7)))
+                .map(ToString::to_string),
+            Some("java/util/List<java/lang/String>".to_owned())
+        );
+        assert_eq!(
+            types.get(&Identifier::Local(LocalValue::new(1))),
+            Some(&TypeSignature::Base(
+                crate::types::field_type::PrimitiveType::Int
+            ))
+        );
+    }
+}
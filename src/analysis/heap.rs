@@ -0,0 +1,422 @@
+//! An abstract model of the heap shared by heap analyses, such as points-to, escape, and taint
+//! analyses.
+//!
+//! The objects are abstracted by their allocation sites (see [`HeapObject`]).
+//! A [`Heap`] records the objects each static field may point to, and the objects each field
+//! of an object may point to.
+//! How the fields of an object are told apart is decided by a [`FieldSensitivity`] policy.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    ir::{
+        expression::{ArrayOperation, Expression},
+        MokaIRMethod, MokaInstruction,
+    },
+    jvm::{
+        code::ProgramCounter,
+        references::{ClassRef, FieldRef, MethodRef},
+        ConstantValue,
+    },
+    types::field_type::FieldType,
+};
+
+/// A location in a method where objects are allocated.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+#[display("{method}@{pc}")]
+pub struct AllocationSite {
+    /// The method containing the allocation.
+    pub method: MethodRef,
+    /// The location of the allocation.
+    pub pc: ProgramCounter,
+}
+
+/// An abstract object in the heap.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum HeapObject {
+    /// All the objects allocated at a site in an analyzed method.
+    #[display("new {object_type} at {site}")]
+    Allocated {
+        /// The site of the allocation.
+        site: AllocationSite,
+        /// The type of the objects.
+        object_type: FieldType,
+    },
+    /// All the objects of a type created outside the analyzed methods (e.g., by native code or
+    /// by the callers of an entry point).
+    #[display("external {_0}")]
+    External(FieldType),
+}
+
+impl HeapObject {
+    /// Returns the type of the object.
+    #[must_use]
+    pub const fn object_type(&self) -> &FieldType {
+        match self {
+            Self::Allocated { object_type, .. } | Self::External(object_type) => object_type,
+        }
+    }
+
+    /// Returns the objects allocated in `method`, i.e., the ones created by `new`, the array
+    /// creation instructions, and the string constants.
+    #[must_use]
+    pub fn allocated_in(method: &MokaIRMethod) -> Vec<Self> {
+        let method_ref = MethodRef::new(
+            method.owner.clone(),
+            &method.name,
+            method.descriptor.clone(),
+        );
+        method
+            .instructions
+            .iter()
+            .filter_map(|(pc, insn)| {
+                let MokaInstruction::Definition { expr, .. } = insn else {
+                    return None;
+                };
+                let object_type = match expr {
+                    Expression::New(class) => FieldType::Object(class.clone()),
+                    Expression::Const(ConstantValue::String(_)) => {
+                        FieldType::Object(ClassRef::new("java/lang/String"))
+                    }
+                    Expression::Array(ArrayOperation::New { element_type, .. }) => {
+                        element_type.clone().into_array_type()
+                    }
+                    // The type of `multianewarray` is the type of the array itself.
+                    Expression::Array(ArrayOperation::NewMultiDim { element_type, .. }) => {
+                        element_type.clone()
+                    }
+                    _ => return None,
+                };
+                let site = AllocationSite {
+                    method: method_ref.clone(),
+                    pc: *pc,
+                };
+                Some(Self::Allocated { site, object_type })
+            })
+            .collect()
+    }
+}
+
+/// The abstraction of a field of an object in the heap.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum FieldKey {
+    /// A single field.
+    Field(FieldRef),
+    /// The elements of an array.
+    #[display("[]")]
+    ArrayElement,
+    /// All the fields of an object.
+    #[display("*")]
+    AnyField,
+}
+
+/// A policy of how the fields of an object are told apart.
+pub trait FieldSensitivity {
+    /// Returns the abstraction of `field`.
+    fn key_of(&self, field: &FieldRef) -> FieldKey;
+}
+
+/// Distinguishes every field of an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FieldSensitive;
+
+impl FieldSensitivity for FieldSensitive {
+    fn key_of(&self, field: &FieldRef) -> FieldKey {
+        FieldKey::Field(field.clone())
+    }
+}
+
+/// Merges all the fields of an object into one, which is less precise but keeps the heap
+/// smaller.
+/// The elements of arrays are still kept apart from the fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FieldInsensitive;
+
+impl FieldSensitivity for FieldInsensitive {
+    fn key_of(&self, _field: &FieldRef) -> FieldKey {
+        FieldKey::AnyField
+    }
+}
+
+/// A snapshot of the abstract heap.
+///
+/// The heap only grows; each of the methods adding an edge returns whether the heap changed
+/// so that it can be used in a fixed-point iteration.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Heap<P = FieldSensitive> {
+    policy: P,
+    statics: BTreeMap<FieldRef, BTreeSet<HeapObject>>,
+    fields: BTreeMap<(HeapObject, FieldKey), BTreeSet<HeapObject>>,
+}
+
+impl Heap {
+    /// Creates an empty field-sensitive heap.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<P: FieldSensitivity> Heap<P> {
+    /// Creates an empty heap using `policy` to abstract the fields.
+    #[must_use]
+    pub fn with_policy(policy: P) -> Self {
+        Self {
+            policy,
+            statics: BTreeMap::new(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the policy used to abstract the fields.
+    pub const fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Records that the static field `field` may point to `target`.
+    /// Returns `true` if the heap changed.
+    pub fn add_static(&mut self, field: FieldRef, target: HeapObject) -> bool {
+        self.statics.entry(field).or_default().insert(target)
+    }
+
+    /// Records that `field` of `object` may point to `target`.
+    /// Returns `true` if the heap changed.
+    pub fn add_field(&mut self, object: HeapObject, field: &FieldRef, target: HeapObject) -> bool {
+        let key = self.policy.key_of(field);
+        self.fields.entry((object, key)).or_default().insert(target)
+    }
+
+    /// Records that an element of the array `array` may point to `target`.
+    /// Returns `true` if the heap changed.
+    pub fn add_element(&mut self, array: HeapObject, target: HeapObject) -> bool {
+        self.fields
+            .entry((array, FieldKey::ArrayElement))
+            .or_default()
+            .insert(target)
+    }
+
+    /// Returns the objects the static field `field` may point to.
+    pub fn static_targets(&self, field: &FieldRef) -> impl Iterator<Item = &HeapObject> {
+        self.statics.get(field).into_iter().flatten()
+    }
+
+    /// Returns the objects `field` of `object` may point to.
+    pub fn field_targets(
+        &self,
+        object: &HeapObject,
+        field: &FieldRef,
+    ) -> impl Iterator<Item = &HeapObject> {
+        self.fields
+            .get(&(object.clone(), self.policy.key_of(field)))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Returns the objects the elements of the array `array` may point to.
+    pub fn element_targets(&self, array: &HeapObject) -> impl Iterator<Item = &HeapObject> {
+        self.fields
+            .get(&(array.clone(), FieldKey::ArrayElement))
+            .into_iter()
+            .flatten()
+    }
+
+    /// Creates an iterator over the points-to edges of the static fields.
+    pub fn statics(&self) -> impl Iterator<Item = (&FieldRef, &HeapObject)> {
+        self.statics
+            .iter()
+            .flat_map(|(field, targets)| targets.iter().map(move |it| (field, it)))
+    }
+
+    /// Creates an iterator over the points-to edges of the fields of objects.
+    pub fn fields(&self) -> impl Iterator<Item = (&HeapObject, &FieldKey, &HeapObject)> {
+        self.fields
+            .iter()
+            .flat_map(|((object, key), targets)| targets.iter().map(move |it| (object, key, it)))
+    }
+
+    /// Returns the objects reachable from `roots` through the fields, including the roots.
+    #[must_use]
+    pub fn reachable_from<'a, I>(&self, roots: I) -> BTreeSet<HeapObject>
+    where
+        I: IntoIterator<Item = &'a HeapObject>,
+    {
+        let mut successors: BTreeMap<&HeapObject, Vec<&HeapObject>> = BTreeMap::new();
+        for (object, _, target) in self.fields() {
+            successors.entry(object).or_default().push(target);
+        }
+        let mut reachable = BTreeSet::new();
+        let mut worklist: Vec<_> = roots.into_iter().collect();
+        while let Some(object) = worklist.pop() {
+            if reachable.insert(object.clone()) {
+                worklist.extend(successors.get(object).into_iter().flatten());
+            }
+        }
+        reachable
+    }
+
+    /// Returns the objects reachable from the static fields, which are shared globally.
+    #[must_use]
+    pub fn reachable_from_statics(&self) -> BTreeSet<HeapObject> {
+        self.reachable_from(self.statics.values().flatten())
+    }
+
+    /// Adds all the points-to edges in `other` to this heap.
+    /// Returns `true` if the heap changed.
+    pub fn join(&mut self, other: &Self) -> bool {
+        let mut changed = false;
+        for (field, target) in other.statics() {
+            changed |= self.add_static(field.clone(), target.clone());
+        }
+        for (object, key, target) in other.fields() {
+            changed |= self
+                .fields
+                .entry((object.clone(), key.clone()))
+                .or_default()
+                .insert(target.clone());
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, MethodBody},
+            method, JavaString, Method,
+        },
+        types::field_type::PrimitiveType,
+    };
+
+    fn object(pc: u16, class: &str) -> HeapObject {
+        HeapObject::Allocated {
+            site: AllocationSite {
+                method: MethodRef::new(
+                    ClassRef::new("org/mokapot/Test"),
+                    "test",
+                    "()V".parse().unwrap(),
+                ),
+                pc: pc.into(),
+            },
+            object_type: FieldType::Object(ClassRef::new(class)),
+        }
+    }
+
+    fn field(name: &str) -> FieldRef {
+        FieldRef::new(
+            ClassRef::new("org/mokapot/Node"),
+            name,
+            FieldType::Object(ClassRef::new("java/lang/Object")),
+        )
+    }
+
+    #[test]
+    fn field_sensitive() {
+        let mut heap = Heap::new();
+        let (node, left, right) = (object(0, "Node"), object(4, "A"), object(8, "B"));
+        assert!(heap.add_field(node.clone(), &field("left"), left.clone()));
+        assert!(heap.add_field(node.clone(), &field("right"), right.clone()));
+        assert!(!heap.add_field(node.clone(), &field("left"), left.clone()));
+        assert_eq!(
+            heap.field_targets(&node, &field("left"))
+                .collect::<Vec<_>>(),
+            vec![&left]
+        );
+        assert_eq!(heap.element_targets(&node).count(), 0);
+    }
+
+    #[test]
+    fn field_insensitive() {
+        let mut heap = Heap::with_policy(FieldInsensitive);
+        let (node, left, right) = (object(0, "Node"), object(4, "A"), object(8, "B"));
+        heap.add_field(node.clone(), &field("left"), left.clone());
+        heap.add_field(node.clone(), &field("right"), right.clone());
+        heap.add_element(node.clone(), node.clone());
+        assert_eq!(
+            heap.field_targets(&node, &field("left"))
+                .collect::<Vec<_>>(),
+            vec![&left, &right]
+        );
+        assert_eq!(heap.element_targets(&node).collect::<Vec<_>>(), vec![&node]);
+    }
+
+    #[test]
+    fn reachability_and_join() {
+        let (root, child, grandchild, other) = (
+            object(0, "Root"),
+            object(4, "Child"),
+            object(8, "Grandchild"),
+            object(12, "Other"),
+        );
+        let mut heap = Heap::new();
+        heap.add_static(field("INSTANCE"), root.clone());
+        heap.add_field(root.clone(), &field("child"), child.clone());
+        let mut other_heap = Heap::new();
+        other_heap.add_element(child.clone(), grandchild.clone());
+        other_heap.add_static(field("OTHER"), other.clone());
+
+        assert!(heap.join(&other_heap));
+        assert!(!heap.join(&other_heap));
+        assert_eq!(
+            heap.reachable_from([&child]),
+            BTreeSet::from([child.clone(), grandchild.clone()])
+        );
+        assert_eq!(
+            heap.reachable_from_statics(),
+            BTreeSet::from([root, child, grandchild, other])
+        );
+    }
+
+    #[test]
+    fn allocation_sites() {
+        use Instruction::{ANewArray, IConst1, Ldc, New, NewArray, Pop, Return};
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: "test".to_owned(),
+            descriptor: "()V".parse().unwrap(),
+            owner: ClassRef::new("org/mokapot/Test"),
+            body: Some(MethodBody {
+                max_stack: 1,
+                max_locals: 0,
+                instructions: [
+                    (0, New(ClassRef::new("Node"))),
+                    (3, Pop),
+                    (
+                        4,
+                        Ldc(ConstantValue::String(JavaString::Utf8("x".to_owned()))),
+                    ),
+                    (6, Pop),
+                    (7, IConst1),
+                    (8, NewArray(PrimitiveType::Int)),
+                    (10, Pop),
+                    (11, IConst1),
+                    (12, ANewArray(ClassRef::new("Node"))),
+                    (15, Pop),
+                    (16, Return),
+                ]
+                .into_iter()
+                .map(|(pc, insn)| (pc.into(), insn))
+                .collect::<BTreeMap<_, _>>()
+                .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let objects = HeapObject::allocated_in(&method.brew().unwrap());
+        assert_eq!(objects[0], object(0, "Node"));
+        assert_eq!(
+            objects
+                .iter()
+                .map(HeapObject::object_type)
+                .collect::<Vec<_>>(),
+            vec![
+                &FieldType::Object(ClassRef::new("Node")),
+                &FieldType::Object(ClassRef::new("java/lang/String")),
+                &FieldType::Base(PrimitiveType::Int).into_array_type(),
+                &FieldType::Object(ClassRef::new("Node")).into_array_type(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,628 @@
+//! Synthesizing the call edges implied by dependency injection containers.
+//!
+//! Containers such as Spring and Guice instantiate the beans and wire their dependencies
+//! reflectively, so the constructors, factory methods, and injected methods have no callers in
+//! the bytecode.
+//! A [`ContainerModel`] recognizes the beans and the injection points declared with
+//! annotations, from which the [`InjectionAnalyzer`] synthesizes the [`SyntheticCall`]s to be
+//! added to a call graph.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    jvm::{
+        class, method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Annotation, Class, Method,
+    },
+    types::{field_type::FieldType, method_descriptor::ReturnType},
+};
+
+/// A bean managed by a container.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BeanDefinition {
+    /// The type of the bean.
+    pub bean_type: ClassRef,
+    /// The constructor or the factory method creating the bean.
+    pub factory: MethodRef,
+}
+
+/// The location where a container injects dependencies.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InjectionSite {
+    /// The dependencies are passed to a constructor.
+    Constructor(MethodRef),
+    /// The dependencies are passed to a method (e.g., a setter) after construction.
+    Method(MethodRef),
+    /// The dependencies are passed to a factory method (e.g., a `@Bean` method).
+    Factory(MethodRef),
+    /// The dependency is assigned to a field.
+    Field(FieldRef),
+}
+
+/// A location where a container injects dependencies.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InjectionPoint {
+    /// The location of the injection.
+    pub site: InjectionSite,
+    /// The types of the dependencies being injected.
+    pub dependencies: Vec<ClassRef>,
+}
+
+/// A model of a dependency injection container.
+pub trait ContainerModel {
+    /// Returns the beans declared by `class`.
+    fn beans(&self, class: &Class) -> Vec<BeanDefinition>;
+
+    /// Returns the injection points declared by `class`.
+    fn injection_points(&self, class: &Class) -> Vec<InjectionPoint>;
+}
+
+/// The kind of a [`SyntheticCall`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SyntheticCallKind {
+    /// The container creates a bean.
+    Instantiation,
+    /// The container calls a method to inject dependencies.
+    Injection,
+    /// The bean created by the callee is injected at the caller.
+    Provision {
+        /// The type of the dependency.
+        dependency: ClassRef,
+    },
+}
+
+/// A call edge made by a container.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SyntheticCall {
+    /// The method in which the call happens, or [`None`] if the container makes the call
+    /// directly (e.g., for field injections).
+    pub caller: Option<MethodRef>,
+    /// The method being called.
+    pub callee: MethodRef,
+    /// The kind of the call.
+    pub kind: SyntheticCallKind,
+}
+
+/// The beans and the wiring recognized in a set of classes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContainerGraph {
+    /// The beans declared.
+    pub beans: BTreeSet<BeanDefinition>,
+    /// The injection points declared.
+    pub injection_points: BTreeSet<InjectionPoint>,
+    /// The dependencies that are not provided by any bean.
+    pub unsatisfied: BTreeMap<ClassRef, BTreeSet<InjectionSite>>,
+    /// The synthesized call edges.
+    pub calls: BTreeSet<SyntheticCall>,
+}
+
+/// An analyzer building [`ContainerGraph`]s.
+pub struct InjectionAnalyzer {
+    models: Vec<Box<dyn ContainerModel>>,
+}
+
+impl Default for InjectionAnalyzer {
+    /// Creates an analyzer with the [`SpringModel`] and the [`GuiceModel`].
+    fn default() -> Self {
+        Self::new()
+            .with_model(SpringModel::default())
+            .with_model(GuiceModel::default())
+    }
+}
+
+impl std::fmt::Debug for InjectionAnalyzer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InjectionAnalyzer")
+            .field("models", &self.models.len())
+            .finish()
+    }
+}
+
+impl InjectionAnalyzer {
+    /// Creates an analyzer without any container model.
+    #[must_use]
+    pub fn new() -> Self {
+        Self { models: Vec::new() }
+    }
+
+    /// Adds a container model.
+    #[must_use]
+    pub fn with_model(mut self, model: impl ContainerModel + 'static) -> Self {
+        self.models.push(Box::new(model));
+        self
+    }
+
+    /// Recognizes the beans and the injection points in `classes`, and synthesizes the calls
+    /// made by the containers.
+    /// A dependency is provided by the beans whose types are the type of the dependency or its
+    /// subtypes among `classes`.
+    pub fn analyze<'a>(&self, classes: impl IntoIterator<Item = &'a Class>) -> ContainerGraph {
+        let mut graph = ContainerGraph::default();
+        let mut super_types: BTreeMap<ClassRef, Vec<ClassRef>> = BTreeMap::new();
+        for class in classes {
+            super_types.insert(
+                class.as_ref(),
+                class
+                    .super_class
+                    .iter()
+                    .chain(&class.interfaces)
+                    .cloned()
+                    .collect(),
+            );
+            for model in &self.models {
+                graph.beans.extend(model.beans(class));
+                graph.injection_points.extend(model.injection_points(class));
+            }
+        }
+
+        let mut providers: BTreeMap<ClassRef, BTreeSet<&BeanDefinition>> = BTreeMap::new();
+        for bean in &graph.beans {
+            let mut worklist = vec![bean.bean_type.clone()];
+            let mut visited = BTreeSet::new();
+            while let Some(current) = worklist.pop() {
+                if !visited.insert(current.clone()) {
+                    continue;
+                }
+                worklist.extend(super_types.get(&current).into_iter().flatten().cloned());
+                providers.entry(current).or_default().insert(bean);
+            }
+            graph.calls.insert(SyntheticCall {
+                caller: None,
+                callee: bean.factory.clone(),
+                kind: SyntheticCallKind::Instantiation,
+            });
+        }
+        for point in &graph.injection_points {
+            let caller = match &point.site {
+                InjectionSite::Method(method) => {
+                    graph.calls.insert(SyntheticCall {
+                        caller: None,
+                        callee: method.clone(),
+                        kind: SyntheticCallKind::Injection,
+                    });
+                    Some(method)
+                }
+                InjectionSite::Constructor(method) | InjectionSite::Factory(method) => Some(method),
+                InjectionSite::Field(_) => None,
+            };
+            for dependency in &point.dependencies {
+                let Some(beans) = providers.get(dependency) else {
+                    graph
+                        .unsatisfied
+                        .entry(dependency.clone())
+                        .or_default()
+                        .insert(point.site.clone());
+                    continue;
+                };
+                for bean in beans {
+                    graph.calls.insert(SyntheticCall {
+                        caller: caller.cloned(),
+                        callee: bean.factory.clone(),
+                        kind: SyntheticCallKind::Provision {
+                            dependency: dependency.clone(),
+                        },
+                    });
+                }
+            }
+        }
+        graph
+    }
+}
+
+/// A model of the Spring container.
+/// Meta-annotations and XML configurations are not recognized.
+#[derive(Debug, Clone)]
+pub struct SpringModel {
+    /// The annotations marking the classes as beans (e.g., `@Component`).
+    pub component_annotations: BTreeSet<ClassRef>,
+    /// The annotations marking the methods as bean factories (e.g., `@Bean`).
+    pub factory_annotations: BTreeSet<ClassRef>,
+    /// The annotations marking the injection points (e.g., `@Autowired`).
+    pub inject_annotations: BTreeSet<ClassRef>,
+}
+
+impl Default for SpringModel {
+    fn default() -> Self {
+        let stereotypes = [
+            "org/springframework/stereotype/Component",
+            "org/springframework/stereotype/Controller",
+            "org/springframework/stereotype/Repository",
+            "org/springframework/stereotype/Service",
+            "org/springframework/web/bind/annotation/RestController",
+            "org/springframework/context/annotation/Configuration",
+        ];
+        Self {
+            component_annotations: stereotypes.into_iter().map(ClassRef::new).collect(),
+            factory_annotations: BTreeSet::from([ClassRef::new(
+                "org/springframework/context/annotation/Bean",
+            )]),
+            inject_annotations: [
+                "org/springframework/beans/factory/annotation/Autowired",
+                "javax/inject/Inject",
+                "jakarta/inject/Inject",
+            ]
+            .into_iter()
+            .map(ClassRef::new)
+            .collect(),
+        }
+    }
+}
+
+impl ContainerModel for SpringModel {
+    fn beans(&self, class: &Class) -> Vec<BeanDefinition> {
+        let mut beans: Vec<_> = class
+            .methods
+            .iter()
+            .filter(|it| has_annotation(&it.runtime_visible_annotations, &self.factory_annotations))
+            .filter_map(|it| {
+                Some(BeanDefinition {
+                    bean_type: returned_class(it)?,
+                    factory: it.as_ref(),
+                })
+            })
+            .collect();
+        if is_concrete(class)
+            && has_annotation(
+                &class.runtime_visible_annotations,
+                &self.component_annotations,
+            )
+        {
+            beans.extend(
+                injected_constructor(class, &self.inject_annotations, true).map(|it| {
+                    BeanDefinition {
+                        bean_type: class.as_ref(),
+                        factory: it.as_ref(),
+                    }
+                }),
+            );
+        }
+        beans
+    }
+
+    fn injection_points(&self, class: &Class) -> Vec<InjectionPoint> {
+        let mut points = members_injection_points(class, &self.inject_annotations);
+        points.extend(
+            class
+                .methods
+                .iter()
+                .filter(|it| {
+                    has_annotation(&it.runtime_visible_annotations, &self.factory_annotations)
+                })
+                .map(|it| InjectionPoint {
+                    site: InjectionSite::Factory(it.as_ref()),
+                    dependencies: parameter_classes(it),
+                }),
+        );
+        if is_concrete(class)
+            && has_annotation(
+                &class.runtime_visible_annotations,
+                &self.component_annotations,
+            )
+        {
+            points.extend(
+                injected_constructor(class, &self.inject_annotations, true).map(|it| {
+                    InjectionPoint {
+                        site: InjectionSite::Constructor(it.as_ref()),
+                        dependencies: parameter_classes(it),
+                    }
+                }),
+            );
+        }
+        points.retain(|it| !it.dependencies.is_empty());
+        points
+    }
+}
+
+/// A model of the Guice injector.
+/// The bindings made in `Module.configure` are not recognized, so interfaces are only provided
+/// by `@Provides` methods.
+#[derive(Debug, Clone)]
+pub struct GuiceModel {
+    /// The annotations marking the methods as providers (e.g., `@Provides`).
+    pub provider_annotations: BTreeSet<ClassRef>,
+    /// The annotations marking the injection points (e.g., `@Inject`).
+    pub inject_annotations: BTreeSet<ClassRef>,
+}
+
+impl Default for GuiceModel {
+    fn default() -> Self {
+        Self {
+            provider_annotations: BTreeSet::from([ClassRef::new("com/google/inject/Provides")]),
+            inject_annotations: [
+                "com/google/inject/Inject",
+                "javax/inject/Inject",
+                "jakarta/inject/Inject",
+            ]
+            .into_iter()
+            .map(ClassRef::new)
+            .collect(),
+        }
+    }
+}
+
+impl ContainerModel for GuiceModel {
+    /// Returns the `@Provides` methods, and the `@Inject` constructors of concrete classes as
+    /// just-in-time bindings.
+    fn beans(&self, class: &Class) -> Vec<BeanDefinition> {
+        let mut beans: Vec<_> = class
+            .methods
+            .iter()
+            .filter(|it| {
+                has_annotation(&it.runtime_visible_annotations, &self.provider_annotations)
+            })
+            .filter_map(|it| {
+                Some(BeanDefinition {
+                    bean_type: returned_class(it)?,
+                    factory: it.as_ref(),
+                })
+            })
+            .collect();
+        if is_concrete(class) {
+            beans.extend(
+                injected_constructor(class, &self.inject_annotations, false).map(|it| {
+                    BeanDefinition {
+                        bean_type: class.as_ref(),
+                        factory: it.as_ref(),
+                    }
+                }),
+            );
+        }
+        beans
+    }
+
+    fn injection_points(&self, class: &Class) -> Vec<InjectionPoint> {
+        let mut points = members_injection_points(class, &self.inject_annotations);
+        points.extend(
+            class
+                .methods
+                .iter()
+                .filter(|it| {
+                    has_annotation(&it.runtime_visible_annotations, &self.provider_annotations)
+                })
+                .map(|it| InjectionPoint {
+                    site: InjectionSite::Factory(it.as_ref()),
+                    dependencies: parameter_classes(it),
+                }),
+        );
+        points.extend(
+            injected_constructor(class, &self.inject_annotations, false).map(|it| InjectionPoint {
+                site: InjectionSite::Constructor(it.as_ref()),
+                dependencies: parameter_classes(it),
+            }),
+        );
+        points.retain(|it| !it.dependencies.is_empty());
+        points
+    }
+}
+
+fn has_annotation(annotations: &[Annotation], expected: &BTreeSet<ClassRef>) -> bool {
+    annotations.iter().any(|it| match &it.annotation_type {
+        FieldType::Object(annotation_type) => expected.contains(annotation_type),
+        _ => false,
+    })
+}
+
+fn is_concrete(class: &Class) -> bool {
+    !class.access_flags.intersects(
+        class::AccessFlags::INTERFACE
+            | class::AccessFlags::ABSTRACT
+            | class::AccessFlags::ANNOTATION
+            | class::AccessFlags::ENUM,
+    )
+}
+
+fn returned_class(method: &Method) -> Option<ClassRef> {
+    match &method.descriptor.return_type {
+        ReturnType::Some(FieldType::Object(class_ref)) => Some(class_ref.clone()),
+        _ => None,
+    }
+}
+
+fn parameter_classes(method: &Method) -> Vec<ClassRef> {
+    method
+        .descriptor
+        .parameters_types
+        .iter()
+        .filter_map(|it| match it {
+            FieldType::Object(class_ref) => Some(class_ref.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Finds the constructor used by the container, which is the annotated one, or the only
+/// constructor if `implicit` is set.
+fn injected_constructor<'a>(
+    class: &'a Class,
+    inject_annotations: &BTreeSet<ClassRef>,
+    implicit: bool,
+) -> Option<&'a Method> {
+    let mut constructors = class.methods.iter().filter(|it| it.is_constructor());
+    let annotated = constructors
+        .clone()
+        .find(|it| has_annotation(&it.runtime_visible_annotations, inject_annotations));
+    match (annotated, constructors.next(), constructors.next()) {
+        (Some(constructor), _, _) => Some(constructor),
+        (None, Some(constructor), None) if implicit => Some(constructor),
+        _ => None,
+    }
+}
+
+/// Collects the annotated non-static fields and methods.
+fn members_injection_points(
+    class: &Class,
+    inject_annotations: &BTreeSet<ClassRef>,
+) -> Vec<InjectionPoint> {
+    let fields = class
+        .fields
+        .iter()
+        .filter(|it| has_annotation(&it.runtime_visible_annotations, inject_annotations))
+        .filter_map(|it| match &it.field_type {
+            FieldType::Object(class_ref) => Some(InjectionPoint {
+                site: InjectionSite::Field(it.as_ref()),
+                dependencies: vec![class_ref.clone()],
+            }),
+            _ => None,
+        });
+    let methods = class
+        .methods
+        .iter()
+        .filter(|it| {
+            !it.is_constructor()
+                && !it.access_flags.contains(method::AccessFlags::STATIC)
+                && has_annotation(&it.runtime_visible_annotations, inject_annotations)
+        })
+        .map(|it| InjectionPoint {
+            site: InjectionSite::Method(it.as_ref()),
+            dependencies: parameter_classes(it),
+        });
+    fields.chain(methods).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::Field;
+
+    use super::*;
+    use crate::tests::{annotation, method_ref};
+
+    fn method(owner: &str, name: &str, descriptor: &str, annotations: &[&str]) -> Method {
+        Method {
+            name: name.to_owned(),
+            owner: ClassRef::new(owner),
+            descriptor: descriptor.parse().unwrap(),
+            runtime_visible_annotations: annotations.iter().map(|it| annotation(it)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn spring_wiring() {
+        const COMPONENT: &str = "org/springframework/stereotype/Service";
+        const CONFIGURATION: &str = "org/springframework/context/annotation/Configuration";
+        const BEAN: &str = "org/springframework/context/annotation/Bean";
+        const AUTOWIRED: &str = "org/springframework/beans/factory/annotation/Autowired";
+        let classes = [
+            Class {
+                binary_name: "Repo".to_owned(),
+                access_flags: class::AccessFlags::INTERFACE | class::AccessFlags::ABSTRACT,
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Config".to_owned(),
+                runtime_visible_annotations: vec![annotation(CONFIGURATION)],
+                methods: vec![
+                    method("Config", "<init>", "()V", &[]),
+                    method("Config", "repo", "()LJdbcRepo;", &[BEAN]),
+                ],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "JdbcRepo".to_owned(),
+                interfaces: vec![ClassRef::new("Repo")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Service".to_owned(),
+                runtime_visible_annotations: vec![annotation(COMPONENT)],
+                methods: vec![
+                    method("Service", "<init>", "(LRepo;)V", &[]),
+                    method("Service", "setClock", "(LClock;)V", &[AUTOWIRED]),
+                ],
+                fields: vec![Field {
+                    name: "config".to_owned(),
+                    owner: ClassRef::new("Service"),
+                    field_type: "LConfig;".parse().unwrap(),
+                    runtime_visible_annotations: vec![annotation(AUTOWIRED)],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ];
+        let graph = InjectionAnalyzer::default().analyze(&classes);
+
+        let service_init = method_ref("Service", "<init>", "(LRepo;)V");
+        let repo_factory = method_ref("Config", "repo", "()LJdbcRepo;");
+        let config_init = method_ref("Config", "<init>", "()V");
+        let set_clock = method_ref("Service", "setClock", "(LClock;)V");
+        let expected = BTreeSet::from([
+            SyntheticCall {
+                caller: None,
+                callee: config_init.clone(),
+                kind: SyntheticCallKind::Instantiation,
+            },
+            SyntheticCall {
+                caller: None,
+                callee: repo_factory.clone(),
+                kind: SyntheticCallKind::Instantiation,
+            },
+            SyntheticCall {
+                caller: None,
+                callee: service_init.clone(),
+                kind: SyntheticCallKind::Instantiation,
+            },
+            SyntheticCall {
+                caller: None,
+                callee: set_clock.clone(),
+                kind: SyntheticCallKind::Injection,
+            },
+            SyntheticCall {
+                caller: Some(service_init),
+                callee: repo_factory,
+                kind: SyntheticCallKind::Provision {
+                    dependency: ClassRef::new("Repo"),
+                },
+            },
+            SyntheticCall {
+                caller: None,
+                callee: config_init,
+                kind: SyntheticCallKind::Provision {
+                    dependency: ClassRef::new("Config"),
+                },
+            },
+        ]);
+        assert_eq!(graph.calls, expected);
+        assert_eq!(
+            graph.unsatisfied,
+            BTreeMap::from([(
+                ClassRef::new("Clock"),
+                BTreeSet::from([InjectionSite::Method(set_clock)])
+            )])
+        );
+    }
+
+    #[test]
+    fn guice_just_in_time_bindings() {
+        const INJECT: &str = "com/google/inject/Inject";
+        let classes = [
+            Class {
+                binary_name: "Engine".to_owned(),
+                methods: vec![method("Engine", "<init>", "()V", &[INJECT])],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Car".to_owned(),
+                methods: vec![
+                    method("Car", "<init>", "()V", &[]),
+                    method("Car", "<init>", "(LEngine;)V", &[INJECT]),
+                ],
+                ..Default::default()
+            },
+        ];
+        let graph = InjectionAnalyzer::new()
+            .with_model(GuiceModel::default())
+            .analyze(&classes);
+        let car_init = method_ref("Car", "<init>", "(LEngine;)V");
+        let engine_init = method_ref("Engine", "<init>", "()V");
+        assert_eq!(graph.beans.len(), 2);
+        assert!(graph.calls.contains(&SyntheticCall {
+            caller: Some(car_init),
+            callee: engine_init,
+            kind: SyntheticCallKind::Provision {
+                dependency: ClassRef::new("Engine"),
+            },
+        }));
+        assert!(graph.unsatisfied.is_empty());
+    }
+}
@@ -0,0 +1,409 @@
+//! Identification of known libraries embedded in a set of classes.
+//!
+//! Fat and shaded JAR files often bundle open-source libraries, possibly with their packages
+//! relocated.
+//! The fingerprints computed by [`class_fingerprint`] ignore the packages and the names of the
+//! referenced classes, so a relocated copy of a class has the same fingerprint as the original.
+//! A [`SignatureDatabase`] records the fingerprints of the classes of known library versions and
+//! identifies the libraries whose classes appear in a set of classes, producing a
+//! [`LibraryReport`] listing the embedded components in the manner of an SBOM.
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    jvm::{Class, Method},
+    types::field_type::FieldType,
+};
+
+use super::artifacts::{Digest, Hasher};
+
+/// An error when parsing a signature database.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Malformed signature database at line {line}: {content}")]
+pub struct ParseError {
+    /// The line number (starting from 1).
+    pub line: usize,
+    /// The content of the line.
+    pub content: String,
+}
+
+/// Computes the fingerprint of `method` from its name, the shape of its descriptor, and the
+/// opcodes of its instructions.
+#[must_use]
+pub fn method_fingerprint(method: &Method) -> Digest {
+    let mut hasher = Hasher::default();
+    hasher.update(method.name.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(shape(&method.descriptor.descriptor()).as_bytes());
+    if let Some(body) = &method.body {
+        hasher.update(&[0]);
+        for (_, insn) in &body.instructions {
+            hasher.update(&[insn.opcode()]);
+        }
+    }
+    hasher.finish()
+}
+
+/// Computes the fingerprint of `class` from its simple name and the fingerprints of its fields
+/// and methods.
+/// The fingerprint does not depend on the package of the class or the order of its members.
+#[must_use]
+pub fn class_fingerprint(class: &Class) -> Digest {
+    let simple_name = class
+        .binary_name
+        .rsplit_once('/')
+        .map_or(class.binary_name.as_str(), |(_, it)| it);
+    let fields: BTreeSet<_> = class
+        .fields
+        .iter()
+        .map(|it| format!("{}:{}", it.name, field_shape(&it.field_type)))
+        .collect();
+    let methods: BTreeSet<_> = class.methods.iter().map(method_fingerprint).collect();
+    let mut hasher = Hasher::default();
+    hasher.update(simple_name.as_bytes());
+    for field in fields {
+        hasher.update(&[0]);
+        hasher.update(field.as_bytes());
+    }
+    for method in methods {
+        hasher.update(method.as_bytes());
+    }
+    hasher.finish()
+}
+
+/// Replaces the class names in a descriptor with `L;`.
+fn shape(descriptor: &str) -> String {
+    let mut shape = String::with_capacity(descriptor.len());
+    let mut in_class_name = false;
+    for ch in descriptor.chars() {
+        match ch {
+            'L' if !in_class_name => {
+                in_class_name = true;
+                shape.push('L');
+            }
+            ';' if in_class_name => {
+                in_class_name = false;
+                shape.push(';');
+            }
+            _ if in_class_name => {}
+            ch => shape.push(ch),
+        }
+    }
+    shape
+}
+
+fn field_shape(field_type: &FieldType) -> String {
+    shape(&field_type.descriptor())
+}
+
+/// The fingerprints of the classes of a version of a library.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibrarySignature {
+    /// The name of the library (e.g., `com.google.guava:guava`).
+    pub name: String,
+    /// The version of the library.
+    pub version: String,
+    /// The fingerprints of the classes in the library.
+    pub classes: BTreeSet<Digest>,
+}
+
+impl LibrarySignature {
+    /// Creates the signature of the library `name` at `version` consisting of `classes`.
+    pub fn from_classes<'a>(
+        name: impl Into<String>,
+        version: impl Into<String>,
+        classes: impl IntoIterator<Item = &'a Class>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            classes: classes.into_iter().map(class_fingerprint).collect(),
+        }
+    }
+}
+
+/// A library identified in a set of classes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Component {
+    /// The name of the library.
+    pub name: String,
+    /// The version of the library.
+    pub version: String,
+    /// The number of classes of the library found.
+    pub matched_classes: usize,
+    /// The number of classes in the library.
+    pub total_classes: usize,
+    /// The packages containing the classes found, which differ from the ones of the library if
+    /// it is relocated.
+    pub packages: BTreeSet<String>,
+}
+
+impl Component {
+    /// Returns the fraction of the classes of the library that are found, ranging from 0 to 1.
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "The numbers of classes are far below 2^52"
+    )]
+    pub fn coverage(&self) -> f64 {
+        if self.total_classes == 0 {
+            return 0.0;
+        }
+        self.matched_classes as f64 / self.total_classes as f64
+    }
+}
+
+/// The libraries identified in a set of classes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LibraryReport {
+    /// The libraries identified, ordered by name. Only the best matching version of each library
+    /// is reported, or several versions if they match equally well.
+    pub components: Vec<Component>,
+}
+
+impl Display for LibraryReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for component in &self.components {
+            write!(
+                f,
+                "{}@{} {}/{} classes ({:.1}%)",
+                component.name,
+                component.version,
+                component.matched_classes,
+                component.total_classes,
+                component.coverage() * 100.0
+            )?;
+            for package in &component.packages {
+                write!(f, " {package}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A database of library signatures. See the [module-level documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignatureDatabase {
+    libraries: Vec<LibrarySignature>,
+}
+
+impl SignatureDatabase {
+    /// Creates an empty database.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the signature of a library version.
+    pub fn add(&mut self, library: LibrarySignature) {
+        self.libraries.push(library);
+    }
+
+    /// Returns the signatures in the database.
+    #[must_use]
+    pub fn libraries(&self) -> &[LibrarySignature] {
+        &self.libraries
+    }
+
+    /// Identifies the libraries in `classes` of which at least the fraction `min_coverage` of the
+    /// classes are found.
+    #[must_use]
+    pub fn identify(&self, classes: &[Class], min_coverage: f64) -> LibraryReport {
+        let mut fingerprints: HashMap<Digest, Vec<&Class>> = HashMap::new();
+        for class in classes {
+            fingerprints
+                .entry(class_fingerprint(class))
+                .or_default()
+                .push(class);
+        }
+        let mut best: BTreeMap<&str, Vec<Component>> = BTreeMap::new();
+        for library in &self.libraries {
+            let found: Vec<_> = library
+                .classes
+                .iter()
+                .filter_map(|it| fingerprints.get(it))
+                .flatten()
+                .collect();
+            let packages = found
+                .iter()
+                .filter_map(|it| it.binary_name.rsplit_once('/'))
+                .map(|(package, _)| package.to_owned())
+                .collect();
+            let matched_classes = library
+                .classes
+                .iter()
+                .filter(|it| fingerprints.contains_key(it))
+                .count();
+            let component = Component {
+                name: library.name.clone(),
+                version: library.version.clone(),
+                matched_classes,
+                total_classes: library.classes.len(),
+                packages,
+            };
+            if matched_classes == 0 || component.coverage() < min_coverage {
+                continue;
+            }
+            let candidates = best.entry(&library.name).or_default();
+            match candidates.first().map(Component::coverage) {
+                Some(coverage) if coverage > component.coverage() => {}
+                Some(coverage) if coverage < component.coverage() => {
+                    *candidates = vec![component];
+                }
+                _ => candidates.push(component),
+            }
+        }
+        LibraryReport {
+            components: best.into_values().flatten().collect(),
+        }
+    }
+
+    /// Parses a database in the text format written by its [`Display`] implementation, where
+    /// each library starts with a line `library <name> <version>` followed by the indented
+    /// fingerprints of its classes.
+    ///
+    /// # Errors
+    /// See [`ParseError`].
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut libraries: Vec<LibrarySignature> = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let error = || ParseError {
+                line: idx + 1,
+                content: line.to_owned(),
+            };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                let library = libraries.last_mut().ok_or_else(error)?;
+                library
+                    .classes
+                    .insert(trimmed.parse().map_err(|_| error())?);
+            } else {
+                let [name, version] = trimmed
+                    .strip_prefix("library ")
+                    .map(|it| it.split_whitespace().collect::<Vec<_>>())
+                    .and_then(|it| it.try_into().ok())
+                    .ok_or_else(error)?;
+                libraries.push(LibrarySignature {
+                    name: name.to_owned(),
+                    version: version.to_owned(),
+                    classes: BTreeSet::new(),
+                });
+            }
+        }
+        Ok(Self { libraries })
+    }
+}
+
+impl Display for SignatureDatabase {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for library in &self.libraries {
+            writeln!(f, "library {} {}", library.name, library.version)?;
+            for class in &library.classes {
+                writeln!(f, "  {class}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    fn class(name: &str, body: &str) -> Class {
+        jasmin::read(&format!(
+            "
+            .class public {name}
+            .super java/lang/Object
+            .method public static run(L{name};)I
+                .limit stack 1
+                .limit locals 1
+                {body}
+            .end method
+            "
+        ))
+        .unwrap()
+    }
+
+    fn library(package: &str) -> Vec<Class> {
+        vec![
+            class(&format!("{package}/Alpha"), "iconst_1\nireturn"),
+            class(&format!("{package}/Beta"), "iconst_2\nireturn"),
+            class(&format!("{package}/Gamma"), "iconst_3\nireturn"),
+        ]
+    }
+
+    #[test]
+    fn identify_relocated_library() {
+        let original = library("com/example/lib");
+        let mut database = SignatureDatabase::new();
+        database.add(LibrarySignature::from_classes(
+            "com.example:lib",
+            "1.0",
+            &original,
+        ));
+        database.add(LibrarySignature::from_classes(
+            "com.example:lib",
+            "0.9",
+            &original[..1],
+        ));
+        database.add(LibrarySignature::from_classes(
+            "com.example:other",
+            "2.0",
+            &[class("com/example/other/Delta", "iconst_4\nireturn")],
+        ));
+
+        let mut shaded = library("org/app/shaded/lib");
+        shaded.pop();
+        shaded.push(class("org/app/Main", "iconst_0\nireturn"));
+        let report = database.identify(&shaded, 0.5);
+        let [component] = report.components.as_slice() else {
+            panic!("Unexpected components: {report:?}");
+        };
+        // Version 0.9 is fully covered while only two thirds of version 1.0 are found.
+        assert_eq!(component.version, "0.9");
+        assert_eq!(
+            component.packages,
+            BTreeSet::from(["org/app/shaded/lib".to_owned()])
+        );
+        assert!(report
+            .to_string()
+            .starts_with("com.example:lib@0.9 1/1 classes"));
+
+        let both = database.identify(&library("org/app/shaded/lib"), 0.5);
+        assert_eq!(
+            both.components
+                .iter()
+                .map(|it| it.version.as_str())
+                .collect::<Vec<_>>(),
+            ["1.0", "0.9"]
+        );
+    }
+
+    #[test]
+    fn database_round_trip() {
+        let mut database = SignatureDatabase::new();
+        database.add(LibrarySignature::from_classes(
+            "com.example:lib",
+            "1.0",
+            &library("com/example/lib"),
+        ));
+        let text = database.to_string();
+        assert_eq!(SignatureDatabase::parse(&text).unwrap(), database);
+        assert_eq!(
+            SignatureDatabase::parse("  0123"),
+            Err(ParseError {
+                line: 1,
+                content: "  0123".to_owned()
+            })
+        );
+    }
+}
@@ -0,0 +1,621 @@
+//! Recovery of the local variables of methods compiled without debug information.
+//!
+//! When a method does not have a `LocalVariableTable` attribute, e.g., in release builds, only
+//! the indices of the local variables are known.
+//! [`LocalVariableRecovery`] reconstructs the variables by grouping the stores into a local
+//! variable slot with the loads they reach, so that a slot reused for different purposes
+//! yields different variables.
+//! Each variable is given a stable synthetic name and a type inferred from the bytecode.
+//! The entries in the local variable table, if any, take precedence over the recovered ones.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::Range,
+};
+
+use crate::{
+    ir::{MokaIRBrewingError, MokaIRMethodExt},
+    jvm::{
+        code::{
+            Frame, FrameInferenceError, Instruction, LocalVariableId, LocalVariableTable,
+            LocalVariableTableEntry, MethodBody, ProgramCounter, VerificationType, WideInstruction,
+        },
+        method,
+        references::ClassRef,
+        Method,
+    },
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+use super::fixed_point::Analyzer;
+
+/// An error that occurs when recovering local variables.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The method does not have a body.
+    #[error("The method does not have a body")]
+    NoMethodBody,
+    /// The types of the local variables cannot be inferred.
+    #[error("Failed to infer the frames: {0}")]
+    FrameInference(#[from] FrameInferenceError),
+    /// The control flow of the method cannot be constructed.
+    #[error("Failed to construct the control flow graph: {0}")]
+    Brewing(#[from] MokaIRBrewingError),
+}
+
+/// A local variable recovered by [`LocalVariableRecovery`].
+#[derive(Debug, Clone)]
+pub struct RecoveredVariable {
+    /// The slot and the range of the variable.
+    pub id: LocalVariableId,
+    /// The name, type, and signature of the variable.
+    pub entry: LocalVariableTableEntry,
+    /// Whether the name is synthetic, i.e., not taken from the local variable table.
+    pub is_synthetic: bool,
+}
+
+/// Recovers the local variables of a method.
+#[derive(Debug, Clone, Copy)]
+pub struct LocalVariableRecovery<'a> {
+    method: &'a Method,
+}
+
+impl<'a> LocalVariableRecovery<'a> {
+    /// Creates a recovery for `method`.
+    #[must_use]
+    pub const fn new(method: &'a Method) -> Self {
+        Self { method }
+    }
+
+    /// Recovers the local variables ordered by slot and start of the range.
+    /// The receiver of an instance method is named `this`, the parameters are named `arg0`,
+    /// `arg1`, and so on, and the other variables are named `var0`, `var1`, and so on in the
+    /// order of their first definition.
+    /// A variable that is described by the local variable table takes its entry instead.
+    /// The range of a recovered variable spans from its first definition to its last use.
+    /// Values that are stored but never loaded do not form variables.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn recover(self) -> Result<Vec<RecoveredVariable>, Error> {
+        let body = self.method.body.as_ref().ok_or(Error::NoMethodBody)?;
+        let frames = body.infer_frames(self.method)?;
+        // The range of the parameters covers the last instruction.
+        let end_of_code = body
+            .instructions
+            .last_instruction()
+            .and_then(|(pc, _)| (*pc + 1i32).ok())
+            .unwrap_or(ProgramCounter::ZERO);
+        let mut variables: Vec<_> = self
+            .def_use_webs(body)?
+            .into_iter()
+            .map(|(slot, definitions, uses)| {
+                let range = if definitions.contains(&Definition::Entry) {
+                    ProgramCounter::ZERO..end_of_code
+                } else {
+                    let start = definitions
+                        .iter()
+                        .filter_map(|it| match it {
+                            Definition::Entry => None,
+                            Definition::At(pc) => body.instructions.next_pc_of(pc),
+                        })
+                        .min()
+                        .unwrap_or(ProgramCounter::ZERO);
+                    let end = uses
+                        .iter()
+                        .max()
+                        .and_then(|it| body.instructions.next_pc_of(it))
+                        .unwrap_or(end_of_code);
+                    start..end.max(start)
+                };
+                let var_type = self.type_of(slot, &definitions, &uses, body, &frames);
+                (slot, range, uses, var_type, definitions)
+            })
+            .collect();
+        variables.sort_by_key(|(slot, range, ..)| (range.start, *slot));
+
+        let mut result = Vec::new();
+        let mut counter = 0;
+        let parameter_names: BTreeMap<_, _> = self.parameter_slots().collect();
+        for (slot, range, uses, var_type, definitions) in variables {
+            let debug_entry = body
+                .local_variable_table
+                .as_ref()
+                .and_then(|it| find_entry(it, slot, &range, &uses));
+            let recovered = if let Some((id, entry)) = debug_entry {
+                if result.iter().any(|it: &RecoveredVariable| it.id == *id) {
+                    continue;
+                }
+                RecoveredVariable {
+                    id: id.clone(),
+                    entry: entry.clone(),
+                    is_synthetic: false,
+                }
+            } else {
+                let name = match parameter_names.get(&slot) {
+                    Some(name) if definitions.contains(&Definition::Entry) => name.clone(),
+                    _ => {
+                        let name = format!("var{counter}");
+                        counter += 1;
+                        name
+                    }
+                };
+                RecoveredVariable {
+                    id: LocalVariableId {
+                        effective_range: range,
+                        index: slot,
+                    },
+                    entry: LocalVariableTableEntry {
+                        name: Some(name),
+                        var_type,
+                        signature: None,
+                    },
+                    is_synthetic: true,
+                }
+            };
+            result.push(recovered);
+        }
+        if let Some(table) = &body.local_variable_table {
+            append_unmatched_entries(table, &mut result);
+        }
+        result.sort_by_key(|it| (it.id.index, it.id.effective_range.start));
+        Ok(result)
+    }
+
+    /// Groups the definitions of each slot with the uses they reach.
+    fn def_use_webs(self, body: &MethodBody) -> Result<Vec<Web>, Error> {
+        let ir = self.method.brew()?;
+        let successors: BTreeMap<_, Vec<_>> =
+            ir.control_flow_graph
+                .edges()
+                .fold(BTreeMap::new(), |mut acc, (src, dst, _)| {
+                    acc.entry(src).or_default().push(dst);
+                    acc
+                });
+        let mut reaching_definitions = ReachingDefinitions {
+            body,
+            entry_slots: self.parameter_slots().map(|(slot, _)| slot).collect(),
+            successors,
+        };
+        let definitions = reaching_definitions.analyze()?;
+
+        let mut webs = Webs::default();
+        for (pc, insn) in &body.instructions {
+            let Some((slot, access)) = local_access(insn) else {
+                continue;
+            };
+            if access == Access::Store {
+                continue;
+            }
+            let reaching = definitions
+                .get(pc)
+                .and_then(|it| it.get(&slot))
+                .into_iter()
+                .flatten();
+            webs.add_use(slot, *pc, reaching);
+        }
+        for (slot, _) in self.parameter_slots() {
+            webs.ensure(slot, Definition::Entry);
+        }
+
+        Ok(webs.into_groups())
+    }
+
+    /// Gets the slots of the receiver and the parameters together with their names.
+    fn parameter_slots(self) -> impl Iterator<Item = (u16, String)> + 'a {
+        let is_static = self
+            .method
+            .access_flags
+            .contains(method::AccessFlags::STATIC);
+        let receiver = (!is_static).then(|| (0, "this".to_owned()));
+        let mut slot = u16::from(!is_static);
+        let parameters = self
+            .method
+            .descriptor
+            .parameters_types
+            .iter()
+            .enumerate()
+            .map(move |(idx, parameter_type)| {
+                let current = slot;
+                slot += if is_wide(parameter_type) { 2 } else { 1 };
+                (current, format!("arg{idx}"))
+            });
+        receiver.into_iter().chain(parameters)
+    }
+
+    /// Infers the type of a variable from the types of the values stored into it and the types
+    /// of the slot where it is loaded.
+    fn type_of(
+        self,
+        slot: u16,
+        definitions: &BTreeSet<Definition>,
+        uses: &BTreeSet<ProgramCounter>,
+        body: &MethodBody,
+        frames: &BTreeMap<ProgramCounter, Frame>,
+    ) -> Option<FieldType> {
+        if definitions.contains(&Definition::Entry) {
+            let parameter_type = self.parameter_type(slot);
+            if parameter_type.is_some() {
+                return parameter_type;
+            }
+        }
+        let stored = definitions.iter().filter_map(|it| match it {
+            Definition::Entry => None,
+            Definition::At(pc) => match body.instruction_at(*pc) {
+                Some(Instruction::IInc(..) | Instruction::Wide(WideInstruction::IInc(..))) => {
+                    Some(VerificationType::IntegerVariable)
+                }
+                _ => frames.get(pc).and_then(|it| it.stack.last().cloned()),
+            },
+        });
+        let loaded = uses
+            .iter()
+            .filter_map(|pc| frames.get(pc)?.locals.get(usize::from(slot)).cloned());
+        let mut types = stored.chain(loaded);
+        let first = types.next()?;
+        let merged = types.try_fold(first, |acc, it| match (&acc, &it) {
+            _ if acc == it => Some(acc),
+            (VerificationType::NullVariable, VerificationType::ObjectVariable(_)) => Some(it),
+            (VerificationType::ObjectVariable(_), VerificationType::NullVariable) => Some(acc),
+            (VerificationType::ObjectVariable(_), VerificationType::ObjectVariable(_)) => Some(
+                VerificationType::ObjectVariable(ClassRef::new("java/lang/Object")),
+            ),
+            _ => None,
+        })?;
+        field_type_of(&merged)
+    }
+
+    fn parameter_type(self, slot: u16) -> Option<FieldType> {
+        let is_static = self
+            .method
+            .access_flags
+            .contains(method::AccessFlags::STATIC);
+        if !is_static && slot == 0 {
+            return Some(FieldType::Object(self.method.owner.clone()));
+        }
+        let mut current = u16::from(!is_static);
+        for parameter_type in self.method.descriptor.parameters_types.iter() {
+            if current == slot {
+                return Some(parameter_type.clone());
+            }
+            current += if is_wide(parameter_type) { 2 } else { 1 };
+        }
+        None
+    }
+}
+
+/// Appends the entries of the local variable table that do not match any recovered variable,
+/// e.g., variables that are never loaded.
+fn append_unmatched_entries(table: &LocalVariableTable, variables: &mut Vec<RecoveredVariable>) {
+    for (id, entry) in table.iter() {
+        let is_matched = variables.iter().any(|it| !it.is_synthetic && it.id == *id);
+        if !is_matched {
+            variables.push(RecoveredVariable {
+                id: id.clone(),
+                entry: entry.clone(),
+                is_synthetic: false,
+            });
+        }
+    }
+}
+
+/// Finds the entry in the local variable table that describes the variable in `slot` loaded at
+/// `uses`.
+fn find_entry<'t>(
+    table: &'t LocalVariableTable,
+    slot: u16,
+    range: &Range<ProgramCounter>,
+    uses: &BTreeSet<ProgramCounter>,
+) -> Option<(&'t LocalVariableId, &'t LocalVariableTableEntry)> {
+    table
+        .iter()
+        .filter(|(id, _)| id.index == slot)
+        .find(|(id, _)| {
+            if uses.is_empty() {
+                id.effective_range.start <= range.start && range.start < id.effective_range.end
+            } else {
+                uses.iter().any(|it| id.effective_range.contains(it))
+            }
+        })
+}
+
+fn is_wide(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Base(PrimitiveType::Long | PrimitiveType::Double)
+    )
+}
+
+fn field_type_of(verification_type: &VerificationType) -> Option<FieldType> {
+    match verification_type {
+        VerificationType::IntegerVariable => Some(FieldType::Base(PrimitiveType::Int)),
+        VerificationType::FloatVariable => Some(FieldType::Base(PrimitiveType::Float)),
+        VerificationType::LongVariable => Some(FieldType::Base(PrimitiveType::Long)),
+        VerificationType::DoubleVariable => Some(FieldType::Base(PrimitiveType::Double)),
+        VerificationType::NullVariable => {
+            Some(FieldType::Object(ClassRef::new("java/lang/Object")))
+        }
+        VerificationType::ObjectVariable(class) if class.binary_name.starts_with('[') => {
+            class.binary_name.parse().ok()
+        }
+        VerificationType::ObjectVariable(class) => Some(FieldType::Object(class.clone())),
+        VerificationType::TopVariable
+        | VerificationType::UninitializedThisVariable
+        | VerificationType::UninitializedVariable { .. } => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Load,
+    Store,
+    Increment,
+}
+
+/// Gets the slot accessed by `insn` and how it is accessed.
+fn local_access(insn: &Instruction) -> Option<(u16, Access)> {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let access = match insn {
+        ILoad(idx) | LLoad(idx) | FLoad(idx) | DLoad(idx) | ALoad(idx) | Ret(idx) => {
+            (u16::from(*idx), Access::Load)
+        }
+        IStore(idx) | LStore(idx) | FStore(idx) | DStore(idx) | AStore(idx) => {
+            (u16::from(*idx), Access::Store)
+        }
+        IInc(idx, _) => (u16::from(*idx), Access::Increment),
+        ILoad0 | LLoad0 | FLoad0 | DLoad0 | ALoad0 => (0, Access::Load),
+        ILoad1 | LLoad1 | FLoad1 | DLoad1 | ALoad1 => (1, Access::Load),
+        ILoad2 | LLoad2 | FLoad2 | DLoad2 | ALoad2 => (2, Access::Load),
+        ILoad3 | LLoad3 | FLoad3 | DLoad3 | ALoad3 => (3, Access::Load),
+        IStore0 | LStore0 | FStore0 | DStore0 | AStore0 => (0, Access::Store),
+        IStore1 | LStore1 | FStore1 | DStore1 | AStore1 => (1, Access::Store),
+        IStore2 | LStore2 | FStore2 | DStore2 | AStore2 => (2, Access::Store),
+        IStore3 | LStore3 | FStore3 | DStore3 | AStore3 => (3, Access::Store),
+        Wide(
+            WideInstruction::ILoad(idx)
+            | WideInstruction::LLoad(idx)
+            | WideInstruction::FLoad(idx)
+            | WideInstruction::DLoad(idx)
+            | WideInstruction::ALoad(idx)
+            | WideInstruction::Ret(idx),
+        ) => (*idx, Access::Load),
+        Wide(
+            WideInstruction::IStore(idx)
+            | WideInstruction::LStore(idx)
+            | WideInstruction::FStore(idx)
+            | WideInstruction::DStore(idx)
+            | WideInstruction::AStore(idx),
+        ) => (*idx, Access::Store),
+        Wide(WideInstruction::IInc(idx, _)) => (*idx, Access::Increment),
+        _ => return None,
+    };
+    Some(access)
+}
+
+/// A slot together with the definitions and the uses of a variable in it.
+type Web = (u16, BTreeSet<Definition>, BTreeSet<ProgramCounter>);
+
+/// Where the value of a local variable is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Definition {
+    /// The value is an argument passed to the method.
+    Entry,
+    /// The value is stored by the instruction.
+    At(ProgramCounter),
+}
+
+struct ReachingDefinitions<'a> {
+    body: &'a MethodBody,
+    entry_slots: BTreeSet<u16>,
+    successors: BTreeMap<ProgramCounter, Vec<ProgramCounter>>,
+}
+
+impl Analyzer for ReachingDefinitions<'_> {
+    type Location = ProgramCounter;
+    type Fact = BTreeMap<u16, BTreeSet<Definition>>;
+    type Err = Error;
+    type AffectedLocations = Vec<(ProgramCounter, Self::Fact)>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        let fact = self
+            .entry_slots
+            .iter()
+            .map(|it| (*it, BTreeSet::from([Definition::Entry])))
+            .collect();
+        Ok(vec![(ProgramCounter::ZERO, fact)])
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let mut fact = fact.clone();
+        let access = self.body.instruction_at(*location).and_then(local_access);
+        if let Some((slot, Access::Store | Access::Increment)) = access {
+            fact.insert(slot, BTreeSet::from([Definition::At(*location)]));
+        }
+        let successors = self.successors.get(location).into_iter().flatten();
+        Ok(successors.map(|it| (*it, fact.clone())).collect())
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        let mut merged = current_fact.clone();
+        for (slot, definitions) in incoming_fact {
+            merged.entry(slot).or_default().extend(definitions);
+        }
+        Ok(merged)
+    }
+}
+
+/// Groups the definitions of a slot that reach a common use, i.e., the def-use webs.
+#[derive(Debug, Default)]
+struct Webs {
+    parents: BTreeMap<(u16, Definition), (u16, Definition)>,
+    uses: BTreeMap<(u16, Definition), BTreeSet<ProgramCounter>>,
+}
+
+impl Webs {
+    fn ensure(&mut self, slot: u16, definition: Definition) {
+        self.parents
+            .entry((slot, definition))
+            .or_insert((slot, definition));
+    }
+
+    fn find(&mut self, key: (u16, Definition)) -> (u16, Definition) {
+        self.ensure(key.0, key.1);
+        let parent = self.parents[&key];
+        if parent == key {
+            return key;
+        }
+        let root = self.find(parent);
+        self.parents.insert(key, root);
+        root
+    }
+
+    fn add_use<'d>(
+        &mut self,
+        slot: u16,
+        pc: ProgramCounter,
+        definitions: impl IntoIterator<Item = &'d Definition>,
+    ) {
+        let mut roots = definitions.into_iter().map(|it| self.find((slot, *it)));
+        let Some(root) = roots.next() else {
+            return;
+        };
+        let others: Vec<_> = roots.collect();
+        for other in others {
+            let other = self.find(other);
+            if other != root {
+                self.parents.insert(other, root);
+            }
+        }
+        self.uses.entry((slot, root.1)).or_default().insert(pc);
+    }
+
+    fn into_groups(mut self) -> Vec<Web> {
+        let mut groups: BTreeMap<_, (BTreeSet<_>, BTreeSet<_>)> = BTreeMap::new();
+        let keys: Vec<_> = self.parents.keys().copied().collect();
+        for key in keys {
+            let root = self.find(key);
+            groups.entry(root).or_default().0.insert(key.1);
+        }
+        for (key, uses) in std::mem::take(&mut self.uses) {
+            let root = self.find(key);
+            groups.entry(root).or_default().1.extend(uses);
+        }
+        groups
+            .into_iter()
+            .filter(|(_, (definitions, uses))| {
+                !uses.is_empty() || definitions.contains(&Definition::Entry)
+            })
+            .map(|((slot, _), (definitions, uses))| (slot, definitions, uses))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    fn recover(source: &str) -> Vec<RecoveredVariable> {
+        let class = jasmin::read(source).unwrap();
+        LocalVariableRecovery::new(&class.methods[0])
+            .recover()
+            .unwrap()
+    }
+
+    fn describe(variables: &[RecoveredVariable]) -> Vec<(u16, &str, String)> {
+        variables
+            .iter()
+            .map(|it| {
+                (
+                    it.id.index,
+                    it.entry.name.as_deref().unwrap(),
+                    it.entry
+                        .var_type
+                        .as_ref()
+                        .map(FieldType::descriptor)
+                        .unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reused_slot() {
+        let variables = recover(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static run(I)V
+                .limit stack 2
+                .limit locals 2
+                iload_0
+                istore_1
+                iload_1
+                pop
+                ldc \"text\"
+                astore_1
+                aload_1
+                pop
+                iconst_0
+                istore_1
+                return
+            .end method
+            ",
+        );
+        assert_eq!(
+            describe(&variables),
+            vec![
+                (0, "arg0", "I".to_owned()),
+                (1, "var0", "I".to_owned()),
+                (1, "var1", "Ljava/lang/String;".to_owned()),
+            ]
+        );
+        assert!(variables.iter().all(|it| it.is_synthetic));
+        let string = &variables[2].id.effective_range;
+        assert!(string.start < string.end);
+    }
+
+    #[test]
+    fn merged_definitions() {
+        let variables = recover(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public choose(Z)Ljava/lang/Object;
+                .limit stack 1
+                .limit locals 3
+                iload_1
+                ifeq Else
+                ldc \"string\"
+                astore_2
+                goto End
+            Else:
+                aconst_null
+                astore_2
+            End:
+                aload_2
+                areturn
+            .end method
+            ",
+        );
+        assert_eq!(
+            describe(&variables),
+            vec![
+                (0, "this", "LTest;".to_owned()),
+                (1, "arg0", "Z".to_owned()),
+                (2, "var0", "Ljava/lang/String;".to_owned()),
+            ]
+        );
+    }
+}
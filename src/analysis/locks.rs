@@ -0,0 +1,789 @@
+//! Analysis of the monitors acquired in methods and the order in which they are acquired.
+//!
+//! [`MethodLocks::of`] pairs each [`LockOperation::Acquire`] with the matching
+//! [`LockOperation::Release`]s to compute the regions where locks are held, and reports
+//! monitors that are unbalanced on some paths.
+//! [`LockAnalyzer`] combines the results of the methods in a [`ResolutionContext`] into a
+//! [`LockOrderGraph`], where a cycle indicates a potential deadlock.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+};
+
+use crate::{
+    ir::{
+        control_flow::ControlTransfer,
+        expression::{Expression, FieldAccess, LockOperation},
+        DefUseChain, Identifier, MokaIRMethod, MokaIRMethodExt, MokaInstruction, Operand,
+    },
+    jvm::{
+        code::ProgramCounter,
+        method,
+        references::{ClassRef, FieldRef, MethodRef},
+        ConstantValue,
+    },
+};
+
+use super::{fixed_point, ResolutionContext};
+
+/// The maximum number of nested locks tracked on a path.
+const MAX_NESTING: usize = 16;
+
+/// A lock, identified by where the locked object comes from.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Lock {
+    /// The monitor of `this`, which is an instance of the given class.
+    #[display("this@{_0}")]
+    This(ClassRef),
+    /// The monitor of a class object, e.g., locked by `static synchronized` methods.
+    #[display("{_0}.class")]
+    Class(ClassRef),
+    /// The monitor of the object stored in a field.
+    #[display("{_0}")]
+    Field(FieldRef),
+    /// The monitor of an object that cannot be identified across methods.
+    #[display("unknown")]
+    Unknown,
+}
+
+/// A region of a method where a lock is held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockRegion {
+    /// The location of the `monitorenter`.
+    pub acquired_at: ProgramCounter,
+    /// The lock acquired.
+    pub lock: Lock,
+    /// The locations of the `monitorexit`s releasing the lock.
+    pub released_at: BTreeSet<ProgramCounter>,
+    /// The locations where the lock may be held.
+    pub held_at: BTreeSet<ProgramCounter>,
+}
+
+/// The kind of an unbalanced monitor operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Unbalanced {
+    /// A `monitorexit` is reached on a path without a matching `monitorenter`.
+    ReleaseWithoutAcquire,
+    /// The method may return while still holding a lock acquired in it.
+    HeldAtExit,
+}
+
+/// An unbalanced monitor operation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnbalancedMonitor {
+    /// The location of the `monitorexit`, or of the exit of the method.
+    pub pc: ProgramCounter,
+    /// The kind of the unbalanced operation.
+    pub kind: Unbalanced,
+}
+
+/// The locks used in a method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MethodLocks {
+    /// The lock held during the whole method if it is `synchronized`.
+    pub method_lock: Option<Lock>,
+    /// The regions where the locks acquired with `monitorenter` are held.
+    pub regions: Vec<LockRegion>,
+    /// The monitor operations that are unbalanced on some paths.
+    pub unbalanced: Vec<UnbalancedMonitor>,
+    /// The calls made while holding locks, and the locks held at each of them.
+    pub guarded_calls: Vec<(ProgramCounter, MethodRef, BTreeSet<Lock>)>,
+    /// The locks acquired while holding other locks, as `(pc, held, acquired)`.
+    pub nested_acquisitions: Vec<(ProgramCounter, Lock, Lock)>,
+}
+
+impl MethodLocks {
+    /// Analyzes the locks used in `ir_method`.
+    #[must_use]
+    pub fn of(ir_method: &MokaIRMethod) -> Self {
+        let mut analyzer = MonitorAnalyzer { ir_method };
+        let Ok(facts) = fixed_point::Analyzer::analyze(&mut analyzer);
+        let du_chain = DefUseChain::new(ir_method);
+        let lock_of = |operand: &Operand| identify(ir_method, &du_chain, operand);
+        let method_lock = ir_method
+            .access_flags
+            .contains(method::AccessFlags::SYNCHRONIZED)
+            .then(|| {
+                if ir_method.is_static() {
+                    Lock::Class(ir_method.owner.clone())
+                } else {
+                    Lock::This(ir_method.owner.clone())
+                }
+            });
+
+        let mut regions = acquisitions(ir_method, lock_of);
+        let mut locks = Self {
+            method_lock,
+            ..Self::default()
+        };
+        for (pc, stacks) in &facts {
+            for acquired_at in stacks.iter().flatten() {
+                if let Some(region) = regions.get_mut(acquired_at) {
+                    region.held_at.insert(*pc);
+                }
+            }
+            let held = |stacks: &BTreeSet<Vec<ProgramCounter>>| -> BTreeSet<Lock> {
+                stacks
+                    .iter()
+                    .flatten()
+                    .filter_map(|it| regions.get(it))
+                    .map(|it| it.lock.clone())
+                    .chain(locks.method_lock.clone())
+                    .collect()
+            };
+            let Some(MokaInstruction::Definition { expr, .. }) = ir_method.instructions.get(pc)
+            else {
+                continue;
+            };
+            match expr {
+                Expression::Synchronization(LockOperation::Acquire(operand)) => {
+                    let acquired = lock_of(operand);
+                    for held in held(stacks) {
+                        locks
+                            .nested_acquisitions
+                            .push((*pc, held, acquired.clone()));
+                    }
+                }
+                Expression::Synchronization(LockOperation::Release(_)) => {
+                    for stack in stacks {
+                        match stack.last().and_then(|it| regions.get_mut(it)) {
+                            Some(region) => {
+                                region.released_at.insert(*pc);
+                            }
+                            None if stack.is_empty() => locks.unbalanced.push(UnbalancedMonitor {
+                                pc: *pc,
+                                kind: Unbalanced::ReleaseWithoutAcquire,
+                            }),
+                            None => {}
+                        }
+                    }
+                }
+                Expression::Call { method, .. } => {
+                    let held = held(stacks);
+                    if !held.is_empty() {
+                        locks.guarded_calls.push((*pc, method.clone(), held));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for exit in ir_method.control_flow_graph.exits() {
+            if facts
+                .get(&exit)
+                .is_some_and(|stacks| stacks.iter().any(|it| !it.is_empty()))
+            {
+                locks.unbalanced.push(UnbalancedMonitor {
+                    pc: exit,
+                    kind: Unbalanced::HeldAtExit,
+                });
+            }
+        }
+        locks.unbalanced.sort();
+        locks.unbalanced.dedup();
+        locks.regions = regions.into_values().collect();
+        locks
+    }
+
+    /// Checks whether every `monitorenter` is released on all paths.
+    #[must_use]
+    pub fn is_balanced(&self) -> bool {
+        self.unbalanced.is_empty()
+    }
+}
+
+/// An edge in a [`LockOrderGraph`], meaning that `acquired` is acquired while `held` is held.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LockOrderEdge {
+    /// The lock being held.
+    pub held: Lock,
+    /// The lock being acquired.
+    pub acquired: Lock,
+    /// The method where the lock is acquired, or where the method acquiring it is called.
+    pub method: MethodRef,
+    /// The location in `method`.
+    pub pc: ProgramCounter,
+}
+
+/// The orders in which locks are acquired across methods.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LockOrderGraph {
+    edges: BTreeSet<LockOrderEdge>,
+}
+
+/// A set of locks that may be acquired in a cyclic order, and may therefore deadlock when
+/// acquired by different threads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PotentialDeadlock {
+    /// The locks in the cycle.
+    pub locks: BTreeSet<Lock>,
+    /// The edges among the locks.
+    pub edges: Vec<LockOrderEdge>,
+}
+
+impl LockOrderGraph {
+    /// Creates an iterator over the edges.
+    pub fn edges(&self) -> impl Iterator<Item = &LockOrderEdge> {
+        self.edges.iter()
+    }
+
+    /// Finds the sets of locks acquired in a cyclic order, i.e., the strongly connected
+    /// components with more than one lock.
+    /// Reentrant acquisitions of the same lock are not reported.
+    #[must_use]
+    pub fn potential_deadlocks(&self) -> Vec<PotentialDeadlock> {
+        let mut successors: BTreeMap<&Lock, BTreeSet<&Lock>> = BTreeMap::new();
+        for edge in &self.edges {
+            successors
+                .entry(&edge.held)
+                .or_default()
+                .insert(&edge.acquired);
+        }
+        let reachable = |from: &Lock| {
+            let mut visited = BTreeSet::new();
+            let mut worklist = vec![from];
+            while let Some(lock) = worklist.pop() {
+                for next in successors.get(lock).into_iter().flatten() {
+                    if visited.insert(*next) {
+                        worklist.push(next);
+                    }
+                }
+            }
+            visited
+        };
+        let reachability: BTreeMap<_, _> =
+            successors.keys().map(|it| (*it, reachable(it))).collect();
+        let mut assigned = BTreeSet::new();
+        let mut deadlocks = Vec::new();
+        for (lock, reachable) in &reachability {
+            if assigned.contains(lock) {
+                continue;
+            }
+            let component: BTreeSet<_> = reachable
+                .iter()
+                .filter(|it| reachability.get(*it).is_some_and(|it| it.contains(lock)))
+                .copied()
+                .chain(std::iter::once(*lock))
+                .collect();
+            assigned.extend(component.iter().copied());
+            if component.len() > 1 {
+                deadlocks.push(PotentialDeadlock {
+                    edges: self
+                        .edges
+                        .iter()
+                        .filter(|it| {
+                            component.contains(&it.held) && component.contains(&it.acquired)
+                        })
+                        .cloned()
+                        .collect(),
+                    locks: component.into_iter().cloned().collect(),
+                });
+            }
+        }
+        deadlocks
+    }
+}
+
+/// The results of a [`LockAnalyzer`].
+#[derive(Debug, Clone, Default)]
+pub struct LockAnalysis {
+    /// The locks used in each method.
+    pub methods: BTreeMap<MethodRef, MethodLocks>,
+    /// The order in which locks are acquired.
+    pub lock_order: LockOrderGraph,
+}
+
+/// An analyzer of the locks used in the application classes of a [`ResolutionContext`].
+#[derive(Debug)]
+pub struct LockAnalyzer<'a> {
+    context: &'a ResolutionContext,
+}
+
+impl<'a> LockAnalyzer<'a> {
+    /// Creates an analyzer for the application classes in `context`.
+    #[must_use]
+    pub const fn new(context: &'a ResolutionContext) -> Self {
+        Self { context }
+    }
+
+    /// Analyzes the locks in all application classes and builds the lock order graph.
+    /// The locks acquired by a callee are considered acquired at the call site.
+    /// Methods that cannot be brewed into Moka IR are skipped.
+    #[must_use]
+    pub fn analyze(&self) -> LockAnalysis {
+        let ir_methods: Vec<_> = self
+            .context
+            .application_classes
+            .values()
+            .flat_map(|it| &it.methods)
+            .filter(|it| it.body.is_some())
+            .filter_map(|it| Some((it.as_ref(), it.brew().ok()?)))
+            .collect();
+        let methods: BTreeMap<_, _> = ir_methods
+            .iter()
+            .map(|(method, ir_method)| (method.clone(), MethodLocks::of(ir_method)))
+            .collect();
+
+        // The locks that may be acquired during the execution of each method.
+        let mut acquired: BTreeMap<&MethodRef, BTreeSet<Lock>> = methods
+            .iter()
+            .map(|(method, locks)| {
+                let own = locks
+                    .regions
+                    .iter()
+                    .map(|it| it.lock.clone())
+                    .chain(locks.method_lock.clone())
+                    .filter(|it| *it != Lock::Unknown)
+                    .collect();
+                (method, own)
+            })
+            .collect();
+        let calls: Vec<_> = ir_methods
+            .iter()
+            .map(|(method, ir_method)| (method, callees(ir_method)))
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (method, callees) in &calls {
+                let from_callees: BTreeSet<_> = callees
+                    .iter()
+                    .filter_map(|it| acquired.get(*it))
+                    .flatten()
+                    .cloned()
+                    .collect();
+                let current = acquired.entry(method).or_default();
+                let before = current.len();
+                current.extend(from_callees);
+                changed |= current.len() != before;
+            }
+        }
+
+        let mut lock_order = LockOrderGraph::default();
+        for (method, locks) in &methods {
+            let nested = locks
+                .nested_acquisitions
+                .iter()
+                .map(|(pc, held, acquired)| (*pc, held, acquired));
+            let via_calls = locks.guarded_calls.iter().flat_map(|(pc, callee, held)| {
+                let callee_locks = acquired.get(callee).into_iter().flatten();
+                held.iter()
+                    .flat_map(move |held| callee_locks.clone().map(move |it| (*pc, held, it)))
+            });
+            for (pc, held, acquired) in nested.chain(via_calls) {
+                if *held == Lock::Unknown || *acquired == Lock::Unknown {
+                    continue;
+                }
+                lock_order.edges.insert(LockOrderEdge {
+                    held: held.clone(),
+                    acquired: acquired.clone(),
+                    method: method.clone(),
+                    pc,
+                });
+            }
+        }
+        LockAnalysis {
+            methods,
+            lock_order,
+        }
+    }
+}
+
+/// Creates the lock regions for the `monitorenter`s in `ir_method`, with the locations where the
+/// locks are held or released yet to be filled.
+fn acquisitions(
+    ir_method: &MokaIRMethod,
+    lock_of: impl Fn(&Operand) -> Lock,
+) -> BTreeMap<ProgramCounter, LockRegion> {
+    ir_method
+        .instructions
+        .iter()
+        .filter_map(|(pc, insn)| match insn {
+            MokaInstruction::Definition {
+                expr: Expression::Synchronization(LockOperation::Acquire(operand)),
+                ..
+            } => Some((
+                *pc,
+                LockRegion {
+                    acquired_at: *pc,
+                    lock: lock_of(operand),
+                    released_at: BTreeSet::new(),
+                    held_at: BTreeSet::new(),
+                },
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the methods called in `ir_method`.
+fn callees(ir_method: &MokaIRMethod) -> BTreeSet<&MethodRef> {
+    ir_method
+        .instructions
+        .iter()
+        .filter_map(|(_, insn)| match insn {
+            MokaInstruction::Definition {
+                expr: Expression::Call { method, .. },
+                ..
+            } => Some(method),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Identifies the lock referred to by `operand`.
+fn identify(ir_method: &MokaIRMethod, du_chain: &DefUseChain<'_>, operand: &Operand) -> Lock {
+    let locks: BTreeSet<_> = operand
+        .iter()
+        .map(|it| match it {
+            Identifier::This => Lock::This(ir_method.owner.clone()),
+            Identifier::Local(value) => {
+                let definition = du_chain
+                    .defined_at(value)
+                    .and_then(|pc| ir_method.instructions.get(&pc));
+                match definition {
+                    Some(MokaInstruction::Definition {
+                        expr:
+                            Expression::Field(
+                                FieldAccess::ReadStatic { field }
+                                | FieldAccess::ReadInstance { field, .. },
+                            ),
+                        ..
+                    }) => Lock::Field(field.clone()),
+                    Some(MokaInstruction::Definition {
+                        expr: Expression::Const(ConstantValue::Class(class_ref)),
+                        ..
+                    }) => Lock::Class(class_ref.clone()),
+                    _ => Lock::Unknown,
+                }
+            }
+            Identifier::Arg(_) | Identifier::CaughtException => Lock::Unknown,
+        })
+        .collect();
+    let mut locks = locks.into_iter();
+    match (locks.next(), locks.next()) {
+        (Some(lock), None) => lock,
+        _ => Lock::Unknown,
+    }
+}
+
+/// Tracks the possible stacks of the `monitorenter`s whose locks are held.
+struct MonitorAnalyzer<'a> {
+    ir_method: &'a MokaIRMethod,
+}
+
+impl fixed_point::Analyzer for MonitorAnalyzer<'_> {
+    type Location = ProgramCounter;
+    type Fact = BTreeSet<Vec<ProgramCounter>>;
+    type Err = Infallible;
+    type AffectedLocations = Vec<(Self::Location, Self::Fact)>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        Ok(vec![(
+            self.ir_method.control_flow_graph.entry_point(),
+            BTreeSet::from([Vec::new()]),
+        )])
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let after = match self.ir_method.instructions.get(location) {
+            Some(MokaInstruction::Definition {
+                expr: Expression::Synchronization(operation),
+                ..
+            }) => fact
+                .iter()
+                .map(|stack| {
+                    let mut stack = stack.clone();
+                    match operation {
+                        LockOperation::Acquire(_) if stack.len() < MAX_NESTING => {
+                            stack.push(*location);
+                        }
+                        LockOperation::Acquire(_) => {}
+                        LockOperation::Release(_) => {
+                            stack.pop();
+                        }
+                    }
+                    stack
+                })
+                .collect(),
+            _ => fact.clone(),
+        };
+        let Some(edges) = self.ir_method.control_flow_graph.edges_from(*location) else {
+            return Ok(Vec::default());
+        };
+        let affected_locations = edges
+            .map(|(_, dst, transfer)| match transfer {
+                // The monitor operation does not take effect if it throws.
+                ControlTransfer::Exception(_) => (dst, fact.clone()),
+                _ => (dst, after.clone()),
+            })
+            .collect();
+        Ok(affected_locations)
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        Ok(current_fact.iter().cloned().chain(incoming_fact).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        ir::{ClassHierarchy, InterfaceImplHierarchy},
+        jvm::interop::jasmin,
+    };
+
+    const JASMIN: &str = "
+        .bytecode 52.0
+        .class public Foo
+        .super java/lang/Object
+        .field static a Ljava/lang/Object;
+        .field static b Ljava/lang/Object;
+        .method public static ab()V
+            .limit stack 1
+            getstatic Foo/a Ljava/lang/Object;
+            monitorenter
+            getstatic Foo/b Ljava/lang/Object;
+            monitorenter
+            getstatic Foo/b Ljava/lang/Object;
+            monitorexit
+            getstatic Foo/a Ljava/lang/Object;
+            monitorexit
+            return
+        .end method
+        .method public static lockB()V
+            .limit stack 1
+            getstatic Foo/b Ljava/lang/Object;
+            monitorenter
+            invokestatic Foo/lockA()V
+            getstatic Foo/b Ljava/lang/Object;
+            monitorexit
+            return
+        .end method
+        .method public static lockA()V
+            .limit stack 1
+            getstatic Foo/a Ljava/lang/Object;
+            monitorenter
+            return
+        .end method
+    ";
+
+    const MONITORS: &str = "
+        .bytecode 52.0
+        .class public Bar
+        .super java/lang/Object
+        .field static a Ljava/lang/Object;
+        .method public static releaseOnly()V
+            .limit stack 1
+            getstatic Bar/a Ljava/lang/Object;
+            monitorexit
+            return
+        .end method
+        .method public static conditional(Z)V
+            .limit stack 1
+            .limit locals 1
+            iload_0
+            ifeq L0
+            getstatic Bar/a Ljava/lang/Object;
+            monitorenter
+        L0:
+            return
+        .end method
+        .method public static block()V
+            .limit stack 2
+            .limit locals 2
+            .catch all from L0 to L1 using L2
+            .catch all from L2 to L3 using L2
+            getstatic Bar/a Ljava/lang/Object;
+            dup
+            astore_0
+            monitorenter
+        L0:
+            invokestatic Bar/staticWork()V
+            aload_0
+            monitorexit
+        L1:
+            return
+        L2:
+            astore_1
+            aload_0
+            monitorexit
+        L3:
+            aload_1
+            athrow
+        .end method
+        .method public static reentrant()V
+            .limit stack 1
+            getstatic Bar/a Ljava/lang/Object;
+            monitorenter
+            getstatic Bar/a Ljava/lang/Object;
+            monitorenter
+            getstatic Bar/a Ljava/lang/Object;
+            monitorexit
+            getstatic Bar/a Ljava/lang/Object;
+            monitorexit
+            return
+        .end method
+        .method public synchronized work()V
+            .limit stack 1
+            .limit locals 1
+            getstatic Bar/a Ljava/lang/Object;
+            monitorenter
+            getstatic Bar/a Ljava/lang/Object;
+            monitorexit
+            return
+        .end method
+        .method public static synchronized staticWork()V
+            return
+        .end method
+        .method public static holdA(LBar;)V
+            .limit stack 1
+            .limit locals 1
+            getstatic Bar/a Ljava/lang/Object;
+            monitorenter
+            aload_0
+            invokevirtual Bar/work()V
+            getstatic Bar/a Ljava/lang/Object;
+            monitorexit
+            return
+        .end method
+    ";
+
+    fn analyze(source: &str) -> LockAnalysis {
+        let class = jasmin::read(source).unwrap();
+        let classes = BTreeMap::from([(class.as_ref(), class)]);
+        let context = ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(classes.values()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(classes.values()),
+            application_classes: classes,
+            library_classes: BTreeMap::new(),
+        };
+        LockAnalyzer::new(&context).analyze()
+    }
+
+    fn method<'a>(analysis: &'a LockAnalysis, name: &str, descriptor: &str) -> &'a MethodLocks {
+        analysis
+            .methods
+            .iter()
+            .find(|(it, _)| &*it.name == name && it.descriptor.descriptor() == descriptor)
+            .map(|(_, it)| it)
+            .unwrap()
+    }
+
+    #[test]
+    fn lock_order_cycle() {
+        let analysis = analyze(JASMIN);
+        let method = |name: &str| method(&analysis, name, "()V");
+
+        let ab = method("ab");
+        assert!(ab.is_balanced());
+        assert_eq!(ab.regions.len(), 2);
+        assert_eq!(ab.regions[0].released_at.len(), 1);
+        assert!(ab.regions[0].held_at.is_superset(&ab.regions[1].held_at));
+        assert!(method("lockB").is_balanced());
+        assert_eq!(
+            method("lockA").unbalanced,
+            vec![UnbalancedMonitor {
+                pc: ProgramCounter::from(4),
+                kind: Unbalanced::HeldAtExit
+            }]
+        );
+
+        let deadlocks = analysis.lock_order.potential_deadlocks();
+        assert_eq!(deadlocks.len(), 1);
+        assert_eq!(deadlocks[0].locks.len(), 2);
+        assert_eq!(deadlocks[0].edges.len(), 2);
+    }
+
+    #[test]
+    fn unbalanced_monitors() {
+        let analysis = analyze(MONITORS);
+        assert_eq!(
+            method(&analysis, "releaseOnly", "()V").unbalanced,
+            vec![UnbalancedMonitor {
+                pc: ProgramCounter::from(3),
+                kind: Unbalanced::ReleaseWithoutAcquire
+            }]
+        );
+        // The lock is held at the exit only if it is acquired.
+        assert_eq!(
+            method(&analysis, "conditional", "(Z)V").unbalanced,
+            vec![UnbalancedMonitor {
+                pc: ProgramCounter::from(8),
+                kind: Unbalanced::HeldAtExit
+            }]
+        );
+    }
+
+    #[test]
+    fn release_on_exception_paths() {
+        let analysis = analyze(MONITORS);
+        let block = method(&analysis, "block", "()V");
+        assert!(block.is_balanced());
+        assert_eq!(block.regions.len(), 1);
+        let lock_a = Lock::Field(FieldRef::new(
+            ClassRef::new("Bar"),
+            "a",
+            "Ljava/lang/Object;".parse().unwrap(),
+        ));
+        assert_eq!(block.regions[0].lock, lock_a);
+        // Released both after the call and in the handler.
+        assert_eq!(block.regions[0].released_at.len(), 2);
+        assert_eq!(block.guarded_calls.len(), 1);
+        assert_eq!(block.guarded_calls[0].2, BTreeSet::from([lock_a]));
+    }
+
+    #[test]
+    fn nested_locks() {
+        let analysis = analyze(JASMIN);
+        let ab = method(&analysis, "ab", "()V");
+        let field = |name: &str| {
+            Lock::Field(FieldRef::new(
+                ClassRef::new("Foo"),
+                name,
+                "Ljava/lang/Object;".parse().unwrap(),
+            ))
+        };
+        assert_eq!(
+            ab.nested_acquisitions,
+            vec![(ProgramCounter::from(7), field("a"), field("b"))]
+        );
+
+        // Reentrant acquisitions are balanced and do not deadlock.
+        let analysis = analyze(MONITORS);
+        let reentrant = method(&analysis, "reentrant", "()V");
+        assert!(reentrant.is_balanced());
+        assert_eq!(reentrant.regions.len(), 2);
+        assert_eq!(reentrant.nested_acquisitions.len(), 1);
+    }
+
+    #[test]
+    fn synchronized_methods() {
+        let analysis = analyze(MONITORS);
+        let bar = ClassRef::new("Bar");
+        let work = method(&analysis, "work", "()V");
+        assert_eq!(work.method_lock, Some(Lock::This(bar.clone())));
+        assert!(work.is_balanced());
+        assert_eq!(work.nested_acquisitions[0].1, Lock::This(bar.clone()));
+        assert_eq!(
+            method(&analysis, "staticWork", "()V").method_lock,
+            Some(Lock::Class(bar.clone()))
+        );
+
+        // `holdA` acquires `a` before the monitor of `this` while `work` does the opposite.
+        let deadlocks = analysis.lock_order.potential_deadlocks();
+        assert_eq!(deadlocks.len(), 1);
+        assert!(deadlocks[0].locks.contains(&Lock::This(bar)));
+    }
+}
@@ -0,0 +1,561 @@
+//! Detection of loops in Moka IR, and of the loop-invariant definitions and induction variables
+//! in them.
+//!
+//! The [`LoopForest`] of a method consists of its natural loops, which are identified by the back
+//! edges to the instructions dominating their sources.
+//! Loops sharing the same header are merged into one.
+//! For each [`Loop`], it reports
+//! - the definitions whose values are the same in every iteration, and
+//! - the [`InductionVariable`]s, which change by a constant stride in every iteration.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    decompiler::dominators::{dominates, immediate_dominators},
+    ir::{
+        control_flow::ControlTransfer,
+        expression::{ArrayOperation, Expression, MathOperation},
+        Identifier, LocalValue, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::{code::ProgramCounter, ConstantValue},
+};
+
+/// A natural loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    /// The location of the header, which dominates every instruction in the loop.
+    pub header: ProgramCounter,
+    /// The locations of the instructions in the loop, including the ones of the nested loops.
+    pub body: BTreeSet<ProgramCounter>,
+    /// The locations of the instructions jumping back to the header.
+    pub latches: BTreeSet<ProgramCounter>,
+    /// The locations outside the loop that the control may reach from the loop.
+    pub exits: BTreeSet<ProgramCounter>,
+    /// The header of the innermost loop enclosing this loop.
+    pub parent: Option<ProgramCounter>,
+    /// The values defined in the loop that are the same in every iteration.
+    pub invariants: BTreeSet<LocalValue>,
+    /// The induction variables of the loop, keyed by the values defining them.
+    pub induction_variables: BTreeMap<LocalValue, InductionVariable>,
+}
+
+impl Loop {
+    /// Checks whether `pc` is in the loop.
+    #[must_use]
+    pub fn contains(&self, pc: ProgramCounter) -> bool {
+        self.body.contains(&pc)
+    }
+}
+
+/// A variable that changes by a constant stride in every iteration of a loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InductionVariable {
+    /// A variable updated by adding a constant in every iteration, i.e., `i = i + stride`.
+    Basic {
+        /// The values of the variable when entering the loop.
+        initial: Operand,
+        /// The amount added in every iteration.
+        stride: i64,
+    },
+    /// A linear function of a basic induction variable, i.e., `scale * i + offset`, where `i` is
+    /// the value of the basic induction variable at the start of the iteration.
+    Derived {
+        /// The value defining the basic induction variable.
+        base: LocalValue,
+        /// The factor multiplied to the basic induction variable.
+        scale: i64,
+        /// The amount added to the product.
+        offset: i64,
+    },
+}
+
+/// The loops in a method.
+#[derive(Debug, Clone, Default)]
+pub struct LoopForest {
+    loops: BTreeMap<ProgramCounter, Loop>,
+}
+
+impl LoopForest {
+    /// Finds the loops in `ir_method`.
+    #[must_use]
+    pub fn of(ir_method: &MokaIRMethod) -> Self {
+        let pcs: Vec<_> = ir_method.instructions.iter().map(|(pc, _)| *pc).collect();
+        let index_of: BTreeMap<_, _> = pcs
+            .iter()
+            .enumerate()
+            .map(|(index, pc)| (*pc, index))
+            .collect();
+        if pcs.is_empty() {
+            return Self::default();
+        }
+        let mut successors = vec![Vec::new(); pcs.len()];
+        let mut predecessors = vec![Vec::new(); pcs.len()];
+        let mut normal_edges = Vec::new();
+        for (src, dst, transfer) in ir_method.control_flow_graph.edges() {
+            let (Some(&src), Some(&dst)) = (index_of.get(&src), index_of.get(&dst)) else {
+                continue;
+            };
+            successors[src].push(dst);
+            predecessors[dst].push(src);
+            if !matches!(transfer, ControlTransfer::Exception(_)) {
+                normal_edges.push((src, dst));
+            }
+        }
+        let dominators = immediate_dominators(&successors, 0);
+
+        let mut bodies: BTreeMap<usize, (BTreeSet<usize>, BTreeSet<usize>)> = BTreeMap::new();
+        for (latch, header) in normal_edges {
+            if !dominates(&dominators, header, latch) {
+                continue;
+            }
+            let (body, latches) = bodies
+                .entry(header)
+                .or_insert_with(|| (BTreeSet::from([header]), BTreeSet::new()));
+            latches.insert(latch);
+            let mut worklist = vec![latch];
+            while let Some(node) = worklist.pop() {
+                if body.insert(node) {
+                    worklist.extend(predecessors[node].iter().copied());
+                }
+            }
+        }
+
+        let facts = MethodFacts::of(ir_method);
+        let mut loops: BTreeMap<_, _> = bodies
+            .into_iter()
+            .map(|(header, (body, latches))| {
+                let exits = body
+                    .iter()
+                    .flat_map(|&node| successors[node].iter())
+                    .filter(|succ| !body.contains(succ))
+                    .map(|&it| pcs[it])
+                    .collect();
+                let body: BTreeSet<_> = body.into_iter().map(|it| pcs[it]).collect();
+                let invariants = facts.invariants(&body);
+                let induction_variables = facts.induction_variables(&body);
+                let header = pcs[header];
+                let a_loop = Loop {
+                    header,
+                    body,
+                    latches: latches.into_iter().map(|it| pcs[it]).collect(),
+                    exits,
+                    parent: None,
+                    invariants,
+                    induction_variables,
+                };
+                (header, a_loop)
+            })
+            .collect();
+        let parents: Vec<_> = loops
+            .values()
+            .map(|inner| {
+                let parent = loops
+                    .values()
+                    .filter(|outer| {
+                        outer.header != inner.header
+                            && outer.contains(inner.header)
+                            && outer.body.len() > inner.body.len()
+                    })
+                    .min_by_key(|outer| outer.body.len())
+                    .map(|outer| outer.header);
+                (inner.header, parent)
+            })
+            .collect();
+        for (header, parent) in parents {
+            if let Some(it) = loops.get_mut(&header) {
+                it.parent = parent;
+            }
+        }
+        Self { loops }
+    }
+
+    /// Returns the loop whose header is at `header`.
+    #[must_use]
+    pub fn get(&self, header: ProgramCounter) -> Option<&Loop> {
+        self.loops.get(&header)
+    }
+
+    /// Creates an iterator over the loops ordered by the locations of their headers.
+    pub fn iter(&self) -> impl Iterator<Item = &Loop> {
+        self.loops.values()
+    }
+
+    /// Returns the innermost loop containing `pc`.
+    #[must_use]
+    pub fn innermost(&self, pc: ProgramCounter) -> Option<&Loop> {
+        self.loops
+            .values()
+            .filter(|it| it.contains(pc))
+            .min_by_key(|it| it.body.len())
+    }
+}
+
+/// The facts about the definitions in a method used to analyze its loops.
+#[derive(Debug)]
+struct MethodFacts<'a> {
+    definitions: BTreeMap<LocalValue, (ProgramCounter, &'a Expression)>,
+    constants: BTreeMap<LocalValue, i64>,
+}
+
+impl<'a> MethodFacts<'a> {
+    fn of(ir_method: &'a MokaIRMethod) -> Self {
+        let definitions: BTreeMap<_, _> = ir_method
+            .instructions
+            .iter()
+            .filter_map(|(pc, insn)| match insn {
+                MokaInstruction::Definition { value, expr } => Some((*value, (*pc, expr))),
+                _ => None,
+            })
+            .collect();
+        let constants = definitions
+            .iter()
+            .filter_map(|(value, (_, expr))| match expr {
+                Expression::Const(ConstantValue::Integer(it)) => Some((*value, i64::from(*it))),
+                Expression::Const(ConstantValue::Long(it)) => Some((*value, *it)),
+                _ => None,
+            })
+            .collect();
+        Self {
+            definitions,
+            constants,
+        }
+    }
+
+    fn is_defined_in(&self, value: LocalValue, body: &BTreeSet<ProgramCounter>) -> bool {
+        self.definitions
+            .get(&value)
+            .is_some_and(|(pc, _)| body.contains(pc))
+    }
+
+    fn constant(&self, operand: &Operand) -> Option<i64> {
+        match operand {
+            Operand::Just(Identifier::Local(value)) => self.constants.get(value).copied(),
+            _ => None,
+        }
+    }
+
+    fn invariants(&self, body: &BTreeSet<ProgramCounter>) -> BTreeSet<LocalValue> {
+        let mut invariants = BTreeSet::new();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (value, (pc, expr)) in &self.definitions {
+                if !body.contains(pc) || invariants.contains(value) {
+                    continue;
+                }
+                // Only the expressions evaluating to the same value given the same operands.
+                let is_pure = matches!(
+                    expr,
+                    Expression::Const(_)
+                        | Expression::Math(_)
+                        | Expression::Conversion(_)
+                        | Expression::Array(ArrayOperation::Length { .. })
+                );
+                let is_invariant = is_pure
+                    && expr.uses().iter().all(|id| match id {
+                        Identifier::This | Identifier::Arg(_) => true,
+                        Identifier::Local(it) => {
+                            !self.is_defined_in(*it, body) || invariants.contains(it)
+                        }
+                        Identifier::CaughtException => false,
+                    });
+                if is_invariant {
+                    invariants.insert(*value);
+                    changed = true;
+                }
+            }
+        }
+        invariants
+    }
+
+    /// Checks whether `value` is defined by `expr` as a basic induction variable.
+    /// If so, returns the operand referring to its value at the start of the iteration.
+    fn basic_induction_variable<'e>(
+        &self,
+        value: LocalValue,
+        expr: &'e Expression,
+        body: &BTreeSet<ProgramCounter>,
+    ) -> Option<(&'e BTreeSet<Identifier>, InductionVariable)> {
+        use MathOperation::{Add, Increment, Subtract};
+
+        let (current, stride) = match expr {
+            Expression::Math(Increment(it, stride)) => (it, i64::from(*stride)),
+            Expression::Math(Add(it, other) | Add(other, it)) if merges(it, value) => {
+                (it, self.constant(other)?)
+            }
+            Expression::Math(Subtract(it, other)) => (it, self.constant(other)?.checked_neg()?),
+            _ => return None,
+        };
+        let Operand::Phi(ids) = current else {
+            return None;
+        };
+        let mut initial = ids.iter().filter(|it| **it != Identifier::Local(value));
+        let enters_from_outside = initial.clone().all(|id| match id {
+            Identifier::Local(it) => !self.is_defined_in(*it, body),
+            Identifier::This | Identifier::Arg(_) => true,
+            Identifier::CaughtException => false,
+        });
+        if !ids.contains(&Identifier::Local(value)) || !enters_from_outside {
+            return None;
+        }
+        let first = Operand::Just(*initial.next()?);
+        let initial = initial.fold(first, |acc, id| acc | Operand::Just(*id));
+        Some((ids, InductionVariable::Basic { initial, stride }))
+    }
+
+    fn induction_variables(
+        &self,
+        body: &BTreeSet<ProgramCounter>,
+    ) -> BTreeMap<LocalValue, InductionVariable> {
+        use MathOperation::{Add, Increment, Multiply, Negate, ShiftLeft, Subtract};
+
+        let in_loop: Vec<_> = self
+            .definitions
+            .iter()
+            .filter(|(_, (pc, _))| body.contains(pc))
+            .map(|(value, (_, expr))| (*value, *expr))
+            .collect();
+        let mut induction_variables = BTreeMap::new();
+        // The values of the basic induction variables at the start of the iteration.
+        let mut headers = BTreeMap::new();
+        for (value, expr) in &in_loop {
+            if let Some((ids, basic)) = self.basic_induction_variable(*value, expr, body) {
+                headers.insert(ids.clone(), *value);
+                induction_variables.insert(*value, basic);
+            }
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (value, expr) in &in_loop {
+                if induction_variables.contains_key(value) {
+                    continue;
+                }
+                let term = |operand: &Operand| -> Option<(LocalValue, i64, i64)> {
+                    match operand {
+                        Operand::Phi(ids) => headers.get(ids).map(|base| (*base, 1, 0)),
+                        Operand::Just(Identifier::Local(it)) => {
+                            match induction_variables.get(it)? {
+                                InductionVariable::Basic { stride, .. } => Some((*it, 1, *stride)),
+                                InductionVariable::Derived {
+                                    base,
+                                    scale,
+                                    offset,
+                                } => Some((*base, *scale, *offset)),
+                            }
+                        }
+                        Operand::Just(_) => None,
+                    }
+                };
+                let linear = |a: &Operand, b: &Operand| term(a).zip(self.constant(b));
+                let derived = match expr {
+                    Expression::Math(Add(a, b)) => linear(a, b)
+                        .or_else(|| linear(b, a))
+                        .and_then(|((base, s, o), k)| Some((base, s, o.checked_add(k)?))),
+                    Expression::Math(Subtract(a, b)) => linear(a, b)
+                        .and_then(|((base, s, o), k)| Some((base, s, o.checked_sub(k)?))),
+                    Expression::Math(Increment(a, k)) => term(a)
+                        .and_then(|(base, s, o)| Some((base, s, o.checked_add(i64::from(*k))?))),
+                    Expression::Math(Multiply(a, b)) => linear(a, b)
+                        .or_else(|| linear(b, a))
+                        .and_then(|((base, s, o), k)| {
+                            Some((base, s.checked_mul(k)?, o.checked_mul(k)?))
+                        }),
+                    Expression::Math(ShiftLeft(a, b)) => {
+                        linear(a, b).and_then(|((base, s, o), k)| {
+                            let factor = 1i64.checked_shl(u32::try_from(k).ok()?)?;
+                            Some((base, s.checked_mul(factor)?, o.checked_mul(factor)?))
+                        })
+                    }
+                    Expression::Math(Negate(a)) => term(a)
+                        .and_then(|(base, s, o)| Some((base, s.checked_neg()?, o.checked_neg()?))),
+                    _ => None,
+                };
+                if let Some((base, scale, offset)) = derived {
+                    induction_variables.insert(
+                        *value,
+                        InductionVariable::Derived {
+                            base,
+                            scale,
+                            offset,
+                        },
+                    );
+                    changed = true;
+                }
+            }
+        }
+        induction_variables
+    }
+}
+
+/// Checks whether `operand` merges `value` with other values, i.e., it is the value of the
+/// variable updated by `value` at the start of the iteration.
+fn merges(operand: &Operand, value: LocalValue) -> bool {
+    matches!(operand, Operand::Phi(ids) if ids.contains(&Identifier::Local(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, MethodBody},
+            method, Method,
+        },
+    };
+    use Instruction::{
+        BiPush, Goto, IAdd, IConst0, IConst1, IConst2, IInc, ILoad0, ILoad1, ILoad2, IMul, IReturn,
+        IStore1, IStore2, IfICmpGe, IfICmpLt, Return,
+    };
+
+    fn brew(descriptor: &str, instructions: Vec<(u16, Instruction)>) -> MokaIRMethod {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: "test".to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 3,
+                max_locals: 3,
+                instructions: instructions
+                    .into_iter()
+                    .map(|(pc, insn)| (pc.into(), insn))
+                    .collect::<BTreeMap<_, _>>()
+                    .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        method.brew().unwrap()
+    }
+
+    #[test]
+    fn nested_loops() {
+        // for (int i = 0; i < 10; i++) { for (int j = 0; j < 10; j++) {} }
+        let method = brew(
+            "()V",
+            vec![
+                (0, IConst0),
+                (1, IStore1),
+                (2, IConst0),
+                (3, IStore2),
+                (4, IInc(2, 1)),
+                (7, ILoad2),
+                (8, BiPush(10)),
+                (10, IfICmpLt(4.into())),
+                (13, IInc(1, 1)),
+                (16, ILoad1),
+                (17, BiPush(10)),
+                (19, IfICmpLt(2.into())),
+                (22, Return),
+            ],
+        );
+        let forest = LoopForest::of(&method);
+        let headers: Vec<_> = forest.iter().map(|it| it.header).collect();
+        assert_eq!(headers, vec![2.into(), 4.into()]);
+
+        let outer = forest.get(2.into()).unwrap();
+        assert_eq!(outer.parent, None);
+        assert_eq!(outer.latches, BTreeSet::from([19.into()]));
+        assert_eq!(outer.exits, BTreeSet::from([22.into()]));
+        let inner = forest.get(4.into()).unwrap();
+        assert_eq!(inner.parent, Some(2.into()));
+        assert_eq!(
+            inner.body,
+            BTreeSet::from([4.into(), 7.into(), 8.into(), 10.into()])
+        );
+
+        assert_eq!(
+            forest.innermost(8.into()).map(|it| it.header),
+            Some(4.into())
+        );
+        assert_eq!(
+            forest.innermost(13.into()).map(|it| it.header),
+            Some(2.into())
+        );
+        assert!(forest.innermost(22.into()).is_none());
+
+        // `j` is an induction variable of the inner loop only.
+        let j = LocalValue::new(4);
+        assert_eq!(
+            inner.induction_variables.get(&j),
+            Some(&InductionVariable::Basic {
+                initial: Operand::Just(Identifier::Local(LocalValue::new(2))),
+                stride: 1,
+            })
+        );
+        assert!(!outer.induction_variables.contains_key(&j));
+        assert!(outer.induction_variables.contains_key(&LocalValue::new(13)));
+    }
+
+    #[test]
+    fn invariants_and_induction_variables() {
+        // int sum = 0;
+        // for (int i = 0; i < n; i++) { sum += n * 2 + (i * 4 + 1); }
+        // return sum;
+        let method = brew(
+            "(I)I",
+            vec![
+                (0, IConst0),
+                (1, IStore1),
+                (2, IConst0),
+                (3, IStore2),
+                (4, ILoad2),
+                (5, ILoad0),
+                (6, IfICmpGe(28.into())),
+                (9, ILoad0),
+                (10, IConst2),
+                (11, IMul),
+                (12, ILoad2),
+                (13, BiPush(4)),
+                (15, IMul),
+                (16, IConst1),
+                (17, IAdd),
+                (18, IAdd),
+                (19, ILoad1),
+                (20, IAdd),
+                (21, IStore1),
+                (22, IInc(2, 1)),
+                (25, Goto(4.into())),
+                (28, ILoad1),
+                (29, IReturn),
+            ],
+        );
+        let forest = LoopForest::of(&method);
+        let the_loop = forest.get(4.into()).unwrap();
+        assert_eq!(the_loop.latches, BTreeSet::from([25.into()]));
+        assert_eq!(
+            the_loop.invariants,
+            [10, 11, 13, 16].into_iter().map(LocalValue::new).collect()
+        );
+
+        let i = LocalValue::new(22);
+        let expected = BTreeMap::from([
+            (
+                i,
+                InductionVariable::Basic {
+                    initial: Operand::Just(Identifier::Local(LocalValue::new(2))),
+                    stride: 1,
+                },
+            ),
+            (
+                LocalValue::new(15),
+                InductionVariable::Derived {
+                    base: i,
+                    scale: 4,
+                    offset: 0,
+                },
+            ),
+            (
+                LocalValue::new(17),
+                InductionVariable::Derived {
+                    base: i,
+                    scale: 4,
+                    offset: 1,
+                },
+            ),
+        ]);
+        assert_eq!(the_loop.induction_variables, expected);
+    }
+}
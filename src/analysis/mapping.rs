@@ -0,0 +1,535 @@
+//! De-obfuscation with the mapping files produced by `ProGuard` and R8.
+//!
+//! A mapping file (usually named `mapping.txt`) lists the original names of the renamed classes
+//! and members as follows.
+//! ```text
+//! com.example.Original -> a.a:
+//!     int count -> a
+//!     1:4:void run(java.lang.String[]):10:13 -> b
+//!     5:5:int helper():20:20 -> b
+//!     5:5:void run(java.lang.String[]):14 -> b
+//! ```
+//! The lines of a method may be prefixed with the range of the line numbers in the obfuscated
+//! code, and suffixed with the corresponding range in the original code.
+//! The methods inlined by R8 share the obfuscated line range with the methods they are inlined
+//! into, listed from the innermost to the outermost.
+
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use crate::{
+    jvm::{
+        references::{ClassRef, FieldRef, MethodRef},
+        remap::Remapper,
+    },
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+/// An error when parsing a mapping file.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Malformed mapping at line {line}: {content}")]
+pub struct ParseError {
+    /// The line number (starting from 1).
+    pub line: usize,
+    /// The content of the line.
+    pub content: String,
+}
+
+/// A mapping from the obfuscated names to the original names.
+#[derive(Debug, Clone, Default)]
+pub struct Mapping {
+    /// The mappings of the classes indexed by their obfuscated names.
+    classes: BTreeMap<String, ClassMapping>,
+    /// The obfuscated names of the classes indexed by their original names.
+    obfuscated_names: BTreeMap<String, String>,
+}
+
+/// The mapping of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassMapping {
+    /// The original binary name.
+    pub original_name: String,
+    /// The obfuscated binary name.
+    pub obfuscated_name: String,
+    /// The mappings of the fields.
+    pub fields: Vec<FieldMapping>,
+    /// The mappings of the methods, in the order they appear in the mapping file.
+    pub methods: Vec<MethodMapping>,
+}
+
+/// The mapping of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldMapping {
+    /// The original name.
+    pub original_name: String,
+    /// The obfuscated name.
+    pub obfuscated_name: String,
+    /// The type of the field in terms of the original class names.
+    pub field_type: FieldType,
+}
+
+/// The mapping of a method, or a method inlined into it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodMapping {
+    /// The original name.
+    pub original_name: String,
+    /// The obfuscated name.
+    pub obfuscated_name: String,
+    /// The descriptor of the method in terms of the original class names.
+    pub descriptor: MethodDescriptor,
+    /// The binary name of the class declaring the method, if it is not the class containing
+    /// this mapping, i.e., the method is inlined from another class.
+    pub original_class: Option<String>,
+    /// The range of the line numbers in the obfuscated code.
+    pub obfuscated_lines: Option<RangeInclusive<u32>>,
+    /// The range of the line numbers in the original code.
+    /// A single line denotes the call site of an inlined method.
+    pub original_lines: Option<RangeInclusive<u32>>,
+}
+
+impl MethodMapping {
+    /// Gets the original line of the obfuscated line `line`.
+    #[must_use]
+    pub fn original_line(&self, line: u32) -> Option<u32> {
+        let original_lines = self.original_lines.as_ref()?;
+        match &self.obfuscated_lines {
+            Some(obfuscated_lines)
+                if obfuscated_lines.contains(&line)
+                    && original_lines.end() - original_lines.start()
+                        == obfuscated_lines.end() - obfuscated_lines.start() =>
+            {
+                Some(original_lines.start() + (line - obfuscated_lines.start()))
+            }
+            _ => Some(*original_lines.start()),
+        }
+    }
+}
+
+/// A frame of a stack trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// The class declaring the method.
+    pub class: ClassRef,
+    /// The name of the method.
+    pub method_name: String,
+    /// The line number, if known.
+    pub line: Option<u32>,
+}
+
+impl Mapping {
+    /// Parses a mapping file.
+    ///
+    /// # Errors
+    /// See [`ParseError`].
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut classes: Vec<ClassMapping> = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let error = || ParseError {
+                line: idx + 1,
+                content: line.to_owned(),
+            };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            if line.starts_with(char::is_whitespace) {
+                let class = classes.last_mut().ok_or_else(error)?;
+                if trimmed.contains('(') {
+                    class.methods.push(parse_method(trimmed).ok_or_else(error)?);
+                } else {
+                    class.fields.push(parse_field(trimmed).ok_or_else(error)?);
+                }
+            } else {
+                let (original, obfuscated) = trimmed
+                    .strip_suffix(':')
+                    .and_then(|it| it.split_once(" -> "))
+                    .ok_or_else(error)?;
+                classes.push(ClassMapping {
+                    original_name: binary_name(original),
+                    obfuscated_name: binary_name(obfuscated),
+                    fields: Vec::new(),
+                    methods: Vec::new(),
+                });
+            }
+        }
+        let obfuscated_names = classes
+            .iter()
+            .map(|it| (it.original_name.clone(), it.obfuscated_name.clone()))
+            .collect();
+        let classes = classes
+            .into_iter()
+            .map(|it| (it.obfuscated_name.clone(), it))
+            .collect();
+        Ok(Self {
+            classes,
+            obfuscated_names,
+        })
+    }
+
+    /// Returns an iterator over the mappings of the classes.
+    pub fn classes(&self) -> impl Iterator<Item = &ClassMapping> {
+        self.classes.values()
+    }
+
+    /// Gets the mapping of the class with the obfuscated name `class`.
+    #[must_use]
+    pub fn class(&self, class: &ClassRef) -> Option<&ClassMapping> {
+        self.classes.get(&*class.binary_name)
+    }
+
+    /// Gets the original class of the obfuscated class `class`.
+    /// Classes not in the mapping are returned unchanged.
+    #[must_use]
+    pub fn original_class(&self, class: &ClassRef) -> ClassRef {
+        self.class(class)
+            .map_or_else(|| class.clone(), |it| ClassRef::new(&it.original_name))
+    }
+
+    /// Gets the type in terms of the original class names.
+    #[must_use]
+    pub fn original_type(&self, field_type: &FieldType) -> FieldType {
+        map_type(field_type, &|it| self.original_class(it))
+    }
+
+    /// Gets the descriptor in terms of the original class names.
+    #[must_use]
+    pub fn original_descriptor(&self, descriptor: &MethodDescriptor) -> MethodDescriptor {
+        map_descriptor(descriptor, &|it| self.original_class(it))
+    }
+
+    /// Gets the mapping of the obfuscated field `field`.
+    #[must_use]
+    pub fn field(&self, field: &FieldRef) -> Option<&FieldMapping> {
+        let original_type = self.original_type(&field.field_type);
+        self.class(&field.owner)?
+            .fields
+            .iter()
+            .find(|it| it.obfuscated_name == *field.name && it.field_type == original_type)
+    }
+
+    /// Gets the original field of the obfuscated field `field`.
+    #[must_use]
+    pub fn original_field(&self, field: &FieldRef) -> FieldRef {
+        let name = self
+            .field(field)
+            .map_or_else(|| field.name.to_string(), |it| it.original_name.clone());
+        FieldRef::new(
+            self.original_class(&field.owner),
+            name,
+            self.original_type(&field.field_type),
+        )
+    }
+
+    /// Gets the mapping of the obfuscated method `method`.
+    /// If there are multiple mappings (e.g., because of inlining), the last one, i.e., the
+    /// outermost method, is returned.
+    #[must_use]
+    pub fn method(&self, method: &MethodRef) -> Option<&MethodMapping> {
+        let original_descriptor = self.original_descriptor(&method.descriptor);
+        self.class(&method.owner)?.methods.iter().rfind(|it| {
+            it.obfuscated_name == *method.name
+                && it.original_class.is_none()
+                && it.descriptor == original_descriptor
+        })
+    }
+
+    /// Gets the original method of the obfuscated method `method`.
+    #[must_use]
+    pub fn original_method(&self, method: &MethodRef) -> MethodRef {
+        let name = self
+            .method(method)
+            .map_or_else(|| method.name.to_string(), |it| it.original_name.clone());
+        MethodRef::new(
+            self.original_class(&method.owner),
+            name,
+            self.original_descriptor(&method.descriptor),
+        )
+    }
+
+    /// Gets the obfuscated class of the original class `class`.
+    /// Classes not in the mapping are returned unchanged.
+    #[must_use]
+    pub fn obfuscated_class(&self, class: &ClassRef) -> ClassRef {
+        self.obfuscated_names
+            .get(&*class.binary_name)
+            .map_or_else(|| class.clone(), ClassRef::new)
+    }
+
+    /// Creates a [`Remapper`] renaming the obfuscated classes and members to their original
+    /// names, e.g., to de-obfuscate the classes with [`Remapper::remap_class`].
+    #[must_use]
+    pub fn to_remapper(&self) -> Remapper {
+        let obfuscated = |it: &ClassRef| self.obfuscated_class(it);
+        let mut remapper = Remapper::new();
+        for class in self.classes.values() {
+            remapper = remapper.with_class(&class.obfuscated_name, &class.original_name);
+            let owner = ClassRef::new(&class.obfuscated_name);
+            for field in &class.fields {
+                let field_ref = FieldRef::new(
+                    owner.clone(),
+                    &field.obfuscated_name,
+                    map_type(&field.field_type, &obfuscated),
+                );
+                remapper = remapper.with_field(field_ref, &field.original_name);
+            }
+            for method in class
+                .methods
+                .iter()
+                .filter(|it| it.original_class.is_none())
+            {
+                let method_ref = MethodRef::new(
+                    owner.clone(),
+                    &method.obfuscated_name,
+                    map_descriptor(&method.descriptor, &obfuscated),
+                );
+                remapper = remapper.with_method(method_ref, &method.original_name);
+            }
+        }
+        remapper
+    }
+
+    /// Recovers the original frames of an obfuscated stack frame.
+    /// A frame in an obfuscated method may correspond to multiple frames, from the innermost to
+    /// the outermost, if other methods are inlined into it.
+    /// If the method cannot be determined without the line number, the frames of all the
+    /// candidates are returned.
+    #[must_use]
+    pub fn retrace(&self, frame: &StackFrame) -> Vec<StackFrame> {
+        let original_class = self.original_class(&frame.class);
+        let Some(class) = self.class(&frame.class) else {
+            return vec![frame.clone()];
+        };
+        let candidates: Vec<_> = class
+            .methods
+            .iter()
+            .filter(|it| it.obfuscated_name == frame.method_name)
+            .collect();
+        let by_line: Vec<_> = candidates
+            .iter()
+            .filter(|it| {
+                matches!(
+                    (&it.obfuscated_lines, frame.line),
+                    (Some(lines), Some(line)) if lines.contains(&line)
+                )
+            })
+            .collect();
+        let to_frame = |it: &MethodMapping, line: Option<u32>| StackFrame {
+            class: it
+                .original_class
+                .as_ref()
+                .map_or_else(|| original_class.clone(), ClassRef::new),
+            method_name: it.original_name.clone(),
+            line,
+        };
+        if by_line.is_empty() {
+            let mut frames: Vec<StackFrame> = Vec::new();
+            for it in candidates {
+                let frame = to_frame(it, None);
+                if !frames.contains(&frame) {
+                    frames.push(frame);
+                }
+            }
+            if frames.is_empty() {
+                frames.push(StackFrame {
+                    class: original_class,
+                    ..frame.clone()
+                });
+            }
+            frames
+        } else {
+            by_line
+                .into_iter()
+                .map(|it| to_frame(it, frame.line.and_then(|line| it.original_line(line))))
+                .collect()
+        }
+    }
+}
+
+fn binary_name(java_name: &str) -> String {
+    java_name.trim().replace('.', "/")
+}
+
+fn parse_field(line: &str) -> Option<FieldMapping> {
+    let (declaration, obfuscated_name) = line.split_once(" -> ")?;
+    let (field_type, original_name) = declaration.trim().rsplit_once(' ')?;
+    Some(FieldMapping {
+        original_name: original_name.to_owned(),
+        obfuscated_name: obfuscated_name.trim().to_owned(),
+        field_type: parse_java_type(field_type)?,
+    })
+}
+
+fn parse_method(line: &str) -> Option<MethodMapping> {
+    let (declaration, obfuscated_name) = line.split_once(" -> ")?;
+    let mut declaration = declaration.trim();
+    // The leading `start:end:` is the obfuscated line range.
+    let mut obfuscated_lines = None;
+    if declaration.starts_with(|it: char| it.is_ascii_digit()) {
+        let mut parts = declaration.splitn(3, ':');
+        let start = parts.next()?.parse().ok()?;
+        let end = parts.next()?.parse().ok()?;
+        declaration = parts.next()?;
+        obfuscated_lines = Some(start..=end);
+    }
+    let (signature, suffix) = declaration.split_once(')')?;
+    let (head, parameters) = signature.split_once('(')?;
+    let (return_type, qualified_name) = head.trim().rsplit_once(' ')?;
+    let (original_class, original_name) = match qualified_name.rsplit_once('.') {
+        Some((class, name)) => (Some(binary_name(class)), name),
+        None => (None, qualified_name),
+    };
+    let original_lines = match suffix.strip_prefix(':') {
+        None if suffix.is_empty() => None,
+        None => return None,
+        Some(lines) => {
+            let (start, end) = lines.split_once(':').unwrap_or((lines, lines));
+            Some(start.parse().ok()?..=end.parse().ok()?)
+        }
+    };
+    let parameters_types = parameters
+        .split(',')
+        .map(str::trim)
+        .filter(|it| !it.is_empty())
+        .map(parse_java_type)
+        .collect::<Option<Vec<_>>>()?;
+    let return_type = match return_type {
+        "void" => ReturnType::Void,
+        it => ReturnType::Some(parse_java_type(it)?),
+    };
+    Some(MethodMapping {
+        original_name: original_name.to_owned(),
+        obfuscated_name: obfuscated_name.trim().to_owned(),
+        descriptor: MethodDescriptor::new(parameters_types, return_type),
+        original_class,
+        obfuscated_lines,
+        original_lines,
+    })
+}
+
+/// Parses a type written in the Java syntax, e.g., `java.lang.String[]`.
+fn parse_java_type(name: &str) -> Option<FieldType> {
+    if let Some(element_type) = name.strip_suffix("[]") {
+        return parse_java_type(element_type).map(FieldType::into_array_type);
+    }
+    let primitive_type = match name {
+        "boolean" => PrimitiveType::Boolean,
+        "char" => PrimitiveType::Char,
+        "float" => PrimitiveType::Float,
+        "double" => PrimitiveType::Double,
+        "byte" => PrimitiveType::Byte,
+        "short" => PrimitiveType::Short,
+        "int" => PrimitiveType::Int,
+        "long" => PrimitiveType::Long,
+        "" | "void" => return None,
+        it => return Some(FieldType::Object(ClassRef::new(binary_name(it)))),
+    };
+    Some(FieldType::Base(primitive_type))
+}
+
+fn map_type(field_type: &FieldType, map_class: &impl Fn(&ClassRef) -> ClassRef) -> FieldType {
+    match field_type {
+        FieldType::Base(_) => field_type.clone(),
+        FieldType::Object(class) => FieldType::Object(map_class(class)),
+        FieldType::Array(element_type) => map_type(element_type, map_class).into_array_type(),
+    }
+}
+
+fn map_descriptor(
+    descriptor: &MethodDescriptor,
+    map_class: &impl Fn(&ClassRef) -> ClassRef,
+) -> MethodDescriptor {
+    MethodDescriptor::new(
+        descriptor
+            .parameters_types
+            .iter()
+            .map(|it| map_type(it, map_class))
+            .collect::<Vec<_>>(),
+        match &descriptor.return_type {
+            ReturnType::Void => ReturnType::Void,
+            ReturnType::Some(it) => ReturnType::Some(map_type(it, map_class)),
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAPPING: &str = "
+# compiler: R8
+com.example.Main -> a.a:
+    com.example.Helper helper -> a
+    1:4:void main(java.lang.String[]):10:13 -> main
+    5:5:int com.example.Helper.compute(int):30:30 -> main
+    5:5:void main(java.lang.String[]):14 -> main
+    void run(com.example.Helper) -> b
+com.example.Helper -> a.b:
+    int compute(int) -> a
+";
+
+    #[test]
+    fn members() {
+        let mapping = Mapping::parse(MAPPING).unwrap();
+        assert_eq!(mapping.classes().count(), 2);
+        let helper = FieldRef::new(ClassRef::new("a/a"), "a", "La/b;".parse().unwrap());
+        assert_eq!(
+            mapping.original_field(&helper),
+            FieldRef::new(
+                ClassRef::new("com/example/Main"),
+                "helper",
+                "Lcom/example/Helper;".parse().unwrap()
+            )
+        );
+        let run = MethodRef::new(ClassRef::new("a/a"), "b", "(La/b;)V".parse().unwrap());
+        let original_run = mapping.original_method(&run);
+        assert_eq!(&*original_run.name, "run");
+        assert_eq!(
+            original_run.descriptor.descriptor(),
+            "(Lcom/example/Helper;)V"
+        );
+        assert_eq!(
+            mapping.obfuscated_class(&ClassRef::new("com/example/Helper")),
+            ClassRef::new("a/b")
+        );
+        let unknown = MethodRef::new(ClassRef::new("a/a"), "c", "()V".parse().unwrap());
+        assert_eq!(&*mapping.original_method(&unknown).name, "c");
+        let remapper = mapping.to_remapper();
+        assert_eq!(remapper.map_method(&run), original_run);
+        assert_eq!(remapper.map_field(&helper), mapping.original_field(&helper));
+        assert_eq!(Mapping::parse("a.B -> c:\n  bad").unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn retrace_inlined_frames() {
+        let mapping = Mapping::parse(MAPPING).unwrap();
+        let frame = |class: &str, method_name: &str, line| StackFrame {
+            class: ClassRef::new(class),
+            method_name: method_name.to_owned(),
+            line,
+        };
+        assert_eq!(
+            mapping.retrace(&frame("a/a", "main", Some(3))),
+            vec![frame("com/example/Main", "main", Some(12))]
+        );
+        assert_eq!(
+            mapping.retrace(&frame("a/a", "main", Some(5))),
+            vec![
+                frame("com/example/Helper", "compute", Some(30)),
+                frame("com/example/Main", "main", Some(14)),
+            ]
+        );
+        assert_eq!(
+            mapping.retrace(&frame("a/a", "main", None)),
+            vec![
+                frame("com/example/Main", "main", None),
+                frame("com/example/Helper", "compute", None),
+            ]
+        );
+        assert_eq!(
+            mapping.retrace(&frame("x/Y", "z", Some(1))),
+            vec![frame("x/Y", "z", Some(1))]
+        );
+    }
+}
@@ -0,0 +1,711 @@
+//! Size and complexity metrics of methods and classes for reporting.
+//!
+//! [`MethodMetrics`] and [`ClassMetrics`] are computed from the bytecode, while [`IrMetrics`]
+//! are computed from the Moka IR, where the expressions are reconstructed into trees.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    ir::{
+        expression::{
+            ArrayOperation, Condition, Conversion, Expression, FieldAccess, LockOperation,
+            MathOperation,
+        },
+        Identifier, LocalValue, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::{
+        code::{Frame, Instruction, MethodBody, WideInstruction},
+        method,
+        references::MethodRef,
+        Class, Method,
+    },
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+/// The metrics of a method with a body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodMetrics {
+    /// The size of the bytecode in bytes.
+    pub bytecode_size: u32,
+    /// The number of instructions.
+    pub instruction_count: usize,
+    /// The number of occurrences of each instruction, keyed by mnemonic.
+    pub instruction_histogram: BTreeMap<&'static str, usize>,
+    /// The cyclomatic complexity, i.e., the number of decision points in the normal control flow
+    /// plus one. Exception handlers are not counted as decision points.
+    pub cyclomatic_complexity: usize,
+    /// The maximum depth of the operand stack declared by the method.
+    pub max_stack: u16,
+    /// The maximum depth of the operand stack reached by the instructions, or [`None`] if it
+    /// cannot be computed because the frames of the method cannot be inferred.
+    pub peak_stack: Option<u16>,
+    /// The number of local variable slots declared by the method.
+    pub max_locals: u16,
+    /// The number of local variable slots taken by the arguments or accessed by the
+    /// instructions.
+    pub used_locals: u16,
+    /// The number of `invoke*` instructions.
+    pub call_sites: usize,
+    /// The number of entries in the exception table.
+    pub exception_handlers: usize,
+}
+
+impl MethodMetrics {
+    /// Computes the metrics of `method`, or returns [`None`] if it has no body.
+    #[must_use]
+    pub fn of(method: &Method) -> Option<Self> {
+        let body = method.body.as_ref()?;
+        let bytecode_size = body.instructions.code_length();
+        let mut instruction_histogram = BTreeMap::new();
+        for (_, insn) in &body.instructions {
+            *instruction_histogram.entry(insn.name()).or_default() += 1;
+        }
+        let cyclomatic_complexity = 1 + body
+            .basic_blocks()
+            .iter()
+            .map(|it| it.successors.len().saturating_sub(1))
+            .sum::<usize>();
+        let peak_stack = body.infer_frames(method).ok().map(|frames| {
+            let depth = frames.values().map(Frame::stack_depth).max();
+            depth.map_or(0, |it| u16::try_from(it).unwrap_or(u16::MAX))
+        });
+        let call_sites = body
+            .instructions
+            .iter()
+            .filter(|(_, insn)| is_call(insn))
+            .count();
+        Some(Self {
+            bytecode_size,
+            instruction_count: body.instructions.iter().count(),
+            instruction_histogram,
+            cyclomatic_complexity,
+            max_stack: body.max_stack,
+            peak_stack,
+            max_locals: body.max_locals,
+            used_locals: used_locals(method, body),
+            call_sites,
+            exception_handlers: body.exception_table.len(),
+        })
+    }
+}
+
+/// The metrics of a class, aggregated from the metrics of its methods.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClassMetrics {
+    /// The metrics of the methods with a body.
+    pub methods: BTreeMap<MethodRef, MethodMetrics>,
+    /// The number of methods without a body, i.e., `abstract` or `native` methods.
+    pub methods_without_body: usize,
+    /// The total size of the bytecode in bytes.
+    pub bytecode_size: u64,
+    /// The number of occurrences of each instruction in all methods, keyed by mnemonic.
+    pub instruction_histogram: BTreeMap<&'static str, usize>,
+    /// The sum of the cyclomatic complexities of the methods.
+    pub total_cyclomatic_complexity: usize,
+    /// The highest cyclomatic complexity of the methods.
+    pub max_cyclomatic_complexity: usize,
+    /// The number of `invoke*` instructions in all methods.
+    pub call_sites: usize,
+    /// The number of exception table entries in all methods.
+    pub exception_handlers: usize,
+}
+
+impl ClassMetrics {
+    /// Computes the metrics of `class`.
+    #[must_use]
+    pub fn of(class: &Class) -> Self {
+        let mut metrics = Self::default();
+        for method in &class.methods {
+            let Some(method_metrics) = MethodMetrics::of(method) else {
+                metrics.methods_without_body += 1;
+                continue;
+            };
+            metrics.bytecode_size += u64::from(method_metrics.bytecode_size);
+            for (name, count) in &method_metrics.instruction_histogram {
+                *metrics.instruction_histogram.entry(name).or_default() += count;
+            }
+            metrics.total_cyclomatic_complexity += method_metrics.cyclomatic_complexity;
+            metrics.max_cyclomatic_complexity = metrics
+                .max_cyclomatic_complexity
+                .max(method_metrics.cyclomatic_complexity);
+            metrics.call_sites += method_metrics.call_sites;
+            metrics.exception_handlers += method_metrics.exception_handlers;
+            metrics.methods.insert(method.as_ref(), method_metrics);
+        }
+        metrics
+    }
+}
+
+/// The Halstead measures of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HalsteadMetrics {
+    /// The number of distinct operators.
+    pub distinct_operators: usize,
+    /// The number of distinct operands, i.e., values and constants.
+    pub distinct_operands: usize,
+    /// The total number of occurrences of operators.
+    pub total_operators: usize,
+    /// The total number of occurrences of operands.
+    pub total_operands: usize,
+}
+
+impl HalsteadMetrics {
+    /// Gets the vocabulary, i.e., the number of distinct operators and operands.
+    #[must_use]
+    pub const fn vocabulary(&self) -> usize {
+        self.distinct_operators + self.distinct_operands
+    }
+
+    /// Gets the length, i.e., the total number of operators and operands.
+    #[must_use]
+    pub const fn length(&self) -> usize {
+        self.total_operators + self.total_operands
+    }
+
+    /// Gets the volume, i.e., the length times the base-2 logarithm of the vocabulary.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, reason = "The counts are far below 2^52")]
+    pub fn volume(&self) -> f64 {
+        let vocabulary = self.vocabulary();
+        if vocabulary == 0 {
+            return 0.0;
+        }
+        self.length() as f64 * (vocabulary as f64).log2()
+    }
+
+    /// Gets the difficulty, i.e., how hard the method is to understand.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, reason = "The counts are far below 2^52")]
+    pub fn difficulty(&self) -> f64 {
+        if self.distinct_operands == 0 {
+            return 0.0;
+        }
+        (self.distinct_operators as f64 / 2.0)
+            * (self.total_operands as f64 / self.distinct_operands as f64)
+    }
+
+    /// Gets the effort, i.e., the difficulty times the volume.
+    #[must_use]
+    pub fn effort(&self) -> f64 {
+        self.difficulty() * self.volume()
+    }
+}
+
+/// The metrics of a method computed from its Moka IR.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IrMetrics {
+    /// The Halstead measures.
+    pub halstead: HalsteadMetrics,
+    /// The number of instructions, excluding `nop`s.
+    pub instruction_count: usize,
+    /// The cyclomatic complexity, i.e., the number of decision points plus one.
+    pub cyclomatic_complexity: usize,
+    /// The number of distinct values defined or used, including the arguments.
+    pub distinct_identifiers: usize,
+    /// The depth of the deepest expression tree, where a value used exactly once is inlined into
+    /// its use. An expression whose operands are not inlined has a depth of one.
+    pub max_expression_depth: usize,
+}
+
+impl IrMetrics {
+    /// Computes the metrics of `method`.
+    #[must_use]
+    pub fn of(method: &MokaIRMethod) -> Self {
+        let mut operators = BTreeMap::<String, usize>::new();
+        let mut operands = BTreeMap::<String, usize>::new();
+        let mut identifiers = BTreeSet::new();
+        let mut instruction_count = 0;
+        let mut cyclomatic_complexity = 1;
+        for (_, insn) in &method.instructions {
+            if matches!(insn, MokaInstruction::Nop) {
+                continue;
+            }
+            instruction_count += 1;
+            cyclomatic_complexity += decision_points(insn);
+            for operator in operators_of(insn) {
+                *operators.entry(operator).or_default() += 1;
+            }
+            for id in identifiers_of(insn) {
+                identifiers.insert(id);
+                *operands.entry(id.to_string()).or_default() += 1;
+            }
+            if let MokaInstruction::Definition {
+                expr: Expression::Const(constant),
+                ..
+            } = insn
+            {
+                *operands.entry(constant.to_string()).or_default() += 1;
+            }
+        }
+        let halstead = HalsteadMetrics {
+            distinct_operators: operators.len(),
+            distinct_operands: operands.len(),
+            total_operators: operators.values().sum(),
+            total_operands: operands.values().sum(),
+        };
+        Self {
+            halstead,
+            instruction_count,
+            cyclomatic_complexity,
+            distinct_identifiers: identifiers.len(),
+            max_expression_depth: ExpressionTrees::of(method).max_depth(),
+        }
+    }
+
+    /// Gets the maintainability index normalized to the range from 0 to 100, where a higher value
+    /// means the method is easier to maintain. It is computed from the Halstead volume, the
+    /// cyclomatic complexity, and the number of instructions, which takes the place of the
+    /// number of lines of code.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss, reason = "The counts are far below 2^52")]
+    pub fn maintainability_index(&self) -> f64 {
+        let volume = self.halstead.volume().max(1.0);
+        let lines = (self.instruction_count as f64).max(1.0);
+        let raw = 171.0
+            - 5.2 * volume.ln()
+            - 0.23 * self.cyclomatic_complexity as f64
+            - 16.2 * lines.ln();
+        (raw * 100.0 / 171.0).clamp(0.0, 100.0)
+    }
+}
+
+fn decision_points(insn: &MokaInstruction) -> usize {
+    match insn {
+        MokaInstruction::Jump {
+            condition: Some(_), ..
+        } => 1,
+        MokaInstruction::Switch {
+            default, branches, ..
+        } => {
+            let targets: BTreeSet<_> = branches.values().chain([default]).collect();
+            targets.len() - 1
+        }
+        _ => 0,
+    }
+}
+
+/// Gets the identifiers defined or used by `insn`, with repetition.
+fn identifiers_of(insn: &MokaInstruction) -> Vec<Identifier> {
+    let mut ids = used_identifiers(insn);
+    if let MokaInstruction::Definition { value, .. } | MokaInstruction::Phi { value, .. } = insn {
+        ids.push(Identifier::Local(*value));
+    }
+    ids
+}
+
+/// Gets the identifiers used by `insn`, with repetition.
+fn used_identifiers(insn: &MokaInstruction) -> Vec<Identifier> {
+    if let MokaInstruction::Phi { incoming, .. } = insn {
+        return incoming.values().copied().collect();
+    }
+    operands_of(insn).into_iter().flatten().collect()
+}
+
+fn operands_of(insn: &MokaInstruction) -> Vec<Operand> {
+    // The operands are only exposed mutably, so a copy of the instruction is traversed.
+    let mut copy = insn.clone();
+    copy.operands_mut()
+        .into_iter()
+        .map(|it| it.clone())
+        .collect()
+}
+
+fn operators_of(insn: &MokaInstruction) -> Vec<String> {
+    match insn {
+        MokaInstruction::Nop => Vec::new(),
+        MokaInstruction::Definition {
+            expr: Expression::Const(_),
+            ..
+        } => vec!["=".to_owned()],
+        MokaInstruction::Definition { expr, .. } => {
+            vec!["=".to_owned(), expression_operator(expr)]
+        }
+        MokaInstruction::Jump {
+            condition: Some(condition),
+            ..
+        } => vec!["if".to_owned(), condition_operator(condition).to_owned()],
+        MokaInstruction::Jump {
+            condition: None, ..
+        } => vec!["goto".to_owned()],
+        MokaInstruction::Switch { .. } => vec!["switch".to_owned()],
+        MokaInstruction::Return(_) => vec!["return".to_owned()],
+        MokaInstruction::SubroutineRet(_) => vec!["ret".to_owned()],
+        MokaInstruction::Phi { .. } => vec!["phi".to_owned()],
+    }
+}
+
+fn expression_operator(expr: &Expression) -> String {
+    let operator = match expr {
+        Expression::Const(_) => "const",
+        Expression::Call { method, .. } => return format!("call {}", method.name),
+        Expression::Closure { name, .. } => return format!("closure {name}"),
+        Expression::Math(operation) => math_operator(operation),
+        Expression::Field(access) => match access {
+            FieldAccess::ReadStatic { .. } => "getstatic",
+            FieldAccess::WriteStatic { .. } => "putstatic",
+            FieldAccess::ReadInstance { .. } => "getfield",
+            FieldAccess::WriteInstance { .. } => "putfield",
+        },
+        Expression::Array(operation) => match operation {
+            ArrayOperation::New { .. } => "new[]",
+            ArrayOperation::NewMultiDim { .. } => "new[][]",
+            ArrayOperation::Read { .. } => "[]",
+            ArrayOperation::Write { .. } => "[]=",
+            ArrayOperation::Length { .. } => "length",
+        },
+        Expression::Conversion(conversion) => conversion_operator(conversion),
+        Expression::Throw(_) => "throw",
+        Expression::Synchronization(LockOperation::Acquire(_)) => "monitorenter",
+        Expression::Synchronization(LockOperation::Release(_)) => "monitorexit",
+        Expression::New(_) => "new",
+        Expression::Subroutine { .. } => "jsr",
+    };
+    operator.to_owned()
+}
+
+const fn math_operator(operation: &MathOperation) -> &'static str {
+    match operation {
+        MathOperation::Add(..) => "+",
+        MathOperation::Subtract(..) => "-",
+        MathOperation::Multiply(..) => "*",
+        MathOperation::Divide(..) => "/",
+        MathOperation::Remainder(..) => "%",
+        MathOperation::Negate(_) => "neg",
+        MathOperation::Increment(..) => "+=",
+        MathOperation::ShiftLeft(..) => "<<",
+        MathOperation::ShiftRight(..) => ">>",
+        MathOperation::LogicalShiftRight(..) => ">>>",
+        MathOperation::BitwiseAnd(..) => "&",
+        MathOperation::BitwiseOr(..) => "|",
+        MathOperation::BitwiseXor(..) => "^",
+        MathOperation::LongComparison(..) | MathOperation::FloatingPointComparison(..) => "cmp",
+    }
+}
+
+const fn conversion_operator(conversion: &Conversion) -> &'static str {
+    match conversion {
+        Conversion::Int2Long(_) | Conversion::Float2Long(_) | Conversion::Double2Long(_) => {
+            "(long)"
+        }
+        Conversion::Int2Float(_) | Conversion::Long2Float(_) | Conversion::Double2Float(_) => {
+            "(float)"
+        }
+        Conversion::Int2Double(_) | Conversion::Long2Double(_) | Conversion::Float2Double(_) => {
+            "(double)"
+        }
+        Conversion::Long2Int(_) | Conversion::Float2Int(_) | Conversion::Double2Int(_) => "(int)",
+        Conversion::Int2Byte(_) => "(byte)",
+        Conversion::Int2Char(_) => "(char)",
+        Conversion::Int2Short(_) => "(short)",
+        Conversion::CheckCast(..) => "checkcast",
+        Conversion::InstanceOf(..) => "instanceof",
+    }
+}
+
+const fn condition_operator(condition: &Condition) -> &'static str {
+    match condition {
+        Condition::Equal(..) | Condition::IsNull(_) | Condition::IsZero(_) => "==",
+        Condition::NotEqual(..) | Condition::IsNotNull(_) | Condition::IsNonZero(_) => "!=",
+        Condition::LessThan(..) | Condition::IsNegative(_) => "<",
+        Condition::LessThanOrEqual(..) | Condition::IsNonPositive(_) => "<=",
+        Condition::GreaterThan(..) | Condition::IsPositive(_) => ">",
+        Condition::GreaterThanOrEqual(..) | Condition::IsNonNegative(_) => ">=",
+    }
+}
+
+/// The expression trees reconstructed by inlining the values used exactly once.
+struct ExpressionTrees<'a> {
+    /// The definitions of the values that are used exactly once.
+    inlinable: HashMap<LocalValue, &'a Expression>,
+    method: &'a MokaIRMethod,
+}
+
+impl<'a> ExpressionTrees<'a> {
+    fn of(method: &'a MokaIRMethod) -> Self {
+        let mut use_counts = HashMap::<LocalValue, usize>::new();
+        for (_, insn) in &method.instructions {
+            for id in used_identifiers(insn) {
+                if let Identifier::Local(value) = id {
+                    *use_counts.entry(value).or_default() += 1;
+                }
+            }
+        }
+        let inlinable = method
+            .instructions
+            .iter()
+            .filter_map(|(_, insn)| match insn {
+                MokaInstruction::Definition { value, expr }
+                    if use_counts.get(value) == Some(&1) =>
+                {
+                    Some((*value, expr))
+                }
+                _ => None,
+            })
+            .collect();
+        Self { inlinable, method }
+    }
+
+    fn max_depth(&self) -> usize {
+        let mut depths = HashMap::new();
+        self.method
+            .instructions
+            .iter()
+            .map(|(_, insn)| {
+                let children = self.depth_of_operands(&operands_of(insn), &mut depths);
+                match insn {
+                    MokaInstruction::Nop | MokaInstruction::Phi { .. } => 0,
+                    _ => 1 + children,
+                }
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn depth_of_operands(
+        &self,
+        operands: &[Operand],
+        depths: &mut HashMap<LocalValue, usize>,
+    ) -> usize {
+        operands
+            .iter()
+            .filter_map(|it| match it {
+                Operand::Just(Identifier::Local(value)) => Some(self.depth_of(*value, depths)),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Gets the depth of the tree of the expression defining `value` if it is inlined, or `0`.
+    fn depth_of(&self, value: LocalValue, depths: &mut HashMap<LocalValue, usize>) -> usize {
+        if let Some(depth) = depths.get(&value) {
+            return *depth;
+        }
+        let Some(expr) = self.inlinable.get(&value) else {
+            return 0;
+        };
+        // Guards against cycles, which do not occur in well-formed IR.
+        depths.insert(value, 0);
+        let mut copy = (*expr).clone();
+        let operands: Vec<_> = copy
+            .operands_mut()
+            .into_iter()
+            .map(|it| it.clone())
+            .collect();
+        let depth = 1 + self.depth_of_operands(&operands, depths);
+        depths.insert(value, depth);
+        depth
+    }
+}
+
+const fn is_call(insn: &Instruction) -> bool {
+    matches!(
+        insn,
+        Instruction::InvokeVirtual(_)
+            | Instruction::InvokeSpecial(_)
+            | Instruction::InvokeStatic(_)
+            | Instruction::InvokeInterface(..)
+            | Instruction::InvokeDynamic { .. }
+    )
+}
+
+fn used_locals(method: &Method, body: &MethodBody) -> u16 {
+    let mut slots = BTreeSet::new();
+    let mut next = 0u16;
+    if !method.access_flags.contains(method::AccessFlags::STATIC) {
+        slots.insert(next);
+        next += 1;
+    }
+    for param in method.descriptor.parameters_types.iter() {
+        let width = if is_wide(param) { 2 } else { 1 };
+        slots.extend(next..next.saturating_add(width));
+        next = next.saturating_add(width);
+    }
+    for (_, insn) in &body.instructions {
+        if let Some((index, width)) = local_access(insn) {
+            slots.extend(index..index.saturating_add(width));
+        }
+    }
+    u16::try_from(slots.len()).unwrap_or(u16::MAX)
+}
+
+const fn is_wide(field_type: &FieldType) -> bool {
+    matches!(
+        field_type,
+        FieldType::Base(PrimitiveType::Long | PrimitiveType::Double)
+    )
+}
+
+/// Gets the index of the local variable accessed by `insn` and the number of slots it takes.
+fn local_access(insn: &Instruction) -> Option<(u16, u16)> {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let access = match insn {
+        ILoad(it)
+        | FLoad(it)
+        | ALoad(it)
+        | IStore(it)
+        | FStore(it)
+        | AStore(it)
+        | Ret(it)
+        | IInc(it, _) => (u16::from(*it), 1),
+        LLoad(it) | DLoad(it) | LStore(it) | DStore(it) => (u16::from(*it), 2),
+        ILoad0 | FLoad0 | ALoad0 | IStore0 | FStore0 | AStore0 => (0, 1),
+        ILoad1 | FLoad1 | ALoad1 | IStore1 | FStore1 | AStore1 => (1, 1),
+        ILoad2 | FLoad2 | ALoad2 | IStore2 | FStore2 | AStore2 => (2, 1),
+        ILoad3 | FLoad3 | ALoad3 | IStore3 | FStore3 | AStore3 => (3, 1),
+        LLoad0 | DLoad0 | LStore0 | DStore0 => (0, 2),
+        LLoad1 | DLoad1 | LStore1 | DStore1 => (1, 2),
+        LLoad2 | DLoad2 | LStore2 | DStore2 => (2, 2),
+        LLoad3 | DLoad3 | LStore3 | DStore3 => (3, 2),
+        Wide(
+            WideInstruction::ILoad(it)
+            | WideInstruction::FLoad(it)
+            | WideInstruction::ALoad(it)
+            | WideInstruction::IStore(it)
+            | WideInstruction::FStore(it)
+            | WideInstruction::AStore(it)
+            | WideInstruction::IInc(it, _)
+            | WideInstruction::Ret(it),
+        ) => (*it, 1),
+        Wide(
+            WideInstruction::LLoad(it)
+            | WideInstruction::DLoad(it)
+            | WideInstruction::LStore(it)
+            | WideInstruction::DStore(it),
+        ) => (*it, 2),
+        _ => return None,
+    };
+    Some(access)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ir::MokaIRMethodExt, jvm::interop::jasmin};
+
+    #[test]
+    fn method_and_class_metrics() {
+        let class = jasmin::read(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static sign(J)I
+                .limit stack 4
+                .limit locals 4
+                .catch java/lang/Exception from Begin to End using Handler
+                lload_0
+                lconst_0
+                lcmp
+                tableswitch -1
+                    Negative
+                    Zero
+                    Positive
+                    default : Zero
+            Negative:
+                iconst_m1
+                ireturn
+            Zero:
+                iconst_0
+                ireturn
+            Positive:
+            Begin:
+                invokestatic Test/one()I
+            End:
+                ireturn
+            Handler:
+                pop
+                iconst_1
+                ireturn
+            .end method
+            .method public native run()V
+            .end method
+            ",
+        )
+        .unwrap();
+        let metrics = MethodMetrics::of(&class.methods[0]).unwrap();
+        assert_eq!(metrics.instruction_count, 13);
+        assert_eq!(metrics.instruction_histogram["ireturn"], 4);
+        // `lcmp` at 2, `tableswitch` at 3 padded to 4 with 3 targets.
+        assert_eq!(metrics.bytecode_size, 4 + 12 + 12 + 2 + 2 + 3 + 1 + 3);
+        assert_eq!(metrics.cyclomatic_complexity, 3);
+        assert_eq!(metrics.max_stack, 4);
+        assert_eq!(metrics.peak_stack, Some(4));
+        assert_eq!(metrics.max_locals, 4);
+        assert_eq!(metrics.used_locals, 2);
+        assert_eq!(metrics.call_sites, 1);
+        assert_eq!(metrics.exception_handlers, 1);
+
+        let class_metrics = ClassMetrics::of(&class);
+        assert_eq!(class_metrics.methods.len(), 1);
+        assert_eq!(class_metrics.methods_without_body, 1);
+        assert_eq!(class_metrics.max_cyclomatic_complexity, 3);
+        assert_eq!(
+            class_metrics.bytecode_size,
+            u64::from(metrics.bytecode_size)
+        );
+        assert_eq!(
+            class_metrics.instruction_histogram,
+            metrics.instruction_histogram
+        );
+    }
+
+    #[test]
+    fn ir_metrics() {
+        let class = jasmin::read(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static f(II)I
+                .limit stack 3
+                .limit locals 2
+                iload_0
+                iload_1
+                iadd
+                iload_0
+                iconst_1
+                isub
+                imul
+                ireturn
+            .end method
+            .method public static abs(I)I
+                .limit stack 1
+                .limit locals 1
+                iload_0
+                ifge NonNegative
+                iload_0
+                ineg
+                ireturn
+            NonNegative:
+                iload_0
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let metrics = IrMetrics::of(&class.methods[0].brew().unwrap());
+        assert_eq!(
+            metrics.halstead,
+            HalsteadMetrics {
+                distinct_operators: 5,
+                distinct_operands: 7,
+                total_operators: 8,
+                total_operands: 12,
+            }
+        );
+        assert_eq!(metrics.instruction_count, 5);
+        assert_eq!(metrics.cyclomatic_complexity, 1);
+        assert_eq!(metrics.distinct_identifiers, 6);
+        // `return (arg0 + arg1) * (arg0 - 1)`
+        assert_eq!(metrics.max_expression_depth, 4);
+        let index = metrics.maintainability_index();
+        assert!(index > 0.0 && index < 100.0);
+
+        let metrics = IrMetrics::of(&class.methods[1].brew().unwrap());
+        assert_eq!(metrics.cyclomatic_complexity, 2);
+        assert!(metrics.maintainability_index() > index);
+    }
+}
@@ -1,21 +1,64 @@
 //! APIs for static analysis.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::{
     ir::{ClassHierarchy, InterfaceImplHierarchy},
     jvm::{class_loader::ClassPath, references::ClassRef, Class},
 };
 
+pub mod annotations;
+pub mod anonymization;
+pub mod api_surface;
+pub mod artifacts;
+pub mod bindings;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod closure;
+pub mod devirtualize;
+pub mod diff;
+pub mod duplicates;
+pub mod events;
+pub mod exception_flow;
+pub mod exception_smells;
 pub mod fixed_point;
+pub mod generated;
+pub mod generics;
+pub mod heap;
+pub mod injection;
+pub mod libraries;
+pub mod local_variables;
+pub mod locks;
+pub mod loops;
+pub mod mapping;
+pub mod metrics;
+pub mod nullness;
+pub mod obfuscation;
+pub mod pipeline;
+pub mod precision;
+pub mod provenance;
+pub mod reflection;
+pub mod relocation;
+pub mod resolution;
+pub mod resolver;
+pub mod sandbox;
+pub mod services;
+pub mod shrink;
+pub mod side_effects;
+pub mod slice;
+pub mod strings;
+pub mod try_regions;
+pub mod validation;
+pub mod version_audit;
+pub mod workspace;
 
 /// A context for class resolution during analysis.
 #[derive(Debug)]
 pub struct ResolutionContext {
     /// The application classes.
-    pub application_classes: HashMap<ClassRef, Class>,
+    pub application_classes: BTreeMap<ClassRef, Class>,
     /// The library classes.
-    pub library_classes: HashMap<ClassRef, Class>,
+    pub library_classes: BTreeMap<ClassRef, Class>,
     /// The class hierarchy.
     pub class_hierarchy: ClassHierarchy,
     /// The interface implementations.
@@ -25,7 +68,7 @@ pub struct ResolutionContext {
 /// A trait that can provide an exhaustive list of [`ClassRef`].
 pub trait ClassRefs {
     /// List all classes.
-    fn class_refs(&self) -> HashSet<ClassRef>;
+    fn class_refs(&self) -> BTreeSet<ClassRef>;
 }
 
 impl ResolutionContext {
@@ -47,13 +90,21 @@ impl ResolutionContext {
             interface_implementations,
         }
     }
+
+    /// Gets a class by its reference, looking up the application classes first.
+    #[must_use]
+    pub fn get_class(&self, class_ref: &ClassRef) -> Option<&Class> {
+        self.application_classes
+            .get(class_ref)
+            .or_else(|| self.library_classes.get(class_ref))
+    }
 }
 
 /// An error that occurs during initialization of a [`ResolutionContext`].
 #[derive(Debug, derive_more::Display)]
 pub enum InitError {}
 
-fn load_classes<P>(class_path: &[P]) -> HashMap<ClassRef, Class>
+fn load_classes<P>(class_path: &[P]) -> BTreeMap<ClassRef, Class>
 where
     P: ClassPath + ClassRefs,
 {
@@ -0,0 +1,511 @@
+//! Nullness analysis for detecting possible null pointer dereferences.
+//!
+//! The analysis tracks the [`Nullness`] of the values in a [`MokaIRMethod`].
+//! It is seeded by `null` constants, nullness annotations (e.g., `@Nullable` and `@NotNull`),
+//! and refined by the null checks on the control flow edges.
+
+use std::{
+    collections::BTreeMap,
+    convert::Infallible,
+    iter::{once, repeat},
+};
+
+use crate::{
+    ir::{
+        control_flow::{
+            path_condition::{PathCondition, Predicate, Value},
+            ControlTransfer,
+        },
+        expression::{ArrayOperation, Conversion, Expression, FieldAccess, LockOperation},
+        Identifier, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::{code::ProgramCounter, Annotation, ConstantValue, Method},
+    types::field_type::FieldType,
+};
+
+use super::{fixed_point, ResolutionContext};
+
+/// The nullness of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Nullness {
+    /// The value is definitely not `null`.
+    #[display("non-null")]
+    NonNull,
+    /// The value is definitely `null`.
+    #[display("null")]
+    Null,
+    /// The value may be `null`.
+    #[display("nullable")]
+    Nullable,
+    /// Nothing is known about the value.
+    #[display("unknown")]
+    Unknown,
+}
+
+impl Nullness {
+    /// Computes the least upper bound of two nullness values.
+    #[must_use]
+    pub const fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::NonNull, Self::NonNull) => Self::NonNull,
+            (Self::Null, Self::Null) => Self::Null,
+            (Self::NonNull | Self::Unknown, Self::Unknown) | (Self::Unknown, Self::NonNull) => {
+                Self::Unknown
+            }
+            _ => Self::Nullable,
+        }
+    }
+
+    /// Checks whether dereferencing a value with this nullness may throw a `NullPointerException`.
+    #[must_use]
+    pub const fn may_be_null(self) -> bool {
+        matches!(self, Self::Null | Self::Nullable)
+    }
+
+    /// Gets the nullness declared by the annotations on an element, if any.
+    #[must_use]
+    pub fn from_annotations<'a>(annotations: impl IntoIterator<Item = &'a Annotation>) -> Self {
+        annotations
+            .into_iter()
+            .find_map(|annotation| {
+                let FieldType::Object(ref annotation_type) = annotation.annotation_type else {
+                    return None;
+                };
+                let simple_name = annotation_type
+                    .binary_name
+                    .rsplit(['/', '$'])
+                    .next()
+                    .unwrap_or_default();
+                match simple_name {
+                    "Nullable" | "CheckForNull" => Some(Self::Nullable),
+                    "NotNull" | "NonNull" | "Nonnull" => Some(Self::NonNull),
+                    _ => None,
+                }
+            })
+            .unwrap_or(Self::Unknown)
+    }
+}
+
+/// A dereference of a value that may be `null`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PossibleNullDereference {
+    /// The location of the dereference.
+    pub pc: ProgramCounter,
+    /// The value being dereferenced.
+    pub operand: Operand,
+    /// The nullness of the value being dereferenced.
+    pub nullness: Nullness,
+    /// The path condition under which the dereference is reached.
+    pub path_condition: PathCondition<Predicate<Value>>,
+}
+
+/// The nullness of the values at a program point.
+/// Values that are absent have [`Nullness::Unknown`].
+pub type NullnessFact = BTreeMap<Identifier, Nullness>;
+
+/// An analyzer that tracks the nullness of values in a method.
+#[derive(Debug)]
+pub struct NullnessAnalyzer<'a> {
+    method: &'a Method,
+    ir_method: &'a MokaIRMethod,
+    resolution_context: Option<&'a ResolutionContext>,
+}
+
+impl<'a> NullnessAnalyzer<'a> {
+    /// Creates a new analyzer for `method`, whose Moka IR is `ir_method`.
+    #[must_use]
+    pub const fn new(method: &'a Method, ir_method: &'a MokaIRMethod) -> Self {
+        Self {
+            method,
+            ir_method,
+            resolution_context: None,
+        }
+    }
+
+    /// Uses the given resolution context to look up the annotations on the called methods and
+    /// the accessed fields.
+    #[must_use]
+    pub const fn with_resolution_context(
+        mut self,
+        resolution_context: &'a ResolutionContext,
+    ) -> Self {
+        self.resolution_context = Some(resolution_context);
+        self
+    }
+
+    /// Finds the dereferences of values that may be `null`.
+    #[must_use]
+    pub fn possible_null_dereferences(mut self) -> Vec<PossibleNullDereference> {
+        let Ok(facts) = fixed_point::Analyzer::analyze(&mut self);
+        let dereferences: Vec<_> = facts
+            .iter()
+            .filter_map(|(pc, fact)| Some((*pc, fact, self.ir_method.instructions.get(pc)?)))
+            .flat_map(|(pc, fact, insn)| {
+                dereferenced_operands(insn)
+                    .into_iter()
+                    .map(move |operand| (pc, operand, nullness_of(fact, operand)))
+            })
+            .filter(|(_, _, nullness)| nullness.may_be_null())
+            .collect();
+        if dereferences.is_empty() {
+            return Vec::default();
+        }
+        // The conditions in normal forms grow exponentially with the branches before they merge.
+        let path_conditions = self.ir_method.control_flow_graph.bdd_path_conditions();
+        dereferences
+            .into_iter()
+            .filter_map(|(pc, operand, nullness)| {
+                Some(PossibleNullDereference {
+                    pc,
+                    operand: operand.clone(),
+                    nullness,
+                    path_condition: path_conditions.get(&pc)?.to_dnf(),
+                })
+            })
+            .collect()
+    }
+
+    fn nullness_of_expression(&self, fact: &NullnessFact, expr: &Expression) -> Nullness {
+        match expr {
+            Expression::Const(ConstantValue::Null) => Nullness::Null,
+            Expression::Const(_)
+            | Expression::New(_)
+            | Expression::Closure { .. }
+            | Expression::Array(ArrayOperation::New { .. } | ArrayOperation::NewMultiDim { .. }) => {
+                Nullness::NonNull
+            }
+            Expression::Conversion(Conversion::CheckCast(operand, _)) => nullness_of(fact, operand),
+            Expression::Call { method, .. } => self
+                .resolution_context
+                .and_then(|ctx| ctx.get_class(&method.owner))
+                .and_then(|class| class.get_method(&method.name, &method.descriptor))
+                .map_or(Nullness::Unknown, |callee| {
+                    Nullness::from_annotations(
+                        callee
+                            .runtime_visible_annotations
+                            .iter()
+                            .chain(&callee.runtime_invisible_annotations),
+                    )
+                }),
+            Expression::Field(
+                FieldAccess::ReadStatic { field } | FieldAccess::ReadInstance { field, .. },
+            ) => self
+                .resolution_context
+                .and_then(|ctx| ctx.get_class(&field.owner))
+                .and_then(|class| class.get_field(&field.name, &field.field_type))
+                .map_or(Nullness::Unknown, |field| {
+                    Nullness::from_annotations(
+                        field
+                            .runtime_visible_annotations
+                            .iter()
+                            .chain(&field.runtime_invisible_annotations),
+                    )
+                }),
+            _ => Nullness::Unknown,
+        }
+    }
+}
+
+impl fixed_point::Analyzer for NullnessAnalyzer<'_> {
+    type Location = ProgramCounter;
+    type Fact = NullnessFact;
+    type Err = Infallible;
+    type AffectedLocations = Vec<(Self::Location, Self::Fact)>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        let this = (!self.ir_method.is_static()).then_some((Identifier::This, Nullness::NonNull));
+        let visible = self.method.runtime_visible_parameter_annotations.iter();
+        let invisible = self.method.runtime_invisible_parameter_annotations.iter();
+        let args = (0..self.method.descriptor.parameters_types.len())
+            .zip(
+                visible
+                    .map(Some)
+                    .chain(repeat(None))
+                    .zip(invisible.map(Some).chain(repeat(None))),
+            )
+            .filter_map(|(idx, (visible, invisible))| {
+                let annotations = visible.into_iter().chain(invisible).flatten();
+                let nullness = Nullness::from_annotations(annotations);
+                let idx = u16::try_from(idx).ok()?;
+                Some((Identifier::Arg(idx), nullness))
+            });
+        let fact = this
+            .into_iter()
+            .chain(once((Identifier::CaughtException, Nullness::NonNull)))
+            .chain(args)
+            .filter(|(_, nullness)| nullness != &Nullness::Unknown)
+            .collect();
+        Ok(vec![(
+            self.ir_method.control_flow_graph.entry_point(),
+            fact,
+        )])
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let mut fact = fact.clone();
+        if let Some(insn) = self.ir_method.instructions.get(location) {
+            // The execution proceeds only if the dereferenced values are not `null`.
+            for operand in dereferenced_operands(insn) {
+                if let Operand::Just(id) = operand {
+                    fact.insert(*id, Nullness::NonNull);
+                }
+            }
+            if let MokaInstruction::Definition { value, expr } = insn {
+                let id = Identifier::Local(*value);
+                match self.nullness_of_expression(&fact, expr) {
+                    Nullness::Unknown => fact.remove(&id),
+                    nullness => fact.insert(id, nullness),
+                };
+            }
+        }
+        let Some(edges) = self.ir_method.control_flow_graph.edges_from(*location) else {
+            return Ok(Vec::default());
+        };
+        let affected_locations = edges
+            .map(|(_, dst, transfer)| {
+                let mut fact = fact.clone();
+                if let ControlTransfer::Conditional(condition) = transfer {
+                    refine(&mut fact, condition);
+                }
+                (dst, fact)
+            })
+            .collect();
+        Ok(affected_locations)
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        let merged = current_fact
+            .keys()
+            .chain(incoming_fact.keys())
+            .map(|id| {
+                let current = current_fact.get(id).copied().unwrap_or(Nullness::Unknown);
+                let incoming = incoming_fact.get(id).copied().unwrap_or(Nullness::Unknown);
+                (*id, current.join(incoming))
+            })
+            .filter(|(_, nullness)| nullness != &Nullness::Unknown)
+            .collect();
+        Ok(merged)
+    }
+}
+
+fn nullness_of(fact: &NullnessFact, operand: &Operand) -> Nullness {
+    operand
+        .iter()
+        .map(|id| fact.get(id).copied().unwrap_or(Nullness::Unknown))
+        .reduce(Nullness::join)
+        .unwrap_or(Nullness::Unknown)
+}
+
+/// Refines the nullness of the values checked against `null` in `condition`.
+/// Only a condition consisting of a single conjunction is used, since each of its predicates must hold.
+fn refine(fact: &mut NullnessFact, condition: &PathCondition<Predicate<Value>>) {
+    let mut conjunctions = condition.conjunctions();
+    let (Some(conjunction), None) = (conjunctions.next(), conjunctions.next()) else {
+        return;
+    };
+    for predicate in conjunction.iter() {
+        match predicate {
+            Predicate::IsNull(Value::Variable(Operand::Just(id))) => {
+                fact.insert(*id, Nullness::Null);
+            }
+            Predicate::IsNotNull(Value::Variable(Operand::Just(id))) => {
+                fact.insert(*id, Nullness::NonNull);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn dereferenced_operands(insn: &MokaInstruction) -> Vec<&Operand> {
+    let MokaInstruction::Definition { expr, .. } = insn else {
+        return Vec::default();
+    };
+    match expr {
+        Expression::Call {
+            this: Some(operand),
+            ..
+        }
+        | Expression::Field(
+            FieldAccess::ReadInstance {
+                object_ref: operand,
+                ..
+            }
+            | FieldAccess::WriteInstance {
+                object_ref: operand,
+                ..
+            },
+        )
+        | Expression::Array(
+            ArrayOperation::Read {
+                array_ref: operand, ..
+            }
+            | ArrayOperation::Write {
+                array_ref: operand, ..
+            }
+            | ArrayOperation::Length { array_ref: operand },
+        )
+        | Expression::Throw(operand)
+        | Expression::Synchronization(
+            LockOperation::Acquire(operand) | LockOperation::Release(operand),
+        ) => vec![operand],
+        _ => Vec::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, InstructionList, MethodBody},
+            method,
+        },
+        tests::annotation,
+    };
+
+    use super::*;
+    use Instruction::{AConstNull, ALoad0, ArrayLength, ILoad0, IfEq, IfNonNull, Nop, Pop, Return};
+
+    fn method_with(instructions: InstructionList<Instruction>, descriptor: &str) -> Method {
+        Method {
+            access_flags: method::AccessFlags::STATIC,
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 1,
+                max_locals: 1,
+                instructions,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn analyze(method: &Method) -> Vec<PossibleNullDereference> {
+        let ir_method = method.brew().unwrap();
+        NullnessAnalyzer::new(method, &ir_method).possible_null_dereferences()
+    }
+
+    #[test]
+    fn join() {
+        use Nullness::{NonNull, Null, Nullable, Unknown};
+        assert_eq!(NonNull.join(NonNull), NonNull);
+        assert_eq!(Null.join(Null), Null);
+        assert_eq!(Null.join(NonNull), Nullable);
+        assert_eq!(Unknown.join(NonNull), Unknown);
+        assert_eq!(Unknown.join(Null), Nullable);
+        assert_eq!(Nullable.join(Unknown), Nullable);
+    }
+
+    #[test]
+    fn annotations() {
+        assert_eq!(
+            Nullness::from_annotations(&[annotation("org/jetbrains/annotations/Nullable")]),
+            Nullness::Nullable
+        );
+        assert_eq!(
+            Nullness::from_annotations(&[annotation("lombok/NonNull")]),
+            Nullness::NonNull
+        );
+        assert_eq!(
+            Nullness::from_annotations(&[annotation("java/lang/Deprecated")]),
+            Nullness::Unknown
+        );
+    }
+
+    #[test]
+    fn null_constant() {
+        let method = method_with(
+            InstructionList::from([
+                (0.into(), AConstNull),
+                (1.into(), ArrayLength),
+                (2.into(), Pop),
+                (3.into(), Return),
+            ]),
+            "()V",
+        );
+        let derefs = analyze(&method);
+        assert_eq!(derefs.len(), 1);
+        assert_eq!(derefs[0].pc, 1.into());
+        assert_eq!(derefs[0].nullness, Nullness::Null);
+    }
+
+    #[test]
+    fn null_check() {
+        let method = method_with(
+            InstructionList::from([
+                (0.into(), ALoad0),
+                (1.into(), IfNonNull(7.into())),
+                (4.into(), ALoad0),
+                (5.into(), ArrayLength),
+                (6.into(), Pop),
+                (7.into(), ALoad0),
+                (8.into(), ArrayLength),
+                (9.into(), Pop),
+                (10.into(), Return),
+            ]),
+            "([I)V",
+        );
+        let derefs = analyze(&method);
+        assert_eq!(derefs.len(), 1);
+        let deref = &derefs[0];
+        assert_eq!(deref.pc, 5.into());
+        assert_eq!(deref.operand, Operand::Just(Identifier::Arg(0)));
+        assert_eq!(deref.nullness, Nullness::Null);
+        assert_eq!(
+            deref.path_condition,
+            Predicate::IsNull(Operand::Just(Identifier::Arg(0)).into()).into()
+        );
+    }
+
+    #[test]
+    fn nullable_parameter() {
+        let instructions = InstructionList::from([
+            (0.into(), ALoad0),
+            (1.into(), ArrayLength),
+            (2.into(), Pop),
+            (3.into(), Return),
+        ]);
+        let mut method = method_with(instructions, "([I)V");
+        assert!(analyze(&method).is_empty());
+
+        method.runtime_invisible_parameter_annotations =
+            vec![vec![annotation("javax/annotation/Nullable")]];
+        let derefs = analyze(&method);
+        assert_eq!(derefs.len(), 1);
+        assert_eq!(derefs[0].nullness, Nullness::Nullable);
+    }
+
+    #[test]
+    fn many_sequential_branches() {
+        const BRANCHES: u16 = 40;
+        let branches = (0..BRANCHES).flat_map(|i| {
+            let pc = i * 5;
+            [
+                (pc.into(), ILoad0),
+                ((pc + 1).into(), IfEq((pc + 5).into())),
+                ((pc + 4).into(), Nop),
+            ]
+        });
+        let end = BRANCHES * 5;
+        let instructions: BTreeMap<_, _> = branches
+            .chain([
+                (end.into(), AConstNull),
+                ((end + 1).into(), ArrayLength),
+                ((end + 2).into(), Pop),
+                ((end + 3).into(), Return),
+            ])
+            .collect();
+        let method = method_with(instructions.into(), "(I)V");
+        let derefs = analyze(&method);
+        assert_eq!(derefs.len(), 1);
+        assert_eq!(derefs[0].path_condition, PathCondition::tautology());
+    }
+}
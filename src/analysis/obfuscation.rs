@@ -0,0 +1,543 @@
+//! Detection of common obfuscation patterns.
+//!
+//! The detector looks for markers left by obfuscators: identifiers that are illegal in the Java
+//! language, class initializers decrypting string constants, opaque predicates, flattened control
+//! flow, and exception tables inflated beyond what compilers generate.
+//! Each marker is only a hint, so the markers of a class are combined into a confidence score
+//! (see [`ClassObfuscation::confidence`]).
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+};
+
+use crate::{
+    ir::{
+        control_flow::path_condition::{BddPathCondition, Predicate, Value},
+        expression::Expression,
+        Identifier, MokaIRBrewingError, MokaIRMethod, MokaIRMethodExt, MokaInstruction, Operand,
+    },
+    jvm::{
+        code::{Instruction, ProgramCounter},
+        references::{ClassRef, MethodRef},
+        Class, ConstantValue, Method,
+    },
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+/// The reserved keywords and literals of the Java language, which are not valid identifiers.
+const JAVA_KEYWORDS: &[&str] = &[
+    "_",
+    "abstract",
+    "assert",
+    "boolean",
+    "break",
+    "byte",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "default",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "goto",
+    "if",
+    "implements",
+    "import",
+    "instanceof",
+    "int",
+    "interface",
+    "long",
+    "native",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "short",
+    "static",
+    "strictfp",
+    "super",
+    "switch",
+    "synchronized",
+    "this",
+    "throw",
+    "throws",
+    "transient",
+    "true",
+    "try",
+    "void",
+    "volatile",
+    "while",
+];
+
+/// A marker of obfuscation.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, derive_more::Display)]
+pub enum ObfuscationMarker {
+    /// The name of the class or one of its members is not a legal Java identifier.
+    #[display("illegal identifier {name:?}")]
+    IllegalIdentifier {
+        /// The name.
+        name: String,
+    },
+    /// The class initializer decrypts strings with `xor` and stores them in static fields.
+    #[display("string decryption in {method}")]
+    StringDecryption {
+        /// The class initializer.
+        method: MethodRef,
+    },
+    /// A branch whose outcome is fixed, either because it compares constants or because one of
+    /// its successors is unreachable under the path condition.
+    #[display("opaque predicate in {method} at {pc}")]
+    OpaquePredicate {
+        /// The method containing the branch.
+        method: MethodRef,
+        /// The location of the branch.
+        pc: ProgramCounter,
+    },
+    /// A `switch` acting as the dispatcher of a flattened control flow, i.e., most of its
+    /// branches jump back to it.
+    /// It can be undone with [`deflatten`](crate::ir::transform::deflatten).
+    #[display("flattened control flow in {method} dispatched at {dispatcher_pc}")]
+    ControlFlowFlattening {
+        /// The method containing the dispatcher.
+        method: MethodRef,
+        /// The location of the `switch`.
+        dispatcher_pc: ProgramCounter,
+    },
+    /// The method has more exception handlers than a compiler would generate.
+    #[display("{handlers} exception handlers in {method}")]
+    ExceptionTableAbuse {
+        /// The method.
+        method: MethodRef,
+        /// The number of entries in the exception table.
+        handlers: usize,
+    },
+}
+
+impl ObfuscationMarker {
+    /// Returns the probability that the marker indicates obfuscation on its own.
+    #[must_use]
+    pub const fn weight(&self) -> f64 {
+        match self {
+            Self::IllegalIdentifier { .. } => 0.2,
+            Self::ExceptionTableAbuse { .. } => 0.3,
+            Self::OpaquePredicate { .. } => 0.4,
+            Self::StringDecryption { .. } => 0.6,
+            Self::ControlFlowFlattening { .. } => 0.7,
+        }
+    }
+}
+
+/// The obfuscation markers found in a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassObfuscation {
+    /// The class.
+    pub class: ClassRef,
+    /// The markers found in the class.
+    pub markers: Vec<ObfuscationMarker>,
+}
+
+impl ClassObfuscation {
+    /// Returns the confidence that the class is obfuscated, ranging from 0 to 1.
+    /// The markers are treated as independent evidence, i.e., the confidence is the probability
+    /// that at least one of them indicates obfuscation given their
+    /// [weights](ObfuscationMarker::weight).
+    #[must_use]
+    pub fn confidence(&self) -> f64 {
+        1.0 - self
+            .markers
+            .iter()
+            .map(|it| 1.0 - it.weight())
+            .product::<f64>()
+    }
+}
+
+/// The obfuscation markers found in a set of classes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObfuscationReport {
+    /// The classes with at least one marker, in descending order of confidence.
+    pub classes: Vec<ClassObfuscation>,
+}
+
+impl Display for ObfuscationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for class in &self.classes {
+            writeln!(f, "{:.2} {}", class.confidence(), class.class)?;
+            for marker in &class.markers {
+                writeln!(f, "  {marker}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A detector of [`ObfuscationMarker`]s.
+#[derive(Debug, Clone)]
+pub struct ObfuscationDetector {
+    min_handlers: usize,
+    min_flattened_branches: usize,
+}
+
+impl Default for ObfuscationDetector {
+    /// Creates a detector flagging methods with at least 8 exception handlers outnumbering half
+    /// of their instructions, and `switch`es with at least 3 branches jumping back to them.
+    fn default() -> Self {
+        Self {
+            min_handlers: 8,
+            min_flattened_branches: 3,
+        }
+    }
+}
+
+impl ObfuscationDetector {
+    /// Sets the minimum number of exception handlers for
+    /// [`ObfuscationMarker::ExceptionTableAbuse`].
+    #[must_use]
+    pub const fn with_min_handlers(mut self, min_handlers: usize) -> Self {
+        self.min_handlers = min_handlers;
+        self
+    }
+
+    /// Sets the minimum number of branches jumping back to a `switch` for
+    /// [`ObfuscationMarker::ControlFlowFlattening`].
+    #[must_use]
+    pub const fn with_min_flattened_branches(mut self, min_flattened_branches: usize) -> Self {
+        self.min_flattened_branches = min_flattened_branches;
+        self
+    }
+
+    /// Detects the markers in `classes`, reporting only the classes with markers.
+    #[must_use]
+    pub fn detect(&self, classes: &[Class]) -> ObfuscationReport {
+        let mut classes: Vec<_> = classes
+            .iter()
+            .map(|it| self.detect_class(it))
+            .filter(|it| !it.markers.is_empty())
+            .collect();
+        classes.sort_by(|lhs, rhs| {
+            rhs.confidence()
+                .total_cmp(&lhs.confidence())
+                .then_with(|| lhs.class.cmp(&rhs.class))
+        });
+        ObfuscationReport { classes }
+    }
+
+    /// Detects the markers in `class`.
+    /// Methods that cannot be brewed into Moka IR are only checked for the markers not requiring
+    /// it.
+    #[must_use]
+    pub fn detect_class(&self, class: &Class) -> ClassObfuscation {
+        let names = class
+            .binary_name
+            .split('/')
+            .filter(|it| !matches!(*it, "package-info" | "module-info"))
+            .chain(class.fields.iter().map(|it| it.name.as_str()))
+            .chain(
+                class
+                    .methods
+                    .iter()
+                    .filter(|it| !it.is_constructor() && !it.is_static_initializer_block())
+                    .map(|it| it.name.as_str()),
+            );
+        let mut markers: Vec<_> = names
+            .filter(|it| !is_java_identifier(it))
+            .map(|name| ObfuscationMarker::IllegalIdentifier {
+                name: name.to_owned(),
+            })
+            .collect();
+        markers.dedup();
+        for method in &class.methods {
+            markers.extend(self.detect_bytecode(method));
+            if method.body.is_some() {
+                markers.extend(self.detect_method(method).unwrap_or_default());
+            }
+        }
+        ClassObfuscation {
+            class: class.as_ref(),
+            markers,
+        }
+    }
+
+    /// Detects the markers visible in the bytecode of `method`, i.e., string decryption and
+    /// exception table abuse.
+    fn detect_bytecode(&self, method: &Method) -> Vec<ObfuscationMarker> {
+        let Some(body) = &method.body else {
+            return Vec::new();
+        };
+        let mut markers = Vec::new();
+        if method.is_static_initializer_block() && is_string_decryption(method) {
+            markers.push(ObfuscationMarker::StringDecryption {
+                method: method.as_ref(),
+            });
+        }
+        let handlers = body.exception_table.len();
+        if handlers >= self.min_handlers && handlers * 2 > body.instructions.len() {
+            markers.push(ObfuscationMarker::ExceptionTableAbuse {
+                method: method.as_ref(),
+                handlers,
+            });
+        }
+        markers
+    }
+
+    /// Detects opaque predicates and flattened control flow in `method`.
+    ///
+    /// # Errors
+    /// See [`MokaIRBrewingError`].
+    pub fn detect_method(
+        &self,
+        method: &Method,
+    ) -> Result<Vec<ObfuscationMarker>, MokaIRBrewingError> {
+        let ir_method = method.brew()?;
+        let constants: BTreeMap<_, _> = ir_method
+            .instructions
+            .iter()
+            .filter_map(|(_, insn)| match insn {
+                MokaInstruction::Definition {
+                    value,
+                    expr: Expression::Const(ConstantValue::Integer(it)),
+                } => Some((*value, *it)),
+                _ => None,
+            })
+            .collect();
+        let constant = |operand: &Operand| match operand {
+            Operand::Just(Identifier::Local(value)) => constants.get(value).copied(),
+            _ => None,
+        };
+        let path_conditions = ir_method.control_flow_graph.bdd_path_conditions();
+        let mut markers = Vec::new();
+        for (pc, insn) in &ir_method.instructions {
+            match insn {
+                MokaInstruction::Jump {
+                    condition: Some(condition),
+                    ..
+                } if condition.evaluate(constant).is_some()
+                    || has_infeasible_successor(&ir_method, &path_conditions, *pc) =>
+                {
+                    markers.push(ObfuscationMarker::OpaquePredicate {
+                        method: method.as_ref(),
+                        pc: *pc,
+                    });
+                }
+                MokaInstruction::Switch { branches, .. } => {
+                    let back_jumps = ir_method
+                        .instructions
+                        .iter()
+                        .filter(|(jump_pc, it)| {
+                            *jump_pc > pc
+                                && matches!(
+                                    it,
+                                    MokaInstruction::Jump { condition: None, target }
+                                        if target <= pc
+                                )
+                        })
+                        .count();
+                    if back_jumps >= self.min_flattened_branches && back_jumps * 2 >= branches.len()
+                    {
+                        markers.push(ObfuscationMarker::ControlFlowFlattening {
+                            method: method.as_ref(),
+                            dispatcher_pc: *pc,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(markers)
+    }
+}
+
+/// Checks whether `name` is a legal identifier in the Java language.
+fn is_java_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|it| it.is_alphabetic() || it == '_' || it == '$')
+        && chars.all(|it| it.is_alphanumeric() || it == '_' || it == '$')
+        && !JAVA_KEYWORDS.contains(&name)
+}
+
+/// Checks whether the class initializer `method` decrypts strings, i.e., it uses `ixor`, converts
+/// between strings and character arrays, and stores strings in static fields.
+fn is_string_decryption(method: &Method) -> bool {
+    let Some(body) = &method.body else {
+        return false;
+    };
+    let string = FieldType::Object(ClassRef::new("java/lang/String"));
+    let (mut has_xor, mut has_conversion, mut has_store) = (false, false, false);
+    for (_, insn) in &body.instructions {
+        match insn {
+            Instruction::IXor => has_xor = true,
+            Instruction::InvokeVirtual(callee) | Instruction::InvokeSpecial(callee)
+                if &*callee.owner.binary_name == "java/lang/String" =>
+            {
+                has_conversion |= &*callee.name == "toCharArray"
+                    || callee.is_constructor()
+                        && callee.descriptor.parameters_types.first()
+                            == Some(&FieldType::from(PrimitiveType::Char).into_array_type());
+            }
+            Instruction::PutStatic(field) => {
+                has_store |= field.field_type == string
+                    || field.field_type == string.clone().into_array_type();
+            }
+            _ => {}
+        }
+    }
+    has_xor && has_conversion && has_store
+}
+
+/// Checks whether the branch at `pc` is feasible but one of its successors is only reachable
+/// under a contradictory path condition.
+fn has_infeasible_successor(
+    ir_method: &MokaIRMethod,
+    path_conditions: &BTreeMap<ProgramCounter, BddPathCondition<Predicate<Value>>>,
+    pc: ProgramCounter,
+) -> bool {
+    let is_contradiction = |pc: ProgramCounter| {
+        path_conditions
+            .get(&pc)
+            .is_some_and(BddPathCondition::is_contradiction)
+    };
+    !is_contradiction(pc)
+        && ir_method
+            .control_flow_graph
+            .edges_from(pc)
+            .into_iter()
+            .flatten()
+            .any(|(_, dst, _)| is_contradiction(dst))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    #[test]
+    fn identifiers() {
+        assert!(is_java_identifier("lambda$main$0"));
+        assert!(is_java_identifier("größe"));
+        assert!(!is_java_identifier("if"));
+        assert!(!is_java_identifier("1a"));
+        assert!(!is_java_identifier("a b"));
+        assert!(!is_java_identifier(""));
+    }
+
+    const OBFUSCATED_CLASS: &str = r#"
+        .class public org/mokapot/do
+        .super java/lang/Object
+        .field private static s [Ljava/lang/String;
+        .method static <clinit>()V
+            .limit stack 4
+            .limit locals 1
+            ldc "kfgb"
+            invokevirtual java/lang/String/toCharArray()[C
+            astore_0
+            aload_0
+            iconst_0
+            aload_0
+            iconst_0
+            caload
+            bipush 42
+            ixor
+            i2c
+            castore
+            iconst_1
+            anewarray java/lang/String
+            dup
+            iconst_0
+            new java/lang/String
+            dup
+            aload_0
+            invokespecial java/lang/String/<init>([C)V
+            aastore
+            putstatic org/mokapot/do/s [Ljava/lang/String;
+            return
+        .end method
+        .method public static opaque(I)I
+            .limit stack 2
+            .limit locals 1
+            iconst_1
+            iconst_2
+            if_icmpeq Dead
+            iload_0
+            ifeq Zero
+            iload_0
+            ifeq Unreachable
+            iconst_1
+            ireturn
+        Zero:
+            iconst_0
+            ireturn
+        Unreachable:
+            iconst_2
+            ireturn
+        Dead:
+            iconst_m1
+            ireturn
+        .end method
+        .method public static flattened(I)I
+            .limit stack 1
+            .limit locals 1
+        Dispatch:
+            iload_0
+            tableswitch 0
+                A
+                B
+                C
+                default : Exit
+        A:
+            iconst_1
+            istore_0
+            goto Dispatch
+        B:
+            iconst_2
+            istore_0
+            goto Dispatch
+        C:
+            iconst_3
+            istore_0
+            goto Dispatch
+        Exit:
+            iload_0
+            ireturn
+        .end method
+        "#;
+
+    #[test]
+    fn obfuscated_class() {
+        let class = jasmin::read(OBFUSCATED_CLASS).unwrap();
+        let report = ObfuscationDetector::default().detect(std::slice::from_ref(&class));
+        let [result] = report.classes.as_slice() else {
+            panic!("Unexpected report: {report}");
+        };
+        let kinds: Vec<_> = result
+            .markers
+            .iter()
+            .map(|it| match it {
+                ObfuscationMarker::IllegalIdentifier { name } => name.as_str(),
+                ObfuscationMarker::StringDecryption { .. } => "decryption",
+                ObfuscationMarker::OpaquePredicate { method, .. } => method.name.as_ref(),
+                ObfuscationMarker::ControlFlowFlattening { .. } => "flattening",
+                ObfuscationMarker::ExceptionTableAbuse { .. } => "handlers",
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            ["do", "decryption", "opaque", "opaque", "flattening"]
+        );
+        assert!(result.confidence() > 0.9);
+    }
+}
@@ -0,0 +1,560 @@
+//! A manager of analysis passes with declared dependencies.
+//!
+//! A [`Pass`] computes a result for a [`Target`], i.e., the whole program, a class, or a method.
+//! Passes are registered with a [`PassManager`] after the passes they depend on, so the
+//! dependencies always form a directed acyclic graph.
+//! Results are computed on demand, cached per pass and target, and invalidated together with the
+//! results computed from them when a class changes.
+
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::{HashMap, HashSet},
+    fmt::{self, Debug, Display},
+    rc::Rc,
+};
+
+use crate::{
+    ir::{ClassHierarchy, InterfaceImplHierarchy},
+    jvm::{
+        references::{ClassRef, MethodRef},
+        Class, Method,
+    },
+};
+
+use super::ResolutionContext;
+
+/// An error when running a [`Pass`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The pass is not registered.
+    #[error("Pass {0} is not registered")]
+    NotRegistered(&'static str),
+    /// The pass is already registered.
+    #[error("Pass {0} is already registered")]
+    AlreadyRegistered(&'static str),
+    /// A pass depends on a pass that is not registered before it.
+    #[error("Pass {pass} depends on {dependency}, which is not registered")]
+    UnregisteredDependency {
+        /// The name of the dependent pass.
+        pass: &'static str,
+        /// The name of the missing dependency.
+        dependency: &'static str,
+    },
+    /// A pass requests the result of a pass that it does not declare as a dependency.
+    #[error("Pass {pass} does not declare {dependency} as a dependency")]
+    UndeclaredDependency {
+        /// The name of the requesting pass.
+        pass: &'static str,
+        /// The name of the requested pass.
+        dependency: &'static str,
+    },
+    /// The target of the pass cannot be found.
+    #[error("Target {0} is not found")]
+    TargetNotFound(Target),
+    /// A pass failed.
+    #[error(transparent)]
+    Custom(Box<dyn std::error::Error + Send + Sync>),
+}
+
+impl Error {
+    /// Creates an [`Error::Custom`] from an error raised by a pass.
+    pub fn custom(err: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        Self::Custom(err.into())
+    }
+}
+
+/// The identifier of a [`Pass`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PassId {
+    type_id: TypeId,
+    name: &'static str,
+}
+
+impl PassId {
+    /// Gets the identifier of the pass `P`.
+    #[must_use]
+    pub fn of<P: Pass>() -> Self {
+        Self {
+            type_id: TypeId::of::<P>(),
+            name: type_name::<P>(),
+        }
+    }
+
+    /// Gets the name of the pass.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// The unit of code that a [`Pass`] computes a result for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+    /// All the classes in the [`ResolutionContext`].
+    Program,
+    /// A class.
+    Class(ClassRef),
+    /// A method.
+    Method(MethodRef),
+}
+
+impl Target {
+    /// Checks whether the result for the target may change when `class_ref` changes.
+    #[must_use]
+    pub fn depends_on(&self, class_ref: &ClassRef) -> bool {
+        match self {
+            Self::Program => true,
+            Self::Class(it) => it == class_ref,
+            Self::Method(it) => &it.owner == class_ref,
+        }
+    }
+}
+
+impl Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Program => write!(f, "<program>"),
+            Self::Class(it) => Display::fmt(it, f),
+            Self::Method(it) => Display::fmt(it, f),
+        }
+    }
+}
+
+/// An analysis run by a [`PassManager`].
+pub trait Pass: 'static {
+    /// The result of the pass.
+    type Output: 'static;
+
+    /// Gets the passes whose results this pass requests.
+    fn dependencies(&self) -> Vec<PassId> {
+        Vec::new()
+    }
+
+    /// Runs the pass on [`PassContext::target`].
+    ///
+    /// # Errors
+    /// See [`Error`].
+    fn run(&self, context: &mut PassContext<'_>) -> Result<Self::Output, Error>;
+}
+
+type Output = Rc<dyn Any>;
+
+trait ErasedPass {
+    fn run(&self, context: &mut PassContext<'_>) -> Result<Output, Error>;
+}
+
+impl<P: Pass> ErasedPass for P {
+    fn run(&self, context: &mut PassContext<'_>) -> Result<Output, Error> {
+        Pass::run(self, context).map(|it| Rc::new(it) as Output)
+    }
+}
+
+type Key = (PassId, Target);
+
+struct CachedResult {
+    output: Output,
+    /// The results computed from this one.
+    dependents: HashSet<Key>,
+}
+
+/// A registry of [`Pass`]es and a cache of their results. See the
+/// [module-level documentation](self).
+pub struct PassManager {
+    context: ResolutionContext,
+    passes: HashMap<PassId, Rc<dyn ErasedPass>>,
+    dependencies: HashMap<PassId, HashSet<PassId>>,
+    results: HashMap<Key, CachedResult>,
+}
+
+impl Debug for PassManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PassManager")
+            .field(
+                "passes",
+                &self.passes.keys().map(PassId::name).collect::<Vec<_>>(),
+            )
+            .field("results", &self.results.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PassManager {
+    /// Creates a pass manager running passes on the classes in `context`.
+    #[must_use]
+    pub fn new(context: ResolutionContext) -> Self {
+        Self {
+            context,
+            passes: HashMap::new(),
+            dependencies: HashMap::new(),
+            results: HashMap::new(),
+        }
+    }
+
+    /// Returns the classes the passes run on.
+    #[must_use]
+    pub const fn context(&self) -> &ResolutionContext {
+        &self.context
+    }
+
+    /// Registers `pass`. Its dependencies must be registered before.
+    ///
+    /// # Errors
+    /// - [`Error::AlreadyRegistered`] if the pass is already registered.
+    /// - [`Error::UnregisteredDependency`] if a dependency is not registered.
+    pub fn register<P: Pass>(&mut self, pass: P) -> Result<(), Error> {
+        let id = PassId::of::<P>();
+        if self.passes.contains_key(&id) {
+            return Err(Error::AlreadyRegistered(id.name));
+        }
+        let dependencies: HashSet<_> = Pass::dependencies(&pass).into_iter().collect();
+        if let Some(missing) = dependencies.iter().find(|it| !self.passes.contains_key(it)) {
+            return Err(Error::UnregisteredDependency {
+                pass: id.name,
+                dependency: missing.name,
+            });
+        }
+        self.passes.insert(id, Rc::new(pass));
+        self.dependencies.insert(id, dependencies);
+        Ok(())
+    }
+
+    /// Gets the result of the pass `P` on `target`, running it and its dependencies if they are
+    /// not cached.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn get<P: Pass>(&mut self, target: &Target) -> Result<Rc<P::Output>, Error> {
+        self.get_erased(PassId::of::<P>(), target).map(|it| {
+            it.downcast()
+                .unwrap_or_else(|_| unreachable!("The output is keyed by the type of the pass"))
+        })
+    }
+
+    /// Checks whether the result of the pass `P` on `target` is cached.
+    #[must_use]
+    pub fn is_cached<P: Pass>(&self, target: &Target) -> bool {
+        self.results
+            .contains_key(&(PassId::of::<P>(), target.clone()))
+    }
+
+    /// Discards the result of the pass `P` on `target` and the results computed from it.
+    pub fn invalidate<P: Pass>(&mut self, target: &Target) {
+        self.invalidate_key(&(PassId::of::<P>(), target.clone()));
+    }
+
+    /// Discards the results that may change when the class referred to by `class_ref` changes,
+    /// and the results computed from them.
+    pub fn invalidate_class(&mut self, class_ref: &ClassRef) {
+        let stale: Vec<_> = self
+            .results
+            .keys()
+            .filter(|(_, target)| target.depends_on(class_ref))
+            .cloned()
+            .collect();
+        for key in stale {
+            self.invalidate_key(&key);
+        }
+    }
+
+    /// Replaces or adds `class` as an application class, unless it is a library class, and
+    /// invalidates the affected results.
+    pub fn update_class(&mut self, class: Class) {
+        let class_ref = class.as_ref();
+        let classes = if self.context.library_classes.contains_key(&class_ref) {
+            &mut self.context.library_classes
+        } else {
+            &mut self.context.application_classes
+        };
+        classes.insert(class_ref.clone(), class);
+        let all_classes = self
+            .context
+            .application_classes
+            .values()
+            .chain(self.context.library_classes.values());
+        self.context.class_hierarchy = ClassHierarchy::from_classes(all_classes.clone());
+        self.context.interface_implementations = InterfaceImplHierarchy::from_classes(all_classes);
+        self.invalidate_class(&class_ref);
+    }
+
+    fn invalidate_key(&mut self, key: &Key) {
+        let mut worklist = vec![key.clone()];
+        while let Some(key) = worklist.pop() {
+            if let Some(result) = self.results.remove(&key) {
+                worklist.extend(result.dependents);
+            }
+        }
+    }
+
+    fn get_erased(&mut self, id: PassId, target: &Target) -> Result<Output, Error> {
+        let key = (id, target.clone());
+        if let Some(result) = self.results.get(&key) {
+            return Ok(result.output.clone());
+        }
+        let pass = self
+            .passes
+            .get(&id)
+            .cloned()
+            .ok_or(Error::NotRegistered(id.name))?;
+        let mut context = PassContext {
+            manager: self,
+            pass: id,
+            target,
+            reads: HashSet::new(),
+        };
+        let output = pass.run(&mut context)?;
+        let reads = context.reads;
+        for read in reads {
+            // The dependency may have been invalidated while this pass was running.
+            if let Some(result) = self.results.get_mut(&read) {
+                result.dependents.insert(key.clone());
+            }
+        }
+        self.results.insert(
+            key,
+            CachedResult {
+                output: output.clone(),
+                dependents: HashSet::new(),
+            },
+        );
+        Ok(output)
+    }
+}
+
+/// The environment in which a [`Pass`] runs.
+pub struct PassContext<'a> {
+    manager: &'a mut PassManager,
+    pass: PassId,
+    target: &'a Target,
+    reads: HashSet<Key>,
+}
+
+impl Debug for PassContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PassContext")
+            .field("pass", &self.pass.name)
+            .field("target", &self.target)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PassContext<'_> {
+    /// Returns the target the pass runs on.
+    #[must_use]
+    pub const fn target(&self) -> &Target {
+        self.target
+    }
+
+    /// Returns the classes the pass runs on.
+    #[must_use]
+    pub const fn context(&self) -> &ResolutionContext {
+        &self.manager.context
+    }
+
+    /// Returns the target class, or the class declaring the target method.
+    ///
+    /// # Errors
+    /// Returns [`Error::TargetNotFound`] if the target is [`Target::Program`] or the class is
+    /// not found.
+    pub fn class(&self) -> Result<&Class, Error> {
+        let class_ref = match self.target {
+            Target::Program => None,
+            Target::Class(it) => Some(it),
+            Target::Method(it) => Some(&it.owner),
+        };
+        class_ref
+            .and_then(|it| self.manager.context.get_class(it))
+            .ok_or_else(|| Error::TargetNotFound(self.target.clone()))
+    }
+
+    /// Returns the target method.
+    ///
+    /// # Errors
+    /// Returns [`Error::TargetNotFound`] if the target is not a method or the method is not
+    /// found.
+    pub fn method(&self) -> Result<&Method, Error> {
+        let Target::Method(method_ref) = self.target else {
+            return Err(Error::TargetNotFound(self.target.clone()));
+        };
+        self.class()?
+            .get_method(&method_ref.name, &method_ref.descriptor)
+            .ok_or_else(|| Error::TargetNotFound(self.target.clone()))
+    }
+
+    /// Gets the result of the pass `P` on `target`. `P` must be declared as a dependency of the
+    /// running pass.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn get<P: Pass>(&mut self, target: &Target) -> Result<Rc<P::Output>, Error> {
+        let id = PassId::of::<P>();
+        let declared = self
+            .manager
+            .dependencies
+            .get(&self.pass)
+            .is_some_and(|it| it.contains(&id));
+        if !declared {
+            return Err(Error::UndeclaredDependency {
+                pass: self.pass.name,
+                dependency: id.name,
+            });
+        }
+        let output = self.manager.get::<P>(target)?;
+        self.reads.insert((id, target.clone()));
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, collections::BTreeMap};
+
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    fn class(name: &str, methods: &str) -> Class {
+        jasmin::read(&format!(
+            "
+            .bytecode 52.0
+            .class public {name}
+            .super java/lang/Object
+            {methods}
+            "
+        ))
+        .unwrap()
+    }
+
+    fn method(name: &str) -> String {
+        format!(
+            "
+            .method public static {name}()V
+                .limit stack 0
+                .limit locals 0
+                return
+            .end method
+            "
+        )
+    }
+
+    fn manager(classes: impl IntoIterator<Item = Class>) -> PassManager {
+        let application_classes: BTreeMap<_, _> =
+            classes.into_iter().map(|it| (it.as_ref(), it)).collect();
+        let all_classes = application_classes.values();
+        PassManager::new(ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(all_classes.clone()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(all_classes),
+            application_classes,
+            library_classes: BTreeMap::new(),
+        })
+    }
+
+    struct MethodCount(Rc<Cell<usize>>);
+
+    impl Pass for MethodCount {
+        type Output = usize;
+
+        fn run(&self, context: &mut PassContext<'_>) -> Result<Self::Output, Error> {
+            self.0.set(self.0.get() + 1);
+            Ok(context.class()?.methods.len())
+        }
+    }
+
+    struct TotalMethodCount;
+
+    impl Pass for TotalMethodCount {
+        type Output = usize;
+
+        fn dependencies(&self) -> Vec<PassId> {
+            vec![PassId::of::<MethodCount>()]
+        }
+
+        fn run(&self, context: &mut PassContext<'_>) -> Result<Self::Output, Error> {
+            let mut classes: Vec<_> = context
+                .context()
+                .application_classes
+                .keys()
+                .cloned()
+                .collect();
+            classes.sort();
+            let mut total = 0;
+            for class_ref in classes {
+                total += *context.get::<MethodCount>(&Target::Class(class_ref))?;
+            }
+            Ok(total)
+        }
+    }
+
+    struct MethodName;
+
+    impl Pass for MethodName {
+        type Output = String;
+
+        fn run(&self, context: &mut PassContext<'_>) -> Result<Self::Output, Error> {
+            let name = context.method()?.name.clone();
+            if name == "bad" {
+                context.get::<MethodCount>(&Target::Program)?;
+            }
+            Ok(name)
+        }
+    }
+
+    #[test]
+    fn cache_and_invalidate() {
+        let runs = Rc::new(Cell::new(0));
+        let mut manager = manager([class("A", &method("a")), class("B", &method("b"))]);
+        assert!(matches!(
+            manager.register(TotalMethodCount),
+            Err(Error::UnregisteredDependency { .. })
+        ));
+        manager.register(MethodCount(runs.clone())).unwrap();
+        manager.register(TotalMethodCount).unwrap();
+        assert!(matches!(
+            manager.register(TotalMethodCount),
+            Err(Error::AlreadyRegistered(_))
+        ));
+
+        assert_eq!(
+            *manager.get::<TotalMethodCount>(&Target::Program).unwrap(),
+            2
+        );
+        assert_eq!(
+            *manager.get::<TotalMethodCount>(&Target::Program).unwrap(),
+            2
+        );
+        assert_eq!(runs.get(), 2);
+
+        let a = Target::Class(ClassRef::new("A"));
+        let b = Target::Class(ClassRef::new("B"));
+        manager.invalidate::<MethodCount>(&a);
+        assert!(!manager.is_cached::<TotalMethodCount>(&Target::Program));
+        assert!(manager.is_cached::<MethodCount>(&b));
+
+        manager.update_class(class("B", &(method("b") + &method("c"))));
+        assert!(!manager.is_cached::<MethodCount>(&b));
+        assert_eq!(
+            *manager.get::<TotalMethodCount>(&Target::Program).unwrap(),
+            3
+        );
+        assert_eq!(runs.get(), 4);
+    }
+
+    #[test]
+    fn method_targets() {
+        let mut manager = manager([class("A", &(method("a") + &method("bad")))]);
+        manager.register(MethodName).unwrap();
+        let a = manager.context().application_classes[&ClassRef::new("A")].methods[0].as_ref();
+        let bad = manager.context().application_classes[&ClassRef::new("A")].methods[1].as_ref();
+        assert_eq!(*manager.get::<MethodName>(&Target::Method(a)).unwrap(), "a");
+        assert!(matches!(
+            manager.get::<MethodName>(&Target::Method(bad)),
+            Err(Error::UndeclaredDependency { .. })
+        ));
+        assert!(matches!(
+            manager.get::<MethodName>(&Target::Program),
+            Err(Error::TargetNotFound(Target::Program))
+        ));
+        assert!(matches!(
+            manager.get::<MethodCount>(&Target::Program),
+            Err(Error::NotRegistered(_))
+        ));
+    }
+}
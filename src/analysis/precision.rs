@@ -0,0 +1,250 @@
+//! Selection of the analysis precision for each method based on its size and complexity.
+//!
+//! Small methods are analyzed with full precision, while large methods fall back to cheaper
+//! variants of an analysis (e.g., without path conditions or with coarser abstract domains).
+//! A [`PrecisionDriver`] selects the level with a [`PrecisionPolicy`], runs the analysis, and
+//! records which level was used for each method.
+
+use std::collections::BTreeMap;
+
+use crate::jvm::{code::Instruction, references::MethodRef, Method};
+
+/// The precision of an analysis, ordered from the cheapest to the most precise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PrecisionLevel {
+    /// The cheapest variant (e.g., flow-insensitive).
+    Minimal,
+    /// A variant with reduced precision (e.g., without path conditions).
+    Reduced,
+    /// The most precise variant.
+    Full,
+}
+
+/// The size and complexity of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MethodMetrics {
+    /// The number of instructions.
+    pub instructions: usize,
+    /// The cyclomatic complexity, i.e., the number of decision points plus one.
+    pub complexity: usize,
+    /// The number of exception handlers.
+    pub exception_handlers: usize,
+}
+
+impl MethodMetrics {
+    /// Measures `method`. Methods without a body have no instructions and a complexity of 1.
+    #[must_use]
+    pub fn of(method: &Method) -> Self {
+        let Some(body) = &method.body else {
+            return Self {
+                complexity: 1,
+                ..Self::default()
+            };
+        };
+        let decisions: usize = body
+            .instructions
+            .iter()
+            .map(|(_, insn)| {
+                #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+                use Instruction::*;
+                match insn {
+                    IfEq(_) | IfNe(_) | IfLt(_) | IfGe(_) | IfGt(_) | IfLe(_) | IfICmpEq(_)
+                    | IfICmpNe(_) | IfICmpLt(_) | IfICmpGe(_) | IfICmpGt(_) | IfICmpLe(_)
+                    | IfACmpEq(_) | IfACmpNe(_) | IfNull(_) | IfNonNull(_) => 1,
+                    TableSwitch { jump_targets, .. } => jump_targets.len(),
+                    LookupSwitch { match_targets, .. } => match_targets.len(),
+                    _ => 0,
+                }
+            })
+            .sum();
+        Self {
+            instructions: body.instructions.iter().count(),
+            complexity: decisions + body.exception_table.len() + 1,
+            exception_handlers: body.exception_table.len(),
+        }
+    }
+}
+
+/// The upper bounds of the metrics of the methods analyzed at a [`PrecisionLevel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of instructions.
+    pub max_instructions: usize,
+    /// The maximum cyclomatic complexity.
+    pub max_complexity: usize,
+}
+
+impl Limits {
+    const fn admits(self, metrics: MethodMetrics) -> bool {
+        metrics.instructions <= self.max_instructions && metrics.complexity <= self.max_complexity
+    }
+}
+
+/// A policy selecting the [`PrecisionLevel`] of each method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrecisionPolicy {
+    full: Limits,
+    reduced: Limits,
+}
+
+impl Default for PrecisionPolicy {
+    /// Creates a policy analyzing methods with up to 1000 instructions and a complexity of 50
+    /// at [`PrecisionLevel::Full`], methods with up to 10000 instructions and a complexity of 500
+    /// at [`PrecisionLevel::Reduced`], and the others at [`PrecisionLevel::Minimal`].
+    fn default() -> Self {
+        Self {
+            full: Limits {
+                max_instructions: 1_000,
+                max_complexity: 50,
+            },
+            reduced: Limits {
+                max_instructions: 10_000,
+                max_complexity: 500,
+            },
+        }
+    }
+}
+
+impl PrecisionPolicy {
+    /// Sets the limits of the methods analyzed at [`PrecisionLevel::Full`].
+    #[must_use]
+    pub const fn with_full_limits(mut self, limits: Limits) -> Self {
+        self.full = limits;
+        self
+    }
+
+    /// Sets the limits of the methods analyzed at [`PrecisionLevel::Reduced`].
+    /// Methods exceeding them are analyzed at [`PrecisionLevel::Minimal`].
+    #[must_use]
+    pub const fn with_reduced_limits(mut self, limits: Limits) -> Self {
+        self.reduced = limits;
+        self
+    }
+
+    /// Selects the level for a method with the given metrics.
+    #[must_use]
+    pub const fn select(&self, metrics: MethodMetrics) -> PrecisionLevel {
+        if self.full.admits(metrics) {
+            PrecisionLevel::Full
+        } else if self.reduced.admits(metrics) {
+            PrecisionLevel::Reduced
+        } else {
+            PrecisionLevel::Minimal
+        }
+    }
+}
+
+/// The precision used to analyze a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrecisionRecord {
+    /// The metrics of the method.
+    pub metrics: MethodMetrics,
+    /// The selected level.
+    pub level: PrecisionLevel,
+}
+
+/// Runs analyses at the precision selected by a [`PrecisionPolicy`].
+#[derive(Debug, Clone, Default)]
+pub struct PrecisionDriver {
+    policy: PrecisionPolicy,
+    records: BTreeMap<MethodRef, PrecisionRecord>,
+}
+
+impl PrecisionDriver {
+    /// Creates a driver with the given policy.
+    #[must_use]
+    pub const fn new(policy: PrecisionPolicy) -> Self {
+        Self {
+            policy,
+            records: BTreeMap::new(),
+        }
+    }
+
+    /// Selects the level for `method`, runs `analysis` at that level, and records the level.
+    pub fn run<T>(&mut self, method: &Method, analysis: impl FnOnce(PrecisionLevel) -> T) -> T {
+        let metrics = MethodMetrics::of(method);
+        let level = self.policy.select(metrics);
+        self.records
+            .insert(method.as_ref(), PrecisionRecord { metrics, level });
+        analysis(level)
+    }
+
+    /// Returns the precision used for `method`, if it has been analyzed.
+    #[must_use]
+    pub fn record_of(&self, method: &MethodRef) -> Option<&PrecisionRecord> {
+        self.records.get(method)
+    }
+
+    /// Returns the precision used for each analyzed method.
+    #[must_use]
+    pub const fn records(&self) -> &BTreeMap<MethodRef, PrecisionRecord> {
+        &self.records
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::code::{
+        ExceptionTableEntry, Instruction::*, InstructionList, MethodBody, ProgramCounter,
+    };
+
+    fn method(name: &str, instructions: InstructionList<Instruction>) -> Method {
+        Method {
+            name: name.to_owned(),
+            body: Some(MethodBody {
+                instructions,
+                exception_table: vec![ExceptionTableEntry {
+                    covered_pc: ProgramCounter::from(0)..=ProgramCounter::from(0),
+                    handler_pc: 0.into(),
+                    catch_type: None,
+                }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn selects_level_by_size() {
+        let small = method(
+            "small",
+            InstructionList::from([
+                (0.into(), ILoad0),
+                (1.into(), IfEq(4.into())),
+                (4.into(), Return),
+            ]),
+        );
+        let large = method(
+            "large",
+            (0..20u16)
+                .map(|it| (it.into(), Nop))
+                .collect::<BTreeMap<_, _>>()
+                .into(),
+        );
+        assert_eq!(
+            MethodMetrics::of(&small),
+            MethodMetrics {
+                instructions: 3,
+                complexity: 3,
+                exception_handlers: 1,
+            }
+        );
+
+        let limits = |max_instructions| Limits {
+            max_instructions,
+            max_complexity: 10,
+        };
+        let policy = PrecisionPolicy::default()
+            .with_full_limits(limits(5))
+            .with_reduced_limits(limits(10));
+        let mut driver = PrecisionDriver::new(policy);
+        assert_eq!(driver.run(&small, |level| level), PrecisionLevel::Full);
+        assert_eq!(driver.run(&large, |level| level), PrecisionLevel::Minimal);
+        assert_eq!(
+            driver.record_of(&small.as_ref()).map(|it| it.level),
+            Some(PrecisionLevel::Full)
+        );
+        assert_eq!(driver.records().len(), 2);
+    }
+}
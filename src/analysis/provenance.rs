@@ -0,0 +1,400 @@
+//! Heuristic identification of the toolchain producing a class.
+//!
+//! Compilers and post-processors leave fingerprints in the attributes they emit, the names of
+//! the synthetic members they generate, and the idioms in the instructions.
+//! Each fingerprint found in a class is reported as an [`Evidence`] with a weight, and the
+//! weights for the same toolchain are combined as independent observations, i.e.,
+//! `1 - (1 - w1) * (1 - w2) * ...`.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    jvm::{code::Instruction, references::ClassRef, Class},
+    types::field_type::FieldType,
+};
+
+/// A toolchain producing or processing class files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Toolchain {
+    /// The Java compiler in the JDK.
+    Javac,
+    /// The Eclipse compiler for Java.
+    #[display("ECJ")]
+    Ecj,
+    /// The Kotlin compiler.
+    Kotlin,
+    /// The Scala compiler.
+    Scala,
+    /// The Groovy compiler.
+    Groovy,
+    /// The `ProGuard` shrinker and obfuscator, or R8 in its compatibility mode.
+    #[display("ProGuard")]
+    ProGuard,
+    /// The R8 shrinker and obfuscator.
+    R8,
+}
+
+impl Toolchain {
+    /// Checks whether the toolchain processes compiled class files instead of compiling them.
+    #[must_use]
+    pub const fn is_post_processor(&self) -> bool {
+        matches!(self, Self::ProGuard | Self::R8)
+    }
+}
+
+/// A fingerprint of a toolchain found in a class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evidence {
+    /// The toolchain indicated.
+    pub toolchain: Toolchain,
+    /// How strongly the fingerprint indicates the toolchain, ranging from 0 to 1.
+    pub weight: f64,
+    /// A description of the fingerprint.
+    pub description: String,
+}
+
+/// The toolchains inferred for a class.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provenance {
+    /// The class being inspected.
+    pub class: ClassRef,
+    /// The most likely compiler and the confidence, or [`None`] if there is no evidence of any
+    /// compiler.
+    pub compiler: Option<(Toolchain, f64)>,
+    /// The post-processors likely to have processed the class and the confidence.
+    pub post_processors: Vec<(Toolchain, f64)>,
+    /// The lowest JDK release whose `javac` can produce the class, if the compiler is
+    /// [`Toolchain::Javac`].
+    pub minimum_javac_release: Option<u16>,
+    /// The fingerprints found.
+    pub evidence: Vec<Evidence>,
+}
+
+impl Provenance {
+    /// Gets the combined confidence in each toolchain.
+    #[must_use]
+    pub fn confidence(&self) -> BTreeMap<Toolchain, f64> {
+        let mut doubts = BTreeMap::new();
+        for evidence in &self.evidence {
+            let doubt: &mut f64 = doubts.entry(evidence.toolchain).or_insert(1.0);
+            *doubt *= 1.0 - evidence.weight.clamp(0.0, 1.0);
+        }
+        doubts
+            .into_iter()
+            .map(|(toolchain, doubt)| (toolchain, 1.0 - doubt))
+            .collect()
+    }
+}
+
+/// Infers the [`Provenance`] of `class`.
+#[must_use]
+pub fn identify(class: &Class) -> Provenance {
+    let evidence = collect_evidence(class);
+    let mut provenance = Provenance {
+        class: class.as_ref(),
+        compiler: None,
+        post_processors: Vec::new(),
+        minimum_javac_release: None,
+        evidence,
+    };
+    let confidence = provenance.confidence();
+    let (post_processors, compilers): (Vec<_>, Vec<_>) = confidence
+        .into_iter()
+        .partition(|(toolchain, _)| toolchain.is_post_processor());
+    provenance.compiler = compilers
+        .into_iter()
+        .max_by(|(_, lhs), (_, rhs)| lhs.total_cmp(rhs));
+    provenance.post_processors = post_processors;
+    if let Some((Toolchain::Javac, _)) = provenance.compiler {
+        provenance.minimum_javac_release = Some(minimum_javac_release(class));
+    }
+    provenance
+}
+
+fn collect_evidence(class: &Class) -> Vec<Evidence> {
+    let mut evidence = Vec::new();
+    let mut report = |toolchain, weight, description: &str| {
+        evidence.push(Evidence {
+            toolchain,
+            weight,
+            description: description.to_owned(),
+        });
+    };
+    attribute_evidence(class, &mut report);
+    naming_evidence(class, &mut report);
+    code_evidence(class, &mut report);
+    evidence
+}
+
+/// Collects the fingerprints in the attributes of `class`.
+fn attribute_evidence(class: &Class, report: &mut impl FnMut(Toolchain, f64, &str)) {
+    let annotations: Vec<_> = class
+        .runtime_visible_annotations
+        .iter()
+        .chain(&class.runtime_invisible_annotations)
+        .filter_map(|it| match &it.annotation_type {
+            FieldType::Object(class_ref) => Some(class_ref.binary_name.as_ref()),
+            _ => None,
+        })
+        .collect();
+    if annotations.contains(&"kotlin/Metadata") {
+        report(
+            Toolchain::Kotlin,
+            0.99,
+            "The class is annotated with @kotlin.Metadata",
+        );
+    }
+    if annotations.contains(&"scala/reflect/ScalaSignature") {
+        report(
+            Toolchain::Scala,
+            0.99,
+            "The class is annotated with @ScalaSignature",
+        );
+    }
+    for (name, _) in &class.free_attributes {
+        match name.as_str() {
+            "ScalaSig" | "ScalaInlineInfo" | "Scala" => {
+                report(Toolchain::Scala, 0.99, "The class has a Scala attribute");
+            }
+            _ => {}
+        }
+    }
+    if class
+        .interfaces
+        .iter()
+        .any(|it| &*it.binary_name == "groovy/lang/GroovyObject")
+    {
+        report(Toolchain::Groovy, 0.95, "The class implements GroovyObject");
+    }
+    let simple_name = class
+        .binary_name
+        .rsplit(['/', '$'])
+        .next()
+        .unwrap_or_default();
+    if matches!(simple_name, "DefaultImpls" | "WhenMappings") {
+        report(
+            Toolchain::Kotlin,
+            0.6,
+            "The class is a Kotlin synthetic class",
+        );
+    }
+
+    match class.source_file.as_deref() {
+        Some(source_file) if source_file.starts_with("r8-map-id-") => {
+            report(Toolchain::R8, 0.95, "The source file is an R8 mapping ID");
+        }
+        Some("SourceFile") => {
+            report(
+                Toolchain::ProGuard,
+                0.6,
+                "The source file is renamed to `SourceFile`",
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Collects the fingerprints in the names of the members of `class`.
+fn naming_evidence(class: &Class, report: &mut impl FnMut(Toolchain, f64, &str)) {
+    let member_names: Vec<_> = class
+        .fields
+        .iter()
+        .map(|it| it.name.as_str())
+        .chain(
+            class
+                .methods
+                .iter()
+                .filter(|it| !it.is_constructor() && !it.is_static_initializer_block())
+                .map(|it| it.name.as_str()),
+        )
+        .collect();
+    let obfuscated = member_names
+        .iter()
+        .filter(|it| it.len() <= 2 && it.chars().all(|c| c.is_ascii_lowercase()))
+        .count();
+    if member_names.len() >= 3 && obfuscated * 2 >= member_names.len() {
+        report(
+            Toolchain::ProGuard,
+            0.7,
+            "Most of the members have short names",
+        );
+    }
+
+    for field in &class.fields {
+        if field.name == "MODULE$" {
+            report(Toolchain::Scala, 0.7, "The class declares `MODULE$`");
+        } else if field.name.starts_with("$SwitchMap$") {
+            report(
+                Toolchain::Javac,
+                0.6,
+                "The class declares a javac switch map",
+            );
+        } else if field.name == "$staticClassInfo" {
+            report(
+                Toolchain::Groovy,
+                0.9,
+                "The class declares `$staticClassInfo`",
+            );
+        }
+    }
+    for method in &class.methods {
+        let name = method.name.as_str();
+        if name.starts_with("$SWITCH_TABLE$") {
+            report(
+                Toolchain::Ecj,
+                0.9,
+                "The class declares an ECJ switch table",
+            );
+        } else if name == "$getStaticMetaClass" {
+            report(
+                Toolchain::Groovy,
+                0.9,
+                "The class declares `$getStaticMetaClass`",
+            );
+        } else if let Some(suffix) = name.strip_prefix("lambda$") {
+            if is_digits(suffix) {
+                report(
+                    Toolchain::Ecj,
+                    0.8,
+                    "The lambda bodies are named `lambda$N`",
+                );
+            } else if suffix.rsplit_once('$').is_some_and(|(_, it)| is_digits(it)) {
+                report(
+                    Toolchain::Javac,
+                    0.7,
+                    "The lambda bodies are named `lambda$method$N`",
+                );
+            }
+        } else if let Some(suffix) = name.strip_prefix("access$") {
+            if is_digits(suffix) && suffix.len() >= 3 {
+                report(
+                    Toolchain::Javac,
+                    0.6,
+                    "The accessors are named `access$NNN`",
+                );
+            } else if is_digits(suffix) {
+                report(Toolchain::Ecj, 0.6, "The accessors are named `access$N`");
+            }
+        }
+    }
+}
+
+/// Collects the fingerprints in the instructions of `class`.
+fn code_evidence(class: &Class, report: &mut impl FnMut(Toolchain, f64, &str)) {
+    let mut owners = BTreeSet::new();
+    for (_, insn) in class
+        .methods
+        .iter()
+        .filter_map(|it| it.body.as_ref())
+        .flat_map(|it| it.instructions.iter())
+    {
+        let owner = match insn {
+            Instruction::InvokeStatic(method)
+            | Instruction::InvokeVirtual(method)
+            | Instruction::InvokeInterface(method, _)
+            | Instruction::InvokeSpecial(method) => method.owner.binary_name.as_ref(),
+            _ => continue,
+        };
+        owners.insert(owner);
+    }
+    if owners.contains("kotlin/jvm/internal/Intrinsics") {
+        report(Toolchain::Kotlin, 0.95, "The code calls Kotlin intrinsics");
+    }
+    if owners.contains("scala/runtime/BoxesRunTime") {
+        report(Toolchain::Scala, 0.8, "The code calls the Scala runtime");
+    }
+    if owners
+        .iter()
+        .any(|it| it.starts_with("org/codehaus/groovy/runtime/"))
+    {
+        report(Toolchain::Groovy, 0.9, "The code calls the Groovy runtime");
+    }
+}
+
+/// Estimates the lowest JDK release whose `javac` can emit the class.
+fn minimum_javac_release(class: &Class) -> u16 {
+    // Since JDK 5, the class file version of release N is N + 44.
+    let mut release = class.version.major().saturating_sub(44).max(1);
+    let instructions = class
+        .methods
+        .iter()
+        .filter_map(|it| it.body.as_ref())
+        .flat_map(|it| it.instructions.iter());
+    for (_, insn) in instructions {
+        if let Instruction::InvokeDynamic { name, .. } = insn {
+            if name == "makeConcatWithConstants" {
+                release = release.max(9);
+            }
+        }
+    }
+    if class.nest_host.is_some() || !class.nest_members.is_empty() {
+        release = release.max(11);
+    }
+    if class.record.is_some() {
+        release = release.max(16);
+    }
+    if !class.permitted_subclasses.is_empty() {
+        release = release.max(17);
+    }
+    release
+}
+
+fn is_digits(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|it| it.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::{class::Version, Annotation, Field, Method};
+
+    use super::*;
+
+    fn method(name: &str) -> Method {
+        Method {
+            name: name.to_owned(),
+            descriptor: "()V".parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn javac() {
+        let class = Class {
+            binary_name: "org/mokapot/Main".to_owned(),
+            version: Version::Jdk11,
+            nest_members: vec![ClassRef::new("org/mokapot/Main$Inner")],
+            methods: vec![method("run"), method("lambda$run$0")],
+            ..Default::default()
+        };
+        let provenance = identify(&class);
+        let (compiler, confidence) = provenance.compiler.unwrap();
+        assert_eq!(compiler, Toolchain::Javac);
+        assert!((confidence - 0.7).abs() < f64::EPSILON);
+        assert!(provenance.post_processors.is_empty());
+        assert_eq!(provenance.minimum_javac_release, Some(11));
+    }
+
+    #[test]
+    fn kotlin_processed_by_proguard() {
+        let class = Class {
+            binary_name: "a/b".to_owned(),
+            source_file: Some("SourceFile".to_owned()),
+            runtime_visible_annotations: vec![Annotation {
+                annotation_type: FieldType::Object(ClassRef::new("kotlin/Metadata")),
+                element_value_pairs: Vec::new(),
+            }],
+            fields: vec![Field {
+                name: "a".to_owned(),
+                ..Default::default()
+            }],
+            methods: vec![method("b"), method("c"), method("lambda$0")],
+            ..Default::default()
+        };
+        let provenance = identify(&class);
+        assert!(matches!(provenance.compiler, Some((Toolchain::Kotlin, _))));
+        assert_eq!(provenance.minimum_javac_release, None);
+        let (post_processor, confidence) = provenance.post_processors[0];
+        assert_eq!(post_processor, Toolchain::ProGuard);
+        assert!((confidence - 0.88).abs() < 1e-9);
+    }
+}
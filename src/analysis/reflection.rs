@@ -0,0 +1,1104 @@
+//! Detection and resolution of reflective usages of classes and their members.
+//!
+//! [`class_name_usages`] finds the calls that look up classes by name.
+//! [`ReflectionResolver`] goes further and tracks the `Class`, `Method`, `Field`, and
+//! `MethodHandle` objects obtained through the reflection APIs.
+//! When the names are known statically (see [`StringAnalyzer`]), it resolves the targets of the
+//! lookups and materializes the calls and field accesses made through them as
+//! [`SyntheticEdge`]s.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use crate::{
+    ir::{
+        expression::{Conversion, Expression},
+        DefUseChain, Identifier, LocalValue, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::{
+        code::ProgramCounter,
+        field, method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, ConstantValue, Field, JavaString, Method,
+    },
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::{
+    strings::{StringAnalyzer, StringValue, StringValues},
+    ResolutionContext,
+};
+
+/// A reflection API that takes the name of a class as an argument.
+struct ClassNameApi {
+    /// The owner of the API, or [`None`] if the API can be called through any subclass.
+    owner: Option<&'static str>,
+    name: &'static str,
+    descriptor: &'static str,
+    /// The index of the argument holding the class name.
+    argument_index: usize,
+}
+
+const CLASS_NAME_APIS: &[ClassNameApi] = &[
+    ClassNameApi {
+        owner: Some("java/lang/Class"),
+        name: "forName",
+        descriptor: "(Ljava/lang/String;)Ljava/lang/Class;",
+        argument_index: 0,
+    },
+    ClassNameApi {
+        owner: Some("java/lang/Class"),
+        name: "forName",
+        descriptor: "(Ljava/lang/String;ZLjava/lang/ClassLoader;)Ljava/lang/Class;",
+        argument_index: 0,
+    },
+    ClassNameApi {
+        owner: Some("java/lang/Class"),
+        name: "forName",
+        descriptor: "(Ljava/lang/Module;Ljava/lang/String;)Ljava/lang/Class;",
+        argument_index: 1,
+    },
+    ClassNameApi {
+        owner: None,
+        name: "loadClass",
+        descriptor: "(Ljava/lang/String;)Ljava/lang/Class;",
+        argument_index: 0,
+    },
+    ClassNameApi {
+        owner: None,
+        name: "loadClass",
+        descriptor: "(Ljava/lang/String;Z)Ljava/lang/Class;",
+        argument_index: 0,
+    },
+];
+
+/// A call to a reflection API that looks up a class by its name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassNameUsage {
+    /// The location of the call.
+    pub pc: ProgramCounter,
+    /// The reflection API being called.
+    pub api: MethodRef,
+    /// The class name passed to the API.
+    pub class_name: ClassNameArgument,
+}
+
+/// The class name passed to a reflection API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClassNameArgument {
+    /// A string constant.
+    Constant {
+        /// The location where the constant is loaded.
+        defined_at: ProgramCounter,
+        /// The value of the constant.
+        value: String,
+    },
+    /// A value that cannot be determined statically.
+    Unknown,
+}
+
+/// Finds the calls to the reflection APIs that look up classes by name (e.g., `Class.forName`).
+#[must_use]
+pub fn class_name_usages(method: &MokaIRMethod) -> Vec<ClassNameUsage> {
+    let du_chain = DefUseChain::new(method);
+    method
+        .instructions
+        .iter()
+        .filter_map(|(pc, insn)| {
+            let MokaInstruction::Definition {
+                expr: Expression::Call {
+                    method: api, args, ..
+                },
+                ..
+            } = insn
+            else {
+                return None;
+            };
+            let matched = class_name_api(api)?;
+            let class_name = args
+                .get(matched.argument_index)
+                .and_then(|arg| constant_string(method, &du_chain, arg))
+                .map_or(ClassNameArgument::Unknown, |(defined_at, value)| {
+                    ClassNameArgument::Constant { defined_at, value }
+                });
+            Some(ClassNameUsage {
+                pc: *pc,
+                api: api.clone(),
+                class_name,
+            })
+        })
+        .collect()
+}
+
+fn class_name_api(method: &MethodRef) -> Option<&'static ClassNameApi> {
+    CLASS_NAME_APIS.iter().find(|it| {
+        it.owner
+            .is_none_or(|owner| *owner == *method.owner.binary_name)
+            && it.name == &*method.name
+            && it.descriptor == method.descriptor.descriptor()
+    })
+}
+
+fn constant_string(
+    method: &MokaIRMethod,
+    du_chain: &DefUseChain<'_>,
+    operand: &Operand,
+) -> Option<(ProgramCounter, String)> {
+    let Operand::Just(Identifier::Local(value)) = operand else {
+        return None;
+    };
+    let defined_at = du_chain.defined_at(value)?;
+    match method.instructions.get(&defined_at)? {
+        MokaInstruction::Definition {
+            expr: Expression::Const(ConstantValue::String(JavaString::Utf8(value))),
+            ..
+        } => Some((defined_at, value.clone())),
+        _ => None,
+    }
+}
+
+/// A class or member that a reflective lookup may return.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ReflectiveTarget {
+    /// A class, e.g., returned by `Class.forName`.
+    Class(ClassRef),
+    /// A method or a constructor, e.g., returned by `Class.getMethod` or
+    /// `MethodHandles.Lookup.findVirtual`.
+    Method(MethodRef),
+    /// A field, e.g., returned by `Class.getDeclaredField` or `MethodHandles.Lookup.findGetter`.
+    Field(FieldRef),
+}
+
+/// A call to a reflection API that looks up a class or a member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReflectiveLookup {
+    /// The location of the call.
+    pub pc: ProgramCounter,
+    /// The reflection API being called.
+    pub api: MethodRef,
+    /// The possible results of the lookup, or [`None`] if they cannot be determined statically.
+    pub targets: Option<BTreeSet<ReflectiveTarget>>,
+}
+
+/// A call or a field access made through reflection.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SyntheticEdge {
+    /// A method or a constructor called by, e.g., `Method.invoke` or `MethodHandle.invokeExact`.
+    Call {
+        /// The location of the reflective call.
+        pc: ProgramCounter,
+        /// The method being called.
+        callee: MethodRef,
+    },
+    /// A field read by, e.g., `Field.get` or a getter `MethodHandle`.
+    FieldRead {
+        /// The location of the reflective access.
+        pc: ProgramCounter,
+        /// The field being read.
+        field: FieldRef,
+    },
+    /// A field written by, e.g., `Field.set` or a setter `MethodHandle`.
+    FieldWrite {
+        /// The location of the reflective access.
+        pc: ProgramCounter,
+        /// The field being written.
+        field: FieldRef,
+    },
+}
+
+/// The reflective lookups in a method and the edges materialized from them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReflectionTargets {
+    /// The calls to the reflection APIs that look up classes or members.
+    pub lookups: Vec<ReflectiveLookup>,
+    /// The calls and field accesses made through the reflective objects.
+    pub edges: Vec<SyntheticEdge>,
+}
+
+/// A reflection API that returns a class, a member, or a handle to a member.
+#[derive(Debug, Clone, Copy)]
+enum LookupApi {
+    /// `Class.forName` and `ClassLoader.loadClass`.
+    ClassName { argument_index: usize },
+    /// `Class.getMethod` and `Class.getDeclaredMethod`.
+    Method { declared_only: bool },
+    /// `Class.getConstructor` and `Class.getDeclaredConstructor`.
+    Constructor { declared_only: bool },
+    /// `Class.getField` and `Class.getDeclaredField`.
+    Field { declared_only: bool },
+    /// `MethodHandles.Lookup.findVirtual`, `findStatic`, and `findSpecial`.
+    FindMethod,
+    /// `MethodHandles.Lookup.findConstructor`.
+    FindConstructor,
+    /// `MethodHandles.Lookup.findGetter`, `findSetter`, and their static counterparts.
+    FindField { setter: bool },
+    /// `MethodHandles.Lookup.unreflect`, `unreflectSpecial`, and `unreflectConstructor`.
+    Unreflect,
+    /// `MethodHandles.Lookup.unreflectGetter` and `unreflectSetter`.
+    UnreflectField { setter: bool },
+}
+
+const CLASS: &str = "java/lang/Class";
+const LOOKUP: &str = "java/lang/invoke/MethodHandles$Lookup";
+const FIELD_GETTERS: [&str; 9] = [
+    "get",
+    "getBoolean",
+    "getByte",
+    "getChar",
+    "getShort",
+    "getInt",
+    "getLong",
+    "getFloat",
+    "getDouble",
+];
+const FIELD_SETTERS: [&str; 9] = [
+    "set",
+    "setBoolean",
+    "setByte",
+    "setChar",
+    "setShort",
+    "setInt",
+    "setLong",
+    "setFloat",
+    "setDouble",
+];
+
+impl LookupApi {
+    fn of(method: &MethodRef) -> Option<Self> {
+        if let Some(api) = class_name_api(method) {
+            return Some(Self::ClassName {
+                argument_index: api.argument_index,
+            });
+        }
+        let api = match (method.owner.binary_name.as_ref(), method.name.as_ref()) {
+            (CLASS, "getMethod") => Self::Method {
+                declared_only: false,
+            },
+            (CLASS, "getDeclaredMethod") => Self::Method {
+                declared_only: true,
+            },
+            (CLASS, "getConstructor") => Self::Constructor {
+                declared_only: false,
+            },
+            (CLASS, "getDeclaredConstructor") => Self::Constructor {
+                declared_only: true,
+            },
+            (CLASS, "getField") => Self::Field {
+                declared_only: false,
+            },
+            (CLASS, "getDeclaredField") => Self::Field {
+                declared_only: true,
+            },
+            (LOOKUP, "findVirtual" | "findStatic" | "findSpecial") => Self::FindMethod,
+            (LOOKUP, "findConstructor") => Self::FindConstructor,
+            (LOOKUP, "findGetter" | "findStaticGetter") => Self::FindField { setter: false },
+            (LOOKUP, "findSetter" | "findStaticSetter") => Self::FindField { setter: true },
+            (LOOKUP, "unreflect" | "unreflectSpecial" | "unreflectConstructor") => Self::Unreflect,
+            (LOOKUP, "unreflectGetter") => Self::UnreflectField { setter: false },
+            (LOOKUP, "unreflectSetter") => Self::UnreflectField { setter: true },
+            _ => return None,
+        };
+        Some(api)
+    }
+}
+
+/// The objects that a reflective value may refer to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Reflected {
+    /// `Class` objects.
+    Classes(BTreeSet<ClassRef>),
+    /// `Method` and `Constructor` objects, or `MethodHandle`s that call them.
+    Methods(BTreeSet<MethodRef>),
+    /// `Field` objects.
+    Fields(BTreeSet<FieldRef>),
+    /// `MethodHandle`s that read the fields.
+    Getters(BTreeSet<FieldRef>),
+    /// `MethodHandle`s that write the fields.
+    Setters(BTreeSet<FieldRef>),
+}
+
+impl Reflected {
+    fn join(self, other: Self) -> Option<Self> {
+        match (self, other) {
+            (Self::Classes(mut lhs), Self::Classes(rhs)) => {
+                lhs.extend(rhs);
+                Some(Self::Classes(lhs))
+            }
+            (Self::Methods(mut lhs), Self::Methods(rhs)) => {
+                lhs.extend(rhs);
+                Some(Self::Methods(lhs))
+            }
+            (Self::Fields(mut lhs), Self::Fields(rhs)) => {
+                lhs.extend(rhs);
+                Some(Self::Fields(lhs))
+            }
+            (Self::Getters(mut lhs), Self::Getters(rhs)) => {
+                lhs.extend(rhs);
+                Some(Self::Getters(lhs))
+            }
+            (Self::Setters(mut lhs), Self::Setters(rhs)) => {
+                lhs.extend(rhs);
+                Some(Self::Setters(lhs))
+            }
+            _ => None,
+        }
+    }
+
+    fn targets(self) -> BTreeSet<ReflectiveTarget> {
+        match self {
+            Self::Classes(classes) => classes.into_iter().map(ReflectiveTarget::Class).collect(),
+            Self::Methods(methods) => methods.into_iter().map(ReflectiveTarget::Method).collect(),
+            Self::Fields(fields) | Self::Getters(fields) | Self::Setters(fields) => {
+                fields.into_iter().map(ReflectiveTarget::Field).collect()
+            }
+        }
+    }
+}
+
+/// Resolves the targets of the reflective lookups in a method.
+///
+/// The names of the classes and members are computed with a [`StringAnalyzer`].
+/// Lookups that take a method descriptor or a field type (e.g., `MethodHandles.Lookup.findVirtual`)
+/// are resolved symbolically, while lookups by name only (e.g., `Class.getMethod`) require the
+/// looked-up classes to be in the [`ResolutionContext`].
+#[derive(Debug)]
+pub struct ReflectionResolver<'a> {
+    ir_method: &'a MokaIRMethod,
+    resolution_context: Option<&'a ResolutionContext>,
+}
+
+impl<'a> ReflectionResolver<'a> {
+    /// Creates a new resolver for `ir_method`.
+    #[must_use]
+    pub const fn new(ir_method: &'a MokaIRMethod) -> Self {
+        Self {
+            ir_method,
+            resolution_context: None,
+        }
+    }
+
+    /// Uses the given resolution context to look up the members of the classes by name.
+    #[must_use]
+    pub const fn with_resolution_context(
+        mut self,
+        resolution_context: &'a ResolutionContext,
+    ) -> Self {
+        self.resolution_context = Some(resolution_context);
+        self
+    }
+
+    /// Finds the reflective lookups in the method and the edges made through them.
+    #[must_use]
+    pub fn resolve(self) -> ReflectionTargets {
+        let evaluator = LookupEvaluator {
+            ir_method: self.ir_method,
+            resolution_context: self.resolution_context,
+            du_chain: DefUseChain::new(self.ir_method),
+            strings: StringAnalyzer::new(self.ir_method).analyze(),
+        };
+        let mut targets = ReflectionTargets::default();
+        for (pc, insn) in &self.ir_method.instructions {
+            let MokaInstruction::Definition {
+                expr: Expression::Call { method, this, args },
+                ..
+            } = insn
+            else {
+                continue;
+            };
+            if let Some(api) = LookupApi::of(method) {
+                let reflected =
+                    evaluator.lookup(*pc, api, this.as_ref(), args, &mut HashSet::new());
+                targets.lookups.push(ReflectiveLookup {
+                    pc: *pc,
+                    api: method.clone(),
+                    targets: reflected.map(Reflected::targets),
+                });
+            } else if let Some(receiver) = this {
+                targets.edges.extend(evaluator.edges(*pc, method, receiver));
+            }
+        }
+        targets
+    }
+}
+
+struct LookupEvaluator<'a> {
+    ir_method: &'a MokaIRMethod,
+    resolution_context: Option<&'a ResolutionContext>,
+    du_chain: DefUseChain<'a>,
+    strings: StringValues,
+}
+
+impl LookupEvaluator<'_> {
+    fn edges(
+        &self,
+        pc: ProgramCounter,
+        method: &MethodRef,
+        receiver: &Operand,
+    ) -> Vec<SyntheticEdge> {
+        let owner = method.owner.binary_name.as_ref();
+        let name = method.name.as_ref();
+        let is_invocation = matches!(
+            (owner, name),
+            ("java/lang/reflect/Method", "invoke")
+                | ("java/lang/reflect/Constructor", "newInstance")
+                | (
+                    "java/lang/invoke/MethodHandle",
+                    "invoke" | "invokeExact" | "invokeWithArguments"
+                )
+        );
+        let is_field_access = owner == "java/lang/reflect/Field"
+            && (FIELD_GETTERS.contains(&name) || FIELD_SETTERS.contains(&name));
+        let is_instantiation = (owner, name) == (CLASS, "newInstance");
+        if !(is_invocation || is_field_access || is_instantiation) {
+            return Vec::new();
+        }
+        let Some(reflected) = self.reflected(receiver, &mut HashSet::new()) else {
+            return Vec::new();
+        };
+        match reflected {
+            Reflected::Methods(callees) if is_invocation => callees
+                .into_iter()
+                .map(|callee| SyntheticEdge::Call { pc, callee })
+                .collect(),
+            Reflected::Classes(classes) if is_instantiation => classes
+                .into_iter()
+                .map(|owner| SyntheticEdge::Call {
+                    pc,
+                    callee: MethodRef::new(
+                        owner,
+                        "<init>",
+                        MethodDescriptor::new([], ReturnType::Void),
+                    ),
+                })
+                .collect(),
+            Reflected::Fields(fields) if is_field_access && FIELD_GETTERS.contains(&name) => fields
+                .into_iter()
+                .map(|field| SyntheticEdge::FieldRead { pc, field })
+                .collect(),
+            Reflected::Fields(fields) if is_field_access => fields
+                .into_iter()
+                .map(|field| SyntheticEdge::FieldWrite { pc, field })
+                .collect(),
+            Reflected::Getters(fields) if is_invocation => fields
+                .into_iter()
+                .map(|field| SyntheticEdge::FieldRead { pc, field })
+                .collect(),
+            Reflected::Setters(fields) if is_invocation => fields
+                .into_iter()
+                .map(|field| SyntheticEdge::FieldWrite { pc, field })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn reflected(
+        &self,
+        operand: &Operand,
+        visiting: &mut HashSet<LocalValue>,
+    ) -> Option<Reflected> {
+        operand
+            .into_iter()
+            .map(|id| match id {
+                Identifier::Local(value) => self.reflected_value(*value, visiting),
+                _ => None,
+            })
+            .try_fold(None, |acc: Option<Reflected>, it| {
+                let it = it?;
+                match acc {
+                    Some(acc) => acc.join(it).map(Some),
+                    None => Some(Some(it)),
+                }
+            })
+            .flatten()
+    }
+
+    fn reflected_value(
+        &self,
+        value: LocalValue,
+        visiting: &mut HashSet<LocalValue>,
+    ) -> Option<Reflected> {
+        if !visiting.insert(value) {
+            // The value is defined in terms of itself, e.g., in a loop.
+            return None;
+        }
+        let reflected = self.reflected_definition(value, visiting);
+        visiting.remove(&value);
+        reflected
+    }
+
+    fn reflected_definition(
+        &self,
+        value: LocalValue,
+        visiting: &mut HashSet<LocalValue>,
+    ) -> Option<Reflected> {
+        let pc = self.du_chain.defined_at(&value)?;
+        let MokaInstruction::Definition { expr, .. } = self.ir_method.instructions.get(&pc)? else {
+            return None;
+        };
+        match expr {
+            Expression::Const(ConstantValue::Class(class)) => {
+                Some(Reflected::Classes(BTreeSet::from([class.clone()])))
+            }
+            Expression::Conversion(Conversion::CheckCast(operand, _)) => {
+                self.reflected(operand, visiting)
+            }
+            Expression::Call { method, this, args } => match LookupApi::of(method) {
+                Some(api) => self.lookup(pc, api, this.as_ref(), args, visiting),
+                None if &*method.name == "getClass"
+                    && method.descriptor.descriptor() == "()Ljava/lang/Class;" =>
+                {
+                    this.as_ref()
+                        .and_then(|it| self.allocated_class(it))
+                        .map(|class| Reflected::Classes(BTreeSet::from([class])))
+                }
+                None => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn lookup(
+        &self,
+        pc: ProgramCounter,
+        api: LookupApi,
+        this: Option<&Operand>,
+        args: &[Operand],
+        visiting: &mut HashSet<LocalValue>,
+    ) -> Option<Reflected> {
+        match api {
+            LookupApi::ClassName { argument_index } => {
+                let names = self.strings_of(pc, args.get(argument_index)?)?;
+                Some(Reflected::Classes(
+                    names
+                        .into_iter()
+                        .map(|it| ClassRef::new(it.replace('.', "/")))
+                        .collect(),
+                ))
+            }
+            LookupApi::Method { declared_only } => {
+                let classes = self.classes_of(this, visiting)?;
+                let names = self.strings_of(pc, args.first()?)?;
+                let methods = classes
+                    .iter()
+                    .map(|class| self.methods_named(class, &names, declared_only))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Reflected::Methods(methods.into_iter().flatten().collect()))
+            }
+            LookupApi::Constructor { declared_only } => {
+                let context = self.resolution_context?;
+                let classes = self.classes_of(this, visiting)?;
+                let constructors = classes
+                    .iter()
+                    .map(|class| {
+                        let class = context.get_class(class)?;
+                        Some(
+                            class
+                                .methods
+                                .iter()
+                                .filter(|it| it.is_constructor())
+                                .filter(move |it| {
+                                    declared_only
+                                        || it.access_flags.contains(method::AccessFlags::PUBLIC)
+                                })
+                                .map(Method::as_ref),
+                        )
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Reflected::Methods(
+                    constructors.into_iter().flatten().collect(),
+                ))
+            }
+            LookupApi::Field { declared_only } => {
+                let classes = self.classes_of(this, visiting)?;
+                let names = self.strings_of(pc, args.first()?)?;
+                let fields = classes
+                    .iter()
+                    .flat_map(|class| names.iter().map(move |name| (class, name)))
+                    .map(|(class, name)| self.field_named(class, name, declared_only))
+                    .collect::<Option<_>>()?;
+                Some(Reflected::Fields(fields))
+            }
+            LookupApi::FindMethod | LookupApi::FindConstructor | LookupApi::FindField { .. } => {
+                self.find_member(pc, api, args, visiting)
+            }
+            LookupApi::Unreflect => match self.reflected(args.first()?, visiting)? {
+                methods @ Reflected::Methods(_) => Some(methods),
+                _ => None,
+            },
+            LookupApi::UnreflectField { setter } => {
+                match self.reflected(args.first()?, visiting)? {
+                    Reflected::Fields(fields) if setter => Some(Reflected::Setters(fields)),
+                    Reflected::Fields(fields) => Some(Reflected::Getters(fields)),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Evaluates the `find*` methods of `MethodHandles.Lookup`, which take the descriptor or the
+    /// type of the member.
+    fn find_member(
+        &self,
+        pc: ProgramCounter,
+        api: LookupApi,
+        args: &[Operand],
+        visiting: &mut HashSet<LocalValue>,
+    ) -> Option<Reflected> {
+        match api {
+            LookupApi::FindMethod => {
+                let classes = self.classes_of(args.first(), visiting)?;
+                let names = self.strings_of(pc, args.get(1)?)?;
+                let descriptor = self.method_type(args.get(2)?)?;
+                Some(Reflected::Methods(
+                    classes
+                        .iter()
+                        .flat_map(|owner| {
+                            names
+                                .iter()
+                                .map(|name| MethodRef::new(owner.clone(), name, descriptor.clone()))
+                        })
+                        .collect(),
+                ))
+            }
+            LookupApi::FindConstructor => {
+                let classes = self.classes_of(args.first(), visiting)?;
+                let descriptor = self.method_type(args.get(1)?)?;
+                Some(Reflected::Methods(
+                    classes
+                        .into_iter()
+                        .map(|owner| MethodRef::new(owner, "<init>", descriptor.clone()))
+                        .collect(),
+                ))
+            }
+            LookupApi::FindField { setter } => {
+                let owners = self.classes_of(args.first(), visiting)?;
+                let names = self.strings_of(pc, args.get(1)?)?;
+                let field_types = self.classes_of(args.get(2), visiting)?;
+                let fields = owners
+                    .iter()
+                    .flat_map(|owner| names.iter().map(move |name| (owner, name)))
+                    .flat_map(|(owner, name)| {
+                        field_types.iter().map(move |field_type| {
+                            FieldRef::new(owner.clone(), name, field_type_of(field_type))
+                        })
+                    })
+                    .collect();
+                Some(if setter {
+                    Reflected::Setters(fields)
+                } else {
+                    Reflected::Getters(fields)
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn classes_of(
+        &self,
+        operand: Option<&Operand>,
+        visiting: &mut HashSet<LocalValue>,
+    ) -> Option<BTreeSet<ClassRef>> {
+        match self.reflected(operand?, visiting)? {
+            Reflected::Classes(classes) => Some(classes),
+            _ => None,
+        }
+    }
+
+    fn strings_of(&self, pc: ProgramCounter, operand: &Operand) -> Option<BTreeSet<String>> {
+        match self.strings.value_at(pc, operand) {
+            StringValue::Known(candidates) => Some(candidates),
+            StringValue::Unknown => None,
+        }
+    }
+
+    fn method_type(&self, operand: &Operand) -> Option<MethodDescriptor> {
+        let Operand::Just(Identifier::Local(value)) = operand else {
+            return None;
+        };
+        let defined_at = self.du_chain.defined_at(value)?;
+        match self.ir_method.instructions.get(&defined_at)? {
+            MokaInstruction::Definition {
+                expr: Expression::Const(ConstantValue::MethodType(descriptor)),
+                ..
+            } => Some(descriptor.clone()),
+            _ => None,
+        }
+    }
+
+    fn allocated_class(&self, operand: &Operand) -> Option<ClassRef> {
+        let Operand::Just(Identifier::Local(value)) = operand else {
+            return None;
+        };
+        let defined_at = self.du_chain.defined_at(value)?;
+        match self.ir_method.instructions.get(&defined_at)? {
+            MokaInstruction::Definition {
+                expr: Expression::New(class),
+                ..
+            } => Some(class.clone()),
+            _ => None,
+        }
+    }
+
+    /// Finds the methods named one of `names` in `class`, or [`None`] if `class` is not in the
+    /// resolution context.
+    /// Unless `declared_only` is set, the public methods inherited from the super classes and
+    /// interfaces are included, as `Class.getMethod` does.
+    fn methods_named(
+        &self,
+        class: &ClassRef,
+        names: &BTreeSet<String>,
+        declared_only: bool,
+    ) -> Option<BTreeSet<MethodRef>> {
+        let context = self.resolution_context?;
+        let class = context.get_class(class)?;
+        if declared_only {
+            return Some(
+                class
+                    .methods
+                    .iter()
+                    .filter(|it| names.contains(&it.name))
+                    .map(Method::as_ref)
+                    .collect(),
+            );
+        }
+        let mut methods = BTreeSet::new();
+        let mut seen_signatures = HashSet::new();
+        for class in supertypes(context, class) {
+            for method in &class.methods {
+                let is_public = method.access_flags.contains(method::AccessFlags::PUBLIC);
+                if is_public
+                    && names.contains(&method.name)
+                    && seen_signatures.insert((&method.name, &method.descriptor))
+                {
+                    methods.insert(method.as_ref());
+                }
+            }
+        }
+        Some(methods)
+    }
+
+    /// Finds the field named `name` in `class`, or [`None`] if it cannot be found.
+    /// Unless `declared_only` is set, the public fields inherited from the super classes and
+    /// interfaces are included, as `Class.getField` does.
+    fn field_named(&self, class: &ClassRef, name: &str, declared_only: bool) -> Option<FieldRef> {
+        let context = self.resolution_context?;
+        let class = context.get_class(class)?;
+        if declared_only {
+            return class
+                .fields
+                .iter()
+                .find(|it| it.name == name)
+                .map(Field::as_ref);
+        }
+        supertypes(context, class)
+            .flat_map(|class| &class.fields)
+            .find(|it| it.name == name && it.access_flags.contains(field::AccessFlags::PUBLIC))
+            .map(Field::as_ref)
+    }
+}
+
+/// Iterates over `class` and its super classes and interfaces that are in `context`.
+fn supertypes<'a>(
+    context: &'a ResolutionContext,
+    class: &'a Class,
+) -> impl Iterator<Item = &'a Class> {
+    let mut queue = VecDeque::from([class]);
+    let mut visited = HashSet::from([class.binary_name.as_str()]);
+    std::iter::from_fn(move || {
+        let class = queue.pop_front()?;
+        let supertypes = class.interfaces.iter().chain(&class.super_class);
+        for supertype in supertypes.filter_map(|it| context.get_class(it)) {
+            if visited.insert(supertype.binary_name.as_str()) {
+                queue.push_back(supertype);
+            }
+        }
+        Some(class)
+    })
+}
+
+/// Converts the class of a `Class` constant to the type it denotes.
+fn field_type_of(class: &ClassRef) -> FieldType {
+    if class.binary_name.starts_with('[') {
+        if let Ok(array_type) = class.binary_name.parse() {
+            return array_type;
+        }
+    }
+    FieldType::Object(class.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        analysis::{ClassHierarchy, InterfaceImplHierarchy},
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, InstructionList, MethodBody},
+            interop::jasmin,
+        },
+        types::field_type::PrimitiveType,
+    };
+
+    use super::*;
+    use crate::tests::method_ref;
+
+    fn for_name() -> MethodRef {
+        MethodRef::new(
+            ClassRef::new("java/lang/Class"),
+            "forName",
+            "(Ljava/lang/String;)Ljava/lang/Class;".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn constant_class_name() {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            descriptor: "(Ljava/lang/String;)V".parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 1,
+                max_locals: 1,
+                instructions: InstructionList::from([
+                    (
+                        0.into(),
+                        Instruction::Ldc(ConstantValue::String(JavaString::Utf8(
+                            "org.mokapot.Foo".to_owned(),
+                        ))),
+                    ),
+                    (2.into(), Instruction::InvokeStatic(for_name())),
+                    (5.into(), Instruction::Pop),
+                    (6.into(), Instruction::ALoad0),
+                    (7.into(), Instruction::InvokeStatic(for_name())),
+                    (10.into(), Instruction::Pop),
+                    (11.into(), Instruction::Return),
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let ir_method = method.brew().unwrap();
+        let usages = class_name_usages(&ir_method);
+        assert_eq!(
+            usages,
+            vec![
+                ClassNameUsage {
+                    pc: 2.into(),
+                    api: for_name(),
+                    class_name: ClassNameArgument::Constant {
+                        defined_at: 0.into(),
+                        value: "org.mokapot.Foo".to_owned()
+                    }
+                },
+                ClassNameUsage {
+                    pc: 7.into(),
+                    api: for_name(),
+                    class_name: ClassNameArgument::Unknown
+                }
+            ]
+        );
+    }
+
+    const BASE: &str = "
+        .bytecode 52.0
+        .class public org/mokapot/Base
+        .super java/lang/Object
+        .field public name Ljava/lang/String;
+        .method public run()V
+            .limit stack 0
+            return
+        .end method
+    ";
+
+    const PLUGIN: &str = r#"
+        .bytecode 52.0
+        .class public org/mokapot/Plugin
+        .super org/mokapot/Base
+        .field private count I
+        .method public <init>()V
+            .limit stack 1
+            aload_0
+            invokespecial org/mokapot/Base/<init>()V
+            return
+        .end method
+        .method public run(I)V
+            .limit stack 0
+            return
+        .end method
+        .method public static reflect(Ljava/lang/String;)V
+            .limit stack 4
+            .limit locals 2
+            new java/lang/StringBuilder
+            dup
+            invokespecial java/lang/StringBuilder/<init>()V
+            ldc "org.mokapot."
+            invokevirtual java/lang/StringBuilder/append(Ljava/lang/String;)Ljava/lang/StringBuilder;
+            ldc "Plugin"
+            invokevirtual java/lang/StringBuilder/append(Ljava/lang/String;)Ljava/lang/StringBuilder;
+            invokevirtual java/lang/StringBuilder/toString()Ljava/lang/String;
+            invokestatic java/lang/Class/forName(Ljava/lang/String;)Ljava/lang/Class;
+            astore_1
+            aload_1
+            invokevirtual java/lang/Class/newInstance()Ljava/lang/Object;
+            aload_1
+            ldc "run"
+            aconst_null
+            invokevirtual java/lang/Class/getMethod(Ljava/lang/String;[Ljava/lang/Class;)Ljava/lang/reflect/Method;
+            swap
+            aconst_null
+            invokevirtual java/lang/reflect/Method/invoke(Ljava/lang/Object;[Ljava/lang/Object;)Ljava/lang/Object;
+            pop
+            aload_1
+            ldc "count"
+            invokevirtual java/lang/Class/getDeclaredField(Ljava/lang/String;)Ljava/lang/reflect/Field;
+            aconst_null
+            invokevirtual java/lang/reflect/Field/getInt(Ljava/lang/Object;)I
+            pop
+            aload_1
+            aload_0
+            invokevirtual java/lang/Class/getDeclaredField(Ljava/lang/String;)Ljava/lang/reflect/Field;
+            pop
+            return
+        .end method
+    "#;
+
+    const HANDLES: &str = r#"
+        .bytecode 52.0
+        .class public Handles
+        .super java/lang/Object
+        .method public static handles()V
+            .limit stack 5
+            invokestatic java/lang/invoke/MethodHandles/lookup()Ljava/lang/invoke/MethodHandles$Lookup;
+            dup
+            ldc class org/mokapot/Plugin
+            ldc "run"
+            ldc methodtype (I)V
+            invokevirtual java/lang/invoke/MethodHandles$Lookup/findVirtual(Ljava/lang/Class;Ljava/lang/String;Ljava/lang/invoke/MethodType;)Ljava/lang/invoke/MethodHandle;
+            invokevirtual java/lang/invoke/MethodHandle/invokeExact()V
+            ldc class org/mokapot/Plugin
+            ldc "count"
+            ldc class [I
+            invokevirtual java/lang/invoke/MethodHandles$Lookup/findSetter(Ljava/lang/Class;Ljava/lang/String;Ljava/lang/Class;)Ljava/lang/invoke/MethodHandle;
+            invokevirtual java/lang/invoke/MethodHandle/invoke()V
+            return
+        .end method
+    "#;
+
+    fn context() -> ResolutionContext {
+        let classes: BTreeMap<_, _> = [BASE, PLUGIN]
+            .into_iter()
+            .map(|it| jasmin::read(it).unwrap())
+            .map(|it| (it.as_ref(), it))
+            .collect();
+        ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(classes.values()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(classes.values()),
+            application_classes: classes,
+            library_classes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn resolve_class_members() {
+        let context = context();
+        let plugin = &context.application_classes[&ClassRef::new("org/mokapot/Plugin")];
+        let ir_method = plugin.methods[2].brew().unwrap();
+        let ReflectionTargets { lookups, edges } = ReflectionResolver::new(&ir_method)
+            .with_resolution_context(&context)
+            .resolve();
+
+        let targets: Vec<_> = lookups.into_iter().map(|it| it.targets).collect();
+        let count = FieldRef::new(
+            ClassRef::new("org/mokapot/Plugin"),
+            "count",
+            FieldType::Base(PrimitiveType::Int),
+        );
+        let run = method_ref("org/mokapot/Base", "run", "()V");
+        let run_int = method_ref("org/mokapot/Plugin", "run", "(I)V");
+        assert_eq!(
+            targets,
+            vec![
+                Some(BTreeSet::from([ReflectiveTarget::Class(ClassRef::new(
+                    "org/mokapot/Plugin"
+                ))])),
+                Some(BTreeSet::from([
+                    ReflectiveTarget::Method(run.clone()),
+                    ReflectiveTarget::Method(run_int.clone()),
+                ])),
+                Some(BTreeSet::from([ReflectiveTarget::Field(count.clone())])),
+                None,
+            ]
+        );
+        let pcs: Vec<_> = edges
+            .iter()
+            .map(|it| match it {
+                SyntheticEdge::Call { pc, .. }
+                | SyntheticEdge::FieldRead { pc, .. }
+                | SyntheticEdge::FieldWrite { pc, .. } => *pc,
+            })
+            .collect();
+        assert_eq!(
+            edges,
+            vec![
+                SyntheticEdge::Call {
+                    pc: pcs[0],
+                    callee: method_ref("org/mokapot/Plugin", "<init>", "()V"),
+                },
+                SyntheticEdge::Call {
+                    pc: pcs[1],
+                    callee: run,
+                },
+                SyntheticEdge::Call {
+                    pc: pcs[2],
+                    callee: run_int,
+                },
+                SyntheticEdge::FieldRead {
+                    pc: pcs[3],
+                    field: count,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn members_unknown_without_context() {
+        let plugin = jasmin::read(PLUGIN).unwrap();
+        let ir_method = plugin.methods[2].brew().unwrap();
+        let ReflectionTargets { lookups, edges } = ReflectionResolver::new(&ir_method).resolve();
+        assert!(lookups[0].targets.is_some());
+        assert!(lookups[1..].iter().all(|it| it.targets.is_none()));
+        assert_eq!(edges.len(), 1);
+    }
+
+    #[test]
+    fn method_handles() {
+        let handles = jasmin::read(HANDLES).unwrap();
+        let ir_method = handles.methods[0].brew().unwrap();
+        let ReflectionTargets { lookups, edges } = ReflectionResolver::new(&ir_method).resolve();
+        let run_int = method_ref("org/mokapot/Plugin", "run", "(I)V");
+        let count = FieldRef::new(
+            ClassRef::new("org/mokapot/Plugin"),
+            "count",
+            "[I".parse().unwrap(),
+        );
+        assert_eq!(
+            lookups
+                .iter()
+                .map(|it| it.targets.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                Some(BTreeSet::from([ReflectiveTarget::Method(run_int.clone())])),
+                Some(BTreeSet::from([ReflectiveTarget::Field(count.clone())])),
+            ]
+        );
+        assert!(matches!(
+            edges.as_slice(),
+            [
+                SyntheticEdge::Call { callee, .. },
+                SyntheticEdge::FieldWrite { field, .. },
+            ] if *callee == run_int && *field == count
+        ));
+    }
+}
@@ -0,0 +1,303 @@
+//! Rewriting of class names in string constants when relocating packages.
+//!
+//! When the classes in a package are moved to another package (e.g., when shading a library),
+//! the string constants naming the relocated classes must be rewritten as well if they are used
+//! to look up classes reflectively (e.g., `Class.forName("com.example.Foo")`).
+//! Only the string constants that flow into the reflection APIs recognized by
+//! [`class_name_usages`] are rewritten.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    ir::MokaIRMethodExt,
+    jvm::{
+        code::{Instruction, ProgramCounter},
+        references::MethodRef,
+        Class, ConstantValue, JavaString, Method,
+    },
+};
+
+use super::reflection::{class_name_usages, ClassNameArgument};
+
+/// Relocation of the classes in a package (and its subpackages) to another package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageRelocation {
+    from: String,
+    to: String,
+}
+
+impl PackageRelocation {
+    /// Creates a relocation from the package `from` to the package `to`.
+    /// Both are binary names of packages (e.g., `com/example`).
+    pub fn new(from: impl Into<String>, to: impl Into<String>) -> Self {
+        let from = from.into().trim_end_matches('/').to_owned();
+        let to = to.into().trim_end_matches('/').to_owned();
+        Self { from, to }
+    }
+
+    /// Relocates a class name in the form used by the reflection APIs (e.g., `com.example.Foo` or
+    /// `[Lcom.example.Foo;`).
+    /// Returns [`None`] if the class is not in the relocated package.
+    #[must_use]
+    pub fn relocate_class_name(&self, class_name: &str) -> Option<String> {
+        let element_name = class_name.trim_start_matches('[');
+        let dimensions = &class_name[..class_name.len() - element_name.len()];
+        let (element_name, suffix) = if dimensions.is_empty() {
+            (element_name, "")
+        } else {
+            (element_name.strip_prefix('L')?.strip_suffix(';')?, ";")
+        };
+        let from = self.from.replace('/', ".");
+        let simple_name = element_name.strip_prefix(&from)?.strip_prefix('.')?;
+        let to = self.to.replace('/', ".");
+        let array_prefix = if dimensions.is_empty() { "" } else { "L" };
+        Some(format!(
+            "{dimensions}{array_prefix}{to}.{simple_name}{suffix}"
+        ))
+    }
+}
+
+/// A rewrite of a string constant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringRewrite {
+    /// The method containing the string constant.
+    pub method: MethodRef,
+    /// The location where the string constant is loaded.
+    pub pc: ProgramCounter,
+    /// The original value.
+    pub original: String,
+    /// The relocated value.
+    pub relocated: String,
+}
+
+/// A string that is left unchanged by the rewriting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedString {
+    /// The method containing the string.
+    pub method: MethodRef,
+    /// The location of the string constant or the reflective call.
+    pub pc: ProgramCounter,
+    /// The reason for not rewriting the string.
+    pub reason: SkipReason,
+}
+
+/// The reason why a string is not rewritten.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The string names a relocated class but is not used to look up the class reflectively.
+    NotUsedReflectively(String),
+    /// A class is looked up reflectively with a name that cannot be determined statically.
+    UnknownClassName,
+    /// The method cannot be analyzed.
+    Unanalyzable,
+}
+
+/// A report on the rewriting of string constants.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RewriteReport {
+    /// The strings that are (or would be in a dry run) rewritten.
+    pub rewrites: Vec<StringRewrite>,
+    /// The strings that are not rewritten.
+    pub skipped: Vec<SkippedString>,
+}
+
+/// Rewrites the class names in string constants according to a list of [`PackageRelocation`]s.
+#[derive(Debug, Clone)]
+pub struct StringRelocator {
+    relocations: Vec<PackageRelocation>,
+}
+
+impl StringRelocator {
+    /// Creates a new relocator.
+    #[must_use]
+    pub fn new(relocations: Vec<PackageRelocation>) -> Self {
+        Self { relocations }
+    }
+
+    fn relocate(&self, class_name: &str) -> Option<String> {
+        self.relocations
+            .iter()
+            .find_map(|it| it.relocate_class_name(class_name))
+    }
+
+    /// Reports the strings that would be rewritten in `class` without modifying it.
+    #[must_use]
+    pub fn dry_run(&self, class: &Class) -> RewriteReport {
+        let mut report = RewriteReport::default();
+        for method in &class.methods {
+            self.plan_method(method, &mut report);
+        }
+        report
+    }
+
+    /// Rewrites the strings in `class`, and returns a report on what has been done.
+    pub fn rewrite(&self, class: &mut Class) -> RewriteReport {
+        let report = self.dry_run(class);
+        for rewrite in &report.rewrites {
+            let instruction = class
+                .methods
+                .iter_mut()
+                .find(|it| {
+                    *it.name == *rewrite.method.name && it.descriptor == rewrite.method.descriptor
+                })
+                .and_then(|it| it.body.as_mut())
+                .and_then(|it| it.instructions.get_mut(&rewrite.pc));
+            if let Some(
+                Instruction::Ldc(ConstantValue::String(value))
+                | Instruction::LdcW(ConstantValue::String(value)),
+            ) = instruction
+            {
+                *value = JavaString::Utf8(rewrite.relocated.clone());
+            }
+        }
+        report
+    }
+
+    fn plan_method(&self, method: &Method, report: &mut RewriteReport) {
+        let Some(body) = method.body.as_ref() else {
+            return;
+        };
+        let method_ref = method.as_ref();
+        let Ok(ir_method) = method.brew() else {
+            report.skipped.push(SkippedString {
+                method: method_ref,
+                pc: ProgramCounter::ZERO,
+                reason: SkipReason::Unanalyzable,
+            });
+            return;
+        };
+
+        let mut used_reflectively = BTreeSet::new();
+        for usage in class_name_usages(&ir_method) {
+            match usage.class_name {
+                ClassNameArgument::Constant { defined_at, value } => {
+                    if !used_reflectively.insert(defined_at) {
+                        continue;
+                    }
+                    if let Some(relocated) = self.relocate(&value) {
+                        report.rewrites.push(StringRewrite {
+                            method: method_ref.clone(),
+                            pc: defined_at,
+                            original: value,
+                            relocated,
+                        });
+                    }
+                }
+                ClassNameArgument::Unknown => report.skipped.push(SkippedString {
+                    method: method_ref.clone(),
+                    pc: usage.pc,
+                    reason: SkipReason::UnknownClassName,
+                }),
+            }
+        }
+
+        for (pc, insn) in &body.instructions {
+            let (Instruction::Ldc(ConstantValue::String(JavaString::Utf8(value)))
+            | Instruction::LdcW(ConstantValue::String(JavaString::Utf8(value)))) = insn
+            else {
+                continue;
+            };
+            if used_reflectively.contains(pc) || self.relocate(value).is_none() {
+                continue;
+            }
+            report.skipped.push(SkippedString {
+                method: method_ref.clone(),
+                pc: *pc,
+                reason: SkipReason::NotUsedReflectively(value.clone()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::{code::MethodBody, method, references::ClassRef};
+
+    use super::*;
+
+    fn string(value: &str) -> ConstantValue {
+        ConstantValue::String(JavaString::Utf8(value.to_owned()))
+    }
+
+    fn for_name() -> MethodRef {
+        MethodRef::new(
+            ClassRef::new("java/lang/Class"),
+            "forName",
+            "(Ljava/lang/String;)Ljava/lang/Class;".parse().unwrap(),
+        )
+    }
+
+    #[test]
+    fn relocate_class_name() {
+        let relocation = PackageRelocation::new("com/example", "shaded/com/example/");
+        assert_eq!(
+            relocation.relocate_class_name("com.example.Foo"),
+            Some("shaded.com.example.Foo".to_owned())
+        );
+        assert_eq!(
+            relocation.relocate_class_name("com.example.sub.Foo$Bar"),
+            Some("shaded.com.example.sub.Foo$Bar".to_owned())
+        );
+        assert_eq!(
+            relocation.relocate_class_name("[[Lcom.example.Foo;"),
+            Some("[[Lshaded.com.example.Foo;".to_owned())
+        );
+        assert_eq!(relocation.relocate_class_name("com.examples.Foo"), None);
+        assert_eq!(relocation.relocate_class_name("[I"), None);
+    }
+
+    #[test]
+    fn rewrite_reflective_strings() {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: "test".to_owned(),
+            owner: ClassRef::new("org/mokapot/Test"),
+            body: Some(MethodBody {
+                max_stack: 1,
+                max_locals: 0,
+                instructions: [
+                    (0.into(), Instruction::Ldc(string("com.example.Foo"))),
+                    (2.into(), Instruction::InvokeStatic(for_name())),
+                    (5.into(), Instruction::Pop),
+                    (6.into(), Instruction::Ldc(string("com.example.Bar"))),
+                    (8.into(), Instruction::Pop),
+                    (9.into(), Instruction::Return),
+                ]
+                .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut class = Class {
+            binary_name: "org/mokapot/Test".to_owned(),
+            methods: vec![method],
+            ..Default::default()
+        };
+        let relocator = StringRelocator::new(vec![PackageRelocation::new("com/example", "shaded")]);
+
+        let report = relocator.rewrite(&mut class);
+        assert_eq!(report.rewrites.len(), 1);
+        assert_eq!(report.rewrites[0].relocated, "shaded.Foo");
+        assert_eq!(
+            report
+                .skipped
+                .iter()
+                .map(|it| &it.reason)
+                .collect::<Vec<_>>(),
+            vec![&SkipReason::NotUsedReflectively(
+                "com.example.Bar".to_owned()
+            )]
+        );
+
+        let instructions = &class.methods[0].body.as_ref().unwrap().instructions;
+        assert_eq!(
+            instructions.get(&0.into()),
+            Some(&Instruction::Ldc(string("shaded.Foo")))
+        );
+        assert_eq!(
+            instructions.get(&6.into()),
+            Some(&Instruction::Ldc(string("com.example.Bar")))
+        );
+        assert!(relocator.dry_run(&class).rewrites.is_empty());
+    }
+}
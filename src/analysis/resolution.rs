@@ -0,0 +1,638 @@
+//! Method and field resolution and selection following the JVM specification.
+//!
+//! [`resolve_method`], [`resolve_interface_method`], and [`resolve_field`] find the members
+//! referred to by symbolic references as described in
+//! [JVMS §5.4.3](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.3),
+//! and [`select_method`] finds the method invoked on a receiver as described in
+//! [JVMS §5.4.6](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.6).
+//! Access control is not checked except for deciding whether a method overrides another.
+//!
+//! The `*_partial` variants tolerate classes missing from the [`ResolutionContext`]: when the
+//! lookup reaches a missing class before finding the member, they return [`Partial::Unknown`]
+//! with that class instead of failing, since the member may be declared in or above it.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    jvm::{
+        method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, Field, Method,
+    },
+    types::{field_type::FieldType, method_descriptor::MethodDescriptor},
+};
+
+use super::ResolutionContext;
+
+const OBJECT: &str = "java/lang/Object";
+const SIGNATURE_POLYMORPHIC_OWNERS: [&str; 2] = [
+    "java/lang/invoke/MethodHandle",
+    "java/lang/invoke/VarHandle",
+];
+
+/// An error that occurs during resolution or selection.
+/// The variants correspond to the errors thrown by the JVM.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ResolutionError {
+    /// A class is not in the [`ResolutionContext`], i.e., `NoClassDefFoundError`.
+    #[error("Class {0} is not found")]
+    ClassNotFound(ClassRef),
+    /// A class or an interface is found where the other is expected, or the selection is
+    /// ambiguous, i.e., `IncompatibleClassChangeError`.
+    #[error("Incompatible class change at {0}")]
+    IncompatibleClassChange(ClassRef),
+    /// The method cannot be found, i.e., `NoSuchMethodError`.
+    #[error("Method {0} is not found")]
+    NoSuchMethod(MethodRef),
+    /// The field cannot be found, i.e., `NoSuchFieldError`.
+    #[error("Field {0} is not found")]
+    NoSuchField(FieldRef),
+    /// The selected method is abstract, i.e., `AbstractMethodError`.
+    #[error("Method {0} is abstract")]
+    AbstractMethod(MethodRef),
+}
+
+/// Resolves a reference to a method of a class, e.g., the operand of `invokevirtual`.
+/// See [JVMS §5.4.3.3](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.3.3).
+///
+/// # Errors
+/// See [`ResolutionError`].
+pub fn resolve_method<'a>(
+    method_ref: &MethodRef,
+    context: &'a ResolutionContext,
+) -> Result<&'a Method, ResolutionError> {
+    let class = get_class(context, &method_ref.owner)?;
+    if class.is_interface() {
+        return Err(ResolutionError::IncompatibleClassChange(class.as_ref()));
+    }
+    let mut current = Some(class);
+    while let Some(class) = current {
+        if let Some(method) = signature_polymorphic_method(class, &method_ref.name)
+            .or_else(|| class.get_method(&method_ref.name, &method_ref.descriptor))
+        {
+            return Ok(method);
+        }
+        current = super_class(context, class)?;
+    }
+    superinterface_method(context, class, &method_ref.name, &method_ref.descriptor)?
+        .ok_or_else(|| ResolutionError::NoSuchMethod(method_ref.clone()))
+}
+
+/// Resolves a reference to a method of an interface, e.g., the operand of `invokeinterface`.
+/// See [JVMS §5.4.3.4](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.3.4).
+///
+/// # Errors
+/// See [`ResolutionError`].
+pub fn resolve_interface_method<'a>(
+    method_ref: &MethodRef,
+    context: &'a ResolutionContext,
+) -> Result<&'a Method, ResolutionError> {
+    let interface = get_class(context, &method_ref.owner)?;
+    if !interface.is_interface() {
+        return Err(ResolutionError::IncompatibleClassChange(interface.as_ref()));
+    }
+    if let Some(method) = interface.get_method(&method_ref.name, &method_ref.descriptor) {
+        return Ok(method);
+    }
+    let object = get_class(context, &ClassRef::new(OBJECT))?;
+    if let Some(method) = object
+        .get_method(&method_ref.name, &method_ref.descriptor)
+        .filter(|it| {
+            it.access_flags.contains(method::AccessFlags::PUBLIC)
+                && !it.access_flags.contains(method::AccessFlags::STATIC)
+        })
+    {
+        return Ok(method);
+    }
+    superinterface_method(context, interface, &method_ref.name, &method_ref.descriptor)?
+        .ok_or_else(|| ResolutionError::NoSuchMethod(method_ref.clone()))
+}
+
+/// Resolves a reference to a field.
+/// See [JVMS §5.4.3.2](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.3.2).
+///
+/// # Errors
+/// See [`ResolutionError`].
+pub fn resolve_field<'a>(
+    field_ref: &FieldRef,
+    context: &'a ResolutionContext,
+) -> Result<&'a Field, ResolutionError> {
+    let class = get_class(context, &field_ref.owner)?;
+    lookup_field(context, class, &field_ref.name, &field_ref.field_type)?
+        .ok_or_else(|| ResolutionError::NoSuchField(field_ref.clone()))
+}
+
+/// Selects the method invoked by `invokevirtual` or `invokeinterface` when the resolved method
+/// is `resolved` and the receiver is an instance of `receiver`.
+/// See [JVMS §5.4.6](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.6).
+///
+/// # Errors
+/// See [`ResolutionError`].
+pub fn select_method<'a>(
+    resolved: &'a Method,
+    receiver: &ClassRef,
+    context: &'a ResolutionContext,
+) -> Result<&'a Method, ResolutionError> {
+    if resolved.access_flags.contains(method::AccessFlags::PRIVATE) {
+        return Ok(resolved);
+    }
+    let receiver = get_class(context, receiver)?;
+    let mut current = Some(receiver);
+    while let Some(class) = current {
+        let overriding = class
+            .get_method(&resolved.name, &resolved.descriptor)
+            .filter(|it| {
+                !it.access_flags.contains(method::AccessFlags::STATIC) && overrides(it, resolved)
+            });
+        if let Some(method) = overriding {
+            return if method.access_flags.contains(method::AccessFlags::ABSTRACT) {
+                Err(ResolutionError::AbstractMethod(method.as_ref()))
+            } else {
+                Ok(method)
+            };
+        }
+        current = super_class(context, class)?;
+    }
+    let candidates =
+        maximally_specific_methods(context, receiver, &resolved.name, &resolved.descriptor)?;
+    let mut non_abstract = candidates
+        .iter()
+        .filter(|it| !it.access_flags.contains(method::AccessFlags::ABSTRACT));
+    match (non_abstract.next(), non_abstract.next()) {
+        (Some(method), None) => Ok(method),
+        (None, _) => Err(ResolutionError::AbstractMethod(resolved.as_ref())),
+        (Some(_), Some(_)) => Err(ResolutionError::IncompatibleClassChange(receiver.as_ref())),
+    }
+}
+
+/// The result of a resolution or selection in which some classes may be missing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Partial<T> {
+    /// The member is found.
+    Resolved(T),
+    /// The lookup reached a class that is not in the [`ResolutionContext`] before finding the
+    /// member.
+    Unknown(ClassRef),
+}
+
+impl<T> Partial<T> {
+    /// Returns the found member, or [`None`] if it is unknown.
+    pub fn resolved(self) -> Option<T> {
+        match self {
+            Self::Resolved(it) => Some(it),
+            Self::Unknown(_) => None,
+        }
+    }
+}
+
+/// Same as [`resolve_method`], but returns [`Partial::Unknown`] on missing classes.
+///
+/// # Errors
+/// See [`ResolutionError`]. [`ResolutionError::ClassNotFound`] is never returned.
+pub fn resolve_method_partial<'a>(
+    method_ref: &MethodRef,
+    context: &'a ResolutionContext,
+) -> Result<Partial<&'a Method>, ResolutionError> {
+    partial(resolve_method(method_ref, context))
+}
+
+/// Same as [`resolve_interface_method`], but returns [`Partial::Unknown`] on missing classes.
+///
+/// # Errors
+/// See [`ResolutionError`]. [`ResolutionError::ClassNotFound`] is never returned.
+pub fn resolve_interface_method_partial<'a>(
+    method_ref: &MethodRef,
+    context: &'a ResolutionContext,
+) -> Result<Partial<&'a Method>, ResolutionError> {
+    partial(resolve_interface_method(method_ref, context))
+}
+
+/// Same as [`resolve_field`], but returns [`Partial::Unknown`] on missing classes.
+///
+/// # Errors
+/// See [`ResolutionError`]. [`ResolutionError::ClassNotFound`] is never returned.
+pub fn resolve_field_partial<'a>(
+    field_ref: &FieldRef,
+    context: &'a ResolutionContext,
+) -> Result<Partial<&'a Field>, ResolutionError> {
+    partial(resolve_field(field_ref, context))
+}
+
+/// Same as [`select_method`], but returns [`Partial::Unknown`] on missing classes.
+///
+/// # Errors
+/// See [`ResolutionError`]. [`ResolutionError::ClassNotFound`] is never returned.
+pub fn select_method_partial<'a>(
+    resolved: &'a Method,
+    receiver: &ClassRef,
+    context: &'a ResolutionContext,
+) -> Result<Partial<&'a Method>, ResolutionError> {
+    partial(select_method(resolved, receiver, context))
+}
+
+fn partial<T>(result: Result<T, ResolutionError>) -> Result<Partial<T>, ResolutionError> {
+    match result {
+        Ok(it) => Ok(Partial::Resolved(it)),
+        Err(ResolutionError::ClassNotFound(it)) => Ok(Partial::Unknown(it)),
+        Err(err) => Err(err),
+    }
+}
+
+fn get_class<'a>(
+    context: &'a ResolutionContext,
+    class_ref: &ClassRef,
+) -> Result<&'a Class, ResolutionError> {
+    context
+        .get_class(class_ref)
+        .ok_or_else(|| ResolutionError::ClassNotFound(class_ref.clone()))
+}
+
+fn super_class<'a>(
+    context: &'a ResolutionContext,
+    class: &Class,
+) -> Result<Option<&'a Class>, ResolutionError> {
+    class
+        .super_class
+        .as_ref()
+        .map(|it| get_class(context, it))
+        .transpose()
+}
+
+/// Gets the signature polymorphic method named `name` declared in `class`.
+/// See [JVMS §2.9.3](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-2.html#jvms-2.9.3).
+fn signature_polymorphic_method<'a>(class: &'a Class, name: &str) -> Option<&'a Method> {
+    if !SIGNATURE_POLYMORPHIC_OWNERS.contains(&class.binary_name.as_str()) {
+        return None;
+    }
+    let object_array = FieldType::Object(ClassRef::new(OBJECT)).into_array_type();
+    let mut methods = class.methods.iter().filter(|it| it.name == name);
+    match (methods.next(), methods.next()) {
+        (Some(method), None)
+            if *method.descriptor.parameters_types == [object_array]
+                && method
+                    .access_flags
+                    .contains(method::AccessFlags::VARARGS | method::AccessFlags::NATIVE) =>
+        {
+            Some(method)
+        }
+        _ => None,
+    }
+}
+
+/// Finds the method in the superinterfaces of `class` as in the last steps of method resolution,
+/// preferring the only non-abstract maximally-specific method.
+fn superinterface_method<'a>(
+    context: &'a ResolutionContext,
+    class: &'a Class,
+    name: &str,
+    descriptor: &MethodDescriptor,
+) -> Result<Option<&'a Method>, ResolutionError> {
+    let maximally_specific = maximally_specific_methods(context, class, name, descriptor)?;
+    let mut non_abstract = maximally_specific
+        .iter()
+        .filter(|it| !it.access_flags.contains(method::AccessFlags::ABSTRACT));
+    if let (Some(method), None) = (non_abstract.next(), non_abstract.next()) {
+        return Ok(Some(method));
+    }
+    let method = superinterfaces(context, class)?
+        .into_iter()
+        .find_map(|it| inheritable_interface_method(it, name, descriptor));
+    Ok(method)
+}
+
+/// Gets the maximally-specific superinterface methods of `class`.
+/// See [JVMS §5.4.3.3](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.3.3).
+fn maximally_specific_methods<'a>(
+    context: &'a ResolutionContext,
+    class: &'a Class,
+    name: &str,
+    descriptor: &MethodDescriptor,
+) -> Result<Vec<&'a Method>, ResolutionError> {
+    let declaring: Vec<_> = superinterfaces(context, class)?
+        .into_iter()
+        .filter_map(|it| Some((it, inheritable_interface_method(it, name, descriptor)?)))
+        .collect();
+    let mut methods = Vec::new();
+    for (interface, method) in &declaring {
+        let mut overridden = false;
+        for (other, _) in &declaring {
+            if other.binary_name != interface.binary_name
+                && superinterfaces(context, other)?
+                    .iter()
+                    .any(|it| it.binary_name == interface.binary_name)
+            {
+                overridden = true;
+                break;
+            }
+        }
+        if !overridden {
+            methods.push(*method);
+        }
+    }
+    Ok(methods)
+}
+
+fn inheritable_interface_method<'a>(
+    interface: &'a Class,
+    name: &str,
+    descriptor: &MethodDescriptor,
+) -> Option<&'a Method> {
+    interface.get_method(name, descriptor).filter(|it| {
+        !it.access_flags
+            .intersects(method::AccessFlags::PRIVATE | method::AccessFlags::STATIC)
+    })
+}
+
+/// Gets the direct and indirect superinterfaces of `class`, including those of its super
+/// classes, in the order they are declared.
+fn superinterfaces<'a>(
+    context: &'a ResolutionContext,
+    class: &'a Class,
+) -> Result<Vec<&'a Class>, ResolutionError> {
+    let mut interfaces = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut stack: Vec<&ClassRef> = Vec::new();
+    let mut current = Some(class);
+    while let Some(class) = current {
+        stack.extend(class.interfaces.iter().rev());
+        while let Some(interface) = stack.pop() {
+            if visited.insert(interface) {
+                let interface = get_class(context, interface)?;
+                interfaces.push(interface);
+                stack.extend(interface.interfaces.iter().rev());
+            }
+        }
+        current = super_class(context, class)?;
+    }
+    Ok(interfaces)
+}
+
+/// Looks up a field in `class`, its superinterfaces, and then its super classes.
+fn lookup_field<'a>(
+    context: &'a ResolutionContext,
+    class: &'a Class,
+    name: &str,
+    field_type: &FieldType,
+) -> Result<Option<&'a Field>, ResolutionError> {
+    if let Some(field) = class.get_field(name, field_type) {
+        return Ok(Some(field));
+    }
+    for interface in &class.interfaces {
+        let interface = get_class(context, interface)?;
+        if let Some(field) = lookup_field(context, interface, name, field_type)? {
+            return Ok(Some(field));
+        }
+    }
+    match super_class(context, class)? {
+        Some(super_class) => lookup_field(context, super_class, name, field_type),
+        None => Ok(None),
+    }
+}
+
+/// Checks if `method` overrides `overridden`, assuming they have the same name and descriptor.
+/// See [JVMS §5.4.5](https://docs.oracle.com/javase/specs/jvms/se22/html/jvms-5.html#jvms-5.4.5).
+fn overrides(method: &Method, overridden: &Method) -> bool {
+    if method.owner == overridden.owner {
+        return true;
+    }
+    let access_flags = overridden.access_flags;
+    if access_flags.contains(method::AccessFlags::PRIVATE) {
+        return false;
+    }
+    access_flags.intersects(method::AccessFlags::PUBLIC | method::AccessFlags::PROTECTED)
+        || package_of(&method.owner) == package_of(&overridden.owner)
+}
+
+fn package_of(class: &ClassRef) -> &str {
+    class
+        .binary_name
+        .rsplit_once('/')
+        .map_or("", |(package, _)| package)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        ir::{ClassHierarchy, InterfaceImplHierarchy},
+        jvm::{class, field},
+    };
+
+    use super::*;
+
+    fn method(owner: &str, name: &str, access_flags: method::AccessFlags) -> Method {
+        Method {
+            access_flags,
+            name: name.to_owned(),
+            descriptor: "()V".parse().unwrap(),
+            owner: ClassRef::new(owner),
+            ..Default::default()
+        }
+    }
+
+    fn interface(name: &str, interfaces: &[&str], methods: Vec<Method>) -> Class {
+        Class {
+            access_flags: class::AccessFlags::INTERFACE | class::AccessFlags::ABSTRACT,
+            binary_name: name.to_owned(),
+            super_class: Some(ClassRef::new(OBJECT)),
+            interfaces: interfaces.iter().copied().map(ClassRef::new).collect(),
+            methods,
+            ..Default::default()
+        }
+    }
+
+    fn context(classes: Vec<Class>) -> ResolutionContext {
+        let classes: BTreeMap<_, _> = classes
+            .into_iter()
+            .chain([Class {
+                binary_name: OBJECT.to_owned(),
+                ..Default::default()
+            }])
+            .map(|it| (it.as_ref(), it))
+            .collect();
+        ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(classes.values()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(classes.values()),
+            application_classes: classes,
+            library_classes: BTreeMap::new(),
+        }
+    }
+
+    fn method_ref(owner: &str, name: &str) -> MethodRef {
+        MethodRef::new(ClassRef::new(owner), name, "()V".parse().unwrap())
+    }
+
+    const PUBLIC: method::AccessFlags = method::AccessFlags::PUBLIC;
+    const ABSTRACT: method::AccessFlags = PUBLIC.union(method::AccessFlags::ABSTRACT);
+
+    #[test]
+    fn resolve_and_select_overriding_method() {
+        let context = context(vec![
+            Class {
+                binary_name: "p/Base".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                methods: vec![
+                    method("p/Base", "run", PUBLIC),
+                    method("p/Base", "hidden", method::AccessFlags::empty()),
+                ],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "p/Sub".to_owned(),
+                super_class: Some(ClassRef::new("p/Base")),
+                methods: vec![method("p/Sub", "run", PUBLIC)],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "q/Other".to_owned(),
+                super_class: Some(ClassRef::new("p/Sub")),
+                methods: vec![method("q/Other", "hidden", PUBLIC)],
+                ..Default::default()
+            },
+        ]);
+
+        let resolved = resolve_method(&method_ref("p/Sub", "run"), &context).unwrap();
+        assert_eq!(resolved.owner, ClassRef::new("p/Sub"));
+        let resolved = resolve_method(&method_ref("q/Other", "run"), &context).unwrap();
+        let selected = select_method(resolved, &ClassRef::new("q/Other"), &context).unwrap();
+        assert_eq!(selected.owner, ClassRef::new("p/Sub"));
+
+        // A package-private method is not overridden by a method in another package.
+        let hidden = resolve_method(&method_ref("p/Base", "hidden"), &context).unwrap();
+        let selected = select_method(hidden, &ClassRef::new("q/Other"), &context).unwrap();
+        assert_eq!(selected.owner, ClassRef::new("p/Base"));
+
+        assert_eq!(
+            resolve_method(&method_ref("p/Sub", "missing"), &context).unwrap_err(),
+            ResolutionError::NoSuchMethod(method_ref("p/Sub", "missing"))
+        );
+        assert_eq!(
+            resolve_method(&method_ref("p/Missing", "run"), &context).unwrap_err(),
+            ResolutionError::ClassNotFound(ClassRef::new("p/Missing"))
+        );
+    }
+
+    #[test]
+    fn default_methods() {
+        let context = context(vec![
+            interface("A", &[], vec![method("A", "m", PUBLIC)]),
+            interface("B", &["A"], vec![method("B", "m", PUBLIC)]),
+            interface("C", &[], vec![method("C", "m", PUBLIC)]),
+            interface("D", &[], vec![method("D", "m", ABSTRACT)]),
+            Class {
+                binary_name: "Specific".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("A"), ClassRef::new("B")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Conflicting".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("B"), ClassRef::new("C")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Abstract".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("D")],
+                ..Default::default()
+            },
+        ]);
+
+        // `B.m` is more specific than `A.m`.
+        let resolved = resolve_method(&method_ref("Specific", "m"), &context).unwrap();
+        assert_eq!(resolved.owner, ClassRef::new("B"));
+        let resolved = resolve_interface_method(&method_ref("A", "m"), &context).unwrap();
+        let selected = select_method(resolved, &ClassRef::new("Specific"), &context).unwrap();
+        assert_eq!(selected.owner, ClassRef::new("B"));
+
+        let resolved = resolve_interface_method(&method_ref("C", "m"), &context).unwrap();
+        assert_eq!(
+            select_method(resolved, &ClassRef::new("Conflicting"), &context).unwrap_err(),
+            ResolutionError::IncompatibleClassChange(ClassRef::new("Conflicting"))
+        );
+
+        let resolved = resolve_interface_method(&method_ref("D", "m"), &context).unwrap();
+        assert!(matches!(
+            select_method(resolved, &ClassRef::new("Abstract"), &context),
+            Err(ResolutionError::AbstractMethod(_))
+        ));
+        assert_eq!(
+            resolve_interface_method(&method_ref("Specific", "m"), &context).unwrap_err(),
+            ResolutionError::IncompatibleClassChange(ClassRef::new("Specific"))
+        );
+    }
+
+    #[test]
+    fn fields_in_superinterfaces_first() {
+        let field = |owner: &str| Field {
+            access_flags: field::AccessFlags::PUBLIC,
+            name: "value".to_owned(),
+            owner: ClassRef::new(owner),
+            ..Default::default()
+        };
+        let context = context(vec![
+            Class {
+                fields: vec![field("Constants")],
+                ..interface("Constants", &[], Vec::new())
+            },
+            Class {
+                binary_name: "Base".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                fields: vec![field("Base")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Sub".to_owned(),
+                super_class: Some(ClassRef::new("Base")),
+                interfaces: vec![ClassRef::new("Constants")],
+                ..Default::default()
+            },
+        ]);
+        let field_ref = FieldRef::new(ClassRef::new("Sub"), "value", field("Sub").field_type);
+        let resolved = resolve_field(&field_ref, &context).unwrap();
+        assert_eq!(resolved.owner, ClassRef::new("Constants"));
+    }
+
+    #[test]
+    fn partial_world() {
+        let context = context(vec![
+            Class {
+                binary_name: "Known".to_owned(),
+                super_class: Some(ClassRef::new("Missing")),
+                methods: vec![method("Known", "run", PUBLIC)],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Sub".to_owned(),
+                super_class: Some(ClassRef::new("Known")),
+                interfaces: vec![ClassRef::new("MissingInterface")],
+                ..Default::default()
+            },
+        ]);
+        let resolved = resolve_method_partial(&method_ref("Sub", "run"), &context).unwrap();
+        assert_eq!(resolved.resolved().unwrap().owner, ClassRef::new("Known"));
+        let unknown = |partial: Partial<_>| match partial {
+            Partial::Resolved(_) => None,
+            Partial::Unknown(it) => Some(it),
+        };
+        assert_eq!(
+            unknown(resolve_method_partial(&method_ref("Sub", "stop"), &context).unwrap()),
+            Some(ClassRef::new("Missing"))
+        );
+        assert_eq!(
+            unknown(resolve_method_partial(&method_ref("Unknown", "run"), &context).unwrap()),
+            Some(ClassRef::new("Unknown"))
+        );
+        let field_ref = FieldRef::new(ClassRef::new("Sub"), "value", "I".parse().unwrap());
+        assert!(matches!(
+            resolve_field_partial(&field_ref, &context).unwrap(),
+            Partial::Unknown(it) if it == ClassRef::new("MissingInterface")
+        ));
+        assert_eq!(
+            resolve_interface_method_partial(&method_ref("Sub", "run"), &context).unwrap_err(),
+            ResolutionError::IncompatibleClassChange(ClassRef::new("Sub"))
+        );
+    }
+}
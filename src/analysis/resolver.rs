@@ -0,0 +1,295 @@
+//! A class resolver that can be shared by multiple analysis threads.
+//!
+//! A [`Resolver`] loads classes on demand with a [`CachingClassLoader`] and builds the
+//! [`ClassHierarchy`] and the [`InterfaceImplHierarchy`] incrementally from the classes loaded so
+//! far, instead of requiring all classes to be collected before the analysis starts.
+//! Queries about the super types of a class load the classes they need, while queries about the
+//! subtypes only consider the classes that are already loaded.
+
+use std::{
+    collections::{BTreeSet, HashSet, VecDeque},
+    sync::{PoisonError, RwLock},
+};
+
+use crate::{
+    ir::{ClassHierarchy, InterfaceImplHierarchy},
+    jvm::{
+        class_loader::{CachingClassLoader, ClassPath, Error},
+        references::ClassRef,
+        Class, ClassLoader,
+    },
+};
+
+#[derive(Debug)]
+struct Hierarchies {
+    known: HashSet<ClassRef>,
+    class_hierarchy: ClassHierarchy,
+    interface_implementations: InterfaceImplHierarchy,
+}
+
+/// A thread-safe class resolver. See the [module-level documentation](self).
+#[derive(Debug)]
+pub struct Resolver<P> {
+    class_loader: CachingClassLoader<P>,
+    hierarchies: RwLock<Hierarchies>,
+}
+
+impl<P> From<CachingClassLoader<P>> for Resolver<P> {
+    fn from(class_loader: CachingClassLoader<P>) -> Self {
+        Self {
+            class_loader,
+            hierarchies: RwLock::new(Hierarchies {
+                known: HashSet::new(),
+                class_hierarchy: ClassHierarchy::from_classes([]),
+                interface_implementations: InterfaceImplHierarchy::from_classes([]),
+            }),
+        }
+    }
+}
+
+impl<P> From<ClassLoader<P>> for Resolver<P> {
+    fn from(class_loader: ClassLoader<P>) -> Self {
+        CachingClassLoader::from(class_loader).into()
+    }
+}
+
+impl<P: ClassPath> Resolver<P> {
+    /// Creates a resolver loading classes from `class_path`.
+    #[must_use]
+    pub fn new<C: IntoIterator<Item = P>>(class_path: C) -> Self {
+        ClassLoader::new(class_path).into()
+    }
+
+    /// Loads the class with the given binary name and adds it to the hierarchies.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn load_class(&self, binary_name: &str) -> Result<&Class, Error> {
+        let class = self.class_loader.load_class(binary_name)?;
+        let class_ref = class.as_ref();
+        let known = self
+            .hierarchies
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .known
+            .contains(&class_ref);
+        if !known {
+            let mut hierarchies = self
+                .hierarchies
+                .write()
+                .unwrap_or_else(PoisonError::into_inner);
+            // Another thread may have added the class in the meantime.
+            if hierarchies.known.insert(class_ref) {
+                hierarchies.class_hierarchy.insert(class);
+                hierarchies.interface_implementations.insert(class);
+            }
+        }
+        Ok(class)
+    }
+
+    /// Returns the super classes of `class_ref`, starting from its direct super class, loading
+    /// them if necessary.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn super_classes(&self, class_ref: &ClassRef) -> Result<Vec<ClassRef>, Error> {
+        let mut super_classes = Vec::new();
+        let mut current = self.load_class(&class_ref.binary_name)?;
+        while let Some(super_class) = &current.super_class {
+            // Guards against malformed class files with circular inheritance.
+            if super_classes.contains(super_class) || super_class == class_ref {
+                break;
+            }
+            super_classes.push(super_class.clone());
+            current = self.load_class(&super_class.binary_name)?;
+        }
+        Ok(super_classes)
+    }
+
+    /// Returns the interfaces implemented by `class_ref` directly or through its super classes
+    /// and super interfaces, loading them if necessary.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn implemented_interfaces(
+        &self,
+        class_ref: &ClassRef,
+    ) -> Result<BTreeSet<ClassRef>, Error> {
+        let mut interfaces = BTreeSet::new();
+        let mut visited = HashSet::from([class_ref.clone()]);
+        let mut worklist = VecDeque::from([class_ref.clone()]);
+        while let Some(current) = worklist.pop_front() {
+            let class = self.load_class(&current.binary_name)?;
+            interfaces.extend(class.interfaces.iter().cloned());
+            let super_types = class.super_class.iter().chain(&class.interfaces);
+            for super_type in super_types {
+                if visited.insert(super_type.clone()) {
+                    worklist.push_back(super_type.clone());
+                }
+            }
+        }
+        Ok(interfaces)
+    }
+
+    /// Checks whether `sub` is `sup` or one of its subtypes, loading the super types of `sub` if
+    /// necessary.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn is_subtype_of(&self, sub: &ClassRef, sup: &ClassRef) -> Result<bool, Error> {
+        Ok(sub == sup
+            || self.super_classes(sub)?.contains(sup)
+            || self.implemented_interfaces(sub)?.contains(sup))
+    }
+}
+
+impl<P> Resolver<P> {
+    /// Returns the loaded subclasses of `class_ref`.
+    #[must_use]
+    pub fn known_subclasses(&self, class_ref: &ClassRef) -> BTreeSet<ClassRef> {
+        self.hierarchies
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .class_hierarchy
+            .subclasses(class_ref)
+    }
+
+    /// Returns the loaded classes implementing `interface`.
+    #[must_use]
+    pub fn known_implementors(&self, interface: &ClassRef) -> BTreeSet<ClassRef> {
+        self.hierarchies
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .interface_implementations
+            .implementors(interface)
+    }
+
+    /// Returns the class hierarchy of the classes loaded so far.
+    #[must_use]
+    pub fn class_hierarchy(&self) -> ClassHierarchy {
+        self.hierarchies
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .class_hierarchy
+            .clone()
+    }
+
+    /// Returns the interface implementations of the classes loaded so far.
+    #[must_use]
+    pub fn interface_implementations(&self) -> InterfaceImplHierarchy {
+        self.hierarchies
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .interface_implementations
+            .clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rayon::prelude::*;
+
+    use super::*;
+    use crate::jvm::class::AccessFlags;
+
+    struct InMemoryClassPath(HashMap<String, Class>);
+
+    impl ClassPath for InMemoryClassPath {
+        fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+            self.0.get(binary_name).cloned().ok_or(Error::NotFound)
+        }
+    }
+
+    fn resolver() -> Resolver<InMemoryClassPath> {
+        let classes = [
+            Class {
+                binary_name: "java/lang/Object".to_owned(),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "A".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Object")),
+                interfaces: vec![ClassRef::new("I")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "B".to_owned(),
+                super_class: Some(ClassRef::new("A")),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "C".to_owned(),
+                super_class: Some(ClassRef::new("A")),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "J".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Object")),
+                ..Default::default()
+            },
+            Class {
+                access_flags: AccessFlags::INTERFACE | AccessFlags::ABSTRACT,
+                binary_name: "I".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Object")),
+                interfaces: vec![ClassRef::new("J")],
+                ..Default::default()
+            },
+        ];
+        Resolver::new([InMemoryClassPath(
+            classes
+                .into_iter()
+                .map(|it| (it.binary_name.clone(), it))
+                .collect(),
+        )])
+    }
+
+    #[test]
+    fn lazy_hierarchies() {
+        let resolver = resolver();
+        let (a, b, c) = (ClassRef::new("A"), ClassRef::new("B"), ClassRef::new("C"));
+        assert!(resolver.known_subclasses(&a).is_empty());
+
+        assert_eq!(
+            resolver.super_classes(&b).unwrap(),
+            vec![a.clone(), ClassRef::new("java/lang/Object")]
+        );
+        assert_eq!(resolver.known_subclasses(&a), BTreeSet::from([b.clone()]));
+        assert_eq!(
+            resolver.implemented_interfaces(&b).unwrap(),
+            BTreeSet::from([ClassRef::new("I"), ClassRef::new("J")])
+        );
+        assert!(resolver.is_subtype_of(&b, &ClassRef::new("J")).unwrap());
+        assert!(!resolver.is_subtype_of(&a, &b).unwrap());
+
+        resolver.load_class("C").unwrap();
+        assert_eq!(resolver.known_subclasses(&a), BTreeSet::from([b, c]));
+        assert_eq!(
+            resolver.known_implementors(&ClassRef::new("I")),
+            BTreeSet::from([a])
+        );
+        assert!(matches!(
+            resolver.super_classes(&ClassRef::new("Missing")),
+            Err(Error::NotFound)
+        ));
+    }
+
+    #[test]
+    fn shared_across_threads() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+
+        let resolver = resolver();
+        assert_send_sync(&resolver);
+        ["A", "B", "C", "I", "J"].par_iter().for_each(|it| {
+            let supers = resolver.super_classes(&ClassRef::new(*it)).unwrap();
+            assert_eq!(supers.last(), Some(&ClassRef::new("java/lang/Object")));
+        });
+        assert_eq!(
+            resolver
+                .known_subclasses(&ClassRef::new("java/lang/Object"))
+                .len(),
+            5
+        );
+    }
+}
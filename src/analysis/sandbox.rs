@@ -0,0 +1,434 @@
+//! Checking that untrusted classes (e.g., third-party plugins) only use a safe subset of the
+//! Java platform.
+//!
+//! A [`SandboxPolicy`] allow-lists the packages, classes, and methods that may be referenced.
+//! Regardless of the allow list, native methods and calls related to class loading are
+//! rejected, and reflection and `invokedynamic` are rejected unless permitted by the policy.
+
+use std::collections::BTreeSet;
+
+use crate::{
+    jvm::{
+        class::MethodHandle,
+        code::{Instruction, ProgramCounter},
+        method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, ConstantValue, Method,
+    },
+    types::field_type::FieldType,
+};
+
+/// A policy over the API surface referenced by untrusted classes.
+#[derive(Debug, Clone)]
+pub struct SandboxPolicy {
+    packages: BTreeSet<String>,
+    classes: BTreeSet<ClassRef>,
+    methods: BTreeSet<MethodRef>,
+    allow_reflection: bool,
+    allow_invoke_dynamic: bool,
+}
+
+impl Default for SandboxPolicy {
+    /// Creates a policy allowing the core packages `java/lang`, `java/util`,
+    /// `java/util/function`, `java/util/stream`, `java/math`, and `java/time`, and rejecting
+    /// reflection and `invokedynamic`.
+    fn default() -> Self {
+        Self::new()
+            .allow_package("java/lang")
+            .allow_package("java/util")
+            .allow_package("java/util/function")
+            .allow_package("java/util/stream")
+            .allow_package("java/math")
+            .allow_package("java/time")
+    }
+}
+
+impl SandboxPolicy {
+    /// Creates a policy that allows nothing but the checked classes themselves.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            packages: BTreeSet::new(),
+            classes: BTreeSet::new(),
+            methods: BTreeSet::new(),
+            allow_reflection: false,
+            allow_invoke_dynamic: false,
+        }
+    }
+
+    /// Allows the classes in `package` (e.g., `java/util`), excluding its subpackages.
+    #[must_use]
+    pub fn allow_package(mut self, package: impl Into<String>) -> Self {
+        self.packages.insert(package.into());
+        self
+    }
+
+    /// Allows `class` and all its members.
+    #[must_use]
+    pub fn allow_class(mut self, class: ClassRef) -> Self {
+        self.classes.insert(class);
+        self
+    }
+
+    /// Allows `method` without allowing the other members of its owner.
+    #[must_use]
+    pub fn allow_method(mut self, method: MethodRef) -> Self {
+        self.methods.insert(method);
+        self
+    }
+
+    /// Sets whether the reflection APIs (e.g., `java/lang/reflect` and `Class.forName`) may be
+    /// used.
+    #[must_use]
+    pub const fn with_reflection(mut self, allowed: bool) -> Self {
+        self.allow_reflection = allowed;
+        self
+    }
+
+    /// Sets whether `invokedynamic` (e.g., lambdas and string concatenation) may be used.
+    #[must_use]
+    pub const fn with_invoke_dynamic(mut self, allowed: bool) -> Self {
+        self.allow_invoke_dynamic = allowed;
+        self
+    }
+
+    /// Checks `classes` against the policy.
+    /// The classes may freely reference each other.
+    #[must_use]
+    pub fn check(&self, classes: &[Class]) -> SandboxReport {
+        let own_classes: BTreeSet<_> = classes.iter().map(Class::as_ref).collect();
+        let violations = classes
+            .iter()
+            .flat_map(|class| {
+                let mut checker = Checker {
+                    policy: self,
+                    own_classes: &own_classes,
+                    class: class.as_ref(),
+                    method: None,
+                    violations: Vec::new(),
+                };
+                checker.check_class(class);
+                checker.violations
+            })
+            .collect();
+        SandboxReport { violations }
+    }
+}
+
+/// The result of checking classes against a [`SandboxPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SandboxReport {
+    /// The violations of the policy.
+    pub violations: Vec<Violation>,
+}
+
+impl SandboxReport {
+    /// Returns `true` if no violation is found.
+    #[must_use]
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// A violation of a [`SandboxPolicy`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Violation {
+    /// The class containing the violation.
+    pub class: ClassRef,
+    /// The method containing the violation, or [`None`] if it is in the class declaration.
+    pub method: Option<MethodRef>,
+    /// The location of the violating instruction, if any.
+    pub pc: Option<ProgramCounter>,
+    /// The kind of the violation.
+    pub kind: ViolationKind,
+}
+
+/// The kind of a [`Violation`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ViolationKind {
+    /// A class outside the allow list is referenced.
+    DisallowedClass(ClassRef),
+    /// A field outside the allow list is accessed.
+    DisallowedField(FieldRef),
+    /// A method outside the allow list is called.
+    DisallowedMethod(MethodRef),
+    /// A method is declared `native`.
+    NativeMethod,
+    /// A reflection API is called.
+    Reflection(MethodRef),
+    /// A class loader is obtained, created, or called.
+    ClassLoading(MethodRef),
+    /// An `invokedynamic` instruction is used.
+    InvokeDynamic,
+}
+
+const CLASS_LOADERS: &[&str] = &[
+    "java/lang/ClassLoader",
+    "java/security/SecureClassLoader",
+    "java/net/URLClassLoader",
+    "java/lang/invoke/MethodHandles$Lookup",
+];
+
+const CLASS_LOADING_METHODS: &[&str] = &[
+    "getClassLoader",
+    "getContextClassLoader",
+    "setContextClassLoader",
+    "getSystemClassLoader",
+    "getPlatformClassLoader",
+    "defineClass",
+    "defineHiddenClass",
+    "loadClass",
+];
+
+const REFLECTIVE_CLASS_METHODS: &[&str] = &[
+    "forName",
+    "newInstance",
+    "getMethod",
+    "getMethods",
+    "getDeclaredMethod",
+    "getDeclaredMethods",
+    "getField",
+    "getFields",
+    "getDeclaredField",
+    "getDeclaredFields",
+    "getConstructor",
+    "getConstructors",
+    "getDeclaredConstructor",
+    "getDeclaredConstructors",
+    "getRecordComponents",
+];
+
+fn package_of(class: &ClassRef) -> &str {
+    class
+        .binary_name
+        .rsplit_once('/')
+        .map_or("", |(package, _)| package)
+}
+
+fn is_class_loading(method: &MethodRef) -> bool {
+    CLASS_LOADERS.contains(&method.owner.binary_name.as_ref())
+        || CLASS_LOADING_METHODS.contains(&method.name.as_ref())
+}
+
+fn is_reflection(method: &MethodRef) -> bool {
+    let owner = method.owner.binary_name.as_ref();
+    package_of(&method.owner) == "java/lang/reflect"
+        || owner == "java/lang/invoke/MethodHandles"
+        || (owner == "java/lang/Class" && REFLECTIVE_CLASS_METHODS.contains(&method.name.as_ref()))
+}
+
+struct Checker<'a> {
+    policy: &'a SandboxPolicy,
+    own_classes: &'a BTreeSet<ClassRef>,
+    class: ClassRef,
+    method: Option<MethodRef>,
+    violations: Vec<Violation>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, pc: Option<ProgramCounter>, kind: ViolationKind) {
+        let violation = Violation {
+            class: self.class.clone(),
+            method: self.method.clone(),
+            pc,
+            kind,
+        };
+        if !self.violations.contains(&violation) {
+            self.violations.push(violation);
+        }
+    }
+
+    fn is_allowed(&self, class: &ClassRef) -> bool {
+        self.own_classes.contains(class)
+            || self.policy.classes.contains(class)
+            || self.policy.packages.contains(package_of(class))
+    }
+
+    fn check_class_ref(&mut self, pc: Option<ProgramCounter>, class: &ClassRef) {
+        if !self.is_allowed(class) {
+            self.report(pc, ViolationKind::DisallowedClass(class.clone()));
+        }
+    }
+
+    fn check_type(&mut self, pc: Option<ProgramCounter>, field_type: &FieldType) {
+        match field_type {
+            FieldType::Base(_) => {}
+            FieldType::Object(class) => self.check_class_ref(pc, class),
+            FieldType::Array(element) => self.check_type(pc, element),
+        }
+    }
+
+    fn check_field(&mut self, pc: ProgramCounter, field: &FieldRef) {
+        if !self.is_allowed(&field.owner) {
+            self.report(Some(pc), ViolationKind::DisallowedField(field.clone()));
+        }
+    }
+
+    fn check_method_ref(&mut self, pc: ProgramCounter, method: &MethodRef) {
+        if is_class_loading(method) {
+            self.report(Some(pc), ViolationKind::ClassLoading(method.clone()));
+        } else if is_reflection(method) {
+            if !self.policy.allow_reflection {
+                self.report(Some(pc), ViolationKind::Reflection(method.clone()));
+            }
+        } else if !self.is_allowed(&method.owner) && !self.policy.methods.contains(method) {
+            self.report(Some(pc), ViolationKind::DisallowedMethod(method.clone()));
+        }
+    }
+
+    fn check_class(&mut self, class: &Class) {
+        if let Some(super_class) = &class.super_class {
+            self.check_class_ref(None, super_class);
+        }
+        for interface in &class.interfaces {
+            self.check_class_ref(None, interface);
+        }
+        for field in &class.fields {
+            self.check_type(None, &field.field_type);
+        }
+        for method in &class.methods {
+            self.method = Some(method.as_ref());
+            self.check_method(method);
+        }
+        self.method = None;
+    }
+
+    fn check_method(&mut self, method: &Method) {
+        if method.access_flags.contains(method::AccessFlags::NATIVE) {
+            self.report(None, ViolationKind::NativeMethod);
+        }
+        let Some(body) = &method.body else {
+            return;
+        };
+        for (pc, instruction) in &body.instructions {
+            self.check_instruction(*pc, instruction);
+        }
+        for entry in &body.exception_table {
+            if let Some(catch_type) = &entry.catch_type {
+                self.check_class_ref(Some(entry.handler_pc), catch_type);
+            }
+        }
+    }
+
+    fn check_instruction(&mut self, pc: ProgramCounter, instruction: &Instruction) {
+        #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+        use Instruction::*;
+        match instruction {
+            GetStatic(field) | PutStatic(field) | GetField(field) | PutField(field) => {
+                self.check_field(pc, field);
+            }
+            InvokeVirtual(method)
+            | InvokeSpecial(method)
+            | InvokeStatic(method)
+            | InvokeInterface(method, _) => self.check_method_ref(pc, method),
+            InvokeDynamic { .. } if !self.policy.allow_invoke_dynamic => {
+                self.report(Some(pc), ViolationKind::InvokeDynamic);
+            }
+            New(class) | ANewArray(class) => self.check_class_ref(Some(pc), class),
+            CheckCast(field_type) | InstanceOf(field_type) | MultiANewArray(field_type, _) => {
+                self.check_type(Some(pc), field_type);
+            }
+            Ldc(constant) | LdcW(constant) | Ldc2W(constant) => match constant {
+                ConstantValue::Class(class) => self.check_class_ref(Some(pc), class),
+                ConstantValue::Handle(handle) => match handle {
+                    MethodHandle::RefGetField(field)
+                    | MethodHandle::RefGetStatic(field)
+                    | MethodHandle::RefPutField(field)
+                    | MethodHandle::RefPutStatic(field) => self.check_field(pc, field),
+                    MethodHandle::RefInvokeVirtual(method)
+                    | MethodHandle::RefInvokeStatic(method)
+                    | MethodHandle::RefInvokeSpecial(method)
+                    | MethodHandle::RefNewInvokeSpecial(method)
+                    | MethodHandle::RefInvokeInterface(method) => {
+                        self.check_method_ref(pc, method);
+                    }
+                },
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::code::{Instruction::*, InstructionList, MethodBody};
+    use crate::tests::method_ref;
+
+    fn plugin(instructions: InstructionList<Instruction>) -> Class {
+        Class {
+            binary_name: "com/example/Plugin".to_owned(),
+            super_class: Some(ClassRef::new("java/lang/Object")),
+            methods: vec![
+                Method {
+                    name: "run".to_owned(),
+                    owner: ClassRef::new("com/example/Plugin"),
+                    body: Some(MethodBody {
+                        instructions,
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                Method {
+                    name: "hack".to_owned(),
+                    owner: ClassRef::new("com/example/Plugin"),
+                    access_flags: method::AccessFlags::NATIVE,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reports_violations() {
+        let for_name = method_ref(
+            "java/lang/Class",
+            "forName",
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+        );
+        let get_loader = method_ref(
+            "java/lang/Class",
+            "getClassLoader",
+            "()Ljava/lang/ClassLoader;",
+        );
+        let delete = method_ref("java/io/File", "delete", "()Z");
+        let class = plugin(InstructionList::from([
+            (0.into(), InvokeStatic(for_name.clone())),
+            (3.into(), InvokeVirtual(get_loader.clone())),
+            (9.into(), New(ClassRef::new("java/io/File"))),
+            (12.into(), InvokeVirtual(delete.clone())),
+            (15.into(), ANewArray(ClassRef::new("com/example/Plugin"))),
+            (18.into(), Return),
+        ]));
+        let report = SandboxPolicy::default().check(&[class]);
+        assert!(!report.passed());
+        let kinds: Vec<_> = report
+            .violations
+            .iter()
+            .map(|it| (it.pc, it.kind.clone()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (Some(0.into()), ViolationKind::Reflection(for_name.clone())),
+                (Some(3.into()), ViolationKind::ClassLoading(get_loader)),
+                (
+                    Some(9.into()),
+                    ViolationKind::DisallowedClass(ClassRef::new("java/io/File"))
+                ),
+                (Some(12.into()), ViolationKind::DisallowedMethod(delete)),
+                (None, ViolationKind::NativeMethod),
+            ]
+        );
+
+        let lenient = SandboxPolicy::default()
+            .with_reflection(true)
+            .allow_class(ClassRef::new("java/io/File"));
+        let class = plugin(InstructionList::from([(0.into(), InvokeStatic(for_name))]));
+        let report = lenient.check(&[class]);
+        assert_eq!(report.violations.len(), 1);
+        assert_eq!(report.violations[0].kind, ViolationKind::NativeMethod);
+    }
+}
@@ -0,0 +1,341 @@
+//! Discovery of the service providers loaded by `java.util.ServiceLoader`.
+//!
+//! The providers of a service are declared either in `META-INF/services` resources of JAR files
+//! or in the `provides` directives of modules.
+//! A [`ServiceRegistry`] aggregates both kinds of declarations into a map from each service to its
+//! implementations.
+//! Since `ServiceLoader` instantiates the providers reflectively, the constructors of the
+//! providers have no callers in the bytecode.
+//! [`ServiceRegistry::synthetic_edges`] materializes these calls at the `ServiceLoader.load`
+//! sites as [`SyntheticEdge`]s.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    ir::{expression::Expression, DefUseChain, Identifier, MokaIRMethod, MokaInstruction, Operand},
+    jvm::{
+        code::ProgramCounter,
+        references::{ClassRef, MethodRef},
+        Class, ConstantValue, Module,
+    },
+    types::method_descriptor::{MethodDescriptor, ReturnType},
+};
+
+use super::reflection::SyntheticEdge;
+
+/// The prefix of the names of the provider-configuration files in a JAR file.
+pub const SERVICES_DIR: &str = "META-INF/services/";
+
+/// The `ServiceLoader` methods taking the service class, with the index of the argument holding
+/// the service class.
+const LOAD_APIS: &[(&str, &str, usize)] = &[
+    ("load", "(Ljava/lang/Class;)Ljava/util/ServiceLoader;", 0),
+    (
+        "load",
+        "(Ljava/lang/Class;Ljava/lang/ClassLoader;)Ljava/util/ServiceLoader;",
+        0,
+    ),
+    (
+        "load",
+        "(Ljava/lang/ModuleLayer;Ljava/lang/Class;)Ljava/util/ServiceLoader;",
+        1,
+    ),
+    (
+        "loadInstalled",
+        "(Ljava/lang/Class;)Ljava/util/ServiceLoader;",
+        0,
+    ),
+];
+
+/// A call to `ServiceLoader.load` or `ServiceLoader.loadInstalled`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceLoad {
+    /// The location of the call.
+    pub pc: ProgramCounter,
+    /// The service being loaded, or [`None`] if it cannot be determined statically.
+    pub service: Option<ClassRef>,
+}
+
+/// The service providers declared in a set of JAR files and modules.
+/// See the [module-level documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServiceRegistry {
+    providers: BTreeMap<ClassRef, BTreeSet<ClassRef>>,
+}
+
+impl ServiceRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the providers listed in the provider-configuration file of `service`, where
+    /// `service` is the fully qualified name of the service (i.e., the name of the file) and
+    /// `content` is the content of the file.
+    pub fn add_services_file(&mut self, service: &str, content: &str) {
+        let providers = content
+            .lines()
+            .map(|line| line.split_once('#').map_or(line, |(it, _)| it).trim())
+            .filter(|it| !it.is_empty())
+            .map(|it| ClassRef::new(it.replace('.', "/")));
+        self.providers
+            .entry(ClassRef::new(service.trim().replace('.', "/")))
+            .or_default()
+            .extend(providers);
+    }
+
+    /// Adds the providers in the provider-configuration files of the JAR file `jar_file`.
+    ///
+    /// # Errors
+    /// See [`Error`](crate::jvm::class_loader::Error).
+    #[cfg(feature = "jar")]
+    pub fn add_jar(
+        &mut self,
+        jar_file: impl AsRef<std::path::Path>,
+    ) -> Result<(), crate::jvm::class_loader::Error> {
+        use std::{fs::File, io::BufReader, io::Read};
+
+        use crate::jvm::class_loader::class_paths::zip_error;
+
+        let jar_file = BufReader::new(File::open(jar_file)?);
+        let mut jar_archive = zip::ZipArchive::new(jar_file).map_err(zip_error)?;
+        let services: Vec<_> = jar_archive
+            .file_names()
+            .filter_map(|it| it.strip_prefix(SERVICES_DIR))
+            .filter(|it| !it.is_empty() && !it.contains('/'))
+            .map(ToOwned::to_owned)
+            .collect();
+        for service in services {
+            let mut content = String::new();
+            jar_archive
+                .by_name(&format!("{SERVICES_DIR}{service}"))
+                .map_err(zip_error)?
+                .read_to_string(&mut content)?;
+            self.add_services_file(&service, &content);
+        }
+        Ok(())
+    }
+
+    /// Adds the providers declared by the `provides` directives of `module`.
+    pub fn add_module(&mut self, module: &Module) {
+        for provide in &module.provides {
+            self.providers
+                .entry(provide.service.clone())
+                .or_default()
+                .extend(provide.with.iter().cloned());
+        }
+    }
+
+    /// Adds the providers declared by `class` if it is a `module-info` class.
+    pub fn add_class(&mut self, class: &Class) {
+        if let Some(module) = &class.module {
+            self.add_module(module);
+        }
+    }
+
+    /// Returns the map from the services to their providers.
+    #[must_use]
+    pub const fn services(&self) -> &BTreeMap<ClassRef, BTreeSet<ClassRef>> {
+        &self.providers
+    }
+
+    /// Returns the providers of `service`.
+    pub fn providers(&self, service: &ClassRef) -> impl Iterator<Item = &ClassRef> {
+        self.providers.get(service).into_iter().flatten()
+    }
+
+    /// Returns the calls made by `ServiceLoader` to instantiate the providers of the services
+    /// loaded in `method`.
+    /// The providers are assumed to be instantiated with their public no-argument constructors.
+    #[must_use]
+    pub fn synthetic_edges(&self, method: &MokaIRMethod) -> Vec<SyntheticEdge> {
+        service_loads(method)
+            .into_iter()
+            .filter_map(|load| Some((load.pc, load.service?)))
+            .flat_map(|(pc, service)| {
+                self.providers(&service)
+                    .map(move |provider| SyntheticEdge::Call {
+                        pc,
+                        callee: MethodRef::new(
+                            provider.clone(),
+                            "<init>",
+                            MethodDescriptor::new([], ReturnType::Void),
+                        ),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+/// Finds the calls to `ServiceLoader.load` and `ServiceLoader.loadInstalled` in `method`.
+#[must_use]
+pub fn service_loads(method: &MokaIRMethod) -> Vec<ServiceLoad> {
+    let du_chain = DefUseChain::new(method);
+    method
+        .instructions
+        .iter()
+        .filter_map(|(pc, insn)| {
+            let MokaInstruction::Definition {
+                expr: Expression::Call {
+                    method: api, args, ..
+                },
+                ..
+            } = insn
+            else {
+                return None;
+            };
+            let argument_index = load_api(api)?;
+            let service = args
+                .get(argument_index)
+                .and_then(|arg| constant_class(method, &du_chain, arg));
+            Some(ServiceLoad { pc: *pc, service })
+        })
+        .collect()
+}
+
+fn load_api(method: &MethodRef) -> Option<usize> {
+    if &*method.owner.binary_name != "java/util/ServiceLoader" {
+        return None;
+    }
+    let descriptor = method.descriptor.descriptor();
+    LOAD_APIS
+        .iter()
+        .find(|(name, api_descriptor, _)| *name == &*method.name && *api_descriptor == descriptor)
+        .map(|(_, _, argument_index)| *argument_index)
+}
+
+fn constant_class(
+    method: &MokaIRMethod,
+    du_chain: &DefUseChain<'_>,
+    operand: &Operand,
+) -> Option<ClassRef> {
+    let Operand::Just(Identifier::Local(value)) = operand else {
+        return None;
+    };
+    match method.instructions.get(&du_chain.defined_at(value)?)? {
+        MokaInstruction::Definition {
+            expr: Expression::Const(ConstantValue::Class(class_ref)),
+            ..
+        } => Some(class_ref.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{interop::jasmin, module},
+    };
+
+    #[test]
+    fn aggregate_providers() {
+        let mut registry = ServiceRegistry::new();
+        registry.add_services_file(
+            "org.mokapot.Codec",
+            "# Codecs\norg.mokapot.JsonCodec\n\n  org.mokapot.XmlCodec # legacy\n",
+        );
+        registry.add_module(&Module {
+            name: "org.mokapot".to_owned(),
+            flags: module::Flags::empty(),
+            version: None,
+            requires: Vec::new(),
+            exports: Vec::new(),
+            opens: Vec::new(),
+            uses: Vec::new(),
+            provides: vec![module::Provide {
+                service: ClassRef::new("org/mokapot/Codec"),
+                with: vec![
+                    ClassRef::new("org/mokapot/JsonCodec"),
+                    ClassRef::new("org/mokapot/YamlCodec"),
+                ],
+            }],
+        });
+        assert_eq!(
+            registry
+                .providers(&ClassRef::new("org/mokapot/Codec"))
+                .map(|it| it.binary_name.as_ref())
+                .collect::<Vec<_>>(),
+            [
+                "org/mokapot/JsonCodec",
+                "org/mokapot/XmlCodec",
+                "org/mokapot/YamlCodec"
+            ]
+        );
+        assert_eq!(registry.services().len(), 1);
+    }
+
+    #[test]
+    fn edges_at_load_sites() {
+        let class = jasmin::read(
+            r"
+            .class public org/mokapot/Main
+            .super java/lang/Object
+            .method public static codecs(Ljava/lang/Class;)V
+                .limit stack 1
+                .limit locals 1
+                ldc class org/mokapot/Codec
+                invokestatic java/util/ServiceLoader/load(Ljava/lang/Class;)Ljava/util/ServiceLoader;
+                pop
+                aload_0
+                invokestatic java/util/ServiceLoader/loadInstalled(Ljava/lang/Class;)Ljava/util/ServiceLoader;
+                pop
+                return
+            .end method
+            ",
+        )
+        .unwrap();
+        let ir = class.methods[0].brew().unwrap();
+        let loads = service_loads(&ir);
+        assert_eq!(loads.len(), 2);
+        assert_eq!(loads[0].service, Some(ClassRef::new("org/mokapot/Codec")));
+        assert_eq!(loads[1].service, None);
+
+        let mut registry = ServiceRegistry::new();
+        registry.add_services_file("org.mokapot.Codec", "org.mokapot.JsonCodec");
+        let edges = registry.synthetic_edges(&ir);
+        assert!(matches!(
+            edges.as_slice(),
+            [SyntheticEdge::Call { pc, callee }]
+                if *pc == loads[0].pc
+                    && callee.owner == ClassRef::new("org/mokapot/JsonCodec")
+                    && callee.is_constructor()
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "jar")]
+    fn providers_in_jar() {
+        use std::io::Write;
+
+        use zip::{write::SimpleFileOptions, ZipWriter};
+
+        let path = std::env::temp_dir().join(format!(
+            "mokapot-services-{}-{:?}.jar",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let mut writer = ZipWriter::new(std::fs::File::create(&path).unwrap());
+        writer
+            .start_file(
+                "META-INF/services/org.mokapot.Codec",
+                SimpleFileOptions::default(),
+            )
+            .unwrap();
+        writer.write_all(b"org.mokapot.JsonCodec\n").unwrap();
+        writer.finish().unwrap();
+
+        let mut registry = ServiceRegistry::new();
+        registry.add_jar(&path).unwrap();
+        assert_eq!(
+            registry
+                .providers(&ClassRef::new("org/mokapot/Codec"))
+                .collect::<Vec<_>>(),
+            [&ClassRef::new("org/mokapot/JsonCodec")]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,748 @@
+//! Detection of unreachable classes and members for whole-program shrinking.
+//!
+//! Starting from a set of [`EntryPoints`], [`shrink`] computes the classes, methods, and fields
+//! of the application that are reachable through calls, field accesses, and references in the
+//! bytecode, and reports the rest as unreachable.
+//! Virtual calls are resolved with rapid type analysis, i.e., a call dispatches only to the
+//! classes instantiated in reachable code.
+//! Methods that the library may call back, i.e., the overrides of library methods in
+//! instantiated classes, are considered reachable.
+//! Reflective lookups whose targets are known statically (see [`ReflectionResolver`]) are
+//! followed as well.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::{
+    ir::MokaIRMethodExt,
+    jvm::{
+        class::{self, MethodHandle},
+        code::Instruction,
+        field, method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, ConstantValue, Field, Method,
+    },
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::{
+    reflection::{ReflectionResolver, ReflectiveTarget, SyntheticEdge},
+    resolution::{resolve_field, resolve_interface_method, resolve_method, select_method},
+    ResolutionContext,
+};
+
+const OBJECT: &str = "java/lang/Object";
+/// The methods of `java/lang/Object` that the library may call on any object.
+const OBJECT_METHODS: [(&str, &str); 5] = [
+    ("equals", "(Ljava/lang/Object;)Z"),
+    ("hashCode", "()I"),
+    ("toString", "()Ljava/lang/String;"),
+    ("clone", "()Ljava/lang/Object;"),
+    ("finalize", "()V"),
+];
+const REFLECTION_OWNERS: [&str; 2] = ["java/lang/Class", "java/lang/invoke/MethodHandles$Lookup"];
+
+fn main_descriptor() -> MethodDescriptor {
+    MethodDescriptor::new(
+        [FieldType::Object(ClassRef::new("java/lang/String")).into_array_type()],
+        ReturnType::Void,
+    )
+}
+
+/// The roots of the reachability analysis.
+#[derive(Debug, Clone, Default)]
+pub struct EntryPoints {
+    main_methods: bool,
+    public_api: bool,
+    kept_classes: BTreeSet<ClassRef>,
+    methods: BTreeSet<MethodRef>,
+    fields: BTreeSet<FieldRef>,
+}
+
+impl EntryPoints {
+    /// Includes the `public static void main(String[])` methods of the application classes.
+    #[must_use]
+    pub const fn with_main_methods(mut self) -> Self {
+        self.main_methods = true;
+        self
+    }
+
+    /// Includes the `public` and `protected` members of the `public` application classes, e.g.,
+    /// when the application is a library.
+    #[must_use]
+    pub const fn with_public_api(mut self) -> Self {
+        self.public_api = true;
+        self
+    }
+
+    /// Keeps `class` and all of its members, e.g., when it is listed in a reflection
+    /// configuration.
+    #[must_use]
+    pub fn with_kept_class(mut self, class: ClassRef) -> Self {
+        self.kept_classes.insert(class);
+        self
+    }
+
+    /// Includes `method`.
+    #[must_use]
+    pub fn with_method(mut self, method: MethodRef) -> Self {
+        self.methods.insert(method);
+        self
+    }
+
+    /// Includes `field`.
+    #[must_use]
+    pub fn with_field(mut self, field: FieldRef) -> Self {
+        self.fields.insert(field);
+        self
+    }
+}
+
+/// The reachable and unreachable classes and members of the application.
+/// Only the application classes in the [`ResolutionContext`] are reported.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShrinkReport {
+    /// The reachable classes.
+    pub reachable_classes: BTreeSet<ClassRef>,
+    /// The reachable methods.
+    pub reachable_methods: BTreeSet<MethodRef>,
+    /// The reachable fields.
+    pub reachable_fields: BTreeSet<FieldRef>,
+    /// The unreachable classes, which can be removed with all of their members.
+    pub unreachable_classes: BTreeSet<ClassRef>,
+    /// The unreachable methods of the reachable classes.
+    pub unreachable_methods: BTreeSet<MethodRef>,
+    /// The unreachable fields of the reachable classes.
+    pub unreachable_fields: BTreeSet<FieldRef>,
+}
+
+/// Computes the classes and members of the application in `context` that are reachable from
+/// `entry_points`.
+#[must_use]
+pub fn shrink(context: &ResolutionContext, entry_points: &EntryPoints) -> ShrinkReport {
+    let mut reachability = Reachability::new(context);
+    reachability.add_entry_points(entry_points);
+    reachability.run();
+    reachability.report()
+}
+
+/// An element discovered to be reachable whose consequences are yet to be processed.
+enum Discovery {
+    Class(ClassRef),
+    Method(MethodRef),
+    Field(FieldRef),
+    Instantiation(ClassRef),
+    VirtualCall(MethodRef),
+}
+
+struct Reachability<'a> {
+    context: &'a ResolutionContext,
+    classes: BTreeSet<ClassRef>,
+    methods: BTreeSet<MethodRef>,
+    fields: BTreeSet<FieldRef>,
+    instantiated: BTreeSet<ClassRef>,
+    virtual_calls: BTreeSet<MethodRef>,
+    supertypes: HashMap<ClassRef, BTreeSet<ClassRef>>,
+    worklist: VecDeque<Discovery>,
+}
+
+impl<'a> Reachability<'a> {
+    fn new(context: &'a ResolutionContext) -> Self {
+        Self {
+            context,
+            classes: BTreeSet::new(),
+            methods: BTreeSet::new(),
+            fields: BTreeSet::new(),
+            instantiated: BTreeSet::new(),
+            virtual_calls: BTreeSet::new(),
+            supertypes: HashMap::new(),
+            worklist: VecDeque::new(),
+        }
+    }
+
+    fn add_entry_points(&mut self, entry_points: &EntryPoints) {
+        let context = self.context;
+        for class in context.application_classes.values() {
+            if entry_points.main_methods {
+                let main = class.get_method("main", main_descriptor()).filter(|it| {
+                    it.access_flags
+                        .contains(method::AccessFlags::PUBLIC | method::AccessFlags::STATIC)
+                });
+                if let Some(main) = main {
+                    self.add_method(main.as_ref());
+                }
+            }
+            if entry_points.public_api && class.access_flags.contains(class::AccessFlags::PUBLIC) {
+                let is_api = |it: method::AccessFlags| {
+                    it.intersects(method::AccessFlags::PUBLIC | method::AccessFlags::PROTECTED)
+                };
+                self.add_class(class.as_ref());
+                if !class.is_abstract() && class.methods.iter().any(Method::is_constructor) {
+                    self.add_instantiation(class.as_ref());
+                }
+                for method in class.methods.iter().filter(|it| is_api(it.access_flags)) {
+                    self.add_method(method.as_ref());
+                }
+                for field in &class.fields {
+                    if field
+                        .access_flags
+                        .intersects(field::AccessFlags::PUBLIC | field::AccessFlags::PROTECTED)
+                    {
+                        self.add_field(field.as_ref());
+                    }
+                }
+            }
+            if entry_points.kept_classes.contains(&class.as_ref()) {
+                self.add_class(class.as_ref());
+                if !class.is_abstract() {
+                    self.add_instantiation(class.as_ref());
+                }
+                for method in &class.methods {
+                    self.add_method(method.as_ref());
+                }
+                for field in &class.fields {
+                    self.add_field(field.as_ref());
+                }
+            }
+        }
+        for method in &entry_points.methods {
+            self.add_method(method.clone());
+        }
+        for field in &entry_points.fields {
+            self.add_field(field.clone());
+        }
+    }
+
+    fn add_class(&mut self, class: ClassRef) {
+        if self.classes.insert(class.clone()) {
+            self.worklist.push_back(Discovery::Class(class));
+        }
+    }
+
+    fn add_type(&mut self, field_type: &FieldType) {
+        match field_type {
+            FieldType::Base(_) => {}
+            FieldType::Object(class) => self.add_class(class.clone()),
+            FieldType::Array(element) => self.add_type(element),
+        }
+    }
+
+    fn add_method(&mut self, method: MethodRef) {
+        if self.methods.insert(method.clone()) {
+            self.worklist.push_back(Discovery::Method(method));
+        }
+    }
+
+    fn add_field(&mut self, field: FieldRef) {
+        if self.fields.insert(field.clone()) {
+            self.worklist.push_back(Discovery::Field(field));
+        }
+    }
+
+    fn add_instantiation(&mut self, class: ClassRef) {
+        if self.instantiated.insert(class.clone()) {
+            self.worklist.push_back(Discovery::Instantiation(class));
+        }
+    }
+
+    fn add_virtual_call(&mut self, method: MethodRef) {
+        if self.virtual_calls.insert(method.clone()) {
+            self.worklist.push_back(Discovery::VirtualCall(method));
+        }
+    }
+
+    fn run(&mut self) {
+        while let Some(discovery) = self.worklist.pop_front() {
+            match discovery {
+                Discovery::Class(class) => self.visit_class(&class),
+                Discovery::Method(method) => self.visit_method(&method),
+                Discovery::Field(field) => {
+                    self.add_class(field.owner);
+                    self.add_type(&field.field_type);
+                }
+                Discovery::Instantiation(class) => self.visit_instantiation(&class),
+                Discovery::VirtualCall(method) => {
+                    for receiver in self.instantiated.clone() {
+                        self.dispatch(&method, &receiver);
+                    }
+                }
+            }
+        }
+    }
+
+    fn visit_class(&mut self, class_ref: &ClassRef) {
+        let Some(class) = self.context.application_classes.get(class_ref) else {
+            return;
+        };
+        for supertype in class.super_class.iter().chain(&class.interfaces) {
+            self.add_class(supertype.clone());
+        }
+        if let Some(initializer) = class
+            .methods
+            .iter()
+            .find(|it| it.is_static_initializer_block())
+        {
+            self.add_method(initializer.as_ref());
+        }
+    }
+
+    fn visit_method(&mut self, method_ref: &MethodRef) {
+        self.add_class(method_ref.owner.clone());
+        for parameter_type in method_ref.descriptor.parameters_types.iter() {
+            self.add_type(parameter_type);
+        }
+        if let ReturnType::Some(return_type) = &method_ref.descriptor.return_type {
+            self.add_type(return_type);
+        }
+        let Some(class) = self.context.application_classes.get(&method_ref.owner) else {
+            return;
+        };
+        let Some(method) = class.get_method(&method_ref.name, &method_ref.descriptor) else {
+            return;
+        };
+        for exception in &method.exceptions {
+            self.add_class(exception.clone());
+        }
+        let Some(body) = &method.body else {
+            return;
+        };
+        for entry in &body.exception_table {
+            if let Some(catch_type) = &entry.catch_type {
+                self.add_class(catch_type.clone());
+            }
+        }
+        for (_, insn) in &body.instructions {
+            self.visit_instruction(class, insn);
+        }
+        let calls_reflection = body.instructions.iter().any(|(_, insn)| {
+            matches!(
+                insn,
+                Instruction::InvokeStatic(it) | Instruction::InvokeVirtual(it)
+                    if REFLECTION_OWNERS.contains(&it.owner.binary_name.as_ref())
+            )
+        });
+        if calls_reflection {
+            self.visit_reflection(method);
+        }
+    }
+
+    fn visit_instruction(&mut self, class: &Class, insn: &Instruction) {
+        match insn {
+            Instruction::InvokeStatic(method) | Instruction::InvokeSpecial(method) => {
+                let target = self.resolve(method);
+                self.add_method(target);
+            }
+            Instruction::InvokeVirtual(method) | Instruction::InvokeInterface(method, _) => {
+                // The resolved method must exist for the call to be linked.
+                let target = self.resolve(method);
+                self.add_method(target);
+                self.add_virtual_call(method.clone());
+            }
+            Instruction::GetField(field)
+            | Instruction::PutField(field)
+            | Instruction::GetStatic(field)
+            | Instruction::PutStatic(field) => {
+                let target = resolve_field(field, self.context)
+                    .map_or_else(|_| field.clone(), Field::as_ref);
+                self.add_field(target);
+            }
+            Instruction::New(class) => self.add_instantiation(class.clone()),
+            Instruction::ANewArray(class) => match class.binary_name.parse() {
+                Ok(array_type) if class.binary_name.starts_with('[') => self.add_type(&array_type),
+                _ => self.add_class(class.clone()),
+            },
+            Instruction::CheckCast(field_type)
+            | Instruction::InstanceOf(field_type)
+            | Instruction::MultiANewArray(field_type, _) => self.add_type(field_type),
+            Instruction::Ldc(constant)
+            | Instruction::LdcW(constant)
+            | Instruction::Ldc2W(constant) => {
+                self.visit_constant(constant);
+            }
+            Instruction::InvokeDynamic {
+                bootstrap_method_index,
+                ..
+            } => {
+                if let Some(bootstrap_method) = class
+                    .bootstrap_methods
+                    .get(usize::from(*bootstrap_method_index))
+                {
+                    self.visit_handle(&bootstrap_method.method);
+                    for argument in &bootstrap_method.arguments {
+                        self.visit_constant(argument);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_constant(&mut self, constant: &ConstantValue) {
+        match constant {
+            ConstantValue::Class(class) => match class.binary_name.parse() {
+                Ok(array_type) if class.binary_name.starts_with('[') => self.add_type(&array_type),
+                _ => self.add_class(class.clone()),
+            },
+            ConstantValue::Handle(handle) => self.visit_handle(handle),
+            _ => {}
+        }
+    }
+
+    fn visit_handle(&mut self, handle: &MethodHandle) {
+        match handle {
+            MethodHandle::RefGetField(field)
+            | MethodHandle::RefGetStatic(field)
+            | MethodHandle::RefPutField(field)
+            | MethodHandle::RefPutStatic(field) => self.add_field(field.clone()),
+            MethodHandle::RefNewInvokeSpecial(method) => {
+                self.add_instantiation(method.owner.clone());
+                self.add_method(method.clone());
+            }
+            MethodHandle::RefInvokeStatic(method) | MethodHandle::RefInvokeSpecial(method) => {
+                self.add_method(method.clone());
+            }
+            MethodHandle::RefInvokeVirtual(method) | MethodHandle::RefInvokeInterface(method) => {
+                self.add_method(method.clone());
+                self.add_virtual_call(method.clone());
+            }
+        }
+    }
+
+    /// Follows the reflective lookups in `method` whose targets are known statically.
+    /// Methods that cannot be brewed into Moka IR are skipped.
+    fn visit_reflection(&mut self, method: &Method) {
+        let Ok(ir_method) = method.brew() else {
+            return;
+        };
+        let targets = ReflectionResolver::new(&ir_method)
+            .with_resolution_context(self.context)
+            .resolve();
+        let resolved_targets = targets.lookups.into_iter().filter_map(|it| it.targets);
+        for target in resolved_targets.flatten() {
+            match target {
+                ReflectiveTarget::Class(class) => self.add_class(class),
+                ReflectiveTarget::Method(method) => self.add_method(method),
+                ReflectiveTarget::Field(field) => self.add_field(field),
+            }
+        }
+        for edge in targets.edges {
+            match edge {
+                SyntheticEdge::Call { callee, .. } if callee.is_constructor() => {
+                    self.add_instantiation(callee.owner.clone());
+                    self.add_method(callee);
+                }
+                SyntheticEdge::Call { callee, .. } => {
+                    self.add_method(callee.clone());
+                    self.add_virtual_call(callee);
+                }
+                SyntheticEdge::FieldRead { field, .. }
+                | SyntheticEdge::FieldWrite { field, .. } => {
+                    self.add_field(field);
+                }
+            }
+        }
+    }
+
+    fn visit_instantiation(&mut self, class_ref: &ClassRef) {
+        self.add_class(class_ref.clone());
+        if !self.context.application_classes.contains_key(class_ref) {
+            return;
+        }
+        for method in self.virtual_calls.clone() {
+            self.dispatch(&method, class_ref);
+        }
+        // The library may call the methods it declares on the instances of application classes.
+        for supertype in self.supertypes_of(class_ref) {
+            if self.context.application_classes.contains_key(&supertype) {
+                continue;
+            }
+            if let Some(library_class) = self.context.get_class(&supertype) {
+                let overridable = library_class.methods.iter().filter(|it| {
+                    !it.is_constructor()
+                        && !it.is_static_initializer_block()
+                        && !it
+                            .access_flags
+                            .intersects(method::AccessFlags::STATIC | method::AccessFlags::PRIVATE)
+                });
+                for method in overridable {
+                    self.add_virtual_call(method.as_ref());
+                }
+            } else if &*supertype.binary_name == OBJECT {
+                for (name, descriptor) in OBJECT_METHODS {
+                    self.add_virtual_call(MethodRef::new(
+                        supertype.clone(),
+                        name,
+                        descriptor.parse().expect("Valid"),
+                    ));
+                }
+            } else {
+                // The methods of the unknown supertype cannot be determined, so all the instance
+                // methods of the class are kept conservatively.
+                self.keep_instance_methods(class_ref);
+            }
+        }
+    }
+
+    fn keep_instance_methods(&mut self, class_ref: &ClassRef) {
+        let context = self.context;
+        let mut current = context.application_classes.get(class_ref);
+        while let Some(class) = current {
+            for method in &class.methods {
+                let is_instance_method = !method.is_constructor()
+                    && !method
+                        .access_flags
+                        .intersects(method::AccessFlags::STATIC | method::AccessFlags::PRIVATE);
+                if is_instance_method {
+                    self.add_method(method.as_ref());
+                }
+            }
+            current = class
+                .super_class
+                .as_ref()
+                .and_then(|it| context.application_classes.get(it));
+        }
+    }
+
+    /// Adds the method selected by a call to `method` on an instance of `receiver`.
+    fn dispatch(&mut self, method: &MethodRef, receiver: &ClassRef) {
+        if *receiver != method.owner && !self.supertypes_of(receiver).contains(&method.owner) {
+            return;
+        }
+        let context = self.context;
+        let selected = resolve_method(method, context)
+            .or_else(|_| resolve_interface_method(method, context))
+            .and_then(|resolved| select_method(resolved, receiver, context))
+            .map(Method::as_ref)
+            .ok()
+            .or_else(|| self.select_in_super_classes(method, receiver));
+        if let Some(selected) = selected {
+            self.add_method(selected);
+        }
+    }
+
+    /// Selects the method by looking up the super classes only, which is used when the method
+    /// cannot be resolved, e.g., because a library class is missing.
+    fn select_in_super_classes(
+        &self,
+        method: &MethodRef,
+        receiver: &ClassRef,
+    ) -> Option<MethodRef> {
+        let mut current = self.context.get_class(receiver);
+        while let Some(class) = current {
+            let declared = class
+                .get_method(&method.name, &method.descriptor)
+                .filter(|it| {
+                    !it.access_flags
+                        .intersects(method::AccessFlags::STATIC | method::AccessFlags::ABSTRACT)
+                });
+            if let Some(declared) = declared {
+                return Some(declared.as_ref());
+            }
+            current = class
+                .super_class
+                .as_ref()
+                .and_then(|it| self.context.get_class(it));
+        }
+        None
+    }
+
+    fn resolve(&self, method: &MethodRef) -> MethodRef {
+        resolve_method(method, self.context)
+            .or_else(|_| resolve_interface_method(method, self.context))
+            .map_or_else(|_| method.clone(), Method::as_ref)
+    }
+
+    /// Gets the direct and indirect super classes and interfaces of `class_ref`, including those
+    /// that are not in the context.
+    fn supertypes_of(&mut self, class_ref: &ClassRef) -> BTreeSet<ClassRef> {
+        if let Some(supertypes) = self.supertypes.get(class_ref) {
+            return supertypes.clone();
+        }
+        let mut supertypes = BTreeSet::new();
+        let mut stack = vec![class_ref.clone()];
+        while let Some(current) = stack.pop() {
+            let Some(class) = self.context.get_class(&current) else {
+                continue;
+            };
+            for supertype in class.super_class.iter().chain(&class.interfaces) {
+                if supertypes.insert(supertype.clone()) {
+                    stack.push(supertype.clone());
+                }
+            }
+        }
+        self.supertypes
+            .insert(class_ref.clone(), supertypes.clone());
+        supertypes
+    }
+
+    fn report(self) -> ShrinkReport {
+        let mut report = ShrinkReport::default();
+        for class in self.context.application_classes.values() {
+            let class_ref = class.as_ref();
+            if !self.classes.contains(&class_ref) {
+                report.unreachable_classes.insert(class_ref);
+                continue;
+            }
+            report.reachable_classes.insert(class_ref);
+            for method in class.methods.iter().map(Method::as_ref) {
+                if self.methods.contains(&method) {
+                    report.reachable_methods.insert(method);
+                } else {
+                    report.unreachable_methods.insert(method);
+                }
+            }
+            for field in class.fields.iter().map(Field::as_ref) {
+                if self.fields.contains(&field) {
+                    report.reachable_fields.insert(field);
+                } else {
+                    report.unreachable_fields.insert(field);
+                }
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{
+        ir::{ClassHierarchy, InterfaceImplHierarchy},
+        jvm::interop::jasmin,
+        tests::method_ref,
+    };
+
+    const SHAPE: &str = "
+        .bytecode 52.0
+        .class public abstract Shape
+        .super java/lang/Object
+        .method public abstract area()I
+        .end method
+    ";
+
+    const SQUARE: &str = "
+        .bytecode 52.0
+        .class public Square
+        .super Shape
+        .field private side I
+        .field private unused I
+        .method public <init>()V
+            .limit stack 1
+            aload_0
+            invokespecial Shape/<init>()V
+            return
+        .end method
+        .method public area()I
+            .limit stack 2
+            aload_0
+            getfield Square/side I
+            ireturn
+        .end method
+        .method public toString()Ljava/lang/String;
+            .limit stack 1
+            ldc \"Square\"
+            areturn
+        .end method
+        .method public perimeter()I
+            .limit stack 1
+            iconst_4
+            ireturn
+        .end method
+    ";
+
+    const CIRCLE: &str = "
+        .bytecode 52.0
+        .class public Circle
+        .super Shape
+        .method public area()I
+            .limit stack 1
+            iconst_3
+            ireturn
+        .end method
+    ";
+
+    const MAIN: &str = "
+        .bytecode 52.0
+        .class public Main
+        .super java/lang/Object
+        .method public static main([Ljava/lang/String;)V
+            .limit stack 2
+            new Square
+            dup
+            invokespecial Square/<init>()V
+            invokevirtual Shape/area()I
+            pop
+            return
+        .end method
+    ";
+
+    fn context() -> ResolutionContext {
+        let classes: BTreeMap<_, _> = [SHAPE, SQUARE, CIRCLE, MAIN]
+            .into_iter()
+            .map(|it| jasmin::read(it).unwrap())
+            .map(|it| (it.as_ref(), it))
+            .collect();
+        ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(classes.values()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(classes.values()),
+            application_classes: classes,
+            library_classes: BTreeMap::new(),
+        }
+    }
+
+    fn field_ref(owner: &str, name: &str) -> FieldRef {
+        FieldRef::new(ClassRef::new(owner), name, "I".parse().unwrap())
+    }
+
+    #[test]
+    fn reachable_from_main() {
+        let context = context();
+        let report = shrink(&context, &EntryPoints::default().with_main_methods());
+        assert_eq!(
+            report.reachable_classes,
+            BTreeSet::from(["Main", "Shape", "Square"].map(ClassRef::new))
+        );
+        assert_eq!(
+            report.unreachable_classes,
+            BTreeSet::from([ClassRef::new("Circle")])
+        );
+        let reachable = &report.reachable_methods;
+        assert!(reachable.contains(&method_ref("Square", "area", "()I")));
+        assert!(reachable.contains(&method_ref("Shape", "area", "()I")));
+        // `toString` overrides a library method and may be called by the library.
+        assert!(reachable.contains(&method_ref("Square", "toString", "()Ljava/lang/String;")));
+        assert_eq!(
+            report.unreachable_methods,
+            BTreeSet::from([method_ref("Square", "perimeter", "()I")])
+        );
+        assert_eq!(
+            report.reachable_fields,
+            BTreeSet::from([field_ref("Square", "side")])
+        );
+        assert_eq!(
+            report.unreachable_fields,
+            BTreeSet::from([field_ref("Square", "unused")])
+        );
+    }
+
+    #[test]
+    fn kept_classes() {
+        let context = context();
+        let entry_points = EntryPoints::default()
+            .with_method(method_ref("Main", "main", "([Ljava/lang/String;)V"))
+            .with_kept_class(ClassRef::new("Circle"));
+        let report = shrink(&context, &entry_points);
+        assert!(report.unreachable_classes.is_empty());
+        assert!(report
+            .reachable_methods
+            .contains(&method_ref("Circle", "area", "()I")));
+
+        let report = shrink(&context, &EntryPoints::default());
+        assert!(report.reachable_classes.is_empty());
+        assert_eq!(report.unreachable_classes.len(), 4);
+    }
+}
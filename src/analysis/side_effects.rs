@@ -0,0 +1,421 @@
+//! Inference of the side effects of methods.
+//!
+//! The [`SideEffectAnalyzer`] scans the Moka IR of every method for the [`Effects`] it has
+//! locally (e.g., writing a static field), and propagates the effects from the callees to the
+//! callers until a fixed point is reached.
+//! Calls to methods outside the analyzed classes are summarized by configurable
+//! [`MethodPattern`]s, such as the I/O sinks in the JDK; calls that match no pattern are
+//! assumed to have [`Effects::UNKNOWN`] effects.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use bitflags::bitflags;
+
+use crate::{
+    ir::{
+        expression::{ArrayOperation, Expression, FieldAccess},
+        ClassHierarchy, InterfaceImplHierarchy, MokaIRMethodExt, MokaInstruction,
+    },
+    jvm::{
+        method,
+        references::{ClassRef, MethodRef},
+        Class, Method,
+    },
+};
+
+use super::events::MethodPattern;
+
+bitflags! {
+    /// The side effects of a method.
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+    pub struct Effects: u16 {
+        /// Reads a static field.
+        const READS_STATICS = 0x0001;
+        /// Writes a static field.
+        const WRITES_STATICS = 0x0002;
+        /// Reads an instance field or an array element.
+        const READS_FIELDS = 0x0004;
+        /// Writes an instance field or an array element.
+        const WRITES_FIELDS = 0x0008;
+        /// Performs I/O (e.g., on files, sockets, or the console).
+        const IO = 0x0010;
+        /// Throws an exception explicitly.
+        const THROWS = 0x0020;
+        /// Acquires or releases a monitor.
+        const SYNCHRONIZES = 0x0040;
+        /// Calls a method whose effects are unknown (e.g., a native method).
+        const UNKNOWN = 0x0080;
+    }
+}
+
+/// The summary of the side effects of a method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MethodSummary {
+    /// The effects of the method, including the ones of its transitive callees.
+    pub effects: Effects,
+}
+
+impl MethodSummary {
+    /// Checks whether the method has no effect at all, i.e., its result depends only on its
+    /// arguments and calling it can be eliminated if the result is unused.
+    #[must_use]
+    pub const fn is_pure(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// Checks whether the method does not modify any state observable by its callers, though
+    /// it may read the state or throw exceptions.
+    #[must_use]
+    pub const fn is_side_effect_free(&self) -> bool {
+        !self.effects.intersects(
+            Effects::WRITES_STATICS
+                .union(Effects::WRITES_FIELDS)
+                .union(Effects::IO)
+                .union(Effects::SYNCHRONIZES)
+                .union(Effects::UNKNOWN),
+        )
+    }
+}
+
+/// The summaries of the analyzed methods.
+#[derive(Debug, Clone, Default)]
+pub struct SideEffectSummaries {
+    summaries: BTreeMap<MethodRef, MethodSummary>,
+}
+
+impl SideEffectSummaries {
+    /// Returns the summary of `method`, or [`None`] if `method` is not analyzed.
+    #[must_use]
+    pub fn get(&self, method: &MethodRef) -> Option<&MethodSummary> {
+        self.summaries.get(method)
+    }
+
+    /// Creates an iterator over the summaries.
+    pub fn iter(&self) -> impl Iterator<Item = (&MethodRef, &MethodSummary)> {
+        self.summaries.iter()
+    }
+}
+
+/// An analyzer of the side effects of methods.
+#[derive(Debug, Clone)]
+pub struct SideEffectAnalyzer {
+    library: Vec<(MethodPattern, Effects)>,
+}
+
+impl Default for SideEffectAnalyzer {
+    /// Creates an analyzer knowing the common I/O sinks and pure methods in the JDK.
+    fn default() -> Self {
+        let io = [
+            "java/io/*",
+            "java/nio/*",
+            "java/net/*",
+            "java/sql/*",
+            "java/lang/Process*",
+        ]
+        .map(|owner| (MethodPattern::new("*").with_owner(owner), Effects::IO));
+        let pure = [
+            "java/lang/Math",
+            "java/lang/StrictMath",
+            "java/lang/String",
+            "java/lang/Boolean",
+            "java/lang/Byte",
+            "java/lang/Character",
+            "java/lang/Short",
+            "java/lang/Integer",
+            "java/lang/Long",
+            "java/lang/Float",
+            "java/lang/Double",
+            "java/util/Objects",
+        ]
+        .map(|owner| (MethodPattern::new("*").with_owner(owner), Effects::empty()));
+        let builders = ["java/lang/StringBuilder", "java/lang/StringBuffer"].map(|owner| {
+            (
+                MethodPattern::new("*").with_owner(owner),
+                Effects::WRITES_FIELDS,
+            )
+        });
+        let object_init = (
+            MethodPattern::new("<init>").with_owner("java/lang/Object"),
+            Effects::empty(),
+        );
+        let exec = (
+            MethodPattern::new("exec").with_owner("java/lang/Runtime"),
+            Effects::IO,
+        );
+        Self {
+            library: io
+                .into_iter()
+                .chain(pure)
+                .chain(builders)
+                .chain([object_init, exec])
+                .collect(),
+        }
+    }
+}
+
+/// A method being analyzed.
+struct Node {
+    local: Effects,
+    callees: BTreeSet<MethodRef>,
+}
+
+impl SideEffectAnalyzer {
+    /// Creates an analyzer that knows no library method, i.e., every call to a method outside
+    /// the analyzed classes has [`Effects::UNKNOWN`] effects.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            library: Vec::new(),
+        }
+    }
+
+    /// Summarizes the methods matching `pattern` as having `effects`.
+    /// Patterns added later take precedence over the ones added earlier.
+    #[must_use]
+    pub fn with_library_method(mut self, pattern: MethodPattern, effects: Effects) -> Self {
+        self.library.push((pattern, effects));
+        self
+    }
+
+    /// Computes the summaries of the methods in `classes`.
+    /// Methods that cannot be brewed into Moka IR have [`Effects::UNKNOWN`] effects.
+    pub fn analyze<'a>(&self, classes: impl IntoIterator<Item = &'a Class>) -> SideEffectSummaries {
+        let classes: HashMap<_, _> = classes.into_iter().map(|it| (it.as_ref(), it)).collect();
+        let resolver = Resolver {
+            classes: &classes,
+            class_hierarchy: ClassHierarchy::from_classes(classes.values().copied()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(
+                classes.values().copied(),
+            ),
+        };
+        let mut nodes = BTreeMap::new();
+        for method in classes.values().flat_map(|it| &it.methods) {
+            if method.access_flags.contains(method::AccessFlags::ABSTRACT) {
+                continue;
+            }
+            let mut node = self.local_effects(method);
+            let mut callees = BTreeSet::new();
+            for callee in std::mem::take(&mut node.callees) {
+                match resolver.resolve(&callee) {
+                    Some(targets) => callees.extend(targets),
+                    None => node.local |= self.library_effects(&callee),
+                }
+            }
+            node.callees = callees;
+            nodes.insert(method.as_ref(), node);
+        }
+
+        let mut summaries: BTreeMap<_, _> = nodes
+            .iter()
+            .map(|(method, node)| {
+                let summary = MethodSummary {
+                    effects: node.local,
+                };
+                (method.clone(), summary)
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (method, node) in &nodes {
+                let effects = node
+                    .callees
+                    .iter()
+                    .filter_map(|it| summaries.get(it))
+                    .fold(node.local, |acc, it| acc | it.effects);
+                let summary = summaries.entry(method.clone()).or_default();
+                if summary.effects != effects {
+                    summary.effects = effects;
+                    changed = true;
+                }
+            }
+        }
+        SideEffectSummaries { summaries }
+    }
+
+    fn library_effects(&self, method: &MethodRef) -> Effects {
+        self.library
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(method))
+            .map_or(Effects::UNKNOWN, |(_, effects)| *effects)
+    }
+
+    /// Computes the effects a method has by itself and collects the methods it calls.
+    fn local_effects(&self, method: &Method) -> Node {
+        if method.body.is_none() {
+            return Node {
+                local: self.library_effects(&method.as_ref()),
+                callees: BTreeSet::new(),
+            };
+        }
+        let Ok(ir_method) = method.brew() else {
+            return Node {
+                local: Effects::UNKNOWN,
+                callees: BTreeSet::new(),
+            };
+        };
+        let mut local = Effects::empty();
+        let mut callees = BTreeSet::new();
+        for (_, insn) in &ir_method.instructions {
+            let MokaInstruction::Definition { expr, .. } = insn else {
+                continue;
+            };
+            local |= match expr {
+                Expression::Field(FieldAccess::ReadStatic { .. }) => Effects::READS_STATICS,
+                Expression::Field(FieldAccess::WriteStatic { .. }) => Effects::WRITES_STATICS,
+                Expression::Field(FieldAccess::ReadInstance { .. })
+                | Expression::Array(ArrayOperation::Read { .. }) => Effects::READS_FIELDS,
+                Expression::Field(FieldAccess::WriteInstance { .. })
+                | Expression::Array(ArrayOperation::Write { .. }) => Effects::WRITES_FIELDS,
+                Expression::Throw(_) => Effects::THROWS,
+                Expression::Synchronization(_) => Effects::SYNCHRONIZES,
+                Expression::Call { method, .. } => {
+                    callees.insert(method.clone());
+                    Effects::empty()
+                }
+                _ => Effects::empty(),
+            };
+        }
+        Node { local, callees }
+    }
+}
+
+/// Resolves the methods that may be invoked by a call.
+struct Resolver<'a> {
+    classes: &'a HashMap<ClassRef, &'a Class>,
+    class_hierarchy: ClassHierarchy,
+    interface_implementations: InterfaceImplHierarchy,
+}
+
+impl<'a> Resolver<'a> {
+    /// Returns the analyzed methods that may be invoked by a call to `method`, or [`None`] if
+    /// the call leaves the analyzed classes.
+    fn resolve(&self, method: &MethodRef) -> Option<BTreeSet<MethodRef>> {
+        let declared = self.declared_in_hierarchy(method)?;
+        let mut targets = BTreeSet::new();
+        if !declared
+            .access_flags
+            .contains(method::AccessFlags::ABSTRACT)
+        {
+            targets.insert(declared.as_ref());
+        }
+        let is_dispatched = !declared.is_constructor()
+            && !declared
+                .access_flags
+                .intersects(method::AccessFlags::STATIC | method::AccessFlags::PRIVATE);
+        if is_dispatched {
+            let mut overriding_classes = self.class_hierarchy.subclasses(&method.owner);
+            for implementor in self.interface_implementations.implementors(&method.owner) {
+                overriding_classes.extend(self.class_hierarchy.subclasses(&implementor));
+                overriding_classes.insert(implementor);
+            }
+            targets.extend(
+                overriding_classes
+                    .iter()
+                    .filter_map(|it| self.classes.get(it))
+                    .flat_map(|it| &it.methods)
+                    .filter(|it| {
+                        *it.name == *method.name
+                            && it.descriptor == method.descriptor
+                            && !it.access_flags.contains(method::AccessFlags::ABSTRACT)
+                    })
+                    .map(Method::as_ref),
+            );
+        }
+        // An abstract method without any analyzed implementation may be implemented elsewhere.
+        (!targets.is_empty()).then_some(targets)
+    }
+
+    /// Finds the declaration of `method` in its owner or the super classes of the owner.
+    fn declared_in_hierarchy(&self, method: &MethodRef) -> Option<&'a Method> {
+        let mut owner = self.classes.get(&method.owner)?;
+        loop {
+            if let Some(declared) = owner
+                .methods
+                .iter()
+                .find(|it| *it.name == *method.name && it.descriptor == method.descriptor)
+            {
+                return Some(declared);
+            }
+            owner = self.classes.get(owner.super_class.as_ref()?)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    const JASMIN: &str = "
+        .bytecode 52.0
+        .class public Foo
+        .super java/lang/Object
+        .field static count I
+        .field value I
+        .method public static add(II)I
+            .limit stack 2
+            iload_0
+            iload_1
+            iadd
+            ireturn
+        .end method
+        .method public static sum(II)I
+            .limit stack 2
+            iload_0
+            iload_1
+            invokestatic Foo/add(II)I
+            ireturn
+        .end method
+        .method public static count()I
+            .limit stack 1
+            getstatic Foo/count I
+            ireturn
+        .end method
+        .method public set(I)V
+            .limit stack 2
+            .limit locals 2
+            aload_0
+            iload_1
+            putfield Foo/value I
+            invokestatic Foo/count()I
+            pop
+            return
+        .end method
+        .method public static print()V
+            .limit stack 2
+            getstatic java/lang/System/out Ljava/io/PrintStream;
+            ldc \"Hello\"
+            invokevirtual java/io/PrintStream/println(Ljava/lang/String;)V
+            return
+        .end method
+        .method public static fail()V
+            .limit stack 1
+            aconst_null
+            athrow
+        .end method
+        .method public static native unknown()V
+        .end method
+    ";
+
+    #[test]
+    fn summarize_methods() {
+        let class = jasmin::read(JASMIN).unwrap();
+        let summaries = SideEffectAnalyzer::default().analyze([&class]);
+        let effects = |name: &str| {
+            let method = class.methods.iter().find(|it| it.name == name).unwrap();
+            summaries.get(&method.as_ref()).unwrap().effects
+        };
+        assert!(summaries.iter().any(|(_, it)| it.is_pure()));
+        assert_eq!(effects("sum"), Effects::empty());
+        assert_eq!(effects("count"), Effects::READS_STATICS);
+        assert_eq!(
+            effects("set"),
+            Effects::WRITES_FIELDS | Effects::READS_STATICS
+        );
+        assert_eq!(effects("print"), Effects::READS_STATICS | Effects::IO);
+        assert_eq!(effects("fail"), Effects::THROWS);
+        assert_eq!(effects("unknown"), Effects::UNKNOWN);
+    }
+}
@@ -0,0 +1,383 @@
+//! Program slicing on Moka IR.
+//!
+//! A slice of a method with respect to a [`Criterion`] is the part of the method that may affect
+//! (backward) or be affected by (forward) the value of an [`Identifier`] at an instruction.
+//! Slices are computed on the [`ProgramDependenceGraph`] of the method, which combines the data
+//! dependences between the definitions and the uses of the values with the control dependences
+//! derived from the postdominators of the instructions.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+};
+
+use crate::{
+    decompiler::dominators::immediate_dominators,
+    ir::{control_flow::ControlTransfer, Identifier, MokaIRMethod, MokaInstruction},
+    jvm::code::ProgramCounter,
+};
+
+/// The instruction and the value a slice is computed from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Criterion {
+    /// The location of the instruction.
+    pub pc: ProgramCounter,
+    /// The value of interest at the instruction.
+    pub id: Identifier,
+}
+
+/// The direction of a slice.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Collects the instructions that may affect the criterion.
+    Backward,
+    /// Collects the instructions that may be affected by the criterion.
+    Forward,
+}
+
+/// The program dependence graph of a method.
+/// Exceptional control flow is not considered for control dependences.
+#[derive(Debug)]
+pub struct ProgramDependenceGraph<'a> {
+    method: &'a MokaIRMethod,
+    /// The instructions each instruction is data dependent on, with the values carried.
+    data: BTreeMap<ProgramCounter, BTreeSet<(ProgramCounter, Identifier)>>,
+    /// The branching instructions each instruction is control dependent on.
+    control: BTreeMap<ProgramCounter, BTreeSet<ProgramCounter>>,
+}
+
+impl<'a> ProgramDependenceGraph<'a> {
+    /// Builds the program dependence graph of `method`.
+    #[must_use]
+    pub fn new(method: &'a MokaIRMethod) -> Self {
+        let def_sites: BTreeMap<_, _> = method
+            .instructions
+            .iter()
+            .filter_map(|(pc, insn)| insn.def().map(|it| (Identifier::from(it), *pc)))
+            .collect();
+        let data = method
+            .instructions
+            .iter()
+            .map(|(pc, insn)| {
+                let deps = insn
+                    .uses()
+                    .into_iter()
+                    .filter_map(|id| def_sites.get(&id).map(|def| (*def, id)))
+                    .collect();
+                (*pc, deps)
+            })
+            .collect();
+        let control = control_dependences(method);
+        Self {
+            method,
+            data,
+            control,
+        }
+    }
+
+    /// Returns the instructions defining the values used by the instruction at `pc`, paired with
+    /// the values.
+    pub fn data_dependences(
+        &self,
+        pc: ProgramCounter,
+    ) -> impl Iterator<Item = &(ProgramCounter, Identifier)> {
+        self.data.get(&pc).into_iter().flatten()
+    }
+
+    /// Returns the branching instructions deciding whether the instruction at `pc` is executed.
+    pub fn control_dependences(
+        &self,
+        pc: ProgramCounter,
+    ) -> impl Iterator<Item = ProgramCounter> + '_ {
+        self.control.get(&pc).into_iter().flatten().copied()
+    }
+
+    /// Computes the slice of the method with respect to `criterion` in `direction`.
+    /// The instruction of the criterion is always in the slice.
+    #[must_use]
+    pub fn slice(&self, criterion: Criterion, direction: Direction) -> Slice {
+        let mut instructions = BTreeSet::from([criterion.pc]);
+        let mut worklist = match direction {
+            Direction::Backward => self.criterion_dependences(criterion),
+            Direction::Forward => self.criterion_dependents(criterion),
+        };
+        while let Some(pc) = worklist.pop() {
+            if !instructions.insert(pc) {
+                continue;
+            }
+            match direction {
+                Direction::Backward => {
+                    worklist.extend(self.data_dependences(pc).map(|(def, _)| *def));
+                    worklist.extend(self.control_dependences(pc));
+                }
+                Direction::Forward => worklist.extend(self.dependents(pc)),
+            }
+        }
+        let edges = self.project_edges(&instructions);
+        Slice {
+            instructions,
+            edges,
+        }
+    }
+
+    /// The dependences of the value of `criterion`, or of all the values used if the instruction
+    /// defines the value.
+    fn criterion_dependences(&self, criterion: Criterion) -> Vec<ProgramCounter> {
+        let defines = self
+            .method
+            .instructions
+            .get(&criterion.pc)
+            .and_then(MokaInstruction::def)
+            .is_some_and(|it| Identifier::from(it) == criterion.id);
+        self.data_dependences(criterion.pc)
+            .filter(|(_, id)| defines || *id == criterion.id)
+            .map(|(def, _)| *def)
+            .chain(self.control_dependences(criterion.pc))
+            .collect()
+    }
+
+    /// The uses of the value of `criterion`, and the dependents of the instruction if it uses the
+    /// value.
+    fn criterion_dependents(&self, criterion: Criterion) -> Vec<ProgramCounter> {
+        let uses_criterion = self
+            .method
+            .instructions
+            .get(&criterion.pc)
+            .is_some_and(|it| it.uses().contains(&criterion.id));
+        let dependents = if uses_criterion {
+            self.dependents(criterion.pc)
+        } else {
+            Vec::new()
+        };
+        self.method
+            .instructions
+            .iter()
+            .filter(|(_, insn)| insn.uses().contains(&criterion.id))
+            .map(|(pc, _)| *pc)
+            .chain(dependents)
+            .filter(|it| *it != criterion.pc)
+            .collect()
+    }
+
+    /// The instructions data or control dependent on the instruction at `pc`.
+    fn dependents(&self, pc: ProgramCounter) -> Vec<ProgramCounter> {
+        let data = self
+            .data
+            .iter()
+            .filter(|(_, deps)| deps.iter().any(|(def, _)| *def == pc))
+            .map(|(it, _)| *it);
+        let control = self
+            .control
+            .iter()
+            .filter(|(_, deps)| deps.contains(&pc))
+            .map(|(it, _)| *it);
+        data.chain(control).collect()
+    }
+
+    /// Connects each instruction in the slice to the ones in the slice it reaches without
+    /// passing through other instructions in the slice.
+    fn project_edges(
+        &self,
+        instructions: &BTreeSet<ProgramCounter>,
+    ) -> BTreeSet<(ProgramCounter, ProgramCounter)> {
+        let cfg = &self.method.control_flow_graph;
+        let mut edges = BTreeSet::new();
+        for &src in instructions {
+            let mut visited = BTreeSet::new();
+            let mut worklist = vec![src];
+            while let Some(pc) = worklist.pop() {
+                for (_, dst, _) in cfg.edges_from(pc).into_iter().flatten() {
+                    if instructions.contains(&dst) {
+                        edges.insert((src, dst));
+                    } else if visited.insert(dst) {
+                        worklist.push(dst);
+                    }
+                }
+            }
+        }
+        edges
+    }
+}
+
+/// A slice of a method.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Slice {
+    /// The instructions in the slice.
+    pub instructions: BTreeSet<ProgramCounter>,
+    /// The control flow between the instructions in the slice, where an edge connects two
+    /// instructions if the latter is reachable from the former without passing through other
+    /// instructions in the slice.
+    pub edges: BTreeSet<(ProgramCounter, ProgramCounter)>,
+}
+
+impl Slice {
+    /// Renders the instructions of `method` in the slice, one per line, with the successors of
+    /// each instruction in the slice.
+    #[must_use]
+    pub fn render(&self, method: &MokaIRMethod) -> String {
+        let mut rendered = String::new();
+        for (pc, insn) in method
+            .instructions
+            .iter()
+            .filter(|(pc, _)| self.instructions.contains(pc))
+        {
+            let successors: Vec<_> = self
+                .edges
+                .range((*pc, ProgramCounter::ZERO)..)
+                .take_while(|(src, _)| src == pc)
+                .map(|(_, dst)| dst.to_string())
+                .collect();
+            // Writing to a `String` never fails.
+            let _ = write!(rendered, "{pc}: {insn}");
+            if !successors.is_empty() {
+                let _ = write!(rendered, " -> {}", successors.join(", "));
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
+}
+
+/// Computes the branching instructions each instruction is control dependent on, following
+/// [The Program Dependence Graph and Its Use in Optimization](https://doi.org/10.1145/24039.24041).
+/// Instructions doing nothing are omitted.
+fn control_dependences(
+    method: &MokaIRMethod,
+) -> BTreeMap<ProgramCounter, BTreeSet<ProgramCounter>> {
+    let pcs: Vec<_> = method.instructions.iter().map(|(pc, _)| *pc).collect();
+    let index_of: BTreeMap<_, _> = pcs
+        .iter()
+        .enumerate()
+        .map(|(index, pc)| (*pc, index))
+        .collect();
+    let successors: Vec<BTreeSet<usize>> = pcs
+        .iter()
+        .map(|pc| {
+            method
+                .control_flow_graph
+                .edges_from(*pc)
+                .into_iter()
+                .flatten()
+                .filter(|(_, _, transfer)| !matches!(transfer, ControlTransfer::Exception(_)))
+                .filter_map(|(_, dst, _)| index_of.get(&dst).copied())
+                .collect()
+        })
+        .collect();
+    // The postdominators are the dominators on the reversed graph with a virtual exit node.
+    let exit = pcs.len();
+    let mut reversed = vec![Vec::new(); pcs.len() + 1];
+    for (src, dsts) in successors.iter().enumerate() {
+        if dsts.is_empty() {
+            reversed[exit].push(src);
+        }
+        for &dst in dsts {
+            reversed[dst].push(src);
+        }
+    }
+    let ipdom = immediate_dominators(&reversed, exit);
+
+    let mut control: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+    for (branch, dsts) in successors.iter().enumerate() {
+        if dsts.len() < 2 {
+            continue;
+        }
+        for &dst in dsts {
+            let mut runner = Some(dst);
+            while let Some(node) = runner.filter(|it| Some(*it) != ipdom[branch] && *it != exit) {
+                let pc = pcs[node];
+                if method
+                    .instructions
+                    .get(&pc)
+                    .is_some_and(|it| *it != MokaInstruction::Nop)
+                {
+                    control.entry(pc).or_default().insert(pcs[branch]);
+                }
+                runner = ipdom[node];
+            }
+        }
+    }
+    control
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{LocalValue, MokaIRMethodExt},
+        jvm::interop::jasmin,
+    };
+
+    fn method() -> MokaIRMethod {
+        let class = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static run(II)I
+                .limit stack 2
+                .limit locals 4
+                iload_0
+                iload_1
+                iadd
+                istore_2
+                iload_1
+                iconst_3
+                imul
+                istore_3
+                iload_0
+                ifle Negative
+                iload_2
+                ireturn
+            Negative:
+                iload_3
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        class.methods[0].brew().unwrap()
+    }
+
+    #[test]
+    fn backward_slice() {
+        let method = method();
+        let pdg = ProgramDependenceGraph::new(&method);
+        assert_eq!(
+            pdg.control_dependences(13.into()).collect::<Vec<_>>(),
+            [9.into()]
+        );
+        let slice = pdg.slice(
+            Criterion {
+                pc: 13.into(),
+                id: Identifier::Local(LocalValue::new(2)),
+            },
+            Direction::Backward,
+        );
+        let pcs = |it: &[u16]| it.iter().copied().map(ProgramCounter::from).collect();
+        assert_eq!(slice.instructions, pcs(&[2, 9, 13]));
+        assert_eq!(
+            slice.edges,
+            BTreeSet::from([(2.into(), 9.into()), (9.into(), 13.into())])
+        );
+        let rendered = slice.render(&method);
+        assert_eq!(rendered.lines().count(), 3);
+        assert!(rendered.starts_with("#0002: %2 = "));
+    }
+
+    #[test]
+    fn forward_slice() {
+        let method = method();
+        let pdg = ProgramDependenceGraph::new(&method);
+        let slice = pdg.slice(
+            Criterion {
+                pc: 6.into(),
+                id: Identifier::Arg(1),
+            },
+            Direction::Forward,
+        );
+        let pcs: BTreeSet<_> = [2, 6, 13, 15]
+            .into_iter()
+            .map(ProgramCounter::from)
+            .collect();
+        assert_eq!(slice.instructions, pcs);
+    }
+}
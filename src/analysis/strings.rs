@@ -0,0 +1,705 @@
+//! Constant string analysis for resolving strings assembled at runtime.
+//!
+//! The [`StringAnalyzer`] tracks the string constants in a [`MokaIRMethod`] through
+//! `StringBuilder`/`StringBuffer` appends, `String.concat`, and `String.valueOf`, and computes
+//! the possible values of each string where they can be determined statically.
+//! It is useful for resolving the arguments of reflection APIs hidden behind string assembly.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+};
+
+use crate::{
+    ir::{
+        expression::{Conversion, Expression},
+        DefUseChain, Identifier, LocalValue, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::{
+        code::ProgramCounter,
+        references::{ClassRef, MethodRef},
+        ConstantValue, JavaString,
+    },
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::ReturnType,
+    },
+};
+
+use super::fixed_point;
+
+/// The maximum number of possible values of a string before it is considered unknown.
+const MAX_CANDIDATES: usize = 16;
+
+const STRING: &str = "java/lang/String";
+const BUILDERS: [&str; 2] = ["java/lang/StringBuilder", "java/lang/StringBuffer"];
+
+/// The possible values of a string.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StringValue {
+    /// The string is one of the candidates.
+    Known(BTreeSet<String>),
+    /// The value of the string cannot be determined statically.
+    Unknown,
+}
+
+impl StringValue {
+    /// Creates a string with a single possible value.
+    #[must_use]
+    pub fn exact(value: impl Into<String>) -> Self {
+        Self::Known(BTreeSet::from([value.into()]))
+    }
+
+    /// Returns the value of the string if there is only one possibility.
+    #[must_use]
+    pub fn as_exact(&self) -> Option<&str> {
+        match self {
+            Self::Known(candidates) if candidates.len() == 1 => {
+                candidates.first().map(String::as_str)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the possible values of the string, or [`None`] if it is unknown.
+    #[must_use]
+    pub const fn candidates(&self) -> Option<&BTreeSet<String>> {
+        match self {
+            Self::Known(candidates) => Some(candidates),
+            Self::Unknown => None,
+        }
+    }
+
+    /// Computes the possible values of either of two strings.
+    #[must_use]
+    pub fn join(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Known(mut lhs), Self::Known(rhs)) => {
+                lhs.extend(rhs);
+                Self::bounded(lhs)
+            }
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Computes the possible values of the concatenation of two strings.
+    #[must_use]
+    pub fn concat(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Self::Known(lhs), Self::Known(rhs)) if lhs.len() * rhs.len() <= MAX_CANDIDATES => {
+                Self::Known(
+                    lhs.iter()
+                        .flat_map(|prefix| {
+                            rhs.iter().map(move |suffix| format!("{prefix}{suffix}"))
+                        })
+                        .collect(),
+                )
+            }
+            _ => Self::Unknown,
+        }
+    }
+
+    fn bounded(candidates: BTreeSet<String>) -> Self {
+        if candidates.len() > MAX_CANDIDATES {
+            Self::Unknown
+        } else {
+            Self::Known(candidates)
+        }
+    }
+}
+
+/// The strings known at a program point.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct StringFact {
+    /// The values of the strings. Values that are absent are unknown.
+    strings: BTreeMap<LocalValue, StringValue>,
+    /// The allocation site of each string builder reference, i.e., the value defined by `new`.
+    builders: BTreeMap<LocalValue, LocalValue>,
+    /// The contents of the string builders keyed by their allocation sites.
+    contents: BTreeMap<LocalValue, StringValue>,
+}
+
+/// A method argument whose value should be resolved, e.g., the class name passed to
+/// `Class.forName`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringSink {
+    /// The binary name of the class declaring the method, or [`None`] to match any class.
+    pub owner: Option<String>,
+    /// The name of the method.
+    pub name: String,
+    /// The descriptor of the method.
+    pub descriptor: String,
+    /// The index of the string argument, excluding the receiver.
+    pub argument_index: usize,
+}
+
+impl StringSink {
+    /// Creates a new sink.
+    #[must_use]
+    pub fn new(
+        owner: Option<&str>,
+        name: impl Into<String>,
+        descriptor: impl Into<String>,
+        argument_index: usize,
+    ) -> Self {
+        Self {
+            owner: owner.map(ToOwned::to_owned),
+            name: name.into(),
+            descriptor: descriptor.into(),
+            argument_index,
+        }
+    }
+
+    /// Returns the reflection APIs taking the names of classes or members.
+    #[must_use]
+    pub fn reflection() -> Vec<Self> {
+        let class = Some("java/lang/Class");
+        vec![
+            Self::new(class, "forName", "(Ljava/lang/String;)Ljava/lang/Class;", 0),
+            Self::new(
+                class,
+                "forName",
+                "(Ljava/lang/String;ZLjava/lang/ClassLoader;)Ljava/lang/Class;",
+                0,
+            ),
+            Self::new(
+                None,
+                "loadClass",
+                "(Ljava/lang/String;)Ljava/lang/Class;",
+                0,
+            ),
+            Self::new(
+                class,
+                "getMethod",
+                "(Ljava/lang/String;[Ljava/lang/Class;)Ljava/lang/reflect/Method;",
+                0,
+            ),
+            Self::new(
+                class,
+                "getDeclaredMethod",
+                "(Ljava/lang/String;[Ljava/lang/Class;)Ljava/lang/reflect/Method;",
+                0,
+            ),
+            Self::new(
+                class,
+                "getField",
+                "(Ljava/lang/String;)Ljava/lang/reflect/Field;",
+                0,
+            ),
+            Self::new(
+                class,
+                "getDeclaredField",
+                "(Ljava/lang/String;)Ljava/lang/reflect/Field;",
+                0,
+            ),
+        ]
+    }
+
+    fn matches(&self, method: &MethodRef) -> bool {
+        self.owner
+            .as_ref()
+            .is_none_or(|owner| **owner == *method.owner.binary_name)
+            && *self.name == *method.name
+            && self.descriptor == method.descriptor.descriptor()
+    }
+}
+
+/// The value of a string reaching a [`StringSink`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SinkArgument {
+    /// The location of the call.
+    pub pc: ProgramCounter,
+    /// The method being called.
+    pub callee: MethodRef,
+    /// The possible values of the argument.
+    pub value: StringValue,
+}
+
+/// The possible values of the strings in a method.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringValues {
+    facts: BTreeMap<ProgramCounter, StringFact>,
+}
+
+impl StringValues {
+    /// Returns the possible values of `operand` before the instruction at `pc` executes.
+    #[must_use]
+    pub fn value_at(&self, pc: ProgramCounter, operand: &Operand) -> StringValue {
+        self.facts
+            .get(&pc)
+            .map_or(StringValue::Unknown, |fact| string_of(fact, operand))
+    }
+
+    /// Finds the calls to the sinks in `method` and the values of the string arguments.
+    #[must_use]
+    pub fn sink_arguments(&self, method: &MokaIRMethod, sinks: &[StringSink]) -> Vec<SinkArgument> {
+        method
+            .instructions
+            .iter()
+            .filter_map(|(pc, insn)| {
+                let MokaInstruction::Definition {
+                    expr: Expression::Call { method, args, .. },
+                    ..
+                } = insn
+                else {
+                    return None;
+                };
+                let sink = sinks.iter().find(|it| it.matches(method))?;
+                let value = args
+                    .get(sink.argument_index)
+                    .map_or(StringValue::Unknown, |arg| self.value_at(*pc, arg));
+                Some(SinkArgument {
+                    pc: *pc,
+                    callee: method.clone(),
+                    value,
+                })
+            })
+            .collect()
+    }
+}
+
+/// An analyzer that computes the possible values of the strings in a method.
+#[derive(Debug)]
+pub struct StringAnalyzer<'a> {
+    ir_method: &'a MokaIRMethod,
+    du_chain: DefUseChain<'a>,
+}
+
+impl<'a> StringAnalyzer<'a> {
+    /// Creates a new analyzer for `ir_method`.
+    #[must_use]
+    pub fn new(ir_method: &'a MokaIRMethod) -> Self {
+        Self {
+            ir_method,
+            du_chain: DefUseChain::new(ir_method),
+        }
+    }
+
+    /// Computes the possible values of the strings at each program point.
+    #[must_use]
+    pub fn analyze(mut self) -> StringValues {
+        let Ok(facts) = fixed_point::Analyzer::analyze(&mut self);
+        StringValues { facts }
+    }
+
+    fn transfer(&self, fact: &mut StringFact, insn: &MokaInstruction) {
+        let MokaInstruction::Definition { value, expr } = insn else {
+            return;
+        };
+        match expr {
+            Expression::Const(ConstantValue::String(JavaString::Utf8(string))) => {
+                fact.strings
+                    .insert(*value, StringValue::exact(string.clone()));
+            }
+            Expression::Conversion(Conversion::CheckCast(operand, _)) => {
+                let string = string_of(fact, operand);
+                set_string(fact, *value, string);
+            }
+            Expression::New(ClassRef { binary_name })
+                if BUILDERS.contains(&binary_name.as_ref()) =>
+            {
+                fact.builders.insert(*value, *value);
+                fact.contents.insert(*value, StringValue::exact(""));
+            }
+            Expression::Call { method, this, args } => {
+                self.transfer_call(fact, *value, method, this.as_ref(), args);
+            }
+            _ => {}
+        }
+    }
+
+    fn transfer_call(
+        &self,
+        fact: &mut StringFact,
+        value: LocalValue,
+        method: &MethodRef,
+        this: Option<&Operand>,
+        args: &[Operand],
+    ) {
+        let owner = method.owner.binary_name.as_ref();
+        let builder = this.and_then(|it| builder_of(fact, it));
+        match (builder, owner, method.name.as_ref(), args) {
+            (Some(builder), _, "<init>", []) => {
+                fact.contents.insert(builder, StringValue::exact(""));
+            }
+            (Some(builder), _, "<init>", [arg]) => {
+                let content = match &*method.descriptor.parameters_types {
+                    [FieldType::Base(PrimitiveType::Int)] => StringValue::exact(""),
+                    _ => string_of(fact, arg),
+                };
+                fact.contents.insert(builder, content);
+            }
+            (Some(builder), _, "append", [arg]) => {
+                let appended = self.appended(fact, method, arg);
+                let content = fact
+                    .contents
+                    .get(&builder)
+                    .unwrap_or(&StringValue::Unknown)
+                    .concat(&appended);
+                fact.contents.insert(builder, content);
+                fact.builders.insert(value, builder);
+            }
+            (Some(builder), _, "toString", []) => {
+                let content = fact
+                    .contents
+                    .get(&builder)
+                    .cloned()
+                    .unwrap_or(StringValue::Unknown);
+                set_string(fact, value, content);
+            }
+            (None, STRING, "concat", [arg]) => {
+                let string = this
+                    .map_or(StringValue::Unknown, |it| string_of(fact, it))
+                    .concat(&string_of(fact, arg));
+                set_string(fact, value, string);
+            }
+            (None, STRING, "valueOf" | "intern" | "toString", [] | [_]) => {
+                let operand = args.first().or(this);
+                let string = match (operand, &*method.descriptor.parameters_types) {
+                    (Some(operand), [] | [FieldType::Object(_)]) => string_of(fact, operand),
+                    (Some(operand), [FieldType::Base(base)]) => {
+                        self.primitive_string(*base, operand)
+                    }
+                    _ => StringValue::Unknown,
+                };
+                set_string(fact, value, string);
+            }
+            _ => {
+                // The builders escaping to other methods may be modified there.
+                let escaped: Vec<_> = this
+                    .into_iter()
+                    .chain(args)
+                    .filter_map(|it| builder_of(fact, it))
+                    .collect();
+                for builder in escaped {
+                    fact.contents.insert(builder, StringValue::Unknown);
+                }
+                if let ReturnType::Some(FieldType::Object(ClassRef { binary_name })) =
+                    &method.descriptor.return_type
+                {
+                    if &**binary_name == STRING {
+                        fact.strings.remove(&value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn appended(&self, fact: &StringFact, method: &MethodRef, arg: &Operand) -> StringValue {
+        match &*method.descriptor.parameters_types {
+            [FieldType::Base(base)] => self.primitive_string(*base, arg),
+            [FieldType::Object(_)] => string_of(fact, arg),
+            _ => StringValue::Unknown,
+        }
+    }
+
+    /// Converts a primitive constant to a string.
+    fn primitive_string(&self, primitive_type: PrimitiveType, operand: &Operand) -> StringValue {
+        let Operand::Just(Identifier::Local(value)) = operand else {
+            return StringValue::Unknown;
+        };
+        let constant = self
+            .du_chain
+            .defined_at(value)
+            .and_then(|pc| self.ir_method.instructions.get(&pc));
+        let Some(MokaInstruction::Definition {
+            expr: Expression::Const(constant),
+            ..
+        }) = constant
+        else {
+            return StringValue::Unknown;
+        };
+        let string = match (primitive_type, constant) {
+            (PrimitiveType::Boolean, ConstantValue::Integer(it)) => (*it != 0).to_string(),
+            (PrimitiveType::Char, ConstantValue::Integer(it)) => {
+                match u32::try_from(*it).ok().and_then(char::from_u32) {
+                    Some(it) => it.to_string(),
+                    None => return StringValue::Unknown,
+                }
+            }
+            (
+                PrimitiveType::Int | PrimitiveType::Short | PrimitiveType::Byte,
+                ConstantValue::Integer(it),
+            ) => it.to_string(),
+            (PrimitiveType::Long, ConstantValue::Long(it)) => it.to_string(),
+            _ => return StringValue::Unknown,
+        };
+        StringValue::exact(string)
+    }
+}
+
+fn set_string(fact: &mut StringFact, value: LocalValue, string: StringValue) {
+    match string {
+        StringValue::Unknown => fact.strings.remove(&value),
+        known @ StringValue::Known(_) => fact.strings.insert(value, known),
+    };
+}
+
+fn string_of(fact: &StringFact, operand: &Operand) -> StringValue {
+    operand
+        .iter()
+        .map(|id| match id {
+            Identifier::Local(value) => fact
+                .strings
+                .get(value)
+                .cloned()
+                .unwrap_or(StringValue::Unknown),
+            _ => StringValue::Unknown,
+        })
+        .reduce(StringValue::join)
+        .unwrap_or(StringValue::Unknown)
+}
+
+/// Finds the allocation site of the string builder referenced by `operand`.
+fn builder_of(fact: &StringFact, operand: &Operand) -> Option<LocalValue> {
+    let mut sites = operand.iter().map(|id| match id {
+        Identifier::Local(value) => fact.builders.get(value).copied(),
+        _ => None,
+    });
+    let first = sites.next()??;
+    sites.all(|it| it == Some(first)).then_some(first)
+}
+
+impl fixed_point::Analyzer for StringAnalyzer<'_> {
+    type Location = ProgramCounter;
+    type Fact = StringFact;
+    type Err = Infallible;
+    type AffectedLocations = Vec<(Self::Location, Self::Fact)>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        Ok(vec![(
+            self.ir_method.control_flow_graph.entry_point(),
+            StringFact::default(),
+        )])
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let mut fact = fact.clone();
+        if let Some(insn) = self.ir_method.instructions.get(location) {
+            self.transfer(&mut fact, insn);
+        }
+        let Some(edges) = self.ir_method.control_flow_graph.edges_from(*location) else {
+            return Ok(Vec::default());
+        };
+        Ok(edges.map(|(_, dst, _)| (dst, fact.clone())).collect())
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        fn join_values(
+            current: &BTreeMap<LocalValue, StringValue>,
+            incoming: BTreeMap<LocalValue, StringValue>,
+        ) -> BTreeMap<LocalValue, StringValue> {
+            let mut merged = current.clone();
+            for (value, string) in incoming {
+                let joined = match merged.remove(&value) {
+                    Some(current) => current.join(string),
+                    None => string,
+                };
+                merged.insert(value, joined);
+            }
+            merged
+        }
+
+        let StringFact {
+            strings,
+            builders,
+            contents,
+        } = incoming_fact;
+        let mut merged_builders = current_fact.builders.clone();
+        for (reference, site) in builders {
+            if merged_builders
+                .get(&reference)
+                .is_some_and(|it| *it != site)
+            {
+                merged_builders.remove(&reference);
+            } else {
+                merged_builders.insert(reference, site);
+            }
+        }
+        Ok(StringFact {
+            strings: join_values(&current_fact.strings, strings),
+            builders: merged_builders,
+            contents: join_values(&current_fact.contents, contents),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, MethodBody},
+            method, Method,
+        },
+        tests::method_ref,
+    };
+    use Instruction::{
+        ALoad0, AReturn, BiPush, Dup, Goto, ILoad1, IfEq, InvokeSpecial, InvokeStatic,
+        InvokeVirtual, Ldc, New, Pop, Return,
+    };
+
+    fn ldc(string: &str) -> Instruction {
+        Ldc(ConstantValue::String(JavaString::Utf8(string.to_owned())))
+    }
+
+    fn for_name() -> Instruction {
+        InvokeStatic(method_ref(
+            "java/lang/Class",
+            "forName",
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+        ))
+    }
+
+    fn append(descriptor: &str) -> Instruction {
+        InvokeVirtual(method_ref("java/lang/StringBuilder", "append", descriptor))
+    }
+
+    fn analyze(descriptor: &str, instructions: Vec<(u16, Instruction)>) -> Vec<SinkArgument> {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 3,
+                max_locals: 2,
+                instructions: instructions
+                    .into_iter()
+                    .map(|(pc, insn)| (pc.into(), insn))
+                    .collect::<BTreeMap<_, _>>()
+                    .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let ir_method = method.brew().unwrap();
+        StringAnalyzer::new(&ir_method)
+            .analyze()
+            .sink_arguments(&ir_method, &StringSink::reflection())
+    }
+
+    #[test]
+    fn string_builder() {
+        // Class.forName(new StringBuilder("org.").append("mokapot").append('.').append(42).toString())
+        let sinks = analyze(
+            "()V",
+            vec![
+                (0, New(ClassRef::new("java/lang/StringBuilder"))),
+                (3, Dup),
+                (4, ldc("org.")),
+                (
+                    6,
+                    InvokeSpecial(method_ref(
+                        "java/lang/StringBuilder",
+                        "<init>",
+                        "(Ljava/lang/String;)V",
+                    )),
+                ),
+                (9, ldc("mokapot")),
+                (11, append("(Ljava/lang/String;)Ljava/lang/StringBuilder;")),
+                (14, BiPush(b'.')),
+                (16, append("(C)Ljava/lang/StringBuilder;")),
+                (19, BiPush(42)),
+                (21, append("(I)Ljava/lang/StringBuilder;")),
+                (
+                    24,
+                    InvokeVirtual(method_ref(
+                        "java/lang/StringBuilder",
+                        "toString",
+                        "()Ljava/lang/String;",
+                    )),
+                ),
+                (27, for_name()),
+                (30, Pop),
+                (31, Return),
+            ],
+        );
+        assert_eq!(sinks.len(), 1);
+        assert_eq!(sinks[0].pc, 27.into());
+        assert_eq!(sinks[0].value.as_exact(), Some("org.mokapot.42"));
+    }
+
+    #[test]
+    fn concat_on_branches() {
+        // Class.forName((flag ? "Foo" : "Bar").concat("Impl"))
+        let sinks = analyze(
+            "(Ljava/lang/Object;Z)V",
+            vec![
+                (0, ILoad1),
+                (1, IfEq(9.into())),
+                (4, ldc("Foo")),
+                (6, Goto(11.into())),
+                (9, ldc("Bar")),
+                (11, ldc("Impl")),
+                (
+                    13,
+                    InvokeVirtual(method_ref(
+                        STRING,
+                        "concat",
+                        "(Ljava/lang/String;)Ljava/lang/String;",
+                    )),
+                ),
+                (16, for_name()),
+                (19, Pop),
+                (20, Return),
+            ],
+        );
+        assert_eq!(
+            sinks[0].value.candidates(),
+            Some(&BTreeSet::from([
+                "BarImpl".to_owned(),
+                "FooImpl".to_owned()
+            ]))
+        );
+    }
+
+    #[test]
+    fn escaped_builder() {
+        // StringBuilder sb = new StringBuilder(); mutate(sb); Class.forName(sb.toString())
+        let sinks = analyze(
+            "()V",
+            vec![
+                (0, New(ClassRef::new("java/lang/StringBuilder"))),
+                (3, Dup),
+                (
+                    4,
+                    InvokeSpecial(method_ref("java/lang/StringBuilder", "<init>", "()V")),
+                ),
+                (7, Dup),
+                (
+                    8,
+                    InvokeStatic(method_ref("Foo", "mutate", "(Ljava/lang/StringBuilder;)V")),
+                ),
+                (
+                    11,
+                    InvokeVirtual(method_ref(
+                        "java/lang/StringBuilder",
+                        "toString",
+                        "()Ljava/lang/String;",
+                    )),
+                ),
+                (14, for_name()),
+                (17, Pop),
+                (18, Return),
+            ],
+        );
+        assert_eq!(sinks[0].value, StringValue::Unknown);
+    }
+
+    #[test]
+    fn argument_is_unknown() {
+        let sinks = analyze(
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+            vec![(0, ALoad0), (1, for_name()), (4, AReturn)],
+        );
+        assert_eq!(sinks[0].value, StringValue::Unknown);
+    }
+}
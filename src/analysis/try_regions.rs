@@ -0,0 +1,551 @@
+//! Reconstruction of structured `try` statements from exception tables.
+//!
+//! Compilers flatten `try`/`catch`/`finally` statements into exception table entries.
+//! A statement whose body is interrupted (e.g., by a `return` that runs an inlined `finally`
+//! block) is split into several ranges, and a `finally` block is encoded as a handler catching
+//! any exception, which covers both the body and the `catch` blocks of the statement.
+//! The [`RegionTree`] groups the entries back into [`TryRegion`]s and nests them.
+//!
+//! The `finally` blocks are recognized in both encodings used by compilers:
+//! - javac (since 1.4.2) and ECJ inline a copy of the block at each exit of the statement, and
+//! - older compilers call a subroutine with `jsr`, which returns with `ret`.
+
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use crate::jvm::{
+    code::{ExceptionTableEntry, Instruction, InstructionList, MethodBody, ProgramCounter},
+    references::ClassRef,
+};
+
+/// A structured `try` statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryRegion {
+    /// The ranges of instructions protected by the statement, in ascending order.
+    pub covered: Vec<RangeInclusive<ProgramCounter>>,
+    /// The `catch` clauses in the order they are matched.
+    pub catches: Vec<CatchClause>,
+    /// The `finally` clause.
+    pub finally: Option<FinallyClause>,
+    /// The statements nested in the body or the handlers of this statement.
+    pub children: Vec<TryRegion>,
+    /// The estimated range of instructions of the whole statement including its handlers.
+    pub span: RangeInclusive<ProgramCounter>,
+}
+
+/// A `catch` clause of a [`TryRegion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchClause {
+    /// The types of exceptions caught by the clause.
+    /// There are multiple types for a multi-catch clause (e.g., `catch (A | B e)`).
+    pub catch_types: Vec<ClassRef>,
+    /// The location of the handler.
+    pub handler: ProgramCounter,
+}
+
+/// A `finally` clause of a [`TryRegion`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinallyClause {
+    /// The location of the handler that runs the block when an exception is thrown.
+    pub handler: ProgramCounter,
+    /// How the block is executed on normal exits.
+    pub encoding: FinallyEncoding,
+}
+
+/// The encoding of a `finally` block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FinallyEncoding {
+    /// The block is copied to each exit of the statement.
+    Inlined {
+        /// The ranges of the copies outside of the exception handler.
+        copies: Vec<RangeInclusive<ProgramCounter>>,
+    },
+    /// The block is a subroutine called with `jsr`.
+    Subroutine {
+        /// The location of the subroutine.
+        subroutine: ProgramCounter,
+        /// The locations of the `jsr` instructions calling the subroutine.
+        call_sites: Vec<ProgramCounter>,
+    },
+    /// The handler does not follow a known pattern.
+    Unrecognized,
+}
+
+impl TryRegion {
+    /// Checks if the instruction at `pc` is protected by the statement.
+    #[must_use]
+    pub fn covers(&self, pc: ProgramCounter) -> bool {
+        self.covered.iter().any(|range| range.contains(&pc))
+    }
+
+    fn covered_end(&self) -> Option<ProgramCounter> {
+        self.covered.iter().map(|it| *it.end()).max()
+    }
+
+    /// Returns the location of the first protected instruction.
+    #[must_use]
+    pub fn start(&self) -> ProgramCounter {
+        *self.span.start()
+    }
+}
+
+/// The [`TryRegion`]s of a method nested by their spans.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegionTree {
+    roots: Vec<TryRegion>,
+}
+
+impl RegionTree {
+    /// Reconstructs the `try` statements of a method body.
+    #[must_use]
+    pub fn of(body: &MethodBody) -> Self {
+        let instructions = &body.instructions;
+        let mut regions = group_entries(&body.exception_table);
+        for region in &mut regions {
+            if let Some(finally) = &mut region.finally {
+                finally.encoding = finally_encoding(instructions, finally.handler);
+            }
+            region.span = estimate_span(instructions, region);
+        }
+        // Outer statements come before the inner ones sharing the same start.
+        regions.sort_by(|lhs, rhs| {
+            lhs.span
+                .start()
+                .cmp(rhs.span.start())
+                .then_with(|| rhs.span.end().cmp(lhs.span.end()))
+                .then_with(|| rhs.covered_end().cmp(&lhs.covered_end()))
+        });
+        let mut roots = Vec::new();
+        let mut stack: Vec<TryRegion> = Vec::new();
+        for region in regions {
+            while let Some(top) = stack.pop() {
+                if top.span.contains(region.span.start()) && top.span.contains(region.span.end()) {
+                    stack.push(top);
+                    break;
+                }
+                attach(&mut stack, &mut roots, top);
+            }
+            stack.push(region);
+        }
+        while let Some(top) = stack.pop() {
+            attach(&mut stack, &mut roots, top);
+        }
+        Self { roots }
+    }
+
+    /// Returns the outermost statements.
+    #[must_use]
+    pub fn roots(&self) -> &[TryRegion] {
+        &self.roots
+    }
+
+    /// Creates an iterator over all the statements in pre-order.
+    pub fn iter(&self) -> impl Iterator<Item = &TryRegion> {
+        let mut stack: Vec<&TryRegion> = self.roots.iter().rev().collect();
+        std::iter::from_fn(move || {
+            let region = stack.pop()?;
+            stack.extend(region.children.iter().rev());
+            Some(region)
+        })
+    }
+
+    /// Returns the innermost statement protecting the instruction at `pc`.
+    #[must_use]
+    pub fn innermost(&self, pc: ProgramCounter) -> Option<&TryRegion> {
+        self.iter().filter(|region| region.covers(pc)).last()
+    }
+}
+
+fn attach(stack: &mut [TryRegion], roots: &mut Vec<TryRegion>, region: TryRegion) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(region),
+        None => roots.push(region),
+    }
+}
+
+/// Groups the entries sharing the same handlers into statements.
+fn group_entries(exception_table: &[ExceptionTableEntry]) -> Vec<TryRegion> {
+    let mut typed: Vec<(RangeInclusive<ProgramCounter>, Vec<CatchClause>)> = Vec::new();
+    let mut untyped: BTreeMap<ProgramCounter, Vec<RangeInclusive<ProgramCounter>>> =
+        BTreeMap::new();
+    for entry in exception_table {
+        let ExceptionTableEntry {
+            covered_pc,
+            handler_pc,
+            catch_type,
+        } = entry;
+        let Some(catch_type) = catch_type else {
+            // javac protects the beginning of a `finally` handler with the handler itself.
+            if !covered_pc.contains(handler_pc) {
+                untyped
+                    .entry(*handler_pc)
+                    .or_default()
+                    .push(covered_pc.clone());
+            }
+            continue;
+        };
+        let index = typed
+            .iter()
+            .position(|(range, _)| range == covered_pc)
+            .unwrap_or_else(|| {
+                typed.push((covered_pc.clone(), Vec::new()));
+                typed.len() - 1
+            });
+        let catches = &mut typed[index].1;
+        match catches.iter_mut().find(|it| it.handler == *handler_pc) {
+            Some(clause) => clause.catch_types.push(catch_type.clone()),
+            None => catches.push(CatchClause {
+                catch_types: vec![catch_type.clone()],
+                handler: *handler_pc,
+            }),
+        }
+    }
+
+    let mut regions: Vec<TryRegion> = Vec::new();
+    for (range, catches) in typed {
+        match regions.iter_mut().find(|it| it.catches == catches) {
+            Some(region) => region.covered.push(range),
+            None => regions.push(TryRegion {
+                covered: vec![range.clone()],
+                catches,
+                finally: None,
+                children: Vec::new(),
+                span: range,
+            }),
+        }
+    }
+    for (handler, ranges) in untyped {
+        let finally = Some(FinallyClause {
+            handler,
+            encoding: FinallyEncoding::Unrecognized,
+        });
+        // A `finally` handler covers exactly the ranges of the body of a `try`-`catch`
+        // statement, and the `catch` blocks in addition.
+        let owner = regions.iter_mut().find(|region| {
+            region.finally.is_none() && region.covered.iter().all(|it| ranges.contains(it))
+        });
+        match owner {
+            Some(region) => region.finally = finally,
+            None => regions.push(TryRegion {
+                span: ranges[0].clone(),
+                covered: ranges,
+                catches: Vec::new(),
+                finally,
+                children: Vec::new(),
+            }),
+        }
+    }
+    for region in &mut regions {
+        region.covered.sort_by_key(|it| *it.start());
+    }
+    regions
+}
+
+/// Estimates the range from the first protected instruction to the end of the last handler.
+fn estimate_span(
+    instructions: &InstructionList<Instruction>,
+    region: &TryRegion,
+) -> RangeInclusive<ProgramCounter> {
+    let start = region
+        .covered
+        .iter()
+        .map(|it| *it.start())
+        .min()
+        .unwrap_or_default();
+    let covered_end = region
+        .covered
+        .iter()
+        .map(|it| *it.end())
+        .max()
+        .unwrap_or_default();
+    let handlers = region
+        .catches
+        .iter()
+        .map(|it| it.handler)
+        .chain(region.finally.as_ref().map(|it| it.handler));
+    let first_handler = handlers.clone().min().unwrap_or(covered_end);
+    let mut end = handlers.max().unwrap_or(covered_end).max(covered_end);
+    // The body jumps over the handlers to the statement following the `try` statement.
+    let follow = instructions
+        .iter()
+        .filter(|(pc, _)| (covered_end..first_handler).contains(pc))
+        .find_map(|(_, insn)| match insn {
+            Instruction::Goto(target) | Instruction::GotoW(target) if *target > end => {
+                Some(*target)
+            }
+            _ => None,
+        });
+    if let Some(last) = follow.and_then(|it| instructions.prev_pc_of(&it)) {
+        end = end.max(last);
+    }
+    // The handler of a `finally` block ends with rethrowing the exception.
+    if let Some(finally) = &region.finally {
+        let rethrow = instructions
+            .iter()
+            .find(|(pc, insn)| **pc >= finally.handler && matches!(insn, Instruction::AThrow));
+        if let Some((&rethrow, _)) = rethrow {
+            end = end.max(rethrow);
+        }
+    }
+    start..=end
+}
+
+/// Recognizes `astore e; <finally block>; aload e; athrow` at `handler`.
+fn finally_encoding(
+    instructions: &InstructionList<Instruction>,
+    handler: ProgramCounter,
+) -> FinallyEncoding {
+    let mut handler_insns = instructions
+        .iter()
+        .skip_while(|(pc, _)| **pc < handler)
+        .map(|(pc, insn)| (*pc, insn));
+    let Some(exception) = handler_insns.next().and_then(|(_, it)| stored_local(it)) else {
+        return FinallyEncoding::Unrecognized;
+    };
+    let handler_insns: Vec<_> = handler_insns.collect();
+    let Some(rethrow) = handler_insns.windows(2).position(|pair| {
+        loaded_local(pair[0].1) == Some(exception) && matches!(pair[1].1, Instruction::AThrow)
+    }) else {
+        return FinallyEncoding::Unrecognized;
+    };
+    let block = &handler_insns[..rethrow];
+    if let [(_, Instruction::Jsr(subroutine) | Instruction::JsrW(subroutine))] = block {
+        let call_sites = instructions
+            .iter()
+            .filter(|(_, insn)| {
+                matches!(insn, Instruction::Jsr(it) | Instruction::JsrW(it) if it == subroutine)
+            })
+            .map(|(pc, _)| *pc)
+            .collect();
+        return FinallyEncoding::Subroutine {
+            subroutine: *subroutine,
+            call_sites,
+        };
+    }
+    let all_insns: Vec<_> = instructions.iter().map(|(pc, insn)| (*pc, insn)).collect();
+    let copies = if block.is_empty() {
+        Vec::new()
+    } else {
+        all_insns
+            .windows(block.len())
+            .filter(|window| window[0].0 != block[0].0)
+            .filter(|window| {
+                window
+                    .iter()
+                    .zip(block)
+                    .all(|((_, lhs), (_, rhs))| same_instruction(lhs, rhs))
+            })
+            .map(|window| window[0].0..=window[window.len() - 1].0)
+            .collect()
+    };
+    FinallyEncoding::Inlined { copies }
+}
+
+/// Compares two instructions regardless of their jump targets.
+fn same_instruction(lhs: &Instruction, rhs: &Instruction) -> bool {
+    use Instruction::{
+        Goto, GotoW, IfACmpEq, IfACmpNe, IfEq, IfGe, IfGt, IfICmpEq, IfICmpGe, IfICmpGt, IfICmpLe,
+        IfICmpLt, IfICmpNe, IfLe, IfLt, IfNe, IfNonNull, IfNull, Jsr, JsrW, LookupSwitch,
+        TableSwitch,
+    };
+    match lhs {
+        IfEq(_)
+        | IfNe(_)
+        | IfLt(_)
+        | IfGe(_)
+        | IfGt(_)
+        | IfLe(_)
+        | IfICmpEq(_)
+        | IfICmpNe(_)
+        | IfICmpLt(_)
+        | IfICmpGe(_)
+        | IfICmpGt(_)
+        | IfICmpLe(_)
+        | IfACmpEq(_)
+        | IfACmpNe(_)
+        | IfNull(_)
+        | IfNonNull(_)
+        | Goto(_)
+        | GotoW(_)
+        | Jsr(_)
+        | JsrW(_)
+        | TableSwitch { .. }
+        | LookupSwitch { .. } => lhs.opcode() == rhs.opcode(),
+        _ => lhs == rhs,
+    }
+}
+
+fn stored_local(insn: &Instruction) -> Option<u16> {
+    use crate::jvm::code::WideInstruction;
+    match insn {
+        Instruction::AStore(idx) => Some((*idx).into()),
+        Instruction::AStore0 => Some(0),
+        Instruction::AStore1 => Some(1),
+        Instruction::AStore2 => Some(2),
+        Instruction::AStore3 => Some(3),
+        Instruction::Wide(WideInstruction::AStore(idx)) => Some(*idx),
+        _ => None,
+    }
+}
+
+fn loaded_local(insn: &Instruction) -> Option<u16> {
+    use crate::jvm::code::WideInstruction;
+    match insn {
+        Instruction::ALoad(idx) => Some((*idx).into()),
+        Instruction::ALoad0 => Some(0),
+        Instruction::ALoad1 => Some(1),
+        Instruction::ALoad2 => Some(2),
+        Instruction::ALoad3 => Some(3),
+        Instruction::Wide(WideInstruction::ALoad(idx)) => Some(*idx),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::references::MethodRef;
+    use Instruction::{
+        ALoad0, ALoad1, AStore0, AStore1, AThrow, Goto, InvokeStatic, Jsr, Ret, Return,
+    };
+
+    fn call(name: &str) -> Instruction {
+        InvokeStatic(MethodRef::new(
+            ClassRef::new("Foo"),
+            name,
+            "()V".parse().unwrap(),
+        ))
+    }
+
+    fn body(
+        instructions: Vec<(u16, Instruction)>,
+        entries: Vec<(u16, u16, u16, Option<&str>)>,
+    ) -> MethodBody {
+        MethodBody {
+            instructions: instructions
+                .into_iter()
+                .map(|(pc, insn)| (pc.into(), insn))
+                .collect::<BTreeMap<_, _>>()
+                .into(),
+            exception_table: entries
+                .into_iter()
+                .map(|(start, end, handler, catch_type)| ExceptionTableEntry {
+                    covered_pc: start.into()..=end.into(),
+                    handler_pc: handler.into(),
+                    catch_type: catch_type.map(ClassRef::new),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn inlined_finally() {
+        // try { a(); } catch (IOException e) { b(); } finally { c(); }
+        let body = body(
+            vec![
+                (0, call("a")),
+                (3, call("c")),
+                (6, Goto(25.into())),
+                (9, AStore0),
+                (10, call("b")),
+                (13, call("c")),
+                (16, Goto(25.into())),
+                (19, AStore1),
+                (20, call("c")),
+                (23, ALoad1),
+                (24, AThrow),
+                (25, Return),
+            ],
+            vec![
+                (0, 0, 9, Some("java/io/IOException")),
+                (0, 0, 19, None),
+                (9, 10, 19, None),
+                (19, 20, 19, None),
+            ],
+        );
+        let tree = RegionTree::of(&body);
+        let [region] = tree.roots() else {
+            panic!("Unexpected regions: {tree:?}");
+        };
+        assert_eq!(region.covered, vec![0.into()..=0.into()]);
+        assert_eq!(
+            region.catches,
+            vec![CatchClause {
+                catch_types: vec![ClassRef::new("java/io/IOException")],
+                handler: 9.into(),
+            }]
+        );
+        assert_eq!(
+            region.finally,
+            Some(FinallyClause {
+                handler: 19.into(),
+                encoding: FinallyEncoding::Inlined {
+                    copies: vec![3.into()..=3.into(), 13.into()..=13.into()],
+                },
+            })
+        );
+        assert_eq!(region.span, 0.into()..=24.into());
+    }
+
+    #[test]
+    fn nested_regions() {
+        // try { try { a(); } catch (E1 e) { b(); } } catch (E2 e) {}
+        let body = body(
+            vec![
+                (0, call("a")),
+                (3, Goto(14.into())),
+                (6, AStore0),
+                (7, call("b")),
+                (10, Goto(14.into())),
+                (13, AStore0),
+                (14, Return),
+            ],
+            vec![(0, 0, 6, Some("E1")), (0, 10, 13, Some("E2"))],
+        );
+        let tree = RegionTree::of(&body);
+        let [outer] = tree.roots() else {
+            panic!("Unexpected regions: {tree:?}");
+        };
+        assert_eq!(outer.catches[0].catch_types, vec![ClassRef::new("E2")]);
+        let [inner] = outer.children.as_slice() else {
+            panic!("Unexpected children: {outer:?}");
+        };
+        assert_eq!(inner.catches[0].catch_types, vec![ClassRef::new("E1")]);
+        assert_eq!(tree.innermost(0.into()), Some(inner));
+        assert_eq!(tree.innermost(7.into()), Some(outer));
+        assert_eq!(tree.innermost(14.into()), None);
+    }
+
+    #[test]
+    fn subroutine_finally() {
+        // try { a(); } finally { c(); } compiled with jsr/ret
+        let body = body(
+            vec![
+                (0, call("a")),
+                (3, Jsr(15.into())),
+                (6, Goto(21.into())),
+                (9, AStore0),
+                (10, Jsr(15.into())),
+                (13, ALoad0),
+                (14, AThrow),
+                (15, AStore1),
+                (16, call("c")),
+                (19, Ret(1)),
+                (21, Return),
+            ],
+            vec![(0, 3, 9, None)],
+        );
+        let tree = RegionTree::of(&body);
+        let [region] = tree.roots() else {
+            panic!("Unexpected regions: {tree:?}");
+        };
+        assert!(region.catches.is_empty());
+        assert_eq!(
+            region.finally.as_ref().map(|it| &it.encoding),
+            Some(&FinallyEncoding::Subroutine {
+                subroutine: 15.into(),
+                call_sites: vec![3.into(), 10.into()],
+            })
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::{collections::BTreeSet, convert::Infallible};
+
+use crate::{
+    analysis::fixed_point,
+    jvm::{
+        code::{Instruction, MethodBody, ProgramCounter, WideInstruction},
+        method, Method,
+    },
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+use super::{Element, StructuralValidator, Violation, ViolationKind};
+
+impl StructuralValidator<'_> {
+    /// Checks that every local variable read in the body of `method` is written on all paths
+    /// leading to the read, following the rules of the JVM verifier.
+    /// Methods without a body have no violation.
+    #[must_use]
+    pub fn validate_definite_assignment(&self, method: &Method) -> Vec<Violation> {
+        let Some(body) = &method.body else {
+            return Vec::new();
+        };
+        let mut analyzer = DefiniteAssignment { method, body };
+        let Ok(facts) = fixed_point::Analyzer::analyze(&mut analyzer);
+        facts
+            .iter()
+            .filter_map(|(pc, assigned)| Some((*pc, assigned, body.instruction_at(*pc)?)))
+            .flat_map(|(pc, assigned, insn)| {
+                local_accesses(insn)
+                    .reads
+                    .into_iter()
+                    .filter(|it| !assigned.contains(it))
+                    .map(move |index| Violation {
+                        element: Element::Method(method.as_ref()),
+                        kind: ViolationKind::UnassignedLocal { pc, index },
+                    })
+            })
+            .collect()
+    }
+}
+
+/// Tracks the local variables that are definitely assigned before each instruction.
+struct DefiniteAssignment<'a> {
+    method: &'a Method,
+    body: &'a MethodBody,
+}
+
+impl fixed_point::Analyzer for DefiniteAssignment<'_> {
+    type Location = ProgramCounter;
+    type Fact = BTreeSet<u16>;
+    type Err = Infallible;
+    type AffectedLocations = Vec<(Self::Location, Self::Fact)>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        let Some((entry_pc, _)) = self.body.instructions.entry_point() else {
+            return Ok(Vec::new());
+        };
+        // The receiver and the arguments are assigned on entry.
+        let receiver = u16::from(
+            !self
+                .method
+                .access_flags
+                .contains(method::AccessFlags::STATIC),
+        );
+        let slots: u16 = self
+            .method
+            .descriptor
+            .parameters_types
+            .iter()
+            .map(slot_size)
+            .sum();
+        Ok(vec![(*entry_pc, (0..receiver + slots).collect())])
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let Some(insn) = self.body.instruction_at(*location) else {
+            return Ok(Vec::new());
+        };
+        let mut after = fact.clone();
+        after.extend(local_accesses(insn).writes);
+        let handlers = self
+            .body
+            .exception_table
+            .iter()
+            .filter(|it| it.covers(*location))
+            .map(|it| (it.handler_pc, fact.clone()));
+        let successors = successors(insn, *location, self.body)
+            .into_iter()
+            .map(|it| (it, after.clone()));
+        Ok(successors.chain(handlers).collect())
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        Ok(current_fact.intersection(&incoming_fact).copied().collect())
+    }
+}
+
+/// The locations the execution may continue at after `insn`, excluding exception handlers.
+fn successors(insn: &Instruction, pc: ProgramCounter, body: &MethodBody) -> Vec<ProgramCounter> {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let next = body.instructions.next_pc_of(&pc);
+    match insn {
+        Goto(target) | GotoW(target) => vec![*target],
+        // A subroutine returns to the instruction following the `jsr`.
+        Jsr(target) | JsrW(target) | IfEq(target) | IfNe(target) | IfLt(target) | IfGe(target)
+        | IfGt(target) | IfLe(target) | IfICmpEq(target) | IfICmpNe(target) | IfICmpLt(target)
+        | IfICmpGe(target) | IfICmpGt(target) | IfICmpLe(target) | IfACmpEq(target)
+        | IfACmpNe(target) | IfNull(target) | IfNonNull(target) => {
+            [*target].into_iter().chain(next).collect()
+        }
+        TableSwitch {
+            jump_targets,
+            default,
+            ..
+        } => jump_targets.iter().chain([default]).copied().collect(),
+        LookupSwitch {
+            default,
+            match_targets,
+        } => match_targets.values().chain([default]).copied().collect(),
+        IReturn
+        | LReturn
+        | FReturn
+        | DReturn
+        | AReturn
+        | Return
+        | AThrow
+        | Ret(_)
+        | Wide(WideInstruction::Ret(_)) => Vec::new(),
+        _ => next.into_iter().collect(),
+    }
+}
+
+/// The local variables read and written by an instruction.
+#[derive(Default)]
+struct LocalAccesses {
+    reads: Vec<u16>,
+    writes: Vec<u16>,
+}
+
+fn local_accesses(insn: &Instruction) -> LocalAccesses {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let single = |index: u16| vec![index];
+    // Values of type `long` and `double` occupy two local variables.
+    let double = |index: u16| vec![index, index.saturating_add(1)];
+    let read = |reads| LocalAccesses {
+        reads,
+        writes: Vec::new(),
+    };
+    let write = |writes| LocalAccesses {
+        reads: Vec::new(),
+        writes,
+    };
+    match insn {
+        ILoad(idx) | FLoad(idx) | ALoad(idx) | Ret(idx) => read(single((*idx).into())),
+        LLoad(idx) | DLoad(idx) => read(double((*idx).into())),
+        ILoad0 | FLoad0 | ALoad0 => read(single(0)),
+        ILoad1 | FLoad1 | ALoad1 => read(single(1)),
+        ILoad2 | FLoad2 | ALoad2 => read(single(2)),
+        ILoad3 | FLoad3 | ALoad3 => read(single(3)),
+        LLoad0 | DLoad0 => read(double(0)),
+        LLoad1 | DLoad1 => read(double(1)),
+        LLoad2 | DLoad2 => read(double(2)),
+        LLoad3 | DLoad3 => read(double(3)),
+        IStore(idx) | FStore(idx) | AStore(idx) => write(single((*idx).into())),
+        LStore(idx) | DStore(idx) => write(double((*idx).into())),
+        IStore0 | FStore0 | AStore0 => write(single(0)),
+        IStore1 | FStore1 | AStore1 => write(single(1)),
+        IStore2 | FStore2 | AStore2 => write(single(2)),
+        IStore3 | FStore3 | AStore3 => write(single(3)),
+        LStore0 | DStore0 => write(double(0)),
+        LStore1 | DStore1 => write(double(1)),
+        LStore2 | DStore2 => write(double(2)),
+        LStore3 | DStore3 => write(double(3)),
+        IInc(idx, _) => LocalAccesses {
+            reads: single((*idx).into()),
+            writes: single((*idx).into()),
+        },
+        Wide(wide) => match wide {
+            WideInstruction::ILoad(idx)
+            | WideInstruction::FLoad(idx)
+            | WideInstruction::ALoad(idx)
+            | WideInstruction::Ret(idx) => read(single(*idx)),
+            WideInstruction::LLoad(idx) | WideInstruction::DLoad(idx) => read(double(*idx)),
+            WideInstruction::IStore(idx)
+            | WideInstruction::FStore(idx)
+            | WideInstruction::AStore(idx) => write(single(*idx)),
+            WideInstruction::LStore(idx) | WideInstruction::DStore(idx) => write(double(*idx)),
+            WideInstruction::IInc(idx, _) => LocalAccesses {
+                reads: single(*idx),
+                writes: single(*idx),
+            },
+        },
+        _ => LocalAccesses::default(),
+    }
+}
+
+fn slot_size(field_type: &FieldType) -> u16 {
+    match field_type {
+        FieldType::Base(PrimitiveType::Long | PrimitiveType::Double) => 2,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::code::{ExceptionTableEntry, InstructionList};
+
+    use super::*;
+
+    fn method_with(
+        descriptor: &str,
+        instructions: impl IntoIterator<Item = (u16, Instruction)>,
+    ) -> Method {
+        Method {
+            access_flags: method::AccessFlags::STATIC,
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                instructions: InstructionList::from(
+                    instructions
+                        .into_iter()
+                        .map(|(pc, insn)| (pc.into(), insn))
+                        .collect::<std::collections::BTreeMap<_, _>>(),
+                ),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn unassigned(method: &Method) -> Vec<ViolationKind> {
+        StructuralValidator::new()
+            .validate_definite_assignment(method)
+            .into_iter()
+            .map(|it| it.kind)
+            .collect()
+    }
+
+    #[test]
+    fn assigned_on_some_paths() {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        let method = method_with(
+            "(JI)I",
+            [
+                (0, LLoad0),
+                (1, ILoad2),
+                (2, IfEq(5.into())),
+                (3, IConst0),
+                (4, IStore3),
+                (5, ILoad3),
+                (6, IReturn),
+            ],
+        );
+        assert_eq!(
+            unassigned(&method),
+            vec![ViolationKind::UnassignedLocal {
+                pc: 5.into(),
+                index: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn handler_sees_state_before_store() {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        let mut method = method_with(
+            "()V",
+            [
+                (0, IConst0),
+                (1, IStore0),
+                (2, Return),
+                (3, Pop),
+                (4, ILoad0),
+                (5, Pop),
+                (6, Return),
+            ],
+        );
+        assert!(unassigned(&method).is_empty());
+        method
+            .body
+            .as_mut()
+            .unwrap()
+            .exception_table
+            .push(ExceptionTableEntry {
+                covered_pc: 0.into()..=2.into(),
+                handler_pc: 3.into(),
+                catch_type: None,
+            });
+        assert_eq!(
+            unassigned(&method),
+            vec![ViolationKind::UnassignedLocal {
+                pc: 4.into(),
+                index: 0
+            }]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::jvm::{
+    code::{ExceptionTableEntry, MethodBody, ProgramCounter},
+    references::ClassRef,
+    Method,
+};
+
+use super::{Element, StructuralValidator, Violation, ViolationKind};
+
+impl StructuralValidator<'_> {
+    /// Validates the exception table of `method`.
+    /// The following constraints are checked:
+    /// - The covered range of each entry is not empty, i.e., `start_pc` is less than `end_pc`.
+    /// - `start_pc` and `handler_pc` are at the start of an instruction, and `end_pc` is at the
+    ///   start of an instruction or at the end of the code.
+    /// - The covered ranges of two entries are either disjoint or nested.
+    /// - Each entry can be selected for some exception thrown in its range, i.e., its range covers
+    ///   at least one instruction that is not already covered by an earlier entry catching the
+    ///   same or a wider exception type.
+    ///
+    /// Checks that compare the exception types of two entries treat the types that cannot be
+    /// resolved as unrelated unless they are identical.
+    /// Methods without a body have no violation.
+    #[must_use]
+    pub fn validate_exception_table(&self, method: &Method) -> Vec<Violation> {
+        let Some(body) = &method.body else {
+            return Vec::new();
+        };
+        let mut violations = Vec::new();
+        let mut report = |kind| {
+            violations.push(Violation {
+                element: Element::Method(method.as_ref()),
+                kind,
+            });
+        };
+        let code_end = body.instructions.code_length();
+        let is_instruction = |pc: ProgramCounter| body.instruction_at(pc).is_some();
+
+        for (index, entry) in body.exception_table.iter().enumerate() {
+            let (start, end) = (*entry.covered_pc.start(), *entry.covered_pc.end());
+            if start >= end {
+                report(ViolationKind::InvertedExceptionRange { index, start, end });
+            }
+            if !is_instruction(start) {
+                report(ViolationKind::MisalignedExceptionRange { index, pc: start });
+            }
+            if !is_instruction(end) && u32::from(u16::from(end)) != code_end {
+                report(ViolationKind::MisalignedExceptionRange { index, pc: end });
+            }
+            if !is_instruction(entry.handler_pc) {
+                report(ViolationKind::MisalignedHandler {
+                    index,
+                    handler: entry.handler_pc,
+                });
+            }
+        }
+
+        let valid_ranges: Vec<_> = body
+            .exception_table
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| it.covered_pc.start() < it.covered_pc.end())
+            .collect();
+        for (offset, (first, earlier)) in valid_ranges.iter().enumerate() {
+            for (second, later) in &valid_ranges[offset + 1..] {
+                if partially_overlaps(earlier, later) {
+                    report(ViolationKind::OverlappingExceptionRanges {
+                        first: *first,
+                        second: *second,
+                    });
+                }
+            }
+        }
+
+        for (index, entry) in body.exception_table.iter().enumerate() {
+            let shadowed = |pc: &ProgramCounter| {
+                body.exception_table[..index]
+                    .iter()
+                    .any(|it| covers(it, *pc) && self.catches_all_of(it, entry))
+            };
+            if covered_instructions(body, entry).all(|pc| shadowed(&pc)) {
+                report(ViolationKind::ShadowedEntry { index });
+            }
+        }
+        violations
+    }
+
+    /// Checks whether every exception caught by `later` is also caught by `earlier`.
+    fn catches_all_of(&self, earlier: &ExceptionTableEntry, later: &ExceptionTableEntry) -> bool {
+        match (&earlier.catch_type, &later.catch_type) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(earlier), Some(later)) => earlier == later || self.is_subclass(later, earlier),
+        }
+    }
+
+    fn is_subclass(&self, class: &ClassRef, super_class: &ClassRef) -> bool {
+        self.class_hierarchy
+            .is_some_and(|it| it.super_classes(class).contains(super_class))
+    }
+}
+
+/// Checks whether `pc` is in the covered range of `entry`, whose end is exclusive.
+fn covers(entry: &ExceptionTableEntry, pc: ProgramCounter) -> bool {
+    *entry.covered_pc.start() <= pc && pc < *entry.covered_pc.end()
+}
+
+fn covered_instructions<'a>(
+    body: &'a MethodBody,
+    entry: &'a ExceptionTableEntry,
+) -> impl Iterator<Item = ProgramCounter> + 'a {
+    body.instructions
+        .iter()
+        .map(|(pc, _)| *pc)
+        .filter(|pc| covers(entry, *pc))
+}
+
+/// Checks whether the covered ranges of `lhs` and `rhs` intersect without one containing the
+/// other.
+fn partially_overlaps(lhs: &ExceptionTableEntry, rhs: &ExceptionTableEntry) -> bool {
+    let (lhs_start, lhs_end) = (lhs.covered_pc.start(), lhs.covered_pc.end());
+    let (rhs_start, rhs_end) = (rhs.covered_pc.start(), rhs.covered_pc.end());
+    (lhs_start < rhs_start && rhs_start < lhs_end && lhs_end < rhs_end)
+        || (rhs_start < lhs_start && lhs_start < rhs_end && rhs_end < lhs_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::ClassHierarchy,
+        jvm::{
+            code::{Instruction, InstructionList},
+            Class,
+        },
+    };
+
+    use super::*;
+
+    fn entry(start: u16, end: u16, handler: u16, catch_type: Option<&str>) -> ExceptionTableEntry {
+        ExceptionTableEntry {
+            covered_pc: start.into()..=end.into(),
+            handler_pc: handler.into(),
+            catch_type: catch_type.map(ClassRef::new),
+        }
+    }
+
+    fn method(exception_table: Vec<ExceptionTableEntry>) -> Method {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        Method {
+            body: Some(MethodBody {
+                instructions: InstructionList::from([
+                    (0.into(), IConst0),
+                    (1.into(), IConst1),
+                    (2.into(), IAdd),
+                    (3.into(), Pop),
+                    (4.into(), Goto(9.into())),
+                    (7.into(), AThrow),
+                    (8.into(), AThrow),
+                    (9.into(), Return),
+                ]),
+                exception_table,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn violation_kinds(validator: &StructuralValidator<'_>, method: &Method) -> Vec<ViolationKind> {
+        validator
+            .validate_exception_table(method)
+            .into_iter()
+            .map(|it| it.kind)
+            .collect()
+    }
+
+    #[test]
+    fn well_formed_table() {
+        let method = method(vec![
+            entry(1, 3, 7, Some("java/lang/RuntimeException")),
+            entry(0, 7, 8, None),
+        ]);
+        assert!(StructuralValidator::new()
+            .validate_exception_table(&method)
+            .is_empty());
+    }
+
+    #[test]
+    fn malformed_ranges() {
+        let method = method(vec![
+            entry(3, 1, 7, None),
+            entry(0, 5, 6, None),
+            entry(1, 3, 7, None),
+            entry(2, 10, 8, None),
+        ]);
+        assert_eq!(
+            violation_kinds(&StructuralValidator::new(), &method),
+            vec![
+                ViolationKind::InvertedExceptionRange {
+                    index: 0,
+                    start: 3.into(),
+                    end: 1.into()
+                },
+                ViolationKind::MisalignedExceptionRange {
+                    index: 1,
+                    pc: 5.into()
+                },
+                ViolationKind::MisalignedHandler {
+                    index: 1,
+                    handler: 6.into()
+                },
+                ViolationKind::OverlappingExceptionRanges {
+                    first: 1,
+                    second: 3
+                },
+                ViolationKind::OverlappingExceptionRanges {
+                    first: 2,
+                    second: 3
+                },
+                ViolationKind::ShadowedEntry { index: 0 },
+                ViolationKind::ShadowedEntry { index: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn shadowed_handlers() {
+        let classes = [
+            Class {
+                binary_name: "java/lang/RuntimeException".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Exception")),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "java/lang/Exception".to_owned(),
+                super_class: Some(ClassRef::new("java/lang/Throwable")),
+                ..Default::default()
+            },
+        ];
+        let hierarchy = ClassHierarchy::from_classes(&classes);
+        let method = method(vec![
+            entry(0, 4, 7, Some("java/lang/Exception")),
+            entry(1, 3, 8, Some("java/lang/RuntimeException")),
+        ]);
+        assert!(StructuralValidator::new()
+            .validate_exception_table(&method)
+            .is_empty());
+        assert_eq!(
+            violation_kinds(
+                &StructuralValidator::new().with_class_hierarchy(&hierarchy),
+                &method
+            ),
+            vec![ViolationKind::ShadowedEntry { index: 1 }]
+        );
+    }
+
+    #[test]
+    fn shadowed_entry_with_reachable_handler() {
+        // `javac` may emit an entry nested in an earlier one with the same handler.
+        let method = method(vec![entry(0, 7, 8, None), entry(1, 3, 8, None)]);
+        let violations = StructuralValidator::new().validate_exception_table(&method);
+        assert_eq!(
+            violations.iter().map(|it| &it.kind).collect::<Vec<_>>(),
+            vec![&ViolationKind::ShadowedEntry { index: 1 }]
+        );
+        assert_eq!(
+            violations[0].kind.to_string(),
+            "The exception table entry 1 is shadowed by earlier entries"
+        );
+    }
+}
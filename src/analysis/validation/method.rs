@@ -0,0 +1,224 @@
+use crate::{
+    jvm::{method, references::ClassRef, Method},
+    types::field_type::FieldType,
+};
+
+use super::{Element, StructuralValidator, Violation, ViolationKind};
+
+const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+const JAVA_LANG_THROWABLE: &str = "java/lang/Throwable";
+
+impl StructuralValidator<'_> {
+    /// Validates the given method.
+    /// The following constraints are checked:
+    /// - The `MethodParameters` attribute, if present, has one entry per parameter in the descriptor.
+    /// - Each class in the `Exceptions` attribute is a subclass of `java/lang/Throwable` if it can be resolved.
+    /// - A method marked `ACC_VARARGS` takes an array as its last parameter.
+    /// - A method marked `ACC_BRIDGE` is synthetic.
+    /// - Every local variable is assigned before it is read (see
+    ///   [`StructuralValidator::validate_definite_assignment`]).
+    /// - The exception table is well-formed (see
+    ///   [`StructuralValidator::validate_exception_table`]).
+    #[must_use]
+    pub fn validate_method(&self, method: &Method) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let mut report = |kind| {
+            violations.push(Violation {
+                element: Element::Method(method.as_ref()),
+                kind,
+            });
+        };
+
+        let in_descriptor = method.descriptor.parameters_types.len();
+        if let Some(parameters) = &method.parameters {
+            let in_attribute = parameters.len();
+            if in_attribute != in_descriptor {
+                report(ViolationKind::ParameterCountMismatch {
+                    in_attribute,
+                    in_descriptor,
+                });
+            }
+        }
+
+        method
+            .exceptions
+            .iter()
+            .filter(|it| self.is_throwable(it) == Some(false))
+            .for_each(|it| report(ViolationKind::NonThrowableException(it.clone())));
+
+        if method.access_flags.contains(method::AccessFlags::VARARGS)
+            && !matches!(
+                method.descriptor.parameters_types.last(),
+                Some(FieldType::Array(_))
+            )
+        {
+            report(ViolationKind::VarargsWithoutTrailingArray);
+        }
+
+        if method.access_flags.contains(method::AccessFlags::BRIDGE)
+            && !method.access_flags.contains(method::AccessFlags::SYNTHETIC)
+            && !method.is_synthetic
+        {
+            report(ViolationKind::BridgeNotSynthetic);
+        }
+
+        violations.extend(self.validate_definite_assignment(method));
+        violations.extend(self.validate_exception_table(method));
+        violations
+    }
+
+    /// Checks whether `class` is a subclass of `java/lang/Throwable`.
+    /// Returns `None` if the super class chain of `class` cannot be resolved up to `java/lang/Object`.
+    fn is_throwable(&self, class: &ClassRef) -> Option<bool> {
+        if &*class.binary_name == JAVA_LANG_THROWABLE {
+            return Some(true);
+        }
+        let super_classes = self.class_hierarchy?.super_classes(class);
+        if super_classes.contains(&ClassRef::new(JAVA_LANG_THROWABLE)) {
+            Some(true)
+        } else if super_classes.contains(&ClassRef::new(JAVA_LANG_OBJECT)) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::ClassHierarchy,
+        jvm::{method::ParameterInfo, Class},
+    };
+
+    use super::*;
+
+    fn class_with_super(name: &str, super_class: &str) -> Class {
+        Class {
+            binary_name: name.to_owned(),
+            super_class: Some(ClassRef::new(super_class)),
+            ..Default::default()
+        }
+    }
+
+    fn violation_kinds(validator: &StructuralValidator<'_>, method: &Method) -> Vec<ViolationKind> {
+        validator
+            .validate_method(method)
+            .into_iter()
+            .map(|it| it.kind)
+            .collect()
+    }
+
+    #[test]
+    fn parameter_count_mismatch() {
+        let method = Method {
+            descriptor: "(IJ)V".parse().unwrap(),
+            parameters: Some(vec![ParameterInfo {
+                name: Some("a".to_owned()),
+                access_flags: method::ParameterAccessFlags::empty(),
+            }]),
+            ..Default::default()
+        };
+        let kinds = violation_kinds(&StructuralValidator::new(), &method);
+        assert_eq!(
+            kinds,
+            vec![ViolationKind::ParameterCountMismatch {
+                in_attribute: 1,
+                in_descriptor: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn empty_method_parameters() {
+        let method = Method {
+            descriptor: "(IJ)V".parse().unwrap(),
+            parameters: Some(Vec::new()),
+            ..Default::default()
+        };
+        let kinds = violation_kinds(&StructuralValidator::new(), &method);
+        assert_eq!(
+            kinds,
+            vec![ViolationKind::ParameterCountMismatch {
+                in_attribute: 0,
+                in_descriptor: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn absent_method_parameters() {
+        let method = Method {
+            descriptor: "(IJ)V".parse().unwrap(),
+            ..Default::default()
+        };
+        assert!(StructuralValidator::new()
+            .validate_method(&method)
+            .is_empty());
+    }
+
+    #[test]
+    fn non_throwable_exception() {
+        let classes = [
+            class_with_super("java/lang/Throwable", JAVA_LANG_OBJECT),
+            class_with_super("java/lang/Exception", JAVA_LANG_THROWABLE),
+            class_with_super("org/mokapot/NotAnException", JAVA_LANG_OBJECT),
+            class_with_super("org/mokapot/Unresolvable", "org/mokapot/Missing"),
+        ];
+        let hierarchy = ClassHierarchy::from_classes(&classes);
+        let validator = StructuralValidator::new().with_class_hierarchy(&hierarchy);
+        let method = Method {
+            exceptions: vec![
+                ClassRef::new("java/lang/Exception"),
+                ClassRef::new("org/mokapot/NotAnException"),
+                ClassRef::new("org/mokapot/Unresolvable"),
+                ClassRef::new("org/mokapot/Unknown"),
+            ],
+            ..Default::default()
+        };
+        let kinds = violation_kinds(&validator, &method);
+        assert_eq!(
+            kinds,
+            vec![ViolationKind::NonThrowableException(ClassRef::new(
+                "org/mokapot/NotAnException"
+            ))]
+        );
+        assert!(StructuralValidator::new()
+            .validate_method(&method)
+            .is_empty());
+    }
+
+    #[test]
+    fn varargs_without_array() {
+        let validator = StructuralValidator::new();
+        let mut method = Method {
+            access_flags: method::AccessFlags::VARARGS,
+            descriptor: "([II)V".parse().unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            violation_kinds(&validator, &method),
+            vec![ViolationKind::VarargsWithoutTrailingArray]
+        );
+        method.descriptor = "(I[I)V".parse().unwrap();
+        assert!(validator.validate_method(&method).is_empty());
+    }
+
+    #[test]
+    fn bridge_not_synthetic() {
+        let validator = StructuralValidator::new();
+        let mut method = Method {
+            access_flags: method::AccessFlags::BRIDGE,
+            ..Default::default()
+        };
+        assert_eq!(
+            violation_kinds(&validator, &method),
+            vec![ViolationKind::BridgeNotSynthetic]
+        );
+        method.is_synthetic = true;
+        assert!(validator.validate_method(&method).is_empty());
+        method.is_synthetic = false;
+        method.access_flags |= method::AccessFlags::SYNTHETIC;
+        assert!(validator.validate_method(&method).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Structural validation of JVM elements.
+//!
+//! The parser only rejects class files that cannot be decoded.
+//! The validator checks the constraints that span across multiple attributes and flags,
+//! and reports every violation it finds instead of stopping at the first one.
+
+use crate::{
+    ir::ClassHierarchy,
+    jvm::{
+        code::ProgramCounter,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class,
+    },
+};
+
+mod definite_assignment;
+mod exception_table;
+mod method;
+
+/// A structural validator for JVM elements.
+#[derive(Debug, Default, Clone)]
+pub struct StructuralValidator<'h> {
+    class_hierarchy: Option<&'h ClassHierarchy>,
+}
+
+impl<'h> StructuralValidator<'h> {
+    /// Creates a new validator that does not resolve any class.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            class_hierarchy: None,
+        }
+    }
+
+    /// Uses the given class hierarchy to resolve classes referred by the elements being validated.
+    /// Checks that require class resolution are skipped when a class cannot be resolved.
+    #[must_use]
+    pub const fn with_class_hierarchy(mut self, class_hierarchy: &'h ClassHierarchy) -> Self {
+        self.class_hierarchy = Some(class_hierarchy);
+        self
+    }
+
+    /// Validates the given class and all its members.
+    #[must_use]
+    pub fn validate_class(&self, class: &Class) -> Vec<Violation> {
+        class
+            .methods
+            .iter()
+            .flat_map(|method| self.validate_method(method))
+            .collect()
+    }
+}
+
+/// A violation of a structural constraint.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{element}: {kind}")]
+pub struct Violation {
+    /// The element that violates the constraint.
+    pub element: Element,
+    /// The constraint that is violated.
+    pub kind: ViolationKind,
+}
+
+/// A reference to an element that is subject to validation.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum Element {
+    /// A class.
+    #[display("class {_0}")]
+    Class(ClassRef),
+    /// A field.
+    #[display("field {_0}")]
+    Field(FieldRef),
+    /// A method.
+    #[display("method {_0}{}", _0.descriptor.descriptor())]
+    Method(MethodRef),
+}
+
+/// The kind of a [`Violation`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ViolationKind {
+    /// The number of entries in the `MethodParameters` attribute does not match the descriptor.
+    #[error(
+        "The MethodParameters attribute has {in_attribute} entries \
+         but the descriptor declares {in_descriptor} parameters"
+    )]
+    ParameterCountMismatch {
+        /// The number of entries in the `MethodParameters` attribute.
+        in_attribute: usize,
+        /// The number of parameters in the method descriptor.
+        in_descriptor: usize,
+    },
+    /// A class listed in the `Exceptions` attribute is not a subclass of `java/lang/Throwable`.
+    #[error("The declared exception {0} is not a subclass of java/lang/Throwable")]
+    NonThrowableException(ClassRef),
+    /// A method is declared with variable arity but its last parameter is not an array.
+    #[error("The method is marked ACC_VARARGS but its last parameter is not an array")]
+    VarargsWithoutTrailingArray,
+    /// A method is marked as a bridge method but is not synthetic.
+    #[error("The method is marked ACC_BRIDGE but is neither ACC_SYNTHETIC nor has a Synthetic attribute")]
+    BridgeNotSynthetic,
+    /// A local variable is read before it is written on some path.
+    #[error("The local variable {index} is read at {pc} before it is assigned on some path")]
+    UnassignedLocal {
+        /// The location of the read.
+        pc: ProgramCounter,
+        /// The index of the local variable.
+        index: u16,
+    },
+    /// The covered range of an exception table entry is empty or inverted.
+    #[error("The exception table entry {index} covers an empty range from {start} to {end}")]
+    InvertedExceptionRange {
+        /// The index of the entry in the exception table.
+        index: usize,
+        /// The start of the covered range.
+        start: ProgramCounter,
+        /// The exclusive end of the covered range.
+        end: ProgramCounter,
+    },
+    /// A bound of the covered range of an exception table entry is not at an instruction
+    /// boundary.
+    #[error("The exception table entry {index} has a range bound {pc} inside an instruction")]
+    MisalignedExceptionRange {
+        /// The index of the entry in the exception table.
+        index: usize,
+        /// The misaligned bound.
+        pc: ProgramCounter,
+    },
+    /// The handler of an exception table entry is not the start of an instruction.
+    #[error("The handler {handler} of the exception table entry {index} is not the start of an instruction")]
+    MisalignedHandler {
+        /// The index of the entry in the exception table.
+        index: usize,
+        /// The location of the handler.
+        handler: ProgramCounter,
+    },
+    /// The covered ranges of two exception table entries intersect but neither contains the
+    /// other.
+    #[error("The exception table entries {first} and {second} have overlapping ranges")]
+    OverlappingExceptionRanges {
+        /// The index of the earlier entry in the exception table.
+        first: usize,
+        /// The index of the later entry in the exception table.
+        second: usize,
+    },
+    /// An exception table entry is never selected, because its range covers no instruction or
+    /// the earlier entries catch all of its exceptions.
+    /// Its handler may still be reached through other entries, which is common in the output of
+    /// `javac`.
+    #[error("The exception table entry {index} is shadowed by earlier entries")]
+    ShadowedEntry {
+        /// The index of the entry in the exception table.
+        index: usize,
+    },
+}
@@ -0,0 +1,342 @@
+//! An audit of the class file versions, preview features, and JDK-internal APIs used by a set of
+//! classes.
+//!
+//! [`audit`] reports, for each class, whether it is compiled with `--enable-preview`, which class
+//! file features it uses together with the class file version that introduced them, and which
+//! JDK-internal classes (in the `sun` and `jdk.internal` packages) it references.
+//! The resulting [`MigrationReport`] helps to find the classes that block upgrading or downgrading
+//! the JDK across a large set of dependencies.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display, Formatter},
+};
+
+use crate::jvm::{
+    class::{self, Version},
+    code::Instruction,
+    method,
+    references::ClassRef,
+    Class, ConstantValue,
+};
+
+/// The packages of the JDK-internal APIs, as prefixes of binary names.
+const INTERNAL_PACKAGES: [&str; 2] = ["sun/", "jdk/internal/"];
+
+/// A class file feature that requires a minimum class file version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Feature {
+    /// `ldc` of a class literal.
+    #[display("class literal constants")]
+    ClassLiterals,
+    /// The `invokedynamic` instruction.
+    #[display("invokedynamic")]
+    InvokeDynamic,
+    /// `ldc` of a method handle or a method type.
+    #[display("method handle constants")]
+    MethodHandleConstants,
+    /// Non-abstract instance methods in interfaces.
+    #[display("default methods")]
+    DefaultMethods,
+    /// Static methods in interfaces.
+    #[display("static interface methods")]
+    StaticInterfaceMethods,
+    /// Type annotations.
+    #[display("type annotations")]
+    TypeAnnotations,
+    /// The `module-info` class.
+    #[display("modules")]
+    Modules,
+    /// The `NestHost` and `NestMembers` attributes.
+    #[display("nest-based access control")]
+    NestMates,
+    /// `ldc` of a dynamically-computed constant.
+    #[display("dynamic constants")]
+    DynamicConstants,
+    /// Record classes.
+    #[display("records")]
+    Records,
+    /// Sealed classes.
+    #[display("sealed classes")]
+    SealedClasses,
+}
+
+impl Feature {
+    /// Returns the major version of the first class file format supporting the feature.
+    #[must_use]
+    pub const fn required_major(&self) -> u16 {
+        match self {
+            Self::ClassLiterals => 49,
+            Self::InvokeDynamic | Self::MethodHandleConstants => 51,
+            Self::DefaultMethods | Self::StaticInterfaceMethods | Self::TypeAnnotations => 52,
+            Self::Modules => 53,
+            Self::NestMates | Self::DynamicConstants => 55,
+            Self::Records => 60,
+            Self::SealedClasses => 61,
+        }
+    }
+}
+
+/// The audit result of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassAudit {
+    /// The version of the class file.
+    pub version: Version,
+    /// The class file features used by the class.
+    pub features: BTreeSet<Feature>,
+    /// The JDK-internal classes referenced by the class.
+    pub internal_references: BTreeSet<ClassRef>,
+}
+
+impl ClassAudit {
+    /// Audits `class`.
+    #[must_use]
+    pub fn of(class: &Class) -> Self {
+        let internal_references = class
+            .referenced_classes()
+            .into_iter()
+            .filter(is_internal)
+            .collect();
+        Self {
+            version: class.version,
+            features: features_of(class),
+            internal_references,
+        }
+    }
+
+    /// Checks whether the class is compiled with `--enable-preview`.
+    #[must_use]
+    pub const fn is_preview_enabled(&self) -> bool {
+        self.version.is_preview_enabled()
+    }
+
+    /// Returns the minimum major version of a class file that can express the features used by
+    /// the class, which is at most the version of the class file.
+    #[must_use]
+    pub fn required_major(&self) -> u16 {
+        self.features
+            .iter()
+            .map(Feature::required_major)
+            .max()
+            .unwrap_or(45)
+    }
+}
+
+/// The audit result of a set of classes. See the [module-level documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The audit results of the classes.
+    pub classes: BTreeMap<ClassRef, ClassAudit>,
+}
+
+/// Audits `classes`.
+#[must_use]
+pub fn audit(classes: &[Class]) -> MigrationReport {
+    let classes = classes
+        .iter()
+        .map(|it| (it.as_ref(), ClassAudit::of(it)))
+        .collect();
+    MigrationReport { classes }
+}
+
+impl MigrationReport {
+    /// Returns the classes compiled with `--enable-preview`.
+    pub fn preview_classes(&self) -> impl Iterator<Item = &ClassRef> {
+        self.classes
+            .iter()
+            .filter(|(_, it)| it.is_preview_enabled())
+            .map(|(class_ref, _)| class_ref)
+    }
+
+    /// Returns the classes referencing JDK-internal APIs.
+    pub fn internal_api_users(&self) -> impl Iterator<Item = &ClassRef> {
+        self.classes
+            .iter()
+            .filter(|(_, it)| !it.internal_references.is_empty())
+            .map(|(class_ref, _)| class_ref)
+    }
+
+    /// Returns the classes whose class file version is newer than `major`, i.e., the classes
+    /// that cannot be loaded by a JVM supporting class files up to `major`.
+    pub fn classes_newer_than(&self, major: u16) -> impl Iterator<Item = &ClassRef> {
+        self.classes
+            .iter()
+            .filter(move |(_, it)| it.version.major() > major)
+            .map(|(class_ref, _)| class_ref)
+    }
+
+    /// Returns the highest class file major version among the classes, or [`None`] if there are
+    /// no classes.
+    #[must_use]
+    pub fn max_major(&self) -> Option<u16> {
+        self.classes.values().map(|it| it.version.major()).max()
+    }
+}
+
+impl Display for MigrationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} classes, {} with preview features, {} referencing JDK-internal APIs",
+            self.classes.len(),
+            self.preview_classes().count(),
+            self.internal_api_users().count()
+        )?;
+        for (class_ref, audit) in &self.classes {
+            let preview = if audit.is_preview_enabled() {
+                ", preview"
+            } else {
+                ""
+            };
+            writeln!(f, "{class_ref} (major {}{preview})", audit.version.major())?;
+            for feature in &audit.features {
+                writeln!(f, "  {feature} (major {})", feature.required_major())?;
+            }
+            for internal in &audit.internal_references {
+                writeln!(f, "  internal API {internal}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_internal(class_ref: &ClassRef) -> bool {
+    INTERNAL_PACKAGES
+        .iter()
+        .any(|it| class_ref.binary_name.starts_with(it))
+}
+
+fn features_of(class: &Class) -> BTreeSet<Feature> {
+    let mut features = BTreeSet::new();
+    if class.module.is_some() {
+        features.insert(Feature::Modules);
+    }
+    if class.nest_host.is_some() || !class.nest_members.is_empty() {
+        features.insert(Feature::NestMates);
+    }
+    if class.record.is_some() {
+        features.insert(Feature::Records);
+    }
+    if !class.permitted_subclasses.is_empty() {
+        features.insert(Feature::SealedClasses);
+    }
+    let is_interface = class.access_flags.contains(class::AccessFlags::INTERFACE);
+    let has_type_annotations = !class.runtime_visible_type_annotations.is_empty()
+        || !class.runtime_invisible_type_annotations.is_empty()
+        || class.fields.iter().any(|it| {
+            !it.runtime_visible_type_annotations.is_empty()
+                || !it.runtime_invisible_type_annotations.is_empty()
+        });
+    if has_type_annotations {
+        features.insert(Feature::TypeAnnotations);
+    }
+    for method in &class.methods {
+        if !method.runtime_visible_type_annotations.is_empty()
+            || !method.runtime_invisible_type_annotations.is_empty()
+        {
+            features.insert(Feature::TypeAnnotations);
+        }
+        if is_interface && method.name != "<clinit>" {
+            if method.access_flags.contains(method::AccessFlags::STATIC) {
+                features.insert(Feature::StaticInterfaceMethods);
+            } else if method.body.is_some() {
+                features.insert(Feature::DefaultMethods);
+            }
+        }
+        let instructions = method.body.iter().flat_map(|it| it.instructions.iter());
+        for (_, insn) in instructions {
+            match insn {
+                Instruction::InvokeDynamic { .. } => {
+                    features.insert(Feature::InvokeDynamic);
+                }
+                Instruction::Ldc(constant)
+                | Instruction::LdcW(constant)
+                | Instruction::Ldc2W(constant) => {
+                    features.extend(constant_feature(constant));
+                }
+                _ => {}
+            }
+        }
+    }
+    features
+}
+
+const fn constant_feature(constant: &ConstantValue) -> Option<Feature> {
+    match constant {
+        ConstantValue::Class(_) => Some(Feature::ClassLiterals),
+        ConstantValue::Handle(_) | ConstantValue::MethodType(_) => {
+            Some(Feature::MethodHandleConstants)
+        }
+        ConstantValue::Dynamic(..) => Some(Feature::DynamicConstants),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    #[test]
+    fn features_and_internal_references() {
+        let mut class = jasmin::read(
+            r"
+            .bytecode 61.0
+            .class public interface abstract org/mokapot/Api
+            .super java/lang/Object
+            .method public static create()Ljava/lang/Object;
+                .limit stack 1
+                .limit locals 0
+                ldc class org/mokapot/Api
+                areturn
+            .end method
+            .method public unsafe()Ljava/lang/Object;
+                .limit stack 1
+                .limit locals 1
+                getstatic sun/misc/Unsafe/theUnsafe Lsun/misc/Unsafe;
+                areturn
+            .end method
+            ",
+        )
+        .unwrap();
+        class.permitted_subclasses = vec![ClassRef::new("org/mokapot/Impl")];
+        let report = audit(&[class]);
+        let api = &report.classes[&ClassRef::new("org/mokapot/Api")];
+        assert_eq!(
+            api.features,
+            BTreeSet::from([
+                Feature::ClassLiterals,
+                Feature::DefaultMethods,
+                Feature::StaticInterfaceMethods,
+                Feature::SealedClasses,
+            ])
+        );
+        assert_eq!(api.required_major(), 61);
+        assert_eq!(
+            api.internal_references,
+            BTreeSet::from([ClassRef::new("sun/misc/Unsafe")])
+        );
+        assert_eq!(report.internal_api_users().count(), 1);
+        assert_eq!(report.classes_newer_than(55).count(), 1);
+        assert_eq!(report.classes_newer_than(61).count(), 0);
+    }
+
+    #[test]
+    fn preview_classes() {
+        let preview = jasmin::read(
+            r"
+            .bytecode 65.65535
+            .class public Preview
+            .super java/lang/Object
+            ",
+        )
+        .unwrap();
+        let report = audit(&[preview]);
+        assert_eq!(report.preview_classes().count(), 1);
+        assert_eq!(report.max_major(), Some(65));
+        assert!(report
+            .to_string()
+            .contains("1 classes, 1 with preview features, 0 referencing JDK-internal APIs"));
+        assert!(report.to_string().contains("Preview (major 65, preview)"));
+    }
+}
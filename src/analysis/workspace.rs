@@ -0,0 +1,392 @@
+//! A long-lived façade over the components needed to analyze a set of classes.
+//!
+//! A [`Workspace`] owns the application and library class paths, the index of the loaded
+//! classes (i.e., a [`ResolutionContext`]), an optional [`ArtifactStore`], and the results of
+//! the [`WorkspaceAnalysis`]es computed so far.
+//! It goes through an explicit lifecycle: it is opened with [`WorkspaceBuilder::open`],
+//! reloaded with [`Workspace::refresh`] when the classes change, and released with
+//! [`Workspace::close`].
+//!
+//! Queries are answered by a [`Snapshot`], which is an immutable view of the workspace at a
+//! given generation. Snapshots can be shared across threads and remain valid after the
+//! workspace is refreshed or closed.
+
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    fmt::{self, Debug},
+    io,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, PoisonError, RwLock,
+    },
+};
+
+use crate::{
+    ir::{ClassHierarchy, InterfaceImplHierarchy},
+    jvm::{
+        class_loader::{self, ClassPath},
+        references::ClassRef,
+        Class,
+    },
+};
+
+use super::{artifacts::ArtifactStore, ClassRefs, ResolutionContext};
+
+/// A class path that can enumerate its classes.
+pub trait IndexedClassPath: ClassPath + ClassRefs + Send + Sync {}
+
+impl<T: ClassPath + ClassRefs + Send + Sync> IndexedClassPath for T {}
+
+/// An error when operating on a [`Workspace`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The workspace is closed.
+    #[error("The workspace is closed")]
+    Closed,
+    /// A class listed by a class path cannot be loaded.
+    #[error("Failed to load class {binary_name}: {source}")]
+    Load {
+        /// The binary name of the class.
+        binary_name: String,
+        /// The cause of the failure.
+        source: class_loader::Error,
+    },
+    /// The artifact store cannot be opened.
+    #[error("Failed to open the artifact store: {0}")]
+    ArtifactStore(#[from] io::Error),
+}
+
+/// An analysis whose result is computed once per [`Snapshot`] and shared by its users.
+pub trait WorkspaceAnalysis: 'static {
+    /// The result of the analysis.
+    type Output: Send + Sync + 'static;
+
+    /// Runs the analysis on `snapshot`.
+    fn run(snapshot: &Snapshot) -> Self::Output;
+}
+
+/// A builder of [`Workspace`].
+#[derive(Default)]
+pub struct WorkspaceBuilder {
+    application_paths: Vec<Box<dyn IndexedClassPath>>,
+    library_paths: Vec<Box<dyn IndexedClassPath>>,
+    artifact_root: Option<PathBuf>,
+}
+
+impl Debug for WorkspaceBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkspaceBuilder")
+            .field("application_paths", &self.application_paths.len())
+            .field("library_paths", &self.library_paths.len())
+            .field("artifact_root", &self.artifact_root)
+            .finish()
+    }
+}
+
+impl WorkspaceBuilder {
+    /// Adds a class path containing application classes.
+    #[must_use]
+    pub fn with_application_path(mut self, class_path: impl IndexedClassPath + 'static) -> Self {
+        self.application_paths.push(Box::new(class_path));
+        self
+    }
+
+    /// Adds a class path containing library classes.
+    #[must_use]
+    pub fn with_library_path(mut self, class_path: impl IndexedClassPath + 'static) -> Self {
+        self.library_paths.push(Box::new(class_path));
+        self
+    }
+
+    /// Uses the [`ArtifactStore`] in the directory `root`.
+    #[must_use]
+    pub fn with_artifact_store(mut self, root: impl Into<PathBuf>) -> Self {
+        self.artifact_root = Some(root.into());
+        self
+    }
+
+    /// Opens the workspace, loading all classes in the class paths.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn open(self) -> Result<Workspace, Error> {
+        let artifact_store = self
+            .artifact_root
+            .as_ref()
+            .map(ArtifactStore::open)
+            .transpose()?
+            .map(Arc::new);
+        let workspace = Workspace {
+            application_paths: self.application_paths,
+            library_paths: self.library_paths,
+            artifact_store,
+            next_generation: AtomicU64::new(0),
+            current: RwLock::new(None),
+        };
+        workspace.refresh()?;
+        Ok(workspace)
+    }
+}
+
+/// A set of classes under analysis. See the [module-level documentation](self).
+pub struct Workspace {
+    application_paths: Vec<Box<dyn IndexedClassPath>>,
+    library_paths: Vec<Box<dyn IndexedClassPath>>,
+    artifact_store: Option<Arc<ArtifactStore>>,
+    next_generation: AtomicU64,
+    current: RwLock<Option<Arc<Snapshot>>>,
+}
+
+impl Debug for Workspace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Workspace")
+            .field("application_paths", &self.application_paths.len())
+            .field("library_paths", &self.library_paths.len())
+            .field("artifact_store", &self.artifact_store)
+            .field("generation", &self.generation().ok())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Workspace {
+    /// Creates a [`WorkspaceBuilder`].
+    #[must_use]
+    pub fn builder() -> WorkspaceBuilder {
+        WorkspaceBuilder::default()
+    }
+
+    /// Reloads all classes in the class paths, discarding the results of the analyses.
+    /// The snapshots taken before remain unchanged.
+    /// Refreshing a closed workspace reopens it.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn refresh(&self) -> Result<(), Error> {
+        let application_classes = load_classes(&self.application_paths)?;
+        let library_classes = load_classes(&self.library_paths)?;
+        let all_classes = application_classes.values().chain(library_classes.values());
+        let context = ResolutionContext {
+            class_hierarchy: ClassHierarchy::from_classes(all_classes.clone()),
+            interface_implementations: InterfaceImplHierarchy::from_classes(all_classes),
+            application_classes,
+            library_classes,
+        };
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        let generation = self.next_generation.fetch_add(1, Ordering::Relaxed);
+        *current = Some(Arc::new(Snapshot {
+            generation,
+            context,
+            artifact_store: self.artifact_store.clone(),
+            analyses: Mutex::default(),
+        }));
+        Ok(())
+    }
+
+    /// Closes the workspace, releasing the loaded classes once all snapshots are dropped.
+    pub fn close(&self) {
+        let mut current = self.current.write().unwrap_or_else(PoisonError::into_inner);
+        *current = None;
+    }
+
+    /// Checks whether the workspace is open.
+    #[must_use]
+    pub fn is_open(&self) -> bool {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some()
+    }
+
+    /// Takes a snapshot of the current state of the workspace.
+    ///
+    /// # Errors
+    /// Returns [`Error::Closed`] if the workspace is closed.
+    pub fn snapshot(&self) -> Result<Arc<Snapshot>, Error> {
+        self.current
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .ok_or(Error::Closed)
+    }
+
+    /// Returns the generation of the workspace, which is incremented on every refresh.
+    ///
+    /// # Errors
+    /// Returns [`Error::Closed`] if the workspace is closed.
+    pub fn generation(&self) -> Result<u64, Error> {
+        self.snapshot().map(|it| it.generation)
+    }
+
+    /// Returns a copy of the class referred to by `class_ref`.
+    ///
+    /// # Errors
+    /// Returns [`Error::Closed`] if the workspace is closed.
+    pub fn class(&self, class_ref: &ClassRef) -> Result<Option<Class>, Error> {
+        self.snapshot().map(|it| it.class(class_ref).cloned())
+    }
+
+    /// Returns the result of the analysis `A` on the current snapshot.
+    ///
+    /// # Errors
+    /// Returns [`Error::Closed`] if the workspace is closed.
+    pub fn analysis<A: WorkspaceAnalysis>(&self) -> Result<Arc<A::Output>, Error> {
+        self.snapshot().map(|it| it.analysis::<A>())
+    }
+}
+
+/// An immutable view of a [`Workspace`].
+pub struct Snapshot {
+    generation: u64,
+    context: ResolutionContext,
+    artifact_store: Option<Arc<ArtifactStore>>,
+    analyses: Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+}
+
+impl Debug for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Snapshot")
+            .field("generation", &self.generation)
+            .field(
+                "application_classes",
+                &self.context.application_classes.len(),
+            )
+            .field("library_classes", &self.context.library_classes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Snapshot {
+    /// Returns the generation of the workspace when the snapshot was taken.
+    #[must_use]
+    pub const fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Returns the index of the loaded classes.
+    #[must_use]
+    pub const fn context(&self) -> &ResolutionContext {
+        &self.context
+    }
+
+    /// Returns the class referred to by `class_ref`, looking up the application classes first.
+    #[must_use]
+    pub fn class(&self, class_ref: &ClassRef) -> Option<&Class> {
+        self.context.get_class(class_ref)
+    }
+
+    /// Checks whether `class_ref` refers to an application class.
+    #[must_use]
+    pub fn is_application_class(&self, class_ref: &ClassRef) -> bool {
+        self.context.application_classes.contains_key(class_ref)
+    }
+
+    /// Returns the artifact store of the workspace, if any.
+    #[must_use]
+    pub fn artifact_store(&self) -> Option<&ArtifactStore> {
+        self.artifact_store.as_deref()
+    }
+
+    /// Returns the result of the analysis `A`, running it if it has not been run on this
+    /// snapshot. The analysis is run without holding a lock, so it may query other analyses.
+    pub fn analysis<A: WorkspaceAnalysis>(&self) -> Arc<A::Output> {
+        let key = TypeId::of::<A>();
+        if let Some(output) = self.cached::<A>() {
+            return output;
+        }
+        let output: Arc<dyn Any + Send + Sync> = Arc::new(A::run(self));
+        let mut analyses = self.analyses.lock().unwrap_or_else(PoisonError::into_inner);
+        // Another thread may have finished the same analysis in the meantime.
+        let output = analyses.entry(key).or_insert(output).clone();
+        drop(analyses);
+        output
+            .downcast()
+            .unwrap_or_else(|_| unreachable!("The output is keyed by the type of the analysis"))
+    }
+
+    fn cached<A: WorkspaceAnalysis>(&self) -> Option<Arc<A::Output>> {
+        let analyses = self.analyses.lock().unwrap_or_else(PoisonError::into_inner);
+        analyses
+            .get(&TypeId::of::<A>())
+            .and_then(|it| it.clone().downcast().ok())
+    }
+}
+
+fn load_classes(
+    class_paths: &[Box<dyn IndexedClassPath>],
+) -> Result<BTreeMap<ClassRef, Class>, Error> {
+    let mut classes = BTreeMap::new();
+    for class_path in class_paths {
+        for class_ref in class_path.class_refs() {
+            let class = class_path
+                .find_class(&class_ref.binary_name)
+                .map_err(|source| Error::Load {
+                    binary_name: class_ref.binary_name.to_string(),
+                    source,
+                })?;
+            classes.entry(class_ref).or_insert(class);
+        }
+    }
+    Ok(classes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, sync::atomic::AtomicUsize};
+
+    use super::*;
+    use crate::{
+        jvm::class_loader::class_paths::DirectoryClassPath, tests::empty_class_with_version,
+    };
+
+    static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    struct ClassCount;
+
+    impl WorkspaceAnalysis for ClassCount {
+        type Output = usize;
+
+        fn run(snapshot: &Snapshot) -> Self::Output {
+            RUNS.fetch_add(1, Ordering::Relaxed);
+            snapshot.context().application_classes.len()
+        }
+    }
+
+    #[test]
+    fn lifecycle() {
+        let root = std::env::temp_dir().join(format!(
+            "mokapot-workspace-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(
+            root.join("HelloWorld.class"),
+            empty_class_with_version(52, 0),
+        )
+        .unwrap();
+
+        let workspace = Workspace::builder()
+            .with_application_path(DirectoryClassPath::new(&root))
+            .open()
+            .unwrap();
+        let hello = ClassRef::new("HelloWorld");
+        assert!(workspace.class(&hello).unwrap().is_some());
+        assert_eq!(*workspace.analysis::<ClassCount>().unwrap(), 1);
+        assert_eq!(*workspace.analysis::<ClassCount>().unwrap(), 1);
+        assert_eq!(RUNS.load(Ordering::Relaxed), 1);
+
+        let before = workspace.snapshot().unwrap();
+        fs::remove_file(root.join("HelloWorld.class")).unwrap();
+        workspace.refresh().unwrap();
+        assert_eq!(workspace.generation().unwrap(), 1);
+        assert_eq!(*workspace.analysis::<ClassCount>().unwrap(), 0);
+        assert!(before.class(&hello).is_some());
+
+        workspace.close();
+        assert!(!workspace.is_open());
+        assert!(matches!(workspace.snapshot(), Err(Error::Closed)));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
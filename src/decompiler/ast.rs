@@ -0,0 +1,282 @@
+//! The abstract syntax tree of decompiled methods.
+
+use std::fmt::{self, Display, Formatter};
+
+use itertools::Itertools;
+
+use crate::{
+    ir::{
+        expression::{Condition, Expression},
+        LocalValue, MokaInstruction, Operand,
+    },
+    jvm::{code::ProgramCounter, method, references::ClassRef},
+    types::method_descriptor::{MethodDescriptor, ReturnType},
+};
+
+/// A sequence of statements.
+pub type Block = Vec<Statement>;
+
+/// A statement in a decompiled method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Statement {
+    /// Defines a value by evaluating an expression.
+    Definition {
+        /// The value being defined.
+        value: LocalValue,
+        /// The expression that defines the value.
+        expr: Expression,
+    },
+    /// Evaluates an expression that does not produce a value, e.g., a call to a `void` method.
+    Expression(Expression),
+    /// A two-way branch.
+    If {
+        /// The condition of the branch.
+        condition: Condition,
+        /// The statements executed when the condition holds.
+        then: Block,
+        /// The statements executed when the condition does not hold.
+        otherwise: Block,
+    },
+    /// A loop labeled with the program counter of its header.
+    /// The loop repeats until a [`Statement::Break`] if the condition is [`None`].
+    Loop {
+        /// The program counter of the loop header.
+        label: ProgramCounter,
+        /// The condition checked before each iteration.
+        condition: Option<Condition>,
+        /// The body of the loop.
+        body: Block,
+    },
+    /// A multi-way branch.
+    Switch {
+        /// The value to match against the cases.
+        value: Operand,
+        /// The cases of the switch.
+        cases: Vec<SwitchCase>,
+    },
+    /// A `try` block with its exception handlers.
+    Try {
+        /// The statements protected by the handlers.
+        body: Block,
+        /// The exception handlers.
+        catches: Vec<CatchClause>,
+    },
+    /// Returns from the method with an optional value.
+    Return(Option<Operand>),
+    /// Throws an exception.
+    Throw(Operand),
+    /// Exits the loop labeled with the program counter.
+    Break(ProgramCounter),
+    /// Starts the next iteration of the loop labeled with the program counter.
+    Continue(ProgramCounter),
+    /// A jump target that cannot be expressed with structured control flow.
+    Label(ProgramCounter),
+    /// A jump that cannot be expressed with structured control flow.
+    Goto(ProgramCounter),
+    /// An instruction without a structured counterpart, e.g., the `ret` of a subroutine.
+    Unstructured(MokaInstruction),
+}
+
+/// A case in a [`Statement::Switch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwitchCase {
+    /// The values matched by the case.
+    pub keys: Vec<i32>,
+    /// Whether the case is the `default` case.
+    pub is_default: bool,
+    /// The statements executed when the case matches.
+    pub body: Block,
+}
+
+/// An exception handler in a [`Statement::Try`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatchClause {
+    /// The type of the exception to be handled, or [`None`] for any exception.
+    pub exception: Option<ClassRef>,
+    /// The program counter of the handler.
+    pub handler: ProgramCounter,
+    /// The statements of the handler.
+    pub body: Block,
+}
+
+/// A method whose body is decompiled into structured statements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecompiledMethod {
+    /// The access flags of the method.
+    pub access_flags: method::AccessFlags,
+    /// The class that contains the method.
+    pub owner: ClassRef,
+    /// The name of the method.
+    pub name: String,
+    /// The descriptor of the method.
+    pub descriptor: MethodDescriptor,
+    /// The body of the method.
+    pub body: Block,
+}
+
+impl Statement {
+    /// Checks if the control never flows to the statement after this one.
+    #[must_use]
+    pub fn is_jump(&self) -> bool {
+        matches!(
+            self,
+            Self::Return(_) | Self::Throw(_) | Self::Break(_) | Self::Continue(_) | Self::Goto(_)
+        )
+    }
+}
+
+const INDENT: &str = "    ";
+
+fn label(pc: ProgramCounter) -> String {
+    format!("L{:04X}", u16::from(pc))
+}
+
+fn write_block(f: &mut Formatter<'_>, block: &[Statement], depth: usize) -> fmt::Result {
+    block
+        .iter()
+        .try_for_each(|stmt| write_statement(f, stmt, depth))
+}
+
+fn write_statement(f: &mut Formatter<'_>, stmt: &Statement, depth: usize) -> fmt::Result {
+    let indent = INDENT.repeat(depth);
+    match stmt {
+        Statement::Definition { value, expr } => writeln!(f, "{indent}{value} = {expr};"),
+        Statement::Expression(expr) => writeln!(f, "{indent}{expr};"),
+        Statement::If {
+            condition,
+            then,
+            otherwise,
+        } => {
+            write!(f, "{indent}")?;
+            write_if(f, condition, then, otherwise, depth)
+        }
+        Statement::Loop {
+            label: pc,
+            condition,
+            body,
+        } => {
+            let condition = condition
+                .as_ref()
+                .map_or_else(|| "true".to_owned(), ToString::to_string);
+            writeln!(f, "{indent}{}: while ({condition}) {{", label(*pc))?;
+            write_block(f, body, depth + 1)?;
+            writeln!(f, "{indent}}}")
+        }
+        Statement::Switch { value, cases } => {
+            writeln!(f, "{indent}switch ({value}) {{")?;
+            let case_indent = INDENT.repeat(depth + 1);
+            for SwitchCase {
+                keys,
+                is_default,
+                body,
+            } in cases
+            {
+                for key in keys {
+                    writeln!(f, "{case_indent}case {key}:")?;
+                }
+                if *is_default {
+                    writeln!(f, "{case_indent}default:")?;
+                }
+                write_block(f, body, depth + 2)?;
+                if !body.last().is_some_and(Statement::is_jump) {
+                    writeln!(f, "{case_indent}{INDENT}break;")?;
+                }
+            }
+            writeln!(f, "{indent}}}")
+        }
+        Statement::Try { body, catches } => {
+            writeln!(f, "{indent}try {{")?;
+            write_block(f, body, depth + 1)?;
+            for CatchClause {
+                exception, body, ..
+            } in catches
+            {
+                let exception = exception
+                    .as_ref()
+                    .map_or_else(|| "any".to_owned(), |it| it.binary_name.replace('/', "."));
+                writeln!(f, "{indent}}} catch ({exception} %caught_exception) {{")?;
+                write_block(f, body, depth + 1)?;
+            }
+            writeln!(f, "{indent}}}")
+        }
+        Statement::Return(value) => match value {
+            Some(value) => writeln!(f, "{indent}return {value};"),
+            None => writeln!(f, "{indent}return;"),
+        },
+        Statement::Throw(value) => writeln!(f, "{indent}throw {value};"),
+        Statement::Break(pc) => writeln!(f, "{indent}break {};", label(*pc)),
+        Statement::Continue(pc) => writeln!(f, "{indent}continue {};", label(*pc)),
+        Statement::Label(pc) => writeln!(f, "{}:", label(*pc)),
+        Statement::Goto(pc) => writeln!(f, "{indent}goto {};", label(*pc)),
+        Statement::Unstructured(insn) => writeln!(f, "{indent}/* {insn} */"),
+    }
+}
+
+fn write_if(
+    f: &mut Formatter<'_>,
+    condition: &Condition,
+    then: &[Statement],
+    otherwise: &[Statement],
+    depth: usize,
+) -> fmt::Result {
+    let indent = INDENT.repeat(depth);
+    writeln!(f, "if ({condition}) {{")?;
+    write_block(f, then, depth + 1)?;
+    match otherwise {
+        [] => writeln!(f, "{indent}}}"),
+        [Statement::If {
+            condition,
+            then,
+            otherwise,
+        }] => {
+            write!(f, "{indent}}} else ")?;
+            write_if(f, condition, then, otherwise, depth)
+        }
+        _ => {
+            writeln!(f, "{indent}}} else {{")?;
+            write_block(f, otherwise, depth + 1)?;
+            writeln!(f, "{indent}}}")
+        }
+    }
+}
+
+impl Display for Statement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write_statement(f, self, 0)
+    }
+}
+
+impl Display for DecompiledMethod {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use method::AccessFlags;
+
+        let modifiers = [
+            (AccessFlags::PUBLIC, "public "),
+            (AccessFlags::PRIVATE, "private "),
+            (AccessFlags::PROTECTED, "protected "),
+            (AccessFlags::ABSTRACT, "abstract "),
+            (AccessFlags::STATIC, "static "),
+            (AccessFlags::FINAL, "final "),
+            (AccessFlags::SYNCHRONIZED, "synchronized "),
+            (AccessFlags::NATIVE, "native "),
+        ]
+        .into_iter()
+        .filter(|(flag, _)| self.access_flags.contains(*flag))
+        .map(|(_, modifier)| modifier)
+        .join("");
+        let return_type = match &self.descriptor.return_type {
+            ReturnType::Some(it) => it.qualified_name(),
+            ReturnType::Void => "void".to_owned(),
+        };
+        let parameters = self
+            .descriptor
+            .parameters_types
+            .iter()
+            .enumerate()
+            .map(|(idx, it)| format!("{} %arg{idx}", it.qualified_name()))
+            .join(", ");
+        writeln!(f, "{modifiers}{return_type} {}({parameters}) {{", self.name)?;
+        write_block(f, &self.body, 1)?;
+        writeln!(f, "}}")
+    }
+}
@@ -0,0 +1,112 @@
+//! Dominator computation on graphs whose nodes are indices.
+//! It implements the algorithm in [A Simple, Fast Dominance Algorithm](https://www.cs.tufts.edu/comp/150FP/archive/keith-cooper/dom14.pdf).
+
+/// Computes the immediate dominator of each node reachable from `entry`.
+/// `successors[n]` lists the successors of node `n`.
+/// The result is [`None`] for `entry` and for the nodes unreachable from it.
+pub(crate) fn immediate_dominators(successors: &[Vec<usize>], entry: usize) -> Vec<Option<usize>> {
+    let node_count = successors.len();
+    let post_order = post_order(successors, entry);
+    let mut rpo_number = vec![usize::MAX; node_count];
+    for (number, &node) in post_order.iter().rev().enumerate() {
+        rpo_number[node] = number;
+    }
+    let mut predecessors = vec![Vec::new(); node_count];
+    for &node in &post_order {
+        for &succ in &successors[node] {
+            predecessors[succ].push(node);
+        }
+    }
+
+    let mut idom = vec![None; node_count];
+    idom[entry] = Some(entry);
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in post_order.iter().rev().skip(1) {
+            let new_idom = predecessors[node]
+                .iter()
+                .copied()
+                .filter(|&pred| idom[pred].is_some())
+                .reduce(|lhs, rhs| intersect(&idom, &rpo_number, lhs, rhs));
+            if new_idom.is_some() && idom[node] != new_idom {
+                idom[node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idom[entry] = None;
+    idom
+}
+
+/// Checks if `dominator` dominates `node` according to the immediate dominators.
+pub(crate) fn dominates(idom: &[Option<usize>], dominator: usize, node: usize) -> bool {
+    let mut current = Some(node);
+    while let Some(it) = current {
+        if it == dominator {
+            return true;
+        }
+        current = idom[it];
+    }
+    false
+}
+
+fn intersect(idom: &[Option<usize>], rpo_number: &[usize], lhs: usize, rhs: usize) -> usize {
+    let (mut lhs, mut rhs) = (lhs, rhs);
+    while lhs != rhs {
+        while rpo_number[lhs] > rpo_number[rhs] {
+            lhs = idom[lhs].expect("Processed nodes must have a dominator");
+        }
+        while rpo_number[rhs] > rpo_number[lhs] {
+            rhs = idom[rhs].expect("Processed nodes must have a dominator");
+        }
+    }
+    lhs
+}
+
+fn post_order(successors: &[Vec<usize>], entry: usize) -> Vec<usize> {
+    let mut visited = vec![false; successors.len()];
+    let mut order = Vec::with_capacity(successors.len());
+    let mut stack = vec![(entry, 0)];
+    visited[entry] = true;
+    while let Some((node, next_idx)) = stack.last_mut() {
+        let node = *node;
+        let next = successors[node].get(*next_idx).copied();
+        *next_idx += 1;
+        match next {
+            Some(succ) if !visited[succ] => {
+                visited[succ] = true;
+                stack.push((succ, 0));
+            }
+            Some(_) => {}
+            None => {
+                order.push(node);
+                stack.pop();
+            }
+        }
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diamond() {
+        // 0 -> 1 -> 3, 0 -> 2 -> 3, 3 -> 4
+        let successors = vec![vec![1, 2], vec![3], vec![3], vec![4], vec![]];
+        let idom = immediate_dominators(&successors, 0);
+        assert_eq!(idom, vec![None, Some(0), Some(0), Some(0), Some(3)]);
+        assert!(dominates(&idom, 3, 4));
+        assert!(!dominates(&idom, 1, 3));
+    }
+
+    #[test]
+    fn loop_with_unreachable_node() {
+        // 0 -> 1 -> 2 -> 1, 2 -> 3; 4 is unreachable
+        let successors = vec![vec![1], vec![2], vec![1, 3], vec![], vec![3]];
+        let idom = immediate_dominators(&successors, 0);
+        assert_eq!(idom, vec![None, Some(0), Some(1), Some(2), None]);
+    }
+}
@@ -0,0 +1,856 @@
+//! Decompilation of Moka IR into structured Java-like code.
+//! The instructions are grouped into basic blocks, which are then structured into
+//! `if`/`else`, loops, `switch`, and `try`/`catch` statements using the dominator and
+//! post-dominator trees of the control flow graph.
+//! Control flow that cannot be structured falls back to labels and `goto`s.
+
+pub mod ast;
+pub(crate) mod dominators;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+};
+
+use crate::{
+    ir::{
+        control_flow::ControlTransfer,
+        expression::{ArrayOperation, Condition, Expression, FieldAccess},
+        MokaIRMethod, MokaInstruction,
+    },
+    jvm::{code::ProgramCounter, references::ClassRef},
+    types::method_descriptor::ReturnType,
+};
+
+use self::{
+    ast::{Block, CatchClause, DecompiledMethod, Statement, SwitchCase},
+    dominators::{dominates, immediate_dominators},
+};
+
+/// Decompiles the body of a method into structured statements.
+#[must_use]
+pub fn decompile(method: &MokaIRMethod) -> DecompiledMethod {
+    let body = if method.instructions.entry_point().is_some() {
+        Structurer::new(method).run()
+    } else {
+        Block::new()
+    };
+    DecompiledMethod {
+        access_flags: method.access_flags,
+        owner: method.owner.clone(),
+        name: method.name.clone(),
+        descriptor: method.descriptor.clone(),
+        body,
+    }
+}
+
+/// A maximal sequence of instructions that is entered only at the first one.
+#[derive(Debug)]
+struct BasicBlock<'a> {
+    start: ProgramCounter,
+    instructions: Vec<&'a MokaInstruction>,
+    /// The successors in the normal control flow.
+    successors: Vec<usize>,
+    /// The exception handlers of the instructions in the block.
+    handlers: BTreeSet<usize>,
+}
+
+#[derive(Debug)]
+struct NaturalLoop {
+    body: BTreeSet<usize>,
+    follow: Option<usize>,
+}
+
+#[derive(Debug)]
+struct TryRegion {
+    covered: RangeInclusive<ProgramCounter>,
+    handlers: Vec<(Option<ClassRef>, ProgramCounter)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ActiveLoop {
+    header: usize,
+    follow: Option<usize>,
+}
+
+/// The enclosing constructs of the statements being structured.
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    loops: Vec<ActiveLoop>,
+    /// The block where the enclosing construct continues.
+    stop: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Structurer<'a> {
+    blocks: Vec<BasicBlock<'a>>,
+    block_of: BTreeMap<ProgramCounter, usize>,
+    post_dominators: Vec<Option<usize>>,
+    loops: BTreeMap<usize, NaturalLoop>,
+    /// The try regions starting at each block, from the outermost to the innermost.
+    try_regions: BTreeMap<usize, Vec<TryRegion>>,
+    emitted: Vec<bool>,
+    goto_targets: BTreeSet<ProgramCounter>,
+}
+
+fn normal_successors(method: &MokaIRMethod, pc: ProgramCounter) -> Vec<ProgramCounter> {
+    method
+        .control_flow_graph
+        .edges_from(pc)
+        .into_iter()
+        .flatten()
+        .filter(|(_, _, transfer)| !matches!(transfer, ControlTransfer::Exception(_)))
+        .map(|(_, dst, _)| dst)
+        .collect()
+}
+
+fn exceptional_successors(method: &MokaIRMethod, pc: ProgramCounter) -> Vec<ProgramCounter> {
+    method
+        .control_flow_graph
+        .edges_from(pc)
+        .into_iter()
+        .flatten()
+        .filter(|(_, _, transfer)| matches!(transfer, ControlTransfer::Exception(_)))
+        .map(|(_, dst, _)| dst)
+        .collect()
+}
+
+fn leaders(method: &MokaIRMethod) -> BTreeSet<ProgramCounter> {
+    let instructions = &method.instructions;
+    let mut leaders = BTreeSet::new();
+    leaders.extend(instructions.entry_point().map(|(pc, _)| *pc));
+    for entry in &method.exception_table {
+        leaders.insert(entry.handler_pc);
+        leaders.insert(*entry.covered_pc.start());
+        leaders.extend(instructions.next_pc_of(entry.covered_pc.end()));
+    }
+    let mut predecessor_count: BTreeMap<ProgramCounter, usize> = BTreeMap::new();
+    for (pc, _) in instructions {
+        let successors = normal_successors(method, *pc);
+        let next_pc = instructions.next_pc_of(pc);
+        for &succ in &successors {
+            *predecessor_count.entry(succ).or_default() += 1;
+        }
+        if successors.len() != 1 || successors.first() != next_pc.as_ref() {
+            leaders.extend(successors);
+            leaders.extend(next_pc);
+        }
+    }
+    leaders.extend(
+        predecessor_count
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(pc, _)| pc),
+    );
+    leaders
+}
+
+impl<'a> Structurer<'a> {
+    fn new(method: &'a MokaIRMethod) -> Self {
+        let leaders = leaders(method);
+        let mut blocks: Vec<BasicBlock<'a>> = Vec::new();
+        let mut block_of = BTreeMap::new();
+        let mut last_pcs = Vec::new();
+        let mut handler_pcs: Vec<BTreeSet<ProgramCounter>> = Vec::new();
+        for (pc, insn) in &method.instructions {
+            if blocks.is_empty() || leaders.contains(pc) {
+                block_of.insert(*pc, blocks.len());
+                blocks.push(BasicBlock {
+                    start: *pc,
+                    instructions: Vec::new(),
+                    successors: Vec::new(),
+                    handlers: BTreeSet::new(),
+                });
+                last_pcs.push(*pc);
+                handler_pcs.push(BTreeSet::new());
+            }
+            let current = blocks.len() - 1;
+            blocks[current].instructions.push(insn);
+            last_pcs[current] = *pc;
+            handler_pcs[current].extend(exceptional_successors(method, *pc));
+        }
+        for (index, block) in blocks.iter_mut().enumerate() {
+            block.successors = normal_successors(method, last_pcs[index])
+                .into_iter()
+                .filter_map(|it| block_of.get(&it).copied())
+                .collect();
+            block.handlers = handler_pcs[index]
+                .iter()
+                .filter_map(|it| block_of.get(it).copied())
+                .collect();
+        }
+
+        let mut structurer = Self {
+            emitted: vec![false; blocks.len()],
+            post_dominators: Vec::new(),
+            loops: BTreeMap::new(),
+            try_regions: BTreeMap::new(),
+            goto_targets: BTreeSet::new(),
+            blocks,
+            block_of,
+        };
+        structurer.post_dominators = structurer.compute_post_dominators();
+        structurer.loops = structurer.find_loops();
+        structurer.try_regions = structurer.find_try_regions(method);
+        structurer
+    }
+
+    fn compute_post_dominators(&self) -> Vec<Option<usize>> {
+        let exit = self.blocks.len();
+        let mut reversed = vec![Vec::new(); exit + 1];
+        for (index, block) in self.blocks.iter().enumerate() {
+            if block.successors.is_empty() {
+                reversed[exit].push(index);
+            }
+            for &succ in &block.successors {
+                reversed[succ].push(index);
+            }
+        }
+        let mut post_dominators = immediate_dominators(&reversed, exit);
+        post_dominators.pop();
+        post_dominators
+            .into_iter()
+            .map(|it| it.filter(|&node| node != exit))
+            .collect()
+    }
+
+    fn find_loops(&self) -> BTreeMap<usize, NaturalLoop> {
+        let successors: Vec<Vec<usize>> = self
+            .blocks
+            .iter()
+            .map(|block| {
+                block
+                    .successors
+                    .iter()
+                    .chain(&block.handlers)
+                    .copied()
+                    .collect()
+            })
+            .collect();
+        let dominators = immediate_dominators(&successors, 0);
+        let mut predecessors = vec![Vec::new(); self.blocks.len()];
+        for (index, block) in self.blocks.iter().enumerate() {
+            for &succ in &block.successors {
+                predecessors[succ].push(index);
+            }
+        }
+        let mut bodies: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for (index, block) in self.blocks.iter().enumerate() {
+            for &header in &block.successors {
+                if !dominates(&dominators, header, index) {
+                    continue;
+                }
+                let body = bodies
+                    .entry(header)
+                    .or_insert_with(|| BTreeSet::from([header]));
+                let mut worklist = vec![index];
+                while let Some(node) = worklist.pop() {
+                    if body.insert(node) {
+                        worklist.extend(predecessors[node].iter().copied());
+                    }
+                }
+            }
+        }
+        bodies
+            .into_iter()
+            .map(|(header, body)| {
+                let exits: BTreeSet<usize> = body
+                    .iter()
+                    .flat_map(|&node| self.blocks[node].successors.iter().copied())
+                    .filter(|succ| !body.contains(succ))
+                    .collect();
+                let follow = self.blocks[header]
+                    .successors
+                    .iter()
+                    .copied()
+                    .find(|succ| exits.contains(succ))
+                    .or_else(|| exits.first().copied());
+                (header, NaturalLoop { body, follow })
+            })
+            .collect()
+    }
+
+    fn find_try_regions(&self, method: &MokaIRMethod) -> BTreeMap<usize, Vec<TryRegion>> {
+        let mut grouped: BTreeMap<(ProgramCounter, ProgramCounter), Vec<_>> = BTreeMap::new();
+        for entry in &method.exception_table {
+            grouped
+                .entry((*entry.covered_pc.start(), *entry.covered_pc.end()))
+                .or_default()
+                .push((entry.catch_type.clone(), entry.handler_pc));
+        }
+        let mut regions: BTreeMap<usize, Vec<TryRegion>> = BTreeMap::new();
+        for ((start, end), handlers) in grouped {
+            if let Some(&index) = self.block_of.get(&start) {
+                regions.entry(index).or_default().push(TryRegion {
+                    covered: start..=end,
+                    handlers,
+                });
+            }
+        }
+        for regions in regions.values_mut() {
+            regions.sort_by_key(|it| std::cmp::Reverse(*it.covered.end()));
+        }
+        regions
+    }
+
+    fn run(mut self) -> Block {
+        let mut body = self.structure(0, &Scope::default());
+        while let Some(index) = self.emitted.iter().position(|it| !it) {
+            self.goto_targets.insert(self.blocks[index].start);
+            body.extend(self.structure(index, &Scope::default()));
+        }
+        simplify(body, &self.goto_targets)
+    }
+
+    fn structure(&mut self, start: usize, scope: &Scope) -> Block {
+        let mut block = Block::new();
+        let mut current = Some(start);
+        while let Some(index) = current {
+            current = self.structure_step(index, scope, &mut block);
+        }
+        block
+    }
+
+    /// Structures the code starting at block `index` and returns the block to continue with.
+    fn structure_step(&mut self, index: usize, scope: &Scope, out: &mut Block) -> Option<usize> {
+        if scope.stop == Some(index) {
+            return None;
+        }
+        if let Some(active) = scope.loops.iter().rev().find(|it| it.follow == Some(index)) {
+            out.push(Statement::Break(self.blocks[active.header].start));
+            return None;
+        }
+        if self.emitted[index] {
+            if let Some(active) = scope.loops.iter().rev().find(|it| it.header == index) {
+                out.push(Statement::Continue(self.blocks[active.header].start));
+            } else {
+                let target = self.blocks[index].start;
+                self.goto_targets.insert(target);
+                out.push(Statement::Goto(target));
+            }
+            return None;
+        }
+        if let Some(region) = self.try_regions.get_mut(&index).and_then(Vec::pop) {
+            return self.structure_try(index, region, scope, out);
+        }
+        if self.loops.contains_key(&index) && scope.loops.iter().all(|it| it.header != index) {
+            return self.structure_loop(index, scope, out);
+        }
+
+        self.emitted[index] = true;
+        let block = &self.blocks[index];
+        out.push(Statement::Label(block.start));
+        let (last, init) = block.instructions.split_last()?;
+        out.extend(init.iter().filter_map(|insn| simple_statement(insn)));
+        let successors = block.successors.clone();
+        match last {
+            MokaInstruction::Jump {
+                condition: Some(condition),
+                target,
+            } => {
+                let target = self.block_of.get(target).copied()?;
+                let fallthrough = successors
+                    .iter()
+                    .copied()
+                    .find(|&it| it != target)
+                    .unwrap_or(target);
+                self.structure_if(index, condition, fallthrough, target, scope, out)
+            }
+            MokaInstruction::Switch {
+                match_value,
+                branches,
+                default,
+            } => {
+                let mut keys_of: BTreeMap<ProgramCounter, Vec<i32>> = BTreeMap::new();
+                for (key, target) in branches {
+                    keys_of.entry(*target).or_default().push(*key);
+                }
+                keys_of.entry(*default).or_default();
+                let merge = self.merge_point(index, scope);
+                let inner = Scope {
+                    stop: merge.or(scope.stop),
+                    ..scope.clone()
+                };
+                let mut cases = Vec::new();
+                for (target, keys) in keys_of {
+                    let Some(&target_index) = self.block_of.get(&target) else {
+                        continue;
+                    };
+                    cases.push(SwitchCase {
+                        keys,
+                        is_default: target == *default,
+                        body: self.structure(target_index, &inner),
+                    });
+                }
+                out.push(Statement::Switch {
+                    value: match_value.clone(),
+                    cases,
+                });
+                merge
+            }
+            MokaInstruction::Return(value) => {
+                out.push(Statement::Return(value.clone()));
+                None
+            }
+            MokaInstruction::SubroutineRet(_) => {
+                out.push(Statement::Unstructured((*last).clone()));
+                None
+            }
+            _ => {
+                out.extend(simple_statement(last));
+                successors.first().copied()
+            }
+        }
+    }
+
+    fn structure_if(
+        &mut self,
+        index: usize,
+        condition: &Condition,
+        fallthrough: usize,
+        target: usize,
+        scope: &Scope,
+        out: &mut Block,
+    ) -> Option<usize> {
+        let merge = self.merge_point(index, scope);
+        let inner = Scope {
+            stop: merge.or(scope.stop),
+            ..scope.clone()
+        };
+        // The fall-through branch comes first in the bytecode, so it is printed first.
+        let then = self.structure(fallthrough, &inner);
+        let otherwise = self.structure(target, &inner);
+        match (then.is_empty(), otherwise.is_empty()) {
+            (true, true) => {}
+            (true, false) => out.push(Statement::If {
+                condition: condition.clone(),
+                then: otherwise,
+                otherwise: then,
+            }),
+            _ => out.push(Statement::If {
+                condition: !condition.clone(),
+                then,
+                otherwise,
+            }),
+        }
+        merge
+    }
+
+    fn structure_loop(&mut self, header: usize, scope: &Scope, out: &mut Block) -> Option<usize> {
+        let follow = self.loops.get(&header).and_then(|it| it.follow);
+        let mut inner = scope.clone();
+        inner.stop = None;
+        inner.loops.push(ActiveLoop { header, follow });
+        let body = self.structure(header, &inner);
+        out.push(Statement::Loop {
+            label: self.blocks[header].start,
+            condition: None,
+            body,
+        });
+        follow
+    }
+
+    fn structure_try(
+        &mut self,
+        start: usize,
+        region: TryRegion,
+        scope: &Scope,
+        out: &mut Block,
+    ) -> Option<usize> {
+        let covered = &region.covered;
+        let follow = self
+            .blocks
+            .iter()
+            .filter(|block| covered.contains(&block.start))
+            .flat_map(|block| block.successors.iter().copied())
+            .filter(|&succ| self.blocks[succ].start > *covered.end())
+            .min();
+        let inner = Scope {
+            stop: follow.or(scope.stop),
+            ..scope.clone()
+        };
+        let body = self.structure(start, &inner);
+        let mut catches = Vec::with_capacity(region.handlers.len());
+        for (exception, handler) in region.handlers {
+            let body = match self.block_of.get(&handler) {
+                Some(&index) => self.structure(index, &inner),
+                None => Block::new(),
+            };
+            catches.push(CatchClause {
+                exception,
+                handler,
+                body,
+            });
+        }
+        out.push(Statement::Try { body, catches });
+        follow
+    }
+
+    /// Finds the block where the branches starting at block `index` merge.
+    /// It is the immediate post-dominator if there is one. Otherwise, it is the first block
+    /// reachable from all the branches, which happens when some of them leave the method.
+    fn merge_point(&self, index: usize, scope: &Scope) -> Option<usize> {
+        let merge = self.post_dominators[index].or_else(|| {
+            self.blocks[index]
+                .successors
+                .iter()
+                .map(|&succ| self.reachable(succ, scope))
+                .reduce(|lhs, rhs| &lhs & &rhs)
+                .and_then(|common| common.first().copied())
+        })?;
+        let in_loop = scope.loops.last().is_none_or(|active| {
+            self.loops
+                .get(&active.header)
+                .is_some_and(|it| it.body.contains(&merge))
+        });
+        (in_loop && !self.emitted[merge]).then_some(merge)
+    }
+
+    /// Finds the blocks reachable from `start` without leaving the innermost loop.
+    fn reachable(&self, start: usize, scope: &Scope) -> BTreeSet<usize> {
+        let active = scope.loops.last();
+        let mut reachable = BTreeSet::new();
+        let mut worklist = vec![start];
+        while let Some(node) = worklist.pop() {
+            let leaves_loop = active.is_some_and(|it| it.header == node || it.follow == Some(node));
+            if leaves_loop || self.emitted[node] || !reachable.insert(node) {
+                continue;
+            }
+            worklist.extend(self.blocks[node].successors.iter().copied());
+        }
+        reachable
+    }
+}
+
+fn produces_value(expr: &Expression) -> bool {
+    !matches!(
+        expr,
+        Expression::Call { method, .. } if method.descriptor.return_type == ReturnType::Void
+    ) && !matches!(
+        expr,
+        Expression::Field(FieldAccess::WriteStatic { .. } | FieldAccess::WriteInstance { .. })
+            | Expression::Array(ArrayOperation::Write { .. })
+            | Expression::Synchronization(_)
+    )
+}
+
+fn simple_statement(insn: &MokaInstruction) -> Option<Statement> {
+    match insn {
+        MokaInstruction::Nop | MokaInstruction::Jump { .. } => None,
+        MokaInstruction::Definition {
+            expr: Expression::Throw(exception),
+            ..
+        } => Some(Statement::Throw(exception.clone())),
+        MokaInstruction::Definition {
+            expr: Expression::Subroutine { .. },
+            ..
+        } => Some(Statement::Unstructured(insn.clone())),
+        MokaInstruction::Definition { value, expr } if produces_value(expr) => {
+            Some(Statement::Definition {
+                value: *value,
+                expr: expr.clone(),
+            })
+        }
+        MokaInstruction::Definition { expr, .. } => Some(Statement::Expression(expr.clone())),
+        _ => Some(Statement::Unstructured(insn.clone())),
+    }
+}
+
+/// Removes unused labels, flattens branches ending with jumps, and recovers `while` loops.
+fn simplify(block: Block, goto_targets: &BTreeSet<ProgramCounter>) -> Block {
+    let mut simplified = Block::with_capacity(block.len());
+    for stmt in block {
+        match stmt {
+            Statement::Label(pc) if !goto_targets.contains(&pc) => {}
+            Statement::If {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let then = simplify(then, goto_targets);
+                let otherwise = simplify(otherwise, goto_targets);
+                if then.last().is_some_and(Statement::is_jump) {
+                    simplified.push(Statement::If {
+                        condition,
+                        then,
+                        otherwise: Block::new(),
+                    });
+                    simplified.extend(otherwise);
+                } else {
+                    simplified.push(Statement::If {
+                        condition,
+                        then,
+                        otherwise,
+                    });
+                }
+            }
+            Statement::Loop {
+                label,
+                condition,
+                body,
+            } => simplified.push(recover_while(
+                label,
+                condition,
+                simplify(body, goto_targets),
+            )),
+            Statement::Switch { value, cases } => simplified.push(Statement::Switch {
+                value,
+                cases: cases
+                    .into_iter()
+                    .map(|case| SwitchCase {
+                        body: simplify(case.body, goto_targets),
+                        ..case
+                    })
+                    .collect(),
+            }),
+            Statement::Try { body, catches } => simplified.push(Statement::Try {
+                body: simplify(body, goto_targets),
+                catches: catches
+                    .into_iter()
+                    .map(|clause| CatchClause {
+                        body: simplify(clause.body, goto_targets),
+                        ..clause
+                    })
+                    .collect(),
+            }),
+            other => simplified.push(other),
+        }
+    }
+    simplified
+}
+
+/// Turns `while (true) { if (c) { ... } else { break; } }` into `while (c) { ... }`.
+fn recover_while(
+    label: ProgramCounter,
+    condition: Option<Condition>,
+    mut body: Block,
+) -> Statement {
+    let is_break =
+        |block: &Block| matches!(block.as_slice(), [Statement::Break(it)] if *it == label);
+    let mut condition = condition;
+    // Undoes the flattening of `if (c) { ...; continue; } break;`.
+    if let [Statement::If { otherwise, .. }, last @ Statement::Break(_)] = body.as_mut_slice() {
+        if otherwise.is_empty() && *last == Statement::Break(label) {
+            body.pop();
+            if let Some(Statement::If { otherwise, .. }) = body.last_mut() {
+                otherwise.push(Statement::Break(label));
+            }
+        }
+    }
+    if let (None, [Statement::If { .. }]) = (&condition, body.as_slice()) {
+        if let Some(Statement::If {
+            condition: branch_condition,
+            then,
+            otherwise,
+        }) = body.pop()
+        {
+            if is_break(&otherwise) {
+                condition = Some(branch_condition);
+                body = then;
+            } else if is_break(&then) {
+                condition = Some(!branch_condition);
+                body = otherwise;
+            } else {
+                body.push(Statement::If {
+                    condition: branch_condition,
+                    then,
+                    otherwise,
+                });
+            }
+        }
+    } else if let (
+        None,
+        [Statement::If {
+            then, otherwise, ..
+        }, ..],
+    ) = (&condition, body.as_slice())
+    {
+        // `if (c) { break; }` at the start of the loop after flattening.
+        if is_break(then) && otherwise.is_empty() {
+            if let Statement::If {
+                condition: branch_condition,
+                ..
+            } = body.remove(0)
+            {
+                condition = Some(!branch_condition);
+            }
+        }
+    }
+    strip_continue(&mut body, label);
+    Statement::Loop {
+        label,
+        condition,
+        body,
+    }
+}
+
+/// Removes the `continue` statements of the loop `label` at the end of its body.
+fn strip_continue(body: &mut Block, label: ProgramCounter) {
+    match body.last_mut() {
+        Some(Statement::Continue(it)) if *it == label => {
+            body.pop();
+        }
+        Some(Statement::If {
+            then, otherwise, ..
+        }) => {
+            strip_continue(then, label);
+            strip_continue(otherwise, label);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{MokaIRMethodExt, Operand},
+        jvm::{
+            code::{ExceptionTableEntry, Instruction, MethodBody},
+            method, Method,
+        },
+    };
+    use Instruction::{
+        AConstNull, AThrow, Goto, IConst0, IConst1, IConst2, IInc, ILoad0, ILoad1, IReturn,
+        IStore1, IfICmpGe, IfLe, Pop, Return,
+    };
+
+    fn decompile_method(
+        descriptor: &str,
+        instructions: Vec<(u16, Instruction)>,
+        exception_table: Vec<ExceptionTableEntry>,
+    ) -> DecompiledMethod {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: "test".to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 2,
+                max_locals: 2,
+                instructions: instructions
+                    .into_iter()
+                    .map(|(pc, insn)| (pc.into(), insn))
+                    .collect::<BTreeMap<_, _>>()
+                    .into(),
+                exception_table,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        decompile(&method.brew().unwrap())
+    }
+
+    #[test]
+    fn if_else() {
+        // if (a > 0) { a = 1; } else { a = 2; } return a;
+        let method = decompile_method(
+            "(I)I",
+            vec![
+                (0, ILoad0),
+                (1, IfLe(8.into())),
+                (4, IConst1),
+                (5, Goto(9.into())),
+                (8, IConst2),
+                (9, IReturn),
+            ],
+            Vec::new(),
+        );
+        let [Statement::If {
+            condition,
+            then,
+            otherwise,
+        }, Statement::Return(Some(_))] = method.body.as_slice()
+        else {
+            panic!("Unexpected body: {method}");
+        };
+        assert!(matches!(condition, Condition::IsPositive(_)));
+        assert!(matches!(then.as_slice(), [Statement::Definition { .. }]));
+        assert!(matches!(
+            otherwise.as_slice(),
+            [Statement::Definition { .. }]
+        ));
+        assert!(method.to_string().contains("} else {"));
+    }
+
+    #[test]
+    fn early_return() {
+        // if (a > 0) { return 1; } return 2;
+        let method = decompile_method(
+            "(I)I",
+            vec![
+                (0, ILoad0),
+                (1, IfLe(6.into())),
+                (4, IConst1),
+                (5, IReturn),
+                (6, IConst2),
+                (7, IReturn),
+            ],
+            Vec::new(),
+        );
+        let [Statement::If {
+            then, otherwise, ..
+        }, .., Statement::Return(Some(_))] = method.body.as_slice()
+        else {
+            panic!("Unexpected body: {method}");
+        };
+        assert!(matches!(then.last(), Some(Statement::Return(Some(_)))));
+        assert!(otherwise.is_empty());
+    }
+
+    #[test]
+    fn while_loop() {
+        // int i = 0; while (i < n) { i++; } return;
+        let method = decompile_method(
+            "(I)V",
+            vec![
+                (0, IConst0),
+                (1, IStore1),
+                (2, ILoad1),
+                (3, ILoad0),
+                (4, IfICmpGe(13.into())),
+                (7, IInc(1, 1)),
+                (10, Goto(2.into())),
+                (13, Return),
+            ],
+            Vec::new(),
+        );
+        let [.., Statement::Loop {
+            label,
+            condition: Some(Condition::LessThan(..)),
+            body,
+        }, Statement::Return(None)] = method.body.as_slice()
+        else {
+            panic!("Unexpected body: {method}");
+        };
+        assert_eq!(*label, ProgramCounter::from(2));
+        assert!(!body.iter().any(Statement::is_jump));
+        assert!(!method.to_string().contains("goto"));
+    }
+
+    #[test]
+    fn try_catch() {
+        // try { throw null; } catch (Throwable e) { return; }
+        let method = decompile_method(
+            "()V",
+            vec![(0, AConstNull), (1, AThrow), (2, Pop), (3, Return)],
+            vec![ExceptionTableEntry {
+                covered_pc: 0.into()..=1.into(),
+                handler_pc: 2.into(),
+                catch_type: Some(ClassRef::new("java/lang/Throwable")),
+            }],
+        );
+        let [Statement::Try { body, catches }] = method.body.as_slice() else {
+            panic!("Unexpected body: {method}");
+        };
+        assert!(matches!(
+            body.last(),
+            Some(Statement::Throw(Operand::Just(_)))
+        ));
+        assert_eq!(catches.len(), 1);
+        assert_eq!(catches[0].handler, ProgramCounter::from(2));
+        assert_eq!(catches[0].body.last(), Some(&Statement::Return(None)));
+        assert!(method
+            .to_string()
+            .contains("catch (java.lang.Throwable %caught_exception)"));
+    }
+}
@@ -0,0 +1,1006 @@
+//! Lowering of Dalvik bytecode into JVM instructions.
+//!
+//! Each Dalvik register is mapped to a local variable slot following the slots of the
+//! parameters, which are copied into the incoming registers by a prologue.
+//! Each Dalvik instruction is lowered into a sequence of instructions loading the source
+//! registers onto the operand stack, computing the result, and storing it into the destination
+//! register.
+//! Dalvik registers are untyped, so the types of the values in the registers are tracked along
+//! the instruction order, which matches the code emitted by `d8` and `dx`.
+//! The lowered code carries no stack map frames and is meant for analysis rather than execution.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    jvm::{
+        code::{
+            ExceptionTableEntry, Instruction, InstructionList, ProgramCounter, WideInstruction,
+        },
+        references::MethodRef,
+        ConstantValue, JavaString,
+    },
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::ReturnType,
+    },
+};
+
+use super::{binary_name, DexFile, Error, Reader};
+
+/// The method body lowered from a `code_item`.
+#[derive(Debug)]
+pub(super) struct LoweredBody {
+    pub max_stack: u16,
+    pub max_locals: u16,
+    pub instructions: InstructionList<Instruction>,
+    pub exception_table: Vec<ExceptionTableEntry>,
+}
+
+/// The computational type of a value in a register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Int,
+    Float,
+    Long,
+    Double,
+    Ref,
+}
+
+impl Kind {
+    fn of(field_type: &FieldType) -> Self {
+        match field_type {
+            FieldType::Base(PrimitiveType::Long) => Self::Long,
+            FieldType::Base(PrimitiveType::Double) => Self::Double,
+            FieldType::Base(PrimitiveType::Float) => Self::Float,
+            FieldType::Base(_) => Self::Int,
+            FieldType::Object(_) | FieldType::Array(_) => Self::Ref,
+        }
+    }
+
+    const fn width(self) -> u16 {
+        match self {
+            Self::Long | Self::Double => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// The value held by a register.
+#[derive(Debug, Clone, Copy)]
+enum Register {
+    /// A value of the given type.
+    Typed(Kind),
+    /// A 32-bit constant, which is also used for `float` and `null` constants.
+    Narrow(i32),
+    /// A 64-bit constant, which is also used for `double` constants.
+    Wide(i64),
+}
+
+/// A decoded Dalvik instruction.
+#[derive(Debug, Default)]
+struct Insn {
+    address: u32,
+    opcode: u8,
+    a: u32,
+    b: u32,
+    c: u32,
+    literal: i64,
+    arguments: Vec<u32>,
+}
+
+#[allow(clippy::too_many_lines)]
+pub(super) fn lower(
+    dex: &DexFile,
+    method: &MethodRef,
+    is_static: bool,
+    code_off: u32,
+) -> Result<LoweredBody, Error> {
+    let mut reader = Reader::new(&dex.data, code_off);
+    let registers_size = reader.u16()?;
+    let ins_size = reader.u16()?;
+    let _outs_size = reader.u16()?;
+    let tries_size = reader.u16()?;
+    let _debug_info_off = reader.u32()?;
+    let insns_size = reader.u32()?;
+    let units = (0..insns_size)
+        .map(|_| reader.u16())
+        .collect::<Result<Vec<_>, _>>()?;
+    if tries_size > 0 && insns_size % 2 == 1 {
+        reader.u16()?;
+    }
+    let tries = (0..tries_size)
+        .map(|_| Ok((reader.u32()?, reader.u16()?, reader.u16()?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let handlers_base = reader.clone();
+
+    let insns = decode(&units)?;
+    let indices: HashMap<u32, usize> = insns
+        .iter()
+        .enumerate()
+        .map(|(index, it)| (it.address, index))
+        .collect();
+    let mut lowering = Lowering {
+        dex,
+        insns_off: code_off + 16,
+        return_type: &method.descriptor.return_type,
+        insns: &insns,
+        indices: &indices,
+        base: ins_size,
+        registers: vec![Register::Typed(Kind::Int); usize::from(registers_size) + 1],
+        pending_result: None,
+        max_stack: 4,
+        code: Vec::new(),
+    };
+
+    // Copy the parameters into the incoming registers.
+    let mut prologue = Vec::new();
+    let first_in = u32::from(registers_size.saturating_sub(ins_size));
+    let receiver = (!is_static).then_some(Kind::Ref);
+    let kinds = receiver
+        .into_iter()
+        .chain(method.descriptor.parameters_types.iter().map(Kind::of));
+    let mut word = 0;
+    for kind in kinds {
+        lowering.code.push(load_local(kind, word));
+        lowering.store(first_in + u32::from(word), kind)?;
+        prologue.append(&mut lowering.code);
+        word += kind.width();
+    }
+
+    let mut groups = Vec::with_capacity(insns.len());
+    for index in 0..insns.len() {
+        lowering.lower(index)?;
+        if lowering.code.is_empty() {
+            lowering.code.push(Instruction::Nop);
+        }
+        groups.push(std::mem::take(&mut lowering.code));
+    }
+
+    // Exception handlers starting without `move-exception` discard the exception in a trampoline.
+    let mut handlers = Vec::new();
+    let mut trampolines = BTreeMap::new();
+    for (start_addr, insn_count, handler_off) in tries {
+        let mut reader = handlers_base.clone();
+        reader.position += usize::from(handler_off);
+        let size = reader.sleb128()?;
+        let mut catches = Vec::new();
+        for _ in 0..size.unsigned_abs() {
+            let type_idx = reader.uleb128()?;
+            catches.push((Some(dex.class_ref(type_idx)?), reader.uleb128()?));
+        }
+        if size <= 0 {
+            catches.push((None, reader.uleb128()?));
+        }
+        let start = lowering.index_of(i64::from(start_addr))?;
+        let end = match start_addr + u32::from(insn_count) {
+            end if end == insns_size => insns.len(),
+            end => lowering.index_of(i64::from(end))?,
+        };
+        for (catch_type, handler_addr) in catches {
+            let handler = lowering.index_of(i64::from(handler_addr))?;
+            if insns[handler].opcode != 0x0d {
+                trampolines.entry(handler).or_insert_with(|| {
+                    vec![Instruction::Pop, Instruction::Goto(pc_of_index(handler))]
+                });
+            }
+            handlers.push((start, end, handler, catch_type));
+        }
+    }
+
+    // Lay out the instructions, where the branch targets are the indices of the Dalvik
+    // instructions until they are retargeted.
+    let mut laid_out = BTreeMap::new();
+    let mut next_pc = 0u32;
+    let mut place = |group: Vec<Instruction>| -> Result<ProgramCounter, Error> {
+        let start = u16::try_from(next_pc).map_err(|_| Error::CodeTooLong)?;
+        for insn in group {
+            let pc = u16::try_from(next_pc).map_err(|_| Error::CodeTooLong)?;
+            next_pc += insn.encoded_size(pc.into());
+            laid_out.insert(ProgramCounter::from(pc), insn);
+        }
+        Ok(start.into())
+    };
+    place(prologue)?;
+    let mut pcs = groups
+        .into_iter()
+        .map(&mut place)
+        .collect::<Result<Vec<_>, _>>()?;
+    let end_pc = place(Vec::new())?;
+    let trampolines = trampolines
+        .into_iter()
+        .map(|(handler, code)| Ok((handler, place(code)?)))
+        .collect::<Result<HashMap<_, _>, Error>>()?;
+    pcs.push(end_pc);
+    let pc_mapping = pcs
+        .iter()
+        .enumerate()
+        .map(|(index, pc)| (pc_of_index(index), *pc))
+        .collect();
+    let instructions = laid_out
+        .into_iter()
+        .map(|(pc, mut insn)| {
+            insn.retarget(&pc_mapping);
+            if insn.fits_branch_offsets(pc) {
+                Ok((pc, insn))
+            } else {
+                Err(Error::CodeTooLong)
+            }
+        })
+        .collect::<Result<BTreeMap<_, _>, Error>>()?;
+
+    let exception_table = handlers
+        .into_iter()
+        .filter_map(|(start, end, handler, catch_type)| {
+            let (last_pc, _) = instructions.range(pcs[start]..pcs[end]).next_back()?;
+            Some(ExceptionTableEntry {
+                covered_pc: pcs[start]..=*last_pc,
+                handler_pc: trampolines.get(&handler).copied().unwrap_or(pcs[handler]),
+                catch_type,
+            })
+        })
+        .collect();
+    Ok(LoweredBody {
+        max_stack: lowering.max_stack,
+        max_locals: ins_size.saturating_add(registers_size),
+        instructions: instructions.into(),
+        exception_table,
+    })
+}
+
+/// Encodes the index of a Dalvik instruction as a placeholder branch target.
+fn pc_of_index(index: usize) -> ProgramCounter {
+    #[allow(clippy::cast_possible_truncation, reason = "Checked when decoding")]
+    ProgramCounter::from(index as u16)
+}
+
+fn load_local(kind: Kind, slot: u16) -> Instruction {
+    match (kind, u8::try_from(slot)) {
+        (Kind::Int, Ok(slot)) => Instruction::ILoad(slot),
+        (Kind::Float, Ok(slot)) => Instruction::FLoad(slot),
+        (Kind::Long, Ok(slot)) => Instruction::LLoad(slot),
+        (Kind::Double, Ok(slot)) => Instruction::DLoad(slot),
+        (Kind::Ref, Ok(slot)) => Instruction::ALoad(slot),
+        (Kind::Int, Err(_)) => Instruction::Wide(WideInstruction::ILoad(slot)),
+        (Kind::Float, Err(_)) => Instruction::Wide(WideInstruction::FLoad(slot)),
+        (Kind::Long, Err(_)) => Instruction::Wide(WideInstruction::LLoad(slot)),
+        (Kind::Double, Err(_)) => Instruction::Wide(WideInstruction::DLoad(slot)),
+        (Kind::Ref, Err(_)) => Instruction::Wide(WideInstruction::ALoad(slot)),
+    }
+}
+
+fn store_local(kind: Kind, slot: u16) -> Instruction {
+    match (kind, u8::try_from(slot)) {
+        (Kind::Int, Ok(slot)) => Instruction::IStore(slot),
+        (Kind::Float, Ok(slot)) => Instruction::FStore(slot),
+        (Kind::Long, Ok(slot)) => Instruction::LStore(slot),
+        (Kind::Double, Ok(slot)) => Instruction::DStore(slot),
+        (Kind::Ref, Ok(slot)) => Instruction::AStore(slot),
+        (Kind::Int, Err(_)) => Instruction::Wide(WideInstruction::IStore(slot)),
+        (Kind::Float, Err(_)) => Instruction::Wide(WideInstruction::FStore(slot)),
+        (Kind::Long, Err(_)) => Instruction::Wide(WideInstruction::LStore(slot)),
+        (Kind::Double, Err(_)) => Instruction::Wide(WideInstruction::DStore(slot)),
+        (Kind::Ref, Err(_)) => Instruction::Wide(WideInstruction::AStore(slot)),
+    }
+}
+
+/// Decodes the instructions in `units`, skipping the payloads of switches and array data.
+#[allow(
+    clippy::too_many_lines,
+    clippy::cast_possible_wrap,
+    clippy::cast_possible_truncation,
+    reason = "Reinterpreting the bits of signed literals is intended"
+)]
+fn decode(units: &[u16]) -> Result<Vec<Insn>, Error> {
+    let unit = |address: usize| units.get(address).copied().ok_or(Error::Truncated);
+    let mut insns = Vec::new();
+    let mut address = 0;
+    while address < units.len() {
+        let u0 = unit(address)?;
+        let payload_len = match u0 {
+            0x0100 => Some(4 + 2 * usize::from(unit(address + 1)?)),
+            0x0200 => Some(2 + 4 * usize::from(unit(address + 1)?)),
+            0x0300 => {
+                let width = usize::from(unit(address + 1)?);
+                let size = usize::from(unit(address + 2)?) | usize::from(unit(address + 3)?) << 16;
+                Some(4 + (width * size).div_ceil(2))
+            }
+            _ => None,
+        };
+        if let Some(len) = payload_len {
+            address += len;
+            continue;
+        }
+        let opcode = (u0 & 0xff) as u8;
+        let aa = u32::from(u0 >> 8);
+        let (nibble_a, nibble_b) = (aa & 0x0f, aa >> 4);
+        let literal = |count: usize| -> Result<u64, Error> {
+            (0..count).try_fold(0u64, |acc, it| {
+                Ok(acc | u64::from(unit(address + 1 + it)?) << (16 * it))
+            })
+        };
+        let mut insn = Insn {
+            address: u32::try_from(address).map_err(|_| Error::CodeTooLong)?,
+            opcode,
+            ..Insn::default()
+        };
+        let len = match opcode {
+            0x00 if aa != 0 => return Err(Error::UnknownOpcode(opcode)),
+            0x00 | 0x0e => 1,
+            // 12x
+            0x01 | 0x04 | 0x07 | 0x21 | 0x7b..=0x8f | 0xb0..=0xcf => {
+                (insn.a, insn.b) = (nibble_a, nibble_b);
+                1
+            }
+            // 11n
+            0x12 => {
+                insn.a = nibble_a;
+                insn.literal = i64::from(((nibble_b as u8) << 4) as i8 >> 4);
+                1
+            }
+            // 11x
+            0x0a..=0x11 | 0x1d | 0x1e | 0x27 => {
+                insn.a = aa;
+                1
+            }
+            // 10t
+            0x28 => {
+                insn.literal = i64::from(aa as u8 as i8);
+                1
+            }
+            // 20t
+            0x29 => {
+                insn.literal = i64::from(unit(address + 1)? as i16);
+                2
+            }
+            // 21t, 21s, 21h
+            0x13 | 0x15 | 0x16 | 0x19 | 0x38..=0x3d => {
+                insn.a = aa;
+                insn.literal = i64::from(unit(address + 1)? as i16);
+                2
+            }
+            // 22x, 21c
+            0x02 | 0x05 | 0x08 | 0x1a | 0x1c | 0x1f | 0x22 | 0x60..=0x6d | 0xfe | 0xff => {
+                (insn.a, insn.b) = (aa, u32::from(unit(address + 1)?));
+                2
+            }
+            // 23x, 22b
+            0x2d..=0x31 | 0x44..=0x51 | 0x90..=0xaf | 0xd8..=0xe2 => {
+                let u1 = unit(address + 1)?;
+                (insn.a, insn.b, insn.c) = (aa, u32::from(u1 & 0xff), u32::from(u1 >> 8));
+                insn.literal = i64::from((u1 >> 8) as u8 as i8);
+                2
+            }
+            // 22t, 22s, 22c
+            0x20 | 0x23 | 0x32..=0x37 | 0x52..=0x5f | 0xd0..=0xd7 => {
+                let u1 = unit(address + 1)?;
+                (insn.a, insn.b, insn.c) = (nibble_a, nibble_b, u32::from(u1));
+                insn.literal = i64::from(u1 as i16);
+                2
+            }
+            // 30t
+            0x2a => {
+                insn.literal = i64::from(literal(2)? as u32 as i32);
+                3
+            }
+            // 32x
+            0x03 | 0x06 | 0x09 => {
+                (insn.a, insn.b) = (u32::from(unit(address + 1)?), u32::from(unit(address + 2)?));
+                3
+            }
+            // 31i, 31t, 31c
+            0x14 | 0x17 | 0x1b | 0x26 | 0x2b | 0x2c => {
+                let value = literal(2)? as u32;
+                (insn.a, insn.b) = (aa, value);
+                insn.literal = i64::from(value as i32);
+                3
+            }
+            // 35c
+            0x24 | 0x6e..=0x72 | 0xfc => {
+                let args = u32::from(unit(address + 2)?);
+                insn.b = u32::from(unit(address + 1)?);
+                insn.arguments = [args & 0xf, (args >> 4) & 0xf, (args >> 8) & 0xf, args >> 12]
+                    .into_iter()
+                    .chain([nibble_a])
+                    .take(nibble_b as usize)
+                    .collect();
+                3
+            }
+            // 3rc
+            0x25 | 0x74..=0x78 | 0xfd => {
+                let first = u32::from(unit(address + 2)?);
+                insn.b = u32::from(unit(address + 1)?);
+                insn.arguments = (first..first + aa).collect();
+                3
+            }
+            // 45cc, 4rcc
+            0xfa | 0xfb => 4,
+            // 51l
+            0x18 => {
+                insn.a = aa;
+                insn.literal = literal(4)? as i64;
+                5
+            }
+            _ => return Err(Error::UnknownOpcode(opcode)),
+        };
+        insns.push(insn);
+        address += len;
+    }
+    if insns.len() > usize::from(u16::MAX) {
+        return Err(Error::CodeTooLong);
+    }
+    Ok(insns)
+}
+
+struct Lowering<'a> {
+    dex: &'a DexFile,
+    /// The offset of the instructions in the DEX file.
+    insns_off: u32,
+    return_type: &'a ReturnType,
+    insns: &'a [Insn],
+    indices: &'a HashMap<u32, usize>,
+    /// The slot of the first register.
+    base: u16,
+    registers: Vec<Register>,
+    /// The type of the result left on the operand stack for the next `move-result`.
+    pending_result: Option<Kind>,
+    max_stack: u16,
+    code: Vec<Instruction>,
+}
+
+impl<'a> Lowering<'a> {
+    fn index_of(&self, address: i64) -> Result<usize, Error> {
+        u32::try_from(address)
+            .ok()
+            .and_then(|it| self.indices.get(&it))
+            .copied()
+            .ok_or(Error::InvalidBranchTarget(address))
+    }
+
+    fn target(&self, insn: &Insn, offset: i64) -> Result<ProgramCounter, Error> {
+        self.index_of(i64::from(insn.address) + offset)
+            .map(pc_of_index)
+    }
+
+    fn slot(&self, register: u32) -> Result<u16, Error> {
+        u16::try_from(register)
+            .ok()
+            .filter(|it| usize::from(*it) < self.registers.len())
+            .and_then(|it| it.checked_add(self.base))
+            .ok_or(Error::InvalidIndex {
+                kind: "register",
+                index: register,
+            })
+    }
+
+    fn register(&self, register: u32) -> Register {
+        self.registers
+            .get(register as usize)
+            .copied()
+            .unwrap_or(Register::Typed(Kind::Int))
+    }
+
+    fn is_ref(&self, register: u32) -> bool {
+        matches!(self.register(register), Register::Typed(Kind::Ref))
+    }
+
+    fn emit(&mut self, insn: Instruction) {
+        self.code.push(insn);
+    }
+
+    fn load(&mut self, register: u32, kind: Kind) -> Result<(), Error> {
+        let slot = self.slot(register)?;
+        let insn = match (kind, self.register(register)) {
+            (Kind::Ref, Register::Narrow(0)) => Instruction::AConstNull,
+            #[allow(clippy::cast_sign_loss, reason = "Reinterpreting the bits is intended")]
+            (Kind::Float, Register::Narrow(bits)) => {
+                Instruction::LdcW(ConstantValue::Float(f32::from_bits(bits as u32)))
+            }
+            #[allow(clippy::cast_sign_loss, reason = "Reinterpreting the bits is intended")]
+            (Kind::Double, Register::Wide(bits)) => {
+                Instruction::Ldc2W(ConstantValue::Double(f64::from_bits(bits as u64)))
+            }
+            _ => load_local(kind, slot),
+        };
+        self.emit(insn);
+        Ok(())
+    }
+
+    fn store(&mut self, register: u32, kind: Kind) -> Result<(), Error> {
+        let slot = self.slot(register)?;
+        self.emit(store_local(kind, slot));
+        self.registers[register as usize] = Register::Typed(kind);
+        Ok(())
+    }
+
+    fn push_int(&mut self, value: i32) {
+        #[allow(
+            clippy::cast_sign_loss,
+            clippy::cast_possible_truncation,
+            reason = "The operands are sign-extended by the JVM"
+        )]
+        let insn = match value {
+            -1 => Instruction::IConstM1,
+            0 => Instruction::IConst0,
+            1 => Instruction::IConst1,
+            2 => Instruction::IConst2,
+            3 => Instruction::IConst3,
+            4 => Instruction::IConst4,
+            5 => Instruction::IConst5,
+            value if i8::try_from(value).is_ok() => Instruction::BiPush(value as u8),
+            value if i16::try_from(value).is_ok() => Instruction::SiPush(value as u16),
+            value => Instruction::LdcW(ConstantValue::Integer(value)),
+        };
+        self.emit(insn);
+    }
+
+    fn push_long(&mut self, value: i64) {
+        self.emit(match value {
+            0 => Instruction::LConst0,
+            1 => Instruction::LConst1,
+            value => Instruction::Ldc2W(ConstantValue::Long(value)),
+        });
+    }
+
+    /// Leaves the result of an invocation on the operand stack if it is consumed by the next
+    /// instruction, or discards it otherwise.
+    fn result(&mut self, index: usize, kind: Option<Kind>) {
+        let Some(kind) = kind else { return };
+        let next = self.insns.get(index + 1).map(|it| it.opcode);
+        if matches!(next, Some(0x0a..=0x0c)) {
+            self.pending_result = Some(kind);
+        } else {
+            self.emit(if kind.width() == 2 {
+                Instruction::Pop2
+            } else {
+                Instruction::Pop
+            });
+        }
+    }
+
+    fn new_array(&mut self, array_type: u32) -> Result<FieldType, Error> {
+        let FieldType::Array(element_type) = self.dex.field_type(array_type)? else {
+            let descriptor = self.dex.type_descriptor(array_type)?;
+            return Err(Error::InvalidDescriptor(descriptor.to_owned()));
+        };
+        let element_type = *element_type;
+        self.emit(match &element_type {
+            FieldType::Base(primitive) => Instruction::NewArray(*primitive),
+            element_type => {
+                let descriptor = element_type.descriptor();
+                let class_name = binary_name(&descriptor).unwrap_or(&descriptor).to_owned();
+                Instruction::ANewArray(crate::jvm::references::ClassRef::new(class_name))
+            }
+        });
+        Ok(element_type)
+    }
+
+    /// Gets a reader over the payload referred to by `insn`, skipping its identifier.
+    fn payload(&self, insn: &Insn) -> Result<Reader<'a>, Error> {
+        let offset = (i64::from(insn.address) + insn.literal)
+            .checked_mul(2)
+            .and_then(|it| it.checked_add(i64::from(self.insns_off) + 2))
+            .and_then(|it| u32::try_from(it).ok())
+            .ok_or(Error::InvalidBranchTarget(insn.literal))?;
+        Ok(Reader::new(&self.dex.data, offset))
+    }
+
+    #[allow(
+        clippy::too_many_lines,
+        clippy::cast_possible_truncation,
+        reason = "There are too many opcodes"
+    )]
+    fn lower(&mut self, index: usize) -> Result<(), Error> {
+        #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+        use Instruction::*;
+
+        let insns = self.insns;
+        let insn = &insns[index];
+        let (a, b, c) = (insn.a, insn.b, insn.c);
+        match insn.opcode {
+            0x00 => self.emit(Nop),
+            // move, move-wide
+            0x01..=0x06 => {
+                let kind = match self.register(b) {
+                    Register::Typed(kind) => kind,
+                    Register::Narrow(_) => Kind::Int,
+                    Register::Wide(_) => Kind::Long,
+                };
+                let kind = match (insn.opcode >= 0x04, kind) {
+                    (false, Kind::Float) | (true, Kind::Double) => kind,
+                    (false, _) => Kind::Int,
+                    (true, _) => Kind::Long,
+                };
+                let value = self.register(b);
+                self.emit(load_local(kind, self.slot(b)?));
+                self.store(a, kind)?;
+                if matches!(value, Register::Narrow(_) | Register::Wide(_)) {
+                    self.registers[a as usize] = value;
+                }
+            }
+            // move-object
+            0x07..=0x09 => {
+                self.load(b, Kind::Ref)?;
+                self.store(a, Kind::Ref)?;
+            }
+            // move-result, move-result-wide, move-result-object
+            0x0a..=0x0c => {
+                let kind = self.pending_result.take().unwrap_or(match insn.opcode {
+                    0x0a => Kind::Int,
+                    0x0b => Kind::Long,
+                    _ => Kind::Ref,
+                });
+                self.store(a, kind)?;
+            }
+            // move-exception
+            0x0d => self.store(a, Kind::Ref)?,
+            0x0e => self.emit(Return),
+            // return, return-wide, return-object
+            0x0f..=0x11 => {
+                let kind = match self.return_type {
+                    ReturnType::Some(it) => Kind::of(it),
+                    ReturnType::Void => Kind::Int,
+                };
+                self.load(a, kind)?;
+                self.emit(match kind {
+                    Kind::Int => IReturn,
+                    Kind::Float => FReturn,
+                    Kind::Long => LReturn,
+                    Kind::Double => DReturn,
+                    Kind::Ref => AReturn,
+                });
+            }
+            // const/4, const/16, const, const/high16
+            0x12..=0x15 => {
+                let value = if insn.opcode == 0x15 {
+                    (insn.literal << 16) as i32
+                } else {
+                    insn.literal as i32
+                };
+                self.push_int(value);
+                self.store(a, Kind::Int)?;
+                self.registers[a as usize] = Register::Narrow(value);
+            }
+            // const-wide/16, const-wide/32, const-wide, const-wide/high16
+            0x16..=0x19 => {
+                let value = if insn.opcode == 0x19 {
+                    insn.literal << 48
+                } else {
+                    insn.literal
+                };
+                self.push_long(value);
+                self.store(a, Kind::Long)?;
+                self.registers[a as usize] = Register::Wide(value);
+            }
+            // const-string, const-string/jumbo
+            0x1a | 0x1b => {
+                let string = self.dex.string(b)?.to_owned();
+                self.emit(LdcW(ConstantValue::String(JavaString::Utf8(string))));
+                self.store(a, Kind::Ref)?;
+            }
+            0x1c => {
+                self.emit(LdcW(ConstantValue::Class(self.dex.class_ref(b)?)));
+                self.store(a, Kind::Ref)?;
+            }
+            0x1d | 0x1e => {
+                self.load(a, Kind::Ref)?;
+                self.emit(if insn.opcode == 0x1d {
+                    MonitorEnter
+                } else {
+                    MonitorExit
+                });
+            }
+            0x1f => {
+                self.load(a, Kind::Ref)?;
+                self.emit(CheckCast(self.dex.field_type(b)?));
+                self.store(a, Kind::Ref)?;
+            }
+            0x20 => {
+                self.load(b, Kind::Ref)?;
+                self.emit(InstanceOf(self.dex.field_type(c)?));
+                self.store(a, Kind::Int)?;
+            }
+            0x21 => {
+                self.load(b, Kind::Ref)?;
+                self.emit(ArrayLength);
+                self.store(a, Kind::Int)?;
+            }
+            0x22 => {
+                self.emit(New(self.dex.class_ref(b)?));
+                self.store(a, Kind::Ref)?;
+            }
+            0x23 => {
+                self.load(b, Kind::Int)?;
+                self.new_array(c)?;
+                self.store(a, Kind::Ref)?;
+            }
+            // filled-new-array, filled-new-array/range
+            0x24 | 0x25 => {
+                let count = i32::try_from(insn.arguments.len()).unwrap_or(i32::MAX);
+                self.push_int(count);
+                let element_type = self.new_array(b)?;
+                let kind = Kind::of(&element_type);
+                for (position, argument) in (0..).zip(&insn.arguments) {
+                    self.emit(Dup);
+                    self.push_int(position);
+                    self.load(*argument, kind)?;
+                    self.emit(if kind == Kind::Ref { AAStore } else { IAStore });
+                }
+                self.result(index, Some(Kind::Ref));
+            }
+            // fill-array-data
+            0x26 => {
+                let mut payload = self.payload(insn)?;
+                let width = payload.u16()?;
+                let size = payload.u32()?;
+                for position in 0..size {
+                    self.load(a, Kind::Ref)?;
+                    self.push_int(i32::try_from(position).map_err(|_| Error::CodeTooLong)?);
+                    let value = payload.signed(usize::from(width))?;
+                    let store = match width {
+                        1 => BAStore,
+                        2 => SAStore,
+                        8 => LAStore,
+                        _ => IAStore,
+                    };
+                    if width == 8 {
+                        self.push_long(value);
+                    } else {
+                        self.push_int(value as i32);
+                    }
+                    self.emit(store);
+                }
+            }
+            0x27 => {
+                self.load(a, Kind::Ref)?;
+                self.emit(AThrow);
+            }
+            // goto, goto/16, goto/32
+            0x28..=0x2a => self.emit(Goto(self.target(insn, insn.literal)?)),
+            // packed-switch, sparse-switch
+            0x2b | 0x2c => {
+                self.load(a, Kind::Int)?;
+                let mut payload = self.payload(insn)?;
+                let size = payload.u16()?;
+                let default = pc_of_index(index + 1);
+                let keys = if insn.opcode == 0x2b {
+                    let first_key = payload.signed(4)? as i32;
+                    (0..i32::from(size))
+                        .map(|it| first_key.wrapping_add(it))
+                        .collect::<Vec<_>>()
+                } else {
+                    (0..size)
+                        .map(|_| payload.signed(4).map(|it| it as i32))
+                        .collect::<Result<_, _>>()?
+                };
+                let mut match_targets = BTreeMap::new();
+                for key in keys {
+                    let offset = payload.signed(4)?;
+                    match_targets.insert(key, self.target(insn, offset)?);
+                }
+                let first = match_targets.first_key_value().map(|(key, _)| *key);
+                let last = match_targets.last_key_value().map(|(key, _)| *key);
+                self.emit(match (insn.opcode, first, last) {
+                    (0x2b, Some(first), Some(last)) => TableSwitch {
+                        range: first..=last,
+                        jump_targets: match_targets.into_values().collect(),
+                        default,
+                    },
+                    _ => LookupSwitch {
+                        default,
+                        match_targets,
+                    },
+                });
+            }
+            // cmpl-float, cmpg-float, cmpl-double, cmpg-double, cmp-long
+            0x2d..=0x31 => {
+                let (kind, compare) = match insn.opcode {
+                    0x2d => (Kind::Float, FCmpL),
+                    0x2e => (Kind::Float, FCmpG),
+                    0x2f => (Kind::Double, DCmpL),
+                    0x30 => (Kind::Double, DCmpG),
+                    _ => (Kind::Long, LCmp),
+                };
+                self.load(b, kind)?;
+                self.load(c, kind)?;
+                self.emit(compare);
+                self.store(a, Kind::Int)?;
+            }
+            // if-eq, if-ne, if-lt, if-ge, if-gt, if-le
+            0x32..=0x37 => {
+                let target = self.target(insn, insn.literal)?;
+                let is_ref = insn.opcode <= 0x33 && (self.is_ref(a) || self.is_ref(b));
+                let kind = if is_ref { Kind::Ref } else { Kind::Int };
+                self.load(a, kind)?;
+                self.load(b, kind)?;
+                self.emit(match (insn.opcode, is_ref) {
+                    (0x32, true) => IfACmpEq(target),
+                    (0x33, true) => IfACmpNe(target),
+                    (0x32, _) => IfICmpEq(target),
+                    (0x33, _) => IfICmpNe(target),
+                    (0x34, _) => IfICmpLt(target),
+                    (0x35, _) => IfICmpGe(target),
+                    (0x36, _) => IfICmpGt(target),
+                    _ => IfICmpLe(target),
+                });
+            }
+            // if-eqz, if-nez, if-ltz, if-gez, if-gtz, if-lez
+            0x38..=0x3d => {
+                let target = self.target(insn, insn.literal)?;
+                let is_ref = insn.opcode <= 0x39 && self.is_ref(a);
+                self.load(a, if is_ref { Kind::Ref } else { Kind::Int })?;
+                self.emit(match (insn.opcode, is_ref) {
+                    (0x38, true) => IfNull(target),
+                    (0x39, true) => IfNonNull(target),
+                    (0x38, _) => IfEq(target),
+                    (0x39, _) => IfNe(target),
+                    (0x3a, _) => IfLt(target),
+                    (0x3b, _) => IfGe(target),
+                    (0x3c, _) => IfGt(target),
+                    _ => IfLe(target),
+                });
+            }
+            // aget-*, aput-*
+            0x44..=0x51 => {
+                let (kind, get, put) = match (insn.opcode - 0x44) % 7 {
+                    0 => (Kind::Int, IALoad, IAStore),
+                    1 => (Kind::Long, LALoad, LAStore),
+                    2 => (Kind::Ref, AALoad, AAStore),
+                    3 | 4 => (Kind::Int, BALoad, BAStore),
+                    5 => (Kind::Int, CALoad, CAStore),
+                    _ => (Kind::Int, SALoad, SAStore),
+                };
+                self.load(b, Kind::Ref)?;
+                self.load(c, Kind::Int)?;
+                if insn.opcode < 0x4b {
+                    self.emit(get);
+                    self.store(a, kind)?;
+                } else {
+                    self.load(a, kind)?;
+                    self.emit(put);
+                }
+            }
+            // iget-*, iput-*, sget-*, sput-*
+            0x52..=0x6d => {
+                let is_static = insn.opcode >= 0x60;
+                let is_get = (insn.opcode - if is_static { 0x60 } else { 0x52 }) < 7;
+                let field = self.dex.field(if is_static { b } else { c })?.clone();
+                let kind = Kind::of(&field.field_type);
+                if !is_static {
+                    self.load(b, Kind::Ref)?;
+                }
+                if is_get {
+                    self.emit(if is_static {
+                        GetStatic(field)
+                    } else {
+                        GetField(field)
+                    });
+                    self.store(a, kind)?;
+                } else {
+                    self.load(a, kind)?;
+                    self.emit(if is_static {
+                        PutStatic(field)
+                    } else {
+                        PutField(field)
+                    });
+                }
+            }
+            // invoke-*, invoke-*/range
+            0x6e..=0x72 | 0x74..=0x78 => {
+                let method = self.dex.method(b)?.clone();
+                let invoke_kind = (insn.opcode - 0x6e) % 6;
+                let receiver = (invoke_kind != 3).then_some(Kind::Ref);
+                let kinds = receiver
+                    .into_iter()
+                    .chain(method.descriptor.parameters_types.iter().map(Kind::of));
+                let mut words = 0u16;
+                for kind in kinds {
+                    let register = insn.arguments.get(usize::from(words)).copied().ok_or(
+                        Error::InvalidIndex {
+                            kind: "register",
+                            index: u32::from(words),
+                        },
+                    )?;
+                    self.load(register, kind)?;
+                    words += kind.width();
+                }
+                self.max_stack = self.max_stack.max(words);
+                let result = match &method.descriptor.return_type {
+                    ReturnType::Some(it) => Some(Kind::of(it)),
+                    ReturnType::Void => None,
+                };
+                self.emit(match invoke_kind {
+                    0 => InvokeVirtual(method),
+                    1 | 2 => InvokeSpecial(method),
+                    3 => InvokeStatic(method),
+                    _ => InvokeInterface(method, u8::try_from(words).unwrap_or(u8::MAX)),
+                });
+                self.result(index, result);
+            }
+            // neg-*, not-*, and conversions
+            0x7b..=0x8f => {
+                let (from, to, ops): (_, _, &[Instruction]) = match insn.opcode {
+                    0x7b => (Kind::Int, Kind::Int, &[INeg]),
+                    0x7c => (Kind::Int, Kind::Int, &[IConstM1, IXor]),
+                    0x7d => (Kind::Long, Kind::Long, &[LNeg]),
+                    0x7e => (
+                        Kind::Long,
+                        Kind::Long,
+                        &[Ldc2W(ConstantValue::Long(-1)), LXor],
+                    ),
+                    0x7f => (Kind::Float, Kind::Float, &[FNeg]),
+                    0x80 => (Kind::Double, Kind::Double, &[DNeg]),
+                    0x81 => (Kind::Int, Kind::Long, &[I2L]),
+                    0x82 => (Kind::Int, Kind::Float, &[I2F]),
+                    0x83 => (Kind::Int, Kind::Double, &[I2D]),
+                    0x84 => (Kind::Long, Kind::Int, &[L2I]),
+                    0x85 => (Kind::Long, Kind::Float, &[L2F]),
+                    0x86 => (Kind::Long, Kind::Double, &[L2D]),
+                    0x87 => (Kind::Float, Kind::Int, &[F2I]),
+                    0x88 => (Kind::Float, Kind::Long, &[F2L]),
+                    0x89 => (Kind::Float, Kind::Double, &[F2D]),
+                    0x8a => (Kind::Double, Kind::Int, &[D2I]),
+                    0x8b => (Kind::Double, Kind::Long, &[D2L]),
+                    0x8c => (Kind::Double, Kind::Float, &[D2F]),
+                    0x8d => (Kind::Int, Kind::Int, &[I2B]),
+                    0x8e => (Kind::Int, Kind::Int, &[I2C]),
+                    _ => (Kind::Int, Kind::Int, &[I2S]),
+                };
+                self.load(b, from)?;
+                self.code.extend_from_slice(ops);
+                self.store(a, to)?;
+            }
+            // binop, binop/2addr
+            0x90..=0xcf => {
+                let op = insn.opcode - if insn.opcode >= 0xb0 { 0xb0 } else { 0x90 };
+                let (lhs, rhs) = if insn.opcode >= 0xb0 { (a, b) } else { (b, c) };
+                let (kind, op) = binary_operation(op);
+                let is_shift = matches!(op, LShl | LShr | LUShr);
+                self.load(lhs, kind)?;
+                self.load(rhs, if is_shift { Kind::Int } else { kind })?;
+                self.emit(op);
+                self.store(a, kind)?;
+            }
+            // binop/lit16, binop/lit8
+            0xd0..=0xe2 => {
+                let op = insn.opcode - if insn.opcode >= 0xd8 { 0xd8 } else { 0xd0 };
+                let literal = insn.literal as i32;
+                if op == 1 {
+                    // rsub-int
+                    self.push_int(literal);
+                    self.load(b, Kind::Int)?;
+                    self.emit(ISub);
+                } else {
+                    self.load(b, Kind::Int)?;
+                    self.push_int(literal);
+                    self.emit(binary_operation(op).1);
+                }
+                self.store(a, Kind::Int)?;
+            }
+            0xfa..=0xff => return Err(Error::UnsupportedInstruction(insn.opcode)),
+            opcode => return Err(Error::UnknownOpcode(opcode)),
+        }
+        Ok(())
+    }
+}
+
+/// Gets the operation of a `binop` by its offset from `add-int`.
+fn binary_operation(op: u8) -> (Kind, Instruction) {
+    #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+    use Instruction::*;
+
+    const INT: [Instruction; 11] = [
+        IAdd, ISub, IMul, IDiv, IRem, IAnd, IOr, IXor, IShl, IShr, IUShr,
+    ];
+    const LONG: [Instruction; 11] = [
+        LAdd, LSub, LMul, LDiv, LRem, LAnd, LOr, LXor, LShl, LShr, LUShr,
+    ];
+    const FLOAT: [Instruction; 5] = [FAdd, FSub, FMul, FDiv, FRem];
+    const DOUBLE: [Instruction; 5] = [DAdd, DSub, DMul, DDiv, DRem];
+    let op = usize::from(op);
+    match op {
+        0..=10 => (Kind::Int, INT[op].clone()),
+        11..=21 => (Kind::Long, LONG[op - 11].clone()),
+        22..=26 => (Kind::Float, FLOAT[op - 22].clone()),
+        _ => (Kind::Double, DOUBLE[(op - 27).min(4)].clone()),
+    }
+}
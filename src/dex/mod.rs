@@ -0,0 +1,902 @@
+//! A front end for Android DEX files.
+//!
+//! A [`DexFile`] holds the classes of a `.dex` file (or of each `classes*.dex` in an APK).
+//! The classes are lowered into the [`Class`] model of this crate, where the Dalvik bytecode of
+//! the methods is translated into JVM [`Instruction`](crate::jvm::code::Instruction)s.
+//! Hence, the same analyses, including the Moka IR generation, apply to both JVM and Android
+//! inputs.
+
+use std::borrow::Cow;
+
+use crate::{
+    jvm::{
+        builder::{self, ClassBuilder, FieldBuilder, MethodBuilder},
+        class::{self, Version},
+        class_loader::{self, ClassPath},
+        field, method,
+        references::{ClassRef, FieldRef, MethodRef},
+        Class, ConstantValue, JavaString,
+    },
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+mod lowering;
+
+const NO_INDEX: u32 = u32::MAX;
+const ACC_CONSTRUCTOR: u32 = 0x1_0000;
+const ACC_DECLARED_SYNCHRONIZED: u32 = 0x2_0000;
+
+/// An error when reading a DEX file.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The data ends before a structure is complete.
+    #[error("Unexpected end of data")]
+    Truncated,
+    /// The file does not start with the DEX magic number.
+    #[error("Not a DEX file")]
+    BadMagic,
+    /// An index does not point to an item in the corresponding section.
+    #[error("Invalid {kind} index {index}")]
+    InvalidIndex {
+        /// The section indexed, e.g., `string` or `type`.
+        kind: &'static str,
+        /// The index.
+        index: u32,
+    },
+    /// A type descriptor is malformed.
+    #[error("Invalid descriptor `{0}`")]
+    InvalidDescriptor(String),
+    /// The opcode is not defined by the Dalvik instruction set.
+    #[error("Unknown opcode {0:#04x}")]
+    UnknownOpcode(u8),
+    /// The instruction cannot be expressed as JVM instructions (e.g., `invoke-polymorphic`).
+    #[error("Unsupported instruction {0:#04x}")]
+    UnsupportedInstruction(u8),
+    /// A branch or a switch refers to an address without instruction.
+    #[error("Invalid branch target {0:#x}")]
+    InvalidBranchTarget(i64),
+    /// The lowered method exceeds the size limit of JVM methods.
+    #[error("The method is too large to be lowered")]
+    CodeTooLong,
+    /// The lowered class violates the constraints of the class model.
+    #[error("Invalid class: {0}")]
+    Build(#[from] builder::Error),
+    /// An error when reading the APK archive.
+    #[error("Failed to read the archive: {0}")]
+    Archive(Box<dyn std::error::Error + Send + Sync>),
+}
+
+/// A parsed DEX file.
+#[derive(Debug, Clone)]
+pub struct DexFile {
+    version: u16,
+    data: Vec<u8>,
+    strings: Vec<String>,
+    types: Vec<String>,
+    protos: Vec<MethodDescriptor>,
+    fields: Vec<FieldRef>,
+    methods: Vec<MethodRef>,
+    class_defs: Vec<ClassDef>,
+}
+
+#[derive(Debug, Clone)]
+struct ClassDef {
+    class_idx: u32,
+    access_flags: u32,
+    superclass_idx: u32,
+    interfaces_off: u32,
+    source_file_idx: u32,
+    class_data_off: u32,
+    static_values_off: u32,
+}
+
+impl DexFile {
+    /// Parses a DEX file.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> Result<Self, Error> {
+        let data = data.into();
+        let mut header = Reader::new(&data, 0);
+        let magic = header.bytes(8)?;
+        let version = match magic {
+            [b'd', b'e', b'x', b'\n', digits @ .., 0] if digits.iter().all(u8::is_ascii_digit) => {
+                digits
+                    .iter()
+                    .fold(0u16, |acc, it| acc * 10 + u16::from(it - b'0'))
+            }
+            _ => return Err(Error::BadMagic),
+        };
+        let mut header = Reader::new(&data, 0x38);
+        let [string_ids_size, string_ids_off, type_ids_size, type_ids_off] = header.u32s()?;
+        let [proto_ids_size, proto_ids_off, field_ids_size, field_ids_off] = header.u32s()?;
+        let [method_ids_size, method_ids_off, class_defs_size, class_defs_off] = header.u32s()?;
+
+        let mut dex = Self {
+            version,
+            data: Vec::new(),
+            strings: Vec::new(),
+            types: Vec::new(),
+            protos: Vec::new(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+            class_defs: Vec::new(),
+        };
+        let mut ids = Reader::new(&data, string_ids_off);
+        for _ in 0..string_ids_size {
+            let mut string_data = Reader::new(&data, ids.u32()?);
+            let _utf16_size = string_data.uleb128()?;
+            dex.strings.push(string_data.mutf8()?);
+        }
+        let mut ids = Reader::new(&data, type_ids_off);
+        for _ in 0..type_ids_size {
+            let descriptor = dex.string(ids.u32()?)?.to_owned();
+            dex.types.push(descriptor);
+        }
+        let mut ids = Reader::new(&data, proto_ids_off);
+        for _ in 0..proto_ids_size {
+            let [_shorty_idx, return_type_idx, parameters_off] = ids.u32s()?;
+            let parameters_types = Self::type_list(&data, parameters_off)?
+                .iter()
+                .map(|it| dex.field_type(*it))
+                .collect::<Result<Vec<_>, _>>()?;
+            let return_type = match dex.type_descriptor(return_type_idx)? {
+                "V" => ReturnType::Void,
+                _ => ReturnType::Some(dex.field_type(return_type_idx)?),
+            };
+            dex.protos
+                .push(MethodDescriptor::new(parameters_types, return_type));
+        }
+        let mut ids = Reader::new(&data, field_ids_off);
+        for _ in 0..field_ids_size {
+            let class_idx = ids.u16()?;
+            let type_idx = ids.u16()?;
+            let name_idx = ids.u32()?;
+            dex.fields.push(FieldRef::new(
+                dex.class_ref(class_idx.into())?,
+                dex.string(name_idx)?,
+                dex.field_type(type_idx.into())?,
+            ));
+        }
+        let mut ids = Reader::new(&data, method_ids_off);
+        for _ in 0..method_ids_size {
+            let class_idx = ids.u16()?;
+            let proto_idx = ids.u16()?;
+            let name_idx = ids.u32()?;
+            dex.methods.push(MethodRef::new(
+                dex.class_ref(class_idx.into())?,
+                dex.string(name_idx)?,
+                dex.proto(proto_idx.into())?.clone(),
+            ));
+        }
+        let mut defs = Reader::new(&data, class_defs_off);
+        for _ in 0..class_defs_size {
+            let [class_idx, access_flags, superclass_idx, interfaces_off] = defs.u32s()?;
+            let [source_file_idx, _annotations_off, class_data_off, static_values_off] =
+                defs.u32s()?;
+            dex.class_defs.push(ClassDef {
+                class_idx,
+                access_flags,
+                superclass_idx,
+                interfaces_off,
+                source_file_idx,
+                class_data_off,
+                static_values_off,
+            });
+        }
+        dex.data = data;
+        Ok(dex)
+    }
+
+    /// Reads the `classes.dex`, `classes2.dex`, ... files in an APK in the order in which they
+    /// are loaded by the Android runtime.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    #[cfg(feature = "jar")]
+    pub fn from_apk(apk: impl std::io::Read + std::io::Seek) -> Result<Vec<Self>, Error> {
+        use std::io::Read;
+
+        let mut archive = zip::ZipArchive::new(apk).map_err(|it| Error::Archive(Box::new(it)))?;
+        let mut dex_files = Vec::new();
+        for index in 1.. {
+            let name = if index == 1 {
+                "classes.dex".to_owned()
+            } else {
+                format!("classes{index}.dex")
+            };
+            let mut entry = match archive.by_name(&name) {
+                Ok(entry) => entry,
+                Err(zip::result::ZipError::FileNotFound) => break,
+                Err(err) => return Err(Error::Archive(Box::new(err))),
+            };
+            let mut bytes = Vec::new();
+            entry
+                .read_to_end(&mut bytes)
+                .map_err(|it| Error::Archive(Box::new(it)))?;
+            dex_files.push(Self::from_bytes(bytes)?);
+        }
+        Ok(dex_files)
+    }
+
+    /// Returns the format version (e.g., `35` for `dex\n035\0`).
+    #[must_use]
+    pub const fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Returns the binary names of the classes defined in the file.
+    pub fn class_names(&self) -> impl Iterator<Item = &str> {
+        self.class_defs
+            .iter()
+            .filter_map(|it| self.types.get(it.class_idx as usize))
+            .filter_map(|it| binary_name(it))
+    }
+
+    /// Lowers the classes defined in the file.
+    pub fn classes(&self) -> impl Iterator<Item = Result<Class, Error>> + '_ {
+        self.class_defs.iter().map(|it| self.lower_class(it))
+    }
+
+    /// Lowers the class with the given binary name, or returns [`None`] if it is not defined in
+    /// the file.
+    #[must_use]
+    pub fn class(&self, binary_name: &str) -> Option<Result<Class, Error>> {
+        self.class_defs
+            .iter()
+            .find(|it| {
+                self.types
+                    .get(it.class_idx as usize)
+                    .and_then(|it| self::binary_name(it))
+                    == Some(binary_name)
+            })
+            .map(|it| self.lower_class(it))
+    }
+
+    fn lower_class(&self, def: &ClassDef) -> Result<Class, Error> {
+        let this_class = self.class_ref(def.class_idx)?;
+        let is_interface = def.access_flags & u32::from(class::AccessFlags::INTERFACE.bits()) != 0;
+        let mut access_flags =
+            class::AccessFlags::from_bits_truncate(truncate_flags(def.access_flags));
+        if !is_interface {
+            access_flags |= class::AccessFlags::SUPER;
+        }
+        let mut builder = ClassBuilder::new(this_class.binary_name.to_string())
+            .with_version(if self.version >= 37 {
+                Version::Jdk8
+            } else {
+                Version::Jdk7
+            })
+            .with_access_flags(access_flags)
+            .with_super_class(if def.superclass_idx == NO_INDEX {
+                None
+            } else {
+                Some(self.class_ref(def.superclass_idx)?)
+            });
+        for interface in Self::type_list(&self.data, def.interfaces_off)? {
+            builder = builder.with_interface(self.class_ref(interface)?);
+        }
+        if def.source_file_idx != NO_INDEX {
+            builder = builder.with_source_file(self.string(def.source_file_idx)?);
+        }
+        if def.class_data_off == 0 {
+            return Ok(builder.build()?);
+        }
+
+        let static_values = if def.static_values_off == 0 {
+            Vec::new()
+        } else {
+            let mut reader = Reader::new(&self.data, def.static_values_off);
+            let size = reader.uleb128()?;
+            (0..size)
+                .map(|_| self.encoded_value(&mut reader))
+                .collect::<Result<_, _>>()?
+        };
+        let mut class_data = Reader::new(&self.data, def.class_data_off);
+        let [static_fields, instance_fields, direct_methods, virtual_methods] =
+            [(); 4].map(|()| class_data.uleb128());
+        for (size, values) in [
+            (static_fields?, static_values.as_slice()),
+            (instance_fields?, [].as_slice()),
+        ] {
+            let mut field_idx = 0;
+            for position in 0..size as usize {
+                field_idx += class_data.uleb128()?;
+                let access_flags = class_data.uleb128()?;
+                let field_ref = self.field(field_idx)?;
+                let mut field =
+                    FieldBuilder::new(field_ref.name.to_string(), field_ref.field_type.clone())
+                        .with_access_flags(field::AccessFlags::from_bits_truncate(truncate_flags(
+                            access_flags,
+                        )));
+                if let Some(Some(value)) = values.get(position) {
+                    field = field.with_constant_value(value.clone());
+                }
+                builder = builder.with_field(field);
+            }
+        }
+        for size in [direct_methods?, virtual_methods?] {
+            let mut method_idx = 0;
+            for _ in 0..size {
+                method_idx += class_data.uleb128()?;
+                let access_flags = class_data.uleb128()?;
+                let code_off = class_data.uleb128()?;
+                builder = builder.with_method(self.lower_method(
+                    self.method(method_idx)?,
+                    access_flags,
+                    code_off,
+                )?);
+            }
+        }
+        Ok(builder.build()?)
+    }
+
+    fn lower_method(
+        &self,
+        method_ref: &MethodRef,
+        dex_flags: u32,
+        code_off: u32,
+    ) -> Result<MethodBuilder, Error> {
+        let mut access_flags =
+            method::AccessFlags::from_bits_truncate(truncate_flags(dex_flags & !ACC_CONSTRUCTOR));
+        if dex_flags & ACC_DECLARED_SYNCHRONIZED != 0 {
+            access_flags |= method::AccessFlags::SYNCHRONIZED;
+        }
+        let builder =
+            MethodBuilder::new(method_ref.name.to_string(), method_ref.descriptor.clone())
+                .with_access_flags(access_flags);
+        if code_off == 0 {
+            return Ok(builder);
+        }
+        let is_static = access_flags.contains(method::AccessFlags::STATIC);
+        let lowered = lowering::lower(self, method_ref, is_static, code_off)?;
+        Ok(lowered.exception_table.into_iter().fold(
+            builder
+                .with_body(lowered.max_stack, lowered.instructions)
+                .with_max_locals(lowered.max_locals),
+            MethodBuilder::with_exception_handler,
+        ))
+    }
+
+    fn string(&self, index: u32) -> Result<&str, Error> {
+        self.strings
+            .get(index as usize)
+            .map(String::as_str)
+            .ok_or(Error::InvalidIndex {
+                kind: "string",
+                index,
+            })
+    }
+
+    fn type_descriptor(&self, index: u32) -> Result<&str, Error> {
+        self.types
+            .get(index as usize)
+            .map(String::as_str)
+            .ok_or(Error::InvalidIndex {
+                kind: "type",
+                index,
+            })
+    }
+
+    fn field_type(&self, index: u32) -> Result<FieldType, Error> {
+        let descriptor = self.type_descriptor(index)?;
+        descriptor
+            .parse()
+            .map_err(|_| Error::InvalidDescriptor(descriptor.to_owned()))
+    }
+
+    /// Gets the class referenced by a `type_id`, where array classes are named by their
+    /// descriptors as in the constant pool of class files.
+    fn class_ref(&self, index: u32) -> Result<ClassRef, Error> {
+        let descriptor = self.type_descriptor(index)?;
+        binary_name(descriptor)
+            .map(ClassRef::new)
+            .ok_or_else(|| Error::InvalidDescriptor(descriptor.to_owned()))
+    }
+
+    fn proto(&self, index: u32) -> Result<&MethodDescriptor, Error> {
+        self.protos.get(index as usize).ok_or(Error::InvalidIndex {
+            kind: "proto",
+            index,
+        })
+    }
+
+    fn field(&self, index: u32) -> Result<&FieldRef, Error> {
+        self.fields.get(index as usize).ok_or(Error::InvalidIndex {
+            kind: "field",
+            index,
+        })
+    }
+
+    fn method(&self, index: u32) -> Result<&MethodRef, Error> {
+        self.methods.get(index as usize).ok_or(Error::InvalidIndex {
+            kind: "method",
+            index,
+        })
+    }
+
+    fn type_list(data: &[u8], offset: u32) -> Result<Vec<u32>, Error> {
+        if offset == 0 {
+            return Ok(Vec::new());
+        }
+        let mut reader = Reader::new(data, offset);
+        let size = reader.u32()?;
+        (0..size).map(|_| reader.u16().map(u32::from)).collect()
+    }
+
+    /// Reads an `encoded_value`, which is lowered into a [`ConstantValue`] if it is a primitive
+    /// value or a string.
+    fn encoded_value(&self, reader: &mut Reader<'_>) -> Result<Option<ConstantValue>, Error> {
+        let header = reader.u8()?;
+        let (value_arg, value_type) = (header >> 5, header & 0x1f);
+        let size = usize::from(value_arg) + 1;
+        let value = match value_type {
+            // byte, short, int
+            0x00 | 0x02 | 0x04 => {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    reason = "The value has 4 bytes at most"
+                )]
+                let value = reader.signed(size)? as i32;
+                Some(ConstantValue::Integer(value))
+            }
+            // char
+            0x03 => {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    reason = "The value has 2 bytes at most"
+                )]
+                let value = reader.unsigned(size)? as i32;
+                Some(ConstantValue::Integer(value))
+            }
+            // long
+            0x06 => Some(ConstantValue::Long(reader.signed(size)?)),
+            // float and double are zero-extended to the right
+            0x10 => {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    reason = "The value has 4 bytes at most"
+                )]
+                let bits = (reader.unsigned(size)? << (8 * (4 - size.min(4)))) as u32;
+                Some(ConstantValue::Float(f32::from_bits(bits)))
+            }
+            0x11 => {
+                let bits = reader.unsigned(size)? << (8 * (8 - size.min(8)));
+                Some(ConstantValue::Double(f64::from_bits(bits)))
+            }
+            // string
+            0x17 => {
+                #[allow(
+                    clippy::cast_possible_truncation,
+                    reason = "The value has 4 bytes at most"
+                )]
+                let index = reader.unsigned(size)? as u32;
+                Some(ConstantValue::String(JavaString::Utf8(
+                    self.string(index)?.to_owned(),
+                )))
+            }
+            // method type, method handle, type, field, method, enum
+            0x15 | 0x16 | 0x18..=0x1b => {
+                reader.bytes(size)?;
+                None
+            }
+            // array
+            0x1c => {
+                let size = reader.uleb128()?;
+                for _ in 0..size {
+                    self.encoded_value(reader)?;
+                }
+                None
+            }
+            // annotation
+            0x1d => {
+                let _type_idx = reader.uleb128()?;
+                let size = reader.uleb128()?;
+                for _ in 0..size {
+                    let _name_idx = reader.uleb128()?;
+                    self.encoded_value(reader)?;
+                }
+                None
+            }
+            // null
+            0x1e => None,
+            // boolean
+            0x1f => Some(ConstantValue::Integer(i32::from(value_arg))),
+            _ => {
+                return Err(Error::InvalidDescriptor(format!(
+                    "encoded value {header:#x}"
+                )))
+            }
+        };
+        Ok(value)
+    }
+}
+
+impl ClassPath for DexFile {
+    fn find_class(&self, binary_name: &str) -> Result<Class, class_loader::Error> {
+        match self.class(binary_name) {
+            Some(Ok(class)) => Ok(class),
+            Some(Err(err)) => Err(class_loader::Error::Other(Box::new(err))),
+            None => Err(class_loader::Error::NotFound),
+        }
+    }
+}
+
+/// Converts a type descriptor into the name of the class as it appears in the constant pool of a
+/// class file.
+fn binary_name(descriptor: &str) -> Option<&str> {
+    if descriptor.starts_with('[') {
+        Some(descriptor)
+    } else {
+        descriptor
+            .strip_prefix('L')
+            .and_then(|it| it.strip_suffix(';'))
+    }
+}
+
+/// Drops the DEX-only access flags, which do not fit in the access flags of class files.
+fn truncate_flags(flags: u32) -> u16 {
+    #[allow(clippy::cast_possible_truncation, reason = "Truncation is intended")]
+    let flags = flags as u16;
+    flags
+}
+
+/// A cursor over the bytes of a DEX file, where all the values are little-endian.
+#[derive(Debug, Clone)]
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], offset: u32) -> Self {
+        Self {
+            data,
+            position: offset as usize,
+        }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.position.checked_add(len).ok_or(Error::Truncated)?;
+        let bytes = self.data.get(self.position..end).ok_or(Error::Truncated)?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        self.bytes(1).map(|it| it[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u32s<const N: usize>(&mut self) -> Result<[u32; N], Error> {
+        let mut values = [0; N];
+        for value in &mut values {
+            *value = self.u32()?;
+        }
+        Ok(values)
+    }
+
+    fn uleb128(&mut self) -> Result<u32, Error> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+
+    fn sleb128(&mut self) -> Result<i32, Error> {
+        let mut value = 0u32;
+        let mut shift = 0;
+        loop {
+            let byte = self.u8()?;
+            value |= u32::from(byte & 0x7f) << shift;
+            shift += 7;
+            if byte & 0x80 == 0 || shift >= 35 {
+                break;
+            }
+        }
+        let unused = 32u32.saturating_sub(shift);
+        #[allow(
+            clippy::cast_possible_wrap,
+            reason = "Reinterpreting the bits is intended"
+        )]
+        let value = (value << unused) as i32 >> unused;
+        Ok(value)
+    }
+
+    /// Reads a zero-extended integer of `size` bytes.
+    fn unsigned(&mut self, size: usize) -> Result<u64, Error> {
+        Ok(self
+            .bytes(size)?
+            .iter()
+            .rev()
+            .fold(0, |acc, it| (acc << 8) | u64::from(*it)))
+    }
+
+    /// Reads a sign-extended integer of `size` bytes.
+    fn signed(&mut self, size: usize) -> Result<i64, Error> {
+        let shift = 64 - 8 * size.min(8);
+        #[allow(
+            clippy::cast_possible_wrap,
+            reason = "Reinterpreting the bits is intended"
+        )]
+        let value = (self.unsigned(size)? << shift) as i64 >> shift;
+        Ok(value)
+    }
+
+    /// Reads a null-terminated MUTF-8 string.
+    fn mutf8(&mut self) -> Result<String, Error> {
+        let rest = self.data.get(self.position..).ok_or(Error::Truncated)?;
+        let len = rest
+            .iter()
+            .position(|it| *it == 0)
+            .ok_or(Error::Truncated)?;
+        let bytes = self.bytes(len + 1)?;
+        let string = cesu8::from_java_cesu8(&bytes[..len])
+            .unwrap_or_else(|_| String::from_utf8_lossy(&bytes[..len]));
+        Ok(Cow::into_owned(string))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::code::{Instruction, ProgramCounter},
+    };
+
+    use super::*;
+
+    fn uleb128(mut value: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                bytes.push(byte);
+                return bytes;
+            }
+            bytes.push(byte | 0x80);
+        }
+    }
+
+    fn put(data: &mut Vec<u8>, data_start: usize, bytes: &[u8], align: usize) -> u32 {
+        while !(data_start + data.len()).is_multiple_of(align) {
+            data.push(0);
+        }
+        let offset = u32::try_from(data_start + data.len()).unwrap();
+        data.extend_from_slice(bytes);
+        offset
+    }
+
+    fn code_item(registers: u16, ins: u16, units: &[u16], tries: &[u8]) -> Vec<u8> {
+        let tries_size = u16::from(!tries.is_empty());
+        let mut bytes = [registers, ins, 1, tries_size]
+            .iter()
+            .flat_map(|it| it.to_le_bytes())
+            .collect::<Vec<_>>();
+        bytes.extend(0u32.to_le_bytes());
+        bytes.extend(u32::try_from(units.len()).unwrap().to_le_bytes());
+        bytes.extend(units.iter().flat_map(|it| it.to_le_bytes()));
+        if !tries.is_empty() && units.len() % 2 == 1 {
+            bytes.extend([0, 0]);
+        }
+        bytes.extend(tries);
+        bytes
+    }
+
+    /// Assembles the following class.
+    /// ```java
+    /// public class Calc {
+    ///     public static final int ANSWER = 42;
+    ///     public static int max(int a, int b) { return a > b ? a : b; }
+    ///     public static int safeLength(String s) {
+    ///         try { return s.length(); } catch (Exception e) { return -1; }
+    ///     }
+    /// }
+    /// ```
+    #[allow(clippy::too_many_lines)]
+    fn calc_dex() -> Vec<u8> {
+        let strings = [
+            "I",
+            "Ljava/lang/Object;",
+            "Lorg/mokapot/Calc;",
+            "Ljava/lang/String;",
+            "Ljava/lang/Exception;",
+            "max",
+            "safeLength",
+            "length",
+            "ANSWER",
+            "Calc.java",
+            "III",
+            "IL",
+        ];
+        let types = [0u32, 1, 2, 3, 4];
+        let protos: [(u32, &[u16]); 3] = [(10, &[0, 0]), (11, &[3]), (0, &[])];
+        let fields: [(u16, u16, u32); 1] = [(2, 0, 8)];
+        let methods: [(u16, u16, u32); 3] = [(2, 0, 5), (2, 1, 6), (3, 2, 7)];
+        let data_start = 0x70
+            + 4 * strings.len()
+            + 4 * types.len()
+            + 12 * protos.len()
+            + 8 * fields.len()
+            + 8 * methods.len()
+            + 32;
+
+        let mut data = Vec::new();
+        let string_offsets: Vec<_> = strings
+            .iter()
+            .map(|it| {
+                let mut bytes = uleb128(u32::try_from(it.len()).unwrap());
+                bytes.extend(it.as_bytes());
+                bytes.push(0);
+                put(&mut data, data_start, &bytes, 1)
+            })
+            .collect();
+        let parameter_offsets: Vec<_> = protos
+            .iter()
+            .map(|(_, parameters)| {
+                if parameters.is_empty() {
+                    return 0;
+                }
+                let mut bytes = u32::try_from(parameters.len())
+                    .unwrap()
+                    .to_le_bytes()
+                    .to_vec();
+                bytes.extend(parameters.iter().flat_map(|it| it.to_le_bytes()));
+                put(&mut data, data_start, &bytes, 4)
+            })
+            .collect();
+        // if-le v0, v1, +3; return v0; return v1
+        let max = code_item(2, 2, &[0x1037, 0x0003, 0x000f, 0x010f], &[]);
+        let max = put(&mut data, data_start, &max, 4);
+        // invoke-virtual {v1}, String.length()I; move-result v0; return v0;
+        // const/4 v0, -1; return v0
+        let mut tries = vec![0, 0, 0, 0, 4, 0, 1, 0];
+        tries.extend([1, 1, 4, 5]);
+        let units = [0x106e, 0x0002, 0x0001, 0x000a, 0x000f, 0xf012, 0x000f];
+        let safe_length = code_item(2, 1, &units, &tries);
+        let safe_length = put(&mut data, data_start, &safe_length, 4);
+        let mut class_data = vec![1, 0, 2, 0, 0, 0x19, 0, 0x09];
+        class_data.extend(uleb128(max));
+        class_data.extend([1, 0x09]);
+        class_data.extend(uleb128(safe_length));
+        let class_data = put(&mut data, data_start, &class_data, 1);
+        let static_values = put(&mut data, data_start, &[1, 0x04, 42], 1);
+
+        let mut dex = b"dex\n035\0".to_vec();
+        dex.resize(0x38, 0);
+        let mut offset = 0x70;
+        for size in [
+            strings.len(),
+            types.len(),
+            protos.len(),
+            fields.len(),
+            methods.len(),
+            1,
+        ] {
+            dex.extend(u32::try_from(size).unwrap().to_le_bytes());
+            dex.extend(u32::try_from(offset).unwrap().to_le_bytes());
+            offset += size
+                * match dex.len() {
+                    0x40 | 0x48 => 4,
+                    0x50 => 12,
+                    0x58 | 0x60 => 8,
+                    _ => 32,
+                };
+        }
+        dex.resize(0x70, 0);
+        dex.extend(string_offsets.iter().flat_map(|it| it.to_le_bytes()));
+        dex.extend(types.iter().flat_map(|it| it.to_le_bytes()));
+        for ((shorty, _), parameters) in protos.iter().zip(parameter_offsets) {
+            dex.extend(
+                [*shorty, 0, parameters]
+                    .iter()
+                    .flat_map(|it| it.to_le_bytes()),
+            );
+        }
+        for (class, ty, name) in fields.iter().chain(&methods) {
+            dex.extend(class.to_le_bytes());
+            dex.extend(ty.to_le_bytes());
+            dex.extend(name.to_le_bytes());
+        }
+        let class_def = [2, 0x1, 1, 0, 9, 0, class_data, static_values];
+        dex.extend(class_def.iter().flat_map(|it| it.to_le_bytes()));
+        assert_eq!(dex.len(), data_start);
+        dex.extend(data);
+        dex
+    }
+
+    #[test]
+    fn lower_class() {
+        let dex = DexFile::from_bytes(calc_dex()).unwrap();
+        assert_eq!(dex.version(), 35);
+        assert_eq!(dex.class_names().collect::<Vec<_>>(), ["org/mokapot/Calc"]);
+        let class = dex.class("org/mokapot/Calc").unwrap().unwrap();
+        assert_eq!(class.super_class, Some(ClassRef::new("java/lang/Object")));
+        assert_eq!(class.source_file.as_deref(), Some("Calc.java"));
+        assert!(class.access_flags.contains(class::AccessFlags::SUPER));
+        assert_eq!(class.fields[0].name, "ANSWER");
+        assert_eq!(
+            class.fields[0].constant_value,
+            Some(ConstantValue::Integer(42))
+        );
+        for method in &class.methods {
+            assert!(method.brew().is_ok(), "{}", method.name);
+        }
+    }
+
+    #[test]
+    fn lower_branches() {
+        let dex = DexFile::from_bytes(calc_dex()).unwrap();
+        let class = dex.class("org/mokapot/Calc").unwrap().unwrap();
+        let body = class.methods[0].body.as_ref().unwrap();
+        assert_eq!(body.max_locals, 4);
+        let instructions: Vec<_> = body.instructions.iter().map(|(_, it)| it.clone()).collect();
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::ILoad(0),
+                Instruction::IStore(2),
+                Instruction::ILoad(1),
+                Instruction::IStore(3),
+                Instruction::ILoad(2),
+                Instruction::ILoad(3),
+                Instruction::IfICmpLe(18.into()),
+                Instruction::ILoad(2),
+                Instruction::IReturn,
+                Instruction::ILoad(3),
+                Instruction::IReturn,
+            ]
+        );
+        assert_eq!(body.instruction_at(18.into()), Some(&Instruction::ILoad(3)));
+    }
+
+    #[test]
+    fn lower_exception_handlers() {
+        let dex = DexFile::from_bytes(calc_dex()).unwrap();
+        let class = dex.class("org/mokapot/Calc").unwrap().unwrap();
+        let body = class.methods[1].body.as_ref().unwrap();
+        let [entry] = body.exception_table.as_slice() else {
+            panic!("Expected exactly one exception handler");
+        };
+        assert_eq!(entry.catch_type, Some(ClassRef::new("java/lang/Exception")));
+        assert_eq!(
+            body.instruction_at(entry.handler_pc),
+            Some(&Instruction::Pop)
+        );
+        let handler_pc: ProgramCounter = body.instructions.next_pc_of(&entry.handler_pc).unwrap();
+        let Some(Instruction::Goto(target)) = body.instruction_at(handler_pc) else {
+            panic!("Expected a jump to the handler");
+        };
+        assert_eq!(body.instruction_at(*target), Some(&Instruction::IConstM1));
+        assert!(matches!(
+            body.instruction_at(*entry.covered_pc.end()),
+            Some(Instruction::IStore(_))
+        ));
+    }
+
+    #[test]
+    fn bad_magic() {
+        assert!(matches!(
+            DexFile::from_bytes(b"cafebabe".to_vec()),
+            Err(Error::BadMagic)
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+//! Entry points for fuzzing the parsing, the IR generation, and the writing of classes.
+//! This module is not part of the stable API.
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod strategies;
+
+use crate::{
+    ir::{MokaIRBrewingError, MokaIRMethod, MokaIRMethodExt},
+    jvm::{visitor::ClassWriterVisitor, writing, Class},
+};
+
+/// Parses `data` as a class file.
+#[must_use]
+pub fn parse(data: &[u8]) -> Option<Class> {
+    Class::from_reader(data).ok()
+}
+
+/// Generates Moka IR for the methods of `class` that have a body.
+#[must_use]
+pub fn brew(class: &Class) -> Vec<Result<MokaIRMethod, MokaIRBrewingError>> {
+    class
+        .methods
+        .iter()
+        .filter(|it| it.body.is_some())
+        .map(MokaIRMethodExt::brew)
+        .collect()
+}
+
+/// Rebuilds `class` with a [`ClassWriterVisitor`].
+///
+/// # Panics
+/// Panics if the rebuilt class differs from `class`.
+#[must_use]
+pub fn rebuild(class: &Class) -> Class {
+    let mut writer = ClassWriterVisitor::new();
+    class.accept(&mut writer);
+    let rebuilt = writer
+        .into_class()
+        .expect("The header of the class is not visited");
+    assert_eq!(
+        format!("{rebuilt:?}"),
+        format!("{class:?}"),
+        "The class is changed by rebuilding it"
+    );
+    rebuilt
+}
+
+/// Writes `class` in the class file format and parses the bytes again.
+///
+/// # Errors
+/// See [`writing::Error`] for the classes that cannot be written.
+///
+/// # Panics
+/// Panics if the written bytes cannot be parsed or the parsed class differs from `class`.
+pub fn write_back(class: &Class) -> Result<Class, writing::Error> {
+    let bytes = class.to_bytes()?;
+    let written = Class::from_reader(bytes.as_slice()).expect("The written class is invalid");
+    assert_eq!(
+        format!("{written:?}"),
+        format!("{class:?}"),
+        "The class is changed by writing it back"
+    );
+    Ok(written)
+}
+
+/// Runs the whole pipeline on `data`: parses it as a class file, brews its methods, rebuilds it,
+/// and writes it back. Inputs that are not valid class files are ignored.
+///
+/// # Panics
+/// Panics if any step of the pipeline panics or the class is changed by rebuilding it or writing
+/// it back.
+pub fn pipeline(data: &[u8]) {
+    let Some(class) = parse(data) else {
+        return;
+    };
+    let _ = brew(&class);
+    let _ = rebuild(&class);
+    let _ = write_back(&class);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::empty_class_with_version;
+
+    #[test]
+    fn pipeline_on_empty_class() {
+        let bytes = empty_class_with_version(52, 0);
+        let class = parse(&bytes).unwrap();
+        assert!(brew(&class).is_empty());
+        assert_eq!(rebuild(&class).binary_name, class.binary_name);
+        assert_eq!(write_back(&class).unwrap().binary_name, class.binary_name);
+        pipeline(&bytes);
+        pipeline(&bytes[..10]);
+    }
+}
@@ -0,0 +1,215 @@
+//! [`proptest`] strategies generating arbitrary valid classes.
+//! The methods are static with `int` parameters and results, and their instructions keep the
+//! depth of the operand stack consistent on every path, so that the generated classes pass the
+//! verification and can be brewed.
+
+use std::fmt::Write;
+
+use proptest::{collection::vec, prelude::*, sample::Index};
+
+use crate::{
+    jvm::{
+        builder::{ClassBuilder, FieldBuilder, MethodBuilder},
+        class::Version,
+        code::{assembler, Instruction, InstructionList},
+        field, method, Class, ConstantValue,
+    },
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+/// An operation on the operand stack.
+#[derive(Debug, Clone)]
+enum StackOp {
+    Push(i16),
+    Load(Index),
+    Store(u16),
+    Unary(&'static str),
+    Binary(&'static str),
+    Dup,
+    Pop,
+    Swap,
+}
+
+/// How a basic block ends.
+#[derive(Debug, Clone)]
+enum Terminator {
+    FallThrough,
+    IfEq(Index),
+    Goto(Index),
+    Return,
+}
+
+/// The number of local variables that are only stored to.
+const SCRATCH_LOCALS: u16 = 2;
+
+fn arb_stack_op() -> impl Strategy<Value = StackOp> {
+    prop_oneof![
+        any::<i16>().prop_map(StackOp::Push),
+        any::<Index>().prop_map(StackOp::Load),
+        (0..SCRATCH_LOCALS).prop_map(StackOp::Store),
+        prop::sample::select(vec!["ineg", "i2b", "i2c", "i2s"]).prop_map(StackOp::Unary),
+        prop::sample::select(vec![
+            "iadd", "isub", "imul", "idiv", "irem", "ishl", "ishr", "iushr", "iand", "ior", "ixor",
+        ])
+        .prop_map(StackOp::Binary),
+        Just(StackOp::Dup),
+        Just(StackOp::Pop),
+        Just(StackOp::Swap),
+    ]
+}
+
+fn arb_terminator() -> impl Strategy<Value = Terminator> {
+    prop_oneof![
+        Just(Terminator::FallThrough),
+        any::<Index>().prop_map(Terminator::IfEq),
+        any::<Index>().prop_map(Terminator::Goto),
+        Just(Terminator::Return),
+    ]
+}
+
+/// Generates the instructions of a static method taking `parameters` `int`s and returning an
+/// `int`, together with the maximum depth of the operand stack.
+/// The code consists of basic blocks that start and end with an empty operand stack, and jump
+/// to each other with `ifeq` and `goto`.
+///
+/// # Panics
+/// Panics if `parameters` is zero.
+pub fn arb_instructions(
+    parameters: u16,
+) -> impl Strategy<Value = (u16, InstructionList<Instruction>)> {
+    assert!(
+        parameters > 0,
+        "The method must take at least one parameter"
+    );
+    vec((vec(arb_stack_op(), 0..12), arb_terminator()), 1..6).prop_map(move |blocks| {
+        let block_count = blocks.len();
+        let mut text = String::new();
+        let mut max_stack = 1u16;
+        for (index, (ops, terminator)) in blocks.into_iter().enumerate() {
+            let mut depth = 0u16;
+            let mut emit = |line: String| writeln!(text, "        {line}").unwrap();
+            emit(format!("L{index}:"));
+            for op in ops {
+                let (line, pops, pushes) = match op {
+                    StackOp::Push(value) => (format!("sipush {value}"), 0, 1),
+                    StackOp::Load(it) => (format!("iload {}", it.index(parameters.into())), 0, 1),
+                    StackOp::Store(it) => (format!("istore {}", parameters + it), 1, 0),
+                    StackOp::Unary(it) => (it.to_owned(), 1, 1),
+                    StackOp::Binary(it) => (it.to_owned(), 2, 1),
+                    StackOp::Dup => ("dup".to_owned(), 1, 2),
+                    StackOp::Pop => ("pop".to_owned(), 1, 0),
+                    StackOp::Swap => ("swap".to_owned(), 2, 2),
+                };
+                // Skips the operations without enough operands.
+                if depth < pops {
+                    continue;
+                }
+                emit(line);
+                depth = depth - pops + pushes;
+                max_stack = max_stack.max(depth);
+            }
+            for _ in 0..depth {
+                emit("pop".to_owned());
+            }
+            let is_last = index + 1 == block_count;
+            match terminator {
+                Terminator::IfEq(target) => {
+                    emit("iload 0".to_owned());
+                    emit(format!("ifeq L{}", target.index(block_count)));
+                }
+                Terminator::Goto(target) if !is_last => {
+                    emit(format!("goto L{}", target.index(block_count)));
+                }
+                _ => {}
+            }
+            if is_last || matches!(terminator, Terminator::Return) {
+                emit("iload 0".to_owned());
+                emit("ireturn".to_owned());
+            }
+        }
+        let instructions = assembler::assemble(&text).expect("The generated code is invalid");
+        (max_stack, instructions)
+    })
+}
+
+fn arb_method(name: String) -> impl Strategy<Value = MethodBuilder> {
+    (1..=3u16).prop_flat_map(move |parameters| {
+        let name = name.clone();
+        arb_instructions(parameters).prop_map(move |(max_stack, instructions)| {
+            let descriptor = MethodDescriptor::new(
+                vec![FieldType::Base(PrimitiveType::Int); parameters.into()],
+                ReturnType::Some(FieldType::Base(PrimitiveType::Int)),
+            );
+            MethodBuilder::new(name.clone(), descriptor)
+                .with_access_flags(method::AccessFlags::PUBLIC | method::AccessFlags::STATIC)
+                .with_body(max_stack, instructions)
+        })
+    })
+}
+
+fn arb_field(name: String) -> impl Strategy<Value = FieldBuilder> {
+    any::<Option<i32>>().prop_map(move |constant| {
+        let builder = FieldBuilder::new(name.clone(), FieldType::Base(PrimitiveType::Int));
+        match constant {
+            Some(value) => builder
+                .with_access_flags(
+                    field::AccessFlags::PRIVATE
+                        | field::AccessFlags::STATIC
+                        | field::AccessFlags::FINAL,
+                )
+                .with_constant_value(ConstantValue::Integer(value)),
+            None => builder,
+        }
+    })
+}
+
+/// Generates a Java 6 class with `int` fields and static methods whose instructions are
+/// generated by [`arb_instructions`].
+pub fn arb_class() -> impl Strategy<Value = Class> {
+    (0..4usize, 1..4usize)
+        .prop_flat_map(|(field_count, method_count)| {
+            let fields: Vec<_> = (0..field_count)
+                .map(|it| arb_field(format!("f{it}")))
+                .collect();
+            let methods: Vec<_> = (0..method_count)
+                .map(|it| arb_method(format!("m{it}")))
+                .collect();
+            (fields, methods)
+        })
+        .prop_map(|(fields, methods)| {
+            // Java 6 does not require stack map frames at the branch targets.
+            let builder =
+                ClassBuilder::new("org/mokapot/fuzz/Generated").with_version(Version::Jdk6);
+            let builder = fields.into_iter().fold(builder, ClassBuilder::with_field);
+            methods
+                .into_iter()
+                .fold(builder, ClassBuilder::with_method)
+                .build()
+                .expect("The generated class is invalid")
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fuzzing::{brew, rebuild, write_back},
+        jvm::verify::verify_class,
+    };
+
+    proptest! {
+        #[test]
+        fn generated_classes_pass_the_pipeline(class in arb_class()) {
+            prop_assert!(verify_class(&class).is_empty(), "{:?}", verify_class(&class));
+            for result in brew(&class) {
+                prop_assert!(result.is_ok(), "{:?}", result.err());
+            }
+            let _ = rebuild(&class);
+            let written = write_back(&class);
+            prop_assert!(written.is_ok(), "{:?}", written.err());
+        }
+    }
+}
@@ -8,7 +8,7 @@ use crate::{
 };
 use std::collections::{BTreeMap, BTreeSet};
 
-use self::path_condition::{PathCondition, Predicate, Value};
+use self::path_condition::{BddPathCondition, PathCondition, Predicate, Value};
 
 use super::ControlFlowGraph;
 
@@ -87,6 +87,36 @@ impl<N, E> ControlFlowGraph<N, E> {
                 .map(move |(dst, data)| (src, *dst, data))
         })
     }
+
+    /// Returns the nodes reachable from the entry point in reverse postorder, in which each node
+    /// precedes its successors except along back edges.
+    /// Visiting the nodes in this order speeds up the convergence of forward data flow analyses
+    /// (see [`IterationOrder::Given`](crate::analysis::fixed_point::IterationOrder::Given)).
+    #[must_use]
+    pub fn reverse_postorder(&self) -> Vec<ProgramCounter> {
+        let mut postorder = Vec::new();
+        let mut visited = BTreeSet::from([self.entry_point()]);
+        // Each node is paired with its successors yet to be visited.
+        let successors = |pc: ProgramCounter| -> Vec<ProgramCounter> {
+            self.inner
+                .get(&pc)
+                .map(|(_, edges)| edges.keys().rev().copied().collect())
+                .unwrap_or_default()
+        };
+        let mut stack = vec![(self.entry_point(), successors(self.entry_point()))];
+        while let Some((pc, pending)) = stack.last_mut() {
+            if let Some(next) = pending.pop() {
+                if visited.insert(next) {
+                    stack.push((next, successors(next)));
+                }
+            } else {
+                postorder.push(*pc);
+                stack.pop();
+            }
+        }
+        postorder.reverse();
+        postorder
+    }
 }
 
 impl<E> ControlFlowGraph<(), E> {
@@ -110,12 +140,26 @@ impl<E> ControlFlowGraph<(), E> {
 
 impl<N> ControlFlowGraph<N, ControlTransfer> {
     /// Analyzes the control flow graph to determine the path conditions at each program counter.
+    /// The size of the conditions may grow exponentially with the number of branches.
+    /// Use [`Self::bdd_path_conditions`] for methods with many branches.
     #[must_use]
     pub fn path_conditions(&self) -> BTreeMap<ProgramCounter, PathCondition<Predicate<Value>>> {
         let mut analyzer = path_condition::Analyzer::new(self);
         let Ok(path_conditions) = analyzer.analyze();
         path_conditions
     }
+
+    /// Analyzes the control flow graph to determine the path conditions at each program counter.
+    /// The conditions share the nodes of a single [`BddManager`](path_condition::BddManager),
+    /// and can be converted with [`BddPathCondition::to_dnf`] for display.
+    #[must_use]
+    pub fn bdd_path_conditions(
+        &self,
+    ) -> BTreeMap<ProgramCounter, BddPathCondition<Predicate<Value>>> {
+        let mut analyzer = path_condition::BddAnalyzer::new(self);
+        let Ok(path_conditions) = analyzer.analyze();
+        path_conditions
+    }
 }
 
 #[cfg(test)]
@@ -153,6 +197,27 @@ mod tests {
         ControlFlowGraph::from_edges(edges);
     }
 
+    #[test]
+    fn reverse_postorder() {
+        // 0 -> 1 -> 3 -> 4 with a branch 0 -> 2 -> 3 and a back edge 4 -> 1.
+        let edges = [
+            (0.into(), 1.into(), ()),
+            (0.into(), 2.into(), ()),
+            (1.into(), 3.into(), ()),
+            (2.into(), 3.into(), ()),
+            (3.into(), 4.into(), ()),
+            (4.into(), 1.into(), ()),
+            (5.into(), 0.into(), ()),
+        ];
+        let cfg = ControlFlowGraph::from_edges(edges);
+        let order: Vec<u16> = cfg
+            .reverse_postorder()
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        assert_eq!(order, [0, 2, 1, 3, 4]);
+    }
+
     #[test]
     fn iter_nodes() {
         let cfg = build_cfg();
@@ -180,4 +245,49 @@ mod tests {
         assert_eq!(exits.len(), 1);
         assert!(exits.contains(&4.into()));
     }
+
+    #[test]
+    fn bdd_path_conditions_many_branches() {
+        use crate::jvm::ConstantValue;
+
+        const BRANCHES: u16 = 40;
+        let predicate = |i: u16| {
+            Predicate::Equal(
+                ConstantValue::Integer(i.into()).into(),
+                ConstantValue::Integer(0).into(),
+            )
+        };
+        let edges = (0..BRANCHES).flat_map(|i| {
+            let cond = PathCondition::from(predicate(i));
+            [
+                (
+                    (i * 3).into(),
+                    (i * 3 + 1).into(),
+                    ControlTransfer::Conditional(cond.clone()),
+                ),
+                (
+                    (i * 3).into(),
+                    (i * 3 + 2).into(),
+                    ControlTransfer::Conditional(!cond),
+                ),
+                (
+                    (i * 3 + 1).into(),
+                    (i * 3 + 3).into(),
+                    ControlTransfer::Unconditional,
+                ),
+                (
+                    (i * 3 + 2).into(),
+                    (i * 3 + 3).into(),
+                    ControlTransfer::Unconditional,
+                ),
+            ]
+        });
+        let cfg = ControlFlowGraph::from_edges(edges);
+        let path_conditions = cfg.bdd_path_conditions();
+        assert!(path_conditions[&(BRANCHES * 3).into()].is_tautology());
+        assert_eq!(
+            path_conditions[&7.into()].to_dnf(),
+            PathCondition::from(predicate(2))
+        );
+    }
 }
@@ -6,7 +6,7 @@ use crate::{
     jvm::{code::ProgramCounter, ConstantValue},
 };
 
-use super::PathCondition;
+use super::{BddManager, BddPathCondition, PathCondition};
 
 /// An analyzer for path conditions.
 #[derive(Debug)]
@@ -69,6 +69,75 @@ impl<N> fixed_point::Analyzer for Analyzer<'_, N> {
     }
 }
 
+/// An analyzer for path conditions represented as [`BddPathCondition`]s.
+#[derive(Debug)]
+pub struct BddAnalyzer<'a, N> {
+    cfg: &'a ControlFlowGraph<N, ControlTransfer>,
+    manager: BddManager<Predicate<Value>>,
+}
+
+impl<'a, N> BddAnalyzer<'a, N> {
+    /// Creates a new path condition analyzer with a fresh [`BddManager`].
+    #[must_use]
+    pub fn new(cfg: &'a ControlFlowGraph<N, ControlTransfer>) -> Self {
+        Self::with_manager(cfg, BddManager::new())
+    }
+
+    /// Creates a new path condition analyzer creating the conditions with `manager`.
+    #[must_use]
+    pub fn with_manager(
+        cfg: &'a ControlFlowGraph<N, ControlTransfer>,
+        manager: BddManager<Predicate<Value>>,
+    ) -> Self {
+        Self { cfg, manager }
+    }
+}
+
+impl<N> fixed_point::Analyzer for BddAnalyzer<'_, N> {
+    type Location = ProgramCounter;
+
+    type Fact = BddPathCondition<Predicate<Value>>;
+
+    type Err = Infallible;
+
+    type AffectedLocations = BTreeMap<Self::Location, Self::Fact>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        Ok(BTreeMap::from([(
+            self.cfg.entry_point(),
+            self.manager.tautology(),
+        )]))
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let Some(outgoing_edges) = self.cfg.edges_from(*location) else {
+            return Ok(BTreeMap::default());
+        };
+        let result = outgoing_edges
+            .map(|(_, dst, trx)| match trx {
+                ControlTransfer::Conditional(cond) => {
+                    let new_cond = self.manager.from_dnf(cond) & fact.clone();
+                    (dst, new_cond)
+                }
+                _ => (dst, fact.clone()),
+            })
+            .collect();
+        Ok(result)
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        Ok(current_fact.clone() | incoming_fact)
+    }
+}
+
 /// A condition.
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
 pub enum Predicate<V> {
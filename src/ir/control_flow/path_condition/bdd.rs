@@ -0,0 +1,582 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::{Debug, Display},
+    rc::Rc,
+};
+
+use super::{Conjunction, PathCondition};
+
+type NodeId = u32;
+
+const FALSE: NodeId = 0;
+const TRUE: NodeId = 1;
+/// The result of an operation that would exceed the node limit.
+/// It may or may not hold, so it is treated as a tautology when converting to a [`PathCondition`].
+const UNKNOWN: NodeId = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Operation {
+    And,
+    Or,
+}
+
+/// A decision node testing the variable `var`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Node {
+    var: u32,
+    low: NodeId,
+    high: NodeId,
+}
+
+/// The hash-consed nodes shared by the conditions created from the same [`BddManager`].
+#[derive(Debug)]
+struct Store<P> {
+    variables: Vec<P>,
+    variable_ids: BTreeMap<P, u32>,
+    nodes: Vec<Node>,
+    unique: HashMap<Node, NodeId>,
+    apply_cache: HashMap<(Operation, NodeId, NodeId), NodeId>,
+    not_cache: HashMap<NodeId, NodeId>,
+    node_limit: usize,
+    saturated: bool,
+}
+
+impl<P> Default for Store<P> {
+    fn default() -> Self {
+        let terminal = Node {
+            var: u32::MAX,
+            low: FALSE,
+            high: FALSE,
+        };
+        Self {
+            variables: Vec::new(),
+            variable_ids: BTreeMap::new(),
+            nodes: vec![terminal; 3],
+            unique: HashMap::new(),
+            apply_cache: HashMap::new(),
+            not_cache: HashMap::new(),
+            node_limit: usize::MAX,
+            saturated: false,
+        }
+    }
+}
+
+impl<P> Store<P> {
+    fn make_node(&mut self, var: u32, low: NodeId, high: NodeId) -> NodeId {
+        if low == high {
+            return low;
+        }
+        let node = Node { var, low, high };
+        if let Some(id) = self.unique.get(&node) {
+            return *id;
+        }
+        match NodeId::try_from(self.nodes.len()) {
+            Ok(id) if self.nodes.len() < self.node_limit => {
+                self.nodes.push(node);
+                self.unique.insert(node, id);
+                id
+            }
+            _ => {
+                self.saturated = true;
+                UNKNOWN
+            }
+        }
+    }
+
+    /// Creates the node for `predicate`.
+    /// A predicate and its negation share the same variable with opposite polarities.
+    fn literal(&mut self, predicate: P) -> NodeId
+    where
+        P: Ord + Clone + std::ops::Not<Output = P>,
+    {
+        let negated = !predicate.clone();
+        let (variable, positive) = if predicate <= negated {
+            (predicate, true)
+        } else {
+            (negated, false)
+        };
+        let var = if let Some(var) = self.variable_ids.get(&variable) {
+            *var
+        } else {
+            let Ok(var) = u32::try_from(self.variables.len()) else {
+                self.saturated = true;
+                return UNKNOWN;
+            };
+            self.variables.push(variable.clone());
+            self.variable_ids.insert(variable, var);
+            var
+        };
+        if positive {
+            self.make_node(var, FALSE, TRUE)
+        } else {
+            self.make_node(var, TRUE, FALSE)
+        }
+    }
+
+    fn apply(&mut self, op: Operation, lhs: NodeId, rhs: NodeId) -> NodeId {
+        let (absorbing, identity) = match op {
+            Operation::And => (FALSE, TRUE),
+            Operation::Or => (TRUE, FALSE),
+        };
+        if lhs == absorbing || rhs == absorbing {
+            return absorbing;
+        }
+        if lhs == identity || lhs == rhs {
+            return rhs;
+        }
+        if rhs == identity {
+            return lhs;
+        }
+        let key = (op, lhs.min(rhs), lhs.max(rhs));
+        if let Some(result) = self.apply_cache.get(&key) {
+            return *result;
+        }
+        let lhs_node = self.nodes[lhs as usize];
+        let rhs_node = self.nodes[rhs as usize];
+        let var = lhs_node.var.min(rhs_node.var);
+        let cofactors = |node: Node, id| {
+            if node.var == var {
+                (node.low, node.high)
+            } else {
+                (id, id)
+            }
+        };
+        let (lhs_low, lhs_high) = cofactors(lhs_node, lhs);
+        let (rhs_low, rhs_high) = cofactors(rhs_node, rhs);
+        let low = self.apply(op, lhs_low, rhs_low);
+        let high = self.apply(op, lhs_high, rhs_high);
+        let result = self.make_node(var, low, high);
+        self.apply_cache.insert(key, result);
+        result
+    }
+
+    fn not(&mut self, id: NodeId) -> NodeId {
+        match id {
+            FALSE => TRUE,
+            TRUE => FALSE,
+            UNKNOWN => UNKNOWN,
+            _ => {
+                if let Some(result) = self.not_cache.get(&id) {
+                    return *result;
+                }
+                let node = self.nodes[id as usize];
+                let low = self.not(node.low);
+                let high = self.not(node.high);
+                let result = self.make_node(node.var, low, high);
+                self.not_cache.insert(id, result);
+                result
+            }
+        }
+    }
+
+    fn collect_products(
+        &self,
+        id: NodeId,
+        path: &mut Vec<P>,
+        products: &mut BTreeSet<Conjunction<P>>,
+    ) where
+        P: Ord + Clone + std::ops::Not<Output = P>,
+    {
+        match id {
+            FALSE => {}
+            TRUE | UNKNOWN => {
+                products.insert(path.iter().cloned().collect());
+            }
+            _ => {
+                let node = self.nodes[id as usize];
+                let variable = &self.variables[node.var as usize];
+                path.push(!variable.clone());
+                self.collect_products(node.low, path, products);
+                path.pop();
+                path.push(variable.clone());
+                self.collect_products(node.high, path, products);
+                path.pop();
+            }
+        }
+    }
+}
+
+/// A factory of [`BddPathCondition`]s sharing the same hash-consed nodes.
+/// Conditions created from the same manager are canonical, i.e., equivalent conditions are
+/// equal, and can be combined in time proportional to their sizes rather than to the size of
+/// their disjunctive normal forms.
+///
+/// The number of nodes is bounded by a limit (see [`BddManager::with_node_limit`]).
+/// Once it is reached, the parts of the conditions that need new nodes become unknown, i.e., they
+/// may or may not hold, and the manager is [saturated](BddManager::is_saturated).
+/// Unknown parts are kept through negation, so the resulting conditions over-approximate the
+/// exact ones when converted with [`BddPathCondition::to_dnf`].
+///
+/// The nodes are shared through an [`Rc`], so the manager and its conditions are neither
+/// [`Send`] nor [`Sync`].
+#[derive(Debug)]
+pub struct BddManager<P> {
+    store: Rc<RefCell<Store<P>>>,
+}
+
+impl<P> Clone for BddManager<P> {
+    fn clone(&self) -> Self {
+        Self {
+            store: Rc::clone(&self.store),
+        }
+    }
+}
+
+impl<P> Default for BddManager<P> {
+    fn default() -> Self {
+        Self {
+            store: Rc::default(),
+        }
+    }
+}
+
+impl<P> BddManager<P> {
+    /// Creates a new manager.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new manager allocating at most `node_limit` nodes.
+    #[must_use]
+    pub fn with_node_limit(node_limit: usize) -> Self {
+        let store = Store {
+            node_limit,
+            ..Store::default()
+        };
+        Self {
+            store: Rc::new(RefCell::new(store)),
+        }
+    }
+
+    /// Checks whether some operation exceeded the node limit.
+    /// If so, the conditions created since then may be over-approximations.
+    #[must_use]
+    pub fn is_saturated(&self) -> bool {
+        self.store.borrow().saturated
+    }
+
+    fn condition(&self, root: NodeId) -> BddPathCondition<P> {
+        BddPathCondition {
+            store: Rc::clone(&self.store),
+            root,
+        }
+    }
+
+    /// Creates a tautology.
+    #[must_use]
+    pub fn tautology(&self) -> BddPathCondition<P> {
+        self.condition(TRUE)
+    }
+
+    /// Creates a contradiction.
+    #[must_use]
+    pub fn contradiction(&self) -> BddPathCondition<P> {
+        self.condition(FALSE)
+    }
+
+    /// Creates a condition consisting of a single predicate.
+    #[must_use]
+    pub fn predicate(&self, predicate: P) -> BddPathCondition<P>
+    where
+        P: Ord + Clone + std::ops::Not<Output = P>,
+    {
+        let root = self.store.borrow_mut().literal(predicate);
+        self.condition(root)
+    }
+
+    /// Creates a conjunction of predicates.
+    #[must_use]
+    pub fn conjunction_of(&self, predicates: impl IntoIterator<Item = P>) -> BddPathCondition<P>
+    where
+        P: Ord + Clone + std::ops::Not<Output = P>,
+    {
+        let mut store = self.store.borrow_mut();
+        let root = predicates.into_iter().fold(TRUE, |acc, predicate| {
+            let literal = store.literal(predicate);
+            store.apply(Operation::And, acc, literal)
+        });
+        drop(store);
+        self.condition(root)
+    }
+
+    /// Converts a [`PathCondition`] in disjunctive normal form.
+    #[must_use]
+    pub fn from_dnf(&self, path_condition: &PathCondition<P>) -> BddPathCondition<P>
+    where
+        P: Ord + Clone + std::ops::Not<Output = P>,
+    {
+        path_condition
+            .conjunctions()
+            .map(|it| self.conjunction_of(it.iter().cloned()))
+            .fold(self.contradiction(), |acc, it| acc | it)
+    }
+
+    /// Returns the number of nodes allocated by this manager.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        self.store.borrow().nodes.len()
+    }
+}
+
+/// A path condition represented as a reduced ordered binary decision diagram.
+/// It supports the same operations as [`PathCondition`] and converts to it for display.
+/// It shares the nodes of its [`BddManager`] through an [`Rc`], so it is neither [`Send`] nor
+/// [`Sync`].
+pub struct BddPathCondition<P> {
+    store: Rc<RefCell<Store<P>>>,
+    root: NodeId,
+}
+
+impl<P> BddPathCondition<P> {
+    /// Returns the manager that created this condition.
+    #[must_use]
+    pub fn manager(&self) -> BddManager<P> {
+        BddManager {
+            store: Rc::clone(&self.store),
+        }
+    }
+
+    /// Checks whether the condition always holds.
+    #[must_use]
+    pub fn is_tautology(&self) -> bool {
+        self.root == TRUE
+    }
+
+    /// Checks whether the condition never holds.
+    #[must_use]
+    pub fn is_contradiction(&self) -> bool {
+        self.root == FALSE
+    }
+
+    /// Converts the condition to disjunctive normal form.
+    /// The conjunctions correspond to the paths in the diagram, so they are mutually exclusive.
+    #[must_use]
+    pub fn to_dnf(&self) -> PathCondition<P>
+    where
+        P: Ord + Clone + std::ops::Not<Output = P>,
+    {
+        let mut products = BTreeSet::new();
+        self.store
+            .borrow()
+            .collect_products(self.root, &mut Vec::new(), &mut products);
+        PathCondition { products }
+    }
+
+    fn combine(self, rhs: &Self, op: Operation) -> Self {
+        assert!(
+            Rc::ptr_eq(&self.store, &rhs.store),
+            "The conditions are created by different managers"
+        );
+        let root = self.store.borrow_mut().apply(op, self.root, rhs.root);
+        Self { root, ..self }
+    }
+}
+
+impl<P> Clone for BddPathCondition<P> {
+    fn clone(&self) -> Self {
+        Self {
+            store: Rc::clone(&self.store),
+            root: self.root,
+        }
+    }
+}
+
+impl<P> PartialEq for BddPathCondition<P> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.store, &other.store) && self.root == other.root
+    }
+}
+
+impl<P> Eq for BddPathCondition<P> {}
+
+impl<P> PartialOrd for BddPathCondition<P> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<P> Ord for BddPathCondition<P> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        Rc::as_ptr(&self.store)
+            .cast::<()>()
+            .cmp(&Rc::as_ptr(&other.store).cast())
+            .then(self.root.cmp(&other.root))
+    }
+}
+
+/// # Panics
+/// Panics if the conditions are created by different managers.
+impl<P> std::ops::BitAnd for BddPathCondition<P> {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        self.combine(&rhs, Operation::And)
+    }
+}
+
+/// # Panics
+/// Panics if the conditions are created by different managers.
+impl<P> std::ops::BitOr for BddPathCondition<P> {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.combine(&rhs, Operation::Or)
+    }
+}
+
+impl<P> std::ops::Not for BddPathCondition<P> {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        let root = self.store.borrow_mut().not(self.root);
+        Self { root, ..self }
+    }
+}
+
+impl<P> Debug for BddPathCondition<P>
+where
+    P: Debug + Ord + Clone + std::ops::Not<Output = P>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("BddPathCondition")
+            .field(&self.to_dnf())
+            .finish()
+    }
+}
+
+impl<P> Display for BddPathCondition<P>
+where
+    P: Display + Ord + Clone + std::ops::Not<Output = P>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.to_dnf())
+    }
+}
+
+impl<P> std::ops::Not for PathCondition<P>
+where
+    P: Ord + Clone + std::ops::Not<Output = P>,
+{
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        let manager = BddManager::new();
+        (!manager.from_dnf(&self)).to_dnf()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use proptest::collection::btree_set;
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord, proptest_derive::Arbitrary)]
+    struct TestPredicate(#[proptest(strategy = "0u32..8")] u32, bool);
+
+    impl std::ops::Not for TestPredicate {
+        type Output = Self;
+
+        fn not(self) -> Self::Output {
+            Self(self.0, !self.1)
+        }
+    }
+
+    fn evaluate(cond: &PathCondition<TestPredicate>, value_map: &HashMap<u32, bool>) -> bool {
+        cond.conjunctions()
+            .any(|product| product.iter().all(|it| value_map[&it.0] == it.1))
+    }
+
+    fn arb_test_cond() -> impl Strategy<Value = PathCondition<TestPredicate>> {
+        btree_set(
+            btree_set(any::<TestPredicate>(), 1..5).prop_map(Conjunction),
+            0..5,
+        )
+        .prop_map(|products| PathCondition { products })
+    }
+
+    fn arb_pred_values() -> impl Strategy<Value = HashMap<u32, bool>> {
+        proptest::collection::vec(any::<bool>(), 8).prop_map(|values| (0..).zip(values).collect())
+    }
+
+    proptest! {
+        #[test]
+        fn operations(
+            lhs in arb_test_cond(),
+            rhs in arb_test_cond(),
+            pred_values in arb_pred_values(),
+        ) {
+            let manager = BddManager::new();
+            let lhs_bdd = manager.from_dnf(&lhs);
+            let rhs_bdd = manager.from_dnf(&rhs);
+            let lhs_eval = evaluate(&lhs, &pred_values);
+            let rhs_eval = evaluate(&rhs, &pred_values);
+
+            assert_eq!(evaluate(&lhs_bdd.to_dnf(), &pred_values), lhs_eval);
+            assert_eq!(
+                evaluate(&(lhs_bdd.clone() & rhs_bdd.clone()).to_dnf(), &pred_values),
+                lhs_eval && rhs_eval
+            );
+            assert_eq!(
+                evaluate(&(lhs_bdd.clone() | rhs_bdd.clone()).to_dnf(), &pred_values),
+                lhs_eval || rhs_eval
+            );
+            assert_eq!(evaluate(&(!lhs.clone()), &pred_values), !lhs_eval);
+            assert_eq!(lhs_bdd.clone() & rhs_bdd.clone(), rhs_bdd & lhs_bdd.clone());
+            assert_eq!(manager.from_dnf(&lhs), lhs_bdd);
+        }
+    }
+
+    #[test]
+    fn saturation() {
+        let exact = BddManager::new();
+        let limited = BddManager::with_node_limit(8);
+        let condition = |manager: &BddManager<TestPredicate>| {
+            (0..4)
+                .map(|it| {
+                    manager.conjunction_of([TestPredicate(it, true), TestPredicate(it + 4, true)])
+                })
+                .fold(manager.contradiction(), |acc, it| acc | it)
+        };
+        let exact_condition = condition(&exact);
+        let limited_condition = condition(&limited);
+        assert!(!exact.is_saturated());
+        assert!(limited.is_saturated());
+        assert!(limited.node_count() <= 8);
+
+        let (exact_dnf, limited_dnf) = (exact_condition.to_dnf(), limited_condition.to_dnf());
+        let (exact_negated, limited_negated) =
+            ((!exact_condition).to_dnf(), (!limited_condition).to_dnf());
+        for bits in 0..=u8::MAX {
+            let values = (0..8).map(|it| (it, bits & (1 << it) != 0)).collect();
+            assert!(!evaluate(&exact_dnf, &values) || evaluate(&limited_dnf, &values));
+            assert!(!evaluate(&exact_negated, &values) || evaluate(&limited_negated, &values));
+        }
+    }
+
+    #[test]
+    fn canonical() {
+        let manager = BddManager::new();
+        let a = manager.predicate(TestPredicate(0, true));
+        let not_a = manager.predicate(TestPredicate(0, false));
+        let b = manager.predicate(TestPredicate(1, true));
+        assert_eq!(!a.clone(), not_a);
+        assert!((a.clone() | not_a.clone()).is_tautology());
+        assert!((a.clone() & not_a.clone()).is_contradiction());
+        assert_eq!((a.clone() & b.clone()) | (not_a & b.clone()), b);
+        assert_eq!(
+            (a.clone() & b.clone()).to_dnf(),
+            PathCondition::conjunction_of([TestPredicate(0, true), TestPredicate(1, true)])
+        );
+        assert_eq!(manager.tautology().to_dnf(), PathCondition::tautology());
+        assert_eq!(
+            manager.contradiction().to_dnf(),
+            PathCondition::contradiction()
+        );
+    }
+}
@@ -4,10 +4,14 @@ use std::{collections::BTreeSet, fmt::Display};
 use itertools::Itertools;
 
 mod analyzer;
+mod bdd;
 
 pub use analyzer::*;
+pub use bdd::*;
 
 /// Path condition in disjunctive normal form.
+/// The number of conjunctions may grow exponentially with the number of branches; see
+/// [`BddPathCondition`] for a compact representation.
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
 pub struct PathCondition<P> {
     /// The clauses in the disjunctive normal form.
@@ -55,6 +59,11 @@ impl<P> Conjunction<P> {
     pub fn tautology() -> Self {
         Self(BTreeSet::default())
     }
+
+    /// Returns an iterator over the predicates in the conjunction.
+    pub fn iter(&self) -> impl Iterator<Item = &P> {
+        self.0.iter()
+    }
 }
 
 impl<P: Display> Display for Conjunction<P> {
@@ -132,6 +141,11 @@ impl<P> PathCondition<P> {
         Self { products }
     }
 
+    /// Returns an iterator over the conjunctions in the disjunctive normal form.
+    pub fn conjunctions(&self) -> impl Iterator<Item = &Conjunction<P>> {
+        self.products.iter()
+    }
+
     /// Simplifies the path condition.
     pub fn simplify(&mut self)
     where
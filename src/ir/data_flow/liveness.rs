@@ -0,0 +1,217 @@
+//! Liveness analysis on Moka IR.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::Infallible,
+};
+
+use crate::{
+    analysis::fixed_point::{self, IterationOrder, Solver},
+    ir::{Identifier, MokaIRMethod},
+    jvm::code::ProgramCounter,
+};
+
+/// The [`Identifier`]s live before and after each instruction of a method, i.e., the ones that
+/// may be used later without being redefined.
+/// The values in a Phi operand are treated as used by the instruction holding the operand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Liveness {
+    live_in: BTreeMap<ProgramCounter, BTreeSet<Identifier>>,
+    live_out: BTreeMap<ProgramCounter, BTreeSet<Identifier>>,
+}
+
+/// The register pressure of a method, i.e., the number of values live at the same time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterPressure {
+    /// The maximum number of [`Identifier`]s simultaneously live.
+    pub max_live: usize,
+    /// The instructions before or after which [`RegisterPressure::max_live`] values are live.
+    pub peaks: BTreeSet<ProgramCounter>,
+}
+
+impl Liveness {
+    /// Computes the liveness of the [`Identifier`]s in `method`.
+    #[must_use]
+    pub fn new(method: &MokaIRMethod) -> Self {
+        let mut predecessors: BTreeMap<_, BTreeSet<_>> = BTreeMap::new();
+        for (src, dst, _) in method.control_flow_graph.edges() {
+            predecessors.entry(dst).or_default().insert(src);
+        }
+        // Visiting the successors first converges faster for a backward analysis.
+        let mut order = method.control_flow_graph.reverse_postorder();
+        order.reverse();
+        let solver = Solver {
+            order: IterationOrder::Given(order),
+            ..Solver::default()
+        };
+        let mut analyzer = LivenessAnalyzer {
+            method,
+            predecessors,
+        };
+        let Ok(result) = fixed_point::Analyzer::analyze_with(&mut analyzer, &solver);
+        let live_out = result.facts;
+        let live_in = live_out
+            .iter()
+            .map(|(pc, live_out)| (*pc, analyzer.live_in(*pc, live_out)))
+            .collect();
+        Self { live_in, live_out }
+    }
+
+    /// Returns the [`Identifier`]s live before the instruction at `pc`.
+    #[must_use]
+    pub fn live_in(&self, pc: ProgramCounter) -> Option<&BTreeSet<Identifier>> {
+        self.live_in.get(&pc)
+    }
+
+    /// Returns the [`Identifier`]s live after the instruction at `pc`.
+    #[must_use]
+    pub fn live_out(&self, pc: ProgramCounter) -> Option<&BTreeSet<Identifier>> {
+        self.live_out.get(&pc)
+    }
+
+    /// Checks whether `id` is live after the instruction at `pc`, i.e., whether the local
+    /// variable slot holding it can not be reused there.
+    #[must_use]
+    pub fn is_live_after(&self, id: &Identifier, pc: ProgramCounter) -> bool {
+        self.live_out(pc).is_some_and(|it| it.contains(id))
+    }
+
+    /// Returns the number of [`Identifier`]s live before or after the instruction at `pc`,
+    /// whichever is larger.
+    #[must_use]
+    pub fn pressure_at(&self, pc: ProgramCounter) -> usize {
+        let live_in = self.live_in(pc).map_or(0, BTreeSet::len);
+        let live_out = self.live_out(pc).map_or(0, BTreeSet::len);
+        live_in.max(live_out)
+    }
+
+    /// Summarizes the register pressure of the method.
+    #[must_use]
+    pub fn register_pressure(&self) -> RegisterPressure {
+        let max_live = self
+            .live_in
+            .keys()
+            .map(|pc| self.pressure_at(*pc))
+            .max()
+            .unwrap_or_default();
+        let peaks = self
+            .live_in
+            .keys()
+            .copied()
+            .filter(|pc| max_live > 0 && self.pressure_at(*pc) == max_live)
+            .collect();
+        RegisterPressure { max_live, peaks }
+    }
+}
+
+/// Propagates the live-out sets of the instructions backward along the control flow graph.
+struct LivenessAnalyzer<'a> {
+    method: &'a MokaIRMethod,
+    predecessors: BTreeMap<ProgramCounter, BTreeSet<ProgramCounter>>,
+}
+
+impl LivenessAnalyzer<'_> {
+    fn live_in(&self, pc: ProgramCounter, live_out: &BTreeSet<Identifier>) -> BTreeSet<Identifier> {
+        let Some(insn) = self.method.instructions.get(&pc) else {
+            return live_out.clone();
+        };
+        let mut live_in = live_out.clone();
+        if let Some(def) = insn.def() {
+            live_in.remove(&def.into());
+        }
+        live_in.extend(insn.uses());
+        live_in
+    }
+}
+
+impl fixed_point::Analyzer for LivenessAnalyzer<'_> {
+    type Location = ProgramCounter;
+    type Fact = BTreeSet<Identifier>;
+    type Err = Infallible;
+    type AffectedLocations = Vec<(Self::Location, Self::Fact)>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        // Every instruction is seeded so that the ones not reaching an exit are covered.
+        Ok(self
+            .method
+            .instructions
+            .iter()
+            .map(|(pc, _)| (*pc, BTreeSet::new()))
+            .collect())
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let live_in = self.live_in(*location, fact);
+        Ok(self
+            .predecessors
+            .get(location)
+            .into_iter()
+            .flatten()
+            .map(|pred| (*pred, live_in.clone()))
+            .collect())
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        Ok(current_fact.union(&incoming_fact).copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{LocalValue, MokaIRMethodExt},
+        jvm::interop::jasmin,
+    };
+
+    #[test]
+    fn live_sets_and_pressure() {
+        let class = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static run(II)I
+                .limit stack 2
+                .limit locals 2
+                iload_0
+                iload_1
+                iadd
+                iload_0
+                imul
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let method = class.methods[0].brew().unwrap();
+        let liveness = Liveness::new(&method);
+        let sum = Identifier::Local(LocalValue::new(2));
+        let product = Identifier::Local(LocalValue::new(4));
+        assert_eq!(
+            liveness.live_in(0.into()),
+            Some(&BTreeSet::from([Identifier::Arg(0), Identifier::Arg(1)]))
+        );
+        assert_eq!(
+            liveness.live_out(2.into()),
+            Some(&BTreeSet::from([Identifier::Arg(0), sum]))
+        );
+        assert!(!liveness.is_live_after(&Identifier::Arg(1), 2.into()));
+        assert_eq!(liveness.live_in(5.into()), Some(&BTreeSet::from([product])));
+        assert_eq!(liveness.live_out(5.into()), Some(&BTreeSet::new()));
+        assert_eq!(
+            liveness.register_pressure(),
+            RegisterPressure {
+                max_live: 2,
+                peaks: (0..=4u16).map(ProgramCounter::from).collect(),
+            }
+        );
+    }
+}
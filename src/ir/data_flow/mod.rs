@@ -1,5 +1,8 @@
 //! Data flow analysis.
 
+pub mod liveness;
+pub mod value_numbering;
+
 use std::collections::BTreeSet;
 
 use itertools::Itertools;
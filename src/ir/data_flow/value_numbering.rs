@@ -0,0 +1,278 @@
+//! Value numbering on Moka IR.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+
+use crate::{
+    ir::{
+        expression::{ArrayOperation, Expression, MathOperation},
+        Identifier, MokaIRMethod, MokaInstruction, Operand,
+    },
+    jvm::code::ProgramCounter,
+};
+
+/// The partition of the [`Identifier`]s of a method into classes of equivalent values, where the
+/// values defined by the same operation over congruent operands are equivalent.
+///
+/// Only the operations whose results are determined by their operands (constants, arithmetic,
+/// conversions, and array lengths) are compared, and the operands of commutative operations are
+/// compared regardless of their order.
+/// The instructions are visited in reverse postorder, and the values flowing along back edges are
+/// conservatively considered distinct.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValueNumbering {
+    numbers: BTreeMap<Identifier, usize>,
+    /// The members of each class, with the first one visited first.
+    classes: Vec<Vec<Identifier>>,
+    /// The locations of the definitions of the values.
+    def_sites: BTreeMap<Identifier, ProgramCounter>,
+}
+
+impl ValueNumbering {
+    /// Computes the value numbers of the [`Identifier`]s in `method`.
+    #[must_use]
+    pub fn new(method: &MokaIRMethod) -> Self {
+        let mut order = method.control_flow_graph.reverse_postorder();
+        let visited: BTreeSet<_> = order.iter().copied().collect();
+        order.extend(
+            method
+                .instructions
+                .iter()
+                .map(|(pc, _)| *pc)
+                .filter(|pc| !visited.contains(pc)),
+        );
+        let mut numbering = Self::default();
+        // The normalized expressions and their value numbers.
+        let mut expressions: Vec<(Expression, usize)> = Vec::new();
+        for pc in order {
+            let Some(insn) = method.instructions.get(&pc) else {
+                continue;
+            };
+            let Some(value) = insn.def() else {
+                continue;
+            };
+            let value = Identifier::from(value);
+            numbering.def_sites.insert(value, pc);
+            if numbering.numbers.contains_key(&value) {
+                // Used along a back edge before being visited.
+                continue;
+            }
+            let number = match insn {
+                MokaInstruction::Definition { expr, .. } if is_pure(expr) => {
+                    let expr = numbering.normalize(expr);
+                    if let Some((_, number)) = expressions.iter().find(|(it, _)| *it == expr) {
+                        Some(*number)
+                    } else {
+                        // Normalizing may have numbered the value already if a loop-carried
+                        // operand refers to it.
+                        expressions.push((expr, numbering.number_of(value)));
+                        None
+                    }
+                }
+                MokaInstruction::Phi { incoming, .. } => {
+                    let numbers: BTreeSet<_> = incoming
+                        .values()
+                        .map(|it| numbering.number_of(*it))
+                        .collect();
+                    numbers.into_iter().exactly_one().ok()
+                }
+                _ => None,
+            };
+            match number {
+                Some(number) if !numbering.numbers.contains_key(&value) => {
+                    numbering.numbers.insert(value, number);
+                    numbering.classes[number].push(value);
+                }
+                _ => {
+                    numbering.number_of(value);
+                }
+            }
+        }
+        numbering
+    }
+
+    /// Returns the value number of `id`, or [`None`] if it does not appear in the method.
+    #[must_use]
+    pub fn value_number(&self, id: &Identifier) -> Option<usize> {
+        self.numbers.get(id).copied()
+    }
+
+    /// Checks whether `lhs` and `rhs` are known to hold the same value.
+    #[must_use]
+    pub fn congruent(&self, lhs: &Identifier, rhs: &Identifier) -> bool {
+        lhs == rhs
+            || self
+                .value_number(lhs)
+                .is_some_and(|it| Some(it) == self.value_number(rhs))
+    }
+
+    /// Returns the members of the class of `id`, with the one visited first first.
+    #[must_use]
+    pub fn class_of(&self, id: &Identifier) -> Option<&[Identifier]> {
+        self.value_number(id).map(|it| self.classes[it].as_slice())
+    }
+
+    /// Returns the member of the class of `id` visited first, which is the candidate to replace
+    /// the other members.
+    #[must_use]
+    pub fn leader(&self, id: &Identifier) -> Option<Identifier> {
+        self.class_of(id).and_then(|it| it.first()).copied()
+    }
+
+    /// Returns the location where `id` is defined, or [`None`] if it is not defined by an
+    /// instruction (e.g., an argument).
+    #[must_use]
+    pub fn def_site(&self, id: &Identifier) -> Option<ProgramCounter> {
+        self.def_sites.get(id).copied()
+    }
+
+    /// Returns the classes with more than one member, i.e., the redundant computations.
+    pub fn redundant_classes(&self) -> impl Iterator<Item = &[Identifier]> {
+        self.classes
+            .iter()
+            .filter(|it| it.len() > 1)
+            .map(Vec::as_slice)
+    }
+
+    fn number_of(&mut self, id: Identifier) -> usize {
+        *self.numbers.entry(id).or_insert_with(|| {
+            self.classes.push(vec![id]);
+            self.classes.len() - 1
+        })
+    }
+
+    /// Replaces the operands of `expr` with the leaders of their classes and orders the operands
+    /// of commutative operations.
+    fn normalize(&mut self, expr: &Expression) -> Expression {
+        let mut expr = expr.clone();
+        for operand in expr.operands_mut() {
+            let leaders: BTreeSet<_> = operand
+                .iter()
+                .map(|it| {
+                    let number = self.number_of(*it);
+                    self.classes[number][0]
+                })
+                .collect();
+            *operand = match leaders.iter().exactly_one() {
+                Ok(leader) => Operand::Just(*leader),
+                Err(_) => Operand::Phi(leaders),
+            };
+        }
+        if let Expression::Math(
+            MathOperation::Add(lhs, rhs)
+            | MathOperation::Multiply(lhs, rhs)
+            | MathOperation::BitwiseAnd(lhs, rhs)
+            | MathOperation::BitwiseOr(lhs, rhs)
+            | MathOperation::BitwiseXor(lhs, rhs),
+        ) = &mut expr
+        {
+            if rhs < lhs {
+                std::mem::swap(lhs, rhs);
+            }
+        }
+        expr
+    }
+}
+
+/// Checks whether the value of `expr` is determined by its operands.
+fn is_pure(expr: &Expression) -> bool {
+    matches!(
+        expr,
+        Expression::Const(_)
+            | Expression::Math(_)
+            | Expression::Conversion(_)
+            | Expression::Array(ArrayOperation::Length { .. })
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{LocalValue, MokaIRMethodExt},
+        jvm::interop::jasmin,
+    };
+
+    #[test]
+    fn congruent_expressions() {
+        let class = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static run(II)I
+                .limit stack 4
+                .limit locals 2
+                iload_0
+                iload_1
+                iadd
+                iload_1
+                iload_0
+                iadd
+                imul
+                iload_0
+                iload_1
+                isub
+                iload_1
+                iload_0
+                isub
+                iadd
+                iadd
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let method = class.methods[0].brew().unwrap();
+        let numbering = ValueNumbering::new(&method);
+        let local = |it| Identifier::Local(LocalValue::new(it));
+        assert!(numbering.congruent(&local(2), &local(5)));
+        assert!(!numbering.congruent(&local(9), &local(12)));
+        assert_eq!(numbering.leader(&local(5)), Some(local(2)));
+        assert_eq!(numbering.def_site(&local(5)), Some(5.into()));
+        assert_eq!(
+            numbering.redundant_classes().collect::<Vec<_>>(),
+            [[local(2), local(5)].as_slice()]
+        );
+    }
+
+    #[test]
+    fn loop_carried_operands() {
+        let class = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static count(I)I
+                .limit stack 4
+                .limit locals 2
+                iconst_0
+                istore_1
+            Loop:
+                iload_1
+                iload_0
+                if_icmpge Done
+                iload_1
+                iconst_1
+                iadd
+                iload_1
+                iconst_1
+                iadd
+                pop
+                istore_1
+                goto Loop
+            Done:
+                iload_1
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let method = class.methods[0].brew().unwrap();
+        let numbering = ValueNumbering::new(&method);
+        let local = |it| Identifier::Local(LocalValue::new(it));
+        // `i + 1` refers to itself through the value of `i` carried along the back edge.
+        assert!(numbering.congruent(&local(9), &local(12)));
+        assert_eq!(numbering.leader(&local(12)), Some(local(9)));
+        assert!(!numbering.congruent(&local(0), &local(9)));
+    }
+}
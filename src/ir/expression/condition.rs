@@ -1,4 +1,4 @@
-use std::collections::BTreeSet;
+use std::{collections::BTreeSet, ops::Not};
 
 use crate::ir::{Identifier, Operand};
 
@@ -70,6 +70,70 @@ impl Condition {
             | Self::IsNonPositive(a) => a.iter().copied().collect(),
         }
     }
+
+    /// Evaluates the condition on integers if `value_of` gives the values of its operands.
+    /// Returns [`None`] if any of the values is unknown or the condition tests for `null`.
+    pub fn evaluate(&self, value_of: impl Fn(&Operand) -> Option<i32>) -> Option<bool> {
+        let result = match self {
+            Self::Equal(a, b) => value_of(a)? == value_of(b)?,
+            Self::NotEqual(a, b) => value_of(a)? != value_of(b)?,
+            Self::LessThan(a, b) => value_of(a)? < value_of(b)?,
+            Self::LessThanOrEqual(a, b) => value_of(a)? <= value_of(b)?,
+            Self::GreaterThan(a, b) => value_of(a)? > value_of(b)?,
+            Self::GreaterThanOrEqual(a, b) => value_of(a)? >= value_of(b)?,
+            Self::IsZero(a) => value_of(a)? == 0,
+            Self::IsNonZero(a) => value_of(a)? != 0,
+            Self::IsPositive(a) => value_of(a)? > 0,
+            Self::IsNegative(a) => value_of(a)? < 0,
+            Self::IsNonPositive(a) => value_of(a)? <= 0,
+            Self::IsNonNegative(a) => value_of(a)? >= 0,
+            Self::IsNull(_) | Self::IsNotNull(_) => return None,
+        };
+        Some(result)
+    }
+
+    /// Returns mutable references to the [`Operand`]s used by the condition.
+    pub(crate) fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Self::Equal(a, b)
+            | Self::NotEqual(a, b)
+            | Self::LessThan(a, b)
+            | Self::LessThanOrEqual(a, b)
+            | Self::GreaterThan(a, b)
+            | Self::GreaterThanOrEqual(a, b) => vec![a, b],
+            Self::IsNull(a)
+            | Self::IsNotNull(a)
+            | Self::IsZero(a)
+            | Self::IsNonZero(a)
+            | Self::IsPositive(a)
+            | Self::IsNegative(a)
+            | Self::IsNonNegative(a)
+            | Self::IsNonPositive(a) => vec![a],
+        }
+    }
+}
+
+impl Not for Condition {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Equal(a, b) => Self::NotEqual(a, b),
+            Self::NotEqual(a, b) => Self::Equal(a, b),
+            Self::LessThan(a, b) => Self::GreaterThanOrEqual(a, b),
+            Self::LessThanOrEqual(a, b) => Self::GreaterThan(a, b),
+            Self::GreaterThan(a, b) => Self::LessThanOrEqual(a, b),
+            Self::GreaterThanOrEqual(a, b) => Self::LessThan(a, b),
+            Self::IsNull(a) => Self::IsNotNull(a),
+            Self::IsNotNull(a) => Self::IsNull(a),
+            Self::IsZero(a) => Self::IsNonZero(a),
+            Self::IsNonZero(a) => Self::IsZero(a),
+            Self::IsPositive(a) => Self::IsNonPositive(a),
+            Self::IsNonPositive(a) => Self::IsPositive(a),
+            Self::IsNegative(a) => Self::IsNonNegative(a),
+            Self::IsNonNegative(a) => Self::IsNegative(a),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -139,5 +203,19 @@ mod tests {
             let is_non_positive = Condition::IsNonPositive(arg1.clone());
             check_uses(&is_non_positive, &arg1_ids);
         }
+
+        #[test]
+        fn negation(
+            arg1 in arb_argument(),
+            arg2 in arb_argument(),
+        ) {
+            let lt = Condition::LessThan(arg1.clone(), arg2.clone());
+            prop_assert_eq!(!lt.clone(), Condition::GreaterThanOrEqual(arg1.clone(), arg2));
+            prop_assert_eq!(!!lt.clone(), lt);
+
+            let is_null = Condition::IsNull(arg1.clone());
+            prop_assert_eq!(!is_null.clone(), Condition::IsNotNull(arg1));
+            prop_assert_eq!(!!is_null.clone(), is_null);
+        }
     }
 }
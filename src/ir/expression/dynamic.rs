@@ -0,0 +1,318 @@
+use itertools::Itertools;
+
+use crate::{
+    ir::Operand,
+    jvm::{
+        class::{BootstrapMethod, MethodHandle},
+        references::{ClassRef, MethodRef},
+        ConstantValue, JavaString,
+    },
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::Expression;
+
+const LAMBDA_METAFACTORY: &str = "java/lang/invoke/LambdaMetafactory";
+const STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+
+/// The tag in a string concatenation recipe that refers to an argument.
+const ARGUMENT_TAG: char = '\u{1}';
+/// The tag in a string concatenation recipe that refers to a constant.
+const CONSTANT_TAG: char = '\u{2}';
+
+/// An [`Expression::Closure`] whose bootstrap method is decoded into a higher-level form.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum DynamicCall {
+    /// A lambda expression or a method reference created by `LambdaMetafactory`.
+    #[display("{_0}")]
+    Lambda(Box<Lambda>),
+    /// A string concatenation created by `StringConcatFactory`.
+    #[display("{_0}")]
+    StringConcat(StringConcat),
+}
+
+/// A lambda expression or a method reference.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[display(
+    "lambda {}::{} -> {}::{}({})",
+    interface,
+    method_name,
+    implementation.owner,
+    implementation.name,
+    captures.iter().map(ToString::to_string).join(", "),
+)]
+pub struct Lambda {
+    /// The functional interface implemented by the lambda.
+    pub interface: ClassRef,
+    /// The name of the method of the functional interface.
+    pub method_name: String,
+    /// The descriptor of the method of the functional interface after type erasure.
+    pub erased_descriptor: MethodDescriptor,
+    /// The descriptor of the method of the functional interface with the type arguments
+    /// instantiated.
+    pub instantiated_descriptor: MethodDescriptor,
+    /// The method handle implementing the lambda.
+    pub implementation_handle: MethodHandle,
+    /// The method implementing the lambda.
+    pub implementation: MethodRef,
+    /// The arguments captured by the lambda, which are passed to the implementation before the
+    /// arguments of the functional interface method.
+    pub captures: Vec<Operand>,
+}
+
+/// A string concatenation.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+#[display("concat({})", recipe.iter().map(ToString::to_string).join(", "))]
+pub struct StringConcat {
+    /// The parts of the string in order.
+    pub recipe: Vec<ConcatElement>,
+}
+
+/// A part of a [`StringConcat`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum ConcatElement {
+    /// A literal string in the recipe.
+    #[display("{_0:?}")]
+    Literal(String),
+    /// A value passed to the call site.
+    #[display("{_0}")]
+    Argument(Operand),
+    /// A constant passed to the bootstrap method.
+    #[display("{_0}")]
+    Constant(ConstantValue),
+}
+
+impl DynamicCall {
+    /// Decodes `expr` if it is an [`Expression::Closure`] created by `LambdaMetafactory` or
+    /// `StringConcatFactory`.
+    /// `bootstrap_methods` are the bootstrap methods of the class containing the expression.
+    /// Returns [`None`] for other expressions or if the arguments of the bootstrap method are
+    /// malformed.
+    #[must_use]
+    pub fn decode(expr: &Expression, bootstrap_methods: &[BootstrapMethod]) -> Option<Self> {
+        let Expression::Closure {
+            name,
+            captures,
+            bootstrap_method_index,
+            closure_descriptor,
+        } = expr
+        else {
+            return None;
+        };
+        let BootstrapMethod { method, arguments } =
+            bootstrap_methods.get(usize::from(*bootstrap_method_index))?;
+        let MethodHandle::RefInvokeStatic(bootstrap) = method else {
+            return None;
+        };
+        match (
+            bootstrap.owner.binary_name.as_ref(),
+            bootstrap.name.as_ref(),
+        ) {
+            (LAMBDA_METAFACTORY, "metafactory" | "altMetafactory") => {
+                Lambda::decode(name, captures, closure_descriptor, arguments)
+                    .map(|it| Self::Lambda(Box::new(it)))
+            }
+            (STRING_CONCAT_FACTORY, "makeConcat") => Some(Self::StringConcat(StringConcat {
+                recipe: captures
+                    .iter()
+                    .cloned()
+                    .map(ConcatElement::Argument)
+                    .collect(),
+            })),
+            (STRING_CONCAT_FACTORY, "makeConcatWithConstants") => {
+                StringConcat::decode(captures, arguments).map(Self::StringConcat)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Lambda {
+    fn decode(
+        name: &str,
+        captures: &[Operand],
+        closure_descriptor: &MethodDescriptor,
+        arguments: &[ConstantValue],
+    ) -> Option<Self> {
+        // `altMetafactory` takes additional flags after the same three leading arguments.
+        let [ConstantValue::MethodType(erased_descriptor), ConstantValue::Handle(handle), ConstantValue::MethodType(instantiated_descriptor), ..] =
+            arguments
+        else {
+            return None;
+        };
+        let implementation = match handle {
+            MethodHandle::RefInvokeVirtual(method)
+            | MethodHandle::RefInvokeStatic(method)
+            | MethodHandle::RefInvokeSpecial(method)
+            | MethodHandle::RefNewInvokeSpecial(method)
+            | MethodHandle::RefInvokeInterface(method) => method.clone(),
+            _ => return None,
+        };
+        let interface = match &closure_descriptor.return_type {
+            ReturnType::Some(FieldType::Object(interface)) => interface.clone(),
+            _ => return None,
+        };
+        Some(Self {
+            interface,
+            method_name: name.to_owned(),
+            erased_descriptor: erased_descriptor.clone(),
+            instantiated_descriptor: instantiated_descriptor.clone(),
+            implementation_handle: handle.clone(),
+            implementation,
+            captures: captures.to_vec(),
+        })
+    }
+}
+
+impl StringConcat {
+    fn decode(captures: &[Operand], arguments: &[ConstantValue]) -> Option<Self> {
+        let (ConstantValue::String(JavaString::Utf8(recipe)), constants) =
+            arguments.split_first()?
+        else {
+            return None;
+        };
+        let mut captures = captures.iter();
+        let mut constants = constants.iter();
+        let mut elements = Vec::new();
+        let mut literal = String::new();
+        for ch in recipe.chars() {
+            let element = match ch {
+                ARGUMENT_TAG => ConcatElement::Argument(captures.next()?.clone()),
+                CONSTANT_TAG => ConcatElement::Constant(constants.next()?.clone()),
+                _ => {
+                    literal.push(ch);
+                    continue;
+                }
+            };
+            if !literal.is_empty() {
+                elements.push(ConcatElement::Literal(std::mem::take(&mut literal)));
+            }
+            elements.push(element);
+        }
+        if !literal.is_empty() {
+            elements.push(ConcatElement::Literal(literal));
+        }
+        // Every argument of the call site must be consumed by the recipe.
+        captures
+            .next()
+            .is_none()
+            .then_some(Self { recipe: elements })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{Identifier, LocalValue};
+
+    use super::*;
+    use crate::tests::method_ref;
+
+    fn bootstrap(owner: &str, name: &str, arguments: Vec<ConstantValue>) -> BootstrapMethod {
+        BootstrapMethod {
+            method: MethodHandle::RefInvokeStatic(method_ref(
+                owner,
+                name,
+                "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;[Ljava/lang/Object;)Ljava/lang/invoke/CallSite;",
+            )),
+            arguments,
+        }
+    }
+
+    fn closure(name: &str, captures: Vec<Operand>, descriptor: &str) -> Expression {
+        Expression::Closure {
+            name: name.to_owned(),
+            captures,
+            bootstrap_method_index: 0,
+            closure_descriptor: descriptor.parse().unwrap(),
+        }
+    }
+
+    fn local(id: u16) -> Operand {
+        Operand::Just(Identifier::Local(LocalValue::new(id)))
+    }
+
+    #[test]
+    fn lambda() {
+        let implementation = method_ref("Main", "lambda$main$0", "(Ljava/lang/String;)V");
+        let bootstrap_methods = [bootstrap(
+            LAMBDA_METAFACTORY,
+            "metafactory",
+            vec![
+                ConstantValue::MethodType("()V".parse().unwrap()),
+                ConstantValue::Handle(MethodHandle::RefInvokeStatic(implementation.clone())),
+                ConstantValue::MethodType("()V".parse().unwrap()),
+            ],
+        )];
+        let expr = closure(
+            "run",
+            vec![local(1)],
+            "(Ljava/lang/String;)Ljava/lang/Runnable;",
+        );
+        let Some(DynamicCall::Lambda(lambda)) = DynamicCall::decode(&expr, &bootstrap_methods)
+        else {
+            panic!("The closure should be decoded as a lambda");
+        };
+        assert_eq!(lambda.interface, ClassRef::new("java/lang/Runnable"));
+        assert_eq!(lambda.method_name, "run");
+        assert_eq!(lambda.implementation, implementation);
+        assert_eq!(lambda.captures, vec![local(1)]);
+        assert_eq!(
+            lambda.to_string(),
+            "lambda java/lang/Runnable::run -> Main::lambda$main$0(%1)"
+        );
+    }
+
+    #[test]
+    fn string_concat() {
+        let bootstrap_methods = [bootstrap(
+            STRING_CONCAT_FACTORY,
+            "makeConcatWithConstants",
+            vec![
+                ConstantValue::String(JavaString::Utf8("x = \u{1}, \u{2}\u{1}!".to_owned())),
+                ConstantValue::Integer(42),
+            ],
+        )];
+        let expr = closure(
+            "makeConcatWithConstants",
+            vec![local(0), local(1)],
+            "(IJ)Ljava/lang/String;",
+        );
+        let Some(DynamicCall::StringConcat(concat)) =
+            DynamicCall::decode(&expr, &bootstrap_methods)
+        else {
+            panic!("The closure should be decoded as a string concatenation");
+        };
+        assert_eq!(
+            concat.recipe,
+            vec![
+                ConcatElement::Literal("x = ".to_owned()),
+                ConcatElement::Argument(local(0)),
+                ConcatElement::Literal(", ".to_owned()),
+                ConcatElement::Constant(ConstantValue::Integer(42)),
+                ConcatElement::Argument(local(1)),
+                ConcatElement::Literal("!".to_owned()),
+            ]
+        );
+
+        let missing_argument = closure(
+            "makeConcatWithConstants",
+            vec![local(0)],
+            "(I)Ljava/lang/String;",
+        );
+        assert_eq!(
+            DynamicCall::decode(&missing_argument, &bootstrap_methods),
+            None
+        );
+    }
+
+    #[test]
+    fn unknown_bootstrap_method() {
+        let bootstrap_methods = [bootstrap("org/mokapot/Bootstrap", "bootstrap", Vec::new())];
+        let expr = closure("run", Vec::new(), "()Ljava/lang/Runnable;");
+        assert_eq!(DynamicCall::decode(&expr, &bootstrap_methods), None);
+        assert_eq!(DynamicCall::decode(&expr, &[]), None);
+    }
+}
@@ -17,6 +17,7 @@ use crate::{
 mod array;
 mod condition;
 mod conversion;
+mod dynamic;
 mod field;
 mod lock;
 mod math;
@@ -25,6 +26,7 @@ pub use {
     array::Operation as ArrayOperation,
     condition::Condition,
     conversion::Operation as Conversion,
+    dynamic::{ConcatElement, DynamicCall, Lambda, StringConcat},
     field::Access as FieldAccess,
     lock::Operation as LockOperation,
     math::{NaNTreatment, Operation as MathOperation},
@@ -59,9 +61,11 @@ pub enum Expression {
         /// A list of arguments.
         args: Vec<Operand>,
     },
-    /// A call to a bootstrap method to create a closure.  
+    /// A call to a bootstrap method to create a closure.
     /// Corresponds to the following JVM instructions:
     /// - `invokedynamic`
+    ///
+    /// Lambdas and string concatenations can be decoded with [`DynamicCall::decode`].
     #[display(
         "closure {} {}#{}({})",
         closure_descriptor.return_type,
@@ -121,4 +125,80 @@ impl Expression {
             _ => BTreeSet::default(),
         }
     }
+
+    /// Returns mutable references to the [`Operand`]s used by the expression.
+    pub(crate) fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        use self::{Conversion as C, MathOperation as M};
+        match self {
+            Self::Const(_)
+            | Self::New(_)
+            | Self::Subroutine { .. }
+            | Self::Field(FieldAccess::ReadStatic { .. }) => Vec::new(),
+            Self::Call { this, args, .. } => this.iter_mut().chain(args.iter_mut()).collect(),
+            Self::Closure { captures, .. } => captures.iter_mut().collect(),
+            Self::Math(
+                M::Add(a, b)
+                | M::Subtract(a, b)
+                | M::Multiply(a, b)
+                | M::Divide(a, b)
+                | M::Remainder(a, b)
+                | M::ShiftLeft(a, b)
+                | M::ShiftRight(a, b)
+                | M::LogicalShiftRight(a, b)
+                | M::BitwiseAnd(a, b)
+                | M::BitwiseOr(a, b)
+                | M::BitwiseXor(a, b)
+                | M::LongComparison(a, b)
+                | M::FloatingPointComparison(a, b, _),
+            )
+            | Self::Field(FieldAccess::WriteInstance {
+                object_ref: a,
+                value: b,
+                ..
+            })
+            | Self::Array(ArrayOperation::Read {
+                array_ref: a,
+                index: b,
+            }) => vec![a, b],
+            Self::Math(M::Negate(a) | M::Increment(a, _))
+            | Self::Conversion(
+                C::Int2Long(a)
+                | C::Int2Float(a)
+                | C::Int2Double(a)
+                | C::Long2Int(a)
+                | C::Long2Float(a)
+                | C::Long2Double(a)
+                | C::Float2Int(a)
+                | C::Float2Long(a)
+                | C::Float2Double(a)
+                | C::Double2Int(a)
+                | C::Double2Long(a)
+                | C::Double2Float(a)
+                | C::Int2Byte(a)
+                | C::Int2Char(a)
+                | C::Int2Short(a)
+                | C::CheckCast(a, _)
+                | C::InstanceOf(a, _),
+            )
+            | Self::Field(
+                FieldAccess::WriteStatic { value: a, .. }
+                | FieldAccess::ReadInstance { object_ref: a, .. },
+            )
+            | Self::Array(
+                ArrayOperation::New { length: a, .. } | ArrayOperation::Length { array_ref: a },
+            )
+            | Self::Throw(a)
+            | Self::Synchronization(LockOperation::Acquire(a) | LockOperation::Release(a)) => {
+                vec![a]
+            }
+            Self::Array(ArrayOperation::Write {
+                array_ref,
+                index,
+                value,
+            }) => vec![array_ref, index, value],
+            Self::Array(ArrayOperation::NewMultiDim { dimensions, .. }) => {
+                dimensions.iter_mut().collect()
+            }
+        }
+    }
 }
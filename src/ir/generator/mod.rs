@@ -1,5 +1,7 @@
 mod execution;
 mod jvm_frame;
+mod subroutines;
+pub(super) mod typing;
 
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -10,7 +12,7 @@ use std::{
 use crate::{
     ir::control_flow::path_condition::{PathCondition, Predicate},
     jvm::{
-        code::{ExceptionTableEntry, InstructionList, MethodBody, ProgramCounter},
+        code::{InstructionList, MethodBody, ProgramCounter},
         method,
         references::ClassRef,
         ConstantValue, Method,
@@ -44,11 +46,41 @@ pub enum MokaIRBrewingError {
     MalformedControlFlow,
 }
 
+/// Options that control how Moka IR is generated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BrewOptions {
+    /// Whether to inline the subroutines (i.e., `jsr` and `ret`) so that each call site gets its
+    /// own copy of the subroutine and no [`MokaInstruction::SubroutineRet`] remains.
+    /// The copies are located after the last instruction of the original method body.
+    pub inline_subroutines: bool,
+    /// Where the control flow edges to exception handlers start.
+    pub exception_edges: ExceptionEdges,
+    /// Whether to keep the instructions unreachable from the entry of the method.
+    /// Since no stack frame reaches them, they are kept as [`MokaInstruction::Nop`] without any
+    /// control flow edges.
+    pub keep_unreachable_code: bool,
+}
+
+/// The granularity of the control flow edges to exception handlers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExceptionEdges {
+    /// An edge starts at each instruction that may throw an exception in a protected region.
+    #[default]
+    PerInstruction,
+    /// An edge starts at the last instruction in a protected region of each basic block.
+    /// The handlers observe the local variables at the end of the block, which reduces the number
+    /// of edges and φ-nodes at the cost of precision.
+    PerBlock,
+}
+
 struct MokaIRGenerator<'m> {
     ir_instructions: BTreeMap<ProgramCounter, MokaInstruction>,
     method: &'m Method,
     body: &'m MethodBody,
     control_flow_edges: BTreeMap<(ProgramCounter, ProgramCounter), ControlTransfer>,
+    /// The locations where the edges to the handlers of the exception table entries (by index)
+    /// start, or [`None`] if they start at every instruction that may throw.
+    exception_sources: Option<BTreeSet<(ProgramCounter, usize)>>,
 }
 
 impl Analyzer for MokaIRGenerator<'_> {
@@ -90,6 +122,7 @@ impl Analyzer for MokaIRGenerator<'_> {
             .instruction_at(location)
             .ok_or(MokaIRBrewingError::MalformedControlFlow)?;
         let ir_instruction = self.run_instruction(insn, location, &mut frame)?;
+        let exception_edges = self.exception_edges(location, &ir_instruction, &frame);
         let edges_and_frames = match &ir_instruction {
             MokaInstruction::Nop => {
                 let next_pc = self.next_pc_of(location)?;
@@ -100,7 +133,7 @@ impl Analyzer for MokaIRGenerator<'_> {
             MokaInstruction::Definition {
                 expr: Expression::Throw(_),
                 ..
-            } => Self::exception_edges(&self.body.exception_table, location, &frame),
+            } => Vec::new(),
             MokaInstruction::Definition {
                 expr:
                     Expression::Subroutine {
@@ -115,10 +148,7 @@ impl Analyzer for MokaIRGenerator<'_> {
             }
             MokaInstruction::Definition { .. } => {
                 let next_pc = self.next_pc_of(location)?;
-                Self::exception_edges(&self.body.exception_table, location, &frame)
-                    .into_iter()
-                    .chain(once(((location, next_pc, Unconditional), frame)))
-                    .collect()
+                vec![((location, next_pc, Unconditional), frame)]
             }
             MokaInstruction::Jump { condition, target } => {
                 if let Some(condition) = condition {
@@ -164,11 +194,15 @@ impl Analyzer for MokaIRGenerator<'_> {
                     (edge, frame.same_frame())
                 })
                 .collect(),
+            MokaInstruction::Phi { .. } => {
+                unreachable!("Phi nodes are only introduced by the SSA construction")
+            }
         };
         self.ir_instructions.insert(location, ir_instruction);
 
-        let (affected_locations, edges) = edges_and_frames
+        let (affected_locations, edges) = exception_edges
             .into_iter()
+            .chain(edges_and_frames)
             .map(|(edge, frame)| ((edge.1, frame), edge))
             .unzip();
         self.control_flow_edges
@@ -195,30 +229,61 @@ impl<'m> MokaIRGenerator<'m> {
             .ok_or(MokaIRBrewingError::MalformedControlFlow)
     }
 
-    fn for_method(method: &'m Method) -> Result<Self, <Self as Analyzer>::Err> {
-        let body = method
-            .body
-            .as_ref()
-            .ok_or(MokaIRBrewingError::NoMethodBody)?;
-        Ok(Self {
+    fn new(method: &'m Method, body: &'m MethodBody, options: BrewOptions) -> Self {
+        let exception_sources = match options.exception_edges {
+            ExceptionEdges::PerInstruction => None,
+            ExceptionEdges::PerBlock => Some(Self::block_exception_sources(body)),
+        };
+        Self {
             ir_instructions: BTreeMap::default(),
             method,
             body,
             control_flow_edges: BTreeMap::default(),
-        })
+            exception_sources,
+        }
+    }
+
+    /// Finds the last location covered by each exception table entry in each basic block.
+    fn block_exception_sources(body: &MethodBody) -> BTreeSet<(ProgramCounter, usize)> {
+        body.basic_blocks()
+            .iter()
+            .flat_map(|block| {
+                body.exception_table
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, entry)| {
+                        block
+                            .instructions
+                            .iter()
+                            .rev()
+                            .find(|(pc, _)| entry.covers(*pc))
+                            .map(|(pc, _)| (*pc, index))
+                    })
+            })
+            .collect()
     }
 
     fn exception_edges(
-        exception_table: &[ExceptionTableEntry],
+        &self,
         pc: ProgramCounter,
+        ir_instruction: &MokaInstruction,
         frame: &JvmStackFrame,
     ) -> Vec<(
         (ProgramCounter, ProgramCounter, ControlTransfer),
         JvmStackFrame,
     )> {
-        exception_table
+        self.body
+            .exception_table
             .iter()
-            .filter(|&it| it.covers(pc))
+            .enumerate()
+            .filter(|(index, entry)| match &self.exception_sources {
+                // Only the definitions may throw exceptions.
+                None => {
+                    matches!(ir_instruction, MokaInstruction::Definition { .. }) && entry.covers(pc)
+                }
+                Some(sources) => sources.contains(&(pc, *index)),
+            })
+            .map(|(_, entry)| entry)
             .into_group_map_by(|&it| it.handler_pc)
             .into_iter()
             .map(|(handler_pc, entries)| {
@@ -244,23 +309,41 @@ impl<'m> MokaIRGenerator<'m> {
 
 /// An extension trait for [`Method`] that generates Moka IR.
 pub trait MokaIRMethodExt {
-    /// Generates Moka IR for the method.
+    /// Generates Moka IR for the method with the default [`BrewOptions`].
     /// # Errors
     /// See [`MokaIRBrewingError`] for more information.
-    fn brew(&self) -> Result<MokaIRMethod, MokaIRBrewingError>;
+    fn brew(&self) -> Result<MokaIRMethod, MokaIRBrewingError> {
+        self.brew_with(&BrewOptions::default())
+    }
+
+    /// Generates Moka IR for the method according to `options`.
+    /// # Errors
+    /// See [`MokaIRBrewingError`] for more information.
+    fn brew_with(&self, options: &BrewOptions) -> Result<MokaIRMethod, MokaIRBrewingError>;
 }
 
 impl MokaIRMethodExt for Method {
-    fn brew(&self) -> Result<MokaIRMethod, MokaIRBrewingError> {
-        let (instructions, control_flow_graph) = MokaIRGenerator::for_method(self)?.generate()?;
+    fn brew_with(&self, options: &BrewOptions) -> Result<MokaIRMethod, MokaIRBrewingError> {
+        let body = self.body.as_ref().ok_or(MokaIRBrewingError::NoMethodBody)?;
+        let inlined;
+        let body = if options.inline_subroutines {
+            inlined = subroutines::inline_subroutines(body);
+            &inlined
+        } else {
+            body
+        };
+        let (instructions, control_flow_graph) =
+            MokaIRGenerator::new(self, body, *options).generate(*options)?;
+        let types = typing::infer_types(self, &instructions);
         Ok(MokaIRMethod {
             access_flags: self.access_flags,
             name: self.name.clone(),
             owner: self.owner.clone(),
             descriptor: self.descriptor.clone(),
             instructions,
-            exception_table: self.body.as_ref().unwrap().exception_table.clone(),
+            exception_table: body.exception_table.clone(),
             control_flow_graph,
+            types,
         })
     }
 }
@@ -268,6 +351,7 @@ impl MokaIRMethodExt for Method {
 impl MokaIRGenerator<'_> {
     fn generate(
         mut self,
+        options: BrewOptions,
     ) -> Result<
         (
             InstructionList<MokaInstruction>,
@@ -276,6 +360,13 @@ impl MokaIRGenerator<'_> {
         MokaIRBrewingError,
     > {
         self.analyze()?;
+        if options.keep_unreachable_code {
+            for (pc, _) in &self.body.instructions {
+                self.ir_instructions
+                    .entry(*pc)
+                    .or_insert(MokaInstruction::Nop);
+            }
+        }
         let cfg = ControlFlowGraph::from_edges(
             self.control_flow_edges
                 .into_iter()
@@ -284,3 +375,74 @@ impl MokaIRGenerator<'_> {
         Ok((InstructionList::from(self.ir_instructions), cfg))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    #[test]
+    fn brew_with_options() {
+        let class = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static run(I)I
+                .limit stack 2
+                .limit locals 3
+            Start:
+                iload_0
+                iconst_1
+                iadd
+                istore_1
+                iload_1
+                iload_1
+                imul
+                istore_1
+            End:
+                jsr Sub
+                iload_1
+                ireturn
+            Handler:
+                pop
+                iconst_0
+                ireturn
+            Sub:
+                astore_2
+                ret 2
+                nop
+            .catch java/lang/Exception from Start to End using Handler
+            .end method
+            ",
+        )
+        .unwrap();
+        let method = &class.methods[0];
+        let handler_edges = |ir: &MokaIRMethod| {
+            ir.control_flow_graph
+                .edges()
+                .filter(|(_, _, it)| matches!(it, ControlTransfer::Exception(_)))
+                .count()
+        };
+
+        let default = method.brew().unwrap();
+        assert_eq!(handler_edges(&default), 3);
+        assert!(default
+            .instructions
+            .iter()
+            .any(|(_, it)| matches!(it, MokaInstruction::SubroutineRet(_))));
+        assert!(default.instructions.get(&19.into()).is_none());
+
+        let options = BrewOptions {
+            inline_subroutines: true,
+            exception_edges: ExceptionEdges::PerBlock,
+            keep_unreachable_code: true,
+        };
+        let ir = method.brew_with(&options).unwrap();
+        assert_eq!(handler_edges(&ir), 1);
+        assert!(!ir
+            .instructions
+            .iter()
+            .any(|(_, it)| matches!(it, MokaInstruction::SubroutineRet(_))));
+        assert_eq!(ir.instructions.get(&19.into()), Some(&MokaInstruction::Nop));
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::jvm::code::{
+    ExceptionTableEntry, Instruction, MethodBody, ProgramCounter, WideInstruction,
+};
+
+/// Returns a copy of `body` where each `jsr` jumps to a private copy of its subroutine, which
+/// jumps back to the instruction following the `jsr` instead of executing `ret`.
+/// The copies are appended after the existing instructions, so the locations of the original
+/// instructions are preserved. The `jsr`s that cannot be inlined (e.g., when the subroutine does
+/// not start by storing its return address or the copies exceed the range of the program
+/// counters) are left unchanged.
+pub(super) fn inline_subroutines(body: &MethodBody) -> MethodBody {
+    let mut body = body.clone();
+    let mut skipped = BTreeSet::new();
+    loop {
+        let Some((jsr_pc, target)) = body.instructions.iter().find_map(|(pc, insn)| match insn {
+            Instruction::Jsr(target) | Instruction::JsrW(target) if !skipped.contains(pc) => {
+                Some((*pc, *target))
+            }
+            _ => None,
+        }) else {
+            break;
+        };
+        if !inline_at(&mut body, jsr_pc, target) {
+            skipped.insert(jsr_pc);
+        }
+    }
+    body
+}
+
+/// Inlines the subroutine at `target` called by the `jsr` at `jsr_pc`.
+/// Returns `false` if the subroutine cannot be inlined.
+fn inline_at(body: &mut MethodBody, jsr_pc: ProgramCounter, target: ProgramCounter) -> bool {
+    let Some(return_pc) = body.instructions.next_pc_of(&jsr_pc) else {
+        return false;
+    };
+    // The return address pushed by `jsr` is stored by the first instruction of the subroutine,
+    // which becomes a `nop` in the copy as the `jsr` is replaced by a `goto`.
+    if !matches!(
+        body.instructions.get(&target),
+        Some(
+            Instruction::AStore(_)
+                | Instruction::AStore0
+                | Instruction::AStore1
+                | Instruction::AStore2
+                | Instruction::AStore3
+                | Instruction::Wide(WideInstruction::AStore(_))
+        )
+    ) {
+        return false;
+    }
+    let members = subroutine_members(body, target);
+    let (Some(first), Some((last_pc, _))) = (members.first(), body.instructions.last_instruction())
+    else {
+        return false;
+    };
+    let Some(offset) = u16::from(*last_pc)
+        .checked_add(1)
+        .and_then(|it| it.checked_sub(u16::from(*first)))
+    else {
+        return false;
+    };
+    let Some(pc_mapping) = members
+        .iter()
+        .map(|pc| (*pc + offset).ok().map(|it| (*pc, it)))
+        .collect::<Option<BTreeMap<_, _>>>()
+    else {
+        return false;
+    };
+
+    let mut instructions: BTreeMap<_, _> = body.instructions.clone().into_iter().collect();
+    for (pc, copy_pc) in &pc_mapping {
+        let mut insn = match &instructions[pc] {
+            _ if *pc == target => Instruction::Nop,
+            Instruction::Ret(_) | Instruction::Wide(WideInstruction::Ret(_)) => {
+                Instruction::Goto(return_pc)
+            }
+            insn => insn.clone(),
+        };
+        insn.retarget(&pc_mapping);
+        instructions.insert(*copy_pc, insn);
+    }
+    instructions.insert(jsr_pc, Instruction::Goto(pc_mapping[&target]));
+    body.instructions = instructions.into();
+
+    let copied_entries: Vec<_> = body
+        .exception_table
+        .iter()
+        .filter_map(|entry| {
+            let mut covered = members.iter().filter(|pc| entry.covers(**pc));
+            let start = pc_mapping[covered.clone().next()?];
+            let end = pc_mapping[covered.next_back()?];
+            Some(ExceptionTableEntry {
+                covered_pc: start..=end,
+                handler_pc: pc_mapping
+                    .get(&entry.handler_pc)
+                    .copied()
+                    .unwrap_or(entry.handler_pc),
+                catch_type: entry.catch_type.clone(),
+            })
+        })
+        .collect();
+    body.exception_table.extend(copied_entries);
+    true
+}
+
+/// Collects the locations of the instructions of the subroutine at `target`, following the
+/// control flow until the `ret`s. The nested `jsr`s are assumed to return.
+fn subroutine_members(body: &MethodBody, target: ProgramCounter) -> BTreeSet<ProgramCounter> {
+    let mut members = BTreeSet::new();
+    let mut pending = vec![target];
+    while let Some(pc) = pending.pop() {
+        let Some(insn) = body.instructions.get(&pc) else {
+            continue;
+        };
+        if !members.insert(pc) {
+            continue;
+        }
+        let falls_through = !matches!(
+            insn,
+            Instruction::Goto(_)
+                | Instruction::GotoW(_)
+                | Instruction::TableSwitch { .. }
+                | Instruction::LookupSwitch { .. }
+                | Instruction::IReturn
+                | Instruction::LReturn
+                | Instruction::FReturn
+                | Instruction::DReturn
+                | Instruction::AReturn
+                | Instruction::Return
+                | Instruction::AThrow
+                | Instruction::Ret(_)
+                | Instruction::Wide(WideInstruction::Ret(_))
+        );
+        match insn {
+            // The nested subroutines are inlined separately.
+            Instruction::Jsr(_) | Instruction::JsrW(_) => {}
+            _ => pending.extend(insn.branch_targets()),
+        }
+        if falls_through {
+            pending.extend(body.instructions.next_pc_of(&pc));
+        }
+    }
+    members
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    #[test]
+    fn inline_subroutine_per_call_site() {
+        let class = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static run()V
+                .limit stack 1
+                .limit locals 1
+                jsr Sub
+                jsr Sub
+                return
+            Sub:
+                astore_0
+                ret 0
+            .end method
+            ",
+        )
+        .unwrap();
+        let body = inline_subroutines(class.methods[0].body.as_ref().unwrap());
+        let instructions: Vec<_> = body.instructions.iter().map(|(pc, it)| (*pc, it)).collect();
+        assert_eq!(
+            instructions
+                .iter()
+                .filter(|(_, it)| matches!(it, Instruction::Jsr(_) | Instruction::Ret(_)))
+                .count(),
+            1,
+            "Only the original subroutine keeps its `ret`: {instructions:?}"
+        );
+        let Some(Instruction::Goto(first_copy)) = body.instructions.get(&0.into()) else {
+            panic!("The first `jsr` is not replaced");
+        };
+        let Some(Instruction::Goto(second_copy)) = body.instructions.get(&3.into()) else {
+            panic!("The second `jsr` is not replaced");
+        };
+        assert_ne!(first_copy, second_copy);
+        assert_eq!(body.instructions.get(first_copy), Some(&Instruction::Nop));
+        let after_first = body.instructions.next_pc_of(first_copy).unwrap();
+        assert_eq!(
+            body.instructions.get(&after_first),
+            Some(&Instruction::Goto(3.into()))
+        );
+    }
+}
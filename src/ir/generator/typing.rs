@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{
+    ir::{
+        expression::{ArrayOperation, Conversion, Expression, FieldAccess, MathOperation},
+        Identifier, MokaInstruction, Operand,
+    },
+    jvm::{code::InstructionList, method, references::ClassRef, ConstantValue, Method},
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::ReturnType,
+    },
+};
+
+const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+const JAVA_LANG_THROWABLE: &str = "java/lang/Throwable";
+
+/// Infers the static types of the values in `instructions` generated from `method`.
+/// The types of the values defined by arithmetic and merged by Phi operands depend on the types
+/// of the operands, so the inference is repeated until no type changes.
+pub(super) fn infer_types(
+    method: &Method,
+    instructions: &InstructionList<MokaInstruction>,
+) -> BTreeMap<Identifier, FieldType> {
+    let mut types = BTreeMap::new();
+    if !method.access_flags.contains(method::AccessFlags::STATIC) {
+        types.insert(Identifier::This, FieldType::Object(method.owner.clone()));
+    }
+    for (index, param) in (0..).zip(method.descriptor.parameters_types.iter()) {
+        types.insert(Identifier::Arg(index), param.clone());
+    }
+    let catch_types: BTreeSet<_> = method
+        .body
+        .iter()
+        .flat_map(|it| &it.exception_table)
+        .map(|it| it.catch_type.clone())
+        .collect();
+    let caught = match catch_types.into_iter().collect::<Vec<_>>().as_slice() {
+        [Some(catch_type)] => catch_type.clone(),
+        _ => ClassRef::new(JAVA_LANG_THROWABLE),
+    };
+    types.insert(Identifier::CaughtException, FieldType::Object(caught));
+
+    // Bounded in case the types of the values in a loop keep alternating.
+    for _ in 0..=instructions.iter().count() {
+        let mut changed = false;
+        for (_, insn) in instructions.iter() {
+            let Some(value) = insn.def() else {
+                continue;
+            };
+            let inferred = match insn {
+                MokaInstruction::Definition { expr, .. } => type_of_expr(expr, &types),
+                MokaInstruction::Phi { incoming, .. } => join(incoming.values(), &types),
+                _ => None,
+            };
+            if let Some(ty) = inferred {
+                if types.get(&value.into()) != Some(&ty) {
+                    types.insert(value.into(), ty);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+    // The values defined as `null` are compatible with any reference type, so they are only
+    // typed after they no longer affect the types merged with them.
+    for (_, insn) in instructions.iter() {
+        if let MokaInstruction::Definition {
+            value,
+            expr: Expression::Const(ConstantValue::Null),
+        } = insn
+        {
+            types.insert(
+                Identifier::Local(*value),
+                FieldType::Object(ClassRef::new(JAVA_LANG_OBJECT)),
+            );
+        }
+    }
+    types
+}
+
+/// Joins the types of `ids`, where distinct reference types join to `java/lang/Object` and
+/// distinct primitive types join to `int` (e.g., `boolean` and `int` from a `baload`).
+pub(crate) fn join<'a>(
+    ids: impl IntoIterator<Item = &'a Identifier>,
+    types: &BTreeMap<Identifier, FieldType>,
+) -> Option<FieldType> {
+    ids.into_iter()
+        .filter_map(|it| types.get(it))
+        .cloned()
+        .reduce(|lhs, rhs| match (lhs, rhs) {
+            (lhs, rhs) if lhs == rhs => lhs,
+            (FieldType::Base(_), FieldType::Base(_)) => FieldType::Base(PrimitiveType::Int),
+            _ => FieldType::Object(ClassRef::new(JAVA_LANG_OBJECT)),
+        })
+}
+
+fn type_of_expr(expr: &Expression, types: &BTreeMap<Identifier, FieldType>) -> Option<FieldType> {
+    use PrimitiveType::{Boolean, Byte, Char, Double, Float, Int, Long, Short};
+    let of = |operand: &Operand| join(operand.iter(), types);
+    let ty = match expr {
+        Expression::Const(constant) => match constant {
+            // Typed after the other values. See `infer_types`.
+            ConstantValue::Null => return None,
+            ConstantValue::Integer(_) => FieldType::Base(Int),
+            ConstantValue::Float(_) => FieldType::Base(Float),
+            ConstantValue::Long(_) => FieldType::Base(Long),
+            ConstantValue::Double(_) => FieldType::Base(Double),
+            ConstantValue::String(_) => FieldType::Object(ClassRef::new("java/lang/String")),
+            ConstantValue::Class(_) => FieldType::Object(ClassRef::new("java/lang/Class")),
+            ConstantValue::Handle(_) => {
+                FieldType::Object(ClassRef::new("java/lang/invoke/MethodHandle"))
+            }
+            ConstantValue::MethodType(_) => {
+                FieldType::Object(ClassRef::new("java/lang/invoke/MethodType"))
+            }
+            ConstantValue::Dynamic(constant) => constant.descriptor.clone(),
+        },
+        Expression::Call { method, .. } => match &method.descriptor.return_type {
+            ReturnType::Some(ty) => ty.clone(),
+            ReturnType::Void => return None,
+        },
+        Expression::Closure {
+            closure_descriptor, ..
+        } => match &closure_descriptor.return_type {
+            ReturnType::Some(ty) => ty.clone(),
+            ReturnType::Void => return None,
+        },
+        Expression::Math(math) => match math {
+            MathOperation::LongComparison(_, _)
+            | MathOperation::FloatingPointComparison(_, _, _) => FieldType::Base(Int),
+            MathOperation::Add(lhs, _)
+            | MathOperation::Subtract(lhs, _)
+            | MathOperation::Multiply(lhs, _)
+            | MathOperation::Divide(lhs, _)
+            | MathOperation::Remainder(lhs, _)
+            | MathOperation::ShiftLeft(lhs, _)
+            | MathOperation::ShiftRight(lhs, _)
+            | MathOperation::LogicalShiftRight(lhs, _)
+            | MathOperation::BitwiseAnd(lhs, _)
+            | MathOperation::BitwiseOr(lhs, _)
+            | MathOperation::BitwiseXor(lhs, _)
+            | MathOperation::Negate(lhs)
+            | MathOperation::Increment(lhs, _) => match of(lhs)? {
+                // The arithmetic on the types narrower than `int` yields `int`.
+                FieldType::Base(Boolean | Byte | Char | Short) => FieldType::Base(Int),
+                it => it,
+            },
+        },
+        Expression::Field(
+            FieldAccess::ReadStatic { field } | FieldAccess::ReadInstance { field, .. },
+        ) => field.field_type.clone(),
+        Expression::Field(_) => return None,
+        Expression::Array(array) => match array {
+            ArrayOperation::New { element_type, .. } => element_type.clone().into_array_type(),
+            // The type of `multianewarray` is the type of the array.
+            ArrayOperation::NewMultiDim { element_type, .. } => element_type.clone(),
+            ArrayOperation::Read { array_ref, .. } => match of(array_ref)? {
+                FieldType::Array(element_type) => *element_type,
+                _ => return None,
+            },
+            ArrayOperation::Length { .. } => FieldType::Base(Int),
+            ArrayOperation::Write { .. } => return None,
+        },
+        Expression::Conversion(conversion) => match conversion {
+            Conversion::Long2Int(_) | Conversion::Float2Int(_) | Conversion::Double2Int(_) => {
+                FieldType::Base(Int)
+            }
+            Conversion::Int2Long(_) | Conversion::Float2Long(_) | Conversion::Double2Long(_) => {
+                FieldType::Base(Long)
+            }
+            Conversion::Int2Float(_) | Conversion::Long2Float(_) | Conversion::Double2Float(_) => {
+                FieldType::Base(Float)
+            }
+            Conversion::Int2Double(_)
+            | Conversion::Long2Double(_)
+            | Conversion::Float2Double(_) => FieldType::Base(Double),
+            Conversion::Int2Byte(_) => FieldType::Base(Byte),
+            Conversion::Int2Char(_) => FieldType::Base(Char),
+            Conversion::Int2Short(_) => FieldType::Base(Short),
+            Conversion::CheckCast(_, ty) => ty.clone(),
+            Conversion::InstanceOf(_, _) => FieldType::Base(Boolean),
+        },
+        Expression::New(class) => FieldType::Object(class.clone()),
+        Expression::Throw(_) | Expression::Synchronization(_) | Expression::Subroutine { .. } => {
+            return None
+        }
+    };
+    Some(ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::{LocalValue, MokaIRMethodExt},
+        jvm::interop::jasmin,
+    };
+
+    #[test]
+    fn infer_array_element_and_merged_null() {
+        let class = jasmin::read(
+            "
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static run([Ljava/lang/String;I)Ljava/lang/Object;
+                .limit stack 2
+                .limit locals 3
+                aload_0
+                iload_1
+                aaload
+                astore_2
+                iload_1
+                ifeq Null
+                aload_2
+                goto Join
+            Null:
+                aconst_null
+            Join:
+                areturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let method = class.methods[0].brew().unwrap();
+        let string = FieldType::Object(ClassRef::new("java/lang/String"));
+        assert_eq!(
+            method.type_of(&Identifier::Arg(0)),
+            Some(&string.clone().into_array_type())
+        );
+        assert_eq!(
+            method.type_of(&Identifier::Arg(1)),
+            Some(&FieldType::Base(PrimitiveType::Int))
+        );
+        assert_eq!(
+            method.type_of(&Identifier::Local(LocalValue::new(2))),
+            Some(&string)
+        );
+        assert_eq!(
+            method.type_of(&Identifier::Local(LocalValue::new(12))),
+            Some(&FieldType::Object(ClassRef::new(JAVA_LANG_OBJECT)))
+        );
+        let Some(MokaInstruction::Return(Some(returned))) = method.instructions.get(&13.into())
+        else {
+            panic!("Unexpected instruction at 13");
+        };
+        assert_eq!(method.type_of_operand(returned), Some(string));
+    }
+}
@@ -9,11 +9,15 @@ mod moka_instruction;
 #[cfg(feature = "petgraph")]
 pub mod petgraph;
 
+pub mod ssa;
+pub mod transform;
 pub mod type_hierarchy;
+#[cfg(feature = "wasm")]
+pub mod wasm_backend;
 
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
-pub use generator::{MokaIRBrewingError, MokaIRMethodExt};
+pub use generator::{BrewOptions, ExceptionEdges, MokaIRBrewingError, MokaIRMethodExt};
 pub use moka_instruction::*;
 
 use crate::{
@@ -21,11 +25,12 @@ use crate::{
         code::{ExceptionTableEntry, InstructionList, ProgramCounter},
         method::{self},
         references::ClassRef,
+        ConstantValue,
     },
-    types::method_descriptor::MethodDescriptor,
+    types::{field_type::FieldType, method_descriptor::MethodDescriptor},
 };
 
-use self::control_flow::ControlTransfer;
+use self::{control_flow::ControlTransfer, expression::Expression};
 
 /// Represents a JVM method where the instructions have been converted to Moka IR.
 #[derive(Debug, Clone)]
@@ -44,6 +49,10 @@ pub struct MokaIRMethod {
     pub exception_table: Vec<ExceptionTableEntry>,
     /// The control flow graph of the method.
     pub control_flow_graph: ControlFlowGraph<(), ControlTransfer>,
+    /// The static types of the values in the method, inferred from the descriptors, the
+    /// constants, and the operations defining the values.
+    /// See [`MokaIRMethod::type_of`].
+    pub types: BTreeMap<Identifier, FieldType>,
 }
 
 impl MokaIRMethod {
@@ -52,6 +61,43 @@ impl MokaIRMethod {
     pub const fn is_static(&self) -> bool {
         self.access_flags.contains(method::AccessFlags::STATIC)
     }
+
+    /// Returns the static type of `id`, or [`None`] if it does not hold a typed value (e.g., the
+    /// result of a `void` call).
+    /// The distinct reference types merged by Phi operands are typed as `java/lang/Object`, and
+    /// the distinct primitive types as `int`.
+    #[must_use]
+    pub fn type_of(&self, id: &Identifier) -> Option<&FieldType> {
+        self.types.get(id)
+    }
+
+    /// Returns the static type of `operand`, joining the types of the values it may refer to.
+    /// The `null` values are compatible with any reference type, so they only determine the type
+    /// if all the values are `null`.
+    #[must_use]
+    pub fn type_of_operand(&self, operand: &Operand) -> Option<FieldType> {
+        generator::typing::join(
+            operand.iter().filter(|it| !self.is_null_constant(it)),
+            &self.types,
+        )
+        .or_else(|| generator::typing::join(operand.iter(), &self.types))
+    }
+
+    /// Checks whether `id` is defined as the `null` constant.
+    #[must_use]
+    pub fn is_null_constant(&self, id: &Identifier) -> bool {
+        let Identifier::Local(value) = id else {
+            return false;
+        };
+        // The values are numbered after the locations of their definitions.
+        matches!(
+            self.instructions.get(&u16::from(*value).into()),
+            Some(MokaInstruction::Definition {
+                value: def,
+                expr: Expression::Const(ConstantValue::Null),
+            }) if def == value
+        )
+    }
 }
 
 /// A control flow graph.
@@ -66,20 +112,30 @@ pub struct ControlFlowGraph<N, E> {
 #[derive(Debug)]
 pub struct DefUseChain<'a> {
     method: &'a MokaIRMethod,
-    defs: HashMap<LocalValue, ProgramCounter>,
-    uses: HashMap<Identifier, BTreeSet<ProgramCounter>>,
+    defs: BTreeMap<LocalValue, ProgramCounter>,
+    uses: BTreeMap<Identifier, BTreeSet<ProgramCounter>>,
 }
 
 /// A class hierarchy based on super class relationships.
 #[derive(Debug, Clone)]
 pub struct ClassHierarchy {
-    inheritance: HashMap<ClassRef, HashSet<ClassRef>>,
-    super_classes: HashMap<ClassRef, ClassRef>,
+    classes: BTreeSet<ClassRef>,
+    inheritance: BTreeMap<ClassRef, BTreeSet<ClassRef>>,
+    super_classes: BTreeMap<ClassRef, ClassRef>,
 }
 
 /// A class hierarchy based on interface implementations.
 #[derive(Debug, Clone)]
 pub struct InterfaceImplHierarchy {
-    implementations: HashMap<ClassRef, HashSet<ClassRef>>,
-    implementors: HashMap<ClassRef, HashSet<ClassRef>>,
+    classes: BTreeSet<ClassRef>,
+    implementations: BTreeMap<ClassRef, BTreeSet<ClassRef>>,
+    implementors: BTreeMap<ClassRef, BTreeSet<ClassRef>>,
+}
+
+/// A hierarchy of `sealed` classes and interfaces and their permitted subclasses.
+#[derive(Debug, Clone)]
+pub struct SealedHierarchy {
+    permitted_subclasses: BTreeMap<ClassRef, Vec<ClassRef>>,
+    direct_subtypes: BTreeMap<ClassRef, BTreeSet<ClassRef>>,
+    final_classes: BTreeSet<ClassRef>,
 }
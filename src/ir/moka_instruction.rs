@@ -56,6 +56,23 @@ pub enum MokaInstruction {
     /// Returns from a subroutine.
     #[display("subroutine_ret {_0}")]
     SubroutineRet(Operand),
+    /// Defines a value by selecting the value from the path through which the control reaches it.
+    /// It only appears at the heads of the basic blocks in the SSA form built by
+    /// [`ssa::construct`](super::ssa::construct).
+    #[display(
+        "{value} = phi({})",
+        incoming
+            .iter()
+            .map(|(pc, id)| format!("{} => {id}", pc.map_or_else(|| "entry".to_owned(), |it| it.to_string())))
+            .join(", ")
+    )]
+    Phi {
+        /// The value defined by the Phi node.
+        value: LocalValue,
+        /// The values coming from the instructions that transfer the control to the block, where
+        /// [`None`] denotes the entry of the method.
+        incoming: BTreeMap<Option<ProgramCounter>, Identifier>,
+    },
 }
 
 impl MokaInstruction {
@@ -63,7 +80,7 @@ impl MokaInstruction {
     #[must_use]
     pub fn def(&self) -> Option<LocalValue> {
         match self {
-            Self::Definition { value, .. } => Some(*value),
+            Self::Definition { value, .. } | Self::Phi { value, .. } => Some(*value),
             _ => None,
         }
     }
@@ -83,9 +100,32 @@ impl MokaInstruction {
             | Self::Switch {
                 match_value: uses, ..
             } => uses.iter().copied().collect(),
+            Self::Phi { incoming, .. } => incoming.values().copied().collect(),
             _ => BTreeSet::default(),
         }
     }
+
+    /// Returns mutable references to the [`Operand`]s used by the instruction.
+    pub(crate) fn operands_mut(&mut self) -> Vec<&mut Operand> {
+        match self {
+            Self::Definition { expr, .. } => expr.operands_mut(),
+            Self::Jump {
+                condition: Some(condition),
+                ..
+            } => condition.operands_mut(),
+            Self::Switch {
+                match_value: it, ..
+            }
+            | Self::Return(Some(it))
+            | Self::SubroutineRet(it) => vec![it],
+            Self::Nop
+            | Self::Jump {
+                condition: None, ..
+            }
+            | Self::Return(None)
+            | Self::Phi { .. } => Vec::new(),
+        }
+    }
 }
 
 /// Represents a reference to a value in the Moka IR.
@@ -1,6 +1,6 @@
 //! Type hierarchy graph implementations.
 //!
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 use petgraph::{
     visit::{GraphBase, GraphRef, IntoNeighbors, IntoNeighborsDirected, Visitable},
@@ -21,14 +21,14 @@ impl<'a> GraphBase for &'a ClassHierarchy {
 impl GraphRef for &ClassHierarchy {}
 
 impl<'a> IntoNeighbors for &'a ClassHierarchy {
-    type Neighbors = <HashSet<&'a ClassRef> as IntoIterator>::IntoIter;
+    type Neighbors = <BTreeSet<&'a ClassRef> as IntoIterator>::IntoIter;
 
     fn neighbors(self, a: Self::NodeId) -> Self::Neighbors {
         self.inheritance
             .get(a)
             .into_iter()
             .flatten()
-            .collect::<HashSet<_>>()
+            .collect::<BTreeSet<_>>()
             .into_iter()
     }
 }
@@ -57,20 +57,20 @@ impl<'a> GraphBase for &'a InterfaceImplHierarchy {
 impl GraphRef for &InterfaceImplHierarchy {}
 
 impl<'a> IntoNeighbors for &'a InterfaceImplHierarchy {
-    type Neighbors = <HashSet<&'a ClassRef> as IntoIterator>::IntoIter;
+    type Neighbors = <BTreeSet<&'a ClassRef> as IntoIterator>::IntoIter;
 
     fn neighbors(self, a: Self::NodeId) -> Self::Neighbors {
         self.implementations
             .get(a)
             .into_iter()
             .flatten()
-            .collect::<HashSet<_>>()
+            .collect::<BTreeSet<_>>()
             .into_iter()
     }
 }
 
 impl<'a> IntoNeighborsDirected for &'a InterfaceImplHierarchy {
-    type NeighborsDirected = <HashSet<&'a ClassRef> as IntoIterator>::IntoIter;
+    type NeighborsDirected = <BTreeSet<&'a ClassRef> as IntoIterator>::IntoIter;
 
     fn neighbors_directed(self, a: Self::NodeId, d: Direction) -> Self::NeighborsDirected {
         if d == Direction::Outgoing {
@@ -80,7 +80,7 @@ impl<'a> IntoNeighborsDirected for &'a InterfaceImplHierarchy {
                 .get(a)
                 .into_iter()
                 .flatten()
-                .collect::<HashSet<_>>()
+                .collect::<BTreeSet<_>>()
                 .into_iter()
         }
     }
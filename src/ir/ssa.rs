@@ -0,0 +1,551 @@
+//! Construction of the conventional SSA form of Moka IR.
+//!
+//! In Moka IR, a value merged from different control flow paths is referred to by an
+//! [`Operand::Phi`] at each of its uses.
+//! The conventional SSA form instead defines such a value with a [`MokaInstruction::Phi`] node at
+//! the head of a basic block, so that every operand refers to exactly one definition.
+//! The Phi nodes are placed at the iterated dominance frontiers of the definitions as described in
+//! [Efficiently Computing Static Single Assignment Form and the Control Dependence Graph](https://doi.org/10.1145/115372.115320),
+//! and the ones whose values are never used are pruned.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    decompiler::dominators::immediate_dominators,
+    jvm::{
+        code::{ExceptionTableEntry, ProgramCounter},
+        method,
+        references::ClassRef,
+    },
+    types::method_descriptor::MethodDescriptor,
+};
+
+use super::{
+    control_flow::ControlTransfer, Identifier, LocalValue, MokaIRMethod, MokaInstruction, Operand,
+};
+
+/// An error when constructing the SSA form.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// There are not enough identifiers left for the values defined by the Phi nodes.
+    #[error("Too many values in the method")]
+    TooManyValues,
+}
+
+/// A method in the conventional SSA form.
+#[derive(Debug, Clone)]
+pub struct SsaMethod {
+    /// The access flags of the method.
+    pub access_flags: method::AccessFlags,
+    /// The name of the method.
+    pub name: String,
+    /// The descriptor of the method.
+    pub descriptor: MethodDescriptor,
+    /// The class that contains the method.
+    pub owner: ClassRef,
+    /// The basic blocks of the method, keyed by the location of their first instruction.
+    pub blocks: BTreeMap<ProgramCounter, BasicBlock>,
+    /// The exception table of the method.
+    pub exception_table: Vec<ExceptionTableEntry>,
+}
+
+/// A maximal sequence of instructions that is entered only at the first one.
+#[derive(Debug, Clone, Default)]
+pub struct BasicBlock {
+    /// The [`MokaInstruction::Phi`] nodes at the head of the block.
+    pub phis: Vec<MokaInstruction>,
+    /// The instructions in the block.
+    /// None of their operands is an [`Operand::Phi`] unless the block is unreachable.
+    pub instructions: Vec<(ProgramCounter, MokaInstruction)>,
+    /// The locations of the blocks that the last instruction may transfer the control to.
+    pub successors: BTreeSet<ProgramCounter>,
+}
+
+/// A Phi node being constructed.
+#[derive(Debug)]
+struct PhiNode {
+    variable: usize,
+    value: LocalValue,
+    incoming: BTreeMap<Option<ProgramCounter>, Identifier>,
+}
+
+/// The control flow between the instructions of a method, indexed by their positions.
+#[derive(Debug)]
+struct Graph {
+    pcs: Vec<ProgramCounter>,
+    normal_successors: Vec<BTreeSet<usize>>,
+    exceptional_successors: Vec<BTreeSet<usize>>,
+    successors: Vec<Vec<usize>>,
+    handlers: BTreeSet<usize>,
+}
+
+impl Graph {
+    fn new(method: &MokaIRMethod) -> Self {
+        let pcs: Vec<_> = method.instructions.iter().map(|(pc, _)| *pc).collect();
+        let index_of: BTreeMap<_, _> = pcs
+            .iter()
+            .enumerate()
+            .map(|(index, pc)| (*pc, index))
+            .collect();
+        let mut normal_successors = vec![BTreeSet::new(); pcs.len()];
+        let mut exceptional_successors = vec![BTreeSet::new(); pcs.len()];
+        for (index, pc) in pcs.iter().enumerate() {
+            for (_, dst, transfer) in method
+                .control_flow_graph
+                .edges_from(*pc)
+                .into_iter()
+                .flatten()
+            {
+                let Some(&dst) = index_of.get(&dst) else {
+                    continue;
+                };
+                if let ControlTransfer::Exception(_) = transfer {
+                    exceptional_successors[index].insert(dst);
+                } else {
+                    normal_successors[index].insert(dst);
+                }
+            }
+        }
+        let successors = normal_successors
+            .iter()
+            .zip(&exceptional_successors)
+            .map(|(normal, exceptional)| normal.union(exceptional).copied().collect())
+            .collect();
+        let handlers = method
+            .exception_table
+            .iter()
+            .filter_map(|it| index_of.get(&it.handler_pc).copied())
+            .collect();
+        Self {
+            pcs,
+            normal_successors,
+            exceptional_successors,
+            successors,
+            handlers,
+        }
+    }
+}
+
+/// Constructs the conventional SSA form of a method.
+/// # Errors
+/// - [`Error::TooManyValues`] if the identifiers of the values defined by the Phi nodes would
+///   exceed [`u16::MAX`].
+pub fn construct(method: &MokaIRMethod) -> Result<SsaMethod, Error> {
+    let graph = Graph::new(method);
+    let mut instructions: Vec<_> = method
+        .instructions
+        .iter()
+        .map(|(_, insn)| insn.clone())
+        .collect();
+
+    // Each distinct set of identifiers merged by a Phi operand is treated as a variable, which is
+    // assigned by the definition of each of its members.
+    let mut variables: Vec<BTreeSet<Identifier>> = Vec::new();
+    for insn in &mut instructions {
+        for operand in insn.operands_mut() {
+            if let Operand::Phi(ids) = operand {
+                if !variables.contains(ids) {
+                    variables.push(ids.clone());
+                }
+            }
+        }
+    }
+
+    let mut phis: Vec<Vec<PhiNode>> = (0..instructions.len()).map(|_| Vec::new()).collect();
+    if !instructions.is_empty() {
+        let idom = immediate_dominators(&graph.successors, 0);
+        place_phis(&graph, &idom, &instructions, &variables, &mut phis)?;
+        rename(&graph, &idom, &mut instructions, &variables, &mut phis);
+    }
+    prune(&mut phis, &instructions);
+
+    Ok(SsaMethod {
+        access_flags: method.access_flags,
+        name: method.name.clone(),
+        descriptor: method.descriptor.clone(),
+        owner: method.owner.clone(),
+        blocks: split_blocks(&graph, instructions, phis),
+        exception_table: method.exception_table.clone(),
+    })
+}
+
+/// Places the Phi nodes for each variable at the iterated dominance frontier of its definitions.
+fn place_phis(
+    graph: &Graph,
+    idom: &[Option<usize>],
+    instructions: &[MokaInstruction],
+    variables: &[BTreeSet<Identifier>],
+    phis: &mut [Vec<PhiNode>],
+) -> Result<(), Error> {
+    let frontiers = dominance_frontiers(&graph.successors, idom, 0);
+    let mut next_value = graph
+        .pcs
+        .last()
+        .map_or(0, |pc| u32::from(u16::from(*pc)) + 1);
+    for (variable, ids) in variables.iter().enumerate() {
+        let mut def_sites = BTreeSet::new();
+        for id in ids {
+            match id {
+                Identifier::Local(value) => def_sites.extend(
+                    instructions
+                        .iter()
+                        .position(|insn| insn.def() == Some(*value)),
+                ),
+                Identifier::CaughtException => def_sites.extend(graph.handlers.iter().copied()),
+                // The arguments are defined when entering the method, which dominates every node.
+                Identifier::This | Identifier::Arg(_) => {}
+            }
+        }
+        let mut worklist: Vec<_> = def_sites.iter().copied().collect();
+        let mut placed = BTreeSet::new();
+        while let Some(node) = worklist.pop() {
+            for &frontier in &frontiers[node] {
+                if placed.insert(frontier) {
+                    let value = u16::try_from(next_value).map_err(|_| Error::TooManyValues)?;
+                    next_value += 1;
+                    phis[frontier].push(PhiNode {
+                        variable,
+                        value: LocalValue::new(value),
+                        incoming: BTreeMap::new(),
+                    });
+                    if !def_sites.contains(&frontier) {
+                        worklist.push(frontier);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the Phi operands with the reaching definitions and fills the incoming values of the
+/// Phi nodes by walking the dominator tree.
+fn rename(
+    graph: &Graph,
+    idom: &[Option<usize>],
+    instructions: &mut [MokaInstruction],
+    variables: &[BTreeSet<Identifier>],
+    phis: &mut [Vec<PhiNode>],
+) {
+    let mut variables_of: HashMap<Identifier, Vec<usize>> = HashMap::new();
+    for (variable, ids) in variables.iter().enumerate() {
+        for id in ids {
+            variables_of.entry(*id).or_default().push(variable);
+        }
+    }
+    let mut children = vec![Vec::new(); instructions.len()];
+    for (node, dominator) in idom.iter().enumerate() {
+        if let Some(dominator) = dominator {
+            children[*dominator].push(node);
+        }
+    }
+    let mut stacks: Vec<Vec<Identifier>> = variables
+        .iter()
+        .map(|ids| {
+            ids.iter()
+                .filter(|it| matches!(it, Identifier::This | Identifier::Arg(_)))
+                .copied()
+                .collect()
+        })
+        .collect();
+    fill_incoming(&mut phis[0], &stacks, None);
+    let mut visits: Vec<(usize, Option<Vec<usize>>)> = vec![(0, None)];
+    while let Some((node, pushed)) = visits.pop() {
+        if let Some(pushed) = pushed {
+            for variable in pushed {
+                stacks[variable].pop();
+            }
+            continue;
+        }
+        let mut pushed: Vec<usize> = Vec::new();
+        for phi in &phis[node] {
+            stacks[phi.variable].push(phi.value.into());
+            pushed.push(phi.variable);
+        }
+        if graph.handlers.contains(&node) {
+            for &variable in variables_of
+                .get(&Identifier::CaughtException)
+                .into_iter()
+                .flatten()
+            {
+                stacks[variable].push(Identifier::CaughtException);
+                pushed.push(variable);
+            }
+        }
+        for operand in instructions[node].operands_mut() {
+            if let Operand::Phi(ids) = operand {
+                let top = variables
+                    .iter()
+                    .position(|it| it == ids)
+                    .and_then(|variable| stacks[variable].last());
+                if let Some(top) = top {
+                    *operand = Operand::Just(*top);
+                }
+            }
+        }
+        let pc = Some(graph.pcs[node]);
+        // The definition does not take effect if the instruction throws.
+        for &successor in &graph.exceptional_successors[node] {
+            fill_incoming(&mut phis[successor], &stacks, pc);
+        }
+        if let Some(def) = instructions[node].def() {
+            let id = Identifier::Local(def);
+            for &variable in variables_of.get(&id).into_iter().flatten() {
+                stacks[variable].push(id);
+                pushed.push(variable);
+            }
+        }
+        for &successor in &graph.normal_successors[node] {
+            fill_incoming(&mut phis[successor], &stacks, pc);
+        }
+        visits.push((node, Some(pushed)));
+        visits.extend(children[node].iter().rev().map(|&child| (child, None)));
+    }
+}
+
+fn fill_incoming(phis: &mut [PhiNode], stacks: &[Vec<Identifier>], source: Option<ProgramCounter>) {
+    for phi in phis {
+        if let Some(top) = stacks[phi.variable].last() {
+            phi.incoming.insert(source, *top);
+        }
+    }
+}
+
+/// Groups the instructions into basic blocks.
+fn split_blocks(
+    graph: &Graph,
+    instructions: Vec<MokaInstruction>,
+    phis: Vec<Vec<PhiNode>>,
+) -> BTreeMap<ProgramCounter, BasicBlock> {
+    let mut predecessor_counts = vec![0usize; instructions.len()];
+    for &successor in graph.successors.iter().flatten() {
+        predecessor_counts[successor] += 1;
+    }
+    let mut blocks = BTreeMap::new();
+    let mut current: Option<(ProgramCounter, BasicBlock)> = None;
+    for (node, (insn, phis)) in instructions.into_iter().zip(phis).enumerate() {
+        let pc = graph.pcs[node];
+        let is_leader = node == 0
+            || predecessor_counts[node] != 1
+            || graph.successors[node - 1] != [node]
+            || !phis.is_empty();
+        if is_leader {
+            blocks.extend(current.take());
+        }
+        let (_, block) = current.get_or_insert_with(|| (pc, BasicBlock::default()));
+        block
+            .phis
+            .extend(phis.into_iter().map(|phi| MokaInstruction::Phi {
+                value: phi.value,
+                incoming: phi.incoming,
+            }));
+        block.instructions.push((pc, insn));
+        block.successors = graph.successors[node]
+            .iter()
+            .map(|&it| graph.pcs[it])
+            .collect();
+    }
+    blocks.extend(current);
+    blocks
+}
+
+/// Computes the dominance frontier of each node.
+/// The entry is considered to have an additional predecessor that enters the method.
+/// See [A Simple, Fast Dominance Algorithm](https://www.cs.tufts.edu/comp/150FP/archive/keith-cooper/dom14.pdf).
+fn dominance_frontiers(
+    successors: &[Vec<usize>],
+    idom: &[Option<usize>],
+    entry: usize,
+) -> Vec<BTreeSet<usize>> {
+    let mut predecessors = vec![Vec::new(); successors.len()];
+    for (node, succs) in successors.iter().enumerate() {
+        for &succ in succs {
+            predecessors[succ].push(node);
+        }
+    }
+    let mut frontiers = vec![BTreeSet::new(); successors.len()];
+    for (node, preds) in predecessors.iter().enumerate() {
+        if preds.len() + usize::from(node == entry) < 2 {
+            continue;
+        }
+        for &pred in preds {
+            let mut runner = Some(pred);
+            while let Some(it) = runner {
+                if Some(it) == idom[node] {
+                    break;
+                }
+                frontiers[it].insert(node);
+                runner = idom[it];
+            }
+        }
+    }
+    frontiers
+}
+
+/// Removes the Phi nodes whose values are not used by any instruction, directly or through
+/// other Phi nodes.
+fn prune(phis: &mut [Vec<PhiNode>], instructions: &[MokaInstruction]) {
+    let incoming: HashMap<Identifier, Vec<Identifier>> = phis
+        .iter()
+        .flatten()
+        .map(|phi| (phi.value.into(), phi.incoming.values().copied().collect()))
+        .collect();
+    let mut live = BTreeSet::new();
+    let mut worklist: Vec<Identifier> = instructions
+        .iter()
+        .flat_map(MokaInstruction::uses)
+        .filter(|it| incoming.contains_key(it))
+        .collect();
+    while let Some(id) = worklist.pop() {
+        if live.insert(id) {
+            worklist.extend(incoming[&id].iter().filter(|it| incoming.contains_key(it)));
+        }
+    }
+    for phis in phis {
+        phis.retain(|phi| live.contains(&phi.value.into()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, MethodBody},
+            Method,
+        },
+    };
+    use Instruction::{
+        BiPush, Goto, IAdd, IConst0, IConst1, IConst2, IInc, ILoad, ILoad0, ILoad1, ILoad2,
+        IReturn, IStore1, IStore2, IfICmpGe, IfICmpLe, IfLe,
+    };
+
+    fn construct_method(descriptor: &str, instructions: Vec<(u16, Instruction)>) -> SsaMethod {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: "test".to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 2,
+                max_locals: 3,
+                instructions: instructions
+                    .into_iter()
+                    .map(|(pc, insn)| (pc.into(), insn))
+                    .collect::<BTreeMap<_, _>>()
+                    .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        construct(&method.brew().unwrap()).unwrap()
+    }
+
+    fn has_phi_operand(method: &SsaMethod) -> bool {
+        method
+            .blocks
+            .values()
+            .flat_map(|it| it.instructions.iter())
+            .any(|(_, insn)| insn.to_string().contains("Phi("))
+    }
+
+    #[test]
+    fn if_else() {
+        // return a > 0 ? 1 : 2;
+        let method = construct_method(
+            "(I)I",
+            vec![
+                (0, ILoad0),
+                (1, IfLe(8.into())),
+                (4, IConst1),
+                (5, Goto(9.into())),
+                (8, IConst2),
+                (9, IReturn),
+            ],
+        );
+        assert!(!has_phi_operand(&method));
+        let merge = &method.blocks[&9.into()];
+        let [MokaInstruction::Phi { value, incoming }] = merge.phis.as_slice() else {
+            panic!("Unexpected Phi nodes: {:?}", merge.phis);
+        };
+        assert_eq!(
+            *incoming,
+            BTreeMap::from([
+                (Some(5.into()), Identifier::Local(LocalValue::new(4))),
+                (Some(8.into()), Identifier::Local(LocalValue::new(8))),
+            ])
+        );
+        assert_eq!(
+            merge.instructions,
+            vec![(
+                9.into(),
+                MokaInstruction::Return(Some(Operand::Just((*value).into())))
+            )]
+        );
+        assert_eq!(method.blocks.len(), 4);
+    }
+
+    #[test]
+    fn loop_header() {
+        // int sum = 0; for (int i = 0; i < n; i++) { sum += i; } return sum;
+        let method = construct_method(
+            "(I)I",
+            vec![
+                (0, IConst0),
+                (1, IStore1),
+                (2, IConst0),
+                (3, IStore2),
+                (4, ILoad2),
+                (5, ILoad0),
+                (6, IfICmpGe(19.into())),
+                (9, ILoad1),
+                (10, ILoad2),
+                (11, IAdd),
+                (12, IStore1),
+                (13, IInc(2, 1)),
+                (16, Goto(4.into())),
+                (19, ILoad(1)),
+                (21, IReturn),
+            ],
+        );
+        assert!(!has_phi_operand(&method));
+        let header = &method.blocks[&4.into()];
+        assert_eq!(header.phis.len(), 2);
+        for phi in &header.phis {
+            let MokaInstruction::Phi { incoming, .. } = phi else {
+                panic!("Unexpected instruction: {phi}");
+            };
+            let sources: Vec<_> = incoming.keys().copied().collect();
+            assert_eq!(sources, vec![Some(3.into()), Some(16.into())]);
+        }
+        assert_eq!(header.successors, BTreeSet::from([9.into(), 19.into()]));
+    }
+
+    #[test]
+    fn loop_at_entry() {
+        // do { a++; } while (a <= 10); return a;
+        let method = construct_method(
+            "(I)I",
+            vec![
+                (0, IInc(0, 1)),
+                (3, ILoad0),
+                (4, BiPush(10)),
+                (6, IfICmpLe(0.into())),
+                (9, ILoad0),
+                (10, IReturn),
+            ],
+        );
+        assert!(!has_phi_operand(&method));
+        let entry = &method.blocks[&0.into()];
+        let [MokaInstruction::Phi { incoming, .. }] = entry.phis.as_slice() else {
+            panic!("Unexpected Phi nodes: {:?}", entry.phis);
+        };
+        assert_eq!(
+            *incoming,
+            BTreeMap::from([
+                (None, Identifier::Arg(0)),
+                (Some(6.into()), Identifier::Local(LocalValue::new(0))),
+            ])
+        );
+    }
+}
@@ -0,0 +1,1312 @@
+//! Transformations of Moka IR.
+#![allow(
+    clippy::similar_names,
+    reason = "The caller and the callee are named after each other"
+)]
+
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fmt::{self, Display, Formatter},
+    ops::{BitOr, RangeInclusive},
+};
+
+use itertools::Itertools;
+
+use crate::{
+    decompiler::dominators::{dominates, immediate_dominators},
+    jvm::{
+        code::{ExceptionTableEntry, ProgramCounter},
+        method, ConstantValue,
+    },
+};
+
+use super::{
+    control_flow::{
+        path_condition::{PathCondition, Predicate, Value},
+        ControlTransfer,
+    },
+    data_flow::value_numbering::ValueNumbering,
+    expression::{Condition, Expression},
+    ControlFlowGraph, Identifier, LocalValue, MokaIRMethod, MokaInstruction, Operand,
+};
+
+/// An error when inlining a method.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// The instruction at the call site is not a call.
+    #[error("There is no call at {0}")]
+    NotACall(ProgramCounter),
+    /// The callee does not match the method being called.
+    #[error("The callee does not match the method being called")]
+    CalleeMismatch,
+    /// The callee or the resulting method exceeds the [`Limits`].
+    #[error("The method is too large to be inlined")]
+    TooLarge,
+    /// The method contains constructs that cannot be inlined.
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+}
+
+/// The size limits of inlining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum number of instructions in a callee.
+    pub max_callee_instructions: usize,
+    /// The maximum number of instructions in the caller after inlining.
+    pub max_instructions: usize,
+}
+
+impl Default for Limits {
+    /// Creates the limits similar to the ones of the `HotSpot` JIT compiler.
+    fn default() -> Self {
+        Self {
+            max_callee_instructions: 35,
+            max_instructions: 8000,
+        }
+    }
+}
+
+/// Inlines `callee` into `caller` at `call_site` under the default [`Limits`].
+/// See [`inline_with_limits`] for details.
+/// # Errors
+/// See [`inline_with_limits`].
+pub fn inline(
+    caller: &mut MokaIRMethod,
+    call_site: ProgramCounter,
+    callee: &MokaIRMethod,
+) -> Result<(), Error> {
+    inline_with_limits(caller, call_site, callee, &Limits::default())
+}
+
+/// Inlines `callee` into `caller` at `call_site`.
+///
+/// The instructions of `callee` are placed after the ones of `caller`, with the values
+/// renamed after their new locations and the parameters replaced by the arguments of the call.
+/// The call is replaced by a jump to the inlined entry, and each `return` is replaced by a jump
+/// to the instruction following the call.
+/// The uses of the value defined by the call refer to the returned values instead.
+/// The exception handlers of `callee` are moved along with its instructions, and the exceptions
+/// escaping from them are caught by the handlers of `caller` covering the call.
+///
+/// The receiver of an instance call is assumed to be non-null, and `callee` is assumed to be
+/// the method the call dispatches to, e.g., as resolved by
+/// [`Devirtualizer`](crate::analysis::devirtualize::Devirtualizer).
+/// # Errors
+/// - [`Error::NotACall`] if the instruction at `call_site` is not a call.
+/// - [`Error::CalleeMismatch`] if the name, descriptor, or staticness of `callee` does not
+///   match the method being called.
+/// - [`Error::TooLarge`] if `callee` or the resulting method exceeds `limits`.
+/// - [`Error::Unsupported`] if `callee` is `synchronized` or any of the methods contains
+///   [`MokaInstruction::Phi`].
+pub fn inline_with_limits(
+    caller: &mut MokaIRMethod,
+    call_site: ProgramCounter,
+    callee: &MokaIRMethod,
+    limits: &Limits,
+) -> Result<(), Error> {
+    let Some(MokaInstruction::Definition {
+        value: result,
+        expr: Expression::Call { method, this, args },
+    }) = caller.instructions.get(&call_site)
+    else {
+        return Err(Error::NotACall(call_site));
+    };
+    if *method.name != *callee.name
+        || method.descriptor != callee.descriptor
+        || this.is_some() == callee.is_static()
+    {
+        return Err(Error::CalleeMismatch);
+    }
+    check_inlinable(caller, callee, limits)?;
+    let (Some((&callee_entry, _)), Some(continuation)) = (
+        callee.instructions.entry_point(),
+        caller.instructions.next_pc_of(&call_site),
+    ) else {
+        return Err(Error::Unsupported(
+            "empty callee or call at the end".to_owned(),
+        ));
+    };
+
+    // The inlined instructions are placed after everything the caller refers to.
+    let base = caller
+        .instructions
+        .iter()
+        .map(|(pc, _)| *pc)
+        .chain(
+            caller
+                .exception_table
+                .iter()
+                .flat_map(|it| [*it.covered_pc.end(), it.handler_pc]),
+        )
+        .max()
+        .map_or(Some(0), |it| u16::from(it).checked_add(1))
+        .ok_or(Error::TooLarge)?;
+    let callee_last = callee
+        .instructions
+        .iter()
+        .map(|(pc, _)| u16::from(*pc))
+        .max()
+        .unwrap_or_default();
+    if base.checked_add(callee_last).is_none() {
+        return Err(Error::TooLarge);
+    }
+    let shift = |pc: ProgramCounter| -> ProgramCounter { (u16::from(pc) + base).into() };
+    let rename = |id: Identifier| match id {
+        Identifier::This => this.clone().unwrap_or(Operand::Just(id)),
+        Identifier::Arg(index) => args
+            .get(usize::from(index))
+            .cloned()
+            .unwrap_or(Operand::Just(id)),
+        Identifier::Local(value) => Operand::Just(LocalValue::new(u16::from(value) + base).into()),
+        Identifier::CaughtException => Operand::Just(id),
+    };
+    let inlined = relocate(callee, shift, rename, continuation);
+    let inlined_types: Vec<_> = callee
+        .types
+        .iter()
+        .filter_map(|(id, ty)| match rename(*id) {
+            Operand::Just(renamed @ Identifier::Local(_)) => Some((renamed, ty.clone())),
+            _ => None,
+        })
+        .collect();
+    let result = Identifier::Local(*result);
+    let edges = merge_edges(caller, call_site, callee, shift, &inlined, continuation);
+
+    let callee_range = shift(callee_entry)..=shift(callee_last.into());
+    merge_exception_tables(caller, call_site, callee, shift, callee_range);
+
+    let mut instructions: BTreeMap<_, _> = caller
+        .instructions
+        .iter()
+        .map(|(pc, insn)| (*pc, insn.clone()))
+        .collect();
+    instructions.insert(
+        call_site,
+        MokaInstruction::Jump {
+            condition: None,
+            target: shift(callee_entry),
+        },
+    );
+    if let Some(returned) = inlined.returned {
+        for insn in instructions.values_mut() {
+            for operand in insn.operands_mut() {
+                if operand.iter().any(|it| *it == result) {
+                    *operand = substitute(operand, |id| {
+                        if id == result {
+                            returned.clone()
+                        } else {
+                            Operand::Just(id)
+                        }
+                    });
+                }
+            }
+        }
+    }
+    instructions.extend(inlined.instructions);
+    caller.instructions = instructions.into();
+    caller.types.extend(inlined_types);
+    caller.control_flow_graph = ControlFlowGraph::from_edges(edges);
+    Ok(())
+}
+
+fn check_inlinable(
+    caller: &MokaIRMethod,
+    callee: &MokaIRMethod,
+    limits: &Limits,
+) -> Result<(), Error> {
+    if callee
+        .access_flags
+        .contains(method::AccessFlags::SYNCHRONIZED)
+    {
+        return Err(Error::Unsupported("synchronized callee".to_owned()));
+    }
+    let has_phi = |it: &MokaIRMethod| {
+        it.instructions
+            .iter()
+            .any(|(_, insn)| matches!(insn, MokaInstruction::Phi { .. }))
+    };
+    if has_phi(caller) || has_phi(callee) {
+        return Err(Error::Unsupported("explicit Phi".to_owned()));
+    }
+    let callee_size = callee.instructions.iter().count();
+    let caller_size = caller.instructions.iter().count();
+    if callee_size > limits.max_callee_instructions
+        || caller_size + callee_size > limits.max_instructions
+    {
+        return Err(Error::TooLarge);
+    }
+    Ok(())
+}
+
+/// Moves the exception handlers of the callee into the caller, and extends the handlers
+/// covering the call site to cover `inlined_range`.
+fn merge_exception_tables(
+    caller: &mut MokaIRMethod,
+    call_site: ProgramCounter,
+    callee: &MokaIRMethod,
+    shift: impl Fn(ProgramCounter) -> ProgramCounter,
+    inlined_range: RangeInclusive<ProgramCounter>,
+) {
+    let escaping_handlers: Vec<_> = caller
+        .exception_table
+        .iter()
+        .filter(|it| it.covers(call_site))
+        .map(|it| ExceptionTableEntry {
+            covered_pc: inlined_range.clone(),
+            handler_pc: it.handler_pc,
+            catch_type: it.catch_type.clone(),
+        })
+        .collect();
+    // The handlers in the callee take precedence over the ones in the caller.
+    caller
+        .exception_table
+        .extend(callee.exception_table.iter().map(|it| ExceptionTableEntry {
+            covered_pc: shift(*it.covered_pc.start())..=shift(*it.covered_pc.end()),
+            handler_pc: shift(it.handler_pc),
+            catch_type: it.catch_type.clone(),
+        }));
+    caller.exception_table.extend(escaping_handlers);
+}
+
+/// The instructions of a callee relocated into the caller.
+#[derive(Debug)]
+struct Relocated {
+    instructions: Vec<(ProgramCounter, MokaInstruction)>,
+    /// The values that may be returned.
+    returned: Option<Operand>,
+    /// The locations of the instructions that used to return.
+    returns: Vec<ProgramCounter>,
+}
+
+fn relocate(
+    callee: &MokaIRMethod,
+    shift: impl Fn(ProgramCounter) -> ProgramCounter,
+    rename: impl Fn(Identifier) -> Operand + Copy,
+    continuation: ProgramCounter,
+) -> Relocated {
+    let mut inlined = Vec::new();
+    let mut returned: Option<Operand> = None;
+    let mut returns = Vec::new();
+    for (pc, insn) in callee.instructions.iter() {
+        let pc = shift(*pc);
+        let mut insn = insn.clone();
+        for operand in insn.operands_mut() {
+            *operand = substitute(operand, rename);
+        }
+        match &mut insn {
+            MokaInstruction::Definition { value, expr } => {
+                if let Operand::Just(Identifier::Local(renamed)) = rename((*value).into()) {
+                    *value = renamed;
+                }
+                if let Expression::Subroutine {
+                    target,
+                    return_address,
+                } = expr
+                {
+                    *target = shift(*target);
+                    *return_address = shift(*return_address);
+                }
+            }
+            MokaInstruction::Jump { target, .. } => *target = shift(*target),
+            MokaInstruction::Switch {
+                default, branches, ..
+            } => {
+                *default = shift(*default);
+                for it in branches.values_mut() {
+                    *it = shift(*it);
+                }
+            }
+            MokaInstruction::Return(value) => {
+                if let Some(value) = value.take() {
+                    returned = Some(match returned {
+                        Some(it) => it | value,
+                        None => value,
+                    });
+                }
+                returns.push(pc);
+                insn = MokaInstruction::Jump {
+                    condition: None,
+                    target: continuation,
+                };
+            }
+            MokaInstruction::Nop
+            | MokaInstruction::SubroutineRet(_)
+            | MokaInstruction::Phi { .. } => {}
+        }
+        inlined.push((pc, insn));
+    }
+    Relocated {
+        instructions: inlined,
+        returned,
+        returns,
+    }
+}
+
+/// Merges the control flow graphs, replacing the edges from the call site.
+fn merge_edges(
+    caller: &MokaIRMethod,
+    call_site: ProgramCounter,
+    callee: &MokaIRMethod,
+    shift: impl Fn(ProgramCounter) -> ProgramCounter,
+    inlined: &Relocated,
+    continuation: ProgramCounter,
+) -> Vec<(ProgramCounter, ProgramCounter, ControlTransfer)> {
+    let callee_entry = callee.control_flow_graph.entry_point();
+    let handler_edges: Vec<_> = caller
+        .control_flow_graph
+        .edges_from(call_site)
+        .into_iter()
+        .flatten()
+        .filter(|(_, _, transfer)| matches!(transfer, ControlTransfer::Exception(_)))
+        .map(|(_, dst, transfer)| (dst, transfer.clone()))
+        .collect();
+    let mut edges: Vec<_> = caller
+        .control_flow_graph
+        .edges()
+        .filter(|(src, _, _)| *src != call_site)
+        .map(|(src, dst, transfer)| (src, dst, transfer.clone()))
+        .collect();
+    edges.push((
+        call_site,
+        shift(callee_entry),
+        ControlTransfer::Unconditional,
+    ));
+    edges.extend(
+        callee
+            .control_flow_graph
+            .edges()
+            .map(|(src, dst, transfer)| (shift(src), shift(dst), transfer.clone())),
+    );
+    edges.extend(
+        inlined
+            .returns
+            .iter()
+            .map(|&pc| (pc, continuation, ControlTransfer::Unconditional)),
+    );
+    // Like in the generated IR, every definition may throw an exception.
+    for (pc, insn) in &inlined.instructions {
+        if let MokaInstruction::Definition { .. } = insn {
+            edges.extend(
+                handler_edges
+                    .iter()
+                    .map(|(handler, transfer)| (*pc, *handler, transfer.clone())),
+            );
+        }
+    }
+    edges
+}
+
+/// Replaces each of the identifiers in `operand` with the result of `f`.
+fn substitute(operand: &Operand, f: impl Fn(Identifier) -> Operand) -> Operand {
+    operand
+        .iter()
+        .map(|it| f(*it))
+        .reduce(BitOr::bitor)
+        .unwrap_or_else(|| operand.clone())
+}
+
+/// A dispatcher of a flattened control flow undone by [`deflatten`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deflattened {
+    /// The location of the `switch` dispatching the blocks.
+    pub dispatcher: ProgramCounter,
+    /// The number of jumps redirected from the dispatcher to the blocks.
+    pub redirected: usize,
+    /// Whether every path into the dispatcher other than the initial one is redirected, in which
+    /// case the `switch` is replaced with a jump to the first block.
+    pub bypassed: bool,
+    /// The blocks in the order they are reached from the first block, i.e., the order of the
+    /// blocks before flattening.
+    pub block_order: Vec<ProgramCounter>,
+}
+
+/// Undoes control flow flattening in `method`.
+///
+/// A flattened method stores the index of the next block in a state variable and jumps back to a
+/// `switch` on it, which dispatches to the block.
+/// For each jump into a `switch` along which the state variable is set to a constant, the jump is
+/// redirected to the block the `switch` would dispatch to, and the control flow graph is updated
+/// accordingly.
+/// If every path into the `switch` is redirected, the `switch` itself is replaced with a jump to
+/// the block selected by the initial state.
+/// The instructions setting the state variable are left in place.
+///
+/// Returns the dispatchers for which at least one jump is redirected.
+pub fn deflatten(method: &mut MokaIRMethod) -> Vec<Deflattened> {
+    let dispatchers: Vec<_> = method
+        .instructions
+        .iter()
+        .filter(|(_, insn)| matches!(insn, MokaInstruction::Switch { .. }))
+        .map(|(pc, _)| *pc)
+        .collect();
+    dispatchers
+        .into_iter()
+        .filter_map(|it| deflatten_dispatcher(method, it))
+        .collect()
+}
+
+fn deflatten_dispatcher(
+    method: &mut MokaIRMethod,
+    dispatcher: ProgramCounter,
+) -> Option<Deflattened> {
+    let Some(MokaInstruction::Switch {
+        match_value,
+        branches,
+        default,
+    }) = method.instructions.get(&dispatcher).cloned()
+    else {
+        return None;
+    };
+    let state: BTreeSet<_> = match_value.iter().copied().collect();
+    let dispatch = |value: i32| branches.get(&value).copied().unwrap_or(default);
+
+    // The dispatcher consists of the `switch` and the `nop`s (e.g., loads) preceding it.
+    let mut region = BTreeSet::from([dispatcher]);
+    let mut head = dispatcher;
+    while let Some(prev) = method.instructions.prev_pc_of(&head) {
+        if !matches!(method.instructions.get(&prev), Some(MokaInstruction::Nop)) {
+            break;
+        }
+        region.insert(prev);
+        head = prev;
+    }
+    let mut predecessors: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for (src, dst, _) in method.control_flow_graph.edges() {
+        predecessors.entry(dst).or_default().push(src);
+    }
+    let incoming: Vec<_> = method
+        .control_flow_graph
+        .edges()
+        .filter(|(src, dst, _)| !region.contains(src) && region.contains(dst))
+        .map(|(src, dst, _)| (src, dst))
+        .collect();
+
+    let mut redirections = BTreeMap::new();
+    let mut entry_state = None;
+    let mut unresolved = 0;
+    for (src, dst) in incoming {
+        let value = state_along(method, &predecessors, &state, &region, src);
+        match (method.instructions.get(&src), value) {
+            (Some(MokaInstruction::Jump { target, .. }), Some(value)) if *target == dst => {
+                redirections.insert(src, (dst, dispatch(value)));
+            }
+            (Some(MokaInstruction::Jump { target, .. }), _) if *target == dst => unresolved += 1,
+            (_, Some(value)) if entry_state.is_none() => entry_state = Some(value),
+            _ => unresolved += 1,
+        }
+    }
+    if redirections.is_empty() {
+        return None;
+    }
+
+    for (src, (_, new_target)) in &redirections {
+        if let Some(MokaInstruction::Jump { target, .. }) = method.instructions.get_mut(src) {
+            *target = *new_target;
+        }
+    }
+    let bypassed = unresolved == 0;
+    let entry_target = entry_state.map(dispatch);
+    // Without an entry into the dispatcher, the `switch` is unreachable and left as is.
+    let replacement = entry_target.filter(|_| bypassed);
+    let mut edges: Vec<_> = method
+        .control_flow_graph
+        .edges()
+        .filter(|(src, _, _)| replacement.is_none() || *src != dispatcher)
+        .map(|(src, dst, transfer)| match redirections.get(&src) {
+            Some((old_target, new_target)) if *old_target == dst => {
+                (src, *new_target, transfer.clone())
+            }
+            _ => (src, dst, transfer.clone()),
+        })
+        .collect();
+    if let (Some(target), Some(insn)) = (replacement, method.instructions.get_mut(&dispatcher)) {
+        *insn = MokaInstruction::Jump {
+            condition: None,
+            target,
+        };
+        edges.push((dispatcher, target, ControlTransfer::Unconditional));
+    }
+    method.control_flow_graph = ControlFlowGraph::from_edges(edges);
+
+    let blocks: BTreeSet<_> = branches.values().copied().chain([default]).collect();
+    let first = entry_target.or_else(|| redirections.values().map(|(_, it)| *it).next());
+    Some(Deflattened {
+        dispatcher,
+        redirected: redirections.len(),
+        bypassed,
+        block_order: first
+            .map(|it| block_order(method, it, &blocks, &region))
+            .unwrap_or_default(),
+    })
+}
+
+/// Finds the constant assigned to the state variable on the straight-line path ending at `pc`.
+fn state_along(
+    method: &MokaIRMethod,
+    predecessors: &BTreeMap<ProgramCounter, Vec<ProgramCounter>>,
+    state: &BTreeSet<Identifier>,
+    region: &BTreeSet<ProgramCounter>,
+    mut pc: ProgramCounter,
+) -> Option<i32> {
+    let mut visited = BTreeSet::new();
+    loop {
+        if region.contains(&pc) || !visited.insert(pc) {
+            return None;
+        }
+        if let Some(MokaInstruction::Definition { value, expr }) = method.instructions.get(&pc) {
+            if state.contains(&Identifier::Local(*value)) {
+                return match expr {
+                    Expression::Const(ConstantValue::Integer(it)) => Some(*it),
+                    _ => None,
+                };
+            }
+        }
+        let [predecessor] = predecessors.get(&pc)?.as_slice() else {
+            return None;
+        };
+        pc = *predecessor;
+    }
+}
+
+/// Lists the `blocks` in the order they are reached from `first`, without passing through the
+/// dispatcher.
+fn block_order(
+    method: &MokaIRMethod,
+    first: ProgramCounter,
+    blocks: &BTreeSet<ProgramCounter>,
+    region: &BTreeSet<ProgramCounter>,
+) -> Vec<ProgramCounter> {
+    let mut order = Vec::new();
+    let mut visited = BTreeSet::new();
+    let mut stack = vec![first];
+    while let Some(pc) = stack.pop() {
+        if region.contains(&pc) || !visited.insert(pc) {
+            continue;
+        }
+        if blocks.contains(&pc) {
+            order.push(pc);
+        }
+        let successors: Vec<_> = method
+            .control_flow_graph
+            .edges_from(pc)
+            .into_iter()
+            .flatten()
+            .filter(|(_, _, transfer)| !matches!(transfer, ControlTransfer::Exception(_)))
+            .map(|(_, dst, _)| dst)
+            .collect();
+        // Visit the successors in ascending order.
+        stack.extend(successors.into_iter().rev());
+    }
+    order
+}
+
+/// A branch with a fixed outcome removed by [`eliminate_opaque_predicates`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EliminatedBranch {
+    /// The location of the branch.
+    pub pc: ProgramCounter,
+    /// The condition of the branch.
+    pub condition: Condition,
+    /// Whether the jump is always taken.
+    pub taken: bool,
+    /// The reason why the outcome is fixed.
+    pub proof: Proof,
+    /// The instructions removed because they were only reachable through the other outcome.
+    pub removed: Vec<ProgramCounter>,
+}
+
+/// The justification of an [`EliminatedBranch`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Proof {
+    /// The operands of the condition are defined as the integer constants.
+    Constants(BTreeMap<Identifier, i32>),
+    /// The path condition reaching the branch contradicts the other outcome.
+    /// The values tested by the condition are defined outside of loops, so that the predicates
+    /// on them in the path condition refer to the same values.
+    PathCondition(PathCondition<Predicate<Value>>),
+}
+
+impl Display for Proof {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Constants(constants) => {
+                write!(f, "constants")?;
+                for (id, value) in constants {
+                    write!(f, " {id} = {value}")?;
+                }
+                Ok(())
+            }
+            Self::PathCondition(path_condition) => write!(f, "path condition {path_condition}"),
+        }
+    }
+}
+
+/// Removes the conditional jumps whose outcomes are fixed (e.g., opaque predicates inserted by
+/// obfuscators) together with the instructions that become unreachable.
+///
+/// The outcome of a jump is fixed if the operands of its condition are integer constants, or if
+/// the path condition reaching it contradicts one of the outcomes.
+/// The latter is only considered when the operands of the condition are defined outside of
+/// loops, since the predicates in a path condition may otherwise refer to the values of earlier
+/// iterations.
+/// A jump that is always taken becomes unconditional, and one that is never taken becomes a
+/// `nop`.
+/// The exception handlers that become unreachable are removed from the exception table, and the
+/// values defined by the removed instructions are removed from the `Phi`s using them.
+///
+/// Returns the removed branches in the order they are removed, each recording the proof of its
+/// outcome.
+pub fn eliminate_opaque_predicates(method: &mut MokaIRMethod) -> Vec<EliminatedBranch> {
+    let mut eliminated = Vec::new();
+    while let Some((pc, condition, taken, proof)) = find_opaque_predicate(method) {
+        let removed = fold_branch(method, pc, taken);
+        eliminated.push(EliminatedBranch {
+            pc,
+            condition,
+            taken,
+            proof,
+            removed,
+        });
+    }
+    eliminated
+}
+
+fn find_opaque_predicate(
+    method: &MokaIRMethod,
+) -> Option<(ProgramCounter, Condition, bool, Proof)> {
+    let constants: BTreeMap<_, _> = method
+        .instructions
+        .iter()
+        .filter_map(|(_, insn)| match insn {
+            MokaInstruction::Definition {
+                value,
+                expr: Expression::Const(ConstantValue::Integer(it)),
+            } => Some((Identifier::Local(*value), *it)),
+            _ => None,
+        })
+        .collect();
+    let constant = |operand: &Operand| match operand {
+        Operand::Just(id) => constants.get(id).copied(),
+        Operand::Phi(_) => None,
+    };
+    let mut path_conditions = None;
+    for (pc, insn) in &method.instructions {
+        let MokaInstruction::Jump {
+            condition: Some(condition),
+            ..
+        } = insn
+        else {
+            continue;
+        };
+        if let Some(taken) = condition.evaluate(constant) {
+            let values = condition
+                .uses()
+                .into_iter()
+                .filter_map(|id| Some((id, *constants.get(&id)?)))
+                .collect();
+            return Some((*pc, condition.clone(), taken, Proof::Constants(values)));
+        }
+        if !is_defined_once(method, condition) {
+            continue;
+        }
+        let path_conditions =
+            path_conditions.get_or_insert_with(|| method.control_flow_graph.bdd_path_conditions());
+        let Some(reaching) = path_conditions.get(pc) else {
+            continue;
+        };
+        if reaching.is_contradiction() {
+            continue;
+        }
+        let jump = reaching
+            .manager()
+            .predicate(Predicate::from(condition.clone()));
+        let taken = if (reaching.clone() & jump.clone()).is_contradiction() {
+            false
+        } else if (reaching.clone() & !jump).is_contradiction() {
+            true
+        } else {
+            continue;
+        };
+        return Some((
+            *pc,
+            condition.clone(),
+            taken,
+            Proof::PathCondition(reaching.to_dnf()),
+        ));
+    }
+    None
+}
+
+/// Checks whether each operand of `condition` is a single value defined outside of loops.
+fn is_defined_once(method: &MokaIRMethod, condition: &Condition) -> bool {
+    let mut operands = condition.clone();
+    operands
+        .operands_mut()
+        .into_iter()
+        .all(|operand| match operand {
+            Operand::Just(Identifier::This | Identifier::Arg(_)) => true,
+            Operand::Just(Identifier::Local(value)) => {
+                let def_pc = ProgramCounter::from(u16::from(*value));
+                let mut visited = BTreeSet::new();
+                let mut worklist = VecDeque::from([def_pc]);
+                while let Some(pc) = worklist.pop_front() {
+                    for (_, dst, _) in method
+                        .control_flow_graph
+                        .edges_from(pc)
+                        .into_iter()
+                        .flatten()
+                    {
+                        if dst == def_pc {
+                            return false;
+                        }
+                        if visited.insert(dst) {
+                            worklist.push_back(dst);
+                        }
+                    }
+                }
+                true
+            }
+            Operand::Just(Identifier::CaughtException) | Operand::Phi(_) => false,
+        })
+}
+
+/// Replaces the jump at `pc` with its fixed outcome and removes the unreachable instructions.
+fn fold_branch(method: &mut MokaIRMethod, pc: ProgramCounter, taken: bool) -> Vec<ProgramCounter> {
+    let Some(MokaInstruction::Jump { target, .. }) = method.instructions.get(&pc).cloned() else {
+        return Vec::new();
+    };
+    let successor = if taken {
+        Some(target)
+    } else {
+        method.instructions.next_pc_of(&pc)
+    };
+    let reachable_before = reachable(&method.control_flow_graph);
+    if let Some(insn) = method.instructions.get_mut(&pc) {
+        *insn = if taken {
+            MokaInstruction::Jump {
+                condition: None,
+                target,
+            }
+        } else {
+            MokaInstruction::Nop
+        };
+    }
+    let edges: Vec<_> = method
+        .control_flow_graph
+        .edges()
+        .filter(|(src, _, _)| *src != pc)
+        .map(|(src, dst, transfer)| (src, dst, transfer.clone()))
+        .chain(successor.map(|it| (pc, it, ControlTransfer::Unconditional)))
+        .collect();
+    method.control_flow_graph = ControlFlowGraph::from_edges(edges);
+    let reachable_after = reachable(&method.control_flow_graph);
+    let removed: BTreeSet<_> = reachable_before
+        .difference(&reachable_after)
+        .copied()
+        .collect();
+    if removed.is_empty() {
+        return Vec::new();
+    }
+
+    let removed_values: BTreeSet<_> = removed
+        .iter()
+        .filter_map(|pc| match method.instructions.get(pc) {
+            Some(MokaInstruction::Definition { value, .. }) => Some(Identifier::Local(*value)),
+            _ => None,
+        })
+        .collect();
+    let mut instructions: BTreeMap<_, _> = method
+        .instructions
+        .iter()
+        .filter(|(pc, _)| !removed.contains(pc))
+        .map(|(pc, insn)| (*pc, insn.clone()))
+        .collect();
+    for insn in instructions.values_mut() {
+        for operand in insn.operands_mut() {
+            if let Operand::Phi(ids) = operand {
+                ids.retain(|it| !removed_values.contains(it));
+                if let Some((id,)) = ids.iter().copied().collect_tuple() {
+                    *operand = Operand::Just(id);
+                }
+            }
+        }
+    }
+    method.instructions = instructions.into();
+    let edges: Vec<_> = method
+        .control_flow_graph
+        .edges()
+        .filter(|(src, dst, _)| !removed.contains(src) && !removed.contains(dst))
+        .map(|(src, dst, transfer)| (src, dst, transfer.clone()))
+        .collect();
+    method.control_flow_graph = ControlFlowGraph::from_edges(edges);
+    method
+        .exception_table
+        .retain(|it| !removed.contains(&it.handler_pc));
+    removed.into_iter().collect()
+}
+
+/// Returns the locations reachable from the entry point of `cfg`.
+fn reachable(cfg: &ControlFlowGraph<(), ControlTransfer>) -> BTreeSet<ProgramCounter> {
+    let mut visited = BTreeSet::from([cfg.entry_point()]);
+    let mut worklist = VecDeque::from([cfg.entry_point()]);
+    while let Some(pc) = worklist.pop_front() {
+        for (_, dst, _) in cfg.edges_from(pc).into_iter().flatten() {
+            if visited.insert(dst) {
+                worklist.push_back(dst);
+            }
+        }
+    }
+    visited
+}
+
+/// A definition removed by [`eliminate_common_subexpressions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedundantDefinition {
+    /// The location of the removed definition.
+    pub pc: ProgramCounter,
+    /// The value defined by the removed definition.
+    pub value: LocalValue,
+    /// The equivalent value replacing the uses of [`RedundantDefinition::value`].
+    pub replacement: Identifier,
+}
+
+/// Removes the definitions computing the same values as earlier ones according to
+/// [`ValueNumbering`], and replaces the uses of the values they define with the earlier ones.
+/// A definition is only removed if the equivalent definition dominates it, so that the earlier
+/// value is available wherever the removed one is used.
+/// The removed definitions become `nop`s.
+///
+/// Returns the removed definitions in ascending order of their locations.
+pub fn eliminate_common_subexpressions(method: &mut MokaIRMethod) -> Vec<RedundantDefinition> {
+    let numbering = ValueNumbering::new(method);
+    let pcs: Vec<_> = method.instructions.iter().map(|(pc, _)| *pc).collect();
+    let index_of: BTreeMap<_, _> = pcs
+        .iter()
+        .enumerate()
+        .map(|(index, pc)| (*pc, index))
+        .collect();
+    let successors: Vec<Vec<_>> = pcs
+        .iter()
+        .map(|pc| {
+            method
+                .control_flow_graph
+                .edges_from(*pc)
+                .into_iter()
+                .flatten()
+                .filter_map(|(_, dst, _)| index_of.get(&dst).copied())
+                .collect()
+        })
+        .collect();
+    let Some(&entry) = index_of.get(&method.control_flow_graph.entry_point()) else {
+        return Vec::new();
+    };
+    let idom = immediate_dominators(&successors, entry);
+    let available_at = |replacement: &Identifier, pc: ProgramCounter| match replacement {
+        Identifier::This | Identifier::Arg(_) => true,
+        Identifier::CaughtException => false,
+        Identifier::Local(_) => numbering
+            .def_site(replacement)
+            .and_then(|it| index_of.get(&it))
+            .is_some_and(|def| dominates(&idom, *def, index_of[&pc])),
+    };
+
+    let redundant: Vec<_> = method
+        .instructions
+        .iter()
+        .filter_map(|(pc, insn)| match insn {
+            MokaInstruction::Definition { value, .. } => {
+                let replacement = numbering.leader(&(*value).into())?;
+                (replacement != Identifier::Local(*value) && available_at(&replacement, *pc))
+                    .then_some(RedundantDefinition {
+                        pc: *pc,
+                        value: *value,
+                        replacement,
+                    })
+            }
+            _ => None,
+        })
+        .collect();
+    let replacements: BTreeMap<_, _> = redundant
+        .iter()
+        .map(|it| (Identifier::Local(it.value), it.replacement))
+        .collect();
+    let replace = |id: Identifier| replacements.get(&id).copied().unwrap_or(id);
+    for pc in &pcs {
+        let Some(insn) = method.instructions.get_mut(pc) else {
+            continue;
+        };
+        if let MokaInstruction::Phi { incoming, .. } = insn {
+            for it in incoming.values_mut() {
+                *it = replace(*it);
+            }
+        }
+        for operand in insn.operands_mut() {
+            *operand = substitute(operand, |it| Operand::Just(replace(it)));
+        }
+    }
+    for it in &redundant {
+        if let Some(insn) = method.instructions.get_mut(&it.pc) {
+            *insn = MokaInstruction::Nop;
+        }
+    }
+    redundant
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, MethodBody},
+            references::{ClassRef, MethodRef},
+            Method,
+        },
+    };
+    use Instruction::{
+        AThrow, Dup, IAdd, IConst1, IConst3, IConstM1, ILoad0, IMul, IReturn, InvokeSpecial,
+        InvokeStatic, New, Pop,
+    };
+
+    fn method_ref(name: &str, descriptor: &str) -> MethodRef {
+        MethodRef::new(
+            ClassRef::new("org/mokapot/Test"),
+            name,
+            descriptor.parse().unwrap(),
+        )
+    }
+
+    fn brew(
+        name: &str,
+        instructions: Vec<(u16, Instruction)>,
+        exception_table: Vec<ExceptionTableEntry>,
+    ) -> MokaIRMethod {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: name.to_owned(),
+            descriptor: "(I)I".parse().unwrap(),
+            owner: ClassRef::new("org/mokapot/Test"),
+            body: Some(MethodBody {
+                max_stack: 3,
+                max_locals: 1,
+                instructions: instructions
+                    .into_iter()
+                    .map(|(pc, insn)| (pc.into(), insn))
+                    .collect::<BTreeMap<_, _>>()
+                    .into(),
+                exception_table,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        method.brew().unwrap()
+    }
+
+    fn instruction_at(method: &MokaIRMethod, pc: u16) -> String {
+        method.instructions.get(&pc.into()).unwrap().to_string()
+    }
+
+    #[test]
+    fn inline_static_call() {
+        // static int twice(int x) { return x + x; }
+        let callee = brew(
+            "twice",
+            vec![(0, ILoad0), (1, ILoad0), (2, IAdd), (3, IReturn)],
+            Vec::new(),
+        );
+        // static int caller(int a) { return twice(a * 3) + 1; }
+        let mut caller = brew(
+            "caller",
+            vec![
+                (0, ILoad0),
+                (1, IConst3),
+                (2, IMul),
+                (3, InvokeStatic(method_ref("twice", "(I)I"))),
+                (6, IConst1),
+                (7, IAdd),
+                (8, IReturn),
+            ],
+            Vec::new(),
+        );
+        inline(&mut caller, 3.into(), &callee).unwrap();
+
+        assert_eq!(instruction_at(&caller, 3), "goto #0009");
+        assert_eq!(instruction_at(&caller, 11), "%11 = %2 + %2");
+        assert_eq!(instruction_at(&caller, 12), "goto #0006");
+        assert_eq!(instruction_at(&caller, 7), "%7 = %11 + %6");
+        let successors: Vec<_> = caller
+            .control_flow_graph
+            .edges_from(12.into())
+            .unwrap()
+            .map(|(_, dst, _)| dst)
+            .collect();
+        assert_eq!(successors, vec![6.into()]);
+    }
+
+    #[test]
+    fn inline_throwing_call() {
+        // static int fail(int x) { throw new RuntimeException(); }
+        let callee = brew(
+            "fail",
+            vec![
+                (0, New(ClassRef::new("java/lang/RuntimeException"))),
+                (3, Dup),
+                (
+                    4,
+                    InvokeSpecial(MethodRef::new(
+                        ClassRef::new("java/lang/RuntimeException"),
+                        "<init>",
+                        "()V".parse().unwrap(),
+                    )),
+                ),
+                (7, AThrow),
+            ],
+            Vec::new(),
+        );
+        // static int caller(int a) { try { return fail(a); } catch (RuntimeException e) { return -1; } }
+        let handler = ExceptionTableEntry {
+            covered_pc: 0.into()..=4.into(),
+            handler_pc: 5.into(),
+            catch_type: Some(ClassRef::new("java/lang/RuntimeException")),
+        };
+        let mut caller = brew(
+            "caller",
+            vec![
+                (0, ILoad0),
+                (1, InvokeStatic(method_ref("fail", "(I)I"))),
+                (4, IReturn),
+                (5, Pop),
+                (6, IConstM1),
+                (7, IReturn),
+            ],
+            vec![handler.clone()],
+        );
+        inline(&mut caller, 1.into(), &callee).unwrap();
+
+        let [_, escaping] = caller.exception_table.as_slice() else {
+            panic!("Unexpected exception table: {:?}", caller.exception_table);
+        };
+        assert_eq!(escaping.covered_pc, 8.into()..=15.into());
+        assert_eq!(escaping.handler_pc, handler.handler_pc);
+        assert_eq!(escaping.catch_type, handler.catch_type);
+        let (_, _, transfer) = caller
+            .control_flow_graph
+            .edges_from(15.into())
+            .unwrap()
+            .find(|(_, dst, _)| *dst == 5.into())
+            .unwrap();
+        assert!(matches!(transfer, ControlTransfer::Exception(_)));
+    }
+
+    #[test]
+    fn reject_invalid_inlining() {
+        let callee = brew("callee", vec![(0, ILoad0), (1, IReturn)], Vec::new());
+        let mut caller = brew(
+            "caller",
+            vec![
+                (0, ILoad0),
+                (1, InvokeStatic(method_ref("other", "(I)I"))),
+                (4, IReturn),
+            ],
+            Vec::new(),
+        );
+        assert_eq!(
+            inline(&mut caller, 0.into(), &callee),
+            Err(Error::NotACall(0.into()))
+        );
+        assert_eq!(
+            inline(&mut caller, 1.into(), &callee),
+            Err(Error::CalleeMismatch)
+        );
+        let limits = Limits {
+            max_callee_instructions: 1,
+            ..Default::default()
+        };
+        let mut renamed = callee.clone();
+        renamed.name = "other".to_owned();
+        assert_eq!(
+            inline_with_limits(&mut caller, 1.into(), &renamed, &limits),
+            Err(Error::TooLarge)
+        );
+    }
+
+    #[test]
+    fn deflatten_dispatcher_loop() {
+        let class = crate::jvm::interop::jasmin::read(
+            r"
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static flattened(I)I
+                .limit stack 1
+                .limit locals 2
+                iconst_0
+                istore_1
+            Dispatch:
+                iload_1
+                tableswitch 0
+                    First
+                    Third
+                    Second
+                    default : Exit
+            First:
+                iconst_2
+                istore_1
+                goto Dispatch
+            Second:
+                iload_0
+                ifeq Zero
+                iconst_1
+                istore_1
+                goto Dispatch
+            Zero:
+                iconst_3
+                istore_1
+                goto Dispatch
+            Third:
+                iload_0
+                ireturn
+            Exit:
+                iconst_m1
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let mut ir_method = class.methods[0].brew().unwrap();
+        let switch_targets: Vec<_> = match ir_method.instructions.get(&3.into()) {
+            Some(MokaInstruction::Switch {
+                branches, default, ..
+            }) => branches.values().copied().chain([*default]).collect(),
+            other => panic!("Unexpected dispatcher: {other:?}"),
+        };
+        let [first, third, second, exit] = switch_targets.as_slice() else {
+            panic!("Unexpected targets: {switch_targets:?}");
+        };
+
+        let [deflattened] = deflatten(&mut ir_method).try_into().unwrap();
+        assert_eq!(deflattened.dispatcher, 3.into());
+        assert_eq!(deflattened.redirected, 3);
+        assert!(deflattened.bypassed);
+        assert_eq!(deflattened.block_order, [*first, *second, *third, *exit]);
+        assert_eq!(instruction_at(&ir_method, 3), format!("goto {first}"));
+        let successors: Vec<_> = ir_method
+            .control_flow_graph
+            .edges_from(3.into())
+            .unwrap()
+            .map(|(_, dst, _)| dst)
+            .collect();
+        assert_eq!(successors, [*first]);
+        assert!(ir_method.instructions.iter().all(|(_, insn)| !matches!(
+            insn,
+            MokaInstruction::Jump { target, .. } if *target == 2.into()
+        )));
+    }
+
+    #[test]
+    fn eliminate_constant_and_contradicting_branches() {
+        let class = crate::jvm::interop::jasmin::read(
+            r"
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static opaque(I)I
+                .limit stack 2
+                .limit locals 1
+                iconst_1
+                iconst_2
+                if_icmpeq ConstantlyDead
+                iload_0
+                ifeq Zero
+                iload_0
+                ifeq ContradictorilyDead
+                iconst_1
+                ireturn
+            Zero:
+                iconst_0
+                ireturn
+            ContradictorilyDead:
+                iconst_2
+                ireturn
+            ConstantlyDead:
+                iconst_m1
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let mut ir_method = class.methods[0].brew().unwrap();
+        let [constant, contradiction] = eliminate_opaque_predicates(&mut ir_method)
+            .try_into()
+            .unwrap();
+
+        assert_eq!(constant.pc, 2.into());
+        assert!(!constant.taken);
+        assert_eq!(constant.proof.to_string(), "constants %0 = 1 %1 = 2");
+        assert_eq!(constant.removed, [19.into(), 20.into()]);
+        assert_eq!(instruction_at(&ir_method, 2), "nop");
+
+        assert_eq!(contradiction.pc, 10.into());
+        assert!(!contradiction.taken);
+        assert!(matches!(contradiction.proof, Proof::PathCondition(_)));
+        assert_eq!(contradiction.removed, [17.into(), 18.into()]);
+        assert!(ir_method.instructions.get(&17.into()).is_none());
+        assert!(ir_method
+            .control_flow_graph
+            .edges()
+            .all(|(_, dst, _)| u16::from(dst) < 17));
+    }
+
+    #[test]
+    fn eliminate_common_subexpressions_in_branches() {
+        let class = crate::jvm::interop::jasmin::read(
+            r"
+            .class public org/mokapot/Test
+            .super java/lang/Object
+            .method public static cse(II)I
+                .limit stack 4
+                .limit locals 2
+                iload_0
+                iload_1
+                iadd
+                ifeq Zero
+                iload_1
+                iload_0
+                iadd
+                ireturn
+            Zero:
+                iload_0
+                iload_1
+                imul
+                iload_0
+                iload_1
+                imul
+                iadd
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let mut method = class.methods[0].brew().unwrap();
+        let redundant = eliminate_common_subexpressions(&mut method);
+        let local = |it| Identifier::Local(LocalValue::new(it));
+        assert_eq!(
+            redundant
+                .iter()
+                .map(|it| (u16::from(it.pc), it.replacement))
+                .collect::<Vec<_>>(),
+            [(8, local(2)), (15, local(12))]
+        );
+        assert_eq!(
+            method.instructions.get(&8.into()),
+            Some(&MokaInstruction::Nop)
+        );
+        assert_eq!(
+            method.instructions.get(&9.into()),
+            Some(&MokaInstruction::Return(Some(Operand::Just(local(2)))))
+        );
+        let Some(MokaInstruction::Definition { expr, .. }) = method.instructions.get(&16.into())
+        else {
+            panic!("Unexpected instruction at 16");
+        };
+        assert_eq!(expr.uses(), BTreeSet::from([local(12)]));
+    }
+}
@@ -1,11 +1,11 @@
 //! Type hierarchy analysis components.
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use petgraph::visit::{depth_first_search, Control, DfsEvent, Reversed};
 
-use crate::jvm::{references::ClassRef, Class};
+use crate::jvm::{class::AccessFlags, references::ClassRef, Class};
 
-use super::{ClassHierarchy, InterfaceImplHierarchy};
+use super::{ClassHierarchy, InterfaceImplHierarchy, SealedHierarchy};
 
 impl ClassHierarchy {
     /// Creates a new [`ClassHierarchy`] from a list of classes.
@@ -14,27 +14,34 @@ impl ClassHierarchy {
     where
         I: IntoIterator<Item = &'a Class>,
     {
-        let mut inheritance: HashMap<ClassRef, HashSet<ClassRef>> = HashMap::new();
-        let mut super_classes: HashMap<ClassRef, ClassRef> = HashMap::new();
+        let mut hierarchy = Self {
+            classes: BTreeSet::new(),
+            inheritance: BTreeMap::new(),
+            super_classes: BTreeMap::new(),
+        };
         for class in classes {
-            if let Some(super_class) = class.super_class.as_ref() {
-                inheritance
-                    .entry(super_class.clone())
-                    .or_default()
-                    .insert(class.as_ref());
-                super_classes.insert(class.as_ref(), super_class.clone());
-            }
+            hierarchy.insert(class);
         }
-        Self {
-            inheritance,
-            super_classes,
+        hierarchy
+    }
+
+    /// Adds the super class relationship of `class` to the hierarchy.
+    pub(crate) fn insert(&mut self, class: &Class) {
+        self.classes.insert(class.as_ref());
+        if let Some(super_class) = class.super_class.as_ref() {
+            self.inheritance
+                .entry(super_class.clone())
+                .or_default()
+                .insert(class.as_ref());
+            self.super_classes
+                .insert(class.as_ref(), super_class.clone());
         }
     }
 
     /// Returns the set of super classes of the given class.
     #[must_use]
-    pub fn super_classes(&self, class: &ClassRef) -> HashSet<ClassRef> {
-        let mut super_classes = HashSet::new();
+    pub fn super_classes(&self, class: &ClassRef) -> BTreeSet<ClassRef> {
+        let mut super_classes = BTreeSet::new();
         let mut current = class;
         while let Some(super_class) = self.super_classes.get(current) {
             super_classes.insert(super_class.clone());
@@ -45,8 +52,8 @@ impl ClassHierarchy {
 
     /// Returns the set of subclasses of the given class.
     #[must_use]
-    pub fn subclasses(&self, class: &ClassRef) -> HashSet<ClassRef> {
-        let mut subclasses = HashSet::new();
+    pub fn subclasses(&self, class: &ClassRef) -> BTreeSet<ClassRef> {
+        let mut subclasses = BTreeSet::new();
         depth_first_search(self, [class], |event| {
             if let DfsEvent::TreeEdge(_, i) = event {
                 subclasses.insert(i);
@@ -59,6 +66,52 @@ impl ClassHierarchy {
         subclasses.remove(class);
         subclasses.into_iter().cloned().collect()
     }
+
+    /// Returns the classes that are referred to as super classes but are not in the hierarchy,
+    /// e.g., because they are missing from the class path.
+    #[must_use]
+    pub fn frontier(&self) -> BTreeSet<ClassRef> {
+        self.inheritance
+            .keys()
+            .filter(|it| !self.classes.contains(*it))
+            .cloned()
+            .collect()
+    }
+
+    /// Checks whether `class` is a subclass of `super_class`.
+    /// Returns [`None`] if it cannot be decided because `class` or one of its super classes
+    /// that are visited before reaching `super_class` is not in the hierarchy.
+    #[must_use]
+    pub fn is_subclass_of(&self, class: &ClassRef, super_class: &ClassRef) -> Option<bool> {
+        let mut visited = BTreeSet::new();
+        let mut current = class;
+        loop {
+            if !self.classes.contains(current) {
+                return None;
+            }
+            match self.super_classes.get(current) {
+                Some(it) if it == super_class => return Some(true),
+                Some(it) if visited.insert(it) => current = it,
+                _ => return Some(false),
+            }
+        }
+    }
+
+    /// Returns the classes that may be subclasses of `class`, conservatively assuming that each
+    /// class in the [frontier](Self::frontier) may be a subclass of any class except its
+    /// known subclasses.
+    #[must_use]
+    pub fn possible_subclasses(&self, class: &ClassRef) -> BTreeSet<ClassRef> {
+        let mut subclasses = self.subclasses(class);
+        for frontier in self.frontier() {
+            if &frontier != class && !subclasses.contains(&frontier) {
+                subclasses.extend(self.subclasses(&frontier));
+                subclasses.insert(frontier);
+            }
+        }
+        subclasses.remove(class);
+        subclasses
+    }
 }
 
 impl InterfaceImplHierarchy {
@@ -68,30 +121,36 @@ impl InterfaceImplHierarchy {
     where
         I: IntoIterator<Item = &'a Class>,
     {
-        let mut implementations: HashMap<ClassRef, HashSet<ClassRef>> = HashMap::new();
-        let mut implementors: HashMap<ClassRef, HashSet<ClassRef>> = HashMap::new();
+        let mut hierarchy = Self {
+            classes: BTreeSet::new(),
+            implementations: BTreeMap::new(),
+            implementors: BTreeMap::new(),
+        };
         for class in classes {
-            for interface in &class.interfaces {
-                implementations
-                    .entry(class.as_ref())
-                    .or_default()
-                    .insert(interface.clone());
-                implementors
-                    .entry(interface.clone())
-                    .or_default()
-                    .insert(class.as_ref());
-            }
+            hierarchy.insert(class);
         }
-        Self {
-            implementations,
-            implementors,
+        hierarchy
+    }
+
+    /// Adds the interfaces implemented by `class` to the hierarchy.
+    pub(crate) fn insert(&mut self, class: &Class) {
+        self.classes.insert(class.as_ref());
+        for interface in &class.interfaces {
+            self.implementations
+                .entry(class.as_ref())
+                .or_default()
+                .insert(interface.clone());
+            self.implementors
+                .entry(interface.clone())
+                .or_default()
+                .insert(class.as_ref());
         }
     }
 
     /// Returns the set of interfaces implemented by the given class.
     #[must_use]
-    pub fn implemented_interfaces(&self, class: &ClassRef) -> HashSet<ClassRef> {
-        let mut interfaces = HashSet::new();
+    pub fn implemented_interfaces(&self, class: &ClassRef) -> BTreeSet<ClassRef> {
+        let mut interfaces = BTreeSet::new();
         depth_first_search(self, [class], |event| {
             if let DfsEvent::TreeEdge(_, i) = event {
                 interfaces.insert(i);
@@ -107,8 +166,8 @@ impl InterfaceImplHierarchy {
 
     /// Returns the set of classes that implement the given interface.
     #[must_use]
-    pub fn implementors(&self, interface: &ClassRef) -> HashSet<ClassRef> {
-        let mut implementors = HashSet::new();
+    pub fn implementors(&self, interface: &ClassRef) -> BTreeSet<ClassRef> {
+        let mut implementors = BTreeSet::new();
         let rev_impl_graph = Reversed(self);
         depth_first_search(&rev_impl_graph, [interface], |event| {
             if let DfsEvent::TreeEdge(_, i) = event {
@@ -122,4 +181,383 @@ impl InterfaceImplHierarchy {
         implementors.remove(interface);
         implementors.into_iter().cloned().collect()
     }
+
+    /// Returns the interfaces that are implemented or extended by some class but are not in the
+    /// hierarchy, e.g., because they are missing from the class path.
+    #[must_use]
+    pub fn frontier(&self) -> BTreeSet<ClassRef> {
+        self.implementors
+            .keys()
+            .filter(|it| !self.classes.contains(*it))
+            .cloned()
+            .collect()
+    }
+
+    /// Checks whether `class` implements `interface` directly or through its super interfaces.
+    /// Returns [`None`] if it cannot be decided because `class` or one of the interfaces it
+    /// implements is not in the hierarchy.
+    #[must_use]
+    pub fn implements(&self, class: &ClassRef, interface: &ClassRef) -> Option<bool> {
+        if !self.classes.contains(class) {
+            return None;
+        }
+        let interfaces = self.implemented_interfaces(class);
+        if interfaces.contains(interface) {
+            Some(true)
+        } else if interfaces.iter().any(|it| !self.classes.contains(it)) {
+            None
+        } else {
+            Some(false)
+        }
+    }
+
+    /// Returns the classes that may implement `interface`, conservatively assuming that each
+    /// interface in the [frontier](Self::frontier) may extend any interface.
+    #[must_use]
+    pub fn possible_implementors(&self, interface: &ClassRef) -> BTreeSet<ClassRef> {
+        let mut implementors = self.implementors(interface);
+        for frontier in self.frontier() {
+            if &frontier != interface && !implementors.contains(&frontier) {
+                implementors.extend(self.implementors(&frontier));
+                implementors.insert(frontier);
+            }
+        }
+        implementors
+    }
+}
+
+/// A violation of the restrictions imposed by a `sealed` class or interface.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SealingViolation {
+    /// A class directly extends or implements a sealed type that does not permit it.
+    NotPermitted {
+        /// The sealed class or interface.
+        sealed: ClassRef,
+        /// The class that is not permitted.
+        subclass: ClassRef,
+    },
+    /// A permitted subclass does not directly extend or implement the sealed type.
+    NotDirectSubtype {
+        /// The sealed class or interface.
+        sealed: ClassRef,
+        /// The permitted subclass.
+        subclass: ClassRef,
+    },
+}
+
+impl SealedHierarchy {
+    /// Creates a new [`SealedHierarchy`] from a list of classes.
+    #[must_use]
+    pub fn from_classes<'a, I>(classes: I) -> Self
+    where
+        I: IntoIterator<Item = &'a Class>,
+    {
+        let mut permitted_subclasses = BTreeMap::new();
+        let mut direct_subtypes: BTreeMap<ClassRef, BTreeSet<ClassRef>> = BTreeMap::new();
+        let mut final_classes = BTreeSet::new();
+        for class in classes {
+            if !class.permitted_subclasses.is_empty() {
+                permitted_subclasses.insert(class.as_ref(), class.permitted_subclasses.clone());
+            }
+            if class.access_flags.contains(AccessFlags::FINAL) {
+                final_classes.insert(class.as_ref());
+            }
+            for super_type in class.super_class.iter().chain(&class.interfaces) {
+                direct_subtypes
+                    .entry(super_type.clone())
+                    .or_default()
+                    .insert(class.as_ref());
+            }
+        }
+        Self {
+            permitted_subclasses,
+            direct_subtypes,
+            final_classes,
+        }
+    }
+
+    /// Checks if the given class or interface is `sealed`.
+    #[must_use]
+    pub fn is_sealed(&self, class: &ClassRef) -> bool {
+        self.permitted_subclasses.contains_key(class)
+    }
+
+    /// Checks if the given class is `final`.
+    #[must_use]
+    pub fn is_final(&self, class: &ClassRef) -> bool {
+        self.final_classes.contains(class)
+    }
+
+    /// Returns the permitted subclasses of the given class, or [`None`] if it is not sealed.
+    #[must_use]
+    pub fn permitted_subclasses(&self, class: &ClassRef) -> Option<&[ClassRef]> {
+        self.permitted_subclasses.get(class).map(Vec::as_slice)
+    }
+
+    /// Returns the classes that are transitively permitted by the given sealed class, i.e., its
+    /// permitted subclasses and, for those that are sealed themselves, their permitted
+    /// subclasses, and so on.
+    /// The hierarchy is closed if none of the returned classes is `non-sealed`, i.e., each of
+    /// them is either sealed or final.
+    /// Returns [`None`] if the given class is not sealed.
+    #[must_use]
+    pub fn sealed_permits_closure(&self, class: &ClassRef) -> Option<BTreeSet<ClassRef>> {
+        let mut closure = BTreeSet::new();
+        let mut queue: VecDeque<_> = self.permitted_subclasses(class)?.iter().collect();
+        while let Some(subclass) = queue.pop_front() {
+            if closure.insert(subclass.clone()) {
+                queue.extend(self.permitted_subclasses(subclass).into_iter().flatten());
+            }
+        }
+        Some(closure)
+    }
+
+    /// Checks if the hierarchy under the given sealed class is closed, i.e., every class in
+    /// its [`sealed_permits_closure`](Self::sealed_permits_closure) is either sealed or final.
+    #[must_use]
+    pub fn is_closed(&self, class: &ClassRef) -> bool {
+        self.sealed_permits_closure(class).is_some_and(|closure| {
+            closure
+                .iter()
+                .all(|it| self.is_sealed(it) || self.is_final(it))
+        })
+    }
+
+    /// Validates that the direct subclasses of every sealed class are permitted and that every
+    /// permitted subclass known to the hierarchy directly extends or implements its sealed type.
+    #[must_use]
+    pub fn validate(&self) -> BTreeSet<SealingViolation> {
+        let mut violations = BTreeSet::new();
+        let known_classes: BTreeSet<_> = self.direct_subtypes.values().flatten().collect();
+        for (sealed, permitted) in &self.permitted_subclasses {
+            let direct_subtypes = self.direct_subtypes.get(sealed);
+            for subclass in direct_subtypes.into_iter().flatten() {
+                if !permitted.contains(subclass) {
+                    violations.insert(SealingViolation::NotPermitted {
+                        sealed: sealed.clone(),
+                        subclass: subclass.clone(),
+                    });
+                }
+            }
+            for subclass in permitted {
+                let is_direct_subtype = direct_subtypes.is_some_and(|it| it.contains(subclass));
+                if known_classes.contains(subclass) && !is_direct_subtype {
+                    violations.insert(SealingViolation::NotDirectSubtype {
+                        sealed: sealed.clone(),
+                        subclass: subclass.clone(),
+                    });
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJECT: &str = "java/lang/Object";
+
+    #[test]
+    fn sealed_closure() {
+        let classes = [
+            Class {
+                access_flags: AccessFlags::INTERFACE | AccessFlags::ABSTRACT,
+                binary_name: "Shape".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                permitted_subclasses: vec![ClassRef::new("Circle"), ClassRef::new("Polygon")],
+                ..Default::default()
+            },
+            Class {
+                access_flags: AccessFlags::FINAL,
+                binary_name: "Circle".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("Shape")],
+                ..Default::default()
+            },
+            Class {
+                access_flags: AccessFlags::ABSTRACT,
+                binary_name: "Polygon".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("Shape")],
+                permitted_subclasses: vec![ClassRef::new("Square"), ClassRef::new("Triangle")],
+                ..Default::default()
+            },
+            Class {
+                access_flags: AccessFlags::FINAL,
+                binary_name: "Square".to_owned(),
+                super_class: Some(ClassRef::new("Polygon")),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "Triangle".to_owned(),
+                super_class: Some(ClassRef::new("Polygon")),
+                ..Default::default()
+            },
+        ];
+        let hierarchy = SealedHierarchy::from_classes(&classes);
+        let shape = ClassRef::new("Shape");
+        let closure = hierarchy.sealed_permits_closure(&shape).unwrap();
+        let expected = ["Circle", "Polygon", "Square", "Triangle"]
+            .into_iter()
+            .map(ClassRef::new)
+            .collect();
+        assert_eq!(closure, expected);
+        // `Triangle` is `non-sealed`.
+        assert!(!hierarchy.is_closed(&shape));
+        assert!(hierarchy
+            .sealed_permits_closure(&ClassRef::new("Circle"))
+            .is_none());
+        assert!(hierarchy.validate().is_empty());
+    }
+
+    #[test]
+    fn violations() {
+        let classes = [
+            Class {
+                binary_name: "Expr".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                permitted_subclasses: vec![ClassRef::new("Const"), ClassRef::new("Neg")],
+                ..Default::default()
+            },
+            Class {
+                access_flags: AccessFlags::FINAL,
+                binary_name: "Const".to_owned(),
+                super_class: Some(ClassRef::new("Expr")),
+                ..Default::default()
+            },
+            Class {
+                access_flags: AccessFlags::FINAL,
+                binary_name: "Neg".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                ..Default::default()
+            },
+            Class {
+                access_flags: AccessFlags::FINAL,
+                binary_name: "Add".to_owned(),
+                super_class: Some(ClassRef::new("Expr")),
+                ..Default::default()
+            },
+        ];
+        let hierarchy = SealedHierarchy::from_classes(&classes);
+        let expr = ClassRef::new("Expr");
+        assert_eq!(
+            hierarchy.validate(),
+            BTreeSet::from([
+                SealingViolation::NotPermitted {
+                    sealed: expr.clone(),
+                    subclass: ClassRef::new("Add"),
+                },
+                SealingViolation::NotDirectSubtype {
+                    sealed: expr.clone(),
+                    subclass: ClassRef::new("Neg"),
+                },
+            ])
+        );
+        assert!(hierarchy.is_closed(&expr));
+    }
+
+    #[test]
+    fn frontier() {
+        let classes = [
+            Class {
+                binary_name: "A".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("I")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "B".to_owned(),
+                super_class: Some(ClassRef::new("A")),
+                ..Default::default()
+            },
+            Class {
+                binary_name: "C".to_owned(),
+                super_class: Some(ClassRef::new("Missing")),
+                interfaces: vec![ClassRef::new("MissingInterface")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "I".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                ..Default::default()
+            },
+            Class {
+                binary_name: OBJECT.to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                ..Default::default()
+            },
+        ];
+        let hierarchy = ClassHierarchy::from_classes(&classes);
+        let (a, b, c) = (ClassRef::new("A"), ClassRef::new("B"), ClassRef::new("C"));
+        let missing = ClassRef::new("Missing");
+        assert_eq!(hierarchy.frontier(), BTreeSet::from([missing.clone()]));
+        assert_eq!(hierarchy.is_subclass_of(&b, &a), Some(true));
+        assert_eq!(hierarchy.is_subclass_of(&a, &b), Some(false));
+        assert_eq!(hierarchy.is_subclass_of(&c, &a), None);
+        assert_eq!(hierarchy.is_subclass_of(&ClassRef::new("D"), &a), None);
+        assert_eq!(
+            hierarchy.possible_subclasses(&a),
+            BTreeSet::from([b.clone(), c.clone(), missing])
+        );
+
+        let interfaces = InterfaceImplHierarchy::from_classes(&classes);
+        let i = ClassRef::new("I");
+        assert_eq!(
+            interfaces.frontier(),
+            BTreeSet::from([ClassRef::new("MissingInterface")])
+        );
+        assert_eq!(interfaces.implements(&a, &i), Some(true));
+        assert_eq!(interfaces.implements(&b, &i), Some(false));
+        assert_eq!(interfaces.implements(&c, &i), None);
+        assert_eq!(
+            interfaces.possible_implementors(&i),
+            BTreeSet::from([a, c, ClassRef::new("MissingInterface")])
+        );
+    }
+
+    #[test]
+    fn iteration_order_is_independent_of_input_order() {
+        let mut classes = vec![
+            Class {
+                binary_name: "Z".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("I")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "M".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("I")],
+                ..Default::default()
+            },
+            Class {
+                binary_name: "A".to_owned(),
+                super_class: Some(ClassRef::new(OBJECT)),
+                interfaces: vec![ClassRef::new("I")],
+                ..Default::default()
+            },
+        ];
+        let listed = |classes: &[Class]| {
+            let hierarchy = ClassHierarchy::from_classes(classes);
+            let interfaces = InterfaceImplHierarchy::from_classes(classes);
+            let object = ClassRef::new(OBJECT);
+            (
+                hierarchy
+                    .subclasses(&object)
+                    .into_iter()
+                    .collect::<Vec<_>>(),
+                interfaces
+                    .implementors(&ClassRef::new("I"))
+                    .into_iter()
+                    .collect::<Vec<_>>(),
+            )
+        };
+        let forward = listed(&classes);
+        classes.reverse();
+        assert_eq!(listed(&classes), forward);
+        let expected: Vec<_> = ["A", "M", "Z"].into_iter().map(ClassRef::new).collect();
+        assert_eq!(forward, (expected.clone(), expected));
+    }
 }
@@ -0,0 +1,1209 @@
+//! Lowering of Moka IR into WebAssembly.
+//!
+//! Only a restricted subset of Moka IR is supported: `static` methods whose parameters and
+//! return values are primitives, integer and floating point arithmetic, conversions between
+//! primitive types, conditional jumps, `switch`es, and `static` calls.
+//! Calls to methods added to the same [`ModuleBuilder`] are lowered to direct calls, and calls
+//! to any other method are lowered to calls to a function imported from the module named after
+//! the binary name of the owner of the method.
+//!
+//! The values are held in the locals of the WebAssembly function, where the identifiers joined
+//! by a Phi operand share the same local.
+//! The basic blocks are dispatched by a `br_table` inside a `loop`, so that arbitrary control
+//! flow can be expressed with the structured control flow of WebAssembly.
+//! The module is encoded with [`wasm_encoder`].
+//! Note that integer division by zero traps instead of throwing an `ArithmeticException`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use wasm_encoder::{
+    BlockType, CodeSection, EntityType, ExportKind, ExportSection, Function as FunctionBody,
+    FunctionSection, ImportSection, Instruction, Module, TypeSection,
+};
+
+use crate::{
+    jvm::{code::ProgramCounter, references::MethodRef, ConstantValue},
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::{
+    expression::{Condition, Conversion, Expression, MathOperation, NaNTreatment},
+    Identifier, MokaIRMethod, MokaInstruction, Operand,
+};
+
+/// An error when lowering Moka IR into WebAssembly.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// The method uses a construct that is not supported by the backend.
+    #[error("Unsupported construct: {0}")]
+    Unsupported(String),
+    /// The type of the value cannot be inferred.
+    #[error("Cannot infer the type of {0}")]
+    UnknownType(Identifier),
+    /// The value is used as different types.
+    #[error("Conflicting types of {0}")]
+    ConflictingTypes(Identifier),
+    /// A function with the same name has already been added to the module.
+    #[error("Duplicate function {0}")]
+    DuplicateFunction(String),
+}
+
+/// A WebAssembly value type that a primitive type is lowered to.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum ValType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl ValType {
+    fn of_field_type(field_type: &FieldType) -> Result<Self, Error> {
+        use PrimitiveType::{Boolean, Byte, Char, Double, Float, Int, Long, Short};
+        match field_type {
+            FieldType::Base(Boolean | Byte | Char | Short | Int) => Ok(Self::I32),
+            FieldType::Base(Long) => Ok(Self::I64),
+            FieldType::Base(Float) => Ok(Self::F32),
+            FieldType::Base(Double) => Ok(Self::F64),
+            _ => Err(Error::Unsupported(format!(
+                "non-primitive type {field_type}"
+            ))),
+        }
+    }
+}
+
+impl From<ValType> for wasm_encoder::ValType {
+    fn from(value: ValType) -> Self {
+        match value {
+            ValType::I32 => Self::I32,
+            ValType::I64 => Self::I64,
+            ValType::F32 => Self::F32,
+            ValType::F64 => Self::F64,
+        }
+    }
+}
+
+type FuncType = (Vec<ValType>, Option<ValType>);
+
+fn func_type(descriptor: &MethodDescriptor) -> Result<FuncType, Error> {
+    let params = descriptor
+        .parameters_types
+        .iter()
+        .map(ValType::of_field_type)
+        .collect::<Result<_, _>>()?;
+    let result = match &descriptor.return_type {
+        ReturnType::Some(it) => Some(ValType::of_field_type(it)?),
+        ReturnType::Void => None,
+    };
+    Ok((params, result))
+}
+
+/// A function lowered from a method.
+#[derive(Debug)]
+struct Function {
+    method: MethodRef,
+    type_index: u32,
+    /// The types of the locals after the parameters.
+    locals: Vec<ValType>,
+    instructions: Vec<Instruction<'static>>,
+    /// The positions of the calls whose callee indices are filled when the module is finished.
+    calls: Vec<(usize, MethodRef)>,
+}
+
+/// A builder of a WebAssembly module from Moka IR methods.
+#[derive(Debug, Default)]
+pub struct ModuleBuilder {
+    types: Vec<FuncType>,
+    functions: Vec<Function>,
+    export_names: HashSet<String>,
+}
+
+impl ModuleBuilder {
+    /// Creates an empty module.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lowers a method into a function of the module.
+    /// The function is exported under the name of the method.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn add_method(&mut self, method: &MokaIRMethod) -> Result<(), Error> {
+        if !method.is_static() {
+            return Err(Error::Unsupported("instance method".to_owned()));
+        }
+        if !method.exception_table.is_empty() {
+            return Err(Error::Unsupported("exception handler".to_owned()));
+        }
+        if self.export_names.contains(&method.name) {
+            return Err(Error::DuplicateFunction(method.name.clone()));
+        }
+        let ty = func_type(&method.descriptor)?;
+        let lowered = FunctionLowering::new(method)?.lower()?;
+        let type_index = self.type_index(ty);
+        self.export_names.insert(method.name.clone());
+        self.functions.push(Function {
+            method: MethodRef::new(
+                method.owner.clone(),
+                &method.name,
+                method.descriptor.clone(),
+            ),
+            type_index,
+            locals: lowered.locals,
+            instructions: lowered.instructions,
+            calls: lowered.calls,
+        });
+        Ok(())
+    }
+
+    fn type_index(&mut self, ty: FuncType) -> u32 {
+        let index = self
+            .types
+            .iter()
+            .position(|it| *it == ty)
+            .unwrap_or_else(|| {
+                self.types.push(ty);
+                self.types.len() - 1
+            });
+        u32::try_from(index).expect("The number of types should be within u32")
+    }
+
+    /// Encodes the module into the WebAssembly binary format.
+    /// # Errors
+    /// - [`Error::Unsupported`] if a callee has a non-primitive type.
+    /// # Panics
+    /// Panics if the module has more than [`u32::MAX`] functions.
+    pub fn finish(mut self) -> Result<Vec<u8>, Error> {
+        let defined: BTreeMap<_, _> = self
+            .functions
+            .iter()
+            .enumerate()
+            .map(|(index, it)| (it.method.clone(), index))
+            .collect();
+        let mut imports: Vec<(MethodRef, u32)> = Vec::new();
+        let mut seen = BTreeSet::new();
+        let callees: Vec<_> = self
+            .functions
+            .iter()
+            .flat_map(|it| it.calls.iter().map(|(_, callee)| callee.clone()))
+            .collect();
+        for callee in callees {
+            if !defined.contains_key(&callee) && seen.insert(callee.clone()) {
+                let type_index = self.type_index(func_type(&callee.descriptor)?);
+                imports.push((callee, type_index));
+            }
+        }
+        let import_count = imports.len();
+        let func_index = |index: usize| {
+            u32::try_from(index).expect("The number of functions should be within u32")
+        };
+        let callee_index = |callee: &MethodRef| {
+            let index = defined.get(callee).map_or_else(
+                || {
+                    imports
+                        .iter()
+                        .position(|(it, _)| it == callee)
+                        .expect("Every callee should be either defined or imported")
+                },
+                |it| import_count + it,
+            );
+            func_index(index)
+        };
+
+        let mut module = Module::new();
+        let mut type_section = TypeSection::new();
+        for (params, result) in &self.types {
+            type_section.ty().function(
+                params.iter().map(|&it| it.into()),
+                result.iter().map(|&it| it.into()),
+            );
+        }
+        module.section(&type_section);
+
+        if !imports.is_empty() {
+            let mut import_section = ImportSection::new();
+            for (callee, type_index) in &imports {
+                import_section.import(
+                    &callee.owner.binary_name,
+                    &callee.name,
+                    EntityType::Function(*type_index),
+                );
+            }
+            module.section(&import_section);
+        }
+
+        let mut function_section = FunctionSection::new();
+        for function in &self.functions {
+            function_section.function(function.type_index);
+        }
+        module.section(&function_section);
+
+        let mut export_section = ExportSection::new();
+        for (index, function) in self.functions.iter().enumerate() {
+            export_section.export(
+                &function.method.name,
+                ExportKind::Func,
+                func_index(import_count + index),
+            );
+        }
+        module.section(&export_section);
+
+        let mut code_section = CodeSection::new();
+        for function in &mut self.functions {
+            for (position, callee) in &function.calls {
+                function.instructions[*position] = Instruction::Call(callee_index(callee));
+            }
+            let mut body =
+                FunctionBody::new_with_locals_types(function.locals.iter().map(|&it| it.into()));
+            for instruction in &function.instructions {
+                body.instruction(instruction);
+            }
+            code_section.function(&body);
+        }
+        module.section(&code_section);
+        Ok(module.finish())
+    }
+}
+
+/// The body of a lowered function.
+struct LoweredBody {
+    locals: Vec<ValType>,
+    instructions: Vec<Instruction<'static>>,
+    calls: Vec<(usize, MethodRef)>,
+}
+
+/// Lowers a single method into the body of a WebAssembly function.
+struct FunctionLowering<'a> {
+    method: &'a MokaIRMethod,
+    /// The representative of the identifiers sharing the same local.
+    parents: BTreeMap<Identifier, Identifier>,
+    types: BTreeMap<Identifier, ValType>,
+    /// The WebAssembly local holding the value of each representative.
+    locals: BTreeMap<Identifier, u32>,
+    param_count: u32,
+    code: Vec<Instruction<'static>>,
+    calls: Vec<(usize, MethodRef)>,
+}
+
+impl<'a> FunctionLowering<'a> {
+    fn new(method: &'a MokaIRMethod) -> Result<Self, Error> {
+        let param_count = u32::try_from(method.descriptor.parameters_types.len())
+            .map_err(|_| Error::Unsupported("too many parameters".to_owned()))?;
+        let mut lowering = Self {
+            method,
+            parents: BTreeMap::new(),
+            types: BTreeMap::new(),
+            locals: BTreeMap::new(),
+            param_count,
+            code: Vec::new(),
+            calls: Vec::new(),
+        };
+        lowering.merge_phis();
+        lowering.infer_types()?;
+        Ok(lowering)
+    }
+
+    fn find(&self, id: Identifier) -> Identifier {
+        let mut current = id;
+        while let Some(&parent) = self.parents.get(&current) {
+            current = parent;
+        }
+        current
+    }
+
+    fn merge_phis(&mut self) {
+        for (_, insn) in self.method.instructions.iter() {
+            let operands: Vec<&Operand> = match insn {
+                MokaInstruction::Definition { expr, .. } => expression_operands(expr),
+                MokaInstruction::Jump {
+                    condition: Some(condition),
+                    ..
+                } => condition_operands(condition),
+                MokaInstruction::Switch { match_value, .. } => vec![match_value],
+                MokaInstruction::Return(Some(operand)) => vec![operand],
+                _ => Vec::new(),
+            };
+            for operand in operands {
+                if let Operand::Phi(ids) = operand {
+                    let roots: Vec<_> = ids.iter().map(|&it| self.find(it)).collect();
+                    if let Some((&root, others)) = roots.split_first() {
+                        for &other in others {
+                            if other != root {
+                                self.parents.insert(other, root);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn assign_type(&mut self, id: Identifier, ty: ValType) -> Result<bool, Error> {
+        let root = self.find(id);
+        match self.types.insert(root, ty) {
+            Some(existing) if existing != ty => Err(Error::ConflictingTypes(id)),
+            existing => Ok(existing.is_none()),
+        }
+    }
+
+    fn infer_types(&mut self) -> Result<(), Error> {
+        let (params, _) = func_type(&self.method.descriptor)?;
+        for (index, ty) in params.into_iter().enumerate() {
+            let index = u16::try_from(index).expect("The number of args should be within u16");
+            self.assign_type(Identifier::Arg(index), ty)?;
+        }
+        loop {
+            let mut changed = false;
+            for (_, insn) in self.method.instructions.iter() {
+                if let MokaInstruction::Definition { value, expr } = insn {
+                    if let Some(ty) = self.expression_type(expr)? {
+                        changed |= self.assign_type((*value).into(), ty)?;
+                    }
+                }
+            }
+            if !changed {
+                break Ok(());
+            }
+        }
+    }
+
+    fn operand_type(&self, operand: &Operand) -> Result<Option<ValType>, Error> {
+        let id = representative(operand)?;
+        Ok(self.types.get(&self.find(id)).copied())
+    }
+
+    fn expression_type(&self, expr: &Expression) -> Result<Option<ValType>, Error> {
+        use Conversion::{
+            Double2Float, Double2Int, Double2Long, Float2Double, Float2Int, Float2Long, Int2Byte,
+            Int2Char, Int2Double, Int2Float, Int2Long, Int2Short, Long2Double, Long2Float,
+            Long2Int,
+        };
+        let ty = match expr {
+            Expression::Const(ConstantValue::Integer(_))
+            | Expression::Math(
+                MathOperation::LongComparison(..) | MathOperation::FloatingPointComparison(..),
+            )
+            | Expression::Conversion(
+                Long2Int(_) | Float2Int(_) | Double2Int(_) | Int2Byte(_) | Int2Char(_)
+                | Int2Short(_),
+            ) => ValType::I32,
+            Expression::Const(ConstantValue::Long(_))
+            | Expression::Conversion(Int2Long(_) | Float2Long(_) | Double2Long(_)) => ValType::I64,
+            Expression::Const(ConstantValue::Float(_))
+            | Expression::Conversion(Int2Float(_) | Long2Float(_) | Double2Float(_)) => {
+                ValType::F32
+            }
+            Expression::Const(ConstantValue::Double(_))
+            | Expression::Conversion(Int2Double(_) | Long2Double(_) | Float2Double(_)) => {
+                ValType::F64
+            }
+            Expression::Math(
+                MathOperation::Add(lhs, _)
+                | MathOperation::Subtract(lhs, _)
+                | MathOperation::Multiply(lhs, _)
+                | MathOperation::Divide(lhs, _)
+                | MathOperation::Remainder(lhs, _)
+                | MathOperation::Negate(lhs)
+                | MathOperation::Increment(lhs, _)
+                | MathOperation::ShiftLeft(lhs, _)
+                | MathOperation::ShiftRight(lhs, _)
+                | MathOperation::LogicalShiftRight(lhs, _)
+                | MathOperation::BitwiseAnd(lhs, _)
+                | MathOperation::BitwiseOr(lhs, _)
+                | MathOperation::BitwiseXor(lhs, _),
+            ) => return self.operand_type(lhs),
+            Expression::Call { method, .. } => return Ok(func_type(&method.descriptor)?.1),
+            _ => return Err(Error::Unsupported(format!("expression {expr}"))),
+        };
+        Ok(Some(ty))
+    }
+
+    fn local_of(&self, id: Identifier) -> Result<u32, Error> {
+        self.locals
+            .get(&self.find(id))
+            .copied()
+            .ok_or(Error::UnknownType(id))
+    }
+
+    fn typed(&self, operand: &Operand) -> Result<ValType, Error> {
+        let id = representative(operand)?;
+        self.operand_type(operand)?.ok_or(Error::UnknownType(id))
+    }
+
+    /// Lowers the method into the body of a function.
+    fn lower(mut self) -> Result<LoweredBody, Error> {
+        let (dispatcher, locals) = self.declare_locals()?;
+        let leaders = self.leaders();
+        if leaders.is_empty() {
+            return Err(Error::Unsupported("method without instructions".to_owned()));
+        }
+        let block_count = u32::try_from(leaders.len())
+            .map_err(|_| Error::Unsupported("too many basic blocks".to_owned()))?;
+        let block_of = |pc: &ProgramCounter| {
+            let index = leaders
+                .iter()
+                .position(|it| it == pc)
+                .expect("Every jump target should be a leader");
+            u32::try_from(index).expect("The number of blocks should be within u32")
+        };
+
+        // loop { block { ... block { br_table } <block 0> } ... <block n - 1> }
+        self.code.push(Instruction::Loop(BlockType::Empty));
+        for _ in 0..block_count {
+            self.code.push(Instruction::Block(BlockType::Empty));
+        }
+        self.code.push(Instruction::LocalGet(dispatcher));
+        let labels: Vec<_> = (0..block_count - 1).collect();
+        self.code
+            .push(Instruction::BrTable(labels.into(), block_count - 1));
+        self.code.push(Instruction::End);
+
+        let mut current_block = 0;
+        for (pc, insn) in self.method.instructions.iter() {
+            if pc != &leaders[0] && leaders.contains(pc) {
+                self.code.push(Instruction::End);
+                current_block += 1;
+            }
+            // The depth of the label of the dispatching loop.
+            let depth = block_count - 1 - current_block;
+            match insn {
+                MokaInstruction::Nop => {}
+                MokaInstruction::Definition { value, expr } => {
+                    if self.lower_expression(expr)? {
+                        let local = self.local_of((*value).into())?;
+                        self.code.push(Instruction::LocalSet(local));
+                    }
+                }
+                MokaInstruction::Jump { condition, target } => {
+                    let target = block_of(target);
+                    if let Some(condition) = condition {
+                        self.lower_condition(condition)?;
+                        self.code.push(Instruction::If(BlockType::Empty));
+                        self.jump(dispatcher, target, depth + 1);
+                        self.code.push(Instruction::End);
+                    } else {
+                        self.jump(dispatcher, target, depth);
+                    }
+                }
+                MokaInstruction::Switch {
+                    match_value,
+                    branches,
+                    default,
+                } => {
+                    for (key, target) in branches {
+                        self.lower_operand(match_value)?;
+                        self.code.push(Instruction::I32Const(*key));
+                        self.code.push(Instruction::I32Eq);
+                        self.code.push(Instruction::If(BlockType::Empty));
+                        self.jump(dispatcher, block_of(target), depth + 1);
+                        self.code.push(Instruction::End);
+                    }
+                    self.jump(dispatcher, block_of(default), depth);
+                }
+                MokaInstruction::Return(value) => {
+                    if let Some(value) = value {
+                        self.lower_operand(value)?;
+                    }
+                    self.code.push(Instruction::Return);
+                }
+                MokaInstruction::SubroutineRet(_) => {
+                    return Err(Error::Unsupported("subroutine".to_owned()));
+                }
+                MokaInstruction::Phi { .. } => {
+                    return Err(Error::Unsupported("explicit Phi".to_owned()));
+                }
+            }
+        }
+        // The end of the dispatching loop is never reached.
+        self.code
+            .extend([Instruction::End, Instruction::Unreachable, Instruction::End]);
+        Ok(LoweredBody {
+            locals,
+            instructions: self.code,
+            calls: self.calls,
+        })
+    }
+
+    /// Declares the locals and copies the parameters into them.
+    /// Returns the local holding the index of the basic block to be dispatched, and the types of
+    /// the locals after the parameters.
+    fn declare_locals(&mut self) -> Result<(u32, Vec<ValType>), Error> {
+        // The locals after the parameters are the block dispatcher and the values.
+        let dispatcher = self.param_count;
+        let mut local_types = vec![ValType::I32];
+        let roots: Vec<_> = self.types.iter().map(|(&id, &ty)| (id, ty)).collect();
+        for (id, ty) in roots {
+            let index = self.param_count
+                + u32::try_from(local_types.len())
+                    .map_err(|_| Error::Unsupported("too many locals".to_owned()))?;
+            self.locals.insert(id, index);
+            local_types.push(ty);
+        }
+        for index in 0..self.param_count {
+            let arg = u16::try_from(index).expect("The number of args should be within u16");
+            if let Ok(local) = self.local_of(Identifier::Arg(arg)) {
+                self.code.push(Instruction::LocalGet(index));
+                self.code.push(Instruction::LocalSet(local));
+            }
+        }
+        Ok((dispatcher, local_types))
+    }
+
+    fn leaders(&self) -> Vec<ProgramCounter> {
+        let instructions = &self.method.instructions;
+        let mut leaders = BTreeSet::new();
+        leaders.extend(instructions.entry_point().map(|(pc, _)| *pc));
+        for (pc, insn) in instructions.iter() {
+            match insn {
+                MokaInstruction::Jump { target, .. } => {
+                    leaders.insert(*target);
+                }
+                MokaInstruction::Switch {
+                    branches, default, ..
+                } => {
+                    leaders.extend(branches.values().copied());
+                    leaders.insert(*default);
+                }
+                MokaInstruction::Return(_) => {}
+                _ => continue,
+            }
+            leaders.extend(instructions.next_pc_of(pc));
+        }
+        leaders.into_iter().collect()
+    }
+
+    fn jump(&mut self, dispatcher: u32, target: u32, depth: u32) {
+        let target = i32::try_from(target).expect("The number of blocks should be within i32");
+        self.code.push(Instruction::I32Const(target));
+        self.code.push(Instruction::LocalSet(dispatcher));
+        self.code.push(Instruction::Br(depth));
+    }
+
+    fn lower_operand(&mut self, operand: &Operand) -> Result<(), Error> {
+        let local = self.local_of(representative(operand)?)?;
+        self.code.push(Instruction::LocalGet(local));
+        Ok(())
+    }
+
+    fn push_zero(&mut self, ty: ValType) {
+        self.code.push(match ty {
+            ValType::I32 => Instruction::I32Const(0),
+            ValType::I64 => Instruction::I64Const(0),
+            ValType::F32 => Instruction::F32Const(0f32.into()),
+            ValType::F64 => Instruction::F64Const(0f64.into()),
+        });
+    }
+
+    /// Lowers an expression and returns whether it leaves a value on the stack.
+    fn lower_expression(&mut self, expr: &Expression) -> Result<bool, Error> {
+        match expr {
+            Expression::Const(value) => self.code.push(match value {
+                ConstantValue::Integer(it) => Instruction::I32Const(*it),
+                ConstantValue::Long(it) => Instruction::I64Const(*it),
+                ConstantValue::Float(it) => Instruction::F32Const((*it).into()),
+                ConstantValue::Double(it) => Instruction::F64Const((*it).into()),
+                _ => return Err(Error::Unsupported(format!("constant {value}"))),
+            }),
+            Expression::Math(operation) => self.lower_math(operation)?,
+            Expression::Conversion(conversion) => self.lower_conversion(conversion)?,
+            Expression::Call { method, this, args } => {
+                if this.is_some() {
+                    return Err(Error::Unsupported(format!("instance call to {method}")));
+                }
+                for arg in args {
+                    self.lower_operand(arg)?;
+                }
+                // The callee index is filled when the module is finished.
+                self.calls.push((self.code.len(), method.clone()));
+                self.code.push(Instruction::Call(0));
+                return Ok(method.descriptor.return_type != ReturnType::Void);
+            }
+            _ => return Err(Error::Unsupported(format!("expression {expr}"))),
+        }
+        Ok(true)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn lower_math(&mut self, operation: &MathOperation) -> Result<(), Error> {
+        use ValType::{F32, F64, I32, I64};
+        match operation {
+            MathOperation::Negate(operand) => {
+                let ty = self.typed(operand)?;
+                if let F32 | F64 = ty {
+                    self.lower_operand(operand)?;
+                    self.code.push(if ty == F32 {
+                        Instruction::F32Neg
+                    } else {
+                        Instruction::F64Neg
+                    });
+                } else {
+                    self.push_zero(ty);
+                    self.lower_operand(operand)?;
+                    self.code.push(if ty == I32 {
+                        Instruction::I32Sub
+                    } else {
+                        Instruction::I64Sub
+                    });
+                }
+            }
+            MathOperation::Increment(operand, constant) => {
+                if self.typed(operand)? != I32 {
+                    return Err(Error::ConflictingTypes(representative(operand)?));
+                }
+                self.lower_operand(operand)?;
+                self.code.push(Instruction::I32Const(*constant));
+                self.code.push(Instruction::I32Add);
+            }
+            MathOperation::LongComparison(lhs, rhs) => {
+                // (lhs > rhs) - (lhs < rhs)
+                self.lower_binary(lhs, rhs, Instruction::I64GtS)?;
+                self.lower_binary(lhs, rhs, Instruction::I64LtS)?;
+                self.code.push(Instruction::I32Sub);
+            }
+            MathOperation::FloatingPointComparison(lhs, rhs, nan_treatment) => {
+                let (gt, ge, lt, le) = match self.typed(lhs)? {
+                    F32 => (
+                        Instruction::F32Gt,
+                        Instruction::F32Ge,
+                        Instruction::F32Lt,
+                        Instruction::F32Le,
+                    ),
+                    _ => (
+                        Instruction::F64Gt,
+                        Instruction::F64Ge,
+                        Instruction::F64Lt,
+                        Instruction::F64Le,
+                    ),
+                };
+                match nan_treatment {
+                    // (lhs > rhs) - !(lhs >= rhs)
+                    NaNTreatment::IsSmallest => {
+                        self.lower_binary(lhs, rhs, gt)?;
+                        self.lower_binary(lhs, rhs, ge)?;
+                        self.code.push(Instruction::I32Eqz);
+                    }
+                    // !(lhs <= rhs) - (lhs < rhs)
+                    NaNTreatment::IsLargest => {
+                        self.lower_binary(lhs, rhs, le)?;
+                        self.code.push(Instruction::I32Eqz);
+                        self.lower_binary(lhs, rhs, lt)?;
+                    }
+                }
+                self.code.push(Instruction::I32Sub);
+            }
+            MathOperation::ShiftLeft(lhs, rhs)
+            | MathOperation::ShiftRight(lhs, rhs)
+            | MathOperation::LogicalShiftRight(lhs, rhs) => {
+                let ty = self.typed(lhs)?;
+                let instruction = match (operation, ty) {
+                    (MathOperation::ShiftLeft(..), I32) => Instruction::I32Shl,
+                    (MathOperation::ShiftRight(..), I32) => Instruction::I32ShrS,
+                    (MathOperation::LogicalShiftRight(..), I32) => Instruction::I32ShrU,
+                    (MathOperation::ShiftLeft(..), I64) => Instruction::I64Shl,
+                    (MathOperation::ShiftRight(..), I64) => Instruction::I64ShrS,
+                    (MathOperation::LogicalShiftRight(..), I64) => Instruction::I64ShrU,
+                    _ => return Err(Error::ConflictingTypes(representative(lhs)?)),
+                };
+                self.lower_operand(lhs)?;
+                self.lower_operand(rhs)?;
+                if ty == I64 {
+                    // The shift distance is an `int`.
+                    self.code.push(Instruction::I64ExtendI32S);
+                }
+                self.code.push(instruction);
+            }
+            MathOperation::Add(lhs, rhs)
+            | MathOperation::Subtract(lhs, rhs)
+            | MathOperation::Multiply(lhs, rhs)
+            | MathOperation::Divide(lhs, rhs)
+            | MathOperation::Remainder(lhs, rhs)
+            | MathOperation::BitwiseAnd(lhs, rhs)
+            | MathOperation::BitwiseOr(lhs, rhs)
+            | MathOperation::BitwiseXor(lhs, rhs) => {
+                use Instruction as I;
+                let ty = self.typed(lhs)?;
+                // The instructions for `int`, `long`, `float`, and `double`.
+                let [int, long, float, double] = match operation {
+                    MathOperation::Add(..) => [
+                        Some(I::I32Add),
+                        Some(I::I64Add),
+                        Some(I::F32Add),
+                        Some(I::F64Add),
+                    ],
+                    MathOperation::Subtract(..) => [
+                        Some(I::I32Sub),
+                        Some(I::I64Sub),
+                        Some(I::F32Sub),
+                        Some(I::F64Sub),
+                    ],
+                    MathOperation::Multiply(..) => [
+                        Some(I::I32Mul),
+                        Some(I::I64Mul),
+                        Some(I::F32Mul),
+                        Some(I::F64Mul),
+                    ],
+                    MathOperation::Divide(..) => [
+                        Some(I::I32DivS),
+                        Some(I::I64DivS),
+                        Some(I::F32Div),
+                        Some(I::F64Div),
+                    ],
+                    MathOperation::Remainder(..) => {
+                        [Some(I::I32RemS), Some(I::I64RemS), None, None]
+                    }
+                    MathOperation::BitwiseAnd(..) => [Some(I::I32And), Some(I::I64And), None, None],
+                    MathOperation::BitwiseOr(..) => [Some(I::I32Or), Some(I::I64Or), None, None],
+                    _ => [Some(I::I32Xor), Some(I::I64Xor), None, None],
+                };
+                let instruction = match ty {
+                    I32 => int,
+                    I64 => long,
+                    F32 => float,
+                    F64 => double,
+                }
+                .ok_or_else(|| Error::Unsupported(format!("math operation {operation}")))?;
+                self.lower_binary(lhs, rhs, instruction)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn lower_binary(
+        &mut self,
+        lhs: &Operand,
+        rhs: &Operand,
+        instruction: Instruction<'static>,
+    ) -> Result<(), Error> {
+        self.lower_operand(lhs)?;
+        self.lower_operand(rhs)?;
+        self.code.push(instruction);
+        Ok(())
+    }
+
+    fn lower_conversion(&mut self, conversion: &Conversion) -> Result<(), Error> {
+        use Instruction as I;
+        let (operand, instructions) = match conversion {
+            Conversion::Int2Long(it) => (it, vec![I::I64ExtendI32S]),
+            Conversion::Int2Float(it) => (it, vec![I::F32ConvertI32S]),
+            Conversion::Int2Double(it) => (it, vec![I::F64ConvertI32S]),
+            Conversion::Long2Int(it) => (it, vec![I::I32WrapI64]),
+            Conversion::Long2Float(it) => (it, vec![I::F32ConvertI64S]),
+            Conversion::Long2Double(it) => (it, vec![I::F64ConvertI64S]),
+            // The saturating truncations agree with the JVM on NaNs and out of range values.
+            Conversion::Float2Int(it) => (it, vec![I::I32TruncSatF32S]),
+            Conversion::Float2Long(it) => (it, vec![I::I64TruncSatF32S]),
+            Conversion::Float2Double(it) => (it, vec![I::F64PromoteF32]),
+            Conversion::Double2Int(it) => (it, vec![I::I32TruncSatF64S]),
+            Conversion::Double2Long(it) => (it, vec![I::I64TruncSatF64S]),
+            Conversion::Double2Float(it) => (it, vec![I::F32DemoteF64]),
+            Conversion::Int2Byte(it) => (it, vec![I::I32Extend8S]),
+            Conversion::Int2Short(it) => (it, vec![I::I32Extend16S]),
+            Conversion::Int2Char(it) => (it, vec![I::I32Const(0xffff), I::I32And]),
+            Conversion::CheckCast(..) | Conversion::InstanceOf(..) => {
+                return Err(Error::Unsupported(format!("conversion {conversion}")));
+            }
+        };
+        self.lower_operand(operand)?;
+        self.code.extend(instructions);
+        Ok(())
+    }
+
+    fn lower_condition(&mut self, condition: &Condition) -> Result<(), Error> {
+        use Instruction as I;
+        use ValType::{F32, F64, I32, I64};
+        let equal = [I::I32Eq, I::I64Eq, I::F32Eq, I::F64Eq];
+        let not_equal = [I::I32Ne, I::I64Ne, I::F32Ne, I::F64Ne];
+        let less_than = [I::I32LtS, I::I64LtS, I::F32Lt, I::F64Lt];
+        let greater_than = [I::I32GtS, I::I64GtS, I::F32Gt, I::F64Gt];
+        let less_or_equal = [I::I32LeS, I::I64LeS, I::F32Le, I::F64Le];
+        let greater_or_equal = [I::I32GeS, I::I64GeS, I::F32Ge, I::F64Ge];
+        let (lhs, rhs, [int, long, float, double]) = match condition {
+            Condition::Equal(lhs, rhs) => (lhs, Some(rhs), equal),
+            Condition::NotEqual(lhs, rhs) => (lhs, Some(rhs), not_equal),
+            Condition::LessThan(lhs, rhs) => (lhs, Some(rhs), less_than),
+            Condition::GreaterThan(lhs, rhs) => (lhs, Some(rhs), greater_than),
+            Condition::LessThanOrEqual(lhs, rhs) => (lhs, Some(rhs), less_or_equal),
+            Condition::GreaterThanOrEqual(lhs, rhs) => (lhs, Some(rhs), greater_or_equal),
+            Condition::IsZero(it) => (it, None, equal),
+            Condition::IsNonZero(it) => (it, None, not_equal),
+            Condition::IsNegative(it) => (it, None, less_than),
+            Condition::IsPositive(it) => (it, None, greater_than),
+            Condition::IsNonPositive(it) => (it, None, less_or_equal),
+            Condition::IsNonNegative(it) => (it, None, greater_or_equal),
+            Condition::IsNull(_) | Condition::IsNotNull(_) => {
+                return Err(Error::Unsupported(format!("condition {condition}")));
+            }
+        };
+        let ty = self.typed(lhs)?;
+        self.lower_operand(lhs)?;
+        match rhs {
+            Some(rhs) => self.lower_operand(rhs)?,
+            None => self.push_zero(ty),
+        }
+        self.code.push(match ty {
+            I32 => int,
+            I64 => long,
+            F32 => float,
+            F64 => double,
+        });
+        Ok(())
+    }
+}
+
+/// Returns an identifier of the values referred to by an operand.
+fn representative(operand: &Operand) -> Result<Identifier, Error> {
+    let id = match operand {
+        Operand::Just(id) => *id,
+        Operand::Phi(ids) => *ids
+            .first()
+            .ok_or_else(|| Error::Unsupported("empty Phi".to_owned()))?,
+    };
+    match id {
+        Identifier::Arg(_) | Identifier::Local(_) => Ok(id),
+        Identifier::This | Identifier::CaughtException => {
+            Err(Error::Unsupported(format!("value {id}")))
+        }
+    }
+}
+
+fn expression_operands(expr: &Expression) -> Vec<&Operand> {
+    match expr {
+        Expression::Math(
+            MathOperation::Add(a, b)
+            | MathOperation::Subtract(a, b)
+            | MathOperation::Multiply(a, b)
+            | MathOperation::Divide(a, b)
+            | MathOperation::Remainder(a, b)
+            | MathOperation::ShiftLeft(a, b)
+            | MathOperation::ShiftRight(a, b)
+            | MathOperation::LogicalShiftRight(a, b)
+            | MathOperation::BitwiseAnd(a, b)
+            | MathOperation::BitwiseOr(a, b)
+            | MathOperation::BitwiseXor(a, b)
+            | MathOperation::LongComparison(a, b)
+            | MathOperation::FloatingPointComparison(a, b, _),
+        ) => vec![a, b],
+        Expression::Math(MathOperation::Negate(a) | MathOperation::Increment(a, _))
+        | Expression::Conversion(
+            Conversion::Int2Long(a)
+            | Conversion::Int2Float(a)
+            | Conversion::Int2Double(a)
+            | Conversion::Long2Int(a)
+            | Conversion::Long2Float(a)
+            | Conversion::Long2Double(a)
+            | Conversion::Float2Int(a)
+            | Conversion::Float2Long(a)
+            | Conversion::Float2Double(a)
+            | Conversion::Double2Int(a)
+            | Conversion::Double2Long(a)
+            | Conversion::Double2Float(a)
+            | Conversion::Int2Byte(a)
+            | Conversion::Int2Char(a)
+            | Conversion::Int2Short(a),
+        ) => vec![a],
+        Expression::Call { args, .. } => args.iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn condition_operands(condition: &Condition) -> Vec<&Operand> {
+    match condition {
+        Condition::Equal(a, b)
+        | Condition::NotEqual(a, b)
+        | Condition::LessThan(a, b)
+        | Condition::LessThanOrEqual(a, b)
+        | Condition::GreaterThan(a, b)
+        | Condition::GreaterThanOrEqual(a, b) => vec![a, b],
+        Condition::IsNull(a)
+        | Condition::IsNotNull(a)
+        | Condition::IsZero(a)
+        | Condition::IsNonZero(a)
+        | Condition::IsPositive(a)
+        | Condition::IsNegative(a)
+        | Condition::IsNonNegative(a)
+        | Condition::IsNonPositive(a) => vec![a],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use wasmparser::{Operator, Parser, Payload, TypeRef};
+
+    use super::*;
+    use crate::{
+        ir::MokaIRMethodExt,
+        jvm::{
+            code::{Instruction, MethodBody},
+            method, Method,
+        },
+    };
+    use Instruction::{
+        DCmpL, DLoad1, FLoad0, Goto, IAdd, IConst0, IConst1, IInc, ILoad, ILoad0, ILoad1, ILoad2,
+        INeg, IReturn, IStore1, IStore2, IfICmpGe, InvokeStatic, LLoad0, LReturn, LShl,
+        LookupSwitch, I2C, I2L, L2I,
+    };
+
+    fn brew_method(
+        name: &str,
+        descriptor: &str,
+        instructions: Vec<(u16, Instruction)>,
+    ) -> MokaIRMethod {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: name.to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+            body: Some(MethodBody {
+                max_stack: 4,
+                max_locals: 4,
+                instructions: instructions
+                    .into_iter()
+                    .map(|(pc, insn)| (pc.into(), insn))
+                    .collect::<BTreeMap<_, _>>()
+                    .into(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        method.brew().unwrap()
+    }
+
+    /// The parts of a validated module that the tests check.
+    #[derive(Debug, Default)]
+    struct Parsed {
+        imports: Vec<(String, String, u32)>,
+        exports: Vec<(String, u32)>,
+        /// The operators of each function body, in their debug representation.
+        bodies: Vec<Vec<String>>,
+    }
+
+    fn validate(module: &[u8]) -> Parsed {
+        wasmparser::validate(module).unwrap();
+        let mut parsed = Parsed::default();
+        for payload in Parser::new(0).parse_all(module) {
+            match payload.unwrap() {
+                Payload::ImportSection(reader) => {
+                    for import in reader {
+                        let import = import.unwrap();
+                        let TypeRef::Func(type_index) = import.ty else {
+                            panic!("Only functions should be imported");
+                        };
+                        parsed.imports.push((
+                            import.module.to_owned(),
+                            import.name.to_owned(),
+                            type_index,
+                        ));
+                    }
+                }
+                Payload::ExportSection(reader) => {
+                    for export in reader {
+                        let export = export.unwrap();
+                        parsed.exports.push((export.name.to_owned(), export.index));
+                    }
+                }
+                Payload::CodeSectionEntry(body) => {
+                    let operators = body
+                        .get_operators_reader()
+                        .unwrap()
+                        .into_iter()
+                        .map(|it| format!("{:?}", it.unwrap()))
+                        .collect();
+                    parsed.bodies.push(operators);
+                }
+                _ => {}
+            }
+        }
+        parsed
+    }
+
+    fn contains(operators: &[String], needle: &[Operator<'_>]) -> bool {
+        let needle: Vec<_> = needle.iter().map(|it| format!("{it:?}")).collect();
+        operators.windows(needle.len()).any(|it| it == needle)
+    }
+
+    #[test]
+    fn lower_arithmetic() {
+        let add = brew_method(
+            "add",
+            "(II)I",
+            vec![(0, ILoad0), (1, ILoad1), (2, IAdd), (3, IReturn)],
+        );
+        let mut builder = ModuleBuilder::new();
+        builder.add_method(&add).unwrap();
+        let module = builder.finish().unwrap();
+        let parsed = validate(&module);
+        assert_eq!(parsed.exports, vec![("add".to_owned(), 0)]);
+        assert!(parsed.bodies[0].contains(&format!("{:?}", Operator::I32Add)));
+    }
+
+    #[test]
+    fn lower_loop() {
+        // int sum = 0; for (int i = 0; i < n; i++) { sum += i; } return sum;
+        let sum = brew_method(
+            "sum",
+            "(I)I",
+            vec![
+                (0, IConst0),
+                (1, IStore1),
+                (2, IConst0),
+                (3, IStore2),
+                (4, ILoad2),
+                (5, ILoad0),
+                (6, IfICmpGe(19.into())),
+                (9, ILoad1),
+                (10, ILoad2),
+                (11, IAdd),
+                (12, IStore1),
+                (13, IInc(2, 1)),
+                (16, Goto(4.into())),
+                (19, ILoad(1)),
+                (21, IReturn),
+            ],
+        );
+        let mut builder = ModuleBuilder::new();
+        builder.add_method(&sum).unwrap();
+        let module = builder.finish().unwrap();
+        let parsed = validate(&module);
+        // The dispatching `br_table` over the four basic blocks.
+        let br_table = parsed.bodies[0]
+            .iter()
+            .find(|it| it.starts_with("BrTable"))
+            .unwrap();
+        assert!(br_table.contains("default: 3"));
+        assert!(br_table.contains("[0, 1, 2]"));
+        assert!(contains(
+            &parsed.bodies[0],
+            &[
+                Operator::I32GeS,
+                Operator::If {
+                    blockty: wasmparser::BlockType::Empty
+                }
+            ]
+        ));
+    }
+
+    #[test]
+    fn lower_conversions_and_switches() {
+        // switch ((int) (long) -(char) dcmpl((double) f, d)) {
+        //     case -1: return 0;
+        //     default: return 1;
+        // }
+        let convert = brew_method(
+            "convert",
+            "(FD)I",
+            vec![
+                (0, FLoad0),
+                (1, Instruction::F2D),
+                (2, DLoad1),
+                (3, DCmpL),
+                (4, I2C),
+                (5, INeg),
+                (6, I2L),
+                (7, L2I),
+                (
+                    8,
+                    LookupSwitch {
+                        default: 32.into(),
+                        match_targets: BTreeMap::from([(-1, 30.into())]),
+                    },
+                ),
+                (30, IConst0),
+                (31, IReturn),
+                (32, IConst1),
+                (33, IReturn),
+            ],
+        );
+        let mut builder = ModuleBuilder::new();
+        builder.add_method(&convert).unwrap();
+        let module = builder.finish().unwrap();
+        let parsed = validate(&module);
+        let body = &parsed.bodies[0];
+        assert!(contains(body, &[Operator::F64PromoteF32]));
+        assert!(contains(
+            body,
+            &[Operator::I32Const { value: 0xffff }, Operator::I32And]
+        ));
+        assert!(contains(
+            body,
+            &[Operator::I32Const { value: -1 }, Operator::I32Eq]
+        ));
+    }
+
+    #[test]
+    fn lower_direct_calls() {
+        let shift = brew_method(
+            "shift",
+            "(J)J",
+            vec![(0, LLoad0), (1, IConst1), (2, LShl), (3, LReturn)],
+        );
+        let shift_ref = MethodRef::new(shift.owner.clone(), "shift", "(J)J".parse().unwrap());
+        let caller = brew_method(
+            "caller",
+            "(J)J",
+            vec![(0, LLoad0), (1, InvokeStatic(shift_ref)), (4, LReturn)],
+        );
+        let mut builder = ModuleBuilder::new();
+        builder.add_method(&caller).unwrap();
+        builder.add_method(&shift).unwrap();
+        let module = builder.finish().unwrap();
+        let parsed = validate(&module);
+        assert!(parsed.imports.is_empty());
+        // The shift distance is extended to `i64`.
+        assert!(contains(
+            &parsed.bodies[1],
+            &[Operator::I64ExtendI32S, Operator::I64Shl]
+        ));
+        assert!(contains(
+            &parsed.bodies[0],
+            &[Operator::Call { function_index: 1 }]
+        ));
+    }
+
+    #[test]
+    fn import_external_methods() {
+        let abs = MethodRef::new(
+            crate::jvm::references::ClassRef::new("java/lang/Math"),
+            "abs",
+            "(I)I".parse().unwrap(),
+        );
+        let caller = brew_method(
+            "caller",
+            "(I)I",
+            vec![(0, ILoad0), (1, InvokeStatic(abs)), (4, IReturn)],
+        );
+        let mut builder = ModuleBuilder::new();
+        builder.add_method(&caller).unwrap();
+        let module = builder.finish().unwrap();
+        let parsed = validate(&module);
+        assert_eq!(
+            parsed.imports,
+            vec![("java/lang/Math".to_owned(), "abs".to_owned(), 0)]
+        );
+        // The defined function comes after the imported one.
+        assert_eq!(parsed.exports, vec![("caller".to_owned(), 1)]);
+        assert!(contains(
+            &parsed.bodies[0],
+            &[Operator::Call { function_index: 0 }]
+        ));
+    }
+
+    #[test]
+    fn reject_unsupported_methods() {
+        let mut method = brew_method("id", "(I)I", vec![(0, ILoad0), (1, IReturn)]);
+        let mut builder = ModuleBuilder::new();
+        builder.add_method(&method).unwrap();
+        assert_eq!(
+            builder.add_method(&method),
+            Err(Error::DuplicateFunction("id".to_owned()))
+        );
+        method.access_flags = method::AccessFlags::PUBLIC;
+        assert!(matches!(
+            builder.add_method(&method),
+            Err(Error::Unsupported(_))
+        ));
+        validate(&builder.finish().unwrap());
+    }
+}
@@ -1,12 +1,18 @@
 //! Module for the APIs for the annotation in JVM.
+use std::collections::BTreeMap;
+
 use crate::{
     macros::see_jvm_spec,
-    types::{field_type::PrimitiveType, method_descriptor::ReturnType},
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::ReturnType,
+    },
 };
 
 use super::{
     code::{LocalVariableId, ProgramCounter},
-    Annotation, ConstantValue,
+    references::ClassRef,
+    Annotation, ConstantValue, JavaString,
 };
 
 /// A value of an annotation field.
@@ -35,6 +41,219 @@ pub enum ElementValue {
     Array(Vec<ElementValue>),
 }
 
+impl ElementValue {
+    /// Gets the value if it is an `int`, `short`, `byte`, or `char`.
+    #[must_use]
+    pub const fn as_int(&self) -> Option<i32> {
+        match self {
+            Self::Primitive(
+                PrimitiveType::Int
+                | PrimitiveType::Short
+                | PrimitiveType::Byte
+                | PrimitiveType::Char,
+                ConstantValue::Integer(value),
+            ) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is a `long`.
+    #[must_use]
+    pub const fn as_long(&self) -> Option<i64> {
+        match self {
+            Self::Primitive(PrimitiveType::Long, ConstantValue::Long(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is a `float` or a `double`.
+    #[must_use]
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            Self::Primitive(PrimitiveType::Float, ConstantValue::Float(value)) => {
+                Some(f64::from(*value))
+            }
+            Self::Primitive(PrimitiveType::Double, ConstantValue::Double(value)) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is a `boolean`.
+    #[must_use]
+    pub const fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Primitive(PrimitiveType::Boolean, ConstantValue::Integer(value)) => {
+                Some(*value != 0)
+            }
+            _ => None,
+        }
+    }
+
+    /// Gets the value if it is a string that is valid UTF-8.
+    #[must_use]
+    pub fn as_string(&self) -> Option<&str> {
+        match self {
+            Self::String(ConstantValue::String(JavaString::Utf8(value))) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Gets the type and the name of the constant if the value is an enum constant.
+    /// Returns [`None`] if the type name is not a valid class descriptor.
+    #[must_use]
+    pub fn as_enum(&self) -> Option<(ClassRef, &str)> {
+        match self {
+            Self::EnumConstant {
+                enum_type_name,
+                const_name,
+            } => match enum_type_name.parse() {
+                Ok(FieldType::Object(enum_type)) => Some((enum_type, const_name)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Gets the type of the class literal if the value is a class literal.
+    /// The type is [`ReturnType::Void`] for `void.class`.
+    #[must_use]
+    pub const fn as_class(&self) -> Option<&ReturnType> {
+        match self {
+            Self::Class { return_descriptor } => Some(return_descriptor),
+            _ => None,
+        }
+    }
+
+    /// Gets the annotation if the value is a nested annotation.
+    #[must_use]
+    pub const fn as_annotation(&self) -> Option<&Annotation> {
+        match self {
+            Self::AnnotationInterface(annotation) => Some(annotation),
+            _ => None,
+        }
+    }
+
+    /// Gets the elements if the value is an array.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[ElementValue]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Converts the value into a [`Value`].
+    /// Returns [`None`] if the value is malformed, e.g., the constant does not match the type.
+    #[must_use]
+    pub fn to_value(&self) -> Option<Value> {
+        let value = match self {
+            Self::Primitive(primitive_type, constant) => match (primitive_type, constant) {
+                (PrimitiveType::Boolean, ConstantValue::Integer(it)) => Value::Boolean(*it != 0),
+                (PrimitiveType::Char, ConstantValue::Integer(it)) => {
+                    Value::Char(u16::try_from(*it).ok()?)
+                }
+                (PrimitiveType::Byte, ConstantValue::Integer(it)) => {
+                    Value::Byte(i8::try_from(*it).ok()?)
+                }
+                (PrimitiveType::Short, ConstantValue::Integer(it)) => {
+                    Value::Short(i16::try_from(*it).ok()?)
+                }
+                (PrimitiveType::Int, ConstantValue::Integer(it)) => Value::Int(*it),
+                (PrimitiveType::Long, ConstantValue::Long(it)) => Value::Long(*it),
+                (PrimitiveType::Float, ConstantValue::Float(it)) => Value::Float(*it),
+                (PrimitiveType::Double, ConstantValue::Double(it)) => Value::Double(*it),
+                _ => return None,
+            },
+            Self::String(ConstantValue::String(it)) => Value::String(it.clone()),
+            Self::String(_) => return None,
+            Self::EnumConstant { .. } => {
+                let (enum_type, name) = self.as_enum()?;
+                Value::Enum {
+                    enum_type,
+                    name: name.to_owned(),
+                }
+            }
+            Self::Class { return_descriptor } => Value::Class(return_descriptor.clone()),
+            Self::AnnotationInterface(annotation) => annotation.to_value()?,
+            Self::Array(values) => {
+                Value::Array(values.iter().map(Self::to_value).collect::<Option<_>>()?)
+            }
+        };
+        Some(value)
+    }
+}
+
+impl Annotation {
+    /// Gets the value of the element named `name`.
+    /// Note that the elements with the default values are not stored in the annotation.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&ElementValue> {
+        self.element_value_pairs
+            .iter()
+            .find_map(|(it, value)| (it == name).then_some(value))
+    }
+
+    /// Converts the annotation into a [`Value::Annotation`].
+    /// Returns [`None`] if the annotation type is not a class or any of the values is malformed.
+    #[must_use]
+    pub fn to_value(&self) -> Option<Value> {
+        let FieldType::Object(annotation_type) = &self.annotation_type else {
+            return None;
+        };
+        let elements = self
+            .element_value_pairs
+            .iter()
+            .map(|(name, value)| value.to_value().map(|it| (name.clone(), it)))
+            .collect::<Option<_>>()?;
+        Some(Value::Annotation {
+            annotation_type: annotation_type.clone(),
+            elements,
+        })
+    }
+}
+
+/// A dynamically typed value of an annotation element, which is decoded from an
+/// [`ElementValue`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// A `boolean`.
+    Boolean(bool),
+    /// A `char` as a UTF-16 code unit.
+    Char(u16),
+    /// A `byte`.
+    Byte(i8),
+    /// A `short`.
+    Short(i16),
+    /// An `int`.
+    Int(i32),
+    /// A `long`.
+    Long(i64),
+    /// A `float`.
+    Float(f32),
+    /// A `double`.
+    Double(f64),
+    /// A string.
+    String(JavaString),
+    /// An enum constant.
+    Enum {
+        /// The enum type.
+        enum_type: ClassRef,
+        /// The name of the constant.
+        name: String,
+    },
+    /// A class literal.
+    Class(ReturnType),
+    /// A nested annotation.
+    Annotation {
+        /// The type of the annotation.
+        annotation_type: ClassRef,
+        /// The elements of the annotation by name.
+        elements: BTreeMap<String, Value>,
+    },
+    /// An array.
+    Array(Vec<Value>),
+}
+
 /// Information about the target of a [`TypeAnnotation`](super::TypeAnnotation).
 #[doc = see_jvm_spec!(4, 7, 20, 1)]
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -108,3 +327,72 @@ pub enum TypePathElement {
     /// Annotation is on a type argument of a parameterized type.
     TypeArgument(u8),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_accessors() {
+        let int = ElementValue::Primitive(PrimitiveType::Int, ConstantValue::Integer(42));
+        assert_eq!(int.as_int(), Some(42));
+        assert_eq!(int.as_bool(), None);
+        let string = ElementValue::String(ConstantValue::String(JavaString::Utf8(
+            "mokapot".to_owned(),
+        )));
+        assert_eq!(string.as_string(), Some("mokapot"));
+        let constant = ElementValue::EnumConstant {
+            enum_type_name: "Ljava/lang/annotation/RetentionPolicy;".to_owned(),
+            const_name: "RUNTIME".to_owned(),
+        };
+        assert_eq!(
+            constant.as_enum(),
+            Some((
+                ClassRef::new("java/lang/annotation/RetentionPolicy"),
+                "RUNTIME"
+            ))
+        );
+        let class = ElementValue::Class {
+            return_descriptor: ReturnType::Void,
+        };
+        assert_eq!(class.as_class(), Some(&ReturnType::Void));
+        let array = ElementValue::Array(vec![int.clone(), string]);
+        assert_eq!(array.as_array().map(<[_]>::len), Some(2));
+        assert_eq!(int.as_array(), None);
+    }
+
+    #[test]
+    fn to_value() {
+        let annotation = Annotation {
+            annotation_type: "LTimeout;".parse().unwrap(),
+            element_value_pairs: vec![
+                (
+                    "value".to_owned(),
+                    ElementValue::Primitive(PrimitiveType::Long, ConstantValue::Long(5)),
+                ),
+                (
+                    "flags".to_owned(),
+                    ElementValue::Array(vec![ElementValue::Primitive(
+                        PrimitiveType::Boolean,
+                        ConstantValue::Integer(1),
+                    )]),
+                ),
+            ],
+        };
+        assert!(annotation.get("value").is_some());
+        assert!(annotation.get("unit").is_none());
+        let Some(Value::Annotation {
+            annotation_type,
+            elements,
+        }) = annotation.to_value()
+        else {
+            panic!("The annotation should be converted");
+        };
+        assert_eq!(annotation_type, ClassRef::new("Timeout"));
+        assert_eq!(elements["value"], Value::Long(5));
+        assert_eq!(elements["flags"], Value::Array(vec![Value::Boolean(true)]));
+
+        let malformed = ElementValue::Primitive(PrimitiveType::Byte, ConstantValue::Integer(1000));
+        assert_eq!(malformed.to_value(), None);
+    }
+}
@@ -0,0 +1,673 @@
+//! Builders assembling JVM elements programmatically.
+
+use std::collections::BTreeSet;
+
+use crate::types::{
+    field_type::{FieldType, PrimitiveType},
+    method_descriptor::MethodDescriptor,
+};
+
+use super::{
+    class::{self, Version},
+    code::{ExceptionTableEntry, Instruction, InstructionList, MethodBody, WideInstruction},
+    field, method,
+    references::ClassRef,
+    Annotation, Class, ConstantValue, Field, Method,
+};
+
+const JAVA_LANG_OBJECT: &str = "java/lang/Object";
+
+/// An error when building a [`Class`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    /// The name of an element is not a valid unqualified or binary name.
+    #[error("Invalid name `{0}`")]
+    InvalidName(String),
+    /// A class other than `java/lang/Object` has no superclass.
+    #[error("The class {0} has no superclass")]
+    MissingSuperClass(String),
+    /// The access flags of an element are conflicting with each other.
+    #[error("The access flags of {0} are conflicting")]
+    ConflictingFlags(String),
+    /// Two fields have the same name and type.
+    #[error("Duplicated field {0}")]
+    DuplicateField(String),
+    /// Two methods have the same name and descriptor.
+    #[error("Duplicated method {0}")]
+    DuplicateMethod(String),
+    /// A method that is neither `abstract` nor `native` has no body.
+    #[error("The method {0} has no body")]
+    MissingBody(String),
+    /// An `abstract` or `native` method has a body.
+    #[error("The abstract or native method {0} has a body")]
+    UnexpectedBody(String),
+    /// An exception handler refers to a location without instruction.
+    #[error("The exception handler in {0} refers to a location without instruction")]
+    BadExceptionHandler(String),
+    /// The constant value does not match the type of the field.
+    #[error("The constant value of the field {0} does not match its type")]
+    MismatchedConstantValue(String),
+    /// An interface violates the constraints on interfaces (e.g., declares instance fields).
+    #[error("The interface is malformed: {0}")]
+    MalformedInterface(&'static str),
+}
+
+/// A builder of [`Class`]es.
+#[derive(Debug, Clone)]
+pub struct ClassBuilder {
+    class: Class,
+    fields: Vec<FieldBuilder>,
+    methods: Vec<MethodBuilder>,
+}
+
+impl ClassBuilder {
+    /// Creates a builder of a `public` class extending `java/lang/Object` with the given binary
+    /// name (e.g., `org/mokapot/Generated`) targeting Java 8.
+    pub fn new(binary_name: impl Into<String>) -> Self {
+        Self {
+            class: Class {
+                version: Version::Jdk8,
+                access_flags: class::AccessFlags::PUBLIC | class::AccessFlags::SUPER,
+                binary_name: binary_name.into(),
+                super_class: Some(ClassRef::new(JAVA_LANG_OBJECT)),
+                interfaces: Vec::new(),
+                fields: Vec::new(),
+                methods: Vec::new(),
+                source_file: None,
+                inner_classes: Vec::new(),
+                enclosing_method: None,
+                source_debug_extension: None,
+                compilation_id: None,
+                source_id: None,
+                runtime_visible_annotations: Vec::new(),
+                runtime_invisible_annotations: Vec::new(),
+                runtime_visible_type_annotations: Vec::new(),
+                runtime_invisible_type_annotations: Vec::new(),
+                bootstrap_methods: Vec::new(),
+                module: None,
+                module_packages: Vec::new(),
+                module_main_class: None,
+                nest_host: None,
+                nest_members: Vec::new(),
+                permitted_subclasses: Vec::new(),
+                is_synthetic: false,
+                is_deprecated: false,
+                signature: None,
+                record: None,
+                free_attributes: Vec::new(),
+                custom_attributes: Vec::new(),
+            },
+            fields: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    /// Sets the class file version.
+    #[must_use]
+    pub fn with_version(mut self, version: Version) -> Self {
+        self.class.version = version;
+        self
+    }
+
+    /// Sets the access flags.
+    #[must_use]
+    pub fn with_access_flags(mut self, access_flags: class::AccessFlags) -> Self {
+        self.class.access_flags = access_flags;
+        self
+    }
+
+    /// Sets the superclass, or removes it if `super_class` is [`None`].
+    #[must_use]
+    pub fn with_super_class(mut self, super_class: Option<ClassRef>) -> Self {
+        self.class.super_class = super_class;
+        self
+    }
+
+    /// Adds an implemented interface.
+    #[must_use]
+    pub fn with_interface(mut self, interface: ClassRef) -> Self {
+        self.class.interfaces.push(interface);
+        self
+    }
+
+    /// Adds a runtime visible annotation.
+    #[must_use]
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.class.runtime_visible_annotations.push(annotation);
+        self
+    }
+
+    /// Sets the generic signature.
+    #[must_use]
+    pub fn with_signature(mut self, signature: impl Into<class::Signature>) -> Self {
+        self.class.signature = Some(signature.into());
+        self
+    }
+
+    /// Sets the source file.
+    #[must_use]
+    pub fn with_source_file(mut self, source_file: impl Into<String>) -> Self {
+        self.class.source_file = Some(source_file.into());
+        self
+    }
+
+    /// Adds a field.
+    #[must_use]
+    pub fn with_field(mut self, field: FieldBuilder) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Adds a method.
+    #[must_use]
+    pub fn with_method(mut self, method: MethodBuilder) -> Self {
+        self.methods.push(method);
+        self
+    }
+
+    /// Validates the invariants of the class and its members, and builds the class.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn build(self) -> Result<Class, Error> {
+        let Self {
+            mut class,
+            fields,
+            methods,
+        } = self;
+        if !is_binary_name(&class.binary_name) {
+            return Err(Error::InvalidName(class.binary_name));
+        }
+        let flags = class.access_flags;
+        let is_interface = flags.contains(class::AccessFlags::INTERFACE);
+        if flags.contains(class::AccessFlags::FINAL | class::AccessFlags::ABSTRACT)
+            || (is_interface
+                && (!flags.contains(class::AccessFlags::ABSTRACT)
+                    || flags.intersects(class::AccessFlags::FINAL | class::AccessFlags::ENUM)))
+            || (flags.contains(class::AccessFlags::ANNOTATION) && !is_interface)
+        {
+            return Err(Error::ConflictingFlags(class.binary_name));
+        }
+        match &class.super_class {
+            None if class.binary_name != JAVA_LANG_OBJECT => {
+                return Err(Error::MissingSuperClass(class.binary_name));
+            }
+            Some(super_class) if is_interface && &*super_class.binary_name != JAVA_LANG_OBJECT => {
+                return Err(Error::MalformedInterface(
+                    "The superclass of an interface must be java/lang/Object",
+                ));
+            }
+            _ => {}
+        }
+
+        let owner = class.as_ref();
+        let mut field_keys = BTreeSet::new();
+        for builder in fields {
+            let field = builder.build(owner.clone())?;
+            let key = format!("{}:{}", field.name, field.field_type.descriptor());
+            if !field_keys.insert(key.clone()) {
+                return Err(Error::DuplicateField(key));
+            }
+            if is_interface
+                && !field.access_flags.contains(
+                    field::AccessFlags::PUBLIC
+                        | field::AccessFlags::STATIC
+                        | field::AccessFlags::FINAL,
+                )
+            {
+                return Err(Error::MalformedInterface(
+                    "The fields of an interface must be public static final",
+                ));
+            }
+            class.fields.push(field);
+        }
+        let mut method_keys = BTreeSet::new();
+        for builder in methods {
+            let method = builder.build(owner.clone())?;
+            let key = format!("{}{}", method.name, method.descriptor.descriptor());
+            if !method_keys.insert(key.clone()) {
+                return Err(Error::DuplicateMethod(key));
+            }
+            if method.access_flags.contains(method::AccessFlags::ABSTRACT)
+                && !flags.contains(class::AccessFlags::ABSTRACT)
+            {
+                return Err(Error::ConflictingFlags(key));
+            }
+            if is_interface && method.is_constructor() {
+                return Err(Error::MalformedInterface(
+                    "An interface cannot declare constructors",
+                ));
+            }
+            class.methods.push(method);
+        }
+        Ok(class)
+    }
+}
+
+/// A builder of [`Field`]s, which are added to a [`ClassBuilder`].
+#[derive(Debug, Clone)]
+pub struct FieldBuilder {
+    access_flags: field::AccessFlags,
+    name: String,
+    field_type: FieldType,
+    constant_value: Option<ConstantValue>,
+    signature: Option<field::Signature>,
+    annotations: Vec<Annotation>,
+}
+
+impl FieldBuilder {
+    /// Creates a builder of a `private` field.
+    pub fn new(name: impl Into<String>, field_type: FieldType) -> Self {
+        Self {
+            access_flags: field::AccessFlags::PRIVATE,
+            name: name.into(),
+            field_type,
+            constant_value: None,
+            signature: None,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Sets the access flags.
+    #[must_use]
+    pub fn with_access_flags(mut self, access_flags: field::AccessFlags) -> Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    /// Sets the initial value stored in the `ConstantValue` attribute.
+    #[must_use]
+    pub fn with_constant_value(mut self, constant_value: ConstantValue) -> Self {
+        self.constant_value = Some(constant_value);
+        self
+    }
+
+    /// Sets the generic signature.
+    #[must_use]
+    pub fn with_signature(mut self, signature: impl Into<field::Signature>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+
+    /// Adds a runtime visible annotation.
+    #[must_use]
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    fn build(self, owner: ClassRef) -> Result<Field, Error> {
+        if !is_unqualified_name(&self.name, false) {
+            return Err(Error::InvalidName(self.name));
+        }
+        let visibility = self.access_flags
+            & (field::AccessFlags::PUBLIC
+                | field::AccessFlags::PROTECTED
+                | field::AccessFlags::PRIVATE);
+        if visibility.bits().count_ones() > 1
+            || self
+                .access_flags
+                .contains(field::AccessFlags::FINAL | field::AccessFlags::VOLATILE)
+        {
+            return Err(Error::ConflictingFlags(self.name));
+        }
+        if let Some(constant_value) = &self.constant_value {
+            let descriptor = self.field_type.descriptor();
+            let matches = match constant_value {
+                ConstantValue::Integer(_) => ["I", "S", "C", "B", "Z"].contains(&&*descriptor),
+                ConstantValue::Long(_) => descriptor == "J",
+                ConstantValue::Float(_) => descriptor == "F",
+                ConstantValue::Double(_) => descriptor == "D",
+                ConstantValue::String(_) => descriptor == "Ljava/lang/String;",
+                _ => false,
+            };
+            if !matches {
+                return Err(Error::MismatchedConstantValue(self.name));
+            }
+        }
+        Ok(Field {
+            access_flags: self.access_flags,
+            name: self.name,
+            owner,
+            field_type: self.field_type,
+            constant_value: self.constant_value,
+            is_synthetic: false,
+            is_deprecated: false,
+            signature: self.signature,
+            runtime_visible_annotations: self.annotations,
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        })
+    }
+}
+
+/// A builder of [`Method`]s, which are added to a [`ClassBuilder`].
+#[derive(Debug, Clone)]
+pub struct MethodBuilder {
+    access_flags: method::AccessFlags,
+    name: String,
+    descriptor: MethodDescriptor,
+    body: Option<(u16, InstructionList<Instruction>)>,
+    max_locals: Option<u16>,
+    exception_table: Vec<ExceptionTableEntry>,
+    exceptions: Vec<ClassRef>,
+    signature: Option<method::Signature>,
+    annotations: Vec<Annotation>,
+}
+
+impl MethodBuilder {
+    /// Creates a builder of a `public` method.
+    pub fn new(name: impl Into<String>, descriptor: MethodDescriptor) -> Self {
+        Self {
+            access_flags: method::AccessFlags::PUBLIC,
+            name: name.into(),
+            descriptor,
+            body: None,
+            max_locals: None,
+            exception_table: Vec::new(),
+            exceptions: Vec::new(),
+            signature: None,
+            annotations: Vec::new(),
+        }
+    }
+
+    /// Sets the access flags.
+    #[must_use]
+    pub fn with_access_flags(mut self, access_flags: method::AccessFlags) -> Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    /// Sets the instructions and the maximum depth of the operand stack.
+    #[must_use]
+    pub fn with_body(
+        mut self,
+        max_stack: u16,
+        instructions: impl Into<InstructionList<Instruction>>,
+    ) -> Self {
+        self.body = Some((max_stack, instructions.into()));
+        self
+    }
+
+    /// Sets the number of local variable slots.
+    /// If not set, it is computed from the descriptor and the local variables accessed by the
+    /// instructions.
+    #[must_use]
+    pub fn with_max_locals(mut self, max_locals: u16) -> Self {
+        self.max_locals = Some(max_locals);
+        self
+    }
+
+    /// Adds an exception handler.
+    #[must_use]
+    pub fn with_exception_handler(mut self, entry: ExceptionTableEntry) -> Self {
+        self.exception_table.push(entry);
+        self
+    }
+
+    /// Adds an exception to the `throws` clause.
+    #[must_use]
+    pub fn with_exception(mut self, exception: ClassRef) -> Self {
+        self.exceptions.push(exception);
+        self
+    }
+
+    /// Sets the generic signature.
+    #[must_use]
+    pub fn with_signature(mut self, signature: impl Into<method::Signature>) -> Self {
+        self.signature = Some(signature.into());
+        self
+    }
+
+    /// Adds a runtime visible annotation.
+    #[must_use]
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.annotations.push(annotation);
+        self
+    }
+
+    fn build(self, owner: ClassRef) -> Result<Method, Error> {
+        let key = format!("{}{}", self.name, self.descriptor.descriptor());
+        let is_special =
+            self.name == Method::CONSTRUCTOR_NAME || self.name == Method::CLASS_INITIALIZER_NAME;
+        if !is_special && !is_unqualified_name(&self.name, true) {
+            return Err(Error::InvalidName(self.name));
+        }
+        let flags = self.access_flags;
+        let visibility = flags
+            & (method::AccessFlags::PUBLIC
+                | method::AccessFlags::PROTECTED
+                | method::AccessFlags::PRIVATE);
+        let incompatible_with_abstract = method::AccessFlags::PRIVATE
+            | method::AccessFlags::STATIC
+            | method::AccessFlags::FINAL
+            | method::AccessFlags::SYNCHRONIZED
+            | method::AccessFlags::NATIVE
+            | method::AccessFlags::STRICT;
+        if visibility.bits().count_ones() > 1
+            || (flags.contains(method::AccessFlags::ABSTRACT)
+                && flags.intersects(incompatible_with_abstract))
+        {
+            return Err(Error::ConflictingFlags(key));
+        }
+        let is_bodyless =
+            flags.intersects(method::AccessFlags::ABSTRACT | method::AccessFlags::NATIVE);
+        let body = match (self.body, is_bodyless) {
+            (None, false) => return Err(Error::MissingBody(key)),
+            (Some(_), true) => return Err(Error::UnexpectedBody(key)),
+            (None, true) => None,
+            (Some((max_stack, instructions)), false) => {
+                let is_valid_pc = |pc| instructions.get(&pc).is_some();
+                if !self.exception_table.iter().all(|it| {
+                    is_valid_pc(*it.covered_pc.start())
+                        && is_valid_pc(*it.covered_pc.end())
+                        && is_valid_pc(it.handler_pc)
+                }) {
+                    return Err(Error::BadExceptionHandler(key));
+                }
+                let max_locals = self.max_locals.unwrap_or_else(|| {
+                    let this_slot = u16::from(!flags.contains(method::AccessFlags::STATIC));
+                    let arguments = self
+                        .descriptor
+                        .parameters_types
+                        .iter()
+                        .map(|it| match it {
+                            FieldType::Base(PrimitiveType::Long | PrimitiveType::Double) => 2,
+                            _ => 1,
+                        })
+                        .sum::<u16>();
+                    instructions
+                        .iter()
+                        .filter_map(|(_, insn)| local_slots_end(insn))
+                        .fold(this_slot + arguments, u16::max)
+                });
+                Some(MethodBody {
+                    max_stack,
+                    max_locals,
+                    instructions,
+                    exception_table: self.exception_table,
+                    line_number_table: None,
+                    character_range_table: None,
+                    local_variable_table: None,
+                    stack_map_table: None,
+                    runtime_visible_type_annotations: Vec::new(),
+                    runtime_invisible_type_annotations: Vec::new(),
+                    free_attributes: Vec::new(),
+                    custom_attributes: Vec::new(),
+                })
+            }
+        };
+        Ok(Method {
+            access_flags: flags,
+            name: self.name,
+            descriptor: self.descriptor,
+            owner,
+            body,
+            exceptions: self.exceptions,
+            runtime_visible_annotations: self.annotations,
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            runtime_visible_parameter_annotations: Vec::new(),
+            runtime_invisible_parameter_annotations: Vec::new(),
+            annotation_default: None,
+            parameters: None,
+            is_synthetic: false,
+            is_deprecated: false,
+            signature: self.signature,
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        })
+    }
+}
+
+/// Gets the index after the last local variable slot accessed by `insn`.
+fn local_slots_end(insn: &Instruction) -> Option<u16> {
+    #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+    use Instruction::*;
+    let (index, width) = match insn {
+        ILoad(idx)
+        | FLoad(idx)
+        | ALoad(idx)
+        | IStore(idx)
+        | FStore(idx)
+        | AStore(idx)
+        | IInc(idx, _)
+        | Ret(idx) => (u16::from(*idx), 1),
+        LLoad(idx) | DLoad(idx) | LStore(idx) | DStore(idx) => (u16::from(*idx), 2),
+        ILoad0 | FLoad0 | ALoad0 | IStore0 | FStore0 | AStore0 => (0, 1),
+        ILoad1 | FLoad1 | ALoad1 | IStore1 | FStore1 | AStore1 => (1, 1),
+        ILoad2 | FLoad2 | ALoad2 | IStore2 | FStore2 | AStore2 => (2, 1),
+        ILoad3 | FLoad3 | ALoad3 | IStore3 | FStore3 | AStore3 => (3, 1),
+        LLoad0 | DLoad0 | LStore0 | DStore0 => (0, 2),
+        LLoad1 | DLoad1 | LStore1 | DStore1 => (1, 2),
+        LLoad2 | DLoad2 | LStore2 | DStore2 => (2, 2),
+        LLoad3 | DLoad3 | LStore3 | DStore3 => (3, 2),
+        Wide(
+            WideInstruction::ILoad(idx)
+            | WideInstruction::FLoad(idx)
+            | WideInstruction::ALoad(idx)
+            | WideInstruction::IStore(idx)
+            | WideInstruction::FStore(idx)
+            | WideInstruction::AStore(idx)
+            | WideInstruction::IInc(idx, _)
+            | WideInstruction::Ret(idx),
+        ) => (*idx, 1),
+        Wide(
+            WideInstruction::LLoad(idx)
+            | WideInstruction::DLoad(idx)
+            | WideInstruction::LStore(idx)
+            | WideInstruction::DStore(idx),
+        ) => (*idx, 2),
+        _ => return None,
+    };
+    Some(index.saturating_add(width))
+}
+
+fn is_unqualified_name(name: &str, is_method: bool) -> bool {
+    let forbidden: &[char] = if is_method {
+        &['.', ';', '[', '/', '<', '>']
+    } else {
+        &['.', ';', '[', '/']
+    };
+    !name.is_empty() && !name.contains(forbidden)
+}
+
+fn is_binary_name(name: &str) -> bool {
+    name.split('/').all(|it| is_unqualified_name(it, false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_class() {
+        let class = ClassBuilder::new("org/mokapot/Generated")
+            .with_interface(ClassRef::new("java/lang/Runnable"))
+            .with_field(
+                FieldBuilder::new("COUNT", FieldType::Base(PrimitiveType::Long))
+                    .with_access_flags(field::AccessFlags::STATIC | field::AccessFlags::FINAL)
+                    .with_constant_value(ConstantValue::Long(42)),
+            )
+            .with_method(
+                MethodBuilder::new("add", "(JD)J".parse().unwrap())
+                    .with_access_flags(method::AccessFlags::STATIC)
+                    .with_body(
+                        4,
+                        InstructionList::from([
+                            (0.into(), Instruction::LLoad0),
+                            (1.into(), Instruction::DStore(3)),
+                            (2.into(), Instruction::LReturn),
+                        ]),
+                    ),
+            )
+            .with_method(
+                MethodBuilder::new("run", "()V".parse().unwrap())
+                    .with_body(0, InstructionList::from([(0.into(), Instruction::Return)])),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(class.fields[0].owner, class.as_ref());
+        let max_locals: Vec<_> = class
+            .methods
+            .iter()
+            .map(|it| it.body.as_ref().unwrap().max_locals)
+            .collect();
+        assert_eq!(max_locals, vec![5, 1]);
+    }
+
+    #[test]
+    fn invariants() {
+        let abstract_method = || {
+            MethodBuilder::new("run", "()V".parse().unwrap())
+                .with_access_flags(method::AccessFlags::PUBLIC | method::AccessFlags::ABSTRACT)
+        };
+        assert_eq!(
+            ClassBuilder::new("A")
+                .with_method(abstract_method())
+                .build()
+                .err(),
+            Some(Error::ConflictingFlags("run()V".to_owned()))
+        );
+        assert_eq!(
+            ClassBuilder::new("A")
+                .with_method(MethodBuilder::new("run", "()V".parse().unwrap()))
+                .build()
+                .err(),
+            Some(Error::MissingBody("run()V".to_owned()))
+        );
+        let interface = ClassBuilder::new("I").with_access_flags(
+            class::AccessFlags::PUBLIC
+                | class::AccessFlags::INTERFACE
+                | class::AccessFlags::ABSTRACT,
+        );
+        assert!(interface
+            .clone()
+            .with_method(abstract_method())
+            .with_method(abstract_method().with_signature("()V"))
+            .build()
+            .is_err_and(|it| matches!(it, Error::DuplicateMethod(_))));
+        assert!(interface
+            .with_field(FieldBuilder::new("x", FieldType::Base(PrimitiveType::Int)))
+            .build()
+            .is_err_and(|it| matches!(it, Error::MalformedInterface(_))));
+        assert_eq!(
+            ClassBuilder::new("a.B").build().err(),
+            Some(Error::InvalidName("a.B".to_owned()))
+        );
+        assert_eq!(
+            ClassBuilder::new("A")
+                .with_field(
+                    FieldBuilder::new("s", FieldType::Base(PrimitiveType::Int))
+                        .with_constant_value(ConstantValue::Long(0))
+                )
+                .build()
+                .err(),
+            Some(Error::MismatchedConstantValue("s".to_owned()))
+        );
+    }
+}
@@ -0,0 +1,834 @@
+//! Lazy parsing of class files.
+//! A [`LazyClass`] reads only the constant pool, the access flags, and the signatures of the
+//! members. The attributes, including the method bodies, are skipped and parsed on demand.
+//! The annotations, the local variable tables, and the stack map tables are decoded on first
+//! access and cached, while the other attributes remain available as raw bytes.
+//!
+//! [`Class::from_reader`] always decodes every attribute, so [`LazyClass`] is the entry point
+//! for reading the metadata of a class without paying for the parts that are not used.
+//! The attributes decoded on demand are the same as those in the [`Class`] parsed eagerly.
+
+use std::{
+    io::{self, Cursor},
+    ops::Range,
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    jvm::{
+        class::{self, constant_pool::BootstrapMethodEntry, ConstantPool, Version},
+        code::{LocalVariableTable, MethodBody, StackMapFrame},
+        field, method,
+        parsing::{merge_local_variables, reader_utils::ValueReaderExt, Attribute, Context, Error},
+        references::ClassRef,
+        Annotation, Class, Method,
+    },
+    types::{field_type::FieldType, method_descriptor::MethodDescriptor},
+};
+
+const JAVA_CLASS_MAIGC: u32 = 0xCAFE_BABE;
+
+/// A class whose attributes are not parsed yet.
+/// Use it instead of [`Class::from_reader`] when only the metadata of the class is needed.
+#[derive(Debug, Clone)]
+pub struct LazyClass<'a> {
+    /// The version of the class file.
+    pub version: Version,
+    /// The access modifiers of the class.
+    pub access_flags: class::AccessFlags,
+    /// The binary name of the class (e.g., `org/mokapot/jvm/Class`).
+    pub binary_name: String,
+    /// A reference to the superclass of the class.
+    pub super_class: Option<ClassRef>,
+    /// The interfaces implemented by the class.
+    pub interfaces: Vec<ClassRef>,
+    /// The fields declared the class.
+    pub fields: Vec<LazyField>,
+    /// The methods declared in the class.
+    pub methods: Vec<LazyMethod>,
+    attributes: LazyAttributes,
+    field_attributes: Vec<LazyAttributes>,
+    method_attributes: Vec<LazyAttributes>,
+    bytes: &'a [u8],
+    context: Context,
+}
+
+/// The annotations on a class or a member in a [`LazyClass`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Annotations {
+    /// The runtime visible annotations.
+    pub runtime_visible: Vec<Annotation>,
+    /// The runtime invisible annotations.
+    pub runtime_invisible: Vec<Annotation>,
+}
+
+/// The attributes of a class or a member, which are decoded on first access.
+#[derive(Debug, Clone, Default)]
+struct LazyAttributes {
+    /// The name indices and the spans of the `attribute_info` structures.
+    spans: Vec<(u16, Range<usize>)>,
+    annotations: OnceLock<Annotations>,
+    local_variable_table: OnceLock<Option<LocalVariableTable>>,
+    stack_map_table: OnceLock<Option<Vec<StackMapFrame>>>,
+}
+
+/// The signature of a field in a [`LazyClass`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazyField {
+    /// The access modifiers of the field.
+    pub access_flags: field::AccessFlags,
+    /// The name of the field.
+    pub name: String,
+    /// The type of the field.
+    pub field_type: FieldType,
+}
+
+/// The signature of a method in a [`LazyClass`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LazyMethod {
+    /// The access flags of the method.
+    pub access_flags: method::AccessFlags,
+    /// The name of the method.
+    pub name: String,
+    /// The descriptor of the method.
+    pub descriptor: MethodDescriptor,
+    span: Range<usize>,
+}
+
+impl<'a> LazyClass<'a> {
+    /// Parses the class file in `bytes` without parsing the attributes.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, Error> {
+        let reader = &mut Cursor::new(bytes);
+        let magic: u32 = reader.read_value()?;
+        if magic != JAVA_CLASS_MAIGC {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "This is not a Java class file",
+            ))?;
+        }
+        let minor_version = reader.read_value()?;
+        let major_version = reader.read_value()?;
+        let version = Version::from_versions(major_version, minor_version)?;
+        let constant_pool_count = reader.read_value()?;
+        let constant_pool = ConstantPool::from_reader(reader, constant_pool_count)?;
+        let access_flags = reader.read_value()?;
+        let this_class = reader.read_value()?;
+        let ClassRef { binary_name } = constant_pool.get_class_ref(this_class)?;
+        let mut context = Context {
+            constant_pool,
+            class_version: version,
+            current_class_binary_name: binary_name.clone(),
+            attribute_registry: Arc::default(),
+            warnings: None,
+        };
+        let ctx = &context;
+        let access_flags = ctx.flags("ClassAccessFlags", access_flags)?;
+        let super_class = reader.read_value()?;
+        let super_class = Class::resolve_super_class(
+            super_class,
+            &binary_name,
+            access_flags,
+            &ctx.constant_pool,
+        )?;
+        let interfaces_count: u16 = reader.read_value()?;
+        let interfaces = (0..interfaces_count)
+            .map(|_| ctx.constant_pool.get_class_ref(reader.read_value()?))
+            .collect::<Result<_, _>>()?;
+        let fields_count: u16 = reader.read_value()?;
+        let (fields, field_attributes) = (0..fields_count)
+            .map(|_| {
+                let MemberHeader {
+                    access_flags,
+                    name,
+                    descriptor,
+                    attributes,
+                    ..
+                } = MemberHeader::skim(reader, ctx)?;
+                let field = LazyField {
+                    access_flags: ctx.flags("FieldAccessFlag", access_flags)?,
+                    name,
+                    field_type: descriptor.parse()?,
+                };
+                Ok((field, attributes))
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
+        let methods_count: u16 = reader.read_value()?;
+        let (methods, method_attributes) = (0..methods_count)
+            .map(|_| {
+                let MemberHeader {
+                    access_flags,
+                    name,
+                    descriptor,
+                    span,
+                    attributes,
+                } = MemberHeader::skim(reader, ctx)?;
+                let method = LazyMethod {
+                    access_flags: ctx.flags("MethodAccessFlags", access_flags)?,
+                    name,
+                    descriptor: descriptor.parse()?,
+                    span,
+                };
+                Ok((method, attributes))
+            })
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
+            .unzip();
+        let attributes = skim_attributes(reader)?;
+
+        // Make sure there is no extra data in the reader
+        if position(reader) != bytes.len() {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "Extra data"))?;
+        }
+        // The dynamic constants in the members refer to the `BootstrapMethods` attribute.
+        let bootstrap_methods = bootstrap_method_entries(bytes, &attributes, &context)?;
+        context
+            .constant_pool
+            .set_bootstrap_methods(bootstrap_methods);
+        Ok(Self {
+            version,
+            access_flags,
+            binary_name: binary_name.to_string(),
+            super_class,
+            interfaces,
+            fields,
+            methods,
+            attributes,
+            field_attributes,
+            method_attributes,
+            bytes,
+            context,
+        })
+    }
+
+    /// Returns the constant pool of the class.
+    #[must_use]
+    pub const fn constant_pool(&self) -> &ConstantPool {
+        &self.context.constant_pool
+    }
+
+    /// Fully parses the method at `method_index` in [`LazyClass::methods`].
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn parse_method(&self, method_index: usize) -> Result<Method, Error> {
+        let LazyMethod { span, .. } = self
+            .methods
+            .get(method_index)
+            .ok_or(Error::Other("Method index out of range"))?;
+        Method::from_bytes(&self.bytes[span.clone()], span.start as u64, &self.context)
+    }
+
+    /// Parses the body of the method at `method_index` in [`LazyClass::methods`].
+    /// Returns [`None`] if the method is `abstract` or `native`.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn parse_body(&self, method_index: usize) -> Result<Option<MethodBody>, Error> {
+        self.parse_method(method_index).map(|it| it.body)
+    }
+
+    /// Returns the raw bytes of the attribute of the class named `name`, excluding the name and
+    /// the length of the attribute.
+    #[must_use]
+    pub fn attribute(&self, name: &str) -> Option<&'a [u8]> {
+        self.find_attribute(&self.attributes, name)
+    }
+
+    /// Returns the raw bytes of the attribute named `name` of the field at `field_index` in
+    /// [`LazyClass::fields`], excluding the name and the length of the attribute.
+    #[must_use]
+    pub fn field_attribute(&self, field_index: usize, name: &str) -> Option<&'a [u8]> {
+        self.find_attribute(self.field_attributes.get(field_index)?, name)
+    }
+
+    /// Returns the raw bytes of the attribute named `name` of the method at `method_index` in
+    /// [`LazyClass::methods`], excluding the name and the length of the attribute.
+    #[must_use]
+    pub fn method_attribute(&self, method_index: usize, name: &str) -> Option<&'a [u8]> {
+        self.find_attribute(self.method_attributes.get(method_index)?, name)
+    }
+
+    /// Returns the annotations on the class.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn annotations(&self) -> Result<&Annotations, Error> {
+        self.decode_annotations(&self.attributes)
+    }
+
+    /// Returns the annotations on the field at `field_index` in [`LazyClass::fields`].
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn field_annotations(&self, field_index: usize) -> Result<&Annotations, Error> {
+        let attributes = self
+            .field_attributes
+            .get(field_index)
+            .ok_or(Error::Other("Field index out of range"))?;
+        self.decode_annotations(attributes)
+    }
+
+    /// Returns the annotations on the method at `method_index` in [`LazyClass::methods`].
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn method_annotations(&self, method_index: usize) -> Result<&Annotations, Error> {
+        self.decode_annotations(self.method_attributes(method_index)?)
+    }
+
+    /// Returns the local variable table of the method at `method_index` in
+    /// [`LazyClass::methods`] without parsing the instructions.
+    /// Returns [`None`] if the method has no body or the body has no local variable table.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn local_variable_table(
+        &self,
+        method_index: usize,
+    ) -> Result<Option<&LocalVariableTable>, Error> {
+        let attributes = self.method_attributes(method_index)?;
+        get_or_try_init(&attributes.local_variable_table, || {
+            let mut table = None;
+            for span in self.code_attributes(
+                attributes,
+                &["LocalVariableTable", "LocalVariableTypeTable"],
+            )? {
+                let attribute = self.decode(&span)?;
+                merge_local_variables(table.get_or_insert_with(Default::default), attribute)?;
+            }
+            Ok(table)
+        })
+        .map(Option::as_ref)
+    }
+
+    /// Returns the stack map table of the method at `method_index` in [`LazyClass::methods`]
+    /// without parsing the instructions.
+    /// Returns [`None`] if the method has no body or the body has no stack map table.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn stack_map_table(&self, method_index: usize) -> Result<Option<&[StackMapFrame]>, Error> {
+        let attributes = self.method_attributes(method_index)?;
+        get_or_try_init(&attributes.stack_map_table, || {
+            let mut frames = None;
+            for span in self.code_attributes(attributes, &["StackMapTable"])? {
+                if let Attribute::StackMapTable(it) = self.decode(&span)? {
+                    frames = Some(it);
+                }
+            }
+            Ok(frames)
+        })
+        .map(Option::as_deref)
+    }
+
+    fn method_attributes(&self, method_index: usize) -> Result<&LazyAttributes, Error> {
+        self.method_attributes
+            .get(method_index)
+            .ok_or(Error::Other("Method index out of range"))
+    }
+
+    fn attribute_name(&self, name_index: u16) -> Option<&str> {
+        self.context.constant_pool.get_str(name_index).ok()
+    }
+
+    fn find_attribute(&self, attributes: &LazyAttributes, name: &str) -> Option<&'a [u8]> {
+        attributes
+            .spans
+            .iter()
+            .find(|(name_index, _)| self.attribute_name(*name_index) == Some(name))
+            // Skips `attribute_name_index` and `attribute_length`.
+            .map(|(_, span)| &self.bytes[span.start + 6..span.end])
+    }
+
+    fn decode(&self, span: &Range<usize>) -> Result<Attribute, Error> {
+        Attribute::from_bytes(&self.bytes[span.clone()], span.start as u64, &self.context)
+    }
+
+    fn decode_annotations<'s>(
+        &'s self,
+        attributes: &'s LazyAttributes,
+    ) -> Result<&'s Annotations, Error> {
+        get_or_try_init(&attributes.annotations, || {
+            let mut annotations = Annotations::default();
+            for (name_index, span) in &attributes.spans {
+                if !matches!(
+                    self.attribute_name(*name_index),
+                    Some("RuntimeVisibleAnnotations" | "RuntimeInvisibleAnnotations")
+                ) {
+                    continue;
+                }
+                match self.decode(span)? {
+                    Attribute::RuntimeVisibleAnnotations(it) => annotations.runtime_visible = it,
+                    Attribute::RuntimeInvisibleAnnotations(it) => {
+                        annotations.runtime_invisible = it;
+                    }
+                    _ => {}
+                }
+            }
+            Ok(annotations)
+        })
+    }
+
+    /// Returns the spans of the attributes named in `names` in the `Code` attribute of a
+    /// method, skipping the instructions and the exception table.
+    fn code_attributes(
+        &self,
+        attributes: &LazyAttributes,
+        names: &[&str],
+    ) -> Result<Vec<Range<usize>>, Error> {
+        let Some((_, code)) = attributes
+            .spans
+            .iter()
+            .find(|(name_index, _)| self.attribute_name(*name_index) == Some("Code"))
+        else {
+            return Ok(Vec::new());
+        };
+        let reader = &mut Cursor::new(&self.bytes[..code.end]);
+        // Skips `attribute_name_index`, `attribute_length`, `max_stack`, and `max_locals`.
+        reader.set_position(code.start as u64 + 10);
+        let code_length: u32 = reader.read_value()?;
+        reader.set_position(reader.position() + u64::from(code_length));
+        let exception_table_length: u16 = reader.read_value()?;
+        reader.set_position(reader.position() + 8 * u64::from(exception_table_length));
+        let spans = skim_attributes(reader)?
+            .spans
+            .into_iter()
+            .filter(|(name_index, _)| {
+                self.attribute_name(*name_index)
+                    .is_some_and(|it| names.contains(&it))
+            })
+            .map(|(_, span)| span)
+            .collect();
+        Ok(spans)
+    }
+}
+
+/// Gets the value in `cell`, or initializes it with `init` if `init` succeeds.
+fn get_or_try_init<T>(
+    cell: &OnceLock<T>,
+    init: impl FnOnce() -> Result<T, Error>,
+) -> Result<&T, Error> {
+    if let Some(it) = cell.get() {
+        return Ok(it);
+    }
+    let value = init()?;
+    Ok(cell.get_or_init(|| value))
+}
+
+/// The leading part of a `field_info` or `method_info` structure.
+struct MemberHeader {
+    access_flags: u16,
+    name: String,
+    descriptor: String,
+    span: Range<usize>,
+    attributes: LazyAttributes,
+}
+
+impl MemberHeader {
+    /// Reads the header and skips the attributes of the member.
+    fn skim(reader: &mut Cursor<&[u8]>, ctx: &Context) -> Result<Self, Error> {
+        let start = position(reader);
+        let access_flags = reader.read_value()?;
+        let name_index = reader.read_value()?;
+        let descriptor_index = reader.read_value()?;
+        let attributes = skim_attributes(reader)?;
+        Ok(Self {
+            access_flags,
+            name: ctx.constant_pool.get_str(name_index)?.to_owned(),
+            descriptor: ctx.constant_pool.get_str(descriptor_index)?.to_owned(),
+            span: start..position(reader),
+            attributes,
+        })
+    }
+}
+
+/// Skips an attribute table and records the locations of the attributes.
+fn skim_attributes(reader: &mut Cursor<&[u8]>) -> io::Result<LazyAttributes> {
+    let attributes_count: u16 = reader.read_value()?;
+    let spans = (0..attributes_count)
+        .map(|_| {
+            let start = position(reader);
+            let name_index: u16 = reader.read_value()?;
+            let attribute_length: u32 = reader.read_value()?;
+            let end = reader.position() + u64::from(attribute_length);
+            if end > reader.get_ref().len() as u64 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            reader.set_position(end);
+            Ok((name_index, start..position(reader)))
+        })
+        .collect::<io::Result<_>>()?;
+    Ok(LazyAttributes {
+        spans,
+        ..LazyAttributes::default()
+    })
+}
+
+/// Reads the entries of the `BootstrapMethods` attribute among `attributes`, if any.
+fn bootstrap_method_entries(
+    bytes: &[u8],
+    attributes: &LazyAttributes,
+    context: &Context,
+) -> io::Result<Vec<BootstrapMethodEntry>> {
+    attributes
+        .spans
+        .iter()
+        .find(|(name_index, _)| {
+            context.constant_pool.get_str(*name_index).ok() == Some("BootstrapMethods")
+        })
+        .map_or(Ok(Vec::new()), |(_, span)| {
+            BootstrapMethodEntry::read_all(&bytes[span.start + 6..span.end])
+        })
+}
+
+fn position(reader: &Cursor<&[u8]>) -> usize {
+    // The position never exceeds the length of the slice.
+    usize::try_from(reader.position()).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        jvm::{
+            code::{Instruction, LocalVariableId, LocalVariableTableEntry, VerificationType},
+            interop::jasmin,
+            Field,
+        },
+        tests::annotation,
+        types::field_type::PrimitiveType,
+    };
+
+    fn class_bytes() -> Vec<u8> {
+        let mut bytes = vec![
+            0xCA, 0xFE, 0xBA, 0xBE, // Magic
+            0x00, 0x00, 0x00, 0x34, // Version 52.0
+            0x00, 0x0A, // Constant pool count 9+1
+            0x07, 0x00, 0x02, // #1: Class #2
+            0x01, 0x00, 0x03, b'F', b'o', b'o', // #2: Utf8 "Foo"
+            0x07, 0x00, 0x04, // #3: Class #4
+        ];
+        for string in ["java/lang/Object", "f", "I", "run", "()V", "Code"] {
+            bytes.push(0x01);
+            bytes.extend_from_slice(&u16::try_from(string.len()).unwrap().to_be_bytes());
+            bytes.extend_from_slice(string.as_bytes());
+        }
+        bytes.extend_from_slice(&[
+            0x00, 0x21, // Access flags: public super
+            0x00, 0x01, // This class
+            0x00, 0x03, // Super class
+            0x00, 0x00, // Interfaces count
+            0x00, 0x01, // Fields count
+            0x00, 0x01, 0x00, 0x05, 0x00, 0x06, 0x00, 0x00, // public int f
+            0x00, 0x01, // Methods count
+            0x00, 0x01, 0x00, 0x07, 0x00, 0x08, 0x00, 0x01, // public void run()
+            0x00, 0x09, 0x00, 0x00, 0x00, 0x0D, // Code attribute of length 13
+            0x00, 0x00, 0x00, 0x01, // Max stack and max locals
+            0x00, 0x00, 0x00, 0x01, 0xB1, // Code: return
+            0x00, 0x00, 0x00, 0x00, // Exception table and attributes count
+            0x00, 0x00, // Attributes count
+        ]);
+        bytes
+    }
+
+    #[test]
+    fn parse_signatures() {
+        let bytes = class_bytes();
+        let class = LazyClass::parse(&bytes).unwrap();
+        assert_eq!(class.binary_name, "Foo");
+        assert_eq!(class.super_class, Some(ClassRef::new("java/lang/Object")));
+        assert_eq!(class.fields[0].name, "f");
+        assert_eq!(class.fields[0].field_type.descriptor(), "I");
+        assert_eq!(class.methods[0].name, "run");
+        assert_eq!(class.methods[0].descriptor, "()V".parse().unwrap());
+    }
+
+    #[test]
+    fn parse_body_on_demand() {
+        let bytes = class_bytes();
+        let class = LazyClass::parse(&bytes).unwrap();
+        let body = class.parse_body(0).unwrap().unwrap();
+        assert_eq!(
+            body.instructions
+                .into_iter()
+                .map(|(_, it)| it)
+                .collect::<Vec<_>>(),
+            vec![Instruction::Return]
+        );
+        assert!(class.parse_body(1).is_err());
+    }
+
+    #[test]
+    fn locate_errors_in_body() {
+        let mut bytes = class_bytes();
+        // Replaces `return` with an undefined opcode.
+        let return_index = bytes.iter().rposition(|&it| it == 0xB1).unwrap();
+        bytes[return_index] = 0xCB;
+        let class = LazyClass::parse(&bytes).unwrap();
+        let error = class.parse_body(0).unwrap_err();
+        let code_end = return_index + 5;
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Unexpected opcode 0xcb at offset {code_end:#x} in Code attribute of Foo.run()V"
+            )
+        );
+    }
+
+    fn annotated_class_bytes() -> Vec<u8> {
+        let mut bytes = vec![
+            0xCA, 0xFE, 0xBA, 0xBE, // Magic
+            0x00, 0x00, 0x00, 0x34, // Version 52.0
+            0x00, 0x0D, // Constant pool count 12+1
+            0x07, 0x00, 0x02, // #1: Class #2
+            0x01, 0x00, 0x03, b'F', b'o', b'o', // #2: Utf8 "Foo"
+            0x07, 0x00, 0x04, // #3: Class #4
+        ];
+        for string in [
+            "java/lang/Object",
+            "run",
+            "(I)V",
+            "Code",
+            "LocalVariableTable",
+            "x",
+            "I",
+            "RuntimeVisibleAnnotations",
+            "LBar;",
+        ] {
+            bytes.push(0x01);
+            bytes.extend_from_slice(&u16::try_from(string.len()).unwrap().to_be_bytes());
+            bytes.extend_from_slice(string.as_bytes());
+        }
+        bytes.extend_from_slice(&[
+            0x00, 0x21, // Access flags: public super
+            0x00, 0x01, // This class
+            0x00, 0x03, // Super class
+            0x00, 0x00, // Interfaces count
+            0x00, 0x00, // Fields count
+            0x00, 0x01, // Methods count
+            0x00, 0x09, 0x00, 0x05, 0x00, 0x06, 0x00, 0x02, // public static void run(int)
+            0x00, 0x0B, 0x00, 0x00, 0x00, 0x06, // RuntimeVisibleAnnotations of length 6
+            0x00, 0x01, 0x00, 0x0C, 0x00, 0x00, // @Bar
+            0x00, 0x07, 0x00, 0x00, 0x00, 0x1F, // Code attribute of length 31
+            0x00, 0x00, 0x00, 0x01, // Max stack and max locals
+            0x00, 0x00, 0x00, 0x01, 0xB1, // Code: return
+            0x00, 0x00, 0x00, 0x01, // Exception table and attributes count
+            0x00, 0x08, 0x00, 0x00, 0x00, 0x0C, // LocalVariableTable of length 12
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x09, 0x00, 0x0A, 0x00, 0x00, // int x
+            0x00, 0x00, // Attributes count
+        ]);
+        bytes
+    }
+
+    #[test]
+    fn decode_attributes_on_demand() {
+        let bytes = annotated_class_bytes();
+        let class = LazyClass::parse(&bytes).unwrap();
+        assert_eq!(
+            class.method_attribute(0, "RuntimeVisibleAnnotations"),
+            Some([0x00, 0x01, 0x00, 0x0C, 0x00, 0x00].as_slice())
+        );
+        let annotations = class.method_annotations(0).unwrap();
+        assert_eq!(
+            annotations.runtime_visible[0].annotation_type,
+            FieldType::Object(ClassRef::new("Bar"))
+        );
+        assert!(annotations.runtime_invisible.is_empty());
+        assert!(std::ptr::eq(
+            annotations,
+            class.method_annotations(0).unwrap()
+        ));
+        assert_eq!(class.annotations().unwrap(), &Annotations::default());
+
+        let table = class.local_variable_table(0).unwrap().unwrap();
+        let (_, entry) = table.iter().next().unwrap();
+        assert_eq!(entry.name.as_deref(), Some("x"));
+        assert_eq!(entry.var_type, Some(FieldType::Base(PrimitiveType::Int)));
+        assert!(class.stack_map_table(0).unwrap().is_none());
+        assert!(class.local_variable_table(1).is_err());
+    }
+
+    /// Writes a class with every kind of attribute that [`LazyClass`] decodes on demand.
+    fn attributed_class_bytes() -> Vec<u8> {
+        let mut class = jasmin::read(
+            r"
+            .bytecode 52.0
+            .class public super Counter
+            .super java/lang/Object
+            .field private limit I
+
+            .method public static count(I)I
+                .limit stack 2
+                .limit locals 2
+                iconst_0
+                istore_1
+            Loop:
+                iload_1
+                iload_0
+                if_icmpge Done
+                iinc 1 1
+                goto Loop
+            Done:
+                iload_1
+                ireturn
+            .end method
+
+            .method public native stop()V
+            .end method
+            ",
+        )
+        .unwrap();
+        class
+            .runtime_visible_annotations
+            .push(annotation("Visible"));
+        class
+            .runtime_invisible_annotations
+            .push(annotation("Invisible"));
+        class
+            .free_attributes
+            .push(("Note".to_owned(), vec![1, 2, 3]));
+        let field = &mut class.fields[0];
+        field
+            .runtime_invisible_annotations
+            .push(annotation("Nullable"));
+        field.free_attributes.push(("Note".to_owned(), vec![4]));
+        let method = &mut class.methods[0];
+        method.runtime_visible_annotations.push(annotation("Pure"));
+        let body = method.body.as_mut().unwrap();
+        body.local_variable_table = Some(
+            [
+                (
+                    LocalVariableId {
+                        effective_range: 0.into()..15.into(),
+                        index: 0,
+                    },
+                    LocalVariableTableEntry {
+                        name: Some("limit".to_owned()),
+                        var_type: Some(FieldType::Base(PrimitiveType::Int)),
+                        signature: None,
+                    },
+                ),
+                (
+                    LocalVariableId {
+                        effective_range: 2.into()..15.into(),
+                        index: 1,
+                    },
+                    LocalVariableTableEntry {
+                        name: Some("count".to_owned()),
+                        var_type: Some(FieldType::Base(PrimitiveType::Int)),
+                        signature: Some("I".to_owned()),
+                    },
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        body.stack_map_table = Some(vec![
+            StackMapFrame::AppendFrame {
+                offset_delta: 2,
+                locals: vec![VerificationType::IntegerVariable],
+            },
+            StackMapFrame::SameFrame { offset_delta: 10 },
+        ]);
+        class.methods[1]
+            .runtime_invisible_annotations
+            .push(annotation("Blocking"));
+        class.to_bytes().unwrap()
+    }
+
+    /// Asserts that the attributes decoded by [`LazyClass`] are the same as those parsed eagerly.
+    fn assert_agree(lazy: &LazyClass<'_>, class: &Class) {
+        assert_eq!(
+            lazy.annotations().unwrap(),
+            &Annotations {
+                runtime_visible: class.runtime_visible_annotations.clone(),
+                runtime_invisible: class.runtime_invisible_annotations.clone(),
+            }
+        );
+        for (name, bytes) in &class.free_attributes {
+            assert_eq!(lazy.attribute(name), Some(bytes.as_slice()));
+        }
+        assert_eq!(lazy.fields.len(), class.fields.len());
+        for (index, field) in class.fields.iter().enumerate() {
+            let Field {
+                access_flags,
+                name,
+                field_type,
+                ..
+            } = field;
+            assert_eq!(
+                lazy.fields[index],
+                LazyField {
+                    access_flags: *access_flags,
+                    name: name.clone(),
+                    field_type: field_type.clone(),
+                }
+            );
+            assert_eq!(
+                lazy.field_annotations(index).unwrap(),
+                &Annotations {
+                    runtime_visible: field.runtime_visible_annotations.clone(),
+                    runtime_invisible: field.runtime_invisible_annotations.clone(),
+                }
+            );
+            for (name, bytes) in &field.free_attributes {
+                assert_eq!(lazy.field_attribute(index, name), Some(bytes.as_slice()));
+            }
+        }
+        assert_eq!(lazy.methods.len(), class.methods.len());
+        for (index, method) in class.methods.iter().enumerate() {
+            assert_eq!(lazy.methods[index].access_flags, method.access_flags);
+            assert_eq!(lazy.methods[index].name, method.name);
+            assert_eq!(lazy.methods[index].descriptor, method.descriptor);
+            assert_eq!(
+                lazy.method_annotations(index).unwrap(),
+                &Annotations {
+                    runtime_visible: method.runtime_visible_annotations.clone(),
+                    runtime_invisible: method.runtime_invisible_annotations.clone(),
+                }
+            );
+            let body = method.body.as_ref();
+            // The tables are compared by their debug representations as they are not comparable.
+            assert_eq!(
+                format!("{:?}", lazy.local_variable_table(index).unwrap()),
+                format!("{:?}", body.and_then(|it| it.local_variable_table.as_ref()))
+            );
+            assert_eq!(
+                format!("{:?}", lazy.stack_map_table(index).unwrap()),
+                format!("{:?}", body.and_then(|it| it.stack_map_table.as_deref()))
+            );
+            assert_eq!(
+                format!("{:?}", lazy.parse_method(index).unwrap()),
+                format!("{method:?}")
+            );
+        }
+    }
+
+    #[test]
+    fn agree_with_eager_parsing() {
+        let bytes = attributed_class_bytes();
+        let lazy = LazyClass::parse(&bytes).unwrap();
+        let class = Class::from_bytes(&bytes).unwrap();
+        // Makes sure that every kind of attribute is compared.
+        let body = class.methods[0].body.as_ref().unwrap();
+        let table = body.local_variable_table.as_ref().unwrap();
+        assert!(table.iter().any(|(_, it)| it.signature.is_some()));
+        assert_eq!(body.stack_map_table.as_ref().map(Vec::len), Some(2));
+        assert!(class.methods[1].body.is_none());
+        assert_agree(&lazy, &class);
+        assert_eq!(lazy.annotations().unwrap().runtime_invisible.len(), 1);
+        assert_eq!(
+            lazy.field_annotations(0).unwrap().runtime_invisible.len(),
+            1
+        );
+        assert_eq!(lazy.method_annotations(0).unwrap().runtime_visible.len(), 1);
+        assert_eq!(
+            lazy.method_annotations(1).unwrap().runtime_invisible.len(),
+            1
+        );
+    }
+
+    #[test]
+    fn reject_truncated_attribute() {
+        let mut bytes = class_bytes();
+        bytes.truncate(bytes.len() - 8);
+        assert!(LazyClass::parse(&bytes).is_err());
+    }
+}
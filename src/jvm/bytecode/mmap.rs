@@ -0,0 +1,101 @@
+//! Memory-mapped class files.
+//! Mapping the class files avoids copying them into memory when analyzing a large number of
+//! classes in batch.
+
+use std::{fs::File, io, ops::Deref, os::fd::AsRawFd, path::Path, ptr, slice};
+
+/// A read-only memory mapping of a file.
+/// It dereferences to the bytes of the file, which can be parsed with
+/// [`Class::from_bytes`](crate::jvm::Class::from_bytes) or
+/// [`LazyClass::parse`](super::lazy::LazyClass::parse).
+#[derive(Debug)]
+pub struct MappedFile {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+// SAFETY: The mapping belongs to the process rather than to the thread creating it, and
+// `MappedFile` is its only owner, so it can be read and unmapped from any thread.
+unsafe impl Send for MappedFile {}
+// SAFETY: A shared `MappedFile` only gives out `&[u8]` to the mapping, which is never written
+// through `MappedFile`, and the contract of `MappedFile::open` rules out other writes.
+unsafe impl Sync for MappedFile {}
+
+impl MappedFile {
+    /// Maps the file at `path` into memory.
+    /// # Safety
+    /// The file must not be modified or truncated, by this or any other process, while it is
+    /// mapped. Otherwise, the bytes behind the returned mapping may change while borrowed, or
+    /// reading them may raise `SIGBUS`.
+    /// # Errors
+    /// Returns an [`io::Error`] if the file cannot be opened or mapped.
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "The file is too large"))?;
+        if len == 0 {
+            // Empty mappings are rejected by `mmap`.
+            return Ok(Self {
+                ptr: ptr::null_mut(),
+                len,
+            });
+        }
+        // SAFETY: The arguments describe a private read-only mapping of the whole file, and the
+        // result is checked against `MAP_FAILED`.
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { ptr, len })
+    }
+}
+
+impl Deref for MappedFile {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        if self.len == 0 {
+            return &[];
+        }
+        // SAFETY: `ptr` points to a readable mapping of `len` bytes that lives as long as `self`.
+        unsafe { slice::from_raw_parts(self.ptr.cast(), self.len) }
+    }
+}
+
+impl Drop for MappedFile {
+    fn drop(&mut self) {
+        if self.len != 0 {
+            // SAFETY: `ptr` and `len` describe a mapping created by `mmap` that is not yet unmapped.
+            unsafe {
+                libc::munmap(self.ptr, self.len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jvm::Class, tests::empty_class_with_version};
+
+    #[test]
+    fn parse_mapped_file() {
+        let path = std::env::temp_dir().join(format!("mokapot-mmap-{}.class", std::process::id()));
+        std::fs::write(&path, empty_class_with_version(52, 0)).unwrap();
+        // SAFETY: Nothing else writes to the file, which is only removed after being unmapped.
+        let mapped = unsafe { MappedFile::open(&path) }.unwrap();
+        let class = Class::from_bytes(&mapped).unwrap();
+        assert_eq!(class.binary_name, "HelloWorld");
+        drop(mapped);
+        std::fs::remove_file(path).unwrap();
+    }
+}
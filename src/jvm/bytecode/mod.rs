@@ -0,0 +1,5 @@
+//! Direct access to the bytes of class files.
+
+pub mod lazy;
+#[cfg(all(feature = "memmap", unix))]
+pub mod mmap;
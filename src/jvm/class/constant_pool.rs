@@ -1,12 +1,21 @@
 //! Constant pool in a JVM class file.
 
-use std::io::{self, Read};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    io::{self, Read, Write},
+};
 
 use crate::macros::see_jvm_spec;
 
-use crate::jvm::JavaString;
+use crate::{
+    jvm::{
+        references::{ClassRef, FieldRef, MethodRef, ModuleRef, PackageRef},
+        ConstantValue, JavaString,
+    },
+    types::method_descriptor::MethodDescriptor,
+};
 
-use super::ConstantPool;
+use super::{BootstrapMethod, ConstantPool, MethodHandle};
 
 #[derive(Debug, Clone)]
 pub(super) enum Slot {
@@ -37,7 +46,40 @@ impl ConstantPool {
                 inner.push(Slot::Entry(entry));
             }
         }
-        Ok(Self { inner })
+        Ok(Self {
+            inner,
+            bootstrap_methods: Vec::new(),
+        })
+    }
+
+    /// Sets the entries of the `BootstrapMethods` attribute of the class, which are required to
+    /// resolve the `CONSTANT_Dynamic` entries.
+    pub(crate) fn set_bootstrap_methods(&mut self, bootstrap_methods: Vec<BootstrapMethodEntry>) {
+        self.bootstrap_methods = bootstrap_methods;
+    }
+
+    /// Gets the entries of the `BootstrapMethods` attribute that the `CONSTANT_Dynamic` entries
+    /// refer to.
+    /// A constant pool created by [`ConstantPool::from_reader`] has no such entries, since the
+    /// attribute comes after the constant pool in a class file.
+    #[must_use]
+    pub fn bootstrap_methods(&self) -> &[BootstrapMethodEntry] {
+        &self.bootstrap_methods
+    }
+
+    /// Replaces the strings that are not valid UTF-8 with their lossy decoding.
+    /// Returns the indices of the replaced entries.
+    pub(crate) fn repair_utf8(&mut self) -> Vec<u16> {
+        let mut repaired = Vec::new();
+        for (idx, slot) in (0..=u16::MAX).zip(&mut self.inner) {
+            if let Slot::Entry(Entry::Utf8(string)) = slot {
+                if let JavaString::InvalidUtf8(bytes) = string {
+                    *string = JavaString::Utf8(String::from_utf8_lossy(bytes).into_owned());
+                    repaired.push(idx);
+                }
+            }
+        }
+        repaired
     }
 
     /// Gets the constant pool entry at the given index.
@@ -49,6 +91,674 @@ impl ConstantPool {
             _ => Err(BadConstantPoolIndex(index)),
         }
     }
+
+    /// Creates a builder for an empty constant pool.
+    #[must_use]
+    pub fn builder() -> ConstantPoolBuilder {
+        ConstantPoolBuilder::default()
+    }
+
+    /// Gets the `constant_pool_count` of the constant pool, i.e., the maximum index of entries
+    /// plus one.
+    #[must_use]
+    pub fn constant_pool_count(&self) -> u16 {
+        // The builder and the parser never create more than `u16::MAX` slots.
+        u16::try_from(self.inner.len()).unwrap_or(u16::MAX)
+    }
+
+    /// Returns an iterator over the entries and their indices.
+    pub fn iter(&self) -> impl Iterator<Item = (u16, &Entry)> {
+        (0..=u16::MAX)
+            .zip(&self.inner)
+            .filter_map(|(idx, slot)| match slot {
+                Slot::Entry(entry) => Some((idx, entry)),
+                Slot::Padding => None,
+            })
+    }
+
+    /// Finds the index of the first entry equal to `entry`.
+    #[must_use]
+    pub fn index_of(&self, entry: &Entry) -> Option<u16> {
+        let key = entry.to_bytes();
+        self.iter()
+            .find(|(_, it)| it.to_bytes() == key)
+            .map(|(idx, _)| idx)
+    }
+
+    /// Reports the statistics of the entries.
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        let mut statistics = Statistics {
+            slots: self.inner.len().saturating_sub(1),
+            ..Statistics::default()
+        };
+        for (_, entry) in self.iter() {
+            *statistics
+                .entries_by_kind
+                .entry(entry.constant_kind())
+                .or_default() += 1;
+            statistics.size_in_bytes += entry.to_bytes().len();
+        }
+        statistics
+    }
+
+    /// Checks that the indices in the entries point to entries of the expected kinds.
+    ///
+    /// # Errors
+    /// See [`InvalidEntry`].
+    pub fn validate(&self) -> Result<(), InvalidEntry> {
+        for (at, entry) in self.iter() {
+            let expect = |index: u16, expected: &'static str, matches: fn(&Entry) -> bool| {
+                let referenced = self
+                    .get_entry(index)
+                    .map_err(|_| InvalidEntry::BadIndex { at, index })?;
+                if matches(referenced) {
+                    Ok(())
+                } else {
+                    Err(InvalidEntry::MismatchedType {
+                        at,
+                        index,
+                        expected,
+                        found: referenced.constant_kind(),
+                    })
+                }
+            };
+            let is_utf8 = |it: &Entry| matches!(it, Entry::Utf8(_));
+            let is_class = |it: &Entry| matches!(it, Entry::Class { .. });
+            let is_name_and_type = |it: &Entry| matches!(it, Entry::NameAndType { .. });
+            match entry {
+                Entry::Utf8(_) => {
+                    if entry.to_bytes().len() > usize::from(u16::MAX) + 3 {
+                        return Err(InvalidEntry::StringTooLong { at });
+                    }
+                }
+                Entry::Integer(_) | Entry::Float(_) | Entry::Long(_) | Entry::Double(_) => {}
+                &Entry::Class { name_index }
+                | &Entry::Module { name_index }
+                | &Entry::Package { name_index } => expect(name_index, "Utf8", is_utf8)?,
+                &Entry::String { string_index } => expect(string_index, "Utf8", is_utf8)?,
+                &Entry::MethodType { descriptor_index } => {
+                    expect(descriptor_index, "Utf8", is_utf8)?;
+                }
+                &Entry::FieldRef {
+                    class_index,
+                    name_and_type_index,
+                }
+                | &Entry::MethodRef {
+                    class_index,
+                    name_and_type_index,
+                }
+                | &Entry::InterfaceMethodRef {
+                    class_index,
+                    name_and_type_index,
+                } => {
+                    expect(class_index, "Class", is_class)?;
+                    expect(name_and_type_index, "NameAndType", is_name_and_type)?;
+                }
+                &Entry::NameAndType {
+                    name_index,
+                    descriptor_index,
+                } => {
+                    expect(name_index, "Utf8", is_utf8)?;
+                    expect(descriptor_index, "Utf8", is_utf8)?;
+                }
+                &Entry::MethodHandle {
+                    reference_kind,
+                    reference_index,
+                } => match reference_kind {
+                    1..=4 => expect(reference_index, "Fieldref", |it| {
+                        matches!(it, Entry::FieldRef { .. })
+                    })?,
+                    5 | 8 => expect(reference_index, "Methodref", |it| {
+                        matches!(it, Entry::MethodRef { .. })
+                    })?,
+                    6 | 7 => expect(reference_index, "Methodref | InterfaceMethodref", |it| {
+                        matches!(
+                            it,
+                            Entry::MethodRef { .. } | Entry::InterfaceMethodRef { .. }
+                        )
+                    })?,
+                    9 => expect(reference_index, "InterfaceMethodref", |it| {
+                        matches!(it, Entry::InterfaceMethodRef { .. })
+                    })?,
+                    _ => return Err(InvalidEntry::BadReferenceKind { at, reference_kind }),
+                },
+                &Entry::Dynamic {
+                    name_and_type_index,
+                    ..
+                }
+                | &Entry::InvokeDynamic {
+                    name_and_type_index,
+                    ..
+                } => expect(name_and_type_index, "NameAndType", is_name_and_type)?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the entries that are not reachable from `roots`, and renumbers the remaining
+    /// entries without changing their order.
+    /// `roots` are the indices referred to from outside of the constant pool, e.g., by the class
+    /// file structures, the instructions, the attributes, and the arguments of the bootstrap
+    /// methods.
+    /// The entries referred to by [`ConstantPool::bootstrap_methods`] are also kept, and the
+    /// bootstrap methods are updated with the new indices.
+    /// Returns the mapping from the old indices of the remaining entries to the new ones, which
+    /// the references from outside of the constant pool must be updated with.
+    /// [`Class::to_bytes_with_options`](crate::jvm::Class::to_bytes_with_options) does so when
+    /// writing a class with an existing constant pool.
+    ///
+    /// # Errors
+    /// - [`BadConstantPoolIndex`] if a root or an entry refers to an index that does not point to
+    ///   a valid entry. The constant pool is left unchanged in this case.
+    pub fn compact(
+        &mut self,
+        roots: impl IntoIterator<Item = u16>,
+    ) -> Result<BTreeMap<u16, u16>, BadConstantPoolIndex> {
+        let mut reachable = BTreeSet::new();
+        let mut worklist: Vec<_> = roots.into_iter().collect();
+        for entry in &self.bootstrap_methods {
+            worklist.push(entry.method_ref_index);
+            worklist.extend(&entry.argument_indices);
+        }
+        while let Some(index) = worklist.pop() {
+            let entry = self.get_entry(index)?;
+            if reachable.insert(index) {
+                worklist.extend(entry.referenced_indices());
+            }
+        }
+        Ok(self.renumber(reachable))
+    }
+
+    /// Moves the entries at `indices` to the front without changing their order, and renumbers
+    /// the other entries after them, e.g., to make the operands of `ldc` fit in a byte.
+    /// The indices that do not point to a valid entry are ignored.
+    /// Returns the mapping from the old indices to the new ones.
+    pub(crate) fn move_to_front(&mut self, indices: &BTreeSet<u16>) -> BTreeMap<u16, u16> {
+        let rest = self
+            .iter()
+            .map(|(index, _)| index)
+            .filter(|it| !indices.contains(it));
+        let order: Vec<_> = indices.iter().copied().chain(rest).collect();
+        self.renumber(order)
+    }
+
+    /// Rebuilds the constant pool with the entries at `order` in that order, and updates the
+    /// references in the entries and the bootstrap methods, which must point to the entries kept.
+    fn renumber(&mut self, order: impl IntoIterator<Item = u16>) -> BTreeMap<u16, u16> {
+        let mut index_mapping = BTreeMap::new();
+        let mut inner = vec![Slot::Padding];
+        for old_index in order {
+            let Some(Slot::Entry(entry)) = self.inner.get(usize::from(old_index)) else {
+                continue;
+            };
+            // The renumbered constant pool is not larger than the original one.
+            let new_index = u16::try_from(inner.len()).unwrap_or(u16::MAX);
+            index_mapping.insert(old_index, new_index);
+            inner.push(Slot::Entry(entry.clone()));
+            if matches!(entry, Entry::Long(_) | Entry::Double(_)) {
+                inner.push(Slot::Padding);
+            }
+        }
+        for slot in &mut inner {
+            if let Slot::Entry(entry) = slot {
+                for index in entry.referenced_indices_mut() {
+                    *index = index_mapping[index];
+                }
+            }
+        }
+        for entry in &mut self.bootstrap_methods {
+            for index in
+                std::iter::once(&mut entry.method_ref_index).chain(&mut entry.argument_indices)
+            {
+                *index = index_mapping[index];
+            }
+        }
+        self.inner = inner;
+        index_mapping
+    }
+}
+
+/// An invalid entry found by [`ConstantPool::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvalidEntry {
+    /// The entry refers to an index that does not point to a valid entry.
+    #[error("The entry at {at} refers to the bad index {index}")]
+    BadIndex {
+        /// The index of the entry.
+        at: u16,
+        /// The index being referred to.
+        index: u16,
+    },
+    /// The entry refers to an entry of an unexpected kind.
+    #[error("The entry at {at} refers to {found} at {index}, expected {expected}")]
+    MismatchedType {
+        /// The index of the entry.
+        at: u16,
+        /// The index being referred to.
+        index: u16,
+        /// The expected kinds.
+        expected: &'static str,
+        /// The kind of the entry being referred to.
+        found: &'static str,
+    },
+    /// The method handle has an invalid reference kind.
+    #[error("The method handle at {at} has an invalid reference kind {reference_kind}")]
+    BadReferenceKind {
+        /// The index of the entry.
+        at: u16,
+        /// The reference kind.
+        reference_kind: u8,
+    },
+    /// The encoded string is longer than 65535 bytes.
+    #[error("The string at {at} is too long")]
+    StringTooLong {
+        /// The index of the entry.
+        at: u16,
+    },
+}
+
+/// An error when adding an entry to a [`ConstantPoolBuilder`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum BuildError {
+    /// There is no more index available for the entry.
+    #[error("The constant pool is full")]
+    Overflow,
+    /// The encoded string is longer than 65535 bytes.
+    #[error("The string is too long")]
+    StringTooLong,
+    /// The value cannot be stored in the constant pool.
+    #[error("The value cannot be stored in the constant pool: {0}")]
+    Unrepresentable(ConstantValue),
+}
+
+/// The statistics of the entries in a constant pool.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Statistics {
+    /// The number of entries of each kind (e.g., `CONSTANT_Utf8`).
+    pub entries_by_kind: BTreeMap<&'static str, usize>,
+    /// The number of slots used, including the unusable ones following `long` and `double`.
+    pub slots: usize,
+    /// The size of the entries in the class file in bytes.
+    pub size_in_bytes: usize,
+    /// The number of entries that are requested but already exist in the constant pool.
+    /// It is always zero for a parsed constant pool.
+    pub deduplicated: usize,
+}
+
+/// A builder of [`ConstantPool`]s that reuses existing entries.
+/// The `put_*` methods return the index of the (possibly existing) entry, creating the entries
+/// they refer to as needed.
+#[derive(Debug, Clone)]
+pub struct ConstantPoolBuilder {
+    constant_pool: ConstantPool,
+    indices: HashMap<Vec<u8>, u16>,
+    deduplicated: usize,
+    /// The indices returned by [`ConstantPoolBuilder::put_entry`], including the existing ones.
+    requested: BTreeSet<u16>,
+    /// The indices returned by [`ConstantPoolBuilder::put_ldc_operand`].
+    ldc_operands: BTreeSet<u16>,
+}
+
+impl Default for ConstantPoolBuilder {
+    fn default() -> Self {
+        Self {
+            constant_pool: ConstantPool {
+                inner: vec![Slot::Padding],
+                bootstrap_methods: Vec::new(),
+            },
+            indices: HashMap::new(),
+            deduplicated: 0,
+            requested: BTreeSet::new(),
+            ldc_operands: BTreeSet::new(),
+        }
+    }
+}
+
+impl From<ConstantPool> for ConstantPoolBuilder {
+    /// Creates a builder appending entries to an existing constant pool.
+    fn from(constant_pool: ConstantPool) -> Self {
+        let mut indices = HashMap::new();
+        for (idx, entry) in constant_pool.iter() {
+            indices.entry(entry.to_bytes()).or_insert(idx);
+        }
+        Self {
+            constant_pool,
+            indices,
+            deduplicated: 0,
+            requested: BTreeSet::new(),
+            ldc_operands: BTreeSet::new(),
+        }
+    }
+}
+
+impl ConstantPoolBuilder {
+    /// Adds an entry, or finds an equal existing one.
+    /// The indices in `entry` are not checked; see [`ConstantPool::validate`].
+    ///
+    /// # Errors
+    /// - [`BuildError::Overflow`] if the entry does not fit in the constant pool.
+    /// - [`BuildError::StringTooLong`] if the entry is a string longer than 65535 bytes.
+    pub fn put_entry(&mut self, entry: Entry) -> Result<u16, BuildError> {
+        let key = entry.to_bytes();
+        if let Entry::Utf8(_) = entry {
+            if key.len() > usize::from(u16::MAX) + 3 {
+                return Err(BuildError::StringTooLong);
+            }
+        }
+        if let Some(&idx) = self.indices.get(&key) {
+            self.deduplicated += 1;
+            self.requested.insert(idx);
+            return Ok(idx);
+        }
+        let inner = &mut self.constant_pool.inner;
+        let width = if matches!(entry, Entry::Long(_) | Entry::Double(_)) {
+            2
+        } else {
+            1
+        };
+        // `constant_pool_count` must fit in a `u16`.
+        if inner.len() + width > usize::from(u16::MAX) {
+            return Err(BuildError::Overflow);
+        }
+        let idx = u16::try_from(inner.len()).map_err(|_| BuildError::Overflow)?;
+        inner.push(Slot::Entry(entry));
+        if width == 2 {
+            inner.push(Slot::Padding);
+        }
+        self.indices.insert(key, idx);
+        self.requested.insert(idx);
+        Ok(idx)
+    }
+
+    /// Adds a `CONSTANT_Utf8` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_utf8(&mut self, value: impl Into<String>) -> Result<u16, BuildError> {
+        self.put_entry(Entry::Utf8(JavaString::Utf8(value.into())))
+    }
+
+    /// Adds a `CONSTANT_Integer` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_integer(&mut self, value: i32) -> Result<u16, BuildError> {
+        self.put_entry(Entry::Integer(value))
+    }
+
+    /// Adds a `CONSTANT_Float` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_float(&mut self, value: f32) -> Result<u16, BuildError> {
+        self.put_entry(Entry::Float(value))
+    }
+
+    /// Adds a `CONSTANT_Long` entry, which occupies two slots.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_long(&mut self, value: i64) -> Result<u16, BuildError> {
+        self.put_entry(Entry::Long(value))
+    }
+
+    /// Adds a `CONSTANT_Double` entry, which occupies two slots.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_double(&mut self, value: f64) -> Result<u16, BuildError> {
+        self.put_entry(Entry::Double(value))
+    }
+
+    /// Adds a `CONSTANT_String` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_string(&mut self, value: JavaString) -> Result<u16, BuildError> {
+        let string_index = self.put_entry(Entry::Utf8(value))?;
+        self.put_entry(Entry::String { string_index })
+    }
+
+    /// Adds a `CONSTANT_Class` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_class(&mut self, class_ref: &ClassRef) -> Result<u16, BuildError> {
+        let name_index = self.put_utf8(class_ref.binary_name.as_ref())?;
+        self.put_entry(Entry::Class { name_index })
+    }
+
+    /// Adds a `CONSTANT_NameAndType` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_name_and_type(&mut self, name: &str, descriptor: &str) -> Result<u16, BuildError> {
+        let name_index = self.put_utf8(name)?;
+        let descriptor_index = self.put_utf8(descriptor)?;
+        self.put_entry(Entry::NameAndType {
+            name_index,
+            descriptor_index,
+        })
+    }
+
+    /// Adds a `CONSTANT_Fieldref` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_field_ref(&mut self, field_ref: &FieldRef) -> Result<u16, BuildError> {
+        let class_index = self.put_class(&field_ref.owner)?;
+        let name_and_type_index =
+            self.put_name_and_type(&field_ref.name, &field_ref.field_type.descriptor())?;
+        self.put_entry(Entry::FieldRef {
+            class_index,
+            name_and_type_index,
+        })
+    }
+
+    /// Adds a `CONSTANT_Methodref` entry, or a `CONSTANT_InterfaceMethodref` entry if the owner
+    /// of the method is an interface.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_method_ref(
+        &mut self,
+        method_ref: &MethodRef,
+        is_interface: bool,
+    ) -> Result<u16, BuildError> {
+        let class_index = self.put_class(&method_ref.owner)?;
+        let name_and_type_index =
+            self.put_name_and_type(&method_ref.name, &method_ref.descriptor.descriptor())?;
+        if is_interface {
+            self.put_entry(Entry::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            })
+        } else {
+            self.put_entry(Entry::MethodRef {
+                class_index,
+                name_and_type_index,
+            })
+        }
+    }
+
+    /// Adds a `CONSTANT_MethodType` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_method_type(&mut self, descriptor: &MethodDescriptor) -> Result<u16, BuildError> {
+        let descriptor_index = self.put_utf8(descriptor.descriptor())?;
+        self.put_entry(Entry::MethodType { descriptor_index })
+    }
+
+    /// Adds a `CONSTANT_MethodHandle` entry.
+    /// Only the [`MethodHandle::RefInvokeInterface`] handles refer to interface methods.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_method_handle(&mut self, handle: &MethodHandle) -> Result<u16, BuildError> {
+        let (reference_kind, reference_index) = match handle {
+            MethodHandle::RefGetField(field) => (1, self.put_field_ref(field)?),
+            MethodHandle::RefGetStatic(field) => (2, self.put_field_ref(field)?),
+            MethodHandle::RefPutField(field) => (3, self.put_field_ref(field)?),
+            MethodHandle::RefPutStatic(field) => (4, self.put_field_ref(field)?),
+            MethodHandle::RefInvokeVirtual(method) => (5, self.put_method_ref(method, false)?),
+            MethodHandle::RefInvokeStatic(method) => (6, self.put_method_ref(method, false)?),
+            MethodHandle::RefInvokeSpecial(method) => (7, self.put_method_ref(method, false)?),
+            MethodHandle::RefNewInvokeSpecial(method) => (8, self.put_method_ref(method, false)?),
+            MethodHandle::RefInvokeInterface(method) => (9, self.put_method_ref(method, true)?),
+        };
+        self.put_entry(Entry::MethodHandle {
+            reference_kind,
+            reference_index,
+        })
+    }
+
+    /// Adds a `CONSTANT_InvokeDynamic` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_invoke_dynamic(
+        &mut self,
+        bootstrap_method_attr_index: u16,
+        name: &str,
+        descriptor: &MethodDescriptor,
+    ) -> Result<u16, BuildError> {
+        let name_and_type_index = self.put_name_and_type(name, &descriptor.descriptor())?;
+        self.put_entry(Entry::InvokeDynamic {
+            bootstrap_method_attr_index,
+            name_and_type_index,
+        })
+    }
+
+    /// Adds a `CONSTANT_Module` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_module(&mut self, module_ref: &ModuleRef) -> Result<u16, BuildError> {
+        let name_index = self.put_utf8(module_ref.name.as_str())?;
+        self.put_entry(Entry::Module { name_index })
+    }
+
+    /// Adds a `CONSTANT_Package` entry.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_package(&mut self, package_ref: &PackageRef) -> Result<u16, BuildError> {
+        let name_index = self.put_utf8(package_ref.binary_name.as_str())?;
+        self.put_entry(Entry::Package { name_index })
+    }
+
+    /// Adds the entry for a loadable constant value (e.g., the operand of `ldc`).
+    ///
+    /// # Errors
+    /// - [`BuildError::Unrepresentable`] if `value` is [`ConstantValue::Null`].
+    /// - See [`BuildError`] for other errors.
+    pub fn put_constant_value(&mut self, value: &ConstantValue) -> Result<u16, BuildError> {
+        match value {
+            ConstantValue::Null => Err(BuildError::Unrepresentable(value.clone())),
+            &ConstantValue::Integer(it) => self.put_integer(it),
+            &ConstantValue::Float(it) => self.put_float(it),
+            &ConstantValue::Long(it) => self.put_long(it),
+            &ConstantValue::Double(it) => self.put_double(it),
+            ConstantValue::String(it) => self.put_string(it.clone()),
+            ConstantValue::Class(it) => self.put_class(it),
+            ConstantValue::Handle(it) => self.put_method_handle(it),
+            ConstantValue::MethodType(it) => self.put_method_type(it),
+            ConstantValue::Dynamic(constant) => {
+                let bootstrap_method_attr_index = self.put_bootstrap_method(&constant.bootstrap)?;
+                let name_and_type_index =
+                    self.put_name_and_type(&constant.name, &constant.descriptor.descriptor())?;
+                self.put_entry(Entry::Dynamic {
+                    bootstrap_method_attr_index,
+                    name_and_type_index,
+                })
+            }
+        }
+    }
+
+    /// Adds the entry for the operand of an `ldc` instruction, which must be in the first 255
+    /// entries.
+    pub(crate) fn put_ldc_operand(&mut self, value: &ConstantValue) -> Result<u16, BuildError> {
+        let index = self.put_constant_value(value)?;
+        self.ldc_operands.insert(index);
+        Ok(index)
+    }
+
+    /// Adds an entry to the `BootstrapMethods` attribute, or finds an equal existing one, and
+    /// adds the entries of the method handle and the arguments.
+    /// The entries of the attribute are available in [`ConstantPool::bootstrap_methods`].
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    pub fn put_bootstrap_method(
+        &mut self,
+        bootstrap_method: &BootstrapMethod,
+    ) -> Result<u16, BuildError> {
+        let method_ref_index = self.put_method_handle(&bootstrap_method.method)?;
+        let argument_indices = bootstrap_method
+            .arguments
+            .iter()
+            .map(|it| self.put_constant_value(it))
+            .collect::<Result<_, _>>()?;
+        let entry = BootstrapMethodEntry {
+            method_ref_index,
+            argument_indices,
+        };
+        let bootstrap_methods = &mut self.constant_pool.bootstrap_methods;
+        if let Some(idx) = bootstrap_methods.iter().position(|it| it == &entry) {
+            self.deduplicated += 1;
+            return u16::try_from(idx).map_err(|_| BuildError::Overflow);
+        }
+        let idx = u16::try_from(bootstrap_methods.len()).map_err(|_| BuildError::Overflow)?;
+        bootstrap_methods.push(entry);
+        Ok(idx)
+    }
+
+    /// Reports the statistics of the entries added so far.
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        Statistics {
+            deduplicated: self.deduplicated,
+            ..self.constant_pool.statistics()
+        }
+    }
+
+    /// Gets the entries of the `BootstrapMethods` attribute added so far.
+    pub(crate) fn bootstrap_methods(&self) -> &[BootstrapMethodEntry] {
+        &self.constant_pool.bootstrap_methods
+    }
+
+    /// Gets the indices of the entries requested so far, either added or found existing.
+    /// Together with [`ConstantPool::bootstrap_methods`], they are the roots of
+    /// [`ConstantPool::compact`] for a class written with this builder.
+    pub(crate) fn requested_indices(&self) -> impl Iterator<Item = u16> + '_ {
+        self.requested.iter().copied()
+    }
+
+    /// Gets the indices of the operands of the `ldc` instructions added so far.
+    pub(crate) fn ldc_operands(&self) -> &BTreeSet<u16> {
+        &self.ldc_operands
+    }
+
+    /// Finishes building the constant pool.
+    #[must_use]
+    pub fn build(self) -> ConstantPool {
+        self.constant_pool
+    }
+}
+
+/// An entry in the `BootstrapMethods` attribute, whose method handle and arguments are indices
+/// in the [`ConstantPool`].
+#[doc = see_jvm_spec!(4, 7, 23)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootstrapMethodEntry {
+    /// The index of the method handle of the bootstrap method.
+    /// The entry at that index must be a [`Entry::MethodHandle`].
+    pub method_ref_index: u16,
+    /// The indices of the static arguments of the bootstrap method.
+    /// The entries at those indices must be loadable constants.
+    pub argument_indices: Vec<u16>,
 }
 
 /// An error when getting an entry from the constant pool with an invalid index.
@@ -204,6 +914,166 @@ impl Entry {
             Self::Package { .. } => "CONSTANT_Package",
         }
     }
+
+    /// Gets the tag of this constant pool entry.
+    #[must_use]
+    pub const fn tag(&self) -> u8 {
+        match self {
+            Self::Utf8(_) => 1,
+            Self::Integer(_) => 3,
+            Self::Float(_) => 4,
+            Self::Long(_) => 5,
+            Self::Double(_) => 6,
+            Self::Class { .. } => 7,
+            Self::String { .. } => 8,
+            Self::FieldRef { .. } => 9,
+            Self::MethodRef { .. } => 10,
+            Self::InterfaceMethodRef { .. } => 11,
+            Self::NameAndType { .. } => 12,
+            Self::MethodHandle { .. } => 15,
+            Self::MethodType { .. } => 16,
+            Self::Dynamic { .. } => 17,
+            Self::InvokeDynamic { .. } => 18,
+            Self::Module { .. } => 19,
+            Self::Package { .. } => 20,
+        }
+    }
+
+    /// Writes the entry in the class file format.
+    ///
+    /// # Errors
+    /// Returns an [`io::Error`] if writing fails or if the entry is a string longer than 65535
+    /// bytes.
+    pub fn write_to<W: Write + ?Sized>(&self, writer: &mut W) -> io::Result<()> {
+        let u16_pair = |first: u16, second: u16| {
+            let mut bytes = first.to_be_bytes().to_vec();
+            bytes.extend(second.to_be_bytes());
+            bytes
+        };
+        let content = match self {
+            Self::Utf8(value) => {
+                let encoded = match value {
+                    JavaString::Utf8(it) => cesu8::to_java_cesu8(it),
+                    JavaString::InvalidUtf8(it) => it.as_slice().into(),
+                };
+                let length = u16::try_from(encoded.len()).map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "The string is too long")
+                })?;
+                let mut bytes = length.to_be_bytes().to_vec();
+                bytes.extend_from_slice(&encoded);
+                bytes
+            }
+            Self::Integer(it) => it.to_be_bytes().to_vec(),
+            Self::Float(it) => it.to_be_bytes().to_vec(),
+            Self::Long(it) => it.to_be_bytes().to_vec(),
+            Self::Double(it) => it.to_be_bytes().to_vec(),
+            &Self::Class { name_index }
+            | &Self::Module { name_index }
+            | &Self::Package { name_index } => name_index.to_be_bytes().to_vec(),
+            &Self::String { string_index } => string_index.to_be_bytes().to_vec(),
+            &Self::MethodType { descriptor_index } => descriptor_index.to_be_bytes().to_vec(),
+            &Self::FieldRef {
+                class_index,
+                name_and_type_index,
+            }
+            | &Self::MethodRef {
+                class_index,
+                name_and_type_index,
+            }
+            | &Self::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            } => u16_pair(class_index, name_and_type_index),
+            &Self::NameAndType {
+                name_index,
+                descriptor_index,
+            } => u16_pair(name_index, descriptor_index),
+            &Self::MethodHandle {
+                reference_kind,
+                reference_index,
+            } => {
+                let mut bytes = vec![reference_kind];
+                bytes.extend(reference_index.to_be_bytes());
+                bytes
+            }
+            &Self::Dynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            }
+            | &Self::InvokeDynamic {
+                bootstrap_method_attr_index,
+                name_and_type_index,
+            } => u16_pair(bootstrap_method_attr_index, name_and_type_index),
+        };
+        writer.write_all(&[self.tag()])?;
+        writer.write_all(&content)
+    }
+
+    /// Gets the indices of the other entries in the constant pool that the entry refers to.
+    fn referenced_indices(&self) -> Vec<u16> {
+        self.clone()
+            .referenced_indices_mut()
+            .into_iter()
+            .map(|it| *it)
+            .collect()
+    }
+
+    fn referenced_indices_mut(&mut self) -> Vec<&mut u16> {
+        match self {
+            Self::Utf8(_) | Self::Integer(_) | Self::Float(_) | Self::Long(_) | Self::Double(_) => {
+                Vec::new()
+            }
+            Self::Class { name_index }
+            | Self::Module { name_index }
+            | Self::Package { name_index } => vec![name_index],
+            Self::String { string_index } => vec![string_index],
+            Self::MethodType { descriptor_index } => vec![descriptor_index],
+            Self::FieldRef {
+                class_index,
+                name_and_type_index,
+            }
+            | Self::MethodRef {
+                class_index,
+                name_and_type_index,
+            }
+            | Self::InterfaceMethodRef {
+                class_index,
+                name_and_type_index,
+            } => vec![class_index, name_and_type_index],
+            Self::NameAndType {
+                name_index,
+                descriptor_index,
+            } => vec![name_index, descriptor_index],
+            Self::MethodHandle {
+                reference_index, ..
+            } => vec![reference_index],
+            // The bootstrap method index refers to the `BootstrapMethods` attribute.
+            Self::Dynamic {
+                name_and_type_index,
+                ..
+            }
+            | Self::InvokeDynamic {
+                name_and_type_index,
+                ..
+            } => vec![name_and_type_index],
+        }
+    }
+
+    /// Serializes the entry, ignoring the length limit of strings.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if self.write_to(&mut bytes).is_err() {
+            // Strings too long to be written are only compared by their content.
+            if let Self::Utf8(value) = self {
+                bytes = vec![self.tag(), 0xFF, 0xFF];
+                match value {
+                    JavaString::Utf8(it) => bytes.extend_from_slice(&cesu8::to_java_cesu8(it)),
+                    JavaString::InvalidUtf8(it) => bytes.extend_from_slice(it),
+                }
+            }
+        }
+        bytes
+    }
 }
 
 #[cfg(test)]
@@ -212,6 +1082,111 @@ mod tests {
     use crate::jvm::parsing::constant_pool::tests::arb_constant_pool_info;
     use proptest::prelude::*;
 
+    #[test]
+    fn builder_deduplicates() {
+        let mut builder = ConstantPool::builder();
+        let method = MethodRef::new(
+            ClassRef::new("java/lang/Object"),
+            "<init>",
+            "()V".parse().unwrap(),
+        );
+        let method_index = builder.put_method_ref(&method, false).unwrap();
+        assert_eq!(builder.put_method_ref(&method, false), Ok(method_index));
+        let class_index = builder.put_class(&method.owner).unwrap();
+        assert_eq!(builder.put_long(42), Ok(7));
+        assert_eq!(builder.put_integer(42), Ok(9));
+        assert_eq!(
+            builder.put_constant_value(&ConstantValue::Null),
+            Err(BuildError::Unrepresentable(ConstantValue::Null))
+        );
+
+        let statistics = builder.statistics();
+        assert_eq!(statistics.entries_by_kind["CONSTANT_Utf8"], 3);
+        assert_eq!(statistics.entries_by_kind["CONSTANT_Long"], 1);
+        assert_eq!(statistics.slots, 9);
+        assert_eq!(statistics.deduplicated, 8);
+
+        let constant_pool = builder.build();
+        assert_eq!(constant_pool.constant_pool_count(), 10);
+        assert!(matches!(
+            constant_pool.get_entry(class_index),
+            Ok(Entry::Class { .. })
+        ));
+        assert!(constant_pool.get_entry(8).is_err());
+        assert_eq!(constant_pool.index_of(&Entry::Integer(42)), Some(9));
+        assert_eq!(constant_pool.validate(), Ok(()));
+    }
+
+    #[test]
+    fn builder_overflow() {
+        let mut builder = ConstantPool::builder();
+        for value in 1..u16::MAX {
+            builder.put_integer(value.into()).unwrap();
+        }
+        assert_eq!(builder.put_integer(0), Err(BuildError::Overflow));
+        assert_eq!(builder.put_integer(1), Ok(1));
+        assert_eq!(builder.build().constant_pool_count(), u16::MAX);
+    }
+
+    #[test]
+    fn compact() {
+        let mut builder = ConstantPool::builder();
+        let orphan_long = builder.put_long(42).unwrap();
+        let class_index = builder.put_class(&ClassRef::new("Test")).unwrap();
+        let orphan_field = builder
+            .put_field_ref(&FieldRef::new(
+                ClassRef::new("Test"),
+                "value",
+                "I".parse().unwrap(),
+            ))
+            .unwrap();
+        let string_index = builder
+            .put_string(JavaString::Utf8("Test".to_owned()))
+            .unwrap();
+        let mut constant_pool = builder.build();
+        assert!(constant_pool.compact([class_index, 42]).is_err());
+        assert_eq!(constant_pool.get_entry(orphan_long).unwrap().tag(), 5);
+
+        let index_mapping = constant_pool.compact([string_index, class_index]).unwrap();
+        // The class and the string share the UTF-8 entry of the name.
+        assert_eq!(index_mapping.len(), 3);
+        assert_eq!(constant_pool.constant_pool_count(), 4);
+        assert!(!index_mapping.contains_key(&orphan_long));
+        assert!(!index_mapping.contains_key(&orphan_field));
+        assert!(matches!(
+            constant_pool.get_entry(index_mapping[&class_index]),
+            Ok(Entry::Class { name_index: 1 })
+        ));
+        assert!(matches!(
+            constant_pool.get_entry(index_mapping[&string_index]),
+            Ok(Entry::String { string_index: 1 })
+        ));
+        assert_eq!(constant_pool.validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_mismatched_type() {
+        let mut builder = ConstantPool::builder();
+        let integer_index = builder.put_integer(0).unwrap();
+        let class_index = builder
+            .put_entry(Entry::Class {
+                name_index: integer_index,
+            })
+            .unwrap();
+        builder
+            .put_entry(Entry::String { string_index: 42 })
+            .unwrap();
+        assert_eq!(
+            builder.build().validate(),
+            Err(InvalidEntry::MismatchedType {
+                at: class_index,
+                index: integer_index,
+                expected: "Utf8",
+                found: "CONSTANT_Integer",
+            })
+        );
+    }
+
     prop_compose! {
         fn arb_constant_pool_bytes()(
             entries in prop::collection::vec(arb_constant_pool_info(), 1..=50)
@@ -252,5 +1227,25 @@ mod tests {
             assert!(kind.starts_with("CONSTANT_"));
         }
 
+        #[test]
+        fn write_then_parse(entry in any::<Entry>()) {
+            let bytes = entry.to_bytes();
+            let parsed = Entry::parse(&mut bytes.as_slice()).unwrap();
+            assert_eq!(parsed.to_bytes(), bytes);
+        }
+
+        #[test]
+        fn builder_round_trip((count, bytes) in arb_constant_pool_bytes()) {
+            let constant_pool = ConstantPool::from_reader(&mut bytes.as_slice(), count).unwrap();
+            let mut builder = ConstantPoolBuilder::from(constant_pool.clone());
+            for (idx, entry) in constant_pool.iter() {
+                let existing = constant_pool.index_of(entry).unwrap();
+                assert!(existing <= idx);
+                assert_eq!(builder.put_entry(entry.clone()), Ok(existing));
+            }
+            let rebuilt = builder.build();
+            assert_eq!(rebuilt.constant_pool_count(), count);
+        }
+
     }
 }
@@ -0,0 +1,297 @@
+use crate::{
+    jvm::{
+        references::{ClassRef, FieldRef},
+        ConstantValue,
+    },
+    macros::see_jvm_spec,
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+use super::{BootstrapMethod, MethodHandle};
+
+const CONSTANT_BOOTSTRAPS: &str = "java/lang/invoke/ConstantBootstraps";
+
+/// A dynamically-computed constant, whose value is produced by invoking a bootstrap method when
+/// the constant is first loaded.
+#[doc = see_jvm_spec!(4, 4, 10)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, derive_more::Display)]
+#[display("{name}: {descriptor} via {:?}", bootstrap.method)]
+pub struct DynamicConstant {
+    /// The bootstrap method computing the constant.
+    pub bootstrap: BootstrapMethod,
+    /// The name passed to the bootstrap method.
+    pub name: String,
+    /// The type of the constant.
+    pub descriptor: FieldType,
+}
+
+/// A dynamically-computed constant produced by a bootstrap method in
+/// `java/lang/invoke/ConstantBootstraps`, whose value is known without running the bootstrap
+/// method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WellKnownConstant {
+    /// `null`, produced by `ConstantBootstraps.nullConstant`.
+    Null,
+    /// The class of a primitive type (e.g., `int.class`), produced by
+    /// `ConstantBootstraps.primitiveClass`.
+    /// [`None`] denotes `void.class`.
+    PrimitiveClass(Option<PrimitiveType>),
+    /// An enum constant, produced by `ConstantBootstraps.enumConstant`.
+    EnumConstant(FieldRef),
+    /// The value of a `static final` field, produced by `ConstantBootstraps.getStaticFinal`.
+    StaticFinal(FieldRef),
+    /// The result of invoking a method handle, produced by `ConstantBootstraps.invoke`.
+    Invocation {
+        /// The method handle being invoked.
+        handle: MethodHandle,
+        /// The arguments passed to the method handle.
+        arguments: Vec<ConstantValue>,
+    },
+    /// A `VarHandle` for an instance field, produced by `ConstantBootstraps.fieldVarHandle`.
+    FieldVarHandle(FieldRef),
+    /// A `VarHandle` for a static field, produced by `ConstantBootstraps.staticFieldVarHandle`.
+    StaticFieldVarHandle(FieldRef),
+    /// A `VarHandle` for the elements of an array type, produced by
+    /// `ConstantBootstraps.arrayVarHandle`.
+    ArrayVarHandle(FieldType),
+    /// A constant converted to a primitive type, produced by `ConstantBootstraps.explicitCast`.
+    Cast(ConstantValue),
+}
+
+impl DynamicConstant {
+    /// Evaluates the constant if its bootstrap method is a well-known one in
+    /// `java/lang/invoke/ConstantBootstraps`.
+    /// Returns [`None`] for other bootstrap methods, or if the name, the type, or the arguments
+    /// of the constant are not accepted by the bootstrap method.
+    #[must_use]
+    pub fn evaluate(&self) -> Option<WellKnownConstant> {
+        let MethodHandle::RefInvokeStatic(bootstrap) = &self.bootstrap.method else {
+            return None;
+        };
+        if &*bootstrap.owner.binary_name != CONSTANT_BOOTSTRAPS {
+            return None;
+        }
+        let arguments = self.bootstrap.arguments.as_slice();
+        let field =
+            |owner: ClassRef, field_type: FieldType| FieldRef::new(owner, &self.name, field_type);
+        let constant = match (bootstrap.name.as_ref(), arguments) {
+            ("nullConstant", []) if !matches!(self.descriptor, FieldType::Base(_)) => {
+                WellKnownConstant::Null
+            }
+            ("primitiveClass", []) => match self.name.as_str() {
+                "V" => WellKnownConstant::PrimitiveClass(None),
+                name => WellKnownConstant::PrimitiveClass(Some(name.parse().ok()?)),
+            },
+            ("enumConstant", []) => {
+                let FieldType::Object(owner) = &self.descriptor else {
+                    return None;
+                };
+                WellKnownConstant::EnumConstant(field(owner.clone(), self.descriptor.clone()))
+            }
+            ("getStaticFinal", []) => {
+                let owner = match &self.descriptor {
+                    FieldType::Object(owner) => owner.clone(),
+                    FieldType::Base(primitive) => wrapper_class(*primitive),
+                    FieldType::Array(_) => return None,
+                };
+                WellKnownConstant::StaticFinal(field(owner, self.descriptor.clone()))
+            }
+            ("getStaticFinal", [ConstantValue::Class(owner)]) => {
+                WellKnownConstant::StaticFinal(field(owner.clone(), self.descriptor.clone()))
+            }
+            ("invoke", [ConstantValue::Handle(handle), arguments @ ..]) => {
+                WellKnownConstant::Invocation {
+                    handle: handle.clone(),
+                    arguments: arguments.to_vec(),
+                }
+            }
+            ("fieldVarHandle", [ConstantValue::Class(owner), field_type]) => {
+                WellKnownConstant::FieldVarHandle(field(owner.clone(), class_type(field_type)?))
+            }
+            ("staticFieldVarHandle", [ConstantValue::Class(owner), field_type]) => {
+                WellKnownConstant::StaticFieldVarHandle(field(
+                    owner.clone(),
+                    class_type(field_type)?,
+                ))
+            }
+            ("arrayVarHandle", [array_type]) => match class_type(array_type)? {
+                array_type @ FieldType::Array(_) => WellKnownConstant::ArrayVarHandle(array_type),
+                _ => return None,
+            },
+            ("explicitCast", [value]) => WellKnownConstant::Cast(self.cast(value)?),
+            _ => return None,
+        };
+        Some(constant)
+    }
+
+    /// Converts `value` to the type of the constant following the rules of
+    /// `ConstantBootstraps.explicitCast`.
+    /// Only the conversions between primitive types and of `null` are supported.
+    fn cast(&self, value: &ConstantValue) -> Option<ConstantValue> {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+        let converted = match (&self.descriptor, value) {
+            (FieldType::Base(_), ConstantValue::Null) => return None,
+            (_, ConstantValue::Null) => ConstantValue::Null,
+            (FieldType::Base(target), &ConstantValue::Integer(it)) => {
+                cast_integral(*target, i64::from(it))
+            }
+            (FieldType::Base(target), &ConstantValue::Long(it)) => cast_integral(*target, it),
+            (FieldType::Base(target), &ConstantValue::Float(it)) => match target {
+                PrimitiveType::Float => ConstantValue::Float(it),
+                PrimitiveType::Double => ConstantValue::Double(f64::from(it)),
+                // `as` saturates and maps NaN to zero as the JVM does.
+                PrimitiveType::Long => ConstantValue::Long(it as i64),
+                _ => cast_integral(*target, i64::from(it as i32)),
+            },
+            (FieldType::Base(target), &ConstantValue::Double(it)) => match target {
+                PrimitiveType::Float => ConstantValue::Float(it as f32),
+                PrimitiveType::Double => ConstantValue::Double(it),
+                PrimitiveType::Long => ConstantValue::Long(it as i64),
+                _ => cast_integral(*target, i64::from(it as i32)),
+            },
+            _ => return None,
+        };
+        Some(converted)
+    }
+}
+
+/// Converts an integral value to `target`.
+/// Narrowing to `boolean` keeps the lowest bit as `ConstantBootstraps.explicitCast` does.
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn cast_integral(target: PrimitiveType, value: i64) -> ConstantValue {
+    match target {
+        PrimitiveType::Boolean => ConstantValue::Integer(i32::from(value & 1 == 1)),
+        PrimitiveType::Byte => ConstantValue::Integer(i32::from(value as i8)),
+        PrimitiveType::Char => ConstantValue::Integer(i32::from(value as u16)),
+        PrimitiveType::Short => ConstantValue::Integer(i32::from(value as i16)),
+        PrimitiveType::Int => ConstantValue::Integer(value as i32),
+        PrimitiveType::Long => ConstantValue::Long(value),
+        PrimitiveType::Float => ConstantValue::Float(value as f32),
+        PrimitiveType::Double => ConstantValue::Double(value as f64),
+    }
+}
+
+/// Gets the type denoted by a class constant, which is either a class literal or a
+/// `primitiveClass` dynamic constant.
+fn class_type(value: &ConstantValue) -> Option<FieldType> {
+    match value {
+        ConstantValue::Class(class) if class.binary_name.starts_with('[') => {
+            class.binary_name.parse().ok()
+        }
+        ConstantValue::Class(class) => Some(FieldType::Object(class.clone())),
+        ConstantValue::Dynamic(constant) => match constant.evaluate()? {
+            WellKnownConstant::PrimitiveClass(Some(primitive)) => Some(FieldType::Base(primitive)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn wrapper_class(primitive: PrimitiveType) -> ClassRef {
+    let name = match primitive {
+        PrimitiveType::Boolean => "java/lang/Boolean",
+        PrimitiveType::Char => "java/lang/Character",
+        PrimitiveType::Float => "java/lang/Float",
+        PrimitiveType::Double => "java/lang/Double",
+        PrimitiveType::Byte => "java/lang/Byte",
+        PrimitiveType::Short => "java/lang/Short",
+        PrimitiveType::Int => "java/lang/Integer",
+        PrimitiveType::Long => "java/lang/Long",
+    };
+    ClassRef::new(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::references::MethodRef;
+
+    use super::*;
+
+    fn constant(
+        bootstrap: &str,
+        name: &str,
+        descriptor: &str,
+        arguments: Vec<ConstantValue>,
+    ) -> DynamicConstant {
+        DynamicConstant {
+            bootstrap: BootstrapMethod {
+                method: MethodHandle::RefInvokeStatic(MethodRef::new(
+                    ClassRef::new(CONSTANT_BOOTSTRAPS),
+                    bootstrap,
+                    "()V".parse().unwrap(),
+                )),
+                arguments,
+            },
+            name: name.to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn primitive_class() {
+        let int_class = constant("primitiveClass", "I", "Ljava/lang/Class;", Vec::new());
+        assert_eq!(
+            int_class.evaluate(),
+            Some(WellKnownConstant::PrimitiveClass(Some(PrimitiveType::Int)))
+        );
+        let void_class = constant("primitiveClass", "V", "Ljava/lang/Class;", Vec::new());
+        assert_eq!(
+            void_class.evaluate(),
+            Some(WellKnownConstant::PrimitiveClass(None))
+        );
+        let invalid = constant("primitiveClass", "X", "Ljava/lang/Class;", Vec::new());
+        assert_eq!(invalid.evaluate(), None);
+
+        let var_handle = constant(
+            "fieldVarHandle",
+            "count",
+            "Ljava/lang/invoke/VarHandle;",
+            vec![
+                ConstantValue::Class(ClassRef::new("org/mokapot/Counter")),
+                ConstantValue::Dynamic(Box::new(int_class)),
+            ],
+        );
+        assert_eq!(
+            var_handle.evaluate(),
+            Some(WellKnownConstant::FieldVarHandle(FieldRef::new(
+                ClassRef::new("org/mokapot/Counter"),
+                "count",
+                FieldType::Base(PrimitiveType::Int)
+            )))
+        );
+    }
+
+    #[test]
+    fn static_finals_and_casts() {
+        let max_value = constant("getStaticFinal", "MAX_VALUE", "I", Vec::new());
+        assert_eq!(
+            max_value.evaluate(),
+            Some(WellKnownConstant::StaticFinal(FieldRef::new(
+                ClassRef::new("java/lang/Integer"),
+                "MAX_VALUE",
+                FieldType::Base(PrimitiveType::Int)
+            )))
+        );
+        let narrowed = constant(
+            "explicitCast",
+            "_",
+            "B",
+            vec![ConstantValue::Integer(0x1FF)],
+        );
+        assert_eq!(
+            narrowed.evaluate(),
+            Some(WellKnownConstant::Cast(ConstantValue::Integer(-1)))
+        );
+        let to_boolean = constant("explicitCast", "_", "Z", vec![ConstantValue::Long(2)]);
+        assert_eq!(
+            to_boolean.evaluate(),
+            Some(WellKnownConstant::Cast(ConstantValue::Integer(0)))
+        );
+        let null_int = constant("nullConstant", "_", "I", Vec::new());
+        assert_eq!(null_int.evaluate(), None);
+    }
+}
@@ -1,6 +1,12 @@
 //! JVM classes and interfaces
 
 pub mod constant_pool;
+mod dynamic_constant;
+mod referenced_classes;
+mod views;
+
+pub use dynamic_constant::{DynamicConstant, WellKnownConstant};
+pub use views::{EnumConstant, EnumView, RecordAccessor, RecordView};
 
 use std::borrow::Borrow;
 
@@ -14,7 +20,7 @@ use crate::{
 use super::{
     annotation::ElementValue,
     field,
-    parsing::Error,
+    parsing::{CustomAttribute, Error},
     references::{ClassRef, FieldRef, MethodRef},
     Annotation, Class, ConstantValue, Field, Method,
 };
@@ -48,9 +54,7 @@ impl Class {
     /// Creates a [`ClassRef`] referring to the class.
     #[must_use]
     pub fn as_ref(&self) -> ClassRef {
-        ClassRef {
-            binary_name: self.binary_name.clone(),
-        }
+        ClassRef::new(&self.binary_name)
     }
 
     /// Checks if the class is an interface.
@@ -91,6 +95,9 @@ impl Annotation {
 #[derive(Debug, Clone)]
 pub struct ConstantPool {
     inner: Vec<constant_pool::Slot>,
+    /// The entries of the `BootstrapMethods` attribute, which the `CONSTANT_Dynamic` entries
+    /// refer to.
+    bootstrap_methods: Vec<constant_pool::BootstrapMethodEntry>,
 }
 
 /// The maximum supported major version of a class file.
@@ -295,7 +302,7 @@ pub struct EnclosingMethod {
 }
 
 /// The information of a bootstrap method.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BootstrapMethod {
     /// The method handle of the bootstrap method.
     pub method: MethodHandle,
@@ -346,6 +353,8 @@ pub struct RecordComponent {
     pub runtime_invisible_type_annotations: Vec<super::TypeAnnotation>,
     /// Unrecognized JVM attributes.
     pub free_attributes: Vec<(String, Vec<u8>)>,
+    /// Custom attributes decoded by an [`AttributeRegistry`](crate::jvm::parsing::AttributeRegistry).
+    pub custom_attributes: Vec<CustomAttribute>,
 }
 
 bitflags! {
@@ -0,0 +1,531 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    jvm::{
+        annotation::ElementValue,
+        code::{Instruction, LocalVariableTable, MethodBody, StackMapFrame, VerificationType},
+        references::{ClassRef, FieldRef, MethodRef},
+        Annotation, Class, ConstantValue, Field, Method, TypeAnnotation,
+    },
+    macros::see_jvm_spec,
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::{BootstrapMethod, MethodHandle};
+
+impl Class {
+    /// Gets the classes referenced by this class, excluding the class itself.
+    /// The references are collected from the super class and interfaces, the descriptors and
+    /// generic signatures of the members, the annotations, the nesting attributes, the bootstrap
+    /// methods, and the instructions and debug information in the method bodies.
+    /// Array types are reported as their element types, and primitive types are omitted.
+    #[must_use]
+    pub fn referenced_classes(&self) -> BTreeSet<ClassRef> {
+        let mut collector = Collector::default();
+        collector.collect_class(self);
+        collector.classes.remove(&self.as_ref());
+        collector.classes
+    }
+}
+
+#[derive(Default)]
+struct Collector {
+    classes: BTreeSet<ClassRef>,
+}
+
+impl Collector {
+    fn class_ref(&mut self, class_ref: &ClassRef) {
+        // Array classes (e.g., in `anewarray` or class literals) are named by their descriptors.
+        if class_ref.binary_name.starts_with('[') {
+            if let Ok(field_type) = class_ref.binary_name.parse() {
+                self.field_type(&field_type);
+            }
+        } else {
+            self.classes.insert(class_ref.clone());
+        }
+    }
+
+    fn class_refs<'a>(&mut self, class_refs: impl IntoIterator<Item = &'a ClassRef>) {
+        class_refs.into_iter().for_each(|it| self.class_ref(it));
+    }
+
+    fn field_type(&mut self, field_type: &FieldType) {
+        match field_type {
+            FieldType::Base(_) => {}
+            FieldType::Object(class_ref) => self.class_ref(class_ref),
+            FieldType::Array(element_type) => self.field_type(element_type),
+        }
+    }
+
+    fn return_type(&mut self, return_type: &ReturnType) {
+        if let ReturnType::Some(field_type) = return_type {
+            self.field_type(field_type);
+        }
+    }
+
+    fn method_descriptor(&mut self, descriptor: &MethodDescriptor) {
+        descriptor
+            .parameters_types
+            .iter()
+            .for_each(|it| self.field_type(it));
+        self.return_type(&descriptor.return_type);
+    }
+
+    fn field_ref(&mut self, field_ref: &FieldRef) {
+        self.class_ref(&field_ref.owner);
+        self.field_type(&field_ref.field_type);
+    }
+
+    fn method_ref(&mut self, method_ref: &MethodRef) {
+        self.class_ref(&method_ref.owner);
+        self.method_descriptor(&method_ref.descriptor);
+    }
+
+    fn signature(&mut self, signature: Option<&String>) {
+        if let Some(signature) = signature {
+            self.classes.extend(classes_in_signature(signature));
+        }
+    }
+
+    fn method_handle(&mut self, handle: &MethodHandle) {
+        match handle {
+            MethodHandle::RefGetField(field_ref)
+            | MethodHandle::RefGetStatic(field_ref)
+            | MethodHandle::RefPutField(field_ref)
+            | MethodHandle::RefPutStatic(field_ref) => self.field_ref(field_ref),
+            MethodHandle::RefInvokeVirtual(method_ref)
+            | MethodHandle::RefInvokeStatic(method_ref)
+            | MethodHandle::RefInvokeSpecial(method_ref)
+            | MethodHandle::RefNewInvokeSpecial(method_ref)
+            | MethodHandle::RefInvokeInterface(method_ref) => self.method_ref(method_ref),
+        }
+    }
+
+    fn bootstrap_method(&mut self, bootstrap_method: &BootstrapMethod) {
+        self.method_handle(&bootstrap_method.method);
+        bootstrap_method
+            .arguments
+            .iter()
+            .for_each(|it| self.constant_value(it));
+    }
+
+    fn constant_value(&mut self, value: &ConstantValue) {
+        match value {
+            ConstantValue::Class(class_ref) => self.class_ref(class_ref),
+            ConstantValue::Handle(handle) => self.method_handle(handle),
+            ConstantValue::MethodType(descriptor) => self.method_descriptor(descriptor),
+            ConstantValue::Dynamic(constant) => {
+                self.bootstrap_method(&constant.bootstrap);
+                self.field_type(&constant.descriptor);
+            }
+            ConstantValue::Null
+            | ConstantValue::Integer(_)
+            | ConstantValue::Float(_)
+            | ConstantValue::Long(_)
+            | ConstantValue::Double(_)
+            | ConstantValue::String(_) => {}
+        }
+    }
+
+    fn element_value(&mut self, value: &ElementValue) {
+        match value {
+            ElementValue::Primitive(..) | ElementValue::String(_) => {}
+            ElementValue::EnumConstant { enum_type_name, .. } => {
+                if let Ok(enum_type) = enum_type_name.parse() {
+                    self.field_type(&enum_type);
+                }
+            }
+            ElementValue::Class { return_descriptor } => self.return_type(return_descriptor),
+            ElementValue::AnnotationInterface(annotation) => self.annotation(annotation),
+            ElementValue::Array(values) => values.iter().for_each(|it| self.element_value(it)),
+        }
+    }
+
+    fn annotation(&mut self, annotation: &Annotation) {
+        self.field_type(&annotation.annotation_type);
+        annotation
+            .element_value_pairs
+            .iter()
+            .for_each(|(_, value)| self.element_value(value));
+    }
+
+    fn annotations<'a>(&mut self, annotations: impl IntoIterator<Item = &'a Annotation>) {
+        annotations.into_iter().for_each(|it| self.annotation(it));
+    }
+
+    fn type_annotations<'a>(&mut self, annotations: impl IntoIterator<Item = &'a TypeAnnotation>) {
+        for annotation in annotations {
+            self.field_type(&annotation.annotation_type);
+            annotation
+                .element_value_pairs
+                .iter()
+                .for_each(|(_, value)| self.element_value(value));
+        }
+    }
+
+    fn collect_class(&mut self, class: &Class) {
+        self.class_refs(&class.super_class);
+        self.class_refs(&class.interfaces);
+        self.signature(class.signature.as_ref());
+        self.annotations(&class.runtime_visible_annotations);
+        self.annotations(&class.runtime_invisible_annotations);
+        self.type_annotations(&class.runtime_visible_type_annotations);
+        self.type_annotations(&class.runtime_invisible_type_annotations);
+        for inner_class in &class.inner_classes {
+            self.class_ref(&inner_class.inner_class);
+            self.class_refs(&inner_class.outer_class);
+        }
+        if let Some(enclosing_method) = &class.enclosing_method {
+            self.class_ref(&enclosing_method.class);
+            if let Some((_, descriptor)) = &enclosing_method.method_name_and_desc {
+                self.method_descriptor(descriptor);
+            }
+        }
+        for bootstrap_method in &class.bootstrap_methods {
+            self.bootstrap_method(bootstrap_method);
+        }
+        if let Some(module) = &class.module {
+            self.class_refs(&module.uses);
+            for provide in &module.provides {
+                self.class_ref(&provide.service);
+                self.class_refs(&provide.with);
+            }
+        }
+        self.class_refs(&class.module_main_class);
+        self.class_refs(&class.nest_host);
+        self.class_refs(&class.nest_members);
+        self.class_refs(&class.permitted_subclasses);
+        for component in class.record.iter().flatten() {
+            self.field_type(&component.component_type);
+            self.signature(component.signature.as_ref());
+            self.annotations(&component.runtime_visible_annotations);
+            self.annotations(&component.runtime_invisible_annotations);
+            self.type_annotations(&component.runtime_visible_type_annotations);
+            self.type_annotations(&component.runtime_invisible_type_annotations);
+        }
+        class.fields.iter().for_each(|it| self.collect_field(it));
+        class.methods.iter().for_each(|it| self.collect_method(it));
+    }
+
+    fn collect_field(&mut self, field: &Field) {
+        self.field_type(&field.field_type);
+        self.signature(field.signature.as_ref());
+        if let Some(value) = &field.constant_value {
+            self.constant_value(value);
+        }
+        self.annotations(&field.runtime_visible_annotations);
+        self.annotations(&field.runtime_invisible_annotations);
+        self.type_annotations(&field.runtime_visible_type_annotations);
+        self.type_annotations(&field.runtime_invisible_type_annotations);
+    }
+
+    fn collect_method(&mut self, method: &Method) {
+        self.method_descriptor(&method.descriptor);
+        self.signature(method.signature.as_ref());
+        self.class_refs(&method.exceptions);
+        self.annotations(&method.runtime_visible_annotations);
+        self.annotations(&method.runtime_invisible_annotations);
+        self.type_annotations(&method.runtime_visible_type_annotations);
+        self.type_annotations(&method.runtime_invisible_type_annotations);
+        self.annotations(
+            method
+                .runtime_visible_parameter_annotations
+                .iter()
+                .flatten(),
+        );
+        self.annotations(
+            method
+                .runtime_invisible_parameter_annotations
+                .iter()
+                .flatten(),
+        );
+        if let Some(default_value) = &method.annotation_default {
+            self.element_value(default_value);
+        }
+        if let Some(body) = &method.body {
+            self.collect_body(body);
+        }
+    }
+
+    fn collect_body(&mut self, body: &MethodBody) {
+        for (_, insn) in &body.instructions {
+            self.instruction(insn);
+        }
+        for entry in &body.exception_table {
+            self.class_refs(&entry.catch_type);
+        }
+        for (_, entry) in body
+            .local_variable_table
+            .iter()
+            .flat_map(LocalVariableTable::iter)
+        {
+            if let Some(var_type) = &entry.var_type {
+                self.field_type(var_type);
+            }
+            self.signature(entry.signature.as_ref());
+        }
+        for frame in body.stack_map_table.iter().flatten() {
+            let verification_types: Vec<_> = match frame {
+                StackMapFrame::SameFrame { .. } | StackMapFrame::ChopFrame { .. } => Vec::new(),
+                StackMapFrame::SameLocals1StackItemFrame { stack, .. } => vec![stack],
+                StackMapFrame::AppendFrame { locals, .. } => locals.iter().collect(),
+                StackMapFrame::FullFrame { locals, stack, .. } => {
+                    locals.iter().chain(stack).collect()
+                }
+            };
+            for verification_type in verification_types {
+                if let VerificationType::ObjectVariable(class_ref) = verification_type {
+                    self.class_ref(class_ref);
+                }
+            }
+        }
+        self.type_annotations(&body.runtime_visible_type_annotations);
+        self.type_annotations(&body.runtime_invisible_type_annotations);
+    }
+
+    fn instruction(&mut self, insn: &Instruction) {
+        match insn {
+            Instruction::Ldc(value) | Instruction::LdcW(value) | Instruction::Ldc2W(value) => {
+                self.constant_value(value);
+            }
+            Instruction::GetStatic(field_ref)
+            | Instruction::PutStatic(field_ref)
+            | Instruction::GetField(field_ref)
+            | Instruction::PutField(field_ref) => self.field_ref(field_ref),
+            Instruction::InvokeVirtual(method_ref)
+            | Instruction::InvokeSpecial(method_ref)
+            | Instruction::InvokeStatic(method_ref)
+            | Instruction::InvokeInterface(method_ref, _) => self.method_ref(method_ref),
+            Instruction::InvokeDynamic { descriptor, .. } => self.method_descriptor(descriptor),
+            Instruction::New(class_ref) | Instruction::ANewArray(class_ref) => {
+                self.class_ref(class_ref);
+            }
+            Instruction::CheckCast(field_type)
+            | Instruction::InstanceOf(field_type)
+            | Instruction::MultiANewArray(field_type, _) => self.field_type(field_type),
+            _ => {}
+        }
+    }
+}
+
+/// Extracts the classes named in a generic signature.
+/// Parsing stops at the first malformed component, keeping the classes found before it.
+#[doc = see_jvm_spec!(4, 7, 9, 1)]
+fn classes_in_signature(signature: &str) -> BTreeSet<ClassRef> {
+    let mut parser = SignatureParser {
+        remaining: signature,
+        classes: BTreeSet::new(),
+    };
+    let _ = parser.parse();
+    parser.classes
+}
+
+struct SignatureParser<'s> {
+    remaining: &'s str,
+    classes: BTreeSet<ClassRef>,
+}
+
+impl SignatureParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.remaining.chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.remaining = &self.remaining[ch.len_utf8()..];
+        Some(ch)
+    }
+
+    fn take_until(&mut self, delimiters: &[char]) -> Option<&str> {
+        let end = self.remaining.find(delimiters)?;
+        let (taken, rest) = self.remaining.split_at(end);
+        self.remaining = rest;
+        Some(taken)
+    }
+
+    fn parse(&mut self) -> Option<()> {
+        if self.peek() == Some('<') {
+            self.bump();
+            while self.peek()? != '>' {
+                self.take_until(&[':'])?;
+                while self.peek() == Some(':') {
+                    self.bump();
+                    if matches!(self.peek()?, 'L' | 'T' | '[') {
+                        self.reference_type()?;
+                    }
+                }
+            }
+            self.bump();
+        }
+        while let Some(ch) = self.peek() {
+            match ch {
+                '(' | ')' | '^' | 'V' => {
+                    self.bump();
+                }
+                _ => self.java_type()?,
+            }
+        }
+        Some(())
+    }
+
+    fn java_type(&mut self) -> Option<()> {
+        match self.peek()? {
+            'Z' | 'C' | 'F' | 'D' | 'B' | 'S' | 'I' | 'J' => {
+                self.bump();
+                Some(())
+            }
+            _ => self.reference_type(),
+        }
+    }
+
+    fn reference_type(&mut self) -> Option<()> {
+        match self.bump()? {
+            '[' => self.java_type(),
+            'T' => {
+                self.take_until(&[';'])?;
+                self.bump();
+                Some(())
+            }
+            'L' => {
+                let mut binary_name = self.take_until(&['<', '.', ';'])?.to_owned();
+                loop {
+                    self.classes.insert(ClassRef::new(binary_name.clone()));
+                    if self.peek()? == '<' {
+                        self.type_arguments()?;
+                    }
+                    match self.bump()? {
+                        ';' => return Some(()),
+                        '.' => {
+                            let simple_name = self.take_until(&['<', '.', ';'])?;
+                            binary_name = format!("{binary_name}${simple_name}");
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn type_arguments(&mut self) -> Option<()> {
+        self.bump();
+        loop {
+            match self.peek()? {
+                '>' => {
+                    self.bump();
+                    return Some(());
+                }
+                '*' => {
+                    self.bump();
+                }
+                '+' | '-' => {
+                    self.bump();
+                    self.reference_type()?;
+                }
+                _ => self.reference_type()?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jvm::{code::InstructionList, method};
+
+    use super::*;
+
+    fn class_refs<const N: usize>(names: [&str; N]) -> BTreeSet<ClassRef> {
+        names.into_iter().map(ClassRef::new).collect()
+    }
+
+    #[test]
+    fn signature_classes() {
+        assert_eq!(
+            classes_in_signature(
+                "<T:Ljava/lang/Object;U::Ljava/lang/Comparable<-TT;>;>Ljava/util/AbstractMap<TT;[TU;>;"
+            ),
+            class_refs([
+                "java/lang/Object",
+                "java/lang/Comparable",
+                "java/util/AbstractMap"
+            ])
+        );
+        assert_eq!(
+            classes_in_signature(
+                "<E:Ljava/lang/Exception;>(Ljava/util/List<+Ljava/lang/Number;>;[I)Lorg/mokapot/Outer<*>.Inner;^TE;"
+            ),
+            class_refs([
+                "java/lang/Exception",
+                "java/util/List",
+                "java/lang/Number",
+                "org/mokapot/Outer",
+                "org/mokapot/Outer$Inner"
+            ])
+        );
+        assert_eq!(classes_in_signature("TLIST;"), BTreeSet::new());
+    }
+
+    #[test]
+    fn class_references() {
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            name: "test".to_owned(),
+            descriptor: "([Lorg/mokapot/Param;)Lorg/mokapot/Ret;".parse().unwrap(),
+            owner: ClassRef::new("org/mokapot/Test"),
+            exceptions: vec![ClassRef::new("org/mokapot/Thrown")],
+            body: Some(MethodBody {
+                max_stack: 1,
+                max_locals: 1,
+                instructions: InstructionList::from([
+                    (
+                        0.into(),
+                        Instruction::ANewArray(ClassRef::new("[Lorg/mokapot/Element;")),
+                    ),
+                    (
+                        3.into(),
+                        Instruction::Ldc(ConstantValue::Class(ClassRef::new("org/mokapot/Test"))),
+                    ),
+                    (
+                        5.into(),
+                        Instruction::CheckCast("[[Lorg/mokapot/Cast;".parse().unwrap()),
+                    ),
+                    (8.into(), Instruction::AReturn),
+                ]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let class = Class {
+            binary_name: "org/mokapot/Test".to_owned(),
+            super_class: Some(ClassRef::new("java/lang/Object")),
+            methods: vec![method],
+            runtime_visible_annotations: vec![Annotation {
+                annotation_type: "Lorg/mokapot/Marker;".parse().unwrap(),
+                element_value_pairs: vec![(
+                    "value".to_owned(),
+                    ElementValue::EnumConstant {
+                        enum_type_name: "Lorg/mokapot/Kind;".to_owned(),
+                        const_name: "A".to_owned(),
+                    },
+                )],
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            class.referenced_classes(),
+            class_refs([
+                "java/lang/Object",
+                "org/mokapot/Cast",
+                "org/mokapot/Element",
+                "org/mokapot/Kind",
+                "org/mokapot/Marker",
+                "org/mokapot/Param",
+                "org/mokapot/Ret",
+                "org/mokapot/Thrown"
+            ])
+        );
+    }
+}
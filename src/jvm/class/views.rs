@@ -0,0 +1,278 @@
+use crate::{
+    jvm::{field, method, references::ClassRef, Class, Field, Method},
+    types::{
+        field_type::FieldType,
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::{AccessFlags, RecordComponent};
+
+const ENUM_SUPER_CLASS: &str = "java/lang/Enum";
+
+impl Class {
+    /// Gets a view of the class as a `record`, or [`None`] if the class is not a record.
+    #[must_use]
+    pub fn as_record(&self) -> Option<RecordView<'_>> {
+        self.record.as_deref().map(|components| RecordView {
+            class: self,
+            components,
+        })
+    }
+
+    /// Gets a view of the class as an `enum`, or [`None`] if the class is not an enum.
+    /// The classes of the enum constants with a body are not considered enums.
+    #[must_use]
+    pub fn as_enum(&self) -> Option<EnumView<'_>> {
+        let extends_enum = self
+            .super_class
+            .as_ref()
+            .is_some_and(|it| &*it.binary_name == ENUM_SUPER_CLASS);
+        (self.access_flags.contains(AccessFlags::ENUM) && extends_enum)
+            .then_some(EnumView { class: self })
+    }
+}
+
+/// A view of a [`Class`] that represents a `record`.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordView<'a> {
+    class: &'a Class,
+    components: &'a [RecordComponent],
+}
+
+/// A record component together with its accessor method.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordAccessor<'a> {
+    /// The record component.
+    pub component: &'a RecordComponent,
+    /// The accessor method of the component, or [`None`] if the class does not declare it.
+    pub method: Option<&'a Method>,
+}
+
+impl<'a> RecordView<'a> {
+    /// Gets the class of the record.
+    #[must_use]
+    pub const fn class(&self) -> &'a Class {
+        self.class
+    }
+
+    /// Gets the record components in declaration order.
+    #[must_use]
+    pub const fn components(&self) -> &'a [RecordComponent] {
+        self.components
+    }
+
+    /// Gets the accessor method of `component`, i.e., the instance method with the same name as
+    /// the component that takes no arguments and returns the type of the component.
+    #[must_use]
+    pub fn accessor(&self, component: &RecordComponent) -> Option<&'a Method> {
+        let descriptor =
+            MethodDescriptor::new([], ReturnType::Some(component.component_type.clone()));
+        self.class
+            .get_method(&component.name, descriptor)
+            .filter(|it| !it.access_flags.contains(method::AccessFlags::STATIC))
+    }
+
+    /// Gets the record components together with their accessor methods.
+    pub fn accessors(&self) -> impl Iterator<Item = RecordAccessor<'a>> + '_ {
+        self.components.iter().map(|component| RecordAccessor {
+            component,
+            method: self.accessor(component),
+        })
+    }
+
+    /// Gets the canonical constructor, i.e., the constructor whose parameters are the record
+    /// components in declaration order.
+    #[must_use]
+    pub fn canonical_constructor(&self) -> Option<&'a Method> {
+        let descriptor = MethodDescriptor::new(
+            self.components
+                .iter()
+                .map(|it| it.component_type.clone())
+                .collect::<Vec<_>>(),
+            ReturnType::Void,
+        );
+        self.class.get_method(Method::CONSTRUCTOR_NAME, descriptor)
+    }
+}
+
+/// A view of a [`Class`] that represents an `enum`.
+#[derive(Debug, Clone, Copy)]
+pub struct EnumView<'a> {
+    class: &'a Class,
+}
+
+/// A constant of an `enum`.
+#[derive(Debug, Clone, Copy)]
+pub struct EnumConstant<'a> {
+    /// The field holding the constant.
+    pub field: &'a Field,
+    /// The ordinal of the constant.
+    pub ordinal: usize,
+}
+
+impl<'a> EnumConstant<'a> {
+    /// Gets the name of the constant.
+    #[must_use]
+    pub fn name(&self) -> &'a str {
+        &self.field.name
+    }
+}
+
+impl<'a> EnumView<'a> {
+    /// Gets the class of the enum.
+    #[must_use]
+    pub const fn class(&self) -> &'a Class {
+        self.class
+    }
+
+    /// Gets the constants of the enum.
+    /// The ordinals follow the declaration order of the fields marked as `enum`, which is the
+    /// order in which `javac` initializes the constants.
+    pub fn constants(&self) -> impl Iterator<Item = EnumConstant<'a>> {
+        self.class
+            .fields
+            .iter()
+            .filter(|it| it.access_flags.contains(field::AccessFlags::ENUM))
+            .enumerate()
+            .map(|(ordinal, field)| EnumConstant { field, ordinal })
+    }
+
+    /// Gets the constant named `name`.
+    #[must_use]
+    pub fn constant(&self, name: &str) -> Option<EnumConstant<'a>> {
+        self.constants().find(|it| it.name() == name)
+    }
+
+    /// Gets the implicitly declared `public static E[] values()` method.
+    #[must_use]
+    pub fn values_method(&self) -> Option<&'a Method> {
+        let descriptor =
+            MethodDescriptor::new([], ReturnType::Some(self.enum_type().into_array_type()));
+        self.static_method("values", descriptor)
+    }
+
+    /// Gets the implicitly declared `public static E valueOf(String)` method.
+    #[must_use]
+    pub fn value_of_method(&self) -> Option<&'a Method> {
+        let descriptor = MethodDescriptor::new(
+            [FieldType::Object(ClassRef::new("java/lang/String"))],
+            ReturnType::Some(self.enum_type()),
+        );
+        self.static_method("valueOf", descriptor)
+    }
+
+    fn enum_type(self) -> FieldType {
+        FieldType::Object(self.class.as_ref())
+    }
+
+    fn static_method(self, name: &str, descriptor: MethodDescriptor) -> Option<&'a Method> {
+        self.class
+            .get_method(name, descriptor)
+            .filter(|it| it.access_flags.contains(method::AccessFlags::STATIC))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method(name: &str, descriptor: &str, access_flags: method::AccessFlags) -> Method {
+        Method {
+            access_flags,
+            name: name.to_owned(),
+            descriptor: descriptor.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn component(name: &str, component_type: &str) -> RecordComponent {
+        RecordComponent {
+            name: name.to_owned(),
+            component_type: component_type.parse().unwrap(),
+            signature: None,
+            runtime_visible_annotations: Vec::new(),
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn record() {
+        let public = method::AccessFlags::PUBLIC;
+        let class = Class {
+            binary_name: "Point".to_owned(),
+            super_class: Some(ClassRef::new("java/lang/Record")),
+            methods: vec![
+                method("<init>", "(I)V", public),
+                method("<init>", "(II)V", public),
+                method("x", "()I", public),
+                method("y", "()I", method::AccessFlags::STATIC),
+            ],
+            record: Some(vec![component("x", "I"), component("y", "I")]),
+            ..Default::default()
+        };
+        let record = class.as_record().unwrap();
+        assert_eq!(record.components().len(), 2);
+        let accessors: Vec<_> = record
+            .accessors()
+            .map(|it| (it.component.name.as_str(), it.method.map(|m| &m.name)))
+            .collect();
+        assert_eq!(accessors, vec![("x", Some(&"x".to_owned())), ("y", None)]);
+        let canonical = record.canonical_constructor().unwrap();
+        assert_eq!(canonical.descriptor.descriptor(), "(II)V");
+
+        assert!(Class::default().as_record().is_none());
+    }
+
+    #[test]
+    fn enumeration() {
+        let constant = |name: &str| Field {
+            access_flags: field::AccessFlags::PUBLIC
+                | field::AccessFlags::STATIC
+                | field::AccessFlags::FINAL
+                | field::AccessFlags::ENUM,
+            name: name.to_owned(),
+            field_type: "LColor;".parse().unwrap(),
+            ..Default::default()
+        };
+        let public_static = method::AccessFlags::PUBLIC | method::AccessFlags::STATIC;
+        let class = Class {
+            access_flags: AccessFlags::PUBLIC | AccessFlags::FINAL | AccessFlags::ENUM,
+            binary_name: "Color".to_owned(),
+            super_class: Some(ClassRef::new(ENUM_SUPER_CLASS)),
+            fields: vec![
+                constant("RED"),
+                constant("GREEN"),
+                Field {
+                    name: "$VALUES".to_owned(),
+                    field_type: "[LColor;".parse().unwrap(),
+                    ..Default::default()
+                },
+                constant("BLUE"),
+            ],
+            methods: vec![
+                method("values", "()[LColor;", public_static),
+                method("valueOf", "(Ljava/lang/String;)LColor;", public_static),
+            ],
+            ..Default::default()
+        };
+        let view = class.as_enum().unwrap();
+        let constants: Vec<_> = view.constants().map(|it| (it.name(), it.ordinal)).collect();
+        assert_eq!(constants, vec![("RED", 0), ("GREEN", 1), ("BLUE", 2)]);
+        assert_eq!(view.constant("BLUE").map(|it| it.ordinal), Some(2));
+        assert!(view.values_method().is_some());
+        assert!(view.value_of_method().is_some());
+
+        let constant_body = Class {
+            access_flags: AccessFlags::FINAL | AccessFlags::ENUM,
+            binary_name: "Color$1".to_owned(),
+            super_class: Some(ClassRef::new("Color")),
+            ..Default::default()
+        };
+        assert!(constant_body.as_enum().is_none());
+    }
+}
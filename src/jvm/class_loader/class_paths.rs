@@ -1,6 +1,12 @@
 //! Implementations of [`ClassPath`].
 
-use std::{collections::HashSet, fs::File, io::BufReader};
+use std::{collections::BTreeSet, fs::File, io::BufReader};
+#[cfg(feature = "jar")]
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{Read, Seek},
+    path::Path,
+};
 
 #[cfg(feature = "jar")]
 use zip::{result::ZipError, ZipArchive};
@@ -10,6 +16,8 @@ use crate::{
     jvm::{references::ClassRef, Class},
 };
 
+#[cfg(feature = "jar")]
+use super::manifest::{Manifest, MANIFEST_PATH};
 use super::{ClassPath, Error};
 /// A class path that searches for classes in a directory.
 #[derive(Debug)]
@@ -29,6 +37,15 @@ impl ClassPath for DirectoryClassPath {
             Err(Error::NotFound)
         }
     }
+
+    fn find_class_bytes(&self, binary_name: &str) -> Result<Vec<u8>, Error> {
+        let class_file_path = self.directory.join(binary_name).with_extension("class");
+        if class_file_path.exists() {
+            std::fs::read(class_file_path).map_err(Into::into)
+        } else {
+            Err(Error::NotFound)
+        }
+    }
 }
 
 impl DirectoryClassPath {
@@ -41,7 +58,7 @@ impl DirectoryClassPath {
 }
 
 impl ClassRefs for DirectoryClassPath {
-    fn class_refs(&self) -> HashSet<ClassRef> {
+    fn class_refs(&self) -> BTreeSet<ClassRef> {
         walkdir::WalkDir::new(&self.directory)
             .into_iter()
             .filter_map(Result::ok)
@@ -55,17 +72,23 @@ impl ClassRefs for DirectoryClassPath {
                     .to_str()
                     .expect("The path name is not valid UTF-8")
                     .to_owned();
-                ClassRef { binary_name }
+                ClassRef::new(binary_name)
             })
             .collect()
     }
 }
 
 /// A class path that searches for classes in a JAR file.
+///
+/// If the JAR file is a multi-release JAR file (i.e., its manifest declares
+/// `Multi-Release: true`) and a target release is set with [`JarClassPath::with_release`], the
+/// classes in `META-INF/versions/N` take precedence over the ones in the root of the JAR file for
+/// the highest `N` not exceeding the target release.
 #[derive(Debug)]
 #[cfg(feature = "jar")]
 pub struct JarClassPath {
     jar_file: std::path::PathBuf,
+    release: Option<u16>,
 }
 
 #[cfg(feature = "jar")]
@@ -74,47 +97,581 @@ impl JarClassPath {
     pub fn new(jar_file: impl Into<std::path::PathBuf>) -> Self {
         Self {
             jar_file: jar_file.into(),
+            release: None,
         }
     }
+
+    /// Sets the feature release of the target runtime (e.g., `17` for JDK 17), which selects the
+    /// variants of the classes in a multi-release JAR file.
+    #[must_use]
+    pub fn with_release(mut self, release: u16) -> Self {
+        self.release = Some(release);
+        self
+    }
+
+    /// Loads all variants of the class with the given binary name, keyed by the release of the
+    /// versioned directory containing them, or [`None`] for the one in the root of the JAR file.
+    /// The variants are loaded regardless of the target release.
+    ///
+    /// # Errors
+    /// - [`Error::NotFound`] if the JAR file contains no variant of the class.
+    /// - Other variants of [`Error`] if the JAR file or the class files cannot be read.
+    pub fn class_variants(&self, binary_name: &str) -> Result<BTreeMap<Option<u16>, Class>, Error> {
+        let mut jar_archive = self.open()?;
+        let file_name = format!("{binary_name}.class");
+        let entries: Vec<_> = jar_archive
+            .file_names()
+            .filter_map(|it| match versioned_entry(it) {
+                Some((release, name)) => (name == file_name).then_some(Some(release)),
+                None => (it == file_name).then_some(None),
+            })
+            .collect();
+        if entries.is_empty() {
+            return Err(Error::NotFound);
+        }
+        entries
+            .into_iter()
+            .map(|release| {
+                let entry_name = match release {
+                    Some(release) => format!("{VERSIONS_DIR}{release}/{file_name}"),
+                    None => file_name.clone(),
+                };
+                read_class(&mut jar_archive, &entry_name).map(|class| (release, class))
+            })
+            .collect()
+    }
+
+    /// Reads the manifest of the JAR file, or returns [`None`] if there is no manifest.
+    ///
+    /// # Errors
+    /// See [`Error`]. A malformed manifest is reported as [`Error::Other`].
+    pub fn manifest(&self) -> Result<Option<Manifest>, Error> {
+        read_manifest(&mut self.open()?)
+    }
+
+    /// Lists the resources in the JAR file other than the classes and the directories.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn resources(&self) -> Result<Vec<Resource>, Error> {
+        let mut jar_archive = self.open()?;
+        let mut resources = Vec::new();
+        for idx in 0..jar_archive.len() {
+            let entry = jar_archive.by_index_raw(idx).map_err(zip_error)?;
+            let name = entry.name();
+            let is_class = Path::new(name)
+                .extension()
+                .is_some_and(|it| it.eq_ignore_ascii_case("class"));
+            if entry.is_dir() || is_class {
+                continue;
+            }
+            resources.push(Resource {
+                kind: ResourceKind::of(name),
+                name: name.to_owned(),
+                size: entry.size(),
+                compressed_size: entry.compressed_size(),
+                crc32: entry.crc32(),
+            });
+        }
+        Ok(resources)
+    }
+
+    /// Reads the content of the entry `name` in the JAR file.
+    ///
+    /// # Errors
+    /// - [`Error::NotFound`] if there is no such entry.
+    /// - Other variants of [`Error`] if the JAR file cannot be read.
+    pub fn read_resource(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let mut jar_archive = self.open()?;
+        let mut entry = jar_archive.by_name(name).map_err(zip_error)?;
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        Ok(content)
+    }
+
+    /// Returns the JAR files referenced by the `Class-Path` attribute of the manifest, resolved
+    /// against the directory containing this JAR file.
+    /// Directories and non-existent files are skipped, as the JVM does.
+    /// The returned class paths inherit the target release of this class path.
+    ///
+    /// # Errors
+    /// See [`JarClassPath::manifest`].
+    pub fn manifest_class_path(&self) -> Result<Vec<Self>, Error> {
+        let Some(manifest) = self.manifest()? else {
+            return Ok(Vec::new());
+        };
+        let base = self.jar_file.parent().unwrap_or_else(|| Path::new(""));
+        Ok(manifest
+            .class_path()
+            .filter(|it| !it.ends_with('/'))
+            .map(|it| base.join(it))
+            .filter(|it| it.is_file())
+            .map(|jar_file| Self {
+                jar_file,
+                release: self.release,
+            })
+            .collect())
+    }
+
+    /// Returns this class path followed by the ones transitively referenced by the
+    /// `Class-Path` attributes of the manifests (see [`JarClassPath::manifest_class_path`]),
+    /// in the order the JVM searches them. Each JAR file appears at most once.
+    ///
+    /// # Errors
+    /// See [`JarClassPath::manifest`].
+    pub fn with_manifest_class_path(self) -> Result<Vec<Self>, Error> {
+        expand_manifest_class_path([self])
+    }
+
+    /// Returns the path to the JAR file.
+    #[must_use]
+    pub fn jar_file(&self) -> &Path {
+        &self.jar_file
+    }
+
+    fn open(&self) -> Result<ZipArchive<BufReader<File>>, Error> {
+        let jar_file = File::open(&self.jar_file)?;
+        let jar_reader = BufReader::new(jar_file);
+        ZipArchive::new(jar_reader).map_err(zip_error)
+    }
+
+    /// Returns the name of the entry holding the class file of the class, taking the versioned
+    /// directories into account.
+    fn class_entry_name(
+        &self,
+        jar_archive: &mut ZipArchive<BufReader<File>>,
+        binary_name: &str,
+    ) -> String {
+        let file_name = format!("{binary_name}.class");
+        let release = self.effective_release(jar_archive).and_then(|target| {
+            jar_archive
+                .file_names()
+                .filter_map(versioned_entry)
+                .filter(|(release, name)| *release <= target && *name == file_name)
+                .map(|(release, _)| release)
+                .max()
+        });
+        match release {
+            Some(release) => format!("{VERSIONS_DIR}{release}/{file_name}"),
+            None => file_name,
+        }
+    }
+
+    /// Returns the target release if the JAR file is a multi-release JAR file.
+    fn effective_release(&self, jar_archive: &mut ZipArchive<BufReader<File>>) -> Option<u16> {
+        self.release.filter(|_| is_multi_release(jar_archive))
+    }
+}
+
+/// Appends the JAR files transitively referenced by the `Class-Path` attributes of the manifests
+/// of `class_path` to it, in the order the JVM searches them.
+/// The JAR files referenced by a manifest are searched after all the JAR files discovered before
+/// them, and each JAR file appears at most once, so cyclic references are followed only once.
+///
+/// # Errors
+/// See [`JarClassPath::manifest`].
+#[cfg(feature = "jar")]
+pub fn expand_manifest_class_path(
+    class_path: impl IntoIterator<Item = JarClassPath>,
+) -> Result<Vec<JarClassPath>, Error> {
+    let identity = |it: &JarClassPath| {
+        std::fs::canonicalize(&it.jar_file).unwrap_or_else(|_| it.jar_file.clone())
+    };
+    let mut visited = HashSet::new();
+    let mut class_path: Vec<_> = class_path
+        .into_iter()
+        .filter(|it| visited.insert(identity(it)))
+        .collect();
+    let mut idx = 0;
+    while let Some(current) = class_path.get(idx) {
+        let referenced: Vec<_> = current
+            .manifest_class_path()?
+            .into_iter()
+            .filter(|it| visited.insert(identity(it)))
+            .collect();
+        class_path.extend(referenced);
+        idx += 1;
+    }
+    Ok(class_path)
+}
+
+#[cfg(feature = "jar")]
+const VERSIONS_DIR: &str = "META-INF/versions/";
+
+/// Splits an entry in `META-INF/versions` into the release and the path relative to the
+/// versioned directory. Releases before 9 are ignored as specified for multi-release JAR files.
+#[cfg(feature = "jar")]
+fn versioned_entry(entry_name: &str) -> Option<(u16, &str)> {
+    let (release, name) = entry_name.strip_prefix(VERSIONS_DIR)?.split_once('/')?;
+    release
+        .parse()
+        .ok()
+        .filter(|it| *it >= 9)
+        .map(|release| (release, name))
+}
+
+#[cfg(feature = "jar")]
+fn is_multi_release<R: Read + Seek>(jar_archive: &mut ZipArchive<R>) -> bool {
+    read_manifest(jar_archive).is_ok_and(|it| it.is_some_and(|it| it.is_multi_release()))
+}
+
+#[cfg(feature = "jar")]
+fn read_manifest<R: Read + Seek>(
+    jar_archive: &mut ZipArchive<R>,
+) -> Result<Option<Manifest>, Error> {
+    let mut manifest_file = match jar_archive.by_name(MANIFEST_PATH) {
+        Ok(it) => it,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(zip_error(e)),
+    };
+    let mut manifest = String::new();
+    manifest_file.read_to_string(&mut manifest)?;
+    Manifest::parse(&manifest)
+        .map(Some)
+        .map_err(|e| Error::Other(Box::new(e)))
+}
+
+#[cfg(feature = "jar")]
+fn read_class<R: Read + Seek>(
+    jar_archive: &mut ZipArchive<R>,
+    entry_name: &str,
+) -> Result<Class, Error> {
+    let mut class_file = jar_archive.by_name(entry_name).map_err(zip_error)?;
+    Class::from_reader(&mut class_file).map_err(Into::into)
+}
+
+#[cfg(feature = "jar")]
+pub(crate) fn zip_error(error: ZipError) -> Error {
+    match error {
+        ZipError::FileNotFound => Error::NotFound,
+        ZipError::Io(io_err) => Error::IO(io_err),
+        e => Error::Other(Box::new(e)),
+    }
+}
+
+/// The kind of a resource in a JAR file, determined by its name.
+#[cfg(feature = "jar")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    /// The manifest (`META-INF/MANIFEST.MF`).
+    Manifest,
+    /// A file signing the JAR file (e.g., `META-INF/*.SF` or `META-INF/*.RSA`).
+    Signature,
+    /// A provider-configuration file in `META-INF/services`.
+    ServiceConfiguration,
+    /// A `.properties` file.
+    Properties,
+    /// A native library (e.g., a `.so`, `.dll`, or `.dylib` file).
+    NativeLibrary,
+    /// Any other resource.
+    Other,
+}
+
+#[cfg(feature = "jar")]
+impl ResourceKind {
+    /// Determines the kind of the resource with the entry name `name`.
+    #[must_use]
+    pub fn of(name: &str) -> Self {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, it)| it.to_ascii_lowercase())
+            .unwrap_or_default();
+        let in_meta_inf = name
+            .strip_prefix("META-INF/")
+            .is_some_and(|it| !it.contains('/'));
+        if name.eq_ignore_ascii_case(MANIFEST_PATH) {
+            Self::Manifest
+        } else if in_meta_inf && matches!(extension.as_str(), "sf" | "rsa" | "dsa" | "ec") {
+            Self::Signature
+        } else if name.starts_with("META-INF/services/") {
+            Self::ServiceConfiguration
+        } else if extension == "properties" {
+            Self::Properties
+        } else if matches!(extension.as_str(), "so" | "dll" | "dylib" | "jnilib") {
+            Self::NativeLibrary
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// A resource in a JAR file.
+#[cfg(feature = "jar")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    /// The name of the entry.
+    pub name: String,
+    /// The kind of the resource.
+    pub kind: ResourceKind,
+    /// The uncompressed size in bytes.
+    pub size: u64,
+    /// The compressed size in bytes.
+    pub compressed_size: u64,
+    /// The CRC-32 checksum of the content.
+    pub crc32: u32,
 }
 
 #[cfg(feature = "jar")]
 impl ClassPath for JarClassPath {
     fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
-        let jar_file = File::open(&self.jar_file)?;
-        let jar_reader = BufReader::new(jar_file);
-        let mut jar_archive = ZipArchive::new(jar_reader).map_err(|e| match e {
-            ZipError::Io(io_err) => Error::IO(io_err),
-            e => Error::Other(Box::new(e)),
-        })?;
-        let mut class_file = jar_archive
-            .by_name(&format!("{binary_name}.class"))
-            .map_err(|e| match e {
-                ZipError::FileNotFound => Error::NotFound,
-                ZipError::Io(io_err) => Error::IO(io_err),
-                e => Error::Other(Box::new(e)),
-            })?;
-        Class::from_reader(&mut class_file).map_err(Into::into)
+        let mut jar_archive = self.open()?;
+        let entry_name = self.class_entry_name(&mut jar_archive, binary_name);
+        read_class(&mut jar_archive, &entry_name)
+    }
+
+    fn find_class_bytes(&self, binary_name: &str) -> Result<Vec<u8>, Error> {
+        let mut jar_archive = self.open()?;
+        let entry_name = self.class_entry_name(&mut jar_archive, binary_name);
+        let mut class_file = jar_archive.by_name(&entry_name).map_err(zip_error)?;
+        let mut bytes = Vec::new();
+        class_file.read_to_end(&mut bytes)?;
+        Ok(bytes)
     }
 }
 
 #[cfg(feature = "jar")]
 impl ClassRefs for JarClassPath {
-    fn class_refs(&self) -> HashSet<ClassRef> {
-        let Ok(jar_file) = File::open(&self.jar_file) else {
-            return HashSet::default();
-        };
-        let jar_reader = BufReader::new(jar_file);
-        let Ok(jar_archive) = ZipArchive::new(jar_reader) else {
-            return HashSet::default();
+    fn class_refs(&self) -> BTreeSet<ClassRef> {
+        let Ok(mut jar_archive) = self.open() else {
+            return BTreeSet::default();
         };
+        let target = self.effective_release(&mut jar_archive);
         jar_archive
             .file_names()
-            .filter_map(|it| it.strip_suffix(".class"))
-            .map(|binary_name| {
-                let binary_name = binary_name.to_owned();
-                ClassRef { binary_name }
+            .filter_map(|it| match versioned_entry(it) {
+                Some((release, name)) => target.is_some_and(|it| release <= it).then_some(name),
+                None if it.starts_with(VERSIONS_DIR) => None,
+                None => Some(it),
             })
+            .filter_map(|it| it.strip_suffix(".class"))
+            .map(ClassRef::new)
             .collect()
     }
 }
+
+#[cfg(all(test, feature = "jar"))]
+mod tests {
+    use std::io::Write;
+
+    use zip::{write::SimpleFileOptions, ZipWriter};
+
+    use super::*;
+    use crate::{jvm::ClassLoader, tests::empty_class_with_version};
+
+    fn temp_jar(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "mokapot-{name}-{}-{:?}.jar",
+            std::process::id(),
+            std::thread::current().id()
+        ))
+    }
+
+    fn write_jar(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        for (name, bytes) in entries {
+            writer
+                .start_file(*name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(bytes).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn jar(name: &str, multi_release: bool) -> std::path::PathBuf {
+        let path = temp_jar(name);
+        let manifest = format!("Manifest-Version: 1.0\r\nMulti-Release: {multi_release}\r\n");
+        write_jar(
+            &path,
+            &[
+                ("META-INF/MANIFEST.MF", manifest.as_bytes()),
+                ("org/mokapot/A.class", &empty_class_with_version(52, 0)),
+                (
+                    "META-INF/versions/9/org/mokapot/A.class",
+                    &empty_class_with_version(53, 0),
+                ),
+                (
+                    "META-INF/versions/11/org/mokapot/A.class",
+                    &empty_class_with_version(55, 0),
+                ),
+                (
+                    "META-INF/versions/11/org/mokapot/B.class",
+                    &empty_class_with_version(55, 0),
+                ),
+            ],
+        );
+        path
+    }
+
+    fn major(class_path: &JarClassPath, binary_name: &str) -> Result<u16, Error> {
+        class_path
+            .find_class(binary_name)
+            .map(|it| it.version.major())
+    }
+
+    #[test]
+    fn multi_release_jar() {
+        let path = jar("multi-release", true);
+        let base = JarClassPath::new(&path);
+        assert_eq!(major(&base, "org/mokapot/A").unwrap(), 52);
+        assert!(matches!(
+            major(&base, "org/mokapot/B"),
+            Err(Error::NotFound)
+        ));
+        assert_eq!(
+            base.class_refs(),
+            BTreeSet::from([ClassRef::new("org/mokapot/A")])
+        );
+
+        let jdk10 = JarClassPath::new(&path).with_release(10);
+        assert_eq!(major(&jdk10, "org/mokapot/A").unwrap(), 53);
+        let jdk17 = JarClassPath::new(&path).with_release(17);
+        assert_eq!(major(&jdk17, "org/mokapot/A").unwrap(), 55);
+        assert_eq!(major(&jdk17, "org/mokapot/B").unwrap(), 55);
+        assert_eq!(
+            jdk17.find_class_bytes("org/mokapot/A").unwrap(),
+            empty_class_with_version(55, 0)
+        );
+        assert_eq!(
+            jdk17.class_refs(),
+            BTreeSet::from([
+                ClassRef::new("org/mokapot/A"),
+                ClassRef::new("org/mokapot/B")
+            ])
+        );
+
+        let variants = base.class_variants("org/mokapot/A").unwrap();
+        let majors: Vec<_> = variants
+            .iter()
+            .map(|(release, class)| (*release, class.version.major()))
+            .collect();
+        assert_eq!(majors, [(None, 52), (Some(9), 53), (Some(11), 55)]);
+        assert!(matches!(
+            base.class_variants("org/mokapot/C"),
+            Err(Error::NotFound)
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn release_ignored_without_manifest_attribute() {
+        let path = jar("single-release", false);
+        let class_path = JarClassPath::new(&path).with_release(17);
+        assert_eq!(major(&class_path, "org/mokapot/A").unwrap(), 52);
+        assert!(matches!(
+            major(&class_path, "org/mokapot/B"),
+            Err(Error::NotFound)
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn resources_and_manifest_class_path() {
+        let app = temp_jar("app");
+        let lib = temp_jar("lib");
+        let lib_name = lib.file_name().unwrap().to_str().unwrap();
+        let app_name = app.file_name().unwrap().to_str().unwrap();
+        let app_manifest = format!(
+            "Manifest-Version: 1.0\r\nMain-Class: org.mokapot.Main\r\nClass-Path: {lib_name} missing.jar classes/\r\n"
+        );
+        write_jar(
+            &app,
+            &[
+                ("META-INF/MANIFEST.MF", app_manifest.as_bytes()),
+                (
+                    "META-INF/services/org.mokapot.Codec",
+                    b"org.mokapot.JsonCodec",
+                ),
+                ("org/mokapot/Main.class", &empty_class_with_version(52, 0)),
+                ("org/mokapot/app.properties", b"name=mokapot"),
+                ("native/libmokapot.so", b"\x7fELF"),
+            ],
+        );
+        let lib_manifest = format!("Class-Path: {app_name}\r\n");
+        write_jar(&lib, &[("META-INF/MANIFEST.MF", lib_manifest.as_bytes())]);
+
+        let class_path = JarClassPath::new(&app);
+        let manifest = class_path.manifest().unwrap().unwrap();
+        assert_eq!(manifest.main_class().as_deref(), Some("org/mokapot/Main"));
+        let kinds: Vec<_> = class_path
+            .resources()
+            .unwrap()
+            .into_iter()
+            .map(|it| (it.name, it.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                ("META-INF/MANIFEST.MF".to_owned(), ResourceKind::Manifest),
+                (
+                    "META-INF/services/org.mokapot.Codec".to_owned(),
+                    ResourceKind::ServiceConfiguration
+                ),
+                (
+                    "org/mokapot/app.properties".to_owned(),
+                    ResourceKind::Properties
+                ),
+                (
+                    "native/libmokapot.so".to_owned(),
+                    ResourceKind::NativeLibrary
+                ),
+            ]
+        );
+        assert_eq!(
+            class_path
+                .read_resource("org/mokapot/app.properties")
+                .unwrap(),
+            b"name=mokapot"
+        );
+        assert!(matches!(
+            class_path.read_resource("missing"),
+            Err(Error::NotFound)
+        ));
+
+        let chained: Vec<_> = class_path
+            .with_manifest_class_path()
+            .unwrap()
+            .into_iter()
+            .map(|it| it.jar_file)
+            .collect();
+        assert_eq!(chained, [app.clone(), lib.clone()]);
+        std::fs::remove_file(app).unwrap();
+        std::fs::remove_file(lib).unwrap();
+    }
+
+    #[test]
+    fn class_loader_with_manifest_class_path() {
+        let [app, other, lib] = ["chain-app", "chain-other", "chain-lib"].map(temp_jar);
+        let manifest = |it: &Path| {
+            let name = it.file_name().unwrap().to_str().unwrap();
+            format!("Class-Path: {name}\r\n")
+        };
+        write_jar(&app, &[("META-INF/MANIFEST.MF", manifest(&lib).as_bytes())]);
+        write_jar(
+            &other,
+            &[("META-INF/MANIFEST.MF", manifest(&lib).as_bytes())],
+        );
+        write_jar(
+            &lib,
+            &[
+                ("META-INF/MANIFEST.MF", manifest(&app).as_bytes()),
+                ("org/mokapot/Lib.class", &empty_class_with_version(52, 0)),
+            ],
+        );
+
+        let class_loader = ClassLoader::with_manifest_class_path([
+            JarClassPath::new(&app),
+            JarClassPath::new(&other),
+        ])
+        .unwrap();
+        let effective: Vec<_> = class_loader
+            .class_path()
+            .iter()
+            .map(JarClassPath::jar_file)
+            .collect();
+        assert_eq!(effective, [&app, &other, &lib]);
+        assert!(class_loader.load_class("org/mokapot/Lib").is_ok());
+        for it in [app, other, lib] {
+            std::fs::remove_file(it).unwrap();
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! The manifest (`META-INF/MANIFEST.MF`) of a JAR file.
+
+use std::collections::BTreeMap;
+
+/// The path of the manifest in a JAR file.
+pub const MANIFEST_PATH: &str = "META-INF/MANIFEST.MF";
+
+/// An error when parsing a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Malformed manifest at line {line}: {content}")]
+pub struct ParseError {
+    /// The line number (starting from 1).
+    pub line: usize,
+    /// The content of the line.
+    pub content: String,
+}
+
+/// The attributes in a section of a manifest, in the order of their declaration.
+/// The names of the attributes are case-insensitive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attributes(Vec<(String, String)>);
+
+impl Attributes {
+    /// Gets the value of the attribute `name`.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(it, _)| it.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns an iterator over the names and values of the attributes.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Returns the number of attributes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Checks whether there are no attributes.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+/// A parsed manifest.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// The attributes of the main section.
+    pub main_attributes: Attributes,
+    /// The attributes of the per-entry sections, keyed by the names of the entries.
+    pub entries: BTreeMap<String, Attributes>,
+}
+
+impl Manifest {
+    /// Parses a manifest.
+    /// Sections are separated by blank lines, and a line starting with a space continues the
+    /// value of the previous attribute.
+    ///
+    /// # Errors
+    /// See [`ParseError`].
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut sections = Vec::new();
+        let mut section: Vec<(String, String)> = Vec::new();
+        for (idx, line) in text.lines().enumerate() {
+            let error = || ParseError {
+                line: idx + 1,
+                content: line.to_owned(),
+            };
+            if line.is_empty() {
+                // The main section is kept even if it is empty.
+                if !section.is_empty() || sections.is_empty() {
+                    sections.push(std::mem::take(&mut section));
+                }
+            } else if let Some(continuation) = line.strip_prefix(' ') {
+                let (_, value) = section.last_mut().ok_or_else(error)?;
+                value.push_str(continuation);
+            } else {
+                let (name, value) = line.split_once(':').ok_or_else(error)?;
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(error());
+                }
+                section.push((name.to_owned(), value.trim_start().to_owned()));
+            }
+        }
+        if !section.is_empty() || sections.is_empty() {
+            sections.push(section);
+        }
+        let mut sections = sections.into_iter().map(Attributes);
+        let main_attributes = sections.next().unwrap_or_default();
+        let entries = sections
+            .filter_map(|it| Some((it.get("Name")?.to_owned(), it)))
+            .collect();
+        Ok(Self {
+            main_attributes,
+            entries,
+        })
+    }
+
+    /// Gets the attributes of the entry `name`.
+    #[must_use]
+    pub fn entry(&self, name: &str) -> Option<&Attributes> {
+        self.entries.get(name)
+    }
+
+    /// Gets the `Main-Class` attribute as a binary name.
+    #[must_use]
+    pub fn main_class(&self) -> Option<String> {
+        self.main_attributes
+            .get("Main-Class")
+            .map(|it| it.trim().replace('.', "/"))
+    }
+
+    /// Gets the relative URLs in the `Class-Path` attribute.
+    pub fn class_path(&self) -> impl Iterator<Item = &str> {
+        self.main_attributes
+            .get("Class-Path")
+            .into_iter()
+            .flat_map(str::split_whitespace)
+    }
+
+    /// Checks whether the manifest declares a multi-release JAR file.
+    #[must_use]
+    pub fn is_multi_release(&self) -> bool {
+        self.main_attributes
+            .get("Multi-Release")
+            .is_some_and(|it| it.trim().eq_ignore_ascii_case("true"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sections() {
+        let manifest = Manifest::parse(concat!(
+            "Manifest-Version: 1.0\r\n",
+            "Main-Class: org.mokapot.Main\r\n",
+            "Class-Path: lib/a.jar\r\n",
+            "  lib/b.jar\r\n",
+            "multi-release: true\r\n",
+            "\r\n",
+            "Name: org/mokapot/\r\n",
+            "Sealed: true\r\n",
+        ))
+        .unwrap();
+        assert_eq!(manifest.main_class().as_deref(), Some("org/mokapot/Main"));
+        assert_eq!(
+            manifest.class_path().collect::<Vec<_>>(),
+            ["lib/a.jar", "lib/b.jar"]
+        );
+        assert!(manifest.is_multi_release());
+        let entry = manifest.entry("org/mokapot/").unwrap();
+        assert_eq!(entry.get("sealed"), Some("true"));
+        assert_eq!(entry.len(), 2);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            Manifest::parse("Manifest-Version: 1.0\n continued\nbroken\n"),
+            Err(ParseError {
+                line: 3,
+                content: "broken".to_owned()
+            })
+        );
+        assert!(Manifest::parse(" orphan").is_err());
+    }
+}
@@ -30,6 +30,17 @@ pub trait ClassPath {
     /// # Errors
     /// See [`Error`].
     fn find_class(&self, binary_name: &str) -> Result<Class, Error>;
+
+    /// Reads the class file of a class by its binary name.
+    /// The default implementation returns [`Error::NotFound`], which suits the class paths that
+    /// are not backed by class files.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    fn find_class_bytes(&self, binary_name: &str) -> Result<Vec<u8>, Error> {
+        let _ = binary_name;
+        Err(Error::NotFound)
+    }
 }
 
 impl<T> ClassPath for T
@@ -40,10 +51,43 @@ where
     fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
         self.deref().find_class(binary_name)
     }
+
+    fn find_class_bytes(&self, binary_name: &str) -> Result<Vec<u8>, Error> {
+        self.deref().find_class_bytes(binary_name)
+    }
+}
+
+/// The layer of a class loader in a hierarchy of class loaders.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, derive_more::Display)]
+pub enum Layer {
+    /// The bootstrap class loader, which loads the core classes of the JDK.
+    #[display("boot")]
+    Boot,
+    /// The platform class loader, which loads the other modules of the JDK.
+    #[display("platform")]
+    Platform,
+    /// The application class loader, which loads the classes on the class path.
+    #[display("application")]
+    Application,
+    /// A class loader defined by an application.
+    #[display("{_0}")]
+    Custom(String),
+}
+
+/// The class path providing a class and the class loader it belongs to.
+#[derive(Debug)]
+pub struct ClassSource<'a, P> {
+    /// The layer of the class loader, if any.
+    pub layer: Option<&'a Layer>,
+    /// The class path providing the class.
+    pub class_path: &'a P,
+    /// The index of the class path among those of the class loader.
+    pub index: usize,
 }
 
 impl<P> ClassLoader<P> {
-    /// Create a new class loader with the given class paths.
+    /// Loads a class by its binary name.
+    /// The parent class loader, if any, is searched before the class paths of this class loader.
     ///
     /// # Errors
     /// See [`Error`].
@@ -51,21 +95,129 @@ impl<P> ClassLoader<P> {
     where
         P: ClassPath,
     {
-        for class_path in &self.class_path {
+        self.locate(binary_name).map(|(class, _)| class)
+    }
+
+    /// Loads a class by its binary name and returns the class path providing it, following the
+    /// same delegation order as [`ClassLoader::load_class`].
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn locate(&self, binary_name: &str) -> Result<(Class, ClassSource<'_, P>), Error>
+    where
+        P: ClassPath,
+    {
+        if let Some(parent) = &self.parent {
+            match parent.locate(binary_name) {
+                Err(Error::NotFound) => {}
+                result => return result,
+            }
+        }
+        for (index, class_path) in self.class_path.iter().enumerate() {
             match class_path.find_class(binary_name) {
-                Ok(class) => return Ok(class),
-                Err(Error::NotFound) => continue,
+                Ok(class) => {
+                    let source = ClassSource {
+                        layer: self.layer.as_ref(),
+                        class_path,
+                        index,
+                    };
+                    return Ok((class, source));
+                }
+                Err(Error::NotFound) => {}
                 Err(err) => return Err(err),
             }
         }
         Err(Error::NotFound)
     }
 
+    /// Returns all the class paths containing a class with the given binary name, in the order
+    /// they are searched.
+    /// All but the first one are shadowed and never provide the class.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn sources(&self, binary_name: &str) -> Result<Vec<ClassSource<'_, P>>, Error>
+    where
+        P: ClassPath,
+    {
+        let mut sources = match &self.parent {
+            Some(parent) => parent.sources(binary_name)?,
+            None => Vec::new(),
+        };
+        for (index, class_path) in self.class_path.iter().enumerate() {
+            match class_path.find_class(binary_name) {
+                Ok(_) => sources.push(ClassSource {
+                    layer: self.layer.as_ref(),
+                    class_path,
+                    index,
+                }),
+                Err(Error::NotFound) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(sources)
+    }
+
     /// Create a new class loader with the given class paths.
     #[must_use]
     pub fn new<C: IntoIterator<Item = P>>(class_path: C) -> Self {
         let class_path = class_path.into_iter().collect();
-        Self { class_path }
+        Self {
+            class_path,
+            layer: None,
+            parent: None,
+        }
+    }
+
+    /// Creates the hierarchy of the boot, platform, and application class loaders with the
+    /// given class paths, and returns the application class loader.
+    #[must_use]
+    pub fn layered<B, F, A>(boot: B, platform: F, application: A) -> Self
+    where
+        B: IntoIterator<Item = P>,
+        F: IntoIterator<Item = P>,
+        A: IntoIterator<Item = P>,
+    {
+        let boot = Self::new(boot).with_layer(Layer::Boot);
+        let platform = Self::new(platform)
+            .with_layer(Layer::Platform)
+            .with_parent(boot);
+        Self::new(application)
+            .with_layer(Layer::Application)
+            .with_parent(platform)
+    }
+
+    /// Sets the layer of this class loader.
+    #[must_use]
+    pub fn with_layer(mut self, layer: Layer) -> Self {
+        self.layer = Some(layer);
+        self
+    }
+
+    /// Sets the parent of this class loader, to which the loading of classes is delegated first.
+    #[must_use]
+    pub fn with_parent(mut self, parent: Self) -> Self {
+        self.parent = Some(Box::new(parent));
+        self
+    }
+
+    /// Returns the layer of this class loader.
+    #[must_use]
+    pub const fn layer(&self) -> Option<&Layer> {
+        self.layer.as_ref()
+    }
+
+    /// Returns the parent of this class loader.
+    #[must_use]
+    pub fn parent(&self) -> Option<&Self> {
+        self.parent.as_deref()
+    }
+
+    /// Returns the class paths of this class loader, excluding those of its parent, in the order
+    /// they are searched.
+    #[must_use]
+    pub fn class_path(&self) -> &[P] {
+        &self.class_path
     }
 
     /// Convert this class loader into a [`CachingClassLoader`].
@@ -76,7 +228,26 @@ impl<P> ClassLoader<P> {
     }
 }
 
+#[cfg(feature = "jar")]
+impl ClassLoader<class_paths::JarClassPath> {
+    /// Creates a class loader searching the JAR files in `class_path` followed by the JAR files
+    /// they transitively reference in the `Class-Path` attributes of their manifests, as the JVM
+    /// does for the application class path.
+    /// See [`class_paths::expand_manifest_class_path`] for the resulting order.
+    /// The effective class path can be inspected with [`ClassLoader::class_path`].
+    ///
+    /// # Errors
+    /// See [`class_paths::JarClassPath::manifest`].
+    pub fn with_manifest_class_path<C>(class_path: C) -> Result<Self, Error>
+    where
+        C: IntoIterator<Item = class_paths::JarClassPath>,
+    {
+        class_paths::expand_manifest_class_path(class_path).map(Self::new)
+    }
+}
+
 pub mod class_paths;
+pub mod manifest;
 
 /// A class loader that caches loaded classes.
 #[derive(Debug)]
@@ -110,3 +281,86 @@ impl<P> From<ClassLoader<P>> for CachingClassLoader<P> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::jvm::class::Version;
+
+    #[derive(Debug)]
+    struct InMemoryClassPath(HashMap<String, Class>);
+
+    impl InMemoryClassPath {
+        fn new(classes: &[(&str, Version)]) -> Self {
+            let classes = classes
+                .iter()
+                .map(|(name, version)| {
+                    let class = Class {
+                        binary_name: (*name).to_owned(),
+                        version: *version,
+                        ..Default::default()
+                    };
+                    ((*name).to_owned(), class)
+                })
+                .collect();
+            Self(classes)
+        }
+    }
+
+    impl ClassPath for InMemoryClassPath {
+        fn find_class(&self, binary_name: &str) -> Result<Class, Error> {
+            self.0.get(binary_name).cloned().ok_or(Error::NotFound)
+        }
+    }
+
+    #[test]
+    fn parent_delegation() {
+        let class_loader = ClassLoader::layered(
+            [InMemoryClassPath::new(&[(
+                "java/lang/String",
+                Version::Jdk21(false),
+            )])],
+            [],
+            [
+                InMemoryClassPath::new(&[("A", Version::Jdk8)]),
+                InMemoryClassPath::new(&[
+                    ("java/lang/String", Version::Jdk8),
+                    ("A", Version::Jdk11),
+                ]),
+            ],
+        );
+        assert_eq!(class_loader.layer(), Some(&Layer::Application));
+        assert_eq!(
+            class_loader.parent().and_then(ClassLoader::layer),
+            Some(&Layer::Platform)
+        );
+
+        let (string, source) = class_loader.locate("java/lang/String").unwrap();
+        assert_eq!(string.version, Version::Jdk21(false));
+        assert_eq!(source.layer, Some(&Layer::Boot));
+
+        let (a, source) = class_loader.locate("A").unwrap();
+        assert_eq!(a.version, Version::Jdk8);
+        assert_eq!((source.layer, source.index), (Some(&Layer::Application), 0));
+
+        let shadowed: Vec<_> = class_loader
+            .sources("java/lang/String")
+            .unwrap()
+            .into_iter()
+            .map(|it| (it.layer.map(ToString::to_string), it.index))
+            .collect();
+        assert_eq!(
+            shadowed,
+            [
+                (Some("boot".to_owned()), 0),
+                (Some("application".to_owned()), 1)
+            ]
+        );
+        assert!(matches!(
+            class_loader.load_class("Missing"),
+            Err(Error::NotFound)
+        ));
+    }
+}
@@ -0,0 +1,865 @@
+//! An assembler of JVM instructions written in mnemonic text.
+//!
+//! Each non-empty line holds an optional label followed by an instruction, e.g.,
+//! ```text
+//!         iconst_0
+//!         istore_1
+//! loop:   iinc 1 1           // Comments start with `//`.
+//!         iload_1
+//!         bipush 10
+//!         if_icmplt loop
+//!         getstatic java/lang/System.out:Ljava/io/PrintStream;
+//!         ldc "done"
+//!         invokevirtual java/io/PrintStream.println:(Ljava/lang/String;)V
+//!         return
+//! ```
+//!
+//! The operands are written as follows:
+//! - Branch targets are labels.
+//! - Fields and methods are written as `owner.name:descriptor`.
+//! - Classes are binary names (e.g., `java/lang/String`), and the operands of `checkcast`,
+//!   `instanceof`, and `multianewarray` can also be array descriptors (e.g., `[I`).
+//! - Constants of `ldc` are integers (`42`), longs (`42L`), floats (`1.5f`), doubles (`1.5` or
+//!   `1.5d`), strings (`"text"`), classes (`class java/lang/String`), or method types
+//!   (`methodtype (I)V`). `ldc` of a long or a double is assembled to `ldc2_w`.
+//! - `invokeinterface` takes an optional count, which is computed from the descriptor if absent.
+//! - `invokedynamic` takes the index of the bootstrap method followed by `name:descriptor`.
+//! - `tableswitch` takes the lowest key followed by the targets, and `lookupswitch` takes
+//!   `key:target` pairs, both ending with `default target`.
+//! - Local variable indices that do not fit in a byte are assembled to `wide` instructions.
+
+use std::collections::BTreeMap;
+
+use crate::{
+    jvm::{
+        references::{ClassRef, FieldRef, MethodRef},
+        ConstantValue, JavaString,
+    },
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::MethodDescriptor,
+    },
+};
+
+use super::{Instruction, InstructionList, ProgramCounter, WideInstruction};
+
+/// An error when assembling instructions.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Line {line}: {kind}")]
+pub struct Error {
+    /// The line number (starting from 1) where the error occurs.
+    pub line: usize,
+    /// The kind of the error.
+    pub kind: ErrorKind,
+}
+
+/// The kind of an [`Error`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ErrorKind {
+    /// The mnemonic is not recognized.
+    #[error("Unknown mnemonic `{0}`")]
+    UnknownMnemonic(String),
+    /// The instruction has fewer operands than expected.
+    #[error("Missing operand")]
+    MissingOperand,
+    /// The instruction has more operands than expected.
+    #[error("Unexpected operand `{0}`")]
+    UnexpectedOperand(String),
+    /// The operand cannot be parsed.
+    #[error("Invalid operand `{0}`")]
+    InvalidOperand(String),
+    /// The label is defined more than once.
+    #[error("Duplicated label `{0}`")]
+    DuplicateLabel(String),
+    /// The label is not defined.
+    #[error("Undefined label `{0}`")]
+    UndefinedLabel(String),
+    /// The code is longer than 65535 bytes.
+    #[error("The code is too long")]
+    TooLong,
+}
+
+/// Assembles the instructions written in `text`.
+/// See the [module-level documentation](self) for the syntax.
+///
+/// # Errors
+/// See [`Error`].
+pub fn assemble(text: &str) -> Result<InstructionList<Instruction>, Error> {
+    assemble_with_labels(text).map(|(instructions, _)| instructions)
+}
+
+/// Assembles the instructions written in `text`, and returns them with the locations of the
+/// labels.
+pub(crate) fn assemble_with_labels(
+    text: &str,
+) -> Result<
+    (
+        InstructionList<Instruction>,
+        BTreeMap<String, ProgramCounter>,
+    ),
+    Error,
+> {
+    let mut lines = Vec::new();
+    let mut labels = BTreeMap::new();
+    let mut pc = 0u32;
+    for (idx, line) in text.lines().enumerate() {
+        let line_number = idx + 1;
+        let error = |kind| Error {
+            line: line_number,
+            kind,
+        };
+        let mut tokens = tokenize(line).map_err(error)?;
+        while let Some(label) = tokens.first().and_then(|it| it.strip_suffix(':')) {
+            if !is_label(label) {
+                break;
+            }
+            let at = u16::try_from(pc).map_err(|_| error(ErrorKind::TooLong))?;
+            if labels
+                .insert(label.to_owned(), ProgramCounter::from(at))
+                .is_some()
+            {
+                return Err(error(ErrorKind::DuplicateLabel(label.to_owned())));
+            }
+            tokens.remove(0);
+        }
+        if tokens.is_empty() {
+            continue;
+        }
+        let size = instruction_size(&tokens, pc).map_err(error)?;
+        let at = u16::try_from(pc).map_err(|_| error(ErrorKind::TooLong))?;
+        lines.push((line_number, ProgramCounter::from(at), tokens));
+        pc += size;
+    }
+    if pc > u32::from(u16::MAX) {
+        return Err(Error {
+            line: text.lines().count(),
+            kind: ErrorKind::TooLong,
+        });
+    }
+
+    let mut instructions = BTreeMap::new();
+    for (line, pc, tokens) in lines {
+        let instruction = Parser::new(&tokens, &labels)
+            .instruction()
+            .map_err(|kind| Error { line, kind })?;
+        instructions.insert(pc, instruction);
+    }
+    Ok((instructions.into(), labels))
+}
+
+fn is_label(token: &str) -> bool {
+    !token.is_empty()
+        && token
+            .chars()
+            .all(|it| it.is_ascii_alphanumeric() || it == '_' || it == '$')
+}
+
+/// Splits a line into tokens, keeping quoted strings (including the quotes) as single tokens
+/// and dropping the comments.
+fn tokenize(line: &str) -> Result<Vec<String>, ErrorKind> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    let mut current = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                current.push(c);
+                loop {
+                    match chars.next() {
+                        Some('\\') => {
+                            current.push('\\');
+                            current.extend(chars.next());
+                        }
+                        Some('"') => {
+                            current.push('"');
+                            break;
+                        }
+                        Some(it) => current.push(it),
+                        None => return Err(ErrorKind::InvalidOperand(current)),
+                    }
+                }
+            }
+            '/' if current.is_empty() && chars.peek() == Some(&'/') => break,
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+/// Computes the size in bytes of the instruction at `pc`.
+fn instruction_size(tokens: &[String], pc: u32) -> Result<u32, ErrorKind> {
+    let operand = |idx: usize| -> Result<u32, ErrorKind> {
+        let token = tokens.get(idx).ok_or(ErrorKind::MissingOperand)?;
+        token
+            .parse()
+            .map_err(|_| ErrorKind::InvalidOperand(token.clone()))
+    };
+    let padding = 3 - pc % 4;
+    let size = match tokens[0].as_str() {
+        "ldc" => {
+            let no_labels = BTreeMap::new();
+            match Parser::new(tokens, &no_labels).constant()? {
+                ConstantValue::Long(_) | ConstantValue::Double(_) => 3,
+                _ => 2,
+            }
+        }
+        "bipush" | "newarray" => 2,
+        "iload" | "lload" | "fload" | "dload" | "aload" | "istore" | "lstore" | "fstore"
+        | "dstore" | "astore" | "ret" => {
+            if operand(1)? > u32::from(u8::MAX) {
+                4
+            } else {
+                2
+            }
+        }
+        "iinc" => {
+            let delta: i32 = tokens
+                .get(2)
+                .ok_or(ErrorKind::MissingOperand)?
+                .parse()
+                .map_err(|_| ErrorKind::InvalidOperand(tokens[2].clone()))?;
+            if operand(1)? > u32::from(u8::MAX) || i8::try_from(delta).is_err() {
+                6
+            } else {
+                3
+            }
+        }
+        "sipush" | "ldc_w" | "ldc2_w" | "ifeq" | "ifne" | "iflt" | "ifge" | "ifgt" | "ifle"
+        | "if_icmpeq" | "if_icmpne" | "if_icmplt" | "if_icmpge" | "if_icmpgt" | "if_icmple"
+        | "if_acmpeq" | "if_acmpne" | "ifnull" | "ifnonnull" | "goto" | "jsr" | "getstatic"
+        | "putstatic" | "getfield" | "putfield" | "invokevirtual" | "invokespecial"
+        | "invokestatic" | "new" | "anewarray" | "checkcast" | "instanceof" => 3,
+        "multianewarray" => 4,
+        "invokeinterface" | "invokedynamic" | "goto_w" | "jsr_w" => 5,
+        "tableswitch" => {
+            let targets = tokens.len().saturating_sub(4);
+            1 + padding + 12 + 4 * u32::try_from(targets).map_err(|_| ErrorKind::TooLong)?
+        }
+        "lookupswitch" => {
+            let pairs = tokens.len().saturating_sub(3);
+            1 + padding + 8 + 8 * u32::try_from(pairs).map_err(|_| ErrorKind::TooLong)?
+        }
+        mnemonic if simple_instruction(mnemonic).is_some() => 1,
+        mnemonic => return Err(ErrorKind::UnknownMnemonic(mnemonic.to_owned())),
+    };
+    Ok(size)
+}
+
+struct Parser<'a> {
+    mnemonic: &'a str,
+    operands: std::slice::Iter<'a, String>,
+    labels: &'a BTreeMap<String, ProgramCounter>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(tokens: &'a [String], labels: &'a BTreeMap<String, ProgramCounter>) -> Self {
+        Self {
+            mnemonic: &tokens[0],
+            operands: tokens[1..].iter(),
+            labels,
+        }
+    }
+
+    fn next(&mut self) -> Result<&'a str, ErrorKind> {
+        self.operands
+            .next()
+            .map(String::as_str)
+            .ok_or(ErrorKind::MissingOperand)
+    }
+
+    fn parsed<T: std::str::FromStr>(&mut self) -> Result<T, ErrorKind> {
+        let token = self.next()?;
+        token
+            .parse()
+            .map_err(|_| ErrorKind::InvalidOperand(token.to_owned()))
+    }
+
+    fn target(&mut self) -> Result<ProgramCounter, ErrorKind> {
+        let label = self.next()?;
+        self.target_of(label)
+    }
+
+    fn target_of(&self, label: &str) -> Result<ProgramCounter, ErrorKind> {
+        self.labels
+            .get(label)
+            .copied()
+            .ok_or_else(|| ErrorKind::UndefinedLabel(label.to_owned()))
+    }
+
+    fn class(&mut self) -> Result<ClassRef, ErrorKind> {
+        let name = self.next()?;
+        if name.starts_with('[') {
+            // Array classes are written as descriptors (e.g., `[I`).
+            name.parse::<FieldType>()
+                .map_err(|_| ErrorKind::InvalidOperand(name.to_owned()))?;
+        }
+        Ok(ClassRef::new(name))
+    }
+
+    fn field_type(&mut self) -> Result<FieldType, ErrorKind> {
+        let name = self.next()?;
+        if name.starts_with('[') {
+            name.parse()
+                .map_err(|_| ErrorKind::InvalidOperand(name.to_owned()))
+        } else {
+            Ok(FieldType::Object(ClassRef::new(name)))
+        }
+    }
+
+    /// Parses `owner.name:descriptor` into its parts.
+    fn member(&mut self) -> Result<(ClassRef, &'a str, &'a str), ErrorKind> {
+        let token = self.next()?;
+        let invalid = || ErrorKind::InvalidOperand(token.to_owned());
+        let (path, descriptor) = token.split_once(':').ok_or_else(invalid)?;
+        let (owner, name) = path.rsplit_once('.').ok_or_else(invalid)?;
+        if owner.is_empty() || name.is_empty() {
+            return Err(invalid());
+        }
+        Ok((ClassRef::new(owner), name, descriptor))
+    }
+
+    fn field(&mut self) -> Result<FieldRef, ErrorKind> {
+        let (owner, name, descriptor) = self.member()?;
+        Ok(FieldRef::new(
+            owner,
+            name,
+            descriptor
+                .parse()
+                .map_err(|_| ErrorKind::InvalidOperand(descriptor.to_owned()))?,
+        ))
+    }
+
+    fn method(&mut self) -> Result<MethodRef, ErrorKind> {
+        let (owner, name, descriptor) = self.member()?;
+        Ok(MethodRef::new(
+            owner,
+            name,
+            descriptor
+                .parse()
+                .map_err(|_| ErrorKind::InvalidOperand(descriptor.to_owned()))?,
+        ))
+    }
+
+    fn constant(&mut self) -> Result<ConstantValue, ErrorKind> {
+        let token = self.next()?;
+        let invalid = || ErrorKind::InvalidOperand(token.to_owned());
+        let constant = match token {
+            "class" => ConstantValue::Class(self.class()?),
+            "methodtype" => {
+                let descriptor = self.next()?;
+                ConstantValue::MethodType(
+                    descriptor
+                        .parse::<MethodDescriptor>()
+                        .map_err(|_| ErrorKind::InvalidOperand(descriptor.to_owned()))?,
+                )
+            }
+            _ if token.starts_with('"') => {
+                ConstantValue::String(JavaString::Utf8(unescape(token).ok_or_else(invalid)?))
+            }
+            _ => {
+                if let Some(value) = token.strip_suffix(['L', 'l']) {
+                    ConstantValue::Long(value.parse().map_err(|_| invalid())?)
+                } else if let Some(value) = token.strip_suffix(['F', 'f']) {
+                    ConstantValue::Float(value.parse().map_err(|_| invalid())?)
+                } else if let Some(value) = token.strip_suffix(['D', 'd']) {
+                    ConstantValue::Double(value.parse().map_err(|_| invalid())?)
+                } else if token.contains(['.', 'e', 'E']) || ["NaN", "inf", "-inf"].contains(&token)
+                {
+                    ConstantValue::Double(token.parse().map_err(|_| invalid())?)
+                } else {
+                    ConstantValue::Integer(token.parse().map_err(|_| invalid())?)
+                }
+            }
+        };
+        Ok(constant)
+    }
+
+    fn local_variable(
+        &mut self,
+        narrow: fn(u8) -> Instruction,
+        wide: fn(u16) -> WideInstruction,
+    ) -> Result<Instruction, ErrorKind> {
+        let index: u16 = self.parsed()?;
+        Ok(u8::try_from(index).map_or_else(|_| Instruction::Wide(wide(index)), narrow))
+    }
+
+    #[allow(clippy::too_many_lines, reason = "There are many kinds of operands")]
+    fn instruction(mut self) -> Result<Instruction, ErrorKind> {
+        #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+        use Instruction::*;
+
+        let instruction = match self.mnemonic {
+            "bipush" => {
+                let value: i8 = self.parsed()?;
+                BiPush(value.to_be_bytes()[0])
+            }
+            "sipush" => {
+                let value: i16 = self.parsed()?;
+                SiPush(u16::from_be_bytes(value.to_be_bytes()))
+            }
+            "ldc" => match self.constant()? {
+                it @ (ConstantValue::Long(_) | ConstantValue::Double(_)) => Ldc2W(it),
+                it => Ldc(it),
+            },
+            "ldc_w" => LdcW(self.constant()?),
+            "ldc2_w" => Ldc2W(self.constant()?),
+            "iload" => self.local_variable(ILoad, WideInstruction::ILoad)?,
+            "lload" => self.local_variable(LLoad, WideInstruction::LLoad)?,
+            "fload" => self.local_variable(FLoad, WideInstruction::FLoad)?,
+            "dload" => self.local_variable(DLoad, WideInstruction::DLoad)?,
+            "aload" => self.local_variable(ALoad, WideInstruction::ALoad)?,
+            "istore" => self.local_variable(IStore, WideInstruction::IStore)?,
+            "lstore" => self.local_variable(LStore, WideInstruction::LStore)?,
+            "fstore" => self.local_variable(FStore, WideInstruction::FStore)?,
+            "dstore" => self.local_variable(DStore, WideInstruction::DStore)?,
+            "astore" => self.local_variable(AStore, WideInstruction::AStore)?,
+            "ret" => self.local_variable(Ret, WideInstruction::Ret)?,
+            "iinc" => {
+                let index: u16 = self.parsed()?;
+                let delta: i32 = self.parsed()?;
+                match u8::try_from(index) {
+                    Ok(index) if i8::try_from(delta).is_ok() => IInc(index, delta),
+                    _ => Wide(WideInstruction::IInc(index, delta)),
+                }
+            }
+            "ifeq" => IfEq(self.target()?),
+            "ifne" => IfNe(self.target()?),
+            "iflt" => IfLt(self.target()?),
+            "ifge" => IfGe(self.target()?),
+            "ifgt" => IfGt(self.target()?),
+            "ifle" => IfLe(self.target()?),
+            "if_icmpeq" => IfICmpEq(self.target()?),
+            "if_icmpne" => IfICmpNe(self.target()?),
+            "if_icmplt" => IfICmpLt(self.target()?),
+            "if_icmpge" => IfICmpGe(self.target()?),
+            "if_icmpgt" => IfICmpGt(self.target()?),
+            "if_icmple" => IfICmpLe(self.target()?),
+            "if_acmpeq" => IfACmpEq(self.target()?),
+            "if_acmpne" => IfACmpNe(self.target()?),
+            "ifnull" => IfNull(self.target()?),
+            "ifnonnull" => IfNonNull(self.target()?),
+            "goto" => Goto(self.target()?),
+            "goto_w" => GotoW(self.target()?),
+            "jsr" => Jsr(self.target()?),
+            "jsr_w" => JsrW(self.target()?),
+            "tableswitch" => {
+                let low: i32 = self.parsed()?;
+                let mut jump_targets = Vec::new();
+                let default = loop {
+                    match self.next()? {
+                        "default" => break self.target()?,
+                        label => jump_targets.push(self.target_of(label)?),
+                    }
+                };
+                let high = i32::try_from(jump_targets.len())
+                    .ok()
+                    .and_then(|it| (low - 1).checked_add(it))
+                    .ok_or(ErrorKind::TooLong)?;
+                TableSwitch {
+                    range: low..=high,
+                    jump_targets,
+                    default,
+                }
+            }
+            "lookupswitch" => {
+                let mut match_targets = BTreeMap::new();
+                let default = loop {
+                    match self.next()? {
+                        "default" => break self.target()?,
+                        pair => {
+                            let invalid = || ErrorKind::InvalidOperand(pair.to_owned());
+                            let (key, label) = pair.split_once(':').ok_or_else(invalid)?;
+                            let key = key.parse().map_err(|_| invalid())?;
+                            match_targets.insert(key, self.target_of(label)?);
+                        }
+                    }
+                };
+                LookupSwitch {
+                    default,
+                    match_targets,
+                }
+            }
+            "getstatic" => GetStatic(self.field()?),
+            "putstatic" => PutStatic(self.field()?),
+            "getfield" => GetField(self.field()?),
+            "putfield" => PutField(self.field()?),
+            "invokevirtual" => InvokeVirtual(self.method()?),
+            "invokespecial" => InvokeSpecial(self.method()?),
+            "invokestatic" => InvokeStatic(self.method()?),
+            "invokeinterface" => {
+                let method = self.method()?;
+                let count = if self.operands.len() > 0 {
+                    self.parsed()?
+                } else {
+                    let slots: usize = method
+                        .descriptor
+                        .parameters_types
+                        .iter()
+                        .map(|it| match it {
+                            FieldType::Base(PrimitiveType::Long | PrimitiveType::Double) => 2,
+                            _ => 1,
+                        })
+                        .sum();
+                    u8::try_from(slots + 1)
+                        .map_err(|_| ErrorKind::InvalidOperand(method.to_string()))?
+                };
+                InvokeInterface(method, count)
+            }
+            "invokedynamic" => {
+                let bootstrap_method_index = self.parsed()?;
+                let token = self.next()?;
+                let invalid = || ErrorKind::InvalidOperand(token.to_owned());
+                let (name, descriptor) = token.split_once(':').ok_or_else(invalid)?;
+                InvokeDynamic {
+                    bootstrap_method_index,
+                    name: name.to_owned(),
+                    descriptor: descriptor.parse().map_err(|_| invalid())?,
+                }
+            }
+            "new" => New(self.class()?),
+            "anewarray" => ANewArray(self.class()?),
+            "newarray" => {
+                let token = self.next()?;
+                let element_type = match token {
+                    "boolean" => PrimitiveType::Boolean,
+                    "char" => PrimitiveType::Char,
+                    "float" => PrimitiveType::Float,
+                    "double" => PrimitiveType::Double,
+                    "byte" => PrimitiveType::Byte,
+                    "short" => PrimitiveType::Short,
+                    "int" => PrimitiveType::Int,
+                    "long" => PrimitiveType::Long,
+                    _ => return Err(ErrorKind::InvalidOperand(token.to_owned())),
+                };
+                NewArray(element_type)
+            }
+            "checkcast" => CheckCast(self.field_type()?),
+            "instanceof" => InstanceOf(self.field_type()?),
+            "multianewarray" => {
+                let array_type = self.field_type()?;
+                MultiANewArray(array_type, self.parsed()?)
+            }
+            mnemonic => simple_instruction(mnemonic)
+                .ok_or_else(|| ErrorKind::UnknownMnemonic(mnemonic.to_owned()))?,
+        };
+        match self.operands.next() {
+            Some(unexpected) => Err(ErrorKind::UnexpectedOperand(unexpected.clone())),
+            None => Ok(instruction),
+        }
+    }
+}
+
+/// Decodes a quoted string literal with Java escape sequences.
+fn unescape(token: &str) -> Option<String> {
+    let content = token.strip_prefix('"')?.strip_suffix('"')?;
+    let mut chars = content.chars();
+    let mut units = Vec::new();
+    let mut buf = [0u16; 2];
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            units.extend_from_slice(c.encode_utf16(&mut buf));
+            continue;
+        }
+        let escaped = match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            'b' => '\u{8}',
+            'f' => '\u{c}',
+            '0' => '\0',
+            'u' => {
+                let hex: String = chars.by_ref().take(4).collect();
+                units.push(u16::from_str_radix(&hex, 16).ok()?);
+                continue;
+            }
+            it @ ('"' | '\'' | '\\') => it,
+            _ => return None,
+        };
+        units.extend_from_slice(escaped.encode_utf16(&mut buf));
+    }
+    String::from_utf16(&units).ok()
+}
+
+/// Gets the instruction without operands with the given mnemonic.
+#[allow(clippy::too_many_lines)]
+fn simple_instruction(mnemonic: &str) -> Option<Instruction> {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let instruction = match mnemonic {
+        "aaload" => AALoad,
+        "aastore" => AAStore,
+        "aconst_null" => AConstNull,
+        "aload_0" => ALoad0,
+        "aload_1" => ALoad1,
+        "aload_2" => ALoad2,
+        "aload_3" => ALoad3,
+        "areturn" => AReturn,
+        "arraylength" => ArrayLength,
+        "astore_0" => AStore0,
+        "astore_1" => AStore1,
+        "astore_2" => AStore2,
+        "astore_3" => AStore3,
+        "athrow" => AThrow,
+        "baload" => BALoad,
+        "bastore" => BAStore,
+        "caload" => CALoad,
+        "castore" => CAStore,
+        "d2f" => D2F,
+        "d2i" => D2I,
+        "d2l" => D2L,
+        "dadd" => DAdd,
+        "daload" => DALoad,
+        "dastore" => DAStore,
+        "dcmpg" => DCmpG,
+        "dcmpl" => DCmpL,
+        "dconst_0" => DConst0,
+        "dconst_1" => DConst1,
+        "ddiv" => DDiv,
+        "dload_0" => DLoad0,
+        "dload_1" => DLoad1,
+        "dload_2" => DLoad2,
+        "dload_3" => DLoad3,
+        "dmul" => DMul,
+        "dneg" => DNeg,
+        "drem" => DRem,
+        "dreturn" => DReturn,
+        "dstore_0" => DStore0,
+        "dstore_1" => DStore1,
+        "dstore_2" => DStore2,
+        "dstore_3" => DStore3,
+        "dsub" => DSub,
+        "dup" => Dup,
+        "dup_x1" => DupX1,
+        "dup_x2" => DupX2,
+        "dup2" => Dup2,
+        "dup2_x1" => Dup2X1,
+        "dup2_x2" => Dup2X2,
+        "f2d" => F2D,
+        "f2i" => F2I,
+        "f2l" => F2L,
+        "fadd" => FAdd,
+        "faload" => FALoad,
+        "fastore" => FAStore,
+        "fcmpg" => FCmpG,
+        "fcmpl" => FCmpL,
+        "fconst_0" => FConst0,
+        "fconst_1" => FConst1,
+        "fconst_2" => FConst2,
+        "fdiv" => FDiv,
+        "fload_0" => FLoad0,
+        "fload_1" => FLoad1,
+        "fload_2" => FLoad2,
+        "fload_3" => FLoad3,
+        "fmul" => FMul,
+        "fneg" => FNeg,
+        "frem" => FRem,
+        "freturn" => FReturn,
+        "fstore_0" => FStore0,
+        "fstore_1" => FStore1,
+        "fstore_2" => FStore2,
+        "fstore_3" => FStore3,
+        "fsub" => FSub,
+        "i2b" => I2B,
+        "i2c" => I2C,
+        "i2d" => I2D,
+        "i2f" => I2F,
+        "i2l" => I2L,
+        "i2s" => I2S,
+        "iadd" => IAdd,
+        "iaload" => IALoad,
+        "iand" => IAnd,
+        "iastore" => IAStore,
+        "iconst_m1" => IConstM1,
+        "iconst_0" => IConst0,
+        "iconst_1" => IConst1,
+        "iconst_2" => IConst2,
+        "iconst_3" => IConst3,
+        "iconst_4" => IConst4,
+        "iconst_5" => IConst5,
+        "idiv" => IDiv,
+        "iload_0" => ILoad0,
+        "iload_1" => ILoad1,
+        "iload_2" => ILoad2,
+        "iload_3" => ILoad3,
+        "imul" => IMul,
+        "ineg" => INeg,
+        "ior" => IOr,
+        "irem" => IRem,
+        "ireturn" => IReturn,
+        "ishl" => IShl,
+        "ishr" => IShr,
+        "istore_0" => IStore0,
+        "istore_1" => IStore1,
+        "istore_2" => IStore2,
+        "istore_3" => IStore3,
+        "isub" => ISub,
+        "iushr" => IUShr,
+        "ixor" => IXor,
+        "l2d" => L2D,
+        "l2f" => L2F,
+        "l2i" => L2I,
+        "ladd" => LAdd,
+        "laload" => LALoad,
+        "land" => LAnd,
+        "lastore" => LAStore,
+        "lcmp" => LCmp,
+        "lconst_0" => LConst0,
+        "lconst_1" => LConst1,
+        "ldiv" => LDiv,
+        "lload_0" => LLoad0,
+        "lload_1" => LLoad1,
+        "lload_2" => LLoad2,
+        "lload_3" => LLoad3,
+        "lmul" => LMul,
+        "lneg" => LNeg,
+        "lor" => LOr,
+        "lrem" => LRem,
+        "lreturn" => LReturn,
+        "lshl" => LShl,
+        "lshr" => LShr,
+        "lstore_0" => LStore0,
+        "lstore_1" => LStore1,
+        "lstore_2" => LStore2,
+        "lstore_3" => LStore3,
+        "lsub" => LSub,
+        "lushr" => LUShr,
+        "lxor" => LXor,
+        "monitorenter" => MonitorEnter,
+        "monitorexit" => MonitorExit,
+        "nop" => Nop,
+        "pop" => Pop,
+        "pop2" => Pop2,
+        "return" => Return,
+        "saload" => SALoad,
+        "sastore" => SAStore,
+        "swap" => Swap,
+        "breakpoint" => Breakpoint,
+        "impdep1" => ImpDep1,
+        "impdep2" => ImpDep2,
+        _ => return None,
+    };
+    Some(instruction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Instruction::*;
+
+    #[test]
+    fn assemble_with_labels() {
+        let text = r#"
+                iconst_0
+                istore_1
+        loop:   iinc 1 1            // increment
+                iload_1
+                bipush -10
+                if_icmplt loop
+                getstatic java/lang/System.out:Ljava/io/PrintStream;
+                ldc "done\n"
+                invokevirtual java/io/PrintStream.println:(Ljava/lang/String;)V
+                ldc 42L
+                lstore 300
+                return
+        "#;
+        let instructions: Vec<_> = assemble(text).unwrap().into_iter().collect();
+        let print_stream = ClassRef::new("java/io/PrintStream");
+        let expected = vec![
+            (0.into(), IConst0),
+            (1.into(), IStore1),
+            (2.into(), IInc(1, 1)),
+            (5.into(), ILoad1),
+            (6.into(), BiPush(0xF6)),
+            (8.into(), IfICmpLt(2.into())),
+            (
+                11.into(),
+                GetStatic(FieldRef::new(
+                    ClassRef::new("java/lang/System"),
+                    "out",
+                    FieldType::Object(print_stream.clone()),
+                )),
+            ),
+            (
+                14.into(),
+                Ldc(ConstantValue::String(JavaString::Utf8("done\n".to_owned()))),
+            ),
+            (
+                16.into(),
+                InvokeVirtual(MethodRef::new(
+                    print_stream,
+                    "println",
+                    "(Ljava/lang/String;)V".parse().unwrap(),
+                )),
+            ),
+            (19.into(), Ldc2W(ConstantValue::Long(42))),
+            (22.into(), Wide(WideInstruction::LStore(300))),
+            (26.into(), Return),
+        ];
+        assert_eq!(instructions, expected);
+    }
+
+    #[test]
+    fn assemble_switches() {
+        let text = "
+                iload_0
+                tableswitch 1 a b default c
+        a:      lookupswitch -1:a 7:b default c
+        b:      invokeinterface java/util/List.get:(I)Ljava/lang/Object; 
+        c:      invokedynamic 0 run:()Ljava/lang/Runnable;
+        ";
+        let instructions = assemble(text).unwrap();
+        let pcs: Vec<_> = instructions.iter().map(|(pc, _)| u16::from(*pc)).collect();
+        // The tableswitch at 1 is padded to 4, and the lookupswitch at 24 to 28.
+        assert_eq!(pcs, vec![0, 1, 24, 52, 57]);
+        assert_eq!(
+            instructions.get(&1.into()),
+            Some(&TableSwitch {
+                range: 1..=2,
+                jump_targets: vec![24.into(), 52.into()],
+                default: 57.into(),
+            })
+        );
+        assert!(matches!(
+            instructions.get(&52.into()),
+            Some(InvokeInterface(_, 2))
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| assemble(text).unwrap_err();
+        assert_eq!(
+            error("nop\nfoo"),
+            Error {
+                line: 2,
+                kind: ErrorKind::UnknownMnemonic("foo".to_owned()),
+            }
+        );
+        assert_eq!(
+            error("goto nowhere").kind,
+            ErrorKind::UndefinedLabel("nowhere".to_owned())
+        );
+        assert_eq!(
+            error("a: nop\na: nop").kind,
+            ErrorKind::DuplicateLabel("a".to_owned())
+        );
+        assert_eq!(
+            error("return 1").kind,
+            ErrorKind::UnexpectedOperand("1".to_owned())
+        );
+        assert_eq!(error("bipush").kind, ErrorKind::MissingOperand);
+        assert_eq!(
+            error("bipush 200").kind,
+            ErrorKind::InvalidOperand("200".to_owned())
+        );
+    }
+}
@@ -0,0 +1,251 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{Instruction, MethodBody, ProgramCounter, StackMapFrame, WideInstruction};
+
+/// A maximal sequence of instructions that is entered only at the first one and left only at
+/// the last one, except by exceptions.
+#[derive(Debug, Clone)]
+pub struct BasicBlock<'a> {
+    /// The location of the first instruction.
+    pub start: ProgramCounter,
+    /// The location of the last instruction.
+    pub end: ProgramCounter,
+    /// The instructions in the block.
+    pub instructions: Vec<(ProgramCounter, &'a Instruction)>,
+    /// The stack map frame declared at the start of the block, if any.
+    pub frame: Option<&'a StackMapFrame>,
+    /// The indices of the blocks to which the control flow transfers normally.
+    pub successors: Vec<usize>,
+    /// The indices of the blocks handling the exceptions thrown by the instructions in the block.
+    pub handlers: Vec<usize>,
+}
+
+impl MethodBody {
+    /// Partitions the instructions into basic blocks ordered by their locations.
+    ///
+    /// A block starts at
+    /// - the entry of the method,
+    /// - each target of a branch and each exception handler,
+    /// - each instruction following a branch, a `return`, an `athrow`, or a `ret`, and
+    /// - each location where a stack map frame is declared.
+    #[must_use]
+    pub fn basic_blocks(&self) -> Vec<BasicBlock<'_>> {
+        let frames = self.stack_map_frames();
+        let mut leaders = self.block_leaders();
+        leaders.extend(frames.keys());
+        let mut blocks: Vec<BasicBlock<'_>> = Vec::new();
+        for (pc, insn) in &self.instructions {
+            match blocks.last_mut() {
+                Some(block) if !leaders.contains(pc) => {
+                    block.end = *pc;
+                    block.instructions.push((*pc, insn));
+                }
+                _ => blocks.push(BasicBlock {
+                    start: *pc,
+                    end: *pc,
+                    instructions: vec![(*pc, insn)],
+                    frame: frames.get(pc).copied(),
+                    successors: Vec::new(),
+                    handlers: Vec::new(),
+                }),
+            }
+        }
+        let block_of: BTreeMap<_, _> = blocks
+            .iter()
+            .enumerate()
+            .map(|(idx, it)| (it.start, idx))
+            .collect();
+        for block in &mut blocks {
+            let Some((end, last_insn)) = block.instructions.last() else {
+                continue;
+            };
+            block.successors = self
+                .successors_of(*end, last_insn)
+                .iter()
+                .filter_map(|it| block_of.get(it).copied())
+                .collect();
+            block.handlers = self
+                .exception_table
+                .iter()
+                .filter(|entry| block.instructions.iter().any(|(pc, _)| entry.covers(*pc)))
+                .filter_map(|entry| block_of.get(&entry.handler_pc).copied())
+                .fold(Vec::new(), |mut acc, it| {
+                    if !acc.contains(&it) {
+                        acc.push(it);
+                    }
+                    acc
+                });
+        }
+        blocks
+    }
+
+    /// Gets the locations where basic blocks start, excluding the ones only marked by stack map
+    /// frames.
+    pub(super) fn block_leaders(&self) -> BTreeSet<ProgramCounter> {
+        let mut leaders: BTreeSet<_> = self
+            .exception_table
+            .iter()
+            .map(|it| it.handler_pc)
+            .collect();
+        for (pc, insn) in &self.instructions {
+            let targets = insn.branch_targets();
+            if !targets.is_empty() || is_exit(insn) || is_subroutine_return(insn) {
+                leaders.extend(targets);
+                leaders.extend(self.instructions.next_pc_of(pc));
+            }
+        }
+        leaders
+    }
+
+    /// Gets the distinct locations where the control flow continues normally after `insn`.
+    pub(super) fn successors_of(
+        &self,
+        pc: ProgramCounter,
+        insn: &Instruction,
+    ) -> Vec<ProgramCounter> {
+        let next = self.instructions.next_pc_of(&pc);
+        let successors: Vec<_> = match insn {
+            Instruction::Goto(_)
+            | Instruction::GotoW(_)
+            | Instruction::TableSwitch { .. }
+            | Instruction::LookupSwitch { .. } => insn.branch_targets(),
+            _ if is_exit(insn) || is_subroutine_return(insn) => Vec::new(),
+            // A subroutine returns to the instruction following the `jsr`.
+            _ => insn.branch_targets().into_iter().chain(next).collect(),
+        };
+        successors.into_iter().fold(Vec::new(), |mut acc, it| {
+            if !acc.contains(&it) {
+                acc.push(it);
+            }
+            acc
+        })
+    }
+
+    /// Gets the stack map frames keyed by the locations where they are declared.
+    fn stack_map_frames(&self) -> BTreeMap<ProgramCounter, &StackMapFrame> {
+        let mut frames = BTreeMap::new();
+        let mut prev: Option<ProgramCounter> = None;
+        for frame in self.stack_map_table.iter().flatten() {
+            let delta = match frame {
+                StackMapFrame::SameFrame { offset_delta }
+                | StackMapFrame::SameLocals1StackItemFrame { offset_delta, .. }
+                | StackMapFrame::ChopFrame { offset_delta, .. }
+                | StackMapFrame::AppendFrame { offset_delta, .. }
+                | StackMapFrame::FullFrame { offset_delta, .. } => *offset_delta,
+            };
+            // Each frame after the first one is at `offset_delta + 1` after the previous one.
+            let pc = match prev {
+                None => Ok(ProgramCounter::from(delta)),
+                Some(prev) => (prev + delta).and_then(|it| it + 1u16),
+            };
+            let Ok(pc) = pc else {
+                break;
+            };
+            frames.insert(pc, frame);
+            prev = Some(pc);
+        }
+        frames
+    }
+}
+
+pub(super) const fn is_exit(insn: &Instruction) -> bool {
+    matches!(
+        insn,
+        Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::FReturn
+            | Instruction::DReturn
+            | Instruction::AReturn
+            | Instruction::Return
+            | Instruction::AThrow
+    )
+}
+
+const fn is_subroutine_return(insn: &Instruction) -> bool {
+    matches!(
+        insn,
+        Instruction::Ret(_) | Instruction::Wide(WideInstruction::Ret(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    #[test]
+    fn branches_and_handlers() {
+        let class = jasmin::read(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static safeDiv(II)I
+                .limit stack 2
+                .limit locals 2
+                .catch java/lang/ArithmeticException from Begin to End using Handler
+                iload_1
+                ifne Begin
+                iconst_0
+                ireturn
+            Begin:
+                iload_0
+                iload_1
+                idiv
+            End:
+                ireturn
+            Handler:
+                pop
+                iconst_m1
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let body = class.methods[0].body.as_ref().unwrap();
+        let blocks = body.basic_blocks();
+        let starts: Vec<_> = blocks.iter().map(|it| u16::from(it.start)).collect();
+        assert_eq!(starts, vec![0, 4, 6, 10]);
+        assert_eq!(blocks[0].successors, vec![2, 1]);
+        assert!(blocks[1].successors.is_empty());
+        assert_eq!(blocks[2].instructions.len(), 4);
+        assert_eq!(blocks[2].handlers, vec![3]);
+        assert!(blocks[0].handlers.is_empty());
+        assert!(blocks.iter().all(|it| it.frame.is_none()));
+    }
+
+    #[test]
+    fn stack_map_frames() {
+        let class = jasmin::read(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static test()V
+                .limit stack 1
+                .limit locals 1
+                iconst_0
+                istore_0
+                iinc 0 1
+                return
+            .end method
+            ",
+        )
+        .unwrap();
+        let mut body = class.methods[0].body.clone().unwrap();
+        body.stack_map_table = Some(vec![
+            StackMapFrame::SameFrame { offset_delta: 1 },
+            StackMapFrame::SameFrame { offset_delta: 3 },
+        ]);
+        let blocks = body.basic_blocks();
+        let starts: Vec<_> = blocks.iter().map(|it| u16::from(it.start)).collect();
+        assert_eq!(starts, vec![0, 1, 5]);
+        assert_eq!(blocks[0].successors, vec![1]);
+        assert_eq!(blocks[1].successors, vec![2]);
+        assert!(blocks[0].frame.is_none());
+        assert!(matches!(
+            blocks[2].frame,
+            Some(StackMapFrame::SameFrame { offset_delta: 3 })
+        ));
+    }
+}
@@ -0,0 +1,451 @@
+use std::{collections::BTreeMap, ops::Range};
+
+use crate::jvm::{annotation::TargetInfo, TypeAnnotation};
+
+use super::{
+    CharacterRangeTableEntry, ExceptionTableEntry, Instruction, InstructionList,
+    LineNumberTableEntry, LocalVariableId, LocalVariableTable, MethodBody, ProgramCounter,
+    StackMapFrame, VerificationType,
+};
+
+/// Denotes how an attribute depending on the program counters is updated when the instructions
+/// of a [`MethodBody`] are replaced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AttributeUpdate {
+    /// Remaps the attribute to the new instructions.
+    /// The parts that no longer refer to valid locations are removed.
+    #[default]
+    Reconcile,
+    /// Removes the attribute.
+    Drop,
+}
+
+/// Options for replacing the instructions of a [`MethodBody`].
+#[derive(Debug, Clone, Default)]
+pub struct BodyReplacementOptions {
+    /// Maps the program counters in the old instructions to the ones in the new instructions.
+    /// Program counters absent from the mapping are kept unchanged.
+    pub pc_mapping: BTreeMap<ProgramCounter, ProgramCounter>,
+    /// How the `LineNumberTable` attribute is updated.
+    pub line_number_table: AttributeUpdate,
+    /// How the `CharacterRangeTable` attribute is updated.
+    pub character_range_table: AttributeUpdate,
+    /// How the `LocalVariableTable` and `LocalVariableTypeTable` attributes are updated.
+    pub local_variable_table: AttributeUpdate,
+    /// How the `StackMapTable` attribute is updated.
+    /// Since the frames depend on each other, the whole table is dropped if any of its frames
+    /// cannot be remapped.
+    pub stack_map_table: AttributeUpdate,
+    /// How the type annotations on the code are updated.
+    pub type_annotations: AttributeUpdate,
+}
+
+/// An error that occurs when replacing the instructions of a [`MethodBody`].
+#[derive(Debug, thiserror::Error)]
+pub enum BodyReplacementError {
+    /// The method does not have a body.
+    #[error("The method does not have a body")]
+    NoMethodBody,
+    /// The new instruction list is empty.
+    #[error("The instruction list is empty")]
+    EmptyInstructionList,
+    /// An entry of the exception table does not refer to valid locations in the new instructions.
+    #[error("The exception table entry at index {0} does not refer to valid instructions")]
+    InvalidExceptionTableEntry(usize),
+}
+
+impl MethodBody {
+    /// Replaces the instructions of the method body, and updates the exception table and the
+    /// attributes depending on the program counters accordingly.
+    /// Returns the old instructions.
+    /// # Errors
+    /// See [`BodyReplacementError`] for more information.
+    pub fn replace_instructions(
+        &mut self,
+        instructions: InstructionList<Instruction>,
+        options: &BodyReplacementOptions,
+    ) -> Result<InstructionList<Instruction>, BodyReplacementError> {
+        let remapper = Remapper {
+            instructions: &instructions,
+            pc_mapping: &options.pc_mapping,
+        };
+        let exception_table = self
+            .exception_table
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| {
+                remapper
+                    .exception_table_entry(entry)
+                    .ok_or(BodyReplacementError::InvalidExceptionTableEntry(idx))
+            })
+            .collect::<Result<_, _>>()?;
+        if instructions.is_empty() {
+            return Err(BodyReplacementError::EmptyInstructionList);
+        }
+
+        self.exception_table = exception_table;
+        self.line_number_table = match options.line_number_table {
+            AttributeUpdate::Reconcile => self
+                .line_number_table
+                .take()
+                .map(|table| remapper.line_number_table(table)),
+            AttributeUpdate::Drop => None,
+        };
+        self.character_range_table = match options.character_range_table {
+            AttributeUpdate::Reconcile => self
+                .character_range_table
+                .take()
+                .map(|table| remapper.character_range_table(table)),
+            AttributeUpdate::Drop => None,
+        };
+        self.local_variable_table = match options.local_variable_table {
+            AttributeUpdate::Reconcile => self
+                .local_variable_table
+                .take()
+                .map(|table| remapper.local_variable_table(table)),
+            AttributeUpdate::Drop => None,
+        };
+        self.stack_map_table = match options.stack_map_table {
+            AttributeUpdate::Reconcile => self
+                .stack_map_table
+                .take()
+                .and_then(|table| remapper.stack_map_table(table)),
+            AttributeUpdate::Drop => None,
+        };
+        match options.type_annotations {
+            AttributeUpdate::Reconcile => {
+                remapper.type_annotations(&mut self.runtime_visible_type_annotations);
+                remapper.type_annotations(&mut self.runtime_invisible_type_annotations);
+            }
+            AttributeUpdate::Drop => {
+                self.runtime_visible_type_annotations.clear();
+                self.runtime_invisible_type_annotations.clear();
+            }
+        }
+        Ok(std::mem::replace(&mut self.instructions, instructions))
+    }
+}
+
+struct Remapper<'a> {
+    instructions: &'a InstructionList<Instruction>,
+    pc_mapping: &'a BTreeMap<ProgramCounter, ProgramCounter>,
+}
+
+impl Remapper<'_> {
+    fn map(&self, pc: ProgramCounter) -> ProgramCounter {
+        self.pc_mapping.get(&pc).copied().unwrap_or(pc)
+    }
+
+    /// Maps a program counter that must point to an instruction.
+    fn instruction(&self, pc: ProgramCounter) -> Option<ProgramCounter> {
+        let pc = self.map(pc);
+        self.instructions.get(&pc).map(|_| pc)
+    }
+
+    /// Maps a program counter that denotes the exclusive end of a range.
+    /// It may point to an instruction or beyond the last instruction.
+    fn range_end(&self, pc: ProgramCounter) -> Option<ProgramCounter> {
+        let pc = self.map(pc);
+        let (last_pc, _) = self.instructions.last_instruction()?;
+        (pc > *last_pc || self.instructions.get(&pc).is_some()).then_some(pc)
+    }
+
+    fn range(&self, range: &Range<ProgramCounter>) -> Option<Range<ProgramCounter>> {
+        let start = self.instruction(range.start)?;
+        let end = self.range_end(range.end)?;
+        (start < end).then_some(start..end)
+    }
+
+    fn exception_table_entry(&self, entry: &ExceptionTableEntry) -> Option<ExceptionTableEntry> {
+        let start = self.instruction(*entry.covered_pc.start())?;
+        // The end of the covered range is exclusive in the class file.
+        let end = self.range_end(*entry.covered_pc.end())?;
+        let handler_pc = self.instruction(entry.handler_pc)?;
+        (start <= end).then(|| ExceptionTableEntry {
+            covered_pc: start..=end,
+            handler_pc,
+            catch_type: entry.catch_type.clone(),
+        })
+    }
+
+    fn line_number_table(&self, table: Vec<LineNumberTableEntry>) -> Vec<LineNumberTableEntry> {
+        let mut table: Vec<_> = table
+            .into_iter()
+            .filter_map(|entry| {
+                Some(LineNumberTableEntry {
+                    start_pc: self.instruction(entry.start_pc)?,
+                    line_number: entry.line_number,
+                })
+            })
+            .collect();
+        table.sort_by_key(|it| it.start_pc);
+        table
+    }
+
+    fn character_range_table(
+        &self,
+        table: Vec<CharacterRangeTableEntry>,
+    ) -> Vec<CharacterRangeTableEntry> {
+        table
+            .into_iter()
+            .filter_map(|entry| {
+                let start = self.instruction(*entry.pc_range.start())?;
+                // The end of the range is the last byte covered by the range.
+                let end = (*entry.pc_range.end() + 1u16).ok()?;
+                let end = u16::from(self.range_end(end)?).checked_sub(1)?.into();
+                (start <= end).then_some(CharacterRangeTableEntry {
+                    pc_range: start..=end,
+                    ..entry
+                })
+            })
+            .collect()
+    }
+
+    fn local_variable_table(&self, table: LocalVariableTable) -> LocalVariableTable {
+        table
+            .into_iter()
+            .filter_map(|(id, entry)| {
+                let effective_range = self.range(&id.effective_range)?;
+                let id = LocalVariableId {
+                    effective_range,
+                    index: id.index,
+                };
+                Some((id, entry))
+            })
+            .collect()
+    }
+
+    fn stack_map_table(&self, mut table: Vec<StackMapFrame>) -> Option<Vec<StackMapFrame>> {
+        let mut old_pc: Option<ProgramCounter> = None;
+        let mut new_pc: Option<ProgramCounter> = None;
+        for frame in &mut table {
+            let delta = offset_delta(frame);
+            let frame_old_pc = match old_pc {
+                None => ProgramCounter::from(*delta),
+                Some(prev) => (prev + *delta).ok().and_then(|it| (it + 1u16).ok())?,
+            };
+            let frame_new_pc = self.instruction(frame_old_pc)?;
+            *delta = match new_pc {
+                None => frame_new_pc.into(),
+                Some(prev) if prev < frame_new_pc => u16::from(frame_new_pc) - u16::from(prev) - 1,
+                Some(_) => return None,
+            };
+            old_pc = Some(frame_old_pc);
+            new_pc = Some(frame_new_pc);
+            for verification_type in frame.verification_types_mut() {
+                if let VerificationType::UninitializedVariable { offset } = verification_type {
+                    let new_offset = self.instruction(*offset)?;
+                    if !matches!(
+                        self.instructions.get(&new_offset),
+                        Some(Instruction::New(_))
+                    ) {
+                        return None;
+                    }
+                    *offset = new_offset;
+                }
+            }
+        }
+        Some(table)
+    }
+
+    fn type_annotations(&self, annotations: &mut Vec<TypeAnnotation>) {
+        annotations.retain_mut(|annotation| match &mut annotation.target_info {
+            TargetInfo::LocalVar(ids) => {
+                let remapped: Option<Vec<_>> = ids
+                    .iter()
+                    .map(|id| {
+                        Some(LocalVariableId {
+                            effective_range: self.range(&id.effective_range)?,
+                            index: id.index,
+                        })
+                    })
+                    .collect();
+                remapped.map(|it| *ids = it).is_some()
+            }
+            TargetInfo::TypeArgument { offset, .. } => {
+                self.instruction(*offset).map(|it| *offset = it).is_some()
+            }
+            TargetInfo::Offset(offset) => self
+                .instruction(ProgramCounter::from(*offset))
+                .map(|it| *offset = it.into())
+                .is_some(),
+            _ => true,
+        });
+    }
+}
+
+fn offset_delta(frame: &mut StackMapFrame) -> &mut u16 {
+    match frame {
+        StackMapFrame::SameFrame { offset_delta }
+        | StackMapFrame::SameLocals1StackItemFrame { offset_delta, .. }
+        | StackMapFrame::ChopFrame { offset_delta, .. }
+        | StackMapFrame::AppendFrame { offset_delta, .. }
+        | StackMapFrame::FullFrame { offset_delta, .. } => offset_delta,
+    }
+}
+
+impl StackMapFrame {
+    /// Returns the verification types of the locals and the operand stack in the frame.
+    pub(crate) fn verification_types_mut(&mut self) -> Vec<&mut VerificationType> {
+        match self {
+            Self::SameFrame { .. } | Self::ChopFrame { .. } => Vec::default(),
+            Self::SameLocals1StackItemFrame { stack, .. } => vec![stack],
+            Self::AppendFrame { locals, .. } => locals.iter_mut().collect(),
+            Self::FullFrame { locals, stack, .. } => {
+                locals.iter_mut().chain(stack.iter_mut()).collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::code::{CharacterRangeFlags, LocalVariableTableEntry, SourcePosition};
+    use Instruction::{IConst0, Nop, Pop, Return};
+
+    fn body() -> MethodBody {
+        let local_variable_id = LocalVariableId {
+            effective_range: 0.into()..3.into(),
+            index: 0,
+        };
+        MethodBody {
+            max_stack: 1,
+            max_locals: 1,
+            instructions: InstructionList::from([
+                (0.into(), IConst0),
+                (1.into(), Pop),
+                (2.into(), Return),
+            ]),
+            exception_table: vec![ExceptionTableEntry {
+                covered_pc: 0.into()..=2.into(),
+                handler_pc: 2.into(),
+                catch_type: None,
+            }],
+            line_number_table: Some(vec![
+                LineNumberTableEntry {
+                    start_pc: 0.into(),
+                    line_number: 1,
+                },
+                LineNumberTableEntry {
+                    start_pc: 2.into(),
+                    line_number: 2,
+                },
+            ]),
+            character_range_table: Some(vec![CharacterRangeTableEntry {
+                pc_range: 0.into()..=1.into(),
+                start: SourcePosition { line: 1, column: 4 },
+                end: SourcePosition { line: 1, column: 8 },
+                flags: CharacterRangeFlags::STATEMENT,
+            }]),
+            local_variable_table: Some(LocalVariableTable::from_iter([(
+                local_variable_id,
+                LocalVariableTableEntry::default(),
+            )])),
+            stack_map_table: Some(vec![StackMapFrame::SameFrame { offset_delta: 2 }]),
+            runtime_visible_type_annotations: Vec::default(),
+            runtime_invisible_type_annotations: Vec::default(),
+            free_attributes: Vec::default(),
+            custom_attributes: Vec::default(),
+        }
+    }
+
+    fn shifted_instructions() -> InstructionList<Instruction> {
+        InstructionList::from([
+            (0.into(), Nop),
+            (1.into(), IConst0),
+            (2.into(), Pop),
+            (3.into(), Return),
+        ])
+    }
+
+    fn shifted_mapping() -> BTreeMap<ProgramCounter, ProgramCounter> {
+        (0..=3u16).map(|pc| (pc.into(), (pc + 1).into())).collect()
+    }
+
+    #[test]
+    fn reconcile() {
+        let mut body = body();
+        let options = BodyReplacementOptions {
+            pc_mapping: shifted_mapping(),
+            ..Default::default()
+        };
+        let old = body
+            .replace_instructions(shifted_instructions(), &options)
+            .unwrap();
+        assert_eq!(old.len(), 3);
+        assert_eq!(body.instructions.len(), 4);
+
+        let entry = &body.exception_table[0];
+        assert_eq!(entry.covered_pc, 1.into()..=3.into());
+        assert_eq!(entry.handler_pc, 3.into());
+
+        let line_numbers: Vec<_> = body
+            .line_number_table
+            .unwrap()
+            .into_iter()
+            .map(|it| (it.start_pc, it.line_number))
+            .collect();
+        assert_eq!(line_numbers, vec![(1.into(), 1), (3.into(), 2)]);
+        let character_ranges = body.character_range_table.unwrap();
+        assert_eq!(character_ranges[0].pc_range, 1.into()..=2.into());
+
+        let local_variables: Vec<_> = body.local_variable_table.unwrap().into_iter().collect();
+        assert_eq!(local_variables.len(), 1);
+        assert_eq!(local_variables[0].0.effective_range, 1.into()..4.into());
+
+        assert!(matches!(
+            body.stack_map_table.as_deref(),
+            Some([StackMapFrame::SameFrame { offset_delta: 3 }])
+        ));
+    }
+
+    #[test]
+    fn reconcile_removes_dangling_entries() {
+        let mut body = body();
+        let instructions =
+            InstructionList::from([(0.into(), IConst0), (1.into(), Pop), (3.into(), Return)]);
+        let options = BodyReplacementOptions {
+            pc_mapping: BTreeMap::from([(3.into(), 4.into())]),
+            ..Default::default()
+        };
+        body.exception_table.clear();
+        body.replace_instructions(instructions, &options).unwrap();
+        assert_eq!(body.line_number_table.unwrap().len(), 1);
+        let local_variables: Vec<_> = body.local_variable_table.unwrap().into_iter().collect();
+        assert_eq!(local_variables[0].0.effective_range, 0.into()..4.into());
+        assert!(body.stack_map_table.is_none());
+    }
+
+    #[test]
+    fn drop_attributes() {
+        let mut body = body();
+        let options = BodyReplacementOptions {
+            pc_mapping: shifted_mapping(),
+            line_number_table: AttributeUpdate::Drop,
+            character_range_table: AttributeUpdate::Drop,
+            local_variable_table: AttributeUpdate::Drop,
+            stack_map_table: AttributeUpdate::Drop,
+            type_annotations: AttributeUpdate::Drop,
+        };
+        body.replace_instructions(shifted_instructions(), &options)
+            .unwrap();
+        assert!(body.line_number_table.is_none());
+        assert!(body.character_range_table.is_none());
+        assert!(body.local_variable_table.is_none());
+        assert!(body.stack_map_table.is_none());
+    }
+
+    #[test]
+    fn invalid_exception_table() {
+        let mut body = body();
+        let instructions =
+            InstructionList::from([(0.into(), IConst0), (1.into(), Pop), (3.into(), Return)]);
+        let result = body.replace_instructions(instructions, &BodyReplacementOptions::default());
+        assert!(matches!(
+            result,
+            Err(BodyReplacementError::InvalidExceptionTableEntry(0))
+        ));
+        assert_eq!(body.instructions.len(), 3);
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{blocks::is_exit, MethodBody, ProgramCounter};
+
+/// A model of the regions of a method whose execution can be measured by code coverage tools.
+///
+/// The instructions are divided into basic blocks, and probes are placed such that whether each
+/// block is executed can be derived from the probes that fire, similar to `JaCoCo`.
+/// A probe is placed on
+/// - each outgoing edge of a block ending with a conditional branch or a switch,
+/// - each edge entering a block that has multiple predecessors, and
+/// - each block ending with a `return` or an `athrow`.
+///
+/// A block that exits abruptly because an instruction in the middle throws an exception is not
+/// recorded by any probe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageModel {
+    /// The basic blocks ordered by their locations.
+    pub blocks: Vec<CoverageBlock>,
+    /// The probes, where the index of a probe is its ID.
+    pub probes: Vec<Probe>,
+}
+
+/// A basic block in a [`CoverageModel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageBlock {
+    /// The location of the first instruction.
+    pub start: ProgramCounter,
+    /// The location of the last instruction.
+    pub end: ProgramCounter,
+    /// The source lines of the instructions in the block.
+    pub lines: BTreeSet<u16>,
+    /// The indices of the blocks to which the control flow transfers normally.
+    pub successors: Vec<usize>,
+}
+
+/// A probe in a [`CoverageModel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Probe {
+    /// The index of the block where the probe is placed.
+    pub block: usize,
+    /// Where the probe is placed in the block.
+    pub kind: ProbeKind,
+}
+
+/// Where a [`Probe`] is placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeKind {
+    /// The probe fires when a conditional branch or a switch at the end of the block transfers
+    /// the control flow to the block `target`.
+    Branch {
+        /// The index of the target block.
+        target: usize,
+    },
+    /// The probe fires when the control flow falls through or jumps unconditionally to the block
+    /// `target`, which has multiple predecessors.
+    Jump {
+        /// The index of the target block.
+        target: usize,
+    },
+    /// The probe fires when the method returns or throws an exception at the end of the block.
+    Exit,
+}
+
+impl CoverageModel {
+    /// Gets the probes placed at branch points, i.e., on the outgoing edges of conditional
+    /// branches and switches.
+    pub fn branch_probes(&self) -> impl Iterator<Item = (usize, &Probe)> {
+        self.probes
+            .iter()
+            .enumerate()
+            .filter(|(_, it)| matches!(it.kind, ProbeKind::Branch { .. }))
+    }
+
+    /// Gets the indices of the blocks that are executed given the IDs of the probes that fired.
+    #[must_use]
+    pub fn covered_blocks(&self, fired_probes: &BTreeSet<usize>) -> BTreeSet<usize> {
+        let mut covered: BTreeSet<_> = fired_probes
+            .iter()
+            .filter_map(|it| self.probes.get(*it))
+            .map(|it| it.block)
+            .collect();
+        // A block without probes on its only outgoing edge is executed if its successor is.
+        let unprobed_successors: BTreeMap<_, _> = self
+            .blocks
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !self.probes.iter().any(|it| it.block == *idx))
+            .filter_map(|(idx, block)| match block.successors.as_slice() {
+                [successor] => Some((idx, *successor)),
+                _ => None,
+            })
+            .collect();
+        loop {
+            let newly_covered: Vec<_> = unprobed_successors
+                .iter()
+                .filter(|(block, successor)| {
+                    !covered.contains(*block) && covered.contains(*successor)
+                })
+                .map(|(block, _)| *block)
+                .collect();
+            if newly_covered.is_empty() {
+                break covered;
+            }
+            covered.extend(newly_covered);
+        }
+    }
+}
+
+impl MethodBody {
+    /// Builds the [`CoverageModel`] of the body.
+    #[must_use]
+    pub fn coverage_model(&self) -> CoverageModel {
+        let leaders = self.block_leaders();
+        let mut blocks: Vec<CoverageBlock> = Vec::new();
+        let mut block_instructions = Vec::new();
+        for (pc, insn) in &self.instructions {
+            match (blocks.last_mut(), block_instructions.last_mut()) {
+                (Some(block), Some(last_insn)) if !leaders.contains(pc) => {
+                    block.end = *pc;
+                    block.lines.extend(self.line_of(*pc));
+                    *last_insn = insn;
+                }
+                _ => {
+                    blocks.push(CoverageBlock {
+                        start: *pc,
+                        end: *pc,
+                        lines: self.line_of(*pc).into_iter().collect(),
+                        successors: Vec::new(),
+                    });
+                    block_instructions.push(insn);
+                }
+            }
+        }
+        let block_of: BTreeMap<_, _> = blocks
+            .iter()
+            .enumerate()
+            .map(|(idx, it)| (it.start, idx))
+            .collect();
+        for (block, last_insn) in blocks.iter_mut().zip(&block_instructions) {
+            let successors = self.successors_of(block.end, last_insn);
+            block.successors = successors
+                .iter()
+                .filter_map(|it| block_of.get(it).copied())
+                .collect();
+        }
+        let mut predecessor_counts = vec![0usize; blocks.len()];
+        for successor in blocks.iter().flat_map(|it| &it.successors) {
+            predecessor_counts[*successor] += 1;
+        }
+        let mut probes = Vec::new();
+        for (idx, (block, last_insn)) in blocks.iter().zip(&block_instructions).enumerate() {
+            if block.successors.is_empty() {
+                if is_exit(last_insn) {
+                    probes.push(Probe {
+                        block: idx,
+                        kind: ProbeKind::Exit,
+                    });
+                }
+            } else if block.successors.len() > 1 {
+                probes.extend(block.successors.iter().map(|target| Probe {
+                    block: idx,
+                    kind: ProbeKind::Branch { target: *target },
+                }));
+            } else {
+                let target = block.successors[0];
+                if predecessor_counts[target] > 1 {
+                    probes.push(Probe {
+                        block: idx,
+                        kind: ProbeKind::Jump { target },
+                    });
+                }
+            }
+        }
+        CoverageModel { blocks, probes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    #[test]
+    fn branches_and_merge_points() {
+        let class = jasmin::read(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static abs(I)I
+                .limit stack 1
+                .limit locals 1
+                iload_0
+                ifge Positive
+                iload_0
+                ineg
+                istore_0
+            Positive:
+                iload_0
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let model = class.methods[0].body.as_ref().unwrap().coverage_model();
+        assert_eq!(model.blocks.len(), 3);
+        assert_eq!(model.blocks[0].successors, vec![2, 1]);
+        assert_eq!(model.blocks[1].successors, vec![2]);
+        assert_eq!(
+            model.probes,
+            vec![
+                Probe {
+                    block: 0,
+                    kind: ProbeKind::Branch { target: 2 }
+                },
+                Probe {
+                    block: 0,
+                    kind: ProbeKind::Branch { target: 1 }
+                },
+                Probe {
+                    block: 1,
+                    kind: ProbeKind::Jump { target: 2 }
+                },
+                Probe {
+                    block: 2,
+                    kind: ProbeKind::Exit
+                },
+            ]
+        );
+        assert_eq!(model.branch_probes().count(), 2);
+        assert_eq!(
+            model.covered_blocks(&BTreeSet::from([0, 3])),
+            BTreeSet::from([0, 2])
+        );
+        assert_eq!(
+            model.covered_blocks(&BTreeSet::from([1, 2, 3])),
+            BTreeSet::from([0, 1, 2])
+        );
+    }
+}
@@ -0,0 +1,760 @@
+use std::collections::BTreeMap;
+
+use itertools::Itertools;
+
+use crate::{
+    analysis::fixed_point::Analyzer,
+    jvm::{method, references::ClassRef, ConstantValue, Method},
+    types::{
+        field_type::{FieldType, PrimitiveType},
+        method_descriptor::{MethodDescriptor, ReturnType},
+    },
+};
+
+use super::{Instruction, MethodBody, ProgramCounter, VerificationType, WideInstruction};
+
+const OBJECT: &str = "java/lang/Object";
+const THROWABLE: &str = "java/lang/Throwable";
+
+/// The types of the local variables and the values on the operand stack before an instruction
+/// is executed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Frame {
+    /// The types of the local variables indexed by slot.
+    /// A `long` or a `double` takes two slots, the second of which is
+    /// [`VerificationType::TopVariable`].
+    pub locals: Vec<VerificationType>,
+    /// The types of the values on the operand stack from the bottom to the top.
+    /// A `long` or a `double` is a single entry.
+    pub stack: Vec<VerificationType>,
+}
+
+impl Frame {
+    /// Gets the depth of the operand stack in slots, where a `long` or a `double` takes two slots.
+    #[must_use]
+    pub fn stack_depth(&self) -> usize {
+        self.stack.iter().map(slots_of).sum()
+    }
+
+    fn merge(&self, other: &Self) -> Result<Self, FrameInferenceError> {
+        if self.stack.len() != other.stack.len() || self.locals.len() != other.locals.len() {
+            return Err(FrameInferenceError::IncompatibleFrames);
+        }
+        let stack = self
+            .stack
+            .iter()
+            .zip(&other.stack)
+            .map(|(lhs, rhs)| merge_types(lhs, rhs).ok_or(FrameInferenceError::IncompatibleFrames))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            locals: merge_locals(&self.locals, &other.locals),
+            stack,
+        })
+    }
+}
+
+/// An error that occurs when inferring the frames of a method body.
+#[derive(Debug, thiserror::Error)]
+pub enum FrameInferenceError {
+    /// An instruction pops a value from an empty operand stack.
+    #[error("The operand stack underflows at {0}")]
+    StackUnderflow(ProgramCounter),
+    /// The operand stack exceeds the maximum stack size.
+    #[error("The operand stack exceeds the max stack size at {0}")]
+    StackOverflow(ProgramCounter),
+    /// An instruction accesses a local variable beyond the maximum number of local variables.
+    #[error("The local variable {index} at {pc} exceeds the max locals")]
+    InvalidLocal {
+        /// The location of the instruction.
+        pc: ProgramCounter,
+        /// The index of the local variable.
+        index: u16,
+    },
+    /// The frames where the control flow joins have different stack sizes or incompatible
+    /// values on the stack.
+    #[error("The frames at a control flow join are incompatible")]
+    IncompatibleFrames,
+    /// The method uses subroutines (i.e., `jsr` and `ret`), which are not supported.
+    #[error("The subroutine at {0} is not supported")]
+    Subroutine(ProgramCounter),
+    /// The control flow reaches a location that is not an instruction, e.g., falls off the end
+    /// of the method.
+    #[error("The control flow is malformed at {0}")]
+    MalformedControlFlow(ProgramCounter),
+}
+
+impl MethodBody {
+    /// Infers the types of the local variables and the operand stack before each reachable
+    /// instruction, regardless of whether the body has a `StackMapTable` attribute.
+    /// `method` is the method declaring the body, which determines the types of the arguments.
+    /// As the class hierarchy is not available, two different reference types are merged into
+    /// `java/lang/Object` where the control flow joins.
+    /// # Errors
+    /// See [`FrameInferenceError`] for more information.
+    pub fn infer_frames(
+        &self,
+        method: &Method,
+    ) -> Result<BTreeMap<ProgramCounter, Frame>, FrameInferenceError> {
+        FrameInference { body: self, method }.analyze()
+    }
+}
+
+struct FrameInference<'a> {
+    body: &'a MethodBody,
+    method: &'a Method,
+}
+
+impl Analyzer for FrameInference<'_> {
+    type Location = ProgramCounter;
+    type Fact = Frame;
+    type Err = FrameInferenceError;
+    type AffectedLocations = Vec<(ProgramCounter, Frame)>;
+
+    fn entry_fact(&self) -> Result<Self::AffectedLocations, Self::Err> {
+        let entry_pc = *self
+            .body
+            .instructions
+            .entry_point()
+            .ok_or(FrameInferenceError::MalformedControlFlow(
+                ProgramCounter::ZERO,
+            ))?
+            .0;
+        let mut locals = Vec::new();
+        if !self
+            .method
+            .access_flags
+            .contains(method::AccessFlags::STATIC)
+        {
+            let this = if self.method.is_constructor() && &*self.method.owner.binary_name != OBJECT
+            {
+                VerificationType::UninitializedThisVariable
+            } else {
+                VerificationType::ObjectVariable(self.method.owner.clone())
+            };
+            locals.push(this);
+        }
+        for parameter_type in self.method.descriptor.parameters_types.iter() {
+            let parameter_type = verification_type_of(parameter_type);
+            let is_wide = slots_of(&parameter_type) == 2;
+            locals.push(parameter_type);
+            if is_wide {
+                locals.push(VerificationType::TopVariable);
+            }
+        }
+        let max_locals = usize::from(self.body.max_locals);
+        if locals.len() > max_locals {
+            return Err(FrameInferenceError::InvalidLocal {
+                pc: entry_pc,
+                index: u16::try_from(locals.len()).unwrap_or(u16::MAX),
+            });
+        }
+        locals.resize(max_locals, VerificationType::TopVariable);
+        let frame = Frame {
+            locals,
+            stack: Vec::new(),
+        };
+        Ok(vec![(entry_pc, frame)])
+    }
+
+    fn analyze_location(
+        &mut self,
+        location: &Self::Location,
+        fact: &Self::Fact,
+    ) -> Result<Self::AffectedLocations, Self::Err> {
+        let pc = *location;
+        let insn = self
+            .body
+            .instruction_at(pc)
+            .ok_or(FrameInferenceError::MalformedControlFlow(pc))?;
+        let mut execution = Execution {
+            pc,
+            body: self.body,
+            method: self.method,
+            frame: fact.clone(),
+        };
+        let successors = execution.run(insn)?;
+        let mut affected_locations: Vec<_> = successors
+            .into_iter()
+            .map(|it| (it, execution.frame.clone()))
+            .collect();
+        // The handler may observe the local variables either before or after the instruction.
+        let handler_locals = merge_locals(&fact.locals, &execution.frame.locals);
+        let handlers = self
+            .body
+            .exception_table
+            .iter()
+            .filter(|it| it.covers(pc))
+            .into_group_map_by(|it| it.handler_pc);
+        for (handler_pc, entries) in handlers {
+            let exception = entries
+                .into_iter()
+                .map(|it| it.catch_type.clone())
+                .all_equal_value()
+                .ok()
+                .flatten()
+                .unwrap_or_else(|| ClassRef::new(THROWABLE));
+            let frame = Frame {
+                locals: handler_locals.clone(),
+                stack: vec![VerificationType::ObjectVariable(exception)],
+            };
+            affected_locations.push((handler_pc, frame));
+        }
+        Ok(affected_locations)
+    }
+
+    fn merge_facts(
+        &self,
+        current_fact: &Self::Fact,
+        incoming_fact: Self::Fact,
+    ) -> Result<Self::Fact, Self::Err> {
+        current_fact.merge(&incoming_fact)
+    }
+}
+
+struct Execution<'a> {
+    pc: ProgramCounter,
+    body: &'a MethodBody,
+    method: &'a Method,
+    frame: Frame,
+}
+
+#[allow(clippy::too_many_lines)]
+impl Execution<'_> {
+    /// Executes `insn` on the frame and returns the locations to which the control flow
+    /// transfers normally.
+    fn run(&mut self, insn: &Instruction) -> Result<Vec<ProgramCounter>, FrameInferenceError> {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+        use VerificationType::{
+            DoubleVariable as Double, FloatVariable as Float, IntegerVariable as Int,
+            LongVariable as Long,
+        };
+
+        match insn {
+            Nop | Breakpoint | ImpDep1 | ImpDep2 => {}
+            AConstNull => self.push(VerificationType::NullVariable)?,
+            IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5 | BiPush(_)
+            | SiPush(_) => self.push(Int)?,
+            LConst0 | LConst1 => self.push(Long)?,
+            FConst0 | FConst1 | FConst2 => self.push(Float)?,
+            DConst0 | DConst1 => self.push(Double)?,
+            Ldc(constant) | LdcW(constant) | Ldc2W(constant) => {
+                self.push(verification_type_of_constant(constant))?;
+            }
+            ILoad(idx) | LLoad(idx) | FLoad(idx) | DLoad(idx) | ALoad(idx) => {
+                self.load(u16::from(*idx))?;
+            }
+            ILoad0 | LLoad0 | FLoad0 | DLoad0 | ALoad0 => self.load(0)?,
+            ILoad1 | LLoad1 | FLoad1 | DLoad1 | ALoad1 => self.load(1)?,
+            ILoad2 | LLoad2 | FLoad2 | DLoad2 | ALoad2 => self.load(2)?,
+            ILoad3 | LLoad3 | FLoad3 | DLoad3 | ALoad3 => self.load(3)?,
+            IStore(idx) | LStore(idx) | FStore(idx) | DStore(idx) | AStore(idx) => {
+                self.store(u16::from(*idx))?;
+            }
+            IStore0 | LStore0 | FStore0 | DStore0 | AStore0 => self.store(0)?,
+            IStore1 | LStore1 | FStore1 | DStore1 | AStore1 => self.store(1)?,
+            IStore2 | LStore2 | FStore2 | DStore2 | AStore2 => self.store(2)?,
+            IStore3 | LStore3 | FStore3 | DStore3 | AStore3 => self.store(3)?,
+            Wide(wide) => match wide {
+                WideInstruction::ILoad(idx)
+                | WideInstruction::LLoad(idx)
+                | WideInstruction::FLoad(idx)
+                | WideInstruction::DLoad(idx)
+                | WideInstruction::ALoad(idx) => self.load(*idx)?,
+                WideInstruction::IStore(idx)
+                | WideInstruction::LStore(idx)
+                | WideInstruction::FStore(idx)
+                | WideInstruction::DStore(idx)
+                | WideInstruction::AStore(idx) => self.store(*idx)?,
+                WideInstruction::IInc(idx, _) => self.set_local(*idx, Int)?,
+                WideInstruction::Ret(_) => return Err(FrameInferenceError::Subroutine(self.pc)),
+            },
+            AALoad => {
+                self.pop()?;
+                let array = self.pop()?;
+                self.push(element_type_of(&array))?;
+            }
+            IAStore | LAStore | FAStore | DAStore | AAStore | BAStore | CAStore | SAStore => {
+                self.pop_n(3)?;
+            }
+            Pop | PutStatic(_) | MonitorEnter | MonitorExit => {
+                self.pop()?;
+            }
+            Pop2 => {
+                if slots_of(&self.pop()?) == 1 {
+                    self.pop()?;
+                }
+            }
+            Dup => {
+                let value = self.pop()?;
+                self.push_all([value.clone(), value])?;
+            }
+            DupX1 => {
+                let value1 = self.pop()?;
+                let value2 = self.pop()?;
+                self.push_all([value1.clone(), value2, value1])?;
+            }
+            DupX2 => {
+                let value1 = self.pop()?;
+                let value2 = self.pop()?;
+                if slots_of(&value2) == 2 {
+                    self.push_all([value1.clone(), value2, value1])?;
+                } else {
+                    let value3 = self.pop()?;
+                    self.push_all([value1.clone(), value3, value2, value1])?;
+                }
+            }
+            Dup2 => {
+                let value1 = self.pop()?;
+                if slots_of(&value1) == 2 {
+                    self.push_all([value1.clone(), value1])?;
+                } else {
+                    let value2 = self.pop()?;
+                    self.push_all([value2.clone(), value1.clone(), value2, value1])?;
+                }
+            }
+            Dup2X1 => {
+                let value1 = self.pop()?;
+                if slots_of(&value1) == 2 {
+                    let value2 = self.pop()?;
+                    self.push_all([value1.clone(), value2, value1])?;
+                } else {
+                    let value2 = self.pop()?;
+                    let value3 = self.pop()?;
+                    self.push_all([value2.clone(), value1.clone(), value3, value2, value1])?;
+                }
+            }
+            Dup2X2 => {
+                let value1 = self.pop()?;
+                let value2 = self.pop()?;
+                match (slots_of(&value1), slots_of(&value2)) {
+                    (2, 2) => self.push_all([value1.clone(), value2, value1])?,
+                    (2, _) => {
+                        let value3 = self.pop()?;
+                        self.push_all([value1.clone(), value3, value2, value1])?;
+                    }
+                    _ => {
+                        let value3 = self.pop()?;
+                        if slots_of(&value3) == 2 {
+                            self.push_all([
+                                value2.clone(),
+                                value1.clone(),
+                                value3,
+                                value2,
+                                value1,
+                            ])?;
+                        } else {
+                            let value4 = self.pop()?;
+                            self.push_all([
+                                value2.clone(),
+                                value1.clone(),
+                                value4,
+                                value3,
+                                value2,
+                                value1,
+                            ])?;
+                        }
+                    }
+                }
+            }
+            Swap => {
+                let value1 = self.pop()?;
+                let value2 = self.pop()?;
+                self.push_all([value1, value2])?;
+            }
+            IALoad | BALoad | CALoad | SALoad | IAdd | ISub | IMul | IDiv | IRem | IShl | IShr
+            | IUShr | IAnd | IOr | IXor | LCmp | FCmpL | FCmpG | DCmpL | DCmpG => {
+                self.pop_push(2, Int)?;
+            }
+            LALoad | LAdd | LSub | LMul | LDiv | LRem | LShl | LShr | LUShr | LAnd | LOr | LXor => {
+                self.pop_push(2, Long)?;
+            }
+            FALoad | FAdd | FSub | FMul | FDiv | FRem => self.pop_push(2, Float)?,
+            DALoad | DAdd | DSub | DMul | DDiv | DRem => self.pop_push(2, Double)?,
+            INeg | L2I | F2I | D2I | I2B | I2C | I2S | ArrayLength | InstanceOf(_) => {
+                self.pop_push(1, Int)?;
+            }
+            LNeg | I2L | F2L | D2L => self.pop_push(1, Long)?,
+            FNeg | I2F | L2F | D2F => self.pop_push(1, Float)?,
+            DNeg | I2D | L2D | F2D => self.pop_push(1, Double)?,
+            IInc(idx, _) => self.set_local(u16::from(*idx), Int)?,
+            IfEq(target) | IfNe(target) | IfLt(target) | IfGe(target) | IfGt(target)
+            | IfLe(target) | IfNull(target) | IfNonNull(target) => {
+                self.pop()?;
+                return Ok(vec![*target, self.next_pc()?]);
+            }
+            IfICmpEq(target) | IfICmpNe(target) | IfICmpLt(target) | IfICmpGe(target)
+            | IfICmpGt(target) | IfICmpLe(target) | IfACmpEq(target) | IfACmpNe(target) => {
+                self.pop_n(2)?;
+                return Ok(vec![*target, self.next_pc()?]);
+            }
+            Goto(target) | GotoW(target) => return Ok(vec![*target]),
+            Jsr(_) | JsrW(_) | Ret(_) => return Err(FrameInferenceError::Subroutine(self.pc)),
+            TableSwitch {
+                jump_targets,
+                default,
+                ..
+            } => {
+                self.pop()?;
+                return Ok(jump_targets.iter().chain([default]).copied().collect());
+            }
+            LookupSwitch {
+                default,
+                match_targets,
+            } => {
+                self.pop()?;
+                return Ok(match_targets.values().chain([default]).copied().collect());
+            }
+            IReturn | LReturn | FReturn | DReturn | AReturn | Return | AThrow => {
+                return Ok(Vec::new());
+            }
+            GetStatic(field) => self.push(verification_type_of(&field.field_type))?,
+            GetField(field) => self.pop_push(1, verification_type_of(&field.field_type))?,
+            PutField(_) => self.pop_n(2)?,
+            InvokeStatic(method) => self.invoke(&method.descriptor, false)?,
+            InvokeVirtual(method) | InvokeInterface(method, _) => {
+                self.invoke(&method.descriptor, true)?;
+            }
+            InvokeSpecial(method) => {
+                if method.is_constructor() {
+                    self.pop_n(method.descriptor.parameters_types.len())?;
+                    let receiver = self.pop()?;
+                    self.initialize(&receiver)?;
+                } else {
+                    self.invoke(&method.descriptor, true)?;
+                }
+            }
+            InvokeDynamic { descriptor, .. } => self.invoke(descriptor, false)?,
+            New(_) => self.push(VerificationType::UninitializedVariable { offset: self.pc })?,
+            NewArray(primitive_type) => {
+                let array_type = FieldType::Base(*primitive_type).into_array_type();
+                self.pop_push(1, verification_type_of(&array_type))?;
+            }
+            ANewArray(class) => {
+                let array_type = field_type_of(class).into_array_type();
+                self.pop_push(1, verification_type_of(&array_type))?;
+            }
+            CheckCast(target_type) => self.pop_push(1, verification_type_of(target_type))?,
+            MultiANewArray(array_type, dimensions) => {
+                self.pop_push(usize::from(*dimensions), verification_type_of(array_type))?;
+            }
+        }
+        Ok(vec![self.next_pc()?])
+    }
+
+    fn next_pc(&self) -> Result<ProgramCounter, FrameInferenceError> {
+        self.body
+            .instructions
+            .next_pc_of(&self.pc)
+            .ok_or(FrameInferenceError::MalformedControlFlow(self.pc))
+    }
+
+    fn push(&mut self, value: VerificationType) -> Result<(), FrameInferenceError> {
+        self.frame.stack.push(value);
+        if self.frame.stack_depth() > usize::from(self.body.max_stack) {
+            return Err(FrameInferenceError::StackOverflow(self.pc));
+        }
+        Ok(())
+    }
+
+    fn push_all<const N: usize>(
+        &mut self,
+        values: [VerificationType; N],
+    ) -> Result<(), FrameInferenceError> {
+        values.into_iter().try_for_each(|it| self.push(it))
+    }
+
+    fn pop(&mut self) -> Result<VerificationType, FrameInferenceError> {
+        self.frame
+            .stack
+            .pop()
+            .ok_or(FrameInferenceError::StackUnderflow(self.pc))
+    }
+
+    fn pop_n(&mut self, count: usize) -> Result<(), FrameInferenceError> {
+        (0..count).try_for_each(|_| self.pop().map(|_| ()))
+    }
+
+    fn pop_push(
+        &mut self,
+        count: usize,
+        value: VerificationType,
+    ) -> Result<(), FrameInferenceError> {
+        self.pop_n(count)?;
+        self.push(value)
+    }
+
+    fn invoke(
+        &mut self,
+        descriptor: &MethodDescriptor,
+        has_receiver: bool,
+    ) -> Result<(), FrameInferenceError> {
+        self.pop_n(descriptor.parameters_types.len() + usize::from(has_receiver))?;
+        if let ReturnType::Some(return_type) = &descriptor.return_type {
+            self.push(verification_type_of(return_type))?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the uninitialized `receiver` with the initialized object after its constructor
+    /// is called.
+    fn initialize(&mut self, receiver: &VerificationType) -> Result<(), FrameInferenceError> {
+        let class = match receiver {
+            VerificationType::UninitializedThisVariable => self.method.owner.clone(),
+            VerificationType::UninitializedVariable { offset } => {
+                match self.body.instruction_at(*offset) {
+                    Some(Instruction::New(class)) => class.clone(),
+                    _ => return Err(FrameInferenceError::MalformedControlFlow(self.pc)),
+                }
+            }
+            _ => return Ok(()),
+        };
+        let initialized = VerificationType::ObjectVariable(class);
+        self.frame
+            .stack
+            .iter_mut()
+            .chain(&mut self.frame.locals)
+            .filter(|it| *it == receiver)
+            .for_each(|it| *it = initialized.clone());
+        Ok(())
+    }
+
+    fn local_index(&self, index: u16, slots: usize) -> Result<usize, FrameInferenceError> {
+        let idx = usize::from(index);
+        if idx + slots > self.frame.locals.len() {
+            return Err(FrameInferenceError::InvalidLocal { pc: self.pc, index });
+        }
+        Ok(idx)
+    }
+
+    fn load(&mut self, index: u16) -> Result<(), FrameInferenceError> {
+        let idx = self.local_index(index, 1)?;
+        let value = self.frame.locals[idx].clone();
+        self.push(value)
+    }
+
+    fn store(&mut self, index: u16) -> Result<(), FrameInferenceError> {
+        let value = self.pop()?;
+        self.set_local(index, value)
+    }
+
+    fn set_local(
+        &mut self,
+        index: u16,
+        value: VerificationType,
+    ) -> Result<(), FrameInferenceError> {
+        let slots = slots_of(&value);
+        let idx = self.local_index(index, slots)?;
+        // Overwriting the second half of a `long` or a `double` invalidates the first half.
+        if let Some(previous) = idx.checked_sub(1).map(|it| &mut self.frame.locals[it]) {
+            if slots_of(previous) == 2 {
+                *previous = VerificationType::TopVariable;
+            }
+        }
+        // Overwriting the first half of a `long` or a `double` invalidates the second half.
+        if slots_of(&self.frame.locals[idx]) == 2 && slots == 1 {
+            if let Some(next) = self.frame.locals.get_mut(idx + 1) {
+                *next = VerificationType::TopVariable;
+            }
+        }
+        self.frame.locals[idx] = value;
+        if slots == 2 {
+            self.frame.locals[idx + 1] = VerificationType::TopVariable;
+        }
+        Ok(())
+    }
+}
+
+fn slots_of(value: &VerificationType) -> usize {
+    match value {
+        VerificationType::LongVariable | VerificationType::DoubleVariable => 2,
+        _ => 1,
+    }
+}
+
+/// Merges the types of the same value from two frames, or returns [`None`] if they are
+/// incompatible.
+fn merge_types(lhs: &VerificationType, rhs: &VerificationType) -> Option<VerificationType> {
+    use VerificationType::{NullVariable, ObjectVariable};
+    match (lhs, rhs) {
+        _ if lhs == rhs => Some(lhs.clone()),
+        (NullVariable, it @ ObjectVariable(_)) | (it @ ObjectVariable(_), NullVariable) => {
+            Some(it.clone())
+        }
+        (ObjectVariable(_), ObjectVariable(_)) => Some(ObjectVariable(ClassRef::new(OBJECT))),
+        _ => None,
+    }
+}
+
+fn merge_locals(lhs: &[VerificationType], rhs: &[VerificationType]) -> Vec<VerificationType> {
+    lhs.iter()
+        .zip(rhs)
+        .map(|(lhs, rhs)| merge_types(lhs, rhs).unwrap_or(VerificationType::TopVariable))
+        .collect()
+}
+
+fn verification_type_of(field_type: &FieldType) -> VerificationType {
+    match field_type {
+        FieldType::Base(
+            PrimitiveType::Boolean
+            | PrimitiveType::Byte
+            | PrimitiveType::Char
+            | PrimitiveType::Short
+            | PrimitiveType::Int,
+        ) => VerificationType::IntegerVariable,
+        FieldType::Base(PrimitiveType::Float) => VerificationType::FloatVariable,
+        FieldType::Base(PrimitiveType::Long) => VerificationType::LongVariable,
+        FieldType::Base(PrimitiveType::Double) => VerificationType::DoubleVariable,
+        FieldType::Object(class) => VerificationType::ObjectVariable(class.clone()),
+        FieldType::Array(_) => {
+            VerificationType::ObjectVariable(ClassRef::new(field_type.descriptor()))
+        }
+    }
+}
+
+fn verification_type_of_constant(constant: &ConstantValue) -> VerificationType {
+    let object = |name: &str| VerificationType::ObjectVariable(ClassRef::new(name));
+    match constant {
+        ConstantValue::Null => VerificationType::NullVariable,
+        ConstantValue::Integer(_) => VerificationType::IntegerVariable,
+        ConstantValue::Float(_) => VerificationType::FloatVariable,
+        ConstantValue::Long(_) => VerificationType::LongVariable,
+        ConstantValue::Double(_) => VerificationType::DoubleVariable,
+        ConstantValue::String(_) => object("java/lang/String"),
+        ConstantValue::Class(_) => object("java/lang/Class"),
+        ConstantValue::Handle(_) => object("java/lang/invoke/MethodHandle"),
+        ConstantValue::MethodType(_) => object("java/lang/invoke/MethodType"),
+        ConstantValue::Dynamic(constant) => verification_type_of(&constant.descriptor),
+    }
+}
+
+/// Gets the type of a class that may refer to an array type by its descriptor.
+fn field_type_of(class: &ClassRef) -> FieldType {
+    class
+        .binary_name
+        .starts_with('[')
+        .then(|| class.binary_name.parse().ok())
+        .flatten()
+        .unwrap_or_else(|| FieldType::Object(class.clone()))
+}
+
+/// Gets the type of the elements of an array whose type is `array`.
+fn element_type_of(array: &VerificationType) -> VerificationType {
+    match array {
+        VerificationType::ObjectVariable(class) => match field_type_of(class) {
+            FieldType::Array(element_type) => verification_type_of(&element_type),
+            _ => VerificationType::ObjectVariable(ClassRef::new(OBJECT)),
+        },
+        VerificationType::NullVariable => VerificationType::NullVariable,
+        _ => VerificationType::ObjectVariable(ClassRef::new(OBJECT)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    fn frames_of(source: &str, method_name: &str) -> BTreeMap<ProgramCounter, Frame> {
+        let class = jasmin::read(source).unwrap();
+        let method = class
+            .methods
+            .iter()
+            .find(|it| it.name == method_name)
+            .unwrap();
+        method.body.as_ref().unwrap().infer_frames(method).unwrap()
+    }
+
+    #[test]
+    fn straight_line_and_constructor() {
+        let frames = frames_of(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static create(J)Ljava/lang/Object;
+                .limit stack 4
+                .limit locals 3
+                new java/lang/StringBuilder
+                dup
+                invokespecial java/lang/StringBuilder/<init>()V
+                lload_0
+                invokevirtual java/lang/StringBuilder/append(J)Ljava/lang/StringBuilder;
+                areturn
+            .end method
+            ",
+            "create",
+        );
+        let builder = VerificationType::ObjectVariable(ClassRef::new("java/lang/StringBuilder"));
+        let frames: Vec<_> = frames.into_values().collect();
+        assert_eq!(frames.len(), 6);
+        assert_eq!(
+            frames[0].locals,
+            vec![
+                VerificationType::LongVariable,
+                VerificationType::TopVariable,
+                VerificationType::TopVariable
+            ]
+        );
+        assert!(matches!(
+            frames[2].stack.as_slice(),
+            [
+                VerificationType::UninitializedVariable { .. },
+                VerificationType::UninitializedVariable { .. }
+            ]
+        ));
+        assert_eq!(frames[3].stack, vec![builder.clone()]);
+        assert_eq!(
+            frames[4].stack,
+            vec![builder, VerificationType::LongVariable]
+        );
+        assert_eq!(frames[4].stack_depth(), 3);
+    }
+
+    #[test]
+    fn branches_and_handlers() {
+        let frames = frames_of(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static choose(I)Ljava/lang/Object;
+                .limit stack 2
+                .limit locals 2
+                .catch java/lang/RuntimeException from Start to End using Handler
+            Start:
+                iload_0
+                ifeq Else
+                ldc \"string\"
+                astore_1
+                goto End
+            Else:
+                aconst_null
+                astore_1
+            End:
+                aload_1
+                areturn
+            Handler:
+                areturn
+            .end method
+            ",
+            "choose",
+        );
+        let (_, end) = frames
+            .iter()
+            .find(|(_, it)| {
+                it.stack.is_empty()
+                    && it.locals[1]
+                        == VerificationType::ObjectVariable(ClassRef::new("java/lang/String"))
+            })
+            .unwrap();
+        assert_eq!(end.locals[0], VerificationType::IntegerVariable);
+        let (_, handler) = frames.last_key_value().unwrap();
+        assert_eq!(
+            handler.stack,
+            vec![VerificationType::ObjectVariable(ClassRef::new(
+                "java/lang/RuntimeException"
+            ))]
+        );
+    }
+}
@@ -8,11 +8,11 @@ use crate::{
     macros::see_jvm_spec,
     types::{
         field_type::{FieldType, PrimitiveType},
-        method_descriptor::MethodDescriptor,
+        method_descriptor::{MethodDescriptor, ReturnType},
     },
 };
 
-use super::ProgramCounter;
+use super::{Opcode, OperandKind, ProgramCounter, StackEffect};
 
 /// A JVM instruction.
 #[doc = see_jvm_spec!(6, 5)]
@@ -277,6 +277,12 @@ pub enum WideInstruction {
     Ret(u16),
 }
 
+impl From<&Instruction> for Opcode {
+    fn from(instruction: &Instruction) -> Self {
+        instruction.opcode_kind()
+    }
+}
+
 impl Instruction {
     /// Gets the opcode.
     #[must_use]
@@ -294,217 +300,208 @@ impl Instruction {
 
     /// Gets the name of the [Instruction].
     #[must_use]
-    #[allow(clippy::too_many_lines)]
     pub const fn name<'a>(&self) -> &'a str {
+        self.opcode_kind().mnemonic()
+    }
+
+    /// Returns the kinds of the operands following the opcode in the class file.
+    /// See [`Opcode::operand_kinds`] for more information.
+    #[must_use]
+    pub const fn operand_kinds(&self) -> &'static [OperandKind] {
+        self.opcode_kind().operand_kinds()
+    }
+
+    /// Checks whether the instruction may throw an exception or an error when it is executed.
+    /// See [`Opcode::can_throw`] for more information.
+    #[must_use]
+    pub const fn can_throw(&self) -> bool {
+        self.opcode_kind().can_throw()
+    }
+
+    /// Checks whether the instruction jumps to the targets encoded in its operands.
+    /// See [`Opcode::is_branch`] for more information.
+    #[must_use]
+    pub const fn is_branch(&self) -> bool {
+        self.opcode_kind().is_branch()
+    }
+
+    /// Returns the effect of the instruction on the operand stack, where the effects of the
+    /// field accesses and the method invocations are computed from their descriptors.
+    #[must_use]
+    pub fn stack_effect(&self) -> StackEffect {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        let slots = |ty: &FieldType| match ty {
+            FieldType::Base(PrimitiveType::Long | PrimitiveType::Double) => 2u8,
+            _ => 1,
+        };
+        let return_slots = |descriptor: &MethodDescriptor| match &descriptor.return_type {
+            ReturnType::Some(ty) => slots(ty),
+            ReturnType::Void => 0,
+        };
+        let parameter_slots = |descriptor: &MethodDescriptor| {
+            let sum: u32 = descriptor
+                .parameters_types
+                .iter()
+                .map(|it| u32::from(slots(it)))
+                .sum();
+            u8::try_from(sum).unwrap_or(u8::MAX)
+        };
+        if let Some(effect) = self.opcode_kind().stack_effect() {
+            return effect;
+        }
+        let (pops, pushes) = match self {
+            GetStatic(field) => (0, slots(&field.field_type)),
+            PutStatic(field) => (slots(&field.field_type), 0),
+            GetField(field) => (1, slots(&field.field_type)),
+            PutField(field) => (1 + slots(&field.field_type), 0),
+            InvokeVirtual(method) | InvokeSpecial(method) | InvokeInterface(method, _) => (
+                parameter_slots(&method.descriptor).saturating_add(1),
+                return_slots(&method.descriptor),
+            ),
+            InvokeStatic(method) => (
+                parameter_slots(&method.descriptor),
+                return_slots(&method.descriptor),
+            ),
+            InvokeDynamic { descriptor, .. } => {
+                (parameter_slots(descriptor), return_slots(descriptor))
+            }
+            MultiANewArray(_, dimensions) => (*dimensions, 1),
+            Wide(wide) => match wide {
+                WideInstruction::ILoad(_)
+                | WideInstruction::FLoad(_)
+                | WideInstruction::ALoad(_) => (0, 1),
+                WideInstruction::LLoad(_) | WideInstruction::DLoad(_) => (0, 2),
+                WideInstruction::IStore(_)
+                | WideInstruction::FStore(_)
+                | WideInstruction::AStore(_) => (1, 0),
+                WideInstruction::LStore(_) | WideInstruction::DStore(_) => (2, 0),
+                WideInstruction::IInc(_, _) | WideInstruction::Ret(_) => (0, 0),
+            },
+            _ => (0, 0),
+        };
+        StackEffect::new(pops, pushes)
+    }
+
+    /// Returns the locations that the instruction may jump to, excluding the next instruction.
+    #[must_use]
+    pub fn branch_targets(&self) -> Vec<ProgramCounter> {
         #[allow(clippy::enum_glob_use)]
         use Instruction::*;
 
         match self {
-            AALoad => "aaload",
-            AAStore => "aastore",
-            AConstNull => "aconst_null",
-            ALoad(_) => "aload",
-            ALoad0 => "aload_0",
-            ALoad1 => "aload_1",
-            ALoad2 => "aload_2",
-            ALoad3 => "aload_3",
-            ANewArray(_) => "anewarray",
-            AReturn => "areturn",
-            ArrayLength => "arraylength",
-            AStore(_) => "astore",
-            AStore0 => "astore_0",
-            AStore1 => "astore_1",
-            AStore2 => "astore_2",
-            AStore3 => "astore_3",
-            AThrow => "athrow",
-            BALoad => "baload",
-            BAStore => "bastore",
-            BiPush(_) => "bipush",
-            CALoad => "caload",
-            CAStore => "castore",
-            CheckCast(_) => "checkcast",
-            D2F => "d2f",
-            D2I => "d2i",
-            D2L => "d2l",
-            DAdd => "dadd",
-            DALoad => "daload",
-            DAStore => "dastore",
-            DCmpG => "dcmpg",
-            DCmpL => "dcmpl",
-            DConst0 => "dconst_0",
-            DConst1 => "dconst_1",
-            DDiv => "ddiv",
-            DLoad(_) => "dload",
-            DLoad0 => "dload_0",
-            DLoad1 => "dload_1",
-            DLoad2 => "dload_2",
-            DLoad3 => "dload_3",
-            DMul => "dmul",
-            DNeg => "dneg",
-            DRem => "drem",
-            DReturn => "dreturn",
-            DStore(_) => "dstore",
-            DStore0 => "dstore_0",
-            DStore1 => "dstore_1",
-            DStore2 => "dstore_2",
-            DStore3 => "dstore_3",
-            DSub => "dsub",
-            Dup => "dup",
-            DupX1 => "dup_x1",
-            DupX2 => "dup_x2",
-            Dup2 => "dup2",
-            Dup2X1 => "dup2_x1",
-            Dup2X2 => "dup2_x2",
-            F2D => "f2d",
-            F2I => "f2i",
-            F2L => "f2l",
-            FAdd => "fadd",
-            FALoad => "faload",
-            FAStore => "fastore",
-            FCmpG => "fcmpg",
-            FCmpL => "fcmpl",
-            FConst0 => "fconst_0",
-            FConst1 => "fconst_1",
-            FConst2 => "fconst_2",
-            FDiv => "fdiv",
-            FLoad(_) => "fload",
-            FLoad0 => "fload_0",
-            FLoad1 => "fload_1",
-            FLoad2 => "fload_2",
-            FLoad3 => "fload_3",
-            FMul => "fmul",
-            FNeg => "fneg",
-            FRem => "frem",
-            FReturn => "freturn",
-            FStore(_) => "fstore",
-            FStore0 => "fstore_0",
-            FStore1 => "fstore_1",
-            FStore2 => "fstore_2",
-            FStore3 => "fstore_3",
-            FSub => "fsub",
-            GetField(_) => "getfield",
-            GetStatic(_) => "getstatic",
-            Goto(_) => "goto",
-            GotoW(_) => "goto_w",
-            I2B => "i2b",
-            I2C => "i2c",
-            I2D => "i2d",
-            I2F => "i2f",
-            I2L => "i2l",
-            I2S => "i2s",
-            IAdd => "iadd",
-            IALoad => "iaload",
-            IAnd => "iand",
-            IAStore => "iastore",
-            IConstM1 => "iconst_m1",
-            IConst0 => "iconst_0",
-            IConst1 => "iconst_1",
-            IConst2 => "iconst_2",
-            IConst3 => "iconst_3",
-            IConst4 => "iconst_4",
-            IConst5 => "iconst_5",
-            IDiv => "idiv",
-            IfACmpEq(_) => "if_acmpeq",
-            IfACmpNe(_) => "if_acmpne",
-            IfICmpEq(_) => "if_icmpeq",
-            IfICmpNe(_) => "if_icmpne",
-            IfICmpLt(_) => "if_icmplt",
-            IfICmpGe(_) => "if_icmpge",
-            IfICmpGt(_) => "if_icmpgt",
-            IfICmpLe(_) => "if_icmple",
-            IfEq(_) => "ifeq",
-            IfNe(_) => "ifne",
-            IfLt(_) => "iflt",
-            IfGe(_) => "ifge",
-            IfGt(_) => "ifgt",
-            IfLe(_) => "ifle",
-            IfNonNull(_) => "ifnonnull",
-            IfNull(_) => "ifnull",
-            IInc(_, _) => "iinc",
-            ILoad(_) => "iload",
-            ILoad0 => "iload_0",
-            ILoad1 => "iload_1",
-            ILoad2 => "iload_2",
-            ILoad3 => "iload_3",
-            IMul => "imul",
-            INeg => "ineg",
-            InstanceOf(_) => "instanceof",
-            InvokeDynamic { .. } => "invokedynamic",
-            InvokeInterface(_, _) => "invokeinterface",
-            InvokeSpecial(_) => "invokespecial",
-            InvokeStatic(_) => "invokestatic",
-            InvokeVirtual(_) => "invokevirtual",
-            IOr => "ior",
-            IRem => "irem",
-            IReturn => "ireturn",
-            IShl => "ishl",
-            IShr => "ishr",
-            IStore(_) => "istore",
-            IStore0 => "istore_0",
-            IStore1 => "istore_1",
-            IStore2 => "istore_2",
-            IStore3 => "istore_3",
-            ISub => "isub",
-            IUShr => "iushr",
-            IXor => "ixor",
-            Jsr(_) => "jsr",
-            JsrW(_) => "jsr_w",
-            L2D => "l2d",
-            L2F => "l2f",
-            L2I => "l2i",
-            LAdd => "ladd",
-            LALoad => "laload",
-            LAnd => "land",
-            LAStore => "lastore",
-            LCmp => "lcmp",
-            LConst0 => "lconst_0",
-            LConst1 => "lconst_1",
-            Ldc(_) => "ldc",
-            LdcW(_) => "ldc_w",
-            Ldc2W(_) => "ldc2_w",
-            LDiv => "ldiv",
-            LLoad(_) => "lload",
-            LLoad0 => "lload_0",
-            LLoad1 => "lload_1",
-            LLoad2 => "lload_2",
-            LLoad3 => "lload_3",
-            LMul => "lmul",
-            LNeg => "lneg",
-            LookupSwitch { .. } => "lookupswitch",
-            TableSwitch { .. } => "tableswitch",
-            LOr => "lor",
-            LRem => "lrem",
-            LReturn => "lreturn",
-            LShl => "lshl",
-            LShr => "lshr",
-            LStore(_) => "lstore",
-            LStore0 => "lstore_0",
-            LStore1 => "lstore_1",
-            LStore2 => "lstore_2",
-            LStore3 => "lstore_3",
-            LSub => "lsub",
-            LUShr => "lushr",
-            LXor => "lxor",
-            MonitorEnter => "monitorenter",
-            MonitorExit => "monitorexit",
-            MultiANewArray(_, _) => "multianewarray",
-            New(_) => "new",
-            NewArray(_) => "newarray",
-            Nop => "nop",
-            Pop => "pop",
-            Pop2 => "pop2",
-            PutField(_) => "putfield",
-            PutStatic(_) => "putstatic",
-            Ret(_) => "ret",
-            Return => "return",
-            SALoad => "saload",
-            SAStore => "sastore",
-            SiPush(_) => "sipush",
-            Swap => "swap",
-            Wide(_) => "wide",
-            Breakpoint => "breakpoint",
-            ImpDep1 => "impdep1",
-            ImpDep2 => "impdep2",
+            IfEq(target) | IfNe(target) | IfLt(target) | IfGe(target) | IfGt(target)
+            | IfLe(target) | IfICmpEq(target) | IfICmpNe(target) | IfICmpLt(target)
+            | IfICmpGe(target) | IfICmpGt(target) | IfICmpLe(target) | IfACmpEq(target)
+            | IfACmpNe(target) | IfNull(target) | IfNonNull(target) | Goto(target)
+            | GotoW(target) | Jsr(target) | JsrW(target) => vec![*target],
+            TableSwitch {
+                jump_targets,
+                default,
+                ..
+            } => jump_targets.iter().chain([default]).copied().collect(),
+            LookupSwitch {
+                default,
+                match_targets,
+            } => match_targets.values().chain([default]).copied().collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    const fn opcode_kind(&self) -> Opcode {
+        match Opcode::from_u8(self.opcode()) {
+            Some(it) => it,
+            // The discriminants of the variants are the opcodes.
+            None => unreachable!(),
+        }
+    }
+
+    /// Computes the size in bytes of the instruction when it is encoded at `at_pc`.
+    /// The size of `tableswitch` and `lookupswitch` depends on the location because of the
+    /// padding before their operands. See [`ProgramCounter::switch_padding`].
+    #[must_use]
+    pub fn encoded_size(&self, at_pc: ProgramCounter) -> u32 {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        let padding = u32::from(at_pc.switch_padding());
+        match self {
+            BiPush(_) | Ldc(_) | ILoad(_) | LLoad(_) | FLoad(_) | DLoad(_) | ALoad(_)
+            | IStore(_) | LStore(_) | FStore(_) | DStore(_) | AStore(_) | Ret(_) | NewArray(_) => 2,
+            SiPush(_) | LdcW(_) | Ldc2W(_) | IInc(..) | IfEq(_) | IfNe(_) | IfLt(_) | IfGe(_)
+            | IfGt(_) | IfLe(_) | IfICmpEq(_) | IfICmpNe(_) | IfICmpLt(_) | IfICmpGe(_)
+            | IfICmpGt(_) | IfICmpLe(_) | IfACmpEq(_) | IfACmpNe(_) | IfNull(_) | IfNonNull(_)
+            | Goto(_) | Jsr(_) | GetStatic(_) | PutStatic(_) | GetField(_) | PutField(_)
+            | InvokeVirtual(_) | InvokeSpecial(_) | InvokeStatic(_) | New(_) | ANewArray(_)
+            | CheckCast(_) | InstanceOf(_) => 3,
+            Wide(WideInstruction::IInc(..)) => 6,
+            MultiANewArray(..) | Wide(_) => 4,
+            InvokeInterface(..) | InvokeDynamic { .. } | GotoW(_) | JsrW(_) => 5,
+            TableSwitch { jump_targets, .. } => {
+                let targets = u32::try_from(jump_targets.len()).unwrap_or(u32::MAX / 4);
+                1 + padding + 12 + 4 * targets
+            }
+            LookupSwitch { match_targets, .. } => {
+                let pairs = u32::try_from(match_targets.len()).unwrap_or(u32::MAX / 8);
+                1 + padding + 8 + 8 * pairs
+            }
+            _ => 1,
+        }
+    }
+
+    /// Updates the branch targets according to `pc_mapping`.
+    /// The targets absent from `pc_mapping` are left unchanged.
+    pub(crate) fn retarget(&mut self, pc_mapping: &BTreeMap<ProgramCounter, ProgramCounter>) {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        let map = |target: &mut ProgramCounter| {
+            if let Some(new_target) = pc_mapping.get(target) {
+                *target = *new_target;
+            }
+        };
+        match self {
+            IfEq(target) | IfNe(target) | IfLt(target) | IfGe(target) | IfGt(target)
+            | IfLe(target) | IfICmpEq(target) | IfICmpNe(target) | IfICmpLt(target)
+            | IfICmpGe(target) | IfICmpGt(target) | IfICmpLe(target) | IfACmpEq(target)
+            | IfACmpNe(target) | IfNull(target) | IfNonNull(target) | Goto(target)
+            | GotoW(target) | Jsr(target) | JsrW(target) => map(target),
+            TableSwitch {
+                jump_targets,
+                default,
+                ..
+            } => {
+                jump_targets.iter_mut().for_each(map);
+                map(default);
+            }
+            LookupSwitch {
+                default,
+                match_targets,
+            } => {
+                match_targets.values_mut().for_each(map);
+                map(default);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks whether the offsets of the branches fit in the instruction when it is at `pc`,
+    /// i.e., the branches with 16-bit offsets do not jump too far.
+    pub(crate) fn fits_branch_offsets(&self, pc: ProgramCounter) -> bool {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        match self {
+            IfEq(target) | IfNe(target) | IfLt(target) | IfGe(target) | IfGt(target)
+            | IfLe(target) | IfICmpEq(target) | IfICmpNe(target) | IfICmpLt(target)
+            | IfICmpGe(target) | IfICmpGt(target) | IfICmpLe(target) | IfACmpEq(target)
+            | IfACmpNe(target) | IfNull(target) | IfNonNull(target) | Goto(target)
+            | Jsr(target) => {
+                let offset = i32::from(u16::from(*target)) - i32::from(u16::from(pc));
+                i16::try_from(offset).is_ok()
+            }
+            _ => true,
         }
     }
 }
@@ -520,4 +517,25 @@ mod test {
         assert_eq!(IConstM1.opcode(), 0x02);
         assert_eq!(ILoad(233).opcode(), 0x15);
     }
+
+    #[test]
+    fn metadata() {
+        use super::{Opcode, OperandKind, StackEffect};
+        use crate::jvm::references::{ClassRef, MethodRef};
+
+        let invoke = InvokeVirtual(MethodRef::new(
+            ClassRef::new("java/lang/Math"),
+            "scale",
+            "(JI)D".parse().unwrap(),
+        ));
+        assert_eq!(Opcode::from(&invoke), Opcode::InvokeVirtual);
+        assert_eq!(invoke.name(), "invokevirtual");
+        assert_eq!(invoke.stack_effect(), StackEffect::new(4, 2));
+        assert_eq!(invoke.operand_kinds(), &[OperandKind::ConstantPoolIndex]);
+        assert!(invoke.can_throw());
+        assert!(!invoke.is_branch());
+        assert_eq!(IAdd.stack_effect(), StackEffect::new(2, 1));
+        assert!(Goto(4.into()).is_branch());
+        assert_eq!(Goto(4.into()).branch_targets(), vec![4.into()]);
+    }
 }
@@ -0,0 +1,101 @@
+use std::{collections::BTreeSet, ops::Range};
+
+use super::{MethodBody, ProgramCounter};
+
+impl MethodBody {
+    /// Gets the source line of the instruction at `pc`, i.e., the line of the entry in the line
+    /// number table with the greatest start not after `pc`.
+    /// Returns [`None`] if the body does not have a line number table or `pc` is before the
+    /// first entry.
+    #[must_use]
+    pub fn line_of(&self, pc: ProgramCounter) -> Option<u16> {
+        self.line_number_table
+            .as_ref()?
+            .iter()
+            .filter(|it| it.start_pc <= pc)
+            // Among the entries with the same start, the last one in the table wins.
+            .max_by_key(|it| it.start_pc)
+            .map(|it| it.line_number)
+    }
+
+    /// Gets the source lines that have instructions in the body.
+    #[must_use]
+    pub fn lines(&self) -> BTreeSet<u16> {
+        self.line_number_table
+            .iter()
+            .flatten()
+            .map(|it| it.line_number)
+            .collect()
+    }
+
+    /// Gets the ranges of the program counters of the instructions on source line `line`.
+    /// The ranges are sorted and adjacent ranges are merged.
+    #[must_use]
+    pub fn pc_ranges_of_line(&self, line: u16) -> Vec<Range<ProgramCounter>> {
+        let mut ranges: Vec<Range<ProgramCounter>> = Vec::new();
+        let mut current_start = None;
+        for (pc, _) in &self.instructions {
+            let is_on_line = self.line_of(*pc) == Some(line);
+            match (is_on_line, current_start) {
+                (true, None) => current_start = Some(*pc),
+                (false, Some(start)) => {
+                    ranges.push(start..*pc);
+                    current_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = current_start {
+            let end = self
+                .instructions
+                .last_instruction()
+                .and_then(|(pc, _)| (*pc + 1i32).ok())
+                .unwrap_or(start);
+            ranges.push(start..end);
+        }
+        ranges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::code::{Instruction, LineNumberTableEntry};
+
+    #[test]
+    fn line_mapping() {
+        let entry = |start_pc: u16, line_number| LineNumberTableEntry {
+            start_pc: start_pc.into(),
+            line_number,
+        };
+        let body = MethodBody {
+            max_stack: 1,
+            max_locals: 0,
+            instructions: [
+                (0.into(), Instruction::IConst0),
+                (1.into(), Instruction::Pop),
+                (2.into(), Instruction::IConst1),
+                (3.into(), Instruction::Pop),
+                (4.into(), Instruction::Return),
+            ]
+            .into(),
+            exception_table: Vec::new(),
+            line_number_table: Some(vec![entry(2, 11), entry(0, 10), entry(4, 10)]),
+            character_range_table: None,
+            local_variable_table: None,
+            stack_map_table: None,
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        };
+        assert_eq!(body.line_of(1.into()), Some(10));
+        assert_eq!(body.line_of(3.into()), Some(11));
+        assert_eq!(body.lines(), BTreeSet::from([10, 11]));
+        assert_eq!(
+            body.pc_ranges_of_line(10),
+            vec![0.into()..2.into(), 4.into()..5.into()]
+        );
+        assert_eq!(body.pc_ranges_of_line(12), Vec::new());
+    }
+}
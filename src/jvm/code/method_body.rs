@@ -1,11 +1,17 @@
+use bitflags::bitflags;
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::BTreeMap,
     fmt::Display,
     ops::{Bound, Range, RangeInclusive},
 };
 
 use crate::{
-    jvm::{class::ConstantPool, parsing::Error, references::ClassRef, TypeAnnotation},
+    jvm::{
+        class::ConstantPool,
+        parsing::{CustomAttribute, Error},
+        references::ClassRef,
+        TypeAnnotation,
+    },
     macros::{malform, see_jvm_spec},
     types::field_type::FieldType,
 };
@@ -26,6 +32,8 @@ pub struct MethodBody {
     pub exception_table: Vec<ExceptionTableEntry>,
     /// The line number table.
     pub line_number_table: Option<Vec<LineNumberTableEntry>>,
+    /// The character range table emitted by `javac` with `-Xjcov`.
+    pub character_range_table: Option<Vec<CharacterRangeTableEntry>>,
     /// The local variable table.
     pub local_variable_table: Option<LocalVariableTable>,
     /// The stack map table.
@@ -36,6 +44,8 @@ pub struct MethodBody {
     pub runtime_invisible_type_annotations: Vec<TypeAnnotation>,
     /// Unrecognized JVM attributes.
     pub free_attributes: Vec<(String, Vec<u8>)>,
+    /// Custom attributes decoded by an [`AttributeRegistry`](crate::jvm::parsing::AttributeRegistry).
+    pub custom_attributes: Vec<CustomAttribute>,
 }
 
 impl MethodBody {
@@ -120,6 +130,12 @@ impl<I> InstructionList<I> {
         self.0.get(pc)
     }
 
+    /// Returns a mutable reference to the instruction at the given program counter.
+    #[must_use]
+    pub fn get_mut(&mut self, pc: &ProgramCounter) -> Option<&mut I> {
+        self.0.get_mut(pc)
+    }
+
     /// Returns the first instruction in the list.
     #[must_use]
     pub fn entry_point(&self) -> Option<(&ProgramCounter, &I)> {
@@ -178,6 +194,70 @@ impl InstructionList<RawInstruction> {
     }
 }
 
+impl InstructionList<Instruction> {
+    /// Returns the size in bytes of the encoded instructions, i.e., the location right after the
+    /// last instruction.
+    #[must_use]
+    pub fn code_length(&self) -> u32 {
+        self.last_instruction().map_or(0, |(pc, insn)| {
+            u32::from(u16::from(*pc)) + insn.encoded_size(*pc)
+        })
+    }
+
+    /// Lays out the instructions contiguously from program counter `0` in their current order.
+    /// The sizes of `tableswitch` and `lookupswitch` are recomputed at their new locations so that
+    /// their operands stay aligned, and the branch targets are updated accordingly.
+    /// Returns the new instructions and the mapping from the old program counters to the new ones,
+    /// which includes the end of the code and can be passed to
+    /// [`MethodBody::replace_instructions`] via
+    /// [`BodyReplacementOptions::pc_mapping`](super::BodyReplacementOptions::pc_mapping).
+    /// # Errors
+    /// See [`RelayoutError`] for more information.
+    pub fn relayout(
+        self,
+    ) -> Result<(Self, BTreeMap<ProgramCounter, ProgramCounter>), RelayoutError> {
+        let old_end = self.code_length();
+        let mut pc_mapping = BTreeMap::new();
+        let mut laid_out = Vec::with_capacity(self.len());
+        let mut next_pc = 0u32;
+        for (old_pc, insn) in self {
+            let pc = u16::try_from(next_pc)
+                .map(ProgramCounter::from)
+                .map_err(|_| RelayoutError::CodeTooLong)?;
+            next_pc += insn.encoded_size(pc);
+            pc_mapping.insert(old_pc, pc);
+            laid_out.push((pc, insn));
+        }
+        let new_end = u16::try_from(next_pc).map_err(|_| RelayoutError::CodeTooLong)?;
+        if let Ok(old_end) = u16::try_from(old_end) {
+            pc_mapping.insert(old_end.into(), new_end.into());
+        }
+        let instructions = laid_out
+            .into_iter()
+            .map(|(pc, mut insn)| {
+                insn.retarget(&pc_mapping);
+                if insn.fits_branch_offsets(pc) {
+                    Ok((pc, insn))
+                } else {
+                    Err(RelayoutError::BranchTooFar(pc))
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((InstructionList(instructions), pc_mapping))
+    }
+}
+
+/// An error when laying out the instructions of an [`InstructionList`] again.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RelayoutError {
+    /// The code is longer than 65535 bytes.
+    #[error("The code is too long")]
+    CodeTooLong,
+    /// The offset of the branch at the given location does not fit in the instruction.
+    #[error("The branch at {0} is too far from its target")]
+    BranchTooFar(ProgramCounter),
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -200,15 +280,55 @@ mod test {
             max_locals: 0,
             exception_table: vec![],
             line_number_table: None,
+            character_range_table: None,
             local_variable_table: None,
             stack_map_table: None,
             runtime_visible_type_annotations: vec![],
             runtime_invisible_type_annotations: vec![],
             free_attributes: vec![],
+            custom_attributes: vec![],
         };
         assert_eq!(Some(&IConst0), body.instruction_at(1.into()));
     }
 
+    #[test]
+    fn relayout_switch_padding() {
+        let switch = |targets: [u16; 2], default: u16| TableSwitch {
+            range: 0..=1,
+            jump_targets: targets.map(Into::into).to_vec(),
+            default: default.into(),
+        };
+        let instructions = InstructionList::from([
+            (1.into(), ILoad0),
+            (2.into(), switch([24, 25], 25)),
+            (24.into(), IConst0),
+            (25.into(), IReturn),
+        ]);
+        assert_eq!(26, instructions.code_length());
+        let (instructions, pc_mapping) = instructions.relayout().unwrap();
+        assert_eq!(
+            vec![
+                (0.into(), ILoad0),
+                (1.into(), switch([24, 25], 25)),
+                (24.into(), IConst0),
+                (25.into(), IReturn),
+            ],
+            instructions.into_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(Some(&1.into()), pc_mapping.get(&2.into()));
+        assert_eq!(Some(&26.into()), pc_mapping.get(&26.into()));
+
+        let instructions = InstructionList::from([
+            (0.into(), ILoad0),
+            (4.into(), switch([28, 29], 29)),
+            (28.into(), IConst0),
+            (29.into(), IReturn),
+        ]);
+        let (instructions, _) = instructions.relayout().unwrap();
+        assert_eq!(Some(&switch([24, 25], 25)), instructions.get(&1.into()));
+        assert_eq!(26, instructions.code_length());
+    }
+
     #[test]
     fn last_instruction() {
         let instruction_list = InstructionList::from([
@@ -274,13 +394,94 @@ pub struct LineNumberTableEntry {
     pub line_number: u16,
 }
 
+/// An entry in the `CharacterRangeTable` attribute, which maps a range of instructions to a
+/// range of characters in the source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharacterRangeTableEntry {
+    /// The program counters of the instructions in the range.
+    pub pc_range: RangeInclusive<ProgramCounter>,
+    /// The position of the first character in the range.
+    pub start: SourcePosition,
+    /// The position of the last character in the range.
+    pub end: SourcePosition,
+    /// The kind of the source construct covered by the range.
+    pub flags: CharacterRangeFlags,
+}
+
+/// A position in a source file, encoded as `line << 10 | column` in the `CharacterRangeTable`
+/// attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SourcePosition {
+    /// The line number.
+    pub line: u32,
+    /// The column number.
+    pub column: u32,
+}
+
+impl From<u32> for SourcePosition {
+    fn from(value: u32) -> Self {
+        Self {
+            line: value >> 10,
+            column: value & 0x3FF,
+        }
+    }
+}
+
+impl From<SourcePosition> for u32 {
+    fn from(value: SourcePosition) -> Self {
+        value.line << 10 | (value.column & 0x3FF)
+    }
+}
+
+bitflags! {
+    /// The kind of the source construct covered by an entry in the `CharacterRangeTable`.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct CharacterRangeFlags: u16 {
+        /// A statement.
+        const STATEMENT = 0x0001;
+        /// A block.
+        const BLOCK = 0x0002;
+        /// An assignment expression.
+        const ASSIGNMENT = 0x0004;
+        /// An expression controlling the flow (e.g., the condition of an `if`).
+        const FLOW_CONTROLLER = 0x0008;
+        /// A target of the flow (e.g., the body of a loop).
+        const FLOW_TARGET = 0x0010;
+        /// A method invocation.
+        const INVOKE = 0x0020;
+        /// An object or array creation.
+        const CREATE = 0x0040;
+        /// The branch taken if the condition is `true`.
+        const BRANCH_TRUE = 0x0080;
+        /// The branch taken if the condition is `false`.
+        const BRANCH_FALSE = 0x0100;
+    }
+}
+
 /// A local variable table.
 #[derive(Debug, Clone, Default)]
 pub struct LocalVariableTable {
-    entries: HashMap<LocalVariableId, LocalVariableTableEntry>,
+    entries: BTreeMap<LocalVariableId, LocalVariableTableEntry>,
 }
 
 impl LocalVariableTable {
+    /// Returns an iterator over the entries in the table ordered by their [`LocalVariableId`]s.
+    pub fn iter(&self) -> impl Iterator<Item = (&LocalVariableId, &LocalVariableTableEntry)> {
+        self.entries.iter()
+    }
+
+    /// Returns the number of entries in the table.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the table is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
     pub(crate) fn merge_type(
         &mut self,
         key: LocalVariableId,
@@ -316,6 +517,28 @@ impl LocalVariableTable {
     }
 }
 
+impl IntoIterator for LocalVariableTable {
+    type Item = (LocalVariableId, LocalVariableTableEntry);
+
+    // TODO: Replace it with opaque type when it's stable.
+    //       See https://github.com/rust-lang/rust/issues/63063.
+    type IntoIter = <BTreeMap<LocalVariableId, LocalVariableTableEntry> as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl FromIterator<(LocalVariableId, LocalVariableTableEntry)> for LocalVariableTable {
+    fn from_iter<T: IntoIterator<Item = (LocalVariableId, LocalVariableTableEntry)>>(
+        iter: T,
+    ) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
 /// The identifier of a local variable.
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct LocalVariableId {
@@ -325,6 +548,21 @@ pub struct LocalVariableId {
     pub index: u16,
 }
 
+impl PartialOrd for LocalVariableId {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LocalVariableId {
+    /// Orders the identifiers by the start of the effective ranges, then by the end of the
+    /// effective ranges, and finally by the indices.
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        let key = |it: &Self| (it.effective_range.start, it.effective_range.end, it.index);
+        key(self).cmp(&key(other))
+    }
+}
+
 /// An entry in the local variable table.
 #[derive(Debug, Clone, Default)]
 pub struct LocalVariableTableEntry {
@@ -338,7 +576,7 @@ pub struct LocalVariableTableEntry {
 
 /// The type of a value in the stack map table for verification.
 #[doc = see_jvm_spec!(4, 7, 4)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum VerificationType {
     /// Indicates that the local variable has the verification type `top`.
     TopVariable,
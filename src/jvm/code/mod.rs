@@ -1,10 +1,23 @@
 //! Module for the APIs for the executable code in JVM.
+pub mod assembler;
+mod blocks;
+mod body_replacement;
+mod coverage;
+mod frames;
 mod instruction;
+mod lines;
 mod method_body;
+mod opcode;
+pub mod pattern;
 mod pc;
 mod raw_instruction;
 
+pub use blocks::*;
+pub use body_replacement::*;
+pub use coverage::*;
+pub use frames::*;
 pub use instruction::*;
 pub use method_body::*;
+pub use opcode::*;
 pub use pc::*;
 pub use raw_instruction::*;
@@ -0,0 +1,957 @@
+//! Opcodes of JVM instructions and their metadata.
+
+use crate::macros::see_jvm_spec;
+
+/// The opcode of a JVM instruction.
+/// The metadata of the instructions (e.g., their operands and stack effects) are available
+/// uniformly for [`Instruction`](super::Instruction) and [`RawInstruction`](super::RawInstruction)
+/// through their opcodes.
+#[doc = see_jvm_spec!(6, 5)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[allow(missing_docs)]
+#[repr(u8)]
+pub enum Opcode {
+    // Constants
+    Nop = 0x00,
+    AConstNull = 0x01,
+    IConstM1 = 0x02,
+    IConst0 = 0x03,
+    IConst1 = 0x04,
+    IConst2 = 0x05,
+    IConst3 = 0x06,
+    IConst4 = 0x07,
+    IConst5 = 0x08,
+    LConst0 = 0x09,
+    LConst1 = 0x0a,
+    FConst0 = 0x0b,
+    FConst1 = 0x0c,
+    FConst2 = 0x0d,
+    DConst0 = 0x0e,
+    DConst1 = 0x0f,
+    BiPush = 0x10,
+    SiPush = 0x11,
+    Ldc = 0x12,
+    LdcW = 0x13,
+    Ldc2W = 0x14,
+
+    // Loads
+    ILoad = 0x15,
+    LLoad = 0x16,
+    FLoad = 0x17,
+    DLoad = 0x18,
+    ALoad = 0x19,
+    ILoad0 = 0x1a,
+    ILoad1 = 0x1b,
+    ILoad2 = 0x1c,
+    ILoad3 = 0x1d,
+    LLoad0 = 0x1e,
+    LLoad1 = 0x1f,
+    LLoad2 = 0x20,
+    LLoad3 = 0x21,
+    FLoad0 = 0x22,
+    FLoad1 = 0x23,
+    FLoad2 = 0x24,
+    FLoad3 = 0x25,
+    DLoad0 = 0x26,
+    DLoad1 = 0x27,
+    DLoad2 = 0x28,
+    DLoad3 = 0x29,
+    ALoad0 = 0x2a,
+    ALoad1 = 0x2b,
+    ALoad2 = 0x2c,
+    ALoad3 = 0x2d,
+    IALoad = 0x2e,
+    LALoad = 0x2f,
+    FALoad = 0x30,
+    DALoad = 0x31,
+    AALoad = 0x32,
+    BALoad = 0x33,
+    CALoad = 0x34,
+    SALoad = 0x35,
+
+    // Stores
+    IStore = 0x36,
+    LStore = 0x37,
+    FStore = 0x38,
+    DStore = 0x39,
+    AStore = 0x3a,
+    IStore0 = 0x3b,
+    IStore1 = 0x3c,
+    IStore2 = 0x3d,
+    IStore3 = 0x3e,
+    LStore0 = 0x3f,
+    LStore1 = 0x40,
+    LStore2 = 0x41,
+    LStore3 = 0x42,
+    FStore0 = 0x43,
+    FStore1 = 0x44,
+    FStore2 = 0x45,
+    FStore3 = 0x46,
+    DStore0 = 0x47,
+    DStore1 = 0x48,
+    DStore2 = 0x49,
+    DStore3 = 0x4a,
+    AStore0 = 0x4b,
+    AStore1 = 0x4c,
+    AStore2 = 0x4d,
+    AStore3 = 0x4e,
+    IAStore = 0x4f,
+    LAStore = 0x50,
+    FAStore = 0x51,
+    DAStore = 0x52,
+    AAStore = 0x53,
+    BAStore = 0x54,
+    CAStore = 0x55,
+    SAStore = 0x56,
+
+    // Stack
+    Pop = 0x57,
+    Pop2 = 0x58,
+    Dup = 0x59,
+    DupX1 = 0x5a,
+    DupX2 = 0x5b,
+    Dup2 = 0x5c,
+    Dup2X1 = 0x5d,
+    Dup2X2 = 0x5e,
+    Swap = 0x5f,
+
+    // Math
+    IAdd = 0x60,
+    LAdd = 0x61,
+    FAdd = 0x62,
+    DAdd = 0x63,
+    ISub = 0x64,
+    LSub = 0x65,
+    FSub = 0x66,
+    DSub = 0x67,
+    IMul = 0x68,
+    LMul = 0x69,
+    FMul = 0x6a,
+    DMul = 0x6b,
+    IDiv = 0x6c,
+    LDiv = 0x6d,
+    FDiv = 0x6e,
+    DDiv = 0x6f,
+    IRem = 0x70,
+    LRem = 0x71,
+    FRem = 0x72,
+    DRem = 0x73,
+    INeg = 0x74,
+    LNeg = 0x75,
+    FNeg = 0x76,
+    DNeg = 0x77,
+    IShl = 0x78,
+    LShl = 0x79,
+    IShr = 0x7a,
+    LShr = 0x7b,
+    IUShr = 0x7c,
+    LUShr = 0x7d,
+    IAnd = 0x7e,
+    LAnd = 0x7f,
+    IOr = 0x80,
+    LOr = 0x81,
+    IXor = 0x82,
+    LXor = 0x83,
+    IInc = 0x84,
+
+    // Conversions
+    I2L = 0x85,
+    I2F = 0x86,
+    I2D = 0x87,
+    L2I = 0x88,
+    L2F = 0x89,
+    L2D = 0x8a,
+    F2I = 0x8b,
+    F2L = 0x8c,
+    F2D = 0x8d,
+    D2I = 0x8e,
+    D2L = 0x8f,
+    D2F = 0x90,
+    I2B = 0x91,
+    I2C = 0x92,
+    I2S = 0x93,
+
+    // Comparisons
+    LCmp = 0x94,
+    FCmpL = 0x95,
+    FCmpG = 0x96,
+    DCmpL = 0x97,
+    DCmpG = 0x98,
+    IfEq = 0x99,
+    IfNe = 0x9a,
+    IfLt = 0x9b,
+    IfGe = 0x9c,
+    IfGt = 0x9d,
+    IfLe = 0x9e,
+    IfICmpEq = 0x9f,
+    IfICmpNe = 0xa0,
+    IfICmpLt = 0xa1,
+    IfICmpGe = 0xa2,
+    IfICmpGt = 0xa3,
+    IfICmpLe = 0xa4,
+    IfACmpEq = 0xa5,
+    IfACmpNe = 0xa6,
+
+    // Control
+    Goto = 0xa7,
+    Jsr = 0xa8,
+    Ret = 0xa9,
+    TableSwitch = 0xaa,
+    LookupSwitch = 0xab,
+    IReturn = 0xac,
+    LReturn = 0xad,
+    FReturn = 0xae,
+    DReturn = 0xaf,
+    AReturn = 0xb0,
+    Return = 0xb1,
+
+    // References
+    GetStatic = 0xb2,
+    PutStatic = 0xb3,
+    GetField = 0xb4,
+    PutField = 0xb5,
+    InvokeVirtual = 0xb6,
+    InvokeSpecial = 0xb7,
+    InvokeStatic = 0xb8,
+    InvokeInterface = 0xb9,
+    InvokeDynamic = 0xba,
+    New = 0xbb,
+    NewArray = 0xbc,
+    ANewArray = 0xbd,
+    ArrayLength = 0xbe,
+    AThrow = 0xbf,
+    CheckCast = 0xc0,
+    InstanceOf = 0xc1,
+    MonitorEnter = 0xc2,
+    MonitorExit = 0xc3,
+
+    // Extended
+    Wide = 0xc4,
+    MultiANewArray = 0xc5,
+    IfNull = 0xc6,
+    IfNonNull = 0xc7,
+    GotoW = 0xc8,
+    JsrW = 0xc9,
+
+    // Reserved
+    Breakpoint = 0xca,
+    ImpDep1 = 0xfe,
+    ImpDep2 = 0xff,
+}
+
+/// The kind of an operand of an instruction, as encoded in the class file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OperandKind {
+    /// A signed 8-bit integer, e.g., the value of `bipush`.
+    Byte,
+    /// A signed 16-bit integer, e.g., the value of `sipush`.
+    Short,
+    /// The index of a local variable, which is 8-bit or 16-bit if modified by `wide`.
+    LocalVariable,
+    /// The signed increment of `iinc`, which is 8-bit or 16-bit if modified by `wide`.
+    Increment,
+    /// An 8-bit index into the constant pool, i.e., the operand of `ldc`.
+    NarrowConstantPoolIndex,
+    /// A 16-bit index into the constant pool.
+    ConstantPoolIndex,
+    /// A signed 16-bit offset of a branch target.
+    BranchOffset,
+    /// A signed 32-bit offset of a branch target.
+    WideBranchOffset,
+    /// The type code of the elements of `newarray`.
+    ArrayType,
+    /// The number of dimensions of `multianewarray`.
+    Dimensions,
+    /// The number of argument slots of `invokeinterface`.
+    Count,
+    /// A byte that must be zero.
+    Zero,
+    /// The padding, the default offset, the bounds, and the jump offsets of `tableswitch`.
+    TableSwitch,
+    /// The padding, the default offset, and the key-offset pairs of `lookupswitch`.
+    LookupSwitch,
+    /// The opcode modified by `wide` followed by its widened operands.
+    WideInstruction,
+}
+
+/// The effect of an instruction on the operand stack, measured in slots where `long` and
+/// `double` values take two slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StackEffect {
+    /// The number of slots popped from the operand stack.
+    pub pops: u8,
+    /// The number of slots pushed onto the operand stack.
+    pub pushes: u8,
+}
+
+impl StackEffect {
+    /// Creates a [`StackEffect`].
+    #[must_use]
+    pub const fn new(pops: u8, pushes: u8) -> Self {
+        Self { pops, pushes }
+    }
+
+    /// Returns the change of the depth of the operand stack.
+    #[must_use]
+    pub const fn delta(self) -> i16 {
+        self.pushes as i16 - self.pops as i16
+    }
+}
+
+impl TryFrom<u8> for Opcode {
+    type Error = u8;
+
+    /// Returns the opcode with the given value, or the value if it is not a defined opcode.
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::from_u8(value).ok_or(value)
+    }
+}
+
+impl From<Opcode> for u8 {
+    fn from(opcode: Opcode) -> Self {
+        opcode as u8
+    }
+}
+
+impl Opcode {
+    /// Returns the opcode with the given value, or [`None`] if it is not a defined opcode.
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+
+        let opcode = match value {
+            0x00 => Nop,
+            0x01 => AConstNull,
+            0x02 => IConstM1,
+            0x03 => IConst0,
+            0x04 => IConst1,
+            0x05 => IConst2,
+            0x06 => IConst3,
+            0x07 => IConst4,
+            0x08 => IConst5,
+            0x09 => LConst0,
+            0x0a => LConst1,
+            0x0b => FConst0,
+            0x0c => FConst1,
+            0x0d => FConst2,
+            0x0e => DConst0,
+            0x0f => DConst1,
+            0x10 => BiPush,
+            0x11 => SiPush,
+            0x12 => Ldc,
+            0x13 => LdcW,
+            0x14 => Ldc2W,
+            0x15 => ILoad,
+            0x16 => LLoad,
+            0x17 => FLoad,
+            0x18 => DLoad,
+            0x19 => ALoad,
+            0x1a => ILoad0,
+            0x1b => ILoad1,
+            0x1c => ILoad2,
+            0x1d => ILoad3,
+            0x1e => LLoad0,
+            0x1f => LLoad1,
+            0x20 => LLoad2,
+            0x21 => LLoad3,
+            0x22 => FLoad0,
+            0x23 => FLoad1,
+            0x24 => FLoad2,
+            0x25 => FLoad3,
+            0x26 => DLoad0,
+            0x27 => DLoad1,
+            0x28 => DLoad2,
+            0x29 => DLoad3,
+            0x2a => ALoad0,
+            0x2b => ALoad1,
+            0x2c => ALoad2,
+            0x2d => ALoad3,
+            0x2e => IALoad,
+            0x2f => LALoad,
+            0x30 => FALoad,
+            0x31 => DALoad,
+            0x32 => AALoad,
+            0x33 => BALoad,
+            0x34 => CALoad,
+            0x35 => SALoad,
+            0x36 => IStore,
+            0x37 => LStore,
+            0x38 => FStore,
+            0x39 => DStore,
+            0x3a => AStore,
+            0x3b => IStore0,
+            0x3c => IStore1,
+            0x3d => IStore2,
+            0x3e => IStore3,
+            0x3f => LStore0,
+            0x40 => LStore1,
+            0x41 => LStore2,
+            0x42 => LStore3,
+            0x43 => FStore0,
+            0x44 => FStore1,
+            0x45 => FStore2,
+            0x46 => FStore3,
+            0x47 => DStore0,
+            0x48 => DStore1,
+            0x49 => DStore2,
+            0x4a => DStore3,
+            0x4b => AStore0,
+            0x4c => AStore1,
+            0x4d => AStore2,
+            0x4e => AStore3,
+            0x4f => IAStore,
+            0x50 => LAStore,
+            0x51 => FAStore,
+            0x52 => DAStore,
+            0x53 => AAStore,
+            0x54 => BAStore,
+            0x55 => CAStore,
+            0x56 => SAStore,
+            0x57 => Pop,
+            0x58 => Pop2,
+            0x59 => Dup,
+            0x5a => DupX1,
+            0x5b => DupX2,
+            0x5c => Dup2,
+            0x5d => Dup2X1,
+            0x5e => Dup2X2,
+            0x5f => Swap,
+            0x60 => IAdd,
+            0x61 => LAdd,
+            0x62 => FAdd,
+            0x63 => DAdd,
+            0x64 => ISub,
+            0x65 => LSub,
+            0x66 => FSub,
+            0x67 => DSub,
+            0x68 => IMul,
+            0x69 => LMul,
+            0x6a => FMul,
+            0x6b => DMul,
+            0x6c => IDiv,
+            0x6d => LDiv,
+            0x6e => FDiv,
+            0x6f => DDiv,
+            0x70 => IRem,
+            0x71 => LRem,
+            0x72 => FRem,
+            0x73 => DRem,
+            0x74 => INeg,
+            0x75 => LNeg,
+            0x76 => FNeg,
+            0x77 => DNeg,
+            0x78 => IShl,
+            0x79 => LShl,
+            0x7a => IShr,
+            0x7b => LShr,
+            0x7c => IUShr,
+            0x7d => LUShr,
+            0x7e => IAnd,
+            0x7f => LAnd,
+            0x80 => IOr,
+            0x81 => LOr,
+            0x82 => IXor,
+            0x83 => LXor,
+            0x84 => IInc,
+            0x85 => I2L,
+            0x86 => I2F,
+            0x87 => I2D,
+            0x88 => L2I,
+            0x89 => L2F,
+            0x8a => L2D,
+            0x8b => F2I,
+            0x8c => F2L,
+            0x8d => F2D,
+            0x8e => D2I,
+            0x8f => D2L,
+            0x90 => D2F,
+            0x91 => I2B,
+            0x92 => I2C,
+            0x93 => I2S,
+            0x94 => LCmp,
+            0x95 => FCmpL,
+            0x96 => FCmpG,
+            0x97 => DCmpL,
+            0x98 => DCmpG,
+            0x99 => IfEq,
+            0x9a => IfNe,
+            0x9b => IfLt,
+            0x9c => IfGe,
+            0x9d => IfGt,
+            0x9e => IfLe,
+            0x9f => IfICmpEq,
+            0xa0 => IfICmpNe,
+            0xa1 => IfICmpLt,
+            0xa2 => IfICmpGe,
+            0xa3 => IfICmpGt,
+            0xa4 => IfICmpLe,
+            0xa5 => IfACmpEq,
+            0xa6 => IfACmpNe,
+            0xa7 => Goto,
+            0xa8 => Jsr,
+            0xa9 => Ret,
+            0xaa => TableSwitch,
+            0xab => LookupSwitch,
+            0xac => IReturn,
+            0xad => LReturn,
+            0xae => FReturn,
+            0xaf => DReturn,
+            0xb0 => AReturn,
+            0xb1 => Return,
+            0xb2 => GetStatic,
+            0xb3 => PutStatic,
+            0xb4 => GetField,
+            0xb5 => PutField,
+            0xb6 => InvokeVirtual,
+            0xb7 => InvokeSpecial,
+            0xb8 => InvokeStatic,
+            0xb9 => InvokeInterface,
+            0xba => InvokeDynamic,
+            0xbb => New,
+            0xbc => NewArray,
+            0xbd => ANewArray,
+            0xbe => ArrayLength,
+            0xbf => AThrow,
+            0xc0 => CheckCast,
+            0xc1 => InstanceOf,
+            0xc2 => MonitorEnter,
+            0xc3 => MonitorExit,
+            0xc4 => Wide,
+            0xc5 => MultiANewArray,
+            0xc6 => IfNull,
+            0xc7 => IfNonNull,
+            0xc8 => GotoW,
+            0xc9 => JsrW,
+            0xca => Breakpoint,
+            0xfe => ImpDep1,
+            0xff => ImpDep2,
+            _ => return None,
+        };
+        Some(opcode)
+    }
+
+    /// Returns the mnemonic of the opcode, e.g., `iconst_0`.
+    #[must_use]
+    #[allow(clippy::too_many_lines)]
+    pub const fn mnemonic(self) -> &'static str {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+
+        match self {
+            Nop => "nop",
+            AConstNull => "aconst_null",
+            IConstM1 => "iconst_m1",
+            IConst0 => "iconst_0",
+            IConst1 => "iconst_1",
+            IConst2 => "iconst_2",
+            IConst3 => "iconst_3",
+            IConst4 => "iconst_4",
+            IConst5 => "iconst_5",
+            LConst0 => "lconst_0",
+            LConst1 => "lconst_1",
+            FConst0 => "fconst_0",
+            FConst1 => "fconst_1",
+            FConst2 => "fconst_2",
+            DConst0 => "dconst_0",
+            DConst1 => "dconst_1",
+            BiPush => "bipush",
+            SiPush => "sipush",
+            Ldc => "ldc",
+            LdcW => "ldc_w",
+            Ldc2W => "ldc2_w",
+            ILoad => "iload",
+            LLoad => "lload",
+            FLoad => "fload",
+            DLoad => "dload",
+            ALoad => "aload",
+            ILoad0 => "iload_0",
+            ILoad1 => "iload_1",
+            ILoad2 => "iload_2",
+            ILoad3 => "iload_3",
+            LLoad0 => "lload_0",
+            LLoad1 => "lload_1",
+            LLoad2 => "lload_2",
+            LLoad3 => "lload_3",
+            FLoad0 => "fload_0",
+            FLoad1 => "fload_1",
+            FLoad2 => "fload_2",
+            FLoad3 => "fload_3",
+            DLoad0 => "dload_0",
+            DLoad1 => "dload_1",
+            DLoad2 => "dload_2",
+            DLoad3 => "dload_3",
+            ALoad0 => "aload_0",
+            ALoad1 => "aload_1",
+            ALoad2 => "aload_2",
+            ALoad3 => "aload_3",
+            IALoad => "iaload",
+            LALoad => "laload",
+            FALoad => "faload",
+            DALoad => "daload",
+            AALoad => "aaload",
+            BALoad => "baload",
+            CALoad => "caload",
+            SALoad => "saload",
+            IStore => "istore",
+            LStore => "lstore",
+            FStore => "fstore",
+            DStore => "dstore",
+            AStore => "astore",
+            IStore0 => "istore_0",
+            IStore1 => "istore_1",
+            IStore2 => "istore_2",
+            IStore3 => "istore_3",
+            LStore0 => "lstore_0",
+            LStore1 => "lstore_1",
+            LStore2 => "lstore_2",
+            LStore3 => "lstore_3",
+            FStore0 => "fstore_0",
+            FStore1 => "fstore_1",
+            FStore2 => "fstore_2",
+            FStore3 => "fstore_3",
+            DStore0 => "dstore_0",
+            DStore1 => "dstore_1",
+            DStore2 => "dstore_2",
+            DStore3 => "dstore_3",
+            AStore0 => "astore_0",
+            AStore1 => "astore_1",
+            AStore2 => "astore_2",
+            AStore3 => "astore_3",
+            IAStore => "iastore",
+            LAStore => "lastore",
+            FAStore => "fastore",
+            DAStore => "dastore",
+            AAStore => "aastore",
+            BAStore => "bastore",
+            CAStore => "castore",
+            SAStore => "sastore",
+            Pop => "pop",
+            Pop2 => "pop2",
+            Dup => "dup",
+            DupX1 => "dup_x1",
+            DupX2 => "dup_x2",
+            Dup2 => "dup2",
+            Dup2X1 => "dup2_x1",
+            Dup2X2 => "dup2_x2",
+            Swap => "swap",
+            IAdd => "iadd",
+            LAdd => "ladd",
+            FAdd => "fadd",
+            DAdd => "dadd",
+            ISub => "isub",
+            LSub => "lsub",
+            FSub => "fsub",
+            DSub => "dsub",
+            IMul => "imul",
+            LMul => "lmul",
+            FMul => "fmul",
+            DMul => "dmul",
+            IDiv => "idiv",
+            LDiv => "ldiv",
+            FDiv => "fdiv",
+            DDiv => "ddiv",
+            IRem => "irem",
+            LRem => "lrem",
+            FRem => "frem",
+            DRem => "drem",
+            INeg => "ineg",
+            LNeg => "lneg",
+            FNeg => "fneg",
+            DNeg => "dneg",
+            IShl => "ishl",
+            LShl => "lshl",
+            IShr => "ishr",
+            LShr => "lshr",
+            IUShr => "iushr",
+            LUShr => "lushr",
+            IAnd => "iand",
+            LAnd => "land",
+            IOr => "ior",
+            LOr => "lor",
+            IXor => "ixor",
+            LXor => "lxor",
+            IInc => "iinc",
+            I2L => "i2l",
+            I2F => "i2f",
+            I2D => "i2d",
+            L2I => "l2i",
+            L2F => "l2f",
+            L2D => "l2d",
+            F2I => "f2i",
+            F2L => "f2l",
+            F2D => "f2d",
+            D2I => "d2i",
+            D2L => "d2l",
+            D2F => "d2f",
+            I2B => "i2b",
+            I2C => "i2c",
+            I2S => "i2s",
+            LCmp => "lcmp",
+            FCmpL => "fcmpl",
+            FCmpG => "fcmpg",
+            DCmpL => "dcmpl",
+            DCmpG => "dcmpg",
+            IfEq => "ifeq",
+            IfNe => "ifne",
+            IfLt => "iflt",
+            IfGe => "ifge",
+            IfGt => "ifgt",
+            IfLe => "ifle",
+            IfICmpEq => "if_icmpeq",
+            IfICmpNe => "if_icmpne",
+            IfICmpLt => "if_icmplt",
+            IfICmpGe => "if_icmpge",
+            IfICmpGt => "if_icmpgt",
+            IfICmpLe => "if_icmple",
+            IfACmpEq => "if_acmpeq",
+            IfACmpNe => "if_acmpne",
+            Goto => "goto",
+            Jsr => "jsr",
+            Ret => "ret",
+            TableSwitch => "tableswitch",
+            LookupSwitch => "lookupswitch",
+            IReturn => "ireturn",
+            LReturn => "lreturn",
+            FReturn => "freturn",
+            DReturn => "dreturn",
+            AReturn => "areturn",
+            Return => "return",
+            GetStatic => "getstatic",
+            PutStatic => "putstatic",
+            GetField => "getfield",
+            PutField => "putfield",
+            InvokeVirtual => "invokevirtual",
+            InvokeSpecial => "invokespecial",
+            InvokeStatic => "invokestatic",
+            InvokeInterface => "invokeinterface",
+            InvokeDynamic => "invokedynamic",
+            New => "new",
+            NewArray => "newarray",
+            ANewArray => "anewarray",
+            ArrayLength => "arraylength",
+            AThrow => "athrow",
+            CheckCast => "checkcast",
+            InstanceOf => "instanceof",
+            MonitorEnter => "monitorenter",
+            MonitorExit => "monitorexit",
+            Wide => "wide",
+            MultiANewArray => "multianewarray",
+            IfNull => "ifnull",
+            IfNonNull => "ifnonnull",
+            GotoW => "goto_w",
+            JsrW => "jsr_w",
+            Breakpoint => "breakpoint",
+            ImpDep1 => "impdep1",
+            ImpDep2 => "impdep2",
+        }
+    }
+
+    /// Returns the kinds of the operands following the opcode in the class file.
+    #[must_use]
+    pub const fn operand_kinds(self) -> &'static [OperandKind] {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+        use OperandKind::{
+            ArrayType, BranchOffset, ConstantPoolIndex, Count, Dimensions, Increment,
+            LocalVariable, NarrowConstantPoolIndex, WideBranchOffset, Zero,
+        };
+
+        match self {
+            BiPush => &[OperandKind::Byte],
+            SiPush => &[OperandKind::Short],
+            Ldc => &[NarrowConstantPoolIndex],
+            ILoad | LLoad | FLoad | DLoad | ALoad | IStore | LStore | FStore | DStore | AStore
+            | Ret => &[LocalVariable],
+            IInc => &[LocalVariable, Increment],
+            LdcW | Ldc2W | GetStatic | PutStatic | GetField | PutField | InvokeVirtual
+            | InvokeSpecial | InvokeStatic | New | ANewArray | CheckCast | InstanceOf => {
+                &[ConstantPoolIndex]
+            }
+            InvokeInterface => &[ConstantPoolIndex, Count, Zero],
+            InvokeDynamic => &[ConstantPoolIndex, Zero, Zero],
+            MultiANewArray => &[ConstantPoolIndex, Dimensions],
+            NewArray => &[ArrayType],
+            IfEq | IfNe | IfLt | IfGe | IfGt | IfLe | IfICmpEq | IfICmpNe | IfICmpLt | IfICmpGe
+            | IfICmpGt | IfICmpLe | IfACmpEq | IfACmpNe | IfNull | IfNonNull | Goto | Jsr => {
+                &[BranchOffset]
+            }
+            GotoW | JsrW => &[WideBranchOffset],
+            TableSwitch => &[OperandKind::TableSwitch],
+            LookupSwitch => &[OperandKind::LookupSwitch],
+            Wide => &[OperandKind::WideInstruction],
+            _ => &[],
+        }
+    }
+
+    /// Returns the effect of the instruction on the operand stack, or [`None`] if it depends on
+    /// the operands, i.e., for field accesses, method invocations, `multianewarray`, `wide`, and
+    /// the reserved opcodes.
+    #[must_use]
+    pub const fn stack_effect(self) -> Option<StackEffect> {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+
+        let (pops, pushes) = match self {
+            Nop | IInc | Goto | GotoW | Ret | Return => (0, 0),
+            AConstNull | IConstM1 | IConst0 | IConst1 | IConst2 | IConst3 | IConst4 | IConst5
+            | FConst0 | FConst1 | FConst2 | BiPush | SiPush | Ldc | LdcW | ILoad | FLoad
+            | ALoad | ILoad0 | ILoad1 | ILoad2 | ILoad3 | FLoad0 | FLoad1 | FLoad2 | FLoad3
+            | ALoad0 | ALoad1 | ALoad2 | ALoad3 | New | Jsr | JsrW => (0, 1),
+            LConst0 | LConst1 | DConst0 | DConst1 | Ldc2W | LLoad | DLoad | LLoad0 | LLoad1
+            | LLoad2 | LLoad3 | DLoad0 | DLoad1 | DLoad2 | DLoad3 => (0, 2),
+            IStore | FStore | AStore | IStore0 | IStore1 | IStore2 | IStore3 | FStore0
+            | FStore1 | FStore2 | FStore3 | AStore0 | AStore1 | AStore2 | AStore3 | Pop | IfEq
+            | IfNe | IfLt | IfGe | IfGt | IfLe | IfNull | IfNonNull | TableSwitch
+            | LookupSwitch | IReturn | FReturn | AReturn | AThrow | MonitorEnter | MonitorExit => {
+                (1, 0)
+            }
+            INeg | FNeg | I2F | F2I | I2B | I2C | I2S | NewArray | ANewArray | ArrayLength
+            | CheckCast | InstanceOf => (1, 1),
+            I2L | I2D | F2L | F2D | Dup => (1, 2),
+            LStore | DStore | LStore0 | LStore1 | LStore2 | LStore3 | DStore0 | DStore1
+            | DStore2 | DStore3 | Pop2 | IfICmpEq | IfICmpNe | IfICmpLt | IfICmpGe | IfICmpGt
+            | IfICmpLe | IfACmpEq | IfACmpNe | LReturn | DReturn => (2, 0),
+            IALoad | FALoad | AALoad | BALoad | CALoad | SALoad | IAdd | FAdd | ISub | FSub
+            | IMul | FMul | IDiv | FDiv | IRem | FRem | IShl | IShr | IUShr | IAnd | IOr | IXor
+            | L2I | L2F | D2I | D2F | FCmpL | FCmpG => (2, 1),
+            LALoad | DALoad | LNeg | DNeg | L2D | D2L | Swap => (2, 2),
+            DupX1 => (2, 3),
+            Dup2 => (2, 4),
+            IAStore | FAStore | AAStore | BAStore | CAStore | SAStore => (3, 0),
+            LShl | LShr | LUShr => (3, 2),
+            DupX2 => (3, 4),
+            Dup2X1 => (3, 5),
+            LAStore | DAStore => (4, 0),
+            LCmp | DCmpL | DCmpG => (4, 1),
+            LAdd | DAdd | LSub | DSub | LMul | DMul | LDiv | DDiv | LRem | DRem | LAnd | LOr
+            | LXor => (4, 2),
+            Dup2X2 => (4, 6),
+            GetStatic | PutStatic | GetField | PutField | InvokeVirtual | InvokeSpecial
+            | InvokeStatic | InvokeInterface | InvokeDynamic | MultiANewArray | Wide
+            | Breakpoint | ImpDep1 | ImpDep2 => return None,
+        };
+        Some(StackEffect::new(pops, pushes))
+    }
+
+    /// Checks whether the instruction may throw an exception or an error when it is executed,
+    /// including the errors when resolving the symbolic references in its operands.
+    #[must_use]
+    pub const fn can_throw(self) -> bool {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+
+        matches!(
+            self,
+            Ldc | LdcW
+                | Ldc2W
+                | IALoad
+                | LALoad
+                | FALoad
+                | DALoad
+                | AALoad
+                | BALoad
+                | CALoad
+                | SALoad
+                | IAStore
+                | LAStore
+                | FAStore
+                | DAStore
+                | AAStore
+                | BAStore
+                | CAStore
+                | SAStore
+                | IDiv
+                | LDiv
+                | IRem
+                | LRem
+                | IReturn
+                | LReturn
+                | FReturn
+                | DReturn
+                | AReturn
+                | Return
+                | GetStatic
+                | PutStatic
+                | GetField
+                | PutField
+                | InvokeVirtual
+                | InvokeSpecial
+                | InvokeStatic
+                | InvokeInterface
+                | InvokeDynamic
+                | New
+                | NewArray
+                | ANewArray
+                | ArrayLength
+                | AThrow
+                | CheckCast
+                | InstanceOf
+                | MonitorEnter
+                | MonitorExit
+                | MultiANewArray
+        )
+    }
+
+    /// Checks whether the instruction jumps to the targets encoded in its operands, i.e., the
+    /// conditional and unconditional branches, `jsr`, and the switches.
+    /// Note that `ret` jumps to a target stored in a local variable, so it is not a branch.
+    #[must_use]
+    pub const fn is_branch(self) -> bool {
+        #[allow(clippy::enum_glob_use)]
+        use Opcode::*;
+
+        matches!(
+            self,
+            IfEq | IfNe
+                | IfLt
+                | IfGe
+                | IfGt
+                | IfLe
+                | IfICmpEq
+                | IfICmpNe
+                | IfICmpLt
+                | IfICmpGe
+                | IfICmpGt
+                | IfICmpLe
+                | IfACmpEq
+                | IfACmpNe
+                | IfNull
+                | IfNonNull
+                | Goto
+                | GotoW
+                | Jsr
+                | JsrW
+                | TableSwitch
+                | LookupSwitch
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opcode_round_trip() {
+        for value in 0..=u8::MAX {
+            if let Some(opcode) = Opcode::from_u8(value) {
+                assert_eq!(u8::from(opcode), value);
+            }
+        }
+        assert_eq!(Opcode::try_from(0x12), Ok(Opcode::Ldc));
+        assert_eq!(Opcode::try_from(0xcb), Err(0xcb));
+        assert_eq!(Opcode::Ldc.mnemonic(), "ldc");
+    }
+
+    #[test]
+    fn stack_effects() {
+        assert_eq!(Opcode::LAdd.stack_effect(), Some(StackEffect::new(4, 2)));
+        assert_eq!(
+            Opcode::Dup2X1.stack_effect().map(StackEffect::delta),
+            Some(2)
+        );
+        assert_eq!(Opcode::InvokeStatic.stack_effect(), None);
+    }
+}
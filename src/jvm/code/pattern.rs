@@ -0,0 +1,425 @@
+//! Matching of instruction sequences.
+//!
+//! A [`Pattern`] is a sequence of [`Step`]s, each of which matches a number of consecutive
+//! instructions satisfying a [`Matcher`]. Steps can be captured by name, and the program
+//! counters bound by the captures can be referred to by later steps with [`branch_to`].
+//!
+//! For example, the following pattern detects a getter:
+//! ```
+//! use mokapot::jvm::code::pattern::{mnemonic, one_of, Pattern};
+//!
+//! let getter = Pattern::new()
+//!     .then(mnemonic("aload_0"))
+//!     .then(mnemonic("getfield").capture("field"))
+//!     .then(one_of(["areturn", "ireturn", "lreturn", "freturn", "dreturn"]));
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Debug},
+    ops::{Bound, RangeBounds},
+    sync::Arc,
+};
+
+use super::{Instruction, InstructionList, ProgramCounter};
+
+/// The program counters bound by the captures matched so far.
+pub type Bindings = BTreeMap<String, ProgramCounter>;
+
+type Predicate = dyn Fn(ProgramCounter, &Instruction, &Bindings) -> bool + Send + Sync;
+
+/// A predicate on a single instruction.
+#[derive(Clone)]
+pub struct Matcher(Arc<Predicate>);
+
+impl Debug for Matcher {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Matcher")
+    }
+}
+
+impl Matcher {
+    /// Creates a matcher from a predicate on the instruction.
+    pub fn new<F>(predicate: F) -> Self
+    where
+        F: Fn(&Instruction) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(move |_, insn, _| predicate(insn)))
+    }
+
+    /// Creates a matcher from a predicate on the program counter, the instruction, and the
+    /// program counters bound by the preceding captures.
+    pub fn with_bindings<F>(predicate: F) -> Self
+    where
+        F: Fn(ProgramCounter, &Instruction, &Bindings) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(predicate))
+    }
+
+    /// Checks whether the instruction at `pc` satisfies the matcher.
+    #[must_use]
+    pub fn matches(&self, pc: ProgramCounter, insn: &Instruction, bindings: &Bindings) -> bool {
+        (self.0)(pc, insn, bindings)
+    }
+
+    /// Creates a matcher that is satisfied when both `self` and `other` are.
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        Self(Arc::new(move |pc, insn, bindings| {
+            self.matches(pc, insn, bindings) && other.matches(pc, insn, bindings)
+        }))
+    }
+
+    /// Creates a matcher that is satisfied when either `self` or `other` is.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self(Arc::new(move |pc, insn, bindings| {
+            self.matches(pc, insn, bindings) || other.matches(pc, insn, bindings)
+        }))
+    }
+
+    /// Creates a matcher that is satisfied when `self` is not.
+    #[must_use]
+    pub fn negate(self) -> Self {
+        Self(Arc::new(move |pc, insn, bindings| {
+            !self.matches(pc, insn, bindings)
+        }))
+    }
+
+    /// Creates a step matching one instruction and binding it to `name`.
+    #[must_use]
+    pub fn capture(self, name: impl Into<String>) -> Step {
+        Step::from(self).capture(name)
+    }
+
+    /// Creates a step matching a number of instructions in `count`.
+    #[must_use]
+    pub fn repeat(self, count: impl RangeBounds<usize>) -> Step {
+        Step::from(self).repeat(count)
+    }
+
+    /// Creates a step matching zero or one instruction.
+    #[must_use]
+    pub fn optional(self) -> Step {
+        Step::from(self).optional()
+    }
+}
+
+/// Creates a matcher that is satisfied by every instruction.
+#[must_use]
+pub fn any() -> Matcher {
+    Matcher::new(|_| true)
+}
+
+/// Creates a matcher that is satisfied by the instructions with the given opcode.
+#[must_use]
+pub fn opcode(opcode: u8) -> Matcher {
+    Matcher::new(move |insn| insn.opcode() == opcode)
+}
+
+/// Creates a matcher that is satisfied by the instructions with the given mnemonic, e.g.,
+/// `"getfield"`.
+#[must_use]
+pub fn mnemonic(name: &'static str) -> Matcher {
+    Matcher::new(move |insn| insn.name() == name)
+}
+
+/// Creates a matcher that is satisfied by the instructions with any of the given mnemonics.
+#[must_use]
+pub fn one_of<const N: usize>(names: [&'static str; N]) -> Matcher {
+    Matcher::new(move |insn| names.contains(&insn.name()))
+}
+
+/// Creates a matcher that is satisfied by the instructions equal to `expected`.
+#[must_use]
+pub fn exactly(expected: Instruction) -> Matcher {
+    Matcher::new(move |insn| insn == &expected)
+}
+
+/// Creates a matcher that is satisfied by the branch instructions that may jump to the
+/// instruction bound to the capture `name`.
+#[must_use]
+pub fn branch_to(name: impl Into<String>) -> Matcher {
+    let name = name.into();
+    Matcher::with_bindings(move |_, insn, bindings| {
+        bindings
+            .get(&name)
+            .is_some_and(|target| insn.branch_targets().contains(target))
+    })
+}
+
+/// A step of a [`Pattern`].
+#[derive(Debug, Clone)]
+pub struct Step {
+    matcher: Matcher,
+    min: usize,
+    max: usize,
+    capture: Option<String>,
+}
+
+impl From<Matcher> for Step {
+    fn from(matcher: Matcher) -> Self {
+        Self {
+            matcher,
+            min: 1,
+            max: 1,
+            capture: None,
+        }
+    }
+}
+
+impl Step {
+    /// Binds the instructions matched by the step to `name`.
+    #[must_use]
+    pub fn capture(mut self, name: impl Into<String>) -> Self {
+        self.capture = Some(name.into());
+        self
+    }
+
+    /// Makes the step match a number of instructions in `count`.
+    /// The step matches as many instructions as possible that still allow the rest of the
+    /// pattern to match.
+    #[must_use]
+    pub fn repeat(mut self, count: impl RangeBounds<usize>) -> Self {
+        self.min = match count.start_bound() {
+            Bound::Included(&min) => min,
+            Bound::Excluded(&min) => min.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        self.max = match count.end_bound() {
+            Bound::Included(&max) => max,
+            Bound::Excluded(&max) => max.saturating_sub(1),
+            Bound::Unbounded => usize::MAX,
+        };
+        self
+    }
+
+    /// Makes the step match zero or one instruction.
+    #[must_use]
+    pub fn optional(self) -> Self {
+        self.repeat(0..=1)
+    }
+}
+
+/// A sequence of [`Step`]s matching consecutive instructions.
+#[derive(Debug, Clone, Default)]
+pub struct Pattern {
+    steps: Vec<Step>,
+}
+
+/// A successful match of a [`Pattern`].
+#[derive(Debug, Clone)]
+pub struct Match<'i> {
+    /// The matched instructions.
+    pub instructions: Vec<(ProgramCounter, &'i Instruction)>,
+    captures: BTreeMap<String, Vec<(ProgramCounter, &'i Instruction)>>,
+}
+
+impl<'i> Match<'i> {
+    /// Returns the program counter of the first matched instruction, or [`None`] if the match
+    /// is empty.
+    #[must_use]
+    pub fn start(&self) -> Option<ProgramCounter> {
+        self.instructions.first().map(|(pc, _)| *pc)
+    }
+
+    /// Returns the instructions bound to `name`.
+    #[must_use]
+    pub fn captured(&self, name: &str) -> Option<&[(ProgramCounter, &'i Instruction)]> {
+        self.captures.get(name).map(Vec::as_slice)
+    }
+
+    /// Returns the first instruction bound to `name`.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&'i Instruction> {
+        self.captured(name)?.first().map(|(_, insn)| *insn)
+    }
+
+    /// Returns the program counter of the first instruction bound to `name`.
+    #[must_use]
+    pub fn pc(&self, name: &str) -> Option<ProgramCounter> {
+        self.captured(name)?.first().map(|(pc, _)| *pc)
+    }
+}
+
+impl Pattern {
+    /// Creates an empty pattern.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `step` to the pattern.
+    #[must_use]
+    pub fn then(mut self, step: impl Into<Step>) -> Self {
+        self.steps.push(step.into());
+        self
+    }
+
+    /// Matches the pattern against the instructions starting at `pc`.
+    #[must_use]
+    pub fn match_at<'i>(
+        &self,
+        instructions: &'i InstructionList<Instruction>,
+        pc: ProgramCounter,
+    ) -> Option<Match<'i>> {
+        let insns: Vec<_> = instructions
+            .iter()
+            .skip_while(|(it, _)| **it < pc)
+            .map(|(pc, insn)| (*pc, insn))
+            .collect();
+        self.match_slice(&insns)
+    }
+
+    /// Finds the first match of the pattern in `instructions`.
+    #[must_use]
+    pub fn find<'i>(&self, instructions: &'i InstructionList<Instruction>) -> Option<Match<'i>> {
+        self.find_all(instructions).into_iter().next()
+    }
+
+    /// Finds the non-overlapping matches of the pattern in `instructions`.
+    #[must_use]
+    pub fn find_all<'i>(&self, instructions: &'i InstructionList<Instruction>) -> Vec<Match<'i>> {
+        let insns: Vec<_> = instructions.iter().map(|(pc, insn)| (*pc, insn)).collect();
+        let mut matches = Vec::new();
+        let mut start = 0;
+        while start < insns.len() {
+            match self.match_slice(&insns[start..]) {
+                Some(found) => {
+                    start += found.instructions.len().max(1);
+                    matches.push(found);
+                }
+                None => start += 1,
+            }
+        }
+        matches
+    }
+
+    /// Checks whether the pattern matches exactly the whole instruction list.
+    #[must_use]
+    pub fn matches_whole(&self, instructions: &InstructionList<Instruction>) -> bool {
+        let insns: Vec<_> = instructions.iter().map(|(pc, insn)| (*pc, insn)).collect();
+        let mut counts = Vec::with_capacity(self.steps.len());
+        let mut bindings = Bindings::new();
+        solve(&self.steps, &insns, true, &mut counts, &mut bindings)
+    }
+
+    fn match_slice<'i>(&self, insns: &[(ProgramCounter, &'i Instruction)]) -> Option<Match<'i>> {
+        let mut counts = Vec::with_capacity(self.steps.len());
+        let mut bindings = Bindings::new();
+        if !solve(&self.steps, insns, false, &mut counts, &mut bindings) {
+            return None;
+        }
+        let mut captures = BTreeMap::new();
+        let mut offset = 0;
+        for (step, count) in self.steps.iter().zip(counts) {
+            if let Some(name) = &step.capture {
+                captures.insert(name.clone(), insns[offset..offset + count].to_vec());
+            }
+            offset += count;
+        }
+        Some(Match {
+            instructions: insns[..offset].to_vec(),
+            captures,
+        })
+    }
+}
+
+/// Matches `steps` against the prefix of `insns` with backtracking, recording the number of
+/// instructions matched by each step in `counts`. If `to_end` is set, the steps must match all
+/// of `insns`.
+fn solve(
+    steps: &[Step],
+    insns: &[(ProgramCounter, &Instruction)],
+    to_end: bool,
+    counts: &mut Vec<usize>,
+    bindings: &mut Bindings,
+) -> bool {
+    let Some((step, rest)) = steps.split_first() else {
+        return !to_end || insns.is_empty();
+    };
+    let available = insns
+        .iter()
+        .take(step.max)
+        .take_while(|(pc, insn)| step.matcher.matches(*pc, insn, bindings))
+        .count();
+    if available < step.min {
+        return false;
+    }
+    for count in (step.min..=available).rev() {
+        let previous = match (&step.capture, insns.get(..count).and_then(<[_]>::first)) {
+            (Some(name), Some((pc, _))) => bindings.insert(name.clone(), *pc),
+            _ => None,
+        };
+        counts.push(count);
+        if solve(rest, &insns[count..], to_end, counts, bindings) {
+            return true;
+        }
+        counts.pop();
+        if let Some(name) = &step.capture {
+            match previous {
+                Some(pc) => bindings.insert(name.clone(), pc),
+                None => bindings.remove(name),
+            };
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        jvm::references::{ClassRef, FieldRef},
+        types::field_type::{FieldType, PrimitiveType},
+    };
+
+    #[test]
+    fn detect_getter() {
+        let field = FieldRef::new(
+            ClassRef::new("Foo"),
+            "bar",
+            FieldType::Base(PrimitiveType::Int),
+        );
+        let getter = InstructionList::from([
+            (0.into(), Instruction::ALoad0),
+            (1.into(), Instruction::GetField(field.clone())),
+            (4.into(), Instruction::IReturn),
+        ]);
+        let pattern = Pattern::new()
+            .then(mnemonic("aload_0"))
+            .then(mnemonic("getfield").capture("field"))
+            .then(one_of(["areturn", "ireturn"]));
+        assert!(pattern.matches_whole(&getter));
+        let found = pattern.find(&getter).unwrap();
+        assert_eq!(found.pc("field"), Some(1.into()));
+        assert_eq!(found.get("field"), Some(&Instruction::GetField(field)));
+
+        let not_getter = InstructionList::from([
+            (0.into(), Instruction::ALoad1),
+            (1.into(), Instruction::AReturn),
+        ]);
+        assert!(pattern.find(&not_getter).is_none());
+    }
+
+    #[test]
+    fn backtrack_and_bind_pc() {
+        // A loop: `L: nop; nop; goto L`, preceded by an unrelated instruction.
+        let instructions = InstructionList::from([
+            (0.into(), Instruction::IConst0),
+            (1.into(), Instruction::Nop),
+            (2.into(), Instruction::Nop),
+            (3.into(), Instruction::Goto(1.into())),
+        ]);
+        let pattern = Pattern::new()
+            .then(any().capture("head"))
+            .then(any().repeat(..).capture("body"))
+            .then(branch_to("head"));
+        let found = pattern.find(&instructions).unwrap();
+        assert_eq!(found.start(), Some(1.into()));
+        assert_eq!(found.captured("body").map(<[_]>::len), Some(1));
+        assert_eq!(pattern.find_all(&instructions).len(), 1);
+        assert!(!pattern.matches_whole(&instructions));
+        assert!(pattern.match_at(&instructions, 1.into()).is_some());
+    }
+}
@@ -1,5 +1,7 @@
 use std::{fmt::Debug, ops::Add};
 
+use crate::macros::see_jvm_spec;
+
 /// Denotes a program counter in an instruction sequence.
 #[derive(
     Clone,
@@ -86,6 +88,15 @@ impl ProgramCounter {
     pub const fn is_entry_point(&self) -> bool {
         self.0 == 0
     }
+
+    /// Returns the number of zero bytes following the opcode of a `tableswitch` or a
+    /// `lookupswitch` at this location, which align its operands to a multiple of four bytes from
+    /// the start of the code.
+    #[doc = see_jvm_spec!(6, 5, "tableswitch")]
+    #[must_use]
+    pub const fn switch_padding(&self) -> u16 {
+        3 - self.0 % 4
+    }
 }
 
 impl Debug for ProgramCounter {
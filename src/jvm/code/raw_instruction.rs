@@ -2,6 +2,8 @@ use std::ptr;
 
 use crate::macros::see_jvm_spec;
 
+use super::{InvalidOffset, Opcode, OperandKind, ProgramCounter, StackEffect};
+
 /// A raw JVM instruction without the information form the constant pool.
 #[doc = see_jvm_spec!(6, 5)]
 #[repr(u8)]
@@ -348,6 +350,12 @@ pub enum RawWideInstruction {
     Ret { index: u16 } = 0xA9,
     IInc { index: u16, increment: i16 } = 0x84,
 }
+impl From<&RawInstruction> for Opcode {
+    fn from(instruction: &RawInstruction) -> Self {
+        instruction.opcode_kind()
+    }
+}
+
 impl RawInstruction {
     /// Gets the opcode.
     #[must_use]
@@ -362,6 +370,186 @@ impl RawInstruction {
         // See https://doc.rust-lang.org/std/mem/fn.discriminant.html#accessing-the-numeric-value-of-the-discriminant
         unsafe { *ptr::from_ref(self).cast::<u8>() }
     }
+
+    /// Gets the name of the instruction.
+    #[must_use]
+    pub const fn name(&self) -> &'static str {
+        self.opcode_kind().mnemonic()
+    }
+
+    /// Returns the kinds of the operands following the opcode in the class file.
+    /// See [`Opcode::operand_kinds`] for more information.
+    #[must_use]
+    pub const fn operand_kinds(&self) -> &'static [OperandKind] {
+        self.opcode_kind().operand_kinds()
+    }
+
+    /// Checks whether the instruction may throw an exception or an error when it is executed.
+    /// See [`Opcode::can_throw`] for more information.
+    #[must_use]
+    pub const fn can_throw(&self) -> bool {
+        self.opcode_kind().can_throw()
+    }
+
+    /// Checks whether the instruction jumps to the targets encoded in its operands.
+    /// See [`Opcode::is_branch`] for more information.
+    #[must_use]
+    pub const fn is_branch(&self) -> bool {
+        self.opcode_kind().is_branch()
+    }
+
+    /// Returns the effect of the instruction on the operand stack, or [`None`] for the field
+    /// accesses and the method invocations, whose effects depend on the descriptors in the
+    /// constant pool.
+    #[must_use]
+    pub const fn stack_effect(&self) -> Option<StackEffect> {
+        let (pops, pushes) = match self {
+            Self::MultiANewArray { dimensions, .. } => (*dimensions, 1),
+            Self::Wide(wide) => match wide {
+                RawWideInstruction::ILoad { .. }
+                | RawWideInstruction::FLoad { .. }
+                | RawWideInstruction::ALoad { .. } => (0, 1),
+                RawWideInstruction::LLoad { .. } | RawWideInstruction::DLoad { .. } => (0, 2),
+                RawWideInstruction::IStore { .. }
+                | RawWideInstruction::FStore { .. }
+                | RawWideInstruction::AStore { .. } => (1, 0),
+                RawWideInstruction::LStore { .. } | RawWideInstruction::DStore { .. } => (2, 0),
+                RawWideInstruction::IInc { .. } | RawWideInstruction::Ret { .. } => (0, 0),
+            },
+            Self::Breakpoint | Self::ImpDep1 | Self::ImpDep2 => (0, 0),
+            _ => return self.opcode_kind().stack_effect(),
+        };
+        Some(StackEffect::new(pops, pushes))
+    }
+
+    /// Returns the locations that the instruction at `pc` may jump to, excluding the next
+    /// instruction.
+    /// # Errors
+    /// Returns [`InvalidOffset`] if a branch target is out of the range of [`ProgramCounter`].
+    pub fn branch_targets(&self, pc: ProgramCounter) -> Result<Vec<ProgramCounter>, InvalidOffset> {
+        #[allow(clippy::enum_glob_use)]
+        use RawInstruction::*;
+
+        match self {
+            IfEq { offset }
+            | IfNe { offset }
+            | IfLt { offset }
+            | IfGe { offset }
+            | IfGt { offset }
+            | IfLe { offset }
+            | IfICmpEq { offset }
+            | IfICmpNe { offset }
+            | IfICmpLt { offset }
+            | IfICmpGe { offset }
+            | IfICmpGt { offset }
+            | IfICmpLe { offset }
+            | IfACmpEq { offset }
+            | IfACmpNe { offset }
+            | IfNull { offset }
+            | IfNonNull { offset }
+            | Goto { offset }
+            | Jsr { offset } => Ok(vec![(pc + *offset)?]),
+            GotoW { offset } | JsrW { offset } => Ok(vec![(pc + *offset)?]),
+            TableSwitch {
+                default,
+                jump_offsets,
+                ..
+            } => jump_offsets
+                .iter()
+                .chain([default])
+                .map(|it| pc + *it)
+                .collect(),
+            LookupSwitch {
+                default,
+                match_offsets,
+            } => match_offsets
+                .iter()
+                .map(|(_, it)| it)
+                .chain([default])
+                .map(|it| pc + *it)
+                .collect(),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Computes the size in bytes of the instruction when it is encoded at `at_pc`.
+    /// See [`Instruction::encoded_size`](super::Instruction::encoded_size) for more information.
+    #[must_use]
+    pub fn encoded_size(&self, at_pc: ProgramCounter) -> u32 {
+        #[allow(clippy::enum_glob_use)]
+        use RawInstruction::*;
+
+        let padding = u32::from(at_pc.switch_padding());
+        match self {
+            BiPush { .. }
+            | Ldc { .. }
+            | ILoad { .. }
+            | LLoad { .. }
+            | FLoad { .. }
+            | DLoad { .. }
+            | ALoad { .. }
+            | IStore { .. }
+            | LStore { .. }
+            | FStore { .. }
+            | DStore { .. }
+            | AStore { .. }
+            | Ret { .. }
+            | NewArray { .. } => 2,
+            SiPush { .. }
+            | LdcW { .. }
+            | Ldc2W { .. }
+            | IInc { .. }
+            | IfEq { .. }
+            | IfNe { .. }
+            | IfLt { .. }
+            | IfGe { .. }
+            | IfGt { .. }
+            | IfLe { .. }
+            | IfICmpEq { .. }
+            | IfICmpNe { .. }
+            | IfICmpLt { .. }
+            | IfICmpGe { .. }
+            | IfICmpGt { .. }
+            | IfICmpLe { .. }
+            | IfACmpEq { .. }
+            | IfACmpNe { .. }
+            | IfNull { .. }
+            | IfNonNull { .. }
+            | Goto { .. }
+            | Jsr { .. }
+            | GetStatic { .. }
+            | PutStatic { .. }
+            | GetField { .. }
+            | PutField { .. }
+            | InvokeVirtual { .. }
+            | InvokeSpecial { .. }
+            | InvokeStatic { .. }
+            | New { .. }
+            | ANewArray { .. }
+            | CheckCast { .. }
+            | InstanceOf { .. } => 3,
+            Wide(RawWideInstruction::IInc { .. }) => 6,
+            MultiANewArray { .. } | Wide(_) => 4,
+            InvokeInterface { .. } | InvokeDynamic { .. } | GotoW { .. } | JsrW { .. } => 5,
+            TableSwitch { jump_offsets, .. } => {
+                let targets = u32::try_from(jump_offsets.len()).unwrap_or(u32::MAX / 4);
+                1 + padding + 12 + 4 * targets
+            }
+            LookupSwitch { match_offsets, .. } => {
+                let pairs = u32::try_from(match_offsets.len()).unwrap_or(u32::MAX / 8);
+                1 + padding + 8 + 8 * pairs
+            }
+            _ => 1,
+        }
+    }
+
+    const fn opcode_kind(&self) -> Opcode {
+        match Opcode::from_u8(self.opcode()) {
+            Some(it) => it,
+            // The discriminants of the variants are the opcodes.
+            None => unreachable!(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -375,4 +563,29 @@ mod test {
         assert_eq!(IConstM1.opcode(), 0x02);
         assert_eq!(ILoad { index: 233 }.opcode(), 0x15);
     }
+
+    #[test]
+    fn metadata() {
+        use super::{Opcode, StackEffect};
+
+        let switch = LookupSwitch {
+            default: 12,
+            match_offsets: vec![(1, 4), (2, -4)],
+        };
+        assert_eq!(Opcode::from(&switch), Opcode::LookupSwitch);
+        assert_eq!(
+            switch.branch_targets(8.into()).unwrap(),
+            vec![12.into(), 4.into(), 20.into()]
+        );
+        assert!(IfEq { offset: -10 }.branch_targets(8.into()).is_err());
+        assert_eq!(GetField { field_ref_index: 1 }.stack_effect(), None);
+        assert_eq!(
+            MultiANewArray {
+                index: 1,
+                dimensions: 3
+            }
+            .stack_effect(),
+            Some(StackEffect::new(3, 1))
+        );
+    }
 }
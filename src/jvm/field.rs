@@ -6,11 +6,7 @@ impl Field {
     /// Creates a [`FieldRef`] referring to the field.
     #[must_use]
     pub fn as_ref(&self) -> FieldRef {
-        FieldRef {
-            owner: self.owner.clone(),
-            name: self.name.clone(),
-            field_type: self.field_type.clone(),
-        }
+        FieldRef::new(self.owner.clone(), &self.name, self.field_type.clone())
     }
 }
 
@@ -0,0 +1,344 @@
+//! High-level operations for instrumenting the code of methods.
+//!
+//! The operations insert or replace instructions in a [`MethodBody`] without changing the
+//! types of the locals and the operand stack at the existing instructions.
+//! The instructions are laid out again, the branch targets are updated, and the exception
+//! table, the `StackMapTable`, and the debugging attributes are remapped to the new locations
+//! by [`MethodBody::replace_instructions`].
+
+use std::collections::BTreeMap;
+
+use crate::types::{
+    field_type::FieldType,
+    method_descriptor::{MethodDescriptor, ReturnType},
+};
+
+use super::{
+    code::{
+        BodyReplacementError, BodyReplacementOptions, Instruction, InstructionList, MethodBody,
+        ProgramCounter,
+    },
+    references::MethodRef,
+    Method,
+};
+
+/// An error when instrumenting a method.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The method does not have a body.
+    #[error("The method does not have a body")]
+    NoMethodBody,
+    /// The descriptor of the hook is not compatible with the instrumented location.
+    #[error("The hook {0} is not compatible with the instrumented location")]
+    IncompatibleHook(MethodRef),
+    /// The instrumented code is longer than 65535 bytes.
+    #[error("The code is too long")]
+    CodeTooLong,
+    /// The offset of the branch at the given location in the instrumented code does not fit in
+    /// the instruction.
+    #[error("The branch at {0} is too far from its target")]
+    BranchTooFar(ProgramCounter),
+    /// The instrumented instructions cannot replace the original ones.
+    #[error("Failed to replace the instructions: {0}")]
+    Replacement(#[from] BodyReplacementError),
+}
+
+/// Inserts a call to the static method `hook` at the beginning of `method`.
+/// The hook must have the descriptor `()V`.
+/// Branches to the first instruction of the method do not execute the hook.
+///
+/// # Errors
+/// See [`Error`].
+pub fn insert_method_entry_call(method: &mut Method, hook: &MethodRef) -> Result<(), Error> {
+    if hook.descriptor != void_descriptor() {
+        return Err(Error::IncompatibleHook(hook.clone()));
+    }
+    let body = method.body.as_mut().ok_or(Error::NoMethodBody)?;
+    let prologue = vec![Instruction::InvokeStatic(hook.clone())];
+    rewrite(body, prologue, BTreeMap::new())
+}
+
+/// Inserts a call to the static method `hook` before each instruction that returns normally from
+/// `method`, and returns the number of the instrumented exits.
+/// The hook must either have the descriptor `()V`, or take and return a value of the return type
+/// of `method`, in which case the returned value of `method` is replaced by the one of the hook.
+///
+/// # Errors
+/// See [`Error`].
+pub fn wrap_method_exit(method: &mut Method, hook: &MethodRef) -> Result<usize, Error> {
+    let is_compatible = match &method.descriptor.return_type {
+        ReturnType::Void => hook.descriptor == void_descriptor(),
+        ReturnType::Some(return_type) => {
+            hook.descriptor == void_descriptor()
+                || hook.descriptor
+                    == MethodDescriptor::new(
+                        [return_type.clone()],
+                        ReturnType::Some(return_type.clone()),
+                    )
+        }
+    };
+    if !is_compatible {
+        return Err(Error::IncompatibleHook(hook.clone()));
+    }
+    let body = method.body.as_mut().ok_or(Error::NoMethodBody)?;
+    let edits: BTreeMap<_, _> = body
+        .instructions
+        .iter()
+        .filter(|(_, insn)| is_return(insn))
+        .map(|(pc, _)| {
+            let edit = Edit {
+                insert_before: vec![Instruction::InvokeStatic(hook.clone())],
+                replacement: None,
+            };
+            (*pc, edit)
+        })
+        .collect();
+    let count = edits.len();
+    rewrite(body, Vec::new(), edits)?;
+    Ok(count)
+}
+
+/// Replaces the `invokevirtual`, `invokeinterface`, and `invokestatic` instructions in `method`
+/// that invoke a method matched by `matcher` with an `invokestatic` instruction invoking
+/// `wrapper`, and returns the number of the replaced call sites.
+///
+/// For an instance method, the wrapper takes the receiver as its first parameter followed by the
+/// parameters of the invoked method. For a static method, the wrapper takes the same parameters.
+/// In both cases, the wrapper must return the same type as the invoked method.
+/// The calls via `invokespecial` are not intercepted since their receivers may be uninitialized.
+///
+/// # Errors
+/// See [`Error`].
+pub fn intercept_call_sites(
+    method: &mut Method,
+    matcher: impl Fn(&MethodRef) -> bool,
+    wrapper: &MethodRef,
+) -> Result<usize, Error> {
+    let body = method.body.as_mut().ok_or(Error::NoMethodBody)?;
+    let mut edits = BTreeMap::new();
+    for (pc, insn) in &body.instructions {
+        let (target, has_receiver) = match insn {
+            Instruction::InvokeVirtual(target) | Instruction::InvokeInterface(target, _) => {
+                (target, true)
+            }
+            Instruction::InvokeStatic(target) => (target, false),
+            _ => continue,
+        };
+        if !matcher(target) {
+            continue;
+        }
+        if !is_wrapper_of(wrapper, target, has_receiver) {
+            return Err(Error::IncompatibleHook(wrapper.clone()));
+        }
+        let edit = Edit {
+            insert_before: Vec::new(),
+            replacement: Some(Instruction::InvokeStatic(wrapper.clone())),
+        };
+        edits.insert(*pc, edit);
+    }
+    let count = edits.len();
+    if count > 0 {
+        rewrite(body, Vec::new(), edits)?;
+    }
+    Ok(count)
+}
+
+/// Checks if `wrapper` can replace a call to `target`.
+fn is_wrapper_of(wrapper: &MethodRef, target: &MethodRef, has_receiver: bool) -> bool {
+    let wrapper_params = &wrapper.descriptor.parameters_types;
+    let target_params = &target.descriptor.parameters_types;
+    let params_match = if has_receiver {
+        matches!(
+            wrapper_params.split_first(),
+            Some((FieldType::Object(_) | FieldType::Array(_), rest)) if rest == &**target_params
+        )
+    } else {
+        wrapper_params == target_params
+    };
+    params_match && wrapper.descriptor.return_type == target.descriptor.return_type
+}
+
+fn void_descriptor() -> MethodDescriptor {
+    MethodDescriptor::new([], ReturnType::Void)
+}
+
+const fn is_return(insn: &Instruction) -> bool {
+    matches!(
+        insn,
+        Instruction::IReturn
+            | Instruction::LReturn
+            | Instruction::FReturn
+            | Instruction::DReturn
+            | Instruction::AReturn
+            | Instruction::Return
+    )
+}
+
+/// An edit to an instruction.
+struct Edit {
+    /// The instructions inserted before the instruction.
+    /// The branches to the instruction are redirected to the first inserted instruction.
+    insert_before: Vec<Instruction>,
+    /// The instruction replacing the original one.
+    replacement: Option<Instruction>,
+}
+
+/// Applies `edits` to the instructions of `body`, and inserts `prologue` at the beginning of the
+/// body.
+fn rewrite(
+    body: &mut MethodBody,
+    prologue: Vec<Instruction>,
+    mut edits: BTreeMap<ProgramCounter, Edit>,
+) -> Result<(), Error> {
+    let mut laid_out = Vec::new();
+    let mut pc_mapping = BTreeMap::new();
+    let mut next_pc = 0u32;
+    let mut place = |insn: Instruction, laid_out: &mut Vec<_>| -> Result<ProgramCounter, Error> {
+        let pc = u16::try_from(next_pc).map_err(|_| Error::CodeTooLong)?;
+        next_pc += insn.encoded_size(pc.into());
+        laid_out.push((ProgramCounter::from(pc), insn));
+        Ok(pc.into())
+    };
+    for insn in prologue {
+        place(insn, &mut laid_out)?;
+    }
+    for (old_pc, insn) in body.instructions.iter() {
+        let edit = edits.remove(old_pc);
+        let (insert_before, replacement) = edit
+            .map(|it| (it.insert_before, it.replacement))
+            .unwrap_or_default();
+        let mut new_pc = None;
+        for inserted in insert_before {
+            let pc = place(inserted, &mut laid_out)?;
+            new_pc.get_or_insert(pc);
+        }
+        let pc = place(replacement.unwrap_or_else(|| insn.clone()), &mut laid_out)?;
+        pc_mapping.insert(*old_pc, new_pc.unwrap_or(pc));
+    }
+    // The end of the code is the exclusive end of the ranges in the tables.
+    if !body.instructions.is_empty() {
+        let old_end = body.instructions.code_length();
+        let new_end = u16::try_from(next_pc).map_err(|_| Error::CodeTooLong)?;
+        if let Ok(old_end) = u16::try_from(old_end) {
+            pc_mapping.insert(old_end.into(), new_end.into());
+        }
+    }
+
+    let instructions = laid_out
+        .into_iter()
+        .map(|(pc, mut insn)| {
+            insn.retarget(&pc_mapping);
+            if insn.fits_branch_offsets(pc) {
+                Ok((pc, insn))
+            } else {
+                Err(Error::BranchTooFar(pc))
+            }
+        })
+        .collect::<Result<BTreeMap<_, _>, Error>>()?;
+    let options = BodyReplacementOptions {
+        pc_mapping,
+        ..Default::default()
+    };
+    body.replace_instructions(InstructionList::from(instructions), &options)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::{interop::jasmin, references::ClassRef, Class};
+
+    fn class() -> Class {
+        jasmin::read(
+            "
+            .bytecode 52.0
+            .class public Test
+            .super java/lang/Object
+            .method public static max(II)I
+                .limit stack 2
+                .limit locals 2
+                iload_0
+                iload_1
+                if_icmpge First
+                iload_1
+                ireturn
+            First:
+                iload_0
+                ireturn
+            .end method
+            .method public static size(Ljava/util/List;)I
+                .limit stack 1
+                .limit locals 1
+                aload_0
+                invokeinterface java/util/List/size()I 1
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap()
+    }
+
+    fn hook(name: &str, descriptor: &str) -> MethodRef {
+        MethodRef::new(ClassRef::new("Hooks"), name, descriptor.parse().unwrap())
+    }
+
+    #[test]
+    fn entry_and_exit() {
+        let mut method = class().methods.remove(0);
+        insert_method_entry_call(&mut method, &hook("enter", "()V")).unwrap();
+        assert_eq!(
+            wrap_method_exit(&mut method, &hook("exit", "(I)I")).unwrap(),
+            2
+        );
+        let body = method.body.unwrap();
+        assert!(matches!(
+            body.infer_frames(&class().methods[0]),
+            Ok(frames) if frames.len() == 10
+        ));
+        let invoke =
+            |name: &str, descriptor: &str| Instruction::InvokeStatic(hook(name, descriptor));
+        assert_eq!(
+            body.instructions.into_iter().collect::<Vec<_>>(),
+            vec![
+                (0.into(), invoke("enter", "()V")),
+                (3.into(), Instruction::ILoad0),
+                (4.into(), Instruction::ILoad1),
+                (5.into(), Instruction::IfICmpGe(13.into())),
+                (8.into(), Instruction::ILoad1),
+                (9.into(), invoke("exit", "(I)I")),
+                (12.into(), Instruction::IReturn),
+                (13.into(), Instruction::ILoad0),
+                (14.into(), invoke("exit", "(I)I")),
+                (17.into(), Instruction::IReturn),
+            ]
+        );
+    }
+
+    #[test]
+    fn call_sites() {
+        let mut method = class().methods.remove(1);
+        let matcher = |it: &MethodRef| &*it.name == "size";
+        assert!(matches!(
+            intercept_call_sites(&mut method, matcher, &hook("size", "()I")),
+            Err(Error::IncompatibleHook(_))
+        ));
+        let wrapper = hook("size", "(Ljava/util/Collection;)I");
+        assert_eq!(
+            intercept_call_sites(&mut method, matcher, &wrapper).unwrap(),
+            1
+        );
+        assert!(matches!(
+            insert_method_entry_call(&mut method, &hook("enter", "(I)V")),
+            Err(Error::IncompatibleHook(_))
+        ));
+        let body = method.body.unwrap();
+        assert_eq!(
+            body.instructions.into_iter().collect::<Vec<_>>(),
+            vec![
+                (0.into(), Instruction::ALoad0),
+                (1.into(), Instruction::InvokeStatic(wrapper)),
+                (4.into(), Instruction::IReturn),
+            ]
+        );
+    }
+}
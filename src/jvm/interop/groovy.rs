@@ -0,0 +1,128 @@
+//! Access to the metadata emitted by the Groovy compiler.
+//!
+//! Unlike Scala, the Groovy compiler does not define attributes of its own. The metadata of a
+//! Groovy class is carried by marker interfaces, annotations, and synthetic members, which are
+//! summarized in a [`GroovyClassInfo`].
+
+use crate::{
+    jvm::{Annotation, Class},
+    types::field_type::FieldType,
+};
+
+/// The kind of a class compiled by Groovy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroovyClassKind {
+    /// A plain class.
+    Class,
+    /// A script, i.e., a subclass of `groovy.lang.Script`.
+    Script,
+    /// A closure, i.e., a subclass of `groovy.lang.Closure`.
+    Closure,
+    /// A trait, i.e., an interface annotated with `@groovy.transform.Trait`.
+    Trait,
+}
+
+/// The Groovy-specific metadata of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroovyClassInfo {
+    /// The kind of the class.
+    pub kind: GroovyClassKind,
+    /// Whether the class has a `metaClass` field, i.e., it implements `GroovyObject` itself
+    /// rather than inheriting the implementation.
+    pub has_meta_class: bool,
+    /// Whether the class caches its static metadata in `$staticClassInfo`.
+    pub has_static_class_info: bool,
+    /// The names of the methods generated by the compiler, i.e., the ones annotated with
+    /// `@groovy.transform.Generated` or `@groovy.transform.Internal`.
+    pub generated_methods: Vec<String>,
+}
+
+const GROOVY_OBJECT: &str = "groovy/lang/GroovyObject";
+const SCRIPT: &str = "groovy/lang/Script";
+const CLOSURE: &str = "groovy/lang/Closure";
+const TRAIT: &str = "groovy/transform/Trait";
+const GENERATED: &str = "groovy/transform/Generated";
+const INTERNAL: &str = "groovy/transform/Internal";
+
+impl GroovyClassInfo {
+    /// Extracts the Groovy-specific metadata of `class`, returning [`None`] if the class does
+    /// not appear to be compiled by Groovy.
+    #[must_use]
+    pub fn of(class: &Class) -> Option<Self> {
+        let super_class = class.super_class.as_ref().map(|it| it.binary_name.as_ref());
+        let kind = if super_class == Some(SCRIPT) {
+            GroovyClassKind::Script
+        } else if super_class == Some(CLOSURE) {
+            GroovyClassKind::Closure
+        } else if has_annotation(&class.runtime_invisible_annotations, TRAIT)
+            || has_annotation(&class.runtime_visible_annotations, TRAIT)
+        {
+            GroovyClassKind::Trait
+        } else if class
+            .interfaces
+            .iter()
+            .any(|it| &*it.binary_name == GROOVY_OBJECT)
+        {
+            GroovyClassKind::Class
+        } else {
+            return None;
+        };
+        let has_field = |name: &str| class.fields.iter().any(|it| it.name == name);
+        let generated_methods = class
+            .methods
+            .iter()
+            .filter(|it| {
+                has_annotation(&it.runtime_visible_annotations, GENERATED)
+                    || has_annotation(&it.runtime_visible_annotations, INTERNAL)
+            })
+            .map(|it| it.name.clone())
+            .collect();
+        Some(Self {
+            kind,
+            has_meta_class: has_field("metaClass"),
+            has_static_class_info: has_field("$staticClassInfo"),
+            generated_methods,
+        })
+    }
+}
+
+fn has_annotation(annotations: &[Annotation], binary_name: &str) -> bool {
+    annotations.iter().any(|it| {
+        matches!(&it.annotation_type, FieldType::Object(class_ref) if &*class_ref.binary_name == binary_name)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::{references::ClassRef, Method};
+
+    #[test]
+    fn recognize_groovy_class() {
+        assert_eq!(GroovyClassInfo::of(&Class::default()), None);
+
+        let generated = Annotation {
+            annotation_type: FieldType::Object(ClassRef::new(GENERATED)),
+            element_value_pairs: Vec::new(),
+        };
+        let class = Class {
+            super_class: Some(ClassRef::new(SCRIPT)),
+            methods: vec![
+                Method {
+                    name: "getMetaClass".to_owned(),
+                    runtime_visible_annotations: vec![generated],
+                    ..Method::default()
+                },
+                Method {
+                    name: "run".to_owned(),
+                    ..Method::default()
+                },
+            ],
+            ..Class::default()
+        };
+        let info = GroovyClassInfo::of(&class).unwrap();
+        assert_eq!(info.kind, GroovyClassKind::Script);
+        assert!(!info.has_meta_class);
+        assert_eq!(info.generated_methods, vec!["getMetaClass".to_owned()]);
+    }
+}
@@ -0,0 +1,912 @@
+//! Reading and writing classes in the Jasmin assembly format.
+//!
+//! The following directives are supported: `.bytecode`, `.source`, `.class`, `.interface`,
+//! `.super`, `.implements`, `.field`, `.method`, `.limit`, `.throws`, `.catch`, and
+//! `.end method`. The debugging directives `.line` and `.var` are ignored when reading.
+//! The Krakatau variants `.version major minor`, `.method access name : descriptor`,
+//! `.code stack N locals M`, and the operands prefixed with `Field`, `Method`,
+//! `InterfaceMethod`, or `Class` are also accepted when reading.
+//!
+//! Instructions without counterparts in Jasmin, such as `invokedynamic`, cannot be written.
+
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use crate::{
+    jvm::{
+        builder::{self, ClassBuilder, FieldBuilder, MethodBuilder},
+        class::{self, Version},
+        code::{assembler, ExceptionTableEntry, Instruction, ProgramCounter, WideInstruction},
+        field, method,
+        references::ClassRef,
+        Class, ConstantValue, JavaString, Method,
+    },
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+/// An error when reading a class in the Jasmin format.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Line {line}: {kind}")]
+pub struct Error {
+    /// The line number (starting from 1) where the error occurs.
+    pub line: usize,
+    /// The kind of the error.
+    pub kind: ErrorKind,
+}
+
+/// The kind of an [`Error`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ErrorKind {
+    /// The directive is not supported.
+    #[error("Unsupported directive `{0}`")]
+    UnsupportedDirective(String),
+    /// The directive or the instruction is malformed.
+    #[error("Malformed line: {0}")]
+    Malformed(&'static str),
+    /// The directive appears where it is not allowed (e.g., `.limit` outside of methods).
+    #[error("Unexpected `{0}`")]
+    Unexpected(String),
+    /// The file ends before the class or a method is complete.
+    #[error("Unexpected end of file")]
+    UnexpectedEnd,
+    /// An instruction cannot be assembled.
+    #[error(transparent)]
+    Assembly(#[from] assembler::ErrorKind),
+    /// The assembled class is invalid.
+    #[error(transparent)]
+    Invalid(#[from] builder::Error),
+}
+
+/// An element that cannot be written in the Jasmin format.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{0} cannot be written in Jasmin")]
+pub struct Unsupported(pub String);
+
+/// Reads a class in the Jasmin format.
+///
+/// # Errors
+/// See [`Error`].
+pub fn read(text: &str) -> Result<Class, Error> {
+    Reader::default().read(text)
+}
+
+/// Writes `class` in the Jasmin format.
+/// Attributes that have no directive in Jasmin (e.g., annotations) are omitted.
+///
+/// # Errors
+/// See [`Unsupported`].
+pub fn write(class: &Class) -> Result<String, Unsupported> {
+    let mut text = String::new();
+    let flags = class.access_flags;
+    writeln!(
+        text,
+        ".bytecode {}.{}",
+        class.version.major(),
+        class.version.minor()
+    )
+    .expect("Writing to a string never fails");
+    let mut line = |it: String| {
+        text.push_str(&it);
+        text.push('\n');
+    };
+    if let Some(source_file) = &class.source_file {
+        line(format!(".source {source_file}"));
+    }
+    if flags.contains(class::AccessFlags::INTERFACE) {
+        let keywords = keywords(&(flags - class::AccessFlags::INTERFACE));
+        line(format!(".interface {keywords}{}", class.binary_name));
+    } else {
+        line(format!(".class {}{}", keywords(&flags), class.binary_name));
+    }
+    if let Some(super_class) = &class.super_class {
+        line(format!(".super {super_class}"));
+    }
+    for interface in &class.interfaces {
+        line(format!(".implements {interface}"));
+    }
+    for field in &class.fields {
+        let mut declaration = format!(
+            ".field {}{} {}",
+            keywords(&field.access_flags),
+            field.name,
+            field.field_type.descriptor()
+        );
+        if let Some(value) = &field.constant_value {
+            let value = match value {
+                ConstantValue::Integer(it) => it.to_string(),
+                ConstantValue::Long(it) => it.to_string(),
+                ConstantValue::Float(it) => format!("{it:?}"),
+                ConstantValue::Double(it) => format!("{it:?}"),
+                ConstantValue::String(it) => quote(it)?,
+                it => return Err(Unsupported(format!("The constant value {it}"))),
+            };
+            write!(declaration, " = {value}").expect("Writing to a string never fails");
+        }
+        line(declaration);
+    }
+    for method in &class.methods {
+        line(String::new());
+        write_method(&mut line, method)?;
+    }
+    Ok(text)
+}
+
+fn write_method(line: &mut impl FnMut(String), method: &Method) -> Result<(), Unsupported> {
+    line(format!(
+        ".method {}{}{}",
+        keywords(&method.access_flags),
+        method.name,
+        method.descriptor.descriptor()
+    ));
+    for exception in &method.exceptions {
+        line(format!("    .throws {exception}"));
+    }
+    if let Some(body) = &method.body {
+        line(format!("    .limit stack {}", body.max_stack));
+        line(format!("    .limit locals {}", body.max_locals));
+        let label_of = |pc: ProgramCounter| format!("L{}", u16::from(pc));
+        // The exclusive end of a range covering the last instruction is labelled after it.
+        let end_of = |entry: &ExceptionTableEntry| {
+            body.instructions
+                .next_pc_of(entry.covered_pc.end())
+                .unwrap_or_else(|| ProgramCounter::from(u16::from(*entry.covered_pc.end()) + 1))
+        };
+        let mut labelled: Vec<_> = body
+            .exception_table
+            .iter()
+            .flat_map(|it| [*it.covered_pc.start(), end_of(it), it.handler_pc])
+            .chain(
+                body.instructions
+                    .iter()
+                    .flat_map(|(_, insn)| insn.branch_targets()),
+            )
+            .collect();
+        labelled.sort_unstable();
+        labelled.dedup();
+        for (pc, insn) in &body.instructions {
+            if labelled.binary_search(pc).is_ok() {
+                line(format!("{}:", label_of(*pc)));
+            }
+            line(format!("    {}", instruction(insn, label_of)?));
+        }
+        if let Some(last) = labelled
+            .last()
+            .filter(|it| body.instructions.get(it).is_none())
+        {
+            line(format!("{}:", label_of(*last)));
+        }
+        for entry in &body.exception_table {
+            line(format!(
+                "    .catch {} from {} to {} using {}",
+                entry
+                    .catch_type
+                    .as_ref()
+                    .map_or_else(|| "all".to_owned(), ToString::to_string),
+                label_of(*entry.covered_pc.start()),
+                label_of(end_of(entry)),
+                label_of(entry.handler_pc)
+            ));
+        }
+    }
+    line(".end method".to_owned());
+    Ok(())
+}
+
+/// Formats the access flags as lowercase keywords, each followed by a space.
+fn keywords<F: bitflags::Flags>(flags: &F) -> String {
+    flags
+        .iter_names()
+        .fold(String::new(), |mut keywords, (name, _)| {
+            keywords.push_str(&name.to_lowercase());
+            keywords.push(' ');
+            keywords
+        })
+}
+
+fn quote(string: &JavaString) -> Result<String, Unsupported> {
+    let JavaString::Utf8(string) = string else {
+        return Err(Unsupported("A string with invalid UTF-8".to_owned()));
+    };
+    let mut quoted = String::from('"');
+    for c in string.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => {
+                let mut buf = [0u16; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    write!(quoted, "\\u{unit:04x}").expect("Writing to a string never fails");
+                }
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    Ok(quoted)
+}
+
+fn class_operand(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Object(class_ref) => class_ref.binary_name.to_string(),
+        it => it.descriptor(),
+    }
+}
+
+fn instruction(
+    insn: &Instruction,
+    label_of: impl Fn(ProgramCounter) -> String,
+) -> Result<String, Unsupported> {
+    #[allow(clippy::enum_glob_use, reason = "There are too many variants")]
+    use Instruction::*;
+    let name = insn.name();
+    let operands = match insn {
+        BiPush(value) => i8::from_be_bytes([*value]).to_string(),
+        SiPush(value) => i16::from_be_bytes(value.to_be_bytes()).to_string(),
+        Ldc(constant) | LdcW(constant) | Ldc2W(constant) => match constant {
+            ConstantValue::Integer(it) => it.to_string(),
+            ConstantValue::Long(it) => it.to_string(),
+            ConstantValue::Float(it) => format!("{it:?}"),
+            ConstantValue::Double(it) => format!("{it:?}"),
+            ConstantValue::String(it) => quote(it)?,
+            it => return Err(Unsupported(format!("The constant {it}"))),
+        },
+        ILoad(idx) | LLoad(idx) | FLoad(idx) | DLoad(idx) | ALoad(idx) | IStore(idx)
+        | LStore(idx) | FStore(idx) | DStore(idx) | AStore(idx) | Ret(idx) => idx.to_string(),
+        IInc(idx, delta) => format!("{idx} {delta}"),
+        Wide(wide) => {
+            // Jasmin emits `wide` automatically for large indices.
+            let (name, operands) = match wide {
+                WideInstruction::ILoad(idx) => ("iload", idx.to_string()),
+                WideInstruction::LLoad(idx) => ("lload", idx.to_string()),
+                WideInstruction::FLoad(idx) => ("fload", idx.to_string()),
+                WideInstruction::DLoad(idx) => ("dload", idx.to_string()),
+                WideInstruction::ALoad(idx) => ("aload", idx.to_string()),
+                WideInstruction::IStore(idx) => ("istore", idx.to_string()),
+                WideInstruction::LStore(idx) => ("lstore", idx.to_string()),
+                WideInstruction::FStore(idx) => ("fstore", idx.to_string()),
+                WideInstruction::DStore(idx) => ("dstore", idx.to_string()),
+                WideInstruction::AStore(idx) => ("astore", idx.to_string()),
+                WideInstruction::IInc(idx, delta) => ("iinc", format!("{idx} {delta}")),
+                WideInstruction::Ret(idx) => ("ret", idx.to_string()),
+            };
+            return Ok(format!("{name} {operands}"));
+        }
+        TableSwitch {
+            range,
+            jump_targets,
+            default,
+        } => {
+            let targets = jump_targets
+                .iter()
+                .map(|it| format!("\n        {}", label_of(*it)))
+                .join("");
+            format!(
+                "{} {}{targets}\n        default : {}",
+                range.start(),
+                range.end(),
+                label_of(*default)
+            )
+        }
+        LookupSwitch {
+            default,
+            match_targets,
+        } => {
+            let targets = match_targets
+                .iter()
+                .map(|(key, it)| format!("\n        {key} : {}", label_of(*it)))
+                .join("");
+            format!("{targets}\n        default : {}", label_of(*default))
+        }
+        GetStatic(field) | PutStatic(field) | GetField(field) | PutField(field) => format!(
+            "{}/{} {}",
+            field.owner,
+            field.name,
+            field.field_type.descriptor()
+        ),
+        InvokeVirtual(method) | InvokeSpecial(method) | InvokeStatic(method) => format!(
+            "{}/{}{}",
+            method.owner,
+            method.name,
+            method.descriptor.descriptor()
+        ),
+        InvokeInterface(method, count) => format!(
+            "{}/{}{} {count}",
+            method.owner,
+            method.name,
+            method.descriptor.descriptor()
+        ),
+        InvokeDynamic { .. } => return Err(Unsupported("invokedynamic".to_owned())),
+        New(class_ref) | ANewArray(class_ref) => class_ref.binary_name.to_string(),
+        NewArray(element_type) => element_type.to_string(),
+        CheckCast(field_type) | InstanceOf(field_type) => class_operand(field_type),
+        MultiANewArray(field_type, dimensions) => {
+            format!("{} {dimensions}", class_operand(field_type))
+        }
+        insn => {
+            let targets = insn.branch_targets();
+            match targets.as_slice() {
+                [target] => label_of(*target),
+                _ => return Ok(name.to_owned()),
+            }
+        }
+    };
+    Ok(format!("{name} {operands}"))
+}
+
+/// A method being read.
+struct MethodInProgress {
+    builder: MethodBuilder,
+    max_stack: Option<u16>,
+    max_locals: Option<u16>,
+    code: String,
+    /// The line numbers of the lines in `code`.
+    code_lines: Vec<usize>,
+    catches: Vec<(usize, Option<ClassRef>, String, String, String)>,
+}
+
+#[derive(Default)]
+struct Reader {
+    class: Option<ClassBuilder>,
+    version: Option<Version>,
+    source_file: Option<String>,
+    methods: Vec<MethodBuilder>,
+    fields: Vec<FieldBuilder>,
+    super_class: Option<ClassRef>,
+    interfaces: Vec<ClassRef>,
+    method: Option<MethodInProgress>,
+    /// The switch instruction spanning multiple lines being read.
+    switch: Option<(usize, String)>,
+}
+
+impl Reader {
+    fn read(mut self, text: &str) -> Result<Class, Error> {
+        let mut last_line = 0;
+        for (idx, raw_line) in text.lines().enumerate() {
+            let line = idx + 1;
+            last_line = line;
+            let tokens = tokenize(raw_line);
+            if tokens.is_empty() {
+                continue;
+            }
+            if tokens[..] == [".end", "method"] {
+                self.end_method(line)?;
+            } else {
+                self.read_line(line, &tokens)
+                    .map_err(|kind| Error { line, kind })?;
+            }
+        }
+        let error = |kind| Error {
+            line: last_line,
+            kind,
+        };
+        if self.method.is_some() {
+            return Err(error(ErrorKind::UnexpectedEnd));
+        }
+        let mut class = self.class.ok_or(error(ErrorKind::UnexpectedEnd))?;
+        if let Some(version) = self.version {
+            class = class.with_version(version);
+        }
+        if let Some(source_file) = self.source_file {
+            class = class.with_source_file(source_file);
+        }
+        class = class.with_super_class(
+            self.super_class
+                .or_else(|| Some(ClassRef::new("java/lang/Object"))),
+        );
+        for interface in self.interfaces {
+            class = class.with_interface(interface);
+        }
+        for field in self.fields {
+            class = class.with_field(field);
+        }
+        for method in self.methods {
+            class = class.with_method(method);
+        }
+        class.build().map_err(|it| error(it.into()))
+    }
+
+    fn read_line(&mut self, line: usize, tokens: &[String]) -> Result<(), ErrorKind> {
+        if let Some((start, mut switch)) = self.switch.take() {
+            switch.push(' ');
+            if tokens[0] == "default" {
+                let target = tokens
+                    .last()
+                    .ok_or(ErrorKind::Malformed("Missing target"))?;
+                switch.push_str("default ");
+                switch.push_str(target);
+                self.push_code(start, &switch);
+            } else {
+                // A `key : target` pair or a target.
+                switch.push_str(&tokens.concat());
+                self.switch = Some((start, switch));
+            }
+            return Ok(());
+        }
+        let directive = tokens[0].as_str();
+        match directive {
+            ".bytecode" | ".version" => {
+                let (major, minor) = match tokens {
+                    [_, version] => version.split_once('.').unwrap_or((version.as_str(), "0")),
+                    [_, major, minor] => (major.as_str(), minor.as_str()),
+                    _ => return Err(ErrorKind::Malformed("Invalid version")),
+                };
+                let major = major
+                    .parse()
+                    .map_err(|_| ErrorKind::Malformed("Invalid version"))?;
+                let minor = minor
+                    .parse()
+                    .map_err(|_| ErrorKind::Malformed("Invalid version"))?;
+                self.version = Some(
+                    Version::from_versions(major, minor)
+                        .map_err(|_| ErrorKind::Malformed("Unsupported version"))?,
+                );
+            }
+            ".source" => self.source_file = Some(tokens[1..].join(" ")),
+            ".class" | ".interface" => {
+                let (name, keywords) = tokens[1..]
+                    .split_last()
+                    .ok_or(ErrorKind::Malformed("Missing class name"))?;
+                let mut flags: class::AccessFlags = parse_flags(keywords)?;
+                if directive == ".interface" {
+                    flags |= class::AccessFlags::INTERFACE | class::AccessFlags::ABSTRACT;
+                }
+                if self.class.is_some() {
+                    return Err(ErrorKind::Unexpected(directive.to_owned()));
+                }
+                self.class = Some(ClassBuilder::new(name.clone()).with_access_flags(flags));
+            }
+            ".super" => {
+                self.super_class = Some(ClassRef::new(
+                    tokens
+                        .get(1)
+                        .ok_or(ErrorKind::Malformed("Missing super class"))?,
+                ));
+            }
+            ".implements" => self.interfaces.push(ClassRef::new(
+                tokens
+                    .get(1)
+                    .ok_or(ErrorKind::Malformed("Missing interface"))?,
+            )),
+            ".field" => self.fields.push(parse_field(&tokens[1..])?),
+            ".method" => {
+                self.begin_method(tokens)?;
+            }
+            ".end" if tokens.get(1).is_some_and(|it| it == "code") => {}
+            ".line" | ".var" => {}
+            ".limit" | ".code" | ".throws" | ".catch" => {
+                self.read_method_directive(line, tokens)?;
+            }
+            _ if directive.starts_with('.') => {
+                return Err(ErrorKind::UnsupportedDirective(directive.to_owned()));
+            }
+            _ => {
+                if self.method.is_none() {
+                    return Err(ErrorKind::Unexpected(directive.to_owned()));
+                }
+                let code = translate_instruction(tokens);
+                if ["tableswitch", "lookupswitch"]
+                    .iter()
+                    .any(|it| code.split_whitespace().any(|token| token == *it))
+                {
+                    // `tableswitch low high` is followed by the targets in the next lines.
+                    let mut tokens: Vec<_> = code.split_whitespace().collect();
+                    if let Some(idx) = tokens.iter().position(|it| *it == "tableswitch") {
+                        tokens.truncate(idx + 2);
+                    }
+                    self.switch = Some((line, tokens.join(" ")));
+                } else {
+                    self.push_code(line, &code);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn begin_method(&mut self, tokens: &[String]) -> Result<(), ErrorKind> {
+        if self.method.is_some() {
+            return Err(ErrorKind::Unexpected(".method".to_owned()));
+        }
+        let mut tokens = tokens[1..].to_vec();
+        // Krakatau separates the name and the descriptor with ` : `.
+        if let Some(idx) = tokens.iter().position(|it| it == ":") {
+            let descriptor = tokens.remove(idx + 1);
+            tokens.remove(idx);
+            tokens[idx - 1].push_str(&descriptor);
+        }
+        let (signature, keywords) = tokens
+            .split_last()
+            .ok_or(ErrorKind::Malformed("Missing method name"))?;
+        let (name, descriptor) = signature
+            .find('(')
+            .map(|idx| signature.split_at(idx))
+            .ok_or(ErrorKind::Malformed("Missing method descriptor"))?;
+        let descriptor = descriptor
+            .parse()
+            .map_err(|_| ErrorKind::Malformed("Invalid method descriptor"))?;
+        let flags: method::AccessFlags = parse_flags(keywords)?;
+        self.method = Some(MethodInProgress {
+            builder: MethodBuilder::new(name, descriptor).with_access_flags(flags),
+            max_stack: None,
+            max_locals: None,
+            code: String::new(),
+            code_lines: Vec::new(),
+            catches: Vec::new(),
+        });
+        Ok(())
+    }
+
+    fn read_method_directive(&mut self, line: usize, tokens: &[String]) -> Result<(), ErrorKind> {
+        let directive = tokens[0].as_str();
+        let method = self
+            .method
+            .as_mut()
+            .ok_or_else(|| ErrorKind::Unexpected(directive.to_owned()))?;
+        let number = |token: Option<&String>| {
+            token
+                .and_then(|it| it.parse().ok())
+                .ok_or(ErrorKind::Malformed("Invalid limit"))
+        };
+        match (directive, tokens.get(1).map(String::as_str)) {
+            (".limit", Some("stack")) => method.max_stack = Some(number(tokens.get(2))?),
+            (".limit", Some("locals")) => {
+                method.max_locals = Some(number(tokens.get(2))?);
+            }
+            (".code", _) => {
+                for (key, value) in tokens[1..].iter().tuples() {
+                    match key.as_str() {
+                        "stack" => method.max_stack = Some(number(Some(value))?),
+                        "locals" => method.max_locals = Some(number(Some(value))?),
+                        _ => return Err(ErrorKind::Malformed("Invalid `.code`")),
+                    }
+                }
+            }
+            (".throws", Some(exception)) => {
+                method.builder = method
+                    .builder
+                    .clone()
+                    .with_exception(ClassRef::new(exception));
+            }
+            (".catch", Some(catch_type)) => {
+                let [_, _, from, start, to, end, using, handler] = tokens else {
+                    return Err(ErrorKind::Malformed("Invalid `.catch`"));
+                };
+                if (from.as_str(), to.as_str(), using.as_str()) != ("from", "to", "using") {
+                    return Err(ErrorKind::Malformed("Invalid `.catch`"));
+                }
+                let catch_type = (catch_type != "all").then(|| ClassRef::new(catch_type));
+                method.catches.push((
+                    line,
+                    catch_type,
+                    start.clone(),
+                    end.clone(),
+                    handler.clone(),
+                ));
+            }
+            _ => return Err(ErrorKind::Malformed("Invalid directive")),
+        }
+        Ok(())
+    }
+
+    fn push_code(&mut self, line: usize, code: &str) {
+        if let Some(method) = self.method.as_mut() {
+            method.code.push_str(code);
+            method.code.push('\n');
+            method.code_lines.push(line);
+        }
+    }
+
+    fn end_method(&mut self, line: usize) -> Result<(), Error> {
+        let method = self.method.take().ok_or(Error {
+            line,
+            kind: ErrorKind::Unexpected(".end method".to_owned()),
+        })?;
+        let mut builder = method.builder;
+        if !method.code.is_empty() || method.max_stack.is_some() {
+            let (instructions, labels) =
+                assembler::assemble_with_labels(&method.code).map_err(|it| Error {
+                    line: method.code_lines.get(it.line - 1).copied().unwrap_or(line),
+                    kind: it.kind.into(),
+                })?;
+            for (line, catch_type, start, end, handler) in method.catches {
+                let error = |kind| Error { line, kind };
+                let label = |name: &str| {
+                    labels.get(name).copied().ok_or_else(|| {
+                        error(assembler::ErrorKind::UndefinedLabel(name.to_owned()).into())
+                    })
+                };
+                // The end label is exclusive and may follow the last instruction.
+                let covered_end = instructions
+                    .prev_pc_of(&label(&end)?)
+                    .ok_or(error(ErrorKind::Malformed("Empty `.catch` range")))?;
+                builder = builder.with_exception_handler(ExceptionTableEntry {
+                    covered_pc: label(&start)?..=covered_end,
+                    handler_pc: label(&handler)?,
+                    catch_type,
+                });
+            }
+            builder = builder.with_body(method.max_stack.unwrap_or_default(), instructions);
+            if let Some(max_locals) = method.max_locals {
+                builder = builder.with_max_locals(max_locals);
+            }
+        }
+        self.methods.push(builder);
+        Ok(())
+    }
+}
+
+/// Splits a line into tokens, keeping quoted strings as single tokens and dropping the
+/// comments, which start with `;` at the beginning of a token.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                current.push(c);
+                while let Some(c) = chars.next() {
+                    current.push(c);
+                    match c {
+                        '\\' => current.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            ';' if current.is_empty() => break,
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_flags<F: bitflags::Flags>(keywords: &[String]) -> Result<F, ErrorKind> {
+    keywords.iter().try_fold(F::empty(), |flags, keyword| {
+        let flag = F::from_name(&keyword.to_uppercase())
+            .ok_or(ErrorKind::Malformed("Unknown access flag"))?;
+        Ok(flags.union(flag))
+    })
+}
+
+fn parse_field(tokens: &[String]) -> Result<FieldBuilder, ErrorKind> {
+    let (declaration, value) = match tokens.iter().position(|it| it == "=") {
+        Some(idx) => (&tokens[..idx], tokens.get(idx + 1)),
+        None => (tokens, None),
+    };
+    let [keywords @ .., name, descriptor] = declaration else {
+        return Err(ErrorKind::Malformed("Invalid `.field`"));
+    };
+    let field_type: FieldType = descriptor
+        .parse()
+        .map_err(|_| ErrorKind::Malformed("Invalid field descriptor"))?;
+    let flags: field::AccessFlags = parse_flags(keywords)?;
+    let mut field = FieldBuilder::new(name.clone(), field_type.clone()).with_access_flags(flags);
+    if let Some(value) = value {
+        let invalid = || ErrorKind::Malformed("Invalid constant value");
+        let value = match field_type {
+            FieldType::Base(PrimitiveType::Long) => {
+                ConstantValue::Long(value.parse().map_err(|_| invalid())?)
+            }
+            FieldType::Base(PrimitiveType::Float) => {
+                ConstantValue::Float(value.parse().map_err(|_| invalid())?)
+            }
+            FieldType::Base(PrimitiveType::Double) => {
+                ConstantValue::Double(value.parse().map_err(|_| invalid())?)
+            }
+            FieldType::Base(_) => ConstantValue::Integer(value.parse().map_err(|_| invalid())?),
+            FieldType::Object(_) | FieldType::Array(_) => {
+                let text = format!("ldc {value}");
+                match assembler::assemble(&text)
+                    .map_err(|it| it.kind)?
+                    .into_iter()
+                    .next()
+                {
+                    Some((_, Instruction::Ldc(it))) => it,
+                    _ => return Err(invalid()),
+                }
+            }
+        };
+        field = field.with_constant_value(value);
+    }
+    Ok(field)
+}
+
+/// Translates an instruction in Jasmin (or Krakatau) to the dialect of the
+/// [`assembler`](crate::jvm::code::assembler).
+fn translate_instruction(tokens: &[String]) -> String {
+    let mut tokens: Vec<String> = tokens.to_vec();
+    // Krakatau writes references as `Method owner name descriptor`.
+    if let Some(idx) = tokens
+        .iter()
+        .position(|it| matches!(it.as_str(), "Field" | "Method" | "InterfaceMethod"))
+    {
+        if let [owner, name, descriptor] = &tokens.get(idx + 1..idx + 4).unwrap_or_default() {
+            let member = format!("{owner}.{name}:{descriptor}");
+            tokens.splice(idx..idx + 4, [member]);
+        }
+    }
+    if let Some(idx) = tokens.iter().position(|it| it == "Class") {
+        "class".clone_into(&mut tokens[idx]);
+    }
+    // The mnemonic follows the labels.
+    let Some(idx) = tokens.iter().position(|it| !it.ends_with(':')) else {
+        return tokens.join(" ");
+    };
+    let operands_idx = idx + 1;
+    let mnemonic = tokens[idx].clone();
+    match mnemonic.as_str() {
+        "getstatic" | "putstatic" | "getfield" | "putfield" => {
+            if let [path, descriptor] = &tokens[operands_idx..] {
+                if let Some((owner, name)) = path.rsplit_once('/') {
+                    let member = format!("{owner}.{name}:{descriptor}");
+                    tokens.truncate(operands_idx);
+                    tokens.push(member);
+                }
+            }
+        }
+        "invokevirtual" | "invokespecial" | "invokestatic" | "invokeinterface" => {
+            if let Some(operand) = tokens.get_mut(operands_idx).filter(|it| !it.contains(':')) {
+                if let Some(paren) = operand.find('(') {
+                    let (path, descriptor) = operand.split_at(paren);
+                    if let Some((owner, name)) = path.rsplit_once('/') {
+                        *operand = format!("{owner}.{name}:{descriptor}");
+                    }
+                }
+            }
+        }
+        mnemonic @ ("ldc" | "ldc_w" | "ldc2_w") => {
+            if let Some(operand) = tokens.get_mut(operands_idx) {
+                let is_number = operand
+                    .chars()
+                    .next()
+                    .is_some_and(|it| it.is_ascii_digit() || it == '-');
+                let is_decimal = operand.contains(['.', 'e', 'E']);
+                let suffix = match (mnemonic, is_number, is_decimal) {
+                    ("ldc2_w", true, true) => "d",
+                    ("ldc2_w", true, false) => "L",
+                    (_, true, true) => "f",
+                    _ => "",
+                };
+                let has_suffix = operand.ends_with(['d', 'D', 'f', 'F', 'l', 'L']);
+                if !has_suffix {
+                    operand.push_str(suffix);
+                }
+            }
+        }
+        _ => {}
+    }
+    tokens.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO: &str = r#"
+        ; A class printing a greeting.
+        .bytecode 52.0
+        .source Hello.java
+        .class public super Hello
+        .super java/lang/Object
+        .implements java/lang/Runnable
+
+        .field private static final GREETING Ljava/lang/String; = "Hello, \"world\""
+        .field private count I
+
+        .method public <init>()V
+            .limit stack 1
+            .limit locals 1
+            aload_0
+            invokespecial java/lang/Object/<init>()V
+            return
+        .end method
+
+        .method public run()V
+            .limit stack 2
+            .limit locals 2
+        Start:
+            getstatic java/lang/System/out Ljava/io/PrintStream;
+            ldc "Hello"
+            invokevirtual java/io/PrintStream/println(Ljava/lang/String;)V
+            aload_0
+            getfield Hello/count I
+            tableswitch 0 1
+                Start
+                Done
+                default : Done
+        End:
+            astore_1
+        Done:
+            return
+            .catch java/lang/RuntimeException from Start to End using End
+        .end method
+    "#;
+
+    #[test]
+    fn read_jasmin() {
+        let class = read(HELLO).unwrap();
+        assert_eq!(class.binary_name, "Hello");
+        assert_eq!(class.source_file.as_deref(), Some("Hello.java"));
+        assert_eq!(class.interfaces, vec![ClassRef::new("java/lang/Runnable")]);
+        assert_eq!(
+            class.fields[0].constant_value,
+            Some(ConstantValue::String(JavaString::Utf8(
+                "Hello, \"world\"".to_owned()
+            )))
+        );
+        let body = class.methods[1].body.as_ref().unwrap();
+        assert_eq!(body.max_stack, 2);
+        let entry = &body.exception_table[0];
+        assert_eq!(entry.covered_pc, 0.into()..=12.into());
+        assert_eq!(entry.handler_pc, 36.into());
+        assert!(matches!(
+            body.instruction_at(12.into()),
+            Some(Instruction::TableSwitch { .. })
+        ));
+    }
+
+    #[test]
+    fn write_then_read() {
+        let class = read(HELLO).unwrap();
+        let text = write(&class).unwrap();
+        let reread = read(&text).unwrap();
+        assert_eq!(write(&reread).unwrap(), text);
+        assert_eq!(reread.methods.len(), 2);
+        let body = reread.methods[1].body.as_ref().unwrap();
+        assert_eq!(body.exception_table.len(), 1);
+        assert_eq!(body.instructions.len(), 8);
+    }
+
+    #[test]
+    fn read_krakatau() {
+        let text = "
+            .version 52 0
+            .class public Foo
+            .super java/lang/Object
+            .method public static main : ([Ljava/lang/String;)V
+                .code stack 2 locals 1
+                    getstatic Field java/lang/System out Ljava/io/PrintStream;
+                    ldc Class Foo
+                    invokevirtual Method java/io/PrintStream println (Ljava/lang/Object;)V
+                    return
+                .end code
+            .end method
+        ";
+        let class = read(text).unwrap();
+        let body = class.methods[0].body.as_ref().unwrap();
+        assert_eq!(body.max_locals, 1);
+        assert_eq!(
+            body.instruction_at(3.into()),
+            Some(&Instruction::Ldc(ConstantValue::Class(ClassRef::new(
+                "Foo"
+            ))))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            read(".class Foo\n.annotation").unwrap_err(),
+            Error {
+                line: 2,
+                kind: ErrorKind::UnsupportedDirective(".annotation".to_owned()),
+            }
+        );
+        assert_eq!(
+            read(".class Foo\n.method foo()V\n").unwrap_err().kind,
+            ErrorKind::UnexpectedEnd
+        );
+    }
+}
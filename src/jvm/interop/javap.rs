@@ -0,0 +1,732 @@
+//! Conformance checking of the parser against the output of `javap`.
+//!
+//! A [`ClassView`] is a summary of a class (i.e., its version, flags, members, constant values,
+//! and instruction listing) that can be created either from a parsed [`Class`] or from the
+//! output of `javap -v -p -c`.
+//! Comparing the two views reveals the places where the parser disagrees with the reference
+//! implementation. The reference output is either produced by a [`Javap`] found on the machine
+//! or read from a previously recorded dataset with [`ClassView::from_javap`].
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::jvm::{
+    code::{Instruction, WideInstruction},
+    parsing, Class, ConstantValue, JavaString,
+};
+
+/// An error when checking a class against `javap`.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The class file cannot be read, or `javap` cannot be run.
+    #[error(transparent)]
+    IO(#[from] io::Error),
+    /// The class file cannot be parsed by mokapot.
+    #[error("Failed to parse the class: {0}")]
+    Parse(#[from] parsing::Error),
+    /// `javap` exits with an error.
+    #[error("javap failed: {0}")]
+    Javap(String),
+    /// The output of `javap` is not in the expected format.
+    #[error("Line {line} of the javap output: {message}")]
+    Malformed {
+        /// The line number (starting from 1).
+        line: usize,
+        /// The description of the problem.
+        message: &'static str,
+    },
+}
+
+/// A summary of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldView {
+    /// The access flags.
+    pub access_flags: u16,
+    /// The constant value, formatted as `<type> <value>` (e.g., `int 42`).
+    pub constant_value: Option<String>,
+}
+
+/// A summary of a method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodView {
+    /// The access flags.
+    pub access_flags: u16,
+    /// The mnemonics of the instructions indexed by their program counters.
+    pub instructions: BTreeMap<u16, String>,
+}
+
+/// A summary of a class that can be compared with the output of `javap`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ClassView {
+    /// The major and minor version of the class file.
+    pub version: (u16, u16),
+    /// The access flags.
+    pub access_flags: u16,
+    /// The binary name of the class.
+    pub binary_name: String,
+    /// The binary name of the superclass.
+    pub super_class: Option<String>,
+    /// The binary names of the implemented interfaces.
+    pub interfaces: Vec<String>,
+    /// The fields indexed by their names and descriptors (e.g., `x:I`).
+    pub fields: BTreeMap<String, FieldView>,
+    /// The methods indexed by their names and descriptors (e.g., `run()V`).
+    pub methods: BTreeMap<String, MethodView>,
+}
+
+/// A difference between the reference view of a class and the one produced by mokapot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The path to the differing element (e.g., `method run()V/instruction 2`).
+    pub path: String,
+    /// The reference value, or `None` if the element is absent in the reference.
+    pub expected: Option<String>,
+    /// The value produced by mokapot, or `None` if the element is absent.
+    pub actual: Option<String>,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let or_absent = |it: &Option<String>| it.clone().unwrap_or_else(|| "<absent>".into());
+        write!(
+            f,
+            "{}: expected {}, found {}",
+            self.path,
+            or_absent(&self.expected),
+            or_absent(&self.actual)
+        )
+    }
+}
+
+impl From<&Class> for ClassView {
+    fn from(class: &Class) -> Self {
+        let fields = class
+            .fields
+            .iter()
+            .map(|field| {
+                let view = FieldView {
+                    access_flags: field.access_flags.bits(),
+                    constant_value: field.constant_value.as_ref().and_then(format_constant),
+                };
+                (
+                    format!("{}:{}", field.name, field.field_type.descriptor()),
+                    view,
+                )
+            })
+            .collect();
+        let methods = class
+            .methods
+            .iter()
+            .map(|method| {
+                let instructions = method
+                    .body
+                    .iter()
+                    .flat_map(|body| &body.instructions)
+                    .map(|(pc, insn)| (u16::from(*pc), mnemonic(insn)))
+                    .collect();
+                let view = MethodView {
+                    access_flags: method.access_flags.bits(),
+                    instructions,
+                };
+                (
+                    format!("{}{}", method.name, method.descriptor.descriptor()),
+                    view,
+                )
+            })
+            .collect();
+        Self {
+            version: (class.version.major(), class.version.minor()),
+            access_flags: class.access_flags.bits(),
+            binary_name: class.binary_name.clone(),
+            super_class: class
+                .super_class
+                .as_ref()
+                .map(|it| it.binary_name.to_string()),
+            interfaces: class
+                .interfaces
+                .iter()
+                .map(|it| it.binary_name.to_string())
+                .collect(),
+            fields,
+            methods,
+        }
+    }
+}
+
+impl ClassView {
+    /// Reads the output of `javap -v -p -c`.
+    ///
+    /// # Errors
+    /// Returns [`Error::Malformed`] if the text is not in the expected format.
+    pub fn from_javap(text: &str) -> Result<Self, Error> {
+        JavapReader::default().read(text)
+    }
+
+    /// Compares `self` as the reference with `actual`.
+    #[must_use]
+    pub fn compare(&self, actual: &Self) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        let m = &mut mismatches;
+        let flags = |it: u16| Some(format!("{it:#06x}"));
+        let version = |(major, minor): (u16, u16)| Some(format!("{major}.{minor}"));
+        check(m, "version", version(self.version), version(actual.version));
+        check(
+            m,
+            "access_flags",
+            flags(self.access_flags),
+            flags(actual.access_flags),
+        );
+        check(
+            m,
+            "binary_name",
+            Some(self.binary_name.clone()),
+            Some(actual.binary_name.clone()),
+        );
+        check(
+            m,
+            "super_class",
+            self.super_class.clone(),
+            actual.super_class.clone(),
+        );
+        check(
+            m,
+            "interfaces",
+            Some(self.interfaces.join(", ")),
+            Some(actual.interfaces.join(", ")),
+        );
+        compare_maps(
+            m,
+            "field",
+            &self.fields,
+            &actual.fields,
+            |m, path, expected, found| {
+                let path = |it| format!("{path}/{it}");
+                check(
+                    m,
+                    &path("access_flags"),
+                    flags(expected.access_flags),
+                    flags(found.access_flags),
+                );
+                check(
+                    m,
+                    &path("constant_value"),
+                    expected.constant_value.clone(),
+                    found.constant_value.clone(),
+                );
+            },
+        );
+        compare_maps(
+            m,
+            "method",
+            &self.methods,
+            &actual.methods,
+            |m, path, expected, found| {
+                check(
+                    m,
+                    &format!("{path}/access_flags"),
+                    flags(expected.access_flags),
+                    flags(found.access_flags),
+                );
+                let pcs: BTreeSet<_> = expected
+                    .instructions
+                    .keys()
+                    .chain(found.instructions.keys())
+                    .collect();
+                for pc in pcs {
+                    check(
+                        m,
+                        &format!("{path}/instruction {pc}"),
+                        expected.instructions.get(pc).cloned(),
+                        found.instructions.get(pc).cloned(),
+                    );
+                }
+            },
+        );
+        mismatches
+    }
+}
+
+/// Records a mismatch if `expected` and `actual` differ.
+fn check(
+    mismatches: &mut Vec<Mismatch>,
+    path: &str,
+    expected: Option<String>,
+    actual: Option<String>,
+) {
+    if expected != actual {
+        mismatches.push(Mismatch {
+            path: path.to_owned(),
+            expected,
+            actual,
+        });
+    }
+}
+
+fn compare_maps<V>(
+    mismatches: &mut Vec<Mismatch>,
+    kind: &str,
+    expected: &BTreeMap<String, V>,
+    actual: &BTreeMap<String, V>,
+    compare: impl Fn(&mut Vec<Mismatch>, &str, &V, &V),
+) {
+    for key in expected
+        .keys()
+        .chain(actual.keys().filter(|it| !expected.contains_key(*it)))
+    {
+        let path = format!("{kind} {key}");
+        match (expected.get(key), actual.get(key)) {
+            (Some(expected), Some(actual)) => compare(mismatches, &path, expected, actual),
+            (expected, actual) => check(
+                mismatches,
+                &path,
+                expected.map(|_| key.clone()),
+                actual.map(|_| key.clone()),
+            ),
+        }
+    }
+}
+
+/// The `javap` executable of a JDK.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Javap {
+    executable: PathBuf,
+}
+
+impl Javap {
+    /// Creates an instance running the executable at `executable`.
+    pub fn new(executable: impl Into<PathBuf>) -> Self {
+        Self {
+            executable: executable.into(),
+        }
+    }
+
+    /// Finds `javap` in `$JAVA_HOME/bin` or in `PATH`.
+    /// Returns `None` if it is not available.
+    #[must_use]
+    pub fn locate() -> Option<Self> {
+        let from_java_home = std::env::var_os("JAVA_HOME")
+            .map(|it| Path::new(&it).join("bin").join("javap"))
+            .map(Self::new);
+        from_java_home
+            .into_iter()
+            .chain(std::iter::once(Self::new("javap")))
+            .find(|it| {
+                Command::new(&it.executable)
+                    .arg("-version")
+                    .output()
+                    .is_ok_and(|it| it.status.success())
+            })
+    }
+
+    /// Runs `javap -v -p -c` on the class file at `path` and returns its output.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn describe(&self, path: &Path) -> Result<String, Error> {
+        let output = Command::new(&self.executable)
+            .args(["-v", "-p", "-c"])
+            .arg(path)
+            .output()?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        } else {
+            Err(Error::Javap(
+                String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+            ))
+        }
+    }
+
+    /// Parses the class file at `path` with mokapot and compares the result with the output of
+    /// `javap`.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn check(&self, path: &Path) -> Result<Vec<Mismatch>, Error> {
+        let bytes = fs::read(path)?;
+        let class = Class::from_reader(bytes.as_slice())?;
+        let reference = ClassView::from_javap(&self.describe(path)?)?;
+        Ok(reference.compare(&ClassView::from(&class)))
+    }
+}
+
+#[derive(Default)]
+struct JavapReader {
+    view: ClassView,
+    header: Option<String>,
+    in_members: bool,
+    member: Option<Member>,
+}
+
+#[derive(Default)]
+struct Member {
+    name: String,
+    descriptor: Option<String>,
+    access_flags: u16,
+    constant_value: Option<String>,
+    in_code: bool,
+    instructions: BTreeMap<u16, String>,
+}
+
+impl JavapReader {
+    fn read(mut self, text: &str) -> Result<ClassView, Error> {
+        for (index, line) in text.lines().enumerate() {
+            self.read_line(line).map_err(|message| Error::Malformed {
+                line: index + 1,
+                message,
+            })?;
+        }
+        let header = self.header.ok_or(Error::Malformed {
+            line: 0,
+            message: "Missing class declaration",
+        })?;
+        self.view.interfaces = interfaces(&header, self.view.access_flags);
+        Ok(self.view)
+    }
+
+    fn read_line(&mut self, line: &str) -> Result<(), &'static str> {
+        let trimmed = line.trim();
+        let indent = line.len() - line.trim_start().len();
+        if !self.in_members {
+            return self.read_header_line(line, trimmed, indent);
+        }
+        if trimmed == "}" && indent == 0 {
+            self.in_members = false;
+            return self.end_member();
+        }
+        match indent {
+            _ if trimmed.is_empty() => Ok(()),
+            2 => {
+                self.end_member()?;
+                self.member = Some(Member {
+                    name: member_name(trimmed, &self.view.binary_name)?,
+                    ..Member::default()
+                });
+                Ok(())
+            }
+            4 => {
+                let Some(member) = self.member.as_mut() else {
+                    return Err("Member attribute outside of a member");
+                };
+                member.in_code = trimmed == "Code:";
+                if let Some(descriptor) = trimmed.strip_prefix("descriptor: ") {
+                    member.descriptor = Some(descriptor.to_owned());
+                } else if let Some(flags) = trimmed.strip_prefix("flags: ") {
+                    member.access_flags = parse_flags(flags)?;
+                } else if let Some(value) = trimmed.strip_prefix("ConstantValue: ") {
+                    member.constant_value = Some(normalize_constant(value));
+                }
+                Ok(())
+            }
+            _ => {
+                if let Some(member) = self.member.as_mut().filter(|it| it.in_code) {
+                    if indent == 6 && !trimmed.starts_with("stack=") {
+                        // The attributes of the code follow the instructions.
+                        member.in_code = trimmed.starts_with(|c: char| c.is_ascii_digit());
+                    }
+                    if let Some((pc, instruction)) = instruction_line(trimmed) {
+                        member.instructions.insert(pc, instruction);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn read_header_line(
+        &mut self,
+        line: &str,
+        trimmed: &str,
+        indent: usize,
+    ) -> Result<(), &'static str> {
+        if trimmed == "{" {
+            self.in_members = true;
+        } else if indent == 0
+            && self.header.is_none()
+            && !trimmed.is_empty()
+            && !line.starts_with("Classfile")
+        {
+            self.header = Some(trimmed.to_owned());
+        } else if let Some(version) = trimmed.strip_prefix("minor version: ") {
+            self.view.version.1 = version.parse().map_err(|_| "Invalid minor version")?;
+        } else if let Some(version) = trimmed.strip_prefix("major version: ") {
+            self.view.version.0 = version.parse().map_err(|_| "Invalid major version")?;
+        } else if let Some(flags) = trimmed.strip_prefix("flags: ") {
+            self.view.access_flags = parse_flags(flags)?;
+        } else if trimmed.starts_with("this_class: ") {
+            self.view.binary_name = comment(trimmed).ok_or("Missing this class")?;
+        } else if trimmed.starts_with("super_class: ") {
+            self.view.super_class = comment(trimmed);
+        }
+        Ok(())
+    }
+
+    fn end_member(&mut self) -> Result<(), &'static str> {
+        let Some(member) = self.member.take() else {
+            return Ok(());
+        };
+        let descriptor = member.descriptor.ok_or("Missing descriptor")?;
+        if descriptor.starts_with('(') {
+            let view = MethodView {
+                access_flags: member.access_flags,
+                instructions: member.instructions,
+            };
+            self.view
+                .methods
+                .insert(format!("{}{descriptor}", member.name), view);
+        } else {
+            let view = FieldView {
+                access_flags: member.access_flags,
+                constant_value: member.constant_value,
+            };
+            self.view
+                .fields
+                .insert(format!("{}:{descriptor}", member.name), view);
+        }
+        Ok(())
+    }
+}
+
+/// Extracts the text after `//` in a line.
+fn comment(line: &str) -> Option<String> {
+    line.split_once("//")
+        .map(|(_, it)| it.trim().trim_matches('"').to_owned())
+}
+
+/// Parses flags in the form of `(0x0021) ACC_PUBLIC, ACC_SUPER`.
+fn parse_flags(flags: &str) -> Result<u16, &'static str> {
+    flags
+        .strip_prefix("(0x")
+        .and_then(|it| it.split_once(')'))
+        .and_then(|(hex, _)| u16::from_str_radix(hex, 16).ok())
+        .ok_or("Invalid flags")
+}
+
+/// Removes the type arguments and bounds (i.e., the parts in angle brackets).
+fn erase_generics(text: &str) -> String {
+    let mut depth = 0usize;
+    text.chars()
+        .filter(|c| match c {
+            '<' => {
+                depth += 1;
+                false
+            }
+            '>' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect()
+}
+
+fn interfaces(header: &str, access_flags: u16) -> Vec<String> {
+    let header = erase_generics(header);
+    let keyword = if access_flags & 0x0200 == 0 {
+        " implements "
+    } else {
+        " extends "
+    };
+    header
+        .split_once(keyword)
+        .map(|(_, it)| {
+            it.split(',')
+                .map(|it| it.trim().replace('.', "/"))
+                .filter(|it| !it.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn member_name(declaration: &str, binary_name: &str) -> Result<String, &'static str> {
+    if declaration.starts_with("static {}") {
+        return Ok("<clinit>".to_owned());
+    }
+    let declaration = erase_generics(declaration);
+    let (before, is_method) = match declaration.split_once('(') {
+        Some((before, _)) => (before, true),
+        None => (declaration.trim_end_matches(';'), false),
+    };
+    let name = before
+        .split_whitespace()
+        .last()
+        .ok_or("Missing member name")?;
+    let is_constructor = is_method && name.replace('.', "/") == binary_name;
+    Ok(if is_constructor { "<init>" } else { name }.to_owned())
+}
+
+fn instruction_line(line: &str) -> Option<(u16, String)> {
+    let (pc, rest) = line.split_once(':')?;
+    let pc = pc.parse().ok()?;
+    let mnemonic = rest.split_whitespace().next()?;
+    mnemonic
+        .starts_with(|c: char| c.is_ascii_lowercase())
+        .then(|| (pc, mnemonic.to_owned()))
+}
+
+/// Normalizes a constant value printed by `javap` (e.g., `float 1.5f`).
+fn normalize_constant(value: &str) -> String {
+    let (kind, literal) = value.split_once(' ').unwrap_or((value, ""));
+    let number = literal.trim_end_matches(['l', 'f', 'd']);
+    match kind {
+        "float" => number
+            .parse::<f32>()
+            .map_or_else(|_| value.to_owned(), |it| format!("float {it:?}")),
+        "double" => number
+            .parse::<f64>()
+            .map_or_else(|_| value.to_owned(), |it| format!("double {it:?}")),
+        "long" => format!("long {number}"),
+        _ => value.to_owned(),
+    }
+}
+
+fn format_constant(value: &ConstantValue) -> Option<String> {
+    let formatted = match value {
+        ConstantValue::Integer(it) => format!("int {it}"),
+        ConstantValue::Long(it) => format!("long {it}"),
+        ConstantValue::Float(it) => format!("float {it:?}"),
+        ConstantValue::Double(it) => format!("double {it:?}"),
+        ConstantValue::String(JavaString::Utf8(it)) => format!("String {it}"),
+        _ => return None,
+    };
+    Some(formatted)
+}
+
+/// Returns the mnemonic of an instruction as printed by `javap`.
+fn mnemonic(instruction: &Instruction) -> String {
+    let Instruction::Wide(wide) = instruction else {
+        return instruction.name().to_owned();
+    };
+    let name = match wide {
+        WideInstruction::ILoad(_) => "iload",
+        WideInstruction::LLoad(_) => "lload",
+        WideInstruction::FLoad(_) => "fload",
+        WideInstruction::DLoad(_) => "dload",
+        WideInstruction::ALoad(_) => "aload",
+        WideInstruction::IStore(_) => "istore",
+        WideInstruction::LStore(_) => "lstore",
+        WideInstruction::FStore(_) => "fstore",
+        WideInstruction::DStore(_) => "dstore",
+        WideInstruction::AStore(_) => "astore",
+        WideInstruction::IInc(_, _) => "iinc",
+        WideInstruction::Ret(_) => "ret",
+    };
+    format!("{name}_w")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    const JAVAP: &str = r#"Classfile /tmp/Foo.class
+  Last modified Oct 14, 2026; size 1253 bytes
+  Compiled from "Foo.java"
+public class Foo<T extends java.lang.Object> extends java.lang.Object implements java.lang.Comparable<Foo<T>>, java.lang.Runnable
+  minor version: 0
+  major version: 52
+  flags: (0x0021) ACC_PUBLIC, ACC_SUPER
+  this_class: #8                          // Foo
+  super_class: #2                         // java/lang/Object
+  interfaces: 2, fields: 1, methods: 2, attributes: 1
+Constant pool:
+   #1 = Methodref          #2.#3          // java/lang/Object."<init>":()V
+{
+  static final float F;
+    descriptor: F
+    flags: (0x0018) ACC_STATIC, ACC_FINAL
+    ConstantValue: float 1.5f
+
+  public Foo();
+    descriptor: ()V
+    flags: (0x0001) ACC_PUBLIC
+    Code:
+      stack=1, locals=1, args_size=1
+         0: aload_0
+         1: invokespecial #1                  // Method java/lang/Object."<init>":()V
+         4: return
+      LineNumberTable:
+        line 6: 0
+
+  public void run();
+    descriptor: ()V
+    flags: (0x0001) ACC_PUBLIC
+    Code:
+      stack=1, locals=2, args_size=1
+         0: iload_1
+         1: lookupswitch  { // 1
+                       1: 20
+                 default: 20
+            }
+        20: iinc_w        1, 1000
+        26: return
+      StackMapTable: number_of_entries = 1
+        frame_type = 20 /* same */
+}
+SourceFile: "Foo.java"
+"#;
+
+    const JASMIN: &str = "
+        .bytecode 52.0
+        .class public Foo
+        .super java/lang/Object
+        .implements java/lang/Comparable
+        .implements java/lang/Runnable
+        .field static final F F = 1.5
+        .method public <init>()V
+            .limit stack 1
+            aload_0
+            invokespecial java/lang/Object/<init>()V
+            return
+        .end method
+        .method public run()V
+            .limit stack 1
+            .limit locals 2
+            iload_1
+            lookupswitch
+                1 : L
+                default : L
+        L:
+            iinc 1 1000
+            return
+        .end method
+    ";
+
+    #[test]
+    fn compare_with_javap() {
+        let reference = ClassView::from_javap(JAVAP).unwrap();
+        assert_eq!(
+            reference.interfaces,
+            vec!["java/lang/Comparable", "java/lang/Runnable"]
+        );
+        assert_eq!(reference.methods["run()V"].instructions.len(), 4);
+
+        let mut class = jasmin::read(JASMIN).unwrap();
+        class.access_flags |= crate::jvm::class::AccessFlags::SUPER;
+        let actual = ClassView::from(&class);
+        assert_eq!(reference.compare(&actual), Vec::new());
+
+        class.methods.pop();
+        class.fields[0].constant_value = Some(ConstantValue::Float(2.5));
+        let mismatches = reference.compare(&ClassView::from(&class));
+        assert_eq!(
+            mismatches,
+            vec![
+                Mismatch {
+                    path: "field F:F/constant_value".to_owned(),
+                    expected: Some("float 1.5".to_owned()),
+                    actual: Some("float 2.5".to_owned()),
+                },
+                Mismatch {
+                    path: "method run()V".to_owned(),
+                    expected: Some("run()V".to_owned()),
+                    actual: None,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,8 @@
+//! Interoperability with the formats of other tools for JVM classes.
+
+#[cfg(feature = "lang-interop")]
+pub mod groovy;
+pub mod jasmin;
+pub mod javap;
+#[cfg(feature = "lang-interop")]
+pub mod scala;
@@ -0,0 +1,482 @@
+//! Access to the metadata emitted by the Scala 2 compiler.
+//!
+//! The pickled signature of a Scala class (i.e., the `ScalaSig` attribute or the
+//! `scala.reflect.ScalaSignature` annotation) is exposed as a [`ScalaSig`] holding its entry
+//! table, and the `ScalaInlineInfo` attribute as a [`ScalaInlineInfo`].
+//! Register the codecs with [`AttributeRegistry::with_scala_codecs`] to decode the attributes
+//! while parsing.
+
+use bitflags::bitflags;
+
+use crate::{
+    jvm::{
+        annotation::ElementValue,
+        class::constant_pool::{BuildError, ConstantPoolBuilder},
+        parsing::{self, AttributeCodec, AttributeRegistry, Context},
+        Annotation, Class, ConstantValue, JavaString,
+    },
+    types::field_type::FieldType,
+};
+
+/// An error when decoding Scala metadata.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// The data ends before the structure is complete.
+    #[error("Unexpected end of data")]
+    Truncated,
+    /// A variable-length natural number does not fit in 64 bits.
+    #[error("Natural number overflow")]
+    Overflow,
+    /// The version of the `ScalaInlineInfo` attribute is not supported.
+    #[error("Unsupported ScalaInlineInfo version {0}")]
+    UnsupportedVersion(u8),
+    /// The signature annotation does not hold the encoded bytes.
+    #[error("Malformed signature annotation")]
+    MalformedAnnotation,
+}
+
+/// The pickled Scala signature of a class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalaSig {
+    /// The major version of the pickle format.
+    pub major_version: u64,
+    /// The minor version of the pickle format.
+    pub minor_version: u64,
+    /// The entry table. It is empty when the `ScalaSig` attribute only marks the presence of a
+    /// signature stored in the `ScalaSignature` annotation.
+    pub entries: Vec<PickleEntry>,
+}
+
+/// An entry in the table of a [`ScalaSig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PickleEntry {
+    /// The tag of the entry.
+    pub tag: u8,
+    /// The content of the entry.
+    pub data: Vec<u8>,
+}
+
+impl PickleEntry {
+    /// The tag of a term name.
+    pub const TERM_NAME: u8 = 1;
+    /// The tag of a type name.
+    pub const TYPE_NAME: u8 = 2;
+    /// The tag of a class symbol.
+    pub const CLASS_SYM: u8 = 6;
+    /// The tag of a module symbol.
+    pub const MODULE_SYM: u8 = 7;
+    /// The tag of a value or method symbol.
+    pub const VAL_SYM: u8 = 8;
+    /// The tag of an external symbol reference.
+    pub const EXT_REF: u8 = 9;
+    /// The tag of a reference to an external module class.
+    pub const EXT_MOD_CLASS_REF: u8 = 10;
+
+    /// Returns the name held by the entry if it is a term name or a type name.
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        match self.tag {
+            Self::TERM_NAME | Self::TYPE_NAME => std::str::from_utf8(&self.data).ok(),
+            _ => None,
+        }
+    }
+
+    /// Decodes the content of the entry as a sequence of natural numbers, which is the layout
+    /// of most entries other than names and literals. The numbers are usually indices into the
+    /// entry table.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn nats(&self) -> Result<Vec<u64>, Error> {
+        let mut cursor = Cursor(&self.data);
+        let mut nats = Vec::new();
+        while !cursor.0.is_empty() {
+            nats.push(cursor.nat()?);
+        }
+        Ok(nats)
+    }
+}
+
+impl ScalaSig {
+    /// The name of the attribute holding the signature.
+    pub const ATTRIBUTE_NAME: &'static str = "ScalaSig";
+    const ANNOTATION: &'static str = "scala/reflect/ScalaSignature";
+    const LONG_ANNOTATION: &'static str = "scala/reflect/ScalaLongSignature";
+
+    /// Parses a pickled signature.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        let mut cursor = Cursor(bytes);
+        let major_version = cursor.nat()?;
+        let minor_version = cursor.nat()?;
+        let mut entries = Vec::new();
+        if !cursor.0.is_empty() {
+            let count = cursor.nat()?;
+            for _ in 0..count {
+                let tag = cursor.u8()?;
+                let len = usize::try_from(cursor.nat()?).map_err(|_| Error::Overflow)?;
+                let data = cursor.take(len)?.to_vec();
+                entries.push(PickleEntry { tag, data });
+            }
+        }
+        Ok(Self {
+            major_version,
+            minor_version,
+            entries,
+        })
+    }
+
+    /// Serializes the signature in the pickle format.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_nat(&mut bytes, self.major_version);
+        write_nat(&mut bytes, self.minor_version);
+        if !self.entries.is_empty() {
+            write_nat(&mut bytes, self.entries.len() as u64);
+            for entry in &self.entries {
+                bytes.push(entry.tag);
+                write_nat(&mut bytes, entry.data.len() as u64);
+                bytes.extend_from_slice(&entry.data);
+            }
+        }
+        bytes
+    }
+
+    /// Extracts the signature stored in the `ScalaSignature` or `ScalaLongSignature`
+    /// annotation of `class`, returning [`None`] if there is no such annotation.
+    #[must_use]
+    pub fn from_annotations(class: &Class) -> Option<Result<Self, Error>> {
+        class
+            .runtime_visible_annotations
+            .iter()
+            .find_map(|annotation| match &annotation.annotation_type {
+                FieldType::Object(class_ref)
+                    if &*class_ref.binary_name == Self::ANNOTATION
+                        || &*class_ref.binary_name == Self::LONG_ANNOTATION =>
+                {
+                    Some(Self::from_annotation(annotation))
+                }
+                _ => None,
+            })
+    }
+
+    fn from_annotation(annotation: &Annotation) -> Result<Self, Error> {
+        let (_, value) = annotation
+            .element_value_pairs
+            .iter()
+            .find(|(name, _)| name == "bytes")
+            .ok_or(Error::MalformedAnnotation)?;
+        let mut encoded = Vec::new();
+        match value {
+            ElementValue::String(string) => append_string(&mut encoded, string)?,
+            ElementValue::Array(strings) => {
+                for string in strings {
+                    let ElementValue::String(string) = string else {
+                        return Err(Error::MalformedAnnotation);
+                    };
+                    append_string(&mut encoded, string)?;
+                }
+            }
+            _ => return Err(Error::MalformedAnnotation),
+        }
+        Self::parse(&decode_annotation_bytes(&encoded))
+    }
+}
+
+fn append_string(buf: &mut Vec<u8>, value: &ConstantValue) -> Result<(), Error> {
+    match value {
+        ConstantValue::String(JavaString::Utf8(string)) => buf.extend_from_slice(string.as_bytes()),
+        ConstantValue::String(JavaString::InvalidUtf8(bytes)) => buf.extend_from_slice(bytes),
+        _ => return Err(Error::MalformedAnnotation),
+    }
+    Ok(())
+}
+
+/// Reverts the encoding used by the Scala compiler to store bytes in a string constant, i.e.,
+/// shifting every byte by one to avoid zeros and packing the bytes in groups of seven bits.
+fn decode_annotation_bytes(encoded: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 7 / 8 + 1);
+    let mut acc = 0u32;
+    let mut bits = 0;
+    let mut iter = encoded.iter().copied().peekable();
+    while let Some(byte) = iter.next() {
+        // Zeros may be stored either as a plain zero or in the modified UTF-8 form.
+        let septet = match byte {
+            0xC0 if iter.peek() == Some(&0x80) => {
+                iter.next();
+                0x7F
+            }
+            0 => 0x7F,
+            other => other.wrapping_sub(1) & 0x7F,
+        };
+        acc |= u32::from(septet) << bits;
+        bits += 7;
+        if bits >= 8 {
+            bytes.push((acc & 0xFF) as u8);
+            acc >>= 8;
+            bits -= 8;
+        }
+    }
+    if bits > 0 {
+        bytes.push((acc & 0xFF) as u8);
+    }
+    bytes
+}
+
+/// Codec for the `ScalaSig` attribute.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScalaSigCodec;
+
+impl AttributeCodec for ScalaSigCodec {
+    type Value = ScalaSig;
+
+    fn name(&self) -> &str {
+        ScalaSig::ATTRIBUTE_NAME
+    }
+
+    fn decode(&self, bytes: &[u8], _ctx: &Context) -> Result<Self::Value, parsing::Error> {
+        ScalaSig::parse(bytes).map_err(|_| parsing::Error::Other("Malformed ScalaSig attribute"))
+    }
+
+    fn encode(
+        &self,
+        value: &Self::Value,
+        _constant_pool: &mut ConstantPoolBuilder,
+    ) -> Result<Vec<u8>, BuildError> {
+        Ok(value.to_bytes())
+    }
+}
+
+bitflags! {
+    /// The flags of a class in a [`ScalaInlineInfo`].
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct InlineInfoFlags: u8 {
+        /// The class is effectively final.
+        const EFFECTIVELY_FINAL = 0x01;
+        /// The class is a SAM type.
+        const HAS_SAM = 0x02;
+    }
+}
+
+bitflags! {
+    /// The flags of a method in a [`ScalaInlineInfo`].
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct InlineMethodFlags: u8 {
+        /// The method is effectively final.
+        const EFFECTIVELY_FINAL = 0x01;
+        /// The method is a trait method with a static implementation (no longer emitted).
+        const TRAIT_STATIC_IMPL = 0x02;
+        /// The method is annotated with `@inline`.
+        const INLINE = 0x04;
+        /// The method is annotated with `@noinline`.
+        const NO_INLINE = 0x08;
+    }
+}
+
+/// The inlining information emitted by the Scala 2.12+ optimizer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScalaInlineInfo {
+    /// The version of the attribute.
+    pub version: u8,
+    /// The flags of the class.
+    pub flags: InlineInfoFlags,
+    /// The name and descriptor of the single abstract method if the class is a SAM type.
+    pub sam: Option<(String, String)>,
+    /// The name, descriptor, and flags of the methods.
+    pub methods: Vec<InlineMethodInfo>,
+}
+
+/// The inlining information of a method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlineMethodInfo {
+    /// The name of the method.
+    pub name: String,
+    /// The descriptor of the method.
+    pub descriptor: String,
+    /// The flags of the method.
+    pub flags: InlineMethodFlags,
+}
+
+impl ScalaInlineInfo {
+    /// The name of the attribute.
+    pub const ATTRIBUTE_NAME: &'static str = "ScalaInlineInfo";
+    const VERSION: u8 = 1;
+}
+
+/// Codec for the `ScalaInlineInfo` attribute.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScalaInlineInfoCodec;
+
+impl AttributeCodec for ScalaInlineInfoCodec {
+    type Value = ScalaInlineInfo;
+
+    fn name(&self) -> &str {
+        ScalaInlineInfo::ATTRIBUTE_NAME
+    }
+
+    fn decode(&self, bytes: &[u8], ctx: &Context) -> Result<Self::Value, parsing::Error> {
+        let malformed = |_| parsing::Error::Other("Malformed ScalaInlineInfo attribute");
+        let mut cursor = Cursor(bytes);
+        let version = cursor.u8().map_err(malformed)?;
+        if version != ScalaInlineInfo::VERSION {
+            return Err(malformed(Error::UnsupportedVersion(version)));
+        }
+        let flags = InlineInfoFlags::from_bits_retain(cursor.u8().map_err(malformed)?);
+        let utf8 = |cursor: &mut Cursor<'_>| -> Result<String, parsing::Error> {
+            let index = cursor.u16().map_err(malformed)?;
+            Ok(ctx.constant_pool.get_str(index)?.to_owned())
+        };
+        let sam = if flags.contains(InlineInfoFlags::HAS_SAM) {
+            Some((utf8(&mut cursor)?, utf8(&mut cursor)?))
+        } else {
+            None
+        };
+        let count = cursor.u16().map_err(malformed)?;
+        let methods = (0..count)
+            .map(|_| {
+                let name = utf8(&mut cursor)?;
+                let descriptor = utf8(&mut cursor)?;
+                let flags = InlineMethodFlags::from_bits_retain(cursor.u8().map_err(malformed)?);
+                Ok(InlineMethodInfo {
+                    name,
+                    descriptor,
+                    flags,
+                })
+            })
+            .collect::<Result<_, parsing::Error>>()?;
+        Ok(ScalaInlineInfo {
+            version,
+            flags,
+            sam,
+            methods,
+        })
+    }
+
+    fn encode(
+        &self,
+        value: &Self::Value,
+        constant_pool: &mut ConstantPoolBuilder,
+    ) -> Result<Vec<u8>, BuildError> {
+        let mut bytes = vec![value.version, value.flags.bits()];
+        if let Some((name, descriptor)) = &value.sam {
+            bytes.extend_from_slice(&constant_pool.put_utf8(name.clone())?.to_be_bytes());
+            bytes.extend_from_slice(&constant_pool.put_utf8(descriptor.clone())?.to_be_bytes());
+        }
+        let count = u16::try_from(value.methods.len()).map_err(|_| BuildError::Overflow)?;
+        bytes.extend_from_slice(&count.to_be_bytes());
+        for method in &value.methods {
+            bytes.extend_from_slice(&constant_pool.put_utf8(method.name.clone())?.to_be_bytes());
+            bytes.extend_from_slice(
+                &constant_pool
+                    .put_utf8(method.descriptor.clone())?
+                    .to_be_bytes(),
+            );
+            bytes.push(method.flags.bits());
+        }
+        Ok(bytes)
+    }
+}
+
+impl AttributeRegistry {
+    /// Registers the codecs for the attributes emitted by the Scala compiler.
+    #[must_use]
+    pub fn with_scala_codecs(self) -> Self {
+        self.with_codec(ScalaSigCodec)
+            .with_codec(ScalaInlineInfoCodec)
+    }
+}
+
+struct Cursor<'a>(&'a [u8]);
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.0.len() < len {
+            return Err(Error::Truncated);
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        self.take(1).map(|it| it[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        self.take(2).map(|it| u16::from_be_bytes([it[0], it[1]]))
+    }
+
+    /// Reads a natural number stored in big-endian groups of seven bits, where the highest bit
+    /// of every byte but the last is set.
+    fn nat(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        loop {
+            let byte = self.u8()?;
+            if value.leading_zeros() < 7 {
+                return Err(Error::Overflow);
+            }
+            value = (value << 7) | u64::from(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+    }
+}
+
+fn write_nat(buf: &mut Vec<u8>, value: u64) {
+    let mut septets = vec![(value & 0x7F) as u8];
+    let mut rest = value >> 7;
+    while rest != 0 {
+        septets.push((rest & 0x7F) as u8 | 0x80);
+        rest >>= 7;
+    }
+    buf.extend(septets.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pickle_round_trip() {
+        let sig = ScalaSig {
+            major_version: 5,
+            minor_version: 2,
+            entries: vec![
+                PickleEntry {
+                    tag: PickleEntry::TYPE_NAME,
+                    data: b"Foo".to_vec(),
+                },
+                PickleEntry {
+                    tag: PickleEntry::CLASS_SYM,
+                    data: vec![0x00, 0x81, 0x00],
+                },
+            ],
+        };
+        let bytes = sig.to_bytes();
+        let parsed = ScalaSig::parse(&bytes).unwrap();
+        assert_eq!(parsed, sig);
+        assert_eq!(parsed.entries[0].name(), Some("Foo"));
+        assert_eq!(parsed.entries[1].nats().unwrap(), vec![0, 128]);
+        assert_eq!(ScalaSig::parse(&bytes[..bytes.len() - 1]).ok(), None);
+    }
+
+    #[test]
+    fn decode_annotation_encoding() {
+        // The version `5.0` followed by an empty entry table, packed into septets and shifted
+        // by one.
+        let encoded = [0x06, 0x01, 0x01, 0x01];
+        assert_eq!(
+            decode_annotation_bytes(&encoded),
+            vec![0x05, 0x00, 0x00, 0x00]
+        );
+        let sig = ScalaSig::parse(&decode_annotation_bytes(&encoded)).unwrap();
+        assert_eq!((sig.major_version, sig.minor_version), (5, 0));
+        // The septet `0x7F` is written either as a zero or in the modified UTF-8 form.
+        assert_eq!(decode_annotation_bytes(&[0x00]), vec![0x7F]);
+        assert_eq!(decode_annotation_bytes(&[0xC0, 0x80]), vec![0x7F]);
+    }
+}
@@ -2,7 +2,11 @@
 
 use bitflags::bitflags;
 
-use super::{references::MethodRef, Method};
+use super::{
+    code::{BodyReplacementError, BodyReplacementOptions, Instruction, InstructionList},
+    references::MethodRef,
+    Method,
+};
 
 /// A generic type signature for a method.
 pub type Signature = String;
@@ -28,11 +32,23 @@ impl Method {
     /// Creates a [`MethodRef`] pointting to this method.
     #[must_use]
     pub fn as_ref(&self) -> MethodRef {
-        MethodRef {
-            owner: self.owner.clone(),
-            name: self.name.clone(),
-            descriptor: self.descriptor.clone(),
-        }
+        MethodRef::new(self.owner.clone(), &self.name, self.descriptor.clone())
+    }
+
+    /// Replaces the instructions in the body of the method, and returns the old ones.
+    /// See [`MethodBody::replace_instructions`](super::code::MethodBody::replace_instructions)
+    /// for how the exception table and the debug information are updated.
+    /// # Errors
+    /// See [`BodyReplacementError`] for more information.
+    pub fn replace_body(
+        &mut self,
+        new_instructions: InstructionList<Instruction>,
+        options: &BodyReplacementOptions,
+    ) -> Result<InstructionList<Instruction>, BodyReplacementError> {
+        self.body
+            .as_mut()
+            .ok_or(BodyReplacementError::NoMethodBody)?
+            .replace_instructions(new_instructions, options)
     }
 }
 
@@ -111,11 +127,12 @@ mod tests {
             runtime_visible_parameter_annotations: vec![],
             runtime_invisible_parameter_annotations: vec![],
             annotation_default: None,
-            parameters: vec![],
+            parameters: None,
             is_synthetic: false,
             is_deprecated: false,
             signature: None,
             free_attributes: vec![],
+            custom_attributes: vec![],
         }
     }
 
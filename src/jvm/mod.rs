@@ -13,19 +13,31 @@ use self::{
 };
 
 pub mod annotation;
+pub mod builder;
+pub mod bytecode;
 pub mod class;
 pub mod class_loader;
 pub mod code;
 pub mod field;
+pub mod instrument;
+pub mod interop;
 pub mod method;
 pub mod module;
 pub mod parsing;
 pub mod references;
+pub mod remap;
+pub mod verify;
+pub mod visitor;
+pub mod writing;
 
 /// A class loader that can load classes from a list of class paths.
+/// A class loader may have a parent, to which it delegates the loading of classes before
+/// searching its own class paths, as the class loaders in the JVM do.
 #[derive(Debug)]
 pub struct ClassLoader<P> {
     class_path: Vec<P>,
+    layer: Option<class_loader::Layer>,
+    parent: Option<Box<ClassLoader<P>>>,
 }
 
 /// A JVM class
@@ -55,6 +67,10 @@ pub struct Class {
     pub enclosing_method: Option<class::EnclosingMethod>,
     /// The source debug extension.
     pub source_debug_extension: Option<Vec<u8>>,
+    /// The identifier of the compilation that produced the class, emitted by `javac`.
+    pub compilation_id: Option<String>,
+    /// The identifier of the source file of the class, emitted by `javac`.
+    pub source_id: Option<String>,
     /// The runtime visible annotations.
     pub runtime_visible_annotations: Vec<Annotation>,
     /// The runtime invisible annotations.
@@ -87,6 +103,8 @@ pub struct Class {
     pub record: Option<Vec<class::RecordComponent>>,
     /// Unrecognized JVM attributes.
     pub free_attributes: Vec<(String, Vec<u8>)>,
+    /// Custom attributes decoded by an [`AttributeRegistry`](crate::jvm::parsing::AttributeRegistry).
+    pub custom_attributes: Vec<parsing::CustomAttribute>,
 }
 
 /// An annotation on a class, field, method, or parameter.
@@ -149,6 +167,8 @@ pub struct Field {
     pub runtime_invisible_type_annotations: Vec<TypeAnnotation>,
     /// Unrecognized JVM attributes.
     pub free_attributes: Vec<(String, Vec<u8>)>,
+    /// Custom attributes decoded by an [`AttributeRegistry`](crate::jvm::parsing::AttributeRegistry).
+    pub custom_attributes: Vec<parsing::CustomAttribute>,
 }
 
 /// A JVM method.
@@ -181,8 +201,8 @@ pub struct Method {
     pub runtime_invisible_parameter_annotations: Vec<Vec<Annotation>>,
     /// The default value of the annotation.
     pub annotation_default: Option<annotation::ElementValue>,
-    /// The parameters of the method.
-    pub parameters: Vec<method::ParameterInfo>,
+    /// The parameters of the method, if the `MethodParameters` attribute is present.
+    pub parameters: Option<Vec<method::ParameterInfo>>,
     /// Indicates if the method is synthesized by the compiler.
     pub is_synthetic: bool,
     /// Indicates if the method is deprecated.
@@ -191,6 +211,8 @@ pub struct Method {
     pub signature: Option<method::Signature>,
     /// Unrecognized JVM attributes.
     pub free_attributes: Vec<(String, Vec<u8>)>,
+    /// Custom attributes decoded by an [`AttributeRegistry`](crate::jvm::parsing::AttributeRegistry).
+    pub custom_attributes: Vec<parsing::CustomAttribute>,
 }
 
 /// A JVM module.
@@ -258,9 +280,9 @@ pub enum ConstantValue {
     /// A method type.
     #[display("{_0:?}")]
     MethodType(MethodDescriptor),
-    /// A dynamic constant.
-    #[display("Dynamic({_0}, {_1}, {_2})")]
-    Dynamic(u16, String, FieldType),
+    /// A dynamically-computed constant.
+    #[display("Dynamic({_0})")]
+    Dynamic(Box<class::DynamicConstant>),
 }
 
 impl PartialEq<Self> for ConstantValue {
@@ -277,9 +299,7 @@ impl PartialEq<Self> for ConstantValue {
             (Self::Class(lhs), Self::Class(rhs)) => lhs == rhs,
             (Self::Handle(lhs), Self::Handle(rhs)) => lhs == rhs,
             (Self::MethodType(lhs), Self::MethodType(rhs)) => lhs == rhs,
-            (Self::Dynamic(lhs0, lhs1, lhs2), Self::Dynamic(rhs0, rhs1, rhs2)) => {
-                lhs0 == rhs0 && lhs1 == rhs1 && lhs2 == rhs2
-            }
+            (Self::Dynamic(lhs), Self::Dynamic(rhs)) => lhs == rhs,
             _ => false,
         }
     }
@@ -325,10 +345,7 @@ impl Ord for ConstantValue {
             (MethodType(lhs), MethodType(rhs)) => lhs.cmp(rhs),
             (MethodType(_), _) => std::cmp::Ordering::Less,
             (_, MethodType(_)) => std::cmp::Ordering::Greater,
-            (Dynamic(lhs0, lhs1, lhs2), Dynamic(rhs0, rhs1, rhs2)) => lhs0
-                .cmp(rhs0)
-                .then_with(|| lhs1.cmp(rhs1))
-                .then_with(|| lhs2.cmp(rhs2)),
+            (Dynamic(lhs), Dynamic(rhs)) => lhs.cmp(rhs),
         }
     }
 }
@@ -5,8 +5,11 @@ use itertools::Itertools;
 use crate::{
     jvm::{
         annotation::ElementValue,
-        class::{BootstrapMethod, EnclosingMethod, InnerClassInfo, RecordComponent},
-        code::{LineNumberTableEntry, MethodBody, StackMapFrame},
+        class::{
+            constant_pool::BootstrapMethodEntry, BootstrapMethod, ConstantPool, EnclosingMethod,
+            InnerClassInfo, RecordComponent,
+        },
+        code::{CharacterRangeTableEntry, LineNumberTableEntry, MethodBody, StackMapFrame},
         method::ParameterInfo,
         references::{ClassRef, PackageRef},
         Annotation, ConstantValue, Module, TypeAnnotation,
@@ -17,8 +20,9 @@ use crate::{
 use super::{
     code::{LocalVariableDescAttr, LocalVariableTypeAttr},
     jvm_element_parser::ClassElement,
+    raw_attributes,
     reader_utils::{read_byte_chunk, ReadBytes, ValueReaderExt},
-    Context, Error,
+    Context, CustomAttribute, Error,
 };
 
 /// Represent an attribute of a class file, method, field, or code.
@@ -27,11 +31,42 @@ use super::{
 pub(crate) struct AttributeInfo {
     name_idx: u16,
     info: Vec<u8>,
+    /// The offset of the attribute in the class file.
+    offset: u64,
 }
 
 impl AttributeInfo {
-    fn from_raw_parts(name_idx: u16, info: Vec<u8>) -> Self {
-        Self { name_idx, info }
+    const fn from_raw_parts(name_idx: u16, info: Vec<u8>, offset: u64) -> Self {
+        Self {
+            name_idx,
+            info,
+            offset,
+        }
+    }
+
+    /// Reads the entries of the `BootstrapMethods` attribute in `attributes` without resolving
+    /// them, so that the `CONSTANT_Dynamic` entries can be resolved before the attributes are
+    /// parsed.
+    pub(super) fn bootstrap_method_entries(
+        attributes: &[Self],
+        constant_pool: &ConstantPool,
+    ) -> io::Result<Vec<BootstrapMethodEntry>> {
+        attributes
+            .iter()
+            .find(|it| constant_pool.get_str(it.name_idx).ok() == Some("BootstrapMethods"))
+            .map_or_else(
+                || Ok(Vec::new()),
+                |it| BootstrapMethodEntry::read_all(&it.info),
+            )
+    }
+
+    /// Sets the offsets of the `attributes` in a table that ends at `end` in the class file.
+    pub(super) fn locate(attributes: &mut [Self], end: u64) {
+        let mut offset = end;
+        for attribute in attributes.iter_mut().rev() {
+            offset -= 6 + attribute.info.len() as u64;
+            attribute.offset = offset;
+        }
     }
 }
 
@@ -42,7 +77,8 @@ impl ReadBytes for AttributeInfo {
         let attribute_length = usize::try_from(attribute_length)
             .expect("32-bit size is not supported on the current platform");
         let info = read_byte_chunk(reader, attribute_length)?;
-        Ok(Self::from_raw_parts(name_idx, info))
+        // The offset is set by the enclosing structure with `AttributeInfo::locate`.
+        Ok(Self::from_raw_parts(name_idx, info, 0))
     }
 }
 
@@ -55,6 +91,9 @@ pub(crate) enum Attribute {
     Exceptions(Vec<ClassRef>),
     SourceFile(String),
     LineNumberTable(Vec<LineNumberTableEntry>),
+    CharacterRangeTable(Vec<CharacterRangeTableEntry>),
+    CompilationId(String),
+    SourceId(String),
     InnerClasses(Vec<InnerClassInfo>),
     Synthetic,
     Deprecated,
@@ -79,6 +118,7 @@ pub(crate) enum Attribute {
     NestMembers(Vec<ClassRef>),
     Record(Vec<RecordComponent>),
     PermittedSubclasses(Vec<ClassRef>),
+    Custom(CustomAttribute),
     Unrecognized(String, Vec<u8>),
 }
 
@@ -91,6 +131,9 @@ impl Attribute {
             Self::Exceptions(_) => "Exceptions",
             Self::SourceFile(_) => "SourceFile",
             Self::LineNumberTable(_) => "LineNumberTable",
+            Self::CharacterRangeTable(_) => "CharacterRangeTable",
+            Self::CompilationId(_) => "CompilationID",
+            Self::SourceId(_) => "SourceID",
             Self::InnerClasses(_) => "InnerClasses",
             Self::Synthetic => "Synthetic",
             Self::Deprecated => "Deprecated",
@@ -115,6 +158,7 @@ impl Attribute {
             Self::NestMembers(_) => "NestMembers",
             Self::Record(_) => "Record",
             Self::PermittedSubclasses(_) => "PermittedSubclasses",
+            Self::Custom(it) => it.name(),
             Self::Unrecognized(name, _) => name,
         }
     }
@@ -138,10 +182,66 @@ impl ClassElement for Attribute {
     type Raw = AttributeInfo;
 
     fn from_raw(raw: Self::Raw, ctx: &Context) -> Result<Self, Error> {
-        let AttributeInfo { name_idx, info } = raw;
-        let name = ctx.constant_pool.get_str(name_idx)?;
+        if ctx.warnings.is_none() {
+            return Self::decode(raw, ctx);
+        }
+        // In lenient mode, attributes that cannot be decoded are kept as unrecognized ones.
+        let AttributeInfo {
+            name_idx,
+            info,
+            offset,
+        } = raw;
+        let name = match ctx.constant_pool.get_str(name_idx) {
+            Ok(name) => name.to_owned(),
+            Err(error) => {
+                ctx.tolerate("attribute_info", error)?;
+                return Ok(Self::Unrecognized(format!("#{name_idx}"), info));
+            }
+        };
+        match Self::decode(
+            AttributeInfo::from_raw_parts(name_idx, info.clone(), offset),
+            ctx,
+        ) {
+            Ok(attribute) => Ok(attribute),
+            Err(error) => {
+                ctx.tolerate(format!("attribute {name}"), error)?;
+                Ok(Self::Unrecognized(name, info))
+            }
+        }
+    }
+}
+
+impl Attribute {
+    /// Parses an attribute from the `attribute_info` structure in `bytes`, which starts at
+    /// `offset` in the class file.
+    pub(crate) fn from_bytes(bytes: &[u8], offset: u64, ctx: &Context) -> Result<Self, Error> {
+        let raw = AttributeInfo::read_bytes(&mut &*bytes)?;
+        Self::from_raw(AttributeInfo { offset, ..raw }, ctx)
+    }
+
+    fn decode(raw: AttributeInfo, ctx: &Context) -> Result<Self, Error> {
+        let AttributeInfo {
+            name_idx,
+            info,
+            offset,
+        } = raw;
+        let name = ctx
+            .constant_pool
+            .get_str(name_idx)
+            .map_err(|error| error.locate(offset, "attribute_info"))?;
+        // The offset of the `info` item in the class file.
+        let base = offset + 6;
         let reader = &mut io::Cursor::new(info);
+        Self::decode_info(name, reader, base, ctx)
+            .map_err(|error| error.locate(base + reader.position(), format!("{name} attribute")))
+    }
 
+    fn decode_info(
+        name: &str,
+        reader: &mut io::Cursor<Vec<u8>>,
+        base: u64,
+        ctx: &Context,
+    ) -> Result<Self, Error> {
         let result = match name {
             "ConstantValue" => {
                 let idx = reader.read_value()?;
@@ -149,7 +249,11 @@ impl ClassElement for Attribute {
                     .get_constant_value(idx)
                     .map(Self::ConstantValue)
             }
-            "Code" => parse!(reader, ctx => Code),
+            "Code" => {
+                let mut code: raw_attributes::Code = reader.read_value()?;
+                AttributeInfo::locate(&mut code.attributes, base + reader.position());
+                ClassElement::from_raw(code, ctx).map(Self::Code)
+            }
             "StackMapTable" => parse![u16; reader, ctx => StackMapTable],
             "Exceptions" => parse![u16; reader, || {
                 let idx = reader.read_value()?;
@@ -166,6 +270,9 @@ impl ClassElement for Attribute {
                 Ok(Self::SourceDebugExtension(bytes))
             }
             "LineNumberTable" => parse![u16; reader, ctx => LineNumberTable],
+            "CharacterRangeTable" => parse![u16; reader, ctx => CharacterRangeTable],
+            "CompilationID" => parse_string(reader, ctx).map(Self::CompilationId),
+            "SourceID" => parse_string(reader, ctx).map(Self::SourceId),
             "LocalVariableTable" => parse![u16; reader, ctx => LocalVariableTable],
             "LocalVariableTypeTable" => parse![u16; reader, ctx => LocalVariableTypeTable],
             "RuntimeVisibleAnnotations" => parse![u16; reader, ctx => RuntimeVisibleAnnotations],
@@ -206,16 +313,22 @@ impl ClassElement for Attribute {
                 ctx.constant_pool.get_class_ref(idx)
             }]
             .map(Self::NestMembers),
-            "Record" => parse![u16; reader, ctx => Record],
+            "Record" => parse![u16; reader, || {
+                let mut component: raw_attributes::RecordComponentInfo = reader.read_value()?;
+                AttributeInfo::locate(&mut component.attributes, base + reader.position());
+                ClassElement::from_raw(component, ctx)
+            } => Record],
             "PermittedSubclasses" => parse![u16; reader, || {
                 let idx = reader.read_value()?;
                 ctx.constant_pool.get_class_ref(idx)
             } => PermittedSubclasses],
-            name => reader
-                .bytes()
-                .try_collect()
-                .map(|bytes| Attribute::Unrecognized(name.to_owned(), bytes))
-                .map_err(Into::into),
+            name => {
+                let bytes: Vec<u8> = reader.bytes().try_collect()?;
+                match ctx.attribute_registry.decode(name, &bytes, ctx) {
+                    Some(custom) => custom.map(Attribute::Custom),
+                    None => Ok(Attribute::Unrecognized(name.to_owned(), bytes)),
+                }
+            }
         }?;
         match reader.read(&mut [0]) {
             Ok(0) => Ok(result),
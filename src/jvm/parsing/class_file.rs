@@ -1,10 +1,13 @@
-use std::io::{self, Read};
+use std::{
+    io::{self, Read},
+    sync::{Arc, PoisonError},
+};
 
 use crate::{
     jvm::{
         class::{
-            self, BootstrapMethod, ConstantPool, EnclosingMethod, InnerClassInfo,
-            NestedClassAccessFlags, RecordComponent, Version,
+            self, constant_pool::BootstrapMethodEntry, BootstrapMethod, ConstantPool,
+            EnclosingMethod, InnerClassInfo, NestedClassAccessFlags, RecordComponent, Version,
         },
         parsing::reader_utils::ValueReaderExt,
         references::ClassRef,
@@ -14,8 +17,14 @@ use crate::{
 };
 
 use super::{
-    attribute::AttributeInfo, field_info::FieldInfo, jvm_element_parser::ClassElement,
-    method_info::MethodInfo, raw_attributes, reader_utils::ReadBytes, Context, Error,
+    attribute::AttributeInfo,
+    field_info::FieldInfo,
+    jvm_element_parser::ClassElement,
+    method_info::MethodInfo,
+    options::Warnings,
+    raw_attributes,
+    reader_utils::{OffsetReader, ReadBytes},
+    AttributeRegistry, Context, Error, ParsingOptions, Warning,
 };
 
 /// The raw representation of a class file.
@@ -37,20 +46,76 @@ const JAVA_CLASS_MAIGC: u32 = 0xCAFE_BABE;
 
 impl Class {
     /// Parses a class file from the given reader.
+    /// Every attribute, including the method bodies, is decoded eagerly. To read only the
+    /// metadata and decode the attributes on demand, use
+    /// [`LazyClass`](crate::jvm::bytecode::lazy::LazyClass) instead.
     /// # Errors
     /// See [`Error`] for more information.
     pub fn from_reader<R>(reader: R) -> Result<Class, Error>
     where
         R: std::io::Read,
     {
-        let mut reader = reader;
-        let class_file = ClassFile::read_bytes(&mut reader)?;
-        Class::from_raw(class_file)
+        Class::from_reader_with_registry(reader, Arc::default())
+    }
+
+    /// Parses a class file from the given reader, decoding the custom attributes with the codecs
+    /// in `attribute_registry`.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn from_reader_with_registry<R>(
+        reader: R,
+        attribute_registry: Arc<AttributeRegistry>,
+    ) -> Result<Class, Error>
+    where
+        R: std::io::Read,
+    {
+        let options = ParsingOptions::new().with_attribute_registry(attribute_registry);
+        Class::from_reader_with_options(reader, &options).map(|(class, _)| class)
+    }
+
+    /// Parses a class file from the given bytes.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Class, Error> {
+        Class::from_reader(bytes)
+    }
+
+    /// Parses a class file from the given reader with the given options.
+    /// Returns the class together with the problems tolerated in lenient mode, which are always
+    /// empty in strict mode.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn from_reader_with_options<R>(
+        reader: R,
+        options: &ParsingOptions,
+    ) -> Result<(Class, Vec<Warning>), Error>
+    where
+        R: std::io::Read,
+    {
+        let mut reader = OffsetReader::new(reader);
+        let class_file = ClassFile::read_from(&mut reader).map_err(|source| Error::Read {
+            offset: reader.offset(),
+            source,
+        })?;
+        let warnings = options.is_lenient().then(Arc::default);
+        let class = Class::from_raw(
+            class_file,
+            Arc::clone(&options.attribute_registry),
+            warnings.clone(),
+        )?;
+        let warnings = warnings
+            .map(|it| {
+                Arc::try_unwrap(it)
+                    .map(|it| it.into_inner().unwrap_or_else(PoisonError::into_inner))
+                    .unwrap_or_default()
+            })
+            .unwrap_or_default();
+        Ok((class, warnings))
     }
 }
 
-impl ReadBytes for ClassFile {
-    fn read_bytes<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+impl ClassFile {
+    fn read_from<R: Read>(reader: &mut OffsetReader<R>) -> io::Result<Self> {
         let magic: u32 = reader.read_value()?;
         if magic != JAVA_CLASS_MAIGC {
             return Err(io::Error::new(
@@ -71,16 +136,27 @@ impl ReadBytes for ClassFile {
             .collect::<io::Result<_>>()?;
         let fields_count: u16 = reader.read_value()?;
         let fields = (0..fields_count)
-            .map(|_| FieldInfo::read_bytes(reader))
+            .map(|_| {
+                let start = reader.offset();
+                let mut field = FieldInfo::read_bytes(reader)?;
+                field.locate(start, reader.offset());
+                Ok(field)
+            })
             .collect::<io::Result<_>>()?;
         let methods_count: u16 = reader.read_value()?;
         let methods = (0..methods_count)
-            .map(|_| MethodInfo::read_bytes(reader))
+            .map(|_| {
+                let start = reader.offset();
+                let mut method = MethodInfo::read_bytes(reader)?;
+                method.locate(start, reader.offset());
+                Ok(method)
+            })
             .collect::<io::Result<_>>()?;
         let attributes_count: u16 = reader.read_value()?;
-        let attributes = (0..attributes_count)
+        let mut attributes: Vec<_> = (0..attributes_count)
             .map(|_| AttributeInfo::read_bytes(reader))
             .collect::<io::Result<_>>()?;
+        AttributeInfo::locate(&mut attributes, reader.offset());
 
         // Make sure there is no extra data in the reader
         if let Ok(0) = reader.read(&mut [0; 1]) {
@@ -103,11 +179,31 @@ impl ReadBytes for ClassFile {
 }
 
 impl Class {
-    pub(crate) fn from_raw(raw: ClassFile) -> Result<Self, Error> {
+    pub(crate) fn resolve_super_class(
+        super_class: u16,
+        binary_name: &str,
+        access_flags: class::AccessFlags,
+        constant_pool: &ConstantPool,
+    ) -> Result<Option<ClassRef>, Error> {
+        let super_class = match super_class {
+            0 if binary_name == "java/lang/Object" => None,
+            0 if access_flags.contains(class::AccessFlags::MODULE) => None,
+            0 => malform!("Class must have a super type except for java/lang/Object or a module"),
+            it => Some(constant_pool.get_class_ref(it)?),
+        };
+        Ok(super_class)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub(crate) fn from_raw(
+        raw: ClassFile,
+        attribute_registry: Arc<AttributeRegistry>,
+        warnings: Option<Warnings>,
+    ) -> Result<Self, Error> {
         let ClassFile {
             minor_version,
             major_version,
-            constant_pool,
+            mut constant_pool,
             access_flags,
             this_class,
             super_class,
@@ -117,23 +213,33 @@ impl Class {
             attributes,
         } = raw;
         let version = Version::from_versions(major_version, minor_version)?;
-        let access_flags = class::AccessFlags::from_bits(access_flags)
-            .ok_or(Error::UnknownFlags("ClassAccessFlags", access_flags))?;
-        let ClassRef { binary_name } = constant_pool.get_class_ref(this_class)?;
-        let super_class = match super_class {
-            0 if binary_name == "java/lang/Object" => None,
-            0 if access_flags.contains(class::AccessFlags::MODULE) => None,
-            0 => malform!("Class must have a super type except for java/lang/Object or a module"),
-            it => Some(constant_pool.get_class_ref(it)?),
+        // Repairs the broken strings before any of them is used. They are reported once the
+        // context is ready.
+        let repaired = if warnings.is_some() {
+            constant_pool.repair_utf8()
+        } else {
+            Vec::new()
         };
+        let ClassRef { binary_name } = constant_pool.get_class_ref(this_class)?;
+        let bootstrap_methods =
+            AttributeInfo::bootstrap_method_entries(&attributes, &constant_pool)?;
+        constant_pool.set_bootstrap_methods(bootstrap_methods);
 
         let parsing_context = Context {
             constant_pool,
             class_version: version,
             current_class_binary_name: binary_name.clone(),
+            attribute_registry,
+            warnings,
         };
 
         let ctx = &parsing_context;
+        for index in repaired {
+            ctx.tolerate(format!("constant pool entry #{index}"), Error::BrokenUTF8)?;
+        }
+        let access_flags = ctx.flags("ClassAccessFlags", access_flags)?;
+        let super_class =
+            Self::resolve_super_class(super_class, &binary_name, access_flags, &ctx.constant_pool)?;
 
         let interfaces = interfaces
             .into_iter()
@@ -150,14 +256,17 @@ impl Class {
         let attributes: Vec<Attribute> = attributes
             .into_iter()
             .map(|it| ClassElement::from_raw(it, ctx))
-            .collect::<Result<_, _>>()?;
+            .collect::<Result<_, Error>>()
+            .map_err(|error| error.within(|| binary_name.to_string()))?;
 
         extract_attributes! {
-            for attributes in "class_file" {
+            for attributes in "class_file" with ctx {
                 let source_file: SourceFile,
                 let inner_classes: InnerClasses as unwrap_or_default,
                 let enclosing_method: EnclosingMethod,
                 let source_debug_extension: SourceDebugExtension,
+                let compilation_id: CompilationId,
+                let source_id: SourceId,
                 let bootstrap_methods: BootstrapMethods as unwrap_or_default,
                 let runtime_visible_annotations: RuntimeVisibleAnnotations as unwrap_or_default,
                 let runtime_invisible_annotations: RuntimeInvisibleAnnotations as unwrap_or_default,
@@ -173,14 +282,14 @@ impl Class {
                 let record: Record,
                 if let is_synthetic: Synthetic,
                 if let is_deprecated: Deprecated,
-                else let free_attributes
+                else let free_attributes, custom_attributes
             }
         };
 
         Ok(Class {
             version,
             access_flags,
-            binary_name,
+            binary_name: binary_name.to_string(),
             super_class,
             interfaces,
             fields,
@@ -189,6 +298,8 @@ impl Class {
             inner_classes,
             enclosing_method,
             source_debug_extension,
+            compilation_id,
+            source_id,
             runtime_visible_annotations,
             runtime_invisible_annotations,
             runtime_visible_type_annotations,
@@ -205,6 +316,7 @@ impl Class {
             signature,
             record,
             free_attributes,
+            custom_attributes,
         })
     }
 }
@@ -226,6 +338,26 @@ impl ClassElement for BootstrapMethod {
     }
 }
 
+impl BootstrapMethodEntry {
+    /// Reads the entries in the `info` item of a `BootstrapMethods` attribute.
+    pub(crate) fn read_all(info: &[u8]) -> io::Result<Vec<Self>> {
+        let reader = &mut io::Cursor::new(info);
+        let num_bootstrap_methods: u16 = reader.read_value()?;
+        (0..num_bootstrap_methods)
+            .map(|_| {
+                let raw_attributes::BootstrapMethod {
+                    method_ref_idx,
+                    arguments,
+                } = reader.read_value()?;
+                Ok(Self {
+                    method_ref_index: method_ref_idx,
+                    argument_indices: arguments,
+                })
+            })
+            .collect()
+    }
+}
+
 impl ClassElement for InnerClassInfo {
     type Raw = raw_attributes::InnerClass;
 
@@ -248,8 +380,8 @@ impl ClassElement for InnerClassInfo {
         } else {
             Some(ctx.constant_pool.get_str(inner_name_index)?.to_owned())
         };
-        let access_flags = NestedClassAccessFlags::from_bits(access_flags)
-            .ok_or(Error::UnknownFlags("NextClassAccessFlags", access_flags))?;
+        let access_flags: NestedClassAccessFlags =
+            ctx.flags("NextClassAccessFlags", access_flags)?;
         Ok(Self {
             inner_class,
             outer_class,
@@ -275,7 +407,7 @@ impl ClassElement for RecordComponent {
             .map(|it| ClassElement::from_raw(it, ctx))
             .collect::<Result<_, _>>()?;
         extract_attributes! {
-            for attributes in "record_component" {
+            for attributes in "record_component" with ctx {
                 let signature: Signature,
                 let runtime_visible_annotations : RuntimeVisibleAnnotations as unwrap_or_default,
                 let runtime_invisible_annotations : RuntimeInvisibleAnnotations as unwrap_or_default,
@@ -283,7 +415,7 @@ impl ClassElement for RecordComponent {
                     : RuntimeVisibleTypeAnnotations as unwrap_or_default,
                 let runtime_invisible_type_annotations
                     : RuntimeInvisibleTypeAnnotations as unwrap_or_default,
-                else let free_attributes
+                else let free_attributes, custom_attributes
             }
         }
 
@@ -296,6 +428,7 @@ impl ClassElement for RecordComponent {
             runtime_visible_type_annotations,
             runtime_invisible_type_annotations,
             free_attributes,
+            custom_attributes,
         })
     }
 }
@@ -10,8 +10,9 @@ use std::{
 use crate::{
     jvm::{
         code::{
-            ExceptionTableEntry, LineNumberTableEntry, LocalVariableId, LocalVariableTable,
-            MethodBody, ProgramCounter, RawInstruction,
+            CharacterRangeFlags, CharacterRangeTableEntry, ExceptionTableEntry,
+            LineNumberTableEntry, LocalVariableId, LocalVariableTable, MethodBody, ProgramCounter,
+            RawInstruction,
         },
         method::{ParameterAccessFlags, ParameterInfo},
     },
@@ -20,6 +21,7 @@ use crate::{
 };
 
 use super::{
+    attribute::Attribute,
     jvm_element_parser::ClassElement,
     raw_attributes::{self, Code},
     reader_utils::{ReadBytes, ValueReaderExt},
@@ -59,6 +61,30 @@ impl ReadBytes for LineNumberTableEntry {
     }
 }
 
+impl ClassElement for CharacterRangeTableEntry {
+    type Raw = Self;
+
+    fn from_raw(raw: Self::Raw, _ctx: &Context) -> Result<Self, Error> {
+        Ok(raw)
+    }
+}
+
+impl ReadBytes for CharacterRangeTableEntry {
+    fn read_bytes<R: Read + ?Sized>(reader: &mut R) -> io::Result<Self> {
+        let start_pc: ProgramCounter = reader.read_value()?;
+        let end_pc: ProgramCounter = reader.read_value()?;
+        let start: u32 = reader.read_value()?;
+        let end: u32 = reader.read_value()?;
+        let flags = reader.read_value()?;
+        Ok(CharacterRangeTableEntry {
+            pc_range: start_pc..=end_pc,
+            start: start.into(),
+            end: end.into(),
+            flags: CharacterRangeFlags::from_bits_retain(flags),
+        })
+    }
+}
+
 impl ClassElement for ExceptionTableEntry {
     type Raw = raw_attributes::ExceptionTableEntry;
 
@@ -178,8 +204,9 @@ impl ClassElement for MethodBody {
             .collect::<Result<_, _>>()?;
         let mut local_variable_table = None;
         extract_attributes! {
-            for attributes in "code" {
+            for attributes in "code" with ctx {
                 let line_number_table: LineNumberTable,
+                let character_range_table: CharacterRangeTable,
                 let stack_map_table: StackMapTable,
                 let runtime_visible_type_annotations:
                     RuntimeVisibleTypeAnnotations as unwrap_or_default,
@@ -197,7 +224,7 @@ impl ClassElement for MethodBody {
                         table.merge_signature(id, name, signature)?;
                     }
                 },
-                else let free_attributes
+                else let free_attributes, custom_attributes
             }
         }
 
@@ -207,11 +234,70 @@ impl ClassElement for MethodBody {
             instructions,
             exception_table,
             line_number_table,
+            character_range_table,
             local_variable_table,
             stack_map_table,
             runtime_visible_type_annotations,
             runtime_invisible_type_annotations,
             free_attributes,
+            custom_attributes,
         })
     }
 }
+
+/// Merges the entries of a `LocalVariableTable` or `LocalVariableTypeTable` attribute into
+/// `table`. Other attributes are ignored.
+pub(crate) fn merge_local_variables(
+    table: &mut LocalVariableTable,
+    attribute: Attribute,
+) -> Result<(), Error> {
+    match attribute {
+        Attribute::LocalVariableTable(it) => {
+            for LocalVariableDescAttr {
+                id,
+                name,
+                field_type,
+            } in it
+            {
+                table.merge_type(id, name, field_type)?;
+            }
+        }
+        Attribute::LocalVariableTypeTable(it) => {
+            for LocalVariableTypeAttr {
+                id,
+                name,
+                signature,
+            } in it
+            {
+                table.merge_signature(id, name, signature)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_character_range() {
+        let bytes: &[u8] = &[
+            0x00, 0x02, // start_pc
+            0x00, 0x07, // end_pc
+            0x00, 0x00, 0x0C, 0x05, // line 3, column 5
+            0x00, 0x00, 0x0C, 0x12, // line 3, column 18
+            0x00, 0x21, // CRT_STATEMENT | CRT_INVOKE
+        ];
+        let entry = CharacterRangeTableEntry::read_bytes(&mut &bytes[..]).unwrap();
+        assert_eq!(entry.pc_range, 2.into()..=7.into());
+        assert_eq!((entry.start.line, entry.start.column), (3, 5));
+        assert_eq!((entry.end.line, entry.end.column), (3, 18));
+        assert_eq!(
+            entry.flags,
+            CharacterRangeFlags::STATEMENT | CharacterRangeFlags::INVOKE
+        );
+        assert_eq!(u32::from(entry.end), 0x0C12);
+    }
+}
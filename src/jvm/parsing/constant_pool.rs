@@ -9,7 +9,10 @@ use super::{
 };
 use crate::{
     jvm::{
-        class::{constant_pool::Entry, ConstantPool, MethodHandle},
+        class::{
+            constant_pool::{BootstrapMethodEntry, Entry},
+            BootstrapMethod, ConstantPool, DynamicConstant, MethodHandle,
+        },
         references::{ClassRef, FieldRef, MethodRef, ModuleRef, PackageRef},
         ConstantValue, JavaString,
     },
@@ -17,6 +20,10 @@ use crate::{
     types::field_type::FieldType,
 };
 
+/// The maximum number of dynamic constants resolved for a single loadable constant, including
+/// the ones nested in the arguments of their bootstrap methods.
+const MAX_NESTED_DYNAMIC_CONSTANTS: usize = 1024;
+
 #[inline]
 const fn mismatch<T>(expected: &'static str, entry: &Entry) -> Result<T, Error> {
     Err(Error::MismatchedConstantPoolEntryType {
@@ -26,7 +33,7 @@ const fn mismatch<T>(expected: &'static str, entry: &Entry) -> Result<T, Error>
 }
 
 impl ConstantPool {
-    pub(super) fn get_str(&self, index: u16) -> Result<&str, Error> {
+    pub(crate) fn get_str(&self, index: u16) -> Result<&str, Error> {
         let entry = self.get_entry(index)?;
         match entry {
             Entry::Utf8(JavaString::Utf8(string)) => Ok(string),
@@ -35,7 +42,7 @@ impl ConstantPool {
         }
     }
 
-    pub(super) fn get_class_ref(&self, index: u16) -> Result<ClassRef, Error> {
+    pub(crate) fn get_class_ref(&self, index: u16) -> Result<ClassRef, Error> {
         let entry = self.get_entry(index)?;
         if let &Entry::Class { name_index } = entry {
             let name = self.get_str(name_index)?;
@@ -46,6 +53,37 @@ impl ConstantPool {
     }
 
     pub(super) fn get_constant_value(&self, value_index: u16) -> Result<ConstantValue, Error> {
+        let mut budget = MAX_NESTED_DYNAMIC_CONSTANTS;
+        self.get_loadable_constant(value_index, &mut budget)
+    }
+
+    /// Resolves the bootstrap method at `index`, where `budget` is the number of dynamic
+    /// constants that can still be resolved in its arguments.
+    fn resolve_bootstrap_method(
+        &self,
+        index: u16,
+        budget: &mut usize,
+    ) -> Result<BootstrapMethod, Error> {
+        let Some(BootstrapMethodEntry {
+            method_ref_index,
+            argument_indices,
+        }) = self.bootstrap_methods().get(usize::from(index))
+        else {
+            return Err(Error::Other("The bootstrap method index is out of bounds"));
+        };
+        let method = self.get_method_handle(*method_ref_index)?;
+        let arguments = argument_indices
+            .iter()
+            .map(|it| self.get_loadable_constant(*it, budget))
+            .collect::<Result<_, _>>()?;
+        Ok(BootstrapMethod { method, arguments })
+    }
+
+    fn get_loadable_constant(
+        &self,
+        value_index: u16,
+        budget: &mut usize,
+    ) -> Result<ConstantValue, Error> {
         let entry = self.get_entry(value_index)?;
         match entry {
             &Entry::Integer(it) => Ok(ConstantValue::Integer(it)),
@@ -74,12 +112,19 @@ impl ConstantPool {
                 bootstrap_method_attr_index,
                 name_and_type_index,
             } => {
+                // Bounds both the cycles, which are illegal, and the nesting through the arguments
+                // of the bootstrap methods.
+                *budget = budget
+                    .checked_sub(1)
+                    .ok_or(Error::Other("Too many nested dynamic constants"))?;
                 let (name, descriptor) = self.get_name_and_type(name_and_type_index)?;
-                Ok(ConstantValue::Dynamic(
-                    bootstrap_method_attr_index,
+                let bootstrap =
+                    self.resolve_bootstrap_method(bootstrap_method_attr_index, budget)?;
+                Ok(ConstantValue::Dynamic(Box::new(DynamicConstant {
+                    bootstrap,
                     name,
                     descriptor,
-                ))
+                })))
             }
             unexpected => mismatch(
                 concat!(
@@ -122,11 +167,7 @@ impl ConstantPool {
         {
             let owner = self.get_class_ref(class_index)?;
             let (name, field_type) = self.get_name_and_type(name_and_type_index)?;
-            Ok(FieldRef {
-                owner,
-                name,
-                field_type,
-            })
+            Ok(FieldRef::new(owner, &name, field_type))
         } else {
             mismatch("Field", entry)
         }
@@ -167,11 +208,7 @@ impl ConstantPool {
         {
             let owner = self.get_class_ref(class_index)?;
             let (name, descriptor) = self.get_name_and_type(name_and_type_index)?;
-            Ok(MethodRef {
-                owner,
-                name,
-                descriptor,
-            })
+            Ok(MethodRef::new(owner, &name, descriptor))
         } else {
             mismatch("MethodRef | InterfaceMethodRef", entry)
         }
@@ -206,9 +243,9 @@ impl ConstantPool {
     pub(super) fn get_type_ref(&self, index: u16) -> Result<FieldType, Error> {
         let ClassRef { binary_name: name } = self.get_class_ref(index)?;
         let field_type = if name.starts_with('[') {
-            FieldType::from_str(name.as_str())?
+            FieldType::from_str(&name)?
         } else {
-            FieldType::Object(ClassRef::new(name))
+            FieldType::Object(ClassRef { binary_name: name })
         };
         Ok(field_type)
     }
@@ -219,10 +256,10 @@ impl Entry {
         let tag: u8 = reader.read_value()?;
         match tag {
             1 => Self::parse_utf8(reader),
-            3 => reader.read_value().map(Self::Integer).map_err(Into::into),
-            4 => reader.read_value().map(Self::Float).map_err(Into::into),
-            5 => reader.read_value().map(Self::Long).map_err(Into::into),
-            6 => reader.read_value().map(Self::Double).map_err(Into::into),
+            3 => reader.read_value().map(Self::Integer),
+            4 => reader.read_value().map(Self::Float),
+            5 => reader.read_value().map(Self::Long),
+            6 => reader.read_value().map(Self::Double),
             7 => Ok(Self::Class {
                 name_index: reader.read_value()?,
             }),
@@ -291,6 +328,51 @@ pub(crate) mod tests {
 
     const MAX_BYTES: usize = 255;
 
+    #[test]
+    fn dynamic_constants() {
+        let bootstrap = |name: &str, arguments| BootstrapMethod {
+            method: MethodHandle::RefInvokeStatic(MethodRef::new(
+                ClassRef::new("java/lang/invoke/ConstantBootstraps"),
+                name,
+                "()V".parse().unwrap(),
+            )),
+            arguments,
+        };
+        let int_class = ConstantValue::Dynamic(Box::new(DynamicConstant {
+            bootstrap: bootstrap("primitiveClass", Vec::new()),
+            name: "I".to_owned(),
+            descriptor: "Ljava/lang/Class;".parse().unwrap(),
+        }));
+        let var_handle = ConstantValue::Dynamic(Box::new(DynamicConstant {
+            bootstrap: bootstrap(
+                "fieldVarHandle",
+                vec![
+                    ConstantValue::Class(ClassRef::new("Test")),
+                    int_class.clone(),
+                ],
+            ),
+            name: "value".to_owned(),
+            descriptor: "Ljava/lang/invoke/VarHandle;".parse().unwrap(),
+        }));
+        let mut builder = ConstantPool::builder();
+        let orphan = builder.put_long(42).unwrap();
+        let index = builder.put_constant_value(&var_handle).unwrap();
+        assert_eq!(builder.put_constant_value(&int_class), Ok(index - 4));
+        let mut constant_pool = builder.build();
+        assert_eq!(constant_pool.bootstrap_methods().len(), 2);
+        assert_eq!(constant_pool.get_constant_value(index).unwrap(), var_handle);
+
+        let index_mapping = constant_pool.compact([index]).unwrap();
+        assert!(!index_mapping.contains_key(&orphan));
+        assert_eq!(
+            constant_pool
+                .get_constant_value(index_mapping[&index])
+                .unwrap(),
+            var_handle
+        );
+        assert_eq!(constant_pool.validate(), Ok(()));
+    }
+
     proptest! {
 
         #[test]
@@ -0,0 +1,252 @@
+use std::{any::Any, collections::HashMap, fmt::Debug, sync::Arc};
+
+use crate::jvm::class::constant_pool::{BuildError, ConstantPoolBuilder};
+
+use super::{Context, Error};
+
+/// A value of an attribute decoded by an [`AttributeCodec`].
+pub trait AttributeValue: Any + Debug + Send + Sync {
+    /// Returns the value as [`Any`] for downcasting.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Debug + Send + Sync> AttributeValue for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// An attribute that is not defined in the JVM specification, decoded by a codec registered in
+/// an [`AttributeRegistry`].
+#[derive(Debug, Clone)]
+pub struct CustomAttribute {
+    name: String,
+    value: Arc<dyn AttributeValue>,
+}
+
+impl CustomAttribute {
+    /// Creates an attribute named `name` holding `value`.
+    pub fn new(name: impl Into<String>, value: impl AttributeValue) -> Self {
+        Self {
+            name: name.into(),
+            value: Arc::new(value),
+        }
+    }
+
+    /// Returns the name of the attribute.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the value of the attribute if it is of type `T`.
+    #[must_use]
+    pub fn value<T: AttributeValue>(&self) -> Option<&T> {
+        (*self.value).as_any().downcast_ref()
+    }
+}
+
+/// A parser and writer of a custom attribute.
+pub trait AttributeCodec: Send + Sync + 'static {
+    /// The type of the decoded value.
+    type Value: AttributeValue;
+
+    /// The name of the attribute handled by the codec.
+    fn name(&self) -> &str;
+
+    /// Decodes the content of the attribute (i.e., `info` in `attribute_info`).
+    /// The constant pool of the class being parsed is available in `ctx`.
+    ///
+    /// # Errors
+    /// See [`Error`].
+    fn decode(&self, bytes: &[u8], ctx: &Context) -> Result<Self::Value, Error>;
+
+    /// Encodes `value` as the content of the attribute, adding the referenced constants to
+    /// `constant_pool`.
+    ///
+    /// # Errors
+    /// See [`BuildError`].
+    fn encode(
+        &self,
+        value: &Self::Value,
+        constant_pool: &mut ConstantPoolBuilder,
+    ) -> Result<Vec<u8>, BuildError>;
+}
+
+/// An error when encoding a [`CustomAttribute`].
+#[derive(Debug, thiserror::Error)]
+pub enum EncodeError {
+    /// No codec is registered for the attribute.
+    #[error("No codec is registered for attribute {0}")]
+    Unregistered(String),
+    /// The value of the attribute is not of the type handled by the codec.
+    #[error("The value of attribute {0} is not of the type handled by its codec")]
+    MismatchedValue(String),
+    /// The attribute is too long.
+    #[error("The attribute is too long")]
+    TooLong,
+    /// The constant pool cannot hold the referenced constants.
+    #[error(transparent)]
+    ConstantPool(#[from] BuildError),
+}
+
+/// An object-safe version of [`AttributeCodec`].
+trait DynCodec: Send + Sync {
+    fn decode(&self, bytes: &[u8], ctx: &Context) -> Result<Arc<dyn AttributeValue>, Error>;
+
+    fn encode(
+        &self,
+        value: &dyn AttributeValue,
+        constant_pool: &mut ConstantPoolBuilder,
+    ) -> Option<Result<Vec<u8>, BuildError>>;
+}
+
+impl<C: AttributeCodec> DynCodec for C {
+    fn decode(&self, bytes: &[u8], ctx: &Context) -> Result<Arc<dyn AttributeValue>, Error> {
+        AttributeCodec::decode(self, bytes, ctx).map(|it| Arc::new(it) as _)
+    }
+
+    fn encode(
+        &self,
+        value: &dyn AttributeValue,
+        constant_pool: &mut ConstantPoolBuilder,
+    ) -> Option<Result<Vec<u8>, BuildError>> {
+        let value = value.as_any().downcast_ref()?;
+        Some(AttributeCodec::encode(self, value, constant_pool))
+    }
+}
+
+/// A registry of [`AttributeCodec`]s, which is used to parse the attributes that are otherwise
+/// kept as raw bytes in `free_attributes`.
+#[derive(Default, Clone)]
+pub struct AttributeRegistry {
+    codecs: HashMap<String, Arc<dyn DynCodec>>,
+}
+
+impl Debug for AttributeRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.codecs.keys()).finish()
+    }
+}
+
+impl AttributeRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `codec`, replacing the one previously registered for the same attribute name.
+    /// Codecs for the attributes defined in the JVM specification are never used.
+    #[must_use]
+    pub fn with_codec(mut self, codec: impl AttributeCodec) -> Self {
+        self.codecs.insert(codec.name().to_owned(), Arc::new(codec));
+        self
+    }
+
+    /// Decodes the attribute named `name` if a codec is registered for it.
+    pub(super) fn decode(
+        &self,
+        name: &str,
+        bytes: &[u8],
+        ctx: &Context,
+    ) -> Option<Result<CustomAttribute, Error>> {
+        let codec = self.codecs.get(name)?;
+        Some(codec.decode(bytes, ctx).map(|value| CustomAttribute {
+            name: name.to_owned(),
+            value,
+        }))
+    }
+
+    /// Encodes `attribute` as an `attribute_info` structure, including its name index and
+    /// length, adding the referenced constants to `constant_pool`.
+    ///
+    /// # Errors
+    /// See [`EncodeError`].
+    pub fn encode(
+        &self,
+        attribute: &CustomAttribute,
+        constant_pool: &mut ConstantPoolBuilder,
+    ) -> Result<Vec<u8>, EncodeError> {
+        let codec = self
+            .codecs
+            .get(&attribute.name)
+            .ok_or_else(|| EncodeError::Unregistered(attribute.name.clone()))?;
+        let info = codec
+            .encode(attribute.value.as_ref(), constant_pool)
+            .ok_or_else(|| EncodeError::MismatchedValue(attribute.name.clone()))??;
+        let name_index = constant_pool.put_utf8(attribute.name.clone())?;
+        let length = u32::try_from(info.len()).map_err(|_| EncodeError::TooLong)?;
+        let mut bytes = Vec::with_capacity(info.len() + 6);
+        bytes.extend_from_slice(&name_index.to_be_bytes());
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes.extend_from_slice(&info);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jvm::Class, tests::empty_class_with_version};
+
+    /// An attribute holding the index of a UTF-8 constant.
+    struct NameCodec;
+
+    impl AttributeCodec for NameCodec {
+        type Value = String;
+
+        fn name(&self) -> &'static str {
+            "HelloWorld"
+        }
+
+        fn decode(&self, bytes: &[u8], ctx: &Context) -> Result<Self::Value, Error> {
+            let [high, low] = bytes else {
+                return Err(Error::Other("Invalid attribute length"));
+            };
+            let index = u16::from_be_bytes([*high, *low]);
+            Ok(ctx.constant_pool.get_str(index)?.to_owned())
+        }
+
+        fn encode(
+            &self,
+            value: &Self::Value,
+            constant_pool: &mut ConstantPoolBuilder,
+        ) -> Result<Vec<u8>, BuildError> {
+            constant_pool
+                .put_utf8(value.clone())
+                .map(|it| it.to_be_bytes().to_vec())
+        }
+    }
+
+    fn class_bytes() -> Vec<u8> {
+        let mut bytes = empty_class_with_version(52, 0).to_vec();
+        bytes.truncate(bytes.len() - 2);
+        // An attribute named by and pointing to the constant `HelloWorld`.
+        bytes.extend_from_slice(&[0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02]);
+        bytes
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let registry = Arc::new(AttributeRegistry::new().with_codec(NameCodec));
+        let class =
+            Class::from_reader_with_registry(class_bytes().as_slice(), registry.clone()).unwrap();
+        assert!(class.free_attributes.is_empty());
+        let attribute = &class.custom_attributes[0];
+        assert_eq!(attribute.name(), "HelloWorld");
+        assert_eq!(
+            attribute.value::<String>().map(String::as_str),
+            Some("HelloWorld")
+        );
+        assert_eq!(attribute.value::<u32>(), None);
+
+        let mut constant_pool = ConstantPoolBuilder::default();
+        let bytes = registry.encode(attribute, &mut constant_pool).unwrap();
+        assert_eq!(bytes, vec![0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01]);
+
+        let class = Class::from_reader(class_bytes().as_slice()).unwrap();
+        assert!(class.custom_attributes.is_empty());
+        assert_eq!(class.free_attributes[0].0, "HelloWorld");
+    }
+}
@@ -9,6 +9,26 @@ pub enum Error {
     /// An error that occurs when reading from a buffer.
     #[error("Failed to read from buffer: {0}")]
     IO(#[from] std::io::Error),
+    /// An error that occurs when reading the structure of a class file.
+    #[error("Failed to read at offset {offset:#x}: {source}")]
+    Read {
+        /// The number of bytes successfully read before the error.
+        offset: u64,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+    /// An error annotated with where it occurs in the class file.
+    #[error("{source} at offset {offset:#x} in {structure}{}", of(.member.as_deref()))]
+    Located {
+        /// The error.
+        source: Box<Error>,
+        /// The position of the parser in the class file when the error occurs.
+        offset: u64,
+        /// The structure being parsed, e.g., `Code attribute` or `method_info`.
+        structure: String,
+        /// The class or member enclosing the structure, e.g., `com/foo/Bar.baz()V`.
+        member: Option<String>,
+    },
     /// The format of the class file is invalid.
     #[error("MalformedClassFile: {0}")]
     Other(&'static str),
@@ -39,7 +59,7 @@ pub enum Error {
     #[error("Unknown stack map frame type {0}")]
     UnknownStackMapFrameType(u8),
     /// The verification type info tag is invalid.
-    #[error("Invalid verification type info tag {0}")]
+    #[error("Invalid verification type info tag {0:#04x}")]
     InvalidVerificationTypeInfoTag(u8),
     /// The opcode cannot be recognized when parsing the code attribute.
     #[error("Unexpected opcode {0:#x}")]
@@ -63,3 +83,40 @@ pub enum Error {
     #[error("The instruction list is too long, it should be at most 65536 bytes")]
     TooLongInstructionList,
 }
+
+impl Error {
+    /// Annotates the error with the location where it occurs unless it is already located.
+    pub(crate) fn locate(self, offset: u64, structure: impl Into<String>) -> Self {
+        match self {
+            located @ Self::Located { .. } => located,
+            error => Self::Located {
+                source: Box::new(error),
+                offset,
+                structure: structure.into(),
+                member: None,
+            },
+        }
+    }
+
+    /// Annotates the error with the enclosing class or member if it is located but has none.
+    pub(crate) fn within(self, member: impl FnOnce() -> String) -> Self {
+        match self {
+            Self::Located {
+                source,
+                offset,
+                structure,
+                member: None,
+            } => Self::Located {
+                source,
+                offset,
+                structure,
+                member: Some(member()),
+            },
+            error => error,
+        }
+    }
+}
+
+fn of(member: Option<&str>) -> String {
+    member.map(|it| format!(" of {it}")).unwrap_or_default()
+}
@@ -1,11 +1,7 @@
 use std::io::{self, Read};
 
 use crate::{
-    jvm::{
-        field::{self},
-        references::ClassRef,
-        Field,
-    },
+    jvm::{references::ClassRef, Field},
     macros::{extract_attributes, see_jvm_spec},
 };
 
@@ -24,6 +20,16 @@ pub(crate) struct FieldInfo {
     name_index: u16,
     descriptor_index: u16,
     attributes: Vec<AttributeInfo>,
+    /// The offset of the structure in the class file.
+    offset: u64,
+}
+
+impl FieldInfo {
+    /// Sets the offsets of the structure that spans from `start` to `end` in the class file.
+    pub(super) fn locate(&mut self, start: u64, end: u64) {
+        self.offset = start;
+        AttributeInfo::locate(&mut self.attributes, end);
+    }
 }
 
 impl ReadBytes for FieldInfo {
@@ -40,6 +46,8 @@ impl ReadBytes for FieldInfo {
             name_index,
             descriptor_index,
             attributes,
+            // The offset is set by the enclosing structure with `locate`.
+            offset: 0,
         })
     }
 }
@@ -48,14 +56,27 @@ impl ClassElement for Field {
     type Raw = FieldInfo;
 
     fn from_raw(raw: Self::Raw, ctx: &Context) -> Result<Self, Error> {
+        let FieldInfo {
+            name_index, offset, ..
+        } = raw;
+        Self::parse(raw, ctx).map_err(|error| {
+            error
+                .locate(offset, "field_info")
+                .within(|| ctx.member_name(name_index, None))
+        })
+    }
+}
+
+impl Field {
+    fn parse(raw: FieldInfo, ctx: &Context) -> Result<Self, Error> {
         let FieldInfo {
             access_flags,
             name_index,
             descriptor_index,
             attributes,
+            ..
         } = raw;
-        let access_flags = field::AccessFlags::from_bits(access_flags)
-            .ok_or(Error::UnknownFlags("FieldAccessFlag", access_flags))?;
+        let access_flags = ctx.flags("FieldAccessFlag", access_flags)?;
         let name = ctx.constant_pool.get_str(name_index)?.to_owned();
         let field_type = ctx.constant_pool.get_str(descriptor_index)?.parse()?;
         let owner = ClassRef {
@@ -67,7 +88,7 @@ impl ClassElement for Field {
             .collect::<Result<_, _>>()?;
 
         extract_attributes! {
-            for attributes in "field_info" {
+            for attributes in "field_info" with ctx {
                 let constant_value: ConstantValue,
                 let signature: Signature,
                 let runtime_visible_annotations
@@ -80,7 +101,7 @@ impl ClassElement for Field {
                     : RuntimeInvisibleTypeAnnotations as unwrap_or_default,
                 if let is_synthetic: Synthetic,
                 if let is_deprecated: Deprecated,
-                else let free_attributes
+                else let free_attributes, custom_attributes
             }
         }
 
@@ -98,6 +119,7 @@ impl ClassElement for Field {
             runtime_visible_type_annotations,
             runtime_invisible_type_annotations,
             free_attributes,
+            custom_attributes,
         })
     }
 }
@@ -7,7 +7,7 @@ use crate::{
         references::ClassRef,
         Method,
     },
-    macros::{extract_attributes, malform, see_jvm_spec},
+    macros::{extract_attributes, see_jvm_spec},
     types::method_descriptor::MethodDescriptor,
 };
 
@@ -26,6 +26,16 @@ pub(super) struct MethodInfo {
     name_index: u16,
     descriptor_index: u16,
     attributes: Vec<AttributeInfo>,
+    /// The offset of the structure in the class file.
+    offset: u64,
+}
+
+impl MethodInfo {
+    /// Sets the offsets of the structure that spans from `start` to `end` in the class file.
+    pub(super) fn locate(&mut self, start: u64, end: u64) {
+        self.offset = start;
+        AttributeInfo::locate(&mut self.attributes, end);
+    }
 }
 
 impl ReadBytes for MethodInfo {
@@ -42,6 +52,8 @@ impl ReadBytes for MethodInfo {
             name_index,
             descriptor_index,
             attributes,
+            // The offset is set by the enclosing structure with `locate`.
+            offset: 0,
         })
     }
 }
@@ -50,14 +62,38 @@ impl ClassElement for Method {
     type Raw = MethodInfo;
 
     fn from_raw(raw: Self::Raw, ctx: &Context) -> Result<Self, Error> {
+        let MethodInfo {
+            name_index,
+            descriptor_index,
+            offset,
+            ..
+        } = raw;
+        Self::parse(raw, ctx).map_err(|error| {
+            error
+                .locate(offset, "method_info")
+                .within(|| ctx.member_name(name_index, Some(descriptor_index)))
+        })
+    }
+}
+
+impl Method {
+    /// Parses a method from the `method_info` structure in `bytes`, which starts at `offset` in
+    /// the class file.
+    pub(crate) fn from_bytes(bytes: &[u8], offset: u64, ctx: &Context) -> Result<Self, Error> {
+        let mut raw = MethodInfo::read_bytes(&mut &*bytes)?;
+        raw.locate(offset, offset + bytes.len() as u64);
+        Method::from_raw(raw, ctx)
+    }
+
+    fn parse(raw: MethodInfo, ctx: &Context) -> Result<Self, Error> {
         let MethodInfo {
             access_flags,
             name_index,
             descriptor_index,
             attributes,
+            ..
         } = raw;
-        let access_flags = method::AccessFlags::from_bits(access_flags)
-            .ok_or(Error::UnknownFlags("MethodAccessFlags", access_flags))?;
+        let access_flags: method::AccessFlags = ctx.flags("MethodAccessFlags", access_flags)?;
         let name = ctx.constant_pool.get_str(name_index)?.to_owned();
         let descriptor: MethodDescriptor = ctx.constant_pool.get_str(descriptor_index)?.parse()?;
         let owner = ClassRef {
@@ -69,7 +105,7 @@ impl ClassElement for Method {
             .map(|it| Attribute::from_raw(it, ctx))
             .collect::<Result<_, _>>()?;
         extract_attributes! {
-            for attributes in "method_info" {
+            for attributes in "method_info" with ctx {
                 let body: Code,
                 let exceptions: Exceptions as unwrap_or_default,
                 let runtime_visible_annotations
@@ -85,11 +121,11 @@ impl ClassElement for Method {
                 let runtime_invisible_parameter_annotations
                     : RuntimeInvisibleParameterAnnotations as unwrap_or_default,
                 let annotation_default: AnnotationDefault,
-                let parameters: MethodParameters as unwrap_or_default,
+                let parameters: MethodParameters,
                 let signature: Signature,
                 if let is_synthetic: Synthetic,
                 if let is_deprecated: Deprecated,
-                else let free_attributes
+                else let free_attributes, custom_attributes
             }
         };
 
@@ -101,12 +137,12 @@ impl ClassElement for Method {
         {
             // then its method_info structure must not have a Code attribute in its attributes table
             if body.is_some() {
-                malform!("Unexpected code attribute");
+                ctx.tolerate("method_info", Error::Other("Unexpected code attribute"))?;
             }
         } else {
             // Otherwise, its method_info structure must have exactly one Code attribute in its attributes table
             if body.is_none() {
-                malform!("The method must have a body");
+                ctx.tolerate("method_info", Error::Other("The method must have a body"))?;
             }
         }
 
@@ -115,10 +151,11 @@ impl ClassElement for Method {
             if !access_flags.contains(method::AccessFlags::STATIC)
                 || !descriptor.parameters_types.is_empty()
             {
-                malform!(concat!(
+                let error = Error::Other(concat!(
                     "Class initializer in class version 51 or above",
                     "must be static and takes no arguments"
                 ));
+                ctx.tolerate("method_info", error)?;
             }
         }
 
@@ -141,6 +178,7 @@ impl ClassElement for Method {
             is_deprecated,
             signature,
             free_attributes,
+            custom_attributes,
         })
     }
 }
@@ -4,16 +4,26 @@ mod attribute;
 pub(super) mod class_file;
 mod code;
 pub(super) mod constant_pool;
+mod custom_attribute;
 pub(super) mod errors;
 mod field_info;
 mod jvm_element_parser;
 mod method_info;
 mod module;
+mod options;
 mod raw_attributes;
-mod reader_utils;
+pub(crate) mod reader_utils;
+
+use std::sync::Arc;
 
 use crate::jvm::class::{ConstantPool, Version};
+pub(crate) use attribute::Attribute;
+pub(crate) use code::merge_local_variables;
+pub use custom_attribute::{
+    AttributeCodec, AttributeRegistry, AttributeValue, CustomAttribute, EncodeError,
+};
 pub use errors::Error;
+pub use options::{ParsingOptions, Warning};
 
 /// Context used to parse a class file.
 #[derive(Debug, Clone)]
@@ -23,5 +33,26 @@ pub struct Context {
     /// The version of the class file being parsed.
     pub class_version: Version,
     /// The binary name of the class being parsed.
-    pub current_class_binary_name: String,
+    pub current_class_binary_name: Arc<str>,
+    /// The codecs of the custom attributes.
+    pub attribute_registry: Arc<AttributeRegistry>,
+    /// The problems tolerated so far, or [`None`] if parsing in strict mode.
+    pub(crate) warnings: Option<options::Warnings>,
+}
+
+impl Context {
+    /// Describes a member of the class being parsed for error messages, e.g., `com/foo/Bar.baz()V`.
+    pub(crate) fn member_name(&self, name_index: u16, descriptor_index: Option<u16>) -> String {
+        let resolve = |index: u16| {
+            self.constant_pool
+                .get_str(index)
+                .map_or_else(|_| format!("#{index}"), str::to_owned)
+        };
+        let descriptor = descriptor_index.map(resolve).unwrap_or_default();
+        format!(
+            "{}.{}{descriptor}",
+            self.current_class_binary_name,
+            resolve(name_index)
+        )
+    }
 }
@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+
+use super::{AttributeRegistry, Context, Error};
+
+/// Options that control how class files are parsed.
+#[derive(Debug, Clone, Default)]
+pub struct ParsingOptions {
+    pub(super) lenient: bool,
+    pub(super) attribute_registry: Arc<AttributeRegistry>,
+}
+
+impl ParsingOptions {
+    /// Creates options for strict parsing without custom attributes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether to parse in lenient mode.
+    /// In lenient mode, the following problems are recorded as [`Warning`]s instead of
+    /// aborting the parse:
+    /// - Attributes that cannot be decoded, e.g., stack map tables with reserved frame types or
+    ///   code arrays longer than 65535 bytes. They are kept as unrecognized attributes.
+    /// - Attributes that are duplicated or in a location where they are not allowed. They are
+    ///   skipped.
+    /// - Strings in the constant pool that are not valid UTF-8. They are decoded lossily.
+    /// - Unknown bits in access flags. They are ignored.
+    #[must_use]
+    pub const fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Decodes the custom attributes with the codecs in `attribute_registry`.
+    #[must_use]
+    pub fn with_attribute_registry(mut self, attribute_registry: Arc<AttributeRegistry>) -> Self {
+        self.attribute_registry = attribute_registry;
+        self
+    }
+
+    /// Checks whether the options enable lenient mode.
+    #[must_use]
+    pub const fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+}
+
+/// A problem tolerated when parsing in lenient mode.
+#[derive(Debug, thiserror::Error)]
+#[error("{location}: {error}")]
+pub struct Warning {
+    /// Where the problem is found, e.g., `method_info` or `attribute StackMapTable`.
+    pub location: String,
+    /// The error that would abort the parse in strict mode.
+    pub error: Error,
+}
+
+impl Context {
+    /// Records `error` as a [`Warning`] in lenient mode, or returns it otherwise.
+    pub(crate) fn tolerate(&self, location: impl Into<String>, error: Error) -> Result<(), Error> {
+        let Some(warnings) = &self.warnings else {
+            return Err(error);
+        };
+        let warning = Warning {
+            location: location.into(),
+            error,
+        };
+        warnings
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .push(warning);
+        Ok(())
+    }
+
+    /// Parses `bits` as flags, where unknown bits are tolerated in lenient mode.
+    pub(crate) fn flags<F>(&self, name: &'static str, bits: u16) -> Result<F, Error>
+    where
+        F: bitflags::Flags<Bits = u16>,
+    {
+        if let Some(flags) = F::from_bits(bits) {
+            Ok(flags)
+        } else {
+            self.tolerate(name, Error::UnknownFlags(name, bits))?;
+            Ok(F::from_bits_truncate(bits))
+        }
+    }
+}
+
+pub(super) type Warnings = Arc<Mutex<Vec<Warning>>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{jvm::Class, tests::empty_class_with_version};
+
+    fn malformed_class_bytes() -> Vec<u8> {
+        let mut bytes = empty_class_with_version(52, 0).to_vec();
+        // Replaces `W` in the class name with a byte that is not valid UTF-8.
+        bytes[21] = 0xFF;
+        // Sets an access flag that is not defined for classes.
+        bytes[27] = 0x41;
+        bytes
+    }
+
+    #[test]
+    fn strict_mode_aborts() {
+        let options = ParsingOptions::new();
+        assert!(
+            Class::from_reader_with_options(malformed_class_bytes().as_slice(), &options).is_err()
+        );
+    }
+
+    #[test]
+    fn lenient_mode_collects_warnings() {
+        let options = ParsingOptions::new().with_lenient(true);
+        let (class, warnings) =
+            Class::from_reader_with_options(malformed_class_bytes().as_slice(), &options).unwrap();
+        assert_eq!(class.binary_name, "Hello\u{FFFD}orld");
+        assert_eq!(class.access_flags, crate::jvm::class::AccessFlags::PUBLIC);
+        assert!(matches!(warnings[0].error, Error::BrokenUTF8));
+        assert!(matches!(warnings[1].error, Error::UnknownFlags(_, 0x0041)));
+        assert_eq!(warnings.len(), 2);
+    }
+}
@@ -2,10 +2,10 @@ use std::io::{Read, Result};
 
 use crate::jvm::code::ProgramCounter;
 
-pub(super) trait ValueReaderExt: Read {
+pub(crate) trait ValueReaderExt: Read {
     fn read_value<T: ReadBytes>(&mut self) -> Result<T>;
 }
-pub(super) trait ReadBytes {
+pub(crate) trait ReadBytes {
     fn read_bytes<R: Read + ?Sized>(reader: &mut R) -> Result<Self>
     where
         Self: Sized;
@@ -47,6 +47,32 @@ macro_rules! impl_read_bytes_for {
 
 impl_read_bytes_for![u8, u16, u32, i8, i16, i32, i64, f32, f64];
 
+/// A reader that keeps track of the number of bytes read from the underlying reader.
+#[derive(Debug)]
+pub(crate) struct OffsetReader<R> {
+    inner: R,
+    offset: u64,
+}
+
+impl<R> OffsetReader<R> {
+    pub(crate) const fn new(inner: R) -> Self {
+        Self { inner, offset: 0 }
+    }
+
+    /// Returns the number of bytes read so far.
+    pub(crate) const fn offset(&self) -> u64 {
+        self.offset
+    }
+}
+
+impl<R: Read> Read for OffsetReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.offset += len as u64;
+        Ok(len)
+    }
+}
+
 /// Reads [len] bytes and advances the reader by [`len`] bytes.
 pub(super) fn read_byte_chunk<R>(reader: &mut R, len: usize) -> Result<Vec<u8>>
 where
@@ -67,7 +93,7 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::ValueReaderExt;
+    use super::{OffsetReader, ValueReaderExt};
     use std::io::ErrorKind::UnexpectedEof;
 
     #[test]
@@ -190,4 +216,14 @@ mod test {
         let err = super::read_byte_chunk(&mut reader, 3).unwrap_err();
         assert_eq!(err.kind(), UnexpectedEof);
     }
+
+    #[test]
+    fn offset_reader_counts_bytes() {
+        let mut reader = OffsetReader::new([0x01, 0x02, 0x03].as_slice());
+        let _: u16 = reader.read_value().unwrap();
+        assert_eq!(reader.offset(), 2);
+        let result: std::io::Result<u16> = reader.read_value();
+        assert_eq!(result.unwrap_err().kind(), UnexpectedEof);
+        assert_eq!(reader.offset(), 3);
+    }
 }
@@ -1,5 +1,7 @@
 //! References to JVM elements.
 
+use std::sync::Arc;
+
 use crate::types::{
     field_type::FieldType,
     method_descriptor::{MethodDescriptor, ReturnType},
@@ -12,14 +14,16 @@ use super::Method;
 #[display("{binary_name}")]
 pub struct ClassRef {
     /// The binary name of the class.
-    pub binary_name: String,
+    /// Cloning a [`ClassRef`] shares the name instead of copying it.
+    pub binary_name: Arc<str>,
 }
 
 impl ClassRef {
     /// Creates a new [`ClassRef`] from a binary name.
-    pub fn new<S: Into<String>>(binary_name: S) -> Self {
+    /// The name is [interned](crate::types::intern) when the `intern` feature is enabled.
+    pub fn new<S: AsRef<str>>(binary_name: S) -> Self {
         ClassRef {
-            binary_name: binary_name.into(),
+            binary_name: crate::types::intern(binary_name.as_ref()),
         }
     }
 }
@@ -31,7 +35,7 @@ pub struct FieldRef {
     /// A reference to the class that contains the field.
     pub owner: ClassRef,
     /// The name of the field.
-    pub name: String,
+    pub name: Arc<str>,
     /// The type of the field.
     pub field_type: FieldType,
 }
@@ -43,23 +47,45 @@ pub struct MethodRef {
     /// The reference to the class containing the method.
     pub owner: ClassRef,
     /// The name of the method.
-    pub name: String,
+    pub name: Arc<str>,
     /// The descriptor of the method.
     pub descriptor: MethodDescriptor,
 }
 
+impl FieldRef {
+    /// Creates a new [`FieldRef`].
+    /// The name is [interned](crate::types::intern) when the `intern` feature is enabled.
+    pub fn new<S: AsRef<str>>(owner: ClassRef, name: S, field_type: FieldType) -> Self {
+        FieldRef {
+            owner,
+            name: crate::types::intern(name.as_ref()),
+            field_type,
+        }
+    }
+}
+
 impl MethodRef {
+    /// Creates a new [`MethodRef`].
+    /// The name is [interned](crate::types::intern) when the `intern` feature is enabled.
+    pub fn new<S: AsRef<str>>(owner: ClassRef, name: S, descriptor: MethodDescriptor) -> Self {
+        MethodRef {
+            owner,
+            name: crate::types::intern(name.as_ref()),
+            descriptor,
+        }
+    }
+
     /// Checks if the method reference refers to a constructor.
     #[must_use]
     pub fn is_constructor(&self) -> bool {
-        self.name == Method::CONSTRUCTOR_NAME
+        &*self.name == Method::CONSTRUCTOR_NAME
             && matches!(self.descriptor.return_type, ReturnType::Void)
     }
 
     /// Checks if the method reference refers to a static initializer block.
     #[must_use]
     pub fn is_static_initializer_block(&self) -> bool {
-        self.name == Method::CLASS_INITIALIZER_NAME
+        &*self.name == Method::CLASS_INITIALIZER_NAME
             && self.descriptor.parameters_types.is_empty()
             && matches!(self.descriptor.return_type, ReturnType::Void)
     }
@@ -91,35 +117,40 @@ pub(crate) mod tests {
     }
 
     pub(crate) fn arb_field_ref() -> impl Strategy<Value = FieldRef> {
-        (arb_class_ref(), any::<String>(), arb_field_type()).prop_map(
-            |(owner, name, field_type)| FieldRef {
-                owner,
-                name,
-                field_type,
-            },
-        )
+        (arb_class_ref(), any::<String>(), arb_field_type())
+            .prop_map(|(owner, name, field_type)| FieldRef::new(owner, name, field_type))
+    }
+
+    #[test]
+    fn share_member_names() {
+        let owner = ClassRef::new("org/mokapot/Test");
+        let field = FieldRef::new(owner.clone(), String::from("run"), "I".parse().unwrap());
+        let method = MethodRef::new(owner, "run", "()V".parse().unwrap());
+        assert_eq!(field.name, method.name);
+        #[cfg(feature = "intern")]
+        assert!(Arc::ptr_eq(&field.name, &method.name));
     }
 
     proptest! {
 
         #[test]
         fn test_is_constructor(class_name in arb_identifier()) {
-            let method = MethodRef {
-                owner: ClassRef::new(class_name),
-                name: Method::CONSTRUCTOR_NAME.to_string(),
-                descriptor: "()V".parse().unwrap(),
-            };
+            let method = MethodRef::new(
+                ClassRef::new(class_name),
+                Method::CONSTRUCTOR_NAME,
+                "()V".parse().unwrap(),
+            );
 
             assert!(method.is_constructor());
         }
 
         #[test]
         fn test_is_static_initializer_bolck(class_name in arb_identifier()) {
-            let method = MethodRef {
-                owner: ClassRef::new(class_name),
-                name: Method::CLASS_INITIALIZER_NAME.to_string(),
-                descriptor: "()V".parse().unwrap(),
-            };
+            let method = MethodRef::new(
+                ClassRef::new(class_name),
+                Method::CLASS_INITIALIZER_NAME,
+                "()V".parse().unwrap(),
+            );
 
             assert!(method.is_static_initializer_block());
         }
@@ -0,0 +1,730 @@
+//! Renaming of classes and members.
+//!
+//! A [`Remapper`] rewrites every reference to the renamed classes and members in a [`Class`],
+//! including the descriptors, the generic signatures, the annotations, the instructions, the
+//! bootstrap methods, and the inner class attributes.
+//! The members are identified by the classes declaring them, so a reference to an inherited
+//! member via a subclass is only renamed if the mapping also contains the member of the subclass.
+
+use std::collections::BTreeMap;
+
+use crate::macros::see_jvm_spec;
+
+use crate::types::{
+    field_type::FieldType,
+    method_descriptor::{MethodDescriptor, ReturnType},
+};
+
+use super::{
+    annotation::ElementValue,
+    class::{BootstrapMethod, DynamicConstant, MethodHandle},
+    code::{Instruction, MethodBody, VerificationType},
+    references::{ClassRef, FieldRef, MethodRef},
+    Annotation, Class, ConstantValue, Field, Method, TypeAnnotation,
+};
+
+/// A mapping of the names of classes and members.
+#[derive(Debug, Clone, Default)]
+pub struct Remapper {
+    classes: BTreeMap<String, String>,
+    fields: BTreeMap<FieldRef, String>,
+    methods: BTreeMap<MethodRef, String>,
+}
+
+impl Remapper {
+    /// Creates an empty mapping.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renames the class with the binary name `from` to `to`.
+    #[must_use]
+    pub fn with_class(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.classes.insert(from.into(), to.into());
+        self
+    }
+
+    /// Renames `field` to `name`.
+    /// `field` is referred to with the names before renaming.
+    #[must_use]
+    pub fn with_field(mut self, field: FieldRef, name: impl Into<String>) -> Self {
+        self.fields.insert(field, name.into());
+        self
+    }
+
+    /// Renames `method` to `name`.
+    /// `method` is referred to with the names before renaming.
+    #[must_use]
+    pub fn with_method(mut self, method: MethodRef, name: impl Into<String>) -> Self {
+        self.methods.insert(method, name.into());
+        self
+    }
+
+    /// Maps a class, which may also be an array class (e.g., `[Ljava/lang/String;`).
+    #[must_use]
+    pub fn map_class(&self, class: &ClassRef) -> ClassRef {
+        if class.binary_name.starts_with('[') {
+            return match class.binary_name.parse::<FieldType>() {
+                Ok(array_type) => ClassRef::new(self.map_type(&array_type).descriptor()),
+                Err(_) => class.clone(),
+            };
+        }
+        self.classes
+            .get(&*class.binary_name)
+            .map_or_else(|| class.clone(), ClassRef::new)
+    }
+
+    /// Maps the classes in a type.
+    #[must_use]
+    pub fn map_type(&self, field_type: &FieldType) -> FieldType {
+        match field_type {
+            FieldType::Base(_) => field_type.clone(),
+            FieldType::Object(class) => FieldType::Object(self.map_class(class)),
+            FieldType::Array(element_type) => self.map_type(element_type).into_array_type(),
+        }
+    }
+
+    /// Maps the classes in a return type.
+    #[must_use]
+    pub fn map_return_type(&self, return_type: &ReturnType) -> ReturnType {
+        match return_type {
+            ReturnType::Void => ReturnType::Void,
+            ReturnType::Some(it) => ReturnType::Some(self.map_type(it)),
+        }
+    }
+
+    /// Maps the classes in a method descriptor.
+    #[must_use]
+    pub fn map_descriptor(&self, descriptor: &MethodDescriptor) -> MethodDescriptor {
+        MethodDescriptor::new(
+            descriptor
+                .parameters_types
+                .iter()
+                .map(|it| self.map_type(it))
+                .collect::<Vec<_>>(),
+            self.map_return_type(&descriptor.return_type),
+        )
+    }
+
+    /// Maps a field reference.
+    #[must_use]
+    pub fn map_field(&self, field: &FieldRef) -> FieldRef {
+        FieldRef::new(
+            self.map_class(&field.owner),
+            self.fields.get(field).map_or(&*field.name, String::as_str),
+            self.map_type(&field.field_type),
+        )
+    }
+
+    /// Maps a method reference.
+    #[must_use]
+    pub fn map_method(&self, method: &MethodRef) -> MethodRef {
+        MethodRef::new(
+            self.map_class(&method.owner),
+            self.methods
+                .get(method)
+                .map_or(&*method.name, String::as_str),
+            self.map_descriptor(&method.descriptor),
+        )
+    }
+
+    /// Maps the classes in a generic signature of a class, a method, or a field.
+    /// Malformed signatures are returned unchanged.
+    #[must_use]
+    pub fn map_signature(&self, signature: &str) -> String {
+        let mut mapper = SignatureMapper {
+            remapper: self,
+            remaining: signature,
+            output: String::with_capacity(signature.len()),
+        };
+        match mapper.signature() {
+            Some(()) if mapper.remaining.is_empty() => mapper.output,
+            _ => signature.to_owned(),
+        }
+    }
+
+    /// Maps a method handle.
+    #[must_use]
+    pub fn map_method_handle(&self, handle: &MethodHandle) -> MethodHandle {
+        match handle {
+            MethodHandle::RefGetField(it) => MethodHandle::RefGetField(self.map_field(it)),
+            MethodHandle::RefGetStatic(it) => MethodHandle::RefGetStatic(self.map_field(it)),
+            MethodHandle::RefPutField(it) => MethodHandle::RefPutField(self.map_field(it)),
+            MethodHandle::RefPutStatic(it) => MethodHandle::RefPutStatic(self.map_field(it)),
+            MethodHandle::RefInvokeVirtual(it) => {
+                MethodHandle::RefInvokeVirtual(self.map_method(it))
+            }
+            MethodHandle::RefInvokeStatic(it) => MethodHandle::RefInvokeStatic(self.map_method(it)),
+            MethodHandle::RefInvokeSpecial(it) => {
+                MethodHandle::RefInvokeSpecial(self.map_method(it))
+            }
+            MethodHandle::RefNewInvokeSpecial(it) => {
+                MethodHandle::RefNewInvokeSpecial(self.map_method(it))
+            }
+            MethodHandle::RefInvokeInterface(it) => {
+                MethodHandle::RefInvokeInterface(self.map_method(it))
+            }
+        }
+    }
+
+    /// Maps the references in the method handle and the arguments of a bootstrap method.
+    #[must_use]
+    pub fn map_bootstrap_method(&self, bootstrap_method: &BootstrapMethod) -> BootstrapMethod {
+        BootstrapMethod {
+            method: self.map_method_handle(&bootstrap_method.method),
+            arguments: bootstrap_method
+                .arguments
+                .iter()
+                .map(|it| self.map_constant(it))
+                .collect(),
+        }
+    }
+
+    /// Maps the references in a constant value.
+    #[must_use]
+    pub fn map_constant(&self, constant: &ConstantValue) -> ConstantValue {
+        match constant {
+            ConstantValue::Class(it) => ConstantValue::Class(self.map_class(it)),
+            ConstantValue::Handle(it) => ConstantValue::Handle(self.map_method_handle(it)),
+            ConstantValue::MethodType(it) => ConstantValue::MethodType(self.map_descriptor(it)),
+            ConstantValue::Dynamic(it) => ConstantValue::Dynamic(Box::new(DynamicConstant {
+                bootstrap: self.map_bootstrap_method(&it.bootstrap),
+                name: it.name.clone(),
+                descriptor: self.map_type(&it.descriptor),
+            })),
+            it => it.clone(),
+        }
+    }
+
+    /// Maps the types in an annotation.
+    /// The names of the elements are kept unchanged.
+    #[must_use]
+    pub fn map_annotation(&self, annotation: &Annotation) -> Annotation {
+        Annotation {
+            annotation_type: self.map_type(&annotation.annotation_type),
+            element_value_pairs: self.map_element_value_pairs(&annotation.element_value_pairs),
+        }
+    }
+
+    fn map_type_annotation(&self, annotation: &TypeAnnotation) -> TypeAnnotation {
+        TypeAnnotation {
+            annotation_type: self.map_type(&annotation.annotation_type),
+            element_value_pairs: self.map_element_value_pairs(&annotation.element_value_pairs),
+            ..annotation.clone()
+        }
+    }
+
+    fn map_element_value_pairs(
+        &self,
+        pairs: &[(String, ElementValue)],
+    ) -> Vec<(String, ElementValue)> {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), self.map_element_value(value)))
+            .collect()
+    }
+
+    fn map_element_value(&self, value: &ElementValue) -> ElementValue {
+        match value {
+            ElementValue::EnumConstant {
+                enum_type_name,
+                const_name,
+            } => {
+                let enum_type_name = match enum_type_name.parse::<FieldType>() {
+                    Ok(enum_type) => self.map_type(&enum_type).descriptor(),
+                    Err(_) => enum_type_name.clone(),
+                };
+                ElementValue::EnumConstant {
+                    enum_type_name,
+                    const_name: const_name.clone(),
+                }
+            }
+            ElementValue::Class { return_descriptor } => ElementValue::Class {
+                return_descriptor: self.map_return_type(return_descriptor),
+            },
+            ElementValue::AnnotationInterface(it) => {
+                ElementValue::AnnotationInterface(self.map_annotation(it))
+            }
+            ElementValue::Array(values) => {
+                ElementValue::Array(values.iter().map(|it| self.map_element_value(it)).collect())
+            }
+            ElementValue::Primitive(..) | ElementValue::String(_) => value.clone(),
+        }
+    }
+
+    fn map_annotations(&self, annotations: &mut [Annotation]) {
+        for annotation in annotations {
+            *annotation = self.map_annotation(annotation);
+        }
+    }
+
+    fn map_type_annotations(&self, annotations: &mut [TypeAnnotation]) {
+        for annotation in annotations {
+            *annotation = self.map_type_annotation(annotation);
+        }
+    }
+
+    /// Maps the references in an instruction.
+    #[must_use]
+    pub fn map_instruction(&self, instruction: &Instruction) -> Instruction {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        match instruction {
+            Ldc(it) => Ldc(self.map_constant(it)),
+            LdcW(it) => LdcW(self.map_constant(it)),
+            Ldc2W(it) => Ldc2W(self.map_constant(it)),
+            GetStatic(it) => GetStatic(self.map_field(it)),
+            PutStatic(it) => PutStatic(self.map_field(it)),
+            GetField(it) => GetField(self.map_field(it)),
+            PutField(it) => PutField(self.map_field(it)),
+            InvokeVirtual(it) => InvokeVirtual(self.map_method(it)),
+            InvokeSpecial(it) => InvokeSpecial(self.map_method(it)),
+            InvokeStatic(it) => InvokeStatic(self.map_method(it)),
+            InvokeInterface(it, count) => InvokeInterface(self.map_method(it), *count),
+            InvokeDynamic {
+                bootstrap_method_index,
+                name,
+                descriptor,
+            } => InvokeDynamic {
+                bootstrap_method_index: *bootstrap_method_index,
+                name: name.clone(),
+                descriptor: self.map_descriptor(descriptor),
+            },
+            New(it) => New(self.map_class(it)),
+            ANewArray(it) => ANewArray(self.map_class(it)),
+            CheckCast(it) => CheckCast(self.map_type(it)),
+            InstanceOf(it) => InstanceOf(self.map_type(it)),
+            MultiANewArray(it, dimensions) => MultiANewArray(self.map_type(it), *dimensions),
+            it => it.clone(),
+        }
+    }
+
+    /// Renames the class and its members, and rewrites all the references in it.
+    #[must_use]
+    pub fn remap_class(&self, mut class: Class) -> Class {
+        let owner = ClassRef::new(&class.binary_name);
+        class.binary_name = self.map_class(&owner).binary_name.to_string();
+        class.super_class = class.super_class.map(|it| self.map_class(&it));
+        self.map_classes(&mut class.interfaces);
+        class.fields = class
+            .fields
+            .into_iter()
+            .map(|it| self.remap_field(it))
+            .collect();
+        class.methods = class
+            .methods
+            .into_iter()
+            .map(|it| self.remap_method(it))
+            .collect();
+        for inner_class in &mut class.inner_classes {
+            let original_outer = inner_class.outer_class.clone();
+            let mapped_inner = self.map_class(&inner_class.inner_class);
+            inner_class.outer_class = original_outer.as_ref().map(|it| self.map_class(it));
+            if mapped_inner != inner_class.inner_class {
+                if let (Some(outer), Some(_)) = (&inner_class.outer_class, &inner_class.inner_name)
+                {
+                    let simple_name = mapped_inner
+                        .binary_name
+                        .strip_prefix(&*outer.binary_name)
+                        .and_then(|it| it.strip_prefix('$'));
+                    if let Some(simple_name) = simple_name {
+                        inner_class.inner_name = Some(simple_name.to_owned());
+                    }
+                }
+            }
+            inner_class.inner_class = mapped_inner;
+        }
+        if let Some(enclosing_method) = class.enclosing_method.as_mut() {
+            if let Some((name, descriptor)) = enclosing_method.method_name_and_desc.as_mut() {
+                let method = self.map_method(&MethodRef::new(
+                    enclosing_method.class.clone(),
+                    &name,
+                    descriptor.clone(),
+                ));
+                *name = method.name.to_string();
+                *descriptor = method.descriptor;
+            }
+            enclosing_method.class = self.map_class(&enclosing_method.class);
+        }
+        self.map_annotations(&mut class.runtime_visible_annotations);
+        self.map_annotations(&mut class.runtime_invisible_annotations);
+        self.map_type_annotations(&mut class.runtime_visible_type_annotations);
+        self.map_type_annotations(&mut class.runtime_invisible_type_annotations);
+        for bootstrap_method in &mut class.bootstrap_methods {
+            *bootstrap_method = self.map_bootstrap_method(bootstrap_method);
+        }
+        if let Some(module) = class.module.as_mut() {
+            self.map_classes(&mut module.uses);
+            for provide in &mut module.provides {
+                provide.service = self.map_class(&provide.service);
+                self.map_classes(&mut provide.with);
+            }
+        }
+        class.module_main_class = class.module_main_class.map(|it| self.map_class(&it));
+        class.nest_host = class.nest_host.map(|it| self.map_class(&it));
+        self.map_classes(&mut class.nest_members);
+        self.map_classes(&mut class.permitted_subclasses);
+        class.signature = class.signature.map(|it| self.map_signature(&it));
+        for component in class.record.iter_mut().flatten() {
+            let field = self.map_field(&FieldRef::new(
+                owner.clone(),
+                &component.name,
+                component.component_type.clone(),
+            ));
+            component.name = field.name.to_string();
+            component.component_type = field.field_type;
+            component.signature = component
+                .signature
+                .as_ref()
+                .map(|it| self.map_signature(it));
+            self.map_annotations(&mut component.runtime_visible_annotations);
+            self.map_annotations(&mut component.runtime_invisible_annotations);
+            self.map_type_annotations(&mut component.runtime_visible_type_annotations);
+            self.map_type_annotations(&mut component.runtime_invisible_type_annotations);
+        }
+        class
+    }
+
+    fn map_classes(&self, classes: &mut [ClassRef]) {
+        for class in classes {
+            *class = self.map_class(class);
+        }
+    }
+
+    fn remap_field(&self, mut field: Field) -> Field {
+        let mapped = self.map_field(&field.as_ref());
+        field.owner = mapped.owner;
+        field.name = mapped.name.to_string();
+        field.field_type = mapped.field_type;
+        field.constant_value = field.constant_value.map(|it| self.map_constant(&it));
+        field.signature = field.signature.map(|it| self.map_signature(&it));
+        self.map_annotations(&mut field.runtime_visible_annotations);
+        self.map_annotations(&mut field.runtime_invisible_annotations);
+        self.map_type_annotations(&mut field.runtime_visible_type_annotations);
+        self.map_type_annotations(&mut field.runtime_invisible_type_annotations);
+        field
+    }
+
+    fn remap_method(&self, mut method: Method) -> Method {
+        let mapped = self.map_method(&method.as_ref());
+        method.owner = mapped.owner;
+        method.name = mapped.name.to_string();
+        method.descriptor = mapped.descriptor;
+        self.map_classes(&mut method.exceptions);
+        method.signature = method.signature.map(|it| self.map_signature(&it));
+        self.map_annotations(&mut method.runtime_visible_annotations);
+        self.map_annotations(&mut method.runtime_invisible_annotations);
+        self.map_type_annotations(&mut method.runtime_visible_type_annotations);
+        self.map_type_annotations(&mut method.runtime_invisible_type_annotations);
+        for annotations in method
+            .runtime_visible_parameter_annotations
+            .iter_mut()
+            .chain(&mut method.runtime_invisible_parameter_annotations)
+        {
+            self.map_annotations(annotations);
+        }
+        method.annotation_default = method
+            .annotation_default
+            .map(|it| self.map_element_value(&it));
+        if let Some(body) = method.body.as_mut() {
+            self.remap_body(body);
+        }
+        method
+    }
+
+    fn remap_body(&self, body: &mut MethodBody) {
+        body.instructions = body
+            .instructions
+            .iter()
+            .map(|(pc, it)| (*pc, self.map_instruction(it)))
+            .collect::<BTreeMap<_, _>>()
+            .into();
+        for entry in &mut body.exception_table {
+            entry.catch_type = entry.catch_type.as_ref().map(|it| self.map_class(it));
+        }
+        if let Some(table) = body.local_variable_table.take() {
+            body.local_variable_table = Some(
+                table
+                    .into_iter()
+                    .map(|(id, mut entry)| {
+                        entry.var_type = entry.var_type.map(|it| self.map_type(&it));
+                        entry.signature = entry.signature.map(|it| self.map_signature(&it));
+                        (id, entry)
+                    })
+                    .collect(),
+            );
+        }
+        for frame in body.stack_map_table.iter_mut().flatten() {
+            for verification_type in frame.verification_types_mut() {
+                if let VerificationType::ObjectVariable(class) = verification_type {
+                    *class = self.map_class(class);
+                }
+            }
+        }
+        self.map_type_annotations(&mut body.runtime_visible_type_annotations);
+        self.map_type_annotations(&mut body.runtime_invisible_type_annotations);
+    }
+}
+
+/// Maps the class names in a generic signature.
+#[doc = see_jvm_spec!(4, 7, 9, 1)]
+struct SignatureMapper<'a> {
+    remapper: &'a Remapper,
+    remaining: &'a str,
+    output: String,
+}
+
+impl<'a> SignatureMapper<'a> {
+    fn peek(&self) -> Option<char> {
+        self.remaining.chars().next()
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        let rest = self.remaining.strip_prefix(expected)?;
+        self.output.push(expected);
+        self.remaining = rest;
+        Some(())
+    }
+
+    /// Takes the characters until one of `delimiters`.
+    fn identifier(&mut self, delimiters: &[char]) -> Option<&'a str> {
+        let end = self.remaining.find(delimiters)?;
+        let (identifier, rest) = self.remaining.split_at(end);
+        self.remaining = rest;
+        (!identifier.is_empty()).then_some(identifier)
+    }
+
+    fn signature(&mut self) -> Option<()> {
+        if self.peek() == Some('<') {
+            self.type_parameters()?;
+        }
+        if self.peek() == Some('(') {
+            self.expect('(')?;
+            while self.peek() != Some(')') {
+                self.java_type()?;
+            }
+            self.expect(')')?;
+            if self.peek() == Some('V') {
+                self.expect('V')?;
+            } else {
+                self.java_type()?;
+            }
+            while self.peek() == Some('^') {
+                self.expect('^')?;
+                self.java_type()?;
+            }
+        } else {
+            while !self.remaining.is_empty() {
+                self.java_type()?;
+            }
+        }
+        Some(())
+    }
+
+    fn type_parameters(&mut self) -> Option<()> {
+        self.expect('<')?;
+        while self.peek() != Some('>') {
+            let name = self.identifier(&[':'])?;
+            self.output.push_str(name);
+            self.expect(':')?;
+            // The class bound may be absent.
+            if !matches!(self.peek(), Some(':' | '>')) && !self.is_type_parameter_start() {
+                self.java_type()?;
+            }
+            while self.peek() == Some(':') {
+                self.expect(':')?;
+                self.java_type()?;
+            }
+        }
+        self.expect('>')
+    }
+
+    /// Checks if the remaining input starts with the next type parameter, i.e., an identifier
+    /// followed by `:`, rather than a class bound.
+    fn is_type_parameter_start(&self) -> bool {
+        match self.peek() {
+            Some('L' | 'T' | '[') => {
+                let end = self.remaining.find([':', ';', '<', '>']);
+                end.is_some_and(|it| self.remaining[it..].starts_with(':'))
+            }
+            _ => true,
+        }
+    }
+
+    fn java_type(&mut self) -> Option<()> {
+        match self.peek()? {
+            'L' => self.class_type(),
+            'T' => {
+                self.expect('T')?;
+                let name = self.identifier(&[';'])?;
+                self.output.push_str(name);
+                self.expect(';')
+            }
+            '[' => {
+                self.expect('[')?;
+                self.java_type()
+            }
+            it @ ('B' | 'C' | 'D' | 'F' | 'I' | 'J' | 'S' | 'Z') => self.expect(it),
+            _ => None,
+        }
+    }
+
+    fn class_type(&mut self) -> Option<()> {
+        self.expect('L')?;
+        let mut original = self.identifier(&['<', '.', ';'])?.to_owned();
+        let mut mapped = self
+            .remapper
+            .map_class(&ClassRef::new(&original))
+            .binary_name;
+        self.output.push_str(&mapped);
+        if self.peek() == Some('<') {
+            self.type_arguments()?;
+        }
+        while self.peek() == Some('.') {
+            self.expect('.')?;
+            let simple_name = self.identifier(&['<', '.', ';'])?.to_owned();
+            original = format!("{original}${simple_name}");
+            let mapped_inner = self
+                .remapper
+                .map_class(&ClassRef::new(&original))
+                .binary_name;
+            let mapped_simple_name = mapped_inner
+                .strip_prefix(&*mapped)
+                .and_then(|it| it.strip_prefix('$'))
+                .unwrap_or(&simple_name);
+            self.output.push_str(mapped_simple_name);
+            mapped = mapped_inner;
+            if self.peek() == Some('<') {
+                self.type_arguments()?;
+            }
+        }
+        self.expect(';')
+    }
+
+    fn type_arguments(&mut self) -> Option<()> {
+        self.expect('<')?;
+        while self.peek() != Some('>') {
+            match self.peek()? {
+                '*' => self.expect('*')?,
+                it @ ('+' | '-') => {
+                    self.expect(it)?;
+                    self.java_type()?;
+                }
+                _ => self.java_type()?,
+            }
+        }
+        self.expect('>')
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    fn remapper() -> Remapper {
+        Remapper::new()
+            .with_class("a/A", "com/example/Main")
+            .with_class("a/B", "com/example/Helper")
+            .with_class("a/B$C", "com/example/Helper$Inner")
+            .with_field(
+                FieldRef::new(ClassRef::new("a/A"), "a", "La/B;".parse().unwrap()),
+                "helper",
+            )
+            .with_method(
+                MethodRef::new(ClassRef::new("a/B"), "b", "([La/B;)La/B;".parse().unwrap()),
+                "compute",
+            )
+    }
+
+    #[test]
+    fn signatures() {
+        let remapper = remapper();
+        assert_eq!(
+            remapper.map_signature("<T:La/B;:Ljava/lang/Comparable<TT;>;>Ljava/lang/Object;"),
+            "<T:Lcom/example/Helper;:Ljava/lang/Comparable<TT;>;>Ljava/lang/Object;"
+        );
+        assert_eq!(
+            remapper.map_signature("<T::La/A;>(Ljava/util/List<+La/B;>;TT;)La/B<*>.C<[La/A;>;^TE;"),
+            "<T::Lcom/example/Main;>(Ljava/util/List<+Lcom/example/Helper;>;TT;)\
+             Lcom/example/Helper<*>.Inner<[Lcom/example/Main;>;^TE;"
+        );
+        assert_eq!(remapper.map_signature("La/A"), "La/A");
+        assert_eq!(
+            remapper.map_class(&ClassRef::new("[[La/A;")),
+            ClassRef::new("[[Lcom/example/Main;")
+        );
+    }
+
+    #[test]
+    fn remap_class() {
+        let class = jasmin::read(
+            "
+            .bytecode 52.0
+            .class public a/A
+            .super java/lang/Object
+            .implements a/B
+            .field private a La/B;
+            .method public run(La/B;)[La/B;
+                .limit stack 3
+                .limit locals 2
+                aload_0
+                getfield a/A/a La/B;
+                iconst_1
+                anewarray a/B
+                invokevirtual a/B/b([La/B;)La/B;
+                checkcast [La/B;
+                areturn
+            .end method
+            ",
+        )
+        .unwrap();
+        let remapped = remapper().remap_class(class);
+        assert_eq!(remapped.binary_name, "com/example/Main");
+        assert_eq!(
+            remapped.interfaces,
+            vec![ClassRef::new("com/example/Helper")]
+        );
+        assert_eq!(remapped.fields[0].name, "helper");
+        assert_eq!(remapped.fields[0].owner, ClassRef::new("com/example/Main"));
+        let method = &remapped.methods[0];
+        assert_eq!(
+            method.descriptor.descriptor(),
+            "(Lcom/example/Helper;)[Lcom/example/Helper;"
+        );
+        let instructions: Vec<_> = method
+            .body
+            .as_ref()
+            .unwrap()
+            .instructions
+            .iter()
+            .map(|(_, it)| it.clone())
+            .collect();
+        let expected_field = FieldRef::new(
+            ClassRef::new("com/example/Main"),
+            "helper",
+            "Lcom/example/Helper;".parse().unwrap(),
+        );
+        let expected_method = MethodRef::new(
+            ClassRef::new("com/example/Helper"),
+            "compute",
+            "([Lcom/example/Helper;)Lcom/example/Helper;"
+                .parse()
+                .unwrap(),
+        );
+        assert_eq!(
+            instructions,
+            vec![
+                Instruction::ALoad0,
+                Instruction::GetField(expected_field),
+                Instruction::IConst1,
+                Instruction::ANewArray(ClassRef::new("com/example/Helper")),
+                Instruction::InvokeVirtual(expected_method),
+                Instruction::CheckCast("[Lcom/example/Helper;".parse().unwrap()),
+                Instruction::AReturn,
+            ]
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::BTreeSet;
+
+use crate::{
+    jvm::{
+        code::{
+            Instruction, MethodBody, ProgramCounter, StackMapFrame, VerificationType,
+            WideInstruction,
+        },
+        method, Class, ConstantValue, Method,
+    },
+    macros::see_jvm_spec,
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+use super::{Diagnostic, DiagnosticKind, Location};
+
+/// Verifies the static constraints on the instructions and the stack map table of a method.
+#[doc = see_jvm_spec!(4, 9, 1)]
+pub(super) fn verify_body(class: &Class, method: &Method, body: &MethodBody) -> Vec<Diagnostic> {
+    let method_ref = method.as_ref();
+    let mut diagnostics = Vec::new();
+    let mut report = |pc: Option<ProgramCounter>, kind| {
+        let location = match pc {
+            Some(pc) => Location::Instruction(method_ref.clone(), pc),
+            None => Location::Method(method_ref.clone()),
+        };
+        diagnostics.push(Diagnostic { location, kind });
+    };
+    if body.instructions.is_empty() {
+        report(None, DiagnosticKind::EmptyCode);
+        return diagnostics;
+    }
+    let is_instruction = |pc: &ProgramCounter| body.instruction_at(*pc).is_some();
+
+    let mut jump_targets = BTreeSet::new();
+    for (pc, insn) in &body.instructions {
+        for target in insn.branch_targets() {
+            if is_instruction(&target) {
+                jump_targets.insert(target);
+            } else {
+                report(Some(*pc), DiagnosticKind::InvalidBranchTarget(target));
+            }
+        }
+        if let Some(index) = local_slots(insn).filter(|it| *it >= body.max_locals) {
+            report(
+                Some(*pc),
+                DiagnosticKind::LocalOutOfBounds {
+                    index,
+                    max_locals: body.max_locals,
+                },
+            );
+        }
+        if let Some(reason) = illegal_operand(class, insn) {
+            report(Some(*pc), DiagnosticKind::IllegalOperand(reason));
+        }
+    }
+
+    for entry in &body.exception_table {
+        let (start, end) = (entry.covered_pc.start(), entry.covered_pc.end());
+        if !is_instruction(start) {
+            report(
+                None,
+                DiagnosticKind::InvalidExceptionHandler(
+                    "start_pc is not the start of an instruction",
+                ),
+            );
+        }
+        if start >= end {
+            report(
+                None,
+                DiagnosticKind::InvalidExceptionHandler("start_pc must be less than end_pc"),
+            );
+        }
+        if is_instruction(&entry.handler_pc) {
+            jump_targets.insert(entry.handler_pc);
+        } else {
+            report(
+                None,
+                DiagnosticKind::InvalidExceptionHandler(
+                    "handler_pc is not the start of an instruction",
+                ),
+            );
+        }
+    }
+
+    let frames = frame_locations(method, body, &mut report);
+    // Class files of version 50 may fall back to verification by type inference.
+    if class.version.major() >= 51 {
+        for target in jump_targets.difference(&frames) {
+            report(Some(*target), DiagnosticKind::MissingStackMapFrame(*target));
+        }
+    }
+    for frame in frames.iter().filter(|it| !is_instruction(it)) {
+        report(None, DiagnosticKind::MisplacedStackMapFrame(*frame));
+    }
+    diagnostics
+}
+
+/// Computes the locations of the stack map frames, and reports the frames that do not fit in
+/// `max_locals` or `max_stack`.
+#[doc = see_jvm_spec!(4, 7, 4)]
+fn frame_locations(
+    method: &Method,
+    body: &MethodBody,
+    report: &mut impl FnMut(Option<ProgramCounter>, DiagnosticKind),
+) -> BTreeSet<ProgramCounter> {
+    let slots = |it: &VerificationType| match it {
+        VerificationType::LongVariable | VerificationType::DoubleVariable => 2,
+        _ => 1,
+    };
+    // The sizes of the locals in the implicit initial frame.
+    let mut locals: Vec<u32> = (!method.access_flags.contains(method::AccessFlags::STATIC))
+        .then_some(1)
+        .into_iter()
+        .chain(
+            method
+                .descriptor
+                .parameters_types
+                .iter()
+                .map(|it| match it {
+                    FieldType::Base(PrimitiveType::Long | PrimitiveType::Double) => 2,
+                    _ => 1,
+                }),
+        )
+        .collect();
+    let mut locations = BTreeSet::new();
+    let mut previous: Option<u16> = None;
+    for frame in body.stack_map_table.iter().flatten() {
+        let (offset_delta, stack) = match frame {
+            StackMapFrame::SameFrame { offset_delta } => (offset_delta, 0),
+            StackMapFrame::SameLocals1StackItemFrame {
+                offset_delta,
+                stack,
+            } => (offset_delta, slots(stack)),
+            StackMapFrame::ChopFrame {
+                offset_delta,
+                chop_count,
+            } => {
+                locals.truncate(locals.len().saturating_sub(usize::from(*chop_count)));
+                (offset_delta, 0)
+            }
+            StackMapFrame::AppendFrame {
+                offset_delta,
+                locals: appended,
+            } => {
+                locals.extend(appended.iter().map(slots));
+                (offset_delta, 0)
+            }
+            StackMapFrame::FullFrame {
+                offset_delta,
+                locals: full,
+                stack,
+            } => {
+                locals = full.iter().map(slots).collect();
+                (offset_delta, stack.iter().map(slots).sum())
+            }
+        };
+        // Each frame after the first one is at least one byte after the previous frame.
+        let offset = match previous {
+            Some(previous) => previous.saturating_add(*offset_delta).saturating_add(1),
+            None => *offset_delta,
+        };
+        previous = Some(offset);
+        let pc = ProgramCounter::from(offset);
+        locations.insert(pc);
+        if locals.iter().sum::<u32>() > u32::from(body.max_locals)
+            || stack > u32::from(body.max_stack)
+        {
+            report(Some(pc), DiagnosticKind::StackMapFrameOverflow(pc));
+        }
+    }
+    locations
+}
+
+/// Returns the largest index of the local variables accessed by `insn`.
+fn local_slots(insn: &Instruction) -> Option<u16> {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let index = match insn {
+        ILoad(idx)
+        | FLoad(idx)
+        | ALoad(idx)
+        | IStore(idx)
+        | FStore(idx)
+        | AStore(idx)
+        | Ret(idx)
+        | IInc(idx, _) => u16::from(*idx),
+        // Values of type `long` and `double` occupy two local variables.
+        LLoad(idx) | DLoad(idx) | LStore(idx) | DStore(idx) => u16::from(*idx) + 1,
+        ILoad0 | FLoad0 | ALoad0 | IStore0 | FStore0 | AStore0 => 0,
+        ILoad1 | FLoad1 | ALoad1 | IStore1 | FStore1 | AStore1 | LLoad0 | DLoad0 | LStore0
+        | DStore0 => 1,
+        ILoad2 | FLoad2 | ALoad2 | IStore2 | FStore2 | AStore2 | LLoad1 | DLoad1 | LStore1
+        | DStore1 => 2,
+        ILoad3 | FLoad3 | ALoad3 | IStore3 | FStore3 | AStore3 | LLoad2 | DLoad2 | LStore2
+        | DStore2 => 3,
+        LLoad3 | DLoad3 | LStore3 | DStore3 => 4,
+        Wide(
+            WideInstruction::ILoad(idx)
+            | WideInstruction::FLoad(idx)
+            | WideInstruction::ALoad(idx)
+            | WideInstruction::IStore(idx)
+            | WideInstruction::FStore(idx)
+            | WideInstruction::AStore(idx)
+            | WideInstruction::Ret(idx)
+            | WideInstruction::IInc(idx, _),
+        ) => *idx,
+        Wide(
+            WideInstruction::LLoad(idx)
+            | WideInstruction::DLoad(idx)
+            | WideInstruction::LStore(idx)
+            | WideInstruction::DStore(idx),
+        ) => idx.saturating_add(1),
+        _ => return None,
+    };
+    Some(index)
+}
+
+/// Checks the operands of `insn`, and returns the reason if they are illegal.
+fn illegal_operand(class: &Class, insn: &Instruction) -> Option<&'static str> {
+    #[allow(clippy::enum_glob_use)]
+    use Instruction::*;
+
+    let is_wide =
+        |it: &ConstantValue| matches!(it, ConstantValue::Long(_) | ConstantValue::Double(_));
+    match insn {
+        Ldc(value) | LdcW(value) if is_wide(value) => {
+            Some("long and double constants require ldc2_w")
+        }
+        Ldc2W(value) if !is_wide(value) => Some("ldc2_w only loads long and double constants"),
+        New(class_ref) if class_ref.binary_name.starts_with('[') => {
+            Some("new cannot create an array")
+        }
+        ANewArray(class_ref)
+            if class_ref
+                .binary_name
+                .chars()
+                .take_while(|it| *it == '[')
+                .count()
+                >= 255 =>
+        {
+            Some("anewarray cannot create an array of more than 255 dimensions")
+        }
+        MultiANewArray(_, 0) => Some("multianewarray requires at least one dimension"),
+        MultiANewArray(array_type, dimensions)
+            if array_dimensions(array_type) < usize::from(*dimensions) =>
+        {
+            Some("multianewarray has more dimensions than the array type")
+        }
+        InvokeVirtual(method) | InvokeStatic(method) | InvokeInterface(method, _)
+            if method.name.starts_with('<') =>
+        {
+            Some("initialization methods can only be invoked with invokespecial")
+        }
+        InvokeSpecial(method) if &*method.name == Method::CLASS_INITIALIZER_NAME => {
+            Some("class initialization methods cannot be invoked")
+        }
+        InvokeInterface(_, 0) => Some("the count of invokeinterface must not be zero"),
+        InvokeDynamic {
+            bootstrap_method_index,
+            ..
+        } if usize::from(*bootstrap_method_index) >= class.bootstrap_methods.len() => {
+            Some("the bootstrap method index is out of bounds")
+        }
+        _ => None,
+    }
+}
+
+fn array_dimensions(field_type: &FieldType) -> usize {
+    match field_type {
+        FieldType::Array(element_type) => 1 + array_dimensions(element_type),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::{code::InstructionList, references::ClassRef};
+
+    #[test]
+    fn invalid_operands() {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        let class = Class::default();
+        let method = Method {
+            access_flags: method::AccessFlags::STATIC,
+            descriptor: "()V".parse().unwrap(),
+            ..Default::default()
+        };
+        let body = MethodBody {
+            max_locals: 1,
+            max_stack: 1,
+            instructions: InstructionList::from([
+                (0.into(), ILoad1),
+                (1.into(), Goto(3.into())),
+                (4.into(), New(ClassRef::new("[I"))),
+                (7.into(), Return),
+            ]),
+            stack_map_table: Some(vec![StackMapFrame::SameFrame { offset_delta: 7 }]),
+            ..Default::default()
+        };
+        let kinds: Vec<_> = verify_body(&class, &method, &body)
+            .into_iter()
+            .map(|it| it.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiagnosticKind::LocalOutOfBounds {
+                    index: 1,
+                    max_locals: 1
+                },
+                DiagnosticKind::InvalidBranchTarget(3.into()),
+                DiagnosticKind::IllegalOperand("new cannot create an array"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,349 @@
+//! Verification of the static and structural constraints on class files.
+//!
+//! The parser accepts any class file it can decode, so a parsed [`Class`] may still violate
+//! constraints that the JVM checks when loading it, e.g., conflicting access flags or branches
+//! into the middle of an instruction.
+//! [`verify_class`] checks these constraints and reports every violation it finds as a
+//! [`Diagnostic`] instead of stopping at the first one.
+#![doc = see_jvm_spec!(4, 9)]
+
+use crate::macros::see_jvm_spec;
+
+use super::{
+    class,
+    code::ProgramCounter,
+    field, method,
+    references::{ClassRef, FieldRef, MethodRef},
+    Class, Field, Method,
+};
+
+mod code;
+
+/// A violation of a constraint found by [`verify_class`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{location}: {kind}")]
+pub struct Diagnostic {
+    /// Where the violation is found.
+    pub location: Location,
+    /// The constraint that is violated.
+    pub kind: DiagnosticKind,
+}
+
+/// The location of a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::Display)]
+pub enum Location {
+    /// A class.
+    #[display("class {_0}")]
+    Class(ClassRef),
+    /// A field.
+    #[display("field {_0}")]
+    Field(FieldRef),
+    /// A method.
+    #[display("method {_0}{}", _0.descriptor.descriptor())]
+    Method(MethodRef),
+    /// An instruction in the body of a method.
+    #[display("method {_0}{} at {_1}", _0.descriptor.descriptor())]
+    Instruction(MethodRef, ProgramCounter),
+}
+
+/// The kind of a [`Diagnostic`].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DiagnosticKind {
+    /// The access flags are not a legal combination.
+    #[error("Illegal access flags: {0}")]
+    IllegalAccessFlags(&'static str),
+    /// A class other than `java/lang/Object` has no super class.
+    #[error("The class has no super class")]
+    MissingSuperClass,
+    /// The super class of an interface is not `java/lang/Object`.
+    #[error("The super class of an interface must be java/lang/Object")]
+    InterfaceSuperClass,
+    /// An attribute appears where it is not allowed.
+    #[error("The {attribute} attribute is not allowed here: {reason}")]
+    MisplacedAttribute {
+        /// The name of the attribute.
+        attribute: &'static str,
+        /// Why the attribute is not allowed.
+        reason: &'static str,
+    },
+    /// A method that is neither `native` nor `abstract` has no `Code` attribute.
+    #[error("The method has no Code attribute")]
+    MissingCode,
+    /// The `Code` attribute has no instruction.
+    #[error("The Code attribute has no instruction")]
+    EmptyCode,
+    /// A branch targets a location that is not the start of an instruction.
+    #[error("The branch target {0} is not the start of an instruction")]
+    InvalidBranchTarget(ProgramCounter),
+    /// An entry in the exception table is malformed.
+    #[error("Invalid exception table entry: {0}")]
+    InvalidExceptionHandler(&'static str),
+    /// An instruction accesses a local variable beyond `max_locals`.
+    #[error("The local variable {index} is out of bounds (max_locals = {max_locals})")]
+    LocalOutOfBounds {
+        /// The index of the local variable.
+        index: u16,
+        /// The number of local variables of the method.
+        max_locals: u16,
+    },
+    /// An instruction has an operand that is not allowed.
+    #[error("Illegal operand: {0}")]
+    IllegalOperand(&'static str),
+    /// A class file of version 51 or above has no stack map frame at a branch target or an
+    /// exception handler.
+    #[error("There is no stack map frame at {0}")]
+    MissingStackMapFrame(ProgramCounter),
+    /// A stack map frame is not at the start of an instruction.
+    #[error("The stack map frame at {0} is not at the start of an instruction")]
+    MisplacedStackMapFrame(ProgramCounter),
+    /// A stack map frame has more locals or stack entries than the method allows.
+    #[error("The stack map frame at {0} exceeds max_locals or max_stack")]
+    StackMapFrameOverflow(ProgramCounter),
+}
+
+/// Verifies the static and structural constraints on `class` and its members.
+/// The following constraints are checked:
+/// - The access flags of the class, fields, and methods are legal combinations.
+/// - Only `java/lang/Object` has no super class, and interfaces extend `java/lang/Object`.
+/// - Attributes appear only where they are allowed, e.g., `Code` is absent on `abstract` and
+///   `native` methods only.
+/// - The operands of instructions are valid, e.g., branch targets, local variable indices and
+///   the dimensions of `multianewarray`.
+/// - The stack map table is consistent with the instructions.
+#[must_use]
+pub fn verify_class(class: &Class) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut report = |kind| {
+        diagnostics.push(Diagnostic {
+            location: Location::Class(class.as_ref()),
+            kind,
+        });
+    };
+    let flags = class.access_flags;
+    if flags.contains(class::AccessFlags::MODULE) {
+        if class.module.is_none() {
+            report(DiagnosticKind::IllegalAccessFlags(
+                "ACC_MODULE is set on a class without a Module attribute",
+            ));
+        }
+        return diagnostics;
+    }
+    if class.module.is_some() {
+        report(DiagnosticKind::MisplacedAttribute {
+            attribute: "Module",
+            reason: "only allowed in a class with ACC_MODULE",
+        });
+    }
+    if flags.contains(class::AccessFlags::INTERFACE) {
+        if !flags.contains(class::AccessFlags::ABSTRACT) {
+            report(DiagnosticKind::IllegalAccessFlags(
+                "an interface must be ACC_ABSTRACT",
+            ));
+        }
+        if flags.intersects(class::AccessFlags::FINAL | class::AccessFlags::ENUM) {
+            report(DiagnosticKind::IllegalAccessFlags(
+                "an interface must not be ACC_FINAL or ACC_ENUM",
+            ));
+        }
+        if class.super_class != Some(ClassRef::new("java/lang/Object")) {
+            report(DiagnosticKind::InterfaceSuperClass);
+        }
+    } else {
+        if flags.contains(class::AccessFlags::ANNOTATION) {
+            report(DiagnosticKind::IllegalAccessFlags(
+                "ACC_ANNOTATION requires ACC_INTERFACE",
+            ));
+        }
+        if flags.contains(class::AccessFlags::FINAL | class::AccessFlags::ABSTRACT) {
+            report(DiagnosticKind::IllegalAccessFlags(
+                "a class cannot be both ACC_FINAL and ACC_ABSTRACT",
+            ));
+        }
+    }
+    if class.super_class.is_none() && class.binary_name != "java/lang/Object" {
+        report(DiagnosticKind::MissingSuperClass);
+    }
+    if flags.contains(class::AccessFlags::FINAL) && !class.permitted_subclasses.is_empty() {
+        report(DiagnosticKind::MisplacedAttribute {
+            attribute: "PermittedSubclasses",
+            reason: "a final class cannot have permitted subclasses",
+        });
+    }
+    if !class.nest_members.is_empty() && class.nest_host.is_some() {
+        report(DiagnosticKind::MisplacedAttribute {
+            attribute: "NestMembers",
+            reason: "a class with a NestHost attribute cannot have nest members",
+        });
+    }
+
+    diagnostics.extend(class.fields.iter().flat_map(|it| verify_field(class, it)));
+    diagnostics.extend(class.methods.iter().flat_map(|it| verify_method(class, it)));
+    diagnostics
+}
+
+#[doc = see_jvm_spec!(4, 5)]
+fn verify_field(class: &Class, field: &Field) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut report = |reason| {
+        diagnostics.push(Diagnostic {
+            location: Location::Field(field.as_ref()),
+            kind: DiagnosticKind::IllegalAccessFlags(reason),
+        });
+    };
+    let flags = field.access_flags;
+    let visibility =
+        field::AccessFlags::PUBLIC | field::AccessFlags::PRIVATE | field::AccessFlags::PROTECTED;
+    if (flags & visibility).bits().count_ones() > 1 {
+        report("at most one of ACC_PUBLIC, ACC_PRIVATE, and ACC_PROTECTED is allowed");
+    }
+    if flags.contains(field::AccessFlags::FINAL | field::AccessFlags::VOLATILE) {
+        report("a field cannot be both ACC_FINAL and ACC_VOLATILE");
+    }
+    let constant =
+        field::AccessFlags::PUBLIC | field::AccessFlags::STATIC | field::AccessFlags::FINAL;
+    if class.is_interface() && !flags.contains(constant) {
+        report("a field of an interface must be ACC_PUBLIC, ACC_STATIC, and ACC_FINAL");
+    }
+    diagnostics
+}
+
+#[doc = see_jvm_spec!(4, 6)]
+fn verify_method(class: &Class, method: &Method) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let location = Location::Method(method.as_ref());
+    let mut report = |kind| {
+        diagnostics.push(Diagnostic {
+            location: location.clone(),
+            kind,
+        });
+    };
+    let flags = method.access_flags;
+    let major = class.version.major();
+    let visibility =
+        method::AccessFlags::PUBLIC | method::AccessFlags::PRIVATE | method::AccessFlags::PROTECTED;
+    if (flags & visibility).bits().count_ones() > 1 {
+        report(DiagnosticKind::IllegalAccessFlags(
+            "at most one of ACC_PUBLIC, ACC_PRIVATE, and ACC_PROTECTED is allowed",
+        ));
+    }
+    let mut not_abstract = method::AccessFlags::PRIVATE
+        | method::AccessFlags::STATIC
+        | method::AccessFlags::FINAL
+        | method::AccessFlags::SYNCHRONIZED
+        | method::AccessFlags::NATIVE;
+    // `ACC_STRICT` is only meaningful in class files of versions 46 through 60.
+    if (46..61).contains(&major) {
+        not_abstract |= method::AccessFlags::STRICT;
+    }
+    if flags.contains(method::AccessFlags::ABSTRACT) && flags.intersects(not_abstract) {
+        report(DiagnosticKind::IllegalAccessFlags(
+            "an abstract method cannot be private, static, final, synchronized, native, or strict",
+        ));
+    }
+    if class.is_interface() && !method.is_static_initializer_block() {
+        let required = method::AccessFlags::PUBLIC | method::AccessFlags::ABSTRACT;
+        if major < 52 && !flags.contains(required) {
+            report(DiagnosticKind::IllegalAccessFlags(
+                "a method of an interface must be ACC_PUBLIC and ACC_ABSTRACT before version 52",
+            ));
+        }
+        if flags.intersects(
+            method::AccessFlags::PROTECTED
+                | method::AccessFlags::FINAL
+                | method::AccessFlags::SYNCHRONIZED
+                | method::AccessFlags::NATIVE,
+        ) {
+            report(DiagnosticKind::IllegalAccessFlags(
+                "a method of an interface cannot be protected, final, synchronized, or native",
+            ));
+        }
+    }
+    if method.is_constructor()
+        && flags.intersects(
+            method::AccessFlags::STATIC
+                | method::AccessFlags::FINAL
+                | method::AccessFlags::SYNCHRONIZED
+                | method::AccessFlags::NATIVE
+                | method::AccessFlags::ABSTRACT,
+        )
+    {
+        report(DiagnosticKind::IllegalAccessFlags(
+            "an instance initialization method cannot be static, final, synchronized, native, or abstract",
+        ));
+    }
+    if method.is_static_initializer_block()
+        && major >= 51
+        && !flags.contains(method::AccessFlags::STATIC)
+    {
+        report(DiagnosticKind::IllegalAccessFlags(
+            "a class initialization method must be ACC_STATIC",
+        ));
+    }
+
+    let without_code =
+        flags.intersects(method::AccessFlags::ABSTRACT | method::AccessFlags::NATIVE);
+    match &method.body {
+        Some(_) if without_code => report(DiagnosticKind::MisplacedAttribute {
+            attribute: "Code",
+            reason: "not allowed on abstract or native methods",
+        }),
+        Some(body) => diagnostics.extend(code::verify_body(class, method, body)),
+        None if !without_code => report(DiagnosticKind::MissingCode),
+        None => {}
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::interop::jasmin;
+
+    #[test]
+    fn access_flags_and_attributes() {
+        let class = Class {
+            binary_name: "org/mokapot/Foo".to_owned(),
+            access_flags: class::AccessFlags::FINAL | class::AccessFlags::ABSTRACT,
+            super_class: None,
+            permitted_subclasses: vec![ClassRef::new("org/mokapot/Bar")],
+            methods: vec![Method {
+                access_flags: method::AccessFlags::ABSTRACT | method::AccessFlags::PRIVATE,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let kinds: Vec<_> = verify_class(&class).into_iter().map(|it| it.kind).collect();
+        assert!(matches!(kinds[0], DiagnosticKind::IllegalAccessFlags(_)));
+        assert_eq!(kinds[1], DiagnosticKind::MissingSuperClass);
+        assert!(matches!(
+            kinds[2],
+            DiagnosticKind::MisplacedAttribute {
+                attribute: "PermittedSubclasses",
+                ..
+            }
+        ));
+        assert!(matches!(kinds[3], DiagnosticKind::IllegalAccessFlags(_)));
+        assert_eq!(kinds.len(), 4);
+    }
+
+    #[test]
+    fn well_formed_class() {
+        let class = jasmin::read(
+            "
+            .bytecode 52.0
+            .class public Foo
+            .super java/lang/Object
+            .field private value I
+            .method public get()I
+                .limit stack 1
+                .limit locals 1
+                aload_0
+                getfield Foo/value I
+                ireturn
+            .end method
+            ",
+        )
+        .unwrap();
+        assert_eq!(verify_class(&class), Vec::new());
+    }
+}
@@ -0,0 +1,1173 @@
+//! Event-based traversal of classes in the style of the visitor API of `ObjectWeb` ASM.
+//!
+//! [`Class::accept`] walks a class and reports its structure to a [`ClassVisitor`], which may
+//! in turn return a [`FieldVisitor`] or a [`MethodVisitor`] for each member.
+//! Every callback has a default implementation that forwards the event to the visitor returned
+//! by `delegate`, so a transformation only overrides the events it changes and delegates the
+//! rest to the next visitor in the chain (e.g., a [`ClassWriterVisitor`]).
+
+use std::collections::BTreeMap;
+
+use crate::{
+    jvm::{
+        annotation::ElementValue,
+        class::{self, BootstrapMethod, EnclosingMethod, InnerClassInfo, RecordComponent},
+        code::{
+            CharacterRangeTableEntry, ExceptionTableEntry, Instruction, LineNumberTableEntry,
+            LocalVariableTable, MethodBody, ProgramCounter, StackMapFrame,
+        },
+        field,
+        method::{self, ParameterInfo},
+        parsing::CustomAttribute,
+        references::{ClassRef, PackageRef},
+        Annotation, Class, ConstantValue, Field, Method, Module, TypeAnnotation,
+    },
+    types::{field_type::FieldType, method_descriptor::MethodDescriptor},
+};
+
+/// Forwards an event to the delegate of a visitor, if any.
+macro_rules! forward {
+    ($self:ident.$event:ident($($arg:expr),*)) => {
+        if let Some(next) = $self.delegate() {
+            next.$event($($arg),*);
+        }
+    };
+}
+
+/// A visitor of the structure of a [`Class`].
+/// The events are reported in the order of the methods declared in this trait, and
+/// [`visit_end`](ClassVisitor::visit_end) is always the last one.
+#[allow(
+    unused_variables,
+    reason = "The default implementations ignore the arguments"
+)]
+pub trait ClassVisitor {
+    /// Returns the visitor to which the events are forwarded by default.
+    fn delegate(&mut self) -> Option<&mut dyn ClassVisitor> {
+        None
+    }
+
+    /// Visits the header of the class.
+    fn visit(
+        &mut self,
+        version: class::Version,
+        access_flags: class::AccessFlags,
+        binary_name: &str,
+        signature: Option<&class::Signature>,
+        super_class: Option<&ClassRef>,
+        interfaces: &[ClassRef],
+    ) {
+        forward!(self.visit(
+            version,
+            access_flags,
+            binary_name,
+            signature,
+            super_class,
+            interfaces
+        ));
+    }
+
+    /// Visits the source file and the source debug extension of the class.
+    fn visit_source(&mut self, source_file: Option<&str>, debug_extension: Option<&[u8]>) {
+        forward!(self.visit_source(source_file, debug_extension));
+    }
+
+    /// Visits the compilation and source identifiers emitted by `javac`.
+    fn visit_compilation_info(&mut self, compilation_id: Option<&str>, source_id: Option<&str>) {
+        forward!(self.visit_compilation_info(compilation_id, source_id));
+    }
+
+    /// Visits the module declared by a `module-info` class.
+    fn visit_module(
+        &mut self,
+        module: &Module,
+        packages: &[PackageRef],
+        main_class: Option<&ClassRef>,
+    ) {
+        forward!(self.visit_module(module, packages, main_class));
+    }
+
+    /// Visits the nest host of the class.
+    fn visit_nest_host(&mut self, nest_host: &ClassRef) {
+        forward!(self.visit_nest_host(nest_host));
+    }
+
+    /// Visits the enclosing method of the class.
+    fn visit_outer_class(&mut self, enclosing_method: &EnclosingMethod) {
+        forward!(self.visit_outer_class(enclosing_method));
+    }
+
+    /// Visits an annotation on the class.
+    fn visit_annotation(&mut self, annotation: &Annotation, visible: bool) {
+        forward!(self.visit_annotation(annotation, visible));
+    }
+
+    /// Visits a type annotation on the class.
+    fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        forward!(self.visit_type_annotation(annotation, visible));
+    }
+
+    /// Visits the `Synthetic` and `Deprecated` markers of the class.
+    fn visit_markers(&mut self, is_synthetic: bool, is_deprecated: bool) {
+        forward!(self.visit_markers(is_synthetic, is_deprecated));
+    }
+
+    /// Visits an unrecognized attribute of the class.
+    fn visit_attribute(&mut self, name: &str, bytes: &[u8]) {
+        forward!(self.visit_attribute(name, bytes));
+    }
+
+    /// Visits a custom attribute of the class.
+    fn visit_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        forward!(self.visit_custom_attribute(attribute));
+    }
+
+    /// Visits a nest member of the class.
+    fn visit_nest_member(&mut self, nest_member: &ClassRef) {
+        forward!(self.visit_nest_member(nest_member));
+    }
+
+    /// Visits a permitted subclass of the `sealed` class.
+    fn visit_permitted_subclass(&mut self, subclass: &ClassRef) {
+        forward!(self.visit_permitted_subclass(subclass));
+    }
+
+    /// Visits an inner class.
+    fn visit_inner_class(&mut self, inner_class: &InnerClassInfo) {
+        forward!(self.visit_inner_class(inner_class));
+    }
+
+    /// Visits the components of the class if it is a `record`.
+    fn visit_record(&mut self, components: &[RecordComponent]) {
+        forward!(self.visit_record(components));
+    }
+
+    /// Visits a bootstrap method.
+    fn visit_bootstrap_method(&mut self, bootstrap_method: &BootstrapMethod) {
+        forward!(self.visit_bootstrap_method(bootstrap_method));
+    }
+
+    /// Visits a field, and returns a visitor for its attributes if they are of interest.
+    fn visit_field(
+        &mut self,
+        access_flags: field::AccessFlags,
+        name: &str,
+        field_type: &FieldType,
+        signature: Option<&field::Signature>,
+        constant_value: Option<&ConstantValue>,
+    ) -> Option<Box<dyn FieldVisitor + '_>> {
+        self.delegate().and_then(|next| {
+            next.visit_field(access_flags, name, field_type, signature, constant_value)
+        })
+    }
+
+    /// Visits a method, and returns a visitor for its attributes and code if they are of
+    /// interest.
+    fn visit_method(
+        &mut self,
+        access_flags: method::AccessFlags,
+        name: &str,
+        descriptor: &MethodDescriptor,
+        signature: Option<&method::Signature>,
+        exceptions: &[ClassRef],
+    ) -> Option<Box<dyn MethodVisitor + '_>> {
+        self.delegate().and_then(|next| {
+            next.visit_method(access_flags, name, descriptor, signature, exceptions)
+        })
+    }
+
+    /// Visits the end of the class.
+    fn visit_end(&mut self) {
+        forward!(self.visit_end());
+    }
+}
+
+/// A visitor of the attributes of a [`Field`].
+#[allow(
+    unused_variables,
+    reason = "The default implementations ignore the arguments"
+)]
+pub trait FieldVisitor {
+    /// Returns the visitor to which the events are forwarded by default.
+    fn delegate(&mut self) -> Option<&mut dyn FieldVisitor> {
+        None
+    }
+
+    /// Visits an annotation on the field.
+    fn visit_annotation(&mut self, annotation: &Annotation, visible: bool) {
+        forward!(self.visit_annotation(annotation, visible));
+    }
+
+    /// Visits a type annotation on the field.
+    fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        forward!(self.visit_type_annotation(annotation, visible));
+    }
+
+    /// Visits the `Synthetic` and `Deprecated` markers of the field.
+    fn visit_markers(&mut self, is_synthetic: bool, is_deprecated: bool) {
+        forward!(self.visit_markers(is_synthetic, is_deprecated));
+    }
+
+    /// Visits an unrecognized attribute of the field.
+    fn visit_attribute(&mut self, name: &str, bytes: &[u8]) {
+        forward!(self.visit_attribute(name, bytes));
+    }
+
+    /// Visits a custom attribute of the field.
+    fn visit_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        forward!(self.visit_custom_attribute(attribute));
+    }
+
+    /// Visits the end of the field.
+    fn visit_end(&mut self) {
+        forward!(self.visit_end());
+    }
+}
+
+/// A visitor of the attributes and the code of a [`Method`].
+/// The events of the code are reported between [`visit_code`](MethodVisitor::visit_code) and
+/// [`visit_end`](MethodVisitor::visit_end), and the instructions are reported in the order of
+/// their program counters.
+#[allow(
+    unused_variables,
+    reason = "The default implementations ignore the arguments"
+)]
+pub trait MethodVisitor {
+    /// Returns the visitor to which the events are forwarded by default.
+    fn delegate(&mut self) -> Option<&mut dyn MethodVisitor> {
+        None
+    }
+
+    /// Visits a parameter of the method.
+    fn visit_parameter(&mut self, parameter: &ParameterInfo) {
+        forward!(self.visit_parameter(parameter));
+    }
+
+    /// Visits the default value of the annotation interface element.
+    fn visit_annotation_default(&mut self, value: &ElementValue) {
+        forward!(self.visit_annotation_default(value));
+    }
+
+    /// Visits an annotation on the method.
+    fn visit_annotation(&mut self, annotation: &Annotation, visible: bool) {
+        forward!(self.visit_annotation(annotation, visible));
+    }
+
+    /// Visits a type annotation on the method.
+    fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        forward!(self.visit_type_annotation(annotation, visible));
+    }
+
+    /// Visits the number of parameters that may have annotations.
+    fn visit_annotable_parameter_count(&mut self, count: usize, visible: bool) {
+        forward!(self.visit_annotable_parameter_count(count, visible));
+    }
+
+    /// Visits an annotation on the `parameter`-th parameter.
+    fn visit_parameter_annotation(
+        &mut self,
+        parameter: usize,
+        annotation: &Annotation,
+        visible: bool,
+    ) {
+        forward!(self.visit_parameter_annotation(parameter, annotation, visible));
+    }
+
+    /// Visits the `Synthetic` and `Deprecated` markers of the method.
+    fn visit_markers(&mut self, is_synthetic: bool, is_deprecated: bool) {
+        forward!(self.visit_markers(is_synthetic, is_deprecated));
+    }
+
+    /// Visits an unrecognized attribute of the method.
+    fn visit_attribute(&mut self, name: &str, bytes: &[u8]) {
+        forward!(self.visit_attribute(name, bytes));
+    }
+
+    /// Visits a custom attribute of the method.
+    fn visit_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        forward!(self.visit_custom_attribute(attribute));
+    }
+
+    /// Visits the start of the code of the method.
+    fn visit_code(&mut self, max_stack: u16, max_locals: u16) {
+        forward!(self.visit_code(max_stack, max_locals));
+    }
+
+    /// Visits an instruction.
+    fn visit_instruction(&mut self, pc: ProgramCounter, instruction: &Instruction) {
+        forward!(self.visit_instruction(pc, instruction));
+    }
+
+    /// Visits an exception handler.
+    fn visit_exception_handler(&mut self, entry: &ExceptionTableEntry) {
+        forward!(self.visit_exception_handler(entry));
+    }
+
+    /// Visits the line number table.
+    fn visit_line_number_table(&mut self, entries: &[LineNumberTableEntry]) {
+        forward!(self.visit_line_number_table(entries));
+    }
+
+    /// Visits the character range table.
+    fn visit_character_range_table(&mut self, entries: &[CharacterRangeTableEntry]) {
+        forward!(self.visit_character_range_table(entries));
+    }
+
+    /// Visits the local variable table.
+    fn visit_local_variable_table(&mut self, table: &LocalVariableTable) {
+        forward!(self.visit_local_variable_table(table));
+    }
+
+    /// Visits the stack map table.
+    fn visit_stack_map_table(&mut self, frames: &[StackMapFrame]) {
+        forward!(self.visit_stack_map_table(frames));
+    }
+
+    /// Visits a type annotation in the code.
+    fn visit_code_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        forward!(self.visit_code_type_annotation(annotation, visible));
+    }
+
+    /// Visits an unrecognized attribute of the code.
+    fn visit_code_attribute(&mut self, name: &str, bytes: &[u8]) {
+        forward!(self.visit_code_attribute(name, bytes));
+    }
+
+    /// Visits a custom attribute of the code.
+    fn visit_code_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        forward!(self.visit_code_custom_attribute(attribute));
+    }
+
+    /// Visits the end of the method.
+    fn visit_end(&mut self) {
+        forward!(self.visit_end());
+    }
+}
+
+/// Reports the annotations in the visible and invisible lists as `(annotation, visible)`.
+fn annotations<'a, A>(visible: &'a [A], invisible: &'a [A]) -> impl Iterator<Item = (&'a A, bool)> {
+    visible
+        .iter()
+        .map(|it| (it, true))
+        .chain(invisible.iter().map(|it| (it, false)))
+}
+
+impl Class {
+    /// Walks the class and reports its structure to `visitor`.
+    pub fn accept(&self, visitor: &mut dyn ClassVisitor) {
+        visitor.visit(
+            self.version,
+            self.access_flags,
+            &self.binary_name,
+            self.signature.as_ref(),
+            self.super_class.as_ref(),
+            &self.interfaces,
+        );
+        if self.source_file.is_some() || self.source_debug_extension.is_some() {
+            visitor.visit_source(
+                self.source_file.as_deref(),
+                self.source_debug_extension.as_deref(),
+            );
+        }
+        if self.compilation_id.is_some() || self.source_id.is_some() {
+            visitor
+                .visit_compilation_info(self.compilation_id.as_deref(), self.source_id.as_deref());
+        }
+        if let Some(module) = &self.module {
+            visitor.visit_module(
+                module,
+                &self.module_packages,
+                self.module_main_class.as_ref(),
+            );
+        }
+        if let Some(nest_host) = &self.nest_host {
+            visitor.visit_nest_host(nest_host);
+        }
+        if let Some(enclosing_method) = &self.enclosing_method {
+            visitor.visit_outer_class(enclosing_method);
+        }
+        for (annotation, visible) in annotations(
+            &self.runtime_visible_annotations,
+            &self.runtime_invisible_annotations,
+        ) {
+            visitor.visit_annotation(annotation, visible);
+        }
+        for (annotation, visible) in annotations(
+            &self.runtime_visible_type_annotations,
+            &self.runtime_invisible_type_annotations,
+        ) {
+            visitor.visit_type_annotation(annotation, visible);
+        }
+        visitor.visit_markers(self.is_synthetic, self.is_deprecated);
+        for (name, bytes) in &self.free_attributes {
+            visitor.visit_attribute(name, bytes);
+        }
+        self.custom_attributes
+            .iter()
+            .for_each(|it| visitor.visit_custom_attribute(it));
+        self.nest_members
+            .iter()
+            .for_each(|it| visitor.visit_nest_member(it));
+        self.permitted_subclasses
+            .iter()
+            .for_each(|it| visitor.visit_permitted_subclass(it));
+        self.inner_classes
+            .iter()
+            .for_each(|it| visitor.visit_inner_class(it));
+        if let Some(components) = &self.record {
+            visitor.visit_record(components);
+        }
+        self.bootstrap_methods
+            .iter()
+            .for_each(|it| visitor.visit_bootstrap_method(it));
+        for field in &self.fields {
+            if let Some(mut field_visitor) = visitor.visit_field(
+                field.access_flags,
+                &field.name,
+                &field.field_type,
+                field.signature.as_ref(),
+                field.constant_value.as_ref(),
+            ) {
+                field.accept(field_visitor.as_mut());
+            }
+        }
+        for method in &self.methods {
+            if let Some(mut method_visitor) = visitor.visit_method(
+                method.access_flags,
+                &method.name,
+                &method.descriptor,
+                method.signature.as_ref(),
+                &method.exceptions,
+            ) {
+                method.accept(method_visitor.as_mut());
+            }
+        }
+        visitor.visit_end();
+    }
+}
+
+impl Field {
+    fn accept(&self, visitor: &mut dyn FieldVisitor) {
+        for (annotation, visible) in annotations(
+            &self.runtime_visible_annotations,
+            &self.runtime_invisible_annotations,
+        ) {
+            visitor.visit_annotation(annotation, visible);
+        }
+        for (annotation, visible) in annotations(
+            &self.runtime_visible_type_annotations,
+            &self.runtime_invisible_type_annotations,
+        ) {
+            visitor.visit_type_annotation(annotation, visible);
+        }
+        visitor.visit_markers(self.is_synthetic, self.is_deprecated);
+        for (name, bytes) in &self.free_attributes {
+            visitor.visit_attribute(name, bytes);
+        }
+        self.custom_attributes
+            .iter()
+            .for_each(|it| visitor.visit_custom_attribute(it));
+        visitor.visit_end();
+    }
+}
+
+impl Method {
+    fn accept(&self, visitor: &mut dyn MethodVisitor) {
+        self.parameters
+            .iter()
+            .flatten()
+            .for_each(|it| visitor.visit_parameter(it));
+        if let Some(value) = &self.annotation_default {
+            visitor.visit_annotation_default(value);
+        }
+        for (annotation, visible) in annotations(
+            &self.runtime_visible_annotations,
+            &self.runtime_invisible_annotations,
+        ) {
+            visitor.visit_annotation(annotation, visible);
+        }
+        for (annotation, visible) in annotations(
+            &self.runtime_visible_type_annotations,
+            &self.runtime_invisible_type_annotations,
+        ) {
+            visitor.visit_type_annotation(annotation, visible);
+        }
+        for (parameters, visible) in [
+            (&self.runtime_visible_parameter_annotations, true),
+            (&self.runtime_invisible_parameter_annotations, false),
+        ] {
+            if parameters.is_empty() {
+                continue;
+            }
+            visitor.visit_annotable_parameter_count(parameters.len(), visible);
+            for (idx, annotations) in parameters.iter().enumerate() {
+                for annotation in annotations {
+                    visitor.visit_parameter_annotation(idx, annotation, visible);
+                }
+            }
+        }
+        visitor.visit_markers(self.is_synthetic, self.is_deprecated);
+        for (name, bytes) in &self.free_attributes {
+            visitor.visit_attribute(name, bytes);
+        }
+        self.custom_attributes
+            .iter()
+            .for_each(|it| visitor.visit_custom_attribute(it));
+        if let Some(body) = &self.body {
+            body.accept(visitor);
+        }
+        visitor.visit_end();
+    }
+}
+
+impl MethodBody {
+    fn accept(&self, visitor: &mut dyn MethodVisitor) {
+        visitor.visit_code(self.max_stack, self.max_locals);
+        for (pc, instruction) in &self.instructions {
+            visitor.visit_instruction(*pc, instruction);
+        }
+        self.exception_table
+            .iter()
+            .for_each(|it| visitor.visit_exception_handler(it));
+        if let Some(entries) = &self.line_number_table {
+            visitor.visit_line_number_table(entries);
+        }
+        if let Some(entries) = &self.character_range_table {
+            visitor.visit_character_range_table(entries);
+        }
+        if let Some(table) = &self.local_variable_table {
+            visitor.visit_local_variable_table(table);
+        }
+        if let Some(frames) = &self.stack_map_table {
+            visitor.visit_stack_map_table(frames);
+        }
+        for (annotation, visible) in annotations(
+            &self.runtime_visible_type_annotations,
+            &self.runtime_invisible_type_annotations,
+        ) {
+            visitor.visit_code_type_annotation(annotation, visible);
+        }
+        for (name, bytes) in &self.free_attributes {
+            visitor.visit_code_attribute(name, bytes);
+        }
+        self.custom_attributes
+            .iter()
+            .for_each(|it| visitor.visit_code_custom_attribute(it));
+    }
+}
+
+/// A [`ClassVisitor`] that rebuilds a [`Class`] from the events it receives.
+/// Accepting a class with this visitor produces an identical class.
+#[derive(Debug, Default)]
+pub struct ClassWriterVisitor {
+    class: Option<Class>,
+}
+
+impl ClassWriterVisitor {
+    /// Creates a new writer.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the rebuilt class, or `None` if the header of the class was never visited.
+    #[must_use]
+    pub fn into_class(self) -> Option<Class> {
+        self.class
+    }
+}
+
+/// Pushes an annotation to the visible or the invisible list.
+fn push_annotation<A>(
+    visible_list: &mut Vec<A>,
+    invisible_list: &mut Vec<A>,
+    it: A,
+    visible: bool,
+) {
+    if visible {
+        visible_list.push(it);
+    } else {
+        invisible_list.push(it);
+    }
+}
+
+impl ClassVisitor for ClassWriterVisitor {
+    fn visit(
+        &mut self,
+        version: class::Version,
+        access_flags: class::AccessFlags,
+        binary_name: &str,
+        signature: Option<&class::Signature>,
+        super_class: Option<&ClassRef>,
+        interfaces: &[ClassRef],
+    ) {
+        self.class = Some(Class {
+            version,
+            access_flags,
+            binary_name: binary_name.to_owned(),
+            super_class: super_class.cloned(),
+            interfaces: interfaces.to_vec(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+            source_file: None,
+            inner_classes: Vec::new(),
+            enclosing_method: None,
+            source_debug_extension: None,
+            compilation_id: None,
+            source_id: None,
+            runtime_visible_annotations: Vec::new(),
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            bootstrap_methods: Vec::new(),
+            module: None,
+            module_packages: Vec::new(),
+            module_main_class: None,
+            nest_host: None,
+            nest_members: Vec::new(),
+            permitted_subclasses: Vec::new(),
+            is_synthetic: false,
+            is_deprecated: false,
+            signature: signature.cloned(),
+            record: None,
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        });
+    }
+
+    fn visit_source(&mut self, source_file: Option<&str>, debug_extension: Option<&[u8]>) {
+        if let Some(class) = self.class.as_mut() {
+            class.source_file = source_file.map(ToOwned::to_owned);
+            class.source_debug_extension = debug_extension.map(<[u8]>::to_vec);
+        }
+    }
+
+    fn visit_compilation_info(&mut self, compilation_id: Option<&str>, source_id: Option<&str>) {
+        if let Some(class) = self.class.as_mut() {
+            class.compilation_id = compilation_id.map(ToOwned::to_owned);
+            class.source_id = source_id.map(ToOwned::to_owned);
+        }
+    }
+
+    fn visit_module(
+        &mut self,
+        module: &Module,
+        packages: &[PackageRef],
+        main_class: Option<&ClassRef>,
+    ) {
+        if let Some(class) = self.class.as_mut() {
+            class.module = Some(module.clone());
+            class.module_packages = packages.to_vec();
+            class.module_main_class = main_class.cloned();
+        }
+    }
+
+    fn visit_nest_host(&mut self, nest_host: &ClassRef) {
+        if let Some(class) = self.class.as_mut() {
+            class.nest_host = Some(nest_host.clone());
+        }
+    }
+
+    fn visit_outer_class(&mut self, enclosing_method: &EnclosingMethod) {
+        if let Some(class) = self.class.as_mut() {
+            class.enclosing_method = Some(enclosing_method.clone());
+        }
+    }
+
+    fn visit_annotation(&mut self, annotation: &Annotation, visible: bool) {
+        if let Some(class) = self.class.as_mut() {
+            push_annotation(
+                &mut class.runtime_visible_annotations,
+                &mut class.runtime_invisible_annotations,
+                annotation.clone(),
+                visible,
+            );
+        }
+    }
+
+    fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        if let Some(class) = self.class.as_mut() {
+            push_annotation(
+                &mut class.runtime_visible_type_annotations,
+                &mut class.runtime_invisible_type_annotations,
+                annotation.clone(),
+                visible,
+            );
+        }
+    }
+
+    fn visit_markers(&mut self, is_synthetic: bool, is_deprecated: bool) {
+        if let Some(class) = self.class.as_mut() {
+            class.is_synthetic = is_synthetic;
+            class.is_deprecated = is_deprecated;
+        }
+    }
+
+    fn visit_attribute(&mut self, name: &str, bytes: &[u8]) {
+        if let Some(class) = self.class.as_mut() {
+            class
+                .free_attributes
+                .push((name.to_owned(), bytes.to_vec()));
+        }
+    }
+
+    fn visit_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        if let Some(class) = self.class.as_mut() {
+            class.custom_attributes.push(attribute.clone());
+        }
+    }
+
+    fn visit_nest_member(&mut self, nest_member: &ClassRef) {
+        if let Some(class) = self.class.as_mut() {
+            class.nest_members.push(nest_member.clone());
+        }
+    }
+
+    fn visit_permitted_subclass(&mut self, subclass: &ClassRef) {
+        if let Some(class) = self.class.as_mut() {
+            class.permitted_subclasses.push(subclass.clone());
+        }
+    }
+
+    fn visit_inner_class(&mut self, inner_class: &InnerClassInfo) {
+        if let Some(class) = self.class.as_mut() {
+            class.inner_classes.push(inner_class.clone());
+        }
+    }
+
+    fn visit_record(&mut self, components: &[RecordComponent]) {
+        if let Some(class) = self.class.as_mut() {
+            class.record = Some(components.to_vec());
+        }
+    }
+
+    fn visit_bootstrap_method(&mut self, bootstrap_method: &BootstrapMethod) {
+        if let Some(class) = self.class.as_mut() {
+            class.bootstrap_methods.push(bootstrap_method.clone());
+        }
+    }
+
+    fn visit_field(
+        &mut self,
+        access_flags: field::AccessFlags,
+        name: &str,
+        field_type: &FieldType,
+        signature: Option<&field::Signature>,
+        constant_value: Option<&ConstantValue>,
+    ) -> Option<Box<dyn FieldVisitor + '_>> {
+        let class = self.class.as_mut()?;
+        class.fields.push(Field {
+            access_flags,
+            name: name.to_owned(),
+            owner: ClassRef::new(&class.binary_name),
+            field_type: field_type.clone(),
+            constant_value: constant_value.cloned(),
+            is_synthetic: false,
+            is_deprecated: false,
+            signature: signature.cloned(),
+            runtime_visible_annotations: Vec::new(),
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        });
+        let field = class.fields.last_mut()?;
+        Some(Box::new(FieldWriter { field }))
+    }
+
+    fn visit_method(
+        &mut self,
+        access_flags: method::AccessFlags,
+        name: &str,
+        descriptor: &MethodDescriptor,
+        signature: Option<&method::Signature>,
+        exceptions: &[ClassRef],
+    ) -> Option<Box<dyn MethodVisitor + '_>> {
+        let class = self.class.as_mut()?;
+        class.methods.push(Method {
+            access_flags,
+            name: name.to_owned(),
+            descriptor: descriptor.clone(),
+            owner: ClassRef::new(&class.binary_name),
+            body: None,
+            exceptions: exceptions.to_vec(),
+            runtime_visible_annotations: Vec::new(),
+            runtime_invisible_annotations: Vec::new(),
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            runtime_visible_parameter_annotations: Vec::new(),
+            runtime_invisible_parameter_annotations: Vec::new(),
+            annotation_default: None,
+            parameters: None,
+            is_synthetic: false,
+            is_deprecated: false,
+            signature: signature.cloned(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        });
+        let method = class.methods.last_mut()?;
+        Some(Box::new(MethodWriter {
+            method,
+            instructions: BTreeMap::new(),
+        }))
+    }
+}
+
+/// Writes the attributes of a field added by a [`ClassWriterVisitor`].
+struct FieldWriter<'a> {
+    field: &'a mut Field,
+}
+
+impl FieldVisitor for FieldWriter<'_> {
+    fn visit_annotation(&mut self, annotation: &Annotation, visible: bool) {
+        push_annotation(
+            &mut self.field.runtime_visible_annotations,
+            &mut self.field.runtime_invisible_annotations,
+            annotation.clone(),
+            visible,
+        );
+    }
+
+    fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        push_annotation(
+            &mut self.field.runtime_visible_type_annotations,
+            &mut self.field.runtime_invisible_type_annotations,
+            annotation.clone(),
+            visible,
+        );
+    }
+
+    fn visit_markers(&mut self, is_synthetic: bool, is_deprecated: bool) {
+        self.field.is_synthetic = is_synthetic;
+        self.field.is_deprecated = is_deprecated;
+    }
+
+    fn visit_attribute(&mut self, name: &str, bytes: &[u8]) {
+        self.field
+            .free_attributes
+            .push((name.to_owned(), bytes.to_vec()));
+    }
+
+    fn visit_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        self.field.custom_attributes.push(attribute.clone());
+    }
+}
+
+/// Writes the attributes and the code of a method added by a [`ClassWriterVisitor`].
+/// The instructions are written to the body on [`visit_end`](MethodVisitor::visit_end).
+struct MethodWriter<'a> {
+    method: &'a mut Method,
+    instructions: BTreeMap<ProgramCounter, Instruction>,
+}
+
+impl MethodWriter<'_> {
+    fn body(&mut self) -> Option<&mut MethodBody> {
+        self.method.body.as_mut()
+    }
+}
+
+impl MethodVisitor for MethodWriter<'_> {
+    fn visit_parameter(&mut self, parameter: &ParameterInfo) {
+        self.method
+            .parameters
+            .get_or_insert_with(Vec::new)
+            .push(parameter.clone());
+    }
+
+    fn visit_annotation_default(&mut self, value: &ElementValue) {
+        self.method.annotation_default = Some(value.clone());
+    }
+
+    fn visit_annotation(&mut self, annotation: &Annotation, visible: bool) {
+        push_annotation(
+            &mut self.method.runtime_visible_annotations,
+            &mut self.method.runtime_invisible_annotations,
+            annotation.clone(),
+            visible,
+        );
+    }
+
+    fn visit_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        push_annotation(
+            &mut self.method.runtime_visible_type_annotations,
+            &mut self.method.runtime_invisible_type_annotations,
+            annotation.clone(),
+            visible,
+        );
+    }
+
+    fn visit_annotable_parameter_count(&mut self, count: usize, visible: bool) {
+        let parameters = if visible {
+            &mut self.method.runtime_visible_parameter_annotations
+        } else {
+            &mut self.method.runtime_invisible_parameter_annotations
+        };
+        parameters.resize_with(count, Vec::new);
+    }
+
+    fn visit_parameter_annotation(
+        &mut self,
+        parameter: usize,
+        annotation: &Annotation,
+        visible: bool,
+    ) {
+        let parameters = if visible {
+            &mut self.method.runtime_visible_parameter_annotations
+        } else {
+            &mut self.method.runtime_invisible_parameter_annotations
+        };
+        if parameters.len() <= parameter {
+            parameters.resize_with(parameter + 1, Vec::new);
+        }
+        parameters[parameter].push(annotation.clone());
+    }
+
+    fn visit_markers(&mut self, is_synthetic: bool, is_deprecated: bool) {
+        self.method.is_synthetic = is_synthetic;
+        self.method.is_deprecated = is_deprecated;
+    }
+
+    fn visit_attribute(&mut self, name: &str, bytes: &[u8]) {
+        self.method
+            .free_attributes
+            .push((name.to_owned(), bytes.to_vec()));
+    }
+
+    fn visit_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        self.method.custom_attributes.push(attribute.clone());
+    }
+
+    fn visit_code(&mut self, max_stack: u16, max_locals: u16) {
+        self.method.body = Some(MethodBody {
+            max_stack,
+            max_locals,
+            instructions: BTreeMap::new().into(),
+            exception_table: Vec::new(),
+            line_number_table: None,
+            character_range_table: None,
+            local_variable_table: None,
+            stack_map_table: None,
+            runtime_visible_type_annotations: Vec::new(),
+            runtime_invisible_type_annotations: Vec::new(),
+            free_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
+        });
+    }
+
+    fn visit_instruction(&mut self, pc: ProgramCounter, instruction: &Instruction) {
+        self.instructions.insert(pc, instruction.clone());
+    }
+
+    fn visit_exception_handler(&mut self, entry: &ExceptionTableEntry) {
+        if let Some(body) = self.body() {
+            body.exception_table.push(entry.clone());
+        }
+    }
+
+    fn visit_line_number_table(&mut self, entries: &[LineNumberTableEntry]) {
+        if let Some(body) = self.body() {
+            body.line_number_table = Some(entries.to_vec());
+        }
+    }
+
+    fn visit_character_range_table(&mut self, entries: &[CharacterRangeTableEntry]) {
+        if let Some(body) = self.body() {
+            body.character_range_table = Some(entries.to_vec());
+        }
+    }
+
+    fn visit_local_variable_table(&mut self, table: &LocalVariableTable) {
+        if let Some(body) = self.body() {
+            body.local_variable_table = Some(table.clone());
+        }
+    }
+
+    fn visit_stack_map_table(&mut self, frames: &[StackMapFrame]) {
+        if let Some(body) = self.body() {
+            body.stack_map_table = Some(frames.to_vec());
+        }
+    }
+
+    fn visit_code_type_annotation(&mut self, annotation: &TypeAnnotation, visible: bool) {
+        if let Some(body) = self.body() {
+            push_annotation(
+                &mut body.runtime_visible_type_annotations,
+                &mut body.runtime_invisible_type_annotations,
+                annotation.clone(),
+                visible,
+            );
+        }
+    }
+
+    fn visit_code_attribute(&mut self, name: &str, bytes: &[u8]) {
+        if let Some(body) = self.body() {
+            body.free_attributes.push((name.to_owned(), bytes.to_vec()));
+        }
+    }
+
+    fn visit_code_custom_attribute(&mut self, attribute: &CustomAttribute) {
+        if let Some(body) = self.body() {
+            body.custom_attributes.push(attribute.clone());
+        }
+    }
+
+    fn visit_end(&mut self) {
+        let instructions = std::mem::take(&mut self.instructions);
+        if let Some(body) = self.body() {
+            body.instructions = instructions.into();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jvm::code::{Instruction::*, InstructionList};
+
+    fn test_class() -> Class {
+        let owner = ClassRef::new("org/mokapot/Foo");
+        let annotation = Annotation {
+            annotation_type: FieldType::Object(ClassRef::new("java/lang/Deprecated")),
+            element_value_pairs: Vec::new(),
+        };
+        Class {
+            binary_name: owner.binary_name.to_string(),
+            super_class: Some(ClassRef::new("java/lang/Object")),
+            interfaces: vec![ClassRef::new("java/lang/Runnable")],
+            source_file: Some("Foo.java".to_owned()),
+            runtime_invisible_annotations: vec![annotation.clone()],
+            nest_members: vec![ClassRef::new("org/mokapot/Foo$Bar")],
+            is_deprecated: true,
+            free_attributes: vec![("Custom".to_owned(), vec![1, 2, 3])],
+            custom_attributes: vec![CustomAttribute::new("Decoded", 42u32)],
+            fields: vec![Field {
+                name: "value".to_owned(),
+                owner: owner.clone(),
+                constant_value: Some(ConstantValue::Integer(42)),
+                runtime_visible_annotations: vec![annotation.clone()],
+                ..Default::default()
+            }],
+            methods: vec![Method {
+                name: "run".to_owned(),
+                owner,
+                runtime_invisible_parameter_annotations: vec![Vec::new(), vec![annotation]],
+                body: Some(MethodBody {
+                    max_stack: 1,
+                    instructions: InstructionList::from([(0.into(), IConst0), (1.into(), IReturn)]),
+                    line_number_table: Some(vec![LineNumberTableEntry {
+                        start_pc: 0.into(),
+                        line_number: 7,
+                    }]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn write_identical_class() {
+        let class = test_class();
+        let mut writer = ClassWriterVisitor::new();
+        class.accept(&mut writer);
+        let written = writer.into_class().unwrap();
+        assert_eq!(format!("{written:?}"), format!("{class:?}"));
+    }
+
+    /// Replaces `iconst_0` with `iconst_1` and drops the private fields.
+    struct Transformer(ClassWriterVisitor);
+
+    struct InstructionTransformer<'a>(Box<dyn MethodVisitor + 'a>);
+
+    impl ClassVisitor for Transformer {
+        fn delegate(&mut self) -> Option<&mut dyn ClassVisitor> {
+            Some(&mut self.0)
+        }
+
+        fn visit_field(
+            &mut self,
+            access_flags: field::AccessFlags,
+            name: &str,
+            field_type: &FieldType,
+            signature: Option<&field::Signature>,
+            constant_value: Option<&ConstantValue>,
+        ) -> Option<Box<dyn FieldVisitor + '_>> {
+            if access_flags.contains(field::AccessFlags::PRIVATE) {
+                None
+            } else {
+                self.0
+                    .visit_field(access_flags, name, field_type, signature, constant_value)
+            }
+        }
+
+        fn visit_method(
+            &mut self,
+            access_flags: method::AccessFlags,
+            name: &str,
+            descriptor: &MethodDescriptor,
+            signature: Option<&method::Signature>,
+            exceptions: &[ClassRef],
+        ) -> Option<Box<dyn MethodVisitor + '_>> {
+            let next =
+                self.0
+                    .visit_method(access_flags, name, descriptor, signature, exceptions)?;
+            Some(Box::new(InstructionTransformer(next)))
+        }
+    }
+
+    impl MethodVisitor for InstructionTransformer<'_> {
+        fn delegate(&mut self) -> Option<&mut dyn MethodVisitor> {
+            Some(self.0.as_mut())
+        }
+
+        fn visit_instruction(&mut self, pc: ProgramCounter, instruction: &Instruction) {
+            let instruction = match instruction {
+                IConst0 => &IConst1,
+                it => it,
+            };
+            self.0.visit_instruction(pc, instruction);
+        }
+    }
+
+    #[test]
+    fn transform_with_delegation() {
+        let class = Class {
+            fields: vec![
+                Field {
+                    name: "secret".to_owned(),
+                    access_flags: field::AccessFlags::PRIVATE,
+                    ..Default::default()
+                },
+                Field {
+                    name: "shared".to_owned(),
+                    access_flags: field::AccessFlags::PUBLIC,
+                    ..Default::default()
+                },
+            ],
+            methods: vec![Method {
+                body: Some(MethodBody {
+                    instructions: InstructionList::from([(0.into(), IConst0), (1.into(), IReturn)]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut transformer = Transformer(ClassWriterVisitor::new());
+        class.accept(&mut transformer);
+        let result = transformer.0.into_class().unwrap();
+        assert_eq!(
+            result
+                .fields
+                .iter()
+                .map(|it| it.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["shared"]
+        );
+        let body = result.methods[0].body.as_ref().unwrap();
+        assert_eq!(body.instruction_at(0.into()), Some(&IConst1));
+        assert_eq!(body.instruction_at(1.into()), Some(&IReturn));
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{
+    jvm::{
+        annotation::{ElementValue, TargetInfo, TypePathElement},
+        class::constant_pool::Entry,
+        code::{Instruction, InstructionList, ProgramCounter},
+        Annotation, ConstantValue, TypeAnnotation,
+    },
+    types::field_type::PrimitiveType,
+};
+
+use super::{count, writer_utils::ValueWriterExt, Attributes, Error, Writer};
+
+/// Where a type annotation is, which determines its `target_type`.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum TargetLocation<'a> {
+    Class,
+    Field,
+    Method,
+    Code(&'a InstructionList<Instruction>),
+}
+
+impl TargetLocation<'_> {
+    /// Chooses the `target_type` of `target_info`.
+    /// The target types that the parser does not distinguish are inferred from the location and
+    /// the annotated instruction, with those of the more common constructs as the fallback.
+    fn target_type(self, target_info: &TargetInfo) -> u8 {
+        let instruction_at = |offset: ProgramCounter| match self {
+            Self::Code(instructions) => instructions.get(&offset),
+            _ => None,
+        };
+        match target_info {
+            TargetInfo::TypeParameter { .. } if matches!(self, Self::Method) => 0x01,
+            TargetInfo::TypeParameter { .. } => 0x00,
+            TargetInfo::SuperType { .. } => 0x10,
+            TargetInfo::TypeParameterBound { .. } if matches!(self, Self::Method) => 0x12,
+            TargetInfo::TypeParameterBound { .. } => 0x11,
+            TargetInfo::Empty if matches!(self, Self::Method) => 0x14,
+            TargetInfo::Empty => 0x13,
+            TargetInfo::FormalParameter { .. } => 0x16,
+            TargetInfo::Throws { .. } => 0x17,
+            TargetInfo::LocalVar(_) => 0x40,
+            TargetInfo::Catch { .. } => 0x42,
+            TargetInfo::Offset(offset) => match instruction_at(ProgramCounter::from(*offset)) {
+                Some(Instruction::New(_)) => 0x44,
+                Some(Instruction::InvokeDynamic { .. }) => 0x46,
+                _ => 0x43,
+            },
+            TargetInfo::TypeArgument { offset, .. } => match instruction_at(*offset) {
+                Some(Instruction::InvokeSpecial(method)) if &*method.name == "<init>" => 0x48,
+                Some(
+                    Instruction::InvokeVirtual(_)
+                    | Instruction::InvokeSpecial(_)
+                    | Instruction::InvokeStatic(_)
+                    | Instruction::InvokeInterface(..),
+                ) => 0x49,
+                Some(Instruction::InvokeDynamic { .. }) => 0x4B,
+                _ => 0x47,
+            },
+        }
+    }
+}
+
+impl Writer<'_> {
+    /// Adds the `RuntimeVisibleAnnotations` and `RuntimeInvisibleAnnotations` attributes.
+    pub(super) fn annotation_attributes(
+        &mut self,
+        attributes: &mut Attributes,
+        visible: &[Annotation],
+        invisible: &[Annotation],
+    ) -> Result<(), Error> {
+        for (name, annotations) in [
+            ("RuntimeVisibleAnnotations", visible),
+            ("RuntimeInvisibleAnnotations", invisible),
+        ] {
+            if !annotations.is_empty() {
+                self.attribute(attributes, name, |this, info| {
+                    this.annotations(info, annotations)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the `RuntimeVisibleTypeAnnotations` and `RuntimeInvisibleTypeAnnotations` attributes.
+    pub(super) fn type_annotation_attributes(
+        &mut self,
+        attributes: &mut Attributes,
+        visible: &[TypeAnnotation],
+        invisible: &[TypeAnnotation],
+        location: TargetLocation<'_>,
+    ) -> Result<(), Error> {
+        for (name, annotations) in [
+            ("RuntimeVisibleTypeAnnotations", visible),
+            ("RuntimeInvisibleTypeAnnotations", invisible),
+        ] {
+            if !annotations.is_empty() {
+                self.attribute(attributes, name, |this, info| {
+                    info.write_value(count(annotations.len(), name)?);
+                    for annotation in annotations {
+                        this.type_annotation(info, annotation, location)?;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn annotations(
+        &mut self,
+        out: &mut Vec<u8>,
+        annotations: &[Annotation],
+    ) -> Result<(), Error> {
+        out.write_value(count(annotations.len(), "annotations")?);
+        for annotation in annotations {
+            self.annotation(out, annotation)?;
+        }
+        Ok(())
+    }
+
+    fn annotation(&mut self, out: &mut Vec<u8>, annotation: &Annotation) -> Result<(), Error> {
+        out.write_value(
+            self.constant_pool
+                .put_utf8(annotation.annotation_type.descriptor())?,
+        );
+        self.element_value_pairs(out, &annotation.element_value_pairs)
+    }
+
+    fn type_annotation(
+        &mut self,
+        out: &mut Vec<u8>,
+        annotation: &TypeAnnotation,
+        location: TargetLocation<'_>,
+    ) -> Result<(), Error> {
+        out.write_value(location.target_type(&annotation.target_info));
+        match &annotation.target_info {
+            &TargetInfo::TypeParameter { index } | &TargetInfo::FormalParameter { index } => {
+                out.write_value(index);
+            }
+            &TargetInfo::SuperType { index }
+            | &TargetInfo::Throws { index }
+            | &TargetInfo::Catch { index }
+            | &TargetInfo::Offset(index) => out.write_value(index),
+            &TargetInfo::TypeParameterBound {
+                type_parameter_index,
+                bound_index,
+            } => {
+                out.write_value(type_parameter_index);
+                out.write_value(bound_index);
+            }
+            TargetInfo::Empty => {}
+            TargetInfo::LocalVar(variables) => {
+                out.write_value(count(variables.len(), "localvar_target")?);
+                for variable in variables {
+                    let range = &variable.effective_range;
+                    let length = u16::from(range.end)
+                        .checked_sub(u16::from(range.start))
+                        .ok_or(Error::Other("The range of a local variable is reversed"))?;
+                    out.write_value(range.start);
+                    out.write_value(length);
+                    out.write_value(variable.index);
+                }
+            }
+            &TargetInfo::TypeArgument { offset, index } => {
+                out.write_value(offset);
+                out.write_value(index);
+            }
+        }
+        let path_length = u8::try_from(annotation.target_path.len())
+            .map_err(|_| Error::TooManyEntries("type_path"))?;
+        out.write_value(path_length);
+        for element in &annotation.target_path {
+            let (kind, argument_index) = match element {
+                TypePathElement::Array => (0u8, 0u8),
+                TypePathElement::Nested => (1, 0),
+                TypePathElement::Bound => (2, 0),
+                &TypePathElement::TypeArgument(index) => (3, index),
+            };
+            out.write_value(kind);
+            out.write_value(argument_index);
+        }
+        out.write_value(
+            self.constant_pool
+                .put_utf8(annotation.annotation_type.descriptor())?,
+        );
+        self.element_value_pairs(out, &annotation.element_value_pairs)
+    }
+
+    fn element_value_pairs(
+        &mut self,
+        out: &mut Vec<u8>,
+        pairs: &[(String, ElementValue)],
+    ) -> Result<(), Error> {
+        out.write_value(count(pairs.len(), "element_value_pairs")?);
+        for (name, value) in pairs {
+            out.write_value(self.constant_pool.put_utf8(name.as_str())?);
+            self.element_value(out, value)?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn element_value(
+        &mut self,
+        out: &mut Vec<u8>,
+        value: &ElementValue,
+    ) -> Result<(), Error> {
+        match value {
+            ElementValue::Primitive(primitive_type, value) => {
+                let tag = match primitive_type {
+                    PrimitiveType::Boolean => b'Z',
+                    PrimitiveType::Char => b'C',
+                    PrimitiveType::Float => b'F',
+                    PrimitiveType::Double => b'D',
+                    PrimitiveType::Byte => b'B',
+                    PrimitiveType::Short => b'S',
+                    PrimitiveType::Int => b'I',
+                    PrimitiveType::Long => b'J',
+                };
+                out.write_value(tag);
+                out.write_value(self.constant_pool.put_constant_value(value)?);
+            }
+            ElementValue::String(ConstantValue::String(value)) => {
+                out.write_value(b's');
+                out.write_value(self.constant_pool.put_entry(Entry::Utf8(value.clone()))?);
+            }
+            ElementValue::String(_) => {
+                return Err(Error::Other(
+                    "The value of a string element is not a string",
+                ));
+            }
+            ElementValue::EnumConstant {
+                enum_type_name,
+                const_name,
+            } => {
+                out.write_value(b'e');
+                out.write_value(self.constant_pool.put_utf8(enum_type_name.as_str())?);
+                out.write_value(self.constant_pool.put_utf8(const_name.as_str())?);
+            }
+            ElementValue::Class { return_descriptor } => {
+                out.write_value(b'c');
+                out.write_value(
+                    self.constant_pool
+                        .put_utf8(return_descriptor.descriptor())?,
+                );
+            }
+            ElementValue::AnnotationInterface(annotation) => {
+                out.write_value(b'@');
+                self.annotation(out, annotation)?;
+            }
+            ElementValue::Array(values) => {
+                out.write_value(b'[');
+                out.write_value(count(values.len(), "array_value")?);
+                for value in values {
+                    self.element_value(out, value)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,352 @@
+use std::collections::BTreeSet;
+
+use crate::jvm::{
+    class::{constant_pool::ConstantPoolBuilder, RecordComponent},
+    parsing::AttributeRegistry,
+    references::ClassRef,
+    Class,
+};
+
+use super::{
+    annotation::TargetLocation, count, writer_utils::ValueWriterExt, Attributes, Error, Writer,
+    WritingOptions,
+};
+
+const JAVA_CLASS_MAGIC: u32 = 0xCAFE_BABE;
+
+impl Class {
+    /// Writes the class in the class file format with a fresh constant pool.
+    /// The unrecognized attributes are written as they are, so the constant pool indices in them
+    /// are only valid when writing with the constant pool they are parsed with.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes_with_options(&WritingOptions::new())
+    }
+
+    /// Writes the class in the class file format with the given options.
+    /// # Errors
+    /// See [`Error`] for more information.
+    pub fn to_bytes_with_options(&self, options: &WritingOptions) -> Result<Vec<u8>, Error> {
+        let registry = &options.attribute_registry;
+        let seed = options
+            .constant_pool
+            .as_ref()
+            .filter(|_| !options.deterministic);
+        let (constant_pool, body) = match seed {
+            None => {
+                let (builder, body) =
+                    self.write_body(ConstantPoolBuilder::default(), registry, false, true)?;
+                if builder
+                    .ldc_operands()
+                    .iter()
+                    .all(|&it| it <= u8::MAX.into())
+                {
+                    (builder, body)
+                } else {
+                    let ldc_operands = builder.ldc_operands().clone();
+                    let mut constant_pool = builder.build();
+                    constant_pool.move_to_front(&ldc_operands);
+                    // The second pass finds the operands of `ldc` in the first 255 entries.
+                    self.write_body(
+                        ConstantPoolBuilder::from(constant_pool),
+                        registry,
+                        false,
+                        false,
+                    )?
+                }
+            }
+            Some(constant_pool) if !options.compact => {
+                let builder = ConstantPoolBuilder::from(constant_pool.clone());
+                self.write_body(builder, registry, false, false)?
+            }
+            Some(constant_pool) => {
+                let mut constant_pool = constant_pool.clone();
+                // The bootstrap methods in use are added again by the first pass.
+                constant_pool.set_bootstrap_methods(Vec::new());
+                let (builder, _) = self.write_body(
+                    ConstantPoolBuilder::from(constant_pool),
+                    registry,
+                    true,
+                    true,
+                )?;
+                let roots: Vec<_> = builder.requested_indices().collect();
+                let ldc_operands = builder.ldc_operands().clone();
+                let mut constant_pool = builder.build();
+                let index_mapping = constant_pool.compact(roots)?;
+                let ldc_operands: BTreeSet<_> =
+                    ldc_operands.iter().map(|it| index_mapping[it]).collect();
+                if ldc_operands.iter().any(|&it| it > u8::MAX.into()) {
+                    constant_pool.move_to_front(&ldc_operands);
+                }
+                // The second pass finds every entry at its new index, so all the references,
+                // including those in the attributes, are renumbered.
+                self.write_body(
+                    ConstantPoolBuilder::from(constant_pool),
+                    registry,
+                    true,
+                    false,
+                )?
+            }
+        };
+        let constant_pool = constant_pool.build();
+        let mut bytes = Vec::new();
+        bytes.write_value(JAVA_CLASS_MAGIC);
+        bytes.write_value(self.version.minor());
+        bytes.write_value(self.version.major());
+        bytes.write_value(constant_pool.constant_pool_count());
+        for (_, entry) in constant_pool.iter() {
+            entry
+                .write_to(&mut bytes)
+                .map_err(|_| Error::Other("A string in the constant pool is too long"))?;
+        }
+        bytes.extend(body);
+        Ok(bytes)
+    }
+
+    /// Writes the class file after the constant pool.
+    fn write_body(
+        &self,
+        constant_pool: ConstantPoolBuilder,
+        attribute_registry: &AttributeRegistry,
+        compacting: bool,
+        relocating_ldc_operands: bool,
+    ) -> Result<(ConstantPoolBuilder, Vec<u8>), Error> {
+        let mut writer = Writer {
+            constant_pool,
+            attribute_registry,
+            current_class_binary_name: &self.binary_name,
+            is_interface: self.is_interface(),
+            bootstrap_method_indices: Vec::new(),
+            compacting,
+            relocating_ldc_operands,
+        };
+        let mut body = Vec::new();
+        body.write_value(self.access_flags.bits());
+        let this_class = writer
+            .constant_pool
+            .put_class(&ClassRef::new(&self.binary_name))?;
+        body.write_value(this_class);
+        let super_class = self
+            .super_class
+            .as_ref()
+            .map(|it| writer.constant_pool.put_class(it))
+            .transpose()?;
+        body.write_value(super_class.unwrap_or(0));
+        body.write_value(count(self.interfaces.len(), "interfaces")?);
+        for interface in &self.interfaces {
+            body.write_value(writer.constant_pool.put_class(interface)?);
+        }
+        writer.bootstrap_method_indices = self
+            .bootstrap_methods
+            .iter()
+            .map(|it| writer.constant_pool.put_bootstrap_method(it))
+            .collect::<Result<_, _>>()?;
+        body.write_value(count(self.fields.len(), "fields")?);
+        for field in &self.fields {
+            writer
+                .field(&mut body, field)
+                .map_err(|source| Error::Located {
+                    source: Box::new(source),
+                    member: format!("{}.{}", self.binary_name, field.name),
+                })?;
+        }
+        body.write_value(count(self.methods.len(), "methods")?);
+        for method in &self.methods {
+            writer
+                .method(&mut body, method)
+                .map_err(|source| Error::Located {
+                    source: Box::new(source),
+                    member: format!(
+                        "{}.{}{}",
+                        self.binary_name,
+                        method.name,
+                        method.descriptor.descriptor()
+                    ),
+                })?;
+        }
+        let mut attributes = Attributes::default();
+        writer.class_attributes(&mut attributes, self)?;
+        attributes.write_to(&mut body);
+        Ok((writer.constant_pool, body))
+    }
+}
+
+impl Writer<'_> {
+    #[allow(clippy::too_many_lines)]
+    fn class_attributes(
+        &mut self,
+        attributes: &mut Attributes,
+        class: &Class,
+    ) -> Result<(), Error> {
+        self.signature(attributes, class.signature.as_ref())?;
+        if let Some(source_file) = &class.source_file {
+            self.attribute(attributes, "SourceFile", |this, info| {
+                info.write_value(this.constant_pool.put_utf8(source_file.as_str())?);
+                Ok(())
+            })?;
+        }
+        if let Some(source_debug_extension) = &class.source_debug_extension {
+            self.attribute(attributes, "SourceDebugExtension", |_, info| {
+                info.extend_from_slice(source_debug_extension);
+                Ok(())
+            })?;
+        }
+        for (name, value) in [
+            ("CompilationID", &class.compilation_id),
+            ("SourceID", &class.source_id),
+        ] {
+            if let Some(value) = value {
+                self.attribute(attributes, name, |this, info| {
+                    info.write_value(this.constant_pool.put_utf8(value.as_str())?);
+                    Ok(())
+                })?;
+            }
+        }
+        if let Some(module) = &class.module {
+            self.attribute(attributes, "Module", |this, info| this.module(info, module))?;
+        }
+        if !class.module_packages.is_empty() {
+            self.attribute(attributes, "ModulePackages", |this, info| {
+                info.write_value(count(class.module_packages.len(), "ModulePackages")?);
+                for package in &class.module_packages {
+                    info.write_value(this.constant_pool.put_package(package)?);
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(main_class) = &class.module_main_class {
+            self.attribute(attributes, "ModuleMainClass", |this, info| {
+                info.write_value(this.constant_pool.put_class(main_class)?);
+                Ok(())
+            })?;
+        }
+        if let Some(nest_host) = &class.nest_host {
+            self.attribute(attributes, "NestHost", |this, info| {
+                info.write_value(this.constant_pool.put_class(nest_host)?);
+                Ok(())
+            })?;
+        }
+        if let Some(enclosing_method) = &class.enclosing_method {
+            self.attribute(attributes, "EnclosingMethod", |this, info| {
+                info.write_value(this.constant_pool.put_class(&enclosing_method.class)?);
+                let method_index = enclosing_method
+                    .method_name_and_desc
+                    .as_ref()
+                    .map(|(name, descriptor)| {
+                        this.constant_pool
+                            .put_name_and_type(name, &descriptor.descriptor())
+                    })
+                    .transpose()?;
+                info.write_value(method_index.unwrap_or(0));
+                Ok(())
+            })?;
+        }
+        self.annotation_attributes(
+            attributes,
+            &class.runtime_visible_annotations,
+            &class.runtime_invisible_annotations,
+        )?;
+        self.type_annotation_attributes(
+            attributes,
+            &class.runtime_visible_type_annotations,
+            &class.runtime_invisible_type_annotations,
+            TargetLocation::Class,
+        )?;
+        self.markers(attributes, class.is_synthetic, class.is_deprecated)?;
+        for (name, classes) in [
+            ("NestMembers", &class.nest_members),
+            ("PermittedSubclasses", &class.permitted_subclasses),
+        ] {
+            if !classes.is_empty() {
+                self.attribute(attributes, name, |this, info| {
+                    info.write_value(count(classes.len(), name)?);
+                    for class_ref in classes {
+                        info.write_value(this.constant_pool.put_class(class_ref)?);
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        if !class.inner_classes.is_empty() {
+            self.attribute(attributes, "InnerClasses", |this, info| {
+                info.write_value(count(class.inner_classes.len(), "InnerClasses")?);
+                for inner_class in &class.inner_classes {
+                    info.write_value(this.constant_pool.put_class(&inner_class.inner_class)?);
+                    let outer_class = inner_class
+                        .outer_class
+                        .as_ref()
+                        .map(|it| this.constant_pool.put_class(it))
+                        .transpose()?;
+                    info.write_value(outer_class.unwrap_or(0));
+                    let inner_name = inner_class
+                        .inner_name
+                        .as_ref()
+                        .map(|it| this.constant_pool.put_utf8(it.as_str()))
+                        .transpose()?;
+                    info.write_value(inner_name.unwrap_or(0));
+                    info.write_value(inner_class.access_flags.bits());
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(components) = &class.record {
+            self.attribute(attributes, "Record", |this, info| {
+                info.write_value(count(components.len(), "Record")?);
+                for component in components {
+                    this.record_component(info, component)?;
+                }
+                Ok(())
+            })?;
+        }
+        self.extra_attributes(attributes, &class.free_attributes, &class.custom_attributes)?;
+        // The other attributes may add bootstrap methods, so this one comes last.
+        if !self.constant_pool.bootstrap_methods().is_empty() {
+            self.attribute(attributes, "BootstrapMethods", |this, info| {
+                let bootstrap_methods = this.constant_pool.bootstrap_methods();
+                info.write_value(count(bootstrap_methods.len(), "BootstrapMethods")?);
+                for entry in bootstrap_methods {
+                    info.write_value(entry.method_ref_index);
+                    info.write_value(count(entry.argument_indices.len(), "bootstrap_arguments")?);
+                    for &argument in &entry.argument_indices {
+                        info.write_value(argument);
+                    }
+                }
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    fn record_component(
+        &mut self,
+        out: &mut Vec<u8>,
+        component: &RecordComponent,
+    ) -> Result<(), Error> {
+        out.write_value(self.constant_pool.put_utf8(component.name.as_str())?);
+        out.write_value(
+            self.constant_pool
+                .put_utf8(component.component_type.descriptor())?,
+        );
+        let mut attributes = Attributes::default();
+        self.signature(&mut attributes, component.signature.as_ref())?;
+        self.annotation_attributes(
+            &mut attributes,
+            &component.runtime_visible_annotations,
+            &component.runtime_invisible_annotations,
+        )?;
+        self.type_annotation_attributes(
+            &mut attributes,
+            &component.runtime_visible_type_annotations,
+            &component.runtime_invisible_type_annotations,
+            TargetLocation::Field,
+        )?;
+        self.extra_attributes(
+            &mut attributes,
+            &component.free_attributes,
+            &component.custom_attributes,
+        )?;
+        attributes.write_to(out);
+        Ok(())
+    }
+}
@@ -0,0 +1,439 @@
+use crate::{
+    jvm::{
+        code::{
+            Instruction, InstructionList, LocalVariableId, MethodBody, ProgramCounter,
+            StackMapFrame, VerificationType, WideInstruction,
+        },
+        references::ClassRef,
+    },
+    types::field_type::{FieldType, PrimitiveType},
+};
+
+use super::{
+    annotation::TargetLocation, count, writer_utils::ValueWriterExt, Attributes, Error, Writer,
+};
+
+impl Writer<'_> {
+    #[allow(clippy::too_many_lines)]
+    pub(super) fn code(&mut self, out: &mut Vec<u8>, body: &MethodBody) -> Result<(), Error> {
+        out.write_value(body.max_stack);
+        out.write_value(body.max_locals);
+        let code = self.instructions(&body.instructions)?;
+        if code.len() > usize::from(u16::MAX) {
+            return Err(Error::Other("The code is longer than 65535 bytes"));
+        }
+        // The length is checked above.
+        #[allow(clippy::cast_possible_truncation)]
+        out.write_value(code.len() as u32);
+        out.extend(code);
+        out.write_value(count(body.exception_table.len(), "exception_table")?);
+        for entry in &body.exception_table {
+            out.write_value(*entry.covered_pc.start());
+            out.write_value(*entry.covered_pc.end());
+            out.write_value(entry.handler_pc);
+            let catch_type = entry
+                .catch_type
+                .as_ref()
+                .map(|it| self.constant_pool.put_class(it))
+                .transpose()?;
+            out.write_value(catch_type.unwrap_or(0));
+        }
+        let mut attributes = Attributes::default();
+        if let Some(entries) = &body.line_number_table {
+            self.attribute(&mut attributes, "LineNumberTable", |_, info| {
+                info.write_value(count(entries.len(), "LineNumberTable")?);
+                for entry in entries {
+                    info.write_value(entry.start_pc);
+                    info.write_value(entry.line_number);
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(entries) = &body.character_range_table {
+            self.attribute(&mut attributes, "CharacterRangeTable", |_, info| {
+                info.write_value(count(entries.len(), "CharacterRangeTable")?);
+                for entry in entries {
+                    info.write_value(*entry.pc_range.start());
+                    info.write_value(*entry.pc_range.end());
+                    info.write_value(u32::from(entry.start));
+                    info.write_value(u32::from(entry.end));
+                    info.write_value(entry.flags.bits());
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(table) = &body.local_variable_table {
+            // The parser merges `LocalVariableTable` and `LocalVariableTypeTable`, so they are
+            // split again here.
+            let typed: Vec<_> = table
+                .iter()
+                .filter_map(|(id, entry)| {
+                    let descriptor = entry.var_type.as_ref()?.descriptor();
+                    Some((id, entry.name.as_ref(), descriptor))
+                })
+                .collect();
+            let generic: Vec<_> = table
+                .iter()
+                .filter_map(|(id, entry)| {
+                    let signature = entry.signature.clone()?;
+                    Some((id, entry.name.as_ref(), signature))
+                })
+                .collect();
+            // An empty table is written as an empty `LocalVariableTable`.
+            if !typed.is_empty() || generic.is_empty() {
+                self.local_variable_table(&mut attributes, "LocalVariableTable", typed)?;
+            }
+            if !generic.is_empty() {
+                self.local_variable_table(&mut attributes, "LocalVariableTypeTable", generic)?;
+            }
+        }
+        if let Some(frames) = &body.stack_map_table {
+            self.attribute(&mut attributes, "StackMapTable", |this, info| {
+                info.write_value(count(frames.len(), "StackMapTable")?);
+                for frame in frames {
+                    this.stack_map_frame(info, frame)?;
+                }
+                Ok(())
+            })?;
+        }
+        self.type_annotation_attributes(
+            &mut attributes,
+            &body.runtime_visible_type_annotations,
+            &body.runtime_invisible_type_annotations,
+            TargetLocation::Code(&body.instructions),
+        )?;
+        self.extra_attributes(
+            &mut attributes,
+            &body.free_attributes,
+            &body.custom_attributes,
+        )?;
+        attributes.write_to(out);
+        Ok(())
+    }
+
+    fn instructions(
+        &mut self,
+        instructions: &InstructionList<Instruction>,
+    ) -> Result<Vec<u8>, Error> {
+        let mut code = Vec::new();
+        for (&pc, instruction) in instructions {
+            let result = if usize::from(u16::from(pc)) == code.len() {
+                self.instruction(&mut code, pc, instruction)
+            } else {
+                Err(Error::Other(
+                    "The instructions are not laid out contiguously",
+                ))
+            };
+            result.map_err(|source| Error::Instruction {
+                source: Box::new(source),
+                pc,
+            })?;
+        }
+        Ok(code)
+    }
+
+    #[allow(clippy::too_many_lines)]
+    fn instruction(
+        &mut self,
+        code: &mut Vec<u8>,
+        pc: ProgramCounter,
+        instruction: &Instruction,
+    ) -> Result<(), Error> {
+        #[allow(clippy::enum_glob_use)]
+        use Instruction::*;
+
+        code.write_value(instruction.opcode());
+        match instruction {
+            &BiPush(value) => code.write_value(value),
+            &SiPush(value) => code.write_value(value),
+            Ldc(value) => {
+                let index = self.constant_pool.put_ldc_operand(value)?;
+                let index = match u8::try_from(index) {
+                    Ok(index) => index,
+                    // The instruction is written again after moving its operand.
+                    Err(_) if self.relocating_ldc_operands => 0,
+                    Err(_) => {
+                        return Err(Error::Other("The constant of ldc is at an index above 255"))
+                    }
+                };
+                code.write_value(index);
+            }
+            LdcW(value) | Ldc2W(value) => {
+                code.write_value(self.constant_pool.put_constant_value(value)?);
+            }
+            &ILoad(index) | &LLoad(index) | &FLoad(index) | &DLoad(index) | &ALoad(index)
+            | &IStore(index) | &LStore(index) | &FStore(index) | &DStore(index)
+            | &AStore(index) | &Ret(index) => code.write_value(index),
+            &IInc(index, constant) => {
+                let constant = i8::try_from(constant)
+                    .map_err(|_| Error::Other("The increment of iinc does not fit in a byte"))?;
+                code.write_value(index);
+                code.write_value(constant);
+            }
+            &IfEq(target) | &IfNe(target) | &IfLt(target) | &IfGe(target) | &IfGt(target)
+            | &IfLe(target) | &IfICmpEq(target) | &IfICmpNe(target) | &IfICmpLt(target)
+            | &IfICmpGe(target) | &IfICmpGt(target) | &IfICmpLe(target) | &IfACmpEq(target)
+            | &IfACmpNe(target) | &IfNull(target) | &IfNonNull(target) | &Goto(target)
+            | &Jsr(target) => {
+                let offset = i16::try_from(branch_offset(pc, target))
+                    .map_err(|_| Error::Other("The branch offset does not fit in 16 bits"))?;
+                code.write_value(offset);
+            }
+            &GotoW(target) | &JsrW(target) => code.write_value(branch_offset(pc, target)),
+            TableSwitch {
+                range,
+                jump_targets,
+                default,
+            } => {
+                let expected_len = i64::from(*range.end()) - i64::from(*range.start()) + 1;
+                if i64::try_from(jump_targets.len()).ok() != Some(expected_len) {
+                    return Err(Error::Other(
+                        "The jump targets of tableswitch do not match its range",
+                    ));
+                }
+                code.extend(std::iter::repeat_n(0, usize::from(pc.switch_padding())));
+                code.write_value(branch_offset(pc, *default));
+                code.write_value(*range.start());
+                code.write_value(*range.end());
+                for &target in jump_targets {
+                    code.write_value(branch_offset(pc, target));
+                }
+            }
+            LookupSwitch {
+                default,
+                match_targets,
+            } => {
+                code.extend(std::iter::repeat_n(0, usize::from(pc.switch_padding())));
+                code.write_value(branch_offset(pc, *default));
+                let npairs = i32::try_from(match_targets.len())
+                    .map_err(|_| Error::TooManyEntries("lookupswitch"))?;
+                code.write_value(npairs);
+                for (&key, &target) in match_targets {
+                    code.write_value(key);
+                    code.write_value(branch_offset(pc, target));
+                }
+            }
+            GetStatic(field) | PutStatic(field) | GetField(field) | PutField(field) => {
+                code.write_value(self.constant_pool.put_field_ref(field)?);
+            }
+            InvokeVirtual(method) => {
+                code.write_value(self.constant_pool.put_method_ref(method, false)?);
+            }
+            InvokeSpecial(method) | InvokeStatic(method) => {
+                let is_interface = self.is_interface
+                    && *method.owner.binary_name == *self.current_class_binary_name;
+                code.write_value(self.constant_pool.put_method_ref(method, is_interface)?);
+            }
+            &InvokeInterface(ref method, count) => {
+                code.write_value(self.constant_pool.put_method_ref(method, true)?);
+                code.write_value(count);
+                code.write_value(0u8);
+            }
+            InvokeDynamic {
+                bootstrap_method_index,
+                name,
+                descriptor,
+            } => {
+                let bootstrap_method_attr_index = self
+                    .bootstrap_method_indices
+                    .get(usize::from(*bootstrap_method_index))
+                    .copied()
+                    .ok_or(Error::Other(
+                        "The bootstrap method of invokedynamic does not exist",
+                    ))?;
+                code.write_value(self.constant_pool.put_invoke_dynamic(
+                    bootstrap_method_attr_index,
+                    name,
+                    descriptor,
+                )?);
+                code.write_value(0u16);
+            }
+            New(class_ref) | ANewArray(class_ref) => {
+                code.write_value(self.constant_pool.put_class(class_ref)?);
+            }
+            &NewArray(element_type) => {
+                let atype: u8 = match element_type {
+                    PrimitiveType::Boolean => 4,
+                    PrimitiveType::Char => 5,
+                    PrimitiveType::Float => 6,
+                    PrimitiveType::Double => 7,
+                    PrimitiveType::Byte => 8,
+                    PrimitiveType::Short => 9,
+                    PrimitiveType::Int => 10,
+                    PrimitiveType::Long => 11,
+                };
+                code.write_value(atype);
+            }
+            CheckCast(target_type) | InstanceOf(target_type) => {
+                code.write_value(self.type_operand(target_type)?);
+            }
+            &MultiANewArray(ref array_type, dimensions) => {
+                code.write_value(self.type_operand(array_type)?);
+                code.write_value(dimensions);
+            }
+            Wide(wide) => {
+                let (opcode, index) = match *wide {
+                    WideInstruction::ILoad(index) => (0x15u8, index),
+                    WideInstruction::LLoad(index) => (0x16, index),
+                    WideInstruction::FLoad(index) => (0x17, index),
+                    WideInstruction::DLoad(index) => (0x18, index),
+                    WideInstruction::ALoad(index) => (0x19, index),
+                    WideInstruction::IStore(index) => (0x36, index),
+                    WideInstruction::LStore(index) => (0x37, index),
+                    WideInstruction::FStore(index) => (0x38, index),
+                    WideInstruction::DStore(index) => (0x39, index),
+                    WideInstruction::AStore(index) => (0x3a, index),
+                    WideInstruction::IInc(index, _) => (0x84, index),
+                    WideInstruction::Ret(index) => (0xa9, index),
+                };
+                code.write_value(opcode);
+                code.write_value(index);
+                if let WideInstruction::IInc(_, constant) = *wide {
+                    let constant = i16::try_from(constant).map_err(|_| {
+                        Error::Other("The increment of wide iinc does not fit in 16 bits")
+                    })?;
+                    code.write_value(constant);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Adds the `CONSTANT_Class` entry of the class or array type operand of an instruction.
+    fn type_operand(&mut self, field_type: &FieldType) -> Result<u16, Error> {
+        let index = match field_type {
+            FieldType::Object(class_ref) => self.constant_pool.put_class(class_ref)?,
+            FieldType::Array(_) => self
+                .constant_pool
+                .put_class(&ClassRef::new(field_type.descriptor()))?,
+            FieldType::Base(_) => {
+                return Err(Error::Other("The operand is not a class or an array type"));
+            }
+        };
+        Ok(index)
+    }
+
+    /// Adds a `LocalVariableTable` or `LocalVariableTypeTable` attribute with the names and the
+    /// descriptors or signatures of `variables`.
+    fn local_variable_table(
+        &mut self,
+        attributes: &mut Attributes,
+        name: &'static str,
+        variables: Vec<(&LocalVariableId, Option<&String>, String)>,
+    ) -> Result<(), Error> {
+        self.attribute(attributes, name, |this, info| {
+            info.write_value(count(variables.len(), name)?);
+            for (id, variable_name, descriptor) in variables {
+                let range = &id.effective_range;
+                let length = u16::from(range.end)
+                    .checked_sub(u16::from(range.start))
+                    .ok_or(Error::Other("The range of a local variable is reversed"))?;
+                let variable_name =
+                    variable_name.ok_or(Error::Other("A local variable has no name"))?;
+                info.write_value(range.start);
+                info.write_value(length);
+                info.write_value(this.constant_pool.put_utf8(variable_name.as_str())?);
+                info.write_value(this.constant_pool.put_utf8(descriptor)?);
+                info.write_value(id.index);
+            }
+            Ok(())
+        })
+    }
+
+    fn stack_map_frame(&mut self, out: &mut Vec<u8>, frame: &StackMapFrame) -> Result<(), Error> {
+        match frame {
+            &StackMapFrame::SameFrame { offset_delta } => {
+                if let Ok(frame_type @ 0..=63) = u8::try_from(offset_delta) {
+                    out.write_value(frame_type);
+                } else {
+                    out.write_value(251u8);
+                    out.write_value(offset_delta);
+                }
+            }
+            StackMapFrame::SameLocals1StackItemFrame {
+                offset_delta,
+                stack,
+            } => {
+                if let Ok(delta @ 0..=63) = u8::try_from(*offset_delta) {
+                    out.write_value(64 + delta);
+                } else {
+                    out.write_value(247u8);
+                    out.write_value(*offset_delta);
+                }
+                self.verification_type(out, stack)?;
+            }
+            &StackMapFrame::ChopFrame {
+                offset_delta,
+                chop_count,
+            } => {
+                if !(1..=3).contains(&chop_count) {
+                    return Err(Error::Other("A chop frame must chop 1 to 3 locals"));
+                }
+                out.write_value(251 - chop_count);
+                out.write_value(offset_delta);
+            }
+            StackMapFrame::AppendFrame {
+                offset_delta,
+                locals,
+            } => {
+                let frame_type = match locals.len() {
+                    1 => 252u8,
+                    2 => 253,
+                    3 => 254,
+                    _ => return Err(Error::Other("An append frame must append 1 to 3 locals")),
+                };
+                out.write_value(frame_type);
+                out.write_value(*offset_delta);
+                for local in locals {
+                    self.verification_type(out, local)?;
+                }
+            }
+            StackMapFrame::FullFrame {
+                offset_delta,
+                locals,
+                stack,
+            } => {
+                out.write_value(255u8);
+                out.write_value(*offset_delta);
+                for types in [locals, stack] {
+                    out.write_value(count(types.len(), "full_frame")?);
+                    for verification_type in types {
+                        self.verification_type(out, verification_type)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn verification_type(
+        &mut self,
+        out: &mut Vec<u8>,
+        verification_type: &VerificationType,
+    ) -> Result<(), Error> {
+        match verification_type {
+            VerificationType::TopVariable => out.write_value(0u8),
+            VerificationType::IntegerVariable => out.write_value(1u8),
+            VerificationType::FloatVariable => out.write_value(2u8),
+            VerificationType::DoubleVariable => out.write_value(3u8),
+            VerificationType::LongVariable => out.write_value(4u8),
+            VerificationType::NullVariable => out.write_value(5u8),
+            VerificationType::UninitializedThisVariable => out.write_value(6u8),
+            VerificationType::ObjectVariable(class_ref) => {
+                out.write_value(7u8);
+                out.write_value(self.constant_pool.put_class(class_ref)?);
+            }
+            &VerificationType::UninitializedVariable { offset } => {
+                out.write_value(8u8);
+                out.write_value(offset);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Computes the offset of a branch at `pc` to `target`.
+fn branch_offset(pc: ProgramCounter, target: ProgramCounter) -> i32 {
+    i32::from(u16::from(target)) - i32::from(u16::from(pc))
+}
@@ -0,0 +1,48 @@
+use crate::jvm::{
+    class::constant_pool::{BadConstantPoolIndex, BuildError},
+    code::ProgramCounter,
+    parsing::EncodeError,
+};
+
+/// An error that occurs when writing a Java class file.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// An error annotated with the member of the class where it occurs.
+    #[error("{source} in {member}")]
+    Located {
+        /// The error.
+        source: Box<Error>,
+        /// The member being written, e.g., `com/foo/Bar.baz()V`.
+        member: String,
+    },
+    /// An error annotated with the instruction where it occurs.
+    #[error("{source} at pc {pc}")]
+    Instruction {
+        /// The error.
+        source: Box<Error>,
+        /// The program counter of the instruction.
+        pc: ProgramCounter,
+    },
+    /// An entry cannot be added to the constant pool.
+    #[error("Failed to build the constant pool: {0}")]
+    ConstantPool(#[from] BuildError),
+    /// The constant pool to write the class with refers to an invalid index.
+    #[error("Error when compacting the constant pool: {0}")]
+    BadConstantPoolIndex(#[from] BadConstantPoolIndex),
+    /// A custom attribute cannot be encoded.
+    #[error("Failed to encode custom attribute: {0}")]
+    CustomAttribute(#[from] EncodeError),
+    /// A table has more entries than its length can represent.
+    #[error("Too many entries in {0}")]
+    TooManyEntries(&'static str),
+    /// An attribute is longer than 4 GiB.
+    #[error("The attribute {0} is too long")]
+    AttributeTooLong(String),
+    /// An unrecognized attribute would refer to the wrong constants after the constant pool is
+    /// compacted.
+    #[error("The unrecognized attribute {0} cannot be written with a compacted constant pool")]
+    OpaqueAttribute(String),
+    /// The class cannot be represented in the class file format.
+    #[error("Unrepresentable class: {0}")]
+    Other(&'static str),
+}
@@ -0,0 +1,38 @@
+use crate::jvm::Field;
+
+use super::{annotation::TargetLocation, writer_utils::ValueWriterExt, Attributes, Error, Writer};
+
+impl Writer<'_> {
+    pub(super) fn field(&mut self, out: &mut Vec<u8>, field: &Field) -> Result<(), Error> {
+        out.write_value(field.access_flags.bits());
+        out.write_value(self.constant_pool.put_utf8(field.name.as_str())?);
+        out.write_value(self.constant_pool.put_utf8(field.field_type.descriptor())?);
+        let mut attributes = Attributes::default();
+        if let Some(value) = &field.constant_value {
+            self.attribute(&mut attributes, "ConstantValue", |this, info| {
+                info.write_value(this.constant_pool.put_constant_value(value)?);
+                Ok(())
+            })?;
+        }
+        self.signature(&mut attributes, field.signature.as_ref())?;
+        self.annotation_attributes(
+            &mut attributes,
+            &field.runtime_visible_annotations,
+            &field.runtime_invisible_annotations,
+        )?;
+        self.type_annotation_attributes(
+            &mut attributes,
+            &field.runtime_visible_type_annotations,
+            &field.runtime_invisible_type_annotations,
+            TargetLocation::Field,
+        )?;
+        self.markers(&mut attributes, field.is_synthetic, field.is_deprecated)?;
+        self.extra_attributes(
+            &mut attributes,
+            &field.free_attributes,
+            &field.custom_attributes,
+        )?;
+        attributes.write_to(out);
+        Ok(())
+    }
+}
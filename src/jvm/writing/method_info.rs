@@ -0,0 +1,101 @@
+use crate::jvm::{Annotation, Method};
+
+use super::{
+    annotation::TargetLocation, count, writer_utils::ValueWriterExt, Attributes, Error, Writer,
+};
+
+impl Writer<'_> {
+    pub(super) fn method(&mut self, out: &mut Vec<u8>, method: &Method) -> Result<(), Error> {
+        out.write_value(method.access_flags.bits());
+        out.write_value(self.constant_pool.put_utf8(method.name.as_str())?);
+        out.write_value(
+            self.constant_pool
+                .put_utf8(method.descriptor.descriptor())?,
+        );
+        let mut attributes = Attributes::default();
+        if let Some(body) = &method.body {
+            self.attribute(&mut attributes, "Code", |this, info| this.code(info, body))?;
+        }
+        if !method.exceptions.is_empty() {
+            self.attribute(&mut attributes, "Exceptions", |this, info| {
+                info.write_value(count(method.exceptions.len(), "Exceptions")?);
+                for exception in &method.exceptions {
+                    info.write_value(this.constant_pool.put_class(exception)?);
+                }
+                Ok(())
+            })?;
+        }
+        self.signature(&mut attributes, method.signature.as_ref())?;
+        if let Some(parameters) = &method.parameters {
+            self.attribute(&mut attributes, "MethodParameters", |this, info| {
+                let parameters_count = u8::try_from(parameters.len())
+                    .map_err(|_| Error::TooManyEntries("MethodParameters"))?;
+                info.write_value(parameters_count);
+                for parameter in parameters {
+                    let name_index = parameter
+                        .name
+                        .as_ref()
+                        .map(|it| this.constant_pool.put_utf8(it.as_str()))
+                        .transpose()?;
+                    info.write_value(name_index.unwrap_or(0));
+                    info.write_value(parameter.access_flags.bits());
+                }
+                Ok(())
+            })?;
+        }
+        if let Some(value) = &method.annotation_default {
+            self.attribute(&mut attributes, "AnnotationDefault", |this, info| {
+                this.element_value(info, value)
+            })?;
+        }
+        self.annotation_attributes(
+            &mut attributes,
+            &method.runtime_visible_annotations,
+            &method.runtime_invisible_annotations,
+        )?;
+        for (name, parameters) in [
+            (
+                "RuntimeVisibleParameterAnnotations",
+                &method.runtime_visible_parameter_annotations,
+            ),
+            (
+                "RuntimeInvisibleParameterAnnotations",
+                &method.runtime_invisible_parameter_annotations,
+            ),
+        ] {
+            if !parameters.is_empty() {
+                self.attribute(&mut attributes, name, |this, info| {
+                    this.parameter_annotations(info, parameters)
+                })?;
+            }
+        }
+        self.type_annotation_attributes(
+            &mut attributes,
+            &method.runtime_visible_type_annotations,
+            &method.runtime_invisible_type_annotations,
+            TargetLocation::Method,
+        )?;
+        self.markers(&mut attributes, method.is_synthetic, method.is_deprecated)?;
+        self.extra_attributes(
+            &mut attributes,
+            &method.free_attributes,
+            &method.custom_attributes,
+        )?;
+        attributes.write_to(out);
+        Ok(())
+    }
+
+    fn parameter_annotations(
+        &mut self,
+        out: &mut Vec<u8>,
+        parameters: &[Vec<Annotation>],
+    ) -> Result<(), Error> {
+        let num_parameters =
+            u8::try_from(parameters.len()).map_err(|_| Error::TooManyEntries("parameters"))?;
+        out.write_value(num_parameters);
+        for annotations in parameters {
+            self.annotations(out, annotations)?;
+        }
+        Ok(())
+    }
+}
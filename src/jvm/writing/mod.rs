@@ -0,0 +1,556 @@
+//! The writing logic for the JVM class file format.
+//!
+//! A [`Class`](crate::jvm::Class) does not keep the details of the class file it is parsed from,
+//! so the writer chooses them as follows:
+//! - The constant pool contains the entries the class refers to, in the order they are first
+//!   referred to, optionally following the entries of an existing constant pool (see
+//!   [`WritingOptions::with_constant_pool`]).
+//!   If an operand of `ldc` would be above index 255, the operands of `ldc` are moved to the
+//!   front, unless the indices of the existing constant pool are kept.
+//! - The known attributes are written in a fixed order, followed by the unrecognized and the
+//!   custom attributes in their original order.
+//! - Methods are referred to by `CONSTANT_InterfaceMethodref` entries in `invokeinterface`, and
+//!   in `invokestatic` and `invokespecial` if the method is declared in the class being written
+//!   and the class is an interface.
+//! - The `target_type` of a type annotation is inferred from where the annotation is and, for
+//!   the annotations on instructions, the annotated instruction.
+//! - The stack map frames are written in their most compact form.
+mod annotation;
+mod class_file;
+mod code;
+mod errors;
+mod field_info;
+mod method_info;
+mod module;
+mod options;
+pub(crate) mod writer_utils;
+
+use crate::jvm::{
+    class::constant_pool::ConstantPoolBuilder,
+    parsing::{AttributeRegistry, CustomAttribute},
+};
+pub use errors::Error;
+pub use options::WritingOptions;
+use writer_utils::ValueWriterExt;
+
+/// Context used to write a class file.
+#[derive(Debug)]
+struct Writer<'a> {
+    /// The constant pool the entries are added to.
+    constant_pool: ConstantPoolBuilder,
+    /// The codecs of the custom attributes.
+    attribute_registry: &'a AttributeRegistry,
+    /// The binary name of the class being written.
+    current_class_binary_name: &'a str,
+    /// Whether the class being written is an interface.
+    is_interface: bool,
+    /// The indices in the `BootstrapMethods` attribute of the bootstrap methods of the class.
+    bootstrap_method_indices: Vec<u16>,
+    /// Whether the constant pool is going to be compacted.
+    compacting: bool,
+    /// Whether the operands of `ldc` above 255 are going to be moved to the front of the constant
+    /// pool.
+    relocating_ldc_operands: bool,
+}
+
+/// The `attributes` table of a structure.
+#[derive(Debug, Default)]
+struct Attributes {
+    count: u16,
+    bytes: Vec<u8>,
+}
+
+impl Attributes {
+    fn push(&mut self, attribute_info: &[u8]) -> Result<(), Error> {
+        self.count = self
+            .count
+            .checked_add(1)
+            .ok_or(Error::TooManyEntries("attributes"))?;
+        self.bytes.extend_from_slice(attribute_info);
+        Ok(())
+    }
+
+    fn write_to(self, out: &mut Vec<u8>) {
+        out.write_value(self.count);
+        out.extend(self.bytes);
+    }
+}
+
+impl Writer<'_> {
+    /// Adds the attribute `name` whose content is written by `write_info`.
+    fn attribute<F>(
+        &mut self,
+        attributes: &mut Attributes,
+        name: &str,
+        write_info: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Self, &mut Vec<u8>) -> Result<(), Error>,
+    {
+        let name_index = self.constant_pool.put_utf8(name)?;
+        let mut info = Vec::new();
+        write_info(self, &mut info)?;
+        let length =
+            u32::try_from(info.len()).map_err(|_| Error::AttributeTooLong(name.to_owned()))?;
+        let mut attribute_info = Vec::with_capacity(info.len() + 6);
+        attribute_info.write_value(name_index);
+        attribute_info.write_value(length);
+        attribute_info.extend(info);
+        attributes.push(&attribute_info)
+    }
+
+    /// Adds the `Synthetic` and `Deprecated` attributes.
+    fn markers(
+        &mut self,
+        attributes: &mut Attributes,
+        is_synthetic: bool,
+        is_deprecated: bool,
+    ) -> Result<(), Error> {
+        if is_synthetic {
+            self.attribute(attributes, "Synthetic", |_, _| Ok(()))?;
+        }
+        if is_deprecated {
+            self.attribute(attributes, "Deprecated", |_, _| Ok(()))?;
+        }
+        Ok(())
+    }
+
+    /// Adds the `Signature` attribute.
+    fn signature(
+        &mut self,
+        attributes: &mut Attributes,
+        signature: Option<&String>,
+    ) -> Result<(), Error> {
+        if let Some(signature) = signature {
+            self.attribute(attributes, "Signature", |this, info| {
+                info.write_value(this.constant_pool.put_utf8(signature.as_str())?);
+                Ok(())
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Adds the unrecognized attributes as they are, and the custom attributes.
+    fn extra_attributes(
+        &mut self,
+        attributes: &mut Attributes,
+        free_attributes: &[(String, Vec<u8>)],
+        custom_attributes: &[CustomAttribute],
+    ) -> Result<(), Error> {
+        for (name, bytes) in free_attributes {
+            if self.compacting {
+                return Err(Error::OpaqueAttribute(name.clone()));
+            }
+            self.attribute(attributes, name, |_, info| {
+                info.extend_from_slice(bytes);
+                Ok(())
+            })?;
+        }
+        for attribute in custom_attributes {
+            let attribute_info = self
+                .attribute_registry
+                .encode(attribute, &mut self.constant_pool)?;
+            attributes.push(&attribute_info)?;
+        }
+        Ok(())
+    }
+}
+
+/// Converts the length of `table` to a `u16`.
+fn count(len: usize, table: &'static str) -> Result<u16, Error> {
+    u16::try_from(len).map_err(|_| Error::TooManyEntries(table))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        jvm::{
+            annotation::{ElementValue, TargetInfo, TypePathElement},
+            bytecode::lazy::LazyClass,
+            class::{constant_pool::Entry, BootstrapMethod, MethodHandle},
+            code::{
+                Instruction, LineNumberTableEntry, LocalVariableId, LocalVariableTableEntry,
+                StackMapFrame, VerificationType,
+            },
+            interop::jasmin,
+            parsing::{self, AttributeCodec, Context},
+            references::ClassRef,
+            Annotation, Class, ConstantValue, JavaString, TypeAnnotation,
+        },
+        tests::method_ref,
+        types::{
+            field_type::{FieldType, PrimitiveType},
+            method_descriptor::ReturnType,
+        },
+    };
+
+    const SAMPLE: &str = r#"
+        .bytecode 52.0
+        .source Sample.java
+        .class public super Sample
+        .super java/lang/Object
+        .implements java/lang/Runnable
+        .field private static final GREETING Ljava/lang/String; = "Hello"
+        .field private count J
+
+        .method public <init>()V
+            .limit stack 1
+            .limit locals 1
+            aload_0
+            invokespecial java/lang/Object/<init>()V
+            return
+        .end method
+
+        .method public run()V
+            .limit stack 4
+            .limit locals 300
+        Start:
+            ldc "Only in run"
+            astore 299
+            aload_0
+            dup
+            getfield Sample/count J
+            ldc2_w 42
+            ladd
+            putfield Sample/count J
+            iconst_3
+            newarray int
+            checkcast [I
+            pop
+            iinc 1 100
+            iload_1
+            lookupswitch
+                1:Start
+                10:Done
+                default Done
+            invokedynamic 0 run:()Ljava/lang/Runnable;
+            pop
+        Done:
+            return
+            .catch java/lang/RuntimeException from Start to Done using Done
+        .end method
+    "#;
+
+    /// An attribute holding a string in a `CONSTANT_Utf8` entry.
+    struct NoteCodec;
+
+    impl AttributeCodec for NoteCodec {
+        type Value = String;
+
+        fn name(&self) -> &'static str {
+            "Note"
+        }
+
+        fn decode(&self, bytes: &[u8], ctx: &Context) -> Result<Self::Value, parsing::Error> {
+            let [high, low] = bytes else {
+                return Err(parsing::Error::Other("Invalid attribute length"));
+            };
+            let index = u16::from_be_bytes([*high, *low]);
+            Ok(ctx.constant_pool.get_str(index)?.to_owned())
+        }
+
+        fn encode(
+            &self,
+            value: &Self::Value,
+            constant_pool: &mut ConstantPoolBuilder,
+        ) -> Result<Vec<u8>, crate::jvm::class::constant_pool::BuildError> {
+            constant_pool
+                .put_utf8(value.clone())
+                .map(|it| it.to_be_bytes().to_vec())
+        }
+    }
+
+    fn registry() -> Arc<AttributeRegistry> {
+        Arc::new(AttributeRegistry::new().with_codec(NoteCodec))
+    }
+
+    fn marker(name: &str) -> FieldType {
+        FieldType::Object(ClassRef::new(name))
+    }
+
+    fn sample_class() -> Class {
+        let mut class = jasmin::read(SAMPLE).unwrap();
+        class.bootstrap_methods.push(BootstrapMethod {
+            method: MethodHandle::RefInvokeStatic(method_ref(
+                "Factory",
+                "create",
+                "(Ljava/lang/invoke/MethodHandles$Lookup;Ljava/lang/String;Ljava/lang/invoke/MethodType;Ljava/lang/String;)Ljava/lang/invoke/CallSite;",
+            )),
+            arguments: vec![ConstantValue::String(JavaString::Utf8(
+                "Bootstrap argument".to_owned(),
+            ))],
+        });
+        class.runtime_visible_annotations.push(Annotation {
+            annotation_type: marker("Marker"),
+            element_value_pairs: vec![
+                (
+                    "int".to_owned(),
+                    ElementValue::Primitive(PrimitiveType::Int, ConstantValue::Integer(1)),
+                ),
+                (
+                    "string".to_owned(),
+                    ElementValue::String(ConstantValue::String(JavaString::Utf8("s".to_owned()))),
+                ),
+                (
+                    "enum".to_owned(),
+                    ElementValue::EnumConstant {
+                        enum_type_name: "LKind;".to_owned(),
+                        const_name: "ONE".to_owned(),
+                    },
+                ),
+                (
+                    "class".to_owned(),
+                    ElementValue::Class {
+                        return_descriptor: ReturnType::Void,
+                    },
+                ),
+                (
+                    "array".to_owned(),
+                    ElementValue::Array(vec![ElementValue::AnnotationInterface(Annotation {
+                        annotation_type: marker("Nested"),
+                        element_value_pairs: Vec::new(),
+                    })]),
+                ),
+            ],
+        });
+        class.custom_attributes.push(parsing::CustomAttribute::new(
+            "Note",
+            "Only in the attribute".to_owned(),
+        ));
+        class.fields[1]
+            .runtime_invisible_type_annotations
+            .push(TypeAnnotation {
+                annotation_type: marker("Nullable"),
+                target_info: TargetInfo::Empty,
+                target_path: vec![TypePathElement::Array, TypePathElement::TypeArgument(1)],
+                element_value_pairs: Vec::new(),
+            });
+
+        let body = class.methods[1].body.as_mut().unwrap();
+        body.line_number_table = Some(vec![LineNumberTableEntry {
+            start_pc: 0.into(),
+            line_number: 7,
+        }]);
+        body.local_variable_table = Some(
+            [(
+                LocalVariableId {
+                    effective_range: 0.into()..5.into(),
+                    index: 299,
+                },
+                LocalVariableTableEntry {
+                    name: Some("note".to_owned()),
+                    var_type: Some(marker("java/lang/String")),
+                    signature: Some("Ljava/lang/String;".to_owned()),
+                },
+            )]
+            .into_iter()
+            .collect(),
+        );
+        body.stack_map_table = Some(vec![
+            StackMapFrame::SameLocals1StackItemFrame {
+                offset_delta: 100,
+                stack: VerificationType::ObjectVariable(ClassRef::new("java/lang/Throwable")),
+            },
+            StackMapFrame::SameFrame { offset_delta: 3 },
+        ]);
+        let checkcast_pc = body
+            .instructions
+            .iter()
+            .find(|(_, it)| matches!(it, Instruction::CheckCast(_)))
+            .map(|(pc, _)| *pc)
+            .unwrap();
+        body.runtime_visible_type_annotations.push(TypeAnnotation {
+            annotation_type: marker("NonNull"),
+            target_info: TargetInfo::TypeArgument {
+                offset: checkcast_pc,
+                index: 0,
+            },
+            target_path: Vec::new(),
+            element_value_pairs: Vec::new(),
+        });
+        class
+    }
+
+    /// Creates a class whose `ldc` instructions load more constants than fit below index 256.
+    fn many_constants_class() -> Class {
+        let loads = (0..200)
+            .map(|it| format!("ldc \"Constant {it}\"\npop"))
+            .join("\n");
+        let source = format!(
+            "
+            .class public ManyConstants
+            .super java/lang/Object
+            .method public static load()V
+                .limit stack 1
+                .limit locals 0
+                {loads}
+                return
+            .end method
+            "
+        );
+        jasmin::read(&source).unwrap()
+    }
+
+    fn parse(bytes: &[u8]) -> Class {
+        Class::from_reader_with_registry(bytes, registry()).unwrap()
+    }
+
+    fn utf8_constants(bytes: &[u8]) -> Vec<String> {
+        let lazy_class = LazyClass::parse(bytes).unwrap();
+        lazy_class
+            .constant_pool()
+            .iter()
+            .filter_map(|(_, entry)| match entry {
+                Entry::Utf8(JavaString::Utf8(it)) => Some(it.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trip() {
+        let class = sample_class();
+        let options = WritingOptions::new().with_attribute_registry(registry());
+        let bytes = class.to_bytes_with_options(&options).unwrap();
+        let reparsed = parse(&bytes);
+        assert_eq!(format!("{reparsed:?}"), format!("{class:?}"));
+        assert_eq!(reparsed.to_bytes_with_options(&options).unwrap(), bytes);
+    }
+
+    #[test]
+    fn ldc_operands_above_255() {
+        let class = many_constants_class();
+        let bytes = class.to_bytes().unwrap();
+        let constant_pool = LazyClass::parse(&bytes).unwrap().constant_pool().clone();
+        assert!(constant_pool.constant_pool_count() > 256);
+        assert_eq!(format!("{:?}", parse(&bytes)), format!("{class:?}"));
+
+        // The operands are moved to the front after compacting an existing constant pool.
+        let mut seed = ConstantPoolBuilder::default();
+        for it in 0..200 {
+            seed.put_utf8(format!("Constant {it}")).unwrap();
+        }
+        let options = WritingOptions::new().with_constant_pool(seed.build());
+        let bytes = class.to_bytes_with_options(&options).unwrap();
+        assert_eq!(format!("{:?}", parse(&bytes)), format!("{class:?}"));
+
+        // The indices of an existing constant pool are kept without compaction.
+        let options = options.with_compaction(false);
+        assert!(matches!(
+            class.to_bytes_with_options(&options),
+            Err(Error::Located { .. })
+        ));
+    }
+
+    #[test]
+    fn deterministic_output() {
+        let class = sample_class();
+        let options = WritingOptions::new()
+            .with_attribute_registry(registry())
+            .with_deterministic_output(true);
+        let bytes = class.to_bytes_with_options(&options).unwrap();
+        assert_eq!(class.to_bytes_with_options(&options).unwrap(), bytes);
+        assert_eq!(
+            sample_class().to_bytes_with_options(&options).unwrap(),
+            bytes
+        );
+
+        // A constant pool with the entries in another order does not change the output.
+        let mut seed = ConstantPoolBuilder::default();
+        seed.put_utf8("Unrelated").unwrap();
+        seed.put_class(&ClassRef::new("java/lang/Object")).unwrap();
+        let seed = seed.build();
+        let seeded = options.clone().with_constant_pool(seed.clone());
+        assert_eq!(class.to_bytes_with_options(&seeded).unwrap(), bytes);
+        let seeded = seeded.with_compaction(false);
+        assert_eq!(class.to_bytes_with_options(&seeded).unwrap(), bytes);
+
+        let options = options
+            .with_deterministic_output(false)
+            .with_constant_pool(seed);
+        assert_ne!(class.to_bytes_with_options(&options).unwrap(), bytes);
+    }
+
+    #[test]
+    fn deterministic_output_with_many_constants() {
+        let class = many_constants_class();
+        let options = WritingOptions::new().with_deterministic_output(true);
+        let bytes = class.to_bytes_with_options(&options).unwrap();
+        assert_eq!(
+            many_constants_class()
+                .to_bytes_with_options(&options)
+                .unwrap(),
+            bytes
+        );
+        assert_eq!(format!("{:?}", parse(&bytes)), format!("{class:?}"));
+
+        let mut seed = ConstantPoolBuilder::default();
+        for it in (0..200).rev() {
+            seed.put_string(JavaString::Utf8(format!("Constant {it}")))
+                .unwrap();
+        }
+        let seeded = options.with_constant_pool(seed.build());
+        assert_eq!(class.to_bytes_with_options(&seeded).unwrap(), bytes);
+        let seeded = seeded.with_compaction(false);
+        assert_eq!(class.to_bytes_with_options(&seeded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn compact_orphaned_constants() {
+        let options = WritingOptions::new().with_attribute_registry(registry());
+        let bytes = sample_class().to_bytes_with_options(&options).unwrap();
+        let constant_pool = LazyClass::parse(&bytes).unwrap().constant_pool().clone();
+        let mut class = parse(&bytes);
+        class.methods.retain(|it| &*it.name != "run");
+        class.runtime_visible_annotations.clear();
+
+        let loose = class
+            .to_bytes_with_options(
+                &options
+                    .clone()
+                    .with_constant_pool(constant_pool.clone())
+                    .with_compaction(false),
+            )
+            .unwrap();
+        let compact = class
+            .to_bytes_with_options(&options.clone().with_constant_pool(constant_pool))
+            .unwrap();
+        assert!(compact.len() < loose.len());
+        for bytes in [&loose, &compact] {
+            assert_eq!(format!("{:?}", parse(bytes)), format!("{class:?}"));
+        }
+        assert!(utf8_constants(&loose).contains(&"Only in run".to_owned()));
+        let constants = utf8_constants(&compact);
+        assert!(!constants.contains(&"Only in run".to_owned()));
+        assert!(!constants.contains(&"Marker".to_owned()));
+        // The constants of the bootstrap methods and the custom attributes are kept.
+        assert!(constants.contains(&"Bootstrap argument".to_owned()));
+        assert!(constants.contains(&"Only in the attribute".to_owned()));
+    }
+
+    #[test]
+    fn reject_opaque_attributes_when_compacting() {
+        let error = sample_class().to_bytes().unwrap_err();
+        assert!(matches!(error, Error::CustomAttribute(_)));
+
+        let mut class = sample_class();
+        class.custom_attributes.clear();
+        class
+            .free_attributes
+            .push(("Opaque".to_owned(), vec![0, 1]));
+        let bytes = class.to_bytes().unwrap();
+        let constant_pool = LazyClass::parse(&bytes).unwrap().constant_pool().clone();
+        let options = WritingOptions::new().with_constant_pool(constant_pool);
+        assert!(matches!(
+            class.to_bytes_with_options(&options),
+            Err(Error::OpaqueAttribute(name)) if name == "Opaque"
+        ));
+        let options = options.with_compaction(false);
+        assert_eq!(class.to_bytes_with_options(&options).unwrap(), bytes);
+    }
+}
@@ -0,0 +1,63 @@
+use crate::jvm::{references::ModuleRef, Module};
+
+use super::{count, writer_utils::ValueWriterExt, Error, Writer};
+
+impl Writer<'_> {
+    pub(super) fn module(&mut self, out: &mut Vec<u8>, module: &Module) -> Result<(), Error> {
+        let module_ref = ModuleRef {
+            name: module.name.clone(),
+        };
+        out.write_value(self.constant_pool.put_module(&module_ref)?);
+        out.write_value(module.flags.bits());
+        let version = self.optional_utf8(module.version.as_ref())?;
+        out.write_value(version);
+        out.write_value(count(module.requires.len(), "requires")?);
+        for require in &module.requires {
+            out.write_value(self.constant_pool.put_module(&require.module)?);
+            out.write_value(require.flags.bits());
+            let version = self.optional_utf8(require.version.as_ref())?;
+            out.write_value(version);
+        }
+        out.write_value(count(module.exports.len(), "exports")?);
+        for export in &module.exports {
+            out.write_value(self.constant_pool.put_package(&export.package)?);
+            out.write_value(export.flags.bits());
+            self.modules(out, &export.to)?;
+        }
+        out.write_value(count(module.opens.len(), "opens")?);
+        for open in &module.opens {
+            out.write_value(self.constant_pool.put_package(&open.package)?);
+            out.write_value(open.flags.bits());
+            self.modules(out, &open.to)?;
+        }
+        out.write_value(count(module.uses.len(), "uses")?);
+        for service in &module.uses {
+            out.write_value(self.constant_pool.put_class(service)?);
+        }
+        out.write_value(count(module.provides.len(), "provides")?);
+        for provide in &module.provides {
+            out.write_value(self.constant_pool.put_class(&provide.service)?);
+            out.write_value(count(provide.with.len(), "provides_with")?);
+            for implementation in &provide.with {
+                out.write_value(self.constant_pool.put_class(implementation)?);
+            }
+        }
+        Ok(())
+    }
+
+    fn modules(&mut self, out: &mut Vec<u8>, modules: &[ModuleRef]) -> Result<(), Error> {
+        out.write_value(count(modules.len(), "modules")?);
+        for module in modules {
+            out.write_value(self.constant_pool.put_module(module)?);
+        }
+        Ok(())
+    }
+
+    /// Adds the `CONSTANT_Utf8` entry of `value` if any, or returns `0` otherwise.
+    fn optional_utf8(&mut self, value: Option<&String>) -> Result<u16, Error> {
+        let index = value
+            .map(|it| self.constant_pool.put_utf8(it.as_str()))
+            .transpose()?;
+        Ok(index.unwrap_or(0))
+    }
+}
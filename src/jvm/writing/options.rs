@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use crate::jvm::{class::ConstantPool, parsing::AttributeRegistry};
+
+/// Options that control how class files are written.
+#[derive(Debug, Clone)]
+pub struct WritingOptions {
+    pub(super) attribute_registry: Arc<AttributeRegistry>,
+    pub(super) constant_pool: Option<ConstantPool>,
+    pub(super) compact: bool,
+    pub(super) deterministic: bool,
+}
+
+impl Default for WritingOptions {
+    fn default() -> Self {
+        Self {
+            attribute_registry: Arc::default(),
+            constant_pool: None,
+            compact: true,
+            deterministic: false,
+        }
+    }
+}
+
+impl WritingOptions {
+    /// Creates options for writing a class with a fresh constant pool and no custom attributes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Encodes the custom attributes with the codecs in `attribute_registry`.
+    #[must_use]
+    pub fn with_attribute_registry(mut self, attribute_registry: Arc<AttributeRegistry>) -> Self {
+        self.attribute_registry = attribute_registry;
+        self
+    }
+
+    /// Writes the class with `constant_pool`, typically the one the class is parsed with (see
+    /// [`LazyClass::constant_pool`](crate::jvm::bytecode::lazy::LazyClass::constant_pool)).
+    /// The existing entries are reused, and the new ones are appended.
+    #[must_use]
+    pub fn with_constant_pool(mut self, constant_pool: ConstantPool) -> Self {
+        self.constant_pool = Some(constant_pool);
+        self
+    }
+
+    /// Sets whether to drop the entries of the constant pool given with
+    /// [`WritingOptions::with_constant_pool`] that the class no longer refers to, e.g., after
+    /// removing a method. Enabled by default.
+    /// The remaining entries are renumbered with [`ConstantPool::compact`], and the class,
+    /// including its attributes and the arguments of its bootstrap methods, is written with the
+    /// new indices.
+    /// Since the unrecognized attributes may refer to the constant pool in unknown ways, writing
+    /// a class having any of them fails with
+    /// [`Error::OpaqueAttribute`](super::Error::OpaqueAttribute) when compaction is enabled.
+    #[must_use]
+    pub const fn with_compaction(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Sets whether the output only depends on the class being written. Disabled by default.
+    /// If enabled, the constant pool given with [`WritingOptions::with_constant_pool`] is
+    /// ignored, so the entries are always in the order the class first refers to them, with the
+    /// operands of `ldc` moved to the front if needed, and writing equal classes gives identical
+    /// bytes.
+    /// The attributes are written in the same order either way.
+    #[must_use]
+    pub const fn with_deterministic_output(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+}
@@ -0,0 +1,34 @@
+use crate::jvm::code::ProgramCounter;
+
+pub(crate) trait ValueWriterExt {
+    fn write_value<T: WriteBytes>(&mut self, value: T);
+}
+pub(crate) trait WriteBytes {
+    fn write_bytes(self, buf: &mut Vec<u8>);
+}
+
+impl ValueWriterExt for Vec<u8> {
+    fn write_value<T: WriteBytes>(&mut self, value: T) {
+        value.write_bytes(self);
+    }
+}
+
+impl WriteBytes for ProgramCounter {
+    fn write_bytes(self, buf: &mut Vec<u8>) {
+        u16::from(self).write_bytes(buf);
+    }
+}
+
+macro_rules! impl_write_bytes_for {
+    ($($t:ty),*) => {
+        $(
+            impl WriteBytes for $t {
+                fn write_bytes(self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_be_bytes());
+                }
+            }
+        )*
+    };
+}
+
+impl_write_bytes_for![u8, u16, u32, i8, i16, i32];
@@ -11,11 +11,21 @@
 #![deny(rustdoc::broken_intra_doc_links)]
 
 //! Welcome to `MokaPot`, a library to facilitate the analysis of JVM bytecode.
+//! ## Iteration order
+//! The IR and the analysis results iterate in a deterministic order that does not depend on the
+//! platform or the run: the collections keyed by [`ProgramCounter`](jvm::code::ProgramCounter),
+//! [`Identifier`](ir::Identifier), or [`ClassRef`](jvm::references::ClassRef) are ordered by
+//! their keys, and the other sequences follow the order of the instructions or the input.
+//! Hash-based collections are only used for lookups that are not exposed for iteration.
 //! ## Features
 #![doc = document_features::document_features!()]
 
 pub mod analysis;
-
+pub mod decompiler;
+#[cfg(feature = "dex")]
+pub mod dex;
+#[doc(hidden)]
+pub mod fuzzing;
 pub mod ir;
 pub mod jvm;
 pub(crate) mod macros;
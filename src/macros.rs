@@ -1,16 +1,17 @@
 #![deny(meta_variable_misuse)]
 
 macro_rules! extract_attributes {
-    (for $attrs: ident in $env:literal {
+    (for $attrs: ident in $env:literal with $ctx: ident {
          $( let $var: ident: $attr: ident $(as $uw: ident)?, )*
          $( if let $var_true: ident: $attr_true: ident, )*
          $( match $attr_custom: pat => $var_custom: block, )*
-         else let $unrecognized:ident
+         else let $unrecognized:ident, $custom:ident
     }) => {
         use crate::jvm::parsing::attribute::Attribute;
         $( let mut $var = None; )*
         $( let mut $var_true = false; )*
         let mut $unrecognized = Vec::new();
+        let mut $custom = Vec::new();
         {
             for attr in $attrs {
                 match attr {
@@ -22,7 +23,7 @@ macro_rules! extract_attributes {
                             " in a ",
                             $env
                         );
-                        Err(Error::Other(message))?;
+                        $ctx.tolerate($env, Error::Other(message))?;
                     },
                 )*
                 $(
@@ -34,11 +35,15 @@ macro_rules! extract_attributes {
                     Attribute::Unrecognized(name, bytes) => {
                         $unrecognized.push((name, bytes));
                     }
+                    Attribute::Custom(it) => {
+                        $custom.push(it);
+                    }
                     unexpected => {
-                        Err(Error::UnexpectedAttribute(
+                        let error = Error::UnexpectedAttribute(
                             unexpected.name().to_owned(),
                             $env.to_owned()
-                        ))?;
+                        );
+                        $ctx.tolerate($env, error)?;
                     }
                 }
             }
@@ -1,7 +1,13 @@
 use proptest::prelude::*;
 
 use crate::{
-    jvm::{class, references::ClassRef, Class},
+    jvm::{
+        class,
+        code::{InstructionList, MethodBody},
+        field, method,
+        references::{ClassRef, MethodRef},
+        Annotation, Class, Field, Method,
+    },
     types::field_type::{FieldType, PrimitiveType},
 };
 
@@ -51,6 +57,8 @@ impl Default for Class {
             inner_classes: Vec::default(),
             enclosing_method: None,
             source_debug_extension: None,
+            compilation_id: None,
+            source_id: None,
             runtime_visible_annotations: Vec::default(),
             runtime_invisible_annotations: Vec::default(),
             runtime_visible_type_annotations: Vec::default(),
@@ -67,10 +75,94 @@ impl Default for Class {
             signature: None,
             record: None,
             free_attributes: Vec::default(),
+            custom_attributes: Vec::default(),
         }
     }
 }
 
+impl Default for Method {
+    fn default() -> Self {
+        Self {
+            access_flags: method::AccessFlags::empty(),
+            name: String::default(),
+            descriptor: "()V".parse().expect("The descriptor is invalid"),
+            owner: ClassRef::new(String::default()),
+            body: None,
+            exceptions: Vec::default(),
+            runtime_visible_annotations: Vec::default(),
+            runtime_invisible_annotations: Vec::default(),
+            runtime_visible_type_annotations: Vec::default(),
+            runtime_invisible_type_annotations: Vec::default(),
+            runtime_visible_parameter_annotations: Vec::default(),
+            runtime_invisible_parameter_annotations: Vec::default(),
+            annotation_default: None,
+            parameters: None,
+            is_synthetic: false,
+            is_deprecated: false,
+            signature: None,
+            free_attributes: Vec::default(),
+            custom_attributes: Vec::default(),
+        }
+    }
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Self {
+            access_flags: field::AccessFlags::empty(),
+            name: String::default(),
+            owner: ClassRef::new(String::default()),
+            field_type: FieldType::Base(PrimitiveType::Int),
+            constant_value: None,
+            is_synthetic: false,
+            is_deprecated: false,
+            signature: None,
+            runtime_visible_annotations: Vec::default(),
+            runtime_invisible_annotations: Vec::default(),
+            runtime_visible_type_annotations: Vec::default(),
+            runtime_invisible_type_annotations: Vec::default(),
+            free_attributes: Vec::default(),
+            custom_attributes: Vec::default(),
+        }
+    }
+}
+
+impl Default for MethodBody {
+    fn default() -> Self {
+        Self {
+            max_stack: 0,
+            max_locals: 0,
+            instructions: InstructionList::from([]),
+            exception_table: Vec::default(),
+            line_number_table: None,
+            character_range_table: None,
+            local_variable_table: None,
+            stack_map_table: None,
+            runtime_visible_type_annotations: Vec::default(),
+            runtime_invisible_type_annotations: Vec::default(),
+            free_attributes: Vec::default(),
+            custom_attributes: Vec::default(),
+        }
+    }
+}
+
+/// Creates an annotation of the type `binary_name` without any element.
+pub(crate) fn annotation(binary_name: &str) -> Annotation {
+    Annotation {
+        annotation_type: FieldType::Object(ClassRef::new(binary_name)),
+        element_value_pairs: Vec::default(),
+    }
+}
+
+/// Creates a reference to the method `name` with `descriptor` declared in `owner`.
+pub(crate) fn method_ref(owner: &str, name: &str, descriptor: &str) -> MethodRef {
+    MethodRef::new(
+        ClassRef::new(owner),
+        name,
+        descriptor.parse().expect("The descriptor is invalid"),
+    )
+}
+
 pub(crate) fn arb_identifier() -> impl Strategy<Value = String> {
     let arb_ident = prop::string::string_regex(r"[a-zA-Z][\w\$_]*").expect("The regex is invalid");
     prop::collection::vec(arb_ident, 1..10).prop_map(|v| v.join("/"))
@@ -1,7 +1,7 @@
 //! Non-generic JVM method descriptors.
 
 use itertools::Itertools;
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 use crate::{jvm::references::ClassRef, macros::see_jvm_spec};
 
@@ -17,7 +17,8 @@ use super::field_type::{FieldType, PrimitiveType};
 )]
 pub struct MethodDescriptor {
     /// The type of the parameters.
-    pub parameters_types: Vec<FieldType>,
+    /// Cloning a [`MethodDescriptor`] shares the types instead of copying them.
+    pub parameters_types: Arc<[FieldType]>,
     /// The return type.
     pub return_type: ReturnType,
 }
@@ -72,10 +73,7 @@ impl FromStr for MethodDescriptor {
             parameters_types.push(next_param);
         }
         let return_type = ReturnType::from_str(remaining)?;
-        Ok(Self {
-            parameters_types,
-            return_type,
-        })
+        Ok(Self::new(parameters_types, return_type))
     }
 }
 
@@ -105,6 +103,31 @@ impl ReturnType {
         }
     }
 }
+
+impl MethodDescriptor {
+    /// Creates a new [`MethodDescriptor`].
+    /// The parameter types are [interned](crate::types::intern_types) when the `intern` feature
+    /// is enabled.
+    pub fn new<P: AsRef<[FieldType]>>(parameters_types: P, return_type: ReturnType) -> Self {
+        Self {
+            parameters_types: crate::types::intern_types(parameters_types.as_ref()),
+            return_type,
+        }
+    }
+
+    /// Returns the JVM descriptor of the method.
+    #[must_use]
+    pub fn descriptor(&self) -> String {
+        format!(
+            "({}){}",
+            self.parameters_types
+                .iter()
+                .map(FieldType::descriptor)
+                .join(""),
+            self.return_type.descriptor()
+        )
+    }
+}
 #[cfg(test)]
 mod test {
     use super::*;
@@ -135,7 +158,8 @@ mod test {
             let parsed =
                 MethodDescriptor::from_str(&descriptor).expect("Failed to parse method descriptor");
             assert_eq!(parsed.return_type, ret);
-            assert_eq!(parsed.parameters_types, params);
+            assert_eq!(*parsed.parameters_types, *params);
+            assert_eq!(parsed.descriptor(), descriptor);
         }
 
         #[test]
@@ -152,6 +176,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn share_parameter_types() {
+        let parsed: MethodDescriptor = "(ILjava/lang/String;)V".parse().unwrap();
+        let created = MethodDescriptor::new(
+            [
+                FieldType::Base(PrimitiveType::Int),
+                FieldType::Object(ClassRef::new("java/lang/String")),
+            ],
+            ReturnType::Void,
+        );
+        assert_eq!(parsed, created);
+        #[cfg(feature = "intern")]
+        assert!(Arc::ptr_eq(
+            &parsed.parameters_types,
+            &created.parameters_types
+        ));
+    }
+
     #[test]
     fn empty_desc() {
         let descriptor = "";
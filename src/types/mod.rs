@@ -1,3 +1,111 @@
 //! Module containing the APIs for the JVM type system.
 pub mod field_type;
 pub mod method_descriptor;
+pub mod signature;
+
+use std::sync::Arc;
+
+use field_type::FieldType;
+
+/// Returns a shared string equal to `value`.
+///
+/// When the `intern` feature is enabled, equal strings share a single allocation kept for the
+/// lifetime of the process, which reduces the memory used by the names repeated across the
+/// loaded classes (e.g., `java/lang/Object`). Otherwise, a new allocation is made.
+///
+/// The interned strings are kept in a global table that is never shrunk, so it grows with every
+/// distinct string until the process exits, including the names of the classes that are no
+/// longer used. Processes loading classes from an unbounded set of sources should leave the
+/// feature disabled.
+#[must_use]
+pub fn intern(value: &str) -> Arc<str> {
+    #[cfg(feature = "intern")]
+    {
+        static INTERNED: interning::Table<str> = interning::Table::new();
+        INTERNED.get_or_insert(value)
+    }
+    #[cfg(not(feature = "intern"))]
+    Arc::from(value)
+}
+
+/// Returns a shared list of types equal to `types`, e.g., the parameter types of a
+/// [`MethodDescriptor`](method_descriptor::MethodDescriptor).
+///
+/// Like [`intern`], equal lists share a single allocation kept for the lifetime of the process
+/// when the `intern` feature is enabled, and the global table holding them grows without bound.
+#[must_use]
+pub fn intern_types(types: &[FieldType]) -> Arc<[FieldType]> {
+    #[cfg(feature = "intern")]
+    {
+        static INTERNED: interning::Table<[FieldType]> = interning::Table::new();
+        INTERNED.get_or_insert(types)
+    }
+    #[cfg(not(feature = "intern"))]
+    Arc::from(types)
+}
+
+#[cfg(feature = "intern")]
+mod interning {
+    use std::{
+        collections::HashSet,
+        hash::Hash,
+        sync::{Arc, LazyLock, PoisonError, RwLock},
+    };
+
+    /// A global table of interned values.
+    pub(super) struct Table<T: ?Sized>(LazyLock<RwLock<HashSet<Arc<T>>>>);
+
+    impl<T: ?Sized + Hash + Eq> Table<T>
+    where
+        for<'a> Arc<T>: From<&'a T>,
+    {
+        pub(super) const fn new() -> Self {
+            Self(LazyLock::new(RwLock::default))
+        }
+
+        pub(super) fn get_or_insert(&self, value: &T) -> Arc<T> {
+            if let Some(it) = self
+                .0
+                .read()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(value)
+            {
+                return Arc::clone(it);
+            }
+            let mut interned = self.0.write().unwrap_or_else(PoisonError::into_inner);
+            if let Some(it) = interned.get(value) {
+                return Arc::clone(it);
+            }
+            let it = Arc::from(value);
+            interned.insert(Arc::clone(&it));
+            it
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intern_preserves_value() {
+        let first = intern("java/lang/Object");
+        let second = intern("java/lang/Object");
+        assert_eq!(&*first, "java/lang/Object");
+        assert_eq!(first, second);
+        #[cfg(feature = "intern")]
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn intern_types_preserves_value() {
+        let types: Vec<FieldType> =
+            vec!["I".parse().unwrap(), "Ljava/lang/String;".parse().unwrap()];
+        let first = intern_types(&types);
+        let second = intern_types(&types);
+        assert_eq!(&*first, types.as_slice());
+        assert_eq!(first, second);
+        #[cfg(feature = "intern")]
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+}
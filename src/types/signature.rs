@@ -0,0 +1,404 @@
+//! Generic JVM type signatures.
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use itertools::Itertools;
+
+use super::{
+    field_type::{FieldType, PrimitiveType},
+    method_descriptor::InvalidDescriptor,
+};
+use crate::{jvm::references::ClassRef, macros::see_jvm_spec};
+
+/// A generic type, i.e., a `JavaTypeSignature`.
+#[doc = see_jvm_spec!(4, 7, 9, 1)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum TypeSignature {
+    /// A primitive type.
+    Base(PrimitiveType),
+    /// A class or interface type, possibly parameterized.
+    Class(ClassTypeSignature),
+    /// An array type.
+    Array(Box<TypeSignature>),
+    /// A type variable (e.g., `T`).
+    TypeVariable(String),
+}
+
+/// A class or interface type with its type arguments.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub struct ClassTypeSignature {
+    /// The class or interface.
+    pub class: ClassRef,
+    /// The type arguments of the class or interface.
+    /// The type arguments of the enclosing classes of an inner class are not retained.
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+/// A type argument of a parameterized type.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+pub enum TypeArgument {
+    /// An exact type (e.g., `List<String>`).
+    Exact(TypeSignature),
+    /// A wildcard with an upper bound (e.g., `List<? extends Number>`).
+    Extends(TypeSignature),
+    /// A wildcard with a lower bound (e.g., `List<? super Integer>`).
+    Super(TypeSignature),
+    /// An unbounded wildcard (e.g., `List<?>`).
+    Any,
+}
+
+/// A formal type parameter of a generic class or method.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TypeParameter {
+    /// The name of the type parameter.
+    pub name: String,
+    /// The class bound of the type parameter.
+    pub class_bound: Option<TypeSignature>,
+    /// The interface bounds of the type parameter.
+    pub interface_bounds: Vec<TypeSignature>,
+}
+
+/// The generic signature of a class, parsed from a [`class::Signature`](crate::jvm::class::Signature).
+#[doc = see_jvm_spec!(4, 7, 9, 1)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ClassSignature {
+    /// The type parameters of the class.
+    pub type_parameters: Vec<TypeParameter>,
+    /// The super class.
+    pub super_class: ClassTypeSignature,
+    /// The implemented interfaces.
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+/// The generic signature of a method, parsed from a [`method::Signature`](crate::jvm::method::Signature).
+#[doc = see_jvm_spec!(4, 7, 9, 1)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MethodSignature {
+    /// The type parameters of the method.
+    pub type_parameters: Vec<TypeParameter>,
+    /// The types of the parameters.
+    pub parameters: Vec<TypeSignature>,
+    /// The return type, or [`None`] if the method returns `void`.
+    pub return_type: Option<TypeSignature>,
+    /// The types of the exceptions thrown.
+    pub throws: Vec<TypeSignature>,
+}
+
+impl TypeSignature {
+    /// Creates a class type without type arguments.
+    #[must_use]
+    pub fn raw(class: ClassRef) -> Self {
+        Self::Class(ClassTypeSignature {
+            class,
+            type_arguments: Vec::new(),
+        })
+    }
+
+    /// Returns the type after erasing the type arguments, where the type variables are erased to
+    /// `java/lang/Object`.
+    #[must_use]
+    pub fn erasure(&self) -> FieldType {
+        match self {
+            Self::Base(it) => FieldType::Base(*it),
+            Self::Class(it) => FieldType::Object(it.class.clone()),
+            Self::Array(it) => it.erasure().into_array_type(),
+            Self::TypeVariable(_) => FieldType::Object(ClassRef::new("java/lang/Object")),
+        }
+    }
+
+    /// Checks whether the type has type arguments or type variables.
+    #[must_use]
+    pub fn is_generic(&self) -> bool {
+        match self {
+            Self::Base(_) => false,
+            Self::Class(it) => !it.type_arguments.is_empty(),
+            Self::Array(it) => it.is_generic(),
+            Self::TypeVariable(_) => true,
+        }
+    }
+}
+
+impl From<FieldType> for TypeSignature {
+    fn from(value: FieldType) -> Self {
+        match value {
+            FieldType::Base(it) => Self::Base(it),
+            FieldType::Object(it) => Self::raw(it),
+            FieldType::Array(it) => Self::Array(Box::new((*it).into())),
+        }
+    }
+}
+
+impl Display for TypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Base(it) => write!(f, "{it}"),
+            Self::Class(it) => write!(f, "{it}"),
+            Self::Array(it) => write!(f, "{it}[]"),
+            Self::TypeVariable(it) => write!(f, "{it}"),
+        }
+    }
+}
+
+impl Display for ClassTypeSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.class)?;
+        if !self.type_arguments.is_empty() {
+            write!(f, "<{}>", self.type_arguments.iter().join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TypeArgument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exact(it) => write!(f, "{it}"),
+            Self::Extends(it) => write!(f, "? extends {it}"),
+            Self::Super(it) => write!(f, "? super {it}"),
+            Self::Any => write!(f, "?"),
+        }
+    }
+}
+
+impl FromStr for TypeSignature {
+    type Err = InvalidDescriptor;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser(signature);
+        let parsed = parser.java_type()?;
+        parser.finish(parsed)
+    }
+}
+
+impl FromStr for ClassSignature {
+    type Err = InvalidDescriptor;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser(signature);
+        let type_parameters = parser.type_parameters()?;
+        let super_class = parser.class_type()?;
+        let mut interfaces = Vec::new();
+        while !parser.0.is_empty() {
+            interfaces.push(parser.class_type()?);
+        }
+        Ok(Self {
+            type_parameters,
+            super_class,
+            interfaces,
+        })
+    }
+}
+
+impl FromStr for MethodSignature {
+    type Err = InvalidDescriptor;
+
+    fn from_str(signature: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser(signature);
+        let type_parameters = parser.type_parameters()?;
+        parser.expect('(')?;
+        let mut parameters = Vec::new();
+        while !parser.eat(')') {
+            parameters.push(parser.java_type()?);
+        }
+        let return_type = if parser.eat('V') {
+            None
+        } else {
+            Some(parser.java_type()?)
+        };
+        let mut throws = Vec::new();
+        while parser.eat('^') {
+            throws.push(parser.reference_type()?);
+        }
+        parser.finish(Self {
+            type_parameters,
+            parameters,
+            return_type,
+            throws,
+        })
+    }
+}
+
+/// A recursive descent parser over the remaining input.
+struct Parser<'a>(&'a str);
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.0.chars().next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let matched = self.0.starts_with(expected);
+        if matched {
+            self.0 = &self.0[expected.len_utf8()..];
+        }
+        matched
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), InvalidDescriptor> {
+        self.eat(expected).then_some(()).ok_or(InvalidDescriptor)
+    }
+
+    fn finish<T>(&self, parsed: T) -> Result<T, InvalidDescriptor> {
+        self.0.is_empty().then_some(parsed).ok_or(InvalidDescriptor)
+    }
+
+    /// Parses an identifier, which ends at any of `terminators`.
+    fn identifier(&mut self, terminators: &[char]) -> Result<String, InvalidDescriptor> {
+        let end = self.0.find(terminators).ok_or(InvalidDescriptor)?;
+        if end == 0 {
+            return Err(InvalidDescriptor);
+        }
+        let (identifier, rest) = self.0.split_at(end);
+        self.0 = rest;
+        Ok(identifier.to_owned())
+    }
+
+    fn java_type(&mut self) -> Result<TypeSignature, InvalidDescriptor> {
+        match self.peek() {
+            Some(ch @ ('Z' | 'C' | 'F' | 'D' | 'B' | 'S' | 'I' | 'J')) => {
+                self.0 = &self.0[ch.len_utf8()..];
+                PrimitiveType::try_from(ch).map(TypeSignature::Base)
+            }
+            _ => self.reference_type(),
+        }
+    }
+
+    fn reference_type(&mut self) -> Result<TypeSignature, InvalidDescriptor> {
+        match self.peek() {
+            Some('L') => self.class_type().map(TypeSignature::Class),
+            Some('T') => {
+                self.expect('T')?;
+                let name = self.identifier(&[';'])?;
+                self.expect(';')?;
+                Ok(TypeSignature::TypeVariable(name))
+            }
+            Some('[') => {
+                self.expect('[')?;
+                self.java_type()
+                    .map(|it| TypeSignature::Array(Box::new(it)))
+            }
+            _ => Err(InvalidDescriptor),
+        }
+    }
+
+    fn class_type(&mut self) -> Result<ClassTypeSignature, InvalidDescriptor> {
+        self.expect('L')?;
+        let mut binary_name = self.identifier(&['<', '.', ';'])?;
+        let mut type_arguments = self.type_arguments()?;
+        // The inner classes are separated by `.` with their own type arguments.
+        while self.eat('.') {
+            binary_name.push('$');
+            binary_name.push_str(&self.identifier(&['<', '.', ';'])?);
+            type_arguments = self.type_arguments()?;
+        }
+        self.expect(';')?;
+        Ok(ClassTypeSignature {
+            class: ClassRef::new(binary_name),
+            type_arguments,
+        })
+    }
+
+    fn type_arguments(&mut self) -> Result<Vec<TypeArgument>, InvalidDescriptor> {
+        let mut type_arguments = Vec::new();
+        if !self.eat('<') {
+            return Ok(type_arguments);
+        }
+        while !self.eat('>') {
+            let argument = if self.eat('*') {
+                TypeArgument::Any
+            } else if self.eat('+') {
+                TypeArgument::Extends(self.reference_type()?)
+            } else if self.eat('-') {
+                TypeArgument::Super(self.reference_type()?)
+            } else {
+                TypeArgument::Exact(self.reference_type()?)
+            };
+            type_arguments.push(argument);
+        }
+        if type_arguments.is_empty() {
+            return Err(InvalidDescriptor);
+        }
+        Ok(type_arguments)
+    }
+
+    fn type_parameters(&mut self) -> Result<Vec<TypeParameter>, InvalidDescriptor> {
+        let mut type_parameters = Vec::new();
+        if !self.eat('<') {
+            return Ok(type_parameters);
+        }
+        while !self.eat('>') {
+            let name = self.identifier(&[':'])?;
+            self.expect(':')?;
+            let class_bound = match self.peek() {
+                Some('L' | 'T' | '[') => Some(self.reference_type()?),
+                _ => None,
+            };
+            let mut interface_bounds = Vec::new();
+            while self.eat(':') {
+                interface_bounds.push(self.reference_type()?);
+            }
+            type_parameters.push(TypeParameter {
+                name,
+                class_bound,
+                interface_bounds,
+            });
+        }
+        if type_parameters.is_empty() {
+            return Err(InvalidDescriptor);
+        }
+        Ok(type_parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_type_signatures() {
+        let map: TypeSignature = "Ljava/util/Map<TK;+Ljava/util/List<*>;>;".parse().unwrap();
+        assert_eq!(
+            map.to_string(),
+            "java/util/Map<K, ? extends java/util/List<?>>"
+        );
+        assert!(map.is_generic());
+        assert_eq!(
+            map.erasure(),
+            FieldType::Object(ClassRef::new("java/util/Map"))
+        );
+        let inner: TypeSignature = "Lorg/mokapot/Outer<TT;>.Inner<[I>;".parse().unwrap();
+        assert_eq!(inner.to_string(), "org/mokapot/Outer$Inner<int[]>");
+        assert!("Ljava/util/List<>;".parse::<TypeSignature>().is_err());
+        assert!("Ljava/util/List;X".parse::<TypeSignature>().is_err());
+    }
+
+    #[test]
+    fn parse_class_and_method_signatures() {
+        let class: ClassSignature =
+            "<E:Ljava/lang/Object;>Ljava/util/AbstractList<TE;>;Ljava/util/List<TE;>;"
+                .parse()
+                .unwrap();
+        assert_eq!(class.type_parameters[0].name, "E");
+        assert_eq!(class.super_class.to_string(), "java/util/AbstractList<E>");
+        assert_eq!(class.interfaces.len(), 1);
+
+        let method: MethodSignature =
+            "<T::Ljava/lang/Comparable<-TT;>;>(Ljava/util/List<TT;>;)TT;^TX;"
+                .parse()
+                .unwrap();
+        let [parameter] = method.type_parameters.as_slice() else {
+            panic!("Unexpected type parameters: {:?}", method.type_parameters);
+        };
+        assert_eq!(parameter.class_bound, None);
+        assert_eq!(parameter.interface_bounds.len(), 1);
+        assert_eq!(method.parameters[0].to_string(), "java/util/List<T>");
+        assert_eq!(
+            method.return_type,
+            Some(TypeSignature::TypeVariable("T".to_owned()))
+        );
+        assert_eq!(method.throws, [TypeSignature::TypeVariable("X".to_owned())]);
+        assert!("(I)".parse::<MethodSignature>().is_err());
+    }
+}
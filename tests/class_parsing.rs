@@ -4,9 +4,12 @@ use std::io::{self};
 
 use mokapot::{
     jvm::{
+        bytecode::lazy::LazyClass,
         class::{self, AccessFlags, RecordComponent},
+        interop::javap::Javap,
         parsing::Error,
         references::ClassRef,
+        writing::WritingOptions,
         Class,
     },
     types::{
@@ -130,7 +133,7 @@ fn parse_record() {
     assert!(matches!(
         rec_iter.next(),
         Some(RecordComponent { name, component_type: FieldType::Object(ClassRef { binary_name }), .. })
-        if name == "description" && binary_name == "java/lang/String"
+        if name == "description" && &*binary_name == "java/lang/String"
     ));
     assert!(rec_iter.next().is_none());
 }
@@ -140,6 +143,122 @@ fn not_a_class_file() {
     let bytes = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml"));
     assert!(matches!(
         Class::from_reader(bytes.as_slice()),
-        Err(Error::IO(e)) if e.kind() == io::ErrorKind::InvalidData
+        Err(Error::Read { offset: 4, source }) if source.kind() == io::ErrorKind::InvalidData
     ));
 }
+
+#[test]
+fn conforms_to_javap() {
+    let Some(javap) = Javap::locate() else {
+        return;
+    };
+    let class_dir = concat!(env!("OUT_DIR"), "/mokapot/java_classes");
+    for entry in walkdir::WalkDir::new(class_dir) {
+        let path = entry.unwrap().into_path();
+        if path.extension().is_some_and(|it| it == "class") {
+            let mismatches = javap.check(&path).unwrap();
+            assert!(mismatches.is_empty(), "{}: {mismatches:?}", path.display());
+        }
+    }
+}
+
+#[test]
+fn write_back_test_data() {
+    let javap = Javap::locate();
+    let class_dir = concat!(env!("OUT_DIR"), "/mokapot/java_classes");
+    let output = std::env::temp_dir().join(format!("mokapot-write-back-{}", std::process::id()));
+    std::fs::create_dir_all(&output).unwrap();
+    for entry in walkdir::WalkDir::new(class_dir) {
+        let path = entry.unwrap().into_path();
+        if path.extension().is_none_or(|it| it != "class") {
+            continue;
+        }
+        let bytes = std::fs::read(&path).unwrap();
+        let class = Class::from_reader(bytes.as_slice()).unwrap();
+        let constant_pool = LazyClass::parse(&bytes).unwrap().constant_pool().clone();
+        for options in [
+            WritingOptions::new(),
+            WritingOptions::new().with_constant_pool(constant_pool),
+        ] {
+            let written = class.to_bytes_with_options(&options).unwrap();
+            let reparsed = Class::from_reader(written.as_slice()).unwrap();
+            assert_eq!(
+                format!("{reparsed:?}"),
+                format!("{class:?}"),
+                "{}",
+                path.display()
+            );
+            if let Some(javap) = &javap {
+                let written_path = output.join(path.file_name().unwrap());
+                std::fs::write(&written_path, &written).unwrap();
+                let mismatches = javap.check(&written_path).unwrap();
+                assert!(mismatches.is_empty(), "{}: {mismatches:?}", path.display());
+            }
+        }
+    }
+    std::fs::remove_dir_all(&output).unwrap();
+}
+
+#[test]
+fn lazy_parsing_agrees_with_eager_parsing() {
+    let class_dir = concat!(env!("OUT_DIR"), "/mokapot/java_classes");
+    for entry in walkdir::WalkDir::new(class_dir) {
+        let path = entry.unwrap().into_path();
+        if path.extension().is_none_or(|it| it != "class") {
+            continue;
+        }
+        let bytes = std::fs::read(&path).unwrap();
+        let class = Class::from_reader(bytes.as_slice()).unwrap();
+        let lazy = LazyClass::parse(&bytes).unwrap();
+        let annotations = lazy.annotations().unwrap();
+        assert_eq!(
+            annotations.runtime_visible,
+            class.runtime_visible_annotations
+        );
+        assert_eq!(
+            annotations.runtime_invisible,
+            class.runtime_invisible_annotations
+        );
+        for (index, field) in class.fields.iter().enumerate() {
+            let annotations = lazy.field_annotations(index).unwrap();
+            assert_eq!(
+                annotations.runtime_visible,
+                field.runtime_visible_annotations
+            );
+            assert_eq!(
+                annotations.runtime_invisible,
+                field.runtime_invisible_annotations
+            );
+        }
+        for (index, method) in class.methods.iter().enumerate() {
+            let annotations = lazy.method_annotations(index).unwrap();
+            assert_eq!(
+                annotations.runtime_visible,
+                method.runtime_visible_annotations
+            );
+            assert_eq!(
+                annotations.runtime_invisible,
+                method.runtime_invisible_annotations
+            );
+            let body = method.body.as_ref();
+            assert_eq!(
+                format!("{:?}", lazy.local_variable_table(index).unwrap()),
+                format!("{:?}", body.and_then(|it| it.local_variable_table.as_ref())),
+                "{}",
+                path.display()
+            );
+            assert_eq!(
+                format!("{:?}", lazy.stack_map_table(index).unwrap()),
+                format!("{:?}", body.and_then(|it| it.stack_map_table.as_deref())),
+                "{}",
+                path.display()
+            );
+            assert_eq!(
+                format!("{:?}", lazy.parse_method(index).unwrap()),
+                format!("{method:?}"),
+                "{}",
+                path.display()
+            );
+        }
+    }
+}